@@ -1,5 +1,5 @@
 use bumpalo::Bump;
-use roc_load::{ExecutionMode, LoadConfig, LoadedModule, Threading};
+use roc_load::{ExecutionMode, FloatWidth, LoadConfig, LoadedModule, OptLevel, Threading};
 use roc_packaging::cache::RocCacheDir;
 use roc_reporting::report::DEFAULT_PALETTE;
 use roc_target::TargetInfo;
@@ -18,6 +18,8 @@ pub fn load_module(
         palette: DEFAULT_PALETTE,
         threading,
         exec_mode: ExecutionMode::Check,
+        frac_default: FloatWidth::F64,
+        opt_level: OptLevel::Development,
     };
 
     let arena = Bump::new();
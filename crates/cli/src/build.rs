@@ -1,25 +1,33 @@
 use bumpalo::Bump;
 use roc_build::{
+    abi::{abi_hash, read_abi_hash, write_abi_hash},
     link::{
-        legacy_host_filename, link, preprocess_host_wasm32, preprocessed_host_filename,
-        rebuild_host, LinkType, LinkingStrategy,
+        get_target_triple_str, legacy_host_filename, link, link_with_clang, post_link,
+        preprocess_host_wasm32, preprocessed_host_filename, rebuild_host, LinkType,
+        LinkingStrategy, PostLinkOptions,
     },
+    prebuilt_hosts::{PrebuiltHosts, MANIFEST_FILE_NAME},
     program::{self, CodeGenOptions},
 };
 use roc_builtins::bitcode;
+use roc_error_macros::user_error;
 use roc_load::{
-    EntryPoint, ExecutionMode, ExpectMetadata, LoadConfig, LoadMonomorphizedError, LoadedModule,
-    LoadingProblem, Threading,
+    EntryPoint, ExecutionMode, ExpectMetadata, FloatWidth, LoadConfig, LoadMonomorphizedError,
+    LoadedModule, LoadingProblem, Threading,
 };
 use roc_mono::ir::OptLevel;
 use roc_packaging::cache::RocCacheDir;
+use roc_problem::lint::LintConfig;
 use roc_reporting::{
     cli::Problems,
-    report::{RenderTarget, DEFAULT_PALETTE},
+    report::{Palette, RenderTarget},
 };
 use roc_target::TargetInfo;
 use std::time::{Duration, Instant};
-use std::{path::PathBuf, thread::JoinHandle};
+use std::{
+    path::{Path, PathBuf},
+    thread::JoinHandle,
+};
 use target_lexicon::Triple;
 
 fn report_timing(buf: &mut String, label: &str, duration: Duration) {
@@ -69,7 +77,11 @@ pub fn build_file<'a>(
     linking_strategy: LinkingStrategy,
     prebuilt_requested: bool,
     threading: Threading,
+    frac_default: FloatWidth,
     wasm_dev_stack_bytes: Option<u32>,
+    explain_specializations: bool,
+    lint_overrides: &LintConfig,
+    post_link_options: PostLinkOptions,
     roc_cache_dir: RocCacheDir<'_>,
     order: BuildOrdering,
 ) -> Result<BuiltFile<'a>, BuildFileError<'a>> {
@@ -88,9 +100,11 @@ pub fn build_file<'a>(
         target_info,
         // TODO: expose this from CLI?
         render: RenderTarget::ColorTerminal,
-        palette: DEFAULT_PALETTE,
+        palette: Palette::from_env(),
         threading,
         exec_mode,
+        frac_default,
+        opt_level: code_gen_options.opt_level,
     };
     let load_result = roc_load::load_and_monomorphize(
         arena,
@@ -185,10 +199,31 @@ pub fn build_file<'a>(
         host_input_path.with_file_name(preprocessed_host_filename(target).unwrap())
     };
 
+    let app_abi_hash = match &loaded.entry_point {
+        EntryPoint::Executable {
+            exposed_to_host, ..
+        } => abi_hash(target, exposed_to_host, &loaded.interns),
+        EntryPoint::Test => unreachable!(),
+    };
+
     // We don't need to spawn a rebuild thread when using a prebuilt host.
     let rebuild_thread = if matches!(link_type, LinkType::Dylib | LinkType::None) {
         None
     } else if is_prebuilt {
+        if linking_strategy == LinkingStrategy::Surgical {
+            // If the platform ships the host it was preprocessed from, regenerate the
+            // preprocessed host when it is missing or no longer matches that host.
+            roc_linker::refresh_prebuilt_preprocessed_host(
+                target,
+                &host_input_path,
+                &preprocessed_host_path,
+                exposed_values,
+                exposed_closure_types,
+            );
+        }
+
+        check_prebuilt_hosts_manifest(target, &preprocessed_host_path, linking_strategy);
+
         if !preprocessed_host_path.exists() {
             if prebuilt_requested {
                 eprintln!(
@@ -205,6 +240,18 @@ pub fn build_file<'a>(
             std::process::exit(1);
         }
 
+        // Hosts roc didn't build (or that an older roc built) have no ABI hash to check.
+        if let Some(host_abi_hash) = read_abi_hash(&preprocessed_host_path) {
+            if host_abi_hash != app_abi_hash {
+                eprintln!(
+                    "\nThe prebuilt host in this file does not match the platform this app uses:\n\n    {}\n\nIt was built for a different version of the platform (or by a different version of roc), so the types they exchange would be laid out differently in memory.\n\nIf you have the platform's source code locally, you can rebuild the host by re-running this command with --prebuilt-platform=false. Otherwise, use a release of the platform that was built for this version of it.\n",
+                    preprocessed_host_path.to_string_lossy()
+                );
+
+                std::process::exit(1);
+            }
+        }
+
         if linking_strategy == LinkingStrategy::Surgical {
             // Copy preprocessed host to executable location.
             // The surgical linker will modify that copy in-place.
@@ -215,6 +262,7 @@ pub fn build_file<'a>(
     } else {
         Some(spawn_rebuild_thread(
             code_gen_options.opt_level,
+            code_gen_options.lto,
             linking_strategy,
             host_input_path.clone(),
             preprocessed_host_path.clone(),
@@ -274,14 +322,27 @@ pub fn build_file<'a>(
     // This only needs to be mutable for report_problems. This can't be done
     // inside a nested scope without causing a borrow error!
     let mut loaded = loaded;
-    let problems = program::report_problems_monomorphized(&mut loaded);
+    let problems = program::report_problems_monomorphized(&mut loaded, lint_overrides, None);
     let loaded = loaded;
 
+    if explain_specializations {
+        println!(
+            "{}",
+            roc_mono::debug::explain_specializations(
+                &loaded.interns,
+                &loaded.layout_interner,
+                &loaded.procedures
+            )
+        );
+    }
+
     enum HostRebuildTiming {
         BeforeApp(u128),
         ConcurrentWithApp(JoinHandle<u128>),
     }
 
+    let rebuilding_host = rebuild_thread.is_some();
+
     let opt_rebuild_timing = if let Some(rebuild_thread) = rebuild_thread {
         if linking_strategy == LinkingStrategy::Additive {
             let rebuild_duration = rebuild_thread
@@ -308,7 +369,7 @@ pub fn build_file<'a>(
         loaded,
         &app_module_path,
         target,
-        code_gen_options,
+        code_gen_options.clone(),
         &preprocessed_host_path,
         wasm_dev_stack_bytes,
     );
@@ -351,8 +412,38 @@ pub fn build_file<'a>(
         }
     }
 
+    if rebuilding_host {
+        // failing to write the hash only means this host can't be checked when it's reused
+        let _ = write_abi_hash(&preprocessed_host_path, app_abi_hash);
+
+        // Platforms that ship hosts for several targets list them in a manifest. It's the
+        // platform author's to edit, so point out a host it's missing rather than changing it.
+        if let (Some(platform_dir), Some(target_str), Some(file_name)) = (
+            preprocessed_host_path.parent(),
+            get_target_triple_str(target),
+            preprocessed_host_path.file_name(),
+        ) {
+            if let Ok(Some(manifest)) = PrebuiltHosts::read(platform_dir) {
+                let file_name = file_name.to_string_lossy();
+                let listed = manifest.host_files(target_str).map_or(false, |host_files| {
+                    host_files.iter().any(|f| *f == file_name)
+                });
+
+                if !listed {
+                    eprintln!(
+                        "\nNote: this platform's list of prebuilt hosts does not include the host I just built, so it will not be shipped with the platform. To ship it, add it to {}:\n\n    {} {}\n",
+                        platform_dir.join(MANIFEST_FILE_NAME).to_string_lossy(),
+                        target_str,
+                        file_name
+                    );
+                }
+            }
+        }
+    }
+
     // Step 2: link the prebuilt platform and compiled app
     let link_start = Instant::now();
+    let link_span = roc_tracing::trace_span("link", "link");
     let problems = match (linking_strategy, link_type) {
         (LinkingStrategy::Surgical, _) => {
             roc_linker::link_preprocessed_host(
@@ -403,8 +494,20 @@ pub fn build_file<'a>(
                 inputs.push(builtins_host_tempfile.path().to_str().unwrap());
             }
 
-            let (mut child, _) = link(target, binary_path.clone(), &inputs, link_type)
-                .map_err(|_| todo!("gracefully handle `ld` failing to spawn."))?;
+            let linked = if code_gen_options.needs_clang_link() {
+                link_with_clang(
+                    target,
+                    binary_path.clone(),
+                    &inputs,
+                    link_type,
+                    &code_gen_options,
+                )
+            } else {
+                link(target, binary_path.clone(), &inputs, link_type)
+            };
+
+            let (mut child, _) =
+                linked.map_err(|_| todo!("gracefully handle `ld` failing to spawn."))?;
 
             let exit_status = child
                 .wait()
@@ -425,6 +528,17 @@ pub fn build_file<'a>(
         }
     };
 
+    if !post_link_options.is_empty() && link_type != LinkType::None {
+        post_link(target, &binary_path, link_type, post_link_options).unwrap_or_else(|err| {
+            user_error!(
+                "Failed to strip or compress {}: {}",
+                binary_path.display(),
+                err
+            )
+        });
+    }
+
+    drop(link_span);
     let linking_time = link_start.elapsed();
 
     if emit_timings {
@@ -441,9 +555,80 @@ pub fn build_file<'a>(
     })
 }
 
+/// If the platform lists the prebuilt hosts it ships in a manifest, make sure it has one
+/// for this target (and this kind of linking), and explain which ones it has otherwise.
+fn check_prebuilt_hosts_manifest(
+    target: &Triple,
+    preprocessed_host_path: &Path,
+    linking_strategy: LinkingStrategy,
+) {
+    let platform_dir = match preprocessed_host_path.parent() {
+        Some(dir) => dir,
+        None => return,
+    };
+
+    let manifest = match PrebuiltHosts::read(platform_dir) {
+        Ok(Some(manifest)) => manifest,
+        Ok(None) => return,
+        Err(err) => {
+            eprintln!(
+                "\nI could not read this platform's list of prebuilt hosts:\n\n    {}\n\n{}\n",
+                platform_dir.join(MANIFEST_FILE_NAME).to_string_lossy(),
+                err
+            );
+
+            std::process::exit(1);
+        }
+    };
+
+    let target_str = match get_target_triple_str(target) {
+        Some(target_str) => target_str,
+        None => return,
+    };
+
+    let host_files = match manifest.host_files(target_str) {
+        Some(host_files) => host_files,
+        None => {
+            let available: Vec<&str> = manifest.targets().collect();
+
+            eprintln!(
+                "\nThis platform does not have a prebuilt host for the {} target. It has prebuilt hosts for:\n\n    {}\n\nTry building for one of those targets with --target, or if you have the platform's source code locally, re-run this command with --prebuilt-platform=false\n",
+                target_str,
+                available.join("\n    ")
+            );
+
+            std::process::exit(1);
+        }
+    };
+
+    let file_name = preprocessed_host_path
+        .file_name()
+        .map(|name| name.to_string_lossy())
+        .unwrap_or_default();
+
+    if !host_files.iter().any(|host_file| *host_file == file_name) {
+        let suggestion = if linking_strategy == LinkingStrategy::Surgical {
+            "Try linking it with --linker=legacy instead."
+        } else {
+            "Try linking it with the surgical linker instead."
+        };
+
+        eprintln!(
+            "\nThis platform's prebuilt hosts for the {} target are:\n\n    {}\n\nbut I need {} to link this app. {}\n",
+            target_str,
+            host_files.join("\n    "),
+            file_name,
+            suggestion
+        );
+
+        std::process::exit(1);
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 fn spawn_rebuild_thread(
     opt_level: OptLevel,
+    lto: bool,
     linking_strategy: LinkingStrategy,
     host_input_path: PathBuf,
     preprocessed_host_path: PathBuf,
@@ -460,6 +645,7 @@ fn spawn_rebuild_thread(
         eprintln!("🔨 Rebuilding platform...");
 
         let rebuild_host_start = Instant::now();
+        let _span = roc_tracing::trace_span("host", "rebuild platform");
 
         match linking_strategy {
             LinkingStrategy::Additive => {
@@ -468,6 +654,7 @@ fn spawn_rebuild_thread(
                     &thread_local_target,
                     host_input_path.as_path(),
                     None,
+                    false,
                 );
 
                 preprocess_host_wasm32(host_dest.as_path(), &preprocessed_host_path);
@@ -492,6 +679,7 @@ fn spawn_rebuild_thread(
                     &thread_local_target,
                     host_input_path.as_path(),
                     None,
+                    lto,
                 );
             }
         }
@@ -505,8 +693,12 @@ pub fn check_file<'a>(
     arena: &'a Bump,
     roc_file_path: PathBuf,
     emit_timings: bool,
+    write_signatures: bool,
     roc_cache_dir: RocCacheDir<'_>,
     threading: Threading,
+    frac_default: FloatWidth,
+    lint_overrides: &LintConfig,
+    html_report: Option<&Path>,
 ) -> Result<(Problems, Duration), LoadingProblem<'a>> {
     let compilation_start = Instant::now();
 
@@ -521,9 +713,11 @@ pub fn check_file<'a>(
         target_info,
         // TODO: expose this from CLI?
         render: RenderTarget::ColorTerminal,
-        palette: DEFAULT_PALETTE,
+        palette: Palette::from_env(),
         threading,
         exec_mode: ExecutionMode::Check,
+        frac_default,
+        opt_level: OptLevel::Development,
     };
     let mut loaded = roc_load::load_and_typecheck(
         arena,
@@ -576,8 +770,46 @@ pub fn check_file<'a>(
         println!("Finished checking in {} ms\n", compilation_end.as_millis(),);
     }
 
-    Ok((
-        program::report_problems_typechecked(&mut loaded),
-        compilation_end,
-    ))
+    let problems = program::report_problems_typechecked(&mut loaded, lint_overrides, html_report);
+
+    if write_signatures && problems.errors == 0 {
+        write_inferred_signatures(&mut loaded).map_err(|(path, err)| {
+            LoadingProblem::FormattedReport(format!(
+                "I could not add the inferred type annotations to {}: {}\n",
+                path.display(),
+                err
+            ))
+        })?;
+    }
+
+    Ok((problems, compilation_end))
+}
+
+/// Returns the file it couldn't write to, along with why, if writing the signatures failed.
+fn write_inferred_signatures(loaded: &mut LoadedModule) -> Result<(), (PathBuf, std::io::Error)> {
+    use roc_load::signatures::{inferred_signatures, insert_signatures};
+
+    let signatures = inferred_signatures(loaded);
+
+    if signatures.is_empty() {
+        return Ok(());
+    }
+
+    let (path, src) = &loaded.sources[&loaded.module_id];
+    let output = insert_signatures(src, &signatures);
+
+    std::fs::write(path, output).map_err(|err| (path.clone(), err))?;
+
+    println!(
+        "Added {} inferred type {} to {}",
+        signatures.len(),
+        if signatures.len() == 1 {
+            "annotation"
+        } else {
+            "annotations"
+        },
+        path.display()
+    );
+
+    Ok(())
 }
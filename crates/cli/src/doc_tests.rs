@@ -0,0 +1,86 @@
+//! Doc tests: the `expect`s in the ```roc code blocks of a module's doc comments.
+//!
+//! Running them doesn't need any help from the compiler. The code blocks are uncommented in
+//! place, so they become top-level defs and `expect`s of the module itself: they can use
+//! everything in its scope, and since every line stays where it was, failures are reported
+//! at the doc comment they came from.
+
+/// The module's source, with the code blocks in its doc comments that contain an `expect`
+/// uncommented.
+///
+/// Code blocks without an `expect` are left alone, since many examples are only snippets
+/// (e.g. an expression on its own) that wouldn't compile as top-level code.
+pub fn uncomment_doc_tests(src: &str) -> String {
+    let mut buf = String::with_capacity(src.len());
+    let mut lines = src.split_inclusive('\n').peekable();
+
+    while let Some(line) = lines.next() {
+        buf.push_str(line);
+
+        if !is_code_block_fence(line, true) {
+            continue;
+        }
+
+        // Only uncomment the block once we know it's closed, and that it has an expect.
+        let mut block = Vec::new();
+        let mut closing_fence = None;
+
+        while let Some(next) = lines.peek() {
+            if doc_comment_content(next).is_none() {
+                break;
+            }
+
+            if is_code_block_fence(next, false) {
+                closing_fence = lines.next();
+                break;
+            }
+
+            block.push(*next);
+            lines.next();
+        }
+
+        let has_expect = block.iter().any(|line| {
+            doc_comment_content(line).map_or(false, |content| {
+                content.trim_end() == "expect" || content.starts_with("expect ")
+            })
+        });
+
+        if closing_fence.is_some() && has_expect {
+            for line in block {
+                // Keep the line break (if any), since the content's trailing whitespace was trimmed.
+                let content = doc_comment_content(line).unwrap_or_default();
+
+                buf.push_str(content);
+                buf.push_str(&line[line.trim_end().len()..]);
+            }
+        } else {
+            buf.extend(block);
+        }
+
+        buf.extend(closing_fence);
+    }
+
+    buf
+}
+
+/// What's in a top-level doc comment (with the space after the `##` removed)
+fn doc_comment_content(line: &str) -> Option<&str> {
+    // Doc comments on nested defs can't be uncommented into top-level code.
+    let content = line.trim_end().strip_prefix("##")?;
+
+    // `###` starts a regular comment, not a doc comment.
+    if content.starts_with('#') {
+        return None;
+    }
+
+    Some(content.strip_prefix(' ').unwrap_or(content))
+}
+
+/// Whether this line starts (or ends) a code block of Roc code
+fn is_code_block_fence(line: &str, is_opening: bool) -> bool {
+    match doc_comment_content(line).and_then(|content| content.trim().strip_prefix("```")) {
+        Some(info) if is_opening => matches!(info.trim(), "" | "roc"),
+        Some(info) => info.trim().is_empty(),
+        None => false,
+    }
+}
@@ -6,13 +6,16 @@ extern crate const_format;
 use build::BuiltFile;
 use bumpalo::Bump;
 use clap::{Arg, ArgMatches, Command, ValueSource};
-use roc_build::link::{LinkType, LinkingStrategy};
-use roc_build::program::{CodeGenBackend, CodeGenOptions};
+use roc_build::link::{LinkType, LinkingStrategy, PostLinkOptions};
+use roc_build::program::{CodeGenBackend, CodeGenOptions, Pgo, Sanitizer};
+use roc_build::target::TargetCpu;
 use roc_error_macros::{internal_error, user_error};
-use roc_load::{ExpectMetadata, LoadingProblem, Threading};
+use roc_gen_llvm::llvm::build::{LlvmOptLevel, LlvmPipeline};
+use roc_load::{ExpectMetadata, FloatWidth, LoadingProblem, Threading};
 use roc_mono::ir::OptLevel;
 use roc_packaging::cache::RocCacheDir;
 use roc_packaging::tarball::Compression;
+use roc_problem::lint::{LintConfig, ALL_WARNINGS};
 use roc_reporting::cli::Problems;
 use std::env;
 use std::ffi::{CString, OsStr};
@@ -31,12 +34,14 @@ use target_lexicon::{
 use tempfile::TempDir;
 
 pub mod build;
+mod doc_tests;
 mod format;
 pub use format::format;
 
 use crate::build::{BuildFileError, BuildOrdering};
 
 const DEFAULT_ROC_FILENAME: &str = "main.roc";
+const DEFAULT_COVERAGE_DIR: &str = "coverage";
 
 pub const CMD_BUILD: &str = "build";
 pub const CMD_RUN: &str = "run";
@@ -50,6 +55,7 @@ pub const CMD_FORMAT: &str = "format";
 pub const CMD_TEST: &str = "test";
 pub const CMD_GLUE: &str = "glue";
 pub const CMD_GEN_STUB_LIB: &str = "gen-stub-lib";
+pub const CMD_EXPLAIN: &str = "explain";
 
 pub const FLAG_DEBUG: &str = "debug";
 pub const FLAG_BUNDLE: &str = "bundle";
@@ -57,6 +63,15 @@ pub const FLAG_DEV: &str = "dev";
 pub const FLAG_OPTIMIZE: &str = "optimize";
 pub const FLAG_MAX_THREADS: &str = "max-threads";
 pub const FLAG_OPT_SIZE: &str = "opt-size";
+pub const FLAG_OPT_LEVEL: &str = "opt-level";
+pub const FLAG_LLVM_PASSES: &str = "llvm-passes";
+pub const FLAG_LTO: &str = "lto";
+pub const FLAG_TARGET_CPU: &str = "target-cpu";
+pub const FLAG_TARGET_FEATURES: &str = "target-features";
+pub const FLAG_SANITIZE: &str = "sanitize";
+pub const FLAG_NO_LLVM_CACHE: &str = "no-llvm-cache";
+pub const FLAG_PROFILE_GENERATE: &str = "profile-generate";
+pub const FLAG_PROFILE_USE: &str = "profile-use";
 pub const FLAG_LIB: &str = "lib";
 pub const FLAG_NO_LINK: &str = "no-link";
 pub const FLAG_TARGET: &str = "target";
@@ -64,15 +79,78 @@ pub const FLAG_TIME: &str = "time";
 pub const FLAG_LINKER: &str = "linker";
 pub const FLAG_PREBUILT: &str = "prebuilt-platform";
 pub const FLAG_CHECK: &str = "check";
+pub const FLAG_WRITE_SIGNATURES: &str = "write-signatures";
 pub const FLAG_WASM_STACK_SIZE_KB: &str = "wasm-stack-size-kb";
+pub const FLAG_FRAC_DEFAULT: &str = "frac-default";
+pub const FLAG_EXPLAIN_SPECIALIZATIONS: &str = "explain-specializations";
+pub const FLAG_STRIP: &str = "strip";
+pub const FLAG_COMPRESS: &str = "compress";
+pub const FLAG_ASYNC_EFFECTS: &str = "async-effects";
+pub const FLAG_DOCS_FORMAT: &str = "format";
+pub const FLAG_COVERAGE: &str = "coverage";
+pub const FLAG_DOC_TESTS: &str = "doc-tests";
+pub const FLAG_UPDATE_SNAPSHOTS: &str = "update-snapshots";
+pub const FLAG_SEED: &str = "seed";
+pub const FLAG_TEST_THREADS: &str = "test-threads";
+pub const FLAG_LINT: &str = "lint";
+pub const FLAG_DENY_WARNINGS: &str = "deny-warnings";
+pub const FLAG_REPORT_HTML: &str = "report-html";
+pub const FLAG_INTERPRET: &str = "interpret";
+pub const FLAG_LEAK_CHECK: &str = "leak-check";
+pub const FLAG_VALGRIND: &str = "valgrind";
+pub const FLAG_SEARCH: &str = "search";
+pub const FLAG_FX_HARNESS: &str = "fx-harness";
 pub const ROC_FILE: &str = "ROC_FILE";
 pub const ROC_DIR: &str = "ROC_DIR";
 pub const GLUE_FILE: &str = "GLUE_FILE";
 pub const DIRECTORY_OR_FILES: &str = "DIRECTORY_OR_FILES";
 pub const ARGS_FOR_APP: &str = "ARGS_FOR_APP";
+pub const ERROR_CODE: &str = "ERROR_CODE";
 
 const VERSION: &str = include_str!("../../../version.txt");
 
+/// Print the extended description of the problem with the given error code, for `roc explain`
+pub fn explain(code: &str) -> i32 {
+    match roc_reporting::codes::lookup(code) {
+        Some(error_code) => {
+            println!("{} [{}]\n", error_code.title, error_code.code);
+
+            match error_code.explanation {
+                Some(explanation) => print!("{}", explanation),
+                None => println!(
+                    "There's no extended description of this problem yet, but its report explains what went wrong in your code."
+                ),
+            }
+
+            0
+        }
+        None => {
+            eprintln!(
+                "{} is not a Roc error code. Error codes look like E1001, and are shown in the header of each problem's report.",
+                code
+            );
+
+            1
+        }
+    }
+}
+
+/// The lint levels given with --lint and --deny-warnings
+pub fn lint_overrides(matches: &ArgMatches) -> LintConfig {
+    let mut config = LintConfig::default();
+
+    if matches.is_present(FLAG_DENY_WARNINGS) {
+        config.add_entry(&format!("{}=deny", ALL_WARNINGS)).unwrap();
+    }
+
+    // Clap already validated these.
+    for entry in matches.values_of(FLAG_LINT).into_iter().flatten() {
+        config.add_entry(entry).unwrap();
+    }
+
+    config
+}
+
 pub fn build_app<'a>() -> Command<'a> {
     let flag_optimize = Arg::new(FLAG_OPTIMIZE)
         .long(FLAG_OPTIMIZE)
@@ -91,6 +169,64 @@ pub fn build_app<'a>() -> Command<'a> {
         .help("Optimize the compiled program to have a small binary size\n(Optimization takes time to complete.)")
         .required(false);
 
+    let flag_opt_level = Arg::new(FLAG_OPT_LEVEL)
+        .short('O')
+        .long(FLAG_OPT_LEVEL)
+        .help("Choose how much LLVM optimizes the compiled program, like the -O levels of C compilers\n(-O0 through -O3 trade compile time for runtime performance, -Os and -Oz make the binary small.)")
+        .takes_value(true)
+        .possible_values(["0", "1", "2", "3", "s", "z"])
+        .required(false);
+
+    let flag_llvm_passes = Arg::new(FLAG_LLVM_PASSES)
+        .long(FLAG_LLVM_PASSES)
+        .help("Run exactly these comma-separated LLVM passes after code generation, instead of the ones picked by the optimization level\n(This is meant for experimenting with the compiler, for example --llvm-passes=mem2reg,instcombine,simplifycfg.)")
+        .takes_value(true)
+        .required(false);
+
+    let flag_lto = Arg::new(FLAG_LTO)
+        .long(FLAG_LTO)
+        .help("Optimize the app and the platform host together at link time (LTO)\n(This uses the legacy linker and needs clang, plus lld on Linux. For now only C hosts are built for LTO.)")
+        .required(false);
+
+    let flag_target_cpu = Arg::new(FLAG_TARGET_CPU)
+        .long(FLAG_TARGET_CPU)
+        .help("Generate code for this CPU, for example `skylake` or `apple-m1`\n(Use `native` for the CPU of this machine. This only applies to the LLVM backend.)")
+        .takes_value(true)
+        .required(false);
+
+    let flag_target_features = Arg::new(FLAG_TARGET_FEATURES)
+        .long(FLAG_TARGET_FEATURES)
+        .help("Enable or disable CPU features, for example `+avx2,+fma` or `+neon`\n(Use `native` for the features of this machine. This only applies to the LLVM backend.)")
+        .takes_value(true)
+        .allow_hyphen_values(true)
+        .required(false);
+
+    let flag_sanitize = Arg::new(FLAG_SANITIZE)
+        .long(FLAG_SANITIZE)
+        .help("Instrument the compiled app with a sanitizer and link its runtime, to catch memory bugs at the boundary with the platform\n(This uses the legacy linker and needs clang, plus LLVM's `opt` and `llc` on the PATH.)")
+        .possible_values(["address"])
+        .takes_value(true)
+        .multiple_occurrences(true)
+        .required(false);
+
+    let flag_no_llvm_cache = Arg::new(FLAG_NO_LLVM_CACHE)
+        .long(FLAG_NO_LLVM_CACHE)
+        .help("Always run LLVM, instead of reusing its output for the modules that did not change since an earlier build")
+        .required(false);
+
+    let flag_profile_generate = Arg::new(FLAG_PROFILE_GENERATE)
+        .long(FLAG_PROFILE_GENERATE)
+        .help("Instrument the compiled app to write an execution profile when it runs, for use with --profile-use\n(The profile goes to `default.profraw`, or LLVM_PROFILE_FILE if set. Merge profiles with `llvm-profdata merge`.)")
+        .conflicts_with(FLAG_PROFILE_USE)
+        .required(false);
+
+    let flag_profile_use = Arg::new(FLAG_PROFILE_USE)
+        .long(FLAG_PROFILE_USE)
+        .help("Optimize the compiled app for the hot paths in this merged profile (a `.profdata` file)\n(This needs LLVM's `opt` and `llc` on the PATH.)")
+        .takes_value(true)
+        .allow_invalid_utf8(true)
+        .required(false);
+
     let flag_dev = Arg::new(FLAG_DEV)
         .long(FLAG_DEV)
         .help("Make compilation finish as soon as possible, at the expense of runtime performance")
@@ -106,9 +242,23 @@ pub fn build_app<'a>() -> Command<'a> {
         .help("Print detailed compilation time information")
         .required(false);
 
+    let flag_lint = Arg::new(FLAG_LINT)
+        .long(FLAG_LINT)
+        .help("Set how seriously to take a kind of warning, e.g. `--lint unused-def=deny`\n(This takes precedence over the project's roc-lints.txt. `warnings=<level>` sets every kind that isn't set on its own.)")
+        .value_name("LINT=LEVEL")
+        .takes_value(true)
+        .multiple_occurrences(true)
+        .validator(|entry| LintConfig::default().add_entry(entry))
+        .required(false);
+
+    let flag_deny_warnings = Arg::new(FLAG_DENY_WARNINGS)
+        .long(FLAG_DENY_WARNINGS)
+        .help("Report warnings as errors, unless they are set to another level with --lint or in roc-lints.txt\n(This is the same as `--lint warnings=deny`.)")
+        .required(false);
+
     let flag_linker = Arg::new(FLAG_LINKER)
         .long(FLAG_LINKER)
-        .help("Set which linker to use\n(The surgical linker is enabled by default only when building for wasm32 or x86_64 Linux, because those are the only targets it currently supports. Otherwise the legacy linker is used by default.)")
+        .help("Set which linker to use\n(The surgical linker is enabled by default only when building for wasm32, for x86_64 or aarch64 Linux, or for x86_64 Windows or x86_64 macOS (not Apple silicon), because those are the only targets it currently supports. Otherwise the legacy linker is used by default.)")
         .possible_values(["surgical", "legacy"])
         .required(false);
 
@@ -125,6 +275,13 @@ pub fn build_app<'a>() -> Command<'a> {
         .validator(|s| s.parse::<u32>())
         .required(false);
 
+    let flag_frac_default = Arg::new(FLAG_FRAC_DEFAULT)
+        .long(FLAG_FRAC_DEFAULT)
+        .help("Choose the type of fractional number literals whose type is not otherwise determined\n(For example, `Dec` avoids rounding errors in programs that handle money.)")
+        .possible_values(["F64", "F32", "Dec"])
+        .default_value("F64")
+        .required(false);
+
     let roc_file_to_run = Arg::new(ROC_FILE)
         .help("The .roc file of an app to run")
         .allow_invalid_utf8(true)
@@ -147,12 +304,44 @@ pub fn build_app<'a>() -> Command<'a> {
             .arg(flag_optimize.clone())
             .arg(flag_max_threads.clone())
             .arg(flag_opt_size.clone())
+            .arg(flag_opt_level.clone())
+            .arg(flag_llvm_passes.clone())
+            .arg(flag_lto.clone())
+            .arg(flag_target_cpu.clone())
+            .arg(flag_target_features.clone())
+            .arg(flag_sanitize.clone())
+            .arg(flag_no_llvm_cache.clone())
+            .arg(flag_profile_generate.clone())
+            .arg(flag_profile_use.clone())
             .arg(flag_dev.clone())
             .arg(flag_debug.clone())
             .arg(flag_time.clone())
+            .arg(flag_lint.clone())
+            .arg(flag_deny_warnings.clone())
             .arg(flag_linker.clone())
             .arg(flag_prebuilt.clone())
+            .arg(flag_frac_default.clone())
             .arg(flag_wasm_stack_size_kb.clone())
+            .arg(
+                Arg::new(FLAG_EXPLAIN_SPECIALIZATIONS)
+                    .long(FLAG_EXPLAIN_SPECIALIZATIONS)
+                    .help("List the functions that were specialized for more than one layout, where each specialization is called from, and roughly how much code it takes up\n(This helps find the polymorphic functions that make a program large. The procedures are listed as they are after optimization, so calls that were inlined are not counted.)")
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_STRIP)
+                    .long(FLAG_STRIP)
+                    .help("Remove symbols and debug information from the binary after linking, to make it smaller")
+                    .conflicts_with(FLAG_NO_LINK)
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_COMPRESS)
+                    .long(FLAG_COMPRESS)
+                    .help("Compress the executable with UPX, so it decompresses itself when it starts\n(This needs `upx` on your PATH, and is only supported for Linux and Windows executables.)")
+                    .conflicts_with_all(&[FLAG_NO_LINK, FLAG_LIB])
+                    .required(false),
+            )
             .arg(
                 Arg::new(FLAG_TARGET)
                     .long(FLAG_TARGET)
@@ -199,8 +388,84 @@ pub fn build_app<'a>() -> Command<'a> {
             .arg(flag_dev.clone())
             .arg(flag_debug.clone())
             .arg(flag_time.clone())
+            .arg(flag_lint.clone())
+            .arg(flag_deny_warnings.clone())
             .arg(flag_linker.clone())
             .arg(flag_prebuilt.clone())
+            .arg(flag_frac_default.clone())
+            .arg(
+                Arg::new(FLAG_DOC_TESTS)
+                    .long(FLAG_DOC_TESTS)
+                    .help("Also run the `expect`s in ```roc code blocks in the main module's doc comments\n(Each block becomes part of the module, so it can use anything the module can.)")
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_UPDATE_SNAPSHOTS)
+                    .long(FLAG_UPDATE_SNAPSHOTS)
+                    .help("Record the current values of the main module's `expect snapshot`s, instead of comparing them to the recorded ones")
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_SEED)
+                    .long(FLAG_SEED)
+                    .help("The seed for the arguments of property tests (`expect \\args -> ...`), to try the same arguments as an earlier run")
+                    .takes_value(true)
+                    .validator(|s| s.parse::<u64>())
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_TEST_THREADS)
+                    .long(FLAG_TEST_THREADS)
+                    .help("How many tests to run at once, each batch in its own process (defaults to the number of cores; 1 runs them one after another)")
+                    .takes_value(true)
+                    .validator(|s| s.parse::<usize>())
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_COVERAGE)
+                    .long(FLAG_COVERAGE)
+                    .help("Write which definitions the tests ran, as lcov.info and HTML, to the given directory (coverage/ by default)\n(This is per definition, not per line or branch. Use --coverage=<dir> to choose the directory.)")
+                    .value_name("DIR")
+                    .takes_value(true)
+                    .min_values(0)
+                    .require_equals(true)
+                    .default_missing_value(DEFAULT_COVERAGE_DIR)
+                    .allow_invalid_utf8(true)
+                    .conflicts_with(FLAG_TARGET)
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_FX_HARNESS)
+                    .long(FLAG_FX_HARNESS)
+                    .help("The shared library that performs the effects of `expect-fx`s, e.g. one with mock effects\n(Defaults to the platform's <target>-test.so, if it has one. It may also export roc_test_setup and roc_test_teardown, to run around each `expect-fx`.)")
+                    .value_name("PATH")
+                    .takes_value(true)
+                    .allow_invalid_utf8(true)
+                    .conflicts_with(FLAG_TARGET)
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_LEAK_CHECK)
+                    .long(FLAG_LEAK_CHECK)
+                    .help("Fail tests that leak Roc heap allocations or free them twice, and show where each was allocated")
+                    .conflicts_with(FLAG_TARGET)
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_VALGRIND)
+                    .long(FLAG_VALGRIND)
+                    .help("Run the tests under valgrind, failing if it finds any memory errors\n(This implies --leak-check, and needs valgrind to be installed.)")
+                    .conflicts_with(FLAG_TARGET)
+                    .required(false)
+            )
+            .arg(
+                Arg::new(FLAG_TARGET)
+                    .long(FLAG_TARGET)
+                    .help("Run the tests compiled for a different target\n(Only `system` and `wasm32` are supported. wasm32 tests run in a WASI interpreter, or in wasmtime if ROC_WASM_RUNTIME is set to its path.)")
+                    .default_value(Target::default().into())
+                    .possible_values([Target::System, Target::Wasm32].map(Into::<&'static str>::into))
+                    .required(false)
+            )
             .arg(
                 Arg::new(ROC_FILE)
                     .help("The .roc file for the main module")
@@ -212,17 +477,35 @@ pub fn build_app<'a>() -> Command<'a> {
         )
         .subcommand(Command::new(CMD_REPL)
             .about("Launch the interactive Read Eval Print Loop (REPL)")
+            .arg(
+                Arg::new(FLAG_INTERPRET)
+                    .long(FLAG_INTERPRET)
+                    .help("Evaluate expressions with the interpreter instead of compiling them with LLVM")
+                    .required(false),
+            )
         )
         .subcommand(Command::new(CMD_RUN)
             .about("Run a .roc file even if it has build errors")
             .arg(flag_optimize.clone())
             .arg(flag_max_threads.clone())
             .arg(flag_opt_size.clone())
+            .arg(flag_opt_level.clone())
+            .arg(flag_llvm_passes.clone())
+            .arg(flag_lto.clone())
+            .arg(flag_target_cpu.clone())
+            .arg(flag_target_features.clone())
+            .arg(flag_sanitize.clone())
+            .arg(flag_no_llvm_cache.clone())
+            .arg(flag_profile_generate.clone())
+            .arg(flag_profile_use.clone())
             .arg(flag_dev.clone())
             .arg(flag_debug.clone())
             .arg(flag_time.clone())
+            .arg(flag_lint.clone())
+            .arg(flag_deny_warnings.clone())
             .arg(flag_linker.clone())
             .arg(flag_prebuilt.clone())
+            .arg(flag_frac_default.clone())
             .arg(roc_file_to_run.clone())
             .arg(args_for_app.clone())
         )
@@ -231,11 +514,23 @@ pub fn build_app<'a>() -> Command<'a> {
             .arg(flag_optimize.clone())
             .arg(flag_max_threads.clone())
             .arg(flag_opt_size.clone())
+            .arg(flag_opt_level.clone())
+            .arg(flag_llvm_passes.clone())
+            .arg(flag_lto.clone())
+            .arg(flag_target_cpu.clone())
+            .arg(flag_target_features.clone())
+            .arg(flag_sanitize.clone())
+            .arg(flag_no_llvm_cache.clone())
+            .arg(flag_profile_generate.clone())
+            .arg(flag_profile_use.clone())
             .arg(flag_dev.clone())
             .arg(flag_debug.clone())
             .arg(flag_time.clone())
+            .arg(flag_lint.clone())
+            .arg(flag_deny_warnings.clone())
             .arg(flag_linker.clone())
             .arg(flag_prebuilt.clone())
+            .arg(flag_frac_default.clone())
             .arg(roc_file_to_run.clone())
             .arg(args_for_app.clone())
         )
@@ -259,7 +554,24 @@ pub fn build_app<'a>() -> Command<'a> {
         .subcommand(Command::new(CMD_CHECK)
             .about("Check the code for problems, but don’t build or run it")
             .arg(flag_time.clone())
+            .arg(flag_lint.clone())
+            .arg(flag_deny_warnings.clone())
             .arg(flag_max_threads.clone())
+            .arg(flag_frac_default.clone())
+            .arg(
+                Arg::new(FLAG_WRITE_SIGNATURES)
+                    .long(FLAG_WRITE_SIGNATURES)
+                    .help("Add the inferred type annotation above each top-level definition that lacks one\n(Only done when there are no errors.)")
+                    .required(false),
+            )
+            .arg(
+                Arg::new(FLAG_REPORT_HTML)
+                    .long(FLAG_REPORT_HTML)
+                    .help("Also write all the problems found to this file, as a self-contained HTML page\n(Useful as an artifact of a CI run.)")
+                    .takes_value(true)
+                    .allow_invalid_utf8(true)
+                    .required(false),
+            )
             .arg(
                 Arg::new(ROC_FILE)
                     .help("The .roc file of an app to check")
@@ -277,6 +589,26 @@ pub fn build_app<'a>() -> Command<'a> {
                     .help("The directory or files to build documentation for")
                     .allow_invalid_utf8(true)
                 )
+                .arg(Arg::new(FLAG_DOCS_FORMAT)
+                    .long(FLAG_DOCS_FORMAT)
+                    .help("Choose what kind of documentation to generate\n(`markdown` writes a .md file per module, for static site generators. `json` writes a single docs.json file, for package registries and other tools.)")
+                    .possible_values(["html", "markdown", "json"])
+                    .default_value("html")
+                    .required(false)
+                )
+                .arg(Arg::new(FLAG_COVERAGE)
+                    .long(FLAG_COVERAGE)
+                    .help("Instead of generating documentation, list the exposed values and types that have no doc comment\n(Exits with a nonzero status code if there are any, so this can be used in CI.)")
+                    .required(false)
+                )
+                .arg(Arg::new(FLAG_SEARCH)
+                    .long(FLAG_SEARCH)
+                    .help("Instead of generating documentation, list the top-level definitions whose name or type matches the query")
+                    .value_name("QUERY")
+                    .takes_value(true)
+                    .conflicts_with(FLAG_COVERAGE)
+                    .required(false)
+                )
         )
         .subcommand(Command::new(CMD_GLUE)
             .about("Generate glue code between a platform's Roc API and its host language")
@@ -288,10 +620,24 @@ pub fn build_app<'a>() -> Command<'a> {
             )
             .arg(
                 Arg::new(GLUE_FILE)
-                    .help("The filename for the generated glue code\n(Currently, this must be a .rs file because only Rust glue generation is supported so far.)")
+                    .help("The filename for the generated glue code\n(Currently, this must be a .rs file for Rust glue, a .h file for a C header, or a .zig file for Zig glue. Rust glue also comes with layout round-trip tests, in a _layout_tests.rs file next to it.)")
                     .allow_invalid_utf8(true)
                     .required(true)
             )
+            .arg(
+                Arg::new(FLAG_ASYNC_EFFECTS)
+                    .long(FLAG_ASYNC_EFFECTS)
+                    .help("Also generate a trait for implementing the platform's effects with async functions, along with the roc_fx_ functions that call it\n(Currently, this is only supported for Rust glue.)")
+                    .required(false),
+            )
+        )
+        .subcommand(Command::new(CMD_EXPLAIN)
+            .about("Explain a problem by its error code (e.g. `roc explain E1001`), with examples and common fixes")
+            .arg(
+                Arg::new(ERROR_CODE)
+                    .help("The error code, as shown in the header of the problem's report")
+                    .required(true)
+            )
         )
         .subcommand(Command::new(CMD_GEN_STUB_LIB)
             .about("Generate a stubbed shared library that can be used for linking a platform binary.\nThe stubbed library has prototypes, but no function bodies.\n\nNote: This command will be removed in favor of just using `roc build` once all platforms support the surgical linker")
@@ -316,11 +662,23 @@ pub fn build_app<'a>() -> Command<'a> {
         .arg(flag_optimize)
         .arg(flag_max_threads.clone())
         .arg(flag_opt_size)
+        .arg(flag_opt_level)
+        .arg(flag_llvm_passes)
+        .arg(flag_lto)
+        .arg(flag_target_cpu)
+        .arg(flag_target_features)
+        .arg(flag_sanitize)
+        .arg(flag_no_llvm_cache)
+        .arg(flag_profile_generate)
+        .arg(flag_profile_use)
         .arg(flag_dev)
         .arg(flag_debug)
         .arg(flag_time)
+        .arg(flag_lint)
+        .arg(flag_deny_warnings)
         .arg(flag_linker)
         .arg(flag_prebuilt)
+        .arg(flag_frac_default)
         .arg(roc_file_to_run.required(false))
         .arg(args_for_app);
 
@@ -365,6 +723,10 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
     use roc_packaging::cache;
     use roc_target::TargetInfo;
 
+    if matches.is_present(FLAG_VALGRIND) && env::var_os(UNDER_VALGRIND_VAR).is_none() {
+        return test_under_valgrind();
+    }
+
     let start_time = Instant::now();
     let arena = Bump::new();
     let filename = matches.value_of_os(ROC_FILE).unwrap();
@@ -390,6 +752,20 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
         Some(n) => Threading::AtMost(n),
     };
 
+    let frac_default = matches
+        .value_of(FLAG_FRAC_DEFAULT)
+        .and_then(FloatWidth::from_type_str)
+        .unwrap_or(FloatWidth::F64);
+
+    let test_threads = match matches
+        .value_of(FLAG_TEST_THREADS)
+        .and_then(|s| s.parse::<usize>().ok())
+    {
+        None => std::thread::available_parallelism().map_or(1, |n| n.get()),
+        Some(0) => user_error!("cannot run tests with 0 threads"),
+        Some(n) => n,
+    };
+
     let path = Path::new(filename);
 
     // Spawn the root task
@@ -414,6 +790,7 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
     let target = &triple;
     let opt_level = opt_level;
     let target_info = TargetInfo::from(target);
+    let is_wasm32 = matches!(target.architecture, Architecture::Wasm32);
 
     // Step 1: compile the app and generate the .o file
     let subs_by_module = Default::default();
@@ -422,17 +799,61 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
         target_info,
         // TODO: expose this from CLI?
         render: roc_reporting::report::RenderTarget::ColorTerminal,
-        palette: roc_reporting::report::DEFAULT_PALETTE,
+        palette: roc_reporting::report::Palette::from_env(),
         threading,
         exec_mode: ExecutionMode::Test,
+        frac_default,
+        opt_level,
+    };
+    let original_src = std::fs::read_to_string(path)?;
+
+    let (src, snapshot_expects) =
+        roc_repl_expect::snapshot::rewrite_snapshot_expects(path, &original_src);
+
+    let snapshots = roc_repl_expect::snapshot::Snapshots {
+        expects: snapshot_expects,
+        update: matches.is_present(FLAG_UPDATE_SNAPSHOTS),
+    };
+
+    // The other expects run without the property tests, which run afterwards.
+    let (src_with_properties, src, properties) =
+        roc_repl_expect::property::rewrite_property_expects(&src);
+    let property_load_config = load_config.clone();
+
+    if is_wasm32 && (!snapshots.expects.is_empty() || !properties.is_empty()) {
+        user_error!("Snapshot and property `expect`s can't run on wasm32 yet, because the test runner can't read their values back out of a wasm32 test.");
+    }
+
+    let load_result = if matches.is_present(FLAG_DOC_TESTS)
+        || !snapshots.expects.is_empty()
+        || !properties.is_empty()
+    {
+        let src = if matches.is_present(FLAG_DOC_TESTS) {
+            doc_tests::uncomment_doc_tests(&src)
+        } else {
+            src
+        };
+
+        roc_load::load_and_monomorphize_from_str(
+            arena,
+            path.to_path_buf(),
+            arena.alloc_str(&src),
+            path.parent()
+                .unwrap_or_else(|| Path::new("."))
+                .to_path_buf(),
+            subs_by_module,
+            RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
+            load_config,
+        )
+    } else {
+        roc_load::load_and_monomorphize(
+            arena,
+            path.to_path_buf(),
+            subs_by_module,
+            RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
+            load_config,
+        )
     };
-    let load_result = roc_load::load_and_monomorphize(
-        arena,
-        path.to_path_buf(),
-        subs_by_module,
-        RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
-        load_config,
-    );
 
     let mut loaded = match load_result {
         Ok(loaded) => loaded,
@@ -440,26 +861,23 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
             return handle_loading_problem(problem);
         }
         Err(LoadMonomorphizedError::ErrorModule(module)) => {
-            return handle_error_module(module, start_time.elapsed(), filename, false);
+            return handle_error_module(
+                module,
+                start_time.elapsed(),
+                filename,
+                &lint_overrides(matches),
+                false,
+            );
         }
     };
-    let problems = report_problems_monomorphized(&mut loaded);
+    let problems = report_problems_monomorphized(&mut loaded, &lint_overrides(matches), None);
 
     let mut expectations = std::mem::take(&mut loaded.expectations);
 
     let interns = loaded.interns.clone();
 
-    let (lib, expects, layout_interner) = roc_repl_expect::run::expect_mono_module_to_dylib(
-        arena,
-        target.clone(),
-        loaded,
-        opt_level,
-        LlvmBackendMode::CliTest,
-    )
-    .unwrap();
-
     // Print warnings before running tests.
-    {
+    let print_warnings = || {
         debug_assert_eq!(
             problems.errors, 0,
             "if there were errors, we would have already exited."
@@ -468,12 +886,76 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
             print_problems(problems, start_time.elapsed());
             println!(".\n\nRunning tests…\n\n\x1B[36m{}\x1B[39m", "─".repeat(80));
         }
+    };
+
+    if is_wasm32 {
+        use roc_repl_expect::wasm::{
+            expect_mono_module_to_wasm, run_toplevel_expects_wasm, WasmRuntime,
+        };
+
+        let (wasm_bytes, expects) = expect_mono_module_to_wasm(arena, loaded, opt_level)?;
+
+        print_warnings();
+
+        let arena = &bumpalo::Bump::new();
+        let interns = arena.alloc(interns);
+
+        let (failed, passed) = run_toplevel_expects_wasm(
+            &mut std::io::stdout(),
+            roc_reporting::report::RenderTarget::ColorTerminal,
+            arena,
+            interns,
+            &mut expectations,
+            &wasm_bytes,
+            expects,
+            &WasmRuntime::from_env(),
+        )?;
+
+        return Ok(print_test_results(failed, passed, start_time.elapsed()));
+    }
+
+    let coverage = matches.value_of_os(FLAG_COVERAGE).map(|dir| {
+        roc_repl_expect::coverage::enable(&loaded.sources, &loaded.def_regions);
+
+        (
+            PathBuf::from(dir),
+            loaded.sources.clone(),
+            loaded.def_regions.clone(),
+        )
+    });
+
+    let fx_harness = match matches.value_of_os(FLAG_FX_HARNESS) {
+        Some(path) => Some(PathBuf::from(path)),
+        None => loaded.platform_path.as_deref().and_then(|platform_path| {
+            roc_repl_expect::fx_harness::platform_harness(platform_path, target)
+        }),
+    };
+
+    if let Some(path) = fx_harness {
+        if let Err(message) = roc_repl_expect::fx_harness::load(&path) {
+            user_error!("{}", message);
+        }
     }
 
+    let (lib, expects, layout_interner) = roc_repl_expect::run::expect_mono_module_to_dylib(
+        arena,
+        target.clone(),
+        loaded,
+        opt_level,
+        LlvmBackendMode::CliTest,
+    )
+    .unwrap();
+
+    print_warnings();
+
     // Run the tests.
     let arena = &bumpalo::Bump::new();
     let interns = arena.alloc(interns);
 
+    if matches.is_present(FLAG_LEAK_CHECK) || matches.is_present(FLAG_VALGRIND) {
+        roc_repl_expect::leak_check::enable();
+    }
+
     let mut writer = std::io::stdout();
 
     let (failed, passed) = roc_repl_expect::run::run_toplevel_expects(
@@ -485,11 +967,82 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
         &lib,
         &mut expectations,
         expects,
+        &snapshots,
+        test_threads,
     )
     .unwrap();
 
-    let total_time = start_time.elapsed();
+    let (failed, passed) = if properties.is_empty() {
+        (failed, passed)
+    } else {
+        let seed = match matches.value_of(FLAG_SEED) {
+            Some(seed) => seed.parse().unwrap(),
+            None => std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map_or(0, |duration| duration.as_nanos() as u64),
+        };
+
+        let (property_failed, property_passed) = test_properties(
+            path,
+            &src_with_properties,
+            &properties,
+            seed,
+            target,
+            &lint_overrides(matches),
+            property_load_config,
+        )?;
+
+        (failed + property_failed, passed + property_passed)
+    };
+
+    if let Some((dir, sources, def_regions)) = coverage {
+        let (covered, total) =
+            roc_repl_expect::coverage::write_coverage(&dir, interns, &sources, &def_regions)?;
+
+        println!(
+            "\n{} of {} definitions ran. Wrote the coverage report to {}",
+            covered,
+            total,
+            dir.join("index.html").display()
+        );
+    }
+
+    Ok(print_test_results(failed, passed, start_time.elapsed()))
+}
+
+/// Set when `roc test --valgrind` runs itself under valgrind, so it doesn't do that again
+#[cfg(not(windows))]
+const UNDER_VALGRIND_VAR: &str = "ROC_UNDER_VALGRIND";
+
+/// Run this same `roc test` command again, under valgrind, and return its exit code.
+///
+/// valgrind's own leak check is off, because the compiler deliberately leaks some of its own
+/// memory; the built-in leak check covers the tests' Roc heap allocations instead.
+#[cfg(not(windows))]
+fn test_under_valgrind() -> io::Result<i32> {
+    let roc = env::current_exe()?;
+
+    let status = process::Command::new("valgrind")
+        .args(["--quiet", "--error-exitcode=1", "--leak-check=no"])
+        .arg(roc)
+        .args(env::args_os().skip(1))
+        .env(UNDER_VALGRIND_VAR, "1")
+        .status();
+
+    match status {
+        Ok(status) => Ok(status.code().unwrap_or(1)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {
+            user_error!(
+                "`roc test --valgrind` needs valgrind, but it could not be found on the PATH."
+            )
+        }
+        Err(err) => Err(err),
+    }
+}
 
+/// Print how many tests failed and passed, and return the exit code for `roc test`
+#[cfg(not(windows))]
+fn print_test_results(failed: usize, passed: usize, total_time: std::time::Duration) -> i32 {
     if failed == 0 && passed == 0 {
         // TODO print this in a more nicely formatted way!
         println!("No expectations were found.");
@@ -499,7 +1052,7 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
         // you actually have zero tests, but it can save you from
         // having a change to your CI script accidentally stop
         // running tests altogether!
-        Ok(2)
+        2
     } else {
         let failed_color = if failed == 0 {
             32 // green
@@ -512,7 +1065,7 @@ pub fn test(matches: &ArgMatches, triple: Triple) -> io::Result<i32> {
             total_time.as_millis(),
         );
 
-        Ok((failed > 0) as i32)
+        (failed > 0) as i32
     }
 }
 
@@ -606,20 +1159,34 @@ pub fn build(
         }
     };
 
+    let llvm_opt_level = match (
+        matches.is_present(FLAG_OPTIMIZE),
+        matches.is_present(FLAG_OPT_SIZE),
+        matches.value_of(FLAG_OPT_LEVEL),
+    ) {
+        (true, false, None) => Some(LlvmOptLevel::O3),
+        (false, true, None) => Some(LlvmOptLevel::Os),
+        (false, false, Some(level)) => LlvmOptLevel::from_flag(level),
+        (false, false, None) => None,
+        _ => {
+            user_error!("build can be only one of `--optimize`, `--opt-size`, and `-O`")
+        }
+    };
+
     let opt_level = if let BuildConfig::BuildAndRunIfNoErrors = config {
         OptLevel::Development
     } else {
-        match (
-            matches.is_present(FLAG_OPTIMIZE),
-            matches.is_present(FLAG_OPT_SIZE),
-        ) {
-            (true, false) => OptLevel::Optimize,
-            (false, true) => OptLevel::Size,
-            (false, false) => OptLevel::Normal,
-            (true, true) => {
-                user_error!("build can be only one of `--optimize` and `--opt-size`")
-            }
+        llvm_opt_level.map_or(OptLevel::Normal, LlvmOptLevel::opt_level)
+    };
+
+    let llvm_pipeline = match matches.value_of(FLAG_LLVM_PASSES) {
+        Some(passes) => {
+            LlvmPipeline::parse_passes(passes).unwrap_or_else(|problem| user_error!("{}", problem))
         }
+        None => match llvm_opt_level {
+            Some(level) if !matches!(config, BuildAndRunIfNoErrors) => LlvmPipeline::Level(level),
+            _ => opt_level.into(),
+        },
     };
     let emit_debug_info = matches.is_present(FLAG_DEBUG);
     let emit_timings = matches.is_present(FLAG_TIME);
@@ -634,11 +1201,64 @@ pub fn build(
         Some(n) => Threading::AtMost(n),
     };
 
+    let frac_default = matches
+        .value_of(FLAG_FRAC_DEFAULT)
+        .and_then(FloatWidth::from_type_str)
+        .unwrap_or(FloatWidth::F64);
+
     let wasm_dev_backend = matches!(opt_level, OptLevel::Development)
         && matches!(code_gen_backend, CodeGenBackend::Wasm);
 
+    let target_cpu_flag = matches.value_of(FLAG_TARGET_CPU);
+    let target_features_flag = matches.value_of(FLAG_TARGET_FEATURES);
+    if (target_cpu_flag == Some("native") || target_features_flag == Some("native"))
+        && triple != Triple::host()
+    {
+        user_error!("`native` only works when building for the current machine, so it can't be combined with a different --target")
+    }
+    let target_cpu = TargetCpu::from_flags(target_cpu_flag, target_features_flag);
+
+    let lto = matches.is_present(FLAG_LTO);
+
+    let sanitizers: Vec<Sanitizer> = matches
+        .values_of(FLAG_SANITIZE)
+        .into_iter()
+        .flatten()
+        .filter_map(Sanitizer::from_flag)
+        .collect();
+
+    let pgo = if matches.is_present(FLAG_PROFILE_GENERATE) {
+        Some(Pgo::Generate)
+    } else {
+        matches
+            .value_of_os(FLAG_PROFILE_USE)
+            .map(|profile| Pgo::Use(PathBuf::from(profile)))
+    };
+
+    // these all need the LLVM backend, and the clang driver to do the linking
+    let needs_clang_link = lto || !sanitizers.is_empty() || pgo.is_some();
+
+    if needs_clang_link && !matches!(code_gen_backend, CodeGenBackend::Llvm) {
+        user_error!("`--lto`, `--sanitize`, and the `--profile-*` flags are only supported by the LLVM backend for native targets, so they can't be combined with `--dev` or a wasm32 target")
+    }
+
+    if needs_clang_link
+        && !matches!(
+            triple.operating_system,
+            OperatingSystem::Linux | OperatingSystem::Darwin
+        )
+    {
+        user_error!("`--lto`, `--sanitize`, and the `--profile-*` flags are only supported on Linux and macOS, so they can't be combined with a {} target", triple)
+    }
+
     let linking_strategy = if wasm_dev_backend {
         LinkingStrategy::Additive
+    } else if needs_clang_link {
+        if matches.value_of(FLAG_LINKER) == Some("surgical") {
+            user_error!("`--lto`, `--sanitize`, and the `--profile-*` flags need the legacy linker, so they can't be combined with `--linker=surgical`")
+        }
+
+        LinkingStrategy::Legacy
     } else if !roc_linker::supported(link_type, &triple)
         || matches.value_of(FLAG_LINKER) == Some("legacy")
     {
@@ -652,8 +1272,11 @@ pub fn build(
     } else {
         // When compiling for a different target, default to assuming a prebuilt platform.
         // Otherwise compilation would most likely fail because many toolchains assume you're compiling for the current machine.
-        // We make an exception for Wasm, because cross-compiling is the norm in that case.
-        triple != Triple::host() && !matches!(triple.architecture, Architecture::Wasm32)
+        // We make an exception for Wasm, because cross-compiling is the norm in that case,
+        // and for musl, because we build its hosts with zig, which comes with musl.
+        triple != Triple::host()
+            && !matches!(triple.architecture, Architecture::Wasm32)
+            && !matches!(triple.environment, Environment::Musl)
     };
 
     let wasm_dev_stack_bytes: Option<u32> = matches
@@ -663,6 +1286,26 @@ pub fn build(
         .and_then(|s| s.parse::<u32>().ok())
         .map(|x| x * 1024);
 
+    let lint_overrides = lint_overrides(matches);
+
+    // Only `roc build` has this flag
+    let explain_specializations = matches
+        .try_contains_id(FLAG_EXPLAIN_SPECIALIZATIONS)
+        .unwrap_or(false);
+
+    // The package cache lives in `<roc cache>/packages`, so this goes next to it.
+    let llvm_cache_dir = if matches.is_present(FLAG_NO_LLVM_CACHE) {
+        None
+    } else {
+        Some(roc_packaging::cache::roc_cache_dir().with_file_name("llvm"))
+    };
+
+    // Only `roc build` has these flags
+    let post_link_options = PostLinkOptions {
+        strip: matches.try_contains_id(FLAG_STRIP).unwrap_or(false),
+        compress: matches.try_contains_id(FLAG_COMPRESS).unwrap_or(false),
+    };
+
     let build_ordering = match config {
         BuildAndRunIfNoErrors => BuildOrdering::BuildIfChecks,
         _ => BuildOrdering::AlwaysBuild,
@@ -672,6 +1315,12 @@ pub fn build(
         backend: code_gen_backend,
         opt_level,
         emit_debug_info,
+        llvm_pipeline,
+        lto,
+        target_cpu,
+        sanitizers,
+        llvm_cache_dir,
+        pgo,
     };
 
     let res_binary_path = build_file(
@@ -684,7 +1333,11 @@ pub fn build(
         linking_strategy,
         prebuilt,
         threading,
+        frac_default,
         wasm_dev_stack_bytes,
+        explain_specializations,
+        &lint_overrides,
+        post_link_options,
         roc_cache_dir,
         build_ordering,
     );
@@ -756,7 +1409,7 @@ pub fn build(
             }
         }
         Err(BuildFileError::ErrorModule { module, total_time }) => {
-            handle_error_module(module, total_time, filename, true)
+            handle_error_module(module, total_time, filename, &lint_overrides, true)
         }
         Err(BuildFileError::LoadingProblem(problem)) => handle_loading_problem(problem),
     }
@@ -766,11 +1419,13 @@ fn handle_error_module(
     mut module: roc_load::LoadedModule,
     total_time: std::time::Duration,
     filename: &OsStr,
+    lint_overrides: &LintConfig,
     print_run_anyway_hint: bool,
 ) -> io::Result<i32> {
     debug_assert!(module.total_problems() > 0);
 
-    let problems = roc_build::program::report_problems_typechecked(&mut module);
+    let problems =
+        roc_build::program::report_problems_typechecked(&mut module, lint_overrides, None);
 
     print_problems(problems, total_time);
 
@@ -789,6 +1444,193 @@ fn handle_error_module(
     Ok(problems.exit_code())
 }
 
+/// Run the main module's property tests (see `roc_repl_expect::property`), and return how many
+/// failed and passed.
+#[cfg(not(windows))]
+fn test_properties(
+    path: &Path,
+    src_with_defs: &str,
+    properties: &[roc_repl_expect::property::PropertyExpect],
+    seed: u64,
+    target: &Triple,
+    lint_overrides: &LintConfig,
+    load_config: roc_load::LoadConfig,
+) -> io::Result<(usize, usize)> {
+    use roc_build::program::report_problems_typechecked;
+    use roc_gen_llvm::llvm::build::LlvmBackendMode;
+    use roc_packaging::cache;
+    use roc_problem::Severity;
+    use roc_repl_expect::property::{
+        parameter_shapes, run_properties, PropertyOutcome, TestableProperty, DEFAULT_CASES,
+    };
+
+    let start_time = Instant::now();
+    let src_dir = path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .to_path_buf();
+
+    // Find out the types of the properties' parameters.
+    let arena = Bump::new();
+    let mut loaded = match roc_load::load_and_typecheck_str(
+        &arena,
+        path.to_path_buf(),
+        arena.alloc_str(src_with_defs),
+        src_dir.clone(),
+        Default::default(),
+        load_config.target_info,
+        load_config.render,
+        RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
+        load_config.palette,
+    ) {
+        Ok(loaded) => loaded,
+        Err(problem) => {
+            handle_loading_problem(problem)?;
+
+            return Ok((properties.len(), 0));
+        }
+    };
+
+    // The rest of the module has been checked already, so only the errors in the properties are
+    // new. (Its warnings have been shown, and the defs the properties were rewritten to are
+    // unused, which is expected.)
+    let home = loaded.module_id;
+
+    if let Some(problems) = loaded.can_problems.get_mut(&home) {
+        problems.retain(|problem| problem.severity() == Severity::RuntimeError);
+    }
+
+    if let Some(problems) = loaded.type_problems.get_mut(&home) {
+        problems.retain(|problem| problem.severity() == Severity::RuntimeError);
+    }
+
+    if loaded.total_problems() > 0 {
+        let problems = report_problems_typechecked(&mut loaded, lint_overrides, None);
+        print_problems(problems, start_time.elapsed());
+        println!();
+
+        return Ok((properties.len(), 0));
+    }
+
+    let declarations = loaded.declarations_by_id.get(&home);
+    let subs = loaded.solved.inner_mut();
+
+    let shapes: Vec<_> = properties
+        .iter()
+        .map(|property| {
+            let declarations = declarations?;
+            let index = declarations
+                .symbols
+                .iter()
+                .position(|symbol| symbol.value.as_str(&loaded.interns) == property.name)?;
+
+            parameter_shapes(subs, declarations.variables[index])
+        })
+        .collect();
+
+    let testable: Vec<_> = properties
+        .iter()
+        .zip(&shapes)
+        .filter_map(|(property, shapes)| {
+            shapes
+                .clone()
+                .map(|shapes| TestableProperty { property, shapes })
+        })
+        .collect();
+
+    let opt_level = load_config.opt_level;
+
+    let outcomes = run_properties(
+        src_with_defs,
+        &testable,
+        seed,
+        DEFAULT_CASES,
+        |src, generated_start| {
+            let arena = Bump::new();
+
+            let loaded = roc_load::load_and_monomorphize_from_str(
+                &arena,
+                path.to_path_buf(),
+                arena.alloc_str(src),
+                src_dir.clone(),
+                Default::default(),
+                RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
+                load_config.clone(),
+            )
+            .map_err(|_| {
+                "I could not compile the calls to it with the arguments I generated.".to_string()
+            })?;
+
+            let (lib, expects, _layout_interner) =
+                roc_repl_expect::run::expect_mono_module_to_dylib(
+                    &arena,
+                    target.clone(),
+                    loaded,
+                    opt_level,
+                    LlvmBackendMode::CliTest,
+                )
+                .map_err(|err| err.to_string())?;
+
+            let generated: Vec<_> = expects
+                .pure
+                .iter()
+                .copied()
+                .filter(|expect| expect.region.start().offset >= generated_start)
+                .collect();
+
+            Ok(roc_repl_expect::run::failing_expects(&lib, &generated))
+        },
+    );
+
+    // Render the outcomes against the module as it was written.
+    let source = std::fs::read_to_string(path)?;
+    let renderer = roc_reporting::error::expect::Renderer::new(
+        &arena,
+        &loaded.interns,
+        load_config.render,
+        home,
+        path.to_path_buf(),
+        &source,
+    );
+
+    let mut writer = std::io::stdout();
+    let mut outcomes = outcomes.into_iter();
+    let (mut failed, mut passed) = (0, 0);
+
+    for (property, shapes) in properties.iter().zip(&shapes) {
+        let outcome = match shapes {
+            Some(_) => outcomes.next().unwrap_or(PropertyOutcome::Passed { cases: 0 }),
+            None => PropertyOutcome::Untestable {
+                reason: "I can only test functions that return a Bool, with arguments that are numbers, Bools, strings, lists, records or tags of those.".to_string(),
+            },
+        };
+
+        match outcome {
+            PropertyOutcome::Passed { .. } => passed += 1,
+            PropertyOutcome::Failed {
+                cases, arguments, ..
+            } => {
+                renderer.render_property_failure(
+                    &mut writer,
+                    property.region,
+                    &arguments,
+                    cases,
+                    seed,
+                )?;
+                println!();
+                failed += 1;
+            }
+            PropertyOutcome::Untestable { reason } => {
+                renderer.render_untestable_property(&mut writer, property.region, &reason)?;
+                println!();
+                failed += 1;
+            }
+        }
+    }
+
+    Ok((failed, passed))
+}
+
 fn handle_loading_problem(problem: LoadingProblem) -> io::Result<i32> {
     match problem {
         LoadingProblem::FormattedReport(report) => {
@@ -961,7 +1803,9 @@ fn roc_run_native<I: IntoIterator<Item = S>, S: AsRef<OsStr>>(
             .collect_in(arena);
 
         match opt_level {
-            OptLevel::Development => roc_dev_native(arena, executable, argv, envp, expect_metadata),
+            OptLevel::Development => {
+                roc_dev_native(arena, executable, binary_bytes, argv, envp, expect_metadata)
+            }
             OptLevel::Normal | OptLevel::Size | OptLevel::Optimize => {
                 roc_run_native_fast(executable, &argv, &envp);
             }
@@ -976,6 +1820,9 @@ unsafe fn roc_run_native_fast(
     argv: &[*const c_char],
     envp: &[*const c_char],
 ) {
+    // The compiler's part of the trace is done, and exec'ing means nothing else will write it.
+    roc_tracing::write_chrome_trace();
+
     if executable.execve(argv, envp) != 0 {
         internal_error!(
             "libc::{}({:?}, ..., ...) failed: {:?}",
@@ -1038,6 +1885,7 @@ impl ExecutableFile {
 fn roc_dev_native(
     arena: &Bump,
     executable: ExecutableFile,
+    binary_bytes: &[u8],
     argv: bumpalo::collections::Vec<*const c_char>,
     envp: bumpalo::collections::Vec<*const c_char>,
     expect_metadata: ExpectMetadata,
@@ -1111,6 +1959,23 @@ fn roc_dev_native(
                         )
                         .unwrap();
 
+                        memory.reset();
+                    }
+                    ChildProcessMsg::Panic => {
+                        // The host reports the panic itself once we let the program go on.
+                        let frames =
+                            roc_repl_expect::run::panic_frames_in_memory(&memory, binary_bytes);
+
+                        if !frames.is_empty() {
+                            eprintln!("\nThe program crashed in:\n");
+
+                            for frame in frames {
+                                eprintln!("    {}", frame);
+                            }
+
+                            eprintln!();
+                        }
+
                         memory.reset();
                     }
                 }
@@ -1277,6 +2142,8 @@ pub enum Target {
     Linux32,
     #[strum(serialize = "linux64")]
     Linux64,
+    #[strum(serialize = "linux-x64-musl")]
+    LinuxX64Musl,
     #[strum(serialize = "windows64")]
     Windows64,
     #[strum(serialize = "wasm32")]
@@ -1303,6 +2170,13 @@ impl Target {
                 binary_format: BinaryFormat::Elf,
             },
             Linux64 => Triple {
+                architecture: Architecture::X86_64,
+                vendor: Vendor::Unknown,
+                operating_system: OperatingSystem::Linux,
+                environment: Environment::Gnu,
+                binary_format: BinaryFormat::Elf,
+            },
+            LinuxX64Musl => Triple {
                 architecture: Architecture::X86_64,
                 vendor: Vendor::Unknown,
                 operating_system: OperatingSystem::Linux,
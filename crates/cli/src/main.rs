@@ -2,14 +2,16 @@
 use roc_build::link::LinkType;
 use roc_cli::build::check_file;
 use roc_cli::{
-    build_app, format, test, BuildConfig, FormatMode, Target, CMD_BUILD, CMD_CHECK, CMD_DEV,
-    CMD_DOCS, CMD_EDIT, CMD_FORMAT, CMD_GEN_STUB_LIB, CMD_GLUE, CMD_REPL, CMD_RUN, CMD_TEST,
-    CMD_VERSION, DIRECTORY_OR_FILES, FLAG_CHECK, FLAG_LIB, FLAG_NO_LINK, FLAG_TARGET, FLAG_TIME,
-    GLUE_FILE, ROC_FILE,
+    build_app, explain, format, lint_overrides, test, BuildConfig, FormatMode, Target, CMD_BUILD,
+    CMD_CHECK, CMD_DEV, CMD_DOCS, CMD_EDIT, CMD_EXPLAIN, CMD_FORMAT, CMD_GEN_STUB_LIB, CMD_GLUE,
+    CMD_REPL, CMD_RUN, CMD_TEST, CMD_VERSION, DIRECTORY_OR_FILES, ERROR_CODE, FLAG_ASYNC_EFFECTS,
+    FLAG_CHECK, FLAG_COVERAGE, FLAG_DOCS_FORMAT, FLAG_INTERPRET, FLAG_LIB, FLAG_NO_LINK,
+    FLAG_REPORT_HTML, FLAG_SEARCH, FLAG_TARGET, FLAG_TIME, FLAG_WRITE_SIGNATURES, GLUE_FILE,
+    ROC_FILE,
 };
-use roc_docs::generate_docs_html;
+use roc_docs::{generate_docs, report_docs_coverage, search_docs, DocsFormat};
 use roc_error_macros::user_error;
-use roc_load::{LoadingProblem, Threading};
+use roc_load::{FloatWidth, LoadingProblem, Threading};
 use roc_packaging::cache::{self, RocCacheDir};
 use std::fs::{self, FileType};
 use std::io;
@@ -28,6 +30,7 @@ use roc_cli::build;
 
 fn main() -> io::Result<()> {
     let _tracing_guards = roc_tracing::setup_tracing!();
+    roc_tracing::setup_chrome_trace();
 
     let matches = build_app().get_matches();
 
@@ -64,7 +67,9 @@ fn main() -> io::Result<()> {
         }
         Some((CMD_TEST, matches)) => {
             if matches.is_present(ROC_FILE) {
-                test(matches, Triple::host())
+                let target: Target = matches.value_of_t(FLAG_TARGET).unwrap_or_default();
+
+                test(matches, target.to_triple())
             } else {
                 eprintln!("What .roc file do you want to test? Specify it at the end of the `roc test` command.");
 
@@ -90,12 +95,18 @@ fn main() -> io::Result<()> {
             let input_path = Path::new(matches.value_of_os(ROC_FILE).unwrap());
             let output_path = Path::new(matches.value_of_os(GLUE_FILE).unwrap());
 
-            if Some("rs") == output_path.extension().and_then(OsStr::to_str) {
-                roc_glue::generate(input_path, output_path)
-            } else {
-                eprintln!("Currently, `roc glue` only supports generating Rust glue files (with the .rs extension). In the future, the plan is to decouple `roc glue` from any particular output format, by having it accept a second .roc file which gets executed as a plugin to generate glue code for any desired language. However, this has not yet been implemented, and for now only .rs is supported.");
+            match roc_glue::GlueLanguage::from_path(output_path) {
+                Some(language) => roc_glue::generate(
+                    input_path,
+                    output_path,
+                    language,
+                    matches.is_present(FLAG_ASYNC_EFFECTS),
+                ),
+                None => {
+                    eprintln!("Currently, `roc glue` only supports generating Rust glue files (with the .rs extension), C headers (with the .h extension), and Zig glue files (with the .zig extension). In the future, the plan is to decouple `roc glue` from any particular output format, by having it accept a second .roc file which gets executed as a plugin to generate glue code for any desired language. However, this has not yet been implemented, and for now only .rs, .h, and .zig are supported.");
 
-                Ok(1)
+                    Ok(1)
+                }
             }
         }
         Some((CMD_GEN_STUB_LIB, matches)) => {
@@ -142,13 +153,21 @@ fn main() -> io::Result<()> {
                 Some(1) => Threading::Single,
                 Some(n) => Threading::AtMost(n),
             };
+            let frac_default = matches
+                .value_of(roc_cli::FLAG_FRAC_DEFAULT)
+                .and_then(FloatWidth::from_type_str)
+                .unwrap_or(FloatWidth::F64);
 
             match check_file(
                 &arena,
                 roc_file_path,
                 emit_timings,
+                matches.is_present(FLAG_WRITE_SIGNATURES),
                 RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
                 threading,
+                frac_default,
+                &lint_overrides(matches),
+                matches.value_of_os(FLAG_REPORT_HTML).map(Path::new),
             ) {
                 Ok((problems, total_time)) => {
                     println!(
@@ -191,7 +210,17 @@ fn main() -> io::Result<()> {
                 }
             }
         }
-        Some((CMD_REPL, _)) => Ok(roc_repl_cli::main()),
+        Some((CMD_REPL, matches)) => {
+            use roc_repl_cli::repl_state::ReplBackend;
+
+            let backend = if matches.is_present(FLAG_INTERPRET) {
+                ReplBackend::Interpreter
+            } else {
+                ReplBackend::Llvm
+            };
+
+            Ok(roc_repl_cli::main(backend))
+        }
         Some((CMD_EDIT, matches)) => {
             match matches
                 .values_of_os(DIRECTORY_OR_FILES)
@@ -239,9 +268,22 @@ fn main() -> io::Result<()> {
                 roc_files_recursive(os_str.as_os_str(), metadata.file_type(), &mut roc_files)?;
             }
 
-            generate_docs_html(roc_files);
+            if matches.is_present(FLAG_COVERAGE) {
+                let is_fully_documented = report_docs_coverage(roc_files);
 
-            Ok(0)
+                Ok(if is_fully_documented { 0 } else { 1 })
+            } else if let Some(query) = matches.value_of(FLAG_SEARCH) {
+                let found = search_docs(roc_files, query);
+
+                Ok(if found { 0 } else { 1 })
+            } else {
+                let format =
+                    DocsFormat::try_from(matches.value_of(FLAG_DOCS_FORMAT).unwrap()).unwrap();
+
+                generate_docs(roc_files, format);
+
+                Ok(0)
+            }
         }
         Some((CMD_FORMAT, matches)) => {
             let maybe_values = matches.values_of_os(DIRECTORY_OR_FILES);
@@ -289,6 +331,7 @@ fn main() -> io::Result<()> {
 
             Ok(format_exit_code)
         }
+        Some((CMD_EXPLAIN, matches)) => Ok(explain(matches.value_of(ERROR_CODE).unwrap())),
         Some((CMD_VERSION, _)) => {
             print!(
                 "{}",
@@ -300,6 +343,8 @@ fn main() -> io::Result<()> {
         _ => unreachable!(),
     }?;
 
+    roc_tracing::write_chrome_trace();
+
     std::process::exit(exit_code);
 }
 
@@ -51,6 +51,8 @@ mod cli_run {
     const OPTIMIZE_FLAG: &str = concatcp!("--", roc_cli::FLAG_OPTIMIZE);
     const LINKER_FLAG: &str = concatcp!("--", roc_cli::FLAG_LINKER);
     const CHECK_FLAG: &str = concatcp!("--", roc_cli::FLAG_CHECK);
+    const DOC_TESTS_FLAG: &str = concatcp!("--", roc_cli::FLAG_DOC_TESTS);
+    const DENY_WARNINGS_FLAG: &str = concatcp!("--", roc_cli::FLAG_DENY_WARNINGS);
     const PREBUILT_PLATFORM: &str = concatcp!("--", roc_cli::FLAG_PREBUILT, "=true");
     #[allow(dead_code)]
     const TARGET_FLAG: &str = concatcp!("--", roc_cli::FLAG_TARGET);
@@ -623,6 +625,134 @@ mod cli_run {
         );
     }
 
+    #[test]
+    #[cfg_attr(windows, ignore)]
+    fn doc_tests() {
+        let path = file_path_from_root("crates/cli_testing_examples/expects", "DocTests.roc");
+        let out = run_roc(
+            &[CMD_TEST, DOC_TESTS_FLAG, path.to_str().unwrap()],
+            &[],
+            &[],
+        );
+        let actual = strip_colors(&out.stdout);
+
+        // The failure is reported at the doc comment the expect came from.
+        assert!(
+            actual.contains("12│  expect double 3 == 7"),
+            "expected the failing doc test in:\n{}",
+            actual
+        );
+        assert!(
+            actual.contains("1 failed and 1 passed"),
+            "expected one doc test to pass and one to fail in:\n{}",
+            actual
+        );
+    }
+
+    #[test]
+    #[cfg_attr(windows, ignore)]
+    fn expect_fx_harness_hooks() {
+        let dir = file_path_from_root("crates/cli_testing_examples/expects", "fx-harness");
+        let harness_dir = tempfile::tempdir().unwrap();
+        let harness = harness_dir.path().join("harness.so");
+
+        let out = run_cmd(
+            "zig",
+            [],
+            &[
+                "cc".to_string(),
+                "-shared".to_string(),
+                "-fPIC".to_string(),
+                "-o".to_string(),
+                harness.to_str().unwrap().to_string(),
+                dir.join("harness.c").to_str().unwrap().to_string(),
+            ],
+            [],
+        );
+        assert!(
+            out.status.success(),
+            "could not build the harness: {}",
+            out.stderr
+        );
+
+        let path = file_path_from_root("crates/cli_testing_examples/expects", "ExpectFx.roc");
+        let harness_flag = format!("--{}={}", roc_cli::FLAG_FX_HARNESS, harness.display());
+        let out = run_roc(&[CMD_TEST, &harness_flag, path.to_str().unwrap()], &[], &[]);
+        let actual = strip_colors(&out.stdout);
+
+        // Each expect-fx runs between its own setup and teardown, whether it passes or not.
+        let hooks: Vec<_> = actual
+            .lines()
+            .filter(|line| line.starts_with("harness: "))
+            .collect();
+        assert_eq!(
+            hooks,
+            [
+                "harness: setup",
+                "harness: teardown",
+                "harness: setup",
+                "harness: teardown",
+            ],
+            "in:\n{}",
+            actual
+        );
+        assert!(
+            actual.contains("1 failed and 1 passed"),
+            "expected one expect-fx to pass and one to fail in:\n{}",
+            actual
+        );
+    }
+
+    #[test]
+    #[cfg_attr(windows, ignore)]
+    fn expect_frac_default_dec() {
+        let path = file_path_from_root("crates/cli_testing_examples/expects", "FracDefault.roc");
+        let flag = format!("--{}=Dec", roc_cli::FLAG_FRAC_DEFAULT);
+        let out = run_roc(&[CMD_TEST, &flag, path.to_str().unwrap()], &[], &[]);
+        let actual = strip_colors(&out.stdout);
+
+        // The value is read back as a Dec, which is what the literals compiled to.
+        assert!(
+            actual.lines().any(|line| line.trim() == "sum = 0.3"),
+            "expected the Dec value of the sum in:\n{}",
+            actual
+        );
+        assert!(
+            actual.contains("1 failed and 0 passed"),
+            "expected the expect to fail in:\n{}",
+            actual
+        );
+    }
+
+    #[test]
+    #[cfg_attr(windows, ignore)]
+    fn expect_panic_stack_trace() {
+        let path = file_path_from_root("crates/cli_testing_examples/expects", "Crash.roc");
+        let out = run_roc(&[CMD_TEST, path.to_str().unwrap()], &[], &[]);
+        let actual = strip_colors(&out.stdout);
+
+        assert!(
+            actual.contains("cannot divide by zero"),
+            "expected the crash message in:\n{}",
+            actual
+        );
+
+        // e.g. `Crash.divide (/home/user/roc/crates/cli_testing_examples/expects/Crash.roc:6)`
+        let has_frame = actual.lines().any(|line| {
+            line.contains("Crash.divide (")
+                && line
+                    .split("Crash.roc:")
+                    .nth(1)
+                    .map_or(false, |rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+        });
+
+        assert!(
+            has_frame,
+            "expected a stack trace through Crash.divide in:\n{}",
+            actual
+        );
+    }
+
     #[test]
     #[cfg_attr(
         windows,
@@ -727,6 +857,53 @@ mod cli_run {
         assert!(out.status.success());
     }
 
+    #[test]
+    #[cfg_attr(windows, ignore)]
+    fn sanitize_flag_validation() {
+        let path = file_path_from_root("crates/cli_testing_examples/algorithms", "fibonacci.roc");
+        let path = path.to_str().unwrap();
+
+        let rejected = |args: &[&str], reason: &str| {
+            let out = run_roc(args.iter().chain(&[path]), &[], &[]);
+
+            assert!(!out.status.success(), "{:?} should fail", args);
+            assert!(
+                out.stderr.contains(reason),
+                "expected {:?} to fail because of {:?}, but got:\n{}",
+                args,
+                reason,
+                out.stderr
+            );
+        };
+
+        // Roc's code can't be instrumented for UndefinedBehaviorSanitizer
+        rejected(&[CMD_BUILD, "--sanitize=undefined"], "undefined");
+
+        rejected(&[CMD_BUILD, "--sanitize=address", "--dev"], "LLVM backend");
+        rejected(
+            &[CMD_BUILD, "--sanitize=address", "--linker=surgical"],
+            "--linker=surgical",
+        );
+    }
+
+    #[test]
+    fn lto_flag_validation() {
+        let path = file_path_from_root("crates/cli_testing_examples/algorithms", "fibonacci.roc");
+        let out = run_roc(
+            [
+                CMD_BUILD,
+                "--lto",
+                "--target=windows64",
+                path.to_str().unwrap(),
+            ],
+            &[],
+            &[],
+        );
+
+        assert!(!out.status.success());
+        assert!(out.stderr.contains("Linux and macOS"), "{}", out.stderr);
+    }
+
     #[test]
     #[cfg_attr(windows, ignore)]
     fn interactive_effects() {
@@ -1286,7 +1463,7 @@ mod cli_run {
             &[],
             indoc!(
                 r#"
-                ── TYPE MISMATCH ─────────────────────────────── tests/known_bad/TypeError.roc ─
+                ── TYPE MISMATCH [E1001] ─────────────────────── tests/known_bad/TypeError.roc ─
 
                 Something is off with the body of the main definition:
 
@@ -1322,7 +1499,7 @@ mod cli_run {
             &[],
             indoc!(
                 r#"
-                ── MISSING DEFINITION ────────────────── tests/known_bad/ExposedNotDefined.roc ─
+                ── MISSING DEFINITION [E5003] ────────── tests/known_bad/ExposedNotDefined.roc ─
 
                 bar is listed as exposed, but it isn't defined in this module.
 
@@ -1343,7 +1520,7 @@ mod cli_run {
             &[],
             indoc!(
                 r#"
-                ── UNUSED IMPORT ──────────────────────────── tests/known_bad/UnusedImport.roc ─
+                ── UNUSED IMPORT [E2003] ──────────────────── tests/known_bad/UnusedImport.roc ─
 
                 Nothing from Symbol is used in this module.
 
@@ -1359,6 +1536,29 @@ mod cli_run {
         );
     }
 
+    #[test]
+    fn unused_import_with_deny_warnings() {
+        check_compile_error(
+            &known_bad_file("UnusedImport.roc"),
+            &[DENY_WARNINGS_FLAG],
+            indoc!(
+                r#"
+                ── UNUSED IMPORT [E2003] ──────────────────── tests/known_bad/UnusedImport.roc ─
+
+                Nothing from Symbol is used in this module.
+
+                3│      imports [Symbol.{ Ident }]
+                                 ^^^^^^^^^^^^^^^^
+
+                Since Symbol isn't used, you don't need to import it.
+
+                ────────────────────────────────────────────────────────────────────────────────
+
+                1 error and 0 warnings found in <ignored for test> ms."#
+            ),
+        );
+    }
+
     #[test]
     fn unknown_generates_with() {
         check_compile_error(
@@ -1366,7 +1566,7 @@ mod cli_run {
             &[],
             indoc!(
                 r#"
-                ── UNKNOWN GENERATES FUNCTION ─────── tests/known_bad/UnknownGeneratesWith.roc ─
+                ── UNKNOWN GENERATES FUNCTION [E5010] ─ .../known_bad/UnknownGeneratesWith.roc ─
 
                 I don't know how to generate the foobar function.
 
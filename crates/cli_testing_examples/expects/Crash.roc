@@ -0,0 +1,12 @@
+interface Crash
+    exposes [divide]
+    imports []
+
+divide : I64, I64 -> I64
+divide = \a, b ->
+    if b == 0 then
+        crash "cannot divide by zero"
+    else
+        a // b
+
+expect divide 1 0 == 0
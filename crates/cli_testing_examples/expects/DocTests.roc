@@ -0,0 +1,15 @@
+interface DocTests
+    exposes [double]
+    imports []
+
+## Doubles a number.
+##
+## ```roc
+## expect double 2 == 4
+## ```
+##
+## ```roc
+## expect double 3 == 7
+## ```
+double : I64 -> I64
+double = \x -> x * 2
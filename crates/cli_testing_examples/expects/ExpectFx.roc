@@ -0,0 +1,7 @@
+interface ExpectFx
+    exposes []
+    imports []
+
+expect-fx 1 + 1 == 2
+
+expect-fx 2 + 2 == 5
@@ -0,0 +1,8 @@
+interface FracDefault
+    exposes []
+    imports []
+
+# With `--frac-default Dec`, the sum is exactly 0.3 and is shown as such
+expect
+    sum = 0.1 + 0.2
+    sum == 0.4
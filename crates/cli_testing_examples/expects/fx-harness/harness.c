@@ -0,0 +1,13 @@
+// A harness for ExpectFx.roc's `expect-fx`s, which only says when its hooks run.
+//
+// It writes straight to stdout, because each `expect-fx` runs in a child process that
+// doesn't flush stdio before it exits.
+
+#include <string.h>
+#include <unistd.h>
+
+static void say(const char *line) { write(STDOUT_FILENO, line, strlen(line)); }
+
+void roc_test_setup(void) { say("harness: setup\n"); }
+
+void roc_test_teardown(void) { say("harness: teardown\n"); }
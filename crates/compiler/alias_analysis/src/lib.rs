@@ -1331,7 +1331,7 @@ fn recursive_variant_types<'a>(
     let mut result;
 
     match union_layout {
-        NonRecursive(_) => {
+        NonRecursive(_) | NullablePointer { .. } => {
             unreachable!()
         }
         Recursive(tags) => {
@@ -1455,6 +1455,20 @@ fn expr_spec<'a>(
                     let value_id = build_tuple_value(builder, env, block, arguments)?;
                     return builder.add_make_union(block, &variant_types, *tag_id as u32, value_id);
                 }
+                UnionLayout::NullablePointer {
+                    nullable_id,
+                    pointer,
+                } => {
+                    let tags = UnionLayout::nullable_pointer_tags(*nullable_id, pointer);
+                    let variant_types = non_recursive_variant_types(
+                        env,
+                        builder,
+                        interner,
+                        &tags,
+                        &WhenRecursive::Unreachable,
+                    )?;
+                    return builder.add_make_union(block, &variant_types, *tag_id as u32, data_id);
+                }
                 UnionLayout::NonNullableUnwrapped(_) => {
                     let value_id = data_id;
 
@@ -1484,7 +1498,7 @@ fn expr_spec<'a>(
 
             builder.add_make_named(block, MOD_APP, type_name, tag_value_id)
         }
-        ExprBox { symbol } => {
+        ExprBox { symbol } | ReuseBox { value: symbol, .. } => {
             let value_id = env.symbols[symbol];
 
             with_new_heap_cell(builder, block, value_id)
@@ -1501,7 +1515,7 @@ fn expr_spec<'a>(
             structure,
             union_layout,
         } => match union_layout {
-            UnionLayout::NonRecursive(_) => {
+            UnionLayout::NonRecursive(_) | UnionLayout::NullablePointer { .. } => {
                 let index = (*index) as u32;
                 let tag_value_id = env.symbols[structure];
                 let tuple_value_id =
@@ -1608,6 +1622,19 @@ fn expr_spec<'a>(
 
             let union_layout = match layout {
                 Layout::Union(ul) => ul,
+                Layout::Boxed(_) => {
+                    let heap_cell =
+                        builder.add_get_tuple_field(block, tag_value_id, BOX_CELL_INDEX)?;
+                    let value =
+                        builder.add_get_tuple_field(block, tag_value_id, BOX_VALUE_INDEX)?;
+
+                    let mode = update_mode.to_bytes();
+                    let update_mode_var = UpdateModeVar(&mode);
+
+                    let _unit = builder.add_update(block, update_mode_var, heap_cell)?;
+
+                    return with_new_heap_cell(builder, block, value);
+                }
                 _ => unreachable!(),
             };
 
@@ -1722,6 +1749,15 @@ fn layout_spec_help<'a>(
                         non_recursive_variant_types(env, builder, interner, tags, when_recursive)?;
                     builder.add_union_type(&variant_types)
                 }
+                UnionLayout::NullablePointer {
+                    nullable_id,
+                    pointer,
+                } => {
+                    let tags = UnionLayout::nullable_pointer_tags(*nullable_id, pointer);
+                    let variant_types =
+                        non_recursive_variant_types(env, builder, interner, &tags, when_recursive)?;
+                    builder.add_union_type(&variant_types)
+                }
                 UnionLayout::Recursive(_)
                 | UnionLayout::NullableUnwrapped { .. }
                 | UnionLayout::NullableWrapped { .. }
@@ -1748,7 +1784,9 @@ fn layout_spec_help<'a>(
                 unreachable!()
             }
             WhenRecursive::Loop(union_layout) => match union_layout {
-                UnionLayout::NonRecursive(_) => unreachable!(),
+                UnionLayout::NonRecursive(_) | UnionLayout::NullablePointer { .. } => {
+                    unreachable!()
+                }
                 UnionLayout::Recursive(_)
                 | UnionLayout::NullableUnwrapped { .. }
                 | UnionLayout::NullableWrapped { .. }
@@ -1804,7 +1842,6 @@ fn static_list_type<TC: TypeContext>(builder: &mut TC) -> Result<TypeId> {
 const LIST_CELL_INDEX: u32 = 0;
 const LIST_BAG_INDEX: u32 = 1;
 
-#[allow(dead_code)]
 const BOX_CELL_INDEX: u32 = LIST_CELL_INDEX;
 const BOX_VALUE_INDEX: u32 = LIST_BAG_INDEX;
 
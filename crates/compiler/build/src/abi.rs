@@ -0,0 +1,261 @@
+//! A hash of everything a host relies on when it calls into a Roc app: the compiler's ABI
+//! version, the target, and the layouts of the values the platform exposes to the host.
+//!
+//! The hash is embedded in the app (as [`ABI_HASH_SYMBOL`]) and stored next to each host roc
+//! builds (see [`abi_hash_path`]), so a prebuilt host that no longer matches the platform it is
+//! linked with is reported at build time, instead of corrupting memory at runtime.
+use roc_collections::StableHasher;
+use roc_module::symbol::{Interns, Symbol};
+use roc_mono::ir::ProcLayout;
+use roc_mono::layout::{Builtin, Layout, UnionLayout};
+use std::fmt::Write;
+use std::hash::Hasher;
+use std::path::{Path, PathBuf};
+use target_lexicon::Triple;
+
+/// Bump this whenever a change to the compiler changes how values are laid out in memory or
+/// passed between the host and the app, so hosts built by older compilers are rejected.
+pub const ABI_VERSION: u32 = 1;
+
+/// The app exports its ABI hash as a `u64` under this name, so hosts can check it at runtime.
+pub const ABI_HASH_SYMBOL: &str = "roc__abi_hash";
+
+pub fn abi_hash(
+    target: &Triple,
+    exposed_to_host: &[(Symbol, ProcLayout<'_>)],
+    interns: &Interns,
+) -> u64 {
+    let exposed = exposed_to_host
+        .iter()
+        .map(|(symbol, proc_layout)| (symbol.as_str(interns), proc_layout));
+
+    hash_exposed(target, exposed)
+}
+
+/// The hash is written into the `.abi` files that ship with prebuilt hosts, so it must come out
+/// the same from every compiler that has the same [ABI_VERSION]: it's a [StableHasher] over a
+/// description of each exposed function, rather than anything derived from `Hash` impls.
+fn hash_exposed<'a, 'b: 'a>(
+    target: &Triple,
+    exposed_to_host: impl Iterator<Item = (&'a str, &'a ProcLayout<'b>)>,
+) -> u64 {
+    let mut exposed: Vec<String> = exposed_to_host
+        .map(|(name, proc_layout)| {
+            let mut buf = String::new();
+
+            write!(buf, "{}(", name).unwrap();
+
+            for (index, argument) in proc_layout.arguments.iter().enumerate() {
+                if index > 0 {
+                    buf.push_str(", ");
+                }
+
+                write_layout(&mut buf, argument);
+            }
+
+            buf.push_str(") -> ");
+            write_layout(&mut buf, &proc_layout.result);
+
+            buf
+        })
+        .collect();
+    exposed.sort_unstable();
+
+    let mut hasher = StableHasher::new();
+
+    hasher.write_u32(ABI_VERSION);
+    hasher.write_str(&target.to_string());
+    hasher.write_u64(exposed.len() as u64);
+
+    for function in exposed.iter() {
+        hasher.write_str(function);
+    }
+
+    hasher.finish()
+}
+
+/// Describe a layout the way the host sees it. The host treats what is behind a `Box` and the
+/// values a function captured as opaque, and those are the only places an app's own types can
+/// show up in the platform's API, so they are left out: otherwise every app would get its own hash.
+fn write_layout(buf: &mut String, layout: &Layout<'_>) {
+    match layout {
+        Layout::Builtin(Builtin::Int(width)) => write!(buf, "{:?}", width).unwrap(),
+        Layout::Builtin(Builtin::Float(width)) => write!(buf, "{:?}", width).unwrap(),
+        Layout::Builtin(Builtin::Bool) => buf.push_str("Bool"),
+        Layout::Builtin(Builtin::Decimal) => buf.push_str("Dec"),
+        Layout::Builtin(Builtin::Str) => buf.push_str("Str"),
+        Layout::Builtin(Builtin::List(element)) => {
+            buf.push_str("List ");
+            write_layout(buf, element);
+        }
+        Layout::Struct { field_layouts, .. } => {
+            buf.push('{');
+            write_layouts(buf, field_layouts);
+            buf.push('}');
+        }
+        Layout::Boxed(_) => buf.push_str("Box"),
+        Layout::LambdaSet(_) => buf.push_str("Closure"),
+        Layout::RecursivePointer => buf.push_str("Rec"),
+        Layout::Union(union_layout) => match union_layout {
+            UnionLayout::NonRecursive(tags) => write_tags(buf, "NonRecursive", tags),
+            UnionLayout::Recursive(tags) => write_tags(buf, "Recursive", tags),
+            UnionLayout::NonNullableUnwrapped(fields) => {
+                write_tags(buf, "NonNullableUnwrapped", &[*fields])
+            }
+            UnionLayout::NullableWrapped {
+                nullable_id,
+                other_tags,
+            } => write_tags(buf, &format!("NullableWrapped {}", nullable_id), other_tags),
+            UnionLayout::NullableUnwrapped {
+                nullable_id,
+                other_fields,
+            } => write_tags(
+                buf,
+                &format!("NullableUnwrapped {}", nullable_id),
+                &[*other_fields],
+            ),
+            UnionLayout::NullablePointer {
+                nullable_id,
+                pointer,
+            } => write_tags(
+                buf,
+                &format!("NullablePointer {}", nullable_id),
+                &[std::slice::from_ref(*pointer)],
+            ),
+        },
+    }
+}
+
+fn write_tags(buf: &mut String, kind: &str, tags: &[&[Layout<'_>]]) {
+    buf.push_str(kind);
+    buf.push('[');
+
+    for (index, fields) in tags.iter().enumerate() {
+        if index > 0 {
+            buf.push_str(", ");
+        }
+
+        buf.push('(');
+        write_layouts(buf, fields);
+        buf.push(')');
+    }
+
+    buf.push(']');
+}
+
+fn write_layouts(buf: &mut String, layouts: &[Layout<'_>]) {
+    for (index, layout) in layouts.iter().enumerate() {
+        if index > 0 {
+            buf.push_str(", ");
+        }
+
+        write_layout(buf, layout);
+    }
+}
+
+/// The file next to a host that records the ABI hash of the app it was built for.
+pub fn abi_hash_path(host_path: &Path) -> PathBuf {
+    let mut path = host_path.as_os_str().to_owned();
+    path.push(".abi");
+
+    PathBuf::from(path)
+}
+
+pub fn write_abi_hash(host_path: &Path, hash: u64) -> std::io::Result<()> {
+    std::fs::write(abi_hash_path(host_path), format!("{:016x}", hash))
+}
+
+/// The ABI hash a host was built with, if roc built it. Hosts built by older compilers,
+/// or by hand, don't have one.
+pub fn read_abi_hash(host_path: &Path) -> Option<u64> {
+    let contents = std::fs::read_to_string(abi_hash_path(host_path)).ok()?;
+
+    u64::from_str_radix(contents.trim(), 16).ok()
+}
+
+#[cfg(test)]
+mod test_abi {
+    use super::hash_exposed;
+    use bumpalo::Bump;
+    use roc_builtins::bitcode::IntWidth;
+    use roc_mono::ir::ProcLayout;
+    use roc_mono::layout::{Builtin, CapturesNiche, Layout, UnionLayout};
+    use std::str::FromStr;
+    use target_lexicon::Triple;
+
+    fn proc_layout<'a>(arguments: &'a [Layout<'a>], result: Layout<'a>) -> ProcLayout<'a> {
+        ProcLayout {
+            arguments,
+            result,
+            captures_niche: CapturesNiche::no_niche(),
+        }
+    }
+
+    fn hash(exposed: &[(&str, ProcLayout<'_>)]) -> u64 {
+        let target = Triple::from_str("x86_64-unknown-linux-gnu").unwrap();
+
+        hash_exposed(
+            &target,
+            exposed.iter().map(|(name, layout)| (*name, layout)),
+        )
+    }
+
+    #[test]
+    fn abi_hash_is_stable() {
+        let arena = Bump::new();
+        let bytes = arena.alloc(Layout::Builtin(Builtin::Int(IntWidth::U8)));
+        let arguments = arena.alloc([Layout::Builtin(Builtin::Int(IntWidth::I64))]);
+        let result = Layout::struct_no_name_order(arena.alloc([
+            Layout::Builtin(Builtin::Str),
+            Layout::Builtin(Builtin::List(bytes)),
+        ]));
+
+        // This is what every compiler with this ABI_VERSION must compute for this platform, on
+        // every host, so prebuilt hosts keep working with newer compilers. If this changes,
+        // bump ABI_VERSION.
+        assert_eq!(
+            hash(&[("mainForHost", proc_layout(arguments, result))]),
+            0x9f52_9c79_1c8b_ee3c
+        );
+    }
+
+    #[test]
+    fn abi_hash_changes_with_layouts() {
+        let arena = Bump::new();
+        let str_layout = Layout::Builtin(Builtin::Str);
+        let boxed_str: &Layout = arena.alloc(str_layout);
+
+        let no_payload: &[Layout] = &[];
+        let payload: &[Layout] = arena.alloc([Layout::Boxed(boxed_str)]);
+        let nonrecursive = Layout::Union(UnionLayout::NonRecursive(
+            arena.alloc([no_payload, payload]),
+        ));
+        let nullable_pointer = Layout::Union(UnionLayout::NullablePointer {
+            nullable_id: false,
+            pointer: arena.alloc(Layout::Boxed(boxed_str)),
+        });
+
+        let hashes = [
+            hash(&[("main", proc_layout(&[], str_layout))]),
+            hash(&[("main", proc_layout(&[], Layout::Builtin(Builtin::Bool)))]),
+            hash(&[("main", proc_layout(arena.alloc([str_layout]), str_layout))]),
+            hash(&[("main", proc_layout(&[], nonrecursive))]),
+            hash(&[("main", proc_layout(&[], nullable_pointer))]),
+        ];
+
+        for (i, a) in hashes.iter().enumerate() {
+            for b in hashes[i + 1..].iter() {
+                assert_ne!(a, b);
+            }
+        }
+
+        // The same layouts hash the same, in any order.
+        let str_main = proc_layout(&[], str_layout);
+        let bool_main = proc_layout(&[], Layout::Builtin(Builtin::Bool));
+
+        assert_eq!(
+            hash(&[("a", str_main), ("b", bool_main)]),
+            hash(&[("b", bool_main), ("a", str_main)])
+        );
+    }
+}
@@ -1,8 +1,9 @@
-use crate::target::{arch_str, target_zig_str};
+use crate::program::{CodeGenOptions, Pgo};
+use crate::target::{arch_str, target_triple_str, target_zig_str};
 use const_format::concatcp;
 use libloading::{Error, Library};
 use roc_builtins::bitcode;
-use roc_error_macros::internal_error;
+use roc_error_macros::{internal_error, user_error};
 use roc_mono::ir::OptLevel;
 use roc_utils::{cargo, clang, zig};
 use roc_utils::{get_lib_path, rustup};
@@ -11,7 +12,7 @@ use std::env;
 use std::io;
 use std::path::{Path, PathBuf};
 use std::process::{self, Child, Command};
-use target_lexicon::{Architecture, OperatingSystem, Triple};
+use target_lexicon::{Architecture, BinaryFormat, Environment, OperatingSystem, Triple};
 use wasi_libc_sys::{WASI_COMPILER_RT_PATH, WASI_LIBC_PATH};
 
 #[derive(Debug, Copy, Clone, PartialEq, Eq)]
@@ -60,6 +61,96 @@ pub fn link(
     }
 }
 
+/// Like `link`, but lets the clang driver do the linking, for things that need its help:
+///
+/// - with `lto`, the linker runs LTO over LLVM bitcode inputs, so the app and the host can be
+///   optimized together (e.g. inlining the host's allocation hooks into Roc code).
+///   Native objects in `input_paths` are linked as usual.
+/// - with `sanitizers`, the runtimes of those sanitizers are linked in.
+/// - with `Pgo::Generate`, the profiling runtime is linked in.
+pub fn link_with_clang(
+    target: &Triple,
+    output_path: PathBuf,
+    input_paths: &[&str],
+    link_type: LinkType,
+    code_gen_options: &CodeGenOptions,
+) -> io::Result<(Child, PathBuf)> {
+    let (mut command, output_path) = clang_link_command(
+        target,
+        output_path,
+        input_paths,
+        link_type,
+        code_gen_options,
+    );
+
+    Ok((command.spawn()?, output_path))
+}
+
+fn clang_link_command(
+    target: &Triple,
+    output_path: PathBuf,
+    input_paths: &[&str],
+    link_type: LinkType,
+    code_gen_options: &CodeGenOptions,
+) -> (Command, PathBuf) {
+    let lto = code_gen_options.lto;
+    let mut command = clang();
+
+    command
+        .args(["--target", &target.to_string()])
+        .args(input_paths);
+
+    if lto {
+        command.arg("-flto=thin");
+    }
+
+    for sanitizer in &code_gen_options.sanitizers {
+        command.arg(sanitizer.clang_flag());
+    }
+
+    if let Some(Pgo::Generate) = code_gen_options.pgo {
+        command.arg("-fprofile-generate");
+    }
+
+    let dylib_extension = match target.operating_system {
+        OperatingSystem::Linux => {
+            if lto {
+                // ld.lld understands bitcode without a linker plugin
+                command.arg("-fuse-ld=lld");
+            }
+
+            command.args(["-lm", "-lpthread", "-ldl", "-lrt", "-lutil"]);
+
+            "so"
+        }
+        OperatingSystem::Darwin => {
+            // ld64 links bitcode through libLTO
+            "dylib"
+        }
+        // the CLI rejects these options for other targets
+        _ => internal_error!(
+            "linking with clang is not supported for target: {:?}",
+            target
+        ),
+    };
+
+    let output_path = match link_type {
+        LinkType::Executable => output_path,
+        LinkType::Dylib => {
+            command.arg("-shared");
+
+            output_path.with_extension(dylib_extension)
+        }
+        LinkType::None => {
+            internal_error!("link_with_clang should not be called with link type of none")
+        }
+    };
+
+    command.args(["-o", output_path.to_str().unwrap()]);
+
+    (command, output_path)
+}
+
 const fn legacy_host_filename_ext(
     os: roc_target::OperatingSystem,
     opt_level: OptLevel,
@@ -89,6 +180,7 @@ const PRECOMPILED_HOST_EXT: &str = "rh1"; // Short for "roc host version 1" (so
 
 const WASM_TARGET_STR: &str = "wasm32";
 const LINUX_X86_64_TARGET_STR: &str = "linux-x86_64";
+const LINUX_X86_64_MUSL_TARGET_STR: &str = "linux-x86_64-musl";
 const LINUX_ARM64_TARGET_STR: &str = "linux-arm64";
 const MACOS_ARM64_TARGET_STR: &str = "macos-arm64";
 const MACOS_X86_64_TARGET_STR: &str = "macos-x86_64";
@@ -103,6 +195,16 @@ pub const fn preprocessed_host_filename(target: &Triple) -> Option<&'static str>
             architecture: Architecture::Wasm32,
             ..
         } => Some(concatcp!(WASM_TARGET_STR, '.', PRECOMPILED_HOST_EXT)),
+        Triple {
+            operating_system: OperatingSystem::Linux,
+            architecture: Architecture::X86_64,
+            environment: Environment::Musl,
+            ..
+        } => Some(concatcp!(
+            LINUX_X86_64_MUSL_TARGET_STR,
+            '.',
+            PRECOMPILED_HOST_EXT
+        )),
         Triple {
             operating_system: OperatingSystem::Linux,
             architecture: Architecture::X86_64,
@@ -168,6 +270,12 @@ pub fn get_target_triple_str(target: &Triple) -> Option<&'static str> {
             architecture: Architecture::Wasm32,
             ..
         } => Some(WASM_TARGET_STR),
+        Triple {
+            operating_system: OperatingSystem::Linux,
+            architecture: Architecture::X86_64,
+            environment: Environment::Musl,
+            ..
+        } => Some(LINUX_X86_64_MUSL_TARGET_STR),
         Triple {
             operating_system: OperatingSystem::Linux,
             architecture: Architecture::X86_64,
@@ -555,7 +663,14 @@ pub fn build_c_host_native(
     shared_lib_path: Option<&Path>,
     builtins_host_path: &Path,
 ) -> Command {
-    let mut clang_cmd = clang();
+    let mut clang_cmd = if is_static_musl(target) {
+        // zig comes with musl, and `zig cc` takes the same arguments as clang
+        let mut zig_cmd = zig();
+        zig_cmd.args(["cc", "-target", target_zig_str(target)]);
+        zig_cmd
+    } else {
+        clang()
+    };
     clang_cmd
         .env_clear()
         .env("PATH", env_path)
@@ -565,6 +680,12 @@ pub fn build_c_host_native(
         .args(["-o", dest]);
     if let Some(shared_lib_path) = shared_lib_path {
         match target.operating_system {
+            _ if is_static_musl(target) => {
+                internal_error!(
+                    "musl hosts are linked statically, so they are never built against {:?}",
+                    shared_lib_path
+                );
+            }
             OperatingSystem::Windows => {
                 // just use zig as a C compiler
 
@@ -665,11 +786,25 @@ pub fn build_swift_host_native(
     command
 }
 
+fn warn_host_without_lto(lto: bool, host_src: &str) {
+    if lto {
+        eprintln!(
+            "Warning: --lto only optimizes C hosts together with the app. \
+            {} is compiled to native code, so it will be linked without LTO.",
+            host_src
+        );
+    }
+}
+
+/// With `lto`, hosts that we know how to compile to LLVM bitcode (for now just C hosts) are
+/// built that way, so `link_with_clang` can optimize them together with the app.
+/// Other hosts are built as usual, with a warning.
 pub fn rebuild_host(
     opt_level: OptLevel,
     target: &Triple,
     host_input_path: &Path,
     shared_lib_path: Option<&Path>,
+    lto: bool,
 ) -> PathBuf {
     let c_host_src = host_input_path.with_file_name("host.c");
     let c_host_dest = host_input_path.with_file_name("c_host.o");
@@ -720,6 +855,7 @@ pub fn rebuild_host(
 
     if zig_host_src.exists() {
         // Compile host.zig
+        warn_host_without_lto(lto, "host.zig");
 
         let zig_str_path = find_zig_str_path();
 
@@ -785,9 +921,15 @@ pub fn rebuild_host(
         run_build_command(zig_cmd, "host.zig", 0);
     } else if cargo_host_src.exists() {
         // Compile and link Cargo.toml, if it exists
+        warn_host_without_lto(lto, "Cargo.toml");
         let cargo_dir = host_input_path.parent().unwrap();
 
-        let cargo_out_dir = cargo_dir.join("target").join(
+        let mut cargo_out_dir = cargo_dir.join("target");
+        // Static musl hosts are cross-compiled, and cargo keeps those builds apart by target.
+        if is_static_musl(target) {
+            cargo_out_dir.push(target_triple_str(target));
+        }
+        cargo_out_dir.push(
             if matches!(opt_level, OptLevel::Optimize | OptLevel::Size) {
                 "release"
             } else {
@@ -807,6 +949,9 @@ pub fn rebuild_host(
         };
 
         cargo_cmd.arg("build").current_dir(cargo_dir);
+        if is_static_musl(target) {
+            cargo_cmd.args(["--target", target_triple_str(target)]);
+        }
         // Rust doesn't expose size without editing the cargo.toml. Instead just use release.
         if matches!(opt_level, OptLevel::Optimize | OptLevel::Size) {
             cargo_cmd.arg("--release");
@@ -871,12 +1016,16 @@ pub fn rebuild_host(
         }
     } else if rust_host_src.exists() {
         // Compile and link host.rs, if it exists
+        warn_host_without_lto(lto, "host.rs");
         let mut rustc_cmd = Command::new("rustc");
         rustc_cmd.args([
             rust_host_src.to_str().unwrap(),
             "-o",
             rust_host_dest.to_str().unwrap(),
         ]);
+        if is_static_musl(target) {
+            rustc_cmd.args(["--target", target_triple_str(target)]);
+        }
         if matches!(opt_level, OptLevel::Optimize) {
             rustc_cmd.arg("-O");
         } else if matches!(opt_level, OptLevel::Size) {
@@ -940,7 +1089,7 @@ pub fn rebuild_host(
         }
     } else if c_host_src.exists() {
         // Compile host.c, if it exists
-        let clang_cmd = build_c_host_native(
+        let mut clang_cmd = build_c_host_native(
             target,
             &env_path,
             &env_home,
@@ -952,9 +1101,14 @@ pub fn rebuild_host(
             builtins_host_tempfile.path(),
         );
 
+        if lto {
+            clang_cmd.arg("-flto=thin");
+        }
+
         run_build_command(clang_cmd, "host.c", 0);
     } else if swift_host_src.exists() {
         // Compile host.swift, if it exists
+        warn_host_without_lto(lto, "host.swift");
         let swiftc_cmd = build_swift_host_native(
             &env_path,
             &env_home,
@@ -978,11 +1132,18 @@ pub fn rebuild_host(
     host_dest
 }
 
+/// Whether the target is a fully static musl build, whose hosts are cross-compiled for musl no matter what machine we are on.
+fn is_static_musl(target: &Triple) -> bool {
+    target.operating_system == OperatingSystem::Linux && target.environment == Environment::Musl
+}
+
 fn get_target_str(target: &Triple) -> &str {
     if target.operating_system == OperatingSystem::Windows
         && target.environment == target_lexicon::Environment::Gnu
     {
         "x86_64-windows-gnu"
+    } else if is_static_musl(target) {
+        target_zig_str(target)
     } else {
         "native"
     }
@@ -1065,6 +1226,29 @@ fn link_linux(
         ));
     }
 
+    if is_static_musl(target) {
+        // A fully static binary: zig brings its own musl, and there is no dynamic loader.
+        // The builtins are already part of the app (or passed in as an object by the dev backend).
+        if link_type != LinkType::Executable {
+            user_error!("musl builds are fully static, so they can only produce executables");
+        }
+
+        return Ok((
+            zig()
+                .args(["build-exe"])
+                .args(input_paths)
+                .args([
+                    "-target",
+                    target_zig_str(target),
+                    "-lc",
+                    "-static",
+                    &format!("-femit-bin={}", output_path.to_str().unwrap()),
+                ])
+                .spawn()?,
+            output_path,
+        ));
+    }
+
     // Some things we'll need to build a list of dirs to check for libraries
     let maybe_nix_path = nix_path_opt();
     let usr_lib_arch = ["/usr", "lib", &architecture];
@@ -1461,10 +1645,176 @@ fn link_windows(
     }
 }
 
+/// What to do to a binary after it has been linked, to make it smaller for distribution
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct PostLinkOptions {
+    /// Remove symbol tables and debug info
+    pub strip: bool,
+    /// Pack the executable with UPX, so it decompresses itself into memory when it starts
+    pub compress: bool,
+}
+
+impl PostLinkOptions {
+    pub fn is_empty(&self) -> bool {
+        !self.strip && !self.compress
+    }
+}
+
+/// Apply `options` to a binary produced by either linker. This runs after linking rather than
+/// being passed to the linker, because the surgical linker copies the host's symbols and debug
+/// info into the output as they are.
+pub fn post_link(
+    target: &Triple,
+    binary_path: &Path,
+    link_type: LinkType,
+    options: PostLinkOptions,
+) -> io::Result<()> {
+    for mut command in post_link_commands(target, binary_path, link_type, options)? {
+        run_post_link_command(&mut command)?;
+    }
+
+    Ok(())
+}
+
+/// The commands that apply `options`, in the order they need to run. Options that can't be
+/// applied are an error before any of them runs.
+fn post_link_commands(
+    target: &Triple,
+    binary_path: &Path,
+    link_type: LinkType,
+    options: PostLinkOptions,
+) -> io::Result<Vec<Command>> {
+    let mut commands = Vec::new();
+
+    if options.strip {
+        commands.extend(strip_commands(target, binary_path, link_type));
+    }
+
+    if options.compress {
+        commands.push(compress_command(target, binary_path, link_type)?);
+    }
+
+    Ok(commands)
+}
+
+fn strip_commands(target: &Triple, binary_path: &Path, link_type: LinkType) -> Vec<Command> {
+    let mut command = match target.binary_format {
+        BinaryFormat::Elf => {
+            let mut command = Command::new("strip");
+            match link_type {
+                // Keep the symbols that relocations in the library still need.
+                LinkType::Dylib => command.arg("--strip-unneeded"),
+                _ => command.arg("--strip-all"),
+            };
+            command
+        }
+        BinaryFormat::Macho => {
+            let mut command = Command::new("strip");
+            if link_type == LinkType::Dylib {
+                // Only remove local symbols, so the library's exports stay visible.
+                command.arg("-x");
+            }
+            command
+        }
+        // Windows executables keep their debug info in a separate .pdb file, and wasm modules
+        // are already stripped by the linker unless --debug was given.
+        _ => return Vec::new(),
+    };
+
+    command.arg(binary_path);
+
+    let mut commands = vec![command];
+
+    // Stripping changes the binary, which invalidates its code signature.
+    if target.operating_system == OperatingSystem::Darwin
+        && matches!(target.architecture, Architecture::Aarch64(_))
+    {
+        let mut codesign = Command::new("codesign");
+        codesign.args(["--force", "-s", "-"]).arg(binary_path);
+
+        commands.push(codesign);
+    }
+
+    commands
+}
+
+fn compress_command(
+    target: &Triple,
+    binary_path: &Path,
+    link_type: LinkType,
+) -> io::Result<Command> {
+    if link_type != LinkType::Executable {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "only executables can be compressed, not libraries",
+        ));
+    }
+
+    match target.operating_system {
+        OperatingSystem::Linux | OperatingSystem::Windows => {}
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::Unsupported,
+                format!("compressing executables is not supported for {target}"),
+            ));
+        }
+    }
+
+    let mut command = Command::new("upx");
+    command.args(["--best", "-q"]).arg(binary_path);
+
+    Ok(command)
+}
+
+fn run_post_link_command(command: &mut Command) -> io::Result<()> {
+    let tool = command.get_program().to_string_lossy().into_owned();
+    let output = command.output().map_err(|err| match err.kind() {
+        io::ErrorKind::NotFound => io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("`{tool}` was not found, please install it and make sure it is on your PATH"),
+        ),
+        _ => err,
+    })?;
+
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::new(
+            io::ErrorKind::Other,
+            format!(
+                "`{tool}` failed with {}:\n{}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr)
+            ),
+        ))
+    }
+}
+
 pub fn llvm_module_to_dylib(
     module: &inkwell::module::Module,
     target: &Triple,
     opt_level: OptLevel,
+) -> Result<Library, Error> {
+    module_to_dylib_help(module, target, opt_level, false)
+}
+
+/// Like [llvm_module_to_dylib], but the dylib's symbols are made available to the libraries
+/// loaded after it, and its undefined symbols can come from the libraries loaded before it
+/// (e.g. a platform's test harness providing the effects). Only one such dylib can be loaded
+/// at a time, because their symbols would clash.
+pub fn llvm_module_to_global_dylib(
+    module: &inkwell::module::Module,
+    target: &Triple,
+    opt_level: OptLevel,
+) -> Result<Library, Error> {
+    module_to_dylib_help(module, target, opt_level, true)
+}
+
+fn module_to_dylib_help(
+    module: &inkwell::module::Module,
+    target: &Triple,
+    opt_level: OptLevel,
+    export_symbols: bool,
 ) -> Result<Library, Error> {
     use crate::target::{self, convert_opt_level};
     use inkwell::targets::{FileType, RelocMode};
@@ -1506,6 +1856,13 @@ pub fn llvm_module_to_dylib(
     // Load the dylib
     let path = dylib_path.as_path().to_str().unwrap();
 
+    #[cfg(unix)]
+    if export_symbols {
+        use libloading::os::unix::{Library as UnixLibrary, RTLD_GLOBAL, RTLD_LAZY};
+
+        return unsafe { UnixLibrary::open(Some(path), RTLD_LAZY | RTLD_GLOBAL) }.map(Into::into);
+    }
+
     if matches!(target.architecture, Architecture::Aarch64(_)) {
         // On AArch64 darwin machines, calling `ldopen` on Roc-generated libs from multiple threads
         // sometimes fails with
@@ -1524,6 +1881,53 @@ pub fn llvm_module_to_dylib(
     unsafe { Library::new(path) }
 }
 
+/// Compile a module that provides its own roc_alloc, roc_panic, etc. to a standalone wasm32
+/// module, linked against wasi-libc so it runs in any WASI runtime. Every function the module
+/// exports with C linkage is exported from the wasm module too.
+pub fn llvm_module_to_wasm(
+    module: &inkwell::module::Module,
+    opt_level: OptLevel,
+) -> io::Result<Vec<u8>> {
+    use crate::target::{self, convert_opt_level};
+    use inkwell::targets::{FileType, RelocMode};
+
+    let dir = tempfile::tempdir()?;
+    let app_o_file = dir.path().join("app.o");
+    let app_wasm_file = dir.path().join("app.wasm");
+
+    let triple = Triple {
+        architecture: Architecture::Wasm32,
+        operating_system: OperatingSystem::Wasi,
+        binary_format: BinaryFormat::Wasm,
+        ..Triple::unknown()
+    };
+
+    let target_machine =
+        target::target_machine(&triple, convert_opt_level(opt_level), RelocMode::Default).unwrap();
+
+    target_machine
+        .write_to_file(module, FileType::Object, &app_o_file)
+        .expect("Writing .o file failed");
+
+    let mut zig_cmd = zig();
+    zig_cmd.args([
+        "wasm-ld",
+        app_o_file.to_str().unwrap(),
+        WASI_LIBC_PATH,
+        WASI_COMPILER_RT_PATH, // builtins need __multi3, __udivti3, __fixdfti
+        "-o",
+        app_wasm_file.to_str().unwrap(),
+        "--export-dynamic",
+        "--no-entry",
+        // WASI functions are imports, which the runtime provides
+        "--allow-undefined",
+    ]);
+
+    run_build_command(zig_cmd, app_wasm_file.to_str().unwrap(), 0);
+
+    std::fs::read(app_wasm_file)
+}
+
 pub fn preprocess_host_wasm32(host_input_path: &Path, preprocessed_host_path: &Path) {
     let host_input = host_input_path.to_str().unwrap();
     let output_file = preprocessed_host_path.to_str().unwrap();
@@ -1565,6 +1969,29 @@ pub fn preprocess_host_wasm32(host_input_path: &Path, preprocessed_host_path: &P
     // Extend the lifetime of the tempfile so it doesn't get dropped
     // (and thus deleted) before the Zig process is done using it!
     let _ = builtins_host_tempfile;
+
+    // Index the host now, so that every build of the app can load it faster.
+    // If the host can't be parsed, the build reports that when it loads the host.
+    let index_path = wasm_host_index_path(preprocessed_host_path);
+    let index = std::fs::read(preprocessed_host_path)
+        .ok()
+        .and_then(|host_bytes| roc_gen_wasm::index_host(&host_bytes).ok());
+    match index {
+        Some(index) => {
+            let _ = std::fs::write(&index_path, index);
+        }
+        None => {
+            let _ = std::fs::remove_file(&index_path);
+        }
+    }
+}
+
+/// Where `preprocess_host_wasm32` writes the index of the preprocessed host
+pub fn wasm_host_index_path(preprocessed_host_path: &Path) -> PathBuf {
+    let mut path = preprocessed_host_path.as_os_str().to_owned();
+    path.push(".index");
+
+    PathBuf::from(path)
 }
 
 fn run_build_command(mut command: Command, file_to_build: &str, flaky_fail_counter: usize) {
@@ -1614,3 +2041,218 @@ fn run_build_command(mut command: Command, file_to_build: &str, flaky_fail_count
         }
     }
 }
+
+#[cfg(test)]
+mod test_musl {
+    use super::build_c_host_native;
+    use roc_mono::ir::OptLevel;
+    use std::path::Path;
+    use std::str::FromStr;
+    use target_lexicon::Triple;
+
+    #[test]
+    fn musl_c_host_compiles_every_source_with_zig() {
+        let target = Triple::from_str("x86_64-unknown-linux-musl").unwrap();
+        let cmd = build_c_host_native(
+            &target,
+            "",
+            "",
+            "",
+            "host.o",
+            &["host.c", "rust_host.o"],
+            OptLevel::Normal,
+            None,
+            Path::new("builtins.o"),
+        );
+        let args: Vec<_> = cmd.get_args().map(|arg| arg.to_str().unwrap()).collect();
+
+        assert_eq!(args[..3], ["cc", "-target", "x86_64-linux-musl"]);
+        assert!(args.contains(&"host.c"), "{:?}", args);
+        assert!(args.contains(&"rust_host.o"), "{:?}", args);
+    }
+}
+
+#[cfg(test)]
+mod test_post_link {
+    use super::{post_link_commands, LinkType, PostLinkOptions};
+    use std::io;
+    use std::path::Path;
+    use std::process::Command;
+    use std::str::FromStr;
+    use target_lexicon::Triple;
+
+    const STRIP: PostLinkOptions = PostLinkOptions {
+        strip: true,
+        compress: false,
+    };
+
+    const COMPRESS: PostLinkOptions = PostLinkOptions {
+        strip: false,
+        compress: true,
+    };
+
+    fn commands(target: &str, link_type: LinkType, options: PostLinkOptions) -> Vec<String> {
+        let target = Triple::from_str(target).unwrap();
+
+        post_link_commands(&target, Path::new("app"), link_type, options)
+            .unwrap()
+            .iter()
+            .map(command_line)
+            .collect()
+    }
+
+    fn command_line(command: &Command) -> String {
+        std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|arg| arg.to_str().unwrap())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn error(target: &str, link_type: LinkType, options: PostLinkOptions) -> io::ErrorKind {
+        let target = Triple::from_str(target).unwrap();
+
+        match post_link_commands(&target, Path::new("app"), link_type, options) {
+            Ok(commands) => panic!("expected an error, but got {} commands", commands.len()),
+            Err(err) => err.kind(),
+        }
+    }
+
+    #[test]
+    fn strip_elf() {
+        assert_eq!(
+            commands("x86_64-unknown-linux-gnu", LinkType::Executable, STRIP),
+            ["strip --strip-all app"]
+        );
+        assert_eq!(
+            commands("x86_64-unknown-linux-gnu", LinkType::Dylib, STRIP),
+            ["strip --strip-unneeded app"]
+        );
+    }
+
+    #[test]
+    fn strip_macho_and_sign_on_aarch64() {
+        assert_eq!(
+            commands("x86_64-apple-darwin", LinkType::Executable, STRIP),
+            ["strip app"]
+        );
+        assert_eq!(
+            commands("aarch64-apple-darwin", LinkType::Dylib, STRIP),
+            ["strip -x app", "codesign --force -s - app"]
+        );
+    }
+
+    #[test]
+    fn strip_nothing_on_windows_and_wasm() {
+        assert!(commands("x86_64-pc-windows-gnu", LinkType::Executable, STRIP).is_empty());
+        assert!(commands("wasm32-unknown-unknown", LinkType::Executable, STRIP).is_empty());
+    }
+
+    #[test]
+    fn compress_after_strip() {
+        let both = PostLinkOptions {
+            strip: true,
+            compress: true,
+        };
+
+        assert_eq!(
+            commands("x86_64-unknown-linux-gnu", LinkType::Executable, both),
+            ["strip --strip-all app", "upx --best -q app"]
+        );
+    }
+
+    #[test]
+    fn compress_only_executables_on_linux_and_windows() {
+        assert_eq!(
+            commands("x86_64-pc-windows-gnu", LinkType::Executable, COMPRESS),
+            ["upx --best -q app"]
+        );
+        assert_eq!(
+            error("x86_64-unknown-linux-gnu", LinkType::Dylib, COMPRESS),
+            io::ErrorKind::Unsupported
+        );
+        assert_eq!(
+            error("aarch64-apple-darwin", LinkType::Executable, COMPRESS),
+            io::ErrorKind::Unsupported
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_clang_link {
+    use super::{clang_link_command, LinkType};
+    use crate::program::{CodeGenBackend, CodeGenOptions, Pgo, Sanitizer};
+    use roc_mono::ir::OptLevel;
+    use std::path::PathBuf;
+    use std::str::FromStr;
+    use target_lexicon::Triple;
+
+    fn options(lto: bool, sanitizers: Vec<Sanitizer>, pgo: Option<Pgo>) -> CodeGenOptions {
+        CodeGenOptions {
+            backend: CodeGenBackend::Llvm,
+            opt_level: OptLevel::Optimize,
+            emit_debug_info: false,
+            llvm_pipeline: OptLevel::Optimize.into(),
+            lto,
+            target_cpu: Default::default(),
+            sanitizers,
+            llvm_cache_dir: None,
+            pgo,
+        }
+    }
+
+    fn args(target: &str, link_type: LinkType, options: &CodeGenOptions) -> (Vec<String>, PathBuf) {
+        let target = Triple::from_str(target).unwrap();
+        let (command, output_path) = clang_link_command(
+            &target,
+            PathBuf::from("app"),
+            &["app.bc", "host.bc"],
+            link_type,
+            options,
+        );
+        let args = command
+            .get_args()
+            .map(|arg| arg.to_str().unwrap().to_string())
+            .collect();
+
+        (args, output_path)
+    }
+
+    #[test]
+    fn linux_lto_links_bitcode_with_lld() {
+        let (args, output_path) = args(
+            "x86_64-unknown-linux-gnu",
+            LinkType::Executable,
+            &options(true, vec![], None),
+        );
+
+        assert!(args.contains(&"-flto=thin".to_string()), "{:?}", args);
+        assert!(args.contains(&"-fuse-ld=lld".to_string()), "{:?}", args);
+        assert!(args.contains(&"host.bc".to_string()), "{:?}", args);
+        assert_eq!(output_path, PathBuf::from("app"));
+    }
+
+    #[test]
+    fn macos_dylib_without_lto() {
+        let (args, output_path) = args(
+            "x86_64-apple-darwin",
+            LinkType::Dylib,
+            &options(false, vec![Sanitizer::Address], Some(Pgo::Generate)),
+        );
+
+        assert!(!args.contains(&"-flto=thin".to_string()), "{:?}", args);
+        assert!(!args.contains(&"-fuse-ld=lld".to_string()), "{:?}", args);
+        assert!(
+            args.contains(&"-fsanitize=address".to_string()),
+            "{:?}",
+            args
+        );
+        assert!(
+            args.contains(&"-fprofile-generate".to_string()),
+            "{:?}",
+            args
+        );
+        assert!(args.contains(&"-shared".to_string()), "{:?}", args);
+        assert_eq!(output_path, PathBuf::from("app.dylib"));
+    }
+}
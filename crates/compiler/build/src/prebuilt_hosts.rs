@@ -0,0 +1,167 @@
+//! A platform can ship prebuilt hosts for several targets. It lists them in a manifest
+//! next to the platform module, with one line per target:
+//!
+//! ```text
+//! linux-x86_64 linux-x86_64.rh1 linux-x86_64.o
+//! macos-arm64 macos-arm64.o
+//! ```
+//!
+//! Each line is a target (as in the hosts' file names, e.g. `linux-x86_64.rh1`), followed
+//! by the host files the platform has for it. Lines starting with `#` are comments.
+use std::io;
+use std::path::Path;
+
+pub const MANIFEST_FILE_NAME: &str = "prebuilt-hosts.txt";
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct PrebuiltHosts {
+    /// Sorted by target
+    entries: Vec<(String, Vec<String>)>,
+}
+
+/// A line of the manifest that names a target but no host files for it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MalformedLine {
+    /// 1-based, like an editor's
+    pub line_number: usize,
+    pub line: String,
+}
+
+impl std::fmt::Display for MalformedLine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "line {} should be a target followed by its host files, but it is: {}",
+            self.line_number, self.line
+        )
+    }
+}
+
+impl PrebuiltHosts {
+    pub fn parse(src: &str) -> Result<Self, MalformedLine> {
+        let mut manifest = Self::default();
+
+        for (index, line) in src.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut words = line.split_whitespace();
+            let target = words.next().unwrap_or_default();
+            let mut file_names = words.peekable();
+
+            if file_names.peek().is_none() {
+                return Err(MalformedLine {
+                    line_number: index + 1,
+                    line: line.to_string(),
+                });
+            }
+
+            for file_name in file_names {
+                manifest.add(target, file_name);
+            }
+        }
+
+        Ok(manifest)
+    }
+
+    /// The manifest in the given platform directory, if it has one
+    pub fn read(platform_dir: &Path) -> io::Result<Option<Self>> {
+        match std::fs::read_to_string(platform_dir.join(MANIFEST_FILE_NAME)) {
+            Ok(src) => match Self::parse(&src) {
+                Ok(manifest) => Ok(Some(manifest)),
+                Err(malformed) => Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    malformed.to_string(),
+                )),
+            },
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// Record that the platform has the given host file for the given target
+    pub fn add(&mut self, target: &str, file_name: &str) {
+        let index = match self
+            .entries
+            .binary_search_by(|(other, _)| other.as_str().cmp(target))
+        {
+            Ok(index) => index,
+            Err(index) => {
+                self.entries.insert(index, (target.to_string(), Vec::new()));
+
+                index
+            }
+        };
+
+        let file_names = &mut self.entries[index].1;
+
+        if !file_names.iter().any(|other| other == file_name) {
+            file_names.push(file_name.to_string());
+        }
+    }
+
+    pub fn targets(&self) -> impl Iterator<Item = &str> {
+        self.entries.iter().map(|(target, _)| target.as_str())
+    }
+
+    /// The host files the platform has for the given target, if it supports that target
+    pub fn host_files(&self, target: &str) -> Option<&[String]> {
+        self.entries
+            .iter()
+            .find(|(other, _)| other == target)
+            .map(|(_, file_names)| file_names.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod test_prebuilt_hosts {
+    use super::{MalformedLine, PrebuiltHosts};
+
+    const MANIFEST: &str = "# The prebuilt hosts this platform ships
+linux-x86_64 linux-x86_64.rh1 linux-x86_64.o
+
+macos-arm64 macos-arm64.o
+linux-x86_64 linux-x86_64.o
+";
+
+    #[test]
+    fn parse_valid_manifest() {
+        let manifest = PrebuiltHosts::parse(MANIFEST).unwrap();
+
+        assert_eq!(
+            manifest.targets().collect::<Vec<_>>(),
+            vec!["linux-x86_64", "macos-arm64"]
+        );
+        assert_eq!(
+            manifest.host_files("linux-x86_64").unwrap(),
+            &["linux-x86_64.rh1", "linux-x86_64.o"]
+        );
+        assert_eq!(
+            manifest.host_files("macos-arm64").unwrap(),
+            &["macos-arm64.o"]
+        );
+    }
+
+    #[test]
+    fn parse_missing_target() {
+        let manifest = PrebuiltHosts::parse(MANIFEST).unwrap();
+
+        assert_eq!(manifest.host_files("windows-x86_64"), None);
+    }
+
+    #[test]
+    fn parse_malformed_line() {
+        let src = "linux-x86_64 linux-x86_64.o\n  macos-arm64  \n";
+
+        assert_eq!(
+            PrebuiltHosts::parse(src),
+            Err(MalformedLine {
+                line_number: 2,
+                line: "macos-arm64".to_string(),
+            })
+        );
+    }
+}
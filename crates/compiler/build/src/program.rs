@@ -1,42 +1,75 @@
+use crate::abi::{abi_hash, ABI_HASH_SYMBOL};
+use crate::target::TargetCpu;
 use inkwell::memory_buffer::MemoryBuffer;
-use roc_error_macros::internal_error;
-use roc_gen_llvm::llvm::build::{module_from_builtins, LlvmBackendMode};
+use roc_collections::StableHasher;
+use roc_error_macros::{internal_error, user_error};
+use roc_gen_llvm::llvm::build::{module_from_builtins, LlvmBackendMode, LlvmPipeline};
 use roc_gen_llvm::llvm::externs::add_default_roc_externs;
 use roc_load::{EntryPoint, ExpectMetadata, LoadedModule, MonomorphizedModule};
 use roc_mono::ir::{OptLevel, SingleEntryPoint};
+use roc_problem::lint::{LintConfig, LINTS_FILE_NAME};
 use roc_reporting::cli::{report_problems, Problems};
+use std::hash::Hasher;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
 use std::time::{Duration, Instant};
 
 #[cfg(feature = "target-wasm32")]
-use roc_collections::all::MutSet;
+use roc_collections::all::{MutMap, MutSet};
 
 #[derive(Debug, Clone, Copy, Default)]
 pub struct CodeGenTiming {
     pub code_gen: Duration,
 }
 
-pub fn report_problems_monomorphized(loaded: &mut MonomorphizedModule) -> Problems {
+pub fn report_problems_monomorphized(
+    loaded: &mut MonomorphizedModule,
+    lint_overrides: &LintConfig,
+    html_output: Option<&Path>,
+) -> Problems {
     report_problems(
         loaded.total_problems(),
         &loaded.sources,
         &loaded.interns,
         &mut loaded.can_problems,
         &mut loaded.type_problems,
+        &lint_config(&loaded.sources[&loaded.module_id].0, lint_overrides),
+        html_output,
     )
 }
 
-pub fn report_problems_typechecked(loaded: &mut LoadedModule) -> Problems {
+pub fn report_problems_typechecked(
+    loaded: &mut LoadedModule,
+    lint_overrides: &LintConfig,
+    html_output: Option<&Path>,
+) -> Problems {
     report_problems(
         loaded.total_problems(),
         &loaded.sources,
         &loaded.interns,
         &mut loaded.can_problems,
         &mut loaded.type_problems,
+        &lint_config(&loaded.sources[&loaded.module_id].0, lint_overrides),
+        html_output,
     )
 }
 
+/// Read the lint configuration of the project whose main module is at the given path, if it has one,
+/// and apply the overrides (e.g. from the command line) on top of it.
+fn lint_config(main_module_path: &Path, overrides: &LintConfig) -> LintConfig {
+    let path = main_module_path.with_file_name(LINTS_FILE_NAME);
+
+    let mut config = match std::fs::read_to_string(&path) {
+        Ok(src) => LintConfig::parse(&src)
+            .unwrap_or_else(|err| user_error!("Invalid {}: {}", path.display(), err)),
+        Err(_) => LintConfig::default(),
+    };
+
+    config.merge(overrides);
+
+    config
+}
+
 pub enum CodeObject {
     MemoryBuffer(MemoryBuffer),
     Vector(Vec<u8>),
@@ -60,11 +93,69 @@ pub enum CodeGenBackend {
     Wasm,
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub struct CodeGenOptions {
     pub backend: CodeGenBackend,
     pub opt_level: OptLevel,
     pub emit_debug_info: bool,
+    /// The LLVM passes to run after code generation (ignored by the dev backend)
+    pub llvm_pipeline: LlvmPipeline,
+    /// Emit the app as LLVM bitcode, so the linker can optimize it together with the host
+    pub lto: bool,
+    /// The CPU the LLVM backend generates code for
+    pub target_cpu: TargetCpu,
+    /// Instrument the app with these sanitizers (only supported by the LLVM backend)
+    pub sanitizers: Vec<Sanitizer>,
+    /// Where to cache the LLVM output of each module, so builds can skip LLVM for the modules
+    /// whose code did not change
+    pub llvm_cache_dir: Option<PathBuf>,
+    /// Profile guided optimization (only supported by the LLVM backend)
+    pub pgo: Option<Pgo>,
+}
+
+impl CodeGenOptions {
+    /// Whether linking needs the clang driver, rather than calling the linker directly
+    pub fn needs_clang_link(&self) -> bool {
+        self.lto || !self.sanitizers.is_empty() || self.pgo.is_some()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Pgo {
+    /// Instrument the app to write a profile when it runs
+    /// (to `default.profraw`, or to `LLVM_PROFILE_FILE` if it is set)
+    Generate,
+    /// Optimize the app with a profile, as merged by `llvm-profdata merge`
+    Use(PathBuf),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sanitizer {
+    /// Out-of-bounds accesses, use-after-free and double frees (AddressSanitizer)
+    Address,
+}
+
+impl Sanitizer {
+    pub fn from_flag(flag: &str) -> Option<Self> {
+        match flag {
+            "address" => Some(Sanitizer::Address),
+            _ => None,
+        }
+    }
+
+    /// The flag that makes clang link in the runtime of this sanitizer
+    pub fn clang_flag(self) -> &'static str {
+        match self {
+            Sanitizer::Address => "-fsanitize=address",
+        }
+    }
+
+    /// The name used by `ROC_SANITIZERS`
+    fn name(self) -> &'static str {
+        match self {
+            Sanitizer::Address => "address",
+        }
+    }
 }
 
 type GenFromMono<'a> = (CodeObject, CodeGenTiming, ExpectMetadata<'a>);
@@ -79,6 +170,8 @@ pub fn gen_from_mono_module<'a>(
     preprocessed_host_path: &Path,
     wasm_dev_stack_bytes: Option<u32>,
 ) -> GenFromMono<'a> {
+    let _span = roc_tracing::trace_span("codegen", "generate code");
+
     match code_gen_options.backend {
         CodeGenBackend::Assembly => gen_from_mono_module_dev(
             arena,
@@ -97,6 +190,52 @@ pub fn gen_from_mono_module<'a>(
     }
 }
 
+/// Instruments the module to write a profile, or annotates it with one. This has to happen
+/// before the module is optimized: the profile describes the control flow of the unoptimized
+/// module, and the optimizer is what makes use of it. inkwell can't run these passes, so LLVM's
+/// `opt` does, and its output replaces the module.
+fn apply_pgo<'ctx>(
+    context: &'ctx inkwell::context::Context,
+    module: &inkwell::module::Module<'ctx>,
+    pgo: &Pgo,
+) -> inkwell::module::Module<'ctx> {
+    use std::process::Command;
+
+    let dir = tempfile::tempdir().unwrap();
+    let input_file = dir.path().join("app.bc");
+    let output_file = dir.path().join("app.pgo.bc");
+
+    module.write_bitcode_to_path(&input_file);
+
+    let mut opt = Command::new("opt");
+
+    match pgo {
+        Pgo::Generate => {
+            opt.arg("-passes=pgo-instr-gen,instrprof");
+        }
+        Pgo::Use(profile) => {
+            opt.arg("-passes=pgo-instr-use")
+                .arg(format!("-pgo-test-profile-file={}", profile.display()));
+        }
+    }
+
+    match opt.arg(&input_file).arg("-o").arg(&output_file).output() {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => user_error!(
+            "LLVM's `opt` could not apply the profile:\n\n{}",
+            String::from_utf8_lossy(&output.stderr)
+        ),
+        Err(error) => user_error!(
+            "Profile guided optimization needs LLVM's `opt`, but I could not run it: {}",
+            error
+        ),
+    }
+
+    inkwell::module::Module::parse_bitcode_from_path(&output_file, context).unwrap_or_else(
+        |error| internal_error!("`opt` wrote bitcode that LLVM can't parse: {}", error),
+    )
+}
+
 // TODO how should imported modules factor into this? What if those use builtins too?
 // TODO this should probably use more helper functions
 // TODO make this polymorphic in the llvm functions so it can be reused for another backend.
@@ -110,20 +249,33 @@ fn gen_from_mono_module_llvm<'a>(
     use crate::target::{self, convert_opt_level};
     use inkwell::attributes::{Attribute, AttributeLoc};
     use inkwell::context::Context;
-    use inkwell::module::Linkage;
+    use inkwell::module::{Linkage, Module};
     use inkwell::targets::{FileType, RelocMode};
 
     let code_gen_start = Instant::now();
 
+    // where each module's cache entries go, and what all of their keys start with: the compiler,
+    // and what it compiles for
+    let llvm_cache = code_gen_options
+        .llvm_cache_dir
+        .as_deref()
+        .filter(|_| llvm_output_is_cacheable(&code_gen_options))
+        .map(|cache_dir| {
+            let mut hasher = StableHasher::new();
+
+            hasher.write_compiler();
+            hasher.write_str(&target.to_string());
+            hasher.write_str(&format!("{:?}", code_gen_options));
+
+            (llvm_cache_module_dir(cache_dir, &loaded), hasher)
+        });
+
+    let abi_hash = executable_abi_hash(&loaded, target);
+
     // Generate the binary
     let target_info = roc_target::TargetInfo::from(target);
     let context = Context::create();
-    let module = arena.alloc(module_from_builtins(target, &context, "app"));
 
-    // strip Zig debug stuff
-    // module.strip_debug_info();
-
-    // mark our zig-defined builtins as internal
     let app_ll_file = {
         let mut temp = PathBuf::from(roc_file_path);
         temp.set_extension("ll");
@@ -135,58 +287,68 @@ fn gen_from_mono_module_llvm<'a>(
     debug_assert!(kind_id > 0);
     let enum_attr = context.create_enum_attribute(kind_id, 1);
 
-    for function in module.get_functions() {
-        let name = function.get_name().to_str().unwrap();
+    let new_module = |name: &str| {
+        let module = module_from_builtins(target, &context, name);
 
-        // mark our zig-defined builtins as internal
-        if name.starts_with("roc_builtins") {
-            function.set_linkage(Linkage::Internal);
-        }
+        // strip Zig debug stuff
+        // module.strip_debug_info();
 
-        if name.starts_with("roc_builtins.dict")
-            || name.starts_with("roc_builtins.list")
-            || name.starts_with("roc_builtins.dec")
-            || name.starts_with("list.RocList")
-            || name.starts_with("dict.RocDict")
-            || name.contains("incref")
-            || name.contains("decref")
-        {
-            function.add_attribute(AttributeLoc::Function, enum_attr);
+        for function in module.get_functions() {
+            let name = function.get_name().to_str().unwrap();
+
+            // mark our zig-defined builtins as internal
+            if name.starts_with("roc_builtins") {
+                function.set_linkage(Linkage::Internal);
+            }
+
+            if name.starts_with("roc_builtins.dict")
+                || name.starts_with("roc_builtins.list")
+                || name.starts_with("roc_builtins.dec")
+                || name.starts_with("list.RocList")
+                || name.starts_with("dict.RocDict")
+                || name.contains("incref")
+                || name.contains("decref")
+            {
+                function.add_attribute(AttributeLoc::Function, enum_attr);
+            }
         }
-    }
+
+        module
+    };
 
     let CodeGenOptions {
         backend: _,
         opt_level,
         emit_debug_info,
+        llvm_pipeline,
+        lto,
+        target_cpu,
+        sanitizers,
+        llvm_cache_dir: _,
+        pgo,
     } = code_gen_options;
 
-    let builder = context.create_builder();
-    let (dibuilder, compile_unit) = roc_gen_llvm::llvm::build::Env::new_debug_info(module);
-    let (mpm, _fpm) = roc_gen_llvm::llvm::build::construct_optimization_passes(module, opt_level);
-
-    // Compile and add all the Procs before adding main
-    let env = roc_gen_llvm::llvm::build::Env {
-        arena,
-        layout_interner: &loaded.layout_interner,
-        builder: &builder,
-        dibuilder: &dibuilder,
-        compile_unit: &compile_unit,
-        context: &context,
-        interns: loaded.interns,
-        module,
-        target_info,
-        mode: match opt_level {
-            OptLevel::Development => LlvmBackendMode::BinaryDev,
-            OptLevel::Normal | OptLevel::Size | OptLevel::Optimize => LlvmBackendMode::Binary,
-        },
+    use target_lexicon::Architecture;
+    let target_machine = match target.architecture {
+        Architecture::X86_64 | Architecture::X86_32(_) | Architecture::Aarch64(_) => {
+            let reloc = RelocMode::PIC;
+            let target_machine = target::target_machine_for_cpu(
+                target,
+                &target_cpu,
+                convert_opt_level(opt_level),
+                reloc,
+            )
+            .unwrap();
 
-        exposed_to_host: loaded.exposed_to_host.values.keys().copied().collect(),
+            Some(target_machine)
+        }
+        _ => None,
     };
 
-    // does not add any externs for this mode (we have a host) but cleans up some functions around
-    // expects that would confuse the surgical linker
-    add_default_roc_externs(&env);
+    let mode = match opt_level {
+        OptLevel::Development => LlvmBackendMode::BinaryDev,
+        OptLevel::Normal | OptLevel::Size | OptLevel::Optimize => LlvmBackendMode::Binary,
+    };
 
     let entry_point = match loaded.entry_point {
         EntryPoint::Executable {
@@ -202,42 +364,142 @@ fn gen_from_mono_module_llvm<'a>(
         EntryPoint::Test => roc_mono::ir::EntryPoint::Expects { symbols: &[] },
     };
 
-    roc_gen_llvm::llvm::build::build_procedures(
-        &env,
-        opt_level,
-        loaded.procedures,
-        entry_point,
-        Some(&app_ll_file),
-    );
+    let (module, interns) = match llvm_cache {
+        Some(llvm_cache) => {
+            let _span = roc_tracing::trace_span("codegen", "build and optimize LLVM IR per module");
+
+            let module = build_llvm_modules_cached(
+                arena,
+                &context,
+                &new_module,
+                &loaded,
+                LlvmModulesConfig {
+                    target_info,
+                    mode,
+                    opt_level,
+                    entry_point,
+                    llvm_pipeline: &llvm_pipeline,
+                    target_machine: target_machine.as_ref(),
+                },
+                llvm_cache,
+            );
+
+            (&*arena.alloc(module), loaded.interns)
+        }
+        None => {
+            let module = arena.alloc(new_module("app"));
 
-    env.dibuilder.finalize();
+            let builder = context.create_builder();
+            let (dibuilder, compile_unit) = roc_gen_llvm::llvm::build::Env::new_debug_info(module);
+            let (mpm, _fpm) =
+                roc_gen_llvm::llvm::build::construct_pass_pipeline(module, &llvm_pipeline);
 
-    // we don't use the debug info, and it causes weird errors.
-    module.strip_debug_info();
+            if let Some(target_machine) = &target_machine {
+                // let the optimizer know which instructions (e.g. vector extensions) the CPU has
+                target_machine.add_analysis_passes(&mpm);
+            }
 
-    // Uncomment this to see the module's optimized LLVM instruction output:
-    // env.module.print_to_stderr();
+            // Compile and add all the Procs before adding main
+            let env = roc_gen_llvm::llvm::build::Env {
+                arena,
+                layout_interner: &loaded.layout_interner,
+                builder: &builder,
+                dibuilder: &dibuilder,
+                compile_unit: &compile_unit,
+                context: &context,
+                interns: loaded.interns,
+                module,
+                target_info,
+                mode,
+
+                exposed_to_host: loaded.exposed_to_host.values.keys().copied().collect(),
+                debug_sources: roc_gen_llvm::llvm::build::DebugSources::new(
+                    &loaded.sources,
+                    &loaded.def_regions,
+                ),
+            };
+
+            // does not add any externs for this mode (we have a host) but cleans up some functions
+            // around expects that would confuse the surgical linker
+            add_default_roc_externs(&env);
+
+            {
+                let _span = roc_tracing::trace_span("codegen", "build LLVM IR");
+
+                roc_gen_llvm::llvm::build::build_procedures(
+                    &env,
+                    opt_level,
+                    loaded.procedures,
+                    entry_point,
+                    Some(&app_ll_file),
+                );
+            }
 
-    mpm.run_on(module);
+            env.dibuilder.finalize();
 
-    // Verify the module
-    if let Err(errors) = env.module.verify() {
-        // write the ll code to a file, so we can modify it
-        env.module.print_to_file(&app_ll_file).unwrap();
+            // Development builds keep the debug info, so `roc dev` can map the stack of a crash
+            // back to Roc procedures. Otherwise we don't use it, and it causes weird errors.
+            if !matches!(env.mode, LlvmBackendMode::BinaryDev) {
+                module.strip_debug_info();
+            }
 
-        internal_error!(
-            "😱 LLVM errors when defining module; I wrote the full LLVM IR to {:?}\n\n {}",
-            app_ll_file,
-            errors.to_string(),
-        );
+            // The profile has to be applied before the optimization pipeline, which uses it
+            let module: &Module = match &pgo {
+                Some(pgo) => arena.alloc(apply_pgo(&context, module, pgo)),
+                None => module,
+            };
+
+            // Uncomment this to see the module's LLVM instruction output:
+            // module.print_to_stderr();
+
+            {
+                let _span = roc_tracing::trace_span("codegen", "optimize LLVM IR");
+
+                mpm.run_on(module);
+            }
+
+            // Verify the module
+            if let Err(errors) = module.verify() {
+                // write the ll code to a file, so we can modify it
+                module.print_to_file(&app_ll_file).unwrap();
+
+                internal_error!(
+                    "😱 LLVM errors when defining module; I wrote the full LLVM IR to {:?}\n\n {}",
+                    app_ll_file,
+                    errors.to_string(),
+                );
+            }
+
+            (module, env.interns)
+        }
+    };
+
+    if let Some(abi_hash) = abi_hash {
+        let i64_type = context.i64_type();
+        let global = module.add_global(i64_type, None, ABI_HASH_SYMBOL);
+
+        global.set_initializer(&i64_type.const_int(abi_hash, false));
+        global.set_constant(true);
+        global.set_linkage(Linkage::External);
     }
 
     // Uncomment this to see the module's optimized LLVM instruction output:
-    // env.module.print_to_stderr();
+    // module.print_to_stderr();
+
+    // the sanitizers from `--sanitize`, plus the ones from ROC_SANITIZERS (used for fuzzing)
+    let mut sanitizer_names: Vec<String> = sanitizers
+        .iter()
+        .map(|sanitizer| sanitizer.name().to_string())
+        .collect();
+    if cfg!(feature = "sanitizers") {
+        if let Ok(names) = std::env::var("ROC_SANITIZERS") {
+            sanitizer_names.extend(names.split(',').map(|x| x.trim().to_string()));
+        }
+    }
 
     // annotate the LLVM IR output with debug info
     // so errors are reported with the line number of the LLVM source
-    let memory_buffer = if cfg!(feature = "sanitizers") && std::env::var("ROC_SANITIZERS").is_ok() {
+    let memory_buffer = if !sanitizer_names.is_empty() {
         let dir = tempfile::tempdir().unwrap();
         let dir = dir.into_path();
 
@@ -245,6 +507,18 @@ fn gen_from_mono_module_llvm<'a>(
         let app_bc_file = dir.join("app.bc");
         let app_o_file = dir.join("app.o");
 
+        if sanitizer_names.iter().any(|name| name == "address") {
+            // AddressSanitizer only instruments the functions that ask for it
+            let kind_id = Attribute::get_named_enum_kind_id("sanitize_address");
+            let enum_attr = context.create_enum_attribute(kind_id, 0);
+
+            for function in module.get_functions() {
+                if function.count_basic_blocks() > 0 {
+                    function.add_attribute(AttributeLoc::Function, enum_attr);
+                }
+            }
+        }
+
         // write the ll code to a file, so we can modify it
         module.print_to_file(&app_ll_file).unwrap();
 
@@ -254,13 +528,14 @@ fn gen_from_mono_module_llvm<'a>(
         let mut passes = vec![];
         let mut extra_args = vec![];
         let mut unrecognized = vec![];
-        for sanitizer in std::env::var("ROC_SANITIZERS")
-            .unwrap()
-            .split(',')
-            .map(|x| x.trim())
-        {
+
+        for sanitizer in sanitizer_names.iter().map(|x| x.as_str()) {
             match sanitizer {
-                "address" => passes.push("asan-module"),
+                "address" => {
+                    passes.push("require<asan-globals-md>");
+                    passes.push("function(asan)");
+                    passes.push("asan-module");
+                }
                 "memory" => passes.push("msan-module"),
                 "thread" => passes.push("tsan-module"),
                 "fuzzer" => {
@@ -286,37 +561,33 @@ fn gen_from_mono_module_llvm<'a>(
         }
 
         use std::process::Command;
+        let mtriple = format!("-mtriple={}", target);
+
         let mut opt = Command::new("opt");
         opt.args([
             app_ll_file.to_str().unwrap(),
             "-o",
             app_bc_file.to_str().unwrap(),
+            &mtriple,
         ])
         .args(extra_args);
         if !passes.is_empty() {
             opt.arg(format!("-passes={}", passes.join(",")));
         }
-        let opt = opt.output().unwrap();
-
-        assert!(opt.stderr.is_empty(), "{:#?}", opt);
-
-        // write the .o file. Note that this builds the .o for the local machine,
-        // and ignores the `target_machine` entirely.
-        //
-        // different systems name this executable differently, so we shotgun for
-        // the most common ones and then give up.
-        let bc_to_object = Command::new("llc")
-            .args(&[
-                "-relocation-model=pic",
-                "-filetype=obj",
-                app_bc_file.to_str().unwrap(),
-                "-o",
-                app_o_file.to_str().unwrap(),
-            ])
-            .output()
-            .unwrap();
-
-        assert!(bc_to_object.status.success(), "{:#?}", bc_to_object);
+        run_sanitizer_tool(opt, "opt");
+
+        // write the .o file. This builds the .o for the target, but with the generic CPU, because
+        // it ignores the `target_machine` entirely.
+        let mut bc_to_object = Command::new("llc");
+        bc_to_object.args([
+            "-relocation-model=pic",
+            "-filetype=obj",
+            &mtriple,
+            app_bc_file.to_str().unwrap(),
+            "-o",
+            app_o_file.to_str().unwrap(),
+        ]);
+        run_sanitizer_tool(bc_to_object, "llc");
 
         MemoryBuffer::create_from_file(&app_o_file).expect("memory buffer creation works")
     } else if emit_debug_info {
@@ -380,15 +651,15 @@ fn gen_from_mono_module_llvm<'a>(
         MemoryBuffer::create_from_file(&app_o_file).expect("memory buffer creation works")
     } else {
         // Emit the .o file
-        use target_lexicon::Architecture;
         match target.architecture {
+            Architecture::X86_64 | Architecture::X86_32(_) | Architecture::Aarch64(_) if lto => {
+                module.write_bitcode_to_memory()
+            }
             Architecture::X86_64 | Architecture::X86_32(_) | Architecture::Aarch64(_) => {
-                let reloc = RelocMode::PIC;
-                let target_machine =
-                    target::target_machine(target, convert_opt_level(opt_level), reloc).unwrap();
-
                 target_machine
-                    .write_to_memory_buffer(env.module, FileType::Object)
+                    .as_ref()
+                    .unwrap()
+                    .write_to_memory_buffer(module, FileType::Object)
                     .expect("Writing .o file failed")
             }
             Architecture::Wasm32 => {
@@ -409,13 +680,289 @@ fn gen_from_mono_module_llvm<'a>(
         CodeObject::MemoryBuffer(memory_buffer),
         CodeGenTiming { code_gen },
         ExpectMetadata {
-            interns: env.interns,
+            interns,
             layout_interner: loaded.layout_interner,
             expectations: loaded.expectations,
         },
     )
 }
 
+/// Runs one of the LLVM tools that instrument the app for the sanitizers. These are not part of
+/// Roc, so a missing or failing tool is reported to the user rather than as a compiler bug.
+fn run_sanitizer_tool(mut command: std::process::Command, tool: &str) {
+    match command.output() {
+        Ok(output) if output.status.success() => {}
+        Ok(output) => user_error!(
+            "LLVM's `{}` failed to instrument the app for --sanitize:\n\n{}",
+            tool,
+            String::from_utf8_lossy(&output.stderr)
+        ),
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => user_error!(
+            "--sanitize needs LLVM's `{}` on the PATH, but I could not find it",
+            tool
+        ),
+        Err(error) => user_error!("I could not run LLVM's `{}`: {}", tool, error),
+    }
+}
+
+/// The sanitizer, profiling, and debug info builds go through external tools (and files that
+/// are not part of the cache key), so we don't cache those.
+fn llvm_output_is_cacheable(code_gen_options: &CodeGenOptions) -> bool {
+    let env_sanitizers = cfg!(feature = "sanitizers") && std::env::var("ROC_SANITIZERS").is_ok();
+
+    code_gen_options.sanitizers.is_empty()
+        && code_gen_options.pgo.is_none()
+        && !code_gen_options.emit_debug_info
+        && !env_sanitizers
+}
+
+/// How many entries to keep in the cache of each module. A few, so switching back and forth
+/// between branches doesn't run LLVM every time.
+const LLVM_CACHE_ENTRIES_PER_MODULE: usize = 4;
+
+/// Each app (or module that is run as tests) gets its own directory in the cache, so that one
+/// app's builds don't evict another's. The directory is named after the module, and after its
+/// path, because different projects tend to have modules of the same name. Inside it, each of the
+/// app's modules has a directory of its own.
+fn llvm_cache_module_dir(cache_dir: &Path, loaded: &MonomorphizedModule) -> PathBuf {
+    let module_name = loaded.interns.module_name(loaded.module_id).as_str();
+
+    let mut hasher = StableHasher::new();
+    if let Some((path, _)) = loaded.sources.get(&loaded.module_id) {
+        hasher.write_str(&path.to_string_lossy());
+    }
+
+    cache_dir.join(format!("{}-{:016x}", module_name, hasher.finish()))
+}
+
+/// Write the bitcode to a temporary file first and then move it into place, so another build
+/// reading the cache at the same time never sees a partially written entry. Then evict the
+/// module's oldest entries.
+fn write_llvm_cache(cache_path: &Path, bytes: &[u8]) -> std::io::Result<()> {
+    let module_dir = cache_path.parent().unwrap();
+    std::fs::create_dir_all(module_dir)?;
+
+    let temp_path = cache_path.with_extension(format!("{}.tmp", std::process::id()));
+
+    if let Err(err) =
+        std::fs::write(&temp_path, bytes).and_then(|()| std::fs::rename(&temp_path, cache_path))
+    {
+        let _ = std::fs::remove_file(&temp_path);
+
+        return Err(err);
+    }
+
+    let mut objects: Vec<(std::time::SystemTime, PathBuf)> = std::fs::read_dir(module_dir)?
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let modified = path.metadata().ok()?.modified().ok()?;
+
+            (path.extension()? == "bc" && path != cache_path).then_some((modified, path))
+        })
+        .collect();
+
+    // keep the entry we just wrote, even if the clock says another one is newer
+    let keep = LLVM_CACHE_ENTRIES_PER_MODULE - 1;
+
+    if objects.len() > keep {
+        // newest first
+        objects.sort_unstable_by(|a, b| b.cmp(a));
+
+        for (_, path) in objects.drain(keep..) {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+
+    Ok(())
+}
+
+/// What [build_llvm_modules_cached] builds each LLVM module with.
+struct LlvmModulesConfig<'a, 'r> {
+    target_info: roc_target::TargetInfo,
+    mode: LlvmBackendMode,
+    opt_level: OptLevel,
+    entry_point: roc_mono::ir::EntryPoint<'a>,
+    llvm_pipeline: &'r LlvmPipeline,
+    target_machine: Option<&'r inkwell::targets::TargetMachine>,
+}
+
+/// Builds the procedures of each Roc module into an LLVM module of its own and optimizes it,
+/// unless the cache has that module already. Then links the optimized modules back into one.
+///
+/// A module's cache key is its LLVM IR before optimization, which has the module's procedures and
+/// declarations of the specializations from other modules that they call. So after an edit, LLVM
+/// only runs on the modules whose IR changed. The price is that LLVM can't inline a procedure into
+/// a procedure of another module.
+fn build_llvm_modules_cached<'a, 'ctx>(
+    arena: &'a bumpalo::Bump,
+    context: &'ctx inkwell::context::Context,
+    new_module: &dyn Fn(&str) -> inkwell::module::Module<'ctx>,
+    loaded: &MonomorphizedModule<'a>,
+    config: LlvmModulesConfig<'a, '_>,
+    (cache_dir, key_hasher): (PathBuf, StableHasher),
+) -> inkwell::module::Module<'ctx> {
+    use inkwell::module::{Linkage, Module};
+    use roc_gen_llvm::llvm::build::{
+        alias_analysis_solutions, build_module_procedures, construct_pass_pipeline, DebugSources,
+        Env,
+    };
+
+    let interns = &loaded.interns;
+    let builder = context.create_builder();
+
+    // every module has to agree on which specializations there are
+    let mod_solutions = alias_analysis_solutions(
+        arena,
+        &loaded.layout_interner,
+        config.opt_level,
+        &loaded.procedures,
+        config.entry_point,
+    );
+
+    // the modules with procedures, and the app module, in a stable order
+    let mut homes: Vec<_> = loaded
+        .procedures
+        .keys()
+        .map(|(symbol, _)| symbol.module_id())
+        .chain([loaded.module_id])
+        .collect::<roc_collections::all::MutSet<_>>()
+        .into_iter()
+        .collect();
+    homes.sort_by_key(|home| interns.module_name(*home).as_str());
+
+    let mut linked: Option<Module<'ctx>> = None;
+    let mut built_procs = Vec::new();
+
+    for home in homes {
+        let module_name = interns.module_name(home).as_str();
+        let module = arena.alloc(new_module(module_name));
+
+        // every module has a copy of the builtins, and the linked module only needs one
+        if linked.is_some() {
+            for function in module.get_functions() {
+                if function.count_basic_blocks() > 0 {
+                    function.set_linkage(Linkage::Internal);
+                }
+            }
+
+            for global in module.get_globals() {
+                if global.get_initializer().is_some() {
+                    global.set_linkage(Linkage::Internal);
+                }
+            }
+        }
+
+        let (dibuilder, compile_unit) = Env::new_debug_info(module);
+        let env = Env {
+            arena,
+            layout_interner: &loaded.layout_interner,
+            builder: &builder,
+            dibuilder: &dibuilder,
+            compile_unit: &compile_unit,
+            context,
+            interns: interns.clone(),
+            module,
+            target_info: config.target_info,
+            mode: config.mode,
+
+            exposed_to_host: loaded.exposed_to_host.values.keys().copied().collect(),
+            debug_sources: DebugSources::new(&loaded.sources, &loaded.def_regions),
+        };
+
+        add_default_roc_externs(&env);
+
+        built_procs.extend(build_module_procedures(
+            &env,
+            config.opt_level,
+            mod_solutions,
+            loaded.procedures.clone(),
+            home,
+            None,
+        ));
+
+        env.dibuilder.finalize();
+
+        if !matches!(config.mode, LlvmBackendMode::BinaryDev) {
+            module.strip_debug_info();
+        }
+
+        let mut hasher = key_hasher;
+        hasher.write(module.print_to_string().to_bytes());
+
+        let cache_path = cache_dir
+            .join(module_name)
+            .join(format!("{:016x}.bc", hasher.finish()));
+
+        let bitcode = match std::fs::read(&cache_path) {
+            Ok(bitcode) => bitcode,
+            Err(_) => {
+                let (mpm, _fpm) = construct_pass_pipeline(module, config.llvm_pipeline);
+
+                if let Some(target_machine) = config.target_machine {
+                    // let the optimizer know which instructions (e.g. vector extensions) the CPU has
+                    target_machine.add_analysis_passes(&mpm);
+                }
+
+                mpm.run_on(module);
+
+                if let Err(errors) = module.verify() {
+                    internal_error!(
+                        "😱 LLVM errors when defining module {}:\n\n {}",
+                        module_name,
+                        errors.to_string(),
+                    );
+                }
+
+                let bitcode = module.write_bitcode_to_memory().as_slice().to_vec();
+
+                // a failure to cache should not fail the build, we'll just run LLVM again next time
+                let _ = write_llvm_cache(&cache_path, &bitcode);
+
+                bitcode
+            }
+        };
+
+        let buffer = MemoryBuffer::create_from_memory_range_copy(&bitcode, module_name);
+        let module = Module::parse_bitcode_from_buffer(&buffer, context).unwrap_or_else(|error| {
+            internal_error!(
+                "LLVM can't read the bitcode of module {}: {}",
+                module_name,
+                error
+            )
+        });
+
+        match &linked {
+            None => linked = Some(module),
+            Some(linked) => linked.link_in_module(module).unwrap_or_else(|error| {
+                internal_error!("LLVM can't link in module {}: {}", module_name, error)
+            }),
+        }
+    }
+
+    let linked = linked.unwrap();
+
+    // the modules called each other's procedures, but the host only calls the exposed wrappers
+    for name in built_procs {
+        if let Some(function) = linked.get_function(name) {
+            function.set_linkage(Linkage::Internal);
+        }
+    }
+
+    linked
+}
+
+fn executable_abi_hash(
+    loaded: &MonomorphizedModule,
+    target: &target_lexicon::Triple,
+) -> Option<u64> {
+    match &loaded.entry_point {
+        EntryPoint::Executable {
+            exposed_to_host, ..
+        } => Some(abi_hash(target, exposed_to_host, &loaded.interns)),
+        EntryPoint::Test => None,
+    }
+}
+
 #[cfg(feature = "target-wasm32")]
 fn gen_from_mono_module_dev<'a>(
     arena: &'a bumpalo::Bump,
@@ -466,6 +1013,7 @@ fn gen_from_mono_module_dev_wasm32<'a>(
     wasm_dev_stack_bytes: Option<u32>,
 ) -> GenFromMono<'a> {
     let code_gen_start = Instant::now();
+    let dbg_locations = dbg_locations(&loaded);
     let MonomorphizedModule {
         module_id,
         procedures,
@@ -487,6 +1035,7 @@ fn gen_from_mono_module_dev_wasm32<'a>(
         module_id,
         exposed_to_host,
         stack_bytes: wasm_dev_stack_bytes.unwrap_or(roc_gen_wasm::Env::DEFAULT_STACK_BYTES),
+        dbg_locations,
     };
 
     let host_bytes = std::fs::read(preprocessed_host_path).unwrap_or_else(|_| {
@@ -496,7 +1045,14 @@ fn gen_from_mono_module_dev_wasm32<'a>(
         )
     });
 
-    let host_module = roc_gen_wasm::parse_host(arena, &host_bytes).unwrap_or_else(|e| {
+    // Platforms preprocessed by an older compiler, or prebuilt ones, may not have an index.
+    let index_path = crate::link::wasm_host_index_path(preprocessed_host_path);
+    let host_module = match std::fs::read(index_path) {
+        Ok(index_bytes) => roc_gen_wasm::parse_indexed_host(arena, &host_bytes, &index_bytes),
+        Err(_) => roc_gen_wasm::parse_host(arena, &host_bytes),
+    };
+
+    let host_module = host_module.unwrap_or_else(|e| {
         internal_error!(
             "I ran into a problem with the host object file, {} at offset 0x{:x}:\n{}",
             preprocessed_host_path.display(),
@@ -521,6 +1077,34 @@ fn gen_from_mono_module_dev_wasm32<'a>(
     )
 }
 
+/// Where each `dbg` is in the source, for the wasm host to print along with the value
+#[cfg(feature = "target-wasm32")]
+fn dbg_locations(loaded: &MonomorphizedModule) -> MutMap<roc_module::symbol::Symbol, String> {
+    let mut locations = MutMap::default();
+
+    for (module_id, expectations) in loaded.expectations.iter() {
+        let src = match loaded.sources.get(module_id) {
+            Some((_, src)) => src,
+            None => continue,
+        };
+        let line_info = roc_region::all::LineInfo::new(src);
+
+        for (symbol, dbg) in expectations.dbgs.iter() {
+            let position = line_info.convert_pos(dbg.region.start());
+            let location = format!(
+                "{} {}:{}",
+                expectations.path.display(),
+                position.line + 1,
+                position.column + 1
+            );
+
+            locations.insert(*symbol, location);
+        }
+    }
+
+    locations
+}
+
 fn gen_from_mono_module_dev_assembly<'a>(
     arena: &'a bumpalo::Bump,
     loaded: MonomorphizedModule<'a>,
@@ -528,8 +1112,11 @@ fn gen_from_mono_module_dev_assembly<'a>(
 ) -> GenFromMono<'a> {
     let code_gen_start = Instant::now();
 
+    let abi_hash = executable_abi_hash(&loaded, target);
+
     let lazy_literals = true;
     let generate_allocators = false; // provided by the platform
+    let export_all_procs = false; // the linker only needs the host api
 
     let MonomorphizedModule {
         module_id,
@@ -537,6 +1124,8 @@ fn gen_from_mono_module_dev_assembly<'a>(
         mut interns,
         exposed_to_host,
         layout_interner,
+        sources,
+        def_regions,
         ..
     } = loaded;
 
@@ -547,9 +1136,20 @@ fn gen_from_mono_module_dev_assembly<'a>(
         exposed_to_host: exposed_to_host.values.keys().copied().collect(),
         lazy_literals,
         generate_allocators,
+        export_all_procs,
+        sources: &sources,
+        def_regions: &def_regions,
+    };
+
+    let mut module_object = {
+        let _span = roc_tracing::trace_span("codegen", "generate machine code");
+
+        roc_gen_dev::build_module(&env, &mut interns, target, procedures)
     };
 
-    let module_object = roc_gen_dev::build_module(&env, &mut interns, target, procedures);
+    if let Some(abi_hash) = abi_hash {
+        add_abi_hash_symbol(&mut module_object, abi_hash);
+    }
 
     let code_gen = code_gen_start.elapsed();
 
@@ -567,3 +1167,315 @@ fn gen_from_mono_module_dev_assembly<'a>(
         },
     )
 }
+
+fn add_abi_hash_symbol(output: &mut object::write::Object, abi_hash: u64) {
+    use object::write::{StandardSection, Symbol, SymbolSection};
+    use object::{SymbolFlags, SymbolKind, SymbolScope};
+
+    let section = output.section_id(StandardSection::ReadOnlyData);
+    let symbol = output.add_symbol(Symbol {
+        name: ABI_HASH_SYMBOL.as_bytes().to_vec(),
+        value: 0,
+        size: 8,
+        kind: SymbolKind::Data,
+        scope: SymbolScope::Dynamic,
+        weak: false,
+        section: SymbolSection::Undefined,
+        flags: SymbolFlags::None,
+    });
+
+    // the dev backend only targets little-endian architectures
+    output.add_symbol_data(symbol, section, &abi_hash.to_le_bytes(), 8);
+}
+
+#[cfg(test)]
+mod test_llvm_cache {
+    use super::{
+        gen_from_mono_module_llvm, write_llvm_cache, CodeGenBackend, CodeGenOptions,
+        LLVM_CACHE_ENTRIES_PER_MODULE,
+    };
+    use roc_load::{ExecutionMode, FloatWidth, LoadConfig, OptLevel, Threading};
+    use roc_packaging::cache::RocCacheDir;
+    use std::path::{Path, PathBuf};
+    use std::time::SystemTime;
+
+    const PLATFORM: &str = indoc::indoc!(
+        r#"
+        platform "multi-module"
+            requires {}{ main : Str }
+            exposes []
+            packages {}
+            imports []
+            provides [mainForHost]
+
+        mainForHost : Str
+        mainForHost = main
+        "#
+    );
+
+    const DEP: &str = indoc::indoc!(
+        r#"
+        interface Dep exposes [shout] imports []
+
+        shout : Str -> Str
+        shout = \str -> Str.concat str "!"
+        "#
+    );
+
+    fn app(greeting: &str) -> String {
+        format!(
+            indoc::indoc!(
+                r#"
+                app "cache"
+                    packages {{ pf: "platform/main.roc" }}
+                    imports [Dep]
+                    provides [main] to pf
+
+                main : Str
+                main = Dep.shout "{}"
+                "#
+            ),
+            greeting
+        )
+    }
+
+    fn build(app_path: &Path, cache_dir: &Path) {
+        let arena = bumpalo::Bump::new();
+        let target = target_lexicon::Triple::host();
+
+        let load_config = LoadConfig {
+            target_info: roc_target::TargetInfo::from(&target),
+            render: roc_reporting::report::RenderTarget::Generic,
+            palette: roc_reporting::report::DEFAULT_PALETTE,
+            threading: Threading::Single,
+            exec_mode: ExecutionMode::Executable,
+            frac_default: FloatWidth::F64,
+            // so `Dep.shout` is not inlined into the app
+            opt_level: OptLevel::Development,
+        };
+
+        let loaded = roc_load::load_and_monomorphize(
+            &arena,
+            app_path.to_path_buf(),
+            Default::default(),
+            RocCacheDir::Disallowed,
+            load_config,
+        )
+        .unwrap_or_else(|_| panic!("{} does not load", app_path.display()));
+
+        let code_gen_options = CodeGenOptions {
+            backend: CodeGenBackend::Llvm,
+            opt_level: OptLevel::Normal,
+            emit_debug_info: false,
+            llvm_pipeline: OptLevel::Normal.into(),
+            lto: false,
+            target_cpu: Default::default(),
+            sanitizers: vec![],
+            llvm_cache_dir: Some(cache_dir.to_path_buf()),
+            pgo: None,
+        };
+
+        gen_from_mono_module_llvm(&arena, loaded, app_path, &target, code_gen_options);
+    }
+
+    /// The cache entries of each module of the (only) app in the cache, and when they were written
+    fn cache_entries(cache_dir: &Path) -> Vec<(String, Vec<(PathBuf, SystemTime)>)> {
+        let app_dirs: Vec<_> = std::fs::read_dir(cache_dir).unwrap().collect();
+        assert_eq!(app_dirs.len(), 1);
+
+        let app_dir = app_dirs.into_iter().next().unwrap().unwrap().path();
+
+        let mut modules: Vec<_> = std::fs::read_dir(app_dir)
+            .unwrap()
+            .map(|module_dir| {
+                let module_dir = module_dir.unwrap();
+
+                let mut entries: Vec<_> = std::fs::read_dir(module_dir.path())
+                    .unwrap()
+                    .map(|entry| {
+                        let path = entry.unwrap().path();
+                        let modified = path.metadata().unwrap().modified().unwrap();
+
+                        (path, modified)
+                    })
+                    .collect();
+                entries.sort();
+
+                (
+                    module_dir.file_name().to_string_lossy().into_owned(),
+                    entries,
+                )
+            })
+            .collect();
+        modules.sort();
+
+        modules
+    }
+
+    #[test]
+    fn reuse_modules_that_did_not_change() {
+        let project = tempfile::tempdir().unwrap();
+        let cache_dir = tempfile::tempdir().unwrap();
+
+        let app_path = project.path().join("main.roc");
+        std::fs::create_dir(project.path().join("platform")).unwrap();
+        std::fs::write(project.path().join("platform/main.roc"), PLATFORM).unwrap();
+        std::fs::write(project.path().join("Dep.roc"), DEP).unwrap();
+        std::fs::write(&app_path, app("hello")).unwrap();
+
+        build(&app_path, cache_dir.path());
+        let before = cache_entries(cache_dir.path());
+
+        std::fs::write(&app_path, app("goodbye")).unwrap();
+
+        build(&app_path, cache_dir.path());
+        let after = cache_entries(cache_dir.path());
+
+        let entries_of = |modules: &[(String, Vec<(PathBuf, SystemTime)>)], name: &str| {
+            modules
+                .iter()
+                .find(|(module, _)| module == name)
+                .unwrap_or_else(|| panic!("no cache entries for {} in {:?}", name, modules))
+                .1
+                .clone()
+        };
+
+        // Dep is neither rebuilt, nor does it get another entry
+        assert_eq!(entries_of(&before, "Dep").len(), 1);
+        assert_eq!(entries_of(&before, "Dep"), entries_of(&after, "Dep"));
+
+        // the app module did change, so it gets an entry next to the old one
+        let changed: Vec<_> = after
+            .iter()
+            .filter(|(module, entries)| entries_of(&before, module) != *entries)
+            .map(|(module, entries)| (module.as_str(), entries.len()))
+            .collect();
+        assert_eq!(changed.len(), 1, "{:?}", changed);
+        assert_eq!(changed[0].1, 2, "{:?}", changed);
+    }
+
+    #[test]
+    fn evict_oldest_objects() {
+        let dir = tempfile::tempdir().unwrap();
+        let module_dir = dir.path().join("Main");
+        let entries = LLVM_CACHE_ENTRIES_PER_MODULE + 2;
+
+        for key in 0..entries {
+            let path = module_dir.join(format!("{:016x}.bc", key));
+
+            write_llvm_cache(&path, &[key as u8]).unwrap();
+            assert_eq!(std::fs::read(&path).unwrap(), [key as u8]);
+        }
+
+        let mut names: Vec<String> = std::fs::read_dir(&module_dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+
+        // no temporary files are left behind, and the newest object is kept
+        assert_eq!(names.len(), LLVM_CACHE_ENTRIES_PER_MODULE);
+        assert!(names.iter().all(|name| name.ends_with(".bc")));
+        assert!(names.contains(&format!("{:016x}.bc", entries - 1)));
+    }
+}
+
+#[cfg(test)]
+mod test_pgo {
+    use super::{apply_pgo, Pgo};
+    use inkwell::context::Context;
+    use inkwell::module::Module;
+    use inkwell::IntPredicate;
+
+    /// `f x = if x > 0 then x else 0`
+    fn module_with_branch(context: &Context) -> Module {
+        let module = context.create_module("app");
+        let builder = context.create_builder();
+        let i64_type = context.i64_type();
+        let function = module.add_function("f", i64_type.fn_type(&[i64_type.into()], false), None);
+
+        let entry = context.append_basic_block(function, "entry");
+        let then_block = context.append_basic_block(function, "then");
+        let else_block = context.append_basic_block(function, "else");
+
+        builder.position_at_end(entry);
+        let x = function.get_nth_param(0).unwrap().into_int_value();
+        let zero = i64_type.const_zero();
+        let cond = builder.build_int_compare(IntPredicate::SGT, x, zero, "cond");
+        builder.build_conditional_branch(cond, then_block, else_block);
+
+        builder.position_at_end(then_block);
+        builder.build_return(Some(&x));
+
+        builder.position_at_end(else_block);
+        builder.build_return(Some(&zero));
+
+        module
+    }
+
+    /// The line of the IR that defines the given global
+    fn global_line<'a>(ir: &'a str, name: &str) -> &'a str {
+        ir.lines()
+            .find(|line| line.starts_with(&format!("{} = ", name)))
+            .unwrap_or_else(|| panic!("no {} in:\n{}", name, ir))
+    }
+
+    #[test]
+    fn generate_instruments_the_module() {
+        let context = Context::create();
+        let module = apply_pgo(&context, &module_with_branch(&context), &Pgo::Generate);
+        let ir = module.print_to_string().to_string();
+
+        assert!(ir.contains("@__profc_f"), "{}", ir);
+        assert!(module.verify().is_ok());
+    }
+
+    #[test]
+    fn use_annotates_the_module_with_the_profile() {
+        let context = Context::create();
+        let module = module_with_branch(&context);
+
+        // The profile has to match the function's control flow, which the instrumented module
+        // records: its hash, and how many counters it has.
+        let instrumented = apply_pgo(&context, &module, &Pgo::Generate);
+        let ir = instrumented.print_to_string().to_string();
+
+        // `@__profd_f = ... { i64 <name hash>, i64 <control flow hash>, ...`
+        let data = global_line(&ir, "@__profd_f");
+        let fields = &data[data.find("} { ").unwrap() + "} { ".len()..];
+        let hash = fields.split(", ").nth(1).unwrap();
+        let hash = hash.trim_start_matches("i64 ").parse::<i64>().unwrap() as u64;
+
+        // `@__profc_f = ... [<counters> x i64] ...`
+        let counters = global_line(&ir, "@__profc_f");
+        let counters = &counters[counters.find('[').unwrap() + 1..];
+        let counters: u64 = counters[..counters.find(' ').unwrap()].parse().unwrap();
+
+        let dir = tempfile::tempdir().unwrap();
+        let text_profile = dir.path().join("app.proftext");
+        let profile = dir.path().join("app.profdata");
+        let counts: Vec<String> = (0..counters).map(|i| (100 * (i + 1)).to_string()).collect();
+
+        std::fs::write(
+            &text_profile,
+            format!(":ir\nf\n{}\n{}\n{}\n", hash, counters, counts.join("\n")),
+        )
+        .unwrap();
+
+        let merge = std::process::Command::new("llvm-profdata")
+            .arg("merge")
+            .arg(&text_profile)
+            .arg("-o")
+            .arg(&profile)
+            .output()
+            .unwrap();
+        assert!(merge.status.success(), "{:#?}", merge);
+
+        let module = apply_pgo(&context, &module, &Pgo::Use(profile));
+        let ir = module.print_to_string().to_string();
+
+        // the optimization pipeline reads these: the profile summary, and the branch weights
+        assert!(ir.contains("ProfileSummary"), "{}", ir);
+        assert!(ir.contains("branch_weights"), "{}", ir);
+    }
+}
@@ -11,6 +11,12 @@ pub fn target_triple_str(target: &Triple) -> &'static str {
     //
     // https://stackoverflow.com/questions/15036909/clang-how-to-list-supported-target-architectures
     match target {
+        Triple {
+            architecture: Architecture::X86_64,
+            operating_system: OperatingSystem::Linux,
+            environment: Environment::Musl,
+            ..
+        } => "x86_64-unknown-linux-musl",
         Triple {
             architecture: Architecture::X86_64,
             operating_system: OperatingSystem::Linux,
@@ -140,10 +146,62 @@ pub fn arch_str(target: &Triple) -> &'static str {
     }
 }
 
+/// The CPU (and its optional features, like `+avx2` or `+neon`) that LLVM generates code for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TargetCpu {
+    pub name: String,
+    pub features: String,
+}
+
+impl Default for TargetCpu {
+    fn default() -> Self {
+        Self {
+            name: "generic".to_string(),
+            features: String::new(),
+        }
+    }
+}
+
+impl TargetCpu {
+    /// The CPU this compiler is running on, with all of its features
+    pub fn native() -> Self {
+        Self {
+            name: TargetMachine::get_host_cpu_name().to_string(),
+            features: TargetMachine::get_host_cpu_features().to_string(),
+        }
+    }
+
+    /// From the `--target-cpu` and `--target-features` flags, where `native` means the host CPU.
+    pub fn from_flags(name: Option<&str>, features: Option<&str>) -> Self {
+        let name = match name {
+            Some("native") => TargetMachine::get_host_cpu_name().to_string(),
+            Some(name) => name.to_string(),
+            None => Self::default().name,
+        };
+
+        let features = match features {
+            Some("native") => TargetMachine::get_host_cpu_features().to_string(),
+            Some(features) => features.to_string(),
+            None => String::new(),
+        };
+
+        Self { name, features }
+    }
+}
+
 pub fn target_machine(
     target: &Triple,
     opt: OptimizationLevel,
     reloc: RelocMode,
+) -> Option<TargetMachine> {
+    target_machine_for_cpu(target, &TargetCpu::default(), opt, reloc)
+}
+
+pub fn target_machine_for_cpu(
+    target: &Triple,
+    cpu: &TargetCpu,
+    opt: OptimizationLevel,
+    reloc: RelocMode,
 ) -> Option<TargetMachine> {
     let arch = arch_str(target);
 
@@ -161,8 +219,8 @@ pub fn target_machine(
 
     Target::from_name(arch).unwrap().create_target_machine(
         &TargetTriple::create(target_triple_str(target)),
-        "generic",
-        "", // TODO: this probably should be TargetMachine::get_host_cpu_features() to enable all features.
+        &cpu.name,
+        &cpu.features,
         opt,
         reloc,
         code_model,
@@ -177,3 +235,62 @@ pub fn convert_opt_level(level: OptLevel) -> OptimizationLevel {
         OptLevel::Optimize => OptimizationLevel::Aggressive,
     }
 }
+
+#[cfg(test)]
+mod test_target_cpu {
+    use super::*;
+
+    #[test]
+    fn generic_cpu_without_flags() {
+        assert_eq!(TargetCpu::from_flags(None, None), TargetCpu::default());
+        assert_eq!(TargetCpu::default().name, "generic");
+        assert_eq!(TargetCpu::default().features, "");
+    }
+
+    #[test]
+    fn cpu_from_flags() {
+        assert_eq!(
+            TargetCpu::from_flags(Some("skylake"), Some("+avx2,-fma")),
+            TargetCpu {
+                name: "skylake".to_string(),
+                features: "+avx2,-fma".to_string(),
+            }
+        );
+
+        // only the features, for the generic CPU
+        assert_eq!(
+            TargetCpu::from_flags(None, Some("+neon")).name,
+            TargetCpu::default().name
+        );
+    }
+
+    #[test]
+    fn native_cpu_from_flags() {
+        assert_eq!(
+            TargetCpu::from_flags(Some("native"), Some("native")),
+            TargetCpu::native()
+        );
+
+        let native_name = TargetCpu::from_flags(Some("native"), None);
+        assert_eq!(native_name.name, TargetCpu::native().name);
+        assert_eq!(native_name.features, "");
+    }
+
+    #[cfg(feature = "target-x86_64")]
+    #[test]
+    fn target_machine_uses_cpu() {
+        use std::str::FromStr;
+
+        let triple = Triple::from_str("x86_64-unknown-linux-gnu").unwrap();
+        let cpu = TargetCpu::from_flags(Some("skylake"), Some("+avx2"));
+
+        let target_machine =
+            target_machine_for_cpu(&triple, &cpu, OptimizationLevel::None, RelocMode::PIC).unwrap();
+
+        assert_eq!(target_machine.get_cpu().to_string(), "skylake");
+        assert_eq!(
+            target_machine.get_feature_string().to_string_lossy(),
+            "+avx2"
+        );
+    }
+}
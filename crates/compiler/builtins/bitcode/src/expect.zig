@@ -100,3 +100,49 @@ pub fn notifyParentExpect(shared_buffer: [*]u8) callconv(.C) void {
 pub fn notifyParentDbg(shared_buffer: [*]u8) callconv(.C) void {
     notifyParent(shared_buffer, 2);
 }
+
+// Must match PANIC_FRAMES_OFFSET and MAX_PANIC_FRAMES in crates/repl_expect/src/run.rs
+const PANIC_FRAMES_OFFSET: usize = 3;
+const MAX_PANIC_FRAMES: usize = 64;
+
+// A frame bigger than this means the frame pointer we followed wasn't one
+const MAX_FRAME_SIZE: usize = 1 << 20;
+
+// Tells the parent (roc dev) that the program is about to panic, and sends it the return addresses
+// of the stack, innermost first, so it can print a stack trace. They're found by following the
+// frame pointers, which roc dev builds keep. The first word is the address of this function, so
+// the parent can tell where the executable was loaded.
+pub fn notifyParentPanic() callconv(.C) void {
+    if (builtin.os.tag == .macos or builtin.os.tag == .linux) {
+        const shared_buffer = expectFailedStartSharedFile();
+        const words = @ptrCast([*]usize, @alignCast(@alignOf(usize), shared_buffer));
+        const frames = words + PANIC_FRAMES_OFFSET + 2;
+
+        var count: usize = 0;
+        var fp = @frameAddress();
+
+        while (count < MAX_PANIC_FRAMES) {
+            // a frame record is the caller's frame pointer followed by the return address
+            const record = @intToPtr([*]const usize, fp);
+            const caller_fp = record[0];
+            const return_address = record[1];
+
+            if (return_address == 0) break;
+
+            frames[count] = return_address;
+            count += 1;
+
+            // The stack grows down, so callers' frames are at higher addresses. Anything else
+            // means we reached a frame without a frame pointer, like one of the host's.
+            const is_caller = caller_fp > fp and caller_fp - fp <= MAX_FRAME_SIZE;
+            if (!is_caller or caller_fp % @alignOf(usize) != 0) break;
+
+            fp = caller_fp;
+        }
+
+        words[PANIC_FRAMES_OFFSET] = @ptrToInt(notifyParentPanic);
+        words[PANIC_FRAMES_OFFSET + 1] = count;
+
+        notifyParent(shared_buffer, 4);
+    }
+}
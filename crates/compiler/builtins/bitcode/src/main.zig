@@ -174,6 +174,7 @@ comptime {
         exportUtilsFn(expect.expectFailedStartSharedFile, "expect_failed_start_shared_file");
         exportUtilsFn(expect.notifyParentExpect, "notify_parent_expect");
         exportUtilsFn(expect.notifyParentDbg, "notify_parent_dbg");
+        exportUtilsFn(expect.notifyParentPanic, "notify_parent_panic");
 
         // sets the buffer used for expect failures
         @export(expect.setSharedBuffer, .{ .name = "set_shared_buffer", .linkage = .Weak });
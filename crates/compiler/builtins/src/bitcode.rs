@@ -423,6 +423,7 @@ pub const UTILS_EXPECT_FAILED_START_SHARED_FILE: &str =
 pub const UTILS_EXPECT_READ_ENV_SHARED_BUFFER: &str = "roc_builtins.utils.read_env_shared_buffer";
 pub const NOTIFY_PARENT_EXPECT: &str = "roc_builtins.utils.notify_parent_expect";
 pub const NOTIFY_PARENT_DBG: &str = "roc_builtins.utils.notify_parent_dbg";
+pub const NOTIFY_PARENT_PANIC: &str = "roc_builtins.utils.notify_parent_panic";
 
 pub const UTILS_LONGJMP: &str = "longjmp";
 pub const UTILS_SETJMP: &str = "setjmp";
@@ -587,7 +587,8 @@ impl Constraints {
             | Constraint::PatternPresence(_, _, _, _)
             | Constraint::Exhaustive { .. }
             | Constraint::Resolve(..)
-            | Constraint::CheckCycle(..) => false,
+            | Constraint::CheckCycle(..)
+            | Constraint::TypedHole(..) => false,
         }
     }
 
@@ -761,6 +762,9 @@ pub enum Constraint {
     /// Attempt to resolve a specialization.
     Resolve(OpportunisticResolve),
     CheckCycle(Index<Cycle>, IllegalCycleMark),
+    /// Report the type of a typed hole, and the values in scope that could fill it,
+    /// once the rest of the module has been solved.
+    TypedHole(Variable, Region),
 }
 
 #[derive(Debug, Clone, Copy, Default)]
@@ -834,6 +838,9 @@ impl std::fmt::Debug for Constraint {
             Self::CheckCycle(arg0, arg1) => {
                 write!(f, "CheckCycle({:?}, {:?})", arg0, arg1)
             }
+            Self::TypedHole(arg0, arg1) => {
+                write!(f, "TypedHole({:?}, {:?})", arg0, arg1)
+            }
         }
     }
 }
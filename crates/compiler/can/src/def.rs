@@ -907,7 +907,7 @@ pub(crate) fn canonicalize_defs<'a>(
     scope: &mut Scope,
     loc_defs: &'a mut roc_parse::ast::Defs<'a>,
     pattern_type: PatternType,
-) -> (CanDefs, Output, MutMap<Symbol, Region>) {
+) -> (CanDefs, Output, MutMap<Symbol, IntroducedSymbol>) {
     // Canonicalizing defs while detecting shadowing involves a multi-step process:
     //
     // 1. Go through each of the patterns.
@@ -1012,12 +1012,13 @@ fn canonicalize_value_defs<'a>(
     value_defs: Vec<Loc<PendingValue<'a>>>,
     pattern_type: PatternType,
     mut aliases: VecMap<Symbol, Alias>,
-    mut symbols_introduced: MutMap<Symbol, Region>,
-) -> (CanDefs, Output, MutMap<Symbol, Region>) {
+    mut symbols_introduced: MutMap<Symbol, IntroducedSymbol>,
+) -> (CanDefs, Output, MutMap<Symbol, IntroducedSymbol>) {
     // Canonicalize all the patterns, record shadowing problems, and store
     // the ast::Expr values in pending_exprs for further canonicalization
     // once we've finished assembling the entire scope.
     let mut pending_value_defs = Vec::with_capacity(value_defs.len());
+    let mut pending_value_def_regions = Vec::with_capacity(value_defs.len());
     let mut pending_dbgs = Vec::with_capacity(value_defs.len());
     let mut pending_expects = Vec::with_capacity(value_defs.len());
     let mut pending_expect_fx = Vec::with_capacity(value_defs.len());
@@ -1030,6 +1031,7 @@ fn canonicalize_value_defs<'a>(
                 // the exprs right now, they wouldn't have symbols in scope from defs
                 // that get would have gotten added later in the defs list!
                 pending_value_defs.push(pending_def);
+                pending_value_def_regions.push(loc_pending_def.region);
             }
             PendingValue::SignatureDefMismatch => { /* skip */ }
             PendingValue::Dbg(pending_dbg) => {
@@ -1057,13 +1059,28 @@ fn canonicalize_value_defs<'a>(
             ));
         }
 
+        // Removing the whole def only removes this one symbol if the def binds nothing else.
+        let binds_single_symbol = matches!(pending_def.loc_pattern().value, Pattern::Identifier(_));
+
         for (s, r) in new_bindings {
             // store the top-level defs, used to ensure that closures won't capture them
             if let PatternType::TopLevelDef = pattern_type {
                 env.top_level_symbols.insert(s);
             }
 
-            symbols_introduced.insert(s, r);
+            let removal_region = if binds_single_symbol {
+                pending_value_def_regions[def_index]
+            } else {
+                r
+            };
+
+            symbols_introduced.insert(
+                s,
+                IntroducedSymbol {
+                    region: r,
+                    removal_region,
+                },
+            );
 
             debug_assert_eq!(env.home, s.module_id());
             debug_assert!(
@@ -1156,9 +1173,18 @@ fn canonicalize_value_defs<'a>(
     (can_defs, output, symbols_introduced)
 }
 
+/// Where a def introduced a symbol, used to report the symbol if it goes unused.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct IntroducedSymbol {
+    /// The region to point at in reports.
+    pub region: Region,
+    /// The region to delete to remove the definition of the symbol.
+    pub removal_region: Region,
+}
+
 struct CanonicalizedTypeDefs<'a> {
     aliases: VecMap<Symbol, Alias>,
-    symbols_introduced: MutMap<Symbol, Region>,
+    symbols_introduced: MutMap<Symbol, IntroducedSymbol>,
     derived_defs: Vec<DerivedDef<'a>>,
 }
 
@@ -1194,7 +1220,11 @@ fn canonicalize_type_defs<'a>(
 
     for pending_def in pending_type_defs.into_iter() {
         if let Some((symbol, region)) = pending_def.introduction() {
-            symbols_introduced.insert(symbol, region);
+            let introduced = IntroducedSymbol {
+                region,
+                removal_region: region,
+            };
+            symbols_introduced.insert(symbol, introduced);
         }
 
         match pending_def {
@@ -2368,11 +2398,15 @@ pub fn can_defs_with_return<'a>(
 
     // Now that we've collected all the references, check to see if any of the new idents
     // we defined went unused by the return expression or any other def.
-    for (symbol, region) in symbols_introduced {
+    for (symbol, introduced) in symbols_introduced {
         if !output.references.has_type_or_value_lookup(symbol)
             && !scope.abilities_store.is_specialization_name(symbol)
         {
-            env.problem(Problem::UnusedDef(symbol, region));
+            env.problem(Problem::UnusedDef(
+                symbol,
+                introduced.region,
+                introduced.removal_region,
+            ));
         }
     }
 
@@ -2448,6 +2482,8 @@ fn to_pending_alias_or_opaque<'a>(
 
     match scope.introduce_without_shadow_symbol(&Ident::from(name.value), region) {
         Ok(symbol) => {
+            env.usages.insert_definition(symbol, name.region);
+
             let mut can_rigids: Vec<Loc<Lowercase>> = Vec::with_capacity(vars.len());
 
             for loc_var in vars.iter() {
@@ -2565,7 +2601,11 @@ fn to_pending_type_def<'a>(
             let name = match scope
                 .introduce_without_shadow_symbol(&Ident::from(name.value), name.region)
             {
-                Ok(symbol) => Loc::at(name.region, symbol),
+                Ok(symbol) => {
+                    env.usages.insert_definition(symbol, name.region);
+
+                    Loc::at(name.region, symbol)
+                }
                 Err((original_symbol, original_region, shadowed_symbol)) => {
                     env.problem(Problem::Shadowing {
                         original_region,
@@ -1,10 +1,11 @@
 use crate::procedure::References;
 use crate::scope::Scope;
+use crate::usages::SymbolUsages;
 use bumpalo::Bump;
-use roc_collections::{MutMap, VecSet};
+use roc_collections::{MutMap, MutSet, VecSet};
 use roc_module::ident::{Ident, Lowercase, ModuleName};
 use roc_module::symbol::{IdentIdsByModule, ModuleId, ModuleIds, Symbol};
-use roc_problem::can::{Problem, RuntimeError};
+use roc_problem::can::{NameKind, Problem, RuntimeError};
 use roc_region::all::{Loc, Region};
 
 /// The canonicalization environment for a particular module.
@@ -34,6 +35,16 @@ pub struct Env<'a> {
 
     pub top_level_symbols: VecSet<Symbol>,
 
+    /// Where each value and type lookup in this module resolved to, for find-all-references.
+    pub usages: SymbolUsages,
+
+    /// The first use of each tag and record field name, keyed by its lowercased spelling,
+    /// so we can point out names that differ only in capitalization.
+    first_spellings: MutMap<(NameKind, String), Loc<Box<str>>>,
+
+    /// Spellings we already reported as differing only in case, so each is reported once.
+    reported_spellings: MutSet<(NameKind, Box<str>)>,
+
     pub arena: &'a Bump,
 }
 
@@ -55,6 +66,26 @@ impl<'a> Env<'a> {
             qualified_type_lookups: VecSet::default(),
             tailcallable_symbol: None,
             top_level_symbols: VecSet::default(),
+            usages: SymbolUsages::default(),
+            first_spellings: MutMap::default(),
+            reported_spellings: MutSet::default(),
+        }
+    }
+
+    /// Record a use of a tag or record field name, warning if an earlier use spelled it with
+    /// different capitalization.
+    pub fn check_name_case(&mut self, kind: NameKind, name: &str, region: Region) {
+        let original = self
+            .first_spellings
+            .entry((kind, name.to_lowercase()))
+            .or_insert_with(|| Loc::at(region, name.into()));
+
+        if &*original.value != name && self.reported_spellings.insert((kind, name.into())) {
+            self.problems.push(Problem::NameDiffersOnlyInCase {
+                kind,
+                original: original.clone(),
+                similar: Loc::at(region, name.into()),
+            });
         }
     }
 
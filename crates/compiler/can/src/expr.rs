@@ -20,7 +20,7 @@ use roc_module::low_level::LowLevel;
 use roc_module::symbol::Symbol;
 use roc_parse::ast::{self, Defs, EscapedChar, StrLiteral};
 use roc_parse::pattern::PatternType::*;
-use roc_problem::can::{PrecedenceProblem, Problem, RuntimeError};
+use roc_problem::can::{NameKind, PrecedenceProblem, Problem, RuntimeError};
 use roc_region::all::{Loc, Region};
 use roc_types::num::SingleQuoteBound;
 use roc_types::subs::{ExhaustiveMark, IllegalCycleMark, RedundantMark, VarStore, Variable};
@@ -910,17 +910,10 @@ pub fn canonicalize_expr<'a>(
         ast::Expr::Var { module_name, ident } => {
             canonicalize_var_lookup(env, var_store, scope, module_name, ident, region)
         }
-        ast::Expr::Underscore(name) => {
-            // we parse underscores, but they are not valid expression syntax
-            let problem = roc_problem::can::RuntimeError::MalformedIdentifier(
-                (*name).into(),
-                roc_parse::ident::BadIdent::Underscore(region.start()),
-                region,
-            );
-
-            env.problem(Problem::RuntimeError(problem.clone()));
-
-            (RuntimeError(problem), Output::default())
+        ast::Expr::Underscore(_) => {
+            // An underscore in expression position is a typed hole. It type-checks like any
+            // other expression, and the solver reports the type it must have.
+            (TypedHole(var_store.fresh()), Output::default())
         }
         ast::Expr::Crash => {
             // Naked crashes aren't allowed; we'll admit this with our own message, but yield an
@@ -1005,6 +998,10 @@ pub fn canonicalize_expr<'a>(
         ast::Expr::RecordAccess(record_expr, field) => {
             let (loc_expr, output) = canonicalize_expr(env, var_store, scope, region, record_expr);
 
+            // the field is not located by the parser, but it ends the access
+            let field_region = Region::new(region.end().sub(field.len() as u32), region.end());
+            env.check_name_case(NameKind::RecordField, field, field_region);
+
             (
                 Access {
                     record_var: var_store.fresh(),
@@ -1031,6 +1028,8 @@ pub fn canonicalize_expr<'a>(
         ast::Expr::TupleAccess(_record_expr, _field) => todo!("handle TupleAccess"),
         ast::Expr::TupleAccessorFunction(_) => todo!("handle TupleAccessorFunction"),
         ast::Expr::Tag(tag) => {
+            env.check_name_case(NameKind::Tag, tag, region);
+
             let variant_var = var_store.fresh();
             let ext_var = var_store.fresh();
 
@@ -1673,6 +1672,8 @@ fn canonicalize_field<'a>(
     match field {
         // Both a label and a value, e.g. `{ name: "blah" }`
         RequiredValue(label, _, loc_expr) => {
+            env.check_name_case(NameKind::RecordField, label.value, label.region);
+
             let field_var = var_store.fresh();
             let (loc_can_expr, output) =
                 canonicalize_expr(env, var_store, scope, loc_expr.region, &loc_expr.value);
@@ -1722,6 +1723,7 @@ fn canonicalize_var_lookup(
         match scope.lookup_str(ident, region) {
             Ok(symbol) => {
                 output.references.insert_value_lookup(symbol);
+                env.usages.insert_lookup(symbol, region, module_name, ident);
 
                 if scope.abilities_store.is_ability_member_name(symbol) {
                     AbilityMember(
@@ -1745,6 +1747,7 @@ fn canonicalize_var_lookup(
         match env.qualified_lookup(scope, module_name, ident, region) {
             Ok(symbol) => {
                 output.references.insert_value_lookup(symbol);
+                env.usages.insert_lookup(symbol, region, module_name, ident);
 
                 if scope.abilities_store.is_ability_member_name(symbol) {
                     AbilityMember(
@@ -8,6 +8,7 @@ use crate::expr::{
 };
 use crate::pattern::{BindingsFromPattern, Pattern};
 use crate::scope::Scope;
+use crate::usages::SymbolUsages;
 use bumpalo::Bump;
 use roc_collections::{MutMap, SendMap, VecMap, VecSet};
 use roc_error_macros::internal_error;
@@ -134,6 +135,7 @@ pub struct Module {
     pub abilities_store: PendingAbilitiesStore,
     pub loc_expects: VecMap<Region, Vec<ExpectLookup>>,
     pub loc_dbgs: VecMap<Symbol, DbgLookup>,
+    pub usages: SymbolUsages,
 }
 
 #[derive(Debug, Default)]
@@ -385,13 +387,17 @@ pub fn canonicalize_module_defs<'a>(
     //
     // We'll catch symbols that are only referenced due to (mutual) recursion later,
     // when sorting the defs.
-    for (symbol, region) in symbols_introduced {
+    for (symbol, introduced) in symbols_introduced {
         if !output.references.has_type_or_value_lookup(symbol)
             && !exposed_symbols.contains(&symbol)
             && !scope.abilities_store.is_specialization_name(symbol)
             && !symbol.is_exposed_for_builtin_derivers()
         {
-            env.problem(Problem::UnusedDef(symbol, region));
+            env.problem(Problem::UnusedDef(
+                symbol,
+                introduced.region,
+                introduced.removal_region,
+            ));
         }
     }
 
@@ -810,6 +816,7 @@ pub fn canonicalize_module_defs<'a>(
         pending_derives,
         loc_expects: collected.expects,
         loc_dbgs: collected.dbgs,
+        usages: env.usages,
     }
 }
 
@@ -1,7 +1,7 @@
 //! Traversals over the can ast.
 
 use roc_module::{ident::Lowercase, symbol::Symbol};
-use roc_region::all::{Loc, Region};
+use roc_region::all::{Loc, Position, Region};
 use roc_types::{subs::Variable, types::MemberImpl};
 
 use crate::{
@@ -553,6 +553,133 @@ pub fn find_type_at(region: Region, decls: &Declarations) -> Option<Variable> {
     visitor.typ
 }
 
+/// The innermost expression or pattern at some position in the source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FoundAt {
+    pub region: Region,
+    pub var: Variable,
+    /// The symbol the expression looks up, or the pattern introduces, if any.
+    pub symbol: Option<Symbol>,
+}
+
+struct InnermostAtVisitor {
+    position: Position,
+    found: Option<FoundAt>,
+}
+
+impl Visitor for InnermostAtVisitor {
+    fn should_visit(&mut self, region: Region) -> bool {
+        region.contains(&Region::from_pos(self.position))
+    }
+
+    fn visit_expr(&mut self, expr: &Expr, region: Region, var: Variable) {
+        if self.should_visit(region) {
+            let symbol = match expr {
+                Expr::Var(symbol, _) | Expr::AbilityMember(symbol, _, _) => Some(*symbol),
+                _ => None,
+            };
+
+            // Anything found while walking the children is more specific, so it overwrites this.
+            self.found = Some(FoundAt {
+                region,
+                var,
+                symbol,
+            });
+
+            walk_expr(self, expr, var);
+        }
+    }
+
+    fn visit_pattern(&mut self, pattern: &Pattern, region: Region, opt_var: Option<Variable>) {
+        if self.should_visit(region) {
+            if let Some(var) = opt_var {
+                let symbol = match pattern {
+                    Pattern::Identifier(symbol)
+                    | Pattern::Shadowed(_, _, symbol)
+                    | Pattern::AbilityMemberSpecialization { ident: symbol, .. } => Some(*symbol),
+                    _ => None,
+                };
+
+                self.found = Some(FoundAt {
+                    region,
+                    var,
+                    symbol,
+                });
+            }
+
+            walk_pattern(self, pattern);
+        }
+    }
+
+    fn visit_record_destruct(&mut self, destruct: &RecordDestruct, region: Region) {
+        if self.should_visit(region) {
+            self.found = Some(FoundAt {
+                region,
+                var: destruct.var,
+                symbol: Some(destruct.symbol),
+            });
+
+            walk_record_destruct(self, destruct);
+        }
+    }
+}
+
+/// Finds the innermost expression or pattern whose region contains `position`, e.g. to show its
+/// type when hovering over it in an editor.
+pub fn find_innermost_at(position: Position, decls: &Declarations) -> Option<FoundAt> {
+    let mut visitor = InnermostAtVisitor {
+        position,
+        found: None,
+    };
+    visitor.visit_decls(decls);
+    visitor.found
+}
+
+/// Finds the region of the pattern that introduces `symbol` in `decls`, if any.
+pub fn find_symbol_definition(symbol: Symbol, decls: &Declarations) -> Option<Region> {
+    let mut visitor = Finder {
+        symbol,
+        found: None,
+    };
+    visitor.visit_decls(decls);
+    return visitor.found;
+
+    struct Finder {
+        symbol: Symbol,
+        found: Option<Region>,
+    }
+
+    impl Visitor for Finder {
+        fn should_visit(&mut self, _region: Region) -> bool {
+            self.found.is_none()
+        }
+
+        fn visit_pattern(&mut self, pattern: &Pattern, region: Region, _opt_var: Option<Variable>) {
+            use Pattern::*;
+
+            match pattern {
+                Identifier(symbol)
+                | Shadowed(_, _, symbol)
+                | AbilityMemberSpecialization { ident: symbol, .. }
+                    if *symbol == self.symbol =>
+                {
+                    self.found = Some(region);
+                }
+                _ if self.should_visit(region) => walk_pattern(self, pattern),
+                _ => {}
+            }
+        }
+
+        fn visit_record_destruct(&mut self, destruct: &RecordDestruct, region: Region) {
+            if destruct.symbol == self.symbol {
+                self.found = Some(region);
+            } else if self.should_visit(region) {
+                walk_record_destruct(self, destruct);
+            }
+        }
+    }
+}
+
 /// Given an ability Foo has foo : ..., returns (T, foo1) if the symbol at the given region is a
 /// symbol foo1 that specializes foo for T. Otherwise if the symbol is foo but the specialization
 /// is unknown, (Foo, foo) is returned. Otherwise [None] is returned.
@@ -0,0 +1,78 @@
+//! Where each symbol is used in a module, recorded while canonicalizing it.
+use roc_collections::VecMap;
+use roc_module::symbol::Symbol;
+use roc_region::all::{Position, Region};
+
+/// The regions of all value and type lookups in one module, by the symbol they resolved to.
+///
+/// Lookups of symbols from other modules are included, so the usages of all modules together
+/// give every use of a symbol across a project. The regions cover only the name: `concat` in
+/// `Str.concat`, and `Dict` in `Dict Str U64`.
+///
+/// The definitions of types and abilities are recorded too, since unlike values they are not
+/// patterns of the module's declarations.
+#[derive(Debug, Clone, Default)]
+pub struct SymbolUsages {
+    usages: VecMap<Symbol, Vec<Region>>,
+    definitions: VecMap<Symbol, Region>,
+}
+
+impl SymbolUsages {
+    /// Record a lookup of `module_name.ident`, or of just `ident` if `module_name` is empty,
+    /// written at the start of `region`
+    pub fn insert_lookup(
+        &mut self,
+        symbol: Symbol,
+        region: Region,
+        module_name: &str,
+        ident: &str,
+    ) {
+        let qualifier = match module_name {
+            "" => 0,
+            _ => module_name.len() + 1,
+        };
+        let start = region.start().offset + qualifier as u32;
+        let end = start + ident.len() as u32;
+
+        self.insert(
+            symbol,
+            Region::new(Position::new(start), Position::new(end)),
+        );
+    }
+
+    pub fn insert(&mut self, symbol: Symbol, region: Region) {
+        match self.usages.get_mut(&symbol) {
+            Some(regions) => {
+                // Some annotations are canonicalized more than once
+                if !regions.contains(&region) {
+                    regions.push(region);
+                }
+            }
+            None => {
+                self.usages.insert(symbol, vec![region]);
+            }
+        }
+    }
+
+    pub fn get(&self, symbol: Symbol) -> &[Region] {
+        match self.usages.get(&symbol) {
+            Some(regions) => regions,
+            None => &[],
+        }
+    }
+
+    /// Record where a type or ability is defined, given the region of its name
+    pub fn insert_definition(&mut self, symbol: Symbol, region: Region) {
+        self.definitions.insert(symbol, region);
+    }
+
+    pub fn definition(&self, symbol: Symbol) -> Option<Region> {
+        self.definitions.get(&symbol).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (Symbol, &[Region])> {
+        self.usages
+            .iter()
+            .map(|(symbol, regions)| (*symbol, regions.as_slice()))
+    }
+}
@@ -546,7 +546,7 @@ mod test_can {
         assert_eq!(problems.len(), 1);
         assert!(problems
             .iter()
-            .all(|problem| matches!(problem, Problem::UnusedDef(_, _))));
+            .all(|problem| matches!(problem, Problem::UnusedDef(_, _, _))));
     }
 
     #[test]
@@ -568,7 +568,51 @@ mod test_can {
         assert_eq!(problems.len(), 2);
         assert!(problems
             .iter()
-            .all(|problem| matches!(problem, Problem::UnusedDef(_, _))));
+            .all(|problem| matches!(problem, Problem::UnusedDef(_, _, _))));
+    }
+
+    #[test]
+    fn unused_def_removal_region_spans_whole_def() {
+        let src = indoc!(
+            r#"
+                x : Str
+                x = "unused"
+
+                y = 1
+
+                y
+            "#
+        );
+        let arena = Bump::new();
+        let CanExprOut { problems, .. } = can_expr_with(&arena, test_home(), src);
+
+        match problems.as_slice() {
+            [Problem::UnusedDef(_, region, removal_region)] => {
+                assert!(removal_region.contains(region));
+                assert_ne!(removal_region, region);
+            }
+            _ => panic!("expected a single UnusedDef, got {:?}", problems),
+        }
+    }
+
+    #[test]
+    fn unused_destructured_def_removal_region_is_binding() {
+        let src = indoc!(
+            r#"
+                { a, b } = { a: 1, b: 2 }
+
+                a
+            "#
+        );
+        let arena = Bump::new();
+        let CanExprOut { problems, .. } = can_expr_with(&arena, test_home(), src);
+
+        match problems.as_slice() {
+            [Problem::UnusedDef(_, region, removal_region)] => {
+                assert_eq!(removal_region, region);
+            }
+            _ => panic!("expected a single UnusedDef, got {:?}", problems),
+        }
     }
     // LOCALS
 
@@ -738,7 +782,7 @@ mod test_can {
         assert_eq!(problems, Vec::new());
         assert!(problems
             .iter()
-            .all(|problem| matches!(problem, Problem::UnusedDef(_, _))));
+            .all(|problem| matches!(problem, Problem::UnusedDef(_, _, _))));
 
         let actual = loc_expr.value;
 
@@ -785,7 +829,7 @@ mod test_can {
     //     // There should be two UnusedDef problems: one for h, and one for p
     //     assert_eq!(problems.len(), 2);
     //     assert!(problems.iter().all(|problem| match problem {
-    //         Problem::UnusedDef(_, _) => true,
+    //         Problem::UnusedDef(_, _, _) => true,
     //         _ => false,
     //     }));
 
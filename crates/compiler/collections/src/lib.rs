@@ -1,17 +1,37 @@
 //! Domain-specific collections created for the needs of the compiler.
+//!
+//! Some of these are meant for backends in particular, whose output should be the same every
+//! time they compile the same program:
+//!
+//! - [SmallVec] keeps a few elements inline before moving them into the arena, for the many
+//!   short lists a backend keeps per procedure, like the registers it uses.
+//! - [OrderedMap] is a hash map that iterates in a deterministic order, for maps whose iteration
+//!   order ends up in the generated code.
+//!
+//! Hashes that are saved between runs of the compiler, like cache keys, should be computed with
+//! a [StableHasher], whose output doesn't depend on the Rust release or the platform.
+//!
+//! Iterating over a [MutMap] or [MutSet] gives an order that can change from one compilation to
+//! the next, so backends shouldn't let it affect their output.
 #![warn(clippy::dbg_macro)]
 // See github.com/roc-lang/roc/issues/800 for discussion of the large_enum_variant check.
 #![allow(clippy::large_enum_variant)]
 
 pub mod all;
+mod ordered_map;
 mod reference_matrix;
 mod small_string_interner;
+mod small_vec;
 pub mod soa;
+mod stable_hash;
 mod vec_map;
 mod vec_set;
 
 pub use all::{default_hasher, BumpMap, ImEntry, ImMap, ImSet, MutMap, MutSet, SendMap};
+pub use ordered_map::OrderedMap;
 pub use reference_matrix::{ReferenceMatrix, Sccs, TopologicalSort};
 pub use small_string_interner::SmallStringInterner;
+pub use small_vec::SmallVec;
+pub use stable_hash::StableHasher;
 pub use vec_map::VecMap;
 pub use vec_set::VecSet;
@@ -0,0 +1,198 @@
+use std::hash::Hash;
+use std::iter::FromIterator;
+
+use crate::all::MutMap;
+
+/// A hash map that iterates in a deterministic order: the order its keys were first inserted,
+/// until something is removed. Removing works like [Vec::swap_remove], moving the last entry into
+/// the removed one's place, so it stays O(1) and the order stays the same from run to run.
+///
+/// Iterating over a [MutMap] gives an order that depends on the hashes of its keys. Symbols
+/// contain module ids, which depend on the order modules happened to finish loading, and
+/// pointers change from run to run, so code generated by iterating over one can change between
+/// two builds of the same program. Use this instead wherever the order of iteration ends up in
+/// the output.
+#[derive(Clone, Debug)]
+pub struct OrderedMap<K, V> {
+    entries: Vec<(K, V)>,
+    /// The index of each key's entry
+    indices: MutMap<K, usize>,
+}
+
+impl<K, V> Default for OrderedMap<K, V> {
+    fn default() -> Self {
+        Self {
+            entries: Vec::new(),
+            indices: MutMap::default(),
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> OrderedMap<K, V> {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: Vec::with_capacity(capacity),
+            indices: MutMap::with_capacity_and_hasher(capacity, Default::default()),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Inserts the value, returning the one it replaced if the key was already present. A key
+    /// that was already present keeps its place in the order.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.indices.get(&key).copied() {
+            Some(index) => Some(std::mem::replace(&mut self.entries[index].1, value)),
+            None => {
+                self.indices.insert(key.clone(), self.entries.len());
+                self.entries.push((key, value));
+
+                None
+            }
+        }
+    }
+
+    /// The value for the key, inserting the one `default` makes if the key isn't present yet
+    pub fn get_or_insert_with(&mut self, key: K, default: impl FnOnce() -> V) -> &mut V {
+        let index = match self.indices.get(&key).copied() {
+            Some(index) => index,
+            None => {
+                let index = self.entries.len();
+
+                self.indices.insert(key.clone(), index);
+                self.entries.push((key, default()));
+
+                index
+            }
+        };
+
+        &mut self.entries[index].1
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        let index = self.indices.get(key)?;
+
+        Some(&self.entries[*index].1)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        let index = self.indices.get(key)?;
+
+        Some(&mut self.entries[*index].1)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.indices.contains_key(key)
+    }
+
+    /// Removes the key's entry, moving the last entry into its place.
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let index = self.indices.remove(key)?;
+        let (_, value) = self.entries.swap_remove(index);
+
+        if let Some((moved_key, _)) = self.entries.get(index) {
+            self.indices.insert(moved_key.clone(), index);
+        }
+
+        Some(value)
+    }
+
+    /// Removes all entries, without affecting the allocated capacity.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.indices.clear();
+    }
+
+    pub fn iter(&self) -> impl ExactSizeIterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    pub fn iter_mut(&mut self) -> impl ExactSizeIterator<Item = (&K, &mut V)> {
+        self.entries.iter_mut().map(|(key, value)| (&*key, value))
+    }
+
+    pub fn keys(&self) -> impl ExactSizeIterator<Item = &K> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl ExactSizeIterator<Item = &V> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: PartialEq> PartialEq for OrderedMap<K, V> {
+    /// Maps with the same entries in a different order are different, since they'd iterate
+    /// differently.
+    fn eq(&self, other: &Self) -> bool {
+        self.entries == other.entries
+    }
+}
+
+impl<K: Hash + Eq + Clone, V: Eq> Eq for OrderedMap<K, V> {}
+
+impl<K: Hash + Eq + Clone, V> Extend<(K, V)> for OrderedMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V> FromIterator<(K, V)> for OrderedMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = Self::default();
+
+        map.extend(iter);
+
+        map
+    }
+}
+
+impl<K, V> IntoIterator for OrderedMap<K, V> {
+    type Item = (K, V);
+    type IntoIter = std::vec::IntoIter<(K, V)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod test_ordered_map {
+    use super::OrderedMap;
+
+    #[test]
+    fn iterates_in_insertion_order() {
+        let mut map = OrderedMap::default();
+
+        for key in [30, 10, 20] {
+            map.insert(key, key * 2);
+        }
+
+        assert_eq!(map.insert(10, 0), Some(20));
+        assert_eq!(map.keys().copied().collect::<Vec<_>>(), vec![30, 10, 20]);
+        assert_eq!(map.get(&10), Some(&0));
+    }
+
+    #[test]
+    fn remove_moves_the_last_entry() {
+        let mut map: OrderedMap<_, _> = [(1, 'a'), (2, 'b'), (3, 'c'), (4, 'd')]
+            .into_iter()
+            .collect();
+
+        assert_eq!(map.remove(&2), Some('b'));
+        assert_eq!(map.remove(&2), None);
+        assert_eq!(map.keys().copied().collect::<Vec<_>>(), vec![1, 4, 3]);
+
+        // The moved entry can still be found
+        assert_eq!(map.get(&4), Some(&'d'));
+        assert_eq!(*map.get_or_insert_with(5, || 'e'), 'e');
+        assert_eq!(map.len(), 4);
+    }
+}
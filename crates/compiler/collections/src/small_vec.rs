@@ -0,0 +1,229 @@
+use std::fmt::Debug;
+use std::mem::MaybeUninit;
+use std::ops::{Deref, DerefMut};
+
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+
+/// A vector that keeps its first `N` elements inline, and moves them all into the arena once it
+/// grows past that.
+///
+/// Backends keep lots of short lists that are almost always tiny, like the callee-saved
+/// registers a procedure uses, or the arguments of a call. Those shouldn't each cost an arena
+/// allocation, but a list that is sometimes long shouldn't need a size picked up front either.
+///
+/// Only `Copy` elements are supported, so nothing ever needs dropping.
+pub struct SmallVec<'a, T: Copy, const N: usize> {
+    arena: &'a Bump,
+    /// How many of `inline` are initialized, until the elements spill into the arena
+    len: usize,
+    inline: [MaybeUninit<T>; N],
+    spilled: Option<Vec<'a, T>>,
+}
+
+impl<'a, T: Copy, const N: usize> SmallVec<'a, T, N> {
+    pub fn new_in(arena: &'a Bump) -> Self {
+        Self {
+            arena,
+            len: 0,
+            inline: [MaybeUninit::uninit(); N],
+            spilled: None,
+        }
+    }
+
+    pub fn from_slice_in(slice: &[T], arena: &'a Bump) -> Self {
+        let mut vec = Self::new_in(arena);
+
+        vec.extend_from_slice(slice);
+
+        vec
+    }
+
+    /// Whether the elements have moved into the arena
+    pub fn is_spilled(&self) -> bool {
+        self.spilled.is_some()
+    }
+
+    pub fn push(&mut self, value: T) {
+        if let Some(spilled) = &mut self.spilled {
+            spilled.push(value);
+        } else if self.len < N {
+            self.inline[self.len] = MaybeUninit::new(value);
+            self.len += 1;
+        } else {
+            let mut spilled = Vec::with_capacity_in(2 * N.max(1), self.arena);
+
+            spilled.extend_from_slice(self.as_slice());
+            spilled.push(value);
+
+            self.spilled = Some(spilled);
+            self.len = 0;
+        }
+    }
+
+    /// Push the value unless it's already in the vector, for using a handful of elements as a
+    /// set: a linear scan over a few elements is cheaper than hashing them, and it keeps them in
+    /// the order they were first pushed.
+    ///
+    /// Returns whether the value was pushed.
+    pub fn push_unique(&mut self, value: T) -> bool
+    where
+        T: PartialEq,
+    {
+        if self.contains(&value) {
+            false
+        } else {
+            self.push(value);
+
+            true
+        }
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        if let Some(spilled) = &mut self.spilled {
+            return spilled.pop();
+        }
+
+        let last = *self.as_slice().last()?;
+
+        self.len -= 1;
+
+        Some(last)
+    }
+
+    pub fn extend_from_slice(&mut self, slice: &[T]) {
+        for value in slice {
+            self.push(*value);
+        }
+    }
+
+    /// Removes all elements. Once spilled, the elements stay in the arena.
+    pub fn clear(&mut self) {
+        match &mut self.spilled {
+            Some(spilled) => spilled.clear(),
+            None => self.len = 0,
+        }
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        match &self.spilled {
+            Some(spilled) => spilled,
+            None => self.as_slice_with_len(self.len),
+        }
+    }
+
+    pub fn as_mut_slice(&mut self) -> &mut [T] {
+        match &mut self.spilled {
+            Some(spilled) => spilled,
+            None => {
+                let initialized = &mut self.inline[..self.len];
+
+                // SAFETY: the first `len` elements of `inline` are initialized, and
+                // MaybeUninit<T> has the same layout as T.
+                unsafe { &mut *(initialized as *mut [MaybeUninit<T>] as *mut [T]) }
+            }
+        }
+    }
+
+    fn as_slice_with_len(&self, len: usize) -> &[T] {
+        let initialized = &self.inline[..len];
+
+        // SAFETY: callers only ask for elements that were initialized, and MaybeUninit<T> has the
+        // same layout as T.
+        unsafe { &*(initialized as *const [MaybeUninit<T>] as *const [T]) }
+    }
+}
+
+impl<'a, T: Copy, const N: usize> Deref for SmallVec<'a, T, N> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<'a, T: Copy, const N: usize> DerefMut for SmallVec<'a, T, N> {
+    fn deref_mut(&mut self) -> &mut [T] {
+        self.as_mut_slice()
+    }
+}
+
+impl<'a, T: Copy, const N: usize> Clone for SmallVec<'a, T, N> {
+    fn clone(&self) -> Self {
+        Self {
+            arena: self.arena,
+            len: self.len,
+            inline: self.inline,
+            spilled: self.spilled.clone(),
+        }
+    }
+}
+
+impl<'a, T: Copy + Debug, const N: usize> Debug for SmallVec<'a, T, N> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, T: Copy + PartialEq, const N: usize> PartialEq for SmallVec<'a, T, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_slice() == other.as_slice()
+    }
+}
+
+impl<'a, T: Copy + Eq, const N: usize> Eq for SmallVec<'a, T, N> {}
+
+impl<'a, T: Copy, const N: usize> Extend<T> for SmallVec<'a, T, N> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl<'a, 'v, T: Copy, const N: usize> IntoIterator for &'v SmallVec<'a, T, N> {
+    type Item = &'v T;
+    type IntoIter = std::slice::Iter<'v, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.as_slice().iter()
+    }
+}
+
+#[cfg(test)]
+mod test_small_vec {
+    use super::SmallVec;
+    use bumpalo::Bump;
+
+    #[test]
+    fn spills_into_the_arena() {
+        let arena = Bump::new();
+        let mut vec: SmallVec<u32, 2> = SmallVec::new_in(&arena);
+
+        vec.push(1);
+        vec.push(2);
+        assert!(!vec.is_spilled());
+
+        vec.push(3);
+        assert!(vec.is_spilled());
+        assert_eq!(vec.as_slice(), &[1, 2, 3]);
+
+        assert_eq!(vec.pop(), Some(3));
+        vec.clear();
+        assert!(vec.is_empty());
+    }
+
+    #[test]
+    fn push_unique_keeps_first_push_order() {
+        let arena = Bump::new();
+        let mut vec: SmallVec<char, 4> = SmallVec::new_in(&arena);
+
+        for c in "abracadabra".chars() {
+            vec.push_unique(c);
+        }
+
+        assert_eq!(vec.as_slice(), &['a', 'b', 'r', 'c', 'd']);
+        assert_eq!(vec.pop(), Some('d'));
+        assert_eq!(vec.pop(), Some('c'));
+    }
+}
@@ -0,0 +1,143 @@
+use std::hash::Hasher;
+
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// A hasher for hashes that outlive the compiler that computed them, like cache keys and the
+/// ABI hashes written next to hosts: it's 64-bit FNV-1a, which is fully specified, so the same
+/// input hashes the same no matter which Rust release built the compiler, or which platform it
+/// runs on.
+///
+/// Only what's written with the methods of [Hasher] is stable. The [Hash](std::hash::Hash)
+/// impls of the standard library's types are free to change how they write themselves, so write
+/// strings with [StableHasher::write_str] rather than hashing them.
+#[derive(Debug, Clone, Copy)]
+pub struct StableHasher {
+    state: u64,
+}
+
+impl Default for StableHasher {
+    fn default() -> Self {
+        Self {
+            state: FNV_OFFSET_BASIS,
+        }
+    }
+}
+
+impl StableHasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes the length before the bytes, so `"ab", "c"` and `"a", "bc"` hash differently.
+    pub fn write_str(&mut self, string: &str) {
+        self.write_u64(string.len() as u64);
+        self.write(string.as_bytes());
+    }
+
+    /// Writes what identifies the running compiler: its version, and when its executable was
+    /// built, since every build from source has the same version. Caches keyed by this are
+    /// invalidated whenever the compiler is rebuilt.
+    pub fn write_compiler(&mut self) {
+        self.write_str(include_str!("../../../../version.txt"));
+
+        let modified = std::env::current_exe()
+            .and_then(|exe| exe.metadata())
+            .and_then(|metadata| metadata.modified())
+            .ok()
+            .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok());
+
+        if let Some(modified) = modified {
+            self.write_u64(modified.as_secs());
+            self.write_u32(modified.subsec_nanos());
+        }
+    }
+}
+
+impl Hasher for StableHasher {
+    fn finish(&self) -> u64 {
+        self.state
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.state ^= *byte as u64;
+            self.state = self.state.wrapping_mul(FNV_PRIME);
+        }
+    }
+
+    // The default impls write integers in native byte order, which depends on the platform.
+
+    fn write_u16(&mut self, i: u16) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_u128(&mut self, i: u128) {
+        self.write(&i.to_le_bytes())
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_i16(&mut self, i: i16) {
+        self.write_u16(i as u16)
+    }
+
+    fn write_i32(&mut self, i: i32) {
+        self.write_u32(i as u32)
+    }
+
+    fn write_i64(&mut self, i: i64) {
+        self.write_u64(i as u64)
+    }
+
+    fn write_i128(&mut self, i: i128) {
+        self.write_u128(i as u128)
+    }
+
+    fn write_isize(&mut self, i: isize) {
+        self.write_u64(i as u64)
+    }
+}
+
+#[cfg(test)]
+mod test_stable_hash {
+    use super::StableHasher;
+    use std::hash::Hasher;
+
+    #[test]
+    fn matches_fnv_1a() {
+        // Known values of 64-bit FNV-1a, so any change to the algorithm shows up here.
+        assert_eq!(StableHasher::new().finish(), 0xcbf2_9ce4_8422_2325);
+
+        let mut hasher = StableHasher::new();
+        hasher.write(b"a");
+        assert_eq!(hasher.finish(), 0xaf63_dc4c_8601_ec8c);
+
+        let mut hasher = StableHasher::new();
+        hasher.write(b"foobar");
+        assert_eq!(hasher.finish(), 0x8594_4171_f739_67e8);
+    }
+
+    #[test]
+    fn strings_are_length_prefixed() {
+        let mut a = StableHasher::new();
+        a.write_str("ab");
+        a.write_str("c");
+
+        let mut b = StableHasher::new();
+        b.write_str("a");
+        b.write_str("bc");
+
+        assert_ne!(a.finish(), b.finish());
+    }
+}
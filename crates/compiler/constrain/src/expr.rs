@@ -1601,12 +1601,14 @@ pub fn constrain_expr(
         }
         TypedHole(var) => {
             // store the expected type for this position
-            constraints.equal_types_var(
+            let store_expected = constraints.equal_types_var(
                 *var,
                 expected,
                 Category::Storage(std::file!(), std::line!()),
                 region,
-            )
+            );
+
+            constraints.and_constraint([store_expected, Constraint::TypedHole(*var, region)])
         }
         RuntimeError(_) => {
             // Runtime Errors are always going to crash, so they don't introduce any new
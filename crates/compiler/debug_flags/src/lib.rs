@@ -119,7 +119,8 @@ flags! {
 
     // ===Mono===
 
-    /// Type-checks the mono IR after specialization.
+    /// Type-checks the mono IR after specialization and after every pass over it, so that a
+    /// malformed procedure is reported before it reaches the backends.
     ROC_CHECK_MONO_IR
 
     /// Writes a pretty-printed mono IR to stderr after function specialization.
@@ -130,9 +131,16 @@ flags! {
     ROC_PRINT_IR_AFTER_RESET_REUSE
 
     /// Writes a pretty-printed mono IR to stderr after insertion of refcount
-    /// instructions.
+    /// instructions, and removal of the ones that have no effect.
     ROC_PRINT_IR_AFTER_REFCOUNT
 
+    /// Prints where reset/reuse lets a tag or box reuse the memory of a value that is no longer
+    /// used, and why other values' memory could not be reused.
+    ROC_PRINT_IN_PLACE_UPDATES
+
+    /// Prints which calls are inlined, and why the other calls to known procedures are not.
+    ROC_PRINT_INLINING
+
     /// Prints debug information during the alias analysis pass.
     ROC_DEBUG_ALIAS_ANALYSIS
 
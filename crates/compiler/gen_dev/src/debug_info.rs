@@ -0,0 +1,387 @@
+//! Emits just enough DWARF for debuggers to map the machine code of each procedure back to the
+//! Roc source that defines it: a `.debug_line` table, and a `.debug_info` compile unit that points
+//! at the table and covers the addresses of the procedures.
+//!
+//! Mono IR does not keep the regions of individual expressions, so the code of every procedure is
+//! mapped to the line of its definition.
+//! The formats are described in the DWARF 4 standard: https://dwarfstd.org/doc/DWARF4.pdf
+
+use crate::Env;
+use bumpalo::collections::Vec;
+use object::write::{self, Object, SectionId, StandardSegment, SymbolId};
+use object::{BinaryFormat, RelocationEncoding, RelocationKind, SectionKind};
+use roc_collections::all::MutMap;
+use roc_module::symbol::{ModuleId, Symbol};
+use roc_region::all::{LineInfo, Region};
+use std::path::PathBuf;
+
+const DWARF_VERSION: u16 = 4;
+const ADDRESS_SIZE: u8 = 8;
+const PRODUCER: &str = "roc dev backend";
+
+const DW_TAG_COMPILE_UNIT: u8 = 0x11;
+const DW_CHILDREN_NO: u8 = 0x00;
+const DW_AT_NAME: u8 = 0x03;
+const DW_AT_STMT_LIST: u8 = 0x10;
+const DW_AT_LOW_PC: u8 = 0x11;
+const DW_AT_COMP_DIR: u8 = 0x1b;
+const DW_AT_PRODUCER: u8 = 0x25;
+const DW_AT_RANGES: u8 = 0x55;
+const DW_FORM_ADDR: u8 = 0x01;
+const DW_FORM_STRING: u8 = 0x08;
+const DW_FORM_SEC_OFFSET: u8 = 0x17;
+
+const DW_LNS_COPY: u8 = 0x01;
+const DW_LNS_ADVANCE_PC: u8 = 0x02;
+const DW_LNS_ADVANCE_LINE: u8 = 0x03;
+const DW_LNS_SET_FILE: u8 = 0x04;
+const DW_LNE_END_SEQUENCE: u8 = 0x01;
+const DW_LNE_SET_ADDRESS: u8 = 0x02;
+
+// The line program only uses standard opcodes, so these just need to be valid.
+const LINE_BASE: i8 = -5;
+const LINE_RANGE: u8 = 14;
+const OPCODE_BASE: u8 = 13;
+const STANDARD_OPCODE_LENGTHS: [u8; OPCODE_BASE as usize - 1] =
+    [0, 1, 1, 1, 1, 0, 0, 0, 1, 0, 0, 1];
+
+struct ProcLine {
+    proc_id: SymbolId,
+    size: u64,
+    file: u64,
+    line: u64,
+}
+
+/// DebugInfo collects the procedures of an object file as they are built, then writes the DWARF
+/// sections describing them.
+pub struct DebugInfo<'a> {
+    module_id: ModuleId,
+    sources: &'a MutMap<ModuleId, (PathBuf, Box<str>)>,
+    def_regions: &'a MutMap<Symbol, Region>,
+    // The file number and line offsets of every module with procedures in the object.
+    files: MutMap<ModuleId, (u64, LineInfo)>,
+    // The paths of those modules, in file number order.
+    file_names: std::vec::Vec<String>,
+    procs: std::vec::Vec<ProcLine>,
+}
+
+impl<'a> DebugInfo<'a> {
+    /// Returns None for formats whose debuggers don't read DWARF, like COFF.
+    pub fn new(output: &Object, env: &Env<'a>) -> Option<Self> {
+        match output.format() {
+            BinaryFormat::Elf | BinaryFormat::MachO => Some(DebugInfo {
+                module_id: env.module_id,
+                sources: env.sources,
+                def_regions: env.def_regions,
+                files: MutMap::default(),
+                file_names: std::vec::Vec::new(),
+                procs: std::vec::Vec::new(),
+            }),
+            _ => None,
+        }
+    }
+
+    /// Records that the code for the procedure of `symbol` is the `size` bytes at `proc_id`.
+    /// Procedures without a definition in the source, like generated helpers, are skipped.
+    pub fn add_proc(&mut self, symbol: Symbol, proc_id: SymbolId, size: u64) {
+        let region = match self.def_regions.get(&symbol) {
+            Some(region) => *region,
+            None => return,
+        };
+        let module_id = symbol.module_id();
+        let (path, src) = match self.sources.get(&module_id) {
+            Some(source) => source,
+            None => return,
+        };
+
+        // DWARF 4 file numbers start at 1.
+        let next_file = self.files.len() as u64 + 1;
+        let file_names = &mut self.file_names;
+        let (file, line_info) = self.files.entry(module_id).or_insert_with(|| {
+            file_names.push(path.to_string_lossy().into_owned());
+            (next_file, LineInfo::new(src))
+        });
+        let line = line_info.convert_pos(region.start()).line as u64 + 1;
+
+        self.procs.push(ProcLine {
+            proc_id,
+            size,
+            file: *file,
+            line,
+        });
+    }
+
+    /// Adds the debug sections to the object.
+    /// Their relocations are added to `relocations` to be emitted with the ones for the code.
+    pub fn write(
+        self,
+        output: &mut Object,
+        relocations: &mut Vec<'_, (SectionId, write::Relocation)>,
+    ) {
+        if self.procs.is_empty() {
+            return;
+        }
+
+        let abbrev = add_debug_section(output, "debug_abbrev");
+        output.append_section_data(abbrev, &self.abbrev_data(), 1);
+
+        let line = add_debug_section(output, "debug_line");
+        let (line_data, addresses) = self.line_data();
+        output.append_section_data(line, &line_data, 1);
+        for (offset, proc_id) in addresses {
+            relocations.push((line, address_relocation(offset, proc_id, 0)));
+        }
+
+        let ranges = add_debug_section(output, "debug_ranges");
+        let mut ranges_data = std::vec::Vec::new();
+        for proc in self.procs.iter() {
+            let offset = ranges_data.len() as u64;
+            relocations.push((ranges, address_relocation(offset, proc.proc_id, 0)));
+            relocations.push((
+                ranges,
+                address_relocation(offset + 8, proc.proc_id, proc.size as i64),
+            ));
+            ranges_data.extend([0; 16]);
+        }
+        // The list ends with an entry of two zeros.
+        ranges_data.extend([0; 16]);
+        output.append_section_data(ranges, &ranges_data, 1);
+
+        let info = add_debug_section(output, "debug_info");
+        let (info_data, section_offsets) = self.info_data();
+        output.append_section_data(info, &info_data, 1);
+        for (offset, target) in [
+            (section_offsets.abbrev, abbrev),
+            (section_offsets.stmt_list, line),
+            (section_offsets.ranges, ranges),
+        ] {
+            // Mach-O debug sections refer to each other with plain offsets, which are all 0 here.
+            if output.format() == BinaryFormat::Elf {
+                let symbol = output.section_symbol(target);
+                relocations.push((
+                    info,
+                    write::Relocation {
+                        offset,
+                        size: 32,
+                        kind: RelocationKind::Absolute,
+                        encoding: RelocationEncoding::Generic,
+                        symbol,
+                        addend: 0,
+                    },
+                ));
+            }
+        }
+    }
+
+    fn abbrev_data(&self) -> std::vec::Vec<u8> {
+        vec![
+            1, // Abbreviation code.
+            DW_TAG_COMPILE_UNIT,
+            DW_CHILDREN_NO,
+            DW_AT_PRODUCER,
+            DW_FORM_STRING,
+            DW_AT_NAME,
+            DW_FORM_STRING,
+            DW_AT_COMP_DIR,
+            DW_FORM_STRING,
+            DW_AT_STMT_LIST,
+            DW_FORM_SEC_OFFSET,
+            DW_AT_LOW_PC,
+            DW_FORM_ADDR,
+            DW_AT_RANGES,
+            DW_FORM_SEC_OFFSET,
+            0, // End of attributes.
+            0,
+            0, // End of abbreviations.
+        ]
+    }
+
+    /// Builds the line number program, with one sequence per procedure.
+    /// Also returns where the address of each procedure has to be written.
+    fn line_data(&self) -> (std::vec::Vec<u8>, std::vec::Vec<(u64, SymbolId)>) {
+        let mut data = std::vec::Vec::new();
+        let mut addresses = std::vec::Vec::new();
+
+        // The unit length and header length are filled in at the end.
+        data.extend(0u32.to_le_bytes());
+        data.extend(DWARF_VERSION.to_le_bytes());
+        data.extend(0u32.to_le_bytes());
+        let header_start = data.len();
+        data.extend([
+            1, // Minimum instruction length.
+            1, // Maximum operations per instruction.
+            1, // Rows are statements by default.
+            LINE_BASE as u8,
+            LINE_RANGE,
+            OPCODE_BASE,
+        ]);
+        data.extend(STANDARD_OPCODE_LENGTHS);
+        data.push(0); // No include directories, so paths are relative to the compilation directory.
+        for file_name in self.file_names.iter() {
+            data.extend(file_name.as_bytes());
+            // The terminating nul, then directory 0 and an unknown modification time and length.
+            data.extend([0, 0, 0, 0]);
+        }
+        data.push(0);
+        let header_length = (data.len() - header_start) as u32;
+        data[6..10].copy_from_slice(&header_length.to_le_bytes());
+
+        for proc in self.procs.iter() {
+            data.extend([0, 1 + ADDRESS_SIZE, DW_LNE_SET_ADDRESS]);
+            addresses.push((data.len() as u64, proc.proc_id));
+            data.extend(0u64.to_le_bytes());
+            data.push(DW_LNS_SET_FILE);
+            write_uleb128(&mut data, proc.file);
+            // Every sequence starts on line 1.
+            data.push(DW_LNS_ADVANCE_LINE);
+            write_sleb128(&mut data, proc.line as i64 - 1);
+            data.push(DW_LNS_COPY);
+            data.push(DW_LNS_ADVANCE_PC);
+            write_uleb128(&mut data, proc.size);
+            data.extend([0, 1, DW_LNE_END_SEQUENCE]);
+        }
+
+        let unit_length = (data.len() - 4) as u32;
+        data[0..4].copy_from_slice(&unit_length.to_le_bytes());
+
+        (data, addresses)
+    }
+
+    /// Builds the compile unit. Also returns where it refers to other debug sections.
+    fn info_data(&self) -> (std::vec::Vec<u8>, InfoSectionOffsets) {
+        let mut data = std::vec::Vec::new();
+
+        // The unit length is filled in at the end.
+        data.extend(0u32.to_le_bytes());
+        data.extend(DWARF_VERSION.to_le_bytes());
+        let abbrev = data.len() as u64;
+        data.extend(0u32.to_le_bytes());
+        data.push(ADDRESS_SIZE);
+
+        data.push(1); // Abbreviation code.
+        write_string(&mut data, PRODUCER);
+        let name = match self.sources.get(&self.module_id) {
+            Some((path, _)) => path.to_string_lossy().into_owned(),
+            None => self.file_names[0].clone(),
+        };
+        write_string(&mut data, &name);
+        let comp_dir = std::env::current_dir().unwrap_or_default();
+        write_string(&mut data, &comp_dir.to_string_lossy());
+        let stmt_list = data.len() as u64;
+        data.extend(0u32.to_le_bytes());
+        // The ranges are absolute addresses, so the base address is 0.
+        data.extend(0u64.to_le_bytes());
+        let ranges = data.len() as u64;
+        data.extend(0u32.to_le_bytes());
+
+        let unit_length = (data.len() - 4) as u32;
+        data[0..4].copy_from_slice(&unit_length.to_le_bytes());
+
+        (
+            data,
+            InfoSectionOffsets {
+                abbrev,
+                stmt_list,
+                ranges,
+            },
+        )
+    }
+}
+
+/// Where the compile unit refers to other debug sections.
+struct InfoSectionOffsets {
+    abbrev: u64,
+    stmt_list: u64,
+    ranges: u64,
+}
+
+fn add_debug_section(output: &mut Object, name: &str) -> SectionId {
+    let section_name = match output.format() {
+        BinaryFormat::MachO => format!("__{}", name),
+        _ => format!(".{}", name),
+    };
+    output.add_section(
+        output.segment_name(StandardSegment::Debug).to_vec(),
+        section_name.into_bytes(),
+        SectionKind::Debug,
+    )
+}
+
+fn address_relocation(offset: u64, symbol: SymbolId, addend: i64) -> write::Relocation {
+    write::Relocation {
+        offset,
+        size: ADDRESS_SIZE * 8,
+        kind: RelocationKind::Absolute,
+        encoding: RelocationEncoding::Generic,
+        symbol,
+        addend,
+    }
+}
+
+fn write_string(buf: &mut std::vec::Vec<u8>, string: &str) {
+    buf.extend(string.as_bytes());
+    buf.push(0);
+}
+
+fn write_uleb128(buf: &mut std::vec::Vec<u8>, mut value: u64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        if value == 0 {
+            buf.push(byte);
+            return;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+fn write_sleb128(buf: &mut std::vec::Vec<u8>, mut value: i64) {
+    loop {
+        let byte = (value & 0x7f) as u8;
+        value >>= 7;
+        let sign_bit_clear = byte & 0x40 == 0;
+        if (value == 0 && sign_bit_clear) || (value == -1 && !sign_bit_clear) {
+            buf.push(byte);
+            return;
+        }
+        buf.push(byte | 0x80);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uleb128() {
+        for (value, expected) in [
+            (0, vec![0x00]),
+            (2, vec![0x02]),
+            (127, vec![0x7f]),
+            (128, vec![0x80, 0x01]),
+            (624485, vec![0xe5, 0x8e, 0x26]),
+        ] {
+            let mut buf = std::vec::Vec::new();
+            write_uleb128(&mut buf, value);
+            assert_eq!(buf, expected);
+        }
+    }
+
+    #[test]
+    fn sleb128() {
+        for (value, expected) in [
+            (0, vec![0x00]),
+            (2, vec![0x02]),
+            (-2, vec![0x7e]),
+            (63, vec![0x3f]),
+            (64, vec![0xc0, 0x00]),
+            (-64, vec![0x40]),
+            (-65, vec![0xbf, 0x7f]),
+            (-123456, vec![0xc0, 0xbb, 0x78]),
+        ] {
+            let mut buf = std::vec::Vec::new();
+            write_sleb128(&mut buf, value);
+            assert_eq!(buf, expected);
+        }
+    }
+}
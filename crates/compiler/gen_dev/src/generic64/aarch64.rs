@@ -1,10 +1,15 @@
 use crate::generic64::{storage::StorageManager, Assembler, CallConv, RegTrait};
-use crate::Relocation;
+use crate::{
+    single_register_floats, single_register_int_builtins, single_register_integers,
+    single_register_layouts, Relocation,
+};
 use bumpalo::collections::Vec;
 use packed_struct::prelude::*;
+use roc_builtins::bitcode::{FloatWidth, IntWidth};
 use roc_error_macros::internal_error;
 use roc_module::symbol::Symbol;
-use roc_mono::layout::Layout;
+use roc_mono::layout::{Builtin, Layout, STLayoutInterner};
+use roc_target::TargetInfo;
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
 #[allow(dead_code)]
@@ -101,7 +106,40 @@ impl AArch64GeneralReg {
 
 #[derive(Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Debug)]
 #[allow(dead_code)]
-pub enum AArch64FloatReg {}
+pub enum AArch64FloatReg {
+    V0 = 0,
+    V1 = 1,
+    V2 = 2,
+    V3 = 3,
+    V4 = 4,
+    V5 = 5,
+    V6 = 6,
+    V7 = 7,
+    V8 = 8,
+    V9 = 9,
+    V10 = 10,
+    V11 = 11,
+    V12 = 12,
+    V13 = 13,
+    V14 = 14,
+    V15 = 15,
+    V16 = 16,
+    V17 = 17,
+    V18 = 18,
+    V19 = 19,
+    V20 = 20,
+    V21 = 21,
+    V22 = 22,
+    V23 = 23,
+    V24 = 24,
+    V25 = 25,
+    V26 = 26,
+    V27 = 27,
+    V28 = 28,
+    V29 = 29,
+    V30 = 30,
+    V31 = 31,
+}
 impl RegTrait for AArch64FloatReg {
     fn value(&self) -> u8 {
         *self as u8
@@ -109,7 +147,51 @@ impl RegTrait for AArch64FloatReg {
 }
 impl std::fmt::Display for AArch64FloatReg {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "TODO",)
+        write!(
+            f,
+            "{}",
+            match self {
+                AArch64FloatReg::V0 => "v0",
+                AArch64FloatReg::V1 => "v1",
+                AArch64FloatReg::V2 => "v2",
+                AArch64FloatReg::V3 => "v3",
+                AArch64FloatReg::V4 => "v4",
+                AArch64FloatReg::V5 => "v5",
+                AArch64FloatReg::V6 => "v6",
+                AArch64FloatReg::V7 => "v7",
+                AArch64FloatReg::V8 => "v8",
+                AArch64FloatReg::V9 => "v9",
+                AArch64FloatReg::V10 => "v10",
+                AArch64FloatReg::V11 => "v11",
+                AArch64FloatReg::V12 => "v12",
+                AArch64FloatReg::V13 => "v13",
+                AArch64FloatReg::V14 => "v14",
+                AArch64FloatReg::V15 => "v15",
+                AArch64FloatReg::V16 => "v16",
+                AArch64FloatReg::V17 => "v17",
+                AArch64FloatReg::V18 => "v18",
+                AArch64FloatReg::V19 => "v19",
+                AArch64FloatReg::V20 => "v20",
+                AArch64FloatReg::V21 => "v21",
+                AArch64FloatReg::V22 => "v22",
+                AArch64FloatReg::V23 => "v23",
+                AArch64FloatReg::V24 => "v24",
+                AArch64FloatReg::V25 => "v25",
+                AArch64FloatReg::V26 => "v26",
+                AArch64FloatReg::V27 => "v27",
+                AArch64FloatReg::V28 => "v28",
+                AArch64FloatReg::V29 => "v29",
+                AArch64FloatReg::V30 => "v30",
+                AArch64FloatReg::V31 => "v31",
+            }
+        )
+    }
+}
+
+impl AArch64FloatReg {
+    #[inline(always)]
+    fn id(&self) -> u8 {
+        *self as u8
     }
 }
 
@@ -123,6 +205,8 @@ pub struct AArch64Call {}
 
 const STACK_ALIGNMENT: u8 = 16;
 
+const TARGET_INFO: TargetInfo = TargetInfo::default_aarch64();
+
 impl CallConv<AArch64GeneralReg, AArch64FloatReg, AArch64Assembler> for AArch64Call {
     const BASE_PTR_REG: AArch64GeneralReg = AArch64GeneralReg::FP;
     const STACK_PTR_REG: AArch64GeneralReg = AArch64GeneralReg::ZRSP;
@@ -147,6 +231,8 @@ impl CallConv<AArch64GeneralReg, AArch64FloatReg, AArch64Assembler> for AArch64C
         // Don't use platform register: AArch64GeneralReg::PR,
         // Don't use link register: AArch64GeneralReg::LR,
         // Don't use zero register/stack pointer: AArch64GeneralReg::ZRSP,
+        // Don't use the scratch registers of the assembler: AArch64GeneralReg::IP0,
+        // Don't use the scratch registers of the calling convention: AArch64GeneralReg::IP1,
 
         // Use callee saved regs last.
         AArch64GeneralReg::X19,
@@ -175,12 +261,54 @@ impl CallConv<AArch64GeneralReg, AArch64FloatReg, AArch64Assembler> for AArch64C
         AArch64GeneralReg::X13,
         AArch64GeneralReg::X14,
         AArch64GeneralReg::X15,
-        AArch64GeneralReg::IP0,
-        AArch64GeneralReg::IP1,
     ];
-    const FLOAT_PARAM_REGS: &'static [AArch64FloatReg] = &[];
+    const FLOAT_PARAM_REGS: &'static [AArch64FloatReg] = &[
+        AArch64FloatReg::V0,
+        AArch64FloatReg::V1,
+        AArch64FloatReg::V2,
+        AArch64FloatReg::V3,
+        AArch64FloatReg::V4,
+        AArch64FloatReg::V5,
+        AArch64FloatReg::V6,
+        AArch64FloatReg::V7,
+    ];
     const FLOAT_RETURN_REGS: &'static [AArch64FloatReg] = Self::FLOAT_PARAM_REGS;
-    const FLOAT_DEFAULT_FREE_REGS: &'static [AArch64FloatReg] = &[];
+    const FLOAT_DEFAULT_FREE_REGS: &'static [AArch64FloatReg] = &[
+        // Use callee saved regs last.
+        AArch64FloatReg::V8,
+        AArch64FloatReg::V9,
+        AArch64FloatReg::V10,
+        AArch64FloatReg::V11,
+        AArch64FloatReg::V12,
+        AArch64FloatReg::V13,
+        AArch64FloatReg::V14,
+        AArch64FloatReg::V15,
+        // Use caller saved regs first.
+        AArch64FloatReg::V0,
+        AArch64FloatReg::V1,
+        AArch64FloatReg::V2,
+        AArch64FloatReg::V3,
+        AArch64FloatReg::V4,
+        AArch64FloatReg::V5,
+        AArch64FloatReg::V6,
+        AArch64FloatReg::V7,
+        AArch64FloatReg::V16,
+        AArch64FloatReg::V17,
+        AArch64FloatReg::V18,
+        AArch64FloatReg::V19,
+        AArch64FloatReg::V20,
+        AArch64FloatReg::V21,
+        AArch64FloatReg::V22,
+        AArch64FloatReg::V23,
+        AArch64FloatReg::V24,
+        AArch64FloatReg::V25,
+        AArch64FloatReg::V26,
+        AArch64FloatReg::V27,
+        AArch64FloatReg::V28,
+        AArch64FloatReg::V29,
+        AArch64FloatReg::V30,
+        AArch64FloatReg::V31,
+    ];
 
     const SHADOW_SPACE_SIZE: u8 = 0;
 
@@ -201,8 +329,19 @@ impl CallConv<AArch64GeneralReg, AArch64FloatReg, AArch64Assembler> for AArch64C
         )
     }
     #[inline(always)]
-    fn float_callee_saved(_reg: &AArch64FloatReg) -> bool {
-        todo!("AArch64 FloatRegs");
+    fn float_callee_saved(reg: &AArch64FloatReg) -> bool {
+        // Only the bottom 64 bits of these registers are saved, which is all we use.
+        matches!(
+            reg,
+            AArch64FloatReg::V8
+                | AArch64FloatReg::V9
+                | AArch64FloatReg::V10
+                | AArch64FloatReg::V11
+                | AArch64FloatReg::V12
+                | AArch64FloatReg::V13
+                | AArch64FloatReg::V14
+                | AArch64FloatReg::V15
+        )
     }
 
     #[inline(always)]
@@ -213,9 +352,21 @@ impl CallConv<AArch64GeneralReg, AArch64FloatReg, AArch64Assembler> for AArch64C
         requested_stack_size: i32,
         fn_call_stack_size: i32,
     ) -> i32 {
-        // Full size is upcast to i64 to make sure we don't overflow here.
+        // Store the frame pointer and link register, then point the frame pointer at them.
+        // This could be optimized by using `STP` with pre-indexing.
+        AArch64Assembler::sub_reg64_reg64_imm32(
+            buf,
+            AArch64GeneralReg::ZRSP,
+            AArch64GeneralReg::ZRSP,
+            16,
+        );
+        AArch64Assembler::mov_stack32_reg64(buf, 0, AArch64GeneralReg::FP);
+        AArch64Assembler::mov_stack32_reg64(buf, 8, AArch64GeneralReg::LR);
+        // `MOV FP, SP` must be encoded as an addition, `ORR` would read the zero register.
+        add_reg64_reg64_imm12(buf, AArch64GeneralReg::FP, AArch64GeneralReg::ZRSP, 0);
+
         let full_stack_size = match requested_stack_size
-            .checked_add(8 * (saved_general_regs.len() + saved_float_regs.len()) as i32 + 8) // The extra 8 is space to store the frame pointer.
+            .checked_add(8 * (saved_general_regs.len() + saved_float_regs.len()) as i32)
             .and_then(|size| size.checked_add(fn_call_stack_size))
         {
             Some(size) => size,
@@ -233,11 +384,6 @@ impl CallConv<AArch64GeneralReg, AArch64FloatReg, AArch64Assembler> for AArch64C
         };
         if let Some(aligned_stack_size) = full_stack_size.checked_add(offset as i32) {
             if aligned_stack_size > 0 {
-                AArch64Assembler::mov_reg64_reg64(
-                    buf,
-                    AArch64GeneralReg::FP,
-                    AArch64GeneralReg::ZRSP,
-                );
                 AArch64Assembler::sub_reg64_reg64_imm32(
                     buf,
                     AArch64GeneralReg::ZRSP,
@@ -245,21 +391,15 @@ impl CallConv<AArch64GeneralReg, AArch64FloatReg, AArch64Assembler> for AArch64C
                     aligned_stack_size,
                 );
 
-                // All the following stores could be optimized by using `STP` to store pairs.
-                let mut offset = aligned_stack_size;
-                offset -= 8;
-                AArch64Assembler::mov_stack32_reg64(buf, offset, AArch64GeneralReg::LR);
-                offset -= 8;
-                AArch64Assembler::mov_stack32_reg64(buf, offset, AArch64GeneralReg::FP);
-
-                offset = aligned_stack_size - fn_call_stack_size;
+                // Put values at the top of the stack to avoid conflicts with previously saved variables.
+                let mut offset = aligned_stack_size - fn_call_stack_size;
                 for reg in saved_general_regs {
+                    AArch64Assembler::mov_base32_reg64(buf, -offset, *reg);
                     offset -= 8;
-                    AArch64Assembler::mov_base32_reg64(buf, offset, *reg);
                 }
                 for reg in saved_float_regs {
+                    AArch64Assembler::mov_base32_freg64(buf, -offset, *reg);
                     offset -= 8;
-                    AArch64Assembler::mov_base32_freg64(buf, offset, *reg);
                 }
                 aligned_stack_size
             } else {
@@ -279,21 +419,14 @@ impl CallConv<AArch64GeneralReg, AArch64FloatReg, AArch64Assembler> for AArch64C
         fn_call_stack_size: i32,
     ) {
         if aligned_stack_size > 0 {
-            // All the following stores could be optimized by using `STP` to store pairs.
-            let mut offset = aligned_stack_size;
-            offset -= 8;
-            AArch64Assembler::mov_reg64_stack32(buf, AArch64GeneralReg::LR, offset);
-            offset -= 8;
-            AArch64Assembler::mov_reg64_stack32(buf, AArch64GeneralReg::FP, offset);
-
-            offset = aligned_stack_size - fn_call_stack_size;
+            let mut offset = aligned_stack_size - fn_call_stack_size;
             for reg in saved_general_regs {
+                AArch64Assembler::mov_reg64_base32(buf, *reg, -offset);
                 offset -= 8;
-                AArch64Assembler::mov_reg64_base32(buf, *reg, offset);
             }
             for reg in saved_float_regs {
+                AArch64Assembler::mov_freg64_base32(buf, *reg, -offset);
                 offset -= 8;
-                AArch64Assembler::mov_freg64_base32(buf, *reg, offset);
             }
             AArch64Assembler::add_reg64_reg64_imm32(
                 buf,
@@ -302,87 +435,373 @@ impl CallConv<AArch64GeneralReg, AArch64FloatReg, AArch64Assembler> for AArch64C
                 aligned_stack_size,
             );
         }
+        AArch64Assembler::mov_reg64_stack32(buf, AArch64GeneralReg::FP, 0);
+        AArch64Assembler::mov_reg64_stack32(buf, AArch64GeneralReg::LR, 8);
+        AArch64Assembler::add_reg64_reg64_imm32(
+            buf,
+            AArch64GeneralReg::ZRSP,
+            AArch64GeneralReg::ZRSP,
+            16,
+        );
     }
 
     #[inline(always)]
     fn load_args<'a>(
-        _buf: &mut Vec<'a, u8>,
-        _storage_manager: &mut StorageManager<
+        buf: &mut Vec<'a, u8>,
+        storage_manager: &mut StorageManager<
             'a,
             AArch64GeneralReg,
             AArch64FloatReg,
             AArch64Assembler,
             AArch64Call,
         >,
-        _args: &'a [(Layout<'a>, Symbol)],
-        _ret_layout: &Layout<'a>,
+        args: &'a [(Layout<'a>, Symbol)],
+        ret_layout: &Layout<'a>,
     ) {
-        todo!("Loading args for AArch64");
+        let mut arg_offset = 16; // 16 is the size of the stored frame pointer and link register.
+        let mut general_i = 0;
+        let mut float_i = 0;
+        if Self::returns_via_arg_pointer(storage_manager.env.layout_interner, ret_layout) {
+            // The caller passes the address of the result in XR, not in a param reg.
+            storage_manager.ret_pointer_arg(AArch64GeneralReg::XR);
+        }
+        for (layout, sym) in args.iter() {
+            let stack_size = layout.stack_size(storage_manager.env.layout_interner, TARGET_INFO);
+            match layout {
+                single_register_integers!() => {
+                    if general_i < Self::GENERAL_PARAM_REGS.len() {
+                        storage_manager.general_reg_arg(sym, Self::GENERAL_PARAM_REGS[general_i]);
+                        general_i += 1;
+                    } else {
+                        storage_manager.primitive_stack_arg(sym, arg_offset);
+                        arg_offset += 8;
+                    }
+                }
+                single_register_floats!() => {
+                    if float_i < Self::FLOAT_PARAM_REGS.len() {
+                        storage_manager.float_reg_arg(sym, Self::FLOAT_PARAM_REGS[float_i]);
+                        float_i += 1;
+                    } else {
+                        storage_manager.primitive_stack_arg(sym, arg_offset);
+                        arg_offset += 8;
+                    }
+                }
+                _ if stack_size == 0 => {
+                    storage_manager.no_data_arg(sym);
+                }
+                _ if stack_size > 16 => {
+                    // Large values are passed as a pointer to a copy owned by the caller.
+                    // Copy the value into this function's stack frame.
+                    let base_offset = storage_manager.claim_stack_area(sym, stack_size);
+                    let pointer = if general_i < Self::GENERAL_PARAM_REGS.len() {
+                        general_i += 1;
+                        Some(Self::GENERAL_PARAM_REGS[general_i - 1])
+                    } else {
+                        arg_offset += 8;
+                        None
+                    };
+                    for i in (0..stack_size as i32).step_by(8) {
+                        let tmp_reg = AArch64GeneralReg::IP1;
+                        match pointer {
+                            Some(reg) => {
+                                AArch64Assembler::mov_reg64_mem64_offset32(buf, tmp_reg, reg, i)
+                            }
+                            None => {
+                                AArch64Assembler::mov_reg64_base32(buf, tmp_reg, arg_offset - 8);
+                                AArch64Assembler::mov_reg64_mem64_offset32(
+                                    buf, tmp_reg, tmp_reg, i,
+                                );
+                            }
+                        }
+                        AArch64Assembler::mov_base32_reg64(buf, base_offset + i, tmp_reg);
+                    }
+                }
+                _ => {
+                    // Values of up to 16 bytes are passed in consecutive general registers.
+                    let words = (stack_size as usize + 7) / 8;
+                    if Self::needs_even_register(storage_manager.env.layout_interner, layout) {
+                        general_i += general_i % 2;
+                    }
+                    if general_i + words <= Self::GENERAL_PARAM_REGS.len() {
+                        let base_offset = storage_manager.claim_stack_area(sym, stack_size);
+                        for (i, reg) in Self::GENERAL_PARAM_REGS[general_i..][..words]
+                            .iter()
+                            .enumerate()
+                        {
+                            AArch64Assembler::mov_base32_reg64(
+                                buf,
+                                base_offset + 8 * i as i32,
+                                *reg,
+                            );
+                        }
+                        general_i += words;
+                    } else {
+                        // Once a value goes on the stack, all following general args do too.
+                        general_i = Self::GENERAL_PARAM_REGS.len();
+                        if Self::needs_even_register(storage_manager.env.layout_interner, layout) {
+                            arg_offset += arg_offset % 16;
+                        }
+                        storage_manager.complex_stack_arg(sym, arg_offset, stack_size);
+                        arg_offset += 8 * words as i32;
+                    }
+                }
+            }
+        }
     }
 
     #[inline(always)]
     fn store_args<'a>(
-        _buf: &mut Vec<'a, u8>,
-        _storage_manager: &mut StorageManager<
+        buf: &mut Vec<'a, u8>,
+        storage_manager: &mut StorageManager<
             'a,
             AArch64GeneralReg,
             AArch64FloatReg,
             AArch64Assembler,
             AArch64Call,
         >,
-        _dst: &Symbol,
-        _args: &[Symbol],
-        _arg_layouts: &[Layout<'a>],
-        _ret_layout: &Layout<'a>,
+        dst: &Symbol,
+        args: &[Symbol],
+        arg_layouts: &[Layout<'a>],
+        ret_layout: &Layout<'a>,
     ) {
-        todo!("Storing args for AArch64");
+        // The param regs may already hold args, so IP1 is used as a buffer for copies to the stack.
+        let tmp_reg = AArch64GeneralReg::IP1;
+        let mut tmp_stack_offset = Self::SHADOW_SPACE_SIZE as i32;
+        let mut general_i = 0;
+        let mut float_i = 0;
+        if Self::returns_via_arg_pointer(storage_manager.env.layout_interner, ret_layout) {
+            // Save space on the stack for the result we will be return.
+            let base_offset = storage_manager.claim_stack_area(
+                dst,
+                ret_layout.stack_size(storage_manager.env.layout_interner, TARGET_INFO),
+            );
+            // Set the indirect result location to the address base + offset.
+            AArch64Assembler::add_reg64_reg64_imm32(
+                buf,
+                AArch64GeneralReg::XR,
+                AArch64GeneralReg::FP,
+                base_offset,
+            );
+        }
+        for (sym, layout) in args.iter().zip(arg_layouts.iter()) {
+            let stack_size = layout.stack_size(storage_manager.env.layout_interner, TARGET_INFO);
+            match layout {
+                single_register_integers!() => {
+                    if general_i < Self::GENERAL_PARAM_REGS.len() {
+                        storage_manager.load_to_specified_general_reg(
+                            buf,
+                            sym,
+                            Self::GENERAL_PARAM_REGS[general_i],
+                        );
+                        general_i += 1;
+                    } else {
+                        storage_manager.load_to_specified_general_reg(buf, sym, tmp_reg);
+                        AArch64Assembler::mov_stack32_reg64(buf, tmp_stack_offset, tmp_reg);
+                        tmp_stack_offset += 8;
+                    }
+                }
+                single_register_floats!() => {
+                    if float_i < Self::FLOAT_PARAM_REGS.len() {
+                        storage_manager.load_to_specified_float_reg(
+                            buf,
+                            sym,
+                            Self::FLOAT_PARAM_REGS[float_i],
+                        );
+                        float_i += 1;
+                    } else {
+                        // There is no spare float reg, so copy the bits through the stack instead.
+                        storage_manager.ensure_symbol_on_stack(buf, sym);
+                        let (base_offset, _) = storage_manager.stack_offset_and_size(sym);
+                        AArch64Assembler::mov_reg64_base32(buf, tmp_reg, base_offset);
+                        AArch64Assembler::mov_stack32_reg64(buf, tmp_stack_offset, tmp_reg);
+                        tmp_stack_offset += 8;
+                    }
+                }
+                _ if stack_size == 0 => {}
+                _ if stack_size > 16 => {
+                    // Pass a pointer to the value. The callee copies it before using it.
+                    let (base_offset, _) = storage_manager.stack_offset_and_size(sym);
+                    if general_i < Self::GENERAL_PARAM_REGS.len() {
+                        AArch64Assembler::add_reg64_reg64_imm32(
+                            buf,
+                            Self::GENERAL_PARAM_REGS[general_i],
+                            AArch64GeneralReg::FP,
+                            base_offset,
+                        );
+                        general_i += 1;
+                    } else {
+                        AArch64Assembler::add_reg64_reg64_imm32(
+                            buf,
+                            tmp_reg,
+                            AArch64GeneralReg::FP,
+                            base_offset,
+                        );
+                        AArch64Assembler::mov_stack32_reg64(buf, tmp_stack_offset, tmp_reg);
+                        tmp_stack_offset += 8;
+                    }
+                }
+                _ => {
+                    let (base_offset, _) = storage_manager.stack_offset_and_size(sym);
+                    debug_assert_eq!(base_offset % 8, 0);
+                    let words = (stack_size as usize + 7) / 8;
+                    if Self::needs_even_register(storage_manager.env.layout_interner, layout) {
+                        general_i += general_i % 2;
+                    }
+                    if general_i + words <= Self::GENERAL_PARAM_REGS.len() {
+                        for (i, reg) in Self::GENERAL_PARAM_REGS[general_i..][..words]
+                            .iter()
+                            .enumerate()
+                        {
+                            AArch64Assembler::mov_reg64_base32(
+                                buf,
+                                *reg,
+                                base_offset + 8 * i as i32,
+                            );
+                        }
+                        general_i += words;
+                    } else {
+                        general_i = Self::GENERAL_PARAM_REGS.len();
+                        if Self::needs_even_register(storage_manager.env.layout_interner, layout) {
+                            tmp_stack_offset += tmp_stack_offset % 16;
+                        }
+                        for i in (0..8 * words as i32).step_by(8) {
+                            AArch64Assembler::mov_reg64_base32(buf, tmp_reg, base_offset + i);
+                            AArch64Assembler::mov_stack32_reg64(buf, tmp_stack_offset + i, tmp_reg);
+                        }
+                        tmp_stack_offset += 8 * words as i32;
+                    }
+                }
+            }
+        }
+        storage_manager.update_fn_call_stack_size(tmp_stack_offset as u32);
     }
 
     fn return_complex_symbol<'a>(
-        _buf: &mut Vec<'a, u8>,
-        _storage_manager: &mut StorageManager<
+        buf: &mut Vec<'a, u8>,
+        storage_manager: &mut StorageManager<
             'a,
             AArch64GeneralReg,
             AArch64FloatReg,
             AArch64Assembler,
             AArch64Call,
         >,
-        _sym: &Symbol,
-        _layout: &Layout<'a>,
+        sym: &Symbol,
+        layout: &Layout<'a>,
     ) {
-        todo!("Returning complex symbols for AArch64");
+        match layout {
+            single_register_layouts!() => {
+                internal_error!("single register layouts are not complex symbols");
+            }
+            x if x.stack_size(storage_manager.env.layout_interner, TARGET_INFO) == 0 => {}
+            x if !Self::returns_via_arg_pointer(storage_manager.env.layout_interner, x) => {
+                let (base_offset, size) = storage_manager.stack_offset_and_size(sym);
+                debug_assert_eq!(base_offset % 8, 0);
+                if size <= 8 {
+                    AArch64Assembler::mov_reg64_base32(
+                        buf,
+                        Self::GENERAL_RETURN_REGS[0],
+                        base_offset,
+                    );
+                } else if size <= 16 {
+                    AArch64Assembler::mov_reg64_base32(
+                        buf,
+                        Self::GENERAL_RETURN_REGS[0],
+                        base_offset,
+                    );
+                    AArch64Assembler::mov_reg64_base32(
+                        buf,
+                        Self::GENERAL_RETURN_REGS[1],
+                        base_offset + 8,
+                    );
+                } else {
+                    internal_error!(
+                        "types that don't return via arg pointer must be less than 16 bytes"
+                    );
+                }
+            }
+            _ => {
+                // This is a large type returned via the arg pointer.
+                storage_manager.copy_symbol_to_arg_pointer(buf, sym, layout);
+            }
+        }
     }
 
     fn load_returned_complex_symbol<'a>(
-        _buf: &mut Vec<'a, u8>,
-        _storage_manager: &mut StorageManager<
+        buf: &mut Vec<'a, u8>,
+        storage_manager: &mut StorageManager<
             'a,
             AArch64GeneralReg,
             AArch64FloatReg,
             AArch64Assembler,
             AArch64Call,
         >,
-        _sym: &Symbol,
-        _layout: &Layout<'a>,
+        sym: &Symbol,
+        layout: &Layout<'a>,
     ) {
-        todo!("Loading returned complex symbols for AArch64");
+        match layout {
+            single_register_layouts!() => {
+                internal_error!("single register layouts are not complex symbols");
+            }
+            x if x.stack_size(storage_manager.env.layout_interner, TARGET_INFO) == 0 => {}
+            x if !Self::returns_via_arg_pointer(storage_manager.env.layout_interner, x) => {
+                let size = layout.stack_size(storage_manager.env.layout_interner, TARGET_INFO);
+                let offset = storage_manager.claim_stack_area(sym, size);
+                if size <= 8 {
+                    AArch64Assembler::mov_base32_reg64(buf, offset, Self::GENERAL_RETURN_REGS[0]);
+                } else if size <= 16 {
+                    AArch64Assembler::mov_base32_reg64(buf, offset, Self::GENERAL_RETURN_REGS[0]);
+                    AArch64Assembler::mov_base32_reg64(
+                        buf,
+                        offset + 8,
+                        Self::GENERAL_RETURN_REGS[1],
+                    );
+                } else {
+                    internal_error!(
+                        "types that don't return via arg pointer must be less than 16 bytes"
+                    );
+                }
+            }
+            _ => {
+                // This should have been recieved via an arg pointer.
+                // That means the value is already loaded onto the stack area we allocated before the call.
+                // Nothing to do.
+            }
+        }
+    }
+}
+
+impl AArch64Call {
+    fn returns_via_arg_pointer<'a>(
+        interner: &STLayoutInterner<'a>,
+        ret_layout: &Layout<'a>,
+    ) -> bool {
+        // TODO: This will need to be extended to fully support the calling convention.
+        // Homogeneous floating point aggregates should be returned in float registers.
+        // details here: https://github.com/ARM-software/abi-aa/blob/main/aapcs64/aapcs64.rst
+        ret_layout.stack_size(interner, TARGET_INFO) > 16
+    }
+
+    /// 16 byte aligned values like `I128` start at an even numbered register.
+    fn needs_even_register<'a>(interner: &STLayoutInterner<'a>, layout: &Layout<'a>) -> bool {
+        layout.alignment_bytes(interner, TARGET_INFO) == 16
     }
 }
 
 impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
     #[inline(always)]
-    fn abs_reg64_reg64(_buf: &mut Vec<'_, u8>, _dst: AArch64GeneralReg, _src: AArch64GeneralReg) {
-        todo!("abs_reg64_reg64 for AArch64");
+    fn abs_reg64_reg64(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, src: AArch64GeneralReg) {
+        cmp_reg64_imm12(buf, src, 0);
+        cneg_reg64_reg64_cond(buf, dst, src, ConditionCode::LT);
     }
 
     #[inline(always)]
     fn abs_freg64_freg64(
-        _buf: &mut Vec<'_, u8>,
+        buf: &mut Vec<'_, u8>,
         _relocs: &mut Vec<'_, Relocation>,
-        _dst: AArch64FloatReg,
-        _src: AArch64FloatReg,
+        dst: AArch64FloatReg,
+        src: AArch64FloatReg,
     ) {
-        todo!("abs_reg64_reg64 for AArch64");
+        fabs_freg64_freg64(buf, dst, src);
     }
 
     #[inline(always)]
@@ -392,13 +811,7 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
         src: AArch64GeneralReg,
         imm32: i32,
     ) {
-        if imm32 < 0 {
-            todo!("immediate addition with values less than 0");
-        } else if imm32 < 0xFFF {
-            add_reg64_reg64_imm12(buf, dst, src, imm32 as u16);
-        } else {
-            todo!("immediate additions with values greater than 12bits");
-        }
+        Self::add_or_sub_reg64_reg64_imm(buf, imm32 < 0, dst, src, imm32.unsigned_abs());
     }
     #[inline(always)]
     fn add_reg64_reg64_reg64(
@@ -407,157 +820,186 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
         src1: AArch64GeneralReg,
         src2: AArch64GeneralReg,
     ) {
-        add_reg64_reg64_reg64(buf, dst, src1, src2);
+        // Set the flags, so `set_if_overflow` can follow, like it does an x86 add.
+        adds_reg64_reg64_reg64(buf, dst, src1, src2);
     }
     #[inline(always)]
     fn add_freg32_freg32_freg32(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64FloatReg,
-        _src1: AArch64FloatReg,
-        _src2: AArch64FloatReg,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64FloatReg,
+        src1: AArch64FloatReg,
+        src2: AArch64FloatReg,
     ) {
-        todo!("adding floats for AArch64");
+        fadd_freg32_freg32_freg32(buf, dst, src1, src2);
     }
     #[inline(always)]
     fn add_freg64_freg64_freg64(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64FloatReg,
-        _src1: AArch64FloatReg,
-        _src2: AArch64FloatReg,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64FloatReg,
+        src1: AArch64FloatReg,
+        src2: AArch64FloatReg,
     ) {
-        todo!("adding floats for AArch64");
+        fadd_freg64_freg64_freg64(buf, dst, src1, src2);
     }
 
     #[inline(always)]
-    fn call(_buf: &mut Vec<'_, u8>, _relocs: &mut Vec<'_, Relocation>, _fn_name: String) {
-        todo!("calling functions literal for AArch64");
+    fn call(buf: &mut Vec<'_, u8>, relocs: &mut Vec<'_, Relocation>, fn_name: String) {
+        relocs.push(Relocation::LinkedFunction {
+            offset: buf.len() as u64,
+            name: fn_name,
+        });
+        bl_imm26(buf, 0);
     }
 
     #[inline(always)]
     fn imul_reg64_reg64_reg64(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64GeneralReg,
-        _src1: AArch64GeneralReg,
-        _src2: AArch64GeneralReg,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64GeneralReg,
+        src1: AArch64GeneralReg,
+        src2: AArch64GeneralReg,
     ) {
-        todo!("register signed multiplication for AArch64");
+        mul_reg64_reg64_reg64(buf, dst, src1, src2);
     }
 
+    // Unlike on x86, multiplication and division use any registers, so the storage manager is unused.
+
     fn umul_reg64_reg64_reg64<'a, ASM, CC>(
-        _buf: &mut Vec<'a, u8>,
+        buf: &mut Vec<'a, u8>,
         _storage_manager: &mut StorageManager<'a, AArch64GeneralReg, AArch64FloatReg, ASM, CC>,
-        _dst: AArch64GeneralReg,
-        _src1: AArch64GeneralReg,
-        _src2: AArch64GeneralReg,
+        dst: AArch64GeneralReg,
+        src1: AArch64GeneralReg,
+        src2: AArch64GeneralReg,
     ) where
         ASM: Assembler<AArch64GeneralReg, AArch64FloatReg>,
         CC: CallConv<AArch64GeneralReg, AArch64FloatReg, ASM>,
     {
-        todo!("register unsigned multiplication for AArch64");
+        // The low 64 bits of a product are the same whether it's signed or unsigned.
+        mul_reg64_reg64_reg64(buf, dst, src1, src2);
     }
 
     fn idiv_reg64_reg64_reg64<'a, ASM, CC>(
-        _buf: &mut Vec<'a, u8>,
+        buf: &mut Vec<'a, u8>,
         _storage_manager: &mut StorageManager<'a, AArch64GeneralReg, AArch64FloatReg, ASM, CC>,
-        _dst: AArch64GeneralReg,
-        _src1: AArch64GeneralReg,
-        _src2: AArch64GeneralReg,
+        dst: AArch64GeneralReg,
+        src1: AArch64GeneralReg,
+        src2: AArch64GeneralReg,
     ) where
         ASM: Assembler<AArch64GeneralReg, AArch64FloatReg>,
         CC: CallConv<AArch64GeneralReg, AArch64FloatReg, ASM>,
     {
-        todo!("register signed division for AArch64");
+        sdiv_reg64_reg64_reg64(buf, dst, src1, src2);
     }
 
     fn udiv_reg64_reg64_reg64<'a, ASM, CC>(
-        _buf: &mut Vec<'a, u8>,
+        buf: &mut Vec<'a, u8>,
         _storage_manager: &mut StorageManager<'a, AArch64GeneralReg, AArch64FloatReg, ASM, CC>,
-        _dst: AArch64GeneralReg,
-        _src1: AArch64GeneralReg,
-        _src2: AArch64GeneralReg,
+        dst: AArch64GeneralReg,
+        src1: AArch64GeneralReg,
+        src2: AArch64GeneralReg,
     ) where
         ASM: Assembler<AArch64GeneralReg, AArch64FloatReg>,
         CC: CallConv<AArch64GeneralReg, AArch64FloatReg, ASM>,
     {
-        todo!("register unsigned division for AArch64");
+        udiv_reg64_reg64_reg64(buf, dst, src1, src2);
     }
 
     #[inline(always)]
     fn mul_freg32_freg32_freg32(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64FloatReg,
-        _src1: AArch64FloatReg,
-        _src2: AArch64FloatReg,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64FloatReg,
+        src1: AArch64FloatReg,
+        src2: AArch64FloatReg,
     ) {
-        todo!("multiplication for floats for AArch64");
+        fmul_freg32_freg32_freg32(buf, dst, src1, src2);
     }
     #[inline(always)]
     fn mul_freg64_freg64_freg64(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64FloatReg,
-        _src1: AArch64FloatReg,
-        _src2: AArch64FloatReg,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64FloatReg,
+        src1: AArch64FloatReg,
+        src2: AArch64FloatReg,
     ) {
-        todo!("multiplication for floats for AArch64");
+        fmul_freg64_freg64_freg64(buf, dst, src1, src2);
     }
 
     #[inline(always)]
     fn div_freg32_freg32_freg32(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64FloatReg,
-        _src1: AArch64FloatReg,
-        _src2: AArch64FloatReg,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64FloatReg,
+        src1: AArch64FloatReg,
+        src2: AArch64FloatReg,
     ) {
-        todo!("division for floats for AArch64");
+        fdiv_freg32_freg32_freg32(buf, dst, src1, src2);
     }
     #[inline(always)]
     fn div_freg64_freg64_freg64(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64FloatReg,
-        _src1: AArch64FloatReg,
-        _src2: AArch64FloatReg,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64FloatReg,
+        src1: AArch64FloatReg,
+        src2: AArch64FloatReg,
     ) {
-        todo!("division for floats for AArch64");
+        fdiv_freg64_freg64_freg64(buf, dst, src1, src2);
     }
 
     #[inline(always)]
-    fn jmp_imm32(_buf: &mut Vec<'_, u8>, _offset: i32) -> usize {
-        todo!("jump instructions for AArch64");
+    fn jmp_imm32(buf: &mut Vec<'_, u8>, offset: i32) -> usize {
+        // Branches are relative to their own address, rather than to the next instruction like on
+        // x86, so that's the location the offset is from.
+        let location = buf.len();
+        b_imm26(buf, offset >> 2);
+        location
     }
 
     #[inline(always)]
     fn tail_call(buf: &mut Vec<'_, u8>) -> u64 {
         Self::jmp_imm32(buf, 0);
-        buf.len() as u64 - 4 // TODO is 4 the correct offset in ARM?
+        buf.len() as u64 - 4
     }
 
     #[inline(always)]
     fn jne_reg64_imm64_imm32(
-        _buf: &mut Vec<'_, u8>,
-        _reg: AArch64GeneralReg,
-        _imm: u64,
-        _offset: i32,
+        buf: &mut Vec<'_, u8>,
+        reg: AArch64GeneralReg,
+        imm: u64,
+        offset: i32,
     ) -> usize {
-        todo!("jump not equal instructions for AArch64");
+        debug_assert!((-(1 << 20)..(1 << 20)).contains(&offset));
+
+        // The comparison only depends on `imm`, so updating the offset later writes the same size.
+        if imm <= 0xFFF {
+            cmp_reg64_imm12(buf, reg, imm as u16);
+        } else {
+            debug_assert_ne!(reg, AArch64GeneralReg::IP0);
+            Self::mov_reg64_imm64(buf, AArch64GeneralReg::IP0, imm as i64);
+            cmp_reg64_reg64(buf, reg, AArch64GeneralReg::IP0);
+        }
+
+        let location = buf.len();
+        b_cond_imm19(buf, ConditionCode::NE, offset >> 2);
+        location
     }
 
     #[inline(always)]
     fn mov_freg32_imm32(
-        _buf: &mut Vec<'_, u8>,
+        buf: &mut Vec<'_, u8>,
         _relocs: &mut Vec<'_, Relocation>,
-        _dst: AArch64FloatReg,
-        _imm: f32,
+        dst: AArch64FloatReg,
+        imm: f32,
     ) {
-        todo!("loading f32 literal for AArch64");
+        // Build the bits in a general register instead of loading them from the data section.
+        Self::mov_reg64_imm64(buf, AArch64GeneralReg::IP0, imm.to_bits() as i64);
+        fmov_freg32_reg32(buf, dst, AArch64GeneralReg::IP0);
     }
     #[inline(always)]
     fn mov_freg64_imm64(
-        _buf: &mut Vec<'_, u8>,
+        buf: &mut Vec<'_, u8>,
         _relocs: &mut Vec<'_, Relocation>,
-        _dst: AArch64FloatReg,
-        _imm: f64,
+        dst: AArch64FloatReg,
+        imm: f64,
     ) {
-        todo!("loading f64 literal for AArch64");
+        // Build the bits in a general register instead of loading them from the data section.
+        Self::mov_reg64_imm64(buf, AArch64GeneralReg::IP0, imm.to_bits() as i64);
+        fmov_freg64_reg64(buf, dst, AArch64GeneralReg::IP0);
     }
     #[inline(always)]
     fn mov_reg64_imm64(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, imm: i64) {
@@ -577,8 +1019,8 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
         }
     }
     #[inline(always)]
-    fn mov_freg64_freg64(_buf: &mut Vec<'_, u8>, _dst: AArch64FloatReg, _src: AArch64FloatReg) {
-        todo!("moving data between float registers for AArch64");
+    fn mov_freg64_freg64(buf: &mut Vec<'_, u8>, dst: AArch64FloatReg, src: AArch64FloatReg) {
+        fmov_freg64_freg64(buf, dst, src);
     }
     #[inline(always)]
     fn mov_reg64_reg64(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, src: AArch64GeneralReg) {
@@ -586,34 +1028,20 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
     }
 
     #[inline(always)]
-    fn mov_freg64_base32(_buf: &mut Vec<'_, u8>, _dst: AArch64FloatReg, _offset: i32) {
-        todo!("loading floating point reg from base offset for AArch64");
+    fn mov_freg64_base32(buf: &mut Vec<'_, u8>, dst: AArch64FloatReg, offset: i32) {
+        Self::load_freg64_offset32(buf, dst, AArch64GeneralReg::FP, offset);
     }
     #[inline(always)]
     fn mov_reg64_base32(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, offset: i32) {
-        if offset < 0 {
-            todo!("negative base offsets for AArch64");
-        } else if offset < (0xFFF << 8) {
-            debug_assert!(offset % 8 == 0);
-            ldr_reg64_reg64_imm12(buf, dst, AArch64GeneralReg::FP, (offset as u16) >> 3);
-        } else {
-            todo!("base offsets over 32k for AArch64");
-        }
+        Self::load_reg64_offset32(buf, dst, AArch64GeneralReg::FP, offset);
     }
     #[inline(always)]
-    fn mov_base32_freg64(_buf: &mut Vec<'_, u8>, _offset: i32, _src: AArch64FloatReg) {
-        todo!("saving floating point reg to base offset for AArch64");
+    fn mov_base32_freg64(buf: &mut Vec<'_, u8>, offset: i32, src: AArch64FloatReg) {
+        Self::store_freg64_offset32(buf, AArch64GeneralReg::FP, offset, src);
     }
     #[inline(always)]
     fn mov_base32_reg64(buf: &mut Vec<'_, u8>, offset: i32, src: AArch64GeneralReg) {
-        if offset < 0 {
-            todo!("negative base offsets for AArch64");
-        } else if offset < (0xFFF << 8) {
-            debug_assert!(offset % 8 == 0);
-            str_reg64_reg64_imm12(buf, src, AArch64GeneralReg::FP, (offset as u16) >> 3);
-        } else {
-            todo!("base offsets over 32k for AArch64");
-        }
+        Self::store_reg64_offset32(buf, AArch64GeneralReg::FP, offset, src);
     }
 
     #[inline(always)]
@@ -623,14 +1051,7 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
         src: AArch64GeneralReg,
         offset: i32,
     ) {
-        if offset < 0 {
-            todo!("negative mem offsets for AArch64");
-        } else if offset < (0xFFF << 8) {
-            debug_assert!(offset % 8 == 0);
-            ldr_reg64_reg64_imm12(buf, dst, src, (offset as u16) >> 3);
-        } else {
-            todo!("mem offsets over 32k for AArch64");
-        }
+        Self::load_reg64_offset32(buf, dst, src, offset);
     }
     #[inline(always)]
     fn mov_mem64_offset32_reg64(
@@ -639,14 +1060,7 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
         offset: i32,
         src: AArch64GeneralReg,
     ) {
-        if offset < 0 {
-            todo!("negative mem offsets for AArch64");
-        } else if offset < (0xFFF << 8) {
-            debug_assert!(offset % 8 == 0);
-            str_reg64_reg64_imm12(buf, src, dst, (offset as u16) >> 3);
-        } else {
-            todo!("mem offsets over 32k for AArch64");
-        }
+        Self::store_reg64_offset32(buf, dst, offset, src);
     }
 
     #[inline(always)]
@@ -654,12 +1068,8 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
         debug_assert!(size <= 8);
         if size == 8 {
             Self::mov_reg64_base32(buf, dst, offset);
-        } else if size == 4 {
-            todo!("sign extending 4 byte values");
-        } else if size == 2 {
-            todo!("sign extending 2 byte values");
-        } else if size == 1 {
-            todo!("sign extending 1 byte values");
+        } else if size == 4 || size == 2 || size == 1 {
+            Self::load_reg64_sized_offset32(buf, dst, AArch64GeneralReg::FP, offset, size, true);
         } else {
             internal_error!("Invalid size for sign extension: {}", size);
         }
@@ -669,50 +1079,43 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
         debug_assert!(size <= 8);
         if size == 8 {
             Self::mov_reg64_base32(buf, dst, offset);
-        } else if size == 4 {
-            todo!("zero extending 4 byte values");
-        } else if size == 2 {
-            todo!("zero extending 2 byte values");
-        } else if size == 1 {
-            todo!("zero extending 1 byte values");
+        } else if size == 4 || size == 2 || size == 1 {
+            Self::load_reg64_sized_offset32(buf, dst, AArch64GeneralReg::FP, offset, size, false);
         } else {
             internal_error!("Invalid size for zero extension: {}", size);
         }
     }
+    #[inline(always)]
+    fn mov_base32_reg_sized(buf: &mut Vec<'_, u8>, offset: i32, src: AArch64GeneralReg, size: u8) {
+        if size == 8 {
+            Self::mov_base32_reg64(buf, offset, src);
+        } else if size == 4 || size == 2 || size == 1 {
+            Self::store_reg64_sized_offset32(buf, AArch64GeneralReg::FP, offset, src, size);
+        } else {
+            internal_error!("Invalid size for a store: {}", size);
+        }
+    }
 
     #[inline(always)]
-    fn mov_freg64_stack32(_buf: &mut Vec<'_, u8>, _dst: AArch64FloatReg, _offset: i32) {
-        todo!("loading floating point reg from stack for AArch64");
+    fn mov_freg64_stack32(buf: &mut Vec<'_, u8>, dst: AArch64FloatReg, offset: i32) {
+        Self::load_freg64_offset32(buf, dst, AArch64GeneralReg::ZRSP, offset);
     }
     #[inline(always)]
     fn mov_reg64_stack32(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, offset: i32) {
-        if offset < 0 {
-            todo!("negative stack offsets for AArch64");
-        } else if offset < (0xFFF << 8) {
-            debug_assert!(offset % 8 == 0);
-            ldr_reg64_reg64_imm12(buf, dst, AArch64GeneralReg::ZRSP, (offset as u16) >> 3);
-        } else {
-            todo!("stack offsets over 32k for AArch64");
-        }
+        Self::load_reg64_offset32(buf, dst, AArch64GeneralReg::ZRSP, offset);
     }
     #[inline(always)]
-    fn mov_stack32_freg64(_buf: &mut Vec<'_, u8>, _offset: i32, _src: AArch64FloatReg) {
-        todo!("saving floating point reg to stack for AArch64");
+    fn mov_stack32_freg64(buf: &mut Vec<'_, u8>, offset: i32, src: AArch64FloatReg) {
+        Self::store_freg64_offset32(buf, AArch64GeneralReg::ZRSP, offset, src);
     }
     #[inline(always)]
     fn mov_stack32_reg64(buf: &mut Vec<'_, u8>, offset: i32, src: AArch64GeneralReg) {
-        if offset < 0 {
-            todo!("negative stack offsets for AArch64");
-        } else if offset < (0xFFF << 8) {
-            debug_assert!(offset % 8 == 0);
-            str_reg64_reg64_imm12(buf, src, AArch64GeneralReg::ZRSP, (offset as u16) >> 3);
-        } else {
-            todo!("stack offsets over 32k for AArch64");
-        }
+        Self::store_reg64_offset32(buf, AArch64GeneralReg::ZRSP, offset, src);
     }
     #[inline(always)]
-    fn neg_reg64_reg64(_buf: &mut Vec<'_, u8>, _dst: AArch64GeneralReg, _src: AArch64GeneralReg) {
-        todo!("neg for AArch64");
+    fn neg_reg64_reg64(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, src: AArch64GeneralReg) {
+        // `NEG Xd, Xm` is an alias of `SUB Xd, XZR, Xm`.
+        sub_reg64_reg64_reg64(buf, dst, AArch64GeneralReg::ZRSP, src);
     }
 
     #[inline(always)]
@@ -722,112 +1125,106 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
         src: AArch64GeneralReg,
         imm32: i32,
     ) {
-        if imm32 < 0 {
-            todo!("immediate subtractions with values less than 0");
-        } else if imm32 < 0xFFF {
-            sub_reg64_reg64_imm12(buf, dst, src, imm32 as u16);
-        } else {
-            todo!("immediate subtractions with values greater than 12bits");
-        }
+        Self::add_or_sub_reg64_reg64_imm(buf, imm32 >= 0, dst, src, imm32.unsigned_abs());
     }
     #[inline(always)]
     fn sub_reg64_reg64_reg64(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64GeneralReg,
-        _src1: AArch64GeneralReg,
-        _src2: AArch64GeneralReg,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64GeneralReg,
+        src1: AArch64GeneralReg,
+        src2: AArch64GeneralReg,
     ) {
-        todo!("registers subtractions for AArch64");
+        sub_reg64_reg64_reg64(buf, dst, src1, src2);
     }
 
     #[inline(always)]
     fn eq_reg64_reg64_reg64(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64GeneralReg,
-        _src1: AArch64GeneralReg,
-        _src2: AArch64GeneralReg,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64GeneralReg,
+        src1: AArch64GeneralReg,
+        src2: AArch64GeneralReg,
     ) {
-        todo!("registers equality for AArch64");
+        cmp_reg64_reg64(buf, src1, src2);
+        cset_reg64_cond(buf, dst, ConditionCode::EQ);
     }
 
     #[inline(always)]
     fn neq_reg64_reg64_reg64(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64GeneralReg,
-        _src1: AArch64GeneralReg,
-        _src2: AArch64GeneralReg,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64GeneralReg,
+        src1: AArch64GeneralReg,
+        src2: AArch64GeneralReg,
     ) {
-        todo!("registers non-equality for AArch64");
+        cmp_reg64_reg64(buf, src1, src2);
+        cset_reg64_cond(buf, dst, ConditionCode::NE);
     }
 
     #[inline(always)]
     fn lt_reg64_reg64_reg64(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64GeneralReg,
-        _src1: AArch64GeneralReg,
-        _src2: AArch64GeneralReg,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64GeneralReg,
+        src1: AArch64GeneralReg,
+        src2: AArch64GeneralReg,
     ) {
-        todo!("registers less than for AArch64");
+        cmp_reg64_reg64(buf, src1, src2);
+        cset_reg64_cond(buf, dst, ConditionCode::LT);
     }
 
     #[inline(always)]
-    fn to_float_freg64_reg64(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64FloatReg,
-        _src: AArch64GeneralReg,
+    fn ult_reg64_reg64_reg64(
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64GeneralReg,
+        src1: AArch64GeneralReg,
+        src2: AArch64GeneralReg,
     ) {
-        todo!("registers to float for AArch64");
+        cmp_reg64_reg64(buf, src1, src2);
+        cset_reg64_cond(buf, dst, ConditionCode::LO);
     }
 
     #[inline(always)]
-    fn to_float_freg32_reg64(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64FloatReg,
-        _src: AArch64GeneralReg,
-    ) {
-        todo!("registers to float for AArch64");
+    fn to_float_freg64_reg64(buf: &mut Vec<'_, u8>, dst: AArch64FloatReg, src: AArch64GeneralReg) {
+        scvtf_freg64_reg64(buf, dst, src);
     }
 
     #[inline(always)]
-    fn to_float_freg32_freg64(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64FloatReg,
-        _src: AArch64FloatReg,
-    ) {
-        todo!("registers to float for AArch64");
+    fn to_float_freg32_reg64(buf: &mut Vec<'_, u8>, dst: AArch64FloatReg, src: AArch64GeneralReg) {
+        scvtf_freg32_reg64(buf, dst, src);
     }
 
     #[inline(always)]
-    fn to_float_freg64_freg32(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64FloatReg,
-        _src: AArch64FloatReg,
-    ) {
-        todo!("registers to float for AArch64");
+    fn to_float_freg32_freg64(buf: &mut Vec<'_, u8>, dst: AArch64FloatReg, src: AArch64FloatReg) {
+        fcvt_freg32_freg64(buf, dst, src);
+    }
+
+    #[inline(always)]
+    fn to_float_freg64_freg32(buf: &mut Vec<'_, u8>, dst: AArch64FloatReg, src: AArch64FloatReg) {
+        fcvt_freg64_freg32(buf, dst, src);
     }
 
     #[inline(always)]
     fn lte_reg64_reg64_reg64(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64GeneralReg,
-        _src1: AArch64GeneralReg,
-        _src2: AArch64GeneralReg,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64GeneralReg,
+        src1: AArch64GeneralReg,
+        src2: AArch64GeneralReg,
     ) {
-        todo!("registers less than or equal for AArch64");
+        cmp_reg64_reg64(buf, src1, src2);
+        cset_reg64_cond(buf, dst, ConditionCode::LE);
     }
 
     #[inline(always)]
     fn gte_reg64_reg64_reg64(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64GeneralReg,
-        _src1: AArch64GeneralReg,
-        _src2: AArch64GeneralReg,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64GeneralReg,
+        src1: AArch64GeneralReg,
+        src2: AArch64GeneralReg,
     ) {
-        todo!("registers greater than or equal for AArch64");
+        cmp_reg64_reg64(buf, src1, src2);
+        cset_reg64_cond(buf, dst, ConditionCode::GE);
     }
 
-    fn set_if_overflow(_buf: &mut Vec<'_, u8>, _dst: AArch64GeneralReg) {
-        todo!("set if overflow for AArch64");
+    fn set_if_overflow(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg) {
+        cset_reg64_cond(buf, dst, ConditionCode::VS);
     }
 
     #[inline(always)]
@@ -836,34 +1233,200 @@ impl Assembler<AArch64GeneralReg, AArch64FloatReg> for AArch64Assembler {
     }
 
     fn and_reg64_reg64_reg64(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64GeneralReg,
-        _src1: AArch64GeneralReg,
-        _src2: AArch64GeneralReg,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64GeneralReg,
+        src1: AArch64GeneralReg,
+        src2: AArch64GeneralReg,
     ) {
-        todo!("bitwise and for AArch64")
+        and_reg64_reg64_reg64(buf, dst, src1, src2);
     }
 
     fn or_reg64_reg64_reg64(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64GeneralReg,
-        _src1: AArch64GeneralReg,
-        _src2: AArch64GeneralReg,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64GeneralReg,
+        src1: AArch64GeneralReg,
+        src2: AArch64GeneralReg,
     ) {
-        todo!("bitwise or for AArch64")
+        orr_reg64_reg64_reg64(buf, dst, src1, src2);
     }
 
     fn xor_reg64_reg64_reg64(
-        _buf: &mut Vec<'_, u8>,
-        _dst: AArch64GeneralReg,
-        _src1: AArch64GeneralReg,
-        _src2: AArch64GeneralReg,
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64GeneralReg,
+        src1: AArch64GeneralReg,
+        src2: AArch64GeneralReg,
     ) {
-        todo!("bitwise xor for AArch64")
+        eor_reg64_reg64_reg64(buf, dst, src1, src2);
     }
 }
 
-impl AArch64Assembler {}
+impl AArch64Assembler {
+    /// Adds `imm` to `src`, or subtracts it, and places the result into `dst`. Either may be SP.
+    /// The immediate is unsigned, so the magnitude of `i32::MIN` fits.
+    #[inline(always)]
+    fn add_or_sub_reg64_reg64_imm(
+        buf: &mut Vec<'_, u8>,
+        subtract: bool,
+        dst: AArch64GeneralReg,
+        src: AArch64GeneralReg,
+        imm: u32,
+    ) {
+        if imm <= 0xFFF {
+            if subtract {
+                sub_reg64_reg64_imm12(buf, dst, src, imm as u16);
+            } else {
+                add_reg64_reg64_imm12(buf, dst, src, imm as u16);
+            }
+        } else if imm <= 0xFF_FFFF {
+            let (high, low) = ((imm >> 12) as u16, (imm & 0xFFF) as u16);
+            if subtract {
+                sub_reg64_reg64_imm12_lsl12(buf, dst, src, high);
+                if low != 0 {
+                    sub_reg64_reg64_imm12(buf, dst, dst, low);
+                }
+            } else {
+                add_reg64_reg64_imm12_lsl12(buf, dst, src, high);
+                if low != 0 {
+                    add_reg64_reg64_imm12(buf, dst, dst, low);
+                }
+            }
+        } else {
+            // Only the extended register form of the instruction can use SP.
+            debug_assert_ne!(src, AArch64GeneralReg::IP0);
+            Self::mov_reg64_imm64(buf, AArch64GeneralReg::IP0, imm as i64);
+            if subtract {
+                sub_reg64_reg64_reg64_uxtx(buf, dst, src, AArch64GeneralReg::IP0);
+            } else {
+                add_reg64_reg64_reg64_uxtx(buf, dst, src, AArch64GeneralReg::IP0);
+            }
+        }
+    }
+
+    /// Moves an offset that doesn't fit in a load or store into IP0, which the register offset
+    /// form of the instruction then adds to the base. IP0 is left alone by the register
+    /// allocator, so it is free to use here.
+    #[inline(always)]
+    fn offset_in_ip0(buf: &mut Vec<'_, u8>, offset: i32) -> AArch64GeneralReg {
+        Self::mov_reg64_imm64(buf, AArch64GeneralReg::IP0, offset as i64);
+        AArch64GeneralReg::IP0
+    }
+
+    /// Loads `size` bytes at `base + offset` into `dst`, sign extending them if `signed`, and
+    /// zero extending them otherwise.
+    #[inline(always)]
+    fn load_reg64_sized_offset32(
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64GeneralReg,
+        base: AArch64GeneralReg,
+        offset: i32,
+        size: u8,
+        signed: bool,
+    ) {
+        let size = size as i32;
+        if offset >= 0 && offset % size == 0 && offset / size <= 0xFFF {
+            ldr_sized_reg64_reg64_imm12(buf, size as u8, signed, dst, base, (offset / size) as u16);
+        } else if (-256..256).contains(&offset) {
+            ldur_sized_reg64_reg64_imm9(buf, size as u8, signed, dst, base, offset as i16);
+        } else {
+            let index = Self::offset_in_ip0(buf, offset);
+            ldr_sized_reg64_reg64_reg64(buf, size as u8, signed, dst, base, index);
+        }
+    }
+
+    /// Stores the low `size` bytes of `src` at `base + offset`.
+    #[inline(always)]
+    fn store_reg64_sized_offset32(
+        buf: &mut Vec<'_, u8>,
+        base: AArch64GeneralReg,
+        offset: i32,
+        src: AArch64GeneralReg,
+        size: u8,
+    ) {
+        let size = size as i32;
+        if offset >= 0 && offset % size == 0 && offset / size <= 0xFFF {
+            str_sized_reg64_reg64_imm12(buf, size as u8, src, base, (offset / size) as u16);
+        } else if (-256..256).contains(&offset) {
+            stur_sized_reg64_reg64_imm9(buf, size as u8, src, base, offset as i16);
+        } else {
+            debug_assert_ne!(src, AArch64GeneralReg::IP0);
+            let index = Self::offset_in_ip0(buf, offset);
+            str_sized_reg64_reg64_reg64(buf, size as u8, src, base, index);
+        }
+    }
+
+    /// Loads 8 bytes at `base + offset` into `dst`.
+    #[inline(always)]
+    fn load_reg64_offset32(
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64GeneralReg,
+        base: AArch64GeneralReg,
+        offset: i32,
+    ) {
+        if (0..0x8000).contains(&offset) && offset % 8 == 0 {
+            ldr_reg64_reg64_imm12(buf, dst, base, (offset >> 3) as u16);
+        } else if (-256..256).contains(&offset) {
+            ldur_reg64_reg64_imm9(buf, dst, base, offset as i16);
+        } else {
+            let index = Self::offset_in_ip0(buf, offset);
+            ldr_reg64_reg64_reg64(buf, dst, base, index);
+        }
+    }
+
+    /// Stores the 8 bytes in `src` at `base + offset`.
+    #[inline(always)]
+    fn store_reg64_offset32(
+        buf: &mut Vec<'_, u8>,
+        base: AArch64GeneralReg,
+        offset: i32,
+        src: AArch64GeneralReg,
+    ) {
+        if (0..0x8000).contains(&offset) && offset % 8 == 0 {
+            str_reg64_reg64_imm12(buf, src, base, (offset >> 3) as u16);
+        } else if (-256..256).contains(&offset) {
+            stur_reg64_reg64_imm9(buf, src, base, offset as i16);
+        } else {
+            debug_assert_ne!(src, AArch64GeneralReg::IP0);
+            let index = Self::offset_in_ip0(buf, offset);
+            str_reg64_reg64_reg64(buf, src, base, index);
+        }
+    }
+
+    /// Loads 8 bytes at `base + offset` into `dst`.
+    #[inline(always)]
+    fn load_freg64_offset32(
+        buf: &mut Vec<'_, u8>,
+        dst: AArch64FloatReg,
+        base: AArch64GeneralReg,
+        offset: i32,
+    ) {
+        if (0..0x8000).contains(&offset) && offset % 8 == 0 {
+            ldr_freg64_reg64_imm12(buf, dst, base, (offset >> 3) as u16);
+        } else if (-256..256).contains(&offset) {
+            ldur_freg64_reg64_imm9(buf, dst, base, offset as i16);
+        } else {
+            let index = Self::offset_in_ip0(buf, offset);
+            ldr_freg64_reg64_reg64(buf, dst, base, index);
+        }
+    }
+
+    /// Stores the 8 bytes in `src` at `base + offset`.
+    #[inline(always)]
+    fn store_freg64_offset32(
+        buf: &mut Vec<'_, u8>,
+        base: AArch64GeneralReg,
+        offset: i32,
+        src: AArch64FloatReg,
+    ) {
+        if (0..0x8000).contains(&offset) && offset % 8 == 0 {
+            str_freg64_reg64_imm12(buf, src, base, (offset >> 3) as u16);
+        } else if (-256..256).contains(&offset) {
+            stur_freg64_reg64_imm9(buf, src, base, offset as i16);
+        } else {
+            let index = Self::offset_in_ip0(buf, offset);
+            str_freg64_reg64_reg64(buf, src, base, index);
+        }
+    }
+}
 
 // Instructions
 // ARM manual section C3
@@ -1021,29 +1584,66 @@ impl ArithmeticShifted {
     }
 }
 
-#[derive(Debug)]
-#[allow(dead_code)]
-enum LogicalOp {
-    AND,
-    BIC,
-    ORR,
-    ORN,
-    EOR,
-    EON,
-    ANDS,
-    BICS,
-}
-
 #[derive(PackedStruct)]
 #[packed_struct(endian = "msb")]
-pub struct LogicalShiftedRegister {
+pub struct ArithmeticExtended {
     sf: bool,
-    op: Integer<u8, packed_bits::Bits<2>>,
-    fixed: Integer<u8, packed_bits::Bits<5>>, // = 0b01010,
-    shift: Integer<u8, packed_bits::Bits<2>>, // shift
-    n: bool,
-    reg_m: Integer<u8, packed_bits::Bits<5>>,
-    imm6: Integer<u8, packed_bits::Bits<6>>,
+    op: bool, // add or subtract
+    s: bool,
+    fixed: Integer<u8, packed_bits::Bits<5>>, // = 0b01011,
+    opt: Integer<u8, packed_bits::Bits<2>>,   // = 0b00,
+    fixed2: bool,                             // = 0b1,
+    reg_m: Integer<u8, packed_bits::Bits<5>>,
+    option: Integer<u8, packed_bits::Bits<3>>, // = 0b011, which is UXTX
+    imm3: Integer<u8, packed_bits::Bits<3>>,
+    reg_n: Integer<u8, packed_bits::Bits<5>>,
+    reg_d: Integer<u8, packed_bits::Bits<5>>,
+}
+
+impl Aarch64Bytes for ArithmeticExtended {}
+
+impl ArithmeticExtended {
+    #[inline(always)]
+    fn new(op: bool, rm: AArch64GeneralReg, rn: AArch64GeneralReg, rd: AArch64GeneralReg) -> Self {
+        Self {
+            reg_d: rd.id().into(),
+            reg_n: rn.id().into(),
+            imm3: 0.into(),
+            option: 0b011.into(),
+            reg_m: rm.id().into(),
+            fixed2: true,
+            opt: 0b00.into(),
+            fixed: 0b01011.into(),
+            s: false,
+            op,
+            sf: true,
+        }
+    }
+}
+
+#[derive(Debug)]
+#[allow(dead_code)]
+enum LogicalOp {
+    AND,
+    BIC,
+    ORR,
+    ORN,
+    EOR,
+    EON,
+    ANDS,
+    BICS,
+}
+
+#[derive(PackedStruct)]
+#[packed_struct(endian = "msb")]
+pub struct LogicalShiftedRegister {
+    sf: bool,
+    op: Integer<u8, packed_bits::Bits<2>>,
+    fixed: Integer<u8, packed_bits::Bits<5>>, // = 0b01010,
+    shift: Integer<u8, packed_bits::Bits<2>>, // shift
+    n: bool,
+    reg_m: Integer<u8, packed_bits::Bits<5>>,
+    imm6: Integer<u8, packed_bits::Bits<6>>,
     reg_n: Integer<u8, packed_bits::Bits<5>>,
     reg_d: Integer<u8, packed_bits::Bits<5>>,
 }
@@ -1089,6 +1689,77 @@ impl LogicalShiftedRegister {
     }
 }
 
+#[derive(Clone, Copy, Debug)]
+#[allow(dead_code)]
+enum ConditionCode {
+    EQ = 0b0000,
+    NE = 0b0001,
+    HS = 0b0010,
+    LO = 0b0011,
+    VS = 0b0110,
+    VC = 0b0111,
+    GE = 0b1010,
+    LT = 0b1011,
+    GT = 0b1100,
+    LE = 0b1101,
+}
+
+impl ConditionCode {
+    #[inline(always)]
+    fn id(&self) -> u8 {
+        *self as u8
+    }
+
+    /// The condition that holds exactly when this one does not.
+    #[inline(always)]
+    fn invert(&self) -> u8 {
+        self.id() ^ 1
+    }
+}
+
+#[derive(PackedStruct)]
+#[packed_struct(endian = "msb")]
+pub struct ConditionalSelect {
+    sf: bool,
+    op: bool,
+    s: bool,
+    fixed: u8, // = 0b11010100,
+    reg_m: Integer<u8, packed_bits::Bits<5>>,
+    cond: Integer<u8, packed_bits::Bits<4>>,
+    op2: Integer<u8, packed_bits::Bits<2>>,
+    reg_n: Integer<u8, packed_bits::Bits<5>>,
+    reg_d: Integer<u8, packed_bits::Bits<5>>,
+}
+
+impl Aarch64Bytes for ConditionalSelect {}
+
+impl ConditionalSelect {
+    #[inline(always)]
+    fn new(
+        op: bool,
+        op2: u8,
+        cond: u8,
+        rm: AArch64GeneralReg,
+        rn: AArch64GeneralReg,
+        rd: AArch64GeneralReg,
+    ) -> Self {
+        debug_assert!(op2 <= 0b11);
+        debug_assert!(cond <= 0b1111);
+
+        Self {
+            reg_d: rd.id().into(),
+            reg_n: rn.id().into(),
+            op2: op2.into(),
+            cond: cond.into(),
+            reg_m: rm.id().into(),
+            fixed: 0b11010100,
+            s: false,
+            op,
+            sf: true,
+        }
+    }
+}
+
 #[derive(PackedStruct)]
 pub struct UnconditionalBranchRegister {
     fixed: Integer<u8, packed_bits::Bits<7>>,
@@ -1133,7 +1804,7 @@ impl UnconditionalBranchRegister {
 pub struct LoadStoreRegisterImmediate {
     size: Integer<u8, packed_bits::Bits<2>>,
     fixed: Integer<u8, packed_bits::Bits<3>>, // = 0b111,
-    fixed2: bool,
+    v: bool,                                  // SIMD and floating point registers
     fixed3: Integer<u8, packed_bits::Bits<2>>,
     opc: Integer<u8, packed_bits::Bits<2>>,
     imm12: Integer<u16, packed_bits::Bits<12>>,
@@ -1145,17 +1816,17 @@ impl Aarch64Bytes for LoadStoreRegisterImmediate {}
 
 impl LoadStoreRegisterImmediate {
     #[inline(always)]
-    fn new(size: u8, opc: u8, imm12: u16, rn: AArch64GeneralReg, rt: AArch64GeneralReg) -> Self {
+    fn new(size: u8, v: bool, opc: u8, imm12: u16, rn: AArch64GeneralReg, rt: u8) -> Self {
         debug_assert!(size <= 0b11);
         debug_assert!(imm12 <= 0xFFF);
 
         Self {
-            rt: rt.id().into(),
+            rt: rt.into(),
             rn: rn.id().into(),
             imm12: imm12.into(),
             opc: opc.into(),
             fixed3: 0b01.into(),
-            fixed2: false,
+            v,
             fixed: 0b111.into(),
             size: size.into(),
         }
@@ -1163,143 +1834,1152 @@ impl LoadStoreRegisterImmediate {
 
     #[inline(always)]
     fn new_load(size: u8, imm12: u16, rn: AArch64GeneralReg, rt: AArch64GeneralReg) -> Self {
-        Self::new(size, 0b01, imm12, rn, rt)
+        Self::new(size, false, 0b01, imm12, rn, rt.id())
     }
 
     #[inline(always)]
     fn new_store(size: u8, imm12: u16, rn: AArch64GeneralReg, rt: AArch64GeneralReg) -> Self {
-        Self::new(size, 0b00, imm12, rn, rt)
+        Self::new(size, false, 0b00, imm12, rn, rt.id())
     }
-}
-
-// Below here are the functions for all of the assembly instructions.
-// Their names are based on the instruction and operators combined.
-// You should call `buf.reserve()` if you push or extend more than once.
-// Unit tests are added at the bottom of the file to ensure correct asm generation.
-// Please keep these in alphanumeric order.
 
-/// `ADD Xd, Xn, imm12` -> Add Xn and imm12 and place the result into Xd.
-#[inline(always)]
-fn add_reg64_reg64_imm12(
-    buf: &mut Vec<'_, u8>,
-    dst: AArch64GeneralReg,
-    src: AArch64GeneralReg,
-    imm12: u16,
-) {
-    let inst = ArithmeticImmediate::new(false, false, dst, src, imm12, false);
+    #[inline(always)]
+    fn new_float_load(size: u8, imm12: u16, rn: AArch64GeneralReg, rt: AArch64FloatReg) -> Self {
+        Self::new(size, true, 0b01, imm12, rn, rt.id())
+    }
 
-    buf.extend(inst.bytes());
+    #[inline(always)]
+    fn new_float_store(size: u8, imm12: u16, rn: AArch64GeneralReg, rt: AArch64FloatReg) -> Self {
+        Self::new(size, true, 0b00, imm12, rn, rt.id())
+    }
 }
 
-/// `ADD Xd, Xm, Xn` -> Add Xm and Xn and place the result into Xd.
-#[inline(always)]
-fn add_reg64_reg64_reg64(
-    buf: &mut Vec<'_, u8>,
-    dst: AArch64GeneralReg,
-    src1: AArch64GeneralReg,
-    src2: AArch64GeneralReg,
-) {
-    let inst = ArithmeticShifted::new(false, false, ShiftType::LSL, 0, src2, src1, dst);
-
-    buf.extend(inst.bytes());
+// Uses a signed, unscaled offset
+// opc = 0b01 means load
+// opc = 0b00 means store
+#[derive(PackedStruct, Debug)]
+#[packed_struct(endian = "msb")]
+pub struct LoadStoreRegisterUnscaledImmediate {
+    size: Integer<u8, packed_bits::Bits<2>>,
+    fixed: Integer<u8, packed_bits::Bits<3>>,  // = 0b111,
+    v: bool,                                   // SIMD and floating point registers
+    fixed2: Integer<u8, packed_bits::Bits<2>>, // = 0b00,
+    opc: Integer<u8, packed_bits::Bits<2>>,
+    fixed3: bool, // = 0b0,
+    imm9: Integer<u16, packed_bits::Bits<9>>,
+    fixed4: Integer<u8, packed_bits::Bits<2>>, // = 0b00,
+    rn: Integer<u8, packed_bits::Bits<5>>,
+    rt: Integer<u8, packed_bits::Bits<5>>,
 }
 
-/// `LDR Xt, [Xn, #offset]` -> Load Xn + Offset Xt. ZRSP is SP.
-/// Note: imm12 is the offest divided by 8.
-#[inline(always)]
-fn ldr_reg64_reg64_imm12(
-    buf: &mut Vec<'_, u8>,
-    dst: AArch64GeneralReg,
-    base: AArch64GeneralReg,
-    imm12: u16,
-) {
-    let inst = LoadStoreRegisterImmediate::new_load(0b11, imm12, base, dst);
+impl Aarch64Bytes for LoadStoreRegisterUnscaledImmediate {}
 
-    buf.extend(inst.bytes());
-}
+impl LoadStoreRegisterUnscaledImmediate {
+    #[inline(always)]
+    fn new(size: u8, v: bool, opc: u8, imm9: i16, rn: AArch64GeneralReg, rt: u8) -> Self {
+        debug_assert!(size <= 0b11);
+        debug_assert!((-256..256).contains(&imm9));
 
-/// `MOV Xd, Xm` -> Move Xm to Xd.
-#[inline(always)]
-fn mov_reg64_reg64(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, src: AArch64GeneralReg) {
-    // MOV is equvalent to `ORR Xd, XZR, XM` in AARCH64.
-    let inst = LogicalShiftedRegister::new(
-        LogicalOp::ORR,
-        ShiftType::LSL,
-        0,
-        src,
-        AArch64GeneralReg::ZRSP,
-        dst,
-    );
+        Self {
+            rt: rt.into(),
+            rn: rn.id().into(),
+            fixed4: 0b00.into(),
+            imm9: ((imm9 as u16) & 0x1FF).into(),
+            fixed3: false,
+            opc: opc.into(),
+            fixed2: 0b00.into(),
+            v,
+            fixed: 0b111.into(),
+            size: size.into(),
+        }
+    }
+}
 
-    buf.extend(inst.bytes());
+// Uses a register as the offset
+// opc = 0b01 means load
+// opc = 0b00 means store
+#[derive(PackedStruct, Debug)]
+#[packed_struct(endian = "msb")]
+pub struct LoadStoreRegisterRegisterOffset {
+    size: Integer<u8, packed_bits::Bits<2>>,
+    fixed: Integer<u8, packed_bits::Bits<3>>,  // = 0b111,
+    v: bool,                                   // SIMD and floating point registers
+    fixed2: Integer<u8, packed_bits::Bits<2>>, // = 0b00,
+    opc: Integer<u8, packed_bits::Bits<2>>,
+    fixed3: bool, // = 0b1,
+    rm: Integer<u8, packed_bits::Bits<5>>,
+    option: Integer<u8, packed_bits::Bits<3>>, // = 0b011, which is LSL
+    s: bool,                                   // shift the offset by the size
+    fixed4: Integer<u8, packed_bits::Bits<2>>, // = 0b10,
+    rn: Integer<u8, packed_bits::Bits<5>>,
+    rt: Integer<u8, packed_bits::Bits<5>>,
 }
 
-/// `MOVK Xd, imm16` -> Keeps Xd and moves an optionally shifted imm16 to Xd.
-#[inline(always)]
-fn movk_reg64_imm16(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, imm16: u16, hw: u8) {
-    let inst = MoveWideImmediate::new(0b11, dst, imm16, hw, true);
+impl Aarch64Bytes for LoadStoreRegisterRegisterOffset {}
 
-    buf.extend(inst.bytes());
-}
+impl LoadStoreRegisterRegisterOffset {
+    #[inline(always)]
+    fn new(
+        size: u8,
+        v: bool,
+        opc: u8,
+        rm: AArch64GeneralReg,
+        rn: AArch64GeneralReg,
+        rt: u8,
+    ) -> Self {
+        debug_assert!(size <= 0b11);
 
-/// `MOVZ Xd, imm16` -> Zeros Xd and moves an optionally shifted imm16 to Xd.
-#[inline(always)]
-fn movz_reg64_imm16(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, imm16: u16, hw: u8) {
-    let inst = MoveWideImmediate::new(0b10, dst, imm16, hw, true);
+        Self {
+            rt: rt.into(),
+            rn: rn.id().into(),
+            fixed4: 0b10.into(),
+            s: false,
+            option: 0b011.into(),
+            rm: rm.id().into(),
+            fixed3: true,
+            opc: opc.into(),
+            fixed2: 0b00.into(),
+            v,
+            fixed: 0b111.into(),
+            size: size.into(),
+        }
+    }
+}
 
-    buf.extend(inst.bytes());
+#[derive(PackedStruct, Debug)]
+#[packed_struct(endian = "msb")]
+pub struct UnconditionalBranchImmediate {
+    op: bool,                                 // true for branch with link
+    fixed: Integer<u8, packed_bits::Bits<5>>, // = 0b00101,
+    imm26: Integer<u32, packed_bits::Bits<26>>,
 }
 
-/// `STR Xt, [Xn, #offset]` -> Store Xt to Xn + Offset. ZRSP is SP.
-/// Note: imm12 is the offest divided by 8.
-#[inline(always)]
-fn str_reg64_reg64_imm12(
-    buf: &mut Vec<'_, u8>,
-    src: AArch64GeneralReg,
-    base: AArch64GeneralReg,
-    imm12: u16,
-) {
-    let inst = LoadStoreRegisterImmediate::new_store(0b11, imm12, base, src);
+impl Aarch64Bytes for UnconditionalBranchImmediate {}
 
-    buf.extend(inst.bytes());
-}
+impl UnconditionalBranchImmediate {
+    #[inline(always)]
+    fn new(op: bool, imm26: u32) -> Self {
+        debug_assert!(imm26 <= 0x3FF_FFFF);
 
-/// `SUB Xd, Xn, imm12` -> Subtract Xn and imm12 and place the result into Xd.
-#[inline(always)]
-fn sub_reg64_reg64_imm12(
-    buf: &mut Vec<'_, u8>,
-    dst: AArch64GeneralReg,
-    src: AArch64GeneralReg,
-    imm12: u16,
-) {
-    let inst = ArithmeticImmediate::new(true, false, dst, src, imm12, false);
+        Self {
+            imm26: imm26.into(),
+            fixed: 0b00101.into(),
+            op,
+        }
+    }
+}
 
-    buf.extend(inst.bytes());
+#[derive(PackedStruct, Debug)]
+#[packed_struct(endian = "msb")]
+pub struct ConditionalBranchImmediate {
+    fixed: Integer<u8, packed_bits::Bits<7>>, // = 0b0101010,
+    o1: bool,                                 // = 0b0,
+    imm19: Integer<u32, packed_bits::Bits<19>>,
+    o0: bool, // = 0b0,
+    cond: Integer<u8, packed_bits::Bits<4>>,
 }
 
-/// `RET Xn` -> Return to the address stored in Xn.
-#[inline(always)]
-fn ret_reg64(buf: &mut Vec<'_, u8>, xn: AArch64GeneralReg) {
-    let inst = UnconditionalBranchRegister::new(0b10, xn);
+impl Aarch64Bytes for ConditionalBranchImmediate {}
 
-    buf.extend(inst.bytes());
+impl ConditionalBranchImmediate {
+    #[inline(always)]
+    fn new(cond: ConditionCode, imm19: i32) -> Self {
+        Self {
+            cond: cond.id().into(),
+            o0: false,
+            imm19: ((imm19 as u32) & 0x7_FFFF).into(),
+            o1: false,
+            fixed: 0b0101010.into(),
+        }
+    }
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::disassembler_test;
-    use capstone::prelude::*;
+#[derive(PackedStruct)]
+#[packed_struct(endian = "msb")]
+pub struct DataProcessingTwoSource {
+    sf: bool,
+    fixed: bool, // = 0b0,
+    s: bool,
+    fixed2: u8, // = 0b11010110,
+    reg_m: Integer<u8, packed_bits::Bits<5>>,
+    opcode: Integer<u8, packed_bits::Bits<6>>,
+    reg_n: Integer<u8, packed_bits::Bits<5>>,
+    reg_d: Integer<u8, packed_bits::Bits<5>>,
+}
 
-    enum ZRSPKind {
-        UsesZR,
-        UsesSP,
-    }
-    use ZRSPKind::*;
+impl Aarch64Bytes for DataProcessingTwoSource {}
 
-    impl AArch64GeneralReg {
-        fn capstone_string(&self, zrsp_kind: ZRSPKind) -> String {
-            match self {
+impl DataProcessingTwoSource {
+    #[inline(always)]
+    fn new(
+        opcode: u8,
+        rm: AArch64GeneralReg,
+        rn: AArch64GeneralReg,
+        rd: AArch64GeneralReg,
+    ) -> Self {
+        debug_assert!(opcode <= 0b111111);
+
+        Self {
+            reg_d: rd.id().into(),
+            reg_n: rn.id().into(),
+            opcode: opcode.into(),
+            reg_m: rm.id().into(),
+            fixed2: 0b11010110,
+            s: false,
+            fixed: false,
+            sf: true,
+        }
+    }
+}
+
+#[derive(PackedStruct)]
+#[packed_struct(endian = "msb")]
+pub struct DataProcessingThreeSource {
+    sf: bool,
+    op54: Integer<u8, packed_bits::Bits<2>>,
+    fixed: Integer<u8, packed_bits::Bits<5>>, // = 0b11011,
+    op31: Integer<u8, packed_bits::Bits<3>>,
+    reg_m: Integer<u8, packed_bits::Bits<5>>,
+    o0: bool,
+    reg_a: Integer<u8, packed_bits::Bits<5>>,
+    reg_n: Integer<u8, packed_bits::Bits<5>>,
+    reg_d: Integer<u8, packed_bits::Bits<5>>,
+}
+
+impl Aarch64Bytes for DataProcessingThreeSource {}
+
+impl DataProcessingThreeSource {
+    #[inline(always)]
+    fn new(
+        op31: u8,
+        o0: bool,
+        rm: AArch64GeneralReg,
+        ra: AArch64GeneralReg,
+        rn: AArch64GeneralReg,
+        rd: AArch64GeneralReg,
+    ) -> Self {
+        debug_assert!(op31 <= 0b111);
+
+        Self {
+            reg_d: rd.id().into(),
+            reg_n: rn.id().into(),
+            reg_a: ra.id().into(),
+            o0,
+            reg_m: rm.id().into(),
+            op31: op31.into(),
+            fixed: 0b11011.into(),
+            op54: 0b00.into(),
+            sf: true,
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum FloatType {
+    Single = 0b00,
+    Double = 0b01,
+}
+
+impl FloatType {
+    #[inline(always)]
+    fn id(&self) -> u8 {
+        *self as u8
+    }
+}
+
+#[derive(PackedStruct, Debug)]
+#[packed_struct(endian = "msb")]
+pub struct FloatingPointDataProcessingOneSource {
+    fixed: Integer<u8, packed_bits::Bits<3>>,  // = 0b000,
+    fixed2: Integer<u8, packed_bits::Bits<5>>, // = 0b11110,
+    ptype: Integer<u8, packed_bits::Bits<2>>,
+    fixed3: bool, // = 0b1,
+    opcode: Integer<u8, packed_bits::Bits<6>>,
+    fixed4: Integer<u8, packed_bits::Bits<5>>, // = 0b10000,
+    rn: Integer<u8, packed_bits::Bits<5>>,
+    rd: Integer<u8, packed_bits::Bits<5>>,
+}
+
+impl Aarch64Bytes for FloatingPointDataProcessingOneSource {}
+
+impl FloatingPointDataProcessingOneSource {
+    #[inline(always)]
+    fn new(ptype: FloatType, opcode: u8, rn: AArch64FloatReg, rd: AArch64FloatReg) -> Self {
+        debug_assert!(opcode <= 0b111111);
+
+        Self {
+            rd: rd.id().into(),
+            rn: rn.id().into(),
+            fixed4: 0b10000.into(),
+            opcode: opcode.into(),
+            fixed3: true,
+            ptype: ptype.id().into(),
+            fixed2: 0b11110.into(),
+            fixed: 0b000.into(),
+        }
+    }
+}
+
+#[derive(PackedStruct, Debug)]
+#[packed_struct(endian = "msb")]
+pub struct FloatingPointDataProcessingTwoSource {
+    fixed: Integer<u8, packed_bits::Bits<3>>,  // = 0b000,
+    fixed2: Integer<u8, packed_bits::Bits<5>>, // = 0b11110,
+    ptype: Integer<u8, packed_bits::Bits<2>>,
+    fixed3: bool, // = 0b1,
+    rm: Integer<u8, packed_bits::Bits<5>>,
+    opcode: Integer<u8, packed_bits::Bits<4>>,
+    fixed4: Integer<u8, packed_bits::Bits<2>>, // = 0b10,
+    rn: Integer<u8, packed_bits::Bits<5>>,
+    rd: Integer<u8, packed_bits::Bits<5>>,
+}
+
+impl Aarch64Bytes for FloatingPointDataProcessingTwoSource {}
+
+impl FloatingPointDataProcessingTwoSource {
+    #[inline(always)]
+    fn new(
+        ptype: FloatType,
+        opcode: u8,
+        rm: AArch64FloatReg,
+        rn: AArch64FloatReg,
+        rd: AArch64FloatReg,
+    ) -> Self {
+        debug_assert!(opcode <= 0b1111);
+
+        Self {
+            rd: rd.id().into(),
+            rn: rn.id().into(),
+            fixed4: 0b10.into(),
+            opcode: opcode.into(),
+            rm: rm.id().into(),
+            fixed3: true,
+            ptype: ptype.id().into(),
+            fixed2: 0b11110.into(),
+            fixed: 0b000.into(),
+        }
+    }
+}
+
+#[derive(PackedStruct, Debug)]
+#[packed_struct(endian = "msb")]
+pub struct ConversionBetweenFloatAndInteger {
+    sf: bool,
+    fixed: Integer<u8, packed_bits::Bits<2>>,  // = 0b00,
+    fixed2: Integer<u8, packed_bits::Bits<5>>, // = 0b11110,
+    ptype: Integer<u8, packed_bits::Bits<2>>,
+    fixed3: bool, // = 0b1,
+    rmode: Integer<u8, packed_bits::Bits<2>>,
+    opcode: Integer<u8, packed_bits::Bits<3>>,
+    fixed4: Integer<u8, packed_bits::Bits<6>>, // = 0b000000,
+    rn: Integer<u8, packed_bits::Bits<5>>,
+    rd: Integer<u8, packed_bits::Bits<5>>,
+}
+
+impl Aarch64Bytes for ConversionBetweenFloatAndInteger {}
+
+impl ConversionBetweenFloatAndInteger {
+    /// Converts or moves the general register `rn` to the float register `rd`.
+    #[inline(always)]
+    fn new(
+        sf: bool,
+        ptype: FloatType,
+        opcode: u8,
+        rn: AArch64GeneralReg,
+        rd: AArch64FloatReg,
+    ) -> Self {
+        debug_assert!(opcode <= 0b111);
+
+        Self {
+            rd: rd.id().into(),
+            rn: rn.id().into(),
+            fixed4: 0b000000.into(),
+            opcode: opcode.into(),
+            rmode: 0b00.into(),
+            fixed3: true,
+            ptype: ptype.id().into(),
+            fixed2: 0b11110.into(),
+            fixed: 0b00.into(),
+            sf,
+        }
+    }
+}
+
+/// How a sized instruction accesses memory: loads of fewer than 8 bytes either sign or zero extend them.
+#[derive(Clone, Copy)]
+enum SizedAccess {
+    Load { signed: bool },
+    Store,
+}
+
+/// The size and opc fields of a load or store of `size` bytes of a general register. Loads either
+/// sign extend the bytes to 64 bits, or load them into the 32 bit register, which zeroes the rest.
+#[inline(always)]
+fn sized_size_and_opc(size: u8, access: SizedAccess) -> (u8, u8) {
+    let size = match size {
+        1 => 0b00,
+        2 => 0b01,
+        4 => 0b10,
+        _ => internal_error!("Invalid size for a sized load or store: {}", size),
+    };
+
+    let opc = match access {
+        SizedAccess::Store => 0b00,
+        SizedAccess::Load { signed: false } => 0b01,
+        SizedAccess::Load { signed: true } => 0b10,
+    };
+
+    (size, opc)
+}
+
+// Below here are the functions for all of the assembly instructions.
+// Their names are based on the instruction and operators combined.
+// You should call `buf.reserve()` if you push or extend more than once.
+// Unit tests are added at the bottom of the file to ensure correct asm generation.
+// Please keep these in alphanumeric order.
+
+/// `ADD Xd, Xn, imm12` -> Add Xn and imm12 and place the result into Xd.
+#[inline(always)]
+fn add_reg64_reg64_imm12(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src: AArch64GeneralReg,
+    imm12: u16,
+) {
+    let inst = ArithmeticImmediate::new(false, false, dst, src, imm12, false);
+
+    buf.extend(inst.bytes());
+}
+
+/// `ADD Xd, Xn, imm12, LSL 12` -> Add Xn and imm12 shifted left by 12 and place the result into Xd.
+#[inline(always)]
+fn add_reg64_reg64_imm12_lsl12(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src: AArch64GeneralReg,
+    imm12: u16,
+) {
+    let inst = ArithmeticImmediate::new(false, false, dst, src, imm12, true);
+
+    buf.extend(inst.bytes());
+}
+
+/// `ADD Xd, Xm, Xn` -> Add Xm and Xn and place the result into Xd.
+#[inline(always)]
+fn add_reg64_reg64_reg64(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src1: AArch64GeneralReg,
+    src2: AArch64GeneralReg,
+) {
+    let inst = ArithmeticShifted::new(false, false, ShiftType::LSL, 0, src2, src1, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `ADD Xd, Xn, Xm, UXTX` -> Add Xn and Xm and place the result into Xd. ZRSP is SP for Xd and Xn.
+#[inline(always)]
+fn add_reg64_reg64_reg64_uxtx(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src1: AArch64GeneralReg,
+    src2: AArch64GeneralReg,
+) {
+    let inst = ArithmeticExtended::new(false, src2, src1, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `ADDS Xd, Xm, Xn` -> Add Xm and Xn, place the result into Xd, and set the condition flags.
+#[inline(always)]
+fn adds_reg64_reg64_reg64(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src1: AArch64GeneralReg,
+    src2: AArch64GeneralReg,
+) {
+    let inst = ArithmeticShifted::new(false, true, ShiftType::LSL, 0, src2, src1, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `AND Xd, Xn, Xm` -> Bitwise and Xn and Xm and place the result into Xd.
+#[inline(always)]
+fn and_reg64_reg64_reg64(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src1: AArch64GeneralReg,
+    src2: AArch64GeneralReg,
+) {
+    let inst = LogicalShiftedRegister::new(LogicalOp::AND, ShiftType::LSL, 0, src2, src1, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `B.cond imm19` -> Branch to the offset imm19 (in instructions, from this instruction) if the condition holds.
+#[inline(always)]
+fn b_cond_imm19(buf: &mut Vec<'_, u8>, cond: ConditionCode, imm19: i32) {
+    let inst = ConditionalBranchImmediate::new(cond, imm19);
+
+    buf.extend(inst.bytes());
+}
+
+/// `B imm26` -> Branch to the offset imm26 (in instructions, from this instruction).
+#[inline(always)]
+fn b_imm26(buf: &mut Vec<'_, u8>, imm26: i32) {
+    let inst = UnconditionalBranchImmediate::new(false, (imm26 as u32) & 0x3FF_FFFF);
+
+    buf.extend(inst.bytes());
+}
+
+/// `BL imm26` -> Branch to the offset imm26 (in instructions) and store the return address in LR.
+#[inline(always)]
+fn bl_imm26(buf: &mut Vec<'_, u8>, imm26: u32) {
+    let inst = UnconditionalBranchImmediate::new(true, imm26);
+
+    buf.extend(inst.bytes());
+}
+
+/// `CMP Xn, imm12` -> Compare Xn and imm12 and set the condition flags. This is an alias of `SUBS XZR, Xn, imm12`. ZRSP is SP for Xn.
+#[inline(always)]
+fn cmp_reg64_imm12(buf: &mut Vec<'_, u8>, src: AArch64GeneralReg, imm12: u16) {
+    let inst = ArithmeticImmediate::new(true, true, AArch64GeneralReg::ZRSP, src, imm12, false);
+
+    buf.extend(inst.bytes());
+}
+
+/// `CMP Xn, Xm` -> Compare Xn and Xm and set the condition flags. This is an alias of `SUBS XZR, Xn, Xm`.
+#[inline(always)]
+fn cmp_reg64_reg64(buf: &mut Vec<'_, u8>, src1: AArch64GeneralReg, src2: AArch64GeneralReg) {
+    let inst = ArithmeticShifted::new(
+        true,
+        true,
+        ShiftType::LSL,
+        0,
+        src2,
+        src1,
+        AArch64GeneralReg::ZRSP,
+    );
+
+    buf.extend(inst.bytes());
+}
+
+/// `CNEG Xd, Xn, cond` -> Set Xd to the negation of Xn if the condition holds and to Xn otherwise. This is an alias of `CSNEG Xd, Xn, Xn, invert(cond)`.
+#[inline(always)]
+fn cneg_reg64_reg64_cond(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src: AArch64GeneralReg,
+    cond: ConditionCode,
+) {
+    let inst = ConditionalSelect::new(true, 0b01, cond.invert(), src, src, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `CSET Xd, cond` -> Set Xd to 1 if the condition holds and 0 otherwise. This is an alias of `CSINC Xd, XZR, XZR, invert(cond)`.
+#[inline(always)]
+fn cset_reg64_cond(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, cond: ConditionCode) {
+    let inst = ConditionalSelect::new(
+        false,
+        0b01,
+        cond.invert(),
+        AArch64GeneralReg::ZRSP,
+        AArch64GeneralReg::ZRSP,
+        dst,
+    );
+
+    buf.extend(inst.bytes());
+}
+
+/// `EOR Xd, Xn, Xm` -> Bitwise exclusive or Xn and Xm and place the result into Xd.
+#[inline(always)]
+fn eor_reg64_reg64_reg64(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src1: AArch64GeneralReg,
+    src2: AArch64GeneralReg,
+) {
+    let inst = LogicalShiftedRegister::new(LogicalOp::EOR, ShiftType::LSL, 0, src2, src1, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `FABS Dd, Dn` -> Take the absolute value of Dn and place the result into Dd.
+#[inline(always)]
+fn fabs_freg64_freg64(buf: &mut Vec<'_, u8>, dst: AArch64FloatReg, src: AArch64FloatReg) {
+    let inst = FloatingPointDataProcessingOneSource::new(FloatType::Double, 0b000001, src, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `FADD Sd, Sn, Sm` -> Add Sn and Sm and place the result into Sd.
+#[inline(always)]
+fn fadd_freg32_freg32_freg32(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64FloatReg,
+    src1: AArch64FloatReg,
+    src2: AArch64FloatReg,
+) {
+    let inst =
+        FloatingPointDataProcessingTwoSource::new(FloatType::Single, 0b0010, src2, src1, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `FADD Dd, Dn, Dm` -> Add Dn and Dm and place the result into Dd.
+#[inline(always)]
+fn fadd_freg64_freg64_freg64(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64FloatReg,
+    src1: AArch64FloatReg,
+    src2: AArch64FloatReg,
+) {
+    let inst =
+        FloatingPointDataProcessingTwoSource::new(FloatType::Double, 0b0010, src2, src1, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `FCVT Sd, Dn` -> Convert the double Dn to a single and place the result into Sd.
+#[inline(always)]
+fn fcvt_freg32_freg64(buf: &mut Vec<'_, u8>, dst: AArch64FloatReg, src: AArch64FloatReg) {
+    let inst = FloatingPointDataProcessingOneSource::new(FloatType::Double, 0b000100, src, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `FCVT Dd, Sn` -> Convert the single Sn to a double and place the result into Dd.
+#[inline(always)]
+fn fcvt_freg64_freg32(buf: &mut Vec<'_, u8>, dst: AArch64FloatReg, src: AArch64FloatReg) {
+    let inst = FloatingPointDataProcessingOneSource::new(FloatType::Single, 0b000101, src, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `FDIV Sd, Sn, Sm` -> Divide Sn by Sm and place the result into Sd.
+#[inline(always)]
+fn fdiv_freg32_freg32_freg32(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64FloatReg,
+    src1: AArch64FloatReg,
+    src2: AArch64FloatReg,
+) {
+    let inst =
+        FloatingPointDataProcessingTwoSource::new(FloatType::Single, 0b0001, src2, src1, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `FDIV Dd, Dn, Dm` -> Divide Dn by Dm and place the result into Dd.
+#[inline(always)]
+fn fdiv_freg64_freg64_freg64(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64FloatReg,
+    src1: AArch64FloatReg,
+    src2: AArch64FloatReg,
+) {
+    let inst =
+        FloatingPointDataProcessingTwoSource::new(FloatType::Double, 0b0001, src2, src1, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `FMOV Sd, Wn` -> Move the bits of Wn to Sd.
+#[inline(always)]
+fn fmov_freg32_reg32(buf: &mut Vec<'_, u8>, dst: AArch64FloatReg, src: AArch64GeneralReg) {
+    let inst = ConversionBetweenFloatAndInteger::new(false, FloatType::Single, 0b111, src, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `FMOV Dd, Dn` -> Move Dn to Dd.
+#[inline(always)]
+fn fmov_freg64_freg64(buf: &mut Vec<'_, u8>, dst: AArch64FloatReg, src: AArch64FloatReg) {
+    let inst = FloatingPointDataProcessingOneSource::new(FloatType::Double, 0b000000, src, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `FMOV Dd, Xn` -> Move the bits of Xn to Dd.
+#[inline(always)]
+fn fmov_freg64_reg64(buf: &mut Vec<'_, u8>, dst: AArch64FloatReg, src: AArch64GeneralReg) {
+    let inst = ConversionBetweenFloatAndInteger::new(true, FloatType::Double, 0b111, src, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `FMUL Sd, Sn, Sm` -> Multiply Sn and Sm and place the result into Sd.
+#[inline(always)]
+fn fmul_freg32_freg32_freg32(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64FloatReg,
+    src1: AArch64FloatReg,
+    src2: AArch64FloatReg,
+) {
+    let inst =
+        FloatingPointDataProcessingTwoSource::new(FloatType::Single, 0b0000, src2, src1, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `FMUL Dd, Dn, Dm` -> Multiply Dn and Dm and place the result into Dd.
+#[inline(always)]
+fn fmul_freg64_freg64_freg64(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64FloatReg,
+    src1: AArch64FloatReg,
+    src2: AArch64FloatReg,
+) {
+    let inst =
+        FloatingPointDataProcessingTwoSource::new(FloatType::Double, 0b0000, src2, src1, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `LDR Dt, [Xn, #offset]` -> Load Xn + Offset Dt. ZRSP is SP.
+/// Note: imm12 is the offest divided by 8.
+#[inline(always)]
+fn ldr_freg64_reg64_imm12(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64FloatReg,
+    base: AArch64GeneralReg,
+    imm12: u16,
+) {
+    let inst = LoadStoreRegisterImmediate::new_float_load(0b11, imm12, base, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `LDR Dt, [Xn, Xm]` -> Load Xn + Xm to Dt. ZRSP is SP for Xn.
+#[inline(always)]
+fn ldr_freg64_reg64_reg64(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64FloatReg,
+    base: AArch64GeneralReg,
+    offset: AArch64GeneralReg,
+) {
+    let inst = LoadStoreRegisterRegisterOffset::new(0b11, true, 0b01, offset, base, dst.id());
+
+    buf.extend(inst.bytes());
+}
+
+/// `LDR Xt, [Xn, #offset]` -> Load Xn + Offset Xt. ZRSP is SP.
+/// Note: imm12 is the offest divided by 8.
+#[inline(always)]
+fn ldr_reg64_reg64_imm12(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    base: AArch64GeneralReg,
+    imm12: u16,
+) {
+    let inst = LoadStoreRegisterImmediate::new_load(0b11, imm12, base, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `LDR Xt, [Xn, Xm]` -> Load Xn + Xm to Xt. ZRSP is SP for Xn.
+#[inline(always)]
+fn ldr_reg64_reg64_reg64(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    base: AArch64GeneralReg,
+    offset: AArch64GeneralReg,
+) {
+    let inst = LoadStoreRegisterRegisterOffset::new(0b11, false, 0b01, offset, base, dst.id());
+
+    buf.extend(inst.bytes());
+}
+
+/// `LDR{B,H,} Wt, [Xn, #offset]` or `LDRS{B,H,W} Xt, [Xn, #offset]` -> Load `size` bytes at Xn + Offset to Xt,
+/// sign extending them if `signed` and zero extending them otherwise. ZRSP is SP.
+/// Note: imm12 is the offest divided by `size`.
+#[inline(always)]
+fn ldr_sized_reg64_reg64_imm12(
+    buf: &mut Vec<'_, u8>,
+    size: u8,
+    signed: bool,
+    dst: AArch64GeneralReg,
+    base: AArch64GeneralReg,
+    imm12: u16,
+) {
+    let (size, opc) = sized_size_and_opc(size, SizedAccess::Load { signed });
+    let inst = LoadStoreRegisterImmediate::new(size, false, opc, imm12, base, dst.id());
+
+    buf.extend(inst.bytes());
+}
+
+/// `LDR{B,H,} Wt, [Xn, Xm]` or `LDRS{B,H,W} Xt, [Xn, Xm]` -> Load `size` bytes at Xn + Xm to Xt,
+/// sign extending them if `signed` and zero extending them otherwise. ZRSP is SP for Xn.
+#[inline(always)]
+fn ldr_sized_reg64_reg64_reg64(
+    buf: &mut Vec<'_, u8>,
+    size: u8,
+    signed: bool,
+    dst: AArch64GeneralReg,
+    base: AArch64GeneralReg,
+    offset: AArch64GeneralReg,
+) {
+    let (size, opc) = sized_size_and_opc(size, SizedAccess::Load { signed });
+    let inst = LoadStoreRegisterRegisterOffset::new(size, false, opc, offset, base, dst.id());
+
+    buf.extend(inst.bytes());
+}
+
+/// `LDUR Dt, [Xn, #offset]` -> Load Xn + Offset to Dt. ZRSP is SP.
+/// Note: the offset is signed and not scaled.
+#[inline(always)]
+fn ldur_freg64_reg64_imm9(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64FloatReg,
+    base: AArch64GeneralReg,
+    imm9: i16,
+) {
+    let inst = LoadStoreRegisterUnscaledImmediate::new(0b11, true, 0b01, imm9, base, dst.id());
+
+    buf.extend(inst.bytes());
+}
+
+/// `LDUR Xt, [Xn, #offset]` -> Load Xn + Offset to Xt. ZRSP is SP.
+/// Note: the offset is signed and not scaled.
+#[inline(always)]
+fn ldur_reg64_reg64_imm9(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    base: AArch64GeneralReg,
+    imm9: i16,
+) {
+    let inst = LoadStoreRegisterUnscaledImmediate::new(0b11, false, 0b01, imm9, base, dst.id());
+
+    buf.extend(inst.bytes());
+}
+
+/// `LDUR{B,H,} Wt, [Xn, #offset]` or `LDURS{B,H,W} Xt, [Xn, #offset]` -> Load `size` bytes at Xn + Offset to Xt,
+/// sign extending them if `signed` and zero extending them otherwise. ZRSP is SP.
+/// Note: the offset is signed and not scaled.
+#[inline(always)]
+fn ldur_sized_reg64_reg64_imm9(
+    buf: &mut Vec<'_, u8>,
+    size: u8,
+    signed: bool,
+    dst: AArch64GeneralReg,
+    base: AArch64GeneralReg,
+    imm9: i16,
+) {
+    let (size, opc) = sized_size_and_opc(size, SizedAccess::Load { signed });
+    let inst = LoadStoreRegisterUnscaledImmediate::new(size, false, opc, imm9, base, dst.id());
+
+    buf.extend(inst.bytes());
+}
+
+/// `MOV Xd, Xm` -> Move Xm to Xd.
+#[inline(always)]
+fn mov_reg64_reg64(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, src: AArch64GeneralReg) {
+    // MOV is equvalent to `ORR Xd, XZR, XM` in AARCH64.
+    let inst = LogicalShiftedRegister::new(
+        LogicalOp::ORR,
+        ShiftType::LSL,
+        0,
+        src,
+        AArch64GeneralReg::ZRSP,
+        dst,
+    );
+
+    buf.extend(inst.bytes());
+}
+
+/// `MOVK Xd, imm16` -> Keeps Xd and moves an optionally shifted imm16 to Xd.
+#[inline(always)]
+fn movk_reg64_imm16(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, imm16: u16, hw: u8) {
+    let inst = MoveWideImmediate::new(0b11, dst, imm16, hw, true);
+
+    buf.extend(inst.bytes());
+}
+
+/// `MOVZ Xd, imm16` -> Zeros Xd and moves an optionally shifted imm16 to Xd.
+#[inline(always)]
+fn movz_reg64_imm16(buf: &mut Vec<'_, u8>, dst: AArch64GeneralReg, imm16: u16, hw: u8) {
+    let inst = MoveWideImmediate::new(0b10, dst, imm16, hw, true);
+
+    buf.extend(inst.bytes());
+}
+
+/// `MUL Xd, Xn, Xm` -> Multiply Xn and Xm and place the low 64 bits of the result into Xd.
+/// This is an alias of `MADD Xd, Xn, Xm, XZR`.
+#[inline(always)]
+fn mul_reg64_reg64_reg64(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src1: AArch64GeneralReg,
+    src2: AArch64GeneralReg,
+) {
+    let inst =
+        DataProcessingThreeSource::new(0b000, false, src2, AArch64GeneralReg::ZRSP, src1, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `ORR Xd, Xn, Xm` -> Bitwise or Xn and Xm and place the result into Xd.
+#[inline(always)]
+fn orr_reg64_reg64_reg64(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src1: AArch64GeneralReg,
+    src2: AArch64GeneralReg,
+) {
+    let inst = LogicalShiftedRegister::new(LogicalOp::ORR, ShiftType::LSL, 0, src2, src1, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `SCVTF Sd, Xn` -> Convert the signed integer Xn to a single and place the result into Sd.
+#[inline(always)]
+fn scvtf_freg32_reg64(buf: &mut Vec<'_, u8>, dst: AArch64FloatReg, src: AArch64GeneralReg) {
+    let inst = ConversionBetweenFloatAndInteger::new(true, FloatType::Single, 0b010, src, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `SCVTF Dd, Xn` -> Convert the signed integer Xn to a double and place the result into Dd.
+#[inline(always)]
+fn scvtf_freg64_reg64(buf: &mut Vec<'_, u8>, dst: AArch64FloatReg, src: AArch64GeneralReg) {
+    let inst = ConversionBetweenFloatAndInteger::new(true, FloatType::Double, 0b010, src, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `SDIV Xd, Xn, Xm` -> Divide Xn by Xm, as signed integers rounding towards zero, and place the result into Xd.
+/// Note: dividing by zero results in zero rather than a trap.
+#[inline(always)]
+fn sdiv_reg64_reg64_reg64(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src1: AArch64GeneralReg,
+    src2: AArch64GeneralReg,
+) {
+    let inst = DataProcessingTwoSource::new(0b000011, src2, src1, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `STR Dt, [Xn, #offset]` -> Store Dt to Xn + Offset. ZRSP is SP.
+/// Note: imm12 is the offest divided by 8.
+#[inline(always)]
+fn str_freg64_reg64_imm12(
+    buf: &mut Vec<'_, u8>,
+    src: AArch64FloatReg,
+    base: AArch64GeneralReg,
+    imm12: u16,
+) {
+    let inst = LoadStoreRegisterImmediate::new_float_store(0b11, imm12, base, src);
+
+    buf.extend(inst.bytes());
+}
+
+/// `STR Dt, [Xn, Xm]` -> Store Dt to Xn + Xm. ZRSP is SP for Xn.
+#[inline(always)]
+fn str_freg64_reg64_reg64(
+    buf: &mut Vec<'_, u8>,
+    src: AArch64FloatReg,
+    base: AArch64GeneralReg,
+    offset: AArch64GeneralReg,
+) {
+    let inst = LoadStoreRegisterRegisterOffset::new(0b11, true, 0b00, offset, base, src.id());
+
+    buf.extend(inst.bytes());
+}
+
+/// `STR Xt, [Xn, #offset]` -> Store Xt to Xn + Offset. ZRSP is SP.
+/// Note: imm12 is the offest divided by 8.
+#[inline(always)]
+fn str_reg64_reg64_imm12(
+    buf: &mut Vec<'_, u8>,
+    src: AArch64GeneralReg,
+    base: AArch64GeneralReg,
+    imm12: u16,
+) {
+    let inst = LoadStoreRegisterImmediate::new_store(0b11, imm12, base, src);
+
+    buf.extend(inst.bytes());
+}
+
+/// `STR Xt, [Xn, Xm]` -> Store Xt to Xn + Xm. ZRSP is SP for Xn.
+#[inline(always)]
+fn str_reg64_reg64_reg64(
+    buf: &mut Vec<'_, u8>,
+    src: AArch64GeneralReg,
+    base: AArch64GeneralReg,
+    offset: AArch64GeneralReg,
+) {
+    let inst = LoadStoreRegisterRegisterOffset::new(0b11, false, 0b00, offset, base, src.id());
+
+    buf.extend(inst.bytes());
+}
+
+/// `STR{B,H,} Wt, [Xn, #offset]` -> Store the low `size` bytes of Xt to Xn + Offset. ZRSP is SP.
+/// Note: imm12 is the offest divided by `size`.
+#[inline(always)]
+fn str_sized_reg64_reg64_imm12(
+    buf: &mut Vec<'_, u8>,
+    size: u8,
+    src: AArch64GeneralReg,
+    base: AArch64GeneralReg,
+    imm12: u16,
+) {
+    let (size, opc) = sized_size_and_opc(size, SizedAccess::Store);
+    let inst = LoadStoreRegisterImmediate::new(size, false, opc, imm12, base, src.id());
+
+    buf.extend(inst.bytes());
+}
+
+/// `STR{B,H,} Wt, [Xn, Xm]` -> Store the low `size` bytes of Xt to Xn + Xm. ZRSP is SP for Xn.
+#[inline(always)]
+fn str_sized_reg64_reg64_reg64(
+    buf: &mut Vec<'_, u8>,
+    size: u8,
+    src: AArch64GeneralReg,
+    base: AArch64GeneralReg,
+    offset: AArch64GeneralReg,
+) {
+    let (size, opc) = sized_size_and_opc(size, SizedAccess::Store);
+    let inst = LoadStoreRegisterRegisterOffset::new(size, false, opc, offset, base, src.id());
+
+    buf.extend(inst.bytes());
+}
+
+/// `STUR Dt, [Xn, #offset]` -> Store Dt to Xn + Offset. ZRSP is SP.
+/// Note: the offset is signed and not scaled.
+#[inline(always)]
+fn stur_freg64_reg64_imm9(
+    buf: &mut Vec<'_, u8>,
+    src: AArch64FloatReg,
+    base: AArch64GeneralReg,
+    imm9: i16,
+) {
+    let inst = LoadStoreRegisterUnscaledImmediate::new(0b11, true, 0b00, imm9, base, src.id());
+
+    buf.extend(inst.bytes());
+}
+
+/// `STUR Xt, [Xn, #offset]` -> Store Xt to Xn + Offset. ZRSP is SP.
+/// Note: the offset is signed and not scaled.
+#[inline(always)]
+fn stur_reg64_reg64_imm9(
+    buf: &mut Vec<'_, u8>,
+    src: AArch64GeneralReg,
+    base: AArch64GeneralReg,
+    imm9: i16,
+) {
+    let inst = LoadStoreRegisterUnscaledImmediate::new(0b11, false, 0b00, imm9, base, src.id());
+
+    buf.extend(inst.bytes());
+}
+
+/// `STUR{B,H,} Wt, [Xn, #offset]` -> Store the low `size` bytes of Xt to Xn + Offset. ZRSP is SP.
+/// Note: the offset is signed and not scaled.
+#[inline(always)]
+fn stur_sized_reg64_reg64_imm9(
+    buf: &mut Vec<'_, u8>,
+    size: u8,
+    src: AArch64GeneralReg,
+    base: AArch64GeneralReg,
+    imm9: i16,
+) {
+    let (size, opc) = sized_size_and_opc(size, SizedAccess::Store);
+    let inst = LoadStoreRegisterUnscaledImmediate::new(size, false, opc, imm9, base, src.id());
+
+    buf.extend(inst.bytes());
+}
+
+/// `SUB Xd, Xn, imm12` -> Subtract Xn and imm12 and place the result into Xd.
+#[inline(always)]
+fn sub_reg64_reg64_imm12(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src: AArch64GeneralReg,
+    imm12: u16,
+) {
+    let inst = ArithmeticImmediate::new(true, false, dst, src, imm12, false);
+
+    buf.extend(inst.bytes());
+}
+
+/// `SUB Xd, Xn, imm12, LSL 12` -> Subtract Xn and imm12 shifted left by 12 and place the result into Xd.
+#[inline(always)]
+fn sub_reg64_reg64_imm12_lsl12(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src: AArch64GeneralReg,
+    imm12: u16,
+) {
+    let inst = ArithmeticImmediate::new(true, false, dst, src, imm12, true);
+
+    buf.extend(inst.bytes());
+}
+
+/// `SUB Xd, Xm, Xn` -> Subtract Xm and Xn and place the result into Xd.
+#[inline(always)]
+fn sub_reg64_reg64_reg64(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src1: AArch64GeneralReg,
+    src2: AArch64GeneralReg,
+) {
+    let inst = ArithmeticShifted::new(true, false, ShiftType::LSL, 0, src2, src1, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `SUB Xd, Xn, Xm, UXTX` -> Subtract Xn and Xm and place the result into Xd. ZRSP is SP for Xd and Xn.
+#[inline(always)]
+fn sub_reg64_reg64_reg64_uxtx(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src1: AArch64GeneralReg,
+    src2: AArch64GeneralReg,
+) {
+    let inst = ArithmeticExtended::new(true, src2, src1, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `UDIV Xd, Xn, Xm` -> Divide Xn by Xm, as unsigned integers, and place the result into Xd.
+/// Note: dividing by zero results in zero rather than a trap.
+#[inline(always)]
+fn udiv_reg64_reg64_reg64(
+    buf: &mut Vec<'_, u8>,
+    dst: AArch64GeneralReg,
+    src1: AArch64GeneralReg,
+    src2: AArch64GeneralReg,
+) {
+    let inst = DataProcessingTwoSource::new(0b000010, src2, src1, dst);
+
+    buf.extend(inst.bytes());
+}
+
+/// `RET Xn` -> Return to the address stored in Xn.
+#[inline(always)]
+fn ret_reg64(buf: &mut Vec<'_, u8>, xn: AArch64GeneralReg) {
+    let inst = UnconditionalBranchRegister::new(0b10, xn);
+
+    buf.extend(inst.bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::disassembler_test;
+    use crate::generic64::disassembler_test_macro::merge_instructions_without_line_numbers;
+    use capstone::prelude::*;
+
+    enum ZRSPKind {
+        UsesZR,
+        UsesSP,
+    }
+    use ZRSPKind::*;
+
+    impl AArch64GeneralReg {
+        fn capstone_string(&self, zrsp_kind: ZRSPKind) -> String {
+            match self {
                 AArch64GeneralReg::XR => "x8".to_owned(),
                 AArch64GeneralReg::IP0 => "x16".to_owned(),
                 AArch64GeneralReg::IP1 => "x17".to_owned(),
@@ -1313,6 +2993,61 @@ mod tests {
                 _ => format!("{}", self),
             }
         }
+
+        fn capstone_string_32bit(&self) -> String {
+            match self {
+                AArch64GeneralReg::ZRSP => "wzr".to_owned(),
+                _ => format!("w{}", self.id()),
+            }
+        }
+    }
+
+    impl AArch64FloatReg {
+        fn capstone_string(&self, float_width: FloatWidth) -> String {
+            match float_width {
+                FloatWidth::F32 => format!("s{}", self.id()),
+                FloatWidth::F64 => format!("d{}", self.id()),
+            }
+        }
+    }
+
+    fn signed_hex(imm: i16) -> String {
+        if imm < 0 {
+            format!("#-0x{:x}", -imm)
+        } else {
+            format!("#0x{:x}", imm)
+        }
+    }
+
+    /// The sizes and signedness of every sized load
+    const SIZED_LOADS: [(u8, bool); 6] = [
+        (1, false),
+        (1, true),
+        (2, false),
+        (2, true),
+        (4, false),
+        (4, true),
+    ];
+
+    fn sized_mnemonic(op: &str, size: u8, signed: bool) -> String {
+        let width = match (size, signed) {
+            (1, _) => "b",
+            (2, _) => "h",
+            (4, true) => "w",
+            (4, false) => "",
+            _ => unreachable!(),
+        };
+
+        format!("{}{}{}", op, if signed { "s" } else { "" }, width)
+    }
+
+    /// Sign extending loads write the 64 bit register, and the others the 32 bit one.
+    fn sized_capstone_string(reg: AArch64GeneralReg, signed: bool) -> String {
+        if signed {
+            reg.capstone_string(UsesZR)
+        } else {
+            reg.capstone_string_32bit()
+        }
     }
 
     const TEST_U16: u16 = 0x1234;
@@ -1354,27 +3089,537 @@ mod tests {
         AArch64GeneralReg::ZRSP,
     ];
 
-    fn setup_capstone_and_arena<T>(
-        arena: &bumpalo::Bump,
-    ) -> (bumpalo::collections::Vec<T>, Capstone) {
-        let buf = bumpalo::vec![in arena];
-        let cs = Capstone::new()
-            .arm64()
-            .mode(arch::arm64::ArchMode::Arm)
-            .detail(true)
-            .build()
-            .expect("Failed to create Capstone object");
-        (buf, cs)
+    const ALL_FLOAT_REGS: &[AArch64FloatReg] = &[
+        AArch64FloatReg::V0,
+        AArch64FloatReg::V1,
+        AArch64FloatReg::V2,
+        AArch64FloatReg::V3,
+        AArch64FloatReg::V4,
+        AArch64FloatReg::V5,
+        AArch64FloatReg::V6,
+        AArch64FloatReg::V7,
+        AArch64FloatReg::V8,
+        AArch64FloatReg::V9,
+        AArch64FloatReg::V10,
+        AArch64FloatReg::V11,
+        AArch64FloatReg::V12,
+        AArch64FloatReg::V13,
+        AArch64FloatReg::V14,
+        AArch64FloatReg::V15,
+        AArch64FloatReg::V16,
+        AArch64FloatReg::V17,
+        AArch64FloatReg::V18,
+        AArch64FloatReg::V19,
+        AArch64FloatReg::V20,
+        AArch64FloatReg::V21,
+        AArch64FloatReg::V22,
+        AArch64FloatReg::V23,
+        AArch64FloatReg::V24,
+        AArch64FloatReg::V25,
+        AArch64FloatReg::V26,
+        AArch64FloatReg::V27,
+        AArch64FloatReg::V28,
+        AArch64FloatReg::V29,
+        AArch64FloatReg::V30,
+        AArch64FloatReg::V31,
+    ];
+
+    fn setup_capstone_and_arena<T>(
+        arena: &bumpalo::Bump,
+    ) -> (bumpalo::collections::Vec<T>, Capstone) {
+        let buf = bumpalo::vec![in arena];
+        let cs = Capstone::new()
+            .arm64()
+            .mode(arch::arm64::ArchMode::Arm)
+            .detail(true)
+            .build()
+            .expect("Failed to create Capstone object");
+        (buf, cs)
+    }
+
+    #[test]
+    fn test_add_reg64_reg64_reg64() {
+        disassembler_test!(
+            add_reg64_reg64_reg64,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, reg3: AArch64GeneralReg| format!(
+                "add {}, {}, {}",
+                reg1.capstone_string(UsesZR),
+                reg2.capstone_string(UsesZR),
+                reg3.capstone_string(UsesZR)
+            ),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_add_reg64_reg64_imm12() {
+        disassembler_test!(
+            add_reg64_reg64_imm12,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, imm| format!(
+                "add {}, {}, #0x{:x}",
+                reg1.capstone_string(UsesSP),
+                reg2.capstone_string(UsesSP),
+                imm
+            ),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            [0x123]
+        );
+    }
+
+    #[test]
+    fn test_add_reg64_reg64_imm12_lsl12() {
+        disassembler_test!(
+            add_reg64_reg64_imm12_lsl12,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, imm| format!(
+                "add {}, {}, #0x{:x}, lsl #12",
+                reg1.capstone_string(UsesSP),
+                reg2.capstone_string(UsesSP),
+                imm
+            ),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            [0x123]
+        );
+    }
+
+    #[test]
+    fn test_add_reg64_reg64_reg64_uxtx() {
+        disassembler_test!(
+            add_reg64_reg64_reg64_uxtx,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, reg3: AArch64GeneralReg| {
+                // With SP, the extension is written as a shift of zero, which is left out.
+                let extension =
+                    if reg1 == AArch64GeneralReg::ZRSP || reg2 == AArch64GeneralReg::ZRSP {
+                        ""
+                    } else {
+                        ", uxtx"
+                    };
+
+                format!(
+                    "add {}, {}, {}{}",
+                    reg1.capstone_string(UsesSP),
+                    reg2.capstone_string(UsesSP),
+                    reg3.capstone_string(UsesZR),
+                    extension
+                )
+            },
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_adds_reg64_reg64_reg64() {
+        disassembler_test!(
+            adds_reg64_reg64_reg64,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, reg3: AArch64GeneralReg| format!(
+                "adds {}, {}, {}",
+                reg1.capstone_string(UsesZR),
+                reg2.capstone_string(UsesZR),
+                reg3.capstone_string(UsesZR)
+            ),
+            // With XZR as the destination, this disassembles as `CMN Xn, Xm` instead.
+            ALL_GENERAL_REGS[..ALL_GENERAL_REGS.len() - 1],
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_add_reg64_reg64_imm32() {
+        let arena = bumpalo::Bump::new();
+        let (mut buf, cs) = setup_capstone_and_arena(&arena);
+
+        // The magnitude of `i32::MIN` does not fit in an i32, nor in the immediate.
+        AArch64Assembler::add_reg64_reg64_imm32(
+            &mut buf,
+            AArch64GeneralReg::ZRSP,
+            AArch64GeneralReg::ZRSP,
+            i32::MIN,
+        );
+        let instructions = merge_instructions_without_line_numbers(cs.disasm_all(&buf, 0).unwrap());
+        assert_eq!(instructions.lines().last(), Some("sub sp, sp, x16"));
+
+        buf.clear();
+        AArch64Assembler::sub_reg64_reg64_imm32(
+            &mut buf,
+            AArch64GeneralReg::X0,
+            AArch64GeneralReg::X1,
+            -0x12345,
+        );
+        assert_eq!(
+            merge_instructions_without_line_numbers(cs.disasm_all(&buf, 0).unwrap()),
+            "add x0, x1, #0x12, lsl #12\nadd x0, x0, #0x345"
+        );
+    }
+
+    #[test]
+    fn test_and_reg64_reg64_reg64() {
+        disassembler_test!(
+            and_reg64_reg64_reg64,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, reg3: AArch64GeneralReg| format!(
+                "and {}, {}, {}",
+                reg1.capstone_string(UsesZR),
+                reg2.capstone_string(UsesZR),
+                reg3.capstone_string(UsesZR)
+            ),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_b_cond_imm19() {
+        disassembler_test!(
+            b_cond_imm19,
+            |cond: ConditionCode, imm| format!(
+                "b.{} #0x{:x}",
+                format!("{:?}", cond).to_lowercase(),
+                imm << 2
+            ),
+            [ConditionCode::EQ, ConditionCode::NE, ConditionCode::LT],
+            [0x1234]
+        );
+    }
+
+    #[test]
+    fn test_b_imm26() {
+        disassembler_test!(b_imm26, |imm| format!("b #0x{:x}", imm << 2), [0x1234]);
+    }
+
+    #[test]
+    fn test_bl_imm26() {
+        disassembler_test!(bl_imm26, |imm| format!("bl #0x{:x}", imm << 2), [0x1234]);
+    }
+
+    #[test]
+    fn test_cmp_reg64_imm12() {
+        disassembler_test!(
+            cmp_reg64_imm12,
+            |reg1: AArch64GeneralReg, imm| format!(
+                "cmp {}, #0x{:x}",
+                reg1.capstone_string(UsesSP),
+                imm
+            ),
+            ALL_GENERAL_REGS,
+            [0x123]
+        );
+    }
+
+    #[test]
+    fn test_cmp_reg64_reg64() {
+        disassembler_test!(
+            cmp_reg64_reg64,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg| format!(
+                "cmp {}, {}",
+                reg1.capstone_string(UsesZR),
+                reg2.capstone_string(UsesZR)
+            ),
+            // With XZR as the first operand, this disassembles as `NEGS XZR, Xm` instead.
+            ALL_GENERAL_REGS[..ALL_GENERAL_REGS.len() - 1],
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_cneg_reg64_reg64_cond() {
+        disassembler_test!(
+            cneg_reg64_reg64_cond,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, cond: ConditionCode| format!(
+                "cneg {}, {}, {}",
+                reg1.capstone_string(UsesZR),
+                reg2.capstone_string(UsesZR),
+                format!("{:?}", cond).to_lowercase()
+            ),
+            ALL_GENERAL_REGS,
+            // With XZR as the source, this disassembles as `CSETM` or `CSINC` instead.
+            ALL_GENERAL_REGS[..ALL_GENERAL_REGS.len() - 1],
+            [ConditionCode::LT, ConditionCode::GE]
+        );
+    }
+
+    #[test]
+    fn test_cset_reg64_cond() {
+        disassembler_test!(
+            cset_reg64_cond,
+            |reg1: AArch64GeneralReg, cond: ConditionCode| format!(
+                "cset {}, {}",
+                reg1.capstone_string(UsesZR),
+                format!("{:?}", cond).to_lowercase()
+            ),
+            ALL_GENERAL_REGS,
+            [
+                ConditionCode::EQ,
+                ConditionCode::NE,
+                ConditionCode::HS,
+                ConditionCode::LO,
+                ConditionCode::VS,
+                ConditionCode::VC,
+                ConditionCode::GE,
+                ConditionCode::LT,
+                ConditionCode::GT,
+                ConditionCode::LE,
+            ]
+        );
+    }
+
+    #[test]
+    fn test_eor_reg64_reg64_reg64() {
+        disassembler_test!(
+            eor_reg64_reg64_reg64,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, reg3: AArch64GeneralReg| format!(
+                "eor {}, {}, {}",
+                reg1.capstone_string(UsesZR),
+                reg2.capstone_string(UsesZR),
+                reg3.capstone_string(UsesZR)
+            ),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_fabs_freg64_freg64() {
+        disassembler_test!(
+            fabs_freg64_freg64,
+            |reg1: AArch64FloatReg, reg2: AArch64FloatReg| format!(
+                "fabs {}, {}",
+                reg1.capstone_string(FloatWidth::F64),
+                reg2.capstone_string(FloatWidth::F64)
+            ),
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS
+        );
+    }
+
+    #[test]
+    fn test_fadd_freg32_freg32_freg32() {
+        disassembler_test!(
+            fadd_freg32_freg32_freg32,
+            |reg1: AArch64FloatReg, reg2: AArch64FloatReg, reg3: AArch64FloatReg| format!(
+                "fadd {}, {}, {}",
+                reg1.capstone_string(FloatWidth::F32),
+                reg2.capstone_string(FloatWidth::F32),
+                reg3.capstone_string(FloatWidth::F32)
+            ),
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS
+        );
+    }
+
+    #[test]
+    fn test_fadd_freg64_freg64_freg64() {
+        disassembler_test!(
+            fadd_freg64_freg64_freg64,
+            |reg1: AArch64FloatReg, reg2: AArch64FloatReg, reg3: AArch64FloatReg| format!(
+                "fadd {}, {}, {}",
+                reg1.capstone_string(FloatWidth::F64),
+                reg2.capstone_string(FloatWidth::F64),
+                reg3.capstone_string(FloatWidth::F64)
+            ),
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS
+        );
+    }
+
+    #[test]
+    fn test_fcvt_freg32_freg64() {
+        disassembler_test!(
+            fcvt_freg32_freg64,
+            |reg1: AArch64FloatReg, reg2: AArch64FloatReg| format!(
+                "fcvt {}, {}",
+                reg1.capstone_string(FloatWidth::F32),
+                reg2.capstone_string(FloatWidth::F64)
+            ),
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS
+        );
+    }
+
+    #[test]
+    fn test_fcvt_freg64_freg32() {
+        disassembler_test!(
+            fcvt_freg64_freg32,
+            |reg1: AArch64FloatReg, reg2: AArch64FloatReg| format!(
+                "fcvt {}, {}",
+                reg1.capstone_string(FloatWidth::F64),
+                reg2.capstone_string(FloatWidth::F32)
+            ),
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS
+        );
+    }
+
+    #[test]
+    fn test_fdiv_freg32_freg32_freg32() {
+        disassembler_test!(
+            fdiv_freg32_freg32_freg32,
+            |reg1: AArch64FloatReg, reg2: AArch64FloatReg, reg3: AArch64FloatReg| format!(
+                "fdiv {}, {}, {}",
+                reg1.capstone_string(FloatWidth::F32),
+                reg2.capstone_string(FloatWidth::F32),
+                reg3.capstone_string(FloatWidth::F32)
+            ),
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS
+        );
+    }
+
+    #[test]
+    fn test_fdiv_freg64_freg64_freg64() {
+        disassembler_test!(
+            fdiv_freg64_freg64_freg64,
+            |reg1: AArch64FloatReg, reg2: AArch64FloatReg, reg3: AArch64FloatReg| format!(
+                "fdiv {}, {}, {}",
+                reg1.capstone_string(FloatWidth::F64),
+                reg2.capstone_string(FloatWidth::F64),
+                reg3.capstone_string(FloatWidth::F64)
+            ),
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS
+        );
+    }
+
+    #[test]
+    fn test_fmov_freg32_reg32() {
+        disassembler_test!(
+            fmov_freg32_reg32,
+            |reg1: AArch64FloatReg, reg2: AArch64GeneralReg| format!(
+                "fmov {}, {}",
+                reg1.capstone_string(FloatWidth::F32),
+                reg2.capstone_string_32bit()
+            ),
+            ALL_FLOAT_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_fmov_freg64_freg64() {
+        disassembler_test!(
+            fmov_freg64_freg64,
+            |reg1: AArch64FloatReg, reg2: AArch64FloatReg| format!(
+                "fmov {}, {}",
+                reg1.capstone_string(FloatWidth::F64),
+                reg2.capstone_string(FloatWidth::F64)
+            ),
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS
+        );
+    }
+
+    #[test]
+    fn test_fmov_freg64_reg64() {
+        disassembler_test!(
+            fmov_freg64_reg64,
+            |reg1: AArch64FloatReg, reg2: AArch64GeneralReg| format!(
+                "fmov {}, {}",
+                reg1.capstone_string(FloatWidth::F64),
+                reg2.capstone_string(UsesZR)
+            ),
+            ALL_FLOAT_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_fmul_freg32_freg32_freg32() {
+        disassembler_test!(
+            fmul_freg32_freg32_freg32,
+            |reg1: AArch64FloatReg, reg2: AArch64FloatReg, reg3: AArch64FloatReg| format!(
+                "fmul {}, {}, {}",
+                reg1.capstone_string(FloatWidth::F32),
+                reg2.capstone_string(FloatWidth::F32),
+                reg3.capstone_string(FloatWidth::F32)
+            ),
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS
+        );
+    }
+
+    #[test]
+    fn test_fmul_freg64_freg64_freg64() {
+        disassembler_test!(
+            fmul_freg64_freg64_freg64,
+            |reg1: AArch64FloatReg, reg2: AArch64FloatReg, reg3: AArch64FloatReg| format!(
+                "fmul {}, {}, {}",
+                reg1.capstone_string(FloatWidth::F64),
+                reg2.capstone_string(FloatWidth::F64),
+                reg3.capstone_string(FloatWidth::F64)
+            ),
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS,
+            ALL_FLOAT_REGS
+        );
+    }
+
+    #[test]
+    fn test_ldr_freg64_reg64_imm12() {
+        disassembler_test!(
+            ldr_freg64_reg64_imm12,
+            |reg1: AArch64FloatReg, reg2: AArch64GeneralReg, imm| format!(
+                "ldr {}, [{}, #0x{:x}]",
+                reg1.capstone_string(FloatWidth::F64),
+                reg2.capstone_string(UsesSP),
+                imm << 3
+            ),
+            ALL_FLOAT_REGS,
+            ALL_GENERAL_REGS,
+            [0x123]
+        );
+    }
+
+    #[test]
+    fn test_ldr_freg64_reg64_reg64() {
+        disassembler_test!(
+            ldr_freg64_reg64_reg64,
+            |reg1: AArch64FloatReg, reg2: AArch64GeneralReg, reg3: AArch64GeneralReg| format!(
+                "ldr {}, [{}, {}]",
+                reg1.capstone_string(FloatWidth::F64),
+                reg2.capstone_string(UsesSP),
+                reg3.capstone_string(UsesZR)
+            ),
+            ALL_FLOAT_REGS,
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
     }
 
     #[test]
-    fn test_add_reg64_reg64_reg64() {
+    fn test_ldr_reg64_reg64_imm12() {
         disassembler_test!(
-            add_reg64_reg64_reg64,
+            ldr_reg64_reg64_imm12,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, imm| format!(
+                "ldr {}, [{}, #0x{:x}]",
+                reg1.capstone_string(UsesZR),
+                reg2.capstone_string(UsesSP),
+                imm << 3
+            ),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            [0x123]
+        );
+    }
+
+    #[test]
+    fn test_ldr_reg64_reg64_reg64() {
+        disassembler_test!(
+            ldr_reg64_reg64_reg64,
             |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, reg3: AArch64GeneralReg| format!(
-                "add {}, {}, {}",
+                "ldr {}, [{}, {}]",
                 reg1.capstone_string(UsesZR),
-                reg2.capstone_string(UsesZR),
+                reg2.capstone_string(UsesSP),
                 reg3.capstone_string(UsesZR)
             ),
             ALL_GENERAL_REGS,
@@ -1384,34 +3629,114 @@ mod tests {
     }
 
     #[test]
-    fn test_add_reg64_reg64_imm12() {
+    fn test_ldr_sized_reg64_reg64_imm12() {
+        for (size, signed) in SIZED_LOADS {
+            disassembler_test!(
+                |buf: &mut Vec<'_, u8>, reg1, reg2| ldr_sized_reg64_reg64_imm12(
+                    buf, size, signed, reg1, reg2, 0x123
+                ),
+                |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg| format!(
+                    "{} {}, [{}, #0x{:x}]",
+                    sized_mnemonic("ldr", size, signed),
+                    sized_capstone_string(reg1, signed),
+                    reg2.capstone_string(UsesSP),
+                    0x123 * size as u32
+                ),
+                ALL_GENERAL_REGS,
+                ALL_GENERAL_REGS
+            );
+        }
+    }
+
+    #[test]
+    fn test_ldr_sized_reg64_reg64_reg64() {
+        for (size, signed) in SIZED_LOADS {
+            disassembler_test!(
+                |buf: &mut Vec<'_, u8>, reg1, reg2, reg3| ldr_sized_reg64_reg64_reg64(
+                    buf, size, signed, reg1, reg2, reg3
+                ),
+                |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, reg3: AArch64GeneralReg| {
+                    format!(
+                        "{} {}, [{}, {}]",
+                        sized_mnemonic("ldr", size, signed),
+                        sized_capstone_string(reg1, signed),
+                        reg2.capstone_string(UsesSP),
+                        reg3.capstone_string(UsesZR)
+                    )
+                },
+                ALL_GENERAL_REGS,
+                ALL_GENERAL_REGS,
+                ALL_GENERAL_REGS
+            );
+        }
+    }
+
+    #[test]
+    fn test_ldur_freg64_reg64_imm9() {
         disassembler_test!(
-            add_reg64_reg64_imm12,
-            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, imm| format!(
-                "add {}, {}, #0x{:x}",
-                reg1.capstone_string(UsesSP),
+            ldur_freg64_reg64_imm9,
+            |reg1: AArch64FloatReg, reg2: AArch64GeneralReg, imm| format!(
+                "ldur {}, [{}, {}]",
+                reg1.capstone_string(FloatWidth::F64),
                 reg2.capstone_string(UsesSP),
-                imm
+                signed_hex(imm)
             ),
+            ALL_FLOAT_REGS,
             ALL_GENERAL_REGS,
-            ALL_GENERAL_REGS,
-            [0x123]
+            [-0x100, -0x8, 0xff]
         );
     }
 
     #[test]
-    fn test_ldr_reg64_reg64_imm12() {
+    fn test_ldur_reg64_reg64_imm9() {
         disassembler_test!(
-            ldr_reg64_reg64_imm12,
+            ldur_reg64_reg64_imm9,
             |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, imm| format!(
-                "ldr {}, [{}, #0x{:x}]",
+                "ldur {}, [{}, {}]",
                 reg1.capstone_string(UsesZR),
                 reg2.capstone_string(UsesSP),
-                imm << 3
+                signed_hex(imm)
             ),
             ALL_GENERAL_REGS,
             ALL_GENERAL_REGS,
-            [0x123]
+            [-0x100, -0x8, 0xff]
+        );
+    }
+
+    #[test]
+    fn test_ldur_sized_reg64_reg64_imm9() {
+        for (size, signed) in SIZED_LOADS {
+            disassembler_test!(
+                |buf: &mut Vec<'_, u8>, reg1, reg2, imm| ldur_sized_reg64_reg64_imm9(
+                    buf, size, signed, reg1, reg2, imm
+                ),
+                |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, imm| format!(
+                    "{} {}, [{}, {}]",
+                    sized_mnemonic("ldur", size, signed),
+                    sized_capstone_string(reg1, signed),
+                    reg2.capstone_string(UsesSP),
+                    signed_hex(imm)
+                ),
+                ALL_GENERAL_REGS,
+                ALL_GENERAL_REGS,
+                [-0x100, -0x8, 0xff]
+            );
+        }
+    }
+
+    #[test]
+    fn test_sized_base32() {
+        let arena = bumpalo::Bump::new();
+        let (mut buf, cs) = setup_capstone_and_arena(&arena);
+
+        AArch64Assembler::movsx_reg64_base32(&mut buf, AArch64GeneralReg::X0, -0x10, 2);
+        AArch64Assembler::movzx_reg64_base32(&mut buf, AArch64GeneralReg::X1, 0x10, 1);
+        AArch64Assembler::mov_base32_reg_sized(&mut buf, -0x14, AArch64GeneralReg::X2, 4);
+        AArch64Assembler::mov_base32_reg_sized(&mut buf, 0x20, AArch64GeneralReg::X3, 2);
+
+        assert_eq!(
+            merge_instructions_without_line_numbers(cs.disasm_all(&buf, 0).unwrap()),
+            "ldursh x0, [x29, #-0x10]\nldrb w1, [x29, #0x10]\nstur w2, [x29, #-0x14]\nstrh w3, [x29, #0x20]"
         );
     }
 
@@ -1465,6 +3790,125 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_mul_reg64_reg64_reg64() {
+        disassembler_test!(
+            mul_reg64_reg64_reg64,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, reg3: AArch64GeneralReg| format!(
+                "mul {}, {}, {}",
+                reg1.capstone_string(UsesZR),
+                reg2.capstone_string(UsesZR),
+                reg3.capstone_string(UsesZR)
+            ),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_orr_reg64_reg64_reg64() {
+        disassembler_test!(
+            orr_reg64_reg64_reg64,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, reg3: AArch64GeneralReg| {
+                if reg2 == AArch64GeneralReg::ZRSP {
+                    // `ORR Xd, XZR, Xm` is how `MOV Xd, Xm` is encoded.
+                    format!(
+                        "mov {}, {}",
+                        reg1.capstone_string(UsesZR),
+                        reg3.capstone_string(UsesZR)
+                    )
+                } else {
+                    format!(
+                        "orr {}, {}, {}",
+                        reg1.capstone_string(UsesZR),
+                        reg2.capstone_string(UsesZR),
+                        reg3.capstone_string(UsesZR)
+                    )
+                }
+            },
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_scvtf_freg32_reg64() {
+        disassembler_test!(
+            scvtf_freg32_reg64,
+            |reg1: AArch64FloatReg, reg2: AArch64GeneralReg| format!(
+                "scvtf {}, {}",
+                reg1.capstone_string(FloatWidth::F32),
+                reg2.capstone_string(UsesZR)
+            ),
+            ALL_FLOAT_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_scvtf_freg64_reg64() {
+        disassembler_test!(
+            scvtf_freg64_reg64,
+            |reg1: AArch64FloatReg, reg2: AArch64GeneralReg| format!(
+                "scvtf {}, {}",
+                reg1.capstone_string(FloatWidth::F64),
+                reg2.capstone_string(UsesZR)
+            ),
+            ALL_FLOAT_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_sdiv_reg64_reg64_reg64() {
+        disassembler_test!(
+            sdiv_reg64_reg64_reg64,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, reg3: AArch64GeneralReg| format!(
+                "sdiv {}, {}, {}",
+                reg1.capstone_string(UsesZR),
+                reg2.capstone_string(UsesZR),
+                reg3.capstone_string(UsesZR)
+            ),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_str_freg64_reg64_imm12() {
+        disassembler_test!(
+            str_freg64_reg64_imm12,
+            |reg1: AArch64FloatReg, reg2: AArch64GeneralReg, imm| format!(
+                "str {}, [{}, #0x{:x}]",
+                reg1.capstone_string(FloatWidth::F64),
+                reg2.capstone_string(UsesSP),
+                imm << 3
+            ),
+            ALL_FLOAT_REGS,
+            ALL_GENERAL_REGS,
+            [0x123]
+        );
+    }
+
+    #[test]
+    fn test_str_freg64_reg64_reg64() {
+        disassembler_test!(
+            str_freg64_reg64_reg64,
+            |reg1: AArch64FloatReg, reg2: AArch64GeneralReg, reg3: AArch64GeneralReg| format!(
+                "str {}, [{}, {}]",
+                reg1.capstone_string(FloatWidth::F64),
+                reg2.capstone_string(UsesSP),
+                reg3.capstone_string(UsesZR)
+            ),
+            ALL_FLOAT_REGS,
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
     #[test]
     fn test_str_reg64_reg64_imm12() {
         disassembler_test!(
@@ -1481,6 +3925,118 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_str_reg64_reg64_reg64() {
+        disassembler_test!(
+            str_reg64_reg64_reg64,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, reg3: AArch64GeneralReg| format!(
+                "str {}, [{}, {}]",
+                reg1.capstone_string(UsesZR),
+                reg2.capstone_string(UsesSP),
+                reg3.capstone_string(UsesZR)
+            ),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_str_sized_reg64_reg64_imm12() {
+        for size in [1, 2, 4] {
+            disassembler_test!(
+                |buf: &mut Vec<'_, u8>, reg1, reg2| str_sized_reg64_reg64_imm12(
+                    buf, size, reg1, reg2, 0x123
+                ),
+                |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg| format!(
+                    "{} {}, [{}, #0x{:x}]",
+                    sized_mnemonic("str", size, false),
+                    reg1.capstone_string_32bit(),
+                    reg2.capstone_string(UsesSP),
+                    0x123 * size as u32
+                ),
+                ALL_GENERAL_REGS,
+                ALL_GENERAL_REGS
+            );
+        }
+    }
+
+    #[test]
+    fn test_str_sized_reg64_reg64_reg64() {
+        for size in [1, 2, 4] {
+            disassembler_test!(
+                |buf: &mut Vec<'_, u8>, reg1, reg2, reg3| str_sized_reg64_reg64_reg64(
+                    buf, size, reg1, reg2, reg3
+                ),
+                |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, reg3: AArch64GeneralReg| {
+                    format!(
+                        "{} {}, [{}, {}]",
+                        sized_mnemonic("str", size, false),
+                        reg1.capstone_string_32bit(),
+                        reg2.capstone_string(UsesSP),
+                        reg3.capstone_string(UsesZR)
+                    )
+                },
+                ALL_GENERAL_REGS,
+                ALL_GENERAL_REGS,
+                ALL_GENERAL_REGS
+            );
+        }
+    }
+
+    #[test]
+    fn test_stur_freg64_reg64_imm9() {
+        disassembler_test!(
+            stur_freg64_reg64_imm9,
+            |reg1: AArch64FloatReg, reg2: AArch64GeneralReg, imm| format!(
+                "stur {}, [{}, {}]",
+                reg1.capstone_string(FloatWidth::F64),
+                reg2.capstone_string(UsesSP),
+                signed_hex(imm)
+            ),
+            ALL_FLOAT_REGS,
+            ALL_GENERAL_REGS,
+            [-0x100, -0x8, 0xff]
+        );
+    }
+
+    #[test]
+    fn test_stur_reg64_reg64_imm9() {
+        disassembler_test!(
+            stur_reg64_reg64_imm9,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, imm| format!(
+                "stur {}, [{}, {}]",
+                reg1.capstone_string(UsesZR),
+                reg2.capstone_string(UsesSP),
+                signed_hex(imm)
+            ),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            [-0x100, -0x8, 0xff]
+        );
+    }
+
+    #[test]
+    fn test_stur_sized_reg64_reg64_imm9() {
+        for size in [1, 2, 4] {
+            disassembler_test!(
+                |buf: &mut Vec<'_, u8>, reg1, reg2, imm| stur_sized_reg64_reg64_imm9(
+                    buf, size, reg1, reg2, imm
+                ),
+                |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, imm| format!(
+                    "{} {}, [{}, {}]",
+                    sized_mnemonic("stur", size, false),
+                    reg1.capstone_string_32bit(),
+                    reg2.capstone_string(UsesSP),
+                    signed_hex(imm)
+                ),
+                ALL_GENERAL_REGS,
+                ALL_GENERAL_REGS,
+                [-0x100, -0x8, 0xff]
+            );
+        }
+    }
+
     #[test]
     fn test_sub_reg64_reg64_imm12() {
         disassembler_test!(
@@ -1497,6 +4053,106 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_sub_reg64_reg64_imm12_lsl12() {
+        disassembler_test!(
+            sub_reg64_reg64_imm12_lsl12,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, imm| format!(
+                "sub {}, {}, #0x{:x}, lsl #12",
+                reg1.capstone_string(UsesSP),
+                reg2.capstone_string(UsesSP),
+                imm
+            ),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            [0x123]
+        );
+    }
+
+    #[test]
+    fn test_sub_reg64_reg64_reg64() {
+        disassembler_test!(
+            sub_reg64_reg64_reg64,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, reg3: AArch64GeneralReg| format!(
+                "sub {}, {}, {}",
+                reg1.capstone_string(UsesZR),
+                reg2.capstone_string(UsesZR),
+                reg3.capstone_string(UsesZR)
+            ),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_sub_reg64_reg64_reg64_uxtx() {
+        disassembler_test!(
+            sub_reg64_reg64_reg64_uxtx,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, reg3: AArch64GeneralReg| {
+                // With SP, the extension is written as a shift of zero, which is left out.
+                let extension =
+                    if reg1 == AArch64GeneralReg::ZRSP || reg2 == AArch64GeneralReg::ZRSP {
+                        ""
+                    } else {
+                        ", uxtx"
+                    };
+
+                format!(
+                    "sub {}, {}, {}{}",
+                    reg1.capstone_string(UsesSP),
+                    reg2.capstone_string(UsesSP),
+                    reg3.capstone_string(UsesZR),
+                    extension
+                )
+            },
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_udiv_reg64_reg64_reg64() {
+        disassembler_test!(
+            udiv_reg64_reg64_reg64,
+            |reg1: AArch64GeneralReg, reg2: AArch64GeneralReg, reg3: AArch64GeneralReg| format!(
+                "udiv {}, {}, {}",
+                reg1.capstone_string(UsesZR),
+                reg2.capstone_string(UsesZR),
+                reg3.capstone_string(UsesZR)
+            ),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS
+        );
+    }
+
+    #[test]
+    fn test_jumps() {
+        let arena = bumpalo::Bump::new();
+        let (mut buf, cs) = setup_capstone_and_arena(&arena);
+
+        // Both jumps are relative to the branch instruction, which is where they say they are.
+        let jne_location =
+            AArch64Assembler::jne_reg64_imm64_imm32(&mut buf, AArch64GeneralReg::X0, 0x12, 0x100);
+        let jmp_location = AArch64Assembler::jmp_imm32(&mut buf, -0x8);
+        assert_eq!((jne_location, jmp_location), (4, 8));
+
+        AArch64Assembler::add_reg64_reg64_reg64(
+            &mut buf,
+            AArch64GeneralReg::X0,
+            AArch64GeneralReg::X1,
+            AArch64GeneralReg::X2,
+        );
+        AArch64Assembler::set_if_overflow(&mut buf, AArch64GeneralReg::X3);
+
+        assert_eq!(
+            merge_instructions_without_line_numbers(cs.disasm_all(&buf, 0).unwrap()),
+            "cmp x0, #0x12\nb.ne #0x104\nb #0\nadds x0, x1, x2\ncset x3, vs"
+        );
+    }
+
     #[test]
     fn test_ret_reg64() {
         disassembler_test!(
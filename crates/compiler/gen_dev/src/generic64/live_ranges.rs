@@ -0,0 +1,192 @@
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+use roc_collections::all::MutMap;
+use roc_module::symbol::Symbol;
+use roc_mono::ir::{Call, CallType, Expr, Stmt};
+use std::cmp::Reverse;
+
+/// LiveRanges holds the live interval of each symbol of a procedure, for linear scan register allocation.
+/// Statements are numbered in the order scan_ast visits them, which is the order build_stmt builds them in.
+/// A symbol is live from the statement that defines it, which is where it first needs a register,
+/// until the last statement that uses it, which is where free_symbols gives the register back.
+/// So only where each interval ends is needed to pick a register to spill.
+#[derive(Clone)]
+pub struct LiveRanges<'a> {
+    // The position of the last statement that uses each symbol.
+    ends: MutMap<Symbol, u32>,
+    // The positions of statements that call another procedure, in order.
+    calls: Vec<'a, u32>,
+    // How many statements have been scanned.
+    scanned: u32,
+    // The position of the statement currently being built.
+    current: u32,
+}
+
+impl<'a> LiveRanges<'a> {
+    pub fn new(arena: &'a Bump) -> Self {
+        LiveRanges {
+            ends: MutMap::default(),
+            calls: bumpalo::vec![in arena],
+            scanned: 0,
+            current: 0,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.ends.clear();
+        self.calls.clear();
+        self.scanned = 0;
+        self.current = 0;
+    }
+
+    /// Numbers the next statement.
+    /// This must be called in the order statements are built, before adding the symbols the statement uses.
+    pub fn scan_stmt(&mut self, stmt: &Stmt<'a>) {
+        if is_call(stmt) {
+            self.calls.push(self.scanned);
+        }
+        self.scanned += 1;
+    }
+
+    /// Records that the symbol is defined or used in the statement that was scanned last.
+    pub fn add_use(&mut self, sym: Symbol) {
+        debug_assert!(self.scanned > 0, "{:?} is used outside of a statement", sym);
+        self.ends.insert(sym, self.scanned.saturating_sub(1));
+    }
+
+    /// Moves to the statement at the given position, which is about to be built.
+    pub fn start_stmt(&mut self, position: u32) {
+        self.current = position;
+    }
+
+    /// The position where the symbol's live interval ends.
+    /// Symbols that were not scanned, like the ones expanded refcounting defines, are temporaries that end right away.
+    pub fn end(&self, sym: &Symbol) -> u32 {
+        self.ends.get(sym).copied().unwrap_or(self.current)
+    }
+
+    /// Returns true if there is a call between the current statement and the end of the symbol's live interval.
+    /// Those symbols would have to be spilled around the call if they were in a caller saved register.
+    pub fn crosses_call(&self, sym: &Symbol) -> bool {
+        let end = self.end(sym);
+        let i = self
+            .calls
+            .partition_point(|position| *position <= self.current);
+        matches!(self.calls.get(i), Some(call) if *call < end)
+    }
+
+    /// Picks which of the symbols in registers to spill to make room for another: the one whose interval ends furthest away.
+    /// The symbol that needs the register needs it right away, so unlike textbook linear scan it is never the one spilled.
+    /// Ties go to the symbol that got its register first.
+    pub fn spill_candidate<'s>(&self, syms: impl IntoIterator<Item = &'s Symbol>) -> Option<usize> {
+        syms.into_iter()
+            .enumerate()
+            .max_by_key(|(i, sym)| (self.end(sym), Reverse(*i)))
+            .map(|(i, _)| i)
+    }
+}
+
+fn is_call(stmt: &Stmt) -> bool {
+    match stmt {
+        // Most low levels are generated inline, so only count real function calls.
+        Stmt::Let(_, Expr::Call(Call { call_type, .. }), _, _) => {
+            !matches!(call_type, CallType::LowLevel { .. })
+        }
+        // Refcounting is expanded into calls to helper procedures.
+        Stmt::Refcounting(..) => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LiveRanges;
+    use bumpalo::Bump;
+    use roc_module::symbol::{ModuleId, Symbol};
+    use roc_mono::ir::{ModifyRc, Stmt};
+
+    fn symbol(index: u32) -> Symbol {
+        Symbol::from_index(ModuleId::ATTR, index)
+    }
+
+    /// Scans statements that each use the given symbols, where the ones marked true are calls.
+    fn scan<'a>(arena: &'a Bump, stmts: &[(bool, &[Symbol])]) -> LiveRanges<'a> {
+        let ret = Stmt::Ret(symbol(0));
+        let call = Stmt::Refcounting(ModifyRc::Dec(symbol(0)), &ret);
+        let mut live_ranges = LiveRanges::new(arena);
+        for (is_call, syms) in stmts {
+            live_ranges.scan_stmt(if *is_call { &call } else { &ret });
+            for sym in syms.iter() {
+                live_ranges.add_use(*sym);
+            }
+        }
+        live_ranges
+    }
+
+    #[test]
+    fn intervals_end_at_last_use() {
+        let arena = Bump::new();
+        let (a, b, c) = (symbol(1), symbol(2), symbol(3));
+        let mut live_ranges = scan(&arena, &[(false, &[a]), (false, &[b, a]), (false, &[c, b])]);
+
+        assert_eq!(live_ranges.end(&a), 1);
+        assert_eq!(live_ranges.end(&b), 2);
+        assert_eq!(live_ranges.end(&c), 2);
+
+        // Temporaries that were never scanned end wherever building is.
+        live_ranges.start_stmt(1);
+        assert_eq!(live_ranges.end(&symbol(4)), 1);
+    }
+
+    #[test]
+    fn crosses_call_only_before_interval_ends() {
+        let arena = Bump::new();
+        let (a, b, c) = (symbol(1), symbol(2), symbol(3));
+        let mut live_ranges = scan(
+            &arena,
+            &[(false, &[a, b]), (true, &[b]), (false, &[a]), (false, &[c])],
+        );
+
+        live_ranges.start_stmt(0);
+        // The call uses b for the last time, so b does not need to survive it.
+        assert!(!live_ranges.crosses_call(&b));
+        assert!(live_ranges.crosses_call(&a));
+
+        live_ranges.start_stmt(2);
+        assert!(!live_ranges.crosses_call(&a));
+        assert!(!live_ranges.crosses_call(&c));
+    }
+
+    #[test]
+    fn spill_furthest_end() {
+        let arena = Bump::new();
+        let (a, b, c, d) = (symbol(1), symbol(2), symbol(3), symbol(4));
+        let mut live_ranges = scan(
+            &arena,
+            &[
+                (false, &[a, b, c]),
+                (false, &[a]),
+                (false, &[c]),
+                (false, &[b]),
+            ],
+        );
+        live_ranges.start_stmt(1);
+
+        assert_eq!(live_ranges.spill_candidate(&[a, b, c]), Some(1));
+        assert_eq!(live_ranges.spill_candidate(&[a, c]), Some(1));
+        assert_eq!(live_ranges.spill_candidate(&[]), None);
+
+        // A temporary ends right away, so it is spilled last.
+        assert_eq!(live_ranges.spill_candidate(&[d, a]), Some(1));
+    }
+
+    #[test]
+    fn spill_ties_go_to_first_claimed() {
+        let arena = Bump::new();
+        let (a, b) = (symbol(1), symbol(2));
+        let live_ranges = scan(&arena, &[(false, &[a, b]), (false, &[b, a])]);
+
+        assert_eq!(live_ranges.spill_candidate(&[b, a]), Some(0));
+        assert_eq!(live_ranges.spill_candidate(&[a, b]), Some(0));
+    }
+}
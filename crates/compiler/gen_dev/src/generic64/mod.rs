@@ -4,20 +4,21 @@ use crate::{
 };
 use bumpalo::collections::Vec;
 use roc_builtins::bitcode::{self, FloatWidth, IntWidth};
-use roc_collections::all::MutMap;
+use roc_collections::OrderedMap;
 use roc_error_macros::internal_error;
 use roc_module::symbol::{Interns, Symbol};
 use roc_mono::code_gen_help::CodeGenHelp;
 use roc_mono::ir::{
     BranchInfo, JoinPointId, ListLiteralElement, Literal, Param, ProcLayout, SelfRecursive, Stmt,
 };
-use roc_mono::layout::{Builtin, Layout, TagIdIntType, UnionLayout};
+use roc_mono::layout::{Builtin, Layout, LayoutIds, TagIdIntType, UnionLayout};
 use roc_target::TargetInfo;
 use std::marker::PhantomData;
 
 pub(crate) mod aarch64;
 #[cfg(test)]
 mod disassembler_test_macro;
+pub(crate) mod live_ranges;
 pub(crate) mod storage;
 pub(crate) mod x86_64;
 
@@ -68,6 +69,17 @@ pub trait CallConv<GeneralReg: RegTrait, FloatReg: RegTrait, ASM: Assembler<Gene
         fn_call_stack_size: i32,
     );
 
+    /// unwind_info writes the UNWIND_INFO that describes the stack setup_stack set up.
+    /// Only targets that unwind with one write anything.
+    fn unwind_info<'a>(
+        _buf: &mut Vec<'a, u8>,
+        _general_saved_regs: &[GeneralReg],
+        _float_saved_regs: &[FloatReg],
+        _aligned_stack_size: i32,
+        _fn_call_stack_size: i32,
+    ) {
+    }
+
     /// load_args updates the storage manager to know where every arg is stored.
     fn load_args<'a>(
         buf: &mut Vec<'a, u8>,
@@ -224,6 +236,9 @@ pub trait Assembler<GeneralReg: RegTrait, FloatReg: RegTrait>: Sized + Copy {
     /// Zero extends the data at `offset` with `size` as it copies it to `dst`
     /// size must be less than or equal to 8.
     fn movzx_reg64_base32(buf: &mut Vec<'_, u8>, dst: GeneralReg, offset: i32, size: u8);
+    /// Stores the low `size` bytes of `src` at `offset`.
+    /// size must be 1, 2, 4, or 8.
+    fn mov_base32_reg_sized(buf: &mut Vec<'_, u8>, offset: i32, src: GeneralReg, size: u8);
 
     fn mov_freg64_stack32(buf: &mut Vec<'_, u8>, dst: FloatReg, offset: i32);
     fn mov_reg64_stack32(buf: &mut Vec<'_, u8>, dst: GeneralReg, offset: i32);
@@ -319,6 +334,13 @@ pub trait Assembler<GeneralReg: RegTrait, FloatReg: RegTrait>: Sized + Copy {
         src2: GeneralReg,
     );
 
+    fn ult_reg64_reg64_reg64(
+        buf: &mut Vec<'_, u8>,
+        dst: GeneralReg,
+        src1: GeneralReg,
+        src2: GeneralReg,
+    );
+
     fn to_float_freg32_reg64(buf: &mut Vec<'_, u8>, dst: FloatReg, src: GeneralReg);
 
     fn to_float_freg64_reg64(buf: &mut Vec<'_, u8>, dst: FloatReg, src: GeneralReg);
@@ -359,7 +381,8 @@ pub struct Backend64Bit<
     ASM: Assembler<GeneralReg, FloatReg>,
     CC: CallConv<GeneralReg, FloatReg, ASM>,
 > {
-    // TODO: A number of the uses of MutMap could probably be some form of linear mutmap
+    // The maps are OrderedMaps, so nothing built from iterating them depends on hash order.
+    // TODO: Most of them could probably be a vec that is linearly scanned.
     // They are likely to be small enough that it is faster to use a vec and linearly scan it or keep it sorted and binary search.
     phantom_asm: PhantomData<ASM>,
     phantom_cc: PhantomData<CC>,
@@ -368,17 +391,24 @@ pub struct Backend64Bit<
     interns: &'a mut Interns,
     helper_proc_gen: CodeGenHelp<'a>,
     helper_proc_symbols: Vec<'a, (Symbol, ProcLayout<'a>)>,
+    layout_ids: LayoutIds<'a>,
     buf: Vec<'a, u8>,
     relocs: Vec<'a, Relocation>,
+    unwind_info: Vec<'a, u8>,
     proc_name: Option<String>,
     is_self_recursive: Option<SelfRecursive>,
 
-    last_seen_map: MutMap<Symbol, *const Stmt<'a>>,
-    layout_map: MutMap<Symbol, Layout<'a>>,
-    free_map: MutMap<*const Stmt<'a>, Vec<'a, Symbol>>,
+    last_seen_map: OrderedMap<Symbol, *const Stmt<'a>>,
+    layout_map: OrderedMap<Symbol, Layout<'a>>,
+    free_map: OrderedMap<*const Stmt<'a>, Vec<'a, Symbol>>,
+
+    // The position scan_ast gave the next statement to build, and how many statements to build
+    // before it that scan_ast never saw.
+    stmt_position: u32,
+    unscanned_stmts: u32,
 
-    literal_map: MutMap<Symbol, (*const Literal<'a>, *const Layout<'a>)>,
-    join_map: MutMap<JoinPointId, Vec<'a, (u64, u64)>>,
+    literal_map: OrderedMap<Symbol, (*const Literal<'a>, *const Layout<'a>)>,
+    join_map: OrderedMap<JoinPointId, Vec<'a, (u64, u64)>>,
 
     storage_manager: StorageManager<'a, GeneralReg, FloatReg, ASM, CC>,
 }
@@ -408,15 +438,19 @@ pub fn new_backend_64bit<
             env.module_id,
         ),
         helper_proc_symbols: bumpalo::vec![in env.arena],
+        layout_ids: LayoutIds::default(),
         proc_name: None,
         is_self_recursive: None,
         buf: bumpalo::vec![in env.arena],
         relocs: bumpalo::vec![in env.arena],
-        last_seen_map: MutMap::default(),
-        layout_map: MutMap::default(),
-        free_map: MutMap::default(),
-        literal_map: MutMap::default(),
-        join_map: MutMap::default(),
+        unwind_info: bumpalo::vec![in env.arena],
+        last_seen_map: OrderedMap::default(),
+        layout_map: OrderedMap::default(),
+        free_map: OrderedMap::default(),
+        stmt_position: 0,
+        unscanned_stmts: 0,
+        literal_map: OrderedMap::default(),
+        join_map: OrderedMap::default(),
         storage_manager: storage::new_storage_manager(env, target_info),
     }
 }
@@ -460,6 +494,9 @@ impl<
     fn helper_proc_symbols(&self) -> &Vec<'a, (Symbol, ProcLayout<'a>)> {
         &self.helper_proc_symbols
     }
+    fn layout_ids_mut(&mut self) -> &mut LayoutIds<'a> {
+        &mut self.layout_ids
+    }
 
     fn reset(&mut self, name: String, is_self_recursive: SelfRecursive) {
         self.proc_name = Some(name);
@@ -468,31 +505,56 @@ impl<
         self.layout_map.clear();
         self.join_map.clear();
         self.free_map.clear();
+        self.stmt_position = 0;
+        self.unscanned_stmts = 0;
         self.buf.clear();
         self.storage_manager.reset();
     }
 
-    fn literal_map(&mut self) -> &mut MutMap<Symbol, (*const Literal<'a>, *const Layout<'a>)> {
+    fn literal_map(&mut self) -> &mut OrderedMap<Symbol, (*const Literal<'a>, *const Layout<'a>)> {
         &mut self.literal_map
     }
 
-    fn last_seen_map(&mut self) -> &mut MutMap<Symbol, *const Stmt<'a>> {
+    fn last_seen_map(&mut self) -> &mut OrderedMap<Symbol, *const Stmt<'a>> {
         &mut self.last_seen_map
     }
 
-    fn layout_map(&mut self) -> &mut MutMap<Symbol, Layout<'a>> {
+    fn set_last_seen(&mut self, sym: Symbol, stmt: &Stmt<'a>) {
+        self.last_seen_map.insert(sym, stmt);
+        self.storage_manager.add_use(sym);
+    }
+
+    fn scan_stmt(&mut self, stmt: &Stmt<'a>) {
+        self.storage_manager.scan_stmt(stmt);
+    }
+
+    fn start_stmt(&mut self) {
+        // Switches and join points reset the storage manager between branches, so the position is kept here.
+        if self.unscanned_stmts > 0 {
+            self.unscanned_stmts -= 1;
+        } else {
+            self.storage_manager.start_stmt(self.stmt_position);
+            self.stmt_position += 1;
+        }
+    }
+
+    fn skip_stmts(&mut self, count: u32) {
+        self.unscanned_stmts += count;
+    }
+
+    fn layout_map(&mut self) -> &mut OrderedMap<Symbol, Layout<'a>> {
         &mut self.layout_map
     }
 
-    fn set_free_map(&mut self, map: MutMap<*const Stmt<'a>, Vec<'a, Symbol>>) {
+    fn set_free_map(&mut self, map: OrderedMap<*const Stmt<'a>, Vec<'a, Symbol>>) {
         self.free_map = map;
     }
 
-    fn free_map(&mut self) -> &mut MutMap<*const Stmt<'a>, Vec<'a, Symbol>> {
+    fn free_map(&mut self) -> &mut OrderedMap<*const Stmt<'a>, Vec<'a, Symbol>> {
         &mut self.free_map
     }
 
-    fn finalize(&mut self) -> (Vec<u8>, Vec<Relocation>) {
+    fn finalize(&mut self) -> (Vec<'a, u8>, Vec<'a, Relocation>) {
         let mut out = bumpalo::vec![in self.env.arena];
 
         // Setup stack.
@@ -507,6 +569,15 @@ impl<
         );
         let setup_offset = out.len();
 
+        self.unwind_info.clear();
+        CC::unwind_info(
+            &mut self.unwind_info,
+            &used_general_regs,
+            &used_float_regs,
+            aligned_stack_size,
+            self.storage_manager.fn_call_stack_size() as i32,
+        );
+
         // Deal with jumps to the return address.
         let old_relocs = std::mem::replace(&mut self.relocs, bumpalo::vec![in self.env.arena]);
 
@@ -586,6 +657,10 @@ impl<
         (out, out_relocs)
     }
 
+    fn unwind_info(&self) -> &[u8] {
+        &self.unwind_info
+    }
+
     fn load_args(&mut self, args: &'a [(Layout<'a>, Symbol)], ret_layout: &Layout<'a>) {
         CC::load_args(&mut self.buf, &mut self.storage_manager, args, ret_layout);
     }
@@ -698,6 +773,7 @@ impl<
             max_branch_stack_size =
                 std::cmp::max(max_branch_stack_size, self.storage_manager.stack_size());
             base_storage.update_fn_call_stack_size(self.storage_manager.fn_call_stack_size());
+            base_storage.merge_used_callee_saved_regs(&self.storage_manager);
         }
         self.storage_manager = base_storage;
         self.storage_manager
@@ -725,8 +801,8 @@ impl<
         remainder: &'a Stmt<'a>,
         ret_layout: &Layout<'a>,
     ) {
-        // Free everything to the stack to make sure they don't get messed up when looping back to this point.
-        // TODO: look into a nicer solution.
+        // Free everything to the stack, so every jump reaches the join point with the same storage.
+        // Only the parameters are passed in registers.
         self.storage_manager.free_all_to_stack(&mut self.buf);
 
         // Ensure all the joinpoint parameters have storage locations.
@@ -737,8 +813,14 @@ impl<
         self.join_map.insert(*id, bumpalo::vec![in self.env.arena]);
 
         // Build remainder of function first. It is what gets run and jumps to join.
+        let join_storage = self.storage_manager.clone();
         self.build_stmt(remainder, ret_layout);
 
+        // The body starts from the storage every jump sets up, not from wherever the remainder ended.
+        let remainder_storage = std::mem::replace(&mut self.storage_manager, join_storage);
+        self.storage_manager.merge_branch(&remainder_storage);
+        self.storage_manager.enter_joinpoint(id, parameters);
+
         let join_location = self.buf.len() as u64;
 
         // Build all statements in body.
@@ -816,6 +898,18 @@ impl<
                 let src2_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src2);
                 ASM::add_freg32_freg32_freg32(&mut self.buf, dst_reg, src1_reg, src2_reg);
             }
+            Layout::Builtin(Builtin::Int(IntWidth::I128 | IntWidth::U128)) => {
+                self.build_int128_add_sub(dst, Some(src1), src2, false);
+            }
+            Layout::Builtin(Builtin::Decimal) => {
+                self.build_fn_call(
+                    dst,
+                    bitcode::DEC_ADD_OR_PANIC.to_string(),
+                    &[*src1, *src2],
+                    &[*layout, *layout],
+                    layout,
+                );
+            }
             x => todo!("NumAdd: layout, {:?}", x),
         }
     }
@@ -871,6 +965,45 @@ impl<
         }
     }
 
+    fn build_num_add_checked_result(
+        &mut self,
+        dst: &Symbol,
+        src1: &Symbol,
+        src2: &Symbol,
+        num_layout: &Layout<'a>,
+        return_layout: &Layout<'a>,
+    ) {
+        debug_assert_eq!(*num_layout, Layout::Builtin(Builtin::Int(IntWidth::I64)));
+        let (union_layout, ok_tag_id) = self.result_union(return_layout, num_layout);
+        let (id_offset, id_size) = self.union_tag_id_location(&union_layout);
+        let struct_size = return_layout.stack_size(self.env.layout_interner, self.target_info);
+        let base_offset = self.storage_manager.claim_stack_area(dst, struct_size);
+
+        let buf = &mut self.buf;
+        let sum_reg = self
+            .storage_manager
+            .claim_general_reg(buf, &Symbol::DEV_TMP);
+        let tag_reg = self
+            .storage_manager
+            .claim_general_reg(buf, &Symbol::DEV_TMP2);
+        let src1_reg = self.storage_manager.load_to_general_reg(buf, src1);
+        let src2_reg = self.storage_manager.load_to_general_reg(buf, src2);
+
+        ASM::add_reg64_reg64_reg64(buf, sum_reg, src1_reg, src2_reg);
+        ASM::set_if_overflow(buf, tag_reg);
+        // The sum is written even on overflow. `Err Overflow` has no payload, so nothing reads it.
+        ASM::mov_base32_reg64(buf, base_offset, sum_reg);
+        if ok_tag_id != 0 {
+            // The overflow flag is the id of `Err`, flip it to get the id of `Ok`.
+            ASM::mov_reg64_imm64(buf, sum_reg, 1);
+            ASM::xor_reg64_reg64_reg64(buf, tag_reg, tag_reg, sum_reg);
+        }
+        ASM::mov_base32_reg_sized(buf, base_offset + id_offset, tag_reg, id_size);
+
+        self.free_symbol(&Symbol::DEV_TMP);
+        self.free_symbol(&Symbol::DEV_TMP2);
+    }
+
     fn build_num_mul(&mut self, dst: &Symbol, src1: &Symbol, src2: &Symbol, layout: &Layout<'a>) {
         use Builtin::Int;
 
@@ -914,6 +1047,24 @@ impl<
                 let src2_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src2);
                 ASM::mul_freg32_freg32_freg32(&mut self.buf, dst_reg, src1_reg, src2_reg);
             }
+            Layout::Builtin(Int(int_width @ (IntWidth::I128 | IntWidth::U128))) => {
+                self.build_fn_call(
+                    dst,
+                    bitcode::NUM_MUL_OR_PANIC_INT[*int_width].to_string(),
+                    &[*src1, *src2],
+                    &[*layout, *layout],
+                    layout,
+                );
+            }
+            Layout::Builtin(Builtin::Decimal) => {
+                self.build_fn_call(
+                    dst,
+                    bitcode::DEC_MUL_OR_PANIC.to_string(),
+                    &[*src1, *src2],
+                    &[*layout, *layout],
+                    layout,
+                );
+            }
             x => todo!("NumMul: layout, {:?}", x),
         }
     }
@@ -970,6 +1121,15 @@ impl<
                 let src2_reg = self.storage_manager.load_to_float_reg(&mut self.buf, src2);
                 ASM::div_freg32_freg32_freg32(&mut self.buf, dst_reg, src1_reg, src2_reg);
             }
+            Layout::Builtin(Builtin::Decimal) => {
+                self.build_fn_call(
+                    dst,
+                    bitcode::DEC_DIV.to_string(),
+                    &[*src1, *src2],
+                    &[*layout, *layout],
+                    layout,
+                );
+            }
             x => todo!("NumDiv: layout, {:?}", x),
         }
     }
@@ -981,6 +1141,18 @@ impl<
                 let src_reg = self.storage_manager.load_to_general_reg(&mut self.buf, src);
                 ASM::neg_reg64_reg64(&mut self.buf, dst_reg, src_reg);
             }
+            Layout::Builtin(Builtin::Int(IntWidth::I128 | IntWidth::U128)) => {
+                self.build_int128_add_sub(dst, None, src, true);
+            }
+            Layout::Builtin(Builtin::Decimal) => {
+                self.build_fn_call(
+                    dst,
+                    bitcode::DEC_NEGATE.to_string(),
+                    &[*src],
+                    &[*layout],
+                    layout,
+                );
+            }
             x => todo!("NumNeg: layout, {:?}", x),
         }
     }
@@ -997,6 +1169,18 @@ impl<
                     .load_to_general_reg(&mut self.buf, src2);
                 ASM::sub_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
             }
+            Layout::Builtin(Builtin::Int(IntWidth::I128 | IntWidth::U128)) => {
+                self.build_int128_add_sub(dst, Some(src1), src2, true);
+            }
+            Layout::Builtin(Builtin::Decimal) => {
+                self.build_fn_call(
+                    dst,
+                    bitcode::DEC_SUB_OR_PANIC.to_string(),
+                    &[*src1, *src2],
+                    &[*layout, *layout],
+                    layout,
+                );
+            }
             x => todo!("NumSub: layout, {:?}", x),
         }
     }
@@ -1013,6 +1197,10 @@ impl<
                     .load_to_general_reg(&mut self.buf, src2);
                 ASM::eq_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
             }
+            Layout::Builtin(Builtin::Int(IntWidth::I128 | IntWidth::U128) | Builtin::Decimal) => {
+                self.build_int128_eq(dst, src1, src2, false);
+            }
+            Layout::Builtin(Builtin::Str) => self.build_str_eq(dst, src1, src2),
             x => todo!("NumEq: layout, {:?}", x),
         }
     }
@@ -1029,6 +1217,20 @@ impl<
                     .load_to_general_reg(&mut self.buf, src2);
                 ASM::neq_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
             }
+            Layout::Builtin(Builtin::Int(IntWidth::I128 | IntWidth::U128) | Builtin::Decimal) => {
+                self.build_int128_eq(dst, src1, src2, true);
+            }
+            Layout::Builtin(Builtin::Str) => {
+                self.build_str_eq(dst, src1, src2);
+                let dst_reg = self.storage_manager.load_to_general_reg(&mut self.buf, dst);
+                self.storage_manager.with_tmp_general_reg(
+                    &mut self.buf,
+                    |_storage_manager, buf, tmp| {
+                        ASM::mov_reg64_imm64(buf, tmp, 1);
+                        ASM::xor_reg64_reg64_reg64(buf, dst_reg, dst_reg, tmp);
+                    },
+                );
+            }
             x => todo!("NumNeq: layout, {:?}", x),
         }
     }
@@ -1051,6 +1253,12 @@ impl<
                     .load_to_general_reg(&mut self.buf, src2);
                 ASM::lt_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
             }
+            Layout::Builtin(Builtin::Int(IntWidth::I128) | Builtin::Decimal) => {
+                self.build_int128_lt(dst, src1, src2, true, false);
+            }
+            Layout::Builtin(Builtin::Int(IntWidth::U128)) => {
+                self.build_int128_lt(dst, src1, src2, false, false);
+            }
             x => todo!("NumLt: layout, {:?}", x),
         }
     }
@@ -1128,6 +1336,12 @@ impl<
                     .load_to_general_reg(&mut self.buf, src2);
                 ASM::lte_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
             }
+            Layout::Builtin(Builtin::Int(IntWidth::I128) | Builtin::Decimal) => {
+                self.build_int128_lt(dst, src2, src1, true, true);
+            }
+            Layout::Builtin(Builtin::Int(IntWidth::U128)) => {
+                self.build_int128_lt(dst, src2, src1, false, true);
+            }
             x => todo!("NumLte: layout, {:?}", x),
         }
     }
@@ -1150,6 +1364,12 @@ impl<
                     .load_to_general_reg(&mut self.buf, src2);
                 ASM::gte_reg64_reg64_reg64(&mut self.buf, dst_reg, src1_reg, src2_reg);
             }
+            Layout::Builtin(Builtin::Int(IntWidth::I128) | Builtin::Decimal) => {
+                self.build_int128_lt(dst, src1, src2, true, true);
+            }
+            Layout::Builtin(Builtin::Int(IntWidth::U128)) => {
+                self.build_int128_lt(dst, src1, src2, false, true);
+            }
             x => todo!("NumGte: layout, {:?}", x),
         }
     }
@@ -1158,6 +1378,78 @@ impl<
         self.storage_manager.list_len(&mut self.buf, dst, list);
     }
 
+    fn can_inline_list_get(&self, list_layout: &Layout<'a>) -> bool {
+        // Elements are copied out of the list 8 bytes at a time. The copy doesn't increment
+        // the element's refcount like the builtin does, so only elements without one are inlined.
+        match list_layout {
+            Layout::Builtin(Builtin::List(elem_layout)) => {
+                let elem_size = elem_layout.stack_size(self.env.layout_interner, self.target_info);
+                elem_size > 0
+                    && elem_size % 8 == 0
+                    && !elem_layout.contains_refcounted(self.env.layout_interner)
+            }
+            _ => false,
+        }
+    }
+
+    fn build_list_get(
+        &mut self,
+        dst: &Symbol,
+        list: &Symbol,
+        index: &Symbol,
+        list_layout: &Layout<'a>,
+        ret_layout: &Layout<'a>,
+    ) {
+        let elem_layout = match list_layout {
+            Layout::Builtin(Builtin::List(elem_layout)) => **elem_layout,
+            x => internal_error!("ListGet: expected a list layout, found: {:?}", x),
+        };
+        let (union_layout, ok_tag_id) = self.result_union(ret_layout, &elem_layout);
+        let (id_offset, id_size) = self.union_tag_id_location(&union_layout);
+        let elem_size = elem_layout.stack_size(self.env.layout_interner, self.target_info);
+        let ret_size = ret_layout.stack_size(self.env.layout_interner, self.target_info);
+
+        let (list_offset, _) = self.storage_manager.stack_offset_and_size(list);
+        let base_offset = self.storage_manager.claim_stack_area(dst, ret_size);
+        let index_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, index);
+        let tmp_reg = self
+            .storage_manager
+            .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP);
+        let ptr_reg = self
+            .storage_manager
+            .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP2);
+
+        // Default to `Err OutOfBounds`, which has no payload.
+        let buf = &mut self.buf;
+        ASM::mov_reg64_imm64(buf, tmp_reg, (1 - ok_tag_id) as i64);
+        ASM::mov_base32_reg_sized(buf, base_offset + id_offset, tmp_reg, id_size);
+
+        // Skip loading the element if the index is not less than the length.
+        ASM::mov_reg64_base32(buf, tmp_reg, list_offset + 8);
+        ASM::ult_reg64_reg64_reg64(buf, tmp_reg, index_reg, tmp_reg);
+        let jne_location = buf.len();
+        let start_offset = ASM::jne_reg64_imm64_imm32(buf, tmp_reg, 1, 0);
+
+        ASM::mov_reg64_imm64(buf, tmp_reg, elem_size as i64);
+        ASM::imul_reg64_reg64_reg64(buf, tmp_reg, tmp_reg, index_reg);
+        ASM::mov_reg64_base32(buf, ptr_reg, list_offset);
+        ASM::add_reg64_reg64_reg64(buf, ptr_reg, ptr_reg, tmp_reg);
+        for offset in (0..elem_size as i32).step_by(8) {
+            ASM::mov_reg64_mem64_offset32(buf, tmp_reg, ptr_reg, offset);
+            ASM::mov_base32_reg64(buf, base_offset + offset, tmp_reg);
+        }
+        ASM::mov_reg64_imm64(buf, tmp_reg, ok_tag_id as i64);
+        ASM::mov_base32_reg_sized(buf, base_offset + id_offset, tmp_reg, id_size);
+
+        let end_offset = self.buf.len();
+        self.update_jne_imm32_offset(jne_location, start_offset, end_offset, tmp_reg, 1);
+
+        self.free_symbol(&Symbol::DEV_TMP);
+        self.free_symbol(&Symbol::DEV_TMP2);
+    }
+
     fn build_list_get_unsafe(
         &mut self,
         dst: &Symbol,
@@ -1324,12 +1616,128 @@ impl<
         self.free_symbol(&Symbol::DEV_TMP5);
     }
 
-    fn build_ptr_cast(&mut self, dst: &Symbol, src: &Symbol) {
+    fn build_ptr_cast(&mut self, dst: &Symbol, src: &Symbol, src_layout: &Layout<'a>) {
         let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, dst);
-        self.storage_manager
-            .ensure_symbol_on_stack(&mut self.buf, src);
-        let (offset, _) = self.storage_manager.stack_offset_and_size(src);
-        ASM::add_reg64_reg64_imm32(&mut self.buf, dst_reg, CC::BASE_PTR_REG, offset);
+        match src_layout {
+            // Boxes and nullable pointers already are the pointer.
+            Layout::Boxed(_) | Layout::Union(UnionLayout::NullablePointer { .. }) => {
+                let src_reg = self.storage_manager.load_to_general_reg(&mut self.buf, src);
+                ASM::mov_reg64_reg64(&mut self.buf, dst_reg, src_reg);
+            }
+            _ => {
+                self.storage_manager
+                    .ensure_symbol_on_stack(&mut self.buf, src);
+                let (offset, _) = self.storage_manager.stack_offset_and_size(src);
+                ASM::add_reg64_reg64_imm32(&mut self.buf, dst_reg, CC::BASE_PTR_REG, offset);
+            }
+        }
+    }
+
+    fn expr_box(&mut self, sym: &Symbol, value: &Symbol, inner_layout: &Layout<'a>) {
+        let target_info = self.storage_manager.target_info();
+        let alignment = std::cmp::max(
+            8,
+            inner_layout.allocation_alignment_bytes(self.env.layout_interner, target_info),
+        );
+        let inner_size = inner_layout.stack_size(self.env.layout_interner, target_info);
+
+        let u64_layout = Layout::Builtin(Builtin::Int(IntWidth::U64));
+        self.load_literal(
+            &Symbol::DEV_TMP,
+            &u64_layout,
+            &Literal::Int(((inner_size + alignment) as i128).to_ne_bytes()),
+        );
+        let u32_layout = Layout::Builtin(Builtin::Int(IntWidth::U32));
+        self.load_literal(
+            &Symbol::DEV_TMP2,
+            &u32_layout,
+            &Literal::Int((alignment as i128).to_ne_bytes()),
+        );
+
+        self.build_fn_call(
+            &Symbol::DEV_TMP3,
+            "roc_alloc".to_string(),
+            &[Symbol::DEV_TMP, Symbol::DEV_TMP2],
+            &[u64_layout, u32_layout],
+            &u64_layout,
+        );
+        self.free_symbol(&Symbol::DEV_TMP);
+        self.free_symbol(&Symbol::DEV_TMP2);
+
+        // The box points past the refcount, at the value.
+        let ptr_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, &Symbol::DEV_TMP3);
+        ASM::add_reg64_reg64_imm32(&mut self.buf, ptr_reg, ptr_reg, alignment as i32);
+
+        self.storage_manager.with_tmp_general_reg(
+            &mut self.buf,
+            |_storage_manager, buf, tmp_reg| {
+                ASM::mov_reg64_imm64(buf, tmp_reg, REFCOUNT_ONE as i64);
+                ASM::mov_mem64_offset32_reg64(buf, ptr_reg, -8, tmp_reg);
+            },
+        );
+
+        self.store_boxed_value(ptr_reg, value, inner_layout);
+
+        let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, sym);
+        ASM::mov_reg64_reg64(&mut self.buf, dst_reg, ptr_reg);
+        self.free_symbol(&Symbol::DEV_TMP3);
+    }
+
+    fn expr_reuse_box(
+        &mut self,
+        sym: &Symbol,
+        reused: &Symbol,
+        value: &Symbol,
+        inner_layout: &Layout<'a>,
+    ) {
+        // The generated reset of a box never returns NULL, so the value is always written into
+        // the reset memory.
+        let ptr_reg = self
+            .storage_manager
+            .load_to_general_reg(&mut self.buf, reused);
+
+        self.store_boxed_value(ptr_reg, value, inner_layout);
+
+        let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, sym);
+        ASM::mov_reg64_reg64(&mut self.buf, dst_reg, ptr_reg);
+    }
+
+    fn expr_unbox(&mut self, sym: &Symbol, boxed: &Symbol, inner_layout: &Layout<'a>) {
+        let inner_size =
+            inner_layout.stack_size(self.env.layout_interner, self.storage_manager.target_info());
+
+        match inner_layout {
+            _ if inner_size == 0 => {}
+            single_register_integers!() if inner_size == 8 => {
+                let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, sym);
+                let ptr_reg = self
+                    .storage_manager
+                    .load_to_general_reg(&mut self.buf, boxed);
+                ASM::mov_reg64_mem64_offset32(&mut self.buf, dst_reg, ptr_reg, 0);
+            }
+            Layout::Builtin(Builtin::Str | Builtin::List(_))
+            | Layout::Struct { .. }
+            | Layout::Union(_)
+                if inner_size % 8 == 0 =>
+            {
+                let base_offset = self.storage_manager.claim_stack_area(sym, inner_size);
+                let ptr_reg = self
+                    .storage_manager
+                    .load_to_general_reg(&mut self.buf, boxed);
+                self.storage_manager.with_tmp_general_reg(
+                    &mut self.buf,
+                    |_storage_manager, buf, tmp_reg| {
+                        for offset in (0..inner_size as i32).step_by(8) {
+                            ASM::mov_reg64_mem64_offset32(buf, tmp_reg, ptr_reg, offset);
+                            ASM::mov_base32_reg64(buf, base_offset + offset, tmp_reg);
+                        }
+                    },
+                );
+            }
+            x => todo!("unboxing a value with layout, {:?}", x),
+        }
     }
 
     fn create_empty_array(&mut self, sym: &Symbol) {
@@ -1497,6 +1905,15 @@ impl<
                     tag_layouts[tag_id as usize],
                 );
             }
+            UnionLayout::NullablePointer { .. } => {
+                // The only field is the pointer, which is the union itself.
+                debug_assert_eq!(index, 0);
+                let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, sym);
+                let src_reg = self
+                    .storage_manager
+                    .load_to_general_reg(&mut self.buf, structure);
+                ASM::mov_reg64_reg64(&mut self.buf, dst_reg, src_reg);
+            }
             x => todo!("loading from union type: {:?}", x),
         }
     }
@@ -1536,6 +1953,29 @@ impl<
                 let val = *x;
                 ASM::mov_reg64_imm64(&mut self.buf, reg, i128::from_ne_bytes(val) as i64);
             }
+            (
+                Literal::Int(bytes) | Literal::U128(bytes),
+                Layout::Builtin(Builtin::Int(IntWidth::I128 | IntWidth::U128)),
+            )
+            | (Literal::Decimal(bytes), Layout::Builtin(Builtin::Decimal)) => {
+                self.storage_manager.with_tmp_general_reg(
+                    &mut self.buf,
+                    |storage_manager, buf, reg| {
+                        let base_offset = storage_manager.claim_stack_area(sym, 16);
+
+                        let mut num_bytes = [0; 8];
+                        num_bytes.copy_from_slice(&bytes[..8]);
+                        let num = i64::from_ne_bytes(num_bytes);
+                        ASM::mov_reg64_imm64(buf, reg, num);
+                        ASM::mov_base32_reg64(buf, base_offset, reg);
+
+                        num_bytes.copy_from_slice(&bytes[8..16]);
+                        let num = i64::from_ne_bytes(num_bytes);
+                        ASM::mov_reg64_imm64(buf, reg, num);
+                        ASM::mov_base32_reg64(buf, base_offset + 8, reg);
+                    },
+                );
+            }
             (Literal::Bool(x), Layout::Builtin(Builtin::Bool)) => {
                 let reg = self.storage_manager.claim_general_reg(&mut self.buf, sym);
                 let val = [*x as u8; 16];
@@ -1693,6 +2133,169 @@ impl<
         CC: CallConv<GeneralReg, FloatReg, ASM>,
     > Backend64Bit<'a, GeneralReg, FloatReg, ASM, CC>
 {
+    /// Writes `value` into the memory of a box that `ptr_reg` points at.
+    fn store_boxed_value(
+        &mut self,
+        ptr_reg: GeneralReg,
+        value: &Symbol,
+        inner_layout: &Layout<'a>,
+    ) {
+        let inner_size =
+            inner_layout.stack_size(self.env.layout_interner, self.storage_manager.target_info());
+
+        match inner_layout {
+            _ if inner_size == 0 => {}
+            single_register_integers!() if inner_size == 8 => {
+                let value_reg = self
+                    .storage_manager
+                    .load_to_general_reg(&mut self.buf, value);
+                ASM::mov_mem64_offset32_reg64(&mut self.buf, ptr_reg, 0, value_reg);
+            }
+            Layout::Builtin(Builtin::Str | Builtin::List(_))
+            | Layout::Struct { .. }
+            | Layout::Union(_)
+                if inner_size % 8 == 0 =>
+            {
+                let (from_offset, _) = self.storage_manager.stack_offset_and_size(value);
+                self.storage_manager.with_tmp_general_reg(
+                    &mut self.buf,
+                    |_storage_manager, buf, tmp_reg| {
+                        for offset in (0..inner_size as i32).step_by(8) {
+                            ASM::mov_reg64_base32(buf, tmp_reg, from_offset + offset);
+                            ASM::mov_mem64_offset32_reg64(buf, ptr_reg, offset, tmp_reg);
+                        }
+                    },
+                );
+            }
+            x => todo!("boxing a value with layout, {:?}", x),
+        }
+    }
+
+    /// Updates a conditional jump, made by jne_reg64_imm64_imm32, to jump to the target offset.
+    fn update_jne_imm32_offset(
+        &mut self,
+        jne_location: usize,
+        base_offset: usize,
+        target_offset: usize,
+        reg: GeneralReg,
+        imm: u64,
+    ) {
+        let mut tmp = bumpalo::vec![in self.env.arena];
+        let jne_offset = target_offset as i32 - base_offset as i32;
+        ASM::jne_reg64_imm64_imm32(&mut tmp, reg, imm, jne_offset);
+        for (i, byte) in tmp.iter().enumerate() {
+            self.buf[jne_location + i] = *byte;
+        }
+    }
+
+    /// Returns the union layout of a `Result` holding the ok layout, and the tag id of `Ok`.
+    fn result_union(
+        &self,
+        result_layout: &Layout<'a>,
+        ok_layout: &Layout<'a>,
+    ) -> (UnionLayout<'a>, TagIdIntType) {
+        match result_layout {
+            Layout::Union(union_layout @ UnionLayout::NonRecursive(tags)) => {
+                match tags.iter().position(|fields| fields == &[*ok_layout]) {
+                    Some(ok_tag_id) if tags.len() == 2 => {
+                        (*union_layout, ok_tag_id as TagIdIntType)
+                    }
+                    _ => internal_error!("Expected a Result of {:?}: {:?}", ok_layout, tags),
+                }
+            }
+            x => internal_error!("Expected a Result layout, found: {:?}", x),
+        }
+    }
+
+    /// Returns the offset and size of the tag id in a union stored on the stack.
+    fn union_tag_id_location(&self, union_layout: &UnionLayout<'a>) -> (i32, u8) {
+        let id_offset = union_layout
+            .tag_id_offset(self.env.layout_interner, self.target_info)
+            .unwrap();
+        let id_size = union_layout.discriminant().stack_size() as u8;
+        (id_offset as i32, id_size)
+    }
+
+    /// Compares two strings.
+    /// Strings with identical bytes are equal, and two different small strings are never equal.
+    /// Only the remaining cases call into the builtins.
+    fn build_str_eq(&mut self, dst: &Symbol, src1: &Symbol, src2: &Symbol) {
+        // The builtin may be called, so free the caller saved registers up front.
+        // That way both paths leave the storage the same.
+        self.storage_manager
+            .push_used_caller_saved_regs_to_stack(&mut self.buf);
+        let (offset1, _) = self.storage_manager.stack_offset_and_size(src1);
+        let (offset2, _) = self.storage_manager.stack_offset_and_size(src2);
+        let result_reg = CC::GENERAL_RETURN_REGS[0];
+
+        let diff_reg = self
+            .storage_manager
+            .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP);
+        let tmp1_reg = self
+            .storage_manager
+            .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP2);
+        let tmp2_reg = self
+            .storage_manager
+            .claim_general_reg(&mut self.buf, &Symbol::DEV_TMP3);
+
+        // Or together the differences of every word.
+        let buf = &mut self.buf;
+        ASM::mov_reg64_imm64(buf, diff_reg, 0);
+        for i in 0..3 {
+            ASM::mov_reg64_base32(buf, tmp1_reg, offset1 + 8 * i);
+            ASM::mov_reg64_base32(buf, tmp2_reg, offset2 + 8 * i);
+            ASM::xor_reg64_reg64_reg64(buf, tmp1_reg, tmp1_reg, tmp2_reg);
+            ASM::or_reg64_reg64_reg64(buf, diff_reg, diff_reg, tmp1_reg);
+        }
+        // The last words of both strings are negative if they are both small.
+        ASM::mov_reg64_base32(buf, tmp1_reg, offset1 + 16);
+        ASM::mov_reg64_base32(buf, tmp2_reg, offset2 + 16);
+        ASM::and_reg64_reg64_reg64(buf, tmp1_reg, tmp1_reg, tmp2_reg);
+        ASM::mov_reg64_imm64(buf, tmp2_reg, 0);
+        ASM::lt_reg64_reg64_reg64(buf, tmp1_reg, tmp1_reg, tmp2_reg);
+        ASM::eq_reg64_reg64_reg64(buf, tmp2_reg, diff_reg, tmp2_reg);
+        // The result is known if the strings are identical or both small.
+        ASM::or_reg64_reg64_reg64(buf, tmp1_reg, tmp1_reg, tmp2_reg);
+        let jne_location = buf.len();
+        let start_offset = ASM::jne_reg64_imm64_imm32(buf, tmp1_reg, 1, 0);
+        ASM::mov_reg64_reg64(buf, result_reg, tmp2_reg);
+        let jmp_location = buf.len();
+        let jmp_offset = ASM::jmp_imm32(buf, 0);
+
+        self.free_symbol(&Symbol::DEV_TMP);
+        self.free_symbol(&Symbol::DEV_TMP2);
+        self.free_symbol(&Symbol::DEV_TMP3);
+
+        let call_offset = self.buf.len();
+        self.update_jne_imm32_offset(jne_location, start_offset, call_offset, tmp1_reg, 1);
+        let str_layout = Layout::Builtin(Builtin::Str);
+        CC::store_args(
+            &mut self.buf,
+            &mut self.storage_manager,
+            dst,
+            &[*src1, *src2],
+            &[str_layout, str_layout],
+            &Layout::Builtin(Builtin::Bool),
+        );
+        ASM::call(
+            &mut self.buf,
+            &mut self.relocs,
+            bitcode::STR_EQUAL.to_string(),
+        );
+
+        let mut tmp = bumpalo::vec![in self.env.arena];
+        let end_offset = self.buf.len();
+        self.update_jmp_imm32_offset(
+            &mut tmp,
+            jmp_location as u64,
+            jmp_offset as u64,
+            end_offset as u64,
+        );
+        // Both paths leave the result in the return register.
+        let dst_reg = self.storage_manager.claim_general_reg(&mut self.buf, dst);
+        ASM::mov_reg64_reg64(&mut self.buf, dst_reg, result_reg);
+    }
+
     /// Updates a jump instruction to a new offset and returns the number of bytes written.
     fn update_jmp_imm32_offset(
         &mut self,
@@ -1708,6 +2311,144 @@ impl<
             self.buf[jmp_location as usize + i] = *byte;
         }
     }
+
+    /// Loads the low and high words of a 128 bit value into general registers claimed for `lo` and `hi`.
+    fn load_int128_to_general_regs(
+        &mut self,
+        sym: &Symbol,
+        lo: &Symbol,
+        hi: &Symbol,
+    ) -> (GeneralReg, GeneralReg) {
+        let (base_offset, size) = self.storage_manager.stack_offset_and_size(sym);
+        debug_assert_eq!(size, 16);
+        let lo_reg = self.storage_manager.claim_general_reg(&mut self.buf, lo);
+        ASM::mov_reg64_base32(&mut self.buf, lo_reg, base_offset);
+        let hi_reg = self.storage_manager.claim_general_reg(&mut self.buf, hi);
+        ASM::mov_reg64_base32(&mut self.buf, hi_reg, base_offset + 8);
+        (lo_reg, hi_reg)
+    }
+
+    /// Adds or subtracts 128 bit integers one word at a time, carrying from the low word into the high word.
+    /// A missing `src1` is treated as zero, which is how negation is built.
+    fn build_int128_add_sub(
+        &mut self,
+        dst: &Symbol,
+        src1: Option<&Symbol>,
+        src2: &Symbol,
+        subtract: bool,
+    ) {
+        let (lo1, hi1) = match src1 {
+            Some(src1) => {
+                self.load_int128_to_general_regs(src1, &Symbol::DEV_TMP, &Symbol::DEV_TMP2)
+            }
+            None => {
+                let buf = &mut self.buf;
+                let lo1 = self
+                    .storage_manager
+                    .claim_general_reg(buf, &Symbol::DEV_TMP);
+                ASM::mov_reg64_imm64(buf, lo1, 0);
+                let hi1 = self
+                    .storage_manager
+                    .claim_general_reg(buf, &Symbol::DEV_TMP2);
+                ASM::mov_reg64_imm64(buf, hi1, 0);
+                (lo1, hi1)
+            }
+        };
+        let (lo2, hi2) =
+            self.load_int128_to_general_regs(src2, &Symbol::DEV_TMP3, &Symbol::DEV_TMP4);
+
+        let buf = &mut self.buf;
+        let lo = self
+            .storage_manager
+            .claim_general_reg(buf, &Symbol::DEV_TMP5);
+        if subtract {
+            ASM::sub_reg64_reg64_reg64(buf, lo, lo1, lo2);
+            // The low words borrow when the first is smaller than the second.
+            ASM::ult_reg64_reg64_reg64(buf, lo2, lo1, lo2);
+            ASM::sub_reg64_reg64_reg64(buf, hi1, hi1, hi2);
+            ASM::sub_reg64_reg64_reg64(buf, hi1, hi1, lo2);
+        } else {
+            ASM::add_reg64_reg64_reg64(buf, lo, lo1, lo2);
+            // The low words carry when their sum wraps around to less than either of them.
+            ASM::ult_reg64_reg64_reg64(buf, lo2, lo, lo1);
+            ASM::add_reg64_reg64_reg64(buf, hi1, hi1, hi2);
+            ASM::add_reg64_reg64_reg64(buf, hi1, hi1, lo2);
+        }
+
+        let base_offset = self.storage_manager.claim_stack_area(dst, 16);
+        ASM::mov_base32_reg64(buf, base_offset, lo);
+        ASM::mov_base32_reg64(buf, base_offset + 8, hi1);
+
+        self.free_symbol(&Symbol::DEV_TMP);
+        self.free_symbol(&Symbol::DEV_TMP2);
+        self.free_symbol(&Symbol::DEV_TMP3);
+        self.free_symbol(&Symbol::DEV_TMP4);
+        self.free_symbol(&Symbol::DEV_TMP5);
+    }
+
+    /// Compares two 128 bit values for equality, or for inequality if `negate` is set.
+    fn build_int128_eq(&mut self, dst: &Symbol, src1: &Symbol, src2: &Symbol, negate: bool) {
+        let (lo1, hi1) =
+            self.load_int128_to_general_regs(src1, &Symbol::DEV_TMP, &Symbol::DEV_TMP2);
+        let (lo2, hi2) =
+            self.load_int128_to_general_regs(src2, &Symbol::DEV_TMP3, &Symbol::DEV_TMP4);
+
+        let buf = &mut self.buf;
+        let dst_reg = self.storage_manager.claim_general_reg(buf, dst);
+        if negate {
+            ASM::neq_reg64_reg64_reg64(buf, lo1, lo1, lo2);
+            ASM::neq_reg64_reg64_reg64(buf, hi1, hi1, hi2);
+            ASM::or_reg64_reg64_reg64(buf, dst_reg, lo1, hi1);
+        } else {
+            ASM::eq_reg64_reg64_reg64(buf, lo1, lo1, lo2);
+            ASM::eq_reg64_reg64_reg64(buf, hi1, hi1, hi2);
+            ASM::and_reg64_reg64_reg64(buf, dst_reg, lo1, hi1);
+        }
+
+        self.free_symbol(&Symbol::DEV_TMP);
+        self.free_symbol(&Symbol::DEV_TMP2);
+        self.free_symbol(&Symbol::DEV_TMP3);
+        self.free_symbol(&Symbol::DEV_TMP4);
+    }
+
+    /// Checks if src1 is less than src2 for 128 bit values, or the opposite if `negate` is set.
+    /// The high words are compared as signed or unsigned values depending on `signed`.
+    /// The low words are always compared as unsigned values.
+    fn build_int128_lt(
+        &mut self,
+        dst: &Symbol,
+        src1: &Symbol,
+        src2: &Symbol,
+        signed: bool,
+        negate: bool,
+    ) {
+        let (lo1, hi1) =
+            self.load_int128_to_general_regs(src1, &Symbol::DEV_TMP, &Symbol::DEV_TMP2);
+        let (lo2, hi2) =
+            self.load_int128_to_general_regs(src2, &Symbol::DEV_TMP3, &Symbol::DEV_TMP4);
+
+        let buf = &mut self.buf;
+        // (hi1 < hi2) | ((hi1 == hi2) & (lo1 < lo2))
+        ASM::ult_reg64_reg64_reg64(buf, lo1, lo1, lo2);
+        ASM::eq_reg64_reg64_reg64(buf, lo2, hi1, hi2);
+        ASM::and_reg64_reg64_reg64(buf, lo1, lo1, lo2);
+        if signed {
+            ASM::lt_reg64_reg64_reg64(buf, hi1, hi1, hi2);
+        } else {
+            ASM::ult_reg64_reg64_reg64(buf, hi1, hi1, hi2);
+        }
+        let dst_reg = self.storage_manager.claim_general_reg(buf, dst);
+        ASM::or_reg64_reg64_reg64(buf, dst_reg, lo1, hi1);
+        if negate {
+            ASM::mov_reg64_imm64(buf, lo1, 1);
+            ASM::xor_reg64_reg64_reg64(buf, dst_reg, dst_reg, lo1);
+        }
+
+        self.free_symbol(&Symbol::DEV_TMP);
+        self.free_symbol(&Symbol::DEV_TMP2);
+        self.free_symbol(&Symbol::DEV_TMP3);
+        self.free_symbol(&Symbol::DEV_TMP4);
+    }
 }
 
 #[macro_export]
@@ -1743,7 +2484,10 @@ macro_rules! single_register_int_builtins {
 #[macro_export]
 macro_rules! single_register_integers {
     () => {
-        Layout::Builtin(Builtin::Bool | single_register_int_builtins!()) | Layout::RecursivePointer
+        Layout::Builtin(Builtin::Bool | single_register_int_builtins!())
+            | Layout::RecursivePointer
+            | Layout::Boxed(_)
+            | Layout::Union(roc_mono::layout::UnionLayout::NullablePointer { .. })
     };
 }
 
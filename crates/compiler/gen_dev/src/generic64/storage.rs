@@ -1,15 +1,15 @@
 use crate::{
-    generic64::{Assembler, CallConv, RegTrait},
+    generic64::{live_ranges::LiveRanges, Assembler, CallConv, RegTrait},
     sign_extended_int_builtins, single_register_floats, single_register_int_builtins,
     single_register_integers, single_register_layouts, Env,
 };
 use bumpalo::collections::Vec;
 use roc_builtins::bitcode::{FloatWidth, IntWidth};
-use roc_collections::all::{MutMap, MutSet};
+use roc_collections::{OrderedMap, SmallVec};
 use roc_error_macros::internal_error;
 use roc_module::symbol::Symbol;
 use roc_mono::{
-    ir::{JoinPointId, Param},
+    ir::{JoinPointId, Param, Stmt},
     layout::{Builtin, Layout, TagIdIntType, UnionLayout},
 };
 use roc_target::TargetInfo;
@@ -21,6 +21,10 @@ use RegStorage::*;
 use StackStorage::*;
 use Storage::*;
 
+/// Room for the callee saved registers of any of the calling conventions, so keeping track of
+/// them doesn't allocate.
+const INLINE_CALLEE_SAVED_REGS: usize = 16;
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum RegStorage<GeneralReg: RegTrait, FloatReg: RegTrait> {
     General(GeneralReg),
@@ -89,34 +93,37 @@ pub struct StorageManager<
     pub(crate) env: &'a Env<'a>,
     target_info: TargetInfo,
     // Data about where each symbol is stored.
-    symbol_storage_map: MutMap<Symbol, Storage<GeneralReg, FloatReg>>,
+    // It's iterated over to free everything to the stack, so it needs a deterministic order.
+    symbol_storage_map: OrderedMap<Symbol, Storage<GeneralReg, FloatReg>>,
 
     // A map from symbol to its owning allocation.
     // This is only used for complex data on the stack and its references.
     // In the case that subdata is still referenced from an overall structure,
     // We can't free the entire structure until the subdata is no longer needed.
     // If a symbol has only one reference, we can free it.
-    allocation_map: MutMap<Symbol, Rc<(i32, u32)>>,
+    allocation_map: OrderedMap<Symbol, Rc<(i32, u32)>>,
 
     // The storage for parameters of a join point.
     // When jumping to the join point, the parameters should be setup to match this.
-    join_param_map: MutMap<JoinPointId, Vec<'a, Storage<GeneralReg, FloatReg>>>,
+    join_param_map: OrderedMap<JoinPointId, Vec<'a, Storage<GeneralReg, FloatReg>>>,
 
     // This should probably be smarter than a vec.
     // There are certain registers we should always use first. With pushing and popping, this could get mixed.
     general_free_regs: Vec<'a, GeneralReg>,
     float_free_regs: Vec<'a, FloatReg>,
 
-    // The last major thing we need is a way to decide what reg to free when all of them are full.
-    // Theoretically we want a basic lru cache for the currently loaded symbols.
-    // For now just a vec of used registers and the symbols they contain.
+    // The registers in use and the symbols they contain.
+    // When all of them are full, the live ranges decide which one to spill.
     general_used_regs: Vec<'a, (GeneralReg, Symbol)>,
     float_used_regs: Vec<'a, (FloatReg, Symbol)>,
 
-    // TODO: it probably would be faster to make these a list that linearly scans rather than hashing.
+    // Where each symbol's live interval ends, for choosing registers and spilling.
+    live_ranges: LiveRanges<'a>,
+
     // used callee saved regs must be tracked for pushing and popping at the beginning/end of the function.
-    general_used_callee_saved_regs: MutSet<GeneralReg>,
-    float_used_callee_saved_regs: MutSet<FloatReg>,
+    // There are only a few of them, so these are scanned linearly rather than hashed.
+    general_used_callee_saved_regs: SmallVec<'a, GeneralReg, INLINE_CALLEE_SAVED_REGS>,
+    float_used_callee_saved_regs: SmallVec<'a, FloatReg, INLINE_CALLEE_SAVED_REGS>,
 
     free_stack_chunks: Vec<'a, (i32, u32)>,
     stack_size: u32,
@@ -140,15 +147,16 @@ pub fn new_storage_manager<
         phantom_cc: PhantomData,
         env,
         target_info,
-        symbol_storage_map: MutMap::default(),
-        allocation_map: MutMap::default(),
-        join_param_map: MutMap::default(),
+        symbol_storage_map: OrderedMap::default(),
+        allocation_map: OrderedMap::default(),
+        join_param_map: OrderedMap::default(),
         general_free_regs: bumpalo::vec![in env.arena],
         general_used_regs: bumpalo::vec![in env.arena],
-        general_used_callee_saved_regs: MutSet::default(),
+        general_used_callee_saved_regs: SmallVec::new_in(env.arena),
         float_free_regs: bumpalo::vec![in env.arena],
         float_used_regs: bumpalo::vec![in env.arena],
-        float_used_callee_saved_regs: MutSet::default(),
+        float_used_callee_saved_regs: SmallVec::new_in(env.arena),
+        live_ranges: LiveRanges::new(env.arena),
         free_stack_chunks: bumpalo::vec![in env.arena],
         stack_size: 0,
         fn_call_stack_size: 0,
@@ -177,6 +185,7 @@ impl<
         self.float_used_regs.clear();
         self.float_free_regs
             .extend_from_slice(CC::FLOAT_DEFAULT_FREE_REGS);
+        self.live_ranges.clear();
         self.free_stack_chunks.clear();
         self.stack_size = 0;
         self.fn_call_stack_size = 0;
@@ -194,16 +203,30 @@ impl<
         self.fn_call_stack_size
     }
 
-    pub fn general_used_callee_saved_regs(&self) -> Vec<'a, GeneralReg> {
-        let mut used_regs = bumpalo::vec![in self.env.arena];
-        used_regs.extend(&self.general_used_callee_saved_regs);
-        used_regs
+    pub fn general_used_callee_saved_regs(
+        &self,
+    ) -> SmallVec<'a, GeneralReg, INLINE_CALLEE_SAVED_REGS> {
+        self.general_used_callee_saved_regs.clone()
+    }
+
+    pub fn float_used_callee_saved_regs(&self) -> SmallVec<'a, FloatReg, INLINE_CALLEE_SAVED_REGS> {
+        self.float_used_callee_saved_regs.clone()
+    }
+
+    /// Numbers the next statement for the live ranges.
+    /// This must be called in the order statements are built.
+    pub fn scan_stmt(&mut self, stmt: &Stmt<'a>) {
+        self.live_ranges.scan_stmt(stmt);
+    }
+
+    /// Records that the symbol is defined or used in the statement that was scanned last.
+    pub fn add_use(&mut self, sym: Symbol) {
+        self.live_ranges.add_use(sym);
     }
 
-    pub fn float_used_callee_saved_regs(&self) -> Vec<'a, FloatReg> {
-        let mut used_regs = bumpalo::vec![in self.env.arena];
-        used_regs.extend(&self.float_used_callee_saved_regs);
-        used_regs
+    /// Moves the live ranges to the statement at the given position, which is about to be built.
+    pub fn start_stmt(&mut self, position: u32) {
+        self.live_ranges.start_stmt(position);
     }
 
     /// Returns true if the symbol is storing a primitive value.
@@ -214,16 +237,24 @@ impl<
         )
     }
 
-    /// Get a general register from the free list.
+    /// Get a general register from the free list for the symbol, or for a temporary value if there is no symbol.
     /// Will free data to the stack if necessary to get the register.
-    fn get_general_reg(&mut self, buf: &mut Vec<'a, u8>) -> GeneralReg {
-        if let Some(reg) = self.general_free_regs.pop() {
+    fn get_general_reg(&mut self, buf: &mut Vec<'a, u8>, sym: Option<&Symbol>) -> GeneralReg {
+        let wants_callee_saved = sym.map_or(false, |sym| self.live_ranges.crosses_call(sym));
+        if let Some(reg) = pick_free_reg(
+            &mut self.general_free_regs,
+            CC::general_callee_saved,
+            wants_callee_saved,
+        ) {
             if CC::general_callee_saved(&reg) {
-                self.general_used_callee_saved_regs.insert(reg);
+                self.general_used_callee_saved_regs.push_unique(reg);
             }
             reg
-        } else if !self.general_used_regs.is_empty() {
-            let (reg, sym) = self.general_used_regs.remove(0);
+        } else if let Some(index) = self
+            .live_ranges
+            .spill_candidate(self.general_used_regs.iter().map(|(_, sym)| sym))
+        {
+            let (reg, sym) = self.general_used_regs.remove(index);
             self.free_to_stack(buf, &sym, General(reg));
             reg
         } else {
@@ -231,20 +262,28 @@ impl<
         }
     }
 
-    /// Get a float register from the free list.
+    /// Get a float register from the free list for the symbol, or for a temporary value if there is no symbol.
     /// Will free data to the stack if necessary to get the register.
-    fn get_float_reg(&mut self, buf: &mut Vec<'a, u8>) -> FloatReg {
-        if let Some(reg) = self.float_free_regs.pop() {
+    fn get_float_reg(&mut self, buf: &mut Vec<'a, u8>, sym: Option<&Symbol>) -> FloatReg {
+        let wants_callee_saved = sym.map_or(false, |sym| self.live_ranges.crosses_call(sym));
+        if let Some(reg) = pick_free_reg(
+            &mut self.float_free_regs,
+            CC::float_callee_saved,
+            wants_callee_saved,
+        ) {
             if CC::float_callee_saved(&reg) {
-                self.float_used_callee_saved_regs.insert(reg);
+                self.float_used_callee_saved_regs.push_unique(reg);
             }
             reg
-        } else if !self.float_used_regs.is_empty() {
-            let (reg, sym) = self.float_used_regs.remove(0);
+        } else if let Some(index) = self
+            .live_ranges
+            .spill_candidate(self.float_used_regs.iter().map(|(_, sym)| sym))
+        {
+            let (reg, sym) = self.float_used_regs.remove(index);
             self.free_to_stack(buf, &sym, Float(reg));
             reg
         } else {
-            internal_error!("completely out of general purpose registers");
+            internal_error!("completely out of float registers");
         }
     }
 
@@ -252,7 +291,7 @@ impl<
     /// They symbol should not already have storage.
     pub fn claim_general_reg(&mut self, buf: &mut Vec<'a, u8>, sym: &Symbol) -> GeneralReg {
         debug_assert_eq!(self.symbol_storage_map.get(sym), None);
-        let reg = self.get_general_reg(buf);
+        let reg = self.get_general_reg(buf, Some(sym));
         self.general_used_regs.push((reg, *sym));
         self.symbol_storage_map.insert(*sym, Reg(General(reg)));
         reg
@@ -262,7 +301,7 @@ impl<
     /// They symbol should not already have storage.
     pub fn claim_float_reg(&mut self, buf: &mut Vec<'a, u8>, sym: &Symbol) -> FloatReg {
         debug_assert_eq!(self.symbol_storage_map.get(sym), None);
-        let reg = self.get_float_reg(buf);
+        let reg = self.get_float_reg(buf, Some(sym));
         self.float_used_regs.push((reg, *sym));
         self.symbol_storage_map.insert(*sym, Reg(Float(reg)));
         reg
@@ -275,7 +314,7 @@ impl<
         buf: &mut Vec<'a, u8>,
         callback: F,
     ) {
-        let reg = self.get_general_reg(buf);
+        let reg = self.get_general_reg(buf, None);
         callback(self, buf, reg);
         self.general_free_regs.push(reg);
     }
@@ -288,7 +327,7 @@ impl<
         buf: &mut Vec<'a, u8>,
         callback: F,
     ) {
-        let reg = self.get_float_reg(buf);
+        let reg = self.get_float_reg(buf, None);
         callback(self, buf, reg);
         self.float_free_regs.push(reg);
     }
@@ -320,7 +359,7 @@ impl<
                 base_offset,
             }) => {
                 debug_assert_eq!(base_offset % 8, 0);
-                let reg = self.get_general_reg(buf);
+                let reg = self.get_general_reg(buf, Some(sym));
                 ASM::mov_reg64_base32(buf, reg, base_offset);
                 self.general_used_regs.push((reg, *sym));
                 self.symbol_storage_map.insert(
@@ -337,7 +376,7 @@ impl<
                 size,
                 sign_extend,
             }) => {
-                let reg = self.get_general_reg(buf);
+                let reg = self.get_general_reg(buf, Some(sym));
                 if sign_extend {
                     ASM::movsx_reg64_base32(buf, reg, base_offset, size as u8);
                 } else {
@@ -384,7 +423,7 @@ impl<
                 base_offset,
             }) => {
                 debug_assert_eq!(base_offset % 8, 0);
-                let reg = self.get_float_reg(buf);
+                let reg = self.get_float_reg(buf, Some(sym));
                 ASM::mov_freg64_base32(buf, reg, base_offset);
                 self.float_used_regs.push((reg, *sym));
                 self.symbol_storage_map.insert(
@@ -400,7 +439,7 @@ impl<
                 base_offset, size, ..
             }) if base_offset % 8 == 0 && size == 8 => {
                 // The primitive is aligned and the data is exactly 8 bytes, treat it like regular stack.
-                let reg = self.get_float_reg(buf);
+                let reg = self.get_float_reg(buf, Some(sym));
                 ASM::mov_freg64_base32(buf, reg, base_offset);
                 self.float_used_regs.push((reg, *sym));
                 self.symbol_storage_map.insert(*sym, Reg(Float(reg)));
@@ -578,11 +617,26 @@ impl<
 
     pub fn load_union_tag_id(
         &mut self,
-        _buf: &mut Vec<'a, u8>,
+        buf: &mut Vec<'a, u8>,
         sym: &Symbol,
         structure: &Symbol,
         union_layout: &UnionLayout<'a>,
     ) {
+        if let UnionLayout::NullablePointer { nullable_id, .. } = union_layout {
+            // The tag id is whether the pointer is null, for the tag that is null.
+            let dst_reg = self.claim_general_reg(buf, sym);
+            let ptr_reg = self.load_to_general_reg(buf, structure);
+            self.with_tmp_general_reg(buf, |_storage_manager, buf, null_reg| {
+                ASM::mov_reg64_imm64(buf, null_reg, 0);
+                if *nullable_id {
+                    ASM::eq_reg64_reg64_reg64(buf, dst_reg, ptr_reg, null_reg);
+                } else {
+                    ASM::neq_reg64_reg64_reg64(buf, dst_reg, ptr_reg, null_reg);
+                }
+            });
+            return;
+        }
+
         // This must be removed and reinserted for ownership and mutability reasons.
         let owned_data = self.remove_allocation_for_sym(structure);
         self.allocation_map
@@ -591,9 +645,9 @@ impl<
             UnionLayout::NonRecursive(_) => {
                 let (union_offset, _) = self.stack_offset_and_size(structure);
 
-                let (data_size, data_alignment) = union_layout
-                    .data_size_and_alignment(self.env.layout_interner, self.target_info);
-                let id_offset = data_size - data_alignment;
+                let id_offset = union_layout
+                    .tag_id_offset(self.env.layout_interner, self.target_info)
+                    .unwrap();
                 let discriminant = union_layout.discriminant();
 
                 let size = discriminant.stack_size();
@@ -668,12 +722,12 @@ impl<
     ) {
         match union_layout {
             UnionLayout::NonRecursive(field_layouts) => {
-                let (data_size, data_alignment) = union_layout
+                let (data_size, _) = union_layout
                     .data_size_and_alignment(self.env.layout_interner, self.target_info);
-                let id_offset = data_size - data_alignment;
-                if data_alignment < 8 || data_alignment % 8 != 0 {
-                    todo!("small/unaligned tagging");
-                }
+                let id_offset = union_layout
+                    .tag_id_offset(self.env.layout_interner, self.target_info)
+                    .unwrap();
+                let id_size = union_layout.discriminant().stack_size() as u8;
                 let base_offset = self.claim_stack_area(sym, data_size);
                 let mut current_offset = base_offset;
                 for (field, field_layout) in
@@ -684,11 +738,28 @@ impl<
                         field_layout.stack_size(self.env.layout_interner, self.target_info);
                     current_offset += field_size as i32;
                 }
-                self.with_tmp_general_reg(buf, |_symbol_storage, buf, reg| {
-                    ASM::mov_reg64_imm64(buf, reg, tag_id as i64);
-                    debug_assert!((base_offset + id_offset as i32) % 8 == 0);
-                    ASM::mov_base32_reg64(buf, base_offset + id_offset as i32, reg);
-                });
+                if id_size > 0 {
+                    self.with_tmp_general_reg(buf, |_symbol_storage, buf, reg| {
+                        ASM::mov_reg64_imm64(buf, reg, tag_id as i64);
+                        ASM::mov_base32_reg_sized(
+                            buf,
+                            base_offset + id_offset as i32,
+                            reg,
+                            id_size,
+                        );
+                    });
+                }
+            }
+            UnionLayout::NullablePointer { nullable_id, .. } => {
+                let reg = self.claim_general_reg(buf, sym);
+                if tag_id == *nullable_id as TagIdIntType {
+                    ASM::mov_reg64_imm64(buf, reg, 0);
+                } else {
+                    // The union is the pointer in its one field.
+                    debug_assert_eq!(fields.len(), 1);
+                    let pointer_reg = self.load_to_general_reg(buf, &fields[0]);
+                    ASM::mov_reg64_reg64(buf, reg, pointer_reg);
+                }
             }
             x => todo!("creating unions with layout: {:?}", x),
         }
@@ -724,12 +795,24 @@ impl<
         sym: &Symbol,
         layout: &Layout<'a>,
     ) {
+        let layout = layout.runtime_representation(self.env.layout_interner);
         match layout {
-            Layout::Builtin(Builtin::Int(IntWidth::I64 | IntWidth::U64)) => {
+            Layout::Builtin(Builtin::Int(IntWidth::I64 | IntWidth::U64))
+            | Layout::Boxed(_)
+            | Layout::Union(UnionLayout::NullablePointer { .. }) => {
                 debug_assert_eq!(to_offset % 8, 0);
                 let reg = self.load_to_general_reg(buf, sym);
                 ASM::mov_base32_reg64(buf, to_offset, reg);
             }
+            Layout::Builtin(Builtin::Int(int_width)) if int_width.stack_size() < 8 => {
+                let size = int_width.stack_size() as u8;
+                let reg = self.load_to_general_reg(buf, sym);
+                ASM::mov_base32_reg_sized(buf, to_offset, reg, size);
+            }
+            Layout::Builtin(Builtin::Bool) => {
+                let reg = self.load_to_general_reg(buf, sym);
+                ASM::mov_base32_reg_sized(buf, to_offset, reg, 1);
+            }
             Layout::Builtin(Builtin::Float(FloatWidth::F64)) => {
                 debug_assert_eq!(to_offset % 8, 0);
                 let reg = self.load_to_float_reg(buf, sym);
@@ -755,11 +838,31 @@ impl<
                     }
                 });
             }
+            // Small aggregates like captured closure data may not be a multiple of 8 bytes.
+            // They are copied in the largest chunks that fit.
+            Layout::Struct { .. } | Layout::Union(_) => {
+                let (from_offset, _) = self.stack_offset_and_size(sym);
+                let size = layout.stack_size(self.env.layout_interner, self.target_info);
+                debug_assert!(size <= 8);
+                self.with_tmp_general_reg(buf, |_storage_manager, buf, reg| {
+                    let mut copied = 0;
+                    while copied < size {
+                        let chunk = match size - copied {
+                            8.. => 8,
+                            4..=7 => 4,
+                            2..=3 => 2,
+                            _ => 1,
+                        };
+                        ASM::movzx_reg64_base32(buf, reg, from_offset + copied as i32, chunk);
+                        ASM::mov_base32_reg_sized(buf, to_offset + copied as i32, reg, chunk);
+                        copied += chunk as u32;
+                    }
+                });
+            }
             x => todo!("copying data to the stack with layout, {:?}", x),
         }
     }
 
-    #[allow(dead_code)]
     /// Ensures that a register is free. If it is not free, data will be moved to make it free.
     pub fn ensure_reg_free(
         &mut self,
@@ -982,18 +1085,40 @@ impl<
         self.fn_call_stack_size = max(self.fn_call_stack_size, tmp_size);
     }
 
+    /// Keeps the callee saved registers used by code that was built from a clone of this storage manager.
+    /// They have to be saved by the procedure even if that code does not always run.
+    pub fn merge_used_callee_saved_regs(&mut self, other: &Self) {
+        for reg in other.general_used_callee_saved_regs.iter() {
+            self.general_used_callee_saved_regs.push_unique(*reg);
+        }
+        for reg in other.float_used_callee_saved_regs.iter() {
+            self.float_used_callee_saved_regs.push_unique(*reg);
+        }
+    }
+
+    /// Keeps the stack sizes and callee saved registers needed by code that was built from a clone of this storage manager.
+    pub fn merge_branch(&mut self, other: &Self) {
+        self.update_stack_size(other.stack_size);
+        self.update_fn_call_stack_size(other.fn_call_stack_size);
+        self.merge_used_callee_saved_regs(other);
+    }
+
     /// Setups a join point.
     /// To do this, each of the join pionts params are given a storage location.
-    /// Then those locations are stored.
+    /// Primitives get registers, like block parameters, so loops keep their state in registers.
+    /// Everything else is put on the stack.
     /// Later jumps to the join point can overwrite the stored locations to pass parameters.
+    /// The registers stay free until the body of the join point is built, see enter_joinpoint.
     pub fn setup_joinpoint(
         &mut self,
-        _buf: &mut Vec<'a, u8>,
+        buf: &mut Vec<'a, u8>,
         id: &JoinPointId,
         params: &'a [Param<'a>],
     ) {
         let mut param_storage = bumpalo::vec![in self.env.arena];
         param_storage.reserve(params.len());
+        let mut general_regs = bumpalo::vec![in self.env.arena];
+        let mut float_regs = bumpalo::vec![in self.env.arena];
         for Param {
             symbol,
             borrow,
@@ -1006,8 +1131,21 @@ impl<
                 todo!("joinpoints with borrowed parameters");
             }
             // Claim a location for every join point parameter to be loaded at.
-            // Put everything on the stack for simplicity.
+            let layout = layout.runtime_representation(self.env.layout_interner);
             match layout {
+                single_register_integers!() if !self.general_free_regs.is_empty() => {
+                    let reg = self.get_general_reg(buf, Some(symbol));
+                    general_regs.push(reg);
+                    param_storage.push(Reg(General(reg)));
+                    continue;
+                }
+                single_register_floats!() if !self.float_free_regs.is_empty() => {
+                    let reg = self.get_float_reg(buf, Some(symbol));
+                    float_regs.push(reg);
+                    param_storage.push(Reg(Float(reg)));
+                    continue;
+                }
+                // There are more params than registers, so the rest go on the stack.
                 single_register_layouts!() => {
                     let base_offset = self.claim_stack_size(8);
                     self.symbol_storage_map.insert(
@@ -1031,9 +1169,26 @@ impl<
             }
             param_storage.push(*self.get_storage_for_sym(symbol));
         }
+        self.general_free_regs.extend(general_regs);
+        self.float_free_regs.extend(float_regs);
         self.join_param_map.insert(*id, param_storage);
     }
 
+    /// Starts the body of a join point, where the params that are passed in registers have arrived.
+    pub fn enter_joinpoint(&mut self, id: &JoinPointId, params: &'a [Param<'a>]) {
+        let param_storage = match self.join_param_map.get(id) {
+            Some(storages) => storages.clone(),
+            None => internal_error!("Join: unknown join point: {:?}", id),
+        };
+        for (Param { symbol, .. }, storage) in params.iter().zip(param_storage.iter()) {
+            match storage {
+                Reg(General(reg)) => self.general_reg_arg(symbol, *reg),
+                Reg(Float(reg)) => self.float_reg_arg(symbol, *reg),
+                _ => {}
+            }
+        }
+    }
+
     /// Setup jump loads the parameters for the joinpoint.
     /// This enables the jump to correctly passe arguments to the joinpoint.
     pub fn setup_jump(
@@ -1049,6 +1204,7 @@ impl<
             Some(storages) => storages,
             None => internal_error!("Jump: unknown point specified to jump to: {:?}", id),
         };
+        let mut reg_args = bumpalo::vec![in self.env.arena];
         for ((sym, layout), wanted_storage) in
             args.iter().zip(arg_layouts).zip(param_storage.iter())
         {
@@ -1057,8 +1213,9 @@ impl<
                 continue;
             }
             match wanted_storage {
-                Reg(_) => {
-                    internal_error!("Register storage is not allowed for jumping to joinpoint")
+                Reg(reg) => {
+                    // Registers are filled last, since loading the other params may need registers.
+                    reg_args.push((*sym, *reg));
                 }
                 Stack(Complex { base_offset, .. }) => {
                     // TODO: This might be better not to call.
@@ -1099,9 +1256,58 @@ impl<
                 }
             }
         }
+        self.move_to_param_regs(buf, &reg_args);
         self.join_param_map.insert(*id, param_storage);
     }
 
+    /// Loads every symbol into its param register, as if all of them were moved at once.
+    /// A register may still hold a symbol that has to go to another param, like when swapping two params.
+    fn move_to_param_regs(
+        &mut self,
+        buf: &mut Vec<'a, u8>,
+        reg_args: &[(Symbol, RegStorage<GeneralReg, FloatReg>)],
+    ) {
+        // Load referenced primitives first, so every symbol is in a register or a stack slot of its own.
+        for (sym, reg) in reg_args.iter() {
+            if let Stack(ReferencedPrimitive { .. }) = self.get_storage_for_sym(sym) {
+                match reg {
+                    General(_) => {
+                        self.load_to_general_reg(buf, sym);
+                    }
+                    Float(_) => {
+                        self.load_to_float_reg(buf, sym);
+                    }
+                }
+            }
+        }
+        // Move any other symbol out of the param registers.
+        for (sym, reg) in reg_args.iter() {
+            let owner = match reg {
+                General(reg) => self
+                    .general_used_regs
+                    .iter()
+                    .find(|(used_reg, _)| used_reg == reg)
+                    .map(|(_, owner)| *owner),
+                Float(reg) => self
+                    .float_used_regs
+                    .iter()
+                    .find(|(used_reg, _)| used_reg == reg)
+                    .map(|(_, owner)| *owner),
+            };
+            if matches!(owner, Some(owner) if owner != *sym) {
+                self.ensure_reg_free(buf, *reg);
+            }
+        }
+        // Now the param registers only hold their own symbol, so no load overwrites a symbol that is still needed.
+        // This leaves the loaded registers untracked, which is fine since nothing runs after the jump.
+        for (sym, reg) in reg_args.iter() {
+            match reg {
+                General(reg) => self.load_to_specified_general_reg(buf, sym, *reg),
+                Float(reg) => self.load_to_specified_float_reg(buf, sym, *reg),
+            }
+        }
+    }
+
     /// claim_stack_area is the public wrapper around claim_stack_size.
     /// It also deals with updating symbol storage.
     /// It returns the base offset of the stack area.
@@ -1315,3 +1521,18 @@ impl<
 fn is_primitive(layout: &Layout<'_>) -> bool {
     matches!(layout, single_register_layouts!())
 }
+
+/// Takes the free register to use next from the end of the free list.
+/// Symbols that live across a call go in callee saved registers so they don't need to be spilled around it.
+/// Everything else prefers caller saved registers, which don't need to be saved in the prologue.
+fn pick_free_reg<Reg: RegTrait>(
+    free_regs: &mut Vec<'_, Reg>,
+    callee_saved: fn(&Reg) -> bool,
+    wants_callee_saved: bool,
+) -> Option<Reg> {
+    let index = free_regs
+        .iter()
+        .rposition(|reg| callee_saved(reg) == wants_callee_saved)
+        .or_else(|| free_regs.len().checked_sub(1))?;
+    Some(free_regs.remove(index))
+}
@@ -252,7 +252,7 @@ impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64Syste
 
     #[inline(always)]
     fn load_args<'a>(
-        _buf: &mut Vec<'a, u8>,
+        buf: &mut Vec<'a, u8>,
         storage_manager: &mut StorageManager<
             'a,
             X86_64GeneralReg,
@@ -291,17 +291,56 @@ impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64Syste
                         arg_offset += 8;
                     }
                 }
+                Layout::Builtin(
+                    Builtin::Int(IntWidth::I128 | IntWidth::U128) | Builtin::Decimal,
+                ) => {
+                    // 128 bit values are passed in two general registers, low word first.
+                    if general_i + 2 <= Self::GENERAL_PARAM_REGS.len() {
+                        let base_offset = storage_manager.claim_stack_area(sym, stack_size);
+                        X86_64Assembler::mov_base32_reg64(
+                            buf,
+                            base_offset,
+                            Self::GENERAL_PARAM_REGS[general_i],
+                        );
+                        X86_64Assembler::mov_base32_reg64(
+                            buf,
+                            base_offset + 8,
+                            Self::GENERAL_PARAM_REGS[general_i + 1],
+                        );
+                        general_i += 2;
+                    } else {
+                        // On the stack they are 16 byte aligned.
+                        arg_offset += arg_offset % 16;
+                        storage_manager.complex_stack_arg(sym, arg_offset, stack_size);
+                        arg_offset += 16;
+                    }
+                }
                 _ if stack_size == 0 => {
                     storage_manager.no_data_arg(sym);
                 }
-                _ if stack_size > 16 => {
+                _ if stack_size <= 16 => {
+                    // Small aggregates, like closure captures, are passed in 8 byte chunks.
+                    let chunks = (stack_size as usize + 7) / 8;
+                    if general_i + chunks <= Self::GENERAL_PARAM_REGS.len() {
+                        let base_offset = storage_manager.claim_stack_area(sym, stack_size);
+                        for i in 0..chunks {
+                            X86_64Assembler::mov_base32_reg64(
+                                buf,
+                                base_offset + 8 * i as i32,
+                                Self::GENERAL_PARAM_REGS[general_i + i],
+                            );
+                        }
+                        general_i += chunks;
+                    } else {
+                        storage_manager.complex_stack_arg(sym, arg_offset, stack_size);
+                        arg_offset += 8 * chunks as i32;
+                    }
+                }
+                _ => {
                     // TODO: Double check this.
                     storage_manager.complex_stack_arg(sym, arg_offset, stack_size);
                     arg_offset += stack_size as i32;
                 }
-                x => {
-                    todo!("Loading args with layout {:?}", x);
-                }
             }
         }
     }
@@ -388,7 +427,72 @@ impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64Syste
                         tmp_stack_offset += 8;
                     }
                 }
+                Layout::Builtin(
+                    Builtin::Int(IntWidth::I128 | IntWidth::U128) | Builtin::Decimal,
+                ) => {
+                    let (base_offset, _) = storage_manager.stack_offset_and_size(sym);
+                    if general_i + 2 <= Self::GENERAL_PARAM_REGS.len() {
+                        X86_64Assembler::mov_reg64_base32(
+                            buf,
+                            Self::GENERAL_PARAM_REGS[general_i],
+                            base_offset,
+                        );
+                        X86_64Assembler::mov_reg64_base32(
+                            buf,
+                            Self::GENERAL_PARAM_REGS[general_i + 1],
+                            base_offset + 8,
+                        );
+                        general_i += 2;
+                    } else {
+                        // Copy to the 16 byte aligned stack slot using return reg as buffer.
+                        tmp_stack_offset += tmp_stack_offset % 16;
+                        for i in [0, 8] {
+                            X86_64Assembler::mov_reg64_base32(
+                                buf,
+                                Self::GENERAL_RETURN_REGS[0],
+                                base_offset + i,
+                            );
+                            X86_64Assembler::mov_stack32_reg64(
+                                buf,
+                                tmp_stack_offset + i,
+                                Self::GENERAL_RETURN_REGS[0],
+                            );
+                        }
+                        tmp_stack_offset += 16;
+                    }
+                }
                 x if x.stack_size(storage_manager.env.layout_interner, TARGET_INFO) == 0 => {}
+                x if x.stack_size(storage_manager.env.layout_interner, TARGET_INFO) <= 16 => {
+                    // Small aggregates, like closure captures, are passed in 8 byte chunks.
+                    let stack_size = x.stack_size(storage_manager.env.layout_interner, TARGET_INFO);
+                    let chunks = (stack_size as usize + 7) / 8;
+                    let (base_offset, _) = storage_manager.stack_offset_and_size(sym);
+                    if general_i + chunks <= Self::GENERAL_PARAM_REGS.len() {
+                        for i in 0..chunks {
+                            X86_64Assembler::mov_reg64_base32(
+                                buf,
+                                Self::GENERAL_PARAM_REGS[general_i + i],
+                                base_offset + 8 * i as i32,
+                            );
+                        }
+                        general_i += chunks;
+                    } else {
+                        // Copy to stack using return reg as buffer.
+                        for i in 0..chunks as i32 {
+                            X86_64Assembler::mov_reg64_base32(
+                                buf,
+                                Self::GENERAL_RETURN_REGS[0],
+                                base_offset + 8 * i,
+                            );
+                            X86_64Assembler::mov_stack32_reg64(
+                                buf,
+                                tmp_stack_offset + 8 * i,
+                                Self::GENERAL_RETURN_REGS[0],
+                            );
+                        }
+                        tmp_stack_offset += 8 * chunks as i32;
+                    }
+                }
                 x if x.stack_size(storage_manager.env.layout_interner, TARGET_INFO) > 16 => {
                     // TODO: Double check this.
                     // Just copy onto the stack.
@@ -575,7 +679,7 @@ impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64Windo
         // We will use pop to get which reg to use next
         // Use callee saved regs last.
         X86_64FloatReg::XMM15,
-        X86_64FloatReg::XMM15,
+        X86_64FloatReg::XMM14,
         X86_64FloatReg::XMM13,
         X86_64FloatReg::XMM12,
         X86_64FloatReg::XMM11,
@@ -614,12 +718,16 @@ impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64Windo
     fn float_callee_saved(reg: &X86_64FloatReg) -> bool {
         matches!(
             reg,
-            X86_64FloatReg::XMM0
-                | X86_64FloatReg::XMM1
-                | X86_64FloatReg::XMM2
-                | X86_64FloatReg::XMM3
-                | X86_64FloatReg::XMM4
-                | X86_64FloatReg::XMM5
+            X86_64FloatReg::XMM6
+                | X86_64FloatReg::XMM7
+                | X86_64FloatReg::XMM8
+                | X86_64FloatReg::XMM9
+                | X86_64FloatReg::XMM10
+                | X86_64FloatReg::XMM11
+                | X86_64FloatReg::XMM12
+                | X86_64FloatReg::XMM13
+                | X86_64FloatReg::XMM14
+                | X86_64FloatReg::XMM15
         )
     }
 
@@ -657,9 +765,24 @@ impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64Windo
         )
     }
 
+    fn unwind_info<'a>(
+        buf: &mut Vec<'a, u8>,
+        saved_general_regs: &[X86_64GeneralReg],
+        _saved_float_regs: &[X86_64FloatReg],
+        aligned_stack_size: i32,
+        fn_call_stack_size: i32,
+    ) {
+        x86_64_windows_unwind_info(
+            buf,
+            saved_general_regs,
+            aligned_stack_size,
+            fn_call_stack_size,
+        )
+    }
+
     #[inline(always)]
     fn load_args<'a>(
-        _buf: &mut Vec<'a, u8>,
+        buf: &mut Vec<'a, u8>,
         storage_manager: &mut StorageManager<
             'a,
             X86_64GeneralReg,
@@ -670,7 +793,10 @@ impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64Windo
         args: &'a [(Layout<'a>, Symbol)],
         ret_layout: &Layout<'a>,
     ) {
-        let mut arg_offset = Self::SHADOW_SPACE_SIZE as i32 + 16; // 16 is the size of the pushed return address and base pointer.
+        // 16 is the size of the pushed return address and base pointer.
+        let mut arg_offset = Self::SHADOW_SPACE_SIZE as i32 + 16;
+        // Every arg takes the next slot, whether it is an integer or a float.
+        // The first 4 slots are passed in registers, the rest on the stack.
         let mut i = 0;
         if X86_64WindowsFastcall::returns_via_arg_pointer(
             storage_manager.env.layout_interner,
@@ -680,31 +806,68 @@ impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64Windo
             i += 1;
         }
         for (layout, sym) in args.iter() {
-            if i < Self::GENERAL_PARAM_REGS.len() {
-                match layout {
-                    single_register_integers!() => {
+            let stack_size = layout.stack_size(storage_manager.env.layout_interner, TARGET_INFO);
+            match layout {
+                single_register_integers!() => {
+                    if i < Self::GENERAL_PARAM_REGS.len() {
                         storage_manager.general_reg_arg(sym, Self::GENERAL_PARAM_REGS[i]);
-                        i += 1;
+                    } else {
+                        storage_manager.primitive_stack_arg(sym, arg_offset);
+                        arg_offset += 8;
                     }
-                    single_register_floats!() => {
+                    i += 1;
+                }
+                single_register_floats!() => {
+                    if i < Self::FLOAT_PARAM_REGS.len() {
                         storage_manager.float_reg_arg(sym, Self::FLOAT_PARAM_REGS[i]);
-                        i += 1;
-                    }
-                    x if x.stack_size(storage_manager.env.layout_interner, TARGET_INFO) == 0 => {}
-                    x => {
-                        todo!("Loading args with layout {:?}", x);
+                    } else {
+                        storage_manager.primitive_stack_arg(sym, arg_offset);
+                        arg_offset += 8;
                     }
+                    i += 1;
                 }
-            } else {
-                match layout {
-                    single_register_layouts!() => {
-                        storage_manager.primitive_stack_arg(sym, arg_offset);
+                _ if stack_size == 0 => {
+                    storage_manager.no_data_arg(sym);
+                }
+                _ if Self::passed_by_reference(stack_size) => {
+                    // The caller passes a pointer to a copy it owns.
+                    // Copy the value into this function's stack frame.
+                    // R11 is volatile and not a param reg, so it is free to use here.
+                    let tmp_reg = X86_64GeneralReg::R11;
+                    let base_offset = storage_manager.claim_stack_area(sym, stack_size);
+                    let pointer_reg = if i < Self::GENERAL_PARAM_REGS.len() {
+                        Self::GENERAL_PARAM_REGS[i]
+                    } else {
+                        X86_64Assembler::mov_reg64_base32(buf, X86_64GeneralReg::RAX, arg_offset);
                         arg_offset += 8;
+                        X86_64GeneralReg::RAX
+                    };
+                    i += 1;
+                    for offset in (0..stack_size as i32).step_by(8) {
+                        X86_64Assembler::mov_reg64_mem64_offset32(
+                            buf,
+                            tmp_reg,
+                            pointer_reg,
+                            offset,
+                        );
+                        X86_64Assembler::mov_base32_reg64(buf, base_offset + offset, tmp_reg);
                     }
-                    x => {
-                        todo!("Loading args with layout {:?}", x);
+                }
+                _ => {
+                    // Values of 1, 2, 4, or 8 bytes are passed like an integer of the same size.
+                    if i < Self::GENERAL_PARAM_REGS.len() {
+                        let base_offset = storage_manager.claim_stack_area(sym, stack_size);
+                        X86_64Assembler::mov_base32_reg64(
+                            buf,
+                            base_offset,
+                            Self::GENERAL_PARAM_REGS[i],
+                        );
+                    } else {
+                        storage_manager.complex_stack_arg(sym, arg_offset, stack_size);
+                        arg_offset += 8;
                     }
-                };
+                    i += 1;
+                }
             }
         }
     }
@@ -724,16 +887,38 @@ impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64Windo
         arg_layouts: &[Layout<'a>],
         ret_layout: &Layout<'a>,
     ) {
+        // Use return reg as buffer because it will be empty right now.
+        let tmp_reg = Self::GENERAL_RETURN_REGS[0];
         let mut tmp_stack_offset = Self::SHADOW_SPACE_SIZE as i32;
+        let mut i = 0;
         if Self::returns_via_arg_pointer(storage_manager.env.layout_interner, ret_layout) {
-            // Save space on the stack for the arg we will return.
-            storage_manager.claim_stack_area(
+            // Save space on the stack for the result we will be return.
+            let base_offset = storage_manager.claim_stack_area(
                 dst,
                 ret_layout.stack_size(storage_manager.env.layout_interner, TARGET_INFO),
             );
-            todo!("claim first parama reg for the address");
+            // Set the first reg to the address base + offset.
+            X86_64Assembler::add_reg64_reg64_imm32(
+                buf,
+                Self::GENERAL_PARAM_REGS[i],
+                X86_64GeneralReg::RBP,
+                base_offset,
+            );
+            i += 1;
         }
-        for (i, (sym, layout)) in args.iter().zip(arg_layouts.iter()).enumerate() {
+
+        // Copies of values passed by reference go right after the args passed on the stack.
+        let stack_slots = i + arg_layouts
+            .iter()
+            .filter(|layout| {
+                layout.stack_size(storage_manager.env.layout_interner, TARGET_INFO) > 0
+            })
+            .count();
+        let mut copy_offset = tmp_stack_offset
+            + 8 * stack_slots.saturating_sub(Self::GENERAL_PARAM_REGS.len()) as i32;
+
+        for (sym, layout) in args.iter().zip(arg_layouts.iter()) {
+            let stack_size = layout.stack_size(storage_manager.env.layout_interner, TARGET_INFO);
             match layout {
                 single_register_integers!() => {
                     if i < Self::GENERAL_PARAM_REGS.len() {
@@ -744,18 +929,11 @@ impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64Windo
                         );
                     } else {
                         // Copy to stack using return reg as buffer.
-                        storage_manager.load_to_specified_general_reg(
-                            buf,
-                            sym,
-                            Self::GENERAL_RETURN_REGS[0],
-                        );
-                        X86_64Assembler::mov_stack32_reg64(
-                            buf,
-                            tmp_stack_offset,
-                            Self::GENERAL_RETURN_REGS[0],
-                        );
+                        storage_manager.load_to_specified_general_reg(buf, sym, tmp_reg);
+                        X86_64Assembler::mov_stack32_reg64(buf, tmp_stack_offset, tmp_reg);
                         tmp_stack_offset += 8;
                     }
+                    i += 1;
                 }
                 single_register_floats!() => {
                     if i < Self::FLOAT_PARAM_REGS.len() {
@@ -765,57 +943,128 @@ impl CallConv<X86_64GeneralReg, X86_64FloatReg, X86_64Assembler> for X86_64Windo
                             Self::FLOAT_PARAM_REGS[i],
                         );
                     } else {
-                        // Copy to stack using return reg as buffer.
-                        storage_manager.load_to_specified_float_reg(
+                        // The float param regs may already hold args, so copy through the stack.
+                        storage_manager.ensure_symbol_on_stack(buf, sym);
+                        let (base_offset, _) = storage_manager.stack_offset_and_size(sym);
+                        X86_64Assembler::mov_reg64_base32(buf, tmp_reg, base_offset);
+                        X86_64Assembler::mov_stack32_reg64(buf, tmp_stack_offset, tmp_reg);
+                        tmp_stack_offset += 8;
+                    }
+                    i += 1;
+                }
+                _ if stack_size == 0 => {}
+                _ if Self::passed_by_reference(stack_size) => {
+                    // Make a copy the callee is free to modify and pass a pointer to it.
+                    let (base_offset, size) = storage_manager.stack_offset_and_size(sym);
+                    debug_assert_eq!(base_offset % 8, 0);
+                    for offset in (0..size as i32).step_by(8) {
+                        X86_64Assembler::mov_reg64_base32(buf, tmp_reg, base_offset + offset);
+                        X86_64Assembler::mov_stack32_reg64(buf, copy_offset + offset, tmp_reg);
+                    }
+                    if i < Self::GENERAL_PARAM_REGS.len() {
+                        X86_64Assembler::add_reg64_reg64_imm32(
                             buf,
-                            sym,
-                            Self::FLOAT_RETURN_REGS[0],
+                            Self::GENERAL_PARAM_REGS[i],
+                            X86_64GeneralReg::RSP,
+                            copy_offset,
                         );
-                        X86_64Assembler::mov_stack32_freg64(
+                    } else {
+                        X86_64Assembler::add_reg64_reg64_imm32(
                             buf,
-                            tmp_stack_offset,
-                            Self::FLOAT_RETURN_REGS[0],
+                            tmp_reg,
+                            X86_64GeneralReg::RSP,
+                            copy_offset,
                         );
+                        X86_64Assembler::mov_stack32_reg64(buf, tmp_stack_offset, tmp_reg);
                         tmp_stack_offset += 8;
                     }
+                    copy_offset += size as i32;
+                    i += 1;
                 }
-                x if x.stack_size(storage_manager.env.layout_interner, TARGET_INFO) == 0 => {}
-                x => {
-                    todo!("calling with arg type, {:?}", x);
+                _ => {
+                    // Values of 1, 2, 4, or 8 bytes are passed like an integer of the same size.
+                    let (base_offset, _) = storage_manager.stack_offset_and_size(sym);
+                    if i < Self::GENERAL_PARAM_REGS.len() {
+                        X86_64Assembler::mov_reg64_base32(
+                            buf,
+                            Self::GENERAL_PARAM_REGS[i],
+                            base_offset,
+                        );
+                    } else {
+                        X86_64Assembler::mov_reg64_base32(buf, tmp_reg, base_offset);
+                        X86_64Assembler::mov_stack32_reg64(buf, tmp_stack_offset, tmp_reg);
+                        tmp_stack_offset += 8;
+                    }
+                    i += 1;
                 }
             }
         }
-        storage_manager.update_fn_call_stack_size(tmp_stack_offset as u32);
+        storage_manager.update_fn_call_stack_size(copy_offset as u32);
     }
 
     fn return_complex_symbol<'a>(
-        _buf: &mut Vec<'a, u8>,
-        _storage_manager: &mut StorageManager<
+        buf: &mut Vec<'a, u8>,
+        storage_manager: &mut StorageManager<
             'a,
             X86_64GeneralReg,
             X86_64FloatReg,
             X86_64Assembler,
             X86_64WindowsFastcall,
         >,
-        _sym: &Symbol,
-        _layout: &Layout<'a>,
+        sym: &Symbol,
+        layout: &Layout<'a>,
     ) {
-        todo!("Returning complex symbols for X86_64");
+        match layout {
+            single_register_layouts!() => {
+                internal_error!("single register layouts are not complex symbols");
+            }
+            x if x.stack_size(storage_manager.env.layout_interner, TARGET_INFO) == 0 => {}
+            x if !Self::returns_via_arg_pointer(storage_manager.env.layout_interner, x) => {
+                let (base_offset, _) = storage_manager.stack_offset_and_size(sym);
+                debug_assert_eq!(base_offset % 8, 0);
+                X86_64Assembler::mov_reg64_base32(buf, Self::GENERAL_RETURN_REGS[0], base_offset);
+            }
+            _ => {
+                // This is a large type returned via the arg pointer.
+                storage_manager.copy_symbol_to_arg_pointer(buf, sym, layout);
+                // Also set the return reg to the arg pointer.
+                storage_manager.load_to_specified_general_reg(
+                    buf,
+                    &Symbol::RET_POINTER,
+                    Self::GENERAL_RETURN_REGS[0],
+                );
+            }
+        }
     }
 
     fn load_returned_complex_symbol<'a>(
-        _buf: &mut Vec<'a, u8>,
-        _storage_manager: &mut StorageManager<
+        buf: &mut Vec<'a, u8>,
+        storage_manager: &mut StorageManager<
             'a,
             X86_64GeneralReg,
             X86_64FloatReg,
             X86_64Assembler,
             X86_64WindowsFastcall,
         >,
-        _sym: &Symbol,
-        _layout: &Layout<'a>,
+        sym: &Symbol,
+        layout: &Layout<'a>,
     ) {
-        todo!("Loading returned complex symbols for X86_64");
+        match layout {
+            single_register_layouts!() => {
+                internal_error!("single register layouts are not complex symbols");
+            }
+            x if x.stack_size(storage_manager.env.layout_interner, TARGET_INFO) == 0 => {}
+            x if !Self::returns_via_arg_pointer(storage_manager.env.layout_interner, x) => {
+                let size = layout.stack_size(storage_manager.env.layout_interner, TARGET_INFO);
+                let offset = storage_manager.claim_stack_area(sym, size);
+                X86_64Assembler::mov_base32_reg64(buf, offset, Self::GENERAL_RETURN_REGS[0]);
+            }
+            _ => {
+                // This should have been recieved via an arg pointer.
+                // That means the value is already loaded onto the stack area we allocated before the call.
+                // Nothing to do.
+            }
+        }
     }
 }
 
@@ -826,7 +1075,13 @@ impl X86_64WindowsFastcall {
     ) -> bool {
         // TODO: This is not fully correct there are some exceptions for "vector" types.
         // details here: https://docs.microsoft.com/en-us/cpp/build/x64-calling-convention?view=msvc-160#return-values
-        ret_layout.stack_size(interner, TARGET_INFO) > 8
+        Self::passed_by_reference(ret_layout.stack_size(interner, TARGET_INFO))
+    }
+
+    /// Values that are not 1, 2, 4, or 8 bytes are passed and returned as a pointer to a copy.
+    /// details here: https://docs.microsoft.com/en-us/cpp/build/x64-calling-convention?view=msvc-160#parameter-passing
+    fn passed_by_reference(stack_size: u32) -> bool {
+        !matches!(stack_size, 0 | 1 | 2 | 4 | 8)
     }
 }
 
@@ -916,6 +1171,90 @@ fn x86_64_generic_cleanup_stack<'a>(
     X86_64Assembler::pop_reg64(buf, X86_64GeneralReg::RBP);
 }
 
+// The sizes of the instructions x86_64_generic_setup_stack uses.
+const PUSH_RBP_SIZE: u8 = 1;
+const MOV_RBP_RSP_SIZE: u8 = 3;
+const SUB_RSP_IMM32_SIZE: u8 = 7;
+const MOV_BASE32_REG64_SIZE: u8 = 7;
+
+const UWOP_PUSH_NONVOL: u8 = 0;
+const UWOP_ALLOC_LARGE: u8 = 1;
+const UWOP_ALLOC_SMALL: u8 = 2;
+const UWOP_SAVE_NONVOL: u8 = 4;
+const UWOP_SAVE_NONVOL_FAR: u8 = 5;
+
+/// Describes the prologue of x86_64_generic_setup_stack to the Windows unwinder.
+/// details here: https://docs.microsoft.com/en-us/cpp/build/exception-handling-x64
+///
+/// rbp is set before the stack is allocated, and the unwinder only knows frame registers that are
+/// set after it, so no frame register is given. rsp doesn't move between the prologue and the
+/// epilogue, so the allocation and the saved registers are found from rsp instead.
+///
+/// The float registers are saved with `movsd`, which only saves their low half. No unwind code
+/// describes that, so unwinding through a procedure only restores the general registers.
+fn x86_64_windows_unwind_info(
+    buf: &mut Vec<'_, u8>,
+    saved_general_regs: &[X86_64GeneralReg],
+    aligned_stack_size: i32,
+    fn_call_stack_size: i32,
+) {
+    // The unwind codes of each instruction, in the order the prologue runs them.
+    let mut codes: std::vec::Vec<std::vec::Vec<u8>> = std::vec::Vec::new();
+    let mut prologue_size = PUSH_RBP_SIZE;
+    codes.push(vec![
+        prologue_size,
+        UWOP_PUSH_NONVOL | (X86_64GeneralReg::RBP as u8) << 4,
+    ]);
+    prologue_size += MOV_RBP_RSP_SIZE;
+
+    if aligned_stack_size > 0 {
+        prologue_size += SUB_RSP_IMM32_SIZE;
+        let size = aligned_stack_size as u32;
+        let code = if size <= 128 {
+            vec![
+                prologue_size,
+                UWOP_ALLOC_SMALL | ((size / 8 - 1) as u8) << 4,
+            ]
+        } else if size / 8 <= u16::MAX as u32 {
+            let mut code = vec![prologue_size, UWOP_ALLOC_LARGE];
+            code.extend(((size / 8) as u16).to_le_bytes());
+            code
+        } else {
+            let mut code = vec![prologue_size, UWOP_ALLOC_LARGE | 1 << 4];
+            code.extend(size.to_le_bytes());
+            code
+        };
+        codes.push(code);
+
+        // The registers are saved in order, right above the space for the args of calls.
+        for (i, reg) in saved_general_regs.iter().enumerate() {
+            prologue_size += MOV_BASE32_REG64_SIZE;
+            let offset = fn_call_stack_size as u32 + 8 * i as u32;
+            let code = if offset % 8 == 0 && offset / 8 <= u16::MAX as u32 {
+                let mut code = vec![prologue_size, UWOP_SAVE_NONVOL | (*reg as u8) << 4];
+                code.extend(((offset / 8) as u16).to_le_bytes());
+                code
+            } else {
+                let mut code = vec![prologue_size, UWOP_SAVE_NONVOL_FAR | (*reg as u8) << 4];
+                code.extend(offset.to_le_bytes());
+                code
+            };
+            codes.push(code);
+        }
+    }
+
+    // Each unwind code takes 2 bytes, and the codes are listed from the end of the prologue.
+    let count: usize = codes.iter().map(|code| code.len() / 2).sum();
+    buf.extend([0x01, prologue_size, count as u8, 0]);
+    for code in codes.iter().rev() {
+        buf.extend(code);
+    }
+    // The array of codes always has an even length.
+    if count % 2 == 1 {
+        buf.extend([0, 0]);
+    }
+}
+
 type Reg64 = X86_64GeneralReg;
 
 fn binop_move_src_to_dst_reg64<F>(buf: &mut Vec<'_, u8>, f: F, dst: Reg64, src1: Reg64, src2: Reg64)
@@ -1261,7 +1600,9 @@ impl Assembler<X86_64GeneralReg, X86_64FloatReg> for X86_64Assembler {
         debug_assert!(size <= 8);
         match size {
             8 => Self::mov_reg64_base32(buf, dst, offset),
-            4 | 2 | 1 => todo!("sign extending {size} byte values"),
+            4 => movsx_reg64_base32_offset32(buf, dst, X86_64GeneralReg::RBP, offset),
+            2 => movsx_reg64_base16_offset32(buf, dst, X86_64GeneralReg::RBP, offset),
+            1 => movsx_reg64_base8_offset32(buf, dst, X86_64GeneralReg::RBP, offset),
             _ => internal_error!("Invalid size for sign extension: {size}"),
         }
     }
@@ -1270,11 +1611,23 @@ impl Assembler<X86_64GeneralReg, X86_64FloatReg> for X86_64Assembler {
         debug_assert!(size <= 8);
         match size {
             8 => Self::mov_reg64_base32(buf, dst, offset),
-            4 | 2 => todo!("zero extending {size} byte values"),
+            // Writing a 32 bit register clears the upper half.
+            4 => mov_reg32_base32_offset32(buf, dst, X86_64GeneralReg::RBP, offset),
+            2 => movzx_reg64_base16_offset32(buf, dst, X86_64GeneralReg::RBP, offset),
             1 => movzx_reg64_base8_offset32(buf, dst, X86_64GeneralReg::RBP, offset),
             _ => internal_error!("Invalid size for zero extension: {size}"),
         }
     }
+    #[inline(always)]
+    fn mov_base32_reg_sized(buf: &mut Vec<'_, u8>, offset: i32, src: X86_64GeneralReg, size: u8) {
+        match size {
+            8 => Self::mov_base32_reg64(buf, offset, src),
+            4 => mov_base32_offset32_reg32(buf, X86_64GeneralReg::RBP, offset, src),
+            2 => mov_base16_offset32_reg16(buf, X86_64GeneralReg::RBP, offset, src),
+            1 => mov_base8_offset32_reg8(buf, X86_64GeneralReg::RBP, offset, src),
+            _ => internal_error!("Invalid size for a store: {size}"),
+        }
+    }
 
     #[inline(always)]
     fn mov_freg64_stack32(buf: &mut Vec<'_, u8>, dst: X86_64FloatReg, offset: i32) {
@@ -1353,6 +1706,17 @@ impl Assembler<X86_64GeneralReg, X86_64FloatReg> for X86_64Assembler {
         setl_reg64(buf, dst);
     }
 
+    #[inline(always)]
+    fn ult_reg64_reg64_reg64(
+        buf: &mut Vec<'_, u8>,
+        dst: X86_64GeneralReg,
+        src1: X86_64GeneralReg,
+        src2: X86_64GeneralReg,
+    ) {
+        cmp_reg64_reg64(buf, src1, src2);
+        setb_reg64(buf, dst);
+    }
+
     #[inline(always)]
     fn to_float_freg32_reg64(buf: &mut Vec<'_, u8>, dst: X86_64FloatReg, src: X86_64GeneralReg) {
         cvtsi2ss_freg64_reg64(buf, dst, src);
@@ -1904,6 +2268,151 @@ fn movzx_reg64_base8_offset32(
     buf.extend(offset.to_le_bytes());
 }
 
+/// Encodes the ModRM byte, and the SIB byte if needed, for a reg and a base + offset memory operand.
+/// The offset is always encoded with 32 bits.
+#[inline(always)]
+fn base_offset32_operand(
+    buf: &mut Vec<'_, u8>,
+    reg: X86_64GeneralReg,
+    base: X86_64GeneralReg,
+    offset: i32,
+) {
+    let reg_mod = (reg as u8 % 8) << 3;
+    let base_mod = base as u8 % 8;
+    buf.push(0x80 | reg_mod | base_mod);
+    // Using RSP or R12 requires a secondary index byte.
+    if base == X86_64GeneralReg::RSP || base == X86_64GeneralReg::R12 {
+        buf.push(0x24);
+    }
+    buf.extend(offset.to_le_bytes());
+}
+
+/// `MOV r/m32,r32` -> Move r32 to r/m32, where m32 references a base + offset.
+#[inline(always)]
+fn mov_base32_offset32_reg32(
+    buf: &mut Vec<'_, u8>,
+    base: X86_64GeneralReg,
+    offset: i32,
+    src: X86_64GeneralReg,
+) {
+    let rex = add_rm_extension(base, REX);
+    let rex = add_reg_extension(src, rex);
+    buf.reserve(8);
+    if rex != REX {
+        buf.push(rex);
+    }
+    buf.push(0x89);
+    base_offset32_operand(buf, src, base, offset);
+}
+
+/// `MOV r/m16,r16` -> Move r16 to r/m16, where m16 references a base + offset.
+#[inline(always)]
+fn mov_base16_offset32_reg16(
+    buf: &mut Vec<'_, u8>,
+    base: X86_64GeneralReg,
+    offset: i32,
+    src: X86_64GeneralReg,
+) {
+    // The operand size prefix turns the 32 bit move into a 16 bit one.
+    buf.push(0x66);
+    mov_base32_offset32_reg32(buf, base, offset, src);
+}
+
+/// `MOV r/m8,r8` -> Move r8 to r/m8, where m8 references a base + offset.
+#[inline(always)]
+fn mov_base8_offset32_reg8(
+    buf: &mut Vec<'_, u8>,
+    base: X86_64GeneralReg,
+    offset: i32,
+    src: X86_64GeneralReg,
+) {
+    // The REX prefix is always needed so that SPL, BPL, SIL, and DIL are used instead of AH, CH, DH, and BH.
+    let rex = add_rm_extension(base, REX);
+    let rex = add_reg_extension(src, rex);
+    buf.reserve(8);
+    buf.extend([rex, 0x88]);
+    base_offset32_operand(buf, src, base, offset);
+}
+
+/// `MOV r32,r/m32` -> Move r/m32 to r32, where m32 references a base + offset.
+/// This zero extends the value to 64 bits.
+#[inline(always)]
+fn mov_reg32_base32_offset32(
+    buf: &mut Vec<'_, u8>,
+    dst: X86_64GeneralReg,
+    base: X86_64GeneralReg,
+    offset: i32,
+) {
+    let rex = add_rm_extension(base, REX);
+    let rex = add_reg_extension(dst, rex);
+    buf.reserve(8);
+    if rex != REX {
+        buf.push(rex);
+    }
+    buf.push(0x8B);
+    base_offset32_operand(buf, dst, base, offset);
+}
+
+/// `MOVZX r64,r/m16` -> Move r/m16 with zero extention to r64, where m16 references a base + offset.
+#[inline(always)]
+fn movzx_reg64_base16_offset32(
+    buf: &mut Vec<'_, u8>,
+    dst: X86_64GeneralReg,
+    base: X86_64GeneralReg,
+    offset: i32,
+) {
+    let rex = add_rm_extension(base, REX_W);
+    let rex = add_reg_extension(dst, rex);
+    buf.reserve(9);
+    buf.extend([rex, 0x0F, 0xB7]);
+    base_offset32_operand(buf, dst, base, offset);
+}
+
+/// `MOVSXD r64,r/m32` -> Move r/m32 with sign extention to r64, where m32 references a base + offset.
+#[inline(always)]
+fn movsx_reg64_base32_offset32(
+    buf: &mut Vec<'_, u8>,
+    dst: X86_64GeneralReg,
+    base: X86_64GeneralReg,
+    offset: i32,
+) {
+    let rex = add_rm_extension(base, REX_W);
+    let rex = add_reg_extension(dst, rex);
+    buf.reserve(8);
+    buf.extend([rex, 0x63]);
+    base_offset32_operand(buf, dst, base, offset);
+}
+
+/// `MOVSX r64,r/m16` -> Move r/m16 with sign extention to r64, where m16 references a base + offset.
+#[inline(always)]
+fn movsx_reg64_base16_offset32(
+    buf: &mut Vec<'_, u8>,
+    dst: X86_64GeneralReg,
+    base: X86_64GeneralReg,
+    offset: i32,
+) {
+    let rex = add_rm_extension(base, REX_W);
+    let rex = add_reg_extension(dst, rex);
+    buf.reserve(9);
+    buf.extend([rex, 0x0F, 0xBF]);
+    base_offset32_operand(buf, dst, base, offset);
+}
+
+/// `MOVSX r64,r/m8` -> Move r/m8 with sign extention to r64, where m8 references a base + offset.
+#[inline(always)]
+fn movsx_reg64_base8_offset32(
+    buf: &mut Vec<'_, u8>,
+    dst: X86_64GeneralReg,
+    base: X86_64GeneralReg,
+    offset: i32,
+) {
+    let rex = add_rm_extension(base, REX_W);
+    let rex = add_reg_extension(dst, rex);
+    buf.reserve(9);
+    buf.extend([rex, 0x0F, 0xBE]);
+    base_offset32_operand(buf, dst, base, offset);
+}
+
 /// `MOVSD xmm1,xmm2` -> Move scalar double-precision floating-point value from xmm2 to xmm1 register.
 /// This will not generate anything if dst and src are the same.
 #[inline(always)]
@@ -2247,6 +2756,48 @@ mod tests {
                 X86_64GeneralReg::R15 => "r15b",
             }
         }
+
+        fn low_16bits_string(&self) -> &str {
+            match self {
+                X86_64GeneralReg::RAX => "ax",
+                X86_64GeneralReg::RBX => "bx",
+                X86_64GeneralReg::RCX => "cx",
+                X86_64GeneralReg::RDX => "dx",
+                X86_64GeneralReg::RBP => "bp",
+                X86_64GeneralReg::RSP => "sp",
+                X86_64GeneralReg::RDI => "di",
+                X86_64GeneralReg::RSI => "si",
+                X86_64GeneralReg::R8 => "r8w",
+                X86_64GeneralReg::R9 => "r9w",
+                X86_64GeneralReg::R10 => "r10w",
+                X86_64GeneralReg::R11 => "r11w",
+                X86_64GeneralReg::R12 => "r12w",
+                X86_64GeneralReg::R13 => "r13w",
+                X86_64GeneralReg::R14 => "r14w",
+                X86_64GeneralReg::R15 => "r15w",
+            }
+        }
+
+        fn low_32bits_string(&self) -> &str {
+            match self {
+                X86_64GeneralReg::RAX => "eax",
+                X86_64GeneralReg::RBX => "ebx",
+                X86_64GeneralReg::RCX => "ecx",
+                X86_64GeneralReg::RDX => "edx",
+                X86_64GeneralReg::RBP => "ebp",
+                X86_64GeneralReg::RSP => "esp",
+                X86_64GeneralReg::RDI => "edi",
+                X86_64GeneralReg::RSI => "esi",
+                X86_64GeneralReg::R8 => "r8d",
+                X86_64GeneralReg::R9 => "r9d",
+                X86_64GeneralReg::R10 => "r10d",
+                X86_64GeneralReg::R11 => "r11d",
+                X86_64GeneralReg::R12 => "r12d",
+                X86_64GeneralReg::R13 => "r13d",
+                X86_64GeneralReg::R14 => "r14d",
+                X86_64GeneralReg::R15 => "r15d",
+            }
+        }
     }
     const TEST_I32: i32 = 0x12345678;
     const TEST_I64: i64 = 0x1234_5678_9ABC_DEF0;
@@ -2600,6 +3151,98 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_movzx_reg64_base16_offset32() {
+        disassembler_test!(
+            movzx_reg64_base16_offset32,
+            |reg1, reg2, imm| format!("movzx {}, word ptr [{} + 0x{:x}]", reg1, reg2, imm),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            [TEST_I32]
+        );
+    }
+
+    #[test]
+    fn test_mov_reg32_base32_offset32() {
+        disassembler_test!(
+            mov_reg32_base32_offset32,
+            |reg1: X86_64GeneralReg, reg2, imm| format!(
+                "mov {}, dword ptr [{} + 0x{:x}]",
+                reg1.low_32bits_string(),
+                reg2,
+                imm
+            ),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            [TEST_I32]
+        );
+    }
+
+    #[test]
+    fn test_movsx_reg64_base_offset32() {
+        disassembler_test!(
+            movsx_reg64_base32_offset32,
+            |reg1, reg2, imm| format!("movsxd {}, dword ptr [{} + 0x{:x}]", reg1, reg2, imm),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            [TEST_I32]
+        );
+        disassembler_test!(
+            movsx_reg64_base16_offset32,
+            |reg1, reg2, imm| format!("movsx {}, word ptr [{} + 0x{:x}]", reg1, reg2, imm),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            [TEST_I32]
+        );
+        disassembler_test!(
+            movsx_reg64_base8_offset32,
+            |reg1, reg2, imm| format!("movsx {}, byte ptr [{} + 0x{:x}]", reg1, reg2, imm),
+            ALL_GENERAL_REGS,
+            ALL_GENERAL_REGS,
+            [TEST_I32]
+        );
+    }
+
+    #[test]
+    fn test_mov_base_offset32_reg_sized() {
+        disassembler_test!(
+            mov_base32_offset32_reg32,
+            |reg1, imm, reg2: X86_64GeneralReg| format!(
+                "mov dword ptr [{} + 0x{:x}], {}",
+                reg1,
+                imm,
+                reg2.low_32bits_string()
+            ),
+            ALL_GENERAL_REGS,
+            [TEST_I32],
+            ALL_GENERAL_REGS
+        );
+        disassembler_test!(
+            mov_base16_offset32_reg16,
+            |reg1, imm, reg2: X86_64GeneralReg| format!(
+                "mov word ptr [{} + 0x{:x}], {}",
+                reg1,
+                imm,
+                reg2.low_16bits_string()
+            ),
+            ALL_GENERAL_REGS,
+            [TEST_I32],
+            ALL_GENERAL_REGS
+        );
+        disassembler_test!(
+            mov_base8_offset32_reg8,
+            |reg1, imm, reg2: X86_64GeneralReg| format!(
+                "mov byte ptr [{} + 0x{:x}], {}",
+                reg1,
+                imm,
+                reg2.low_8bits_string()
+            ),
+            ALL_GENERAL_REGS,
+            [TEST_I32],
+            ALL_GENERAL_REGS
+        );
+    }
+
     #[test]
     fn test_movsd_freg64_freg64() {
         disassembler_test!(
@@ -2707,4 +3350,83 @@ mod tests {
     fn test_push_reg64() {
         disassembler_test!(push_reg64, |reg| format!("push {}", reg), ALL_GENERAL_REGS);
     }
+
+    /// The UNWIND_INFO of a Windows procedure, and the size of its prologue
+    fn windows_unwind_info(
+        saved_general_regs: &[X86_64GeneralReg],
+        requested_stack_size: i32,
+        fn_call_stack_size: i32,
+    ) -> (std::vec::Vec<u8>, usize) {
+        let arena = bumpalo::Bump::new();
+        let mut prologue = bumpalo::vec![in &arena];
+        let mut info = bumpalo::vec![in &arena];
+
+        let aligned_stack_size = X86_64WindowsFastcall::setup_stack(
+            &mut prologue,
+            saved_general_regs,
+            &[],
+            requested_stack_size,
+            fn_call_stack_size,
+        );
+        X86_64WindowsFastcall::unwind_info(
+            &mut info,
+            saved_general_regs,
+            &[],
+            aligned_stack_size,
+            fn_call_stack_size,
+        );
+
+        (info.to_vec(), prologue.len())
+    }
+
+    #[test]
+    fn test_windows_unwind_info_without_stack() {
+        let (info, prologue_size) = windows_unwind_info(&[], 0, 0);
+
+        assert_eq!(info[1] as usize, prologue_size);
+        assert_eq!(
+            info,
+            [
+                0x01, 4, 1, 0, // header
+                1, 0x50, // push rbp
+                0, 0, // padding
+            ]
+        );
+    }
+
+    #[test]
+    fn test_windows_unwind_info_saved_regs() {
+        // 24 bytes of locals, 16 for the saved registers and 32 for calls, aligned to 80
+        let (info, prologue_size) =
+            windows_unwind_info(&[X86_64GeneralReg::RBX, X86_64GeneralReg::RSI], 24, 32);
+
+        assert_eq!(info[1] as usize, prologue_size);
+        assert_eq!(
+            info,
+            [
+                0x01, 25, 6, 0, // header
+                25, 0x64, 5, 0, // save rsi at rsp + 40
+                18, 0x34, 4, 0, // save rbx at rsp + 32
+                11, 0x92, // sub rsp, 80
+                1, 0x50, // push rbp
+            ]
+        );
+    }
+
+    #[test]
+    fn test_windows_unwind_info_large_stack() {
+        let (info, prologue_size) = windows_unwind_info(&[X86_64GeneralReg::R12], 4096, 0);
+
+        assert_eq!(info[1] as usize, prologue_size);
+        assert_eq!(
+            info,
+            [
+                0x01, 18, 5, 0, // header
+                18, 0xC4, 0, 0, // save r12 at rsp
+                11, 0x01, 0x02, 0x02, // sub rsp, 4112
+                1, 0x50, // push rbp
+                0, 0, // padding
+            ]
+        );
+    }
 }
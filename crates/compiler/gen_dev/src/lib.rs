@@ -8,6 +8,7 @@
 use bumpalo::{collections::Vec, Bump};
 use roc_builtins::bitcode::{self, FloatWidth, IntWidth};
 use roc_collections::all::{MutMap, MutSet};
+use roc_collections::OrderedMap;
 use roc_error_macros::internal_error;
 use roc_module::ident::ModuleName;
 use roc_module::low_level::{LowLevel, LowLevelWrapperType};
@@ -18,9 +19,13 @@ use roc_mono::ir::{
     SelfRecursive, Stmt,
 };
 use roc_mono::layout::{
-    Builtin, Layout, LayoutId, LayoutIds, STLayoutInterner, TagIdIntType, UnionLayout,
+    Builtin, CapturesNiche, Layout, LayoutId, LayoutIds, STLayoutInterner, TagIdIntType,
+    UnionLayout,
 };
+use roc_region::all::Region;
+use std::path::PathBuf;
 
+mod debug_info;
 mod generic64;
 mod object_builder;
 pub use object_builder::build_module;
@@ -33,6 +38,13 @@ pub struct Env<'a> {
     pub exposed_to_host: MutSet<Symbol>,
     pub lazy_literals: bool,
     pub generate_allocators: bool,
+    /// Whether procedures that are not exposed to the host are global symbols.
+    /// Otherwise they are local to the object, so only the host api is left for the linker to resolve.
+    pub export_all_procs: bool,
+    /// The source of every module, used to emit debug line info.
+    pub sources: &'a MutMap<ModuleId, (PathBuf, Box<str>)>,
+    /// Where each top-level definition is in its module's source.
+    pub def_regions: &'a MutMap<Symbol, Region>,
 }
 
 // These relocations likely will need a length.
@@ -50,6 +62,7 @@ pub enum Relocation {
         offset: u64,
         name: String,
     },
+    // On aarch64 the offset is of an `adrp` followed by an `ldr` from the page it loads, which together load the data's GOT entry.
     LinkedData {
         offset: u64,
         name: String,
@@ -74,6 +87,40 @@ trait Backend<'a> {
         layout_id.to_symbol_string(symbol, self.interns())
     }
 
+    /// The layout ids of every procedure in the module, shared so calls use the same names as definitions.
+    fn layout_ids_mut(&mut self) -> &mut LayoutIds<'a>;
+
+    /// Returns the name of the specialization of a function for the given layouts.
+    /// Functions can be specialized many times, for example once for every lambda set passed to them.
+    fn function_name(
+        &mut self,
+        symbol: Symbol,
+        captures_niche: CapturesNiche<'a>,
+        arg_layouts: &[Layout<'a>],
+        ret_layout: &Layout<'a>,
+    ) -> String {
+        let arena = self.env().arena;
+        let proc_layout = ProcLayout::new(
+            arena,
+            arena.alloc_slice_copy(arg_layouts),
+            captures_niche,
+            *ret_layout,
+        );
+        let layout_id = self.layout_ids_mut().get_toplevel(symbol, &proc_layout);
+        self.symbol_to_string(symbol, layout_id)
+    }
+
+    /// Closures are passed around as the runtime representation of their lambda set.
+    fn runtime_layout(&self, layout: &Layout<'a>) -> Layout<'a> {
+        layout.runtime_representation(self.env().layout_interner)
+    }
+
+    /// Gets the runtime representation of every layout, see runtime_layout.
+    fn runtime_layouts(&self, layouts: &[Layout<'a>]) -> &'a [Layout<'a>] {
+        let arena = self.env().arena;
+        arena.alloc_slice_fill_iter(layouts.iter().map(|layout| self.runtime_layout(layout)))
+    }
+
     fn defined_in_app_module(&self, symbol: Symbol) -> bool {
         symbol
             .module_string(self.interns())
@@ -94,7 +141,10 @@ trait Backend<'a> {
     /// finalize does setup because things like stack size and jump locations are not know until the function is written.
     /// For example, this can store the frame pointer and setup stack space.
     /// finalize is run at the end of build_proc when all internal code is finalized.
-    fn finalize(&mut self) -> (Vec<u8>, Vec<Relocation>);
+    fn finalize(&mut self) -> (Vec<'a, u8>, Vec<'a, Relocation>);
+
+    /// The UNWIND_INFO of the procedure finalize last built, empty on targets that don't need one.
+    fn unwind_info(&self) -> &[u8];
 
     // load_args is used to let the backend know what the args are.
     // The backend should track these args so it can use them as needed.
@@ -108,37 +158,61 @@ trait Backend<'a> {
     fn build_proc(
         &mut self,
         proc: Proc<'a>,
-        layout_ids: &mut LayoutIds<'a>,
-    ) -> (Vec<u8>, Vec<Relocation>, Vec<'a, (Symbol, String)>) {
-        let layout_id = layout_ids.get(proc.name.name(), &proc.ret_layout);
-        let proc_name = self.symbol_to_string(proc.name.name(), layout_id);
+    ) -> (Vec<'a, u8>, Vec<'a, Relocation>, Vec<'a, (Symbol, String)>) {
+        let arg_layouts = Vec::from_iter_in(
+            proc.args.iter().map(|(layout, _)| *layout),
+            self.env().arena,
+        );
+        let proc_name = self.function_name(
+            proc.name.name(),
+            proc.name.captures_niche(),
+            &arg_layouts,
+            &proc.ret_layout,
+        );
         self.reset(proc_name, proc.is_self_recursive);
-        self.load_args(proc.args, &proc.ret_layout);
-        for (layout, sym) in proc.args {
+        let args = self.env().arena.alloc_slice_fill_iter(
+            proc.args
+                .iter()
+                .map(|(layout, sym)| (self.runtime_layout(layout), *sym)),
+        );
+        let ret_layout = self.runtime_layout(&proc.ret_layout);
+        self.load_args(args, &ret_layout);
+        for (layout, sym) in args.iter() {
             self.set_layout_map(*sym, layout);
         }
         self.scan_ast(&proc.body);
         self.create_free_map();
-        self.build_stmt(&proc.body, &proc.ret_layout);
+        self.build_stmt(&proc.body, &ret_layout);
         let mut helper_proc_names = bumpalo::vec![in self.env().arena];
         helper_proc_names.reserve(self.helper_proc_symbols().len());
-        for (rc_proc_sym, rc_proc_layout) in self.helper_proc_symbols() {
-            let name = layout_ids
-                .get_toplevel(*rc_proc_sym, rc_proc_layout)
-                .to_symbol_string(*rc_proc_sym, self.interns());
-
-            helper_proc_names.push((*rc_proc_sym, name));
+        for i in 0..self.helper_proc_symbols().len() {
+            let (rc_proc_sym, rc_proc_layout) = self.helper_proc_symbols()[i];
+            let layout_id = self
+                .layout_ids_mut()
+                .get_toplevel(rc_proc_sym, &rc_proc_layout);
+            let name = self.symbol_to_string(rc_proc_sym, layout_id);
+
+            helper_proc_names.push((rc_proc_sym, name));
         }
         let (bytes, relocs) = self.finalize();
         (bytes, relocs, helper_proc_names)
     }
 
+    /// start_stmt is called before each statement is built, so register allocation can follow along with scan_ast.
+    fn start_stmt(&mut self);
+
+    /// skip_stmts is called with how many of the statements about to be built were never scanned,
+    /// like the ones refcounting expands into. They belong to the statement they were expanded from.
+    fn skip_stmts(&mut self, count: u32);
+
     /// build_stmt builds a statement and outputs at the end of the buffer.
     fn build_stmt(&mut self, stmt: &Stmt<'a>, ret_layout: &Layout<'a>) {
+        self.start_stmt();
         match stmt {
             Stmt::Let(sym, expr, layout, following) => {
-                self.build_expr(sym, expr, layout);
-                self.set_layout_map(*sym, layout);
+                let layout = self.runtime_layout(layout);
+                self.build_expr(sym, expr, &layout);
+                self.set_layout_map(*sym, &layout);
                 self.free_symbols(stmt);
                 self.build_stmt(following, ret_layout);
             }
@@ -166,6 +240,7 @@ trait Backend<'a> {
                     self.helper_proc_symbols_mut().push(spec);
                 }
 
+                self.skip_stmts(count_stmts_before(rc_stmt, following));
                 self.build_stmt(rc_stmt, ret_layout)
             }
             Stmt::Switch {
@@ -209,6 +284,7 @@ trait Backend<'a> {
                         internal_error!("the argument, {:?}, has no know layout", arg);
                     }
                 }
+                self.load_literal_symbols(args);
                 self.build_jump(id, args, arg_layouts.into_bump_slice(), ret_layout);
                 self.free_symbols(stmt);
             }
@@ -277,11 +353,17 @@ trait Backend<'a> {
                                 ret_layout,
                             )
                         } else if self.defined_in_app_module(func_sym.name()) {
-                            let layout_id = LayoutIds::default().get(func_sym.name(), layout);
-                            let fn_name = self.symbol_to_string(func_sym.name(), layout_id);
+                            let fn_name = self.function_name(
+                                func_sym.name(),
+                                func_sym.captures_niche(),
+                                arg_layouts,
+                                ret_layout,
+                            );
+                            let arg_layouts = self.runtime_layouts(arg_layouts);
+                            let ret_layout = self.runtime_layout(ret_layout);
                             // Now that the arguments are needed, load them if they are literals.
                             self.load_literal_symbols(arguments);
-                            self.build_fn_call(sym, fn_name, arguments, arg_layouts, ret_layout)
+                            self.build_fn_call(sym, fn_name, arguments, arg_layouts, &ret_layout)
                         } else {
                             self.build_builtin(
                                 sym,
@@ -366,6 +448,43 @@ trait Backend<'a> {
                 self.load_literal_symbols(arguments);
                 self.tag(sym, arguments, tag_layout, *tag_id);
             }
+            Expr::ExprBox { symbol } => {
+                let inner_layout = match layout {
+                    Layout::Boxed(inner_layout) => inner_layout,
+                    x => internal_error!("ExprBox: expected a box layout, found: {:?}", x),
+                };
+                self.load_literal_symbols(std::slice::from_ref(symbol));
+                self.expr_box(sym, symbol, inner_layout);
+            }
+            Expr::ExprUnbox { symbol } => {
+                self.expr_unbox(sym, symbol, layout);
+            }
+            Expr::ReuseBox { symbol, value, .. } => {
+                let inner_layout = match layout {
+                    Layout::Boxed(inner_layout) => inner_layout,
+                    x => internal_error!("ReuseBox: expected a box layout, found: {:?}", x),
+                };
+                self.load_literal_symbols(std::slice::from_ref(value));
+                self.expr_reuse_box(sym, symbol, value, inner_layout);
+            }
+            Expr::Reset { symbol, .. } => {
+                let reset_layout = *self.layout_map().get(symbol).unwrap();
+
+                // Reset is a call to a generated procedure, like the refcounting statements.
+                let (reset_expr, new_specializations) = {
+                    let (env, interns, rc_proc_gen) = self.env_interns_helpers_mut();
+                    let module_id = env.module_id;
+                    let ident_ids = interns.all_ident_ids.get_mut(&module_id).unwrap();
+
+                    rc_proc_gen.call_reset_refcount(ident_ids, reset_layout, *symbol)
+                };
+
+                for spec in new_specializations.into_iter() {
+                    self.helper_proc_symbols_mut().push(spec);
+                }
+
+                self.build_expr(sym, &reset_expr, layout);
+            }
             x => todo!("the expression, {:?}", x),
         }
     }
@@ -664,7 +783,7 @@ trait Backend<'a> {
                     args.len(),
                     "RefCountGetPtr: expected to have exactly one argument"
                 );
-                self.build_ptr_cast(sym, &args[0])
+                self.build_ptr_cast(sym, &args[0], &arg_layouts[0])
             }
             LowLevel::RefCountDec => self.build_fn_call(
                 sym,
@@ -716,17 +835,40 @@ trait Backend<'a> {
                 self.build_eq(sym, &args[0], &Symbol::DEV_TMP, &arg_layouts[0]);
                 self.free_symbol(&Symbol::DEV_TMP)
             }
-            Symbol::LIST_GET | Symbol::LIST_SET | Symbol::LIST_REPLACE => {
-                // TODO: This is probably simple enough to be worth inlining.
-                let layout_id = LayoutIds::default().get(func_sym, ret_layout);
-                let fn_name = self.symbol_to_string(func_sym, layout_id);
-                // Now that the arguments are needed, load them if they are literals.
-                self.load_literal_symbols(args);
-                self.build_fn_call(sym, fn_name, args, arg_layouts, ret_layout)
+            Symbol::LIST_GET if self.can_inline_list_get(&arg_layouts[0]) => {
+                debug_assert_eq!(
+                    2,
+                    args.len(),
+                    "ListGet: expected to have exactly two arguments"
+                );
+                self.build_list_get(sym, &args[0], &args[1], &arg_layouts[0], ret_layout)
             }
-            Symbol::NUM_ADD_CHECKED => {
-                let layout_id = LayoutIds::default().get(func_sym, ret_layout);
-                let fn_name = self.symbol_to_string(func_sym, layout_id);
+            Symbol::NUM_ADD_CHECKED
+                if arg_layouts[0] == Layout::Builtin(Builtin::Int(IntWidth::I64)) =>
+            {
+                debug_assert_eq!(
+                    2,
+                    args.len(),
+                    "NumAddChecked: expected to have exactly two arguments"
+                );
+                self.build_num_add_checked_result(
+                    sym,
+                    &args[0],
+                    &args[1],
+                    &arg_layouts[0],
+                    ret_layout,
+                )
+            }
+            Symbol::LIST_GET
+            | Symbol::LIST_SET
+            | Symbol::LIST_REPLACE
+            | Symbol::NUM_ADD_CHECKED => {
+                let fn_name = self.function_name(
+                    func_sym,
+                    CapturesNiche::no_niche(),
+                    arg_layouts,
+                    ret_layout,
+                );
                 // Now that the arguments are needed, load them if they are literals.
                 self.load_literal_symbols(args);
                 self.build_fn_call(sym, fn_name, args, arg_layouts, ret_layout)
@@ -772,6 +914,17 @@ trait Backend<'a> {
         return_layout: &Layout<'a>,
     );
 
+    /// build_num_add_checked_result stores the sum of src1 and src2 into dst as an `Ok`,
+    /// or stores `Err Overflow` if the addition overflows.
+    fn build_num_add_checked_result(
+        &mut self,
+        dst: &Symbol,
+        src1: &Symbol,
+        src2: &Symbol,
+        num_layout: &Layout<'a>,
+        return_layout: &Layout<'a>,
+    );
+
     /// build_num_mul stores `src1 * src2` into dst.
     fn build_num_mul(&mut self, dst: &Symbol, src1: &Symbol, src2: &Symbol, layout: &Layout<'a>);
 
@@ -850,6 +1003,20 @@ trait Backend<'a> {
     /// build_list_len returns the length of a list.
     fn build_list_len(&mut self, dst: &Symbol, list: &Symbol);
 
+    /// can_inline_list_get returns true if build_list_get can load elements of the list.
+    fn can_inline_list_get(&self, list_layout: &Layout<'a>) -> bool;
+
+    /// build_list_get stores the element at the index into dst as an `Ok`,
+    /// or stores `Err OutOfBounds` if the index is past the end of the list.
+    fn build_list_get(
+        &mut self,
+        dst: &Symbol,
+        list: &Symbol,
+        index: &Symbol,
+        list_layout: &Layout<'a>,
+        ret_layout: &Layout<'a>,
+    );
+
     /// build_list_get_unsafe loads the element from the list at the index.
     fn build_list_get_unsafe(
         &mut self,
@@ -869,10 +1036,10 @@ trait Backend<'a> {
     );
 
     /// build_refcount_getptr loads the pointer to the reference count of src into dst.
-    fn build_ptr_cast(&mut self, dst: &Symbol, src: &Symbol);
+    fn build_ptr_cast(&mut self, dst: &Symbol, src: &Symbol, src_layout: &Layout<'a>);
 
     /// literal_map gets the map from symbol to literal and layout, used for lazy loading and literal folding.
-    fn literal_map(&mut self) -> &mut MutMap<Symbol, (*const Literal<'a>, *const Layout<'a>)>;
+    fn literal_map(&mut self) -> &mut OrderedMap<Symbol, (*const Literal<'a>, *const Layout<'a>)>;
 
     fn load_literal_symbols(&mut self, syms: &[Symbol]) {
         if self.env().lazy_literals {
@@ -936,6 +1103,21 @@ trait Backend<'a> {
         tag_id: TagIdIntType,
     );
 
+    /// expr_box allocates a box on the heap and moves `value` into it.
+    fn expr_box(&mut self, sym: &Symbol, value: &Symbol, inner_layout: &Layout<'a>);
+
+    /// expr_reuse_box moves `value` into the memory of the reset box `reused`.
+    fn expr_reuse_box(
+        &mut self,
+        sym: &Symbol,
+        reused: &Symbol,
+        value: &Symbol,
+        inner_layout: &Layout<'a>,
+    );
+
+    /// expr_unbox loads into `sym` the value that `boxed` points to.
+    fn expr_unbox(&mut self, sym: &Symbol, boxed: &Symbol, inner_layout: &Layout<'a>);
+
     /// return_symbol moves a symbol to the correct return location for the backend and adds a jump to the end of the function.
     fn return_symbol(&mut self, sym: &Symbol, layout: &Layout<'a>);
 
@@ -958,14 +1140,17 @@ trait Backend<'a> {
     }
 
     /// last_seen_map gets the map from symbol to when it is last seen in the function.
-    fn last_seen_map(&mut self) -> &mut MutMap<Symbol, *const Stmt<'a>>;
+    /// It's ordered, so the symbols freed after each statement are freed in the same order
+    /// every time.
+    fn last_seen_map(&mut self) -> &mut OrderedMap<Symbol, *const Stmt<'a>>;
 
     /// set_layout_map sets the layout for a specific symbol.
     fn set_layout_map(&mut self, sym: Symbol, layout: &Layout<'a>) {
-        if let Some(old_layout) = self.layout_map().insert(sym, *layout) {
+        let layout = self.runtime_layout(layout);
+        if let Some(old_layout) = self.layout_map().insert(sym, layout) {
             // Layout map already contains the symbol. We should never need to overwrite.
             // If the layout is not the same, that is a bug.
-            if &old_layout != layout {
+            if old_layout != layout {
                 internal_error!(
                     "Overwriting layout for symbol, {:?}: got {:?}, want {:?}",
                     sym,
@@ -977,29 +1162,31 @@ trait Backend<'a> {
     }
 
     /// layout_map gets the map from symbol to layout.
-    fn layout_map(&mut self) -> &mut MutMap<Symbol, Layout<'a>>;
+    fn layout_map(&mut self) -> &mut OrderedMap<Symbol, Layout<'a>>;
 
     fn create_free_map(&mut self) {
-        let mut free_map = MutMap::default();
+        let mut free_map = OrderedMap::default();
         let arena = self.env().arena;
-        for (sym, stmt) in self.last_seen_map() {
-            let vals = free_map
-                .entry(*stmt)
-                .or_insert_with(|| bumpalo::vec![in arena]);
+        for (sym, stmt) in self.last_seen_map().iter() {
+            let vals = free_map.get_or_insert_with(*stmt, || bumpalo::vec![in arena]);
             vals.push(*sym);
         }
         self.set_free_map(free_map);
     }
 
     /// free_map gets the map statement to the symbols that are free after they run.
-    fn free_map(&mut self) -> &mut MutMap<*const Stmt<'a>, Vec<'a, Symbol>>;
+    fn free_map(&mut self) -> &mut OrderedMap<*const Stmt<'a>, Vec<'a, Symbol>>;
 
     /// set_free_map sets the free map to the given map.
-    fn set_free_map(&mut self, map: MutMap<*const Stmt<'a>, Vec<'a, Symbol>>);
+    fn set_free_map(&mut self, map: OrderedMap<*const Stmt<'a>, Vec<'a, Symbol>>);
+
+    /// scan_stmt is called for each statement as scan_ast reaches it, before the symbols in it are seen.
+    fn scan_stmt(&mut self, stmt: &Stmt<'a>);
 
     /// scan_ast runs through the ast and fill the last seen map.
     /// This must iterate through the ast in the same way that build_stmt does. i.e. then before else.
     fn scan_ast(&mut self, stmt: &Stmt<'a>) {
+        self.scan_stmt(stmt);
         // Join map keeps track of join point parameters so that we can keep them around while they still might be jumped to.
         let mut join_map: MutMap<JoinPointId, &'a [Param<'a>]> = MutMap::default();
         match stmt {
@@ -1050,6 +1237,10 @@ trait Backend<'a> {
                             self.set_last_seen(*sym, stmt);
                         }
                     }
+                    Expr::ReuseBox { symbol, value, .. } => {
+                        self.set_last_seen(*symbol, stmt);
+                        self.set_last_seen(*value, stmt);
+                    }
                     Expr::Reset { symbol, .. } => {
                         self.set_last_seen(*symbol, stmt);
                     }
@@ -1132,3 +1323,34 @@ trait Backend<'a> {
         }
     }
 }
+
+/// count_stmts_before counts the statements build_stmt will build for stmt before it gets to following, which stmt ends in.
+fn count_stmts_before(stmt: &Stmt<'_>, following: &Stmt<'_>) -> u32 {
+    if std::ptr::eq(stmt, following) {
+        return 0;
+    }
+    let nested = match stmt {
+        Stmt::Let(_, _, _, next) | Stmt::Refcounting(_, next) => {
+            count_stmts_before(next, following)
+        }
+        Stmt::Switch {
+            branches,
+            default_branch,
+            ..
+        } => {
+            branches
+                .iter()
+                .map(|(_, _, branch)| count_stmts_before(branch, following))
+                .sum::<u32>()
+                + count_stmts_before(default_branch.1, following)
+        }
+        Stmt::Join {
+            body, remainder, ..
+        } => count_stmts_before(remainder, following) + count_stmts_before(body, following),
+        Stmt::Expect { remainder, .. } | Stmt::ExpectFx { remainder, .. } => {
+            count_stmts_before(remainder, following)
+        }
+        Stmt::Ret(_) | Stmt::Jump(..) | Stmt::Crash(..) => 0,
+    };
+    1 + nested
+}
@@ -1,12 +1,15 @@
-use crate::generic64::{aarch64, new_backend_64bit, x86_64};
+use crate::debug_info::DebugInfo;
+use crate::generic64::{aarch64, new_backend_64bit, x86_64, Assembler, CallConv, RegTrait};
 use crate::{Backend, Env, Relocation};
 use bumpalo::collections::Vec;
+use bumpalo::Bump;
 use object::write::{self, SectionId, SymbolId};
 use object::write::{Object, StandardSection, StandardSegment, Symbol, SymbolSection};
 use object::{
     Architecture, BinaryFormat, Endianness, RelocationEncoding, RelocationKind, SectionKind,
     SymbolFlags, SymbolKind, SymbolScope,
 };
+use rayon::prelude::*;
 use roc_collections::all::MutMap;
 use roc_error_macros::internal_error;
 use roc_module::symbol;
@@ -33,78 +36,90 @@ pub fn build_module<'a>(
             architecture: TargetArch::X86_64,
             binary_format: TargetBF::Elf,
             ..
-        } if cfg!(feature = "target-x86_64") => {
-            let backend = new_backend_64bit::<
-                x86_64::X86_64GeneralReg,
-                x86_64::X86_64FloatReg,
-                x86_64::X86_64Assembler,
-                x86_64::X86_64SystemV,
-            >(env, TargetInfo::default_x86_64(), interns);
-            build_object(
-                procedures,
-                backend,
-                Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little),
-            )
-        }
+        } if cfg!(feature = "target-x86_64") => build_object::<
+            x86_64::X86_64GeneralReg,
+            x86_64::X86_64FloatReg,
+            x86_64::X86_64Assembler,
+            x86_64::X86_64SystemV,
+        >(
+            env,
+            interns,
+            TargetInfo::default_x86_64(),
+            procedures,
+            Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little),
+        ),
         Triple {
             architecture: TargetArch::X86_64,
             binary_format: TargetBF::Macho,
             ..
-        } if cfg!(feature = "target-x86_64") => {
-            let backend = new_backend_64bit::<
-                x86_64::X86_64GeneralReg,
-                x86_64::X86_64FloatReg,
-                x86_64::X86_64Assembler,
-                x86_64::X86_64SystemV,
-            >(env, TargetInfo::default_x86_64(), interns);
-            build_object(
-                procedures,
-                backend,
-                Object::new(
-                    BinaryFormat::MachO,
-                    Architecture::X86_64,
-                    Endianness::Little,
-                ),
-            )
-        }
+        } if cfg!(feature = "target-x86_64") => build_object::<
+            x86_64::X86_64GeneralReg,
+            x86_64::X86_64FloatReg,
+            x86_64::X86_64Assembler,
+            x86_64::X86_64SystemV,
+        >(
+            env,
+            interns,
+            TargetInfo::default_x86_64(),
+            procedures,
+            Object::new(
+                BinaryFormat::MachO,
+                Architecture::X86_64,
+                Endianness::Little,
+            ),
+        ),
+        Triple {
+            architecture: TargetArch::X86_64,
+            binary_format: TargetBF::Coff,
+            ..
+        } if cfg!(feature = "target-x86_64") => build_object::<
+            x86_64::X86_64GeneralReg,
+            x86_64::X86_64FloatReg,
+            x86_64::X86_64Assembler,
+            x86_64::X86_64WindowsFastcall,
+        >(
+            env,
+            interns,
+            TargetInfo::default_x86_64(),
+            procedures,
+            Object::new(BinaryFormat::Coff, Architecture::X86_64, Endianness::Little),
+        ),
         Triple {
             architecture: TargetArch::Aarch64(_),
             binary_format: TargetBF::Elf,
             ..
-        } if cfg!(feature = "target-aarch64") => {
-            let backend = new_backend_64bit::<
-                aarch64::AArch64GeneralReg,
-                aarch64::AArch64FloatReg,
-                aarch64::AArch64Assembler,
-                aarch64::AArch64Call,
-            >(env, TargetInfo::default_aarch64(), interns);
-            build_object(
-                procedures,
-                backend,
-                Object::new(BinaryFormat::Elf, Architecture::Aarch64, Endianness::Little),
-            )
-        }
+        } if cfg!(feature = "target-aarch64") => build_object::<
+            aarch64::AArch64GeneralReg,
+            aarch64::AArch64FloatReg,
+            aarch64::AArch64Assembler,
+            aarch64::AArch64Call,
+        >(
+            env,
+            interns,
+            TargetInfo::default_aarch64(),
+            procedures,
+            Object::new(BinaryFormat::Elf, Architecture::Aarch64, Endianness::Little),
+        ),
         Triple {
             architecture: TargetArch::Aarch64(_),
             binary_format: TargetBF::Macho,
             ..
-        } if cfg!(feature = "target-aarch64") => {
-            let backend = new_backend_64bit::<
-                aarch64::AArch64GeneralReg,
-                aarch64::AArch64FloatReg,
-                aarch64::AArch64Assembler,
-                aarch64::AArch64Call,
-            >(env, TargetInfo::default_aarch64(), interns);
-            build_object(
-                procedures,
-                backend,
-                Object::new(
-                    BinaryFormat::MachO,
-                    Architecture::Aarch64,
-                    Endianness::Little,
-                ),
-            )
-        }
+        } if cfg!(feature = "target-aarch64") => build_object::<
+            aarch64::AArch64GeneralReg,
+            aarch64::AArch64FloatReg,
+            aarch64::AArch64Assembler,
+            aarch64::AArch64Call,
+        >(
+            env,
+            interns,
+            TargetInfo::default_aarch64(),
+            procedures,
+            Object::new(
+                BinaryFormat::MachO,
+                Architecture::Aarch64,
+                Endianness::Little,
+            ),
+        ),
         x => unimplemented!("the target, {:?}", x),
     }
 }
@@ -144,14 +159,7 @@ fn generate_wrapper<'a, B: Backend<'a>>(
     };
     output.add_symbol(symbol);
     if let Some(sym_id) = output.symbol_id(name) {
-        let reloc = write::Relocation {
-            offset: offset + proc_offset,
-            size: 32,
-            kind: RelocationKind::PltRelative,
-            encoding: RelocationEncoding::X86Branch,
-            symbol: sym_id,
-            addend: -4,
-        };
+        let reloc = call_relocation(output, offset + proc_offset, sym_id);
 
         match output.add_relocation(text_section, reloc) {
             Ok(obj) => obj,
@@ -162,14 +170,33 @@ fn generate_wrapper<'a, B: Backend<'a>>(
     }
 }
 
-fn build_object<'a, B: Backend<'a>>(
+/// How many procedures each task on the thread pool builds.
+/// Every task generates its own refcounting helpers, so this is fixed rather than based on the number of threads.
+/// That way the object is the same no matter how many threads built it.
+const PROCS_PER_TASK: usize = 16;
+
+fn build_object<'a, GeneralReg, FloatReg, ASM, CC>(
+    env: &'a Env<'a>,
+    interns: &'a mut Interns,
+    target_info: TargetInfo,
     procedures: MutMap<(symbol::Symbol, ProcLayout<'a>), Proc<'a>>,
-    mut backend: B,
     mut output: Object<'a>,
-) -> Object<'a> {
+) -> Object<'a>
+where
+    GeneralReg: RegTrait,
+    FloatReg: RegTrait,
+    ASM: Assembler<GeneralReg, FloatReg>,
+    CC: CallConv<GeneralReg, FloatReg, ASM>,
+{
     let data_section = output.section_id(StandardSection::Data);
+    let unwind_sections = if output.format() == BinaryFormat::Coff {
+        Some(add_unwind_sections(&mut output))
+    } else {
+        None
+    };
 
-    let arena = backend.env().arena;
+    let arena = env.arena;
+    let mut debug_info = DebugInfo::new(&output, env);
 
     /*
     // Commented out because we couldn't figure out how to get it to work on mac - see https://github.com/roc-lang/roc/pull/1323
@@ -181,7 +208,9 @@ fn build_object<'a, B: Backend<'a>>(
     );
     */
 
-    if backend.env().generate_allocators {
+    if env.generate_allocators {
+        let mut backend =
+            new_backend_64bit::<GeneralReg, FloatReg, ASM, CC>(env, target_info, interns);
         generate_wrapper(
             &mut backend,
             &mut output,
@@ -208,106 +237,136 @@ fn build_object<'a, B: Backend<'a>>(
         );
     }
 
-    // Setup layout_ids for procedure calls.
-    let mut layout_ids = LayoutIds::default();
-    let mut procs = Vec::with_capacity_in(procedures.len(), arena);
+    // Procedures are named and placed in a fixed order, since `procedures` iterates in hash order.
+    let mut procedures = std::vec::Vec::from_iter(procedures);
+    procedures.sort_unstable_by(|(a, _), (b, _)| a.cmp(b));
 
-    // Names and linker data for user procedures
-    for ((sym, layout), proc) in procedures {
-        build_proc_symbol(
+    // Names and linker data for user procedures.
+    // The names are decided up front, so every task uses the same name for calls to the same procedure.
+    let mut layout_ids = LayoutIds::default();
+    let mut proc_symbols = Vec::with_capacity_in(procedures.len(), arena);
+    let mut tasks = std::vec::Vec::new();
+    for (i, ((sym, layout), proc)) in procedures.into_iter().enumerate() {
+        proc_symbols.push(build_proc_symbol(
             &mut output,
+            env,
+            interns,
             &mut layout_ids,
-            &mut procs,
-            &backend,
             sym,
-            layout,
-            proc,
-        )
-    }
-
-    // Build procedures from user code
-    let mut relocations = bumpalo::vec![in arena];
-    for (fn_name, section_id, proc_id, proc) in procs {
-        build_proc(
-            &mut output,
-            &mut backend,
-            &mut relocations,
-            &mut layout_ids,
-            data_section,
-            fn_name,
-            section_id,
-            proc_id,
-            proc,
-        )
+            &layout,
+        ));
+        if i % PROCS_PER_TASK == 0 {
+            tasks.push(std::vec::Vec::with_capacity(PROCS_PER_TASK));
+        }
+        tasks.last_mut().unwrap().push(proc);
     }
 
-    // Generate IR for specialized helper procs (refcounting & equality)
-    let helper_procs = {
-        let module_id = backend.env().module_id;
-
-        let (env, interns, helper_proc_gen) = backend.env_interns_helpers_mut();
+    // Build procedures from user code on the thread pool.
+    // Each task has its own arena, backend and copy of the interns, so it shares nothing mutable with the others.
+    let built_tasks: std::vec::Vec<BuiltTask> = {
+        // The arena can't be shared between threads, so the rest of the env is passed along by itself.
+        let layout_interner = env.layout_interner;
+        let exposed_to_host = &env.exposed_to_host;
+        let (sources, def_regions) = (env.sources, env.def_regions);
+        let (module_id, lazy_literals) = (env.module_id, env.lazy_literals);
+        let (generate_allocators, export_all_procs) =
+            (env.generate_allocators, env.export_all_procs);
+        let interns: &Interns = interns;
+        let layout_ids = &layout_ids;
 
-        let ident_ids = interns.all_ident_ids.get_mut(&module_id).unwrap();
-        let helper_procs = helper_proc_gen.take_procs();
-        env.module_id.register_debug_idents(ident_ids);
+        tasks
+            .into_par_iter()
+            .enumerate()
+            .map(|(task, procs)| {
+                let arena = Bump::new();
+                let env = Env {
+                    arena: &arena,
+                    layout_interner,
+                    module_id,
+                    exposed_to_host: exposed_to_host.clone(),
+                    lazy_literals,
+                    generate_allocators,
+                    export_all_procs,
+                    sources,
+                    def_regions,
+                };
+                let mut interns = interns.clone();
+                let mut backend = new_backend_64bit::<GeneralReg, FloatReg, ASM, CC>(
+                    &env,
+                    target_info,
+                    &mut interns,
+                );
+                *backend.layout_ids_mut() = layout_ids.clone();
 
-        helper_procs
+                build_task(&mut backend, task, procs)
+            })
+            .collect()
     };
 
-    let empty = bumpalo::collections::Vec::new_in(arena);
-    let helper_symbols_and_layouts = std::mem::replace(backend.helper_proc_symbols_mut(), empty);
-    let mut helper_names_symbols_procs = Vec::with_capacity_in(helper_procs.len(), arena);
-
-    // Names and linker data for helpers
-    for ((sym, layout), proc) in helper_symbols_and_layouts.into_iter().zip(helper_procs) {
-        let layout_id = layout_ids.get_toplevel(sym, &layout);
-        let fn_name = backend.symbol_to_string(sym, layout_id);
-        if let Some(proc_id) = output.symbol_id(fn_name.as_bytes()) {
-            if let SymbolSection::Section(section_id) = output.symbol(proc_id).section {
-                helper_names_symbols_procs.push((fn_name, section_id, proc_id, proc));
-                continue;
-            }
-        } else {
-            // The symbol isn't defined yet and will just be used by other rc procs.
+    // Names and linker data for helpers. Calls between procedures go through relocations,
+    // so every symbol has to exist before any procedure is copied into the object.
+    let mut helper_symbols = Vec::with_capacity_in(built_tasks.len(), arena);
+    for built_task in built_tasks.iter() {
+        for (sym, fn_name, _) in built_task.helpers.iter() {
             let section_id = output.add_section(
                 output.segment_name(StandardSegment::Text).to_vec(),
                 format!(".text.{:x}", sym.as_u64()).as_bytes().to_vec(),
                 SectionKind::Text,
             );
-
             let rc_symbol = Symbol {
                 name: fn_name.as_bytes().to_vec(),
                 value: 0,
                 size: 0,
                 kind: SymbolKind::Text,
-                scope: SymbolScope::Linkage,
+                scope: proc_scope(env),
                 weak: false,
                 section: SymbolSection::Section(section_id),
                 flags: SymbolFlags::None,
             };
             let proc_id = output.add_symbol(rc_symbol);
-            helper_names_symbols_procs.push((fn_name, section_id, proc_id, proc));
-            continue;
+            helper_symbols.push((section_id, proc_id));
         }
-        internal_error!("failed to create rc fn for symbol {:?}", sym);
     }
 
-    // Build helpers
-    for (fn_name, section_id, proc_id, proc) in helper_names_symbols_procs {
-        build_proc(
+    // Copy every procedure into the object, in the order they were named.
+    let mut relocations = bumpalo::vec![in arena];
+    let mut built_helpers = Vec::with_capacity_in(helper_symbols.len(), arena);
+    let mut proc_symbols = proc_symbols.into_iter();
+    for built_task in built_tasks {
+        for built_proc in built_task.procs {
+            let (fn_name, sym, section_id, proc_id) = proc_symbols.next().unwrap();
+            let size = emit_proc(
+                &mut output,
+                &mut relocations,
+                data_section,
+                unwind_sections,
+                (&fn_name, section_id, proc_id),
+                built_proc,
+            );
+            if let Some(debug_info) = debug_info.as_mut() {
+                debug_info.add_proc(sym, proc_id, size);
+            }
+        }
+        built_helpers.extend(built_task.helpers);
+    }
+    for ((_, fn_name, built_helper), (section_id, proc_id)) in
+        built_helpers.into_iter().zip(helper_symbols)
+    {
+        emit_proc(
             &mut output,
-            &mut backend,
             &mut relocations,
-            &mut layout_ids,
             data_section,
-            fn_name,
-            section_id,
-            proc_id,
-            proc,
-        )
+            unwind_sections,
+            (&fn_name, section_id, proc_id),
+            built_helper,
+        );
+    }
+
+    if let Some(debug_info) = debug_info {
+        debug_info.write(&mut output, &mut relocations);
     }
 
-    // Relocations for all procedures (user code & helpers)
+    // Relocations for all procedures (user code & helpers) and debug info
     for (section_id, reloc) in relocations {
         match output.add_relocation(section_id, reloc) {
             Ok(obj) => obj,
@@ -317,24 +376,170 @@ fn build_object<'a, B: Backend<'a>>(
     output
 }
 
-fn build_proc_symbol<'a, B: Backend<'a>>(
+/// The sections that let Windows unwind the stack through our procedures.
+#[derive(Clone, Copy)]
+struct UnwindSections {
+    /// Holds a RUNTIME_FUNCTION entry for every procedure.
+    pdata: SectionId,
+    /// Holds the UNWIND_INFO of every procedure, which the calling convention writes.
+    xdata: SectionId,
+    xdata_symbol: SymbolId,
+}
+
+fn add_unwind_sections(output: &mut Object) -> UnwindSections {
+    let xdata = output.add_section(vec![], b".xdata".to_vec(), SectionKind::ReadOnlyData);
+    let xdata_symbol = output.section_symbol(xdata);
+
+    let pdata = output.add_section(vec![], b".pdata".to_vec(), SectionKind::ReadOnlyData);
+
+    UnwindSections {
+        pdata,
+        xdata,
+        xdata_symbol,
+    }
+}
+
+/// Adds the RUNTIME_FUNCTION entry for a procedure: its start and end address and its unwind info.
+fn add_runtime_function(
+    output: &mut Object,
+    relocations: &mut Vec<'_, (SectionId, object::write::Relocation)>,
+    unwind_sections: UnwindSections,
+    proc_id: SymbolId,
+    proc_size: u64,
+    unwind_info: &[u8],
+) {
+    let UnwindSections {
+        pdata,
+        xdata,
+        xdata_symbol,
+    } = unwind_sections;
+    debug_assert!(!unwind_info.is_empty());
+    let info_offset = output.append_section_data(xdata, unwind_info, 4);
+    let entry_offset = output.append_section_data(pdata, &[0; 12], 4);
+    let fields = [
+        (proc_id, 0),
+        (proc_id, proc_size),
+        (xdata_symbol, info_offset),
+    ];
+    for (i, (symbol, addend)) in fields.into_iter().enumerate() {
+        relocations.push((
+            pdata,
+            write::Relocation {
+                offset: entry_offset + 4 * i as u64,
+                size: 32,
+                kind: RelocationKind::ImageOffset,
+                encoding: RelocationEncoding::Generic,
+                symbol,
+                addend: addend as i64,
+            },
+        ));
+    }
+}
+
+/// Constants are loaded directly from memory, so keep them naturally aligned.
+fn constant_alignment(size: usize) -> u64 {
+    size.next_power_of_two().clamp(4, 16) as u64
+}
+
+/// Creates the relocation for a call or jump to a function that may be defined in another object or the host.
+fn call_relocation(output: &Object, offset: u64, symbol: SymbolId) -> write::Relocation {
+    let (size, encoding, addend) = match output.architecture() {
+        // `bl` encodes the word offset to the callee in its low 26 bits.
+        Architecture::Aarch64 => (26, RelocationEncoding::AArch64Call, 0),
+        _ => (32, RelocationEncoding::X86Branch, -4),
+    };
+    let kind = match output.format() {
+        // Mach-O has no PLT. The linker routes branches to dylib symbols through stubs on its own,
+        // so a plain branch relocation is used.
+        BinaryFormat::MachO => RelocationKind::Relative,
+        _ => RelocationKind::PltRelative,
+    };
+    write::Relocation {
+        offset,
+        size,
+        kind,
+        encoding,
+        symbol,
+        addend,
+    }
+}
+
+/// Creates the relocations for loading the address of a data symbol from the GOT.
+/// On aarch64 that takes two: one for the `adrp` of the GOT entry's page, and one for the `ldr` of its offset in the page.
+fn got_load_relocations(
+    output: &Object,
+    offset: u64,
+    symbol: SymbolId,
+) -> (write::Relocation, Option<write::Relocation>) {
+    let relocation = |offset, kind, encoding, addend| write::Relocation {
+        offset,
+        size: 32,
+        kind,
+        encoding,
+        symbol,
+        addend,
+    };
+    let (page, page_offset) = match (output.format(), output.architecture()) {
+        (BinaryFormat::MachO, Architecture::Aarch64) => (
+            RelocationKind::MachO {
+                value: object::macho::ARM64_RELOC_GOT_LOAD_PAGE21,
+                relative: true,
+            },
+            RelocationKind::MachO {
+                value: object::macho::ARM64_RELOC_GOT_LOAD_PAGEOFF12,
+                relative: false,
+            },
+        ),
+        (_, Architecture::Aarch64) => (
+            RelocationKind::Elf(object::elf::R_AARCH64_ADR_GOT_PAGE),
+            RelocationKind::Elf(object::elf::R_AARCH64_LD64_GOT_LO12_NC),
+        ),
+        (format, _) => {
+            let encoding = match format {
+                // On Mach-O, the load must be marked so the linker knows it is a `movq` it can rewrite.
+                BinaryFormat::MachO => RelocationEncoding::X86RipRelativeMovq,
+                _ => RelocationEncoding::Generic,
+            };
+            return (
+                relocation(offset, RelocationKind::GotRelative, encoding, -4),
+                None,
+            );
+        }
+    };
+    (
+        relocation(offset, page, RelocationEncoding::Generic, 0),
+        Some(relocation(
+            offset + 4,
+            page_offset,
+            RelocationEncoding::Generic,
+            0,
+        )),
+    )
+}
+
+/// The scope of procedures that are not exposed to the host.
+fn proc_scope(env: &Env) -> SymbolScope {
+    if env.export_all_procs {
+        SymbolScope::Linkage
+    } else {
+        SymbolScope::Compilation
+    }
+}
+
+fn build_proc_symbol<'a>(
     output: &mut Object<'a>,
+    env: &Env<'a>,
+    interns: &Interns,
     layout_ids: &mut LayoutIds<'a>,
-    procs: &mut Vec<'a, (String, SectionId, SymbolId, Proc<'a>)>,
-    backend: &B,
-    sym: roc_module::symbol::Symbol,
-    layout: ProcLayout<'a>,
-    proc: Proc<'a>,
-) {
-    let layout_id = layout_ids.get_toplevel(sym, &layout);
-    let base_name = backend.symbol_to_string(sym, layout_id);
+    sym: symbol::Symbol,
+    layout: &ProcLayout<'a>,
+) -> (String, symbol::Symbol, SectionId, SymbolId) {
+    let layout_id = layout_ids.get_toplevel(sym, layout);
 
-    let fn_name = if backend.env().exposed_to_host.contains(&sym) {
-        layout_ids
-            .get_toplevel(sym, &layout)
-            .to_exposed_symbol_string(sym, backend.interns())
+    let fn_name = if env.exposed_to_host.contains(&sym) {
+        layout_id.to_exposed_symbol_string(sym, interns)
     } else {
-        base_name
+        layout_id.to_symbol_string(sym, interns)
     };
 
     let section_id = output.add_section(
@@ -348,36 +553,136 @@ fn build_proc_symbol<'a, B: Backend<'a>>(
         value: 0,
         size: 0,
         kind: SymbolKind::Text,
-        // TODO: Depending on whether we are building a static or dynamic lib, this should change.
-        // We should use Dynamic -> anyone, Linkage -> static link, Compilation -> this module only.
-        scope: if backend.env().exposed_to_host.contains(&sym) {
+        scope: if env.exposed_to_host.contains(&sym) {
             SymbolScope::Dynamic
         } else {
-            SymbolScope::Linkage
+            proc_scope(env)
         },
         weak: false,
         section: SymbolSection::Section(section_id),
         flags: SymbolFlags::None,
     };
     let proc_id = output.add_symbol(proc_symbol);
-    procs.push((fn_name, section_id, proc_id, proc));
+    (fn_name, sym, section_id, proc_id)
 }
 
-#[allow(clippy::too_many_arguments)]
-fn build_proc<'a, B: Backend<'a>>(
-    output: &mut Object,
+/// A procedure built into its own buffer.
+/// Its relocations are relative to the start of the procedure until it is copied into the object.
+struct BuiltProc {
+    proc_data: std::vec::Vec<u8>,
+    relocs: std::vec::Vec<Relocation>,
+    unwind_info: std::vec::Vec<u8>,
+}
+
+/// The procedures one task built, in the order it was given them, and the helpers they call.
+struct BuiltTask {
+    procs: std::vec::Vec<BuiltProc>,
+    helpers: std::vec::Vec<(symbol::Symbol, String, BuiltProc)>,
+}
+
+/// Builds a task's procedures and the refcounting helpers they need, without touching the object.
+///
+/// Helpers are named with the interns of the task, so another task can give the same name to a different helper.
+/// The task number is added to the name of each helper, and to the calls to it, to keep them apart.
+fn build_task<'a, B: Backend<'a>>(
     backend: &mut B,
+    task: usize,
+    procs: std::vec::Vec<Proc<'a>>,
+) -> BuiltTask {
+    let mut built_procs = std::vec::Vec::with_capacity(procs.len());
+    for proc in procs {
+        let (proc_data, relocs, _) = backend.build_proc(proc);
+        built_procs.push(BuiltProc {
+            proc_data: proc_data.to_vec(),
+            relocs: relocs.into_iter().collect(),
+            unwind_info: backend.unwind_info().to_vec(),
+        });
+    }
+
+    // Generate IR for specialized helper procs (refcounting & equality)
+    let helper_procs = {
+        let module_id = backend.env().module_id;
+
+        let (env, interns, helper_proc_gen) = backend.env_interns_helpers_mut();
+
+        let ident_ids = interns.all_ident_ids.get_mut(&module_id).unwrap();
+        let helper_procs = helper_proc_gen.take_procs();
+        env.module_id.register_debug_idents(ident_ids);
+
+        helper_procs
+    };
+
+    let empty = bumpalo::collections::Vec::new_in(backend.env().arena);
+    let helper_symbols_and_layouts = std::mem::replace(backend.helper_proc_symbols_mut(), empty);
+
+    let mut helper_names = MutMap::default();
+    let mut helper_syms_procs = std::vec::Vec::with_capacity(helper_procs.len());
+    for ((sym, layout), proc) in helper_symbols_and_layouts.into_iter().zip(helper_procs) {
+        let layout_id = backend.layout_ids_mut().get_toplevel(sym, &layout);
+        let fn_name = backend.symbol_to_string(sym, layout_id);
+        helper_names.insert(fn_name.clone(), format!("{}.task{}", fn_name, task));
+        helper_syms_procs.push((sym, fn_name, proc));
+    }
+
+    let mut built_helpers = std::vec::Vec::with_capacity(helper_syms_procs.len());
+    for (sym, fn_name, proc) in helper_syms_procs {
+        let (proc_data, relocs, _) = backend.build_proc(proc);
+        built_helpers.push((
+            sym,
+            helper_names[&fn_name].clone(),
+            BuiltProc {
+                proc_data: proc_data.to_vec(),
+                relocs: relocs.into_iter().collect(),
+                unwind_info: backend.unwind_info().to_vec(),
+            },
+        ));
+    }
+
+    let built_procs_and_helpers = built_procs
+        .iter_mut()
+        .chain(built_helpers.iter_mut().map(|(_, _, built)| built));
+    for built_proc in built_procs_and_helpers {
+        for reloc in built_proc.relocs.iter_mut() {
+            if let Relocation::LinkedFunction { name, .. } = reloc {
+                if let Some(task_name) = helper_names.get(name.as_str()) {
+                    *name = task_name.clone();
+                }
+            }
+        }
+    }
+
+    BuiltTask {
+        procs: built_procs,
+        helpers: built_helpers,
+    }
+}
+
+/// Copies the procedure into its section, fixes up its relocations, and returns the size of its code.
+fn emit_proc<'a>(
+    output: &mut Object,
     relocations: &mut Vec<'a, (SectionId, object::write::Relocation)>,
-    layout_ids: &mut LayoutIds<'a>,
     data_section: SectionId,
-    fn_name: String,
-    section_id: SectionId,
-    proc_id: SymbolId,
-    proc: Proc<'a>,
-) {
+    unwind_sections: Option<UnwindSections>,
+    (fn_name, section_id, proc_id): (&str, SectionId, SymbolId),
+    built_proc: BuiltProc,
+) -> u64 {
+    let BuiltProc {
+        proc_data,
+        relocs,
+        unwind_info,
+    } = built_proc;
     let mut local_data_index = 0;
-    let (proc_data, relocs, rc_proc_names) = backend.build_proc(proc, layout_ids);
     let proc_offset = output.add_symbol_data(proc_id, section_id, &proc_data, 16);
+    if let Some(unwind_sections) = unwind_sections {
+        add_runtime_function(
+            output,
+            relocations,
+            unwind_sections,
+            proc_id,
+            proc_data.len() as u64,
+            &unwind_info,
+        );
+    }
     for reloc in relocs.iter() {
         let elfreloc = match reloc {
             Relocation::LocalData { offset, data } => {
@@ -395,7 +700,8 @@ fn build_proc<'a, B: Backend<'a>>(
                 };
                 local_data_index += 1;
                 let data_id = output.add_symbol(data_symbol);
-                output.add_symbol_data(data_id, data_section, data, 4);
+                let align = constant_alignment(data.len());
+                output.add_symbol_data(data_id, data_section, data, align);
                 write::Relocation {
                     offset: offset + proc_offset,
                     size: 32,
@@ -407,14 +713,12 @@ fn build_proc<'a, B: Backend<'a>>(
             }
             Relocation::LinkedData { offset, name } => {
                 if let Some(sym_id) = output.symbol_id(name.as_bytes()) {
-                    write::Relocation {
-                        offset: offset + proc_offset,
-                        size: 32,
-                        kind: RelocationKind::GotRelative,
-                        encoding: RelocationEncoding::Generic,
-                        symbol: sym_id,
-                        addend: -4,
+                    let (load, page_offset) =
+                        got_load_relocations(output, offset + proc_offset, sym_id);
+                    if let Some(page_offset) = page_offset {
+                        relocations.push((section_id, page_offset));
                     }
+                    load
                 } else {
                     internal_error!("failed to find data symbol for {:?}", name);
                 }
@@ -434,39 +738,8 @@ fn build_proc<'a, B: Backend<'a>>(
                     };
                     output.add_symbol(builtin_symbol);
                 }
-                // If the symbol is an undefined reference counting procedure, we need to add it here.
-                if output.symbol_id(name.as_bytes()).is_none() {
-                    for (sym, rc_name) in rc_proc_names.iter() {
-                        if name == rc_name {
-                            let section_id = output.add_section(
-                                output.segment_name(StandardSegment::Text).to_vec(),
-                                format!(".text.{:x}", sym.as_u64()).as_bytes().to_vec(),
-                                SectionKind::Text,
-                            );
-
-                            let rc_symbol = Symbol {
-                                name: name.as_bytes().to_vec(),
-                                value: 0,
-                                size: 0,
-                                kind: SymbolKind::Text,
-                                scope: SymbolScope::Linkage,
-                                weak: false,
-                                section: SymbolSection::Section(section_id),
-                                flags: SymbolFlags::None,
-                            };
-                            output.add_symbol(rc_symbol);
-                        }
-                    }
-                }
                 if let Some(sym_id) = output.symbol_id(name.as_bytes()) {
-                    write::Relocation {
-                        offset: offset + proc_offset,
-                        size: 32,
-                        kind: RelocationKind::PltRelative,
-                        encoding: RelocationEncoding::X86Branch,
-                        symbol: sym_id,
-                        addend: -4,
-                    }
+                    call_relocation(output, offset + proc_offset, sym_id)
                 } else {
                     internal_error!("failed to find fn symbol for {:?}", name);
                 }
@@ -475,4 +748,223 @@ fn build_proc<'a, B: Backend<'a>>(
         };
         relocations.push((section_id, elfreloc));
     }
+    proc_data.len() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use roc_intern::SingleThreadedInterner;
+    use roc_mono::layout::STLayoutInterner;
+
+    fn object_with_symbol(
+        format: BinaryFormat,
+        architecture: Architecture,
+    ) -> (Object<'static>, SymbolId) {
+        let mut output = Object::new(format, architecture, Endianness::Little);
+        let symbol = output.add_symbol(Symbol {
+            name: b"roc_alloc".to_vec(),
+            value: 0,
+            size: 0,
+            kind: SymbolKind::Text,
+            scope: SymbolScope::Linkage,
+            weak: false,
+            section: SymbolSection::Undefined,
+            flags: SymbolFlags::None,
+        });
+
+        (output, symbol)
+    }
+
+    fn fields(
+        relocation: &write::Relocation,
+    ) -> (u64, u8, RelocationKind, RelocationEncoding, i64) {
+        (
+            relocation.offset,
+            relocation.size,
+            relocation.kind,
+            relocation.encoding,
+            relocation.addend,
+        )
+    }
+
+    #[test]
+    fn call_relocations() {
+        use Architecture::*;
+        use BinaryFormat::*;
+
+        let cases = [
+            (Elf, X86_64, 32, RelocationKind::PltRelative, -4),
+            (MachO, X86_64, 32, RelocationKind::Relative, -4),
+            (Elf, Aarch64, 26, RelocationKind::PltRelative, 0),
+            (MachO, Aarch64, 26, RelocationKind::Relative, 0),
+        ];
+
+        for (format, architecture, size, kind, addend) in cases {
+            let (output, symbol) = object_with_symbol(format, architecture);
+            let encoding = match architecture {
+                Aarch64 => RelocationEncoding::AArch64Call,
+                _ => RelocationEncoding::X86Branch,
+            };
+
+            assert_eq!(
+                fields(&call_relocation(&output, 8, symbol)),
+                (8, size, kind, encoding, addend),
+                "{:?} {:?}",
+                format,
+                architecture
+            );
+        }
+    }
+
+    #[test]
+    fn got_load_relocations_x86_64() {
+        for (format, encoding) in [
+            (BinaryFormat::Elf, RelocationEncoding::Generic),
+            (BinaryFormat::MachO, RelocationEncoding::X86RipRelativeMovq),
+        ] {
+            let (output, symbol) = object_with_symbol(format, Architecture::X86_64);
+            let (load, next) = got_load_relocations(&output, 8, symbol);
+
+            assert_eq!(
+                fields(&load),
+                (8, 32, RelocationKind::GotRelative, encoding, -4)
+            );
+            assert!(next.is_none());
+        }
+    }
+
+    #[test]
+    fn got_load_relocations_aarch64() {
+        let cases = [
+            (
+                BinaryFormat::Elf,
+                RelocationKind::Elf(object::elf::R_AARCH64_ADR_GOT_PAGE),
+                RelocationKind::Elf(object::elf::R_AARCH64_LD64_GOT_LO12_NC),
+            ),
+            (
+                BinaryFormat::MachO,
+                RelocationKind::MachO {
+                    value: object::macho::ARM64_RELOC_GOT_LOAD_PAGE21,
+                    relative: true,
+                },
+                RelocationKind::MachO {
+                    value: object::macho::ARM64_RELOC_GOT_LOAD_PAGEOFF12,
+                    relative: false,
+                },
+            ),
+        ];
+
+        for (format, page, page_offset) in cases {
+            let (output, symbol) = object_with_symbol(format, Architecture::Aarch64);
+            let (adrp, ldr) = got_load_relocations(&output, 8, symbol);
+
+            // the `adrp` of the page, then the `ldr` of the address from it
+            assert_eq!(fields(&adrp), (8, 32, page, RelocationEncoding::Generic, 0));
+            assert_eq!(
+                fields(&ldr.unwrap()),
+                (12, 32, page_offset, RelocationEncoding::Generic, 0)
+            );
+        }
+    }
+
+    #[test]
+    fn constants_are_naturally_aligned() {
+        let sizes = [1, 4, 8, 12, 16, 32];
+        let alignments = sizes.map(constant_alignment);
+
+        assert_eq!(alignments, [4, 4, 8, 16, 16, 16]);
+    }
+
+    const PROCS: &str = r#"procedure Test.1 (Test.2 : I64) -> I64:
+    ret Test.2;
+
+procedure Test.0 (Test.3 : I64) -> I64:
+    let Test.4 : I64 = CallByName Test.1 Test.3;
+    ret Test.4;
+"#;
+
+    /// Whether the symbols of `Test.0`, which is exposed to the host, and of `Test.1`, which is
+    /// not, are global in the x86_64 ELF object built from PROCS
+    fn globals(export_all_procs: bool) -> (bool, bool) {
+        use object::read::{Object as _, ObjectSymbol as _};
+
+        let arena = Bump::new();
+        let mut interns = Interns::default();
+        let layout_interner: STLayoutInterner = SingleThreadedInterner::with_capacity(4);
+
+        let procs = match roc_mono::ir::text::parse_procs(&arena, &mut interns, PROCS) {
+            Ok(procs) => procs,
+            Err(error) => panic!("{}", error),
+        };
+        let exposed = procs[1].name.name();
+
+        let mut layout_ids = LayoutIds::default();
+        let mut names = std::vec::Vec::new();
+        let procedures: MutMap<_, _> = procs
+            .into_iter()
+            .map(|proc| {
+                let arguments = arena.alloc_slice_fill_iter(proc.args.iter().map(|(l, _)| *l));
+                let layout = ProcLayout::new(
+                    &arena,
+                    arguments,
+                    proc.name.captures_niche(),
+                    proc.ret_layout,
+                );
+                let sym = proc.name.name();
+                let layout_id = layout_ids.get_toplevel(sym, &layout);
+                names.push(if sym == exposed {
+                    layout_id.to_exposed_symbol_string(sym, &interns)
+                } else {
+                    layout_id.to_symbol_string(sym, &interns)
+                });
+
+                ((sym, layout), proc)
+            })
+            .collect();
+
+        let sources = MutMap::default();
+        let def_regions = MutMap::default();
+        let env = Env {
+            arena: &arena,
+            layout_interner: &layout_interner,
+            module_id: exposed.module_id(),
+            exposed_to_host: [exposed].into_iter().collect(),
+            lazy_literals: false,
+            generate_allocators: false,
+            export_all_procs,
+            sources: &sources,
+            def_regions: &def_regions,
+        };
+        let output = build_object::<
+            x86_64::X86_64GeneralReg,
+            x86_64::X86_64FloatReg,
+            x86_64::X86_64Assembler,
+            x86_64::X86_64SystemV,
+        >(
+            &env,
+            &mut interns,
+            TargetInfo::default_x86_64(),
+            procedures,
+            Object::new(BinaryFormat::Elf, Architecture::X86_64, Endianness::Little),
+        );
+        let bytes = output.write().unwrap();
+
+        let object = object::File::parse(bytes.as_slice()).unwrap();
+        let is_global = |name: &str| {
+            object
+                .symbols()
+                .find(|symbol| symbol.name() == Ok(name))
+                .unwrap_or_else(|| panic!("no symbol {}", name))
+                .is_global()
+        };
+
+        (is_global(&names[1]), is_global(&names[0]))
+    }
+
+    #[test]
+    fn only_procs_exposed_to_host_are_global() {
+        assert_eq!(globals(false), (true, false));
+        assert_eq!(globals(true), (true, true));
+    }
 }
@@ -504,18 +504,27 @@ pub fn build_eq_wrapper<'a, 'ctx, 'env>(
     function_value
 }
 
+/// The sort calls the compare function many times with the same element, so when the function
+/// owns its arguments (see `PassedFunction::borrows_arguments`) the wrapper increments both
+/// elements first, and the list keeps its own reference to each.
 pub fn build_compare_wrapper<'a, 'ctx, 'env>(
     env: &Env<'a, 'ctx, 'env>,
+    layout_ids: &mut LayoutIds<'a>,
     roc_function: FunctionValue<'ctx>,
     closure_data_layout: LambdaSet<'a>,
     layout: &Layout<'a>,
+    borrows_arguments: bool,
 ) -> FunctionValue<'ctx> {
     let block = env.builder.get_insert_block().expect("to be in a function");
     let di_location = env.builder.get_current_debug_location().unwrap();
 
+    let increments_arguments =
+        !borrows_arguments && layout.contains_refcounted(env.layout_interner);
+
     let fn_name: &str = &format!(
-        "{}_compare_wrapper",
-        roc_function.get_name().to_string_lossy()
+        "{}_{}compare_wrapper",
+        roc_function.get_name().to_string_lossy(),
+        if increments_arguments { "inc_" } else { "" }
     );
 
     let function_value = match env.module.get_function(fn_name) {
@@ -573,6 +582,11 @@ pub fn build_compare_wrapper<'a, 'ctx, 'env>(
                 .builder
                 .new_build_load(value_type, value_cast2, "load_opaque");
 
+            if increments_arguments {
+                increment_refcount_layout(env, layout_ids, 1, value1, layout);
+                increment_refcount_layout(env, layout_ids, 1, value2, layout);
+            }
+
             let default = [value1.into(), value2.into()];
 
             let arguments_cast =
@@ -3,7 +3,8 @@ use crate::llvm::build_list::{self, allocate_list, empty_polymorphic_list};
 use crate::llvm::convert::{
     argument_type_from_layout, basic_type_from_builtin, basic_type_from_layout, zig_str_type,
 };
-use crate::llvm::expect::{clone_to_shared_memory, SharedMemoryPointer};
+use crate::llvm::expect::{clone_to_shared_memory, notify_parent_panic, SharedMemoryPointer};
+use crate::llvm::externs::build_coverage_hook_call;
 use crate::llvm::refcounting::{
     build_reset, decrement_refcount_layout, increment_refcount_layout, PointerToRefcount,
 };
@@ -14,11 +15,11 @@ use inkwell::basic_block::BasicBlock;
 use inkwell::builder::Builder;
 use inkwell::context::Context;
 use inkwell::debug_info::{
-    AsDIScope, DICompileUnit, DIFlagsConstants, DISubprogram, DebugInfoBuilder,
+    AsDIScope, DICompileUnit, DIFile, DIFlagsConstants, DISubprogram, DebugInfoBuilder,
 };
 use inkwell::memory_buffer::MemoryBuffer;
 use inkwell::module::{Linkage, Module};
-use inkwell::passes::{PassManager, PassManagerBuilder};
+use inkwell::passes::{PassManager, PassManagerBuilder, PassManagerSubType};
 use inkwell::types::{
     AnyType, BasicMetadataTypeEnum, BasicType, BasicTypeEnum, FunctionType, IntType, StructType,
 };
@@ -47,10 +48,12 @@ use roc_mono::layout::{
     Builtin, CapturesNiche, LambdaName, LambdaSet, Layout, LayoutIds, RawFunctionLayout,
     STLayoutInterner, TagIdIntType, UnionLayout,
 };
+use roc_region::all::{LineInfo, Region};
 use roc_std::RocDec;
 use roc_target::{PtrWidth, TargetInfo};
+use std::cell::RefCell;
 use std::convert::TryInto;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use target_lexicon::{Architecture, OperatingSystem, Triple};
 
 use super::convert::{struct_type_from_union_layout, RocUnion};
@@ -251,6 +254,49 @@ pub struct Env<'a, 'ctx, 'env> {
     pub target_info: TargetInfo,
     pub mode: LlvmBackendMode,
     pub exposed_to_host: MutSet<Symbol>,
+    pub debug_sources: DebugSources<'env>,
+}
+
+/// The Roc source of every module, used to point debug info at the definitions of procedures.
+pub struct DebugSources<'env> {
+    sources: &'env MutMap<ModuleId, (PathBuf, Box<str>)>,
+    def_regions: &'env MutMap<Symbol, Region>,
+    line_infos: RefCell<MutMap<ModuleId, LineInfo>>,
+}
+
+impl<'env> DebugSources<'env> {
+    pub fn new(
+        sources: &'env MutMap<ModuleId, (PathBuf, Box<str>)>,
+        def_regions: &'env MutMap<Symbol, Region>,
+    ) -> Self {
+        Self {
+            sources,
+            def_regions,
+            line_infos: RefCell::new(MutMap::default()),
+        }
+    }
+
+    /// The file and (1-based) line where the symbol is defined, if it is known.
+    fn def_location<'ctx>(
+        &self,
+        dibuilder: &DebugInfoBuilder<'ctx>,
+        symbol: Symbol,
+    ) -> Option<(DIFile<'ctx>, u32)> {
+        let region = self.def_regions.get(&symbol)?;
+        let module_id = symbol.module_id();
+        let (path, src) = self.sources.get(&module_id)?;
+
+        let mut line_infos = self.line_infos.borrow_mut();
+        let line_info = line_infos
+            .entry(module_id)
+            .or_insert_with(|| LineInfo::new(src));
+        let line = line_info.convert_pos(region.start()).line + 1;
+
+        let file_name = path.file_name()?.to_str()?;
+        let directory = path.parent().and_then(|dir| dir.to_str()).unwrap_or(".");
+
+        Some((dibuilder.create_file(file_name, directory), line))
+    }
 }
 
 impl<'a, 'ctx, 'env> Env<'a, 'ctx, 'env> {
@@ -404,6 +450,11 @@ impl<'a, 'ctx, 'env> Env<'a, 'ctx, 'env> {
         message: BasicValueEnum<'ctx>,
         tag: CrashTag,
     ) {
+        if let LlvmBackendMode::BinaryDev = env.mode {
+            // `roc dev` prints a stack trace before the host handles the panic.
+            notify_parent_panic(env);
+        }
+
         let function = self.module.get_function("roc_panic").unwrap();
         let tag_id = self.context.i32_type().const_int(tag as u32 as u64, false);
 
@@ -483,6 +534,75 @@ impl<'a, 'ctx, 'env> Env<'a, 'ctx, 'env> {
             /* is_optimized */ false,
         )
     }
+
+    /// A subprogram for a Roc procedure, named after its Roc definition rather than the mangled
+    /// symbol, so debuggers and profilers can find it in the source.
+    pub fn new_proc_subprogram(&self, symbol: Symbol, fn_name: &str) -> DISubprogram<'ctx> {
+        let (file, line) = match self.debug_sources.def_location(self.dibuilder, symbol) {
+            Some(location) => location,
+            None => return self.new_subprogram(fn_name),
+        };
+
+        let dibuilder = self.dibuilder;
+
+        let ditype = dibuilder
+            .create_basic_type(
+                "type_name",
+                0_u64,
+                0x00,
+                inkwell::debug_info::DIFlags::PUBLIC,
+            )
+            .unwrap();
+
+        let subroutine_type = dibuilder.create_subroutine_type(
+            file,
+            /* return type */ Some(ditype.as_type()),
+            /* parameter types */ &[],
+            inkwell::debug_info::DIFlags::PUBLIC,
+        );
+
+        let roc_name = format!(
+            "{}.{}",
+            symbol.module_string(&self.interns),
+            symbol.as_str(&self.interns)
+        );
+
+        dibuilder.create_function(
+            /* scope */ file.as_debug_info_scope(),
+            /* func name */ &roc_name,
+            /* linkage_name */ Some(fn_name),
+            /* file */ file,
+            /* line_no */ line,
+            /* DIType */ subroutine_type,
+            /* is_local_to_unit */ true,
+            /* is_definition */ true,
+            /* scope_line */ line,
+            /* flags */ inkwell::debug_info::DIFlags::PUBLIC,
+            /* is_optimized */ false,
+        )
+    }
+
+    /// Points the instructions that follow at the line where the procedure is defined.
+    /// Mono statements do not keep their regions, so this is as precise as we can get.
+    fn set_proc_debug_location(&self, symbol: Symbol, fn_val: FunctionValue<'ctx>) {
+        let line = match self.debug_sources.def_location(self.dibuilder, symbol) {
+            Some((_, line)) => line,
+            None => {
+                debug_info_init!(self, fn_val);
+                return;
+            }
+        };
+
+        let subprogram = fn_val.get_subprogram().expect("subprogram");
+        let loc = self.dibuilder.create_debug_location(
+            self.context,
+            /* line */ line,
+            /* column */ 0,
+            /* current_scope */ subprogram.as_debug_info_scope(),
+            /* inlined_at */ None,
+        );
+        self.builder.set_current_debug_location(self.context, loc);
+    }
 }
 
 pub fn module_from_builtins<'ctx>(
@@ -541,9 +661,217 @@ pub fn module_from_builtins<'ctx>(
     module
 }
 
+/// How much work the LLVM passes do after code generation, like the `-O` levels of C compilers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LlvmOptLevel {
+    O0,
+    O1,
+    O2,
+    O3,
+    /// Optimize, but keep the binary small
+    Os,
+    /// Optimize for the smallest binary, even at the cost of speed
+    Oz,
+}
+
+impl LlvmOptLevel {
+    pub fn from_flag(flag: &str) -> Option<Self> {
+        match flag {
+            "0" => Some(Self::O0),
+            "1" => Some(Self::O1),
+            "2" => Some(Self::O2),
+            "3" => Some(Self::O3),
+            "s" => Some(Self::Os),
+            "z" => Some(Self::Oz),
+            _ => None,
+        }
+    }
+
+    /// The optimization level the rest of the compiler uses for this pipeline.
+    pub fn opt_level(self) -> OptLevel {
+        match self {
+            Self::O0 | Self::O1 => OptLevel::Normal,
+            Self::O2 | Self::O3 => OptLevel::Optimize,
+            Self::Os | Self::Oz => OptLevel::Size,
+        }
+    }
+}
+
+impl From<OptLevel> for LlvmOptLevel {
+    fn from(opt_level: OptLevel) -> Self {
+        match opt_level {
+            OptLevel::Development | OptLevel::Normal => Self::O0,
+            OptLevel::Size => Self::Os,
+            OptLevel::Optimize => Self::O3,
+        }
+    }
+}
+
+/// A single LLVM pass that can be named in `--llvm-passes`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LlvmPass {
+    AlwaysInline,
+    Inline,
+    GlobalDce,
+    GlobalOpt,
+    ConstMerge,
+    DeadArgElim,
+    FunctionAttrs,
+    Ipsccp,
+    PruneEh,
+    InstCombine,
+    TailCallElim,
+    SimplifyCfg,
+    JumpThreading,
+    MemCpyOpt,
+    Licm,
+    Gvn,
+    NewGvn,
+    Sroa,
+    EarlyCse,
+    Reassociate,
+    Mem2Reg,
+    Adce,
+    Dse,
+    Sccp,
+    LoopRotate,
+    LoopUnroll,
+    LoopDeletion,
+    IndVars,
+    LoopVectorize,
+    SlpVectorize,
+}
+
+impl LlvmPass {
+    const NAMES: &'static [(&'static str, LlvmPass)] = &[
+        ("always-inline", LlvmPass::AlwaysInline),
+        ("inline", LlvmPass::Inline),
+        ("globaldce", LlvmPass::GlobalDce),
+        ("globalopt", LlvmPass::GlobalOpt),
+        ("constmerge", LlvmPass::ConstMerge),
+        ("deadargelim", LlvmPass::DeadArgElim),
+        ("function-attrs", LlvmPass::FunctionAttrs),
+        ("ipsccp", LlvmPass::Ipsccp),
+        ("prune-eh", LlvmPass::PruneEh),
+        ("instcombine", LlvmPass::InstCombine),
+        ("tailcallelim", LlvmPass::TailCallElim),
+        ("simplifycfg", LlvmPass::SimplifyCfg),
+        ("jump-threading", LlvmPass::JumpThreading),
+        ("memcpyopt", LlvmPass::MemCpyOpt),
+        ("licm", LlvmPass::Licm),
+        ("gvn", LlvmPass::Gvn),
+        ("newgvn", LlvmPass::NewGvn),
+        ("sroa", LlvmPass::Sroa),
+        ("early-cse", LlvmPass::EarlyCse),
+        ("reassociate", LlvmPass::Reassociate),
+        ("mem2reg", LlvmPass::Mem2Reg),
+        ("adce", LlvmPass::Adce),
+        ("dse", LlvmPass::Dse),
+        ("sccp", LlvmPass::Sccp),
+        ("loop-rotate", LlvmPass::LoopRotate),
+        ("loop-unroll", LlvmPass::LoopUnroll),
+        ("loop-deletion", LlvmPass::LoopDeletion),
+        ("indvars", LlvmPass::IndVars),
+        ("loop-vectorize", LlvmPass::LoopVectorize),
+        ("slp-vectorizer", LlvmPass::SlpVectorize),
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::NAMES
+            .iter()
+            .find(|(pass_name, _)| *pass_name == name)
+            .map(|(_, pass)| *pass)
+    }
+
+    pub fn names() -> impl Iterator<Item = &'static str> {
+        Self::NAMES.iter().map(|(name, _)| *name)
+    }
+
+    fn add_to<T: PassManagerSubType>(self, pm: &PassManager<T>) {
+        use LlvmPass::*;
+
+        match self {
+            AlwaysInline => pm.add_always_inliner_pass(),
+            Inline => pm.add_function_inlining_pass(),
+            GlobalDce => pm.add_global_dce_pass(),
+            GlobalOpt => pm.add_global_optimizer_pass(),
+            ConstMerge => pm.add_constant_merge_pass(),
+            DeadArgElim => pm.add_dead_arg_elimination_pass(),
+            FunctionAttrs => pm.add_function_attrs_pass(),
+            Ipsccp => pm.add_ipsccp_pass(),
+            PruneEh => pm.add_prune_eh_pass(),
+            InstCombine => pm.add_instruction_combining_pass(),
+            TailCallElim => pm.add_tail_call_elimination_pass(),
+            SimplifyCfg => pm.add_cfg_simplification_pass(),
+            JumpThreading => pm.add_jump_threading_pass(),
+            MemCpyOpt => pm.add_memcpy_optimize_pass(),
+            Licm => pm.add_licm_pass(),
+            Gvn => pm.add_gvn_pass(),
+            NewGvn => pm.add_new_gvn_pass(),
+            Sroa => pm.add_scalar_repl_aggregates_pass(),
+            EarlyCse => pm.add_early_cse_pass(),
+            Reassociate => pm.add_reassociate_pass(),
+            Mem2Reg => pm.add_promote_memory_to_register_pass(),
+            Adce => pm.add_aggressive_dce_pass(),
+            Dse => pm.add_dead_store_elimination_pass(),
+            Sccp => pm.add_sccp_pass(),
+            LoopRotate => pm.add_loop_rotate_pass(),
+            LoopUnroll => pm.add_loop_unroll_pass(),
+            LoopDeletion => pm.add_loop_deletion_pass(),
+            IndVars => pm.add_ind_var_simplify_pass(),
+            LoopVectorize => pm.add_loop_vectorize_pass(),
+            SlpVectorize => pm.add_slp_vectorize_pass(),
+        }
+    }
+}
+
+/// The passes that run on a module after code generation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LlvmPipeline {
+    /// The pipeline for an optimization level
+    Level(LlvmOptLevel),
+    /// Exactly these passes, in this order, as given to `--llvm-passes`
+    Custom(std::vec::Vec<LlvmPass>),
+}
+
+impl LlvmPipeline {
+    /// Parses a comma-separated list of pass names, like `--llvm-passes=mem2reg,instcombine`
+    pub fn parse_passes(passes: &str) -> Result<Self, String> {
+        passes
+            .split(',')
+            .map(str::trim)
+            .filter(|name| !name.is_empty())
+            .map(|name| {
+                LlvmPass::from_name(name).ok_or_else(|| {
+                    let known: std::vec::Vec<_> = LlvmPass::names().collect();
+                    format!(
+                        "unknown LLVM pass `{}`, the known passes are: {}",
+                        name,
+                        known.join(", ")
+                    )
+                })
+            })
+            .collect::<Result<_, _>>()
+            .map(LlvmPipeline::Custom)
+    }
+}
+
+impl From<OptLevel> for LlvmPipeline {
+    fn from(opt_level: OptLevel) -> Self {
+        LlvmPipeline::Level(opt_level.into())
+    }
+}
+
 pub fn construct_optimization_passes<'a>(
     module: &'a Module,
     opt_level: OptLevel,
+) -> (PassManager<Module<'a>>, PassManager<FunctionValue<'a>>) {
+    construct_pass_pipeline(module, &opt_level.into())
+}
+
+pub fn construct_pass_pipeline<'a>(
+    module: &'a Module,
+    pipeline: &LlvmPipeline,
 ) -> (PassManager<Module<'a>>, PassManager<FunctionValue<'a>>) {
     let mpm = PassManager::create(());
     let fpm = PassManager::create(module);
@@ -557,25 +885,50 @@ pub fn construct_optimization_passes<'a>(
     fpm.add_instruction_combining_pass();
     fpm.add_tail_call_elimination_pass();
 
+    let level = match pipeline {
+        LlvmPipeline::Level(level) => *level,
+        LlvmPipeline::Custom(passes) => {
+            for pass in passes {
+                pass.add_to(&mpm);
+            }
+
+            fpm.initialize();
+
+            return (mpm, fpm);
+        }
+    };
+
     let pmb = PassManagerBuilder::create();
-    match opt_level {
-        OptLevel::Development | OptLevel::Normal => {
+    match level {
+        LlvmOptLevel::O0 => {
             pmb.set_optimization_level(OptimizationLevel::None);
         }
-        OptLevel::Size => {
+        LlvmOptLevel::O1 => {
+            pmb.set_optimization_level(OptimizationLevel::Less);
+        }
+        LlvmOptLevel::O2 => {
             pmb.set_optimization_level(OptimizationLevel::Default);
-            // TODO: For some usecase, like embedded, it is useful to expose this and tune it.
-            pmb.set_inliner_with_threshold(50);
+            pmb.set_inliner_with_threshold(225);
         }
-        OptLevel::Optimize => {
+        LlvmOptLevel::O3 => {
             pmb.set_optimization_level(OptimizationLevel::Aggressive);
             // this threshold seems to do what we want
             pmb.set_inliner_with_threshold(750);
         }
+        LlvmOptLevel::Os => {
+            pmb.set_optimization_level(OptimizationLevel::Default);
+            pmb.set_size_level(1);
+            pmb.set_inliner_with_threshold(50);
+        }
+        LlvmOptLevel::Oz => {
+            pmb.set_optimization_level(OptimizationLevel::Default);
+            pmb.set_size_level(2);
+            pmb.set_inliner_with_threshold(25);
+        }
     }
 
-    // Add optimization passes for Size and Optimize.
-    if matches!(opt_level, OptLevel::Size | OptLevel::Optimize) {
+    // Add optimization passes for everything but O0 and O1.
+    if !matches!(level, LlvmOptLevel::O0 | LlvmOptLevel::O1) {
         // TODO figure out which of these actually help
 
         // function passes
@@ -1110,13 +1463,17 @@ pub fn build_exp_expr<'a, 'ctx, 'env>(
 
         ExprBox { symbol } => {
             let (value, layout) = load_symbol_and_layout(scope, symbol);
-            let basic_type = basic_type_from_layout(env, layout);
-            let allocation = reserve_with_refcount_help(
-                env,
-                basic_type,
-                layout.stack_size(env.layout_interner, env.target_info),
-                layout.alignment_bytes(env.layout_interner, env.target_info),
-            );
+            let allocation = reserve_box_with_refcount(env, layout);
+
+            store_roc_value(env, *layout, allocation, value);
+
+            allocation.into()
+        }
+
+        ReuseBox { symbol, value, .. } => {
+            let reset = load_symbol(scope, symbol).into_pointer_value();
+            let (value, layout) = load_symbol_and_layout(scope, value);
+            let allocation = allocate_box(env, parent, reset, layout);
 
             store_roc_value(env, *layout, allocation, value);
 
@@ -1144,19 +1501,19 @@ pub fn build_exp_expr<'a, 'ctx, 'env>(
             let (tag_ptr, layout) = load_symbol_and_layout(scope, symbol);
             let tag_ptr = tag_ptr.into_pointer_value();
 
-            // reset is only generated for union values
-            let union_layout = match layout {
-                Layout::Union(ul) => ul,
-                _ => unreachable!(),
-            };
-
             let ctx = env.context;
             let then_block = ctx.append_basic_block(parent, "then_reset");
             let else_block = ctx.append_basic_block(parent, "else_decref");
             let cont_block = ctx.append_basic_block(parent, "cont");
 
-            let refcount_ptr =
-                PointerToRefcount::from_ptr_to_data(env, tag_pointer_clear_tag_id(env, tag_ptr));
+            // reset is only generated for union values and boxes
+            let refcount_ptr = match layout {
+                Layout::Union(_) => {
+                    PointerToRefcount::from_ptr_to_data(env, tag_pointer_clear_tag_id(env, tag_ptr))
+                }
+                Layout::Boxed(_) => PointerToRefcount::from_ptr_to_data(env, tag_ptr),
+                _ => unreachable!(),
+            };
 
             let is_unique = match update_mode {
                 UpdateMode::InPlace => env.context.bool_type().const_int(1, false),
@@ -1171,14 +1528,25 @@ pub fn build_exp_expr<'a, 'ctx, 'env>(
                 // referenced value, and returns the location of the now-invalid cell
                 env.builder.position_at_end(then_block);
 
-                let reset_function = build_reset(env, layout_ids, *union_layout);
-                let call = env
-                    .builder
-                    .build_call(reset_function, &[tag_ptr.into()], "call_reset");
+                match layout {
+                    Layout::Union(union_layout) => {
+                        let reset_function = build_reset(env, layout_ids, *union_layout);
+                        let call =
+                            env.builder
+                                .build_call(reset_function, &[tag_ptr.into()], "call_reset");
 
-                call.set_call_convention(FAST_CALL_CONV);
+                        call.set_call_convention(FAST_CALL_CONV);
 
-                let _ = call.try_as_basic_value();
+                        let _ = call.try_as_basic_value();
+                    }
+                    Layout::Boxed(inner_layout) => {
+                        let inner =
+                            load_roc_value(env, **inner_layout, tag_ptr, "load_boxed_value");
+
+                        decrement_refcount_layout(env, layout_ids, inner, inner_layout);
+                    }
+                    _ => unreachable!(),
+                }
 
                 env.builder.build_unconditional_branch(cont_block);
             }
@@ -1357,6 +1725,14 @@ pub fn build_exp_expr<'a, 'ctx, 'env>(
                         struct_type.into_struct_type(),
                     )
                 }
+                UnionLayout::NullablePointer { nullable_id, .. } => {
+                    debug_assert!(argument.is_pointer_value());
+                    debug_assert_ne!(*tag_id != 0, *nullable_id);
+                    debug_assert_eq!(*index, 0);
+
+                    // the union is the pointer in its only field
+                    argument
+                }
             }
         }
 
@@ -1698,6 +2074,21 @@ fn build_tag<'a, 'ctx, 'env>(
 
             data_ptr.into()
         }
+        UnionLayout::NullablePointer {
+            nullable_id,
+            pointer,
+        } => {
+            if tag_id == *nullable_id as _ {
+                let output_type = basic_type_from_layout(env, pointer).into_pointer_type();
+
+                return output_type.const_null().into();
+            }
+
+            debug_assert_eq!(arguments.len(), 1);
+
+            // the union is the pointer in its only field; there is nothing to allocate
+            load_symbol(scope, &arguments[0])
+        }
     }
 }
 
@@ -1815,6 +2206,59 @@ fn allocate_tag<'a, 'ctx, 'env>(
     }
 }
 
+fn reserve_box_with_refcount<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    layout: &Layout<'a>,
+) -> PointerValue<'ctx> {
+    reserve_with_refcount_help(
+        env,
+        basic_type_from_layout(env, layout),
+        layout.stack_size(env.layout_interner, env.target_info),
+        layout.alignment_bytes(env.layout_interner, env.target_info),
+    )
+}
+
+/// Memory for a box of a value with the given layout: the memory of `reuse_allocation`, the
+/// result of resetting a box, unless that is NULL.
+fn allocate_box<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    parent: FunctionValue<'ctx>,
+    reuse_allocation: PointerValue<'ctx>,
+    layout: &Layout<'a>,
+) -> PointerValue<'ctx> {
+    let is_null_ptr = env.builder.build_is_null(reuse_allocation, "is_null_ptr");
+    let ctx = env.context;
+    let then_block = ctx.append_basic_block(parent, "then_allocate_fresh");
+    let else_block = ctx.append_basic_block(parent, "else_reuse");
+    let cont_block = ctx.append_basic_block(parent, "cont");
+
+    env.builder
+        .build_conditional_branch(is_null_ptr, then_block, else_block);
+
+    let raw_ptr = {
+        env.builder.position_at_end(then_block);
+        let raw_ptr = reserve_box_with_refcount(env, layout);
+        env.builder.build_unconditional_branch(cont_block);
+        raw_ptr
+    };
+
+    let reuse_ptr = {
+        env.builder.position_at_end(else_block);
+        let cast =
+            env.builder
+                .build_pointer_cast(reuse_allocation, raw_ptr.get_type(), "cast_reused_box");
+        env.builder.build_unconditional_branch(cont_block);
+        cast
+    };
+
+    env.builder.position_at_end(cont_block);
+    let phi = env.builder.build_phi(raw_ptr.get_type(), "branch");
+
+    phi.add_incoming(&[(&raw_ptr, then_block), (&reuse_ptr, else_block)]);
+
+    phi.as_basic_value().into_pointer_value()
+}
+
 pub fn get_tag_id<'a, 'ctx, 'env>(
     env: &Env<'a, 'ctx, 'env>,
     parent: FunctionValue<'ctx>,
@@ -1882,7 +2326,8 @@ pub fn get_tag_id<'a, 'ctx, 'env>(
                 .new_build_load(tag_id_int_type, result, "load_result")
                 .into_int_value()
         }
-        UnionLayout::NullableUnwrapped { nullable_id, .. } => {
+        UnionLayout::NullableUnwrapped { nullable_id, .. }
+        | UnionLayout::NullablePointer { nullable_id, .. } => {
             let argument_ptr = argument.into_pointer_value();
             let is_null = env.builder.build_is_null(argument_ptr, "is_null");
 
@@ -2082,10 +2527,9 @@ fn list_literal<'a, 'ctx, 'env>(
     let list_length = elems.len();
     let list_length_intval = env.ptr_int().const_int(list_length as _, false);
 
-    // TODO re-enable, currently causes morphic segfaults because it tries to update
-    // constants in-place...
-    // if element_type.is_int_type() {
-    if false {
+    // alias analysis models a list of only literals as a static constant, so morphic will never
+    // update it in-place, and it can live in the constants section
+    if element_type.is_int_type() {
         let element_type = element_type.into_int_type();
         let element_width = element_layout.stack_size(env.layout_interner, env.target_info);
         let size = list_length * element_width as usize;
@@ -2149,17 +2593,40 @@ fn list_literal<'a, 'ctx, 'env>(
                 &global_elements[zero_elements..]
             };
 
-            // use None for the address space (e.g. Const does not work)
-            let typ = element_type.array_type(const_elements.len() as u32);
-            let global = env.module.add_global(typ, None, "roc__list_literal");
+            let initializer = element_type.const_array(const_elements);
 
-            global.set_constant(true);
-            global.set_alignment(alignment);
-            global.set_unnamed_addr(true);
-            global.set_linkage(inkwell::module::Linkage::Private);
+            // fully constant lists are shared between all modules, so hash the name
+            // so we don't re-define an identical list
+            let name = if is_all_constant {
+                use std::collections::hash_map::DefaultHasher;
+                use std::hash::{Hash, Hasher};
+
+                let mut hasher = DefaultHasher::new();
+                alignment.hash(&mut hasher);
+                initializer.print_to_string().to_bytes().hash(&mut hasher);
+                let hash = hasher.finish();
+
+                format!("roc__list_literal_{}", hash)
+            } else {
+                "roc__list_literal".to_string()
+            };
 
-            global.set_initializer(&element_type.const_array(const_elements));
-            global.as_pointer_value()
+            match env.module.get_global(&name) {
+                Some(current) if is_all_constant => current.as_pointer_value(),
+                _ => {
+                    // use None for the address space (e.g. Const does not work)
+                    let typ = element_type.array_type(const_elements.len() as u32);
+                    let global = env.module.add_global(typ, None, &name);
+
+                    global.set_constant(true);
+                    global.set_alignment(alignment);
+                    global.set_unnamed_addr(true);
+                    global.set_linkage(inkwell::module::Linkage::Private);
+
+                    global.set_initializer(&initializer);
+                    global.as_pointer_value()
+                }
+            }
         };
 
         if is_all_constant {
@@ -4265,6 +4732,25 @@ pub fn build_proc_headers<'a, 'ctx, 'env>(
         roc_mono::ir::Proc<'a>,
         &'a [(&'a FuncSpecSolutions, FunctionValue<'ctx>)],
     ),
+> {
+    build_proc_headers_help(env, mod_solutions, procedures, scope, layout_ids, None)
+}
+
+/// With a `home`, only the procedures of that module get a header to be built into.
+/// The rest are declared, to be linked in from the LLVM module built for their own module.
+fn build_proc_headers_help<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    mod_solutions: &'a ModSolutions,
+    procedures: MutMap<(Symbol, ProcLayout<'a>), roc_mono::ir::Proc<'a>>,
+    scope: &mut Scope<'a, 'ctx>,
+    layout_ids: &mut LayoutIds<'a>,
+    home: Option<ModuleId>,
+) -> Vec<
+    'a,
+    (
+        roc_mono::ir::Proc<'a>,
+        &'a [(&'a FuncSpecSolutions, FunctionValue<'ctx>)],
+    ),
 > {
     // Populate Procs further and get the low-level Expr from the canonical Expr
     let mut headers = Vec::with_capacity_in(procedures.len(), env.arena);
@@ -4274,10 +4760,16 @@ pub fn build_proc_headers<'a, 'ctx, 'env>(
 
         let func_solutions = mod_solutions.func_solutions(func_name).unwrap();
 
+        let defined = home.map_or(true, |home| symbol.module_id() == home);
+
         let it = func_solutions.specs();
         let mut function_values = Vec::with_capacity_in(it.size_hint().0, env.arena);
         for specialization in it {
-            let fn_val = build_proc_header(env, *specialization, symbol, &proc, layout_ids);
+            let fn_val = if defined {
+                build_proc_header(env, *specialization, symbol, &proc, layout_ids)
+            } else {
+                build_proc_declaration(env, *specialization, symbol, &proc)
+            };
 
             if proc.args.is_empty() {
                 // this is a 0-argument thunk, i.e. a top-level constant definition
@@ -4295,6 +4787,92 @@ pub fn build_proc_headers<'a, 'ctx, 'env>(
     headers
 }
 
+/// Runs the alias analysis of the whole program, which decides how each procedure is specialized.
+pub fn alias_analysis_solutions<'a>(
+    arena: &'a Bump,
+    layout_interner: &STLayoutInterner<'a>,
+    opt_level: OptLevel,
+    procedures: &MutMap<(Symbol, ProcLayout<'a>), roc_mono::ir::Proc<'a>>,
+    entry_point: EntryPoint<'a>,
+) -> &'a ModSolutions {
+    let it = procedures.values();
+
+    let solutions = match roc_alias_analysis::spec_program(
+        arena,
+        layout_interner,
+        opt_level,
+        entry_point,
+        it,
+    ) {
+        Err(e) => panic!("Error in alias analysis: {}", e),
+        Ok(solutions) => solutions,
+    };
+
+    let solutions = arena.alloc(solutions);
+
+    solutions
+        .mod_solutions(roc_alias_analysis::MOD_APP)
+        .unwrap()
+}
+
+/// Builds the procedures of the `home` module, and declares the procedures of every other module,
+/// so that each module can go through LLVM on its own. The procedures have external linkage, so
+/// the calls between modules are resolved when the LLVM modules are linked back together.
+///
+/// The `mod_solutions` must be the [alias_analysis_solutions] of the whole program, so every module
+/// agrees on which specializations there are. Returns the names of the functions it built.
+pub fn build_module_procedures<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    opt_level: OptLevel,
+    mod_solutions: &'a ModSolutions,
+    procedures: MutMap<(Symbol, ProcLayout<'a>), roc_mono::ir::Proc<'a>>,
+    home: ModuleId,
+    debug_output_file: Option<&Path>,
+) -> Vec<'a, &'a str> {
+    let mut layout_ids = roc_mono::layout::LayoutIds::default();
+    let mut scope = Scope::default();
+
+    let headers = build_proc_headers_help(
+        env,
+        mod_solutions,
+        procedures,
+        &mut scope,
+        &mut layout_ids,
+        Some(home),
+    );
+
+    let mut built = Vec::with_capacity_in(headers.len(), env.arena);
+    let mut home_headers = Vec::with_capacity_in(headers.len(), env.arena);
+    for (proc, fn_vals) in headers {
+        for (_, fn_val) in fn_vals.iter() {
+            fn_val.set_linkage(Linkage::External);
+        }
+
+        if proc.name.name().module_id() == home {
+            for (_, fn_val) in fn_vals.iter() {
+                let name = fn_val.get_name().to_str().unwrap();
+                built.push(&*env.arena.alloc_str(name));
+            }
+
+            home_headers.push((proc, fn_vals));
+        }
+    }
+
+    let (_, function_pass) = construct_optimization_passes(env.module, opt_level);
+
+    build_proc_bodies(
+        env,
+        mod_solutions,
+        home_headers,
+        &scope,
+        &mut layout_ids,
+        &function_pass,
+        debug_output_file,
+    );
+
+    built
+}
+
 pub fn build_procedures<'a, 'ctx, 'env>(
     env: &Env<'a, 'ctx, 'env>,
     opt_level: OptLevel,
@@ -4396,23 +4974,72 @@ pub fn build_procedures_expose_expects<'a, 'ctx, 'env>(
         let name = roc_main_fn.get_name().to_str().unwrap();
 
         let expect_name = &format!("Expect_{}", name);
-        let expect_name = env.arena.alloc_str(expect_name);
-        expect_names.push(&*expect_name);
+        let expect_name: &'a str = env.arena.alloc_str(expect_name);
+        expect_names.push(expect_name);
 
         // Add main to the module.
-        let _ = expose_function_to_host_help_c_abi(
+        let expect_fn = expose_function_to_host_help_c_abi(
             env,
             name,
             roc_main_fn,
             top_level.arguments,
             top_level.result,
-            &format!("Expect_{}", name),
+            expect_name,
         );
+
+        if env.target_info.architecture == roc_target::Architecture::Wasm32 {
+            expose_expect_status(env, expect_fn, expect_name);
+        }
     }
 
     expect_names
 }
 
+/// Add `{expect_name}_status : {} -> I32`, which runs the expect and returns 0 if it passed.
+/// Runtimes like wasmtime can't hand the expect a pointer to write its result into, but they
+/// can call a function without arguments and print the number it returns.
+fn expose_expect_status<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    expect_fn: FunctionValue<'ctx>,
+    expect_name: &str,
+) {
+    let builder = env.builder;
+    let context = env.context;
+
+    let status_name = format!("{}_status", expect_name);
+    let i32_type = context.i32_type();
+
+    let status_fn = add_func(
+        context,
+        env.module,
+        &status_name,
+        FunctionSpec::cconv(env, CCReturn::Return, Some(i32_type.into()), &[]),
+        Linkage::External,
+    );
+
+    let subprogram = env.new_subprogram(&status_name);
+    status_fn.set_subprogram(subprogram);
+
+    let entry = context.append_basic_block(status_fn, "entry");
+    builder.position_at_end(entry);
+
+    debug_info_init!(env, status_fn);
+
+    let result_type = roc_call_result_type(env, basic_type_from_layout(env, &Layout::UNIT));
+    let result_ptr = builder.build_alloca(result_type, "expect_result");
+
+    let call = builder.build_call(expect_fn, &[result_ptr.into()], "run_expect");
+    call.set_call_convention(C_CALL_CONV);
+
+    let tag_ptr = builder
+        .new_build_struct_gep(result_type, result_ptr, 0, "tag_ptr")
+        .unwrap();
+    let tag = builder.new_build_load(context.i64_type(), tag_ptr, "tag");
+    let status = builder.build_int_truncate(tag.into_int_value(), i32_type, "status");
+
+    builder.build_return(Some(&status));
+}
+
 fn build_procedures_help<'a, 'ctx, 'env>(
     env: &Env<'a, 'ctx, 'env>,
     opt_level: OptLevel,
@@ -4423,24 +5050,13 @@ fn build_procedures_help<'a, 'ctx, 'env>(
     let mut layout_ids = roc_mono::layout::LayoutIds::default();
     let mut scope = Scope::default();
 
-    let it = procedures.iter().map(|x| x.1);
-
-    let solutions = match roc_alias_analysis::spec_program(
+    let mod_solutions = alias_analysis_solutions(
         env.arena,
         env.layout_interner,
         opt_level,
+        &procedures,
         entry_point,
-        it,
-    ) {
-        Err(e) => panic!("Error in alias analysis: {}", e),
-        Ok(solutions) => solutions,
-    };
-
-    let solutions = env.arena.alloc(solutions);
-
-    let mod_solutions = solutions
-        .mod_solutions(roc_alias_analysis::MOD_APP)
-        .unwrap();
+    );
 
     // Add all the Proc headers to the module.
     // We have to do this in a separate pass first,
@@ -4449,6 +5065,35 @@ fn build_procedures_help<'a, 'ctx, 'env>(
 
     let (_, function_pass) = construct_optimization_passes(env.module, opt_level);
 
+    build_proc_bodies(
+        env,
+        mod_solutions,
+        headers,
+        &scope,
+        &mut layout_ids,
+        &function_pass,
+        debug_output_file,
+    );
+
+    mod_solutions
+}
+
+#[allow(clippy::type_complexity)]
+fn build_proc_bodies<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    mod_solutions: &'a ModSolutions,
+    headers: Vec<
+        'a,
+        (
+            roc_mono::ir::Proc<'a>,
+            &'a [(&'a FuncSpecSolutions, FunctionValue<'ctx>)],
+        ),
+    >,
+    scope: &Scope<'a, 'ctx>,
+    layout_ids: &mut LayoutIds<'a>,
+    function_pass: &PassManager<FunctionValue<'ctx>>,
+    debug_output_file: Option<&Path>,
+) {
     for (proc, fn_vals) in headers {
         for (func_spec_solutions, fn_val) in fn_vals {
             let mut current_scope = scope.clone();
@@ -4461,7 +5106,7 @@ fn build_procedures_help<'a, 'ctx, 'env>(
             build_proc(
                 env,
                 mod_solutions,
-                &mut layout_ids,
+                layout_ids,
                 func_spec_solutions,
                 scope.clone(),
                 &proc,
@@ -4504,8 +5149,6 @@ fn build_procedures_help<'a, 'ctx, 'env>(
             }
         }
     }
-
-    mod_solutions
 }
 
 fn func_spec_name<'a>(
@@ -4561,9 +5204,11 @@ fn build_proc_header<'a, 'ctx, 'env>(
         Linkage::Internal,
     );
 
-    let subprogram = env.new_subprogram(&fn_name);
+    let subprogram = env.new_proc_subprogram(symbol, &fn_name);
     fn_val.set_subprogram(subprogram);
 
+    add_stack_trace_attributes(env, fn_val);
+
     if env.exposed_to_host.contains(&symbol) {
         let arguments = Vec::from_iter_in(proc.args.iter().map(|(layout, _)| *layout), env.arena);
         expose_function_to_host(
@@ -4594,6 +5239,58 @@ fn build_proc_header<'a, 'ctx, 'env>(
     fn_val
 }
 
+/// Declares a procedure that is built into another LLVM module. A declaration has no debug info
+/// of its own, and is not exposed to the host from this module.
+fn build_proc_declaration<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    func_spec: FuncSpec,
+    symbol: Symbol,
+    proc: &roc_mono::ir::Proc<'a>,
+) -> FunctionValue<'ctx> {
+    let fn_name = func_spec_name(env.arena, &env.interns, symbol, func_spec);
+
+    let ret_type = basic_type_from_layout(env, &proc.ret_layout);
+    let arg_basic_types = Vec::from_iter_in(
+        proc.args
+            .iter()
+            .map(|(layout, _)| argument_type_from_layout(env, layout)),
+        env.arena,
+    );
+
+    let roc_return = RocReturn::from_layout(env, &proc.ret_layout);
+    let fn_spec = FunctionSpec::fastcc(env, roc_return, ret_type, arg_basic_types);
+
+    add_func(
+        env.context,
+        env.module,
+        fn_name.as_str(),
+        fn_spec,
+        Linkage::External,
+    )
+}
+
+/// Crashes print a Roc stack trace in `roc dev`, which walks the frame pointers of the program
+/// it runs, and in `roc test`, which unwinds with the system unwinder. Keep what each of them
+/// needs to get through Roc procedures.
+fn add_stack_trace_attributes<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    fn_val: FunctionValue<'ctx>,
+) {
+    match env.mode {
+        LlvmBackendMode::BinaryDev => {
+            let attr = env.context.create_string_attribute("frame-pointer", "all");
+            fn_val.add_attribute(AttributeLoc::Function, attr);
+        }
+        LlvmBackendMode::CliTest => {
+            let kind_id = Attribute::get_named_enum_kind_id("uwtable");
+            debug_assert!(kind_id > 0);
+            let enum_attr = env.context.create_enum_attribute(kind_id, 0);
+            fn_val.add_attribute(AttributeLoc::Function, enum_attr);
+        }
+        LlvmBackendMode::Binary | LlvmBackendMode::GenTest | LlvmBackendMode::WasmGenTest => {}
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 fn expose_alias_to_host<'a, 'ctx, 'env>(
     env: &'a Env<'a, 'ctx, 'env>,
@@ -4916,7 +5613,12 @@ pub fn build_proc<'a, 'ctx, 'env>(
 
     builder.position_at_end(entry);
 
-    debug_info_init!(env, fn_val);
+    env.set_proc_debug_location(proc.name.name(), fn_val);
+
+    // Builtins can't be covered by tests of the app, so don't count their calls
+    if matches!(env.mode, LlvmBackendMode::CliTest) && !proc.name.name().module_id().is_builtin() {
+        build_coverage_hook_call(env, fn_val, proc.name.name());
+    }
 
     // Add args to scope
     for (arg_val, (layout, arg_symbol)) in fn_val.get_param_iter().zip(args) {
@@ -5767,3 +6469,95 @@ impl<'a> WhenRecursive<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn opt_level_flags() {
+        let levels: std::vec::Vec<_> = ["0", "1", "2", "3", "s", "z"]
+            .iter()
+            .map(|flag| LlvmOptLevel::from_flag(flag))
+            .collect();
+
+        assert_eq!(
+            levels,
+            [
+                Some(LlvmOptLevel::O0),
+                Some(LlvmOptLevel::O1),
+                Some(LlvmOptLevel::O2),
+                Some(LlvmOptLevel::O3),
+                Some(LlvmOptLevel::Os),
+                Some(LlvmOptLevel::Oz),
+            ]
+        );
+        assert_eq!(LlvmOptLevel::from_flag("4"), None);
+    }
+
+    #[test]
+    fn opt_levels_round_trip() {
+        assert!(matches!(
+            LlvmOptLevel::from(OptLevel::Normal).opt_level(),
+            OptLevel::Normal
+        ));
+        assert!(matches!(
+            LlvmOptLevel::from(OptLevel::Size).opt_level(),
+            OptLevel::Size
+        ));
+        assert!(matches!(
+            LlvmOptLevel::from(OptLevel::Optimize).opt_level(),
+            OptLevel::Optimize
+        ));
+
+        assert!(matches!(LlvmOptLevel::O2.opt_level(), OptLevel::Optimize));
+        assert!(matches!(LlvmOptLevel::Oz.opt_level(), OptLevel::Size));
+    }
+
+    #[test]
+    fn parse_llvm_passes() {
+        assert_eq!(
+            LlvmPipeline::parse_passes("mem2reg, instcombine,,simplifycfg"),
+            Ok(LlvmPipeline::Custom(std::vec![
+                LlvmPass::Mem2Reg,
+                LlvmPass::InstCombine,
+                LlvmPass::SimplifyCfg,
+            ]))
+        );
+    }
+
+    #[test]
+    fn parse_unknown_llvm_pass() {
+        let problem = LlvmPipeline::parse_passes("mem2reg,loop-fusion").unwrap_err();
+
+        assert!(problem.starts_with("unknown LLVM pass `loop-fusion`"));
+        assert!(problem.contains("mem2reg"));
+    }
+
+    #[test]
+    fn custom_pipeline_runs_only_the_given_passes() {
+        let context = Context::create();
+        let module = context.create_module("test");
+        let builder = context.create_builder();
+
+        // fn f() -> i64 { let x = alloca i64; store 42, x; load x }
+        let i64_type = context.i64_type();
+        let function = module.add_function("f", i64_type.fn_type(&[], false), None);
+        builder.position_at_end(context.append_basic_block(function, "entry"));
+        let ptr = builder.build_alloca(i64_type, "x");
+        builder.build_store(ptr, i64_type.const_int(42, false));
+        let loaded = builder.new_build_load(i64_type, ptr, "loaded");
+        builder.build_return(Some(&loaded));
+
+        let has_alloca = |module: &Module| module.print_to_string().to_string().contains("alloca");
+
+        let (mpm, _fpm) = construct_pass_pipeline(&module, &LlvmPipeline::Custom(std::vec![]));
+        mpm.run_on(&module);
+        assert!(has_alloca(&module));
+
+        let (mpm, _fpm) =
+            construct_pass_pipeline(&module, &LlvmPipeline::Custom(std::vec![LlvmPass::Mem2Reg]));
+        mpm.run_on(&module);
+        assert!(!has_alloca(&module));
+    }
+}
@@ -1090,6 +1090,57 @@ fn build_tag_eq_help<'a, 'ctx, 'env>(
 
             env.builder.build_return(Some(&answer));
         }
+        NullablePointer { pointer, .. } => {
+            let ptr_equal = env.builder.build_int_compare(
+                IntPredicate::EQ,
+                env.builder
+                    .build_ptr_to_int(tag1.into_pointer_value(), env.ptr_int(), "pti"),
+                env.builder
+                    .build_ptr_to_int(tag2.into_pointer_value(), env.ptr_int(), "pti"),
+                "compare_pointers",
+            );
+
+            let check_for_null = ctx.append_basic_block(parent, "check_for_null");
+            let compare_other = ctx.append_basic_block(parent, "compare_other");
+
+            env.builder
+                .build_conditional_branch(ptr_equal, return_true, check_for_null);
+
+            // check for NULL
+
+            env.builder.position_at_end(check_for_null);
+
+            let is_null_1 = env
+                .builder
+                .build_is_null(tag1.into_pointer_value(), "is_null");
+
+            let is_null_2 = env
+                .builder
+                .build_is_null(tag2.into_pointer_value(), "is_null");
+
+            let either_null = env.builder.build_or(is_null_1, is_null_2, "either_null");
+
+            // logic: the pointers are not the same, if one is NULL, the other one is not
+            // therefore the two tags are not equal
+            env.builder
+                .build_conditional_branch(either_null, return_false, compare_other);
+
+            // compare the non-null case: the unions are the pointers of their only field
+
+            env.builder.position_at_end(compare_other);
+
+            let answer = build_eq(
+                env,
+                layout_ids,
+                tag1,
+                tag2,
+                pointer,
+                pointer,
+                when_recursive,
+            );
+
+            env.builder.build_return(Some(&answer));
+        }
         NullableWrapped { other_tags, .. } => {
             let ptr_equal = env.builder.build_int_compare(
                 IntPredicate::EQ,
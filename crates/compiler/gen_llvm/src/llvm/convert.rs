@@ -100,6 +100,13 @@ pub fn struct_type_from_union_layout<'a, 'ctx, 'env>(
             env.target_info,
         )
         .struct_type(),
+        NullablePointer { pointer, .. } => RocUnion::untagged_from_slices(
+            env.layout_interner,
+            env.context,
+            &[std::slice::from_ref(*pointer)],
+            env.target_info,
+        )
+        .struct_type(),
     }
 }
 
@@ -109,14 +116,16 @@ pub fn basic_type_from_union_layout<'a, 'ctx, 'env>(
 ) -> BasicTypeEnum<'ctx> {
     use UnionLayout::*;
 
-    let struct_type = struct_type_from_union_layout(env, union_layout);
-
     match union_layout {
-        NonRecursive(_) => struct_type.into(),
+        NonRecursive(_) => struct_type_from_union_layout(env, union_layout).into(),
         Recursive(_)
         | NonNullableUnwrapped(_)
         | NullableWrapped { .. }
-        | NullableUnwrapped { .. } => struct_type.ptr_type(AddressSpace::Generic).into(),
+        | NullableUnwrapped { .. } => struct_type_from_union_layout(env, union_layout)
+            .ptr_type(AddressSpace::Generic)
+            .into(),
+        // the union is just its pointer, which is null for the other tag
+        NullablePointer { pointer, .. } => basic_type_from_layout(env, pointer),
     }
 }
 
@@ -149,6 +149,17 @@ pub(crate) fn notify_parent_dbg(env: &Env, shared_memory: &SharedMemoryPointer)
     );
 }
 
+/// Send the parent (`roc dev`) the return addresses of the stack, so it can print a stack trace.
+pub(crate) fn notify_parent_panic(env: &Env) {
+    let func = env
+        .module
+        .get_function(bitcode::NOTIFY_PARENT_PANIC)
+        .unwrap();
+
+    env.builder
+        .build_call(func, &[], "call_notify_parent_panic");
+}
+
 // Shape of expect frame:
 //
 //     ===
@@ -880,6 +891,43 @@ fn build_clone_tag_help<'a, 'ctx, 'env>(
                 let answer =
                     build_clone(env, layout_ids, ptr, cursors, data, layout, when_recursive);
 
+                env.builder.build_return(Some(&answer));
+            }
+        }
+        NullablePointer { pointer, .. } => {
+            let other_block = env.context.append_basic_block(parent, "other_block");
+            let null_block = env.context.append_basic_block(parent, "null_block");
+
+            let comparison = env
+                .builder
+                .build_is_null(tag_value.into_pointer_value(), "is_null");
+
+            env.builder
+                .build_conditional_branch(comparison, null_block, other_block);
+
+            {
+                env.builder.position_at_end(null_block);
+
+                let value = env.ptr_int().const_zero();
+                build_copy(env, ptr, offset, value.into());
+
+                env.builder.build_return(Some(&extra_offset));
+            }
+
+            {
+                env.builder.position_at_end(other_block);
+
+                // the union is the pointer of its only field, so write that field
+                let answer = build_clone(
+                    env,
+                    layout_ids,
+                    ptr,
+                    cursors,
+                    tag_value,
+                    *pointer,
+                    when_recursive,
+                );
+
                 env.builder.build_return(Some(&answer));
             }
         }
@@ -1,16 +1,33 @@
 use crate::llvm::bitcode::call_void_bitcode_fn;
 use crate::llvm::build::{add_func, get_panic_msg_ptr, get_panic_tag_ptr, BuilderExt, C_CALL_CONV};
-use crate::llvm::build::{CCReturn, Env, FunctionSpec};
+use crate::llvm::build::{CCReturn, Env, FunctionSpec, LlvmBackendMode};
 use crate::llvm::convert::zig_str_type;
 use inkwell::module::Linkage;
-use inkwell::types::BasicType;
-use inkwell::values::BasicValue;
-use inkwell::AddressSpace;
+use inkwell::types::{BasicType, FunctionType};
+use inkwell::values::{
+    BasicMetadataValueEnum, BasicValue, BasicValueEnum, CallableValue, FunctionValue, IntValue,
+};
+use inkwell::{AddressSpace, IntPredicate};
 use roc_builtins::bitcode;
+use roc_module::symbol::Symbol;
 
 use super::build::get_sjlj_buffer;
 use super::intrinsics::LLVM_LONGJMP;
 
+/// A global that `roc test` can point at a function of its own, which the testing `roc_panic`
+/// calls before unwinding, while the Roc frames that panicked are still on the stack.
+pub const ROC_PANIC_HOOK: &str = "roc_panic_hook";
+
+/// Globals that `roc test` can point at functions of its own, which the testing allocator calls
+/// with each allocation it makes (`ptr, size, alignment`) and each one it is about to free
+/// (`ptr`). Returning nonzero from the dealloc hook skips the free, e.g. for a double free.
+pub const ROC_ALLOC_HOOK: &str = "roc_alloc_hook";
+pub const ROC_DEALLOC_HOOK: &str = "roc_dealloc_hook";
+
+/// A global that `roc test --coverage` points at a function of its own, which every Roc
+/// procedure calls with its symbol (as a `u64`) when it is entered.
+pub const ROC_COVERAGE_HOOK: &str = "roc_coverage_hook";
+
 /// Define functions for roc_alloc, roc_realloc, and roc_dealloc
 /// which use libc implementations (malloc, realloc, and free)
 pub fn add_default_roc_externs(env: &Env<'_, '_, '_>) {
@@ -44,7 +61,7 @@ pub fn add_default_roc_externs(env: &Env<'_, '_, '_>) {
             let fn_val = module.get_function("roc_alloc").unwrap();
             let mut params = fn_val.get_param_iter();
             let size_arg = params.next().unwrap();
-            let _alignment_arg = params.next().unwrap();
+            let alignment_arg = params.next().unwrap();
 
             debug_assert!(params.next().is_none());
 
@@ -58,6 +75,10 @@ pub fn add_default_roc_externs(env: &Env<'_, '_, '_>) {
                 .build_array_malloc(ctx.i8_type(), size_arg.into_int_value(), "call_malloc")
                 .unwrap();
 
+            if let LlvmBackendMode::CliTest = env.mode {
+                build_alloc_hook_call(env, fn_val, retval.into(), size_arg, alignment_arg);
+            }
+
             builder.build_return(Some(&retval));
 
             if cfg!(debug_assertions) {
@@ -104,7 +125,7 @@ pub fn add_default_roc_externs(env: &Env<'_, '_, '_>) {
             let ptr_arg = params.next().unwrap();
             let new_size_arg = params.next().unwrap();
             let _old_size_arg = params.next().unwrap();
-            let _alignment_arg = params.next().unwrap();
+            let alignment_arg = params.next().unwrap();
 
             debug_assert!(params.next().is_none());
 
@@ -124,6 +145,12 @@ pub fn add_default_roc_externs(env: &Env<'_, '_, '_>) {
 
             let retval = call.try_as_basic_value().left().unwrap();
 
+            if let LlvmBackendMode::CliTest = env.mode {
+                // realloc has already freed the old allocation, so there's nothing to skip
+                build_dealloc_hook_call(env, fn_val, ptr_arg);
+                build_alloc_hook_call(env, fn_val, retval, new_size_arg, alignment_arg);
+            }
+
             builder.build_return(Some(&retval));
 
             if cfg!(debug_assertions) {
@@ -147,8 +174,29 @@ pub fn add_default_roc_externs(env: &Env<'_, '_, '_>) {
 
             builder.position_at_end(entry);
 
-            // Call libc free()
-            builder.build_free(ptr_arg.into_pointer_value());
+            if let LlvmBackendMode::CliTest = env.mode {
+                let skip = build_dealloc_hook_call(env, fn_val, ptr_arg);
+
+                let free_block = ctx.append_basic_block(fn_val, "free");
+                let done_block = ctx.append_basic_block(fn_val, "done");
+
+                let should_free = builder.build_int_compare(
+                    IntPredicate::EQ,
+                    skip,
+                    ctx.i8_type().const_zero(),
+                    "should_free",
+                );
+                builder.build_conditional_branch(should_free, free_block, done_block);
+
+                builder.position_at_end(free_block);
+                builder.build_free(ptr_arg.into_pointer_value());
+                builder.build_unconditional_branch(done_block);
+
+                builder.position_at_end(done_block);
+            } else {
+                // Call libc free()
+                builder.build_free(ptr_arg.into_pointer_value());
+            }
 
             builder.build_return(None);
 
@@ -157,9 +205,12 @@ pub fn add_default_roc_externs(env: &Env<'_, '_, '_>) {
             }
         }
 
-        unreachable_function(env, "roc_getppid");
-        unreachable_function(env, "roc_mmap");
-        unreachable_function(env, "roc_shm_open");
+        // The wasm32 builtins don't share memory with the test runner, so they don't use these.
+        if env.target_info.architecture != roc_target::Architecture::Wasm32 {
+            unreachable_function(env, "roc_getppid");
+            unreachable_function(env, "roc_mmap");
+            unreachable_function(env, "roc_shm_open");
+        }
 
         add_sjlj_roc_panic(env)
     }
@@ -250,6 +301,10 @@ pub fn add_sjlj_roc_panic(env: &Env<'_, '_, '_>) {
             env.builder.build_store(get_panic_tag_ptr(env), inc_tag_id);
         }
 
+        if let LlvmBackendMode::CliTest = env.mode {
+            build_panic_hook_call(env, fn_val);
+        }
+
         build_longjmp_call(env);
 
         builder.build_unreachable();
@@ -260,6 +315,141 @@ pub fn add_sjlj_roc_panic(env: &Env<'_, '_, '_>) {
     }
 }
 
+/// Call the function in `roc_panic_hook`, if the test runner has put one there.
+fn build_panic_hook_call<'ctx>(env: &Env<'_, 'ctx, '_>, parent: FunctionValue<'ctx>) {
+    let hook_type = env.context.void_type().fn_type(&[], false);
+
+    build_hook_call(env, parent, ROC_PANIC_HOOK, hook_type, &[], None);
+}
+
+/// Tell the function in `roc_alloc_hook`, if there is one, about a new allocation.
+fn build_alloc_hook_call<'ctx>(
+    env: &Env<'_, 'ctx, '_>,
+    parent: FunctionValue<'ctx>,
+    ptr: BasicValueEnum<'ctx>,
+    size: BasicValueEnum<'ctx>,
+    alignment: BasicValueEnum<'ctx>,
+) {
+    let ctx = env.context;
+    let hook_type = ctx.void_type().fn_type(
+        &[
+            ctx.i8_type().ptr_type(AddressSpace::Generic).into(),
+            env.ptr_int().into(),
+            ctx.i32_type().into(),
+        ],
+        false,
+    );
+
+    build_hook_call(
+        env,
+        parent,
+        ROC_ALLOC_HOOK,
+        hook_type,
+        &[ptr.into(), size.into(), alignment.into()],
+        None,
+    );
+}
+
+/// Tell the function in `roc_coverage_hook`, if there is one, that a procedure was entered.
+pub(crate) fn build_coverage_hook_call<'ctx>(
+    env: &Env<'_, 'ctx, '_>,
+    parent: FunctionValue<'ctx>,
+    symbol: Symbol,
+) {
+    let ctx = env.context;
+    let hook_type = ctx.void_type().fn_type(&[ctx.i64_type().into()], false);
+    let symbol = ctx.i64_type().const_int(symbol.as_u64(), false);
+
+    build_hook_call(
+        env,
+        parent,
+        ROC_COVERAGE_HOOK,
+        hook_type,
+        &[symbol.into()],
+        None,
+    );
+}
+
+/// Tell the function in `roc_dealloc_hook`, if there is one, that an allocation is about to be
+/// freed. Returns nonzero if it should not be.
+fn build_dealloc_hook_call<'ctx>(
+    env: &Env<'_, 'ctx, '_>,
+    parent: FunctionValue<'ctx>,
+    ptr: BasicValueEnum<'ctx>,
+) -> IntValue<'ctx> {
+    let ctx = env.context;
+    let hook_type = ctx.i8_type().fn_type(
+        &[ctx.i8_type().ptr_type(AddressSpace::Generic).into()],
+        false,
+    );
+
+    let skip = build_hook_call(
+        env,
+        parent,
+        ROC_DEALLOC_HOOK,
+        hook_type,
+        &[ptr.into()],
+        Some(ctx.i8_type().const_zero().into()),
+    );
+
+    skip.unwrap().into_int_value()
+}
+
+/// Call the function in the given global, if the test runner has put one there. Returns what
+/// it returned, or `default` if there was nothing to call.
+fn build_hook_call<'ctx>(
+    env: &Env<'_, 'ctx, '_>,
+    parent: FunctionValue<'ctx>,
+    name: &str,
+    hook_type: FunctionType<'ctx>,
+    args: &[BasicMetadataValueEnum<'ctx>],
+    default: Option<BasicValueEnum<'ctx>>,
+) -> Option<BasicValueEnum<'ctx>> {
+    let ctx = env.context;
+    let builder = env.builder;
+
+    let hook_ptr_type = hook_type.ptr_type(AddressSpace::Generic);
+
+    // roc_realloc calls two hooks, so the global may already be there
+    let global = match env.module.get_global(name) {
+        Some(global) => global,
+        None => {
+            let global = env.module.add_global(hook_ptr_type, None, name);
+            global.set_initializer(&hook_ptr_type.const_null());
+
+            global
+        }
+    };
+
+    let hook = builder
+        .new_build_load(hook_ptr_type, global.as_pointer_value(), "load_hook")
+        .into_pointer_value();
+
+    let start_block = builder.get_insert_block().unwrap();
+    let call_block = ctx.append_basic_block(parent, "call_hook");
+    let cont_block = ctx.append_basic_block(parent, "after_hook");
+
+    let is_null = builder.build_is_null(hook, "hook_is_null");
+    builder.build_conditional_branch(is_null, cont_block, call_block);
+
+    builder.position_at_end(call_block);
+    let callable = CallableValue::try_from(hook).unwrap();
+    let call = builder.build_call(callable, args, "call_hook");
+    call.set_call_convention(C_CALL_CONV);
+    builder.build_unconditional_branch(cont_block);
+
+    builder.position_at_end(cont_block);
+
+    default.map(|default| {
+        let returned = call.try_as_basic_value().left().unwrap();
+
+        let phi = builder.build_phi(default.get_type(), "hook_result");
+        phi.add_incoming(&[(&default, start_block), (&returned, call_block)]);
+
+        phi.as_basic_value()
+    })
+}
+
 pub fn build_longjmp_call(env: &Env) {
     let jmp_buf = get_sjlj_buffer(env);
     if cfg!(target_arch = "aarch64") {
@@ -2269,6 +2269,7 @@ pub(crate) fn run_higher_order_low_level<'a, 'ctx, 'env>(
         argument_layouts,
         return_layout: result_layout,
         owns_captured_environment: function_owns_closure_data,
+        borrows_arguments,
         name: function_name,
         captured_environment,
         ..
@@ -2476,10 +2477,16 @@ pub(crate) fn run_higher_order_low_level<'a, 'ctx, 'env>(
 
                     let argument_layouts = &[**element_layout, **element_layout];
 
-                    let compare_wrapper =
-                        build_compare_wrapper(env, function, closure_layout, element_layout)
-                            .as_global_value()
-                            .as_pointer_value();
+                    let compare_wrapper = build_compare_wrapper(
+                        env,
+                        layout_ids,
+                        function,
+                        closure_layout,
+                        element_layout,
+                        borrows_arguments,
+                    )
+                    .as_global_value()
+                    .as_pointer_value();
 
                     let roc_function_call = roc_function_call(
                         env,
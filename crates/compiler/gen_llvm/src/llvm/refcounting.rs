@@ -552,6 +552,18 @@ fn modify_refcount_layout_build_function<'a, 'ctx, 'env>(
                     Some(function)
                 }
 
+                NullablePointer { .. } => {
+                    let function = modify_refcount_nullable_pointer(
+                        env,
+                        layout_ids,
+                        mode,
+                        when_recursive,
+                        *variant,
+                    );
+
+                    Some(function)
+                }
+
                 _ => {
                     let function = build_rec_union(
                         env,
@@ -917,6 +929,112 @@ fn modify_refcount_box_help<'a, 'ctx, 'env>(
     builder.build_return(None);
 }
 
+fn modify_refcount_nullable_pointer<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    layout_ids: &mut LayoutIds<'a>,
+    mode: Mode,
+    when_recursive: &WhenRecursive<'a>,
+    union_layout: UnionLayout<'a>,
+) -> FunctionValue<'ctx> {
+    let block = env.builder.get_insert_block().expect("to be in a function");
+    let di_location = env.builder.get_current_debug_location().unwrap();
+
+    let layout = env.arena.alloc(Layout::Union(union_layout));
+
+    let (_, fn_name) = function_name_from_mode(
+        layout_ids,
+        &env.interns,
+        "increment_nullable_pointer",
+        "decrement_nullable_pointer",
+        layout,
+        mode,
+    );
+
+    let function = match env.module.get_function(fn_name.as_str()) {
+        Some(function_value) => function_value,
+        None => {
+            let basic_type = basic_type_from_layout(env, layout);
+            let function_value = build_header(env, basic_type, mode, &fn_name);
+
+            modify_refcount_nullable_pointer_help(
+                env,
+                layout_ids,
+                mode,
+                when_recursive,
+                union_layout,
+                function_value,
+            );
+
+            function_value
+        }
+    };
+
+    env.builder.position_at_end(block);
+    env.builder
+        .set_current_debug_location(env.context, di_location);
+
+    function
+}
+
+fn modify_refcount_nullable_pointer_help<'a, 'ctx, 'env>(
+    env: &Env<'a, 'ctx, 'env>,
+    layout_ids: &mut LayoutIds<'a>,
+    mode: Mode,
+    when_recursive: &WhenRecursive<'a>,
+    union_layout: UnionLayout<'a>,
+    fn_val: FunctionValue<'ctx>,
+) {
+    let pointer_layout = match union_layout {
+        UnionLayout::NullablePointer { pointer, .. } => pointer,
+        _ => unreachable!(),
+    };
+
+    let builder = env.builder;
+    let ctx = env.context;
+
+    // Add a basic block for the entry point
+    let entry = ctx.append_basic_block(fn_val, "entry");
+
+    builder.position_at_end(entry);
+
+    debug_info_init!(env, fn_val);
+
+    // Add args to scope
+    let arg_symbol = Symbol::ARG_1;
+    let arg_val = fn_val.get_param_iter().next().unwrap();
+    arg_val.set_name(arg_symbol.as_str(&env.interns));
+
+    let parent = fn_val;
+
+    // the null tag has nothing to refcount
+    let is_null = builder.build_is_null(arg_val.into_pointer_value(), "is_null");
+
+    let then_block = ctx.append_basic_block(parent, "then");
+    let cont_block = ctx.append_basic_block(parent, "cont");
+
+    builder.build_conditional_branch(is_null, then_block, cont_block);
+
+    {
+        builder.position_at_end(then_block);
+        builder.build_return(None);
+    }
+
+    // otherwise, the union is the pointer of the other tag, and shares its refcount
+    builder.position_at_end(cont_block);
+
+    modify_refcount_layout_help(
+        env,
+        layout_ids,
+        mode_to_call_mode(fn_val, mode),
+        when_recursive,
+        arg_val,
+        pointer_layout,
+    );
+
+    // this function returns void
+    builder.build_return(None);
+}
+
 /// Build an increment or decrement function for a specific layout
 fn build_header<'a, 'ctx, 'env>(
     env: &Env<'a, 'ctx, 'env>,
@@ -1357,6 +1475,10 @@ fn union_layout_tags<'a>(
         NonNullableUnwrapped(fields) => arena.alloc([*fields]),
         Recursive(tags) => tags,
         NonRecursive(tags) => tags,
+        NullablePointer {
+            nullable_id,
+            pointer,
+        } => arena.alloc(UnionLayout::nullable_pointer_tags(*nullable_id, pointer)),
     }
 }
 
@@ -1,4 +1,3 @@
-use bitvec::vec::BitVec;
 use bumpalo::collections::{String, Vec};
 
 use roc_builtins::bitcode::{FloatWidth, IntWidth};
@@ -8,7 +7,7 @@ use roc_module::low_level::{LowLevel, LowLevelWrapperType};
 use roc_module::symbol::{Interns, Symbol};
 use roc_mono::code_gen_help::{CodeGenHelp, HelperOp, REFCOUNT_MAX};
 use roc_mono::ir::{
-    BranchInfo, CallType, CrashTag, Expr, JoinPointId, ListLiteralElement, Literal, ModifyRc,
+    BranchInfo, Call, CallType, CrashTag, Expr, JoinPointId, ListLiteralElement, Literal, ModifyRc,
     Param, Proc, ProcLayout, Stmt,
 };
 use roc_mono::layout::{Builtin, Layout, LayoutIds, TagIdIntType, UnionLayout};
@@ -20,7 +19,8 @@ use roc_wasm_module::sections::{
     MemorySection, NameSection,
 };
 use roc_wasm_module::{
-    round_up_to_alignment, Align, ExportType, LocalId, Signature, SymInfo, ValueType, WasmModule,
+    round_up_to_alignment, Align, BitSet, ExportType, LocalId, Signature, SymInfo, ValueType,
+    WasmModule,
 };
 
 use crate::code_builder::CodeBuilder;
@@ -38,7 +38,9 @@ pub enum ProcSource {
     Helper,
     /// Wrapper function for higher-order calls from Zig to Roc
     HigherOrderMapper(usize),
-    HigherOrderCompare(usize),
+    /// The index of the compare function, and of the function that increments an element if the
+    /// compare function owns its arguments
+    HigherOrderCompare(usize, Option<u32>),
 }
 
 #[derive(Debug)]
@@ -57,11 +59,14 @@ pub struct WasmBackend<'a> {
     layout_ids: LayoutIds<'a>,
     pub fn_index_offset: u32,
     import_fn_count: u32,
-    called_fns: BitVec<usize>,
+    called_fns: BitSet,
     pub proc_lookup: Vec<'a, ProcLookupData<'a>>,
     host_lookup: Vec<'a, (&'a str, u32)>,
     helper_proc_gen: CodeGenHelp<'a>,
     can_relocate_heap: bool,
+    /// Addresses of the constants already stored in the data section, keyed by their bytes.
+    /// Identical literals from any module share one segment.
+    data_addresses: MutMap<&'a [u8], u32>,
 
     // Function-level data
     pub code_builder: CodeBuilder<'a>,
@@ -109,8 +114,8 @@ impl<'a> WasmBackend<'a> {
         let host_function_count = import_fn_count
             + module.code.dead_import_dummy_count as usize
             + module.code.function_count as usize;
-        let mut called_fns = BitVec::repeat(false, host_function_count);
-        called_fns.extend(std::iter::repeat(true).take(proc_lookup.len()));
+        let mut called_fns = BitSet::with_len(host_function_count + proc_lookup.len());
+        called_fns.insert_range(host_function_count..called_fns.len());
 
         WasmBackend {
             env,
@@ -126,6 +131,7 @@ impl<'a> WasmBackend<'a> {
             host_lookup,
             helper_proc_gen,
             can_relocate_heap,
+            data_addresses: MutMap::default(),
 
             // Function-level data
             block_depth: 0,
@@ -277,7 +283,7 @@ impl<'a> WasmBackend<'a> {
         wasm_fn_index
     }
 
-    pub fn finalize(mut self) -> (WasmModule<'a>, BitVec<usize>) {
+    pub fn finalize(mut self) -> (WasmModule<'a>, BitSet) {
         self.set_memory_layout(self.env.stack_bytes);
         self.export_globals();
 
@@ -335,7 +341,7 @@ impl<'a> WasmBackend<'a> {
         self.code_builder.build_fn_header_and_footer(&[], 0, None);
         self.reset();
 
-        self.called_fns.set(main_fn_index as usize, true);
+        self.called_fns.insert(main_fn_index as usize);
     }
 
     /// Register the debug names of Symbols in a global lookup table
@@ -609,6 +615,7 @@ impl<'a> WasmBackend<'a> {
         &mut self,
         wrapper_lookup_idx: usize,
         inner_lookup_idx: usize,
+        inc_fn_index: Option<u32>,
     ) {
         use ValueType::*;
 
@@ -620,16 +627,27 @@ impl<'a> WasmBackend<'a> {
         let closure_data_layout = wrapper_proc_layout.arguments[0];
         let value_layout = wrapper_proc_layout.arguments[1];
 
+        let inner_layout = match value_layout {
+            Layout::Boxed(inner) => inner,
+            x => internal_error!("Expected a Boxed layout, got {:?}", x),
+        };
+
+        // The sort calls the compare function many times with the same element. If the
+        // function consumes its arguments, give it a reference of its own to each of them.
+        if let Some(inc_fn_index) = inc_fn_index {
+            for value_local in [LocalId(1), LocalId(2)] {
+                self.code_builder.get_local(value_local);
+                self.dereference_boxed_value(inner_layout);
+                self.code_builder.i32_const(1);
+                self.code_builder.call(inc_fn_index, 2, false);
+            }
+        }
+
         let mut n_inner_args = 2;
         if closure_data_layout.stack_size(self.env.layout_interner, TARGET_INFO) > 0 {
             self.code_builder.get_local(LocalId(0));
             n_inner_args += 1;
         }
-
-        let inner_layout = match value_layout {
-            Layout::Boxed(inner) => inner,
-            x => internal_error!("Expected a Boxed layout, got {:?}", x),
-        };
         self.code_builder.get_local(LocalId(1));
         self.dereference_boxed_value(inner_layout);
         self.code_builder.get_local(LocalId(2));
@@ -747,6 +765,22 @@ impl<'a> WasmBackend<'a> {
                 print!("\nlet {:?} = {}", sym, expr.to_pretty(200, true));
             }
 
+            if let Expr::Call(Call {
+                call_type:
+                    CallType::LowLevel {
+                        op: LowLevel::Dbg, ..
+                    },
+                arguments,
+            }) = expr
+            {
+                // `dbg` just gives its argument back, which is already stored in the same symbol
+                debug_assert_eq!(*sym, arguments[0]);
+                self.stmt_dbg(*sym, layout);
+
+                current_stmt = *following;
+                continue;
+            }
+
             let kind = match following {
                 Stmt::Ret(ret_sym) if *sym == *ret_sym => StoredVarKind::ReturnValue,
                 _ => StoredVarKind::Variable,
@@ -1008,6 +1042,53 @@ impl<'a> WasmBackend<'a> {
         self.stmt_crash(msg_sym, CrashTag::Roc);
     }
 
+    /// Print a value for `dbg`. We render it as text with a helper proc, since the host doesn't
+    /// know anything about Roc types, and pass it to the host along with where it's from.
+    fn stmt_dbg(&mut self, value: Symbol, layout: &Layout<'a>) {
+        let str_layout = Layout::Builtin(Builtin::Str);
+        let empty = self.create_symbol("dbg_empty");
+        let text = self.create_symbol("dbg_text");
+        let location = self.create_symbol("dbg_location");
+
+        let text_expr = if let Layout::LambdaSet(_) = layout {
+            Expr::Literal(Literal::Str("<function>"))
+        } else {
+            let ident_ids = self
+                .interns
+                .all_ident_ids
+                .get_mut(&self.env.module_id)
+                .unwrap();
+
+            let (to_str_expr, new_specializations) = self.helper_proc_gen.call_specialized_to_str(
+                ident_ids,
+                layout,
+                self.env.arena.alloc([value, empty]),
+            );
+
+            for (spec_sym, spec_layout) in new_specializations.into_iter() {
+                self.register_helper_proc(spec_sym, spec_layout, ProcSource::Helper);
+            }
+
+            to_str_expr
+        };
+
+        let location_str = match self.env.dbg_locations.get(&value) {
+            Some(location) => location.as_str(),
+            None => self.interns.module_name(value.module_id()).as_str(),
+        };
+        let location_expr = Expr::Literal(Literal::Str(self.env.arena.alloc_str(location_str)));
+
+        let kind = StoredVarKind::Variable;
+        self.stmt_let_store_expr(empty, &str_layout, &Expr::Literal(Literal::Str("")), kind);
+        self.stmt_let_store_expr(text, &str_layout, &text_expr, kind);
+        self.stmt_let_store_expr(location, &str_layout, &location_expr, kind);
+
+        // load the pointers
+        self.storage
+            .load_symbols(&mut self.code_builder, &[location, text]);
+        self.call_host_fn_after_loading_args("roc_dbg", 2, false);
+    }
+
     pub fn stmt_crash(&mut self, msg: Symbol, tag: CrashTag) {
         // load the pointer
         self.storage.load_symbols(&mut self.code_builder, &[msg]);
@@ -1075,6 +1156,12 @@ impl<'a> WasmBackend<'a> {
                 ..
             } => self.expr_tag(tag_layout, *tag_id, arguments, sym, storage, Some(*reused)),
 
+            Expr::ReuseBox {
+                symbol: reused,
+                value: arg_sym,
+                ..
+            } => self.expr_reuse_box(sym, *reused, *arg_sym, storage),
+
             Expr::Reset { symbol: arg, .. } => self.expr_reset(*arg, sym, storage),
 
             Expr::RuntimeErrorFunction(_) => {
@@ -1173,7 +1260,7 @@ impl<'a> WasmBackend<'a> {
             self.code_builder.i32_store(Align::Bytes4, offset + 8);
         } else {
             let bytes = string.as_bytes();
-            let elements_addr = self.store_bytes_in_data_section(bytes);
+            let elements_addr = self.store_bytes_in_data_section(bytes, PTR_SIZE);
 
             // ptr
             self.code_builder.get_local(local_id);
@@ -1192,12 +1279,20 @@ impl<'a> WasmBackend<'a> {
         };
     }
 
-    /// Create a string constant in the module data section
-    /// Return the data we need for code gen: linker symbol index and memory address
-    fn store_bytes_in_data_section(&mut self, bytes: &[u8]) -> u32 {
-        // Place the segment at a 4-byte aligned offset
-        let segment_addr = round_up_to_alignment!(self.module.data.end_addr, PTR_SIZE);
-        let elements_addr = segment_addr + PTR_SIZE;
+    /// Create a string or list constant in the module data section, or find an identical one
+    /// Return the memory address of its elements
+    fn store_bytes_in_data_section(&mut self, bytes: &[u8], alignment_bytes: u32) -> u32 {
+        if let Some(&elements_addr) = self.data_addresses.get(bytes) {
+            if elements_addr % alignment_bytes == 0 {
+                return elements_addr;
+            }
+        }
+
+        // Place the elements at an aligned offset, with the refcount just before them
+        let alignment_bytes = alignment_bytes.max(PTR_SIZE);
+        let elements_addr =
+            round_up_to_alignment!(self.module.data.end_addr + PTR_SIZE, alignment_bytes);
+        let segment_addr = elements_addr - PTR_SIZE;
         let length_with_refcount = 4 + bytes.len();
         self.module.data.end_addr = segment_addr + length_with_refcount as u32;
 
@@ -1213,6 +1308,9 @@ impl<'a> WasmBackend<'a> {
 
         self.module.data.append_segment(segment);
 
+        self.data_addresses
+            .insert(self.env.arena.alloc_slice_copy(bytes), elements_addr);
+
         elements_addr
     }
 
@@ -1358,7 +1456,7 @@ impl<'a> WasmBackend<'a> {
             .find(|(fn_name, _)| *fn_name == name)
             .unwrap_or_else(|| panic!("The Roc app tries to call `{}` but I can't find it!", name));
 
-        self.called_fns.set(*fn_index as usize, true);
+        self.called_fns.insert(*fn_index as usize);
 
         if *fn_index < self.import_fn_count {
             self.code_builder
@@ -1513,7 +1611,9 @@ impl<'a> WasmBackend<'a> {
         elem_layout: &Layout<'a>,
         elems: &'a [ListLiteralElement<'a>],
     ) {
-        if let StoredValue::StackMemory { location, .. } = storage {
+        if let Some(bytes) = self.constant_list_bytes(elem_layout, elems) {
+            self.expr_constant_array(sym, storage, elem_layout, elems.len(), bytes);
+        } else if let StoredValue::StackMemory { location, .. } = storage {
             let size = elem_layout.stack_size(self.env.layout_interner, TARGET_INFO)
                 * (elems.len() as u32);
 
@@ -1579,6 +1679,86 @@ impl<'a> WasmBackend<'a> {
         }
     }
 
+    /// The bytes of a list literal whose elements are all numeric constants.
+    /// Alias analysis treats such a list as static, so it can live in the data section.
+    fn constant_list_bytes(
+        &self,
+        elem_layout: &Layout<'a>,
+        elems: &'a [ListLiteralElement<'a>],
+    ) -> Option<Vec<'a, u8>> {
+        if elems.is_empty() {
+            return None;
+        }
+
+        let elem_size = elem_layout.stack_size(self.env.layout_interner, TARGET_INFO) as usize;
+        let mut bytes = Vec::with_capacity_in(elem_size * elems.len(), self.env.arena);
+
+        for elem in elems.iter() {
+            match (elem, elem_layout) {
+                (
+                    ListLiteralElement::Literal(Literal::Int(x) | Literal::U128(x)),
+                    Layout::Builtin(Builtin::Int(_)),
+                ) => {
+                    let le_bytes = i128::from_ne_bytes(*x).to_le_bytes();
+                    bytes.extend_from_slice(&le_bytes[..elem_size]);
+                }
+                (
+                    ListLiteralElement::Literal(Literal::Float(x)),
+                    Layout::Builtin(Builtin::Float(FloatWidth::F64)),
+                ) => bytes.extend_from_slice(&x.to_le_bytes()),
+                (
+                    ListLiteralElement::Literal(Literal::Float(x)),
+                    Layout::Builtin(Builtin::Float(FloatWidth::F32)),
+                ) => bytes.extend_from_slice(&(*x as f32).to_le_bytes()),
+                (ListLiteralElement::Literal(Literal::Decimal(x)), _) => {
+                    let (upper_bits, lower_bits) = RocDec::from_ne_bytes(*x).as_bits();
+                    bytes.extend_from_slice(&lower_bits.to_le_bytes());
+                    bytes.extend_from_slice(&upper_bits.to_le_bytes());
+                }
+                (ListLiteralElement::Literal(Literal::Bool(x)), _) => bytes.push(*x as u8),
+                (ListLiteralElement::Literal(Literal::Byte(x)), _) => bytes.push(*x),
+                _ => return None,
+            }
+        }
+
+        Some(bytes)
+    }
+
+    fn expr_constant_array(
+        &mut self,
+        sym: Symbol,
+        storage: &StoredValue,
+        elem_layout: &Layout<'a>,
+        len: usize,
+        bytes: Vec<'a, u8>,
+    ) {
+        if let StoredValue::StackMemory { location, .. } = storage {
+            let alignment = elem_layout.alignment_bytes(self.env.layout_interner, TARGET_INFO);
+            let elements_addr = self.store_bytes_in_data_section(&bytes, alignment);
+
+            let (local_id, offset) = location.local_and_offset(self.storage.stack_frame_pointer);
+
+            // elements pointer
+            self.code_builder.get_local(local_id);
+            self.code_builder.i32_const(elements_addr as i32);
+            self.code_builder.i32_store(Align::Bytes4, offset);
+
+            // length of the list
+            self.code_builder.get_local(local_id);
+            self.code_builder.i32_const(len as i32);
+            self.code_builder
+                .i32_store(Align::Bytes4, offset + 4 * Builtin::WRAPPER_LEN);
+
+            // capacity of the list
+            self.code_builder.get_local(local_id);
+            self.code_builder.i32_const(len as i32);
+            self.code_builder
+                .i32_store(Align::Bytes4, offset + 4 * Builtin::WRAPPER_CAPACITY);
+        } else {
+            internal_error!("Unexpected storage for Array {:?}: {:?}", sym, storage)
+        }
+    }
+
     fn expr_empty_array(&mut self, sym: Symbol, storage: &StoredValue) {
         if let StoredValue::StackMemory { location, .. } = storage {
             let (local_id, offset) = location.local_and_offset(self.storage.stack_frame_pointer);
@@ -1614,6 +1794,13 @@ impl<'a> WasmBackend<'a> {
             return;
         }
 
+        if let UnionLayout::NullablePointer { .. } = union_layout {
+            // The tag is represented by its only field, which is already a heap pointer
+            self.storage
+                .load_symbols(&mut self.code_builder, &[arguments[0]]);
+            return;
+        }
+
         let stores_tag_id_as_data = union_layout.stores_tag_id_as_data(TARGET_INFO);
         let stores_tag_id_in_pointer = union_layout.stores_tag_id_in_pointer(TARGET_INFO);
         let (data_size, data_alignment) =
@@ -1744,7 +1931,7 @@ impl<'a> WasmBackend<'a> {
                 self.code_builder.else_();
                 Some(local_id)
             }
-            NullableUnwrapped { nullable_id, .. } => {
+            NullableUnwrapped { nullable_id, .. } | NullablePointer { nullable_id, .. } => {
                 self.code_builder.i32_const(!(*nullable_id) as i32);
                 self.code_builder.i32_const(*nullable_id as i32);
                 self.storage
@@ -1795,6 +1982,14 @@ impl<'a> WasmBackend<'a> {
 
         debug_assert!(!union_layout.tag_is_null(tag_id));
 
+        if let NullablePointer { .. } = union_layout {
+            // The only field is the pointer that represents the whole union
+            debug_assert_eq!(index, 0);
+            self.storage
+                .load_symbols(&mut self.code_builder, &[structure]);
+            return;
+        }
+
         let tag_index = tag_id as usize;
         let field_layouts = match union_layout {
             NonRecursive(tags) => tags[tag_index],
@@ -1812,6 +2007,7 @@ impl<'a> WasmBackend<'a> {
                 other_tags[index]
             }
             NullableUnwrapped { other_fields, .. } => *other_fields,
+            NullablePointer { .. } => unreachable!(),
         };
 
         let field_offset: u32 = field_layouts
@@ -1895,6 +2091,32 @@ impl<'a> WasmBackend<'a> {
             .copy_value_to_memory(&mut self.code_builder, ptr_local_id, 0, arg_sym);
     }
 
+    /// The generated reset of a box never returns NULL, so unlike tags, a reused box is always
+    /// written into the reset memory.
+    fn expr_reuse_box(
+        &mut self,
+        ret_sym: Symbol,
+        reused_sym: Symbol,
+        arg_sym: Symbol,
+        storage: &StoredValue,
+    ) {
+        let ptr_local_id = match self.storage.ensure_value_has_local(
+            &mut self.code_builder,
+            ret_sym,
+            storage.clone(),
+        ) {
+            StoredValue::Local { local_id, .. } => local_id,
+            _ => internal_error!("A heap pointer will always be an i32"),
+        };
+
+        self.storage
+            .load_symbols(&mut self.code_builder, &[reused_sym]);
+        self.code_builder.set_local(ptr_local_id);
+
+        self.storage
+            .copy_value_to_memory(&mut self.code_builder, ptr_local_id, 0, arg_sym);
+    }
+
     fn expr_unbox(&mut self, ret_sym: Symbol, arg_sym: Symbol) {
         let (from_addr_val, from_offset) = match self.storage.get(&arg_sym) {
             StoredValue::VirtualMachineStack { .. } => {
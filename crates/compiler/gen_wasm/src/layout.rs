@@ -95,7 +95,8 @@ impl WasmLayout {
                 Recursive(_)
                 | NonNullableUnwrapped(_)
                 | NullableWrapped { .. }
-                | NullableUnwrapped { .. },
+                | NullableUnwrapped { .. }
+                | NullablePointer { .. },
             )
             | Layout::Boxed(_)
             | Layout::RecursivePointer => Self::Primitive(PTR_TYPE, PTR_SIZE),
@@ -9,7 +9,6 @@ mod storage;
 pub mod wasm32_result;
 pub mod wasm32_sized;
 
-use bitvec::prelude::BitVec;
 use bumpalo::collections::Vec;
 use bumpalo::{self, Bump};
 
@@ -20,7 +19,7 @@ use roc_mono::ir::{Proc, ProcLayout};
 use roc_mono::layout::{LayoutIds, STLayoutInterner};
 use roc_target::TargetInfo;
 use roc_wasm_module::parse::ParseError;
-use roc_wasm_module::{Align, LocalId, ValueType, WasmModule};
+use roc_wasm_module::{Align, BitSet, HostIndex, LocalId, ValueType, WasmModule};
 
 use crate::backend::{ProcLookupData, ProcSource, WasmBackend};
 use crate::code_builder::CodeBuilder;
@@ -47,6 +46,9 @@ pub struct Env<'a> {
     pub module_id: ModuleId,
     pub exposed_to_host: MutSet<Symbol>,
     pub stack_bytes: u32,
+    /// Where each `dbg` is in the source, like `Main.roc 12:5`, for the host to print.
+    /// A `dbg` that isn't in here gets the name of its module instead.
+    pub dbg_locations: MutMap<Symbol, String>,
 }
 
 impl Env<'_> {
@@ -60,6 +62,33 @@ pub fn parse_host<'a>(arena: &'a Bump, host_bytes: &[u8]) -> Result<WasmModule<'
     WasmModule::preload(arena, host_bytes, require_relocatable)
 }
 
+/// Parse the preprocessed host binary, using the index that `index_host` created for it.
+/// If the index is out of date, this is the same as `parse_host`.
+pub fn parse_indexed_host<'a>(
+    arena: &'a Bump,
+    host_bytes: &[u8],
+    index_bytes: &[u8],
+) -> Result<WasmModule<'a>, ParseError> {
+    let require_relocatable = true;
+    HostIndex::parse(arena, index_bytes)
+        .and_then(|index| {
+            WasmModule::preload_indexed(arena, host_bytes, require_relocatable, &index)
+        })
+        .or_else(|_| WasmModule::preload(arena, host_bytes, require_relocatable))
+}
+
+/// Index the preprocessed host binary once, so that `parse_indexed_host` can load it on
+/// every build without decoding its code and relocations item by item.
+pub fn index_host(host_bytes: &[u8]) -> Result<std::vec::Vec<u8>, ParseError> {
+    let arena = Bump::new();
+    let host_module = parse_host(&arena, host_bytes)?;
+    let index = HostIndex::new(host_bytes, &host_module, &arena);
+
+    let mut buffer = std::vec::Vec::new();
+    index.serialize(&mut buffer);
+    Ok(buffer)
+}
+
 /// Generate a Wasm module in binary form, ready to write to a file. Entry point from roc_build.
 ///   env            environment data from previous compiler stages
 ///   interns        names of functions and variables (as memory-efficient interned strings)
@@ -89,7 +118,7 @@ pub fn build_app_module<'a>(
     interns: &'a mut Interns,
     host_module: WasmModule<'a>,
     procedures: MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
-) -> (WasmModule<'a>, BitVec<usize>, u32) {
+) -> (WasmModule<'a>, BitSet, u32) {
     let mut layout_ids = LayoutIds::default();
     let mut procs = Vec::with_capacity_in(procedures.len(), env.arena);
     let mut proc_lookup = Vec::with_capacity_in(procedures.len() * 2, env.arena);
@@ -181,7 +210,9 @@ pub fn build_app_module<'a>(
             Roc => { /* already generated */ }
             Helper => backend.build_proc(helper_iter.next().unwrap()),
             HigherOrderMapper(inner_idx) => backend.build_higher_order_mapper(idx, *inner_idx),
-            HigherOrderCompare(inner_idx) => backend.build_higher_order_compare(idx, *inner_idx),
+            HigherOrderCompare(inner_idx, inc_fn_idx) => {
+                backend.build_higher_order_compare(idx, *inner_idx, *inc_fn_idx)
+            }
         }
     }
 
@@ -1868,7 +1868,7 @@ impl<'a> LowLevelCall<'a> {
                 StoredValue::StackMemory { .. } => { /* do nothing */ }
             },
 
-            Dbg => todo!("{:?}", self.lowlevel),
+            Dbg => internal_error!("Dbg should have been handled by WasmBackend::stmt_dbg"),
         }
     }
 
@@ -2114,6 +2114,7 @@ pub fn call_higher_order_lowlevel<'a>(
         argument_layouts,
         return_layout: result_layout,
         owns_captured_environment,
+        borrows_arguments,
         captured_environment,
         ..
     } = passed_function;
@@ -2211,7 +2212,15 @@ pub fn call_higher_order_lowlevel<'a>(
             })
             .unwrap();
         match op {
-            ListSortWith { .. } => ProcSource::HigherOrderCompare(passed_proc_index),
+            ListSortWith { xs } => {
+                // The refcount helpers have to be requested now, before they are generated
+                let elem_layout = *unwrap_list_elem_layout(backend.storage.symbol_layouts[xs]);
+                let inc_elem_fn_idx = (!borrows_arguments
+                    && elem_layout.contains_refcounted(backend.env.layout_interner))
+                .then(|| backend.get_refcount_fn_index(elem_layout, HelperOp::Inc));
+
+                ProcSource::HigherOrderCompare(passed_proc_index, inc_elem_fn_idx)
+            }
             ListMap { .. } | ListMap2 { .. } | ListMap3 { .. } | ListMap4 { .. } => {
                 ProcSource::HigherOrderMapper(passed_proc_index)
             }
@@ -2246,7 +2255,7 @@ pub fn call_higher_order_lowlevel<'a>(
                     captures_niche: fn_name.captures_niche(),
                 }
             }
-            ProcSource::HigherOrderCompare(_) => ProcLayout {
+            ProcSource::HigherOrderCompare(..) => ProcLayout {
                 arguments: wrapper_arg_layouts.into_bump_slice(),
                 result: *result_layout,
                 captures_niche: fn_name.captures_niche(),
@@ -0,0 +1,326 @@
+//! Evaluate top-level constants at compile time.
+//!
+//! A top-level constant like `answer = List.sum [1, 2, 3]` becomes a procedure without arguments,
+//! which compiled code calls every time the constant is used. When the interpreter can run such a
+//! procedure, its body is replaced by the value it produced:
+//!
+//! ```text
+//! procedure Test.0 () -> I64:
+//!     let Test.2 : I64 = 6i64;
+//!     ret Test.2;
+//! ```
+//!
+//! which [roc_mono]'s constant folding then propagates into the procedures that use it.
+//!
+//! Constants that crash, call a host function, do something observable like a `dbg`, or run for
+//! too long are left alone, and so are values that would make for a lot of code, or that aren't
+//! made of numbers, strings, records and lists.
+//!
+//! Like the other optimizations, this is skipped in development builds.
+
+use bumpalo::Bump;
+use roc_builtins::bitcode::IntWidth;
+use roc_module::symbol::{IdentIds, ModuleId, Symbol};
+use roc_mono::ir::{Expr, ListLiteralElement, Literal, OptLevel, Stmt};
+use roc_mono::layout::{Builtin, Layout};
+
+use crate::{Interpreter, Procedures, Value};
+
+/// How many statements a constant may run before we give up on evaluating it
+const STEP_LIMIT: usize = 100_000;
+
+/// How big (roughly, in literals) a constant's value may be, so that evaluating it doesn't
+/// turn a short computation into a lot of code
+const MAX_SIZE: usize = 256;
+
+pub fn evaluate_constants<'a>(
+    arena: &'a Bump,
+    home: ModuleId,
+    ident_ids: &mut IdentIds,
+    opt_level: OptLevel,
+    procs: &mut Procedures<'a>,
+) {
+    if let OptLevel::Development = opt_level {
+        return;
+    }
+
+    let mut evaluated = Vec::new();
+
+    for (key, proc) in procs.iter() {
+        if !proc.args.is_empty() || is_literal(&proc.body) {
+            continue;
+        }
+
+        let mut interpreter = Interpreter::for_constants(procs, STEP_LIMIT);
+
+        if let Ok(value) = interpreter.call_proc(proc, Vec::new()) {
+            let fits = size(&value, &proc.ret_layout).map_or(false, |size| size <= MAX_SIZE);
+
+            if fits {
+                evaluated.push((*key, value));
+            }
+        }
+    }
+
+    for (key, value) in evaluated {
+        let proc = procs.get_mut(&key).unwrap();
+        let mut env = Env {
+            arena,
+            home,
+            ident_ids,
+            lets: Vec::new(),
+        };
+
+        let symbol = env.bind(&value, &proc.ret_layout);
+
+        proc.body = env.into_stmt(symbol);
+    }
+}
+
+/// Whether the body is already just a literal, which constant folding takes care of
+fn is_literal(body: &Stmt<'_>) -> bool {
+    matches!(
+        body,
+        Stmt::Let(symbol, Expr::Literal(_), _, Stmt::Ret(ret)) if symbol == ret
+    )
+}
+
+/// How many literals it takes to write down the value, or `None` if it can't be
+fn size(value: &Value, layout: &Layout<'_>) -> Option<usize> {
+    match (value, layout) {
+        (Value::Struct(fields), Layout::Struct { field_layouts, .. })
+            if fields.len() == field_layouts.len() =>
+        {
+            fields
+                .iter()
+                .zip(field_layouts.iter())
+                .try_fold(1, |total, (field, layout)| {
+                    Some(total + size(field, layout)?)
+                })
+        }
+        (Value::List(elems), Layout::Builtin(Builtin::List(elem_layout))) => elems
+            .iter()
+            .try_fold(1, |total, elem| Some(total + size(elem, elem_layout)?)),
+        // A long string takes up about as much room as a literal per word of it
+        (Value::Str(string), Layout::Builtin(Builtin::Str)) => Some(1 + string.len() / 8),
+        _ => literal(&Bump::new(), value, layout).map(|_| 1),
+    }
+}
+
+/// The value as a literal of the given layout, if it's a number, bool or string
+fn literal<'a>(arena: &'a Bump, value: &Value, layout: &Layout<'_>) -> Option<Literal<'a>> {
+    let literal = match (value, layout) {
+        (Value::Int(n, _), Layout::Builtin(Builtin::Int(IntWidth::U128))) => {
+            Literal::U128((*n as u128).to_ne_bytes())
+        }
+        (Value::Int(n, _), Layout::Builtin(Builtin::Int(_))) => Literal::Int(n.to_ne_bytes()),
+        (Value::Float(n, _), Layout::Builtin(Builtin::Float(_))) => Literal::Float(*n),
+        (Value::Dec(n), Layout::Builtin(Builtin::Decimal)) => Literal::Decimal(n.to_ne_bytes()),
+        (Value::Bool(b), Layout::Builtin(Builtin::Bool)) => Literal::Bool(*b),
+        (Value::Str(string), Layout::Builtin(Builtin::Str)) => {
+            Literal::Str(arena.alloc_str(string))
+        }
+        _ => return None,
+    };
+
+    Some(literal)
+}
+
+struct Env<'a, 'i> {
+    arena: &'a Bump,
+    home: ModuleId,
+    ident_ids: &'i mut IdentIds,
+    /// The statements that build the value, in order
+    lets: Vec<(Symbol, Expr<'a>, Layout<'a>)>,
+}
+
+impl<'a, 'i> Env<'a, 'i> {
+    /// Bind the value to a new symbol. It must be one that [size] can write down.
+    fn bind(&mut self, value: &Value, layout: &Layout<'a>) -> Symbol {
+        let expr = match (value, layout) {
+            (Value::Struct(fields), Layout::Struct { field_layouts, .. }) => {
+                let symbols: Vec<_> = fields
+                    .iter()
+                    .zip(field_layouts.iter())
+                    .map(|(field, layout)| self.bind(field, layout))
+                    .collect();
+
+                Expr::Struct(self.arena.alloc_slice_copy(&symbols))
+            }
+            (Value::List(elems), _) if elems.is_empty() => Expr::EmptyArray,
+            (Value::List(elems), Layout::Builtin(Builtin::List(elem_layout))) => {
+                let elems: Vec<_> = elems
+                    .iter()
+                    .map(|elem| match literal(self.arena, elem, elem_layout) {
+                        Some(literal) => ListLiteralElement::Literal(literal),
+                        None => ListLiteralElement::Symbol(self.bind(elem, elem_layout)),
+                    })
+                    .collect();
+
+                Expr::Array {
+                    elem_layout: **elem_layout,
+                    elems: self.arena.alloc_slice_copy(&elems),
+                }
+            }
+            _ => match literal(self.arena, value, layout) {
+                Some(literal) => Expr::Literal(literal),
+                None => unreachable!("{:?} cannot be written down as a {:?}", value, layout),
+            },
+        };
+
+        let symbol = Symbol::new(self.home, self.ident_ids.gen_unique());
+
+        self.lets.push((symbol, expr, *layout));
+
+        symbol
+    }
+
+    fn into_stmt(self, result: Symbol) -> Stmt<'a> {
+        let mut stmt = Stmt::Ret(result);
+
+        for (symbol, expr, layout) in self.lets.into_iter().rev() {
+            stmt = Stmt::Let(symbol, expr, layout, self.arena.alloc(stmt));
+        }
+
+        stmt
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::evaluate_constants;
+    use bumpalo::Bump;
+    use roc_collections::MutMap;
+    use roc_intern::SingleThreadedInterner;
+    use roc_module::symbol::Interns;
+    use roc_mono::ir::text::{parse_procs, procs_to_text};
+    use roc_mono::ir::{OptLevel, ProcLayout};
+    use roc_mono::layout::STLayoutInterner;
+
+    /// Evaluate the constants in the procedures of `text`, and print the last one
+    fn evaluate(text: &str, opt_level: OptLevel) -> String {
+        let arena = Bump::new();
+        let mut interns = Interns::default();
+        let interner: STLayoutInterner = SingleThreadedInterner::with_capacity(4);
+
+        let procs = match parse_procs(&arena, &mut interns, text) {
+            Ok(procs) => procs,
+            Err(error) => panic!("{}", error),
+        };
+        let last = procs.last().unwrap().name.name();
+        let home = last.module_id();
+        let mut procs: MutMap<_, _> = procs
+            .into_iter()
+            .map(|proc| {
+                let arguments = arena.alloc_slice_fill_iter(proc.args.iter().map(|(l, _)| *l));
+                let proc_layout = ProcLayout::new(
+                    &arena,
+                    arguments,
+                    proc.name.captures_niche(),
+                    proc.ret_layout,
+                );
+
+                ((proc.name.name(), proc_layout), proc)
+            })
+            .collect();
+
+        let ident_ids = interns.all_ident_ids.get_or_insert(home);
+
+        evaluate_constants(&arena, home, ident_ids, opt_level, &mut procs);
+
+        let evaluated = procs.values().filter(|proc| proc.name.name() == last);
+
+        procs_to_text(&interns, &interner, evaluated)
+    }
+
+    #[test]
+    fn evaluate_arithmetic_through_calls() {
+        const TEXT: &str = r#"procedure Num.19 (Num.1 : I64, Num.2 : I64) -> I64:
+    let Num.3 : I64 = lowlevel NumMul Num.1 Num.2;
+    ret Num.3;
+
+procedure Test.0 () -> I64:
+    let Test.1 : I64 = 6i64;
+    let Test.2 : I64 = 7i64;
+    let Test.3 : I64 = CallByName Num.19 Test.1 Test.2;
+    ret Test.3;
+"#;
+
+        const EVALUATED: &str = r#"procedure Test.0 () -> I64:
+    let Test.4 : I64 = 42i64;
+    ret Test.4;
+"#;
+
+        assert_eq!(evaluate(TEXT, OptLevel::Normal), EVALUATED);
+    }
+
+    #[test]
+    fn evaluate_records_and_lists() {
+        const TEXT: &str = r#"procedure Test.0 () -> {List I64, Str}:
+    let Test.1 : I64 = 1i64;
+    let Test.2 : I64 = 2i64;
+    let Test.3 : List I64 = Array [Test.1, Test.2];
+    let Test.4 : Str = "a";
+    let Test.5 : Str = "b";
+    let Test.6 : Str = lowlevel StrConcat Test.4 Test.5;
+    let Test.7 : {List I64, Str} = Struct {Test.3, Test.6};
+    ret Test.7;
+"#;
+
+        const EVALUATED: &str = r#"procedure Test.0 () -> {List I64, Str}:
+    let Test.8 : List I64 = Array [1i64, 2i64];
+    let Test.9 : Str = "ab";
+    let Test.10 : {List I64, Str} = Struct {Test.8, Test.9};
+    ret Test.10;
+"#;
+
+        assert_eq!(evaluate(TEXT, OptLevel::Normal), EVALUATED);
+    }
+
+    #[test]
+    fn keep_constants_that_crash() {
+        const TEXT: &str = r#"procedure Test.0 () -> U8:
+    let Test.1 : U8 = 255i64;
+    let Test.2 : U8 = 1i64;
+    let Test.3 : U8 = lowlevel NumAdd Test.1 Test.2;
+    ret Test.3;
+"#;
+
+        assert_eq!(evaluate(TEXT, OptLevel::Normal), TEXT);
+    }
+
+    #[test]
+    fn keep_constants_with_dbg() {
+        const TEXT: &str = r#"procedure Test.0 () -> I64:
+    let Test.1 : I64 = 1i64;
+    let Test.2 : I64 = lowlevel Dbg Test.1 Test.1;
+    ret Test.2;
+"#;
+
+        assert_eq!(evaluate(TEXT, OptLevel::Normal), TEXT);
+    }
+
+    #[test]
+    fn keep_constants_that_loop_forever() {
+        const TEXT: &str = r#"procedure Test.0 () -> I64:
+    joinpoint Test.1 (Test.2 : I64):
+        jump Test.1 Test.2;
+    in
+    let Test.3 : I64 = 0i64;
+    jump Test.1 Test.3;
+"#;
+
+        assert_eq!(evaluate(TEXT, OptLevel::Normal), TEXT);
+    }
+
+    #[test]
+    fn skip_evaluation_in_development() {
+        const TEXT: &str = r#"procedure Test.0 () -> I64:
+    let Test.1 : I64 = 1i64;
+    let Test.2 : I64 = lowlevel NumAdd Test.1 Test.1;
+    ret Test.2;
+"#;
+
+        assert_eq!(evaluate(TEXT, OptLevel::Development), TEXT);
+    }
+}
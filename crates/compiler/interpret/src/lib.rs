@@ -0,0 +1,511 @@
+//! Runs Roc programs by interpreting their mono IR.
+//!
+//! The interpreter is much slower than compiled code, but it needs nothing beyond the
+//! procedures the compiler already produced, so it works wherever the compiler does: to
+//! evaluate top-level constants at compile time, to run the repl without LLVM, and to run
+//! programs on targets that no code generation backend supports yet.
+//!
+//! It can't call into a platform's host, so programs that do anything but compute a value
+//! fail with [Problem::Unsupported]. The same goes for the (few) builtins it doesn't implement.
+//!
+//! [evaluate_constants] is the compile-time part: it runs the top-level constants of an
+//! optimized build, and replaces their procedures with the values they produce.
+#![warn(clippy::dbg_macro)]
+// See github.com/roc-lang/roc/issues/800 for discussion of the large_enum_variant check.
+#![allow(clippy::large_enum_variant)]
+
+mod constants;
+mod low_level;
+mod value;
+
+pub use constants::evaluate_constants;
+pub use value::Value;
+
+use value::DEC_ONE;
+
+use roc_collections::MutMap;
+use roc_error_macros::internal_error;
+use roc_module::low_level::LowLevel;
+use roc_module::symbol::Symbol;
+use roc_mono::ir::{
+    Call, CallType, CrashTag, Expr, HigherOrderLowLevel, JoinPointId, ListLiteralElement, Literal,
+    Param, Proc, ProcLayout, Stmt,
+};
+use roc_mono::layout::{Builtin, Layout};
+
+/// How deeply Roc functions may call each other before we give up. Each call uses a few
+/// frames of the Rust stack, so run the interpreter on a thread with a big stack.
+pub const MAX_CALL_DEPTH: usize = 50_000;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    /// The program crashed, e.g. because it called `crash`, an integer overflowed, or it
+    /// looked up an element outside of a list
+    Crash { message: String, tag: CrashTag },
+    /// The calls nested deeper than [MAX_CALL_DEPTH]
+    StackOverflow,
+    /// The program ran more statements than the interpreter was allowed to
+    /// (see [Interpreter::for_constants])
+    StepLimit,
+    /// The program needs something the interpreter can't do, like calling a host function
+    Unsupported(String),
+}
+
+impl Problem {
+    pub(crate) fn crash(message: impl Into<String>) -> Self {
+        Problem::Crash {
+            message: message.into(),
+            tag: CrashTag::Roc,
+        }
+    }
+}
+
+impl std::fmt::Display for Problem {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::Crash { message, .. } => write!(f, "Roc crashed with:\n\n    {}", message),
+            Problem::StackOverflow => f.write_str("Roc ran out of stack space"),
+            Problem::StepLimit => f.write_str("Roc ran longer than the interpreter was allowed to"),
+            Problem::Unsupported(what) => write!(f, "The interpreter can't run {} yet", what),
+        }
+    }
+}
+
+pub type Procedures<'a> = MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>;
+
+pub struct Interpreter<'a, 'p> {
+    procedures: &'p Procedures<'a>,
+    depth: usize,
+    /// How many more statements may run, if there's a limit
+    steps_left: Option<usize>,
+    /// Whether the program is a constant being evaluated at compile time, which must not
+    /// skip over anything it would do at runtime that could be noticed, like a `dbg`
+    constant: bool,
+}
+
+/// The symbols and join points in scope in one call of a procedure. Mono IR never shadows
+/// a symbol within a procedure, so one map covers all of its nested scopes.
+#[derive(Default)]
+struct Frame<'a, 'p> {
+    values: MutMap<Symbol, Value>,
+    join_points: MutMap<JoinPointId, (&'p [Param<'a>], &'p Stmt<'a>)>,
+}
+
+impl<'a, 'p> Frame<'a, 'p> {
+    fn get(&self, symbol: Symbol) -> &Value {
+        match self.values.get(&symbol) {
+            Some(value) => value,
+            None => internal_error!("{:?} is not defined in this procedure", symbol),
+        }
+    }
+
+    fn get_all(&self, symbols: &[Symbol]) -> Vec<Value> {
+        symbols
+            .iter()
+            .map(|symbol| self.get(*symbol).clone())
+            .collect()
+    }
+}
+
+impl<'a, 'p> Interpreter<'a, 'p> {
+    pub fn new(procedures: &'p Procedures<'a>) -> Self {
+        Self {
+            procedures,
+            depth: 0,
+            steps_left: None,
+            constant: false,
+        }
+    }
+
+    /// An interpreter for evaluating constants at compile time. It stops with
+    /// [Problem::StepLimit] after running `step_limit` statements, so that a constant which
+    /// loops forever (or just for a long time) doesn't hang the compiler, and with
+    /// [Problem::Unsupported] at anything a constant does at runtime besides producing its
+    /// value, like a `dbg` or an `expect`.
+    pub fn for_constants(procedures: &'p Procedures<'a>, step_limit: usize) -> Self {
+        Self {
+            procedures,
+            depth: 0,
+            steps_left: Some(step_limit),
+            constant: true,
+        }
+    }
+
+    /// Evaluate a top-level value, i.e. a procedure that takes no arguments
+    pub fn eval_thunk(&mut self, symbol: Symbol) -> Result<Value, Problem> {
+        let procedures = self.procedures;
+        let proc = procedures
+            .iter()
+            .find(|((name, layout), _)| *name == symbol && layout.arguments.is_empty())
+            .map(|(_, proc)| proc);
+
+        match proc {
+            Some(proc) => self.call_proc(proc, Vec::new()),
+            None => internal_error!("there is no thunk for {:?}", symbol),
+        }
+    }
+
+    /// Call the specialization of the given function with the given layout
+    pub fn call(
+        &mut self,
+        name: Symbol,
+        layout: ProcLayout<'a>,
+        arguments: Vec<Value>,
+    ) -> Result<Value, Problem> {
+        let procedures = self.procedures;
+
+        match procedures.get(&(name, layout)) {
+            Some(proc) => self.call_proc(proc, arguments),
+            None => internal_error!("there is no specialization of {:?} at {:?}", name, layout),
+        }
+    }
+
+    pub(crate) fn call_proc(
+        &mut self,
+        proc: &'p Proc<'a>,
+        arguments: Vec<Value>,
+    ) -> Result<Value, Problem> {
+        debug_assert_eq!(proc.args.len(), arguments.len());
+
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(Problem::StackOverflow);
+        }
+
+        let mut frame = Frame::default();
+
+        for ((_, symbol), value) in proc.args.iter().zip(arguments) {
+            frame.values.insert(*symbol, value);
+        }
+
+        self.depth += 1;
+        let result = self.eval_stmt(&mut frame, &proc.body);
+        self.depth -= 1;
+
+        result
+    }
+
+    fn eval_stmt(
+        &mut self,
+        frame: &mut Frame<'a, 'p>,
+        mut stmt: &'p Stmt<'a>,
+    ) -> Result<Value, Problem> {
+        loop {
+            if let Some(steps_left) = &mut self.steps_left {
+                match steps_left.checked_sub(1) {
+                    Some(remaining) => *steps_left = remaining,
+                    None => return Err(Problem::StepLimit),
+                }
+            }
+
+            match stmt {
+                Stmt::Let(symbol, expr, layout, continuation) => {
+                    let value = self.eval_expr(frame, expr, layout)?;
+                    frame.values.insert(*symbol, value);
+
+                    stmt = continuation;
+                }
+                Stmt::Switch {
+                    cond_symbol,
+                    branches,
+                    default_branch,
+                    ..
+                } => {
+                    let cond = frame.get(*cond_symbol);
+                    let cond = match cond.as_switch_value() {
+                        Some(cond) => cond,
+                        None => internal_error!("cannot switch on {:?}", cond),
+                    };
+
+                    stmt = match branches.iter().find(|(value, _, _)| *value == cond) {
+                        Some((_, _, branch)) => branch,
+                        None => default_branch.1,
+                    };
+                }
+                Stmt::Ret(symbol) => {
+                    return Ok(frame.get(*symbol).clone());
+                }
+                Stmt::Refcounting(_, continuation) => {
+                    stmt = continuation;
+                }
+                Stmt::Expect { .. } | Stmt::ExpectFx { .. } if self.constant => {
+                    // `roc test` checks these at runtime, so the constant has to stay as it is
+                    return Err(Problem::Unsupported("an expect in a constant".to_string()));
+                }
+                Stmt::Expect { remainder, .. } | Stmt::ExpectFx { remainder, .. } => {
+                    // Like compiled code outside of `roc test`, don't check expectations
+                    stmt = remainder;
+                }
+                Stmt::Join {
+                    id,
+                    parameters,
+                    body,
+                    remainder,
+                } => {
+                    frame.join_points.insert(*id, (parameters, body));
+
+                    stmt = remainder;
+                }
+                Stmt::Jump(id, arguments) => {
+                    let (parameters, body) = match frame.join_points.get(id) {
+                        Some(join_point) => *join_point,
+                        None => internal_error!("join point {:?} is not defined", id),
+                    };
+
+                    let arguments = frame.get_all(arguments);
+
+                    for (param, value) in parameters.iter().zip(arguments) {
+                        frame.values.insert(param.symbol, value);
+                    }
+
+                    stmt = body;
+                }
+                Stmt::Crash(symbol, tag) => {
+                    let message = match frame.get(*symbol) {
+                        Value::Str(message) => message.to_string(),
+                        other => other.to_string(),
+                    };
+
+                    return Err(Problem::Crash { message, tag: *tag });
+                }
+            }
+        }
+    }
+
+    fn eval_expr(
+        &mut self,
+        frame: &Frame<'a, 'p>,
+        expr: &'p Expr<'a>,
+        layout: &Layout<'a>,
+    ) -> Result<Value, Problem> {
+        let value = match expr {
+            Expr::Literal(literal) => eval_literal(literal, layout),
+            Expr::Call(call) => return self.eval_call(frame, call, layout),
+            Expr::Tag {
+                tag_id, arguments, ..
+            }
+            | Expr::Reuse {
+                tag_id, arguments, ..
+            } => Value::Tag {
+                id: *tag_id,
+                fields: frame.get_all(arguments).into(),
+            },
+            Expr::Struct(fields) => Value::record(frame.get_all(fields)),
+            Expr::StructAtIndex {
+                index, structure, ..
+            } => match frame.get(*structure) {
+                Value::Struct(fields) => fields[*index as usize].clone(),
+                other => internal_error!("{:?} is not a struct", other),
+            },
+            Expr::GetTagId { structure, .. } => {
+                let tag_id = match frame.get(*structure) {
+                    Value::Tag { id, .. } => *id as i128,
+                    Value::Bool(b) => *b as i128,
+                    Value::Int(n, _) => *n,
+                    other => internal_error!("{:?} is not a tag union", other),
+                };
+
+                match layout {
+                    Layout::Builtin(Builtin::Bool) => Value::Bool(tag_id != 0),
+                    Layout::Builtin(Builtin::Int(width)) => Value::int(tag_id, *width),
+                    other => internal_error!("a tag id cannot have layout {:?}", other),
+                }
+            }
+            Expr::UnionAtIndex {
+                structure, index, ..
+            } => match frame.get(*structure) {
+                Value::Tag { fields, .. } => fields[*index as usize].clone(),
+                other => internal_error!("{:?} is not a tag union", other),
+            },
+            Expr::Array { elem_layout, elems } => Value::list(
+                elems
+                    .iter()
+                    .map(|elem| match elem {
+                        ListLiteralElement::Literal(literal) => eval_literal(literal, elem_layout),
+                        ListLiteralElement::Symbol(symbol) => frame.get(*symbol).clone(),
+                    })
+                    .collect(),
+            ),
+            Expr::EmptyArray => Value::list(Vec::new()),
+            Expr::ExprBox { symbol } | Expr::ReuseBox { value: symbol, .. } => {
+                Value::Box(frame.get(*symbol).clone().into())
+            }
+            Expr::ExprUnbox { symbol } => unbox(frame.get(*symbol)),
+            // Values are never updated in place, so there's nothing to reset or reuse.
+            Expr::Reset { symbol, .. } => frame.get(*symbol).clone(),
+            Expr::RuntimeErrorFunction(message) => return Err(Problem::crash(*message)),
+        };
+
+        Ok(value)
+    }
+
+    fn eval_call(
+        &mut self,
+        frame: &Frame<'a, 'p>,
+        call: &'p Call<'a>,
+        ret_layout: &Layout<'a>,
+    ) -> Result<Value, Problem> {
+        match &call.call_type {
+            CallType::ByName {
+                name,
+                ret_layout,
+                arg_layouts,
+                ..
+            } => {
+                let layout = ProcLayout {
+                    arguments: arg_layouts,
+                    result: **ret_layout,
+                    captures_niche: name.captures_niche(),
+                };
+
+                self.call(name.name(), layout, frame.get_all(call.arguments))
+            }
+            CallType::Foreign { foreign_symbol, .. } => Err(Problem::Unsupported(format!(
+                "the host function {}",
+                foreign_symbol.as_str()
+            ))),
+            CallType::LowLevel {
+                op: LowLevel::Dbg, ..
+            } if self.constant => Err(Problem::Unsupported("a dbg in a constant".to_string())),
+            CallType::LowLevel {
+                op: LowLevel::Dbg, ..
+            } => {
+                // The second argument smuggles a type variable for `roc test`, and isn't a value
+                Ok(frame.get(call.arguments[0]).clone())
+            }
+            CallType::LowLevel { op, .. } => {
+                low_level::call(*op, &frame.get_all(call.arguments), ret_layout)
+            }
+            CallType::HigherOrder(higher_order) => {
+                self.call_higher_order(frame, higher_order, call.arguments)
+            }
+        }
+    }
+
+    fn call_higher_order(
+        &mut self,
+        frame: &Frame<'a, 'p>,
+        higher_order: &'p HigherOrderLowLevel<'a>,
+        arguments: &[Symbol],
+    ) -> Result<Value, Problem> {
+        use roc_mono::low_level::HigherOrder::*;
+
+        let passed_function = &higher_order.passed_function;
+        let layout = ProcLayout {
+            arguments: passed_function.argument_layouts,
+            result: passed_function.return_layout,
+            captures_niche: passed_function.name.captures_niche(),
+        };
+
+        let procedures = self.procedures;
+        let proc = match procedures.get(&(passed_function.name.name(), layout)) {
+            Some(proc) => proc,
+            None => internal_error!("there is no specialization of {:?}", passed_function.name),
+        };
+
+        // The passed function only takes the captured environment if it captures anything
+        let captured = frame.get(passed_function.captured_environment);
+        let takes_captured = proc.args.len() > higher_order.op.function_arity();
+
+        let mut call_passed = |mut arguments: Vec<Value>| {
+            if takes_captured {
+                arguments.push(captured.clone());
+            }
+
+            self.call_proc(proc, arguments)
+        };
+
+        let lists = |symbols: &[Symbol]| -> Vec<std::rc::Rc<Vec<Value>>> {
+            symbols
+                .iter()
+                .map(|symbol| match frame.get(*symbol) {
+                    Value::List(elems) => elems.clone(),
+                    other => internal_error!("{:?} is not a list", other),
+                })
+                .collect()
+        };
+
+        let lists = match higher_order.op {
+            ListMap { xs } | ListSortWith { xs } => lists(&[xs]),
+            ListMap2 { xs, ys } => lists(&[xs, ys]),
+            ListMap3 { xs, ys, zs } => lists(&[xs, ys, zs]),
+            ListMap4 { xs, ys, zs, ws } => lists(&[xs, ys, zs, ws]),
+        };
+
+        debug_assert!(arguments.len() >= lists.len());
+
+        match higher_order.op {
+            ListSortWith { .. } => {
+                let mut elems = lists[0].as_ref().clone();
+                let mut problem = None;
+
+                elems.sort_by(|a, b| {
+                    if problem.is_some() {
+                        return std::cmp::Ordering::Equal;
+                    }
+
+                    match call_passed(vec![a.clone(), b.clone()]) {
+                        Ok(ordering) => low_level::to_ordering(&ordering),
+                        Err(err) => {
+                            problem = Some(err);
+                            std::cmp::Ordering::Equal
+                        }
+                    }
+                });
+
+                match problem {
+                    Some(problem) => Err(problem),
+                    None => Ok(Value::list(elems)),
+                }
+            }
+            ListMap { .. } | ListMap2 { .. } | ListMap3 { .. } | ListMap4 { .. } => {
+                // Like List.map2 etc. do, stop at the end of the shortest list
+                let len = lists.iter().map(|list| list.len()).min().unwrap_or(0);
+                let mut output = Vec::with_capacity(len);
+
+                for index in 0..len {
+                    let elems = lists.iter().map(|list| list[index].clone()).collect();
+
+                    output.push(call_passed(elems)?);
+                }
+
+                Ok(Value::list(output))
+            }
+        }
+    }
+}
+
+fn unbox(value: &Value) -> Value {
+    match value {
+        Value::Box(inner) => inner.as_ref().clone(),
+        other => internal_error!("{:?} is not a box", other),
+    }
+}
+
+fn eval_literal(literal: &Literal<'_>, layout: &Layout<'_>) -> Value {
+    use roc_builtins::bitcode::IntWidth;
+
+    match (literal, layout) {
+        (Literal::Int(bytes), Layout::Builtin(Builtin::Int(width))) => {
+            Value::int(i128::from_ne_bytes(*bytes), *width)
+        }
+        (Literal::Int(bytes), Layout::Builtin(Builtin::Float(width))) => {
+            Value::float(i128::from_ne_bytes(*bytes) as f64, *width)
+        }
+        (Literal::Int(bytes), Layout::Builtin(Builtin::Decimal)) => {
+            Value::Dec(i128::from_ne_bytes(*bytes) * DEC_ONE)
+        }
+        (Literal::Int(bytes), _) => Value::Int(i128::from_ne_bytes(*bytes), IntWidth::I64),
+        (Literal::U128(bytes), _) => {
+            Value::Int(u128::from_ne_bytes(*bytes) as i128, IntWidth::U128)
+        }
+        (Literal::Float(n), Layout::Builtin(Builtin::Float(width))) => Value::float(*n, *width),
+        (Literal::Float(n), Layout::Builtin(Builtin::Decimal)) => {
+            Value::Dec((*n * DEC_ONE as f64) as i128)
+        }
+        (Literal::Float(n), _) => Value::float(*n, roc_builtins::bitcode::FloatWidth::F64),
+        (Literal::Decimal(bytes), _) => Value::Dec(i128::from_ne_bytes(*bytes)),
+        (Literal::Str(s), _) => Value::str(s),
+        (Literal::Bool(b), _) => Value::Bool(*b),
+        (Literal::Byte(b), _) => Value::Int(*b as i128, IntWidth::U8),
+    }
+}
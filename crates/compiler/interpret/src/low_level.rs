@@ -0,0 +1,1176 @@
+use std::cmp::Ordering;
+use std::rc::Rc;
+
+use roc_builtins::bitcode::IntWidth;
+use roc_error_macros::internal_error;
+use roc_module::low_level::LowLevel;
+use roc_mono::layout::{Builtin, Layout};
+use roc_std::RocDec;
+use unicode_segmentation::UnicodeSegmentation;
+
+use crate::value::{fits_int, wrap_int, Value, DEC_ONE};
+use crate::Problem;
+
+/// Run a low-level operation on the given arguments. `ret_layout` is the layout of the
+/// result, which some operations (e.g. casts) need to know what to produce.
+pub(crate) fn call(
+    op: LowLevel,
+    args: &[Value],
+    ret_layout: &Layout<'_>,
+) -> Result<Value, Problem> {
+    use LowLevel::*;
+
+    let value = match op {
+        StrConcat => Value::str(&format!("{}{}", str_arg(args, 0), str_arg(args, 1))),
+        StrJoinWith => {
+            let strs: Vec<&str> = list_arg(args, 0).iter().map(as_str).collect();
+
+            Value::str(&strs.join(str_arg(args, 1)))
+        }
+        StrIsEmpty => Value::Bool(str_arg(args, 0).is_empty()),
+        StrStartsWith => Value::Bool(str_arg(args, 0).starts_with(str_arg(args, 1))),
+        StrStartsWithScalar => {
+            let first = str_arg(args, 0).chars().next();
+
+            Value::Bool(first.map(|c| c as i128) == Some(int_arg(args, 1)))
+        }
+        StrEndsWith => Value::Bool(str_arg(args, 0).ends_with(str_arg(args, 1))),
+        StrSplit => {
+            let (string, delimiter) = (str_arg(args, 0), str_arg(args, 1));
+
+            if delimiter.is_empty() {
+                Value::list(vec![Value::str(string)])
+            } else {
+                Value::list(string.split(delimiter).map(Value::str).collect())
+            }
+        }
+        StrCountGraphemes => nat(ret_layout, str_arg(args, 0).graphemes(true).count()),
+        StrCountUtf8Bytes | StrGetCapacity => nat(ret_layout, str_arg(args, 0).len()),
+        StrFromInt | StrFromFloat | NumToStr => Value::str(&num_to_str(&args[0])),
+        StrFromUtf8Range => {
+            let bytes = list_arg(args, 0);
+            let (start, count) = (index_arg(args, 1), index_arg(args, 2));
+            let bytes: Vec<u8> = bytes
+                .iter()
+                .skip(start)
+                .take(count)
+                .map(|byte| int_value(byte) as u8)
+                .collect();
+
+            from_utf8(&bytes, ret_layout)
+        }
+        StrToUtf8 => Value::list(
+            str_arg(args, 0)
+                .bytes()
+                .map(|byte| Value::Int(byte as i128, IntWidth::U8))
+                .collect(),
+        ),
+        StrRepeat => Value::str(&str_arg(args, 0).repeat(index_arg(args, 1))),
+        StrTrim => Value::str(str_arg(args, 0).trim()),
+        StrTrimLeft => Value::str(str_arg(args, 0).trim_start()),
+        StrTrimRight => Value::str(str_arg(args, 0).trim_end()),
+        StrToNum => str_to_num(str_arg(args, 0), ret_layout),
+        StrToScalars => Value::list(
+            str_arg(args, 0)
+                .chars()
+                .map(|c| Value::Int(c as i128, IntWidth::U32))
+                .collect(),
+        ),
+        StrGetUnsafe => {
+            let string = str_arg(args, 0);
+
+            match string.as_bytes().get(index_arg(args, 1)) {
+                Some(byte) => Value::Int(*byte as i128, IntWidth::U8),
+                None => return Err(out_of_bounds()),
+            }
+        }
+        StrSubstringUnsafe => {
+            let bytes = str_arg(args, 0).as_bytes();
+            let start = index_arg(args, 1).min(bytes.len());
+            let end = start.saturating_add(index_arg(args, 2)).min(bytes.len());
+
+            Value::str(&String::from_utf8_lossy(&bytes[start..end]))
+        }
+        StrReserve => args[0].clone(),
+        StrAppendScalar => match char::from_u32(int_arg(args, 1) as u32) {
+            Some(c) => {
+                let mut string = str_arg(args, 0).to_string();
+                string.push(c);
+
+                Value::str(&string)
+            }
+            None => return Err(Problem::crash("Str.appendScalar got an invalid scalar")),
+        },
+        StrGetScalarUnsafe => {
+            let string = str_arg(args, 0);
+            let c = string
+                .get(index_arg(args, 1)..)
+                .and_then(|rest| rest.chars().next());
+
+            match c {
+                // { bytesParsed : Nat, scalar : U32 }
+                Some(c) => Value::record(vec![
+                    nat(&field_layout(ret_layout, 0), c.len_utf8()),
+                    Value::Int(c as i128, IntWidth::U32),
+                ]),
+                None => return Err(out_of_bounds()),
+            }
+        }
+        StrWithCapacity => Value::str(""),
+        StrGraphemes => Value::list(str_arg(args, 0).graphemes(true).map(Value::str).collect()),
+
+        ListLen | ListGetCapacity => nat(ret_layout, list_arg(args, 0).len()),
+        ListWithCapacity => Value::list(Vec::new()),
+        ListReserve => args[0].clone(),
+        ListAppendUnsafe => {
+            let mut elems = list_arg(args, 0).as_ref().clone();
+            elems.push(args[1].clone());
+
+            Value::list(elems)
+        }
+        ListPrepend => {
+            let mut elems = Vec::with_capacity(list_arg(args, 0).len() + 1);
+            elems.push(args[1].clone());
+            elems.extend(list_arg(args, 0).iter().cloned());
+
+            Value::list(elems)
+        }
+        ListGetUnsafe => match list_arg(args, 0).get(index_arg(args, 1)) {
+            Some(elem) => elem.clone(),
+            None => return Err(out_of_bounds()),
+        },
+        ListReplaceUnsafe => {
+            let mut elems = list_arg(args, 0).as_ref().clone();
+            let index = index_arg(args, 1);
+
+            let old = match elems.get_mut(index) {
+                Some(elem) => std::mem::replace(elem, args[2].clone()),
+                None => return Err(out_of_bounds()),
+            };
+
+            // The result is a record of the list and the old element, and which of them comes
+            // first depends on their alignment.
+            let list_first = match ret_layout {
+                Layout::Struct { field_layouts, .. } => match field_layouts[0] {
+                    Layout::Builtin(Builtin::List(elem)) => *elem == field_layouts[1],
+                    _ => false,
+                },
+                other => internal_error!("List.replace cannot return {:?}", other),
+            };
+
+            if list_first {
+                Value::record(vec![Value::list(elems), old])
+            } else {
+                Value::record(vec![old, Value::list(elems)])
+            }
+        }
+        ListConcat => {
+            let mut elems = list_arg(args, 0).as_ref().clone();
+            elems.extend(list_arg(args, 1).iter().cloned());
+
+            Value::list(elems)
+        }
+        ListSublist => {
+            let elems = list_arg(args, 0);
+            let start = index_arg(args, 1).min(elems.len());
+            let end = start.saturating_add(index_arg(args, 2)).min(elems.len());
+
+            Value::list(elems[start..end].to_vec())
+        }
+        ListDropAt => {
+            let mut elems = list_arg(args, 0).as_ref().clone();
+            let index = index_arg(args, 1);
+
+            if index < elems.len() {
+                elems.remove(index);
+            }
+
+            Value::list(elems)
+        }
+        ListSwap => {
+            let mut elems = list_arg(args, 0).as_ref().clone();
+            let (i, j) = (index_arg(args, 1), index_arg(args, 2));
+
+            if i < elems.len() && j < elems.len() {
+                elems.swap(i, j);
+            }
+
+            Value::list(elems)
+        }
+        // Lists are never updated in place, so it doesn't matter whether they're unique
+        ListIsUnique => Value::Bool(true),
+        ListMap | ListMap2 | ListMap3 | ListMap4 | ListSortWith => {
+            internal_error!("{:?} is a higher-order lowlevel", op)
+        }
+
+        NumAdd => arith(Arith::Add, Overflow::Crash, &args[0], &args[1])?,
+        NumAddWrap => arith(Arith::Add, Overflow::Wrap, &args[0], &args[1])?,
+        NumAddChecked => arith(Arith::Add, Overflow::Report, &args[0], &args[1])?,
+        NumAddSaturated => arith(Arith::Add, Overflow::Saturate, &args[0], &args[1])?,
+        NumSub => arith(Arith::Sub, Overflow::Crash, &args[0], &args[1])?,
+        NumSubWrap => arith(Arith::Sub, Overflow::Wrap, &args[0], &args[1])?,
+        NumSubChecked => arith(Arith::Sub, Overflow::Report, &args[0], &args[1])?,
+        NumSubSaturated => arith(Arith::Sub, Overflow::Saturate, &args[0], &args[1])?,
+        NumMul => arith(Arith::Mul, Overflow::Crash, &args[0], &args[1])?,
+        NumMulWrap => arith(Arith::Mul, Overflow::Wrap, &args[0], &args[1])?,
+        NumMulChecked => arith(Arith::Mul, Overflow::Report, &args[0], &args[1])?,
+        NumMulSaturated => arith(Arith::Mul, Overflow::Saturate, &args[0], &args[1])?,
+        NumGt => Value::Bool(compare(&args[0], &args[1]) == Some(Ordering::Greater)),
+        NumGte => Value::Bool(matches!(
+            compare(&args[0], &args[1]),
+            Some(Ordering::Greater | Ordering::Equal)
+        )),
+        NumLt => Value::Bool(compare(&args[0], &args[1]) == Some(Ordering::Less)),
+        NumLte => Value::Bool(matches!(
+            compare(&args[0], &args[1]),
+            Some(Ordering::Less | Ordering::Equal)
+        )),
+        NumCompare => from_ordering(compare(&args[0], &args[1]).unwrap_or(Ordering::Equal)),
+        NumDivFrac => match (&args[0], &args[1]) {
+            (Value::Float(a, width), Value::Float(b, _)) => Value::float(a / b, *width),
+            (Value::Dec(a), Value::Dec(b)) => Value::Dec(dec_div(*a, *b)?),
+            (a, b) => mismatched(op, a, b),
+        },
+        NumDivTruncUnchecked => int_div(op, &args[0], &args[1], false)?,
+        NumDivCeilUnchecked => int_div(op, &args[0], &args[1], true)?,
+        NumRemUnchecked => match (&args[0], &args[1]) {
+            (Value::Int(_, _), Value::Int(0, _)) => {
+                return Err(Problem::crash("Integer division by 0!"));
+            }
+            (Value::Int(a, IntWidth::U128), Value::Int(b, _)) => {
+                Value::Int(((*a as u128) % (*b as u128)) as i128, IntWidth::U128)
+            }
+            (Value::Int(a, width), Value::Int(b, _)) => {
+                Value::Int(a.checked_rem(*b).unwrap_or(0), *width)
+            }
+            (a, b) => mismatched(op, a, b),
+        },
+        NumIsMultipleOf => match (&args[0], &args[1]) {
+            (Value::Int(a, _), Value::Int(0, _)) => Value::Bool(*a == 0),
+            (Value::Int(a, IntWidth::U128), Value::Int(b, _)) => {
+                Value::Bool((*a as u128) % (*b as u128) == 0)
+            }
+            (Value::Int(a, _), Value::Int(b, _)) => {
+                Value::Bool(a.checked_rem(*b).unwrap_or(0) == 0)
+            }
+            (a, b) => mismatched(op, a, b),
+        },
+        NumAbs => match &args[0] {
+            Value::Int(n, width) if !width.is_signed() => Value::Int(*n, *width),
+            Value::Int(n, width) => match n.checked_abs().filter(|n| fits_int(*n, *width)) {
+                Some(n) => Value::Int(n, *width),
+                None => return Err(minimum_value_overflowed("absolute value")),
+            },
+            Value::Float(n, width) => Value::float(n.abs(), *width),
+            Value::Dec(n) => match n.checked_abs() {
+                Some(n) => Value::Dec(n),
+                None => return Err(Problem::crash("Decimal absolute value overflowed")),
+            },
+            other => not_a_number(op, other),
+        },
+        NumNeg => match &args[0] {
+            Value::Int(0, width) => Value::Int(0, *width),
+            Value::Int(_, width) if !width.is_signed() => {
+                return Err(Problem::crash(
+                    "Integer negation of an unsigned number overflowed",
+                ));
+            }
+            Value::Int(n, width) => match n.checked_neg().filter(|n| fits_int(*n, *width)) {
+                Some(n) => Value::Int(n, *width),
+                None => return Err(minimum_value_overflowed("negation")),
+            },
+            Value::Float(n, width) => Value::float(-n, *width),
+            Value::Dec(n) => match n.checked_neg() {
+                Some(n) => Value::Dec(n),
+                None => return Err(Problem::crash("Decimal negation overflowed")),
+            },
+            other => not_a_number(op, other),
+        },
+        NumSin => float_fn(op, &args[0], f64::sin),
+        NumCos => float_fn(op, &args[0], f64::cos),
+        NumSqrtUnchecked => float_fn(op, &args[0], f64::sqrt),
+        NumLogUnchecked => float_fn(op, &args[0], f64::ln),
+        NumAtan => float_fn(op, &args[0], f64::atan),
+        NumAcos => float_fn(op, &args[0], f64::acos),
+        NumAsin => float_fn(op, &args[0], f64::asin),
+        NumPow => match (&args[0], &args[1]) {
+            (Value::Float(a, width), Value::Float(b, _)) => Value::float(a.powf(*b), *width),
+            (Value::Dec(a), Value::Dec(b)) => dec_from_f64(dec_to_f64(*a).powf(dec_to_f64(*b)))?,
+            (a, b) => mismatched(op, a, b),
+        },
+        NumRound => frac_to_int(op, &args[0], ret_layout, f64::round),
+        NumFloor => frac_to_int(op, &args[0], ret_layout, f64::floor),
+        NumCeiling => frac_to_int(op, &args[0], ret_layout, f64::ceil),
+        NumToFrac => match ret_layout {
+            Layout::Builtin(Builtin::Float(width)) => Value::float(to_f64(&args[0]), *width),
+            Layout::Builtin(Builtin::Decimal) => match &args[0] {
+                Value::Int(n, _) => match n.checked_mul(DEC_ONE) {
+                    Some(n) => Value::Dec(n),
+                    None => return Err(Problem::crash("Decimal conversion overflowed")),
+                },
+                Value::Dec(n) => Value::Dec(*n),
+                other => dec_from_f64(to_f64(other))?,
+            },
+            other => internal_error!("Num.toFrac cannot return {:?}", other),
+        },
+        NumPowInt => match (&args[0], &args[1]) {
+            (Value::Int(base, width), Value::Int(exponent, _)) => {
+                int_pow(*base, *exponent, *width)?
+            }
+            (a, b) => mismatched(op, a, b),
+        },
+        NumIsFinite => match &args[0] {
+            Value::Float(n, _) => Value::Bool(n.is_finite()),
+            Value::Dec(_) => Value::Bool(true),
+            other => not_a_number(op, other),
+        },
+        NumBytesToU16 => Value::Int(bytes_to_int(args, 2)?, IntWidth::U16),
+        NumBytesToU32 => Value::Int(bytes_to_int(args, 4)?, IntWidth::U32),
+        NumBitwiseAnd => int_bits(op, &args[0], &args[1], |a, b| a & b),
+        NumBitwiseXor => int_bits(op, &args[0], &args[1], |a, b| a ^ b),
+        NumBitwiseOr => int_bits(op, &args[0], &args[1], |a, b| a | b),
+        NumShiftLeftBy | NumShiftRightBy | NumShiftRightZfBy => match (&args[0], &args[1]) {
+            (Value::Int(n, width), Value::Int(amount, _)) => {
+                Value::Int(shift(op, *n, *width, *amount as u32), *width)
+            }
+            (a, b) => mismatched(op, a, b),
+        },
+        NumIntCast => match ret_layout {
+            Layout::Builtin(Builtin::Int(width)) => Value::int(int_arg(args, 0), *width),
+            other => internal_error!("Num.intCast cannot return {:?}", other),
+        },
+        NumToFloatCast => match ret_layout {
+            Layout::Builtin(Builtin::Float(width)) => Value::float(to_f64(&args[0]), *width),
+            other => internal_error!("a float cast cannot return {:?}", other),
+        },
+        NumToIntChecked | NumToFloatChecked | Hash | PtrCast => {
+            return Err(Problem::Unsupported(format!("the {:?} builtin", op)));
+        }
+
+        Eq => Value::Bool(args[0] == args[1]),
+        NotEq => Value::Bool(args[0] != args[1]),
+        And => Value::Bool(bool_arg(args, 0) && bool_arg(args, 1)),
+        Or => Value::Bool(bool_arg(args, 0) || bool_arg(args, 1)),
+        Not => Value::Bool(!bool_arg(args, 0)),
+
+        RefCountInc | RefCountDec => Value::unit(),
+        BoxExpr => Value::Box(Rc::new(args[0].clone())),
+        UnboxExpr => crate::unbox(&args[0]),
+        Dbg => args[0].clone(),
+        Unreachable => return Err(Problem::crash("reached unreachable code")),
+    };
+
+    Ok(value)
+}
+
+fn str_arg(args: &[Value], index: usize) -> &str {
+    as_str(&args[index])
+}
+
+fn as_str(value: &Value) -> &str {
+    match value {
+        Value::Str(s) => s,
+        other => internal_error!("{:?} is not a string", other),
+    }
+}
+
+fn list_arg(args: &[Value], index: usize) -> &Rc<Vec<Value>> {
+    match &args[index] {
+        Value::List(elems) => elems,
+        other => internal_error!("{:?} is not a list", other),
+    }
+}
+
+fn bool_arg(args: &[Value], index: usize) -> bool {
+    match &args[index] {
+        Value::Bool(b) => *b,
+        other => internal_error!("{:?} is not a Bool", other),
+    }
+}
+
+fn int_arg(args: &[Value], index: usize) -> i128 {
+    int_value(&args[index])
+}
+
+fn int_value(value: &Value) -> i128 {
+    match value {
+        Value::Int(n, _) => *n,
+        other => internal_error!("{:?} is not an integer", other),
+    }
+}
+
+/// An index or length. Ones that don't fit in a usize are out of bounds anyway.
+fn index_arg(args: &[Value], index: usize) -> usize {
+    usize::try_from(int_arg(args, index)).unwrap_or(usize::MAX)
+}
+
+/// A Nat with the width the layout says
+fn nat(layout: &Layout<'_>, n: usize) -> Value {
+    match layout {
+        Layout::Builtin(Builtin::Int(width)) => Value::int(n as i128, *width),
+        _ => Value::Int(n as i128, IntWidth::U64),
+    }
+}
+
+fn field_layout<'a>(layout: &Layout<'a>, index: usize) -> Layout<'a> {
+    match layout {
+        Layout::Struct { field_layouts, .. } => field_layouts[index],
+        other => internal_error!("{:?} is not a struct layout", other),
+    }
+}
+
+fn out_of_bounds() -> Problem {
+    Problem::crash("Index out of bounds")
+}
+
+fn minimum_value_overflowed(operation: &str) -> Problem {
+    Problem::crash(format!(
+        "Integer {} overflowed because its argument is the minimum value",
+        operation
+    ))
+}
+
+fn mismatched(op: LowLevel, a: &Value, b: &Value) -> ! {
+    internal_error!("{:?} cannot be called with {:?} and {:?}", op, a, b)
+}
+
+fn not_a_number(op: LowLevel, value: &Value) -> ! {
+    internal_error!("{:?} cannot be called with {:?}", op, value)
+}
+
+/// `Ordering` is `[EQ, GT, LT]`, so its tag ids are 0, 1 and 2
+fn from_ordering(ordering: Ordering) -> Value {
+    let id = match ordering {
+        Ordering::Equal => 0,
+        Ordering::Greater => 1,
+        Ordering::Less => 2,
+    };
+
+    Value::Int(id, IntWidth::U8)
+}
+
+pub(crate) fn to_ordering(value: &Value) -> Ordering {
+    match value.as_switch_value() {
+        Some(0) => Ordering::Equal,
+        Some(1) => Ordering::Greater,
+        Some(2) => Ordering::Less,
+        _ => internal_error!("{:?} is not an Ordering", value),
+    }
+}
+
+fn compare(a: &Value, b: &Value) -> Option<Ordering> {
+    match (a, b) {
+        (Value::Int(a, IntWidth::U128), Value::Int(b, _)) => Some((*a as u128).cmp(&(*b as u128))),
+        (Value::Int(a, _), Value::Int(b, _)) => Some(a.cmp(b)),
+        (Value::Float(a, _), Value::Float(b, _)) => a.partial_cmp(b),
+        (Value::Dec(a), Value::Dec(b)) => Some(a.cmp(b)),
+        (a, b) => internal_error!("cannot compare {:?} and {:?}", a, b),
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Arith {
+    Add,
+    Sub,
+    Mul,
+}
+
+/// What to do when arithmetic overflows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Overflow {
+    Crash,
+    Wrap,
+    Saturate,
+    /// Return the wrapped result along with whether it overflowed, like `Num.addChecked` needs
+    Report,
+}
+
+impl Arith {
+    fn name(self) -> &'static str {
+        match self {
+            Arith::Add => "addition",
+            Arith::Sub => "subtraction",
+            Arith::Mul => "multiplication",
+        }
+    }
+}
+
+fn arith(arith: Arith, overflow: Overflow, a: &Value, b: &Value) -> Result<Value, Problem> {
+    match (a, b) {
+        (Value::Int(a, width), Value::Int(b, _)) => {
+            let (a, b, width) = (*a, *b, *width);
+            let exact = exact_int_arith(arith, a, b, width);
+
+            // Two's complement makes this right for U128s stored as their bits, too
+            let wrapped = wrap_int(
+                match arith {
+                    Arith::Add => a.wrapping_add(b),
+                    Arith::Sub => a.wrapping_sub(b),
+                    Arith::Mul => a.wrapping_mul(b),
+                },
+                width,
+            );
+
+            match (overflow, exact) {
+                (Overflow::Report, _) => Ok(Value::record(vec![
+                    Value::Int(wrapped, width),
+                    Value::Bool(exact.is_none()),
+                ])),
+                (_, Some(n)) => Ok(Value::Int(n, width)),
+                (Overflow::Crash, None) => Err(Problem::crash(format!(
+                    "Integer {} overflowed!",
+                    arith.name()
+                ))),
+                (Overflow::Wrap, None) => Ok(Value::Int(wrapped, width)),
+                (Overflow::Saturate, None) => {
+                    Ok(Value::Int(saturating_int_arith(arith, a, b, width), width))
+                }
+            }
+        }
+        (Value::Float(a, width), Value::Float(b, _)) => {
+            let n = match arith {
+                Arith::Add => a + b,
+                Arith::Sub => a - b,
+                Arith::Mul => a * b,
+            };
+            let value = Value::float(n, *width);
+
+            if overflow == Overflow::Report {
+                let overflowed = !n.is_finite() && a.is_finite() && b.is_finite();
+
+                Ok(Value::record(vec![value, Value::Bool(overflowed)]))
+            } else {
+                Ok(value)
+            }
+        }
+        (Value::Dec(a), Value::Dec(b)) => {
+            let exact = match arith {
+                Arith::Add => a.checked_add(*b),
+                Arith::Sub => a.checked_sub(*b),
+                Arith::Mul => dec_mul(*a, *b),
+            };
+
+            match (overflow, exact) {
+                (Overflow::Report, _) => Ok(Value::record(vec![
+                    Value::Dec(exact.unwrap_or(0)),
+                    Value::Bool(exact.is_none()),
+                ])),
+                (_, Some(n)) => Ok(Value::Dec(n)),
+                (Overflow::Saturate, None) => {
+                    let positive = match arith {
+                        Arith::Add => *a > 0,
+                        Arith::Sub => *a > *b,
+                        Arith::Mul => (*a > 0) == (*b > 0),
+                    };
+
+                    Ok(Value::Dec(if positive { i128::MAX } else { i128::MIN }))
+                }
+                (Overflow::Crash | Overflow::Wrap, None) => Err(Problem::crash(format!(
+                    "Decimal {} overflowed!",
+                    arith.name()
+                ))),
+            }
+        }
+        (a, b) => internal_error!("cannot do {:?} with {:?} and {:?}", arith, a, b),
+    }
+}
+
+fn exact_int_arith(arith: Arith, a: i128, b: i128, width: IntWidth) -> Option<i128> {
+    if width == IntWidth::U128 {
+        let (a, b) = (a as u128, b as u128);
+
+        let n = match arith {
+            Arith::Add => a.checked_add(b),
+            Arith::Sub => a.checked_sub(b),
+            Arith::Mul => a.checked_mul(b),
+        }?;
+
+        Some(n as i128)
+    } else {
+        let n = match arith {
+            Arith::Add => a.checked_add(b),
+            Arith::Sub => a.checked_sub(b),
+            Arith::Mul => a.checked_mul(b),
+        }?;
+
+        fits_int(n, width).then_some(n)
+    }
+}
+
+fn saturating_int_arith(arith: Arith, a: i128, b: i128, width: IntWidth) -> i128 {
+    if width == IntWidth::U128 {
+        let (a, b) = (a as u128, b as u128);
+
+        let n = match arith {
+            Arith::Add => a.saturating_add(b),
+            Arith::Sub => a.saturating_sub(b),
+            Arith::Mul => a.saturating_mul(b),
+        };
+
+        n as i128
+    } else {
+        let n = match arith {
+            Arith::Add => a.saturating_add(b),
+            Arith::Sub => a.saturating_sub(b),
+            Arith::Mul => a.saturating_mul(b),
+        };
+
+        let (min, max) = int_bounds(width);
+
+        n.clamp(min, max)
+    }
+}
+
+/// The smallest and largest numbers of the width. Not meaningful for U128.
+fn int_bounds(width: IntWidth) -> (i128, i128) {
+    use IntWidth::*;
+
+    match width {
+        U8 => (0, u8::MAX as i128),
+        U16 => (0, u16::MAX as i128),
+        U32 => (0, u32::MAX as i128),
+        U64 => (0, u64::MAX as i128),
+        I8 => (i8::MIN as i128, i8::MAX as i128),
+        I16 => (i16::MIN as i128, i16::MAX as i128),
+        I32 => (i32::MIN as i128, i32::MAX as i128),
+        I64 => (i64::MIN as i128, i64::MAX as i128),
+        U128 | I128 => (i128::MIN, i128::MAX),
+    }
+}
+
+fn int_div(op: LowLevel, a: &Value, b: &Value, ceiling: bool) -> Result<Value, Problem> {
+    match (a, b) {
+        (Value::Int(_, _), Value::Int(0, _)) => Err(Problem::crash("Integer division by 0!")),
+        (Value::Int(a, IntWidth::U128), Value::Int(b, _)) => {
+            let (a, b) = (*a as u128, *b as u128);
+            let rounds_up = ceiling && a % b != 0;
+
+            Ok(Value::Int(
+                (a / b + rounds_up as u128) as i128,
+                IntWidth::U128,
+            ))
+        }
+        (Value::Int(a, width), Value::Int(b, _)) => {
+            let quotient = a.checked_div(*b).filter(|n| fits_int(*n, *width));
+
+            match quotient {
+                Some(quotient) => {
+                    // The quotient was rounded towards zero, so only positive ones need to
+                    // round up.
+                    let rounds_up = ceiling && a % b != 0 && (*a > 0) == (*b > 0);
+
+                    Ok(Value::Int(quotient + rounds_up as i128, *width))
+                }
+                None => Err(Problem::crash("Integer division overflowed!")),
+            }
+        }
+        (a, b) => mismatched(op, a, b),
+    }
+}
+
+fn int_pow(base: i128, exponent: i128, width: IntWidth) -> Result<Value, Problem> {
+    let overflowed = || Problem::crash("Integer raised to power overflowed!");
+
+    if width == IntWidth::U128 {
+        let exponent = u32::try_from(exponent as u128).map_err(|_| overflowed())?;
+
+        return match (base as u128).checked_pow(exponent) {
+            Some(n) => Ok(Value::Int(n as i128, width)),
+            None if base as u128 <= 1 => Ok(Value::Int(base, width)),
+            None => Err(overflowed()),
+        };
+    }
+
+    let n = match u32::try_from(exponent) {
+        Ok(exponent) => base.checked_pow(exponent),
+        Err(_) => match base {
+            0 | 1 => Some(base),
+            -1 => Some(if exponent % 2 == 0 { 1 } else { -1 }),
+            // A negative power of any other integer rounds to zero, and a huge one overflows
+            _ if exponent < 0 => Some(0),
+            _ => None,
+        },
+    };
+
+    match n.filter(|n| fits_int(*n, width)) {
+        Some(n) => Ok(Value::Int(n, width)),
+        None => Err(overflowed()),
+    }
+}
+
+fn int_bits(op: LowLevel, a: &Value, b: &Value, f: impl Fn(i128, i128) -> i128) -> Value {
+    match (a, b) {
+        (Value::Int(a, width), Value::Int(b, _)) => Value::int(f(*a, *b), *width),
+        (a, b) => mismatched(op, a, b),
+    }
+}
+
+fn shift(op: LowLevel, n: i128, width: IntWidth, amount: u32) -> i128 {
+    let bits = width.stack_size() * 8;
+
+    // The arithmetic shift treats the number as signed, and the logical one as unsigned,
+    // whatever its type says.
+    let as_signed = wrap_int(n, signed_width(width));
+    let as_unsigned = wrap_int(n, unsigned_width(width)) as u128;
+
+    let shifted = match op {
+        LowLevel::NumShiftLeftBy if amount >= bits => 0,
+        LowLevel::NumShiftLeftBy => n.wrapping_shl(amount),
+        LowLevel::NumShiftRightBy if amount >= bits => {
+            if as_signed < 0 {
+                -1
+            } else {
+                0
+            }
+        }
+        LowLevel::NumShiftRightBy => as_signed >> amount,
+        LowLevel::NumShiftRightZfBy if amount >= bits => 0,
+        LowLevel::NumShiftRightZfBy => (as_unsigned >> amount) as i128,
+        other => internal_error!("{:?} is not a shift", other),
+    };
+
+    wrap_int(shifted, width)
+}
+
+fn signed_width(width: IntWidth) -> IntWidth {
+    use IntWidth::*;
+
+    match width {
+        U8 | I8 => I8,
+        U16 | I16 => I16,
+        U32 | I32 => I32,
+        U64 | I64 => I64,
+        U128 | I128 => I128,
+    }
+}
+
+fn unsigned_width(width: IntWidth) -> IntWidth {
+    use IntWidth::*;
+
+    match width {
+        U8 | I8 => U8,
+        U16 | I16 => U16,
+        U32 | I32 => U32,
+        U64 | I64 => U64,
+        U128 | I128 => U128,
+    }
+}
+
+fn bytes_to_int(args: &[Value], count: usize) -> Result<i128, Problem> {
+    let bytes = list_arg(args, 0);
+    let start = index_arg(args, 1);
+
+    match bytes.get(start..start.saturating_add(count)) {
+        Some(bytes) => Ok(bytes
+            .iter()
+            .rev()
+            .fold(0, |n, byte| (n << 8) | int_value(byte))),
+        None => Err(out_of_bounds()),
+    }
+}
+
+fn to_f64(value: &Value) -> f64 {
+    match value {
+        Value::Int(n, IntWidth::U128) => *n as u128 as f64,
+        Value::Int(n, _) => *n as f64,
+        Value::Float(n, _) => *n,
+        Value::Dec(n) => dec_to_f64(*n),
+        other => internal_error!("{:?} is not a number", other),
+    }
+}
+
+fn float_fn(op: LowLevel, value: &Value, f: impl Fn(f64) -> f64) -> Value {
+    match value {
+        Value::Float(n, width) => Value::float(f(*n), *width),
+        Value::Dec(n) => Value::Dec((f(dec_to_f64(*n)) * DEC_ONE as f64) as i128),
+        other => not_a_number(op, other),
+    }
+}
+
+fn frac_to_int(
+    op: LowLevel,
+    value: &Value,
+    ret_layout: &Layout<'_>,
+    round: impl Fn(f64) -> f64,
+) -> Value {
+    let n = match value {
+        Value::Float(n, _) => round(*n),
+        Value::Dec(n) => round(dec_to_f64(*n)),
+        other => not_a_number(op, other),
+    };
+
+    match ret_layout {
+        Layout::Builtin(Builtin::Int(IntWidth::U128)) => {
+            Value::Int(n as u128 as i128, IntWidth::U128)
+        }
+        Layout::Builtin(Builtin::Int(width)) => Value::int(n as i128, *width),
+        other => internal_error!("{:?} cannot return {:?}", op, other),
+    }
+}
+
+fn dec_to_f64(n: i128) -> f64 {
+    n as f64 / DEC_ONE as f64
+}
+
+fn dec_from_f64(n: f64) -> Result<Value, Problem> {
+    let scaled = n * DEC_ONE as f64;
+
+    if scaled.is_finite() && scaled.abs() < i128::MAX as f64 {
+        Ok(Value::Dec(scaled as i128))
+    } else {
+        Err(Problem::crash("Decimal conversion overflowed"))
+    }
+}
+
+fn dec_mul(a: i128, b: i128) -> Option<i128> {
+    // Splitting both numbers into their integer and fractional parts keeps every
+    // intermediate product within an i128:
+    // a * b / ONE = ah * bh * ONE + ah * bl + al * bh + al * bl / ONE
+    let (ah, al) = (a / DEC_ONE, a % DEC_ONE);
+    let (bh, bl) = (b / DEC_ONE, b % DEC_ONE);
+
+    ah.checked_mul(bh)?
+        .checked_mul(DEC_ONE)?
+        .checked_add(ah.checked_mul(bl)?)?
+        .checked_add(al.checked_mul(bh)?)?
+        .checked_add(al * bl / DEC_ONE)
+}
+
+fn dec_div(a: i128, b: i128) -> Result<i128, Problem> {
+    if b == 0 {
+        return Err(Problem::crash("Decimal division by 0!"));
+    }
+
+    let overflowed = || Problem::crash("Decimal division overflowed!");
+    let negative = (a < 0) != (b < 0);
+    let (a, b) = (a.unsigned_abs(), b.unsigned_abs());
+
+    // Long division, one decimal place at a time
+    let mut quotient = (a / b)
+        .checked_mul(DEC_ONE as u128)
+        .ok_or_else(overflowed)?;
+    let mut remainder = a % b;
+    let mut place = DEC_ONE as u128 / 10;
+
+    while place > 0 && remainder > 0 {
+        remainder = remainder.checked_mul(10).ok_or_else(overflowed)?;
+        quotient += (remainder / b) * place;
+        remainder %= b;
+        place /= 10;
+    }
+
+    let quotient = i128::try_from(quotient).map_err(|_| overflowed())?;
+
+    Ok(if negative { -quotient } else { quotient })
+}
+
+fn num_to_str(value: &Value) -> String {
+    match value {
+        Value::Int(n, IntWidth::U128) => (*n as u128).to_string(),
+        Value::Int(n, _) => n.to_string(),
+        Value::Float(n, _) => n.to_string(),
+        Value::Dec(n) => RocDec::new(*n).to_string(),
+        other => internal_error!("{:?} is not a number", other),
+    }
+}
+
+/// `Str.toNum` returns `{ aresult : Num *, berrorcode : U8 }`
+fn str_to_num(string: &str, ret_layout: &Layout<'_>) -> Value {
+    let digits = string.replace('_', "");
+
+    let number = match field_layout(ret_layout, 0) {
+        Layout::Builtin(Builtin::Int(IntWidth::U128)) => digits
+            .parse::<u128>()
+            .ok()
+            .map(|n| Value::Int(n as i128, IntWidth::U128)),
+        Layout::Builtin(Builtin::Int(width)) => digits
+            .parse::<i128>()
+            .ok()
+            .filter(|n| fits_int(*n, width))
+            .map(|n| Value::Int(n, width)),
+        Layout::Builtin(Builtin::Float(width)) => {
+            string.parse::<f64>().ok().map(|n| Value::float(n, width))
+        }
+        Layout::Builtin(Builtin::Decimal) => {
+            RocDec::from_str(string).map(|dec| Value::Dec(i128::from_ne_bytes(dec.to_ne_bytes())))
+        }
+        other => internal_error!("Str.toNum cannot return {:?}", other),
+    };
+
+    match number {
+        Some(number) => Value::record(vec![number, Value::Int(0, IntWidth::U8)]),
+        None => Value::record(vec![
+            Value::Int(0, IntWidth::U8),
+            Value::Int(1, IntWidth::U8),
+        ]),
+    }
+}
+
+/// `Str.fromUtf8Range` returns
+/// `{ aByteIndex : Nat, bString : Str, cIsOk : Bool, dProblemCode : U8 }`
+fn from_utf8(bytes: &[u8], ret_layout: &Layout<'_>) -> Value {
+    let nat_layout = field_layout(ret_layout, 0);
+
+    match std::str::from_utf8(bytes) {
+        Ok(string) => Value::record(vec![
+            nat(&nat_layout, 0),
+            Value::str(string),
+            Value::Bool(true),
+            Value::Int(0, IntWidth::U8),
+        ]),
+        Err(err) => {
+            // The tag ids of Utf8ByteProblem; Rust doesn't tell us which of the others it was
+            const INVALID_START_BYTE: i128 = 3;
+            const UNEXPECTED_END_OF_SEQUENCE: i128 = 5;
+
+            let problem = match err.error_len() {
+                Some(_) => INVALID_START_BYTE,
+                None => UNEXPECTED_END_OF_SEQUENCE,
+            };
+
+            Value::record(vec![
+                nat(&nat_layout, err.valid_up_to()),
+                Value::str(""),
+                Value::Bool(false),
+                Value::Int(problem, IntWidth::U8),
+            ])
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_low_level {
+    use super::call;
+    use crate::value::{Value, DEC_ONE};
+    use crate::Problem;
+    use roc_builtins::bitcode::{FloatWidth, IntWidth};
+    use roc_module::low_level::LowLevel::{self, *};
+    use roc_mono::layout::{Builtin, Layout};
+
+    const I8: Layout = Layout::Builtin(Builtin::Int(IntWidth::I8));
+    const U8: Layout = Layout::Builtin(Builtin::Int(IntWidth::U8));
+    const I64: Layout = Layout::Builtin(Builtin::Int(IntWidth::I64));
+    const U64: Layout = Layout::Builtin(Builtin::Int(IntWidth::U64));
+    const F64: Layout = Layout::Builtin(Builtin::Float(FloatWidth::F64));
+    const DEC: Layout = Layout::Builtin(Builtin::Decimal);
+    const STR: Layout = Layout::Builtin(Builtin::Str);
+
+    fn int(n: i128, width: IntWidth) -> Value {
+        Value::Int(n, width)
+    }
+
+    fn u8(n: i128) -> Value {
+        int(n, IntWidth::U8)
+    }
+
+    fn i8(n: i128) -> Value {
+        int(n, IntWidth::I8)
+    }
+
+    fn i64(n: i128) -> Value {
+        int(n, IntWidth::I64)
+    }
+
+    fn dec(n: f64) -> Value {
+        Value::Dec((n * DEC_ONE as f64) as i128)
+    }
+
+    fn ok(op: LowLevel, args: &[Value], ret_layout: &Layout) -> Value {
+        match call(op, args, ret_layout) {
+            Ok(value) => value,
+            Err(problem) => panic!("{:?} failed with {:?}", op, problem),
+        }
+    }
+
+    fn crash(op: LowLevel, args: &[Value], ret_layout: &Layout) -> String {
+        match call(op, args, ret_layout) {
+            Err(Problem::Crash { message, .. }) => message,
+            other => panic!("{:?} should have crashed, but returned {:?}", op, other),
+        }
+    }
+
+    #[test]
+    fn strings() {
+        let hello = Value::str("Hello");
+        let world = Value::str(", World!");
+
+        assert_eq!(
+            ok(StrConcat, &[hello.clone(), world], &STR),
+            Value::str("Hello, World!")
+        );
+        assert_eq!(
+            ok(StrSplit, &[Value::str("a,b,,c"), Value::str(",")], &STR),
+            Value::list(vec![
+                Value::str("a"),
+                Value::str("b"),
+                Value::str(""),
+                Value::str("c")
+            ])
+        );
+        assert_eq!(
+            ok(StrSplit, &[hello.clone(), Value::str("")], &STR),
+            Value::list(vec![hello])
+        );
+        assert_eq!(
+            ok(StrCountGraphemes, &[Value::str("å🤔")], &U64),
+            int(2, IntWidth::U64)
+        );
+        assert_eq!(
+            crash(StrGetUnsafe, &[Value::str("ab"), i64(2)], &U8),
+            "Index out of bounds"
+        );
+    }
+
+    #[test]
+    fn lists() {
+        let list = Value::list(vec![i64(1), i64(2), i64(3)]);
+
+        assert_eq!(
+            ok(ListSublist, &[list.clone(), i64(1), i64(10)], &I64),
+            Value::list(vec![i64(2), i64(3)])
+        );
+        assert_eq!(ok(ListDropAt, &[list.clone(), i64(5)], &I64), list.clone());
+        assert_eq!(
+            ok(ListPrepend, &[list.clone(), i64(0)], &I64),
+            Value::list(vec![i64(0), i64(1), i64(2), i64(3)])
+        );
+        assert_eq!(ok(ListLen, &[list.clone()], &U64), int(3, IntWidth::U64));
+        assert_eq!(
+            crash(ListGetUnsafe, &[list, i64(3)], &I64),
+            "Index out of bounds"
+        );
+    }
+
+    #[test]
+    fn list_replace_puts_the_list_where_the_layout_says() {
+        let list = Value::list(vec![u8(1), u8(2)]);
+        let elem_layout = U8;
+        let list_layout = Layout::Builtin(Builtin::List(&elem_layout));
+
+        // The list is more aligned than the U8, so it comes first.
+        let field_layouts = [list_layout, U8];
+        let ret_layout = Layout::struct_no_name_order(&field_layouts);
+
+        assert_eq!(
+            ok(ListReplaceUnsafe, &[list, i64(0), u8(7)], &ret_layout),
+            Value::record(vec![Value::list(vec![u8(7), u8(2)]), u8(1)])
+        );
+    }
+
+    #[test]
+    fn integer_overflow() {
+        assert_eq!(
+            crash(NumAdd, &[u8(255), u8(1)], &U8),
+            "Integer addition overflowed!"
+        );
+        assert_eq!(ok(NumAddWrap, &[u8(255), u8(1)], &U8), u8(0));
+        assert_eq!(ok(NumAddSaturated, &[u8(255), u8(1)], &U8), u8(255));
+        assert_eq!(ok(NumSubSaturated, &[i8(-128), i8(1)], &I8), i8(-128));
+        assert_eq!(
+            ok(NumMulChecked, &[i8(64), i8(2)], &I8),
+            Value::record(vec![i8(-128), Value::Bool(true)])
+        );
+        assert_eq!(
+            ok(NumMulChecked, &[i8(63), i8(2)], &I8),
+            Value::record(vec![i8(126), Value::Bool(false)])
+        );
+        assert_eq!(
+            crash(NumAbs, &[i8(-128)], &I8),
+            "Integer absolute value overflowed because its argument is the minimum value"
+        );
+        assert_eq!(
+            crash(NumNeg, &[u8(1)], &U8),
+            "Integer negation of an unsigned number overflowed"
+        );
+    }
+
+    #[test]
+    fn integer_division() {
+        assert_eq!(ok(NumDivTruncUnchecked, &[i64(-7), i64(2)], &I64), i64(-3));
+        assert_eq!(ok(NumDivCeilUnchecked, &[i64(7), i64(2)], &I64), i64(4));
+        assert_eq!(ok(NumDivCeilUnchecked, &[i64(-7), i64(2)], &I64), i64(-3));
+        assert_eq!(ok(NumRemUnchecked, &[i64(-7), i64(2)], &I64), i64(-1));
+        assert_eq!(
+            crash(NumDivTruncUnchecked, &[i64(1), i64(0)], &I64),
+            "Integer division by 0!"
+        );
+        assert_eq!(
+            crash(NumDivTruncUnchecked, &[i8(-128), i8(-1)], &I8),
+            "Integer division overflowed!"
+        );
+        assert_eq!(ok(NumPowInt, &[i64(2), i64(10)], &I64), i64(1024));
+        assert_eq!(
+            crash(NumPowInt, &[u8(2), u8(8)], &U8),
+            "Integer raised to power overflowed!"
+        );
+    }
+
+    #[test]
+    fn shifts() {
+        assert_eq!(ok(NumShiftLeftBy, &[u8(0b1000_0001), u8(1)], &U8), u8(0b10));
+        assert_eq!(
+            ok(NumShiftRightBy, &[u8(0b1000_0000), u8(1)], &U8),
+            u8(0b1100_0000)
+        );
+        assert_eq!(ok(NumShiftRightZfBy, &[i8(-128), u8(1)], &I8), i8(64));
+        assert_eq!(ok(NumShiftRightBy, &[i8(-1), u8(100)], &I8), i8(-1));
+    }
+
+    #[test]
+    fn decimals() {
+        assert_eq!(ok(NumMul, &[dec(1.5), dec(-2.5)], &DEC), dec(-3.75));
+        assert_eq!(
+            ok(NumDivFrac, &[dec(1.0), dec(3.0)], &DEC),
+            Value::Dec(333_333_333_333_333_333)
+        );
+        assert_eq!(
+            crash(NumDivFrac, &[dec(1.0), dec(0.0)], &DEC),
+            "Decimal division by 0!"
+        );
+        assert_eq!(ok(NumToFrac, &[i64(3)], &DEC), dec(3.0));
+        assert_eq!(ok(NumToStr, &[dec(2.5)], &STR), Value::str("2.5"));
+    }
+
+    #[test]
+    fn casts_and_conversions() {
+        assert_eq!(ok(NumIntCast, &[i64(300)], &U8), u8(44));
+        assert_eq!(
+            ok(NumIntCast, &[i64(-1)], &U64),
+            int(u64::MAX as i128, IntWidth::U64)
+        );
+        assert_eq!(
+            ok(NumRound, &[Value::Float(2.5, FloatWidth::F64)], &I64),
+            i64(3)
+        );
+        assert_eq!(
+            ok(NumToFrac, &[i64(-2)], &F64),
+            Value::Float(-2.0, FloatWidth::F64)
+        );
+
+        let to_num_layouts = [I8, U8];
+        let to_num_layout = Layout::struct_no_name_order(&to_num_layouts);
+
+        assert_eq!(
+            ok(StrToNum, &[Value::str("-1_2")], &to_num_layout),
+            Value::record(vec![i8(-12), u8(0)])
+        );
+        assert_eq!(
+            ok(StrToNum, &[Value::str("128")], &to_num_layout),
+            Value::record(vec![u8(0), u8(1)])
+        );
+    }
+
+    #[test]
+    fn comparisons() {
+        let max_u128 = int(-1, IntWidth::U128);
+        let one_u128 = int(1, IntWidth::U128);
+
+        // U128s are stored as their bits, but still compare as unsigned numbers.
+        assert_eq!(ok(NumGt, &[max_u128, one_u128], &U8), Value::Bool(true));
+        assert_eq!(ok(NumCompare, &[i64(1), i64(2)], &U8), u8(2));
+        assert_eq!(
+            ok(Eq, &[Value::str("a"), Value::str("a")], &U8),
+            Value::Bool(true)
+        );
+    }
+}
@@ -0,0 +1,142 @@
+use std::rc::Rc;
+
+use roc_builtins::bitcode::{FloatWidth, IntWidth};
+use roc_mono::layout::TagIdIntType;
+
+/// 1 as a `Dec`
+pub(crate) const DEC_ONE: i128 = 1_000_000_000_000_000_000;
+
+/// A Roc value at runtime. Unlike the values a compiled program works with, these know what
+/// they are: numbers carry their width, and tags carry their tag id no matter how their
+/// union is laid out in memory, so the interpreter never needs layouts to take them apart.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    /// Every integer width fits in an i128. U128s are stored as their bits.
+    Int(i128, IntWidth),
+    Float(f64, FloatWidth),
+    /// The same representation as `RocDec`: the number times 10^18
+    Dec(i128),
+    Bool(bool),
+    Str(Rc<str>),
+    List(Rc<Vec<Value>>),
+    /// Also used for records, closure captures, and the empty struct
+    Struct(Rc<[Value]>),
+    Tag {
+        id: TagIdIntType,
+        fields: Rc<[Value]>,
+    },
+    Box(Rc<Value>),
+}
+
+impl Value {
+    /// The empty struct, e.g. what refcounting operations return
+    pub fn unit() -> Self {
+        Value::Struct(Rc::new([]))
+    }
+
+    pub fn str(s: &str) -> Self {
+        Value::Str(Rc::from(s))
+    }
+
+    pub fn list(elems: Vec<Value>) -> Self {
+        Value::List(Rc::new(elems))
+    }
+
+    pub fn record(fields: Vec<Value>) -> Self {
+        Value::Struct(Rc::from(fields))
+    }
+
+    /// Truncates the number to the given width, the way a cast between integers does
+    pub fn int(n: i128, width: IntWidth) -> Self {
+        Value::Int(wrap_int(n, width), width)
+    }
+
+    /// Rounds the number to the given width
+    pub fn float(n: f64, width: FloatWidth) -> Self {
+        match width {
+            FloatWidth::F32 => Value::Float(n as f32 as f64, width),
+            FloatWidth::F64 => Value::Float(n, width),
+        }
+    }
+
+    /// The value a `Switch` compares against its branches
+    pub fn as_switch_value(&self) -> Option<u64> {
+        match self {
+            Value::Int(n, _) => Some(*n as u64),
+            Value::Bool(b) => Some(*b as u64),
+            Value::Tag { id, fields } if fields.is_empty() => Some(*id as u64),
+            _ => None,
+        }
+    }
+}
+
+/// Truncate (and for signed widths, sign-extend) an integer to the given width
+pub(crate) fn wrap_int(n: i128, width: IntWidth) -> i128 {
+    use IntWidth::*;
+
+    match width {
+        U8 => n as u8 as i128,
+        U16 => n as u16 as i128,
+        U32 => n as u32 as i128,
+        U64 => n as u64 as i128,
+        I8 => n as i8 as i128,
+        I16 => n as i16 as i128,
+        I32 => n as i32 as i128,
+        I64 => n as i64 as i128,
+        U128 | I128 => n,
+    }
+}
+
+/// Whether the integer is within the width's range. U128s are stored as their bits, so
+/// every i128 is one.
+pub(crate) fn fits_int(n: i128, width: IntWidth) -> bool {
+    wrap_int(n, width) == n || width == IntWidth::U128
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Int(n, IntWidth::U128) => write!(f, "{}", *n as u128),
+            Value::Int(n, _) => write!(f, "{}", n),
+            Value::Float(n, _) => write!(f, "{}", n),
+            Value::Dec(n) => write!(f, "{}", roc_std::RocDec::new(*n)),
+            Value::Bool(b) => write!(f, "{}", if *b { "Bool.true" } else { "Bool.false" }),
+            Value::Str(s) => write!(f, "{:?}", s),
+            Value::List(elems) => {
+                f.write_str("[")?;
+                write_separated(f, elems.iter())?;
+                f.write_str("]")
+            }
+            Value::Struct(fields) => {
+                f.write_str("{ ")?;
+                write_separated(f, fields.iter())?;
+                f.write_str(" }")
+            }
+            Value::Tag { id, fields } => {
+                write!(f, "#{}", id)?;
+
+                for field in fields.iter() {
+                    write!(f, " ({})", field)?;
+                }
+
+                Ok(())
+            }
+            Value::Box(value) => write!(f, "Box.box ({})", value),
+        }
+    }
+}
+
+fn write_separated<'v>(
+    f: &mut std::fmt::Formatter<'_>,
+    values: impl Iterator<Item = &'v Value>,
+) -> std::fmt::Result {
+    for (index, value) in values.enumerate() {
+        if index > 0 {
+            f.write_str(", ")?;
+        }
+
+        write!(f, "{}", value)?;
+    }
+
+    Ok(())
+}
@@ -106,6 +106,8 @@ fn write_types_for_module_real(module_id: ModuleId, filename: &str, output_path:
         &module.interns,
         &mut module.can_problems,
         &mut module.type_problems,
+        &Default::default(),
+        None,
     );
 
     if problems.errors + problems.warnings > 0 {
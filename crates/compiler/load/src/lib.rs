@@ -21,6 +21,13 @@ pub use roc_load_internal::file::{
     EntryPoint, ExecutionMode, ExpectMetadata, Expectations, LoadConfig, LoadResult, LoadStart,
     LoadedModule, LoadingProblem, MonomorphizedModule, Phase, Threading,
 };
+pub use roc_load_internal::number_defaults;
+pub use roc_load_internal::query;
+pub use roc_load_internal::semantic_tokens;
+pub use roc_load_internal::signatures;
+pub use roc_load_internal::symbol_index;
+pub use roc_mono::ir::OptLevel;
+pub use roc_types::num::FloatWidth;
 
 #[allow(clippy::too_many_arguments)]
 fn load<'a>(
@@ -65,6 +72,8 @@ pub fn load_single_threaded<'a>(
         render,
         palette,
         exec_mode,
+        FloatWidth::F64,
+        OptLevel::Development,
         roc_cache_dir,
     )
 }
@@ -7,6 +7,9 @@ use roc_module::symbol::IdentIds;
 use roc_parse::ast::AssignedField;
 use roc_parse::ast::{self, ExtractSpaces, TypeHeader};
 use roc_parse::ast::{CommentOrNewline, TypeDef, ValueDef};
+use roc_parse::parser::Parser;
+use roc_parse::state::State;
+use roc_region::all::Loc;
 
 // Documentation generation requirements
 
@@ -63,6 +66,19 @@ pub enum TypeAnnotation {
     Ability {
         members: Vec<AbilityMember>,
     },
+    /// e.g. `a -> Str | a has Inspect`
+    Where {
+        ann: Box<TypeAnnotation>,
+        has_clauses: Vec<(String, Vec<TypeAnnotation>)>,
+    },
+    /// e.g. `[Cons a (ConsList a), Nil] as ConsList a`
+    As {
+        ann: Box<TypeAnnotation>,
+        name: String,
+        vars: Vec<String>,
+    },
+    /// The `_` type, which asks for part of an annotation to be inferred
+    Inferred,
     Wildcard,
     NoTypeAnn,
 }
@@ -198,7 +214,26 @@ fn generate_entry_docs<'a>(
                     }
                 }
 
-                ValueDef::Body(_, _) => (),
+                ValueDef::Body(loc_pattern, _) => {
+                    if let Pattern::Identifier(identifier) = loc_pattern.value {
+                        // Check if the definition is exposed, and that it wasn't annotated
+                        // separately (those already got an entry from their annotation)
+                        let is_annotated = acc.iter().any(|entry| {
+                            matches!(entry, DocEntry::DocDef(doc_def) if doc_def.name == identifier)
+                        });
+
+                        if ident_ids.get_id(identifier).is_some() && !is_annotated {
+                            // Docs generation can fill in the inferred type later on
+                            let doc_def = DocDef {
+                                name: identifier.to_string(),
+                                type_annotation: TypeAnnotation::NoTypeAnn,
+                                type_vars: Vec::new(),
+                                docs,
+                            };
+                            acc.push(DocEntry::DocDef(doc_def));
+                        }
+                    }
+                }
 
                 ValueDef::Dbg { .. } => {
 
@@ -309,6 +344,20 @@ fn generate_entry_docs<'a>(
     acc
 }
 
+/// Docs for a type annotation given as source code, e.g. a signature roc inferred
+/// for a definition that doesn't have one.
+pub fn type_annotation_from_str(src: &str) -> Option<TypeAnnotation> {
+    let arena = bumpalo::Bump::new();
+    let src = src.trim();
+
+    match roc_parse::type_annotation::located(false).parse(&arena, State::new(src.as_bytes()), 0) {
+        Ok((_, loc_ann, state)) if state.pos().offset as usize == src.len() => {
+            Some(type_to_docs(false, loc_ann.value))
+        }
+        _ => None,
+    }
+}
+
 fn type_to_docs(in_func_type_ann: bool, type_annotation: ast::TypeAnnotation) -> TypeAnnotation {
     match type_annotation {
         ast::TypeAnnotation::TagUnion { tags, ext } => {
@@ -385,32 +434,51 @@ fn type_to_docs(in_func_type_ann: bool, type_annotation: ast::TypeAnnotation) ->
                 output: Box::new(type_to_docs(true, output_ann.value)),
             }
         }
+        ast::TypeAnnotation::Where(ta, has_clauses) => TypeAnnotation::Where {
+            ann: Box::new(type_to_docs(in_func_type_ann, ta.value)),
+            has_clauses: has_clauses_to_docs(has_clauses),
+        },
+        ast::TypeAnnotation::As(ta, _, TypeHeader { name, vars }) => TypeAnnotation::As {
+            ann: Box::new(type_to_docs(in_func_type_ann, ta.value)),
+            name: name.value.to_string(),
+            vars: vars
+                .iter()
+                .filter_map(|var| match var.value {
+                    ast::Pattern::Identifier(ident_name) => Some(ident_name.to_string()),
+                    _ => None,
+                })
+                .collect(),
+        },
+        ast::TypeAnnotation::Inferred => TypeAnnotation::Inferred,
         ast::TypeAnnotation::Wildcard => TypeAnnotation::Wildcard,
         _ => NoTypeAnn,
     }
 }
 
+fn has_clauses_to_docs(has_clauses: &[Loc<ast::HasClause>]) -> Vec<(String, Vec<TypeAnnotation>)> {
+    has_clauses
+        .iter()
+        .map(|hc| {
+            let ast::HasClause { var, abilities } = hc.value;
+            (
+                var.value.extract_spaces().item.to_string(),
+                abilities
+                    .iter()
+                    .map(|ability| type_to_docs(false, ability.value))
+                    .collect(),
+            )
+        })
+        .collect()
+}
+
 fn ability_member_type_to_docs(
     type_annotation: ast::TypeAnnotation,
 ) -> (TypeAnnotation, Vec<(String, Vec<TypeAnnotation>)>) {
     match type_annotation {
         ast::TypeAnnotation::Where(ta, has_clauses) => {
             let ta = type_to_docs(false, ta.value);
-            let has_clauses = has_clauses
-                .iter()
-                .map(|hc| {
-                    let ast::HasClause { var, abilities } = hc.value;
-                    (
-                        var.value.extract_spaces().item.to_string(),
-                        abilities
-                            .iter()
-                            .map(|ability| type_to_docs(false, ability.value))
-                            .collect(),
-                    )
-                })
-                .collect();
 
-            (ta, has_clauses)
+            (ta, has_clauses_to_docs(has_clauses))
         }
         _ => (type_to_docs(false, type_annotation), vec![]),
     }
@@ -1,6 +1,7 @@
 #![allow(clippy::too_many_arguments)]
 
 use crate::docs::ModuleDocumentation;
+use crate::number_defaults::number_defaults;
 use bumpalo::Bump;
 use crossbeam::channel::{bounded, Sender};
 use crossbeam::deque::{Injector, Stealer, Worker};
@@ -14,6 +15,7 @@ use roc_can::module::{
     canonicalize_module_defs, ExposedByModule, ExposedForModule, ExposedModuleTypes, Module,
     ResolvedImplementations, TypeState,
 };
+use roc_can::usages::SymbolUsages;
 use roc_collections::{default_hasher, BumpMap, MutMap, MutSet, VecMap, VecSet};
 use roc_constrain::module::constrain_module;
 use roc_debug_flags::dbg_do;
@@ -32,15 +34,19 @@ use roc_module::symbol::{
     PackageQualified, Symbol,
 };
 use roc_mono::ir::{
-    CapturedSymbols, ExternalSpecializations, PartialProc, Proc, ProcLayout, Procs, ProcsBase,
-    UpdateModeIds,
+    CapturedSymbols, ExternalSpecializations, OptLevel, PartialProc, Proc, ProcLayout, Procs,
+    ProcsBase, UpdateModeIds,
 };
 use roc_mono::layout::{
     CapturesNiche, LambdaName, Layout, LayoutCache, LayoutProblem, STLayoutInterner,
 };
 use roc_packaging::cache::{self, RocCacheDir};
 #[cfg(not(target_family = "wasm"))]
+use roc_packaging::git::GitPackage;
+#[cfg(not(target_family = "wasm"))]
 use roc_packaging::https::PackageMetadata;
+#[cfg(not(target_family = "wasm"))]
+use roc_packaging::lockfile::Lockfile;
 use roc_parse::ast::{self, Defs, ExtractSpaces, Spaced, StrLiteral, TypeAnnotation};
 use roc_parse::header::{
     ExposedName, ImportsEntry, PackageEntry, PackageHeader, PlatformHeader, To, TypedIdent,
@@ -50,10 +56,12 @@ use roc_parse::module::module_defs;
 use roc_parse::parser::{FileError, Parser, SourceError, SyntaxError};
 use roc_problem::Severity;
 use roc_region::all::{LineInfo, Loc, Region};
+use roc_reporting::codes;
 use roc_reporting::report::{Annotation, Palette, RenderTarget};
 use roc_solve::module::{extract_module_owned_implementations, Solved, SolvedModule};
 use roc_solve_problem::TypeError;
 use roc_target::TargetInfo;
+use roc_types::num::FloatWidth;
 use roc_types::subs::{CopiedImport, ExposedTypesStorageSubs, Subs, VarStore, Variable};
 use roc_types::types::{Alias, Types};
 use std::collections::hash_map::Entry::{Occupied, Vacant};
@@ -67,6 +75,8 @@ use std::str::from_utf8_unchecked;
 use std::sync::Arc;
 use std::{env, fs};
 
+use crate::memory::{format_bytes, MemoryAccounting};
+use crate::type_cache::TypeCache;
 pub use crate::work::Phase;
 use crate::work::{DepCycle, Dependencies};
 
@@ -90,13 +100,17 @@ macro_rules! log {
     ($($arg:tt)*) => (dbg_do!(ROC_PRINT_LOAD_LOG, println!($($arg)*)))
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct LoadConfig {
     pub target_info: TargetInfo,
     pub render: RenderTarget,
     pub palette: Palette,
     pub threading: Threading,
     pub exec_mode: ExecutionMode,
+    /// What fractional number literals compile to when nothing determines their type
+    pub frac_default: FloatWidth,
+    /// Decides how much the mono IR is optimized, e.g. how large an inlined procedure may be
+    pub opt_level: OptLevel,
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -145,10 +159,26 @@ struct ModuleCache<'a> {
     imports: MutMap<ModuleId, MutSet<ModuleId>>,
     top_level_thunks: MutMap<ModuleId, MutSet<Symbol>>,
     documentation: MutMap<ModuleId, ModuleDocumentation>,
+    usages: MutMap<ModuleId, SymbolUsages>,
     can_problems: MutMap<ModuleId, Vec<roc_problem::can::Problem>>,
     type_problems: MutMap<ModuleId, Vec<TypeError>>,
 
     sources: MutMap<ModuleId, (PathBuf, &'a str)>,
+
+    /// Where each top-level definition is in its module's source
+    def_regions: MutMap<Symbol, Region>,
+
+    /// Where each module's header imports its dependencies; used to explain import cycles
+    import_sites: MutMap<ModuleId, ImportSites<'a>>,
+
+    /// What each interface module's solved types are cached under; see `TypeCache::key`
+    type_cache_keys: MutMap<ModuleId, u64>,
+}
+
+#[derive(Debug)]
+struct ImportSites<'a> {
+    src: &'a [u8],
+    regions: MutMap<ModuleId, Region>,
 }
 
 impl<'a> ModuleCache<'a> {
@@ -213,9 +243,12 @@ impl Default for ModuleCache<'_> {
             imports: Default::default(),
             top_level_thunks: Default::default(),
             documentation: Default::default(),
+            usages: Default::default(),
             can_problems: Default::default(),
             type_problems: Default::default(),
             sources: Default::default(),
+            def_regions: Default::default(),
+            import_sites: Default::default(),
         }
     }
 }
@@ -370,6 +403,25 @@ fn start_phase<'a>(
                     }
                 }
 
+                if let (Some(type_cache), HeaderType::Interface { .. }) =
+                    (&state.type_cache, &parsed.header_type)
+                {
+                    let key = type_cache.key(
+                        module_id,
+                        parsed.src,
+                        parsed.imported_modules.keys().copied(),
+                        &state.module_cache.type_cache_keys,
+                    );
+
+                    if let Some(key) = key {
+                        state.module_cache.type_cache_keys.insert(module_id, key);
+
+                        if let Some(type_state) = type_cache.read(key) {
+                            state.cached_types.lock().insert(module_id, type_state);
+                        }
+                    }
+                }
+
                 let skip_constraint_gen = {
                     // Give this its own scope to make sure that the Guard from the lock() is dropped
                     // immediately after contains_key returns
@@ -407,6 +459,12 @@ fn start_phase<'a>(
 
                 let derived_module = SharedDerivedModule::clone(&state.derived_module);
 
+                let type_cache = state.type_cache.clone().and_then(|type_cache| {
+                    let key = state.module_cache.type_cache_keys.get(&module_id)?;
+
+                    Some((type_cache, *key))
+                });
+
                 BuildTask::solve_module(
                     module,
                     ident_ids,
@@ -421,6 +479,7 @@ fn start_phase<'a>(
                     dep_idents,
                     declarations,
                     state.cached_types.clone(),
+                    type_cache,
                     derived_module,
                 )
             }
@@ -508,7 +567,7 @@ fn start_phase<'a>(
                         Subs::default(),
                         None, // no expectations for derived module
                         ProcsBase::default(),
-                        LayoutCache::new(state.layout_interner.fork(), state.target_info),
+                        state.new_layout_cache(),
                         ModuleTiming::new(Instant::now()),
                     )
                 } else if state.make_specializations_pass.current_pass() == 1 {
@@ -630,6 +689,8 @@ pub struct LoadedModule {
     pub sources: MutMap<ModuleId, (PathBuf, Box<str>)>,
     pub timings: MutMap<ModuleId, ModuleTiming>,
     pub documentation: MutMap<ModuleId, ModuleDocumentation>,
+    /// Where each symbol is used, by the module it is used in
+    pub usages: MutMap<ModuleId, SymbolUsages>,
     pub abilities_store: AbilitiesStore,
 }
 
@@ -749,9 +810,13 @@ pub struct MonomorphizedModule<'a> {
     pub entry_point: EntryPoint<'a>,
     pub exposed_to_host: ExposedToHost,
     pub sources: MutMap<ModuleId, (PathBuf, Box<str>)>,
+    /// Where each top-level definition is in its module's source, used to emit debug info
+    pub def_regions: MutMap<Symbol, Region>,
     pub timings: MutMap<ModuleId, ModuleTiming>,
     pub expectations: VecMap<ModuleId, Expectations>,
     pub uses_prebuilt_platform: bool,
+    /// The platform's main module, if there is a platform
+    pub platform_path: Option<PathBuf>,
 }
 
 /// Values used to render expect output
@@ -777,6 +842,8 @@ pub struct Expectations {
     pub expectations: VecMap<Region, Vec<ExpectLookup>>,
     pub dbgs: VecMap<Symbol, DbgLookup>,
     pub ident_ids: IdentIds,
+    /// What fractional literals were compiled to, so the values in a failure are read back with it
+    pub frac_default: FloatWidth,
 }
 
 #[derive(Clone, Debug, Default)]
@@ -869,6 +936,8 @@ enum Msg<'a> {
         external_specializations_requested: BumpMap<ModuleId, ExternalSpecializations<'a>>,
         procs_base: ProcsBase<'a>,
         procedures: MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+        /// Tail calls that could not be made into jumps
+        tail_call_problems: Vec<roc_problem::can::Problem>,
         update_mode_ids: UpdateModeIds,
         module_timing: ModuleTiming,
         subs: Subs,
@@ -901,6 +970,7 @@ struct CanAndCon {
     constrained_module: ConstrainedModule,
     canonicalization_problems: Vec<roc_problem::can::Problem>,
     module_docs: Option<ModuleDocumentation>,
+    usages: SymbolUsages,
 }
 
 #[derive(Debug)]
@@ -990,14 +1060,17 @@ struct State<'a> {
     pub render: RenderTarget,
     pub palette: Palette,
     pub exec_mode: ExecutionMode,
+    pub frac_default: FloatWidth,
+    pub opt_level: OptLevel,
 
     /// All abilities across all modules.
     pub world_abilities: WorldAbilities,
 
     make_specializations_pass: MakeSpecializationsPass,
 
-    // cached types (used for builtin modules, could include packages in the future too)
+    // cached types (used for builtin modules, and for interface modules we've checked before)
     cached_types: CachedTypeState,
+    type_cache: Option<TypeCache>,
 
     layout_interner: Arc<GlobalInterner<'a, Layout<'a>>>,
 }
@@ -1009,6 +1082,13 @@ impl<'a> State<'a> {
         self.exec_mode.goal_phase()
     }
 
+    fn new_layout_cache(&self) -> LayoutCache<'a> {
+        let mut layout_cache = LayoutCache::new(self.layout_interner.fork(), self.target_info);
+        layout_cache.frac_default = self.frac_default;
+
+        layout_cache
+    }
+
     fn new(
         root_id: ModuleId,
         opt_platform_shorthand: Option<&'a str>,
@@ -1017,10 +1097,13 @@ impl<'a> State<'a> {
         arc_modules: Arc<Mutex<PackageModuleIds<'a>>>,
         ident_ids_by_module: SharedIdentIdsByModule,
         cached_types: MutMap<ModuleId, TypeState>,
+        type_cache: Option<TypeCache>,
         render: RenderTarget,
         palette: Palette,
         number_of_workers: usize,
         exec_mode: ExecutionMode,
+        frac_default: FloatWidth,
+        opt_level: OptLevel,
     ) -> Self {
         let arc_shorthands = Arc::new(Mutex::new(MutMap::default()));
         let cache_dir = roc_packaging::cache::roc_cache_dir();
@@ -1051,9 +1134,12 @@ impl<'a> State<'a> {
             timings: MutMap::default(),
             layout_caches: std::vec::Vec::with_capacity(number_of_workers),
             cached_types: Arc::new(Mutex::new(cached_types)),
+            type_cache,
             render,
             palette,
             exec_mode,
+            frac_default,
+            opt_level,
             make_specializations_pass: MakeSpecializationsPass::Pass(1),
             world_abilities: Default::default(),
             layout_interner: GlobalInterner::with_capacity(128),
@@ -1069,6 +1155,8 @@ pub struct ModuleTiming {
     pub canonicalize: Duration,
     pub constrain: Duration,
     pub solve: Duration,
+    /// Whether the solved types were read back from the on-disk type cache, rather than solved
+    pub solved_types_from_cache: bool,
     pub find_specializations: Duration,
     // indexed by make specializations pass
     pub make_specializations: Vec<Duration>,
@@ -1088,6 +1176,7 @@ impl ModuleTiming {
             canonicalize: Duration::default(),
             constrain: Duration::default(),
             solve: Duration::default(),
+            solved_types_from_cache: false,
             find_specializations: Duration::default(),
             make_specializations: Vec::with_capacity(2),
             start_time,
@@ -1166,6 +1255,7 @@ enum BuildTask<'a> {
         declarations: Declarations,
         dep_idents: IdentIdsByModule,
         cached_subs: CachedTypeState,
+        type_cache: Option<(TypeCache, u64)>,
         derived_module: SharedDerivedModule,
     },
     BuildPendingSpecializations {
@@ -1227,9 +1317,32 @@ pub enum LoadingProblem<'a> {
     /// a formatted report
     FormattedReport(String),
 
-    ImportCycle(PathBuf, Vec<ModuleId>),
+    ImportCycle(PathBuf, Vec<ModuleId>, Vec<CycleImport<'a>>),
     IncorrectModuleName(FileError<'a, IncorrectModuleName<'a>>),
     CouldNotFindCacheDir,
+
+    /// A package URL was invalid, or the package could not be downloaded or recorded in the
+    /// lockfile
+    PackageProblem {
+        package: String,
+        problem: String,
+    },
+
+    /// A task grew its arena by more than ROC_MEMORY_LIMIT allows
+    ExceededMemoryBudget {
+        module_id: Option<ModuleId>,
+        phase: &'static str,
+        limit: usize,
+    },
+}
+
+/// One step of an import cycle: the `imports` entry through which `importer` depends on `imported`.
+#[derive(Debug)]
+pub struct CycleImport<'a> {
+    pub importer: ModuleId,
+    pub imported: ModuleId,
+    pub src: &'a [u8],
+    pub region: Region,
 }
 
 pub enum Phases {
@@ -1284,6 +1397,8 @@ pub fn load_and_typecheck_str<'a>(
         palette,
         threading,
         exec_mode: ExecutionMode::Check,
+        frac_default: FloatWidth::F64,
+        opt_level: OptLevel::Development,
     };
 
     match load(
@@ -1509,6 +1624,8 @@ pub fn load<'a>(
     roc_cache_dir: RocCacheDir<'_>,
     load_config: LoadConfig,
 ) -> Result<LoadResult<'a>, LoadingProblem<'a>> {
+    let _span = roc_tracing::trace_span("load", "load");
+
     enum Threads {
         Single,
         Many(usize),
@@ -1543,6 +1660,8 @@ pub fn load<'a>(
             load_config.render,
             load_config.palette,
             load_config.exec_mode,
+            load_config.frac_default,
+            load_config.opt_level,
             roc_cache_dir,
         ),
         Threads::Many(threads) => load_multi_threaded(
@@ -1555,6 +1674,8 @@ pub fn load<'a>(
             load_config.palette,
             threads,
             load_config.exec_mode,
+            load_config.frac_default,
+            load_config.opt_level,
             roc_cache_dir,
         ),
     }
@@ -1570,6 +1691,8 @@ pub fn load_single_threaded<'a>(
     render: RenderTarget,
     palette: Palette,
     exec_mode: ExecutionMode,
+    frac_default: FloatWidth,
+    opt_level: OptLevel,
     roc_cache_dir: RocCacheDir<'_>,
 ) -> Result<LoadResult<'a>, LoadingProblem<'a>> {
     let LoadStart {
@@ -1588,6 +1711,9 @@ pub fn load_single_threaded<'a>(
         .send(root_msg)
         .map_err(|_| LoadingProblem::MsgChannelDied)?;
 
+    let memory = MemoryAccounting::from_env();
+    let module_names = Arc::clone(&arc_modules);
+
     let number_of_workers = 1;
     let mut state = State::new(
         root_id,
@@ -1597,10 +1723,13 @@ pub fn load_single_threaded<'a>(
         arc_modules,
         ident_ids_by_module,
         cached_types,
+        TypeCache::from_cache_dir(roc_cache_dir),
         render,
         palette,
         number_of_workers,
         exec_mode,
+        frac_default,
+        opt_level,
     );
 
     // We'll add tasks to this, and then worker threads will take tasks from it.
@@ -1625,7 +1754,11 @@ pub fn load_single_threaded<'a>(
             &msg_tx,
             &msg_rx,
         ) {
-            Ok(ControlFlow::Break(done)) => return Ok(done),
+            Ok(ControlFlow::Break(done)) => {
+                memory.report(&module_names.lock().clone().into_module_ids());
+
+                return Ok(done);
+            }
             Ok(ControlFlow::Continue(new_state)) => {
                 state = new_state;
             }
@@ -1643,6 +1776,7 @@ pub fn load_single_threaded<'a>(
             &src_dir,
             roc_cache_dir,
             target_info,
+            &memory,
         );
 
         match control_flow {
@@ -1792,7 +1926,7 @@ fn state_thread_step<'a>(
                             );
                             Err(LoadingProblem::FormattedReport(buf))
                         }
-                        Err(LoadingProblem::ImportCycle(filename, cycle)) => {
+                        Err(LoadingProblem::ImportCycle(filename, cycle, imports)) => {
                             let module_ids = arc_modules.lock().clone().into_module_ids();
 
                             let root_exposed_ident_ids = IdentIds::exposed_builtins(0);
@@ -1800,6 +1934,7 @@ fn state_thread_step<'a>(
                                 module_ids,
                                 root_exposed_ident_ids,
                                 cycle,
+                                imports,
                                 filename,
                                 render,
                             );
@@ -1822,6 +1957,27 @@ fn state_thread_step<'a>(
                             );
                             return Err(LoadingProblem::FormattedReport(buf));
                         }
+                        Err(LoadingProblem::ExceededMemoryBudget {
+                            module_id,
+                            phase,
+                            limit,
+                        }) => {
+                            let module_ids = arc_modules.lock().clone().into_module_ids();
+
+                            let buf = to_memory_budget_report(
+                                &module_ids,
+                                module_id,
+                                phase,
+                                limit,
+                                &palette,
+                            );
+                            return Err(LoadingProblem::FormattedReport(buf));
+                        }
+                        Err(LoadingProblem::PackageProblem { package, problem }) => {
+                            let buf = to_package_problem_report(&package, &problem, &palette);
+
+                            return Err(LoadingProblem::FormattedReport(buf));
+                        }
                         Err(e) => Err(e),
                     }
                 }
@@ -1847,10 +2003,17 @@ pub fn report_loading_problem(
 
             to_parse_problem_report(problem, module_ids, root_exposed_ident_ids, render, palette)
         }
-        LoadingProblem::ImportCycle(filename, cycle) => {
+        LoadingProblem::ImportCycle(filename, cycle, imports) => {
             let root_exposed_ident_ids = IdentIds::exposed_builtins(0);
 
-            to_import_cycle_report(module_ids, root_exposed_ident_ids, cycle, filename, render)
+            to_import_cycle_report(
+                module_ids,
+                root_exposed_ident_ids,
+                cycle,
+                imports,
+                filename,
+                render,
+            )
         }
         LoadingProblem::IncorrectModuleName(FileError {
             problem: SourceError { problem, bytes },
@@ -1869,6 +2032,14 @@ pub fn report_loading_problem(
         }
         LoadingProblem::FormattedReport(report) => report,
         LoadingProblem::FileProblem { filename, error } => to_file_problem_report(&filename, error),
+        LoadingProblem::ExceededMemoryBudget {
+            module_id,
+            phase,
+            limit,
+        } => to_memory_budget_report(&module_ids, module_id, phase, limit, &palette),
+        LoadingProblem::PackageProblem { package, problem } => {
+            to_package_problem_report(&package, &problem, &palette)
+        }
         err => todo!("Loading error: {:?}", err),
     }
 }
@@ -1883,6 +2054,8 @@ fn load_multi_threaded<'a>(
     palette: Palette,
     available_threads: usize,
     exec_mode: ExecutionMode,
+    frac_default: FloatWidth,
+    opt_level: OptLevel,
     roc_cache_dir: RocCacheDir<'_>,
 ) -> Result<LoadResult<'a>, LoadingProblem<'a>> {
     let LoadStart {
@@ -1917,6 +2090,9 @@ fn load_multi_threaded<'a>(
         "`load_multi_threaded` needs at least one worker"
     );
 
+    let memory = MemoryAccounting::from_env();
+    let module_names = Arc::clone(&arc_modules);
+
     let mut state = State::new(
         root_id,
         opt_platform_shorthand,
@@ -1925,10 +2101,13 @@ fn load_multi_threaded<'a>(
         arc_modules,
         ident_ids_by_module,
         cached_types,
+        TypeCache::from_cache_dir(roc_cache_dir),
         render,
         palette,
         num_workers,
         exec_mode,
+        frac_default,
+        opt_level,
     );
 
     // an arena for every worker, stored in an arena-allocated bumpalo vec to make the lifetimes work
@@ -1974,6 +2153,7 @@ fn load_multi_threaded<'a>(
                 // (since other threads need to reference it too). Same with src_dir.
                 let injector = &injector;
                 let src_dir = &src_dir;
+                let memory = &memory;
 
                 // Record this thread's handle so the main thread can join it later.
                 let res_join_handle = thread_scope
@@ -1991,6 +2171,7 @@ fn load_multi_threaded<'a>(
                             src_dir,
                             roc_cache_dir,
                             target_info,
+                            memory,
                         )
                     });
 
@@ -2031,6 +2212,7 @@ fn load_multi_threaded<'a>(
                 ) {
                     Ok(ControlFlow::Break(load_result)) => {
                         shut_down_worker_threads!();
+                        memory.report(&module_names.lock().clone().into_module_ids());
 
                         return Ok(load_result);
                     }
@@ -2060,6 +2242,7 @@ fn worker_task_step<'a>(
     src_dir: &Path,
     roc_cache_dir: RocCacheDir<'_>,
     target_info: TargetInfo,
+    memory: &MemoryAccounting,
 ) -> Result<ControlFlow<(), ()>, LoadingProblem<'a>> {
     match worker_msg_rx.try_recv() {
         Ok(msg) => {
@@ -2089,6 +2272,7 @@ fn worker_task_step<'a>(
                             msg_tx.clone(),
                             roc_cache_dir,
                             target_info,
+                            memory,
                         );
 
                         match result {
@@ -2107,6 +2291,9 @@ fn worker_task_step<'a>(
                             Err(LoadingProblem::IncorrectModuleName(err)) => {
                                 msg_tx.send(Msg::IncorrectModuleName(err)).unwrap();
                             }
+                            Err(problem @ LoadingProblem::ExceededMemoryBudget { .. }) => {
+                                msg_tx.send(Msg::FailedToLoad(problem)).unwrap();
+                            }
                             Err(other) => {
                                 return Err(other);
                             }
@@ -2134,6 +2321,7 @@ fn worker_task<'a>(
     src_dir: &Path,
     roc_cache_dir: RocCacheDir<'_>,
     target_info: TargetInfo,
+    memory: &MemoryAccounting,
 ) -> Result<(), LoadingProblem<'a>> {
     // Keep listening until we receive a Shutdown msg
     for msg in worker_msg_rx.iter() {
@@ -2188,6 +2376,7 @@ fn worker_task<'a>(
                         msg_tx.clone(),
                         roc_cache_dir,
                         target_info,
+                        memory,
                     );
 
                     match result {
@@ -2206,6 +2395,9 @@ fn worker_task<'a>(
                         Err(LoadingProblem::IncorrectModuleName(err)) => {
                             msg_tx.send(Msg::IncorrectModuleName(err)).unwrap();
                         }
+                        Err(problem @ LoadingProblem::ExceededMemoryBudget { .. }) => {
+                            msg_tx.send(Msg::FailedToLoad(problem)).unwrap();
+                        }
                         Err(other) => {
                             return Err(other);
                         }
@@ -2250,22 +2442,27 @@ macro_rules! debug_print_ir {
     };
 }
 
+/// With ROC_CHECK_MONO_IR, check the mono IR after a pass over it, so that a malformed procedure
+/// is reported where it was made rather than as a panic in one of the backends.
 macro_rules! debug_check_ir {
-    ($state:expr, $arena:expr, $interner:expr, $flag:path) => {
-        dbg_do!($flag, {
-            use roc_mono::debug::{check_procs, format_problems};
-
-            let interns = Interns {
-                module_ids: $state.arc_modules.lock().clone().into_module_ids(),
-                all_ident_ids: $state.constrained_ident_ids.clone(),
-            };
+    ($state:expr, $arena:expr, $interner:expr, $after:literal) => {
+        debug_check_ir!($state, $arena, $interner, $after, check_procs())
+    };
+    ($state:expr, $arena:expr, $interner:expr, $after:literal, $check:ident($($arg:expr),*)) => {
+        dbg_do!(ROC_CHECK_MONO_IR, {
+            use roc_mono::debug::{check_closure_erasure, check_procs, format_problems};
 
             let procedures = &$state.procedures;
 
-            let problems = check_procs($arena, $interner, procedures);
+            let problems = $check($arena, $interner, $($arg,)* procedures);
             if !problems.is_empty() {
+                let interns = Interns {
+                    module_ids: $state.arc_modules.lock().clone().into_module_ids(),
+                    all_ident_ids: $state.constrained_ident_ids.clone(),
+                };
+
                 let formatted = format_problems(&interns, $interner, problems);
-                eprintln!("IR PROBLEMS FOUND:\n{formatted}");
+                eprintln!("IR PROBLEMS FOUND AFTER {}:\n{formatted}", $after);
             }
         })
     };
@@ -2358,7 +2555,50 @@ fn update<'a>(
 
                 for (shorthand, package_name) in header.packages.iter() {
                     let package_str = package_name.as_str();
-                    let shorthand_path = if package_str.starts_with("https://") {
+                    let shorthand_path = if package_str.starts_with("git+") {
+                        #[cfg(not(target_family = "wasm"))]
+                        {
+                            let url = package_str;
+                            match GitPackage::try_from(url) {
+                                Ok(git_package) => {
+                                    // By now, installing the package has locked it to the
+                                    // hash its files are cached under.
+                                    let locked = Lockfile::read(src_dir)
+                                        .ok()
+                                        .and_then(|lockfile| lockfile.get(url).cloned())
+                                        .unwrap_or_else(|| {
+                                            internal_error!(
+                                                "The git package {:?} was not in the lockfile after it was installed",
+                                                url
+                                            )
+                                        });
+                                    let root_module_dir = state
+                                        .cache_dir
+                                        .join(git_package.cache_subdir)
+                                        .join(locked.content_hash);
+                                    let root_module = root_module_dir.join(
+                                        git_package.root_module_filename.unwrap_or("main.roc"),
+                                    );
+
+                                    ShorthandPath::FromHttpsUrl {
+                                        root_module_dir,
+                                        root_module,
+                                    }
+                                }
+                                Err(url_err) => {
+                                    return Err(LoadingProblem::PackageProblem {
+                                        package: url.to_string(),
+                                        problem: url_err.to_string(),
+                                    });
+                                }
+                            }
+                        }
+
+                        #[cfg(target_family = "wasm")]
+                        {
+                            panic!("Specifying packages via URLs is curently unsupported in wasm.");
+                        }
+                    } else if package_str.starts_with("https://") {
                         #[cfg(not(target_family = "wasm"))]
                         {
                             let url = package_str;
@@ -2379,11 +2619,10 @@ fn update<'a>(
                                     }
                                 }
                                 Err(url_err) => {
-                                    todo!(
-                                        "Gracefully report URL error for {:?} - {:?}",
-                                        url,
-                                        url_err
-                                    );
+                                    return Err(LoadingProblem::PackageProblem {
+                                        package: url.to_string(),
+                                        problem: url_err.to_string(),
+                                    });
                                 }
                             }
                         }
@@ -2543,6 +2782,14 @@ fn update<'a>(
                         .map(|x| *x.as_inner()),
                 );
 
+            state.module_cache.import_sites.insert(
+                header.module_id,
+                ImportSites {
+                    src: header.parse_state.original_bytes(),
+                    regions: header.imported_modules.clone(),
+                },
+            );
+
             let added_deps_result = state.dependencies.add_module(
                 header.module_id,
                 &header.package_qualified_imported_modules,
@@ -2552,9 +2799,24 @@ fn update<'a>(
             let new_work = match added_deps_result {
                 Ok(work) => work,
                 Err(DepCycle { cycle }) => {
+                    let imports = cycle
+                        .windows(2)
+                        .filter_map(|pair| {
+                            let sites = state.module_cache.import_sites.get(&pair[0])?;
+
+                            Some(CycleImport {
+                                importer: pair[0],
+                                imported: pair[1],
+                                src: sites.src,
+                                region: *sites.regions.get(&pair[1])?,
+                            })
+                        })
+                        .collect();
+
                     return Err(LoadingProblem::ImportCycle(
                         header.module_path.clone(),
                         cycle,
+                        imports,
                     ));
                 }
             };
@@ -2607,6 +2869,7 @@ fn update<'a>(
             constrained_module,
             canonicalization_problems,
             module_docs,
+            usages,
         }) => {
             let module_id = constrained_module.module.module_id;
             log!("generated constraints for {:?}", module_id);
@@ -2619,6 +2882,8 @@ fn update<'a>(
                 state.module_cache.documentation.insert(module_id, docs);
             }
 
+            state.module_cache.usages.insert(module_id, usages);
+
             report_unused_imported_modules(&mut state, module_id, &constrained_module);
 
             state
@@ -2659,10 +2924,27 @@ fn update<'a>(
             log!("solved types for {:?}", module_id);
             module_timing.end_time = Instant::now();
 
+            let mut type_problems = solved_module.problems;
+
+            if state.frac_default != FloatWidth::F64 {
+                // Float literals are already checked to fit in an F64 during canonicalization, but
+                // the configured default may not be able to represent them.
+                type_problems.extend(
+                    number_defaults(&decls, solved_subs.inner(), state.frac_default)
+                        .into_iter()
+                        .filter(|default| !default.fits)
+                        .map(|default| TypeError::UnrepresentableDefault {
+                            region: default.region,
+                            literal: default.literal,
+                            typ: state.frac_default,
+                        }),
+                );
+            }
+
             state
                 .module_cache
                 .type_problems
-                .insert(module_id, solved_module.problems);
+                .insert(module_id, type_problems);
 
             let should_include_expects = (!loc_expects.is_empty() || !loc_dbgs.is_empty()) && {
                 let modules = state.arc_modules.lock();
@@ -2680,6 +2962,7 @@ fn update<'a>(
                     subs: solved_subs.clone().into_inner(),
                     path: path.to_owned(),
                     ident_ids: ident_ids.clone(),
+                    frac_default: state.frac_default,
                 })
             } else {
                 None
@@ -2784,9 +3067,17 @@ fn update<'a>(
                 );
 
                 if state.goal_phase() > Phase::SolveTypes || state.exec_mode.build_if_checks() {
-                    let layout_cache = state.layout_caches.pop().unwrap_or_else(|| {
-                        LayoutCache::new(state.layout_interner.fork(), state.target_info)
-                    });
+                    state.module_cache.def_regions.extend(
+                        decls
+                            .symbols
+                            .iter()
+                            .map(|loc_symbol| (loc_symbol.value, loc_symbol.region)),
+                    );
+
+                    let layout_cache = state
+                        .layout_caches
+                        .pop()
+                        .unwrap_or_else(|| state.new_layout_cache());
 
                     let typechecked = TypeCheckedModule {
                         module_id,
@@ -2882,6 +3173,7 @@ fn update<'a>(
             subs,
             procs_base,
             procedures,
+            tail_call_problems,
             external_specializations_requested,
             module_timing,
             layout_cache,
@@ -2899,6 +3191,34 @@ fn update<'a>(
             let _ = layout_cache;
 
             state.procedures.extend(procedures);
+
+            for problem in tail_call_problems {
+                use roc_problem::can::Problem::UnoptimizedTailCall;
+
+                if let UnoptimizedTailCall { caller, region, .. } = problem {
+                    let problems = state
+                        .module_cache
+                        .can_problems
+                        .entry(caller.module_id())
+                        .or_default();
+
+                    // a module can make specializations more than once, and each time reports
+                    // the calls of the functions it specializes again
+                    let reported = problems.iter().any(|other| match other {
+                        UnoptimizedTailCall {
+                            caller: other_caller,
+                            region: other_region,
+                            ..
+                        } => (*other_caller, *other_region) == (caller, region),
+                        _ => false,
+                    });
+
+                    if !reported {
+                        problems.push(problem);
+                    }
+                }
+            }
+
             state.module_cache.late_specializations.insert(
                 module_id,
                 LateSpecializationsModule {
@@ -3010,7 +3330,66 @@ fn update<'a>(
                     log!("specializations complete from {:?}", module_id);
 
                     debug_print_ir!(state, &layout_interner, ROC_PRINT_IR_AFTER_SPECIALIZATION);
-                    debug_check_ir!(state, arena, &layout_interner, ROC_CHECK_MONO_IR);
+                    debug_check_ir!(state, arena, &layout_interner, "SPECIALIZATION");
+
+                    Proc::fuse_list_operations(
+                        arena,
+                        &layout_interner,
+                        module_id,
+                        state.constrained_ident_ids.get_mut(&module_id).unwrap(),
+                        &mut update_mode_ids,
+                        state.target_info,
+                        state.opt_level,
+                        &mut state.procedures,
+                    );
+                    debug_check_ir!(state, arena, &layout_interner, "LIST FUSION");
+
+                    Proc::inline_procedures(
+                        arena,
+                        module_id,
+                        &mut state.constrained_ident_ids,
+                        &mut update_mode_ids,
+                        state.opt_level,
+                        &mut state.procedures,
+                    );
+                    debug_check_ir!(state, arena, &layout_interner, "INLINING");
+
+                    roc_interpret::evaluate_constants(
+                        arena,
+                        module_id,
+                        state.constrained_ident_ids.get_mut(&module_id).unwrap(),
+                        state.opt_level,
+                        &mut state.procedures,
+                    );
+                    debug_check_ir!(state, arena, &layout_interner, "CONSTANT EVALUATION");
+
+                    Proc::fold_constant_operations(arena, state.opt_level, &mut state.procedures);
+                    debug_check_ir!(state, arena, &layout_interner, "CONSTANT FOLDING");
+
+                    let exposed_to_host: MutSet<Symbol> =
+                        state.exposed_to_host.values.keys().copied().collect();
+
+                    Proc::erase_closure_arguments(
+                        arena,
+                        &layout_interner,
+                        state.opt_level,
+                        &exposed_to_host,
+                        &mut state.procedures,
+                    );
+                    debug_check_ir!(state, arena, &layout_interner, "CLOSURE ERASURE");
+
+                    if !matches!(state.opt_level, OptLevel::Development) {
+                        debug_check_ir!(
+                            state,
+                            arena,
+                            &layout_interner,
+                            "CLOSURE ERASURE",
+                            check_closure_erasure(&exposed_to_host)
+                        );
+                    }
+
+                    Proc::eliminate_dead_code(arena, state.opt_level, &mut state.procedures);
+                    debug_check_ir!(state, arena, &layout_interner, "DEAD CODE ELIMINATION");
 
                     let ident_ids = state.constrained_ident_ids.get_mut(&module_id).unwrap();
 
@@ -3023,6 +3402,7 @@ fn update<'a>(
                     );
 
                     debug_print_ir!(state, &layout_interner, ROC_PRINT_IR_AFTER_RESET_REUSE);
+                    debug_check_ir!(state, arena, &layout_interner, "RESET/REUSE");
 
                     Proc::insert_refcount_operations(
                         arena,
@@ -3032,8 +3412,10 @@ fn update<'a>(
                         &mut update_mode_ids,
                         &mut state.procedures,
                     );
+                    Proc::elide_refcount_operations(arena, state.opt_level, &mut state.procedures);
 
                     debug_print_ir!(state, &layout_interner, ROC_PRINT_IR_AFTER_REFCOUNT);
+                    debug_check_ir!(state, arena, &layout_interner, "REFCOUNTING");
 
                     // This is not safe with the new non-recursive RC updates that we do for tag unions
                     //
@@ -3235,6 +3617,7 @@ fn finish_specialization<'a>(
         type_problems,
         can_problems,
         sources,
+        def_regions,
         ..
     } = module_cache;
 
@@ -3243,6 +3626,16 @@ fn finish_specialization<'a>(
         .map(|(id, (path, src))| (id, (path, src.into())))
         .collect();
 
+    // `roc test` has no entry point, but it looks for the platform's test harness next to it
+    let platform_module_path = match &platform_path {
+        PlatformPath::Valid(To::ExistingPackage(shorthand)) => (*state.arc_shorthands)
+            .lock()
+            .get(shorthand)
+            .map(|shorthand_path| shorthand_path.root_module().to_path_buf()),
+        PlatformPath::Valid(To::NewPackage(p_or_p)) => Some(PathBuf::from(p_or_p.as_str())),
+        _ => None,
+    };
+
     let entry_point = {
         match exec_mode {
             ExecutionMode::Test => EntryPoint::Test,
@@ -3331,9 +3724,11 @@ fn finish_specialization<'a>(
         procedures,
         entry_point,
         sources,
+        def_regions,
         timings: state.timings,
         toplevel_expects,
         uses_prebuilt_platform,
+        platform_path: platform_module_path,
     })
 }
 
@@ -3412,6 +3807,7 @@ fn finish(
         sources,
         timings: state.timings,
         documentation,
+        usages: state.module_cache.usages,
         abilities_store,
     }
 }
@@ -4096,6 +4492,22 @@ fn load_packages<'a>(
     module_ids: Arc<Mutex<PackageModuleIds<'a>>>,
     ident_ids_by_module: SharedIdentIdsByModule,
 ) {
+    // What the package URLs resolve to is recorded in a lockfile next to the app
+    #[cfg(not(target_family = "wasm"))]
+    let mut lockfile = match Lockfile::read(&cwd) {
+        Ok(lockfile) => lockfile,
+        Err(err) => {
+            load_messages.push(Msg::FailedToLoad(LoadingProblem::PackageProblem {
+                package: Lockfile::path(&cwd).display().to_string(),
+                problem: err.to_string(),
+            }));
+
+            return;
+        }
+    };
+    #[cfg(not(target_family = "wasm"))]
+    let mut urls = Vec::with_capacity(packages.len());
+
     // Load all the packages
     for Loc { value: entry, .. } in packages.iter() {
         let PackageEntry {
@@ -4112,7 +4524,7 @@ fn load_packages<'a>(
 
         // find the `package` or `platform` module on disk,
         // downloading it into a cache dir first if necessary.
-        let root_module_path = if src.starts_with("https://") {
+        let root_module_path = if src.starts_with("https://") || src.starts_with("git+") {
             #[cfg(not(target_family = "wasm"))]
             {
                 // If this is a HTTPS package, synchronously download it
@@ -4121,10 +4533,20 @@ fn load_packages<'a>(
                 // TODO we should do this async; however, with the current
                 // architecture of file.rs (which doesn't use async/await),
                 // this would be very difficult!
-                let (package_dir, opt_root_module) = cache::install_package(roc_cache_dir, src)
-                    .unwrap_or_else(|err| {
-                        todo!("TODO gracefully handle package install error {:?}", err);
-                    });
+                let (package_dir, opt_root_module) =
+                    match cache::install_package(roc_cache_dir, src, &mut lockfile) {
+                        Ok(installed) => installed,
+                        Err(err) => {
+                            load_messages.push(Msg::FailedToLoad(LoadingProblem::PackageProblem {
+                                package: src.to_string(),
+                                problem: err.to_string(),
+                            }));
+
+                            continue;
+                        }
+                    };
+
+                urls.push(src);
 
                 // You can optionally specify the root module using the URL fragment,
                 // e.g. #foo.roc
@@ -4159,6 +4581,18 @@ fn load_packages<'a>(
             }
         }
     }
+
+    #[cfg(not(target_family = "wasm"))]
+    {
+        lockfile.retain(&urls);
+
+        if let Err(err) = lockfile.write_if_changed(&cwd) {
+            load_messages.push(Msg::FailedToLoad(LoadingProblem::PackageProblem {
+                package: Lockfile::path(&cwd).display().to_string(),
+                problem: err.to_string(),
+            }));
+        }
+    }
 }
 
 /// Load a module by its filename
@@ -4326,6 +4760,11 @@ fn build_header<'a>(
         };
         home = module_ids.get_or_insert(&name);
 
+        roc_tracing::trace_module_name(home, || match &name {
+            PQModuleName::Qualified(shorthand, module) => format!("{}.{}", shorthand, module),
+            PQModuleName::Unqualified(module) => module.to_string(),
+        });
+
         // Ensure this module has an entry in the exposed_ident_ids map.
         ident_ids_by_module.get_or_insert(home);
 
@@ -4554,6 +4993,7 @@ impl<'a> BuildTask<'a> {
         dep_idents: IdentIdsByModule,
         declarations: Declarations,
         cached_subs: CachedTypeState,
+        type_cache: Option<(TypeCache, u64)>,
         derived_module: SharedDerivedModule,
     ) -> Self {
         let exposed_by_module = exposed_types.retain_modules(imported_modules.keys());
@@ -4575,6 +5015,7 @@ impl<'a> BuildTask<'a> {
             dep_idents,
             module_timing,
             cached_subs,
+            type_cache,
             derived_module,
         }
     }
@@ -4951,6 +5392,7 @@ fn run_solve<'a>(
     decls: Declarations,
     dep_idents: IdentIdsByModule,
     cached_types: CachedTypeState,
+    type_cache: Option<(TypeCache, u64)>,
     derived_module: SharedDerivedModule,
 ) -> Msg<'a> {
     let solve_start = Instant::now();
@@ -4966,9 +5408,15 @@ fn run_solve<'a>(
     let module = module;
 
     let (solved_subs, solved_implementations, exposed_vars_by_symbol, problems, abilities_store) = {
-        if module_id.is_builtin() {
-            match cached_types.lock().remove(&module_id) {
-                None => run_solve_solve(
+        let cached = cached_types.lock().remove(&module_id);
+
+        match cached {
+            None => {
+                // Deriving adds to the derived module as a side effect, which the cached types
+                // wouldn't redo, so only modules that don't derive anything can be cached.
+                let cacheable = pending_derives.is_empty();
+
+                let solved = run_solve_solve(
                     exposed_for_module,
                     types,
                     constraints,
@@ -4977,31 +5425,32 @@ fn run_solve<'a>(
                     var_store,
                     module,
                     derived_module,
-                ),
-                Some(TypeState {
-                    subs,
-                    exposed_vars_by_symbol,
-                    abilities,
-                    solved_implementations,
-                }) => (
+                );
+
+                match type_cache {
+                    Some((type_cache, key)) if cacheable && solved.3.is_empty() => {
+                        cache_types(&type_cache, key, solved)
+                    }
+                    _ => solved,
+                }
+            }
+            Some(TypeState {
+                subs,
+                exposed_vars_by_symbol,
+                abilities,
+                solved_implementations,
+            }) => {
+                // the builtins' types are cached too, but not in the on-disk type cache
+                module_timing.solved_types_from_cache = !module_id.is_builtin();
+
+                (
                     Solved(subs),
                     solved_implementations,
                     exposed_vars_by_symbol,
                     vec![],
                     abilities,
-                ),
+                )
             }
-        } else {
-            run_solve_solve(
-                exposed_for_module,
-                types,
-                constraints,
-                constraint,
-                pending_derives,
-                var_store,
-                module,
-                derived_module,
-            )
         }
     };
 
@@ -5041,6 +5490,52 @@ fn run_solve<'a>(
     }
 }
 
+/// Write solved types to the on-disk cache, and give them back
+fn cache_types(
+    type_cache: &TypeCache,
+    key: u64,
+    solved: (
+        Solved<Subs>,
+        ResolvedImplementations,
+        Vec<(Symbol, Variable)>,
+        Vec<TypeError>,
+        AbilitiesStore,
+    ),
+) -> (
+    Solved<Subs>,
+    ResolvedImplementations,
+    Vec<(Symbol, Variable)>,
+    Vec<TypeError>,
+    AbilitiesStore,
+) {
+    let (solved_subs, solved_implementations, exposed_vars_by_symbol, problems, abilities) = solved;
+
+    let type_state = TypeState {
+        subs: solved_subs.into_inner(),
+        exposed_vars_by_symbol,
+        abilities,
+        solved_implementations,
+    };
+
+    // The cache only saves time; if we can't write to it, we'll just solve the module again.
+    let _ = type_cache.write(key, &type_state);
+
+    let TypeState {
+        subs,
+        exposed_vars_by_symbol,
+        abilities,
+        solved_implementations,
+    } = type_state;
+
+    (
+        Solved(subs),
+        solved_implementations,
+        exposed_vars_by_symbol,
+        problems,
+        abilities,
+    )
+}
+
 fn unspace<'a, T: Copy>(arena: &'a Bump, items: &[Loc<Spaced<'a, T>>]) -> &'a [Loc<T>] {
     bumpalo::collections::Vec::from_iter_in(
         items
@@ -5329,6 +5824,7 @@ fn canonicalize_and_constrain<'a>(
         constrained_module,
         canonicalization_problems: module_output.problems,
         module_docs,
+        usages: module_output.usages,
     }
 }
 
@@ -5482,7 +5978,8 @@ fn make_specializations<'a>(
     );
 
     let external_specializations_requested = procs.externals_we_need.clone();
-    let (procedures, restored_procs_base) = procs.get_specialized_procs_without_rc(&mut mono_env);
+    let (procedures, tail_call_problems, restored_procs_base) =
+        procs.get_specialized_procs_without_rc(&mut mono_env);
 
     // Turn `Bytes.Decode.IdentId(238)` into `Bytes.Decode.238`, we rely on this in mono tests
     mono_env.home.register_debug_idents(mono_env.ident_ids);
@@ -5498,6 +5995,7 @@ fn make_specializations<'a>(
         layout_cache,
         procs_base: restored_procs_base,
         procedures,
+        tail_call_problems,
         update_mode_ids,
         subs,
         expectations,
@@ -6051,10 +6549,18 @@ fn run_task<'a>(
     msg_tx: MsgSender<'a>,
     roc_cache_dir: RocCacheDir<'_>,
     target_info: TargetInfo,
+    memory: &MemoryAccounting,
 ) -> Result<(), LoadingProblem<'a>> {
     use BuildTask::*;
 
-    let msg = match task {
+    let (category, phase, module_id) = task_phase(&task);
+
+    let _span = match module_id {
+        Some(module_id) => roc_tracing::trace_module_span(category, phase, module_id),
+        None => roc_tracing::trace_span(category, phase),
+    };
+
+    let result = memory.run(arena, phase, module_id, || match task {
         LoadModule {
             module_name,
             module_ids,
@@ -6106,6 +6612,7 @@ fn run_task<'a>(
             declarations,
             dep_idents,
             cached_subs,
+            type_cache,
             derived_module,
         } => Ok(run_solve(
             module,
@@ -6120,6 +6627,7 @@ fn run_task<'a>(
             declarations,
             dep_idents,
             cached_subs,
+            type_cache,
             derived_module,
         )),
         BuildPendingSpecializations {
@@ -6180,7 +6688,8 @@ fn run_task<'a>(
             derived_module,
             expectations,
         )),
-    }?;
+    })?;
+    let msg = result?;
 
     msg_tx
         .send(msg)
@@ -6189,8 +6698,114 @@ fn run_task<'a>(
     Ok(())
 }
 
+/// The category and phase a task is traced and accounted under, and the module it works on
+fn task_phase(task: &BuildTask<'_>) -> (&'static str, &'static str, Option<ModuleId>) {
+    use BuildTask::*;
+
+    match task {
+        LoadModule { .. } => ("load", "load header", None),
+        Parse { header } => ("load", "parse", Some(header.module_id)),
+        CanonicalizeAndConstrain { parsed, .. } => ("load", "canonicalize", Some(parsed.module_id)),
+        Solve { module, .. } => ("solve", "solve", Some(module.module_id)),
+        BuildPendingSpecializations { module_id, .. } => {
+            ("mono", "find specializations", Some(*module_id))
+        }
+        MakeSpecializations { module_id, .. } => ("mono", "make specializations", Some(*module_id)),
+    }
+}
+
+fn to_memory_budget_report(
+    module_ids: &ModuleIds,
+    module_id: Option<ModuleId>,
+    phase: &'static str,
+    limit: usize,
+    palette: &Palette,
+) -> String {
+    use roc_reporting::report::{Report, RocDocAllocator};
+    use ven_pretty::DocAllocator;
+
+    let src_lines: Vec<&str> = Vec::new();
+    let interns = Interns::default();
+    let home = module_id.unwrap_or(ModuleId::ATTR);
+    let alloc = RocDocAllocator::new(&src_lines, home, &interns);
+
+    let module_name = module_id
+        .and_then(|module_id| module_ids.get_name(module_id))
+        .cloned();
+
+    let culprit = match module_name {
+        Some(module_name) => alloc.concat([
+            alloc.reflow("The "),
+            alloc.module_name(module_name),
+            alloc.reflow(" module exceeded its memory budget of "),
+        ]),
+        None => alloc.reflow("Loading a module header exceeded its memory budget of "),
+    };
+
+    let doc = alloc.stack([
+        alloc.concat([
+            culprit,
+            alloc.text(format_bytes(limit)),
+            alloc.reflow(" in the "),
+            alloc.keyword(phase),
+            alloc.reflow(" phase."),
+        ]),
+        alloc.concat([
+            alloc.reflow("The budget comes from the "),
+            alloc.keyword("ROC_MEMORY_LIMIT"),
+            alloc.reflow(" environment variable. Raise it to give every task more room, "),
+            alloc.reflow("or unset it to take the budget away."),
+        ]),
+    ]);
+
+    let report = Report {
+        filename: "UNKNOWN.roc".into(),
+        doc,
+        title: "MEMORY BUDGET EXCEEDED".to_string(),
+        code: None,
+        severity: Severity::RuntimeError,
+        fixes: Vec::new(),
+        related: Vec::new(),
+    };
+
+    let mut buf = String::new();
+    report.render_color_terminal(&mut buf, &alloc, palette);
+
+    buf
+}
+
+fn to_package_problem_report(package: &str, problem: &str, palette: &Palette) -> String {
+    use roc_reporting::report::{Report, RocDocAllocator};
+    use ven_pretty::DocAllocator;
+
+    let src_lines: Vec<&str> = Vec::new();
+    let interns = Interns::default();
+    let alloc = RocDocAllocator::new(&src_lines, ModuleId::ATTR, &interns);
+
+    let doc = alloc.stack([
+        alloc.reflow("I could not load this package:"),
+        alloc.string(package.to_string()).indent(4),
+        alloc.concat([alloc.reflow("because "), alloc.string(problem.to_string())]),
+    ]);
+
+    let report = Report {
+        filename: "UNKNOWN.roc".into(),
+        doc,
+        title: "PACKAGE PROBLEM".to_string(),
+        code: Some(codes::PACKAGE_PROBLEM),
+        severity: Severity::RuntimeError,
+        fixes: Vec::new(),
+        related: Vec::new(),
+    };
+
+    let mut buf = String::new();
+    report.render_color_terminal(&mut buf, &alloc, palette);
+
+    buf
+}
+
 fn to_file_problem_report(filename: &Path, error: io::ErrorKind) -> String {
-    use roc_reporting::report::{Report, RocDocAllocator, DEFAULT_PALETTE};
+    use roc_reporting::report::{Palette, Report, RocDocAllocator};
     use ven_pretty::DocAllocator;
 
     let src_lines: Vec<&str> = Vec::new();
@@ -6221,7 +6836,10 @@ fn to_file_problem_report(filename: &Path, error: io::ErrorKind) -> String {
                 filename: "UNKNOWN.roc".into(),
                 doc,
                 title: "FILE NOT FOUND".to_string(),
+                code: Some(codes::FILE_NOT_FOUND),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         io::ErrorKind::PermissionDenied => {
@@ -6238,7 +6856,10 @@ fn to_file_problem_report(filename: &Path, error: io::ErrorKind) -> String {
                 filename: "UNKNOWN.roc".into(),
                 doc,
                 title: "FILE PERMISSION DENIED".to_string(),
+                code: Some(codes::FILE_PERMISSION_DENIED),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         _ => {
@@ -6258,13 +6879,16 @@ fn to_file_problem_report(filename: &Path, error: io::ErrorKind) -> String {
                 filename: "UNKNOWN.roc".into(),
                 doc,
                 title: "FILE PROBLEM".to_string(),
+                code: Some(codes::FILE_PROBLEM),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
     };
 
     let mut buf = String::new();
-    let palette = DEFAULT_PALETTE;
+    let palette = Palette::from_env();
     report.render_color_terminal(&mut buf, &alloc, &palette);
 
     buf
@@ -6274,10 +6898,11 @@ fn to_import_cycle_report(
     module_ids: ModuleIds,
     all_ident_ids: IdentIdsByModule,
     import_cycle: Vec<ModuleId>,
+    imports: Vec<CycleImport>,
     filename: PathBuf,
     render: RenderTarget,
 ) -> String {
-    use roc_reporting::report::{Report, RocDocAllocator, DEFAULT_PALETTE};
+    use roc_reporting::report::{Palette, Report, RocDocAllocator};
     use ven_pretty::DocAllocator;
 
     // import_cycle looks like CycleModule, Import1, ..., ImportN, CycleModule
@@ -6285,17 +6910,32 @@ fn to_import_cycle_report(
     debug_assert!(import_cycle.len() >= 2);
     let source_of_cycle = import_cycle.first().unwrap();
 
-    // We won't be printing any lines for this report, so this is okay.
-    // TODO: it would be nice to show how each module imports another in the cycle.
-    let src_lines = &[];
-
     let interns = Interns {
         module_ids,
         all_ident_ids,
     };
-    let alloc = RocDocAllocator::new(src_lines, *source_of_cycle, &interns);
 
-    let doc = alloc.stack([
+    // Each import lives in the header of a different module, so every one of them needs an
+    // allocator that knows the source lines of its own module.
+    let import_srcs: Vec<(&str, Vec<&str>)> = imports
+        .iter()
+        .map(|import| {
+            // SAFETY: a module that is not UTF-8 would fail to parse its header, and so could
+            // never end up in an import cycle.
+            let src = unsafe { from_utf8_unchecked(import.src) };
+
+            (src, src.lines().collect())
+        })
+        .collect();
+    let import_allocs: Vec<RocDocAllocator> = imports
+        .iter()
+        .zip(import_srcs.iter())
+        .map(|(import, (_, src_lines))| RocDocAllocator::new(src_lines, import.importer, &interns))
+        .collect();
+
+    let alloc = RocDocAllocator::new(&[], *source_of_cycle, &interns);
+
+    let mut doc = vec![
         alloc.concat([
             alloc.reflow("I can't compile "),
             alloc.module(*source_of_cycle),
@@ -6313,18 +6953,63 @@ fn to_import_cycle_report(
                 .map(|module| alloc.module(module))
                 .collect(),
         ),
-        alloc.reflow("Cyclic dependencies are not allowed in Roc! Can you restructure a module in this import chain so that it doesn't have to depend on itself?")
-    ]);
+    ];
+
+    for ((import, (src, _)), import_alloc) in imports
+        .iter()
+        .zip(import_srcs.iter())
+        .zip(import_allocs.iter())
+    {
+        let lines = LineInfo::new(src);
+
+        doc.push(alloc.concat([
+            alloc.module(import.importer),
+            alloc.reflow(" imports "),
+            alloc.module(import.imported),
+            alloc.reflow(" here:"),
+        ]));
+        doc.push(import_alloc.region(lines.convert_region(import.region)));
+    }
+
+    doc.push(alloc.reflow("Cyclic dependencies are not allowed in Roc! Can you restructure a module in this import chain so that it doesn't have to depend on itself?"));
+
+    // The import that leads back to the start of the cycle is the one to suggest breaking.
+    match imports.last() {
+        Some(closing) if closing.importer == closing.imported => {
+            doc.push(alloc.tip().append(alloc.concat([
+                alloc.reflow("Remove "),
+                alloc.module(closing.imported),
+                alloc.reflow(" from its own imports."),
+            ])));
+        }
+        Some(closing) => {
+            doc.push(alloc.tip().append(alloc.concat([
+                alloc.reflow("Removing the import of "),
+                alloc.module(closing.imported),
+                alloc.reflow(" from "),
+                alloc.module(closing.importer),
+                alloc.reflow(" would break this cycle. Whatever "),
+                alloc.module(closing.importer),
+                alloc.reflow(" needs from "),
+                alloc.module(closing.imported),
+                alloc.reflow(" could move to a new module that both of them import."),
+            ])));
+        }
+        None => {}
+    }
 
     let report = Report {
         filename,
-        doc,
+        doc: alloc.stack(doc),
         title: "IMPORT CYCLE".to_string(),
+        code: Some(codes::IMPORT_CYCLE),
         severity: Severity::RuntimeError,
+        fixes: Vec::new(),
+        related: Vec::new(),
     };
 
     let mut buf = String::new();
-    let palette = DEFAULT_PALETTE;
+    let palette = Palette::from_env();
     report.render(render, &mut buf, &alloc, &palette);
     buf
 }
@@ -6337,7 +7022,7 @@ fn to_incorrect_module_name_report<'a>(
     src: &'a [u8],
     render: RenderTarget,
 ) -> String {
-    use roc_reporting::report::{Report, RocDocAllocator, DEFAULT_PALETTE};
+    use roc_reporting::report::{Palette, Report, RocDocAllocator};
     use ven_pretty::DocAllocator;
 
     let IncorrectModuleName {
@@ -6369,11 +7054,14 @@ fn to_incorrect_module_name_report<'a>(
         filename,
         doc,
         title: "INCORRECT MODULE NAME".to_string(),
+        code: Some(codes::INCORRECT_MODULE_NAME),
         severity: Severity::RuntimeError,
+        fixes: Vec::new(),
+        related: Vec::new(),
     };
 
     let mut buf = String::new();
-    let palette = DEFAULT_PALETTE;
+    let palette = Palette::from_env();
     report.render(render, &mut buf, &alloc, &palette);
     buf
 }
@@ -6423,7 +7111,7 @@ fn to_parse_problem_report<'a>(
 }
 
 fn to_missing_platform_report(module_id: ModuleId, other: PlatformPath) -> String {
-    use roc_reporting::report::{Report, RocDocAllocator, DEFAULT_PALETTE};
+    use roc_reporting::report::{Palette, Report, RocDocAllocator};
     use ven_pretty::DocAllocator;
     use PlatformPath::*;
 
@@ -6448,7 +7136,10 @@ fn to_missing_platform_report(module_id: ModuleId, other: PlatformPath) -> Strin
                     filename: "UNKNOWN.roc".into(),
                     doc,
                     title: "NO PLATFORM".to_string(),
+                    code: Some(codes::NO_PLATFORM),
                     severity: Severity::RuntimeError,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
             RootIsInterface => {
@@ -6463,7 +7154,10 @@ fn to_missing_platform_report(module_id: ModuleId, other: PlatformPath) -> Strin
                     filename: "UNKNOWN.roc".into(),
                     doc,
                     title: "NO PLATFORM".to_string(),
+                    code: Some(codes::NO_PLATFORM),
                     severity: Severity::RuntimeError,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
             RootIsHosted => {
@@ -6478,7 +7172,10 @@ fn to_missing_platform_report(module_id: ModuleId, other: PlatformPath) -> Strin
                     filename: "UNKNOWN.roc".into(),
                     doc,
                     title: "NO PLATFORM".to_string(),
+                    code: Some(codes::NO_PLATFORM),
                     severity: Severity::RuntimeError,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
             RootIsPlatformModule => {
@@ -6493,13 +7190,16 @@ fn to_missing_platform_report(module_id: ModuleId, other: PlatformPath) -> Strin
                     filename: "UNKNOWN.roc".into(),
                     doc,
                     title: "NO PLATFORM".to_string(),
+                    code: Some(codes::NO_PLATFORM),
                     severity: Severity::RuntimeError,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
         }
     };
 
-    let palette = DEFAULT_PALETTE;
+    let palette = Palette::from_env();
     let mut buf = String::new();
     report.render_color_terminal(&mut buf, &alloc, &palette);
 
@@ -6,6 +6,13 @@
 use roc_module::symbol::ModuleId;
 pub mod docs;
 pub mod file;
+mod memory;
+pub mod number_defaults;
+pub mod query;
+pub mod semantic_tokens;
+pub mod signatures;
+pub mod symbol_index;
+mod type_cache;
 mod work;
 
 #[cfg(target_family = "wasm")]
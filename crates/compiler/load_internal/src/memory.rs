@@ -0,0 +1,269 @@
+//! Accounting for the memory the loader's arenas use.
+//!
+//! Every task runs in its worker's arena, and that's where most of what it allocates goes, so
+//! how much the arena grows while a task runs is a good measure of the task's memory use.
+//!
+//! - ROC_MEMORY_REPORT=1 prints each phase's peak and total arena growth once loading finishes.
+//! - ROC_MEMORY_LIMIT=<size>, e.g. `512M` or `2G`, stops any task that grows its arena by more
+//!   than that, with an error naming its module and phase, rather than letting it run the
+//!   machine out of memory.
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+
+use bumpalo::Bump;
+use parking_lot::Mutex;
+use roc_error_macros::user_error;
+use roc_module::symbol::{ModuleId, ModuleIds};
+
+use crate::file::LoadingProblem;
+
+const REPORT_VAR: &str = "ROC_MEMORY_REPORT";
+const LIMIT_VAR: &str = "ROC_MEMORY_LIMIT";
+
+/// What bumpalo panics with when an allocation would take an arena over its limit
+const BUMPALO_OOM_MESSAGE: &str = "out of memory";
+
+#[derive(Debug, Default)]
+pub struct MemoryAccounting {
+    report: bool,
+    /// How many bytes one task may grow its arena by
+    limit: Option<usize>,
+    phases: Mutex<Vec<PhaseUsage>>,
+}
+
+#[derive(Debug)]
+struct PhaseUsage {
+    phase: &'static str,
+    total: usize,
+    peak: usize,
+    peak_module: Option<ModuleId>,
+}
+
+impl MemoryAccounting {
+    pub fn from_env() -> Self {
+        let report = matches!(std::env::var(REPORT_VAR).as_deref(), Ok(value) if value != "0");
+
+        let limit = std::env::var(LIMIT_VAR).ok().map(|value| {
+            parse_size(&value).unwrap_or_else(|| {
+                user_error!(
+                    "{} should be a number of bytes, optionally followed by K, M, or G, \
+                    but it was {:?}",
+                    LIMIT_VAR,
+                    value
+                )
+            })
+        });
+
+        Self {
+            report,
+            limit,
+            phases: Mutex::default(),
+        }
+    }
+
+    /// Run a task in the given arena, recording how much the arena grows and enforcing the limit
+    pub fn run<'a, T>(
+        &self,
+        arena: &Bump,
+        phase: &'static str,
+        module_id: Option<ModuleId>,
+        task: impl FnOnce() -> T,
+    ) -> Result<T, LoadingProblem<'a>> {
+        if !self.report && self.limit.is_none() {
+            return Ok(task());
+        }
+
+        let before = arena.allocated_bytes();
+
+        let result = match self.limit {
+            None => Ok(task()),
+            Some(limit) => {
+                let previous_limit = arena.allocation_limit();
+
+                arena.set_allocation_limit(Some(before.saturating_add(limit)));
+                let result = catch_unwind(AssertUnwindSafe(task));
+                arena.set_allocation_limit(previous_limit);
+
+                result
+            }
+        };
+
+        match result {
+            Ok(value) => {
+                self.record(phase, module_id, arena.allocated_bytes() - before);
+
+                Ok(value)
+            }
+            Err(payload) if payload.downcast_ref::<&str>() == Some(&BUMPALO_OOM_MESSAGE) => {
+                Err(LoadingProblem::ExceededMemoryBudget {
+                    module_id,
+                    phase,
+                    limit: self.limit.unwrap_or_default(),
+                })
+            }
+            Err(payload) => resume_unwind(payload),
+        }
+    }
+
+    fn record(&self, phase: &'static str, module_id: Option<ModuleId>, bytes: usize) {
+        let mut phases = self.phases.lock();
+
+        match phases.iter_mut().find(|usage| usage.phase == phase) {
+            Some(usage) => {
+                usage.total += bytes;
+
+                if bytes > usage.peak {
+                    usage.peak = bytes;
+                    usage.peak_module = module_id;
+                }
+            }
+            None => phases.push(PhaseUsage {
+                phase,
+                total: bytes,
+                peak: bytes,
+                peak_module: module_id,
+            }),
+        }
+    }
+
+    /// Print the usage report to stderr, if ROC_MEMORY_REPORT asked for one
+    pub fn report(&self, module_ids: &ModuleIds) {
+        if !self.report {
+            return;
+        }
+
+        eprintln!("Arena growth by phase (peak is the most any one task grew its arena by):\n");
+        eprintln!(
+            "    {:<24}{:>12}{:>12}  peak module",
+            "phase", "peak", "total"
+        );
+
+        for usage in self.phases.lock().iter() {
+            let module = usage
+                .peak_module
+                .and_then(|module_id| module_ids.get_name(module_id))
+                .map(|name| name.as_str())
+                .unwrap_or("");
+
+            eprintln!(
+                "    {:<24}{:>12}{:>12}  {}",
+                usage.phase,
+                format_bytes(usage.peak),
+                format_bytes(usage.total),
+                module
+            );
+        }
+
+        eprintln!();
+    }
+}
+
+/// e.g. `1048576`, `512K`, `512M`, or `2G`
+fn parse_size(size: &str) -> Option<usize> {
+    let size = size.trim();
+
+    let (number, multiplier) = match size.char_indices().last()? {
+        (index, 'k' | 'K') => (&size[..index], 1 << 10),
+        (index, 'm' | 'M') => (&size[..index], 1 << 20),
+        (index, 'g' | 'G') => (&size[..index], 1 << 30),
+        _ => (size, 1),
+    };
+
+    number.trim().parse::<usize>().ok()?.checked_mul(multiplier)
+}
+
+pub(crate) fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+
+    let mut amount = bytes as f64;
+    let mut unit = 0;
+
+    while amount >= 1024.0 && unit < UNITS.len() - 1 {
+        amount /= 1024.0;
+        unit += 1;
+    }
+
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", amount, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod test_memory {
+    use super::{format_bytes, parse_size, MemoryAccounting};
+    use crate::file::LoadingProblem;
+    use bumpalo::Bump;
+
+    fn accounting(report: bool, limit: Option<usize>) -> MemoryAccounting {
+        MemoryAccounting {
+            report,
+            limit,
+            phases: Default::default(),
+        }
+    }
+
+    #[test]
+    fn parse_sizes() {
+        assert_eq!(parse_size("1048576"), Some(1 << 20));
+        assert_eq!(parse_size("512K"), Some(512 << 10));
+        assert_eq!(parse_size(" 16 m "), Some(16 << 20));
+        assert_eq!(parse_size("2G"), Some(2 << 30));
+        assert_eq!(parse_size("lots"), None);
+        assert_eq!(parse_size(""), None);
+    }
+
+    #[test]
+    fn format_sizes() {
+        assert_eq!(format_bytes(512), "512 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(3 << 30), "3.0 GB");
+    }
+
+    #[test]
+    fn exceeding_the_limit_is_a_loading_problem() {
+        let arena = Bump::new();
+        let accounting = accounting(false, Some(1 << 10));
+
+        let result = accounting.run(&arena, "Canonicalize", None, || {
+            arena.alloc_slice_fill_copy(1 << 20, 0u8);
+        });
+
+        assert!(
+            matches!(
+                result,
+                Err(LoadingProblem::ExceededMemoryBudget {
+                    module_id: None,
+                    phase: "Canonicalize",
+                    limit: 1024,
+                })
+            ),
+            "{:?}",
+            result
+        );
+
+        // The limit only applies while the task runs.
+        arena.alloc_slice_fill_copy(1 << 20, 0u8);
+    }
+
+    #[test]
+    fn record_peak_and_total_per_phase() {
+        let arena = Bump::new();
+        let accounting = accounting(true, None);
+
+        for size in [1000, 5000] {
+            accounting
+                .run(&arena, "Solve", None, || {
+                    arena.alloc_slice_fill_copy(size, 0u8);
+                })
+                .unwrap();
+        }
+
+        let phases = accounting.phases.lock();
+
+        assert_eq!(phases.len(), 1);
+        assert_eq!(phases[0].phase, "Solve");
+        assert!(phases[0].peak >= 5000, "{:?}", phases);
+        assert!(phases[0].total >= phases[0].peak + 1000, "{:?}", phases);
+    }
+}
@@ -0,0 +1,234 @@
+//! Which concrete type each number literal defaults to, when type checking left it polymorphic.
+//!
+//! A literal like the `1.5` in `Num.toStr 1.5` can be any fractional type. Code generation has to
+//! pick one, so it compiles to the configured default fractional type (`F64` unless configured
+//! otherwise); integer literals like this compile to `I64`, or a wider integer if they don't fit.
+use roc_can::expr::{DeclarationTag, Declarations, Expr};
+use roc_can::traverse::{walk_expr, Visitor};
+use roc_collections::MutSet;
+use roc_module::symbol::Symbol;
+use roc_region::all::Region;
+use roc_std::RocDec;
+use roc_types::num::FloatWidth;
+use roc_types::subs::{Content, FlatType, Subs, SubsSlice, Variable};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NumberDefault {
+    pub region: Region,
+    /// The literal as written, e.g. `1.5`
+    pub literal: Box<str>,
+    /// The type the literal defaults to, e.g. `I64` or `Dec`
+    pub typ: &'static str,
+    /// Whether the literal can be represented in that type. For example `1e300` fits in an
+    /// `F64`, but not in a `Dec`.
+    pub fits: bool,
+}
+
+/// Every number literal in `decls` whose type is left to default, in source order.
+///
+/// Literals whose type is determined by the type of their definition (like the `1` in
+/// `inc = \n -> n + 1`) are not included, since each use of the definition determines it.
+pub fn number_defaults(
+    decls: &Declarations,
+    subs: &Subs,
+    frac_default: FloatWidth,
+) -> Vec<NumberDefault> {
+    let mut defaults = Vec::new();
+
+    for (index, tag) in decls.declarations.iter().enumerate() {
+        if let DeclarationTag::MutualRecursion { .. } = tag {
+            // The definitions of the group follow as their own declarations
+            continue;
+        }
+
+        let loc_expr = &decls.expressions[index];
+        let mut visitor = LiteralVisitor {
+            subs,
+            frac_default,
+            def_vars: unresolved_vars(subs, decls.variables[index]),
+            defaults: &mut defaults,
+        };
+
+        visitor.visit_expr(&loc_expr.value, loc_expr.region, decls.variables[index]);
+    }
+
+    defaults.sort_by_key(|default| default.region.start());
+
+    defaults
+}
+
+struct LiteralVisitor<'a> {
+    subs: &'a Subs,
+    frac_default: FloatWidth,
+    def_vars: MutSet<Variable>,
+    defaults: &'a mut Vec<NumberDefault>,
+}
+
+impl Visitor for LiteralVisitor<'_> {
+    fn visit_expr(&mut self, expr: &Expr, region: Region, var: Variable) {
+        let literal = match expr {
+            Expr::Num(_, literal, _, _)
+            | Expr::Int(_, _, literal, _, _)
+            | Expr::Float(_, _, literal, _, _) => literal,
+            _ => return walk_expr(self, expr, var),
+        };
+
+        if let Some((unresolved, typ)) = default_of(self.subs, var, self.frac_default) {
+            let root = self.subs.get_root_key_without_compacting(unresolved);
+
+            if !self.def_vars.contains(&root) {
+                self.defaults.push(NumberDefault {
+                    region,
+                    literal: literal.clone(),
+                    typ: typ.type_str(),
+                    fits: fits(literal, matches!(expr, Expr::Float(..)), typ),
+                });
+            }
+        }
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Defaulted {
+    Int(&'static str),
+    Frac(FloatWidth),
+}
+
+impl Defaulted {
+    fn type_str(self) -> &'static str {
+        match self {
+            Defaulted::Int(type_str) => type_str,
+            Defaulted::Frac(width) => width.type_str(),
+        }
+    }
+}
+
+/// If the number type `var` is not fully determined, the variable that is left undetermined and
+/// the type it will default to.
+fn default_of(
+    subs: &Subs,
+    var: Variable,
+    frac_default: FloatWidth,
+) -> Option<(Variable, Defaulted)> {
+    use Content::*;
+
+    let int_default = |var: Variable| match subs.get_content_without_compacting(var) {
+        FlexVar(_) | FlexAbleVar(..) => Some((var, Defaulted::Int("I64"))),
+        RangedNumber(range) => Some((
+            var,
+            Defaulted::Int(range.default_compilation_width().type_str()),
+        )),
+        _ => None,
+    };
+
+    match subs.get_content_without_compacting(var) {
+        Alias(_, _, real_var, _) => default_of(subs, *real_var, frac_default),
+        Structure(FlatType::Apply(Symbol::NUM_NUM, args)) => {
+            let arg = *subs.get_subs_slice(*args).first()?;
+
+            match subs.get_content_without_compacting(arg) {
+                Alias(_, _, real_var, _) => default_of_num_arg(subs, *real_var, frac_default),
+                _ => default_of_num_arg(subs, arg, frac_default),
+            }
+            .or_else(|| int_default(arg))
+        }
+        RangedNumber(_) => int_default(var),
+        _ => None,
+    }
+}
+
+fn default_of_num_arg(
+    subs: &Subs,
+    arg: Variable,
+    frac_default: FloatWidth,
+) -> Option<(Variable, Defaulted)> {
+    use Content::*;
+
+    match subs.get_content_without_compacting(arg) {
+        Structure(FlatType::Apply(Symbol::NUM_INTEGER, args)) => {
+            let inner = *subs.get_subs_slice(*args).first()?;
+
+            match subs.get_content_without_compacting(inner) {
+                FlexVar(_) | FlexAbleVar(..) => Some((inner, Defaulted::Int("I64"))),
+                RangedNumber(range) => Some((
+                    inner,
+                    Defaulted::Int(range.default_compilation_width().type_str()),
+                )),
+                _ => None,
+            }
+        }
+        Structure(FlatType::Apply(Symbol::NUM_FLOATINGPOINT, args)) => {
+            let inner = *subs.get_subs_slice(*args).first()?;
+
+            match subs.get_content_without_compacting(inner) {
+                FlexVar(_) | FlexAbleVar(..) | RangedNumber(_) => {
+                    Some((inner, Defaulted::Frac(frac_default)))
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+fn fits(literal: &str, is_float_literal: bool, defaulted: Defaulted) -> bool {
+    let literal = literal.replace('_', "");
+
+    match defaulted {
+        // Integer literals that don't fit any integer type are reported during type checking
+        Defaulted::Int(_) => true,
+        // Decimals are built from the literal as written, not from its parsed value
+        Defaulted::Frac(FloatWidth::Dec) => RocDec::from_str(&literal).is_some(),
+        Defaulted::Frac(FloatWidth::F32) if is_float_literal => literal
+            .parse::<f32>()
+            .map_or(false, |float| float.is_finite()),
+        Defaulted::Frac(FloatWidth::F32 | FloatWidth::F64) => true,
+    }
+}
+
+/// The roots of all variables in the type `var` that are not determined yet.
+fn unresolved_vars(subs: &Subs, var: Variable) -> MutSet<Variable> {
+    use Content::*;
+
+    let mut unresolved = MutSet::default();
+    let mut seen = MutSet::default();
+    let mut stack = vec![var];
+
+    while let Some(var) = stack.pop() {
+        let var = subs.get_root_key_without_compacting(var);
+
+        if !seen.insert(var) {
+            continue;
+        }
+
+        match subs.get_content_without_compacting(var) {
+            FlexVar(_) | RigidVar(_) | FlexAbleVar(..) | RigidAbleVar(..) | RangedNumber(_) => {
+                unresolved.insert(var);
+            }
+            RecursionVar { .. } | LambdaSet(_) | Error => {}
+            Alias(_, _, real_var, _) => stack.push(*real_var),
+            Structure(flat_type) => match flat_type {
+                FlatType::Apply(_, args) => stack.extend(subs.get_subs_slice(*args)),
+                FlatType::Func(args, _, ret) => {
+                    stack.extend(subs.get_subs_slice(*args));
+                    stack.push(*ret);
+                }
+                FlatType::Record(fields, ext) => {
+                    let slice = SubsSlice::new(fields.variables_start, fields.length);
+                    stack.extend(subs.get_subs_slice(slice));
+                    stack.push(*ext);
+                }
+                FlatType::TagUnion(tags, ext) | FlatType::RecursiveTagUnion(_, tags, ext) => {
+                    for slice_index in tags.variables() {
+                        stack.extend(subs.get_subs_slice(subs[slice_index]));
+                    }
+                    stack.push(*ext);
+                }
+                FlatType::FunctionOrTagUnion(_, _, ext) => stack.push(*ext),
+                FlatType::EmptyRecord | FlatType::EmptyTagUnion => {}
+            },
+        }
+    }
+
+    unresolved
+}
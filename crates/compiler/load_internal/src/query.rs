@@ -0,0 +1,211 @@
+//! Questions an editor asks about a loaded module, e.g. to answer hover, go-to-definition,
+//! find-all-references and rename requests of a language server.
+use crate::docs::DocEntry;
+use crate::file::LoadedModule;
+use roc_can::traverse::{find_innermost_at, find_symbol_definition};
+use roc_collections::MutSet;
+use roc_module::symbol::{ModuleId, Symbol};
+use roc_parse::keyword::KEYWORDS;
+use roc_region::all::{Position, Region};
+use roc_types::pretty_print::{name_and_print_var, DebugPrint};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hover {
+    /// The region of the innermost expression or pattern at the queried offset
+    pub region: Region,
+    /// The solved type of that expression or pattern, e.g. `List Str`
+    pub typ: String,
+    /// Where the symbol it refers to is defined, if it refers to one
+    pub definition: Option<Location>,
+    /// The doc comment of that symbol, if it is a documented top-level definition
+    pub docs: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub module_id: ModuleId,
+    pub region: Region,
+}
+
+/// Describe the innermost expression or pattern at the given byte offset of the home module.
+pub fn hover_at(loaded: &mut LoadedModule, offset: u32) -> Option<Hover> {
+    let home = loaded.module_id;
+    let found = find_innermost_at(Position::new(offset), loaded.declarations_by_id.get(&home)?)?;
+
+    let typ = name_and_print_var(
+        found.var,
+        loaded.solved.inner_mut(),
+        home,
+        &loaded.interns,
+        DebugPrint::NOTHING,
+    );
+
+    let (definition, docs) = match found.symbol {
+        Some(symbol) => (definition_of(loaded, symbol), docs_of(loaded, symbol)),
+        None => (None, None),
+    };
+
+    Some(Hover {
+        region: found.region,
+        typ,
+        definition,
+        docs,
+    })
+}
+
+/// Where the symbol used or defined at the given byte offset of the home module is defined.
+pub fn definition_at(loaded: &LoadedModule, offset: u32) -> Option<Location> {
+    let home = loaded.module_id;
+    let found = find_innermost_at(Position::new(offset), loaded.declarations_by_id.get(&home)?)?;
+
+    definition_of(loaded, found.symbol?)
+}
+
+fn definition_of(loaded: &LoadedModule, symbol: Symbol) -> Option<Location> {
+    let module_id = symbol.module_id();
+    let region = loaded
+        .declarations_by_id
+        .get(&module_id)
+        .and_then(|declarations| find_symbol_definition(symbol, declarations))
+        .or_else(|| loaded.usages.get(&module_id)?.definition(symbol))?;
+
+    Some(Location { module_id, region })
+}
+
+/// Every use of `symbol` in all loaded modules, not including its definition.
+pub fn references(loaded: &LoadedModule, symbol: Symbol) -> Vec<Location> {
+    let mut references: Vec<_> = loaded
+        .usages
+        .iter()
+        .flat_map(|(module_id, usages)| {
+            usages.get(symbol).iter().map(|region| Location {
+                module_id: *module_id,
+                region: *region,
+            })
+        })
+        .collect();
+
+    sort_locations(loaded, &mut references);
+
+    references
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RenameProblem {
+    /// Builtins can't be renamed, since their source isn't part of the project
+    Builtin,
+    /// The new name isn't a valid identifier of the same kind as the old one, e.g. a value
+    /// can't be renamed to an uppercase name
+    InvalidName,
+    /// A module that would see the new name already defines or uses something of that name
+    NameTaken {
+        module_id: ModuleId,
+        existing: Symbol,
+    },
+}
+
+/// Check that `symbol` can be renamed to `new_name` without changing what any name in the project
+/// refers to. If it can, returns the regions to replace with `new_name`: the definition and all
+/// uses. For qualified uses like `Foo.bar`, only the `bar` part is included. Renaming a symbol to
+/// its own name changes nothing, so no regions are returned.
+///
+/// Uses in module headers (e.g. `exposes` and `imports` lists) are not tracked, and must be
+/// updated separately.
+pub fn check_rename(
+    loaded: &LoadedModule,
+    symbol: Symbol,
+    new_name: &str,
+) -> Result<Vec<Location>, RenameProblem> {
+    if symbol.is_builtin() {
+        return Err(RenameProblem::Builtin);
+    }
+
+    let old_name = symbol.as_str(&loaded.interns);
+    if old_name == new_name {
+        return Ok(Vec::new());
+    }
+    if !is_valid_rename(old_name, new_name) {
+        return Err(RenameProblem::InvalidName);
+    }
+
+    let mut locations = references(loaded, symbol);
+
+    // Any module that sees the symbol must not already see a different one with the new name.
+    let mut modules: MutSet<ModuleId> = locations
+        .iter()
+        .map(|location| location.module_id)
+        .collect();
+    modules.insert(symbol.module_id());
+
+    for module_id in modules {
+        if let Some(existing) = symbol_named(loaded, module_id, new_name) {
+            return Err(RenameProblem::NameTaken {
+                module_id,
+                existing,
+            });
+        }
+    }
+
+    if let Some(definition) = definition_of(loaded, symbol) {
+        locations.push(definition);
+    }
+
+    sort_locations(loaded, &mut locations);
+
+    Ok(locations)
+}
+
+/// Sort by module name, then by position within the module.
+fn sort_locations(loaded: &LoadedModule, locations: &mut [Location]) {
+    locations.sort_by(|a, b| {
+        let a_name = a.module_id.to_ident_str(&loaded.interns).as_str();
+        let b_name = b.module_id.to_ident_str(&loaded.interns).as_str();
+
+        a_name
+            .cmp(b_name)
+            .then(a.region.start().cmp(&b.region.start()))
+    });
+}
+
+fn is_valid_rename(old_name: &str, new_name: &str) -> bool {
+    let mut chars = new_name.chars();
+
+    match (old_name.chars().next(), chars.next()) {
+        (Some(old_first), Some(new_first)) => {
+            new_first.is_alphabetic()
+                && old_first.is_uppercase() == new_first.is_uppercase()
+                && chars.all(|c| c.is_alphanumeric())
+                && !KEYWORDS.contains(&new_name)
+        }
+        _ => false,
+    }
+}
+
+/// A symbol called `name` that is defined in, or used by, the given module.
+fn symbol_named(loaded: &LoadedModule, module_id: ModuleId, name: &str) -> Option<Symbol> {
+    let defined = loaded
+        .interns
+        .all_ident_ids
+        .get(&module_id)
+        .and_then(|ident_ids| ident_ids.get_id(name))
+        .map(|ident_id| Symbol::new(module_id, ident_id));
+
+    defined.or_else(|| {
+        loaded
+            .usages
+            .get(&module_id)?
+            .iter()
+            .map(|(symbol, _)| symbol)
+            .find(|symbol| symbol.as_str(&loaded.interns) == name)
+    })
+}
+
+fn docs_of(loaded: &LoadedModule, symbol: Symbol) -> Option<String> {
+    let module_docs = loaded.documentation.get(&symbol.module_id())?;
+    let name = symbol.as_str(&loaded.interns);
+
+    module_docs.entries.iter().find_map(|entry| match entry {
+        DocEntry::DocDef(def) if def.name == name => def.docs.clone(),
+        _ => None,
+    })
+}
@@ -0,0 +1,321 @@
+//! Classifying the names in a module for semantic highlighting, e.g. by a language server.
+//!
+//! A lexer can tell an uppercase name from a lowercase one, but not a type from an ability, a
+//! parameter from any other value, or a function that performs effects from one that doesn't.
+//! Canonicalization and type checking can, so the tokens come from the module's declarations,
+//! the lookups recorded while canonicalizing it, and its solved types.
+use roc_can::abilities::AbilitiesStore;
+use roc_can::expr::{Declarations, Expr};
+use roc_can::pattern::{DestructType, Pattern, RecordDestruct};
+use roc_can::traverse::{
+    symbols_introduced_from_pattern, walk_expr, walk_pattern, walk_record_destruct, Visitor,
+};
+use roc_collections::MutSet;
+use roc_module::symbol::{Interns, Symbol};
+use roc_region::all::{Position, Region};
+use roc_types::subs::{Content, FlatType, Subs, Variable};
+
+use crate::file::LoadedModule;
+
+/// How many aliases and opaque types to look through to find what a type really is
+const MAX_ALIAS_DEPTH: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenKind {
+    Type,
+    Ability,
+    Tag,
+    /// The module name of a qualified name, e.g. `Str` in `Str.concat`
+    Module,
+    Parameter,
+    Variable,
+    Function,
+    /// A function that returns a hosted module's effect type, or something built on it, like a
+    /// platform's `Task`
+    EffectfulFunction,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SemanticToken {
+    pub region: Region,
+    pub kind: TokenKind,
+    /// Whether this is where the name is defined, rather than a use of it
+    pub definition: bool,
+}
+
+/// The names in the home module, in source order. A qualified name like `Str.concat` is two
+/// tokens, one for the module and one for the name after the dot.
+///
+/// Names in the module header, and the names of the types a module defines, are not included.
+pub fn semantic_tokens(loaded: &LoadedModule) -> Vec<SemanticToken> {
+    let home = loaded.module_id;
+    let (decls, src) = match (
+        loaded.declarations_by_id.get(&home),
+        loaded.sources.get(&home),
+    ) {
+        (Some(decls), Some((_, src))) => (decls, &**src),
+        _ => return Vec::new(),
+    };
+
+    let mut classifier = Classifier {
+        src,
+        subs: loaded.solved.inner(),
+        interns: &loaded.interns,
+        parameters: parameters_of(decls),
+        effect_types: effect_types(loaded),
+        tokens: Vec::new(),
+    };
+
+    classifier.visit_decls(decls);
+
+    // Annotations don't keep the regions of the types they mention, but the lookups do.
+    if let Some(usages) = loaded.usages.get(&home) {
+        for (symbol, regions) in usages.iter() {
+            let kind = match type_kind(symbol, &loaded.abilities_store, &loaded.interns) {
+                Some(kind) => kind,
+                None => continue,
+            };
+
+            for region in regions {
+                classifier.push_name(*region, symbol, kind, false);
+            }
+        }
+    }
+
+    let mut tokens = classifier.tokens;
+
+    tokens.sort_by_key(|token| token.region.start());
+    tokens.dedup_by_key(|token| token.region.start());
+
+    tokens
+}
+
+fn type_kind(
+    symbol: Symbol,
+    abilities_store: &AbilitiesStore,
+    interns: &Interns,
+) -> Option<TokenKind> {
+    if abilities_store.is_ability(symbol) {
+        Some(TokenKind::Ability)
+    } else if symbol.as_str(interns).starts_with(char::is_uppercase) {
+        Some(TokenKind::Type)
+    } else {
+        None
+    }
+}
+
+/// The symbols bound by the arguments of the module's top-level functions
+fn parameters_of(decls: &Declarations) -> MutSet<Symbol> {
+    decls
+        .function_bodies
+        .iter()
+        .flat_map(|function_def| function_def.value.arguments.iter())
+        .flat_map(|(_, _, argument)| symbols_introduced_from_pattern(argument))
+        .map(|symbol| symbol.value)
+        .collect()
+}
+
+/// The effect types of the loaded hosted modules. The functions a hosted module generates wrap
+/// their call to the host in one of these.
+fn effect_types(loaded: &LoadedModule) -> MutSet<Symbol> {
+    let mut finder = EffectTypeFinder {
+        effect_types: MutSet::default(),
+    };
+
+    for decls in loaded.declarations_by_id.values() {
+        finder.visit_decls(decls);
+    }
+
+    return finder.effect_types;
+
+    struct EffectTypeFinder {
+        effect_types: MutSet<Symbol>,
+    }
+
+    impl Visitor for EffectTypeFinder {
+        fn visit_expr(&mut self, expr: &Expr, _region: Region, var: Variable) {
+            if let Expr::OpaqueRef { name, argument, .. } = expr {
+                if let Expr::Closure(closure) = &argument.1.value {
+                    if matches!(closure.loc_body.value, Expr::ForeignCall { .. }) {
+                        self.effect_types.insert(*name);
+                    }
+                }
+            }
+
+            walk_expr(self, expr, var);
+        }
+    }
+}
+
+struct Classifier<'a> {
+    src: &'a str,
+    subs: &'a Subs,
+    interns: &'a Interns,
+    parameters: MutSet<Symbol>,
+    effect_types: MutSet<Symbol>,
+    tokens: Vec<SemanticToken>,
+}
+
+impl<'a> Classifier<'a> {
+    fn value_kind(&self, symbol: Symbol, var: Variable) -> TokenKind {
+        if self.parameters.contains(&symbol) {
+            return TokenKind::Parameter;
+        }
+
+        match self.function_return(var) {
+            Some(ret) if self.is_effect(ret) => TokenKind::EffectfulFunction,
+            Some(_) => TokenKind::Function,
+            None => TokenKind::Variable,
+        }
+    }
+
+    fn function_return(&self, mut var: Variable) -> Option<Variable> {
+        for _ in 0..MAX_ALIAS_DEPTH {
+            match self.subs.get_content_without_compacting(var) {
+                Content::Alias(_, _, real_var, _) => var = *real_var,
+                Content::Structure(FlatType::Func(_, _, ret)) => return Some(*ret),
+                _ => return None,
+            }
+        }
+
+        None
+    }
+
+    fn is_effect(&self, mut var: Variable) -> bool {
+        for _ in 0..MAX_ALIAS_DEPTH {
+            match self.subs.get_content_without_compacting(var) {
+                Content::Alias(symbol, _, _, _) if self.effect_types.contains(symbol) => {
+                    return true
+                }
+                Content::Alias(_, _, real_var, _) => var = *real_var,
+                _ => return false,
+            }
+        }
+
+        false
+    }
+
+    /// A (possibly qualified) name, which the region ends with. Regions of code the compiler
+    /// generated don't, so they get no tokens.
+    fn push_name(&mut self, region: Region, symbol: Symbol, kind: TokenKind, definition: bool) {
+        let text = self.text(region);
+        let name = symbol.as_str(self.interns);
+
+        let qualifier = match text.strip_suffix(name) {
+            Some(qualifier) => qualifier,
+            None => return,
+        };
+
+        let name_start = region.end().offset - name.len() as u32;
+
+        match qualifier.strip_suffix('.') {
+            Some(module) => self.push(
+                region.start().offset,
+                module.len(),
+                TokenKind::Module,
+                false,
+            ),
+            None if !qualifier.is_empty() => return,
+            None => {}
+        }
+
+        self.push(name_start, name.len(), kind, definition);
+    }
+
+    /// A name that the region starts with, e.g. a tag name at the start of a tag application
+    fn push_prefix(&mut self, region: Region, name: &str, kind: TokenKind, definition: bool) {
+        if self.text(region).starts_with(name) {
+            self.push(region.start().offset, name.len(), kind, definition);
+        }
+    }
+
+    fn push_opaque(&mut self, region: Region, opaque: Symbol) {
+        let name = format!("@{}", opaque.as_str(self.interns));
+
+        self.push_prefix(region, &name, TokenKind::Type, false);
+    }
+
+    fn push(&mut self, start: u32, len: usize, kind: TokenKind, definition: bool) {
+        if len == 0 {
+            return;
+        }
+
+        let region = Region::new(Position::new(start), Position::new(start + len as u32));
+
+        self.tokens.push(SemanticToken {
+            region,
+            kind,
+            definition,
+        });
+    }
+
+    fn text(&self, region: Region) -> &'a str {
+        let start = region.start().offset as usize;
+        let end = region.end().offset as usize;
+
+        self.src.get(start..end).unwrap_or("")
+    }
+}
+
+impl Visitor for Classifier<'_> {
+    fn visit_expr(&mut self, expr: &Expr, region: Region, var: Variable) {
+        match expr {
+            Expr::Var(symbol, _) | Expr::AbilityMember(symbol, _, _) => {
+                let kind = self.value_kind(*symbol, var);
+
+                self.push_name(region, *symbol, kind, false);
+            }
+            Expr::Tag { name, .. } | Expr::ZeroArgumentTag { name, .. } => {
+                self.push_prefix(region, name.0.as_str(), TokenKind::Tag, false);
+            }
+            Expr::OpaqueRef { name, .. } => self.push_opaque(region, *name),
+            Expr::Closure(closure) => {
+                // Before walking it, so the arguments are parameters by the time we see them
+                let arguments = closure.arguments.iter();
+
+                self.parameters.extend(
+                    arguments
+                        .flat_map(|(_, _, argument)| symbols_introduced_from_pattern(argument))
+                        .map(|symbol| symbol.value),
+                );
+            }
+            _ => {}
+        }
+
+        walk_expr(self, expr, var);
+    }
+
+    fn visit_pattern(&mut self, pattern: &Pattern, region: Region, opt_var: Option<Variable>) {
+        match pattern {
+            Pattern::Identifier(symbol)
+            | Pattern::Shadowed(_, _, symbol)
+            | Pattern::AbilityMemberSpecialization { ident: symbol, .. } => {
+                let kind = match opt_var {
+                    Some(var) => self.value_kind(*symbol, var),
+                    None if self.parameters.contains(symbol) => TokenKind::Parameter,
+                    None => TokenKind::Variable,
+                };
+
+                self.push_name(region, *symbol, kind, true);
+            }
+            Pattern::AppliedTag { tag_name, .. } => {
+                self.push_prefix(region, tag_name.0.as_str(), TokenKind::Tag, false);
+            }
+            Pattern::UnwrappedOpaque { opaque, .. } => self.push_opaque(region, *opaque),
+            _ => {}
+        }
+
+        walk_pattern(self, pattern);
+    }
+
+    fn visit_record_destruct(&mut self, destruct: &RecordDestruct, region: Region) {
+        // With a guard, e.g. `{ x: Foo y }`, the field name doesn't bind anything.
+        if !matches!(destruct.typ, DestructType::Guard(..)) {
+            let kind = self.value_kind(destruct.symbol, destruct.var);
+
+            self.push_prefix(region, destruct.label.as_str(), kind, true);
+        }
+
+        walk_record_destruct(self, destruct);
+    }
+}
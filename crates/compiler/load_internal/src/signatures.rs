@@ -0,0 +1,85 @@
+//! Inferred type signatures for top-level definitions that have no annotation.
+//!
+//! These are rendered from the solved types of a module, so they can be inserted
+//! verbatim above their definition (e.g. by `roc check --write-signatures`, or as
+//! the payload of an editor code action).
+use crate::file::LoadedModule;
+use roc_can::expr::DeclarationTag;
+use roc_module::symbol::Symbol;
+use roc_region::all::Region;
+use roc_types::pretty_print::{name_and_print_var, DebugPrint};
+use roc_types::subs::Content;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InferredSignature {
+    pub symbol: Symbol,
+    /// The region of the definition's name; the signature belongs on its own line right above it.
+    pub region: Region,
+    /// The full annotation line, e.g. `add : Num a, Num a -> Num a`
+    pub text: String,
+}
+
+/// The inferred signatures of all unannotated top-level values and functions of the home module,
+/// in source order.
+///
+/// Definitions whose type could not be inferred (because of a type error) and destructuring
+/// definitions are skipped; neither can be given a single valid annotation.
+pub fn inferred_signatures(loaded: &mut LoadedModule) -> Vec<InferredSignature> {
+    let home = loaded.module_id;
+    let declarations = match loaded.declarations_by_id.get(&home) {
+        Some(declarations) => declarations,
+        None => return Vec::new(),
+    };
+
+    let subs = loaded.solved.inner_mut();
+    let interns = &loaded.interns;
+    let mut signatures = Vec::new();
+
+    for index in 0..declarations.len() {
+        use DeclarationTag::*;
+
+        match declarations.declarations[index] {
+            Value | Function(_) | Recursive(_) | TailRecursive(_) => {}
+            Destructure(_) | MutualRecursion { .. } | Expectation | ExpectationFx => continue,
+        }
+
+        if declarations.annotations[index].is_some() {
+            continue;
+        }
+
+        let var = declarations.variables[index];
+        if matches!(subs.get_content_without_compacting(var), Content::Error) {
+            continue;
+        }
+
+        let loc_symbol = declarations.symbols[index];
+        let typ = name_and_print_var(var, subs, home, interns, DebugPrint::NOTHING);
+
+        signatures.push(InferredSignature {
+            symbol: loc_symbol.value,
+            region: loc_symbol.region,
+            text: format!("{} : {}", loc_symbol.value.as_str(interns), typ),
+        });
+    }
+
+    signatures.sort_by_key(|signature| signature.region.start());
+
+    signatures
+}
+
+/// Insert each signature on its own line above its definition, keeping the definition's indentation.
+pub fn insert_signatures(src: &str, signatures: &[InferredSignature]) -> String {
+    let mut output = src.to_string();
+
+    // Insert back to front, so earlier offsets stay valid.
+    for signature in signatures.iter().rev() {
+        let offset = signature.region.start().offset as usize;
+        let line_start = src[..offset].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let line = &src[line_start..];
+        let indent = &line[..line.len() - line.trim_start().len()];
+
+        output.insert_str(line_start, &format!("{}{}\n", indent, signature.text));
+    }
+
+    output
+}
@@ -0,0 +1,450 @@
+//! An index of the top-level definitions of every module in a workspace, for finding them by
+//! name, e.g. an editor's "go to symbol in workspace", or `roc docs --search`.
+//!
+//! Each module is indexed from loading it as the root module, because only the root module's
+//! solved types are kept, and those give the signatures of definitions that have no annotation.
+//! The index remembers a hash of the source it indexed each module from, so keeping it up to date
+//! only reloads the modules that changed. It's saved as a plain text file, one line per module
+//! and one per definition, so it survives between runs.
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::hash::{Hash, Hasher};
+use std::io;
+use std::path::{Path, PathBuf};
+
+use roc_can::expr::DeclarationTag;
+use roc_region::all::{Position, Region};
+use roc_types::pretty_print::{name_and_print_var, DebugPrint};
+use roc_types::subs::Content;
+use roc_types::types::AliasKind;
+
+use crate::file::LoadedModule;
+
+/// Bump this whenever the layout of the saved index changes
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SymbolKind {
+    Function,
+    Value,
+    /// A type alias, e.g. `Shape : [Circle F64, Square F64]`
+    Alias,
+    Opaque,
+    Ability,
+    AbilityMember,
+}
+
+impl SymbolKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SymbolKind::Function => "function",
+            SymbolKind::Value => "value",
+            SymbolKind::Alias => "alias",
+            SymbolKind::Opaque => "opaque",
+            SymbolKind::Ability => "ability",
+            SymbolKind::AbilityMember => "ability-member",
+        }
+    }
+
+    fn from_str(kind: &str) -> Option<Self> {
+        [
+            SymbolKind::Function,
+            SymbolKind::Value,
+            SymbolKind::Alias,
+            SymbolKind::Opaque,
+            SymbolKind::Ability,
+            SymbolKind::AbilityMember,
+        ]
+        .into_iter()
+        .find(|candidate| candidate.as_str() == kind)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedSymbol {
+    pub name: String,
+    pub kind: SymbolKind,
+    /// The type of a value or function, e.g. `Num a, Num a -> Num a`, or the first line of the
+    /// definition of a type or ability, e.g. `Age := U32`. Empty if the type couldn't be inferred.
+    pub signature: String,
+    /// The name of the module that defines it, e.g. `Dict`
+    pub module: String,
+    pub path: PathBuf,
+    /// The region of the name where it's defined
+    pub region: Region,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IndexedModule {
+    /// The hash of the source this module was indexed from
+    source_hash: u64,
+    symbols: Vec<IndexedSymbol>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SymbolIndex {
+    /// By the path of the module's file
+    modules: BTreeMap<PathBuf, IndexedModule>,
+}
+
+impl SymbolIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the module at this path was indexed from exactly this source
+    pub fn is_current(&self, path: &Path, src: &str) -> bool {
+        self.modules
+            .get(path)
+            .map_or(false, |module| module.source_hash == source_hash(src))
+    }
+
+    /// (Re-)index the root module of `loaded`, replacing whatever was indexed for its file before.
+    pub fn update(&mut self, loaded: &mut LoadedModule) {
+        let home = loaded.module_id;
+        let (path, src) = match loaded.sources.get(&home) {
+            Some((path, src)) => (path.clone(), src.clone()),
+            None => return,
+        };
+
+        let module = IndexedModule {
+            source_hash: source_hash(&src),
+            symbols: index_symbols(loaded, &path, &src),
+        };
+
+        self.modules.insert(path, module);
+    }
+
+    pub fn remove(&mut self, path: &Path) {
+        self.modules.remove(path);
+    }
+
+    /// Bring the index up to date with the given files: forget the modules that aren't among
+    /// them any more, and load the ones whose source changed since they were indexed. A file
+    /// that fails to load keeps whatever was indexed for it before.
+    pub fn refresh(
+        &mut self,
+        paths: &[PathBuf],
+        mut load: impl FnMut(&Path) -> Option<LoadedModule>,
+    ) {
+        self.modules.retain(|path, _| paths.contains(path));
+
+        for path in paths {
+            let src = match std::fs::read_to_string(path) {
+                Ok(src) => src,
+                Err(_) => {
+                    self.remove(path);
+
+                    continue;
+                }
+            };
+
+            if self.is_current(path, &src) {
+                continue;
+            }
+
+            if let Some(mut loaded) = load(path) {
+                self.update(&mut loaded);
+            }
+        }
+    }
+
+    pub fn symbols(&self) -> impl Iterator<Item = &IndexedSymbol> {
+        self.modules
+            .values()
+            .flat_map(|module| module.symbols.iter())
+    }
+
+    /// The symbols matching the query, best matches first. Like the search box of the HTML docs,
+    /// it's case-insensitive and ranks exact names first, then names starting with the query,
+    /// then qualified names containing it, then signatures mentioning it. An empty query
+    /// matches everything.
+    pub fn search(&self, query: &str) -> Vec<&IndexedSymbol> {
+        let query = query.to_lowercase();
+
+        let mut matches: Vec<(usize, &IndexedSymbol)> = self
+            .symbols()
+            .filter_map(|symbol| Some((rank(symbol, &query)?, symbol)))
+            .collect();
+
+        matches.sort_by(|(rank_a, a), (rank_b, b)| {
+            rank_a
+                .cmp(rank_b)
+                .then_with(|| a.name.cmp(&b.name))
+                .then_with(|| a.module.cmp(&b.module))
+        });
+
+        matches.into_iter().map(|(_, symbol)| symbol).collect()
+    }
+
+    pub fn save(&self, file: &Path) -> io::Result<()> {
+        let mut buf = format!("roc-symbol-index {}\n", FORMAT_VERSION);
+
+        for (path, module) in self.modules.iter() {
+            let path = match path.to_str() {
+                Some(path) if !path.contains(['\t', '\n']) => path,
+                // It couldn't be read back, so it'll just be indexed again next time.
+                _ => continue,
+            };
+
+            buf.push_str(&format!("module\t{}\t{:x}\n", path, module.source_hash));
+
+            for symbol in module.symbols.iter() {
+                buf.push_str(&format!(
+                    "symbol\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                    symbol.kind.as_str(),
+                    symbol.name,
+                    symbol.module,
+                    symbol.region.start().offset,
+                    symbol.region.end().offset,
+                    symbol.signature,
+                ));
+            }
+        }
+
+        if let Some(dir) = file.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+
+        std::fs::write(file, buf)
+    }
+
+    /// Read an index written by [SymbolIndex::save]. An index written by a different version of
+    /// the compiler, or that can't be read at all, is an empty one.
+    pub fn load(file: &Path) -> Self {
+        std::fs::read_to_string(file)
+            .ok()
+            .and_then(|contents| Self::parse(&contents))
+            .unwrap_or_default()
+    }
+
+    fn parse(contents: &str) -> Option<Self> {
+        let mut lines = contents.lines();
+
+        if lines.next()? != format!("roc-symbol-index {}", FORMAT_VERSION) {
+            return None;
+        }
+
+        let mut index = Self::new();
+        let mut current: Option<(PathBuf, IndexedModule)> = None;
+
+        for line in lines {
+            let fields: Vec<&str> = line.split('\t').collect();
+
+            match fields.as_slice() {
+                ["module", path, hash] => {
+                    if let Some((path, module)) = current.take() {
+                        index.modules.insert(path, module);
+                    }
+
+                    let module = IndexedModule {
+                        source_hash: u64::from_str_radix(hash, 16).ok()?,
+                        symbols: Vec::new(),
+                    };
+
+                    current = Some((PathBuf::from(path), module));
+                }
+                ["symbol", kind, name, module_name, start, end, signature] => {
+                    let (path, module) = current.as_mut()?;
+
+                    module.symbols.push(IndexedSymbol {
+                        name: name.to_string(),
+                        kind: SymbolKind::from_str(kind)?,
+                        signature: signature.to_string(),
+                        module: module_name.to_string(),
+                        path: path.clone(),
+                        region: Region::new(
+                            Position::new(start.parse().ok()?),
+                            Position::new(end.parse().ok()?),
+                        ),
+                    });
+                }
+                _ => return None,
+            }
+        }
+
+        if let Some((path, module)) = current {
+            index.modules.insert(path, module);
+        }
+
+        Some(index)
+    }
+}
+
+/// Where to save the index of the workspace at `root`, next to the package cache, e.g.
+/// `~/.cache/roc/symbols/<hash of root>`
+pub fn index_file(roc_cache_dir: &Path, root: &Path) -> PathBuf {
+    let mut hasher = DefaultHasher::new();
+
+    root.hash(&mut hasher);
+
+    roc_cache_dir
+        .with_file_name("symbols")
+        .join(format!("{:016x}", hasher.finish()))
+}
+
+fn source_hash(src: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+
+    src.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+fn rank(symbol: &IndexedSymbol, query: &str) -> Option<usize> {
+    let name = symbol.name.to_lowercase();
+    let qualified_name = format!("{}.{}", symbol.module, symbol.name).to_lowercase();
+
+    if name == query || qualified_name == query {
+        Some(0)
+    } else if name.starts_with(query) || qualified_name.starts_with(query) {
+        Some(1)
+    } else if qualified_name.contains(query) {
+        Some(2)
+    } else if symbol.signature.to_lowercase().contains(query) {
+        Some(3)
+    } else {
+        None
+    }
+}
+
+/// The top-level definitions of the root module, in source order
+fn index_symbols(loaded: &mut LoadedModule, path: &Path, src: &str) -> Vec<IndexedSymbol> {
+    let home = loaded.module_id;
+    let module = loaded.interns.module_name(home).as_str().to_string();
+    let interns = &loaded.interns;
+    let subs = loaded.solved.inner_mut();
+
+    let mut symbols = Vec::new();
+    let mut push = |name: &str, kind, signature: String, region: Region| {
+        // Skip what the compiler generated, whose regions aren't the name's.
+        if text(src, region) == name {
+            symbols.push(IndexedSymbol {
+                name: name.to_string(),
+                kind,
+                signature: one_line(&signature),
+                module: module.clone(),
+                path: path.to_path_buf(),
+                region,
+            });
+        }
+    };
+
+    if let Some(decls) = loaded.declarations_by_id.get(&home) {
+        for index in 0..decls.len() {
+            use DeclarationTag::*;
+
+            let kind = match decls.declarations[index] {
+                Value => SymbolKind::Value,
+                Function(_) | Recursive(_) | TailRecursive(_) => SymbolKind::Function,
+                Destructure(_) | MutualRecursion { .. } | Expectation | ExpectationFx => continue,
+            };
+
+            let loc_symbol = decls.symbols[index];
+            let var = decls.variables[index];
+
+            let signature = match subs.get_content_without_compacting(var) {
+                Content::Error => String::new(),
+                _ => name_and_print_var(var, subs, home, interns, DebugPrint::NOTHING),
+            };
+
+            push(
+                loc_symbol.value.as_str(interns),
+                kind,
+                signature,
+                loc_symbol.region,
+            );
+        }
+    }
+
+    for (symbol, alias) in loaded.exposed_aliases.iter() {
+        if symbol.module_id() != home {
+            continue;
+        }
+
+        let kind = match alias.kind {
+            AliasKind::Structural => SymbolKind::Alias,
+            AliasKind::Opaque => SymbolKind::Opaque,
+        };
+
+        push(
+            symbol.as_str(interns),
+            kind,
+            first_line(src, alias.region),
+            alias.region,
+        );
+    }
+
+    let abilities_store = &loaded.abilities_store;
+
+    for (ability, members) in abilities_store.iter_abilities() {
+        if ability.module_id() != home {
+            continue;
+        }
+
+        let name = ability.as_str(interns);
+        let mut ability_start = None;
+
+        for member in members {
+            let data = match abilities_store.member_def(*member) {
+                Some(data) => data,
+                None => continue,
+            };
+
+            let signature = name_and_print_var(
+                data.signature_var(),
+                subs,
+                home,
+                interns,
+                DebugPrint::NOTHING,
+            );
+
+            push(
+                member.as_str(interns),
+                SymbolKind::AbilityMember,
+                signature,
+                data.region,
+            );
+
+            let start = data.region.start().offset;
+            ability_start = Some(ability_start.map_or(start, |first: u32| first.min(start)));
+        }
+
+        // Abilities don't keep the region of their name, but it comes right before their
+        // first member, e.g. `Hash has hash : a -> U64 | a has Hash`.
+        let before_members = &src[..ability_start.unwrap_or(0) as usize];
+
+        if let Some(offset) = before_members.rfind(name) {
+            let start = offset as u32;
+            let region = Region::new(
+                Position::new(start),
+                Position::new(start + name.len() as u32),
+            );
+
+            push(name, SymbolKind::Ability, first_line(src, region), region);
+        }
+    }
+
+    symbols.sort_by_key(|symbol| symbol.region.start());
+
+    symbols
+}
+
+fn text(src: &str, region: Region) -> &str {
+    let start = region.start().offset as usize;
+    let end = region.end().offset as usize;
+
+    src.get(start..end).unwrap_or("")
+}
+
+/// The rest of the line the region starts on, e.g. the whole definition of a short type
+fn first_line(src: &str, region: Region) -> String {
+    let rest = src.get(region.start().offset as usize..).unwrap_or("");
+
+    rest.lines().next().unwrap_or("").to_string()
+}
+
+/// Signatures go on one line of the saved index, and on one line of search results.
+fn one_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
@@ -0,0 +1,145 @@
+//! Solving a module's types is the slowest part of loading it, so when an interface module
+//! type-checks without problems we write its solved types to disk. The next time it's loaded
+//! unchanged, they're read back the same way the builtins' cached types are, and the module
+//! skips constraint generation and solving.
+//!
+//! Only the solved types are cached. The module is still parsed and canonicalized, because its
+//! canonical IR is what the later phases (e.g. finding specializations) work on, and that has no
+//! serialized form.
+use std::hash::{Hash, Hasher};
+use std::io::Write;
+use std::path::PathBuf;
+
+use roc_can::module::TypeState;
+use roc_collections::{MutMap, StableHasher};
+use roc_module::symbol::ModuleId;
+use roc_packaging::cache::RocCacheDir;
+
+/// Bump this whenever the layout of the cached files changes
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Clone)]
+pub(crate) struct TypeCache {
+    dir: PathBuf,
+    /// Identifies the compiler that wrote the cached types
+    compiler: u64,
+}
+
+impl TypeCache {
+    /// Types are only cached next to a persistent package cache, so e.g. tests never share them.
+    pub fn from_cache_dir(roc_cache_dir: RocCacheDir<'_>) -> Option<Self> {
+        match roc_cache_dir {
+            // e.g. ~/.cache/roc/modules
+            RocCacheDir::Persistent(dir) => Some(Self::new(dir.with_file_name("modules"))),
+            _ => None,
+        }
+    }
+
+    fn new(dir: PathBuf) -> Self {
+        let mut hasher = StableHasher::new();
+
+        hasher.write_compiler();
+        hasher.write_u32(FORMAT_VERSION);
+
+        Self {
+            dir,
+            compiler: hasher.finish(),
+        }
+    }
+
+    /// The key changes whenever anything the module's types depend on does: the compiler, the
+    /// module's source, and the keys of the modules it imports. Module ids are part of it too,
+    /// because the cached types refer to modules by id, and ids depend on the order modules
+    /// happened to be loaded in.
+    ///
+    /// Returns None if one of the imports has no key, since then we can't tell whether it changed.
+    pub fn key(
+        &self,
+        module_id: ModuleId,
+        src: &str,
+        imports: impl Iterator<Item = ModuleId>,
+        keys: &MutMap<ModuleId, u64>,
+    ) -> Option<u64> {
+        let mut hasher = StableHasher::new();
+
+        hasher.write_u64(self.compiler);
+        module_id.hash(&mut hasher);
+        hasher.write_str(src);
+
+        // The imports come from a map, so combine them in a way that doesn't depend on order.
+        let mut imports_hash: u64 = 0;
+
+        for import in imports {
+            let import_key = if import.is_builtin() {
+                0
+            } else {
+                *keys.get(&import)?
+            };
+
+            let mut import_hasher = StableHasher::new();
+            import.hash(&mut import_hasher);
+            import_hasher.write_u64(import_key);
+
+            imports_hash = imports_hash.wrapping_add(import_hasher.finish());
+        }
+
+        hasher.write_u64(imports_hash);
+
+        Some(hasher.finish())
+    }
+
+    fn path(&self, key: u64) -> PathBuf {
+        self.dir.join(format!("{:016x}.dat", key))
+    }
+
+    /// The types cached under the given key, if there are any and they're intact
+    pub fn read(&self, key: u64) -> Option<TypeState> {
+        let bytes = std::fs::read(self.path(key)).ok()?;
+
+        if bytes.len() < CHECKSUM_BYTES {
+            return None;
+        }
+
+        let (bytes, checksum) = bytes.split_at(bytes.len() - CHECKSUM_BYTES);
+
+        if checksum != self::checksum(bytes) {
+            return None;
+        }
+
+        let (state, len) = TypeState::deserialize(bytes);
+
+        if len == bytes.len() {
+            Some(state)
+        } else {
+            None
+        }
+    }
+
+    /// Cache the types under the given key. The file is written to a temporary file first, so
+    /// a compiler that's interrupted, or another one reading at the same time, never sees
+    /// half of it.
+    pub fn write(&self, key: u64, state: &TypeState) -> std::io::Result<()> {
+        let mut bytes = Vec::new();
+        state.serialize(&mut bytes)?;
+
+        let checksum = checksum(&bytes);
+        bytes.extend_from_slice(&checksum);
+
+        std::fs::create_dir_all(&self.dir)?;
+
+        let mut file = tempfile::NamedTempFile::new_in(&self.dir)?;
+        file.write_all(&bytes)?;
+        file.persist(self.path(key)).map_err(|err| err.error)?;
+
+        Ok(())
+    }
+}
+
+const CHECKSUM_BYTES: usize = 8;
+
+fn checksum(bytes: &[u8]) -> [u8; CHECKSUM_BYTES] {
+    let mut hasher = StableHasher::new();
+    hasher.write(bytes);
+
+    hasher.finish().to_le_bytes()
+}
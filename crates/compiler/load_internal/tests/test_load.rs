@@ -17,10 +17,12 @@ mod helpers;
 use crate::helpers::fixtures_dir;
 use bumpalo::Bump;
 use roc_can::module::ExposedByModule;
+use roc_collections::all::MutMap;
 use roc_load_internal::file::{ExecutionMode, LoadConfig, Threading};
-use roc_load_internal::file::{LoadResult, LoadStart, LoadedModule, LoadingProblem};
+use roc_load_internal::file::{LoadResult, LoadStart, LoadedModule, LoadingProblem, ModuleTiming};
 use roc_module::ident::ModuleName;
 use roc_module::symbol::{Interns, ModuleId};
+use roc_mono::ir::OptLevel;
 use roc_packaging::cache::RocCacheDir;
 use roc_problem::can::Problem;
 use roc_region::all::LineInfo;
@@ -28,6 +30,7 @@ use roc_reporting::report::RenderTarget;
 use roc_reporting::report::RocDocAllocator;
 use roc_reporting::report::{can_problem, DEFAULT_PALETTE};
 use roc_target::TargetInfo;
+use roc_types::num::FloatWidth;
 use roc_types::pretty_print::name_and_print_var;
 use roc_types::pretty_print::DebugPrint;
 use std::collections::HashMap;
@@ -39,13 +42,29 @@ fn load_and_typecheck(
     exposed_types: ExposedByModule,
     target_info: TargetInfo,
 ) -> Result<LoadedModule, LoadingProblem> {
+    load_and_typecheck_with_cache(
+        arena,
+        filename,
+        exposed_types,
+        target_info,
+        RocCacheDir::Disallowed,
+    )
+}
+
+fn load_and_typecheck_with_cache<'a>(
+    arena: &'a Bump,
+    filename: PathBuf,
+    exposed_types: ExposedByModule,
+    target_info: TargetInfo,
+    roc_cache_dir: RocCacheDir<'_>,
+) -> Result<LoadedModule, LoadingProblem<'a>> {
     use LoadResult::*;
 
     let load_start = LoadStart::from_path(
         arena,
         filename,
         RenderTarget::Generic,
-        RocCacheDir::Disallowed,
+        roc_cache_dir,
         DEFAULT_PALETTE,
     )?;
     let load_config = LoadConfig {
@@ -54,6 +73,8 @@ fn load_and_typecheck(
         palette: DEFAULT_PALETTE,
         threading: Threading::Single,
         exec_mode: ExecutionMode::Check,
+        frac_default: FloatWidth::F64,
+        opt_level: OptLevel::Development,
     };
 
     match roc_load_internal::file::load(
@@ -61,7 +82,7 @@ fn load_and_typecheck(
         load_start,
         exposed_types,
         Default::default(), // these tests will re-compile the builtins
-        RocCacheDir::Disallowed,
+        roc_cache_dir,
         load_config,
     )? {
         Monomorphized(_) => unreachable!(""),
@@ -537,6 +558,157 @@ fn iface_dep_types() {
     );
 }
 
+#[test]
+fn iface_dep_types_from_type_cache() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let packages_dir = cache_dir.path().join("packages");
+    let filename = fixtures_dir()
+        .join("interface_with_deps")
+        .join("Primary.roc");
+
+    // The first load solves the modules and caches their types; the second reads them back.
+    for load in 0..2 {
+        let arena = Bump::new();
+        let loaded_module = load_and_typecheck_with_cache(
+            &arena,
+            filename.clone(),
+            Default::default(),
+            TARGET_INFO,
+            RocCacheDir::Persistent(&packages_dir),
+        )
+        .unwrap();
+
+        let from_cache = types_from_cache(&loaded_module.timings);
+        let cached = std::fs::read_dir(cache_dir.path().join("modules"))
+            .unwrap()
+            .count();
+
+        if load == 0 {
+            assert_eq!(from_cache, 0);
+        } else {
+            // every module whose types were cached is a hit
+            assert!(cached > 0);
+            assert_eq!(from_cache, cached);
+        }
+
+        expect_types(
+            loaded_module,
+            hashmap! {
+                "blah2" => "Float *",
+                "blah3" => "Str",
+                "str" => "Str",
+                "alwaysThree" => "* -> Float *",
+                "identity" => "a -> a",
+                "z" => "Float *",
+                "w" => "Dep1.Identity {}",
+                "succeed" => "a -> Dep1.Identity a",
+                "yay" => "Res.Res {} err",
+                "withDefault" => "Res.Res a err, a -> a",
+            },
+        );
+    }
+}
+
+/// How many modules' solved types were read from the on-disk type cache
+fn types_from_cache(timings: &MutMap<ModuleId, ModuleTiming>) -> usize {
+    timings
+        .values()
+        .filter(|timing| timing.solved_types_from_cache)
+        .count()
+}
+
+#[test]
+fn mono_from_warm_type_cache() {
+    let cache_dir = tempfile::tempdir().unwrap();
+    let packages_dir = cache_dir.path().join("packages");
+    let project = tempfile::tempdir().unwrap();
+
+    std::fs::write(
+        project.path().join("Dep.roc"),
+        indoc!(
+            r#"
+            interface Dep exposes [double, pair] imports []
+
+            double : Num a -> Num a
+            double = \n -> n * 2
+
+            pair : a -> { first : a, second : a }
+            pair = \x -> { first: x, second: x }
+            "#
+        ),
+    )
+    .unwrap();
+
+    let filename = project.path().join("Main.roc");
+    std::fs::write(
+        &filename,
+        indoc!(
+            r#"
+            interface Main exposes [] imports [Dep]
+
+            expect Dep.double 21 == 42
+
+            expect (Dep.pair "hi").second == "hi"
+            "#
+        ),
+    )
+    .unwrap();
+
+    // The mono IR of every procedure, and how many modules' types came from the cache
+    let load = || {
+        let arena = Bump::new();
+        let load_start = LoadStart::from_path(
+            &arena,
+            filename.clone(),
+            RenderTarget::Generic,
+            RocCacheDir::Persistent(&packages_dir),
+            DEFAULT_PALETTE,
+        )
+        .unwrap();
+        let load_config = LoadConfig {
+            target_info: TARGET_INFO,
+            render: RenderTarget::Generic,
+            palette: DEFAULT_PALETTE,
+            threading: Threading::Single,
+            exec_mode: ExecutionMode::Test,
+            frac_default: FloatWidth::F64,
+            opt_level: OptLevel::Development,
+        };
+
+        let loaded = match roc_load_internal::file::load(
+            &arena,
+            load_start,
+            Default::default(),
+            Default::default(),
+            RocCacheDir::Persistent(&packages_dir),
+            load_config,
+        ) {
+            Ok(LoadResult::Monomorphized(loaded)) => loaded,
+            Ok(LoadResult::TypeChecked(_)) => unreachable!(),
+            Err(problem) => panic!("{:?}", problem),
+        };
+
+        let mut procs: Vec<String> = loaded
+            .procedures
+            .values()
+            .map(|proc| proc.to_pretty(&loaded.layout_interner, 200, false))
+            .collect();
+        procs.sort();
+
+        (procs, types_from_cache(&loaded.timings))
+    };
+
+    let (cold_procs, cold_from_cache) = load();
+    let (warm_procs, warm_from_cache) = load();
+
+    assert_eq!(cold_from_cache, 0);
+    assert!(warm_from_cache > 0);
+
+    // specializing the functions of Dep works the same with its types read back from the cache
+    assert!(!cold_procs.is_empty());
+    assert_eq!(cold_procs, warm_procs);
+}
+
 #[test]
 fn app_dep_types() {
     let subs_by_module = Default::default();
@@ -572,6 +744,479 @@ fn imported_dep_regression() {
     );
 }
 
+#[test]
+fn inferred_signatures_for_unannotated_defs() {
+    use roc_load_internal::signatures::{inferred_signatures, insert_signatures};
+
+    let modules = vec![(
+        "Main.roc",
+        indoc!(
+            r#"
+            interface Main exposes [twice, greeting, answer] imports []
+
+            twice = \f, x -> f (f x)
+
+            greeting : Str
+            greeting = "hi"
+
+            answer = Str.countGraphemes greeting
+            "#
+        ),
+    )];
+
+    let mut loaded_module =
+        multiple_modules("inferred_signatures_for_unannotated_defs", modules).unwrap();
+    let signatures = inferred_signatures(&mut loaded_module);
+    let (_, src) = &loaded_module.sources[&loaded_module.module_id];
+
+    assert_eq!(
+        insert_signatures(src, &signatures).trim_end(),
+        indoc!(
+            r#"
+            interface Main exposes [twice, greeting, answer] imports []
+
+            twice : (a -> a), a -> a
+            twice = \f, x -> f (f x)
+
+            greeting : Str
+            greeting = "hi"
+
+            answer : Nat
+            answer = Str.countGraphemes greeting"#
+        )
+    );
+}
+
+#[test]
+fn docs_for_unannotated_defs() {
+    use roc_load_internal::docs::{type_annotation_from_str, DocEntry, TypeAnnotation};
+
+    let modules = vec![(
+        "Main.roc",
+        indoc!(
+            r#"
+            interface Main exposes [twice, greeting] imports []
+
+            ## Applies a function twice
+            twice = \f, x -> f (f x)
+
+            greeting : Str
+            greeting = "hi"
+            "#
+        ),
+    )];
+
+    let loaded_module = multiple_modules("docs_for_unannotated_defs", modules).unwrap();
+    let module_docs = &loaded_module.documentation[&loaded_module.module_id];
+
+    let doc_defs: Vec<_> = module_docs
+        .entries
+        .iter()
+        .filter_map(|entry| match entry {
+            DocEntry::DocDef(doc_def) => Some(doc_def),
+            DocEntry::DetachedDoc(_) => None,
+        })
+        .collect();
+
+    // Unannotated defs get an entry too, so the docs can show their inferred type,
+    // but annotated defs only get the one from their annotation.
+    assert_eq!(
+        doc_defs
+            .iter()
+            .map(|doc_def| doc_def.name.as_str())
+            .collect::<Vec<_>>(),
+        ["twice", "greeting"]
+    );
+    assert!(matches!(
+        doc_defs[0].type_annotation,
+        TypeAnnotation::NoTypeAnn
+    ));
+    assert_eq!(
+        doc_defs[0].docs.as_deref().map(str::trim),
+        Some("Applies a function twice")
+    );
+
+    match type_annotation_from_str(" (a -> a), a -> a") {
+        Some(TypeAnnotation::Function { args, output }) => {
+            assert_eq!(args.len(), 2);
+            assert!(matches!(args[0], TypeAnnotation::Function { .. }));
+            assert!(matches!(*output, TypeAnnotation::BoundVariable(ref var) if var == "a"));
+        }
+        other => panic!("expected a function type, got {:?}", other),
+    }
+    assert!(type_annotation_from_str("List a ->").is_none());
+}
+
+#[test]
+fn hover_shows_type_definition_and_docs() {
+    use roc_load_internal::query::{definition_at, hover_at};
+
+    let modules = vec![(
+        "Main.roc",
+        indoc!(
+            r#"
+            interface Main exposes [greeting, answer] imports []
+
+            ## Says hi
+            greeting = "hi"
+
+            answer = Str.countGraphemes greeting
+            "#
+        ),
+    )];
+
+    let mut loaded_module =
+        multiple_modules("hover_shows_type_definition_and_docs", modules).unwrap();
+    let home = loaded_module.module_id;
+    let src = loaded_module.sources[&home].1.to_string();
+    let use_offset = src.rfind("greeting").unwrap() as u32;
+    let def_offset = src.find("greeting =").unwrap() as u32;
+
+    let hover = hover_at(&mut loaded_module, use_offset + 2).unwrap();
+
+    assert_eq!(hover.typ, "Str");
+    assert_eq!(hover.region.start().offset, use_offset);
+    assert_eq!(hover.docs.as_deref().map(str::trim), Some("Says hi"));
+
+    let definition = hover.definition.unwrap();
+    assert_eq!(definition.module_id, home);
+    assert_eq!(definition.region.start().offset, def_offset);
+    assert_eq!(definition_at(&loaded_module, use_offset), Some(definition));
+
+    let hover = hover_at(&mut loaded_module, src.find("Str.").unwrap() as u32).unwrap();
+    assert_eq!(hover.typ, "Str -> Nat");
+}
+
+#[test]
+fn references_and_rename_across_modules() {
+    use roc_load_internal::query::{check_rename, references, RenameProblem};
+
+    let modules = vec![
+        (
+            "Dep",
+            indoc!(
+                r#"
+                interface Dep exposes [double] imports []
+
+                double = \x -> x * 2
+                "#
+            ),
+        ),
+        (
+            "Main.roc",
+            indoc!(
+                r#"
+                interface Main exposes [four, eight] imports [Dep.{ double }]
+
+                four = double 2
+
+                eight = Dep.double four
+                "#
+            ),
+        ),
+    ];
+
+    let mut loaded_module =
+        multiple_modules("references_and_rename_across_modules", modules).unwrap();
+    let home = loaded_module.module_id;
+    let dep = loaded_module.interns.module_id(&"Dep".into());
+    let double = loaded_module.interns.symbol(dep, "double".into());
+    let main_src = loaded_module.sources[&home].1.to_string();
+    let dep_src = loaded_module.sources[&dep].1.to_string();
+
+    let offsets = |locations: Vec<roc_load_internal::query::Location>| {
+        locations
+            .iter()
+            .map(|location| (location.module_id, location.region.start().offset as usize))
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(
+        offsets(references(&loaded_module, double)),
+        vec![
+            (home, main_src.find("double 2").unwrap()),
+            (home, main_src.find("double four").unwrap()),
+        ]
+    );
+
+    assert_eq!(
+        offsets(check_rename(&loaded_module, double, "twice").unwrap()),
+        vec![
+            (dep, dep_src.find("double =").unwrap()),
+            (home, main_src.find("double 2").unwrap()),
+            (home, main_src.find("double four").unwrap()),
+        ]
+    );
+
+    assert_eq!(
+        check_rename(&loaded_module, double, "four"),
+        Err(RenameProblem::NameTaken {
+            module_id: home,
+            existing: loaded_module.interns.symbol(home, "four".into()),
+        })
+    );
+    assert_eq!(
+        check_rename(&loaded_module, double, "Double"),
+        Err(RenameProblem::InvalidName)
+    );
+    assert_eq!(
+        check_rename(&loaded_module, double, "double"),
+        Ok(Vec::new())
+    );
+}
+
+#[test]
+fn rename_types_and_abilities() {
+    use roc_load_internal::query::{check_rename, Location};
+
+    let modules = vec![(
+        "Main.roc",
+        indoc!(
+            r#"
+            interface Main exposes [first, swap, describe] imports []
+
+            Pair a b : { first : a, second : b }
+
+            first : Pair a b -> a
+            first = \pair -> pair.first
+
+            swap : Pair a b -> Pair b a
+            swap = \{ first: x, second: y } -> { first: y, second: x }
+
+            Describe has
+                describe : a -> Str | a has Describe
+            "#
+        ),
+    )];
+
+    let loaded_module = multiple_modules("rename_types_and_abilities", modules).unwrap();
+    let home = loaded_module.module_id;
+    let src = loaded_module.sources[&home].1.to_string();
+
+    // Only the names are replaced, even where a type is applied to arguments
+    let replaced = |locations: Vec<Location>| {
+        locations
+            .iter()
+            .map(|location| {
+                let start = location.region.start().offset as usize;
+                let end = location.region.end().offset as usize;
+
+                (start, &src[start..end])
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let pair = loaded_module.interns.symbol(home, "Pair".into());
+    assert_eq!(
+        replaced(check_rename(&loaded_module, pair, "Tuple").unwrap()),
+        src.match_indices("Pair").collect::<Vec<_>>()
+    );
+
+    let describe = loaded_module.interns.symbol(home, "Describe".into());
+    assert_eq!(
+        replaced(check_rename(&loaded_module, describe, "Show").unwrap()),
+        src.match_indices("Describe").collect::<Vec<_>>()
+    );
+}
+
+#[test]
+fn semantic_tokens_classify_names() {
+    use roc_load_internal::semantic_tokens::{semantic_tokens, TokenKind::*};
+
+    let modules = vec![(
+        "Main.roc",
+        indoc!(
+            r#"
+            interface Main exposes [describe] imports []
+
+            Shape : [Circle F64, Square F64]
+
+            describe : Shape -> Str
+            describe = \shape ->
+                when shape is
+                    Circle radius -> Num.toStr radius
+                    Square side -> Str.concat "square " (Num.toStr side)
+            "#
+        ),
+    )];
+
+    let loaded_module = multiple_modules("semantic_tokens_classify_names", modules).unwrap();
+    let home = loaded_module.module_id;
+    let src = loaded_module.sources[&home].1.to_string();
+
+    let tokens = semantic_tokens(&loaded_module);
+
+    // In source order, without overlaps
+    assert!(tokens
+        .windows(2)
+        .all(|pair| pair[0].region.end() <= pair[1].region.start()));
+
+    let tokens: Vec<_> = tokens
+        .into_iter()
+        .map(|token| {
+            let start = token.region.start().offset as usize;
+            let end = token.region.end().offset as usize;
+
+            (&src[start..end], token.kind, token.definition)
+        })
+        .collect();
+
+    for expected in [
+        ("F64", Type, false),
+        ("Shape", Type, false),
+        ("describe", Function, true),
+        ("shape", Parameter, true),
+        ("shape", Parameter, false),
+        ("Circle", Tag, false),
+        ("radius", Variable, true),
+        ("Num", Module, false),
+        ("toStr", Function, false),
+        ("Str", Module, false),
+        ("concat", Function, false),
+    ] {
+        assert!(
+            tokens.contains(&expected),
+            "{:?} not in {:?}",
+            expected,
+            tokens
+        );
+    }
+}
+
+#[test]
+fn symbol_index_search_and_round_trip() {
+    use roc_load_internal::symbol_index::{SymbolIndex, SymbolKind};
+
+    let modules = vec![(
+        "Main.roc",
+        indoc!(
+            r#"
+            interface Main exposes [Shape, Age, Describe, area, origin] imports []
+
+            Shape : [Circle F64, Square F64]
+
+            Age := U32
+
+            Describe has describe : a -> Str | a has Describe
+
+            area = \shape ->
+                when shape is
+                    Circle radius -> 3.14 * radius * radius
+                    Square side -> side * side
+
+            origin = { x: 0, y: 0 }
+            "#
+        ),
+    )];
+
+    let mut loaded_module =
+        multiple_modules("symbol_index_search_and_round_trip", modules).unwrap();
+    let home = loaded_module.module_id;
+    let (path, src) = loaded_module.sources[&home].clone();
+
+    let mut index = SymbolIndex::new();
+    index.update(&mut loaded_module);
+
+    assert!(index.is_current(&path, &src));
+    assert!(!index.is_current(&path, "interface Main exposes [] imports []"));
+
+    let symbols: Vec<_> = index
+        .symbols()
+        .map(|symbol| (symbol.name.as_str(), symbol.kind))
+        .collect();
+
+    assert_eq!(
+        symbols,
+        vec![
+            ("Shape", SymbolKind::Alias),
+            ("Age", SymbolKind::Opaque),
+            ("Describe", SymbolKind::Ability),
+            ("describe", SymbolKind::AbilityMember),
+            ("area", SymbolKind::Function),
+            ("origin", SymbolKind::Value),
+        ]
+    );
+
+    let signature = |name| {
+        index
+            .symbols()
+            .find(|symbol| symbol.name == name)
+            .map(|symbol| symbol.signature.clone())
+            .unwrap()
+    };
+
+    assert_eq!(signature("Shape"), "Shape : [Circle F64, Square F64]");
+    assert_eq!(signature("Age"), "Age := U32");
+    assert_eq!(signature("describe"), "a -> Str | a has Describe");
+    // Inferred, since it has no annotation
+    assert!(signature("area").ends_with("-> F64"));
+
+    let names = |index: &SymbolIndex, query| -> Vec<String> {
+        index
+            .search(query)
+            .into_iter()
+            .map(|symbol| format!("{}.{}", symbol.module, symbol.name))
+            .collect()
+    };
+
+    // The exact name first, then names starting with the query
+    assert_eq!(
+        names(&index, "describe"),
+        vec!["Main.Describe", "Main.describe"]
+    );
+    assert_eq!(names(&index, "main.ar"), vec!["Main.area"]);
+    // Signatures are searched too
+    assert!(names(&index, "circle").contains(&"Main.Shape".to_string()));
+
+    let dir = tempfile::tempdir().unwrap();
+    let file = dir.path().join("symbols");
+
+    index.save(&file).unwrap();
+
+    assert_eq!(SymbolIndex::load(&file), index);
+    assert_eq!(
+        SymbolIndex::load(&dir.path().join("missing")),
+        SymbolIndex::new()
+    );
+}
+
+#[test]
+fn number_defaults_with_dec_default() {
+    use roc_load_internal::number_defaults::number_defaults;
+
+    let modules = vec![(
+        "Main.roc",
+        indoc!(
+            r#"
+            interface Main exposes [small, huge, inc] imports []
+
+            small = Num.toStr 1.5
+
+            huge = Num.toStr 1e300
+
+            inc = \n -> n + 1
+            "#
+        ),
+    )];
+
+    let loaded_module = multiple_modules("number_defaults_with_dec_default", modules).unwrap();
+    let home = loaded_module.module_id;
+    let decls = &loaded_module.declarations_by_id[&home];
+
+    let defaults: Vec<_> = number_defaults(decls, loaded_module.solved.inner(), FloatWidth::Dec)
+        .into_iter()
+        .map(|default| (default.literal.to_string(), default.typ, default.fits))
+        .collect();
+
+    assert_eq!(
+        defaults,
+        vec![
+            ("1.5".to_string(), "Dec", true),
+            ("1e300".to_string(), "Dec", false),
+        ]
+    );
+}
+
 #[test]
 fn parse_problem() {
     let modules = vec![(
@@ -590,7 +1235,7 @@ fn parse_problem() {
             report,
             indoc!(
                 "
-                    ── UNFINISHED LIST ──────────────────────────────────── tmp/parse_problem/Main ─
+                    ── UNFINISHED LIST [E0036] ──────────────────────────── tmp/parse_problem/Main ─
 
                     I cannot find the end of this list:
 
@@ -780,7 +1425,7 @@ fn opaque_wrapped_unwrapped_outside_defining_module() {
         err,
         indoc!(
             r#"
-                ── OPAQUE TYPE DECLARED OUTSIDE SCOPE ─ ...rapped_outside_defining_module/Main ─
+                ── OPAQUE TYPE DECLARED OUTSIDE SCOPE [E1012] ─ ...utside_defining_module/Main ─
 
                 The unwrapped opaque type Age referenced here:
 
@@ -794,7 +1439,7 @@ fn opaque_wrapped_unwrapped_outside_defining_module() {
 
                 Note: Opaque types can only be wrapped and unwrapped in the module they are defined in!
 
-                ── OPAQUE TYPE DECLARED OUTSIDE SCOPE ─ ...rapped_outside_defining_module/Main ─
+                ── OPAQUE TYPE DECLARED OUTSIDE SCOPE [E1012] ─ ...utside_defining_module/Main ─
 
                 The unwrapped opaque type Age referenced here:
 
@@ -808,7 +1453,7 @@ fn opaque_wrapped_unwrapped_outside_defining_module() {
 
                 Note: Opaque types can only be wrapped and unwrapped in the module they are defined in!
 
-                ── UNUSED IMPORT ─── tmp/opaque_wrapped_unwrapped_outside_defining_module/Main ─
+                ── UNUSED IMPORT [E2003] ─ ...e_wrapped_unwrapped_outside_defining_module/Main ─
 
                 Nothing from Age is used in this module.
 
@@ -863,7 +1508,7 @@ fn issue_2863_module_type_does_not_exist() {
                 report,
                 indoc!(
                     "
-                        ── UNRECOGNIZED NAME ────────── tmp/issue_2863_module_type_does_not_exist/Main ─
+                        ── UNRECOGNIZED NAME [E2001] ── tmp/issue_2863_module_type_does_not_exist/Main ─
 
                         Nothing is named `DoesNotExist` in this scope.
 
@@ -939,7 +1584,7 @@ fn module_doesnt_match_file_path() {
         err,
         indoc!(
             r#"
-            ── WEIRD MODULE NAME ─────────────────── tmp/module_doesnt_match_file_path/Age ─
+            ── WEIRD MODULE NAME [E5024] ─────────── tmp/module_doesnt_match_file_path/Age ─
 
             This module name does not correspond with the file path it is defined
             in:
@@ -972,7 +1617,7 @@ fn module_cyclic_import_itself() {
         err,
         indoc!(
             r#"
-            ── IMPORT CYCLE ────────────────────────── tmp/module_cyclic_import_itself/Age ─
+            ── IMPORT CYCLE [E5004] ────────────────── tmp/module_cyclic_import_itself/Age ─
 
             I can't compile Age because it depends on itself through the following
             chain of module imports:
@@ -983,9 +1628,16 @@ fn module_cyclic_import_itself() {
                 │     Age
                 └─────┘
 
+            Age imports Age here:
+
+            1│  interface Age exposes [] imports [Age]
+                                                  ^^^
+
             Cyclic dependencies are not allowed in Roc! Can you restructure a
             module in this import chain so that it doesn't have to depend on
-            itself?"#
+            itself?
+
+            Tip: Remove Age from its own imports."#
         ),
         "\n{}",
         err
@@ -1018,7 +1670,7 @@ fn module_cyclic_import_transitive() {
         err,
         indoc!(
             r#"
-            ── IMPORT CYCLE ────────────────── tmp/module_cyclic_import_transitive/Age.roc ─
+            ── IMPORT CYCLE [E5004] ────────── tmp/module_cyclic_import_transitive/Age.roc ─
 
             I can't compile Age because it depends on itself through the following
             chain of module imports:
@@ -1031,9 +1683,23 @@ fn module_cyclic_import_transitive() {
                 │     Age
                 └─────┘
 
+            Age imports Person here:
+
+            1│  interface Age exposes [] imports [Person]
+                                                  ^^^^^^
+
+            Person imports Age here:
+
+            1│  interface Person exposes [] imports [Age]
+                                                     ^^^
+
             Cyclic dependencies are not allowed in Roc! Can you restructure a
             module in this import chain so that it doesn't have to depend on
-            itself?"#
+            itself?
+
+            Tip: Removing the import of Age from Person would break this cycle.
+            Whatever Person needs from Age could move to a new module that both of
+            them import."#
         ),
         "\n{}",
         err
@@ -1066,7 +1732,7 @@ fn nested_module_has_incorrect_name() {
         err,
         indoc!(
             r#"
-            ── INCORRECT MODULE NAME ──── tmp/nested_module_has_incorrect_name/Dep/Foo.roc ─
+            ── INCORRECT MODULE NAME [E5008] ─ ...ed_module_has_incorrect_name/Dep/Foo.roc ─
 
             This module has a different name than I expected:
 
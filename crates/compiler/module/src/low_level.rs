@@ -145,6 +145,147 @@ impl LowLevel {
     }
 }
 
+/// We use a rust macro to ensure that every LowLevel can be parsed back from its name
+macro_rules! low_level_names {
+    ($($lowlevel:ident),* $(,)?) => {
+        impl std::str::FromStr for LowLevel {
+            type Err = ();
+
+            /// Parses the name of a LowLevel, as printed by its `Debug` implementation
+            fn from_str(name: &str) -> Result<Self, Self::Err> {
+                match name {
+                    $(
+                    stringify!($lowlevel) => Ok(LowLevel::$lowlevel),
+                    )*
+                    _ => Err(()),
+                }
+            }
+        }
+
+        fn _enforce_names_exhaustiveness(lowlevel: LowLevel) {
+            // when adding a new lowlevel, this match will stop being exhaustive; add the new
+            // lowlevel to the invocation of `low_level_names!` below.
+            match lowlevel {
+                $(
+                LowLevel::$lowlevel => {}
+                )*
+            }
+        }
+    };
+}
+
+low_level_names! {
+    StrConcat,
+    StrJoinWith,
+    StrIsEmpty,
+    StrStartsWith,
+    StrStartsWithScalar,
+    StrEndsWith,
+    StrSplit,
+    StrCountGraphemes,
+    StrCountUtf8Bytes,
+    StrFromInt,
+    StrFromUtf8Range,
+    StrToUtf8,
+    StrRepeat,
+    StrFromFloat,
+    StrTrim,
+    StrTrimLeft,
+    StrTrimRight,
+    StrToNum,
+    StrToScalars,
+    StrGetUnsafe,
+    StrSubstringUnsafe,
+    StrReserve,
+    StrAppendScalar,
+    StrGetScalarUnsafe,
+    StrGetCapacity,
+    StrWithCapacity,
+    StrGraphemes,
+    ListLen,
+    ListWithCapacity,
+    ListReserve,
+    ListAppendUnsafe,
+    ListGetUnsafe,
+    ListReplaceUnsafe,
+    ListConcat,
+    ListPrepend,
+    ListMap,
+    ListMap2,
+    ListMap3,
+    ListMap4,
+    ListSortWith,
+    ListSublist,
+    ListDropAt,
+    ListSwap,
+    ListIsUnique,
+    ListGetCapacity,
+    NumAdd,
+    NumAddWrap,
+    NumAddChecked,
+    NumAddSaturated,
+    NumSub,
+    NumSubWrap,
+    NumSubChecked,
+    NumSubSaturated,
+    NumMul,
+    NumMulWrap,
+    NumMulSaturated,
+    NumMulChecked,
+    NumGt,
+    NumGte,
+    NumLt,
+    NumLte,
+    NumCompare,
+    NumDivFrac,
+    NumDivTruncUnchecked,
+    NumDivCeilUnchecked,
+    NumRemUnchecked,
+    NumIsMultipleOf,
+    NumAbs,
+    NumNeg,
+    NumSin,
+    NumCos,
+    NumSqrtUnchecked,
+    NumLogUnchecked,
+    NumRound,
+    NumToFrac,
+    NumPow,
+    NumCeiling,
+    NumPowInt,
+    NumFloor,
+    NumIsFinite,
+    NumAtan,
+    NumAcos,
+    NumAsin,
+    NumBytesToU16,
+    NumBytesToU32,
+    NumBitwiseAnd,
+    NumBitwiseXor,
+    NumBitwiseOr,
+    NumShiftLeftBy,
+    NumShiftRightBy,
+    NumShiftRightZfBy,
+    NumIntCast,
+    NumToFloatCast,
+    NumToIntChecked,
+    NumToFloatChecked,
+    NumToStr,
+    Eq,
+    NotEq,
+    And,
+    Or,
+    Not,
+    Hash,
+    PtrCast,
+    RefCountInc,
+    RefCountDec,
+    BoxExpr,
+    UnboxExpr,
+    Dbg,
+    Unreachable,
+}
+
 /// Some wrapper functions can just be replaced by lowlevels in the backend for performance.
 /// For example, Num.add should be an instruction, not a function call.
 /// Variant names are chosen to help explain what to do when adding new lowlevels
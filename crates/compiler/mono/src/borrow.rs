@@ -1,5 +1,6 @@
-use crate::ir::{Expr, HigherOrderLowLevel, JoinPointId, Param, Proc, ProcLayout, Stmt};
-use crate::layout::Layout;
+use crate::inline::for_each_call;
+use crate::ir::{CallType, Expr, HigherOrderLowLevel, JoinPointId, Param, Proc, ProcLayout, Stmt};
+use crate::layout::{Layout, STLayoutInterner};
 use bumpalo::collections::Vec;
 use bumpalo::Bump;
 use roc_collections::all::{MutMap, MutSet};
@@ -18,6 +19,7 @@ fn should_borrow_layout(layout: &Layout) -> bool {
 
 pub fn infer_borrow<'a>(
     arena: &'a Bump,
+    layout_interner: &STLayoutInterner<'a>,
     procs: &MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
 ) -> ParamMap<'a> {
     // intern the layouts
@@ -32,8 +34,14 @@ pub fn infer_borrow<'a>(
         }
     };
 
+    let passed_functions = passed_functions(procs);
+
     for (key, proc) in procs {
-        param_map.visit_proc(arena, proc, *key);
+        if passed_functions.contains(key) {
+            param_map.visit_passed_proc(arena, layout_interner, proc, *key);
+        } else {
+            param_map.visit_proc(arena, proc, *key);
+        }
     }
 
     let mut env = BorrowInfState {
@@ -96,6 +104,30 @@ pub fn infer_borrow<'a>(
     param_map
 }
 
+/// The functions that are passed to a higher-order lowlevel like `List.map`
+fn passed_functions<'a>(
+    procs: &MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+) -> MutSet<(Symbol, ProcLayout<'a>)> {
+    let mut passed_functions = MutSet::default();
+
+    for proc in procs.values() {
+        for_each_call(&proc.body, &mut |call| {
+            if let CallType::HigherOrder(holl) = &call.call_type {
+                let passed_function = &holl.passed_function;
+                let proc_layout = ProcLayout {
+                    arguments: passed_function.argument_layouts,
+                    result: passed_function.return_layout,
+                    captures_niche: passed_function.name.captures_niche(),
+                };
+
+                passed_functions.insert((passed_function.name.name(), proc_layout));
+            }
+        });
+    }
+
+    passed_functions
+}
+
 #[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub struct ParamOffset(usize);
 
@@ -238,6 +270,25 @@ impl<'a> ParamMap<'a> {
         .into_bump_slice()
     }
 
+    /// A function passed to a higher-order lowlevel is called once per element, so it is worth
+    /// borrowing any argument that contains refcounted data, including records and the captured
+    /// environment. The fixed-point analysis still makes an argument owned when it must be.
+    fn init_borrow_args_passed(
+        arena: &'a Bump,
+        interner: &STLayoutInterner<'a>,
+        ps: &'a [(Layout<'a>, Symbol)],
+    ) -> &'a [Param<'a>] {
+        Vec::from_iter_in(
+            ps.iter().map(|(layout, symbol)| Param {
+                borrow: layout.contains_refcounted(interner),
+                layout: *layout,
+                symbol: *symbol,
+            }),
+            arena,
+        )
+        .into_bump_slice()
+    }
+
     fn init_borrow_args_always_owned(
         arena: &'a Bump,
         ps: &'a [(Layout<'a>, Symbol)],
@@ -272,6 +323,31 @@ impl<'a> ParamMap<'a> {
         self.visit_stmt(arena, proc.name.name(), &proc.body);
     }
 
+    fn visit_passed_proc(
+        &mut self,
+        arena: &'a Bump,
+        interner: &STLayoutInterner<'a>,
+        proc: &Proc<'a>,
+        key: (Symbol, ProcLayout<'a>),
+    ) {
+        if proc.must_own_arguments {
+            self.visit_proc_always_owned(arena, proc, key);
+            return;
+        }
+
+        let index: usize = self.get_param_offset(key.0, key.1).into();
+
+        for (i, param) in Self::init_borrow_args_passed(arena, interner, proc.args)
+            .iter()
+            .copied()
+            .enumerate()
+        {
+            self.declarations[index + i] = param;
+        }
+
+        self.visit_stmt(arena, proc.name.name(), &proc.body);
+    }
+
     fn visit_proc_always_owned(
         &mut self,
         arena: &'a Bump,
@@ -678,6 +754,15 @@ impl<'a> BorrowInfState<'a> {
                 self.own_var(*x);
                 self.own_args_if_param(ys);
             }
+            ReuseBox {
+                symbol: x,
+                value: y,
+                ..
+            } => {
+                self.own_var(z);
+                self.own_var(*x);
+                self.own_args_if_param(&[*y]);
+            }
             EmptyArray => {
                 self.own_var(z);
             }
@@ -973,7 +1058,11 @@ fn call_info_call<'a>(call: &crate::ir::Call<'a>, info: &mut CallInfo<'a>) {
         }
         Foreign { .. } => {}
         LowLevel { .. } => {}
-        HigherOrder(_) => {}
+        HigherOrder(ref holl) => {
+            // the passed function is analysed before its caller, so the caller
+            // sees the final signature of the passed function
+            info.keys.push(holl.passed_function.name.name());
+        }
     }
 }
 
@@ -1018,3 +1107,104 @@ fn call_info_stmt<'a>(arena: &'a Bump, stmt: &Stmt<'a>, info: &mut CallInfo<'a>)
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{infer_borrow, BORROWED, OWNED};
+    use crate::ir::text::{parse_procs, procs_to_text};
+    use crate::ir::{Proc, ProcLayout, UpdateModeIds};
+    use crate::layout::STLayoutInterner;
+    use bumpalo::Bump;
+    use roc_collections::all::MutMap;
+    use roc_intern::SingleThreadedInterner;
+    use roc_module::ident::ModuleName;
+    use roc_module::symbol::Interns;
+
+    /// `Test.1` only reads the string in its record argument, `Test.6` consumes its argument
+    const TEXT: &str = r#"procedure Test.1 (Test.2 : {Str, I64}, Test.3 : Str) -> Int1:
+    let Test.4 : Str = StructAtIndex 0 Test.2;
+    let Test.5 : Int1 = lowlevel StrStartsWith Test.4 Test.3;
+    ret Test.5;
+
+procedure Test.6 (Test.7 : Str, Test.8 : Str) -> Str:
+    let Test.9 : Str = lowlevel StrConcat Test.7 Test.8;
+    ret Test.9;
+
+procedure Test.0 (Test.10 : List {Str, I64}, Test.11 : List Str, Test.12 : Str) -> {List Int1, List Str}:
+    let Test.13 : List Int1 = higher-order ListMap {xs: Test.10} Test.1 ({Str, I64}, Str) -> Int1 env Test.12 : Str Test.10 Test.1 Test.12;
+    let Test.14 : List Str = higher-order ListMap {xs: Test.11} Test.6 (Str, Str) -> Str env Test.12 : Str Test.11 Test.6 Test.12;
+    let Test.15 : {List Int1, List Str} = Struct {Test.13, Test.14};
+    ret Test.15;
+"#;
+
+    fn proc_map<'a>(
+        arena: &'a Bump,
+        procs: std::vec::Vec<Proc<'a>>,
+    ) -> MutMap<(roc_module::symbol::Symbol, ProcLayout<'a>), Proc<'a>> {
+        procs
+            .into_iter()
+            .map(|proc| {
+                let arguments = arena.alloc_slice_fill_iter(proc.args.iter().map(|(l, _)| *l));
+                let proc_layout = ProcLayout::new(
+                    arena,
+                    arguments,
+                    proc.name.captures_niche(),
+                    proc.ret_layout,
+                );
+
+                ((proc.name.name(), proc_layout), proc)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn passed_functions_borrow_what_they_only_read() {
+        let arena = Bump::new();
+        let mut interns = Interns::default();
+        let interner: STLayoutInterner = SingleThreadedInterner::with_capacity(4);
+
+        let procs = match parse_procs(&arena, &mut interns, TEXT) {
+            Ok(procs) => procs,
+            Err(error) => panic!("{}", error),
+        };
+        let mut procs = proc_map(&arena, procs);
+
+        let param_map = infer_borrow(&arena, &interner, &procs);
+        let borrows = |index: usize| {
+            let (key, _) = procs
+                .iter()
+                .find(|((name, _), _)| name.ident_id().index() == index)
+                .unwrap();
+
+            param_map
+                .get_symbol(key.0, key.1)
+                .unwrap()
+                .iter()
+                .map(|param| param.borrow)
+                .collect::<std::vec::Vec<_>>()
+        };
+
+        // A record is not borrowed by other functions, but it is by a passed function
+        assert_eq!(borrows(1), [BORROWED, BORROWED]);
+        assert_eq!(borrows(6), [OWNED, BORROWED]);
+
+        // The inferred signature reaches the calls, for the backends to see
+        let home = interns.module_id(&ModuleName::from("Test"));
+        let ident_ids = interns.all_ident_ids.get_or_insert(home);
+        let mut update_mode_ids = UpdateModeIds::new();
+
+        Proc::insert_refcount_operations(
+            &arena,
+            &interner,
+            home,
+            ident_ids,
+            &mut update_mode_ids,
+            &mut procs,
+        );
+
+        let text = procs_to_text(&interns, &interner, procs.values());
+
+        assert!(text.contains("Test.1 ({Str, I64}, Str) -> Int1 env Test.12 : Str borrowing "));
+        assert!(text.contains("Test.6 (Str, Str) -> Str env Test.12 : Str Test.11 "));
+    }
+}
@@ -0,0 +1,516 @@
+//! Leave closures that carry no information out of calls.
+//!
+//! A lambda set of a single function that captures nothing is always called directly, so a value
+//! of it is an empty struct. A procedure that is given such a function, like `apply` in
+//!
+//! ```text
+//! apply = \f, x -> f x
+//!
+//! main = apply Num.neg 1
+//! ```
+//!
+//! still takes that empty struct as an argument, and its callers build one to pass it. This pass
+//! removes those arguments from procedures and from the calls to them. The procedure binds the
+//! argument to an empty struct itself, in case it passes it on, and the empty structs that were
+//! built only to be passed are removed by [crate::dead_code] afterwards.
+//!
+//! Only calls by name are rewritten, so procedures that are called some other way keep their
+//! arguments: the ones exposed to the host, the ones passed to a higher-order low-level, and
+//! self-recursive ones, whose join point takes the same arguments as the procedure.
+//!
+//! Like the other optimizations, this is skipped in development builds.
+
+use crate::ir::{
+    Call, CallType, Expr, HostExposedLayouts, OptLevel, Proc, ProcLayout, SelfRecursive, Stmt,
+};
+use crate::layout::{Layout, STLayoutInterner};
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+use roc_collections::all::{MutMap, MutSet};
+use roc_module::symbol::Symbol;
+
+pub fn erase_closure_arguments<'a>(
+    arena: &'a Bump,
+    interner: &STLayoutInterner<'a>,
+    opt_level: OptLevel,
+    exposed_to_host: &MutSet<Symbol>,
+    procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+) {
+    if let OptLevel::Development = opt_level {
+        return;
+    }
+
+    let erased = erasable_arguments(arena, interner, exposed_to_host, procs);
+
+    if erased.is_empty() {
+        return;
+    }
+
+    let env = Env {
+        arena,
+        erased: &erased,
+    };
+
+    let mut rewritten = MutMap::default();
+
+    for (key, mut proc) in procs.drain() {
+        let mut body = env.erase_stmt(&proc.body).clone();
+
+        let key = match erased.get(&key) {
+            Some((new_layout, keep)) => {
+                for ((layout, symbol), keep) in proc.args.iter().zip(keep.iter()).rev() {
+                    if !keep {
+                        body = Stmt::Let(*symbol, Expr::Struct(&[]), *layout, arena.alloc(body));
+                    }
+                }
+
+                proc.args = kept(arena, proc.args, keep);
+
+                (key.0, *new_layout)
+            }
+            None => key,
+        };
+
+        proc.body = body;
+        rewritten.insert(key, proc);
+    }
+
+    *procs = rewritten;
+}
+
+/// The procedures to rewrite, and for each the layout it will have, and which of its
+/// arguments are left out. Once the pass has run, there are none left.
+pub(crate) fn erasable_arguments<'a>(
+    arena: &'a Bump,
+    interner: &STLayoutInterner<'a>,
+    exposed_to_host: &MutSet<Symbol>,
+    procs: &MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+) -> Erased<'a> {
+    let mut pinned = exposed_to_host.clone();
+
+    for proc in procs.values() {
+        if let HostExposedLayouts::HostExposed { aliases, .. } = &proc.host_exposed_layouts {
+            pinned.insert(proc.name.name());
+            pinned.extend(aliases.values().map(|(generated, _, _)| *generated));
+        }
+
+        if let SelfRecursive::SelfRecursive(_) = proc.is_self_recursive {
+            pinned.insert(proc.name.name());
+        }
+
+        find_passed_functions(&proc.body, &mut pinned);
+    }
+
+    let mut erased = MutMap::default();
+
+    for ((symbol, layout), proc) in procs.iter() {
+        if pinned.contains(symbol) {
+            continue;
+        }
+
+        let keep: std::vec::Vec<bool> = layout
+            .arguments
+            .iter()
+            .map(|argument| !is_erasable(interner, argument))
+            .collect();
+
+        if keep.iter().all(|keep| *keep) {
+            continue;
+        }
+
+        let new_layout = ProcLayout {
+            arguments: kept(arena, layout.arguments, &keep),
+            ..*layout
+        };
+
+        // Don't merge two procedures into one
+        if procs.contains_key(&(*symbol, new_layout)) {
+            continue;
+        }
+
+        debug_assert_eq!(proc.args.len(), keep.len());
+
+        erased.insert((*symbol, *layout), (new_layout, keep));
+    }
+
+    erased
+}
+
+/// Whether an argument of this layout is a closure that carries no information
+fn is_erasable<'a>(interner: &STLayoutInterner<'a>, layout: &Layout<'a>) -> bool {
+    match layout {
+        Layout::LambdaSet(lambda_set) => {
+            lambda_set.is_singleton_without_captures()
+                && lambda_set.is_represented(interner).is_none()
+        }
+        _ => false,
+    }
+}
+
+fn kept<'a, T: Copy>(arena: &'a Bump, items: &[T], keep: &[bool]) -> &'a [T] {
+    let items = items
+        .iter()
+        .zip(keep.iter())
+        .filter(|(_, keep)| **keep)
+        .map(|(item, _)| *item);
+
+    Vec::from_iter_in(items, arena).into_bump_slice()
+}
+
+fn find_passed_functions(stmt: &Stmt<'_>, passed: &mut MutSet<Symbol>) {
+    use Stmt::*;
+
+    match stmt {
+        Let(_, expr, _, cont) => {
+            if let Expr::Call(Call {
+                call_type: CallType::HigherOrder(higher_order),
+                ..
+            }) = expr
+            {
+                passed.insert(higher_order.passed_function.name.name());
+            }
+
+            find_passed_functions(cont, passed);
+        }
+        Switch {
+            branches,
+            default_branch,
+            ..
+        } => {
+            for (_, _, branch) in branches.iter() {
+                find_passed_functions(branch, passed);
+            }
+
+            find_passed_functions(default_branch.1, passed);
+        }
+        Join {
+            body, remainder, ..
+        } => {
+            find_passed_functions(body, passed);
+            find_passed_functions(remainder, passed);
+        }
+        Refcounting(_, cont)
+        | Expect {
+            remainder: cont, ..
+        }
+        | ExpectFx {
+            remainder: cont, ..
+        } => find_passed_functions(cont, passed),
+        Ret(_) | Jump(_, _) | Crash(..) => {}
+    }
+}
+
+pub(crate) type Erased<'a> =
+    MutMap<(Symbol, ProcLayout<'a>), (ProcLayout<'a>, std::vec::Vec<bool>)>;
+
+struct Env<'a, 'e> {
+    arena: &'a Bump,
+    erased: &'e Erased<'a>,
+}
+
+impl<'a, 'e> Env<'a, 'e> {
+    fn erase_stmt(&self, stmt: &Stmt<'a>) -> &'a Stmt<'a> {
+        use Stmt::*;
+
+        let arena = self.arena;
+
+        match stmt {
+            Let(symbol, expr, layout, cont) => {
+                let expr = self.erase_expr(expr).unwrap_or_else(|| expr.clone());
+
+                arena.alloc(Let(*symbol, expr, *layout, self.erase_stmt(cont)))
+            }
+            Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout,
+            } => {
+                let branches = Vec::from_iter_in(
+                    branches.iter().map(|(label, info, branch)| {
+                        (*label, info.clone(), self.erase_stmt(branch).clone())
+                    }),
+                    arena,
+                );
+
+                arena.alloc(Switch {
+                    cond_symbol: *cond_symbol,
+                    cond_layout: *cond_layout,
+                    branches: branches.into_bump_slice(),
+                    default_branch: (default_branch.0.clone(), self.erase_stmt(default_branch.1)),
+                    ret_layout: *ret_layout,
+                })
+            }
+            Join {
+                id,
+                parameters,
+                body,
+                remainder,
+            } => arena.alloc(Join {
+                id: *id,
+                parameters: *parameters,
+                body: self.erase_stmt(body),
+                remainder: self.erase_stmt(remainder),
+            }),
+            Refcounting(modify, cont) => arena.alloc(Refcounting(*modify, self.erase_stmt(cont))),
+            Expect {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => arena.alloc(Expect {
+                condition: *condition,
+                region: *region,
+                lookups: *lookups,
+                variables: *variables,
+                remainder: self.erase_stmt(remainder),
+            }),
+            ExpectFx {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => arena.alloc(ExpectFx {
+                condition: *condition,
+                region: *region,
+                lookups: *lookups,
+                variables: *variables,
+                remainder: self.erase_stmt(remainder),
+            }),
+            Ret(_) | Jump(_, _) | Crash(..) => arena.alloc(stmt.clone()),
+        }
+    }
+
+    /// The call without the closures it no longer passes, if it is a call to a rewritten
+    /// procedure
+    fn erase_expr(&self, expr: &Expr<'a>) -> Option<Expr<'a>> {
+        match expr {
+            Expr::Call(Call {
+                call_type:
+                    CallType::ByName {
+                        name,
+                        ret_layout,
+                        arg_layouts,
+                        specialization_id,
+                    },
+                arguments,
+            }) => {
+                let proc_layout = ProcLayout {
+                    arguments: arg_layouts,
+                    result: **ret_layout,
+                    captures_niche: name.captures_niche(),
+                };
+
+                let (new_layout, keep) = self.erased.get(&(name.name(), proc_layout))?;
+
+                Some(Expr::Call(Call {
+                    call_type: CallType::ByName {
+                        name: *name,
+                        ret_layout,
+                        arg_layouts: new_layout.arguments,
+                        specialization_id: *specialization_id,
+                    },
+                    arguments: kept(self.arena, arguments, keep),
+                }))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::erase_closure_arguments;
+    use crate::ir::{
+        Call, CallSpecId, CallType, Expr, HostExposedLayouts, Literal, OptLevel, Proc, ProcLayout,
+        SelfRecursive, Stmt,
+    };
+    use crate::layout::{CapturesNiche, LambdaName, LambdaSet, Layout, STLayoutInterner};
+    use bumpalo::Bump;
+    use roc_collections::all::{MutMap, MutSet};
+    use roc_intern::{Interner, SingleThreadedInterner};
+    use roc_module::symbol::{ModuleId, Symbol};
+
+    fn symbol(index: u32) -> Symbol {
+        Symbol::from_index(ModuleId::ATTR, index)
+    }
+
+    fn proc<'a>(
+        name: Symbol,
+        args: &'a [(Layout<'a>, Symbol)],
+        ret_layout: Layout<'a>,
+        body: Stmt<'a>,
+    ) -> Proc<'a> {
+        Proc {
+            name: LambdaName::no_niche(name),
+            args,
+            body,
+            closure_data_layout: None,
+            ret_layout,
+            is_self_recursive: SelfRecursive::NotSelfRecursive,
+            must_own_arguments: false,
+            host_exposed_layouts: HostExposedLayouts::NotHostExposed,
+        }
+    }
+
+    fn call<'a>(
+        arena: &'a Bump,
+        name: Symbol,
+        arg_layouts: &'a [Layout<'a>],
+        arguments: &'a [Symbol],
+    ) -> Expr<'a> {
+        Expr::Call(Call {
+            call_type: CallType::ByName {
+                name: LambdaName::no_niche(name),
+                ret_layout: arena.alloc(Layout::i64()),
+                arg_layouts,
+                specialization_id: CallSpecId::BACKEND_DUMMY,
+            },
+            arguments,
+        })
+    }
+
+    fn key<'a>(arena: &'a Bump, proc: &Proc<'a>) -> (Symbol, ProcLayout<'a>) {
+        let arguments = arena.alloc_slice_fill_iter(proc.args.iter().map(|(layout, _)| *layout));
+        let layout = ProcLayout::new(arena, arguments, CapturesNiche::no_niche(), proc.ret_layout);
+
+        (proc.name.name(), layout)
+    }
+
+    /// The procedures of
+    ///
+    /// ```text
+    /// neg = \y -> y
+    /// apply = \f, x -> f x
+    /// main = apply neg 1
+    /// ```
+    ///
+    /// where `apply` is specialized to the lambda set of `neg`
+    #[test]
+    fn erase_closure_of_single_function() {
+        let arena = &Bump::new();
+        let mut interner: STLayoutInterner = SingleThreadedInterner::with_capacity(4);
+
+        let (neg, apply, main) = (symbol(1), symbol(2), symbol(3));
+        let lambda_set = LambdaSet::from_parts(
+            arena.alloc([(neg, &[] as &[_])]),
+            interner.insert(&Layout::UNIT),
+        );
+        let closure = Layout::LambdaSet(lambda_set);
+        let int = Layout::i64();
+
+        let (y, f, x, result, main_closure, one, main_result) = (
+            symbol(10),
+            symbol(11),
+            symbol(12),
+            symbol(13),
+            symbol(14),
+            symbol(15),
+            symbol(16),
+        );
+
+        let neg_proc = proc(neg, arena.alloc([(int, y)]), int, Stmt::Ret(y));
+        let apply_proc = proc(
+            apply,
+            arena.alloc([(closure, f), (int, x)]),
+            int,
+            Stmt::Let(
+                result,
+                call(arena, neg, arena.alloc([int]), arena.alloc([x])),
+                int,
+                arena.alloc(Stmt::Ret(result)),
+            ),
+        );
+        let main_proc = proc(
+            main,
+            &[],
+            int,
+            Stmt::Let(
+                main_closure,
+                Expr::Struct(&[]),
+                closure,
+                arena.alloc(Stmt::Let(
+                    one,
+                    Expr::Literal(Literal::Int(1i128.to_ne_bytes())),
+                    int,
+                    arena.alloc(Stmt::Let(
+                        main_result,
+                        call(
+                            arena,
+                            apply,
+                            arena.alloc([closure, int]),
+                            arena.alloc([main_closure, one]),
+                        ),
+                        int,
+                        arena.alloc(Stmt::Ret(main_result)),
+                    )),
+                )),
+            ),
+        );
+
+        let mut procs: MutMap<_, _> = [neg_proc, apply_proc, main_proc]
+            .into_iter()
+            .map(|proc| (key(arena, &proc), proc))
+            .collect();
+
+        erase_closure_arguments(
+            arena,
+            &interner,
+            OptLevel::Normal,
+            &MutSet::default(),
+            &mut procs,
+        );
+
+        let apply_layout = ProcLayout::new(arena, &[int], CapturesNiche::no_niche(), int);
+        let apply_proc = &procs[&(apply, apply_layout)];
+
+        assert_eq!(apply_proc.args, &[(int, x)]);
+        assert!(matches!(
+            apply_proc.body,
+            Stmt::Let(symbol, Expr::Struct(&[]), layout, _) if symbol == f && layout == closure
+        ));
+
+        let main_layout = ProcLayout::new(arena, &[], CapturesNiche::no_niche(), int);
+
+        match &procs[&(main, main_layout)].body {
+            Stmt::Let(_, _, _, Stmt::Let(_, _, _, Stmt::Let(_, Expr::Call(call), _, _))) => {
+                assert_eq!(call.arguments, &[one]);
+                assert!(matches!(
+                    call.call_type,
+                    CallType::ByName { arg_layouts, .. } if arg_layouts == [int]
+                ));
+            }
+            other => panic!("expected main to call apply, but it is {:?}", other),
+        }
+    }
+
+    #[test]
+    fn keep_closures_of_procedures_exposed_to_host() {
+        let arena = &Bump::new();
+        let mut interner: STLayoutInterner = SingleThreadedInterner::with_capacity(4);
+
+        let (neg, apply, f) = (symbol(1), symbol(2), symbol(11));
+        let lambda_set = LambdaSet::from_parts(
+            arena.alloc([(neg, &[] as &[_])]),
+            interner.insert(&Layout::UNIT),
+        );
+        let closure = Layout::LambdaSet(lambda_set);
+
+        let apply_proc = proc(apply, arena.alloc([(closure, f)]), closure, Stmt::Ret(f));
+        let apply_key = key(arena, &apply_proc);
+        let mut procs: MutMap<_, _> = [(apply_key, apply_proc)].into_iter().collect();
+
+        erase_closure_arguments(
+            arena,
+            &interner,
+            OptLevel::Normal,
+            &[apply].into_iter().collect(),
+            &mut procs,
+        );
+
+        assert!(procs.contains_key(&apply_key));
+    }
+}
@@ -186,7 +186,7 @@ fn eq_tag_union<'a>(
     use UnionLayout::*;
 
     let parent_rec_ptr_layout = ctx.recursive_union;
-    if !matches!(union_layout, NonRecursive(_)) {
+    if !matches!(union_layout, NonRecursive(_) | NullablePointer { .. }) {
         ctx.recursive_union = Some(union_layout);
     }
 
@@ -223,6 +223,17 @@ fn eq_tag_union<'a>(
             root.arena.alloc([other_fields]),
             Some(nullable_id as TagIdIntType),
         ),
+
+        NullablePointer {
+            nullable_id,
+            pointer,
+        } => {
+            // compare like a non-recursive union; a null pointer is just another tag id
+            let tags = root
+                .arena
+                .alloc(UnionLayout::nullable_pointer_tags(nullable_id, pointer));
+            eq_tag_union_help(root, ident_ids, ctx, union_layout, tags, None)
+        }
     };
 
     ctx.recursive_union = parent_rec_ptr_layout;
@@ -239,7 +250,10 @@ fn eq_tag_union_help<'a>(
     nullable_id: Option<TagIdIntType>,
 ) -> Stmt<'a> {
     let tailrec_loop = JoinPointId(root.create_symbol(ident_ids, "tailrec_loop"));
-    let is_non_recursive = matches!(union_layout, UnionLayout::NonRecursive(_));
+    let is_non_recursive = matches!(
+        union_layout,
+        UnionLayout::NonRecursive(_) | UnionLayout::NullablePointer { .. }
+    );
     let operands = if is_non_recursive {
         [ARG_1, ARG_2]
     } else {
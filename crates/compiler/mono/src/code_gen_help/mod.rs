@@ -12,9 +12,11 @@ use crate::layout::{Builtin, CapturesNiche, LambdaName, Layout, STLayoutInterner
 
 mod equality;
 mod refcount;
+mod to_str;
 
 const LAYOUT_BOOL: Layout = Layout::Builtin(Builtin::Bool);
 const LAYOUT_UNIT: Layout = Layout::UNIT;
+const LAYOUT_STR: Layout = Layout::Builtin(Builtin::Str);
 
 const ARG_1: Symbol = Symbol::ARG_1;
 const ARG_2: Symbol = Symbol::ARG_2;
@@ -30,6 +32,7 @@ pub enum HelperOp {
     DecRef(JoinPointId),
     Reset,
     Eq,
+    ToStr,
 }
 
 impl HelperOp {
@@ -57,7 +60,7 @@ pub struct Context<'a> {
 /// ----------------------------------------------
 ///
 /// Some low level operations need specialized helper procs to traverse data structures at runtime.
-/// This includes refcounting, hashing, and equality checks.
+/// This includes refcounting, hashing, equality checks, and rendering values as text.
 ///
 /// For example, when checking List equality, we need to visit each element and compare them.
 /// Depending on the type of the list elements, we may need to recurse deeper into each element.
@@ -229,6 +232,28 @@ impl<'a> CodeGenHelp<'a> {
         (expr, ctx.new_linker_data)
     }
 
+    /// Replace a `Lowlevel::Dbg` with a call to a helper proc that renders the value as text.
+    /// The arguments are the value and a string to append the text to.
+    /// There's no helper proc for functions, so the backend should use a literal for those.
+    pub fn call_specialized_to_str(
+        &mut self,
+        ident_ids: &mut IdentIds,
+        layout: &Layout<'a>,
+        arguments: &'a [Symbol],
+    ) -> (Expr<'a>, Vec<'a, (Symbol, ProcLayout<'a>)>) {
+        let mut ctx = Context {
+            new_linker_data: Vec::new_in(self.arena),
+            recursive_union: None,
+            op: HelperOp::ToStr,
+        };
+
+        let expr = self
+            .call_specialized_op(ident_ids, &mut ctx, *layout, arguments)
+            .unwrap();
+
+        (expr, ctx.new_linker_data)
+    }
+
     // ============================================================================
     //
     //              CALL SPECIALIZED OP
@@ -264,6 +289,7 @@ impl<'a> CodeGenHelp<'a> {
                     Reset => (self.arena.alloc(layout), self.arena.alloc([layout])),
                     Inc => (&LAYOUT_UNIT, self.arena.alloc([arg, self.layout_isize])),
                     Eq => (&LAYOUT_BOOL, self.arena.alloc([arg, arg])),
+                    ToStr => (&LAYOUT_STR, self.arena.alloc([arg, LAYOUT_STR])),
                 }
             };
 
@@ -335,6 +361,10 @@ impl<'a> CodeGenHelp<'a> {
                 LAYOUT_BOOL,
                 equality::eq_generic(self, ident_ids, ctx, layout),
             ),
+            ToStr => (
+                LAYOUT_STR,
+                to_str::to_str_generic(self, ident_ids, ctx, layout),
+            ),
         };
 
         let args: &'a [(Layout<'a>, Symbol)] = {
@@ -346,6 +376,7 @@ impl<'a> CodeGenHelp<'a> {
                 }
                 Dec | DecRef(_) | Reset => self.arena.alloc([roc_value]),
                 Eq => self.arena.alloc([roc_value, (layout, ARG_2)]),
+                ToStr => self.arena.alloc([roc_value, (LAYOUT_STR, ARG_2)]),
             }
         };
 
@@ -400,6 +431,11 @@ impl<'a> CodeGenHelp<'a> {
                 result: LAYOUT_BOOL,
                 captures_niche: CapturesNiche::no_niche(),
             },
+            HelperOp::ToStr => ProcLayout {
+                arguments: self.arena.alloc([*layout, LAYOUT_STR]),
+                result: LAYOUT_STR,
+                captures_niche: CapturesNiche::no_niche(),
+            },
         };
 
         (proc_symbol, proc_layout)
@@ -446,6 +482,14 @@ impl<'a> CodeGenHelp<'a> {
                 Layout::Union(UnionLayout::NonRecursive(new_tags.into_bump_slice()))
             }
 
+            Layout::Union(UnionLayout::NullablePointer {
+                nullable_id,
+                pointer,
+            }) => Layout::Union(UnionLayout::NullablePointer {
+                nullable_id,
+                pointer: self.arena.alloc(self.replace_rec_ptr(ctx, *pointer)),
+            }),
+
             Layout::Union(_) => {
                 // we always fully unroll recursive types. That means tha when we find a
                 // recursive tag union we can replace it with the layout
@@ -471,7 +515,7 @@ impl<'a> CodeGenHelp<'a> {
     ) -> (bool, Vec<'a, Option<usize>>) {
         use UnionLayout::*;
         match union {
-            NonRecursive(_) => (false, bumpalo::vec![in self.arena]),
+            NonRecursive(_) | NullablePointer { .. } => (false, bumpalo::vec![in self.arena]),
 
             Recursive(tags) => self.union_tail_recursion_fields_help(tags),
 
@@ -531,6 +575,11 @@ fn let_lowlevel<'a>(
 }
 
 fn layout_needs_helper_proc(layout: &Layout, op: HelperOp) -> bool {
+    if op == HelperOp::ToStr {
+        // Even numbers need a little IR to convert them and append them to the string
+        return true;
+    }
+
     match layout {
         Layout::Builtin(Builtin::Int(_) | Builtin::Float(_) | Builtin::Bool | Builtin::Decimal) => {
             false
@@ -147,143 +147,35 @@ pub fn refcount_reset_proc_body<'a>(
     let is_unique = root.create_symbol(ident_ids, "is_unique");
     let addr = root.create_symbol(ident_ids, "addr");
 
-    let union_layout = match layout {
-        Layout::Union(u) => u,
-        _ => unimplemented!("Reset is only implemented for UnionLayout"),
-    };
-
     // Whenever we recurse into a child layout we will want to Decrement
     ctx.op = HelperOp::Dec;
-    ctx.recursive_union = Some(union_layout);
-
-    // Reset structure is unique. Decrement its children and return a pointer to the allocation.
-    let then_stmt = {
-        use UnionLayout::*;
-
-        let tag_layouts;
-        let mut null_id = None;
-        match union_layout {
-            NonRecursive(tags) => {
-                tag_layouts = tags;
-            }
-            Recursive(tags) => {
-                tag_layouts = tags;
-            }
-            NonNullableUnwrapped(field_layouts) => {
-                tag_layouts = root.arena.alloc([field_layouts]);
-            }
-            NullableWrapped {
-                other_tags: tags,
-                nullable_id,
-            } => {
-                null_id = Some(nullable_id);
-                tag_layouts = tags;
-            }
-            NullableUnwrapped {
-                other_fields,
-                nullable_id,
-            } => {
-                null_id = Some(nullable_id as TagIdIntType);
-                tag_layouts = root.arena.alloc([other_fields]);
-            }
-        };
-
-        let tag_id_layout = union_layout.tag_id_layout();
-
-        let tag_id_sym = root.create_symbol(ident_ids, "tag_id");
-        let tag_id_stmt = |next| {
-            Stmt::Let(
-                tag_id_sym,
-                Expr::GetTagId {
-                    structure,
-                    union_layout,
-                },
-                tag_id_layout,
-                next,
-            )
-        };
-
-        let alloc_addr_stmt = {
-            let alignment = root.create_symbol(ident_ids, "alignment");
-            let alignment_expr = Expr::Literal(Literal::Int(
-                (layout.alignment_bytes(root.layout_interner, root.target_info) as i128)
-                    .to_ne_bytes(),
-            ));
-            let alloc_addr = root.create_symbol(ident_ids, "alloc_addr");
-            let alloc_addr_expr = Expr::Call(Call {
-                call_type: CallType::LowLevel {
-                    op: LowLevel::NumSubWrap,
-                    update_mode: UpdateModeId::BACKEND_DUMMY,
-                },
-                arguments: root.arena.alloc([addr, alignment]),
-            });
-
-            Stmt::Let(
-                alignment,
-                alignment_expr,
-                root.layout_isize,
-                root.arena.alloc(
-                    //
-                    Stmt::Let(
-                        alloc_addr,
-                        alloc_addr_expr,
-                        root.layout_isize,
-                        root.arena.alloc(
-                            //
-                            Stmt::Ret(alloc_addr),
-                        ),
-                    ),
-                ),
-            )
-        };
-
-        let rc_contents_stmt = refcount_union_contents(
-            root,
-            ident_ids,
-            ctx,
-            union_layout,
-            tag_layouts,
-            null_id,
-            structure,
-            tag_id_sym,
-            tag_id_layout,
-            alloc_addr_stmt,
-        );
 
-        tag_id_stmt(root.arena.alloc(
-            //
-            rc_contents_stmt,
-        ))
-    };
+    let (then_stmt, else_stmt, mask_lower_bits) = match layout {
+        Layout::Union(union_layout) => {
+            let then_stmt = reset_union_unique_stmt(
+                root,
+                ident_ids,
+                ctx,
+                layout,
+                union_layout,
+                structure,
+                addr,
+            );
 
-    // Reset structure is not unique. Decrement it and return a NULL pointer.
-    let else_stmt = {
-        let decrement_unit = root.create_symbol(ident_ids, "decrement_unit");
-        let decrement_expr = root
-            .call_specialized_op(ident_ids, ctx, layout, root.arena.alloc([structure]))
-            .unwrap();
-        let decrement_stmt = |next| Stmt::Let(decrement_unit, decrement_expr, LAYOUT_UNIT, next);
+            // Reset structure is not unique. Decrement it and return a NULL pointer.
+            let else_stmt = reset_shared_stmt(root, ident_ids, ctx, layout, structure);
 
-        // Zero
-        let zero = root.create_symbol(ident_ids, "zero");
-        let zero_expr = Expr::Literal(Literal::Int(0i128.to_ne_bytes()));
-        let zero_stmt = |next| Stmt::Let(zero, zero_expr, root.layout_isize, next);
+            let mask_lower_bits = union_layout.stores_tag_id_in_pointer(root.target_info);
 
-        // Null pointer with union layout
-        let null = root.create_symbol(ident_ids, "null");
-        let null_stmt =
-            |next| let_lowlevel(root.arena, root.layout_isize, null, PtrCast, &[zero], next);
+            (then_stmt, else_stmt, mask_lower_bits)
+        }
+        Layout::Boxed(inner_layout) => {
+            let then_stmt = reset_box_unique_stmt(root, ident_ids, ctx, *inner_layout, structure);
+            let else_stmt = reset_box_shared_stmt(root, ident_ids, ctx, layout, structure);
 
-        decrement_stmt(root.arena.alloc(
-            //
-            zero_stmt(root.arena.alloc(
-                //
-                null_stmt(root.arena.alloc(
-                    //
-                    Stmt::Ret(null),
-                )),
-            )),
-        ))
+            (then_stmt, else_stmt, false)
+        }
+        _ => unimplemented!("Reset is only implemented for UnionLayout and boxes"),
     };
 
     let if_stmt = Stmt::Switch {
@@ -341,7 +233,7 @@ pub fn refcount_reset_proc_body<'a>(
             ident_ids,
             structure,
             rc_ptr,
-            union_layout.stores_tag_id_in_pointer(root.target_info),
+            mask_lower_bits,
             root.arena.alloc(rc_stmt),
             addr,
         )
@@ -350,6 +242,236 @@ pub fn refcount_reset_proc_body<'a>(
     rc_ptr_stmt
 }
 
+/// Reset structure is unique. Decrement its children and return a pointer to the allocation.
+fn reset_union_unique_stmt<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    layout: Layout<'a>,
+    union_layout: UnionLayout<'a>,
+    structure: Symbol,
+    addr: Symbol,
+) -> Stmt<'a> {
+    use UnionLayout::*;
+
+    ctx.recursive_union = Some(union_layout);
+
+    let tag_layouts;
+    let mut null_id = None;
+    match union_layout {
+        NonRecursive(tags) => {
+            tag_layouts = tags;
+        }
+        Recursive(tags) => {
+            tag_layouts = tags;
+        }
+        NonNullableUnwrapped(field_layouts) => {
+            tag_layouts = root.arena.alloc([field_layouts]);
+        }
+        NullableWrapped {
+            other_tags: tags,
+            nullable_id,
+        } => {
+            null_id = Some(nullable_id);
+            tag_layouts = tags;
+        }
+        NullableUnwrapped {
+            other_fields,
+            nullable_id,
+        } => {
+            null_id = Some(nullable_id as TagIdIntType);
+            tag_layouts = root.arena.alloc([other_fields]);
+        }
+        NullablePointer { .. } => {
+            unreachable!("A nullable pointer has no allocation of its own to reset")
+        }
+    };
+
+    let tag_id_layout = union_layout.tag_id_layout();
+
+    let tag_id_sym = root.create_symbol(ident_ids, "tag_id");
+    let tag_id_stmt = |next| {
+        Stmt::Let(
+            tag_id_sym,
+            Expr::GetTagId {
+                structure,
+                union_layout,
+            },
+            tag_id_layout,
+            next,
+        )
+    };
+
+    let alloc_addr_stmt = {
+        let alignment = root.create_symbol(ident_ids, "alignment");
+        let alignment_expr = Expr::Literal(Literal::Int(
+            (layout.alignment_bytes(root.layout_interner, root.target_info) as i128).to_ne_bytes(),
+        ));
+        let alloc_addr = root.create_symbol(ident_ids, "alloc_addr");
+        let alloc_addr_expr = Expr::Call(Call {
+            call_type: CallType::LowLevel {
+                op: LowLevel::NumSubWrap,
+                update_mode: UpdateModeId::BACKEND_DUMMY,
+            },
+            arguments: root.arena.alloc([addr, alignment]),
+        });
+
+        Stmt::Let(
+            alignment,
+            alignment_expr,
+            root.layout_isize,
+            root.arena.alloc(
+                //
+                Stmt::Let(
+                    alloc_addr,
+                    alloc_addr_expr,
+                    root.layout_isize,
+                    root.arena.alloc(
+                        //
+                        Stmt::Ret(alloc_addr),
+                    ),
+                ),
+            ),
+        )
+    };
+
+    let rc_contents_stmt = refcount_union_contents(
+        root,
+        ident_ids,
+        ctx,
+        union_layout,
+        tag_layouts,
+        null_id,
+        structure,
+        tag_id_sym,
+        tag_id_layout,
+        alloc_addr_stmt,
+    );
+
+    tag_id_stmt(root.arena.alloc(
+        //
+        rc_contents_stmt,
+    ))
+}
+
+/// A unique box is reset by decrementing the value inside it. Its memory is then handed out as is.
+fn reset_box_unique_stmt<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    inner_layout: Layout<'a>,
+    structure: Symbol,
+) -> Stmt<'a> {
+    let inner = root.create_symbol(ident_ids, "inner");
+    let decrement_unit = root.create_symbol(ident_ids, "decrement_unit");
+
+    let ret_stmt = root.arena.alloc(Stmt::Ret(structure));
+
+    let decrement_stmt =
+        match root.call_specialized_op(ident_ids, ctx, inner_layout, root.arena.alloc([inner])) {
+            Some(decrement_expr) => root.arena.alloc(Stmt::Let(
+                decrement_unit,
+                decrement_expr,
+                LAYOUT_UNIT,
+                ret_stmt,
+            )),
+            None => ret_stmt,
+        };
+
+    Stmt::Let(
+        inner,
+        Expr::ExprUnbox { symbol: structure },
+        inner_layout,
+        decrement_stmt,
+    )
+}
+
+/// Unlike other reset structures, a box that is not unique is not reset to a NULL pointer.
+/// Instead it is decremented and a new box is returned in its place, so that the backends
+/// can always write the reused box into the returned memory, without checking for NULL.
+/// The new box holds a copy of the old value, which is overwritten without being decremented.
+fn reset_box_shared_stmt<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    layout: Layout<'a>,
+    structure: Symbol,
+) -> Stmt<'a> {
+    let inner_layout = match layout {
+        Layout::Boxed(inner_layout) => *inner_layout,
+        _ => unreachable!("Expected a box layout, found {:?}", layout),
+    };
+
+    let inner = root.create_symbol(ident_ids, "inner");
+    let fresh = root.create_symbol(ident_ids, "fresh");
+    let decrement_unit = root.create_symbol(ident_ids, "decrement_unit");
+    let decrement_expr = root
+        .call_specialized_op(ident_ids, ctx, layout, root.arena.alloc([structure]))
+        .unwrap();
+
+    Stmt::Let(
+        inner,
+        Expr::ExprUnbox { symbol: structure },
+        inner_layout,
+        root.arena.alloc(
+            //
+            Stmt::Let(
+                fresh,
+                Expr::ExprBox { symbol: inner },
+                layout,
+                root.arena.alloc(
+                    //
+                    Stmt::Let(
+                        decrement_unit,
+                        decrement_expr,
+                        LAYOUT_UNIT,
+                        root.arena.alloc(
+                            //
+                            Stmt::Ret(fresh),
+                        ),
+                    ),
+                ),
+            ),
+        ),
+    )
+}
+
+/// Decrement a reset structure that is not unique, and return a NULL pointer.
+fn reset_shared_stmt<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    layout: Layout<'a>,
+    structure: Symbol,
+) -> Stmt<'a> {
+    let decrement_unit = root.create_symbol(ident_ids, "decrement_unit");
+    let decrement_expr = root
+        .call_specialized_op(ident_ids, ctx, layout, root.arena.alloc([structure]))
+        .unwrap();
+    let decrement_stmt = |next| Stmt::Let(decrement_unit, decrement_expr, LAYOUT_UNIT, next);
+
+    // Zero
+    let zero = root.create_symbol(ident_ids, "zero");
+    let zero_expr = Expr::Literal(Literal::Int(0i128.to_ne_bytes()));
+    let zero_stmt = |next| Stmt::Let(zero, zero_expr, root.layout_isize, next);
+
+    // Null pointer with union layout
+    let null = root.create_symbol(ident_ids, "null");
+    let null_stmt =
+        |next| let_lowlevel(root.arena, root.layout_isize, null, PtrCast, &[zero], next);
+
+    decrement_stmt(root.arena.alloc(
+        //
+        zero_stmt(root.arena.alloc(
+            //
+            null_stmt(root.arena.alloc(
+                //
+                Stmt::Ret(null),
+            )),
+        )),
+    ))
+}
+
 // Check if refcounting is implemented yet. In the long term, this will be deleted.
 // In the short term, it helps us to skip refcounting and let it leak, so we can make
 // progress incrementally. Kept in sync with generate_procs using assertions.
@@ -381,6 +503,7 @@ where
             NullableUnwrapped { other_fields, .. } => other_fields
                 .iter()
                 .all(|l| is_rc_implemented_yet(interner, l)),
+            NullablePointer { pointer, .. } => is_rc_implemented_yet(interner, pointer),
         },
         Layout::LambdaSet(lambda_set) => {
             is_rc_implemented_yet(interner, &lambda_set.runtime_representation(interner))
@@ -1029,7 +1152,7 @@ fn refcount_union<'a>(
     use UnionLayout::*;
 
     let parent_rec_ptr_layout = ctx.recursive_union;
-    if !matches!(union, NonRecursive(_)) {
+    if !matches!(union, NonRecursive(_) | NullablePointer { .. }) {
         ctx.recursive_union = Some(union);
     }
 
@@ -1084,6 +1207,17 @@ fn refcount_union<'a>(
                 refcount_union_rec(root, ident_ids, ctx, union, tags, null_id, structure)
             }
         }
+
+        NullablePointer {
+            nullable_id,
+            pointer,
+        } => {
+            // the union is the pointer in its one field, so only that field has a refcount
+            let tags = root
+                .arena
+                .alloc(UnionLayout::nullable_pointer_tags(nullable_id, pointer));
+            refcount_union_nonrec(root, ident_ids, ctx, union, tags, structure)
+        }
     };
 
     ctx.recursive_union = parent_rec_ptr_layout;
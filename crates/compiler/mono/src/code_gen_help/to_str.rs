@@ -0,0 +1,571 @@
+use bumpalo::collections::vec::Vec;
+use roc_module::low_level::LowLevel;
+use roc_module::symbol::{IdentIds, Symbol};
+
+use crate::ir::{
+    BranchInfo, Call, CallType, Expr, JoinPointId, Literal, Param, Stmt, UpdateModeId,
+};
+use crate::layout::{Builtin, Layout, TagIdIntType, UnionLayout};
+
+use super::{CodeGenHelp, Context, LAYOUT_BOOL, LAYOUT_STR};
+
+const ARG_1: Symbol = Symbol::ARG_1;
+const ARG_2: Symbol = Symbol::ARG_2;
+
+/// Render a value as text, for `dbg` on targets where the host can't inspect Roc values.
+/// The helper proc appends the text for ARG_1 to the string ARG_2, and returns the result.
+///
+/// All we have at this point is the layout, so there are no names to print.
+/// Record fields come out in memory order, like `{ 1, "a" }`, and tags show their tag ID,
+/// like `#1 (#0 5)`. Two-tag enums have the same layout as Bool, so they print like one.
+/// The intermediate strings for numbers are never freed. That's fine for debugging output.
+pub fn to_str_generic<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    layout: Layout<'a>,
+) -> Stmt<'a> {
+    match layout {
+        Layout::Builtin(Builtin::Int(_) | Builtin::Float(_) | Builtin::Decimal) => {
+            to_str_number(root, ident_ids)
+        }
+        Layout::Builtin(Builtin::Bool) => to_str_bool(root, ident_ids),
+        Layout::Builtin(Builtin::Str) => to_str_str(root, ident_ids),
+        Layout::Builtin(Builtin::List(elem_layout)) => {
+            to_str_list(root, ident_ids, ctx, elem_layout)
+        }
+        Layout::Struct { field_layouts, .. } => to_str_struct(root, ident_ids, ctx, field_layouts),
+        Layout::Union(union_layout) => to_str_tag_union(root, ident_ids, ctx, union_layout),
+        Layout::Boxed(inner_layout) => to_str_boxed(root, ident_ids, ctx, inner_layout),
+        Layout::LambdaSet(_) => {
+            unreachable!("Functions have no helper proc for their text. Use a string literal.")
+        }
+        Layout::RecursivePointer => {
+            unreachable!(
+                "Can't render a RecursivePointer. Should have been replaced by a tag union."
+            )
+        }
+    }
+}
+
+/// Builds a chain of `Let`s that appends one piece of text after another
+struct Appender<'a> {
+    lets: Vec<'a, (Symbol, Expr<'a>, Layout<'a>)>,
+    acc: Symbol,
+}
+
+impl<'a> Appender<'a> {
+    fn new(root: &CodeGenHelp<'a>, acc: Symbol) -> Self {
+        Appender {
+            lets: Vec::new_in(root.arena),
+            acc,
+        }
+    }
+
+    fn let_(&mut self, symbol: Symbol, expr: Expr<'a>, layout: Layout<'a>) {
+        self.lets.push((symbol, expr, layout));
+    }
+
+    fn text(&mut self, root: &CodeGenHelp<'a>, ident_ids: &mut IdentIds, text: &str) {
+        let literal = root.create_symbol(ident_ids, "text");
+        let literal_expr = Expr::Literal(Literal::Str(root.arena.alloc_str(text)));
+
+        self.let_(literal, literal_expr, LAYOUT_STR);
+        self.string(root, ident_ids, literal);
+    }
+
+    fn string(&mut self, root: &CodeGenHelp<'a>, ident_ids: &mut IdentIds, string: Symbol) {
+        let acc = root.create_symbol(ident_ids, "acc");
+        let concat_expr = lowlevel_expr(root, LowLevel::StrConcat, &[self.acc, string]);
+
+        self.let_(acc, concat_expr, LAYOUT_STR);
+        self.acc = acc;
+    }
+
+    fn value(
+        &mut self,
+        root: &mut CodeGenHelp<'a>,
+        ident_ids: &mut IdentIds,
+        ctx: &mut Context<'a>,
+        layout: Layout<'a>,
+        value: Symbol,
+    ) {
+        if matches!(layout, Layout::LambdaSet(_)) {
+            return self.text(root, ident_ids, "<function>");
+        }
+
+        let acc = root.create_symbol(ident_ids, "acc");
+        let call_expr = root
+            .call_specialized_op(ident_ids, ctx, layout, root.arena.alloc([value, self.acc]))
+            .unwrap();
+
+        self.let_(acc, call_expr, LAYOUT_STR);
+        self.acc = acc;
+    }
+
+    /// A tag payload or boxed value, in parentheses if it has spaces in it, like `#1 (#0 5)`
+    fn payload(
+        &mut self,
+        root: &mut CodeGenHelp<'a>,
+        ident_ids: &mut IdentIds,
+        ctx: &mut Context<'a>,
+        layout: Layout<'a>,
+        value: Symbol,
+    ) {
+        let parens = needs_parens(&layout);
+
+        if parens {
+            self.text(root, ident_ids, "(");
+        }
+
+        self.value(root, ident_ids, ctx, layout, value);
+
+        if parens {
+            self.text(root, ident_ids, ")");
+        }
+    }
+
+    /// Wrap the `Let`s around the statement that uses the finished string
+    fn finish(self, root: &CodeGenHelp<'a>, following: Stmt<'a>) -> Stmt<'a> {
+        let mut stmt = following;
+        for (symbol, expr, layout) in self.lets.into_iter().rev() {
+            stmt = Stmt::Let(symbol, expr, layout, root.arena.alloc(stmt));
+        }
+        stmt
+    }
+
+    fn finish_and_return(self, root: &CodeGenHelp<'a>) -> Stmt<'a> {
+        let ret = Stmt::Ret(self.acc);
+        self.finish(root, ret)
+    }
+}
+
+fn lowlevel_expr<'a>(root: &CodeGenHelp<'a>, op: LowLevel, arguments: &[Symbol]) -> Expr<'a> {
+    Expr::Call(Call {
+        call_type: CallType::LowLevel {
+            op,
+            update_mode: UpdateModeId::BACKEND_DUMMY,
+        },
+        arguments: root.arena.alloc_slice_copy(arguments),
+    })
+}
+
+fn needs_parens(layout: &Layout) -> bool {
+    match layout {
+        Layout::Union(UnionLayout::NonRecursive(tags)) => {
+            tags.iter().any(|fields| !fields.is_empty())
+        }
+        Layout::Union(_) | Layout::RecursivePointer | Layout::Boxed(_) => true,
+        _ => false,
+    }
+}
+
+fn to_str_number<'a>(root: &CodeGenHelp<'a>, ident_ids: &mut IdentIds) -> Stmt<'a> {
+    let mut appender = Appender::new(root, ARG_2);
+
+    let number = root.create_symbol(ident_ids, "number");
+    appender.let_(
+        number,
+        lowlevel_expr(root, LowLevel::NumToStr, &[ARG_1]),
+        LAYOUT_STR,
+    );
+    appender.string(root, ident_ids, number);
+
+    appender.finish_and_return(root)
+}
+
+fn to_str_bool<'a>(root: &CodeGenHelp<'a>, ident_ids: &mut IdentIds) -> Stmt<'a> {
+    let mut if_true = Appender::new(root, ARG_2);
+    if_true.text(root, ident_ids, "Bool.true");
+
+    let mut if_false = Appender::new(root, ARG_2);
+    if_false.text(root, ident_ids, "Bool.false");
+
+    Stmt::Switch {
+        cond_symbol: ARG_1,
+        cond_layout: LAYOUT_BOOL,
+        branches: root
+            .arena
+            .alloc([(1, BranchInfo::None, if_true.finish_and_return(root))]),
+        default_branch: (
+            BranchInfo::None,
+            root.arena.alloc(if_false.finish_and_return(root)),
+        ),
+        ret_layout: LAYOUT_STR,
+    }
+}
+
+fn to_str_str<'a>(root: &CodeGenHelp<'a>, ident_ids: &mut IdentIds) -> Stmt<'a> {
+    let mut appender = Appender::new(root, ARG_2);
+
+    appender.text(root, ident_ids, "\"");
+    appender.string(root, ident_ids, ARG_1);
+    appender.text(root, ident_ids, "\"");
+
+    appender.finish_and_return(root)
+}
+
+fn to_str_struct<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    field_layouts: &'a [Layout<'a>],
+) -> Stmt<'a> {
+    let mut appender = Appender::new(root, ARG_2);
+
+    if field_layouts.is_empty() {
+        appender.text(root, ident_ids, "{}");
+        return appender.finish_and_return(root);
+    }
+
+    appender.text(root, ident_ids, "{ ");
+
+    for (i, layout) in field_layouts.iter().enumerate() {
+        if i > 0 {
+            appender.text(root, ident_ids, ", ");
+        }
+
+        let field = root.create_symbol(ident_ids, &format!("field_{}", i));
+        let field_expr = Expr::StructAtIndex {
+            index: i as u64,
+            field_layouts,
+            structure: ARG_1,
+        };
+        appender.let_(field, field_expr, *layout);
+        appender.value(root, ident_ids, ctx, *layout, field);
+    }
+
+    appender.text(root, ident_ids, " }");
+
+    appender.finish_and_return(root)
+}
+
+fn to_str_tag_union<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    union_layout: UnionLayout<'a>,
+) -> Stmt<'a> {
+    use UnionLayout::*;
+
+    let parent_rec_ptr_layout = ctx.recursive_union;
+    if !matches!(union_layout, NonRecursive(_) | NullablePointer { .. }) {
+        ctx.recursive_union = Some(union_layout);
+    }
+
+    let body = match union_layout {
+        NonRecursive(tags) => to_str_tag_union_help(root, ident_ids, ctx, union_layout, tags, None),
+
+        Recursive(tags) => to_str_tag_union_help(root, ident_ids, ctx, union_layout, tags, None),
+
+        NonNullableUnwrapped(field_layouts) => {
+            let tags = root.arena.alloc([field_layouts]);
+            to_str_tag_union_help(root, ident_ids, ctx, union_layout, tags, None)
+        }
+
+        NullableWrapped {
+            other_tags,
+            nullable_id,
+        } => to_str_tag_union_help(
+            root,
+            ident_ids,
+            ctx,
+            union_layout,
+            other_tags,
+            Some(nullable_id),
+        ),
+
+        NullableUnwrapped {
+            other_fields,
+            nullable_id,
+        } => to_str_tag_union_help(
+            root,
+            ident_ids,
+            ctx,
+            union_layout,
+            root.arena.alloc([other_fields]),
+            Some(nullable_id as TagIdIntType),
+        ),
+
+        NullablePointer {
+            nullable_id,
+            pointer,
+        } => {
+            // render like a non-recursive union; a null pointer is just another tag id
+            let tags = root
+                .arena
+                .alloc(UnionLayout::nullable_pointer_tags(nullable_id, pointer));
+            to_str_tag_union_help(root, ident_ids, ctx, union_layout, tags, None)
+        }
+    };
+
+    ctx.recursive_union = parent_rec_ptr_layout;
+
+    body
+}
+
+fn to_str_tag_union_help<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    union_layout: UnionLayout<'a>,
+    tag_layouts: &'a [&'a [Layout<'a>]],
+    nullable_id: Option<TagIdIntType>,
+) -> Stmt<'a> {
+    let tag_id_layout = union_layout.tag_id_layout();
+    let tag_id_sym = root.create_symbol(ident_ids, "tag_id");
+    let tag_id_expr = Expr::GetTagId {
+        structure: ARG_1,
+        union_layout,
+    };
+
+    let mut tag_branches = Vec::with_capacity_in(tag_layouts.len() + 1, root.arena);
+
+    if let Some(id) = nullable_id {
+        let mut appender = Appender::new(root, ARG_2);
+        appender.text(root, ident_ids, &format!("#{}", id));
+        tag_branches.push((
+            id as u64,
+            BranchInfo::None,
+            appender.finish_and_return(root),
+        ));
+    }
+
+    let mut tag_id: TagIdIntType = 0;
+    for field_layouts in tag_layouts.iter() {
+        if nullable_id == Some(tag_id) {
+            tag_id += 1;
+        }
+
+        let mut appender = Appender::new(root, ARG_2);
+        appender.text(root, ident_ids, &format!("#{}", tag_id));
+
+        for (i, layout) in field_layouts.iter().enumerate() {
+            let field = root.create_symbol(ident_ids, &format!("field_{}_{}", tag_id, i));
+            let field_expr = Expr::UnionAtIndex {
+                union_layout,
+                tag_id,
+                index: i as u64,
+                structure: ARG_1,
+            };
+            appender.let_(field, field_expr, *layout);
+            appender.text(root, ident_ids, " ");
+            appender.payload(root, ident_ids, ctx, *layout, field);
+        }
+
+        tag_branches.push((
+            tag_id as u64,
+            BranchInfo::None,
+            appender.finish_and_return(root),
+        ));
+
+        tag_id += 1;
+    }
+
+    // An uninhabited type has no values to render
+    let (_, _, default_stmt) = match tag_branches.pop() {
+        Some(branch) => branch,
+        None => return Stmt::Ret(ARG_2),
+    };
+
+    let tag_switch_stmt = Stmt::Switch {
+        cond_symbol: tag_id_sym,
+        cond_layout: tag_id_layout,
+        branches: tag_branches.into_bump_slice(),
+        default_branch: (BranchInfo::None, root.arena.alloc(default_stmt)),
+        ret_layout: LAYOUT_STR,
+    };
+
+    Stmt::Let(
+        tag_id_sym,
+        tag_id_expr,
+        tag_id_layout,
+        root.arena.alloc(tag_switch_stmt),
+    )
+}
+
+fn to_str_boxed<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    inner_layout: &'a Layout<'a>,
+) -> Stmt<'a> {
+    let mut appender = Appender::new(root, ARG_2);
+
+    let inner = root.create_symbol(ident_ids, "inner");
+    appender.let_(inner, Expr::ExprUnbox { symbol: ARG_1 }, *inner_layout);
+    appender.text(root, ident_ids, "Box.box ");
+    appender.payload(root, ident_ids, ctx, *inner_layout, inner);
+
+    appender.finish_and_return(root)
+}
+
+/// Lists are rendered with the same box-pointer loop as list equality (see `eq_list`),
+/// carrying the string along as a loop parameter. The separator is a parameter too, so the
+/// first element doesn't get a comma in front of it.
+fn to_str_list<'a>(
+    root: &mut CodeGenHelp<'a>,
+    ident_ids: &mut IdentIds,
+    ctx: &mut Context<'a>,
+    elem_layout: &Layout<'a>,
+) -> Stmt<'a> {
+    use LowLevel::*;
+    let layout_isize = root.layout_isize;
+
+    // A "Box" layout (heap pointer to a single list element)
+    let box_union_layout = UnionLayout::NonNullableUnwrapped(root.arena.alloc([*elem_layout]));
+    let box_layout = Layout::Union(box_union_layout);
+
+    //
+    // Loop initialisation
+    //
+
+    let mut init = Appender::new(root, ARG_2);
+
+    let len = root.create_symbol(ident_ids, "len");
+    init.let_(len, lowlevel_expr(root, ListLen, &[ARG_1]), layout_isize);
+
+    let elements = root.create_symbol(ident_ids, "elements");
+    let elements_expr = Expr::StructAtIndex {
+        index: 0,
+        field_layouts: root.arena.alloc([box_layout, layout_isize]),
+        structure: ARG_1,
+    };
+    init.let_(elements, elements_expr, box_layout);
+
+    let start = root.create_symbol(ident_ids, "start");
+    init.let_(
+        start,
+        lowlevel_expr(root, PtrCast, &[elements]),
+        layout_isize,
+    );
+
+    let size = root.create_symbol(ident_ids, "size");
+    let size_expr = Expr::Literal(Literal::Int(
+        (elem_layout.stack_size(root.layout_interner, root.target_info) as i128).to_ne_bytes(),
+    ));
+    init.let_(size, size_expr, layout_isize);
+
+    let list_size = root.create_symbol(ident_ids, "list_size");
+    init.let_(
+        list_size,
+        lowlevel_expr(root, NumMul, &[len, size]),
+        layout_isize,
+    );
+
+    let end = root.create_symbol(ident_ids, "end");
+    init.let_(
+        end,
+        lowlevel_expr(root, NumAdd, &[start, list_size]),
+        layout_isize,
+    );
+
+    let no_separator = root.create_symbol(ident_ids, "no_separator");
+    init.let_(
+        no_separator,
+        Expr::Literal(Literal::Str(root.arena.alloc_str(""))),
+        LAYOUT_STR,
+    );
+
+    init.text(root, ident_ids, "[");
+
+    //
+    // Loop name & parameters
+    //
+
+    let elems_loop = JoinPointId(root.create_symbol(ident_ids, "elems_loop"));
+    let addr = root.create_symbol(ident_ids, "addr");
+    let acc = root.create_symbol(ident_ids, "acc");
+    let separator = root.create_symbol(ident_ids, "separator");
+
+    let loop_params = [
+        (addr, layout_isize),
+        (acc, LAYOUT_STR),
+        (separator, LAYOUT_STR),
+    ]
+    .map(|(symbol, layout)| Param {
+        symbol,
+        borrow: false,
+        layout,
+    });
+
+    //
+    // if we've reached the end, close the brackets
+    //
+
+    let mut end_of_list = Appender::new(root, acc);
+    end_of_list.text(root, ident_ids, "]");
+
+    //
+    // otherwise render the current element and loop back again
+    //
+
+    let mut body = Appender::new(root, acc);
+    body.string(root, ident_ids, separator);
+
+    let box_ptr = root.create_symbol(ident_ids, "box");
+    body.let_(box_ptr, lowlevel_expr(root, PtrCast, &[addr]), box_layout);
+
+    let elem = root.create_symbol(ident_ids, "elem");
+    let elem_expr = Expr::UnionAtIndex {
+        structure: box_ptr,
+        union_layout: box_union_layout,
+        tag_id: 0,
+        index: 0,
+    };
+    body.let_(elem, elem_expr, *elem_layout);
+    body.value(root, ident_ids, ctx, *elem_layout, elem);
+
+    let next_addr = root.create_symbol(ident_ids, "next_addr");
+    body.let_(
+        next_addr,
+        lowlevel_expr(root, NumAdd, &[addr, size]),
+        layout_isize,
+    );
+
+    let comma = root.create_symbol(ident_ids, "comma");
+    body.let_(
+        comma,
+        Expr::Literal(Literal::Str(root.arena.alloc_str(", "))),
+        LAYOUT_STR,
+    );
+
+    let jump_back = Stmt::Jump(elems_loop, root.arena.alloc([next_addr, body.acc, comma]));
+
+    //
+    // Control flow
+    //
+
+    let is_end = root.create_symbol(ident_ids, "is_end");
+    let is_end_expr = lowlevel_expr(root, NumGte, &[addr, end]);
+
+    let if_end_of_list = Stmt::Switch {
+        cond_symbol: is_end,
+        cond_layout: LAYOUT_BOOL,
+        ret_layout: LAYOUT_STR,
+        branches: root
+            .arena
+            .alloc([(1, BranchInfo::None, end_of_list.finish_and_return(root))]),
+        default_branch: (
+            BranchInfo::None,
+            root.arena.alloc(body.finish(root, jump_back)),
+        ),
+    };
+
+    let joinpoint_loop = Stmt::Join {
+        id: elems_loop,
+        parameters: root.arena.alloc(loop_params),
+        body: root.arena.alloc(Stmt::Let(
+            is_end,
+            is_end_expr,
+            LAYOUT_BOOL,
+            root.arena.alloc(if_end_of_list),
+        )),
+        remainder: root.arena.alloc(Stmt::Jump(
+            elems_loop,
+            root.arena.alloc([start, init.acc, no_separator]),
+        )),
+    };
+
+    init.finish(root, joinpoint_loop)
+}
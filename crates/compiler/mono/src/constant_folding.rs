@@ -0,0 +1,573 @@
+//! Evaluate computations on literals at compile time.
+//!
+//! Arithmetic and comparisons whose arguments are all literals are replaced by their result, and a
+//! `Switch` (the `if` or `when`) on a literal is replaced by the branch that will be taken. Calls to
+//! builtins that are just a low-level operation (like `Num.add`), or just a literal (like
+//! `Bool.true`) are folded too, and constants propagate through the `let`s that bind them.
+//!
+//! The literals that are no longer used are removed afterwards by [crate::dead_code], so e.g.
+//!
+//! ```text
+//! let x = 1;
+//! let y = 2;
+//! let z = CallByName Num.add x y;
+//! ret z;
+//! ```
+//!
+//! becomes
+//!
+//! ```text
+//! let z = 3;
+//! ret z;
+//! ```
+//!
+//! Operations that would fail at runtime, like an addition that overflows, are left alone.
+//!
+//! Like the other optimizations, folding is skipped in development builds, so that the IR of
+//! `roc dev` and of the mono tests stays close to the source.
+
+use crate::ir::{Call, CallType, Expr, Literal, OptLevel, Proc, ProcLayout, Stmt};
+use crate::layout::{Builtin, Layout};
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+use roc_builtins::bitcode::IntWidth;
+use roc_collections::all::MutMap;
+use roc_module::low_level::LowLevel;
+use roc_module::symbol::Symbol;
+
+/// What a call to a procedure can be replaced with, if its arguments are known
+#[derive(Debug, Clone, Copy)]
+enum Foldable<'a> {
+    /// A procedure without arguments that returns this literal
+    Constant(Literal<'a>),
+    /// A procedure that applies this operation to its arguments
+    LowLevel(LowLevel),
+}
+
+pub fn fold_constants<'a>(
+    arena: &'a Bump,
+    opt_level: OptLevel,
+    procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+) {
+    if let OptLevel::Development = opt_level {
+        return;
+    }
+
+    let foldable: MutMap<_, _> = procs
+        .iter()
+        .filter_map(|(key, proc)| Some((*key, foldable_proc(proc)?)))
+        .collect();
+
+    for proc in procs.values_mut() {
+        let mut env = Env {
+            arena,
+            foldable: &foldable,
+            known: MutMap::default(),
+        };
+
+        proc.body = env.fold_stmt(&proc.body).clone();
+    }
+}
+
+fn foldable_proc<'a>(proc: &Proc<'a>) -> Option<Foldable<'a>> {
+    match &proc.body {
+        Stmt::Let(symbol, expr, _, Stmt::Ret(ret)) if symbol == ret => match expr {
+            Expr::Literal(literal) if proc.args.is_empty() => Some(Foldable::Constant(*literal)),
+            Expr::Call(Call {
+                call_type: CallType::LowLevel { op, .. },
+                arguments,
+            }) if is_foldable_op(*op)
+                && arguments.iter().eq(proc.args.iter().map(|(_, arg)| arg)) =>
+            {
+                Some(Foldable::LowLevel(*op))
+            }
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+fn is_foldable_op(op: LowLevel) -> bool {
+    use LowLevel::*;
+
+    matches!(
+        op,
+        NumAdd
+            | NumAddWrap
+            | NumSub
+            | NumSubWrap
+            | NumMul
+            | NumMulWrap
+            | NumGt
+            | NumGte
+            | NumLt
+            | NumLte
+            | Eq
+            | NotEq
+            | And
+            | Or
+            | Not
+    )
+}
+
+struct Env<'a, 'f> {
+    arena: &'a Bump,
+    foldable: &'f MutMap<(Symbol, ProcLayout<'a>), Foldable<'a>>,
+    /// The symbols that are bound to a literal. Symbols are unique within a procedure, so this
+    /// does not need to be scoped.
+    known: MutMap<Symbol, Literal<'a>>,
+}
+
+impl<'a, 'f> Env<'a, 'f> {
+    fn fold_stmt(&mut self, stmt: &Stmt<'a>) -> &'a Stmt<'a> {
+        use Stmt::*;
+
+        let arena = self.arena;
+
+        match stmt {
+            Let(symbol, expr, layout, cont) => {
+                let expr = match self.fold_expr(expr, layout) {
+                    Some(literal) => {
+                        self.known.insert(*symbol, literal);
+                        Expr::Literal(literal)
+                    }
+                    None => expr.clone(),
+                };
+
+                let cont = self.fold_stmt(cont);
+
+                arena.alloc(Let(*symbol, expr, *layout, cont))
+            }
+            Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout,
+            } => {
+                if let Some(value) = self.known.get(cond_symbol).and_then(switch_value) {
+                    let taken = branches
+                        .iter()
+                        .find(|(label, _, _)| *label == value)
+                        .map_or(default_branch.1, |(_, _, branch)| branch);
+
+                    return self.fold_stmt(taken);
+                }
+
+                let branches = Vec::from_iter_in(
+                    branches.iter().map(|(label, info, branch)| {
+                        (*label, info.clone(), self.fold_stmt(branch).clone())
+                    }),
+                    arena,
+                );
+
+                arena.alloc(Switch {
+                    cond_symbol: *cond_symbol,
+                    cond_layout: *cond_layout,
+                    branches: branches.into_bump_slice(),
+                    default_branch: (default_branch.0.clone(), self.fold_stmt(default_branch.1)),
+                    ret_layout: *ret_layout,
+                })
+            }
+            Join {
+                id,
+                parameters,
+                body,
+                remainder,
+            } => {
+                let body = self.fold_stmt(body);
+                let remainder = self.fold_stmt(remainder);
+
+                arena.alloc(Join {
+                    id: *id,
+                    parameters: *parameters,
+                    body,
+                    remainder,
+                })
+            }
+            Refcounting(modify, cont) => arena.alloc(Refcounting(*modify, self.fold_stmt(cont))),
+            Expect {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => arena.alloc(Expect {
+                condition: *condition,
+                region: *region,
+                lookups: *lookups,
+                variables: *variables,
+                remainder: self.fold_stmt(remainder),
+            }),
+            ExpectFx {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => arena.alloc(ExpectFx {
+                condition: *condition,
+                region: *region,
+                lookups: *lookups,
+                variables: *variables,
+                remainder: self.fold_stmt(remainder),
+            }),
+            Ret(_) | Jump(_, _) | Crash(..) => arena.alloc(stmt.clone()),
+        }
+    }
+
+    /// The literal that `expr` evaluates to, if it can be known at compile time
+    fn fold_expr(&self, expr: &Expr<'a>, layout: &Layout<'a>) -> Option<Literal<'a>> {
+        match expr {
+            Expr::Literal(literal) => Some(*literal),
+            Expr::Call(Call {
+                call_type: CallType::LowLevel { op, .. },
+                arguments,
+            }) if is_foldable_op(*op) => self.fold_low_level(*op, arguments, layout),
+            Expr::Call(Call {
+                call_type:
+                    CallType::ByName {
+                        name,
+                        ret_layout,
+                        arg_layouts,
+                        ..
+                    },
+                arguments,
+            }) => {
+                let proc_layout = ProcLayout {
+                    arguments: arg_layouts,
+                    result: **ret_layout,
+                    captures_niche: name.captures_niche(),
+                };
+
+                match self.foldable.get(&(name.name(), proc_layout))? {
+                    Foldable::Constant(literal) => Some(*literal),
+                    Foldable::LowLevel(op) => self.fold_low_level(*op, arguments, layout),
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn fold_low_level(
+        &self,
+        op: LowLevel,
+        arguments: &[Symbol],
+        layout: &Layout<'a>,
+    ) -> Option<Literal<'a>> {
+        use LowLevel::*;
+
+        match (op, arguments) {
+            (Not, [x]) => match self.known.get(x)? {
+                Literal::Bool(x) => Some(Literal::Bool(!x)),
+                _ => None,
+            },
+            (And | Or, [x, y]) => match (self.known.get(x)?, self.known.get(y)?) {
+                (Literal::Bool(x), Literal::Bool(y)) if op == And => Some(Literal::Bool(*x && *y)),
+                (Literal::Bool(x), Literal::Bool(y)) => Some(Literal::Bool(*x || *y)),
+                _ => None,
+            },
+            (_, [x, y]) => {
+                let x = IntValue::from_literal(self.known.get(x)?)?;
+                let y = IntValue::from_literal(self.known.get(y)?)?;
+
+                match op {
+                    Eq => Some(Literal::Bool(x == y)),
+                    NotEq => Some(Literal::Bool(x != y)),
+                    NumGt => Some(Literal::Bool(x > y)),
+                    NumGte => Some(Literal::Bool(x >= y)),
+                    NumLt => Some(Literal::Bool(x < y)),
+                    NumLte => Some(Literal::Bool(x <= y)),
+                    _ => match layout {
+                        Layout::Builtin(Builtin::Int(width)) => fold_arithmetic(op, *width, x, y),
+                        _ => None,
+                    },
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// The value of an integer, or of a bool or byte (the representation of some tag unions)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum IntValue {
+    Signed(i128),
+    /// Only used for values that don't fit an `i128`, so it is always greater than a `Signed`
+    Unsigned(u128),
+}
+
+impl IntValue {
+    fn from_literal(literal: &Literal) -> Option<Self> {
+        let value = match literal {
+            Literal::Int(bytes) => IntValue::Signed(i128::from_ne_bytes(*bytes)),
+            Literal::U128(bytes) => {
+                let value = u128::from_ne_bytes(*bytes);
+
+                match i128::try_from(value) {
+                    Ok(value) => IntValue::Signed(value),
+                    Err(_) => IntValue::Unsigned(value),
+                }
+            }
+            Literal::Bool(value) => IntValue::Signed(*value as i128),
+            Literal::Byte(value) => IntValue::Signed(*value as i128),
+            Literal::Float(_) | Literal::Decimal(_) | Literal::Str(_) => return None,
+        };
+
+        Some(value)
+    }
+
+    fn into_literal<'a>(self) -> Literal<'a> {
+        match self {
+            IntValue::Signed(value) => Literal::Int(value.to_ne_bytes()),
+            IntValue::Unsigned(value) => Literal::U128(value.to_ne_bytes()),
+        }
+    }
+}
+
+fn fold_arithmetic<'a>(
+    op: LowLevel,
+    width: IntWidth,
+    x: IntValue,
+    y: IntValue,
+) -> Option<Literal<'a>> {
+    use LowLevel::*;
+
+    let result = if width == IntWidth::U128 {
+        let as_u128 = |value| match value {
+            IntValue::Signed(value) => value as u128,
+            IntValue::Unsigned(value) => value,
+        };
+        let (x, y) = (as_u128(x), as_u128(y));
+
+        let result = match op {
+            NumAdd => x.checked_add(y)?,
+            NumAddWrap => x.wrapping_add(y),
+            NumSub => x.checked_sub(y)?,
+            NumSubWrap => x.wrapping_sub(y),
+            NumMul => x.checked_mul(y)?,
+            NumMulWrap => x.wrapping_mul(y),
+            _ => return None,
+        };
+
+        match i128::try_from(result) {
+            Ok(result) => IntValue::Signed(result),
+            Err(_) => IntValue::Unsigned(result),
+        }
+    } else {
+        let (x, y) = match (x, y) {
+            (IntValue::Signed(x), IntValue::Signed(y)) => (x, y),
+            _ => return None,
+        };
+
+        let result = match op {
+            NumAdd => x.checked_add(y).filter(|result| fits(width, *result))?,
+            NumAddWrap => wrap(width, x.wrapping_add(y)),
+            NumSub => x.checked_sub(y).filter(|result| fits(width, *result))?,
+            NumSubWrap => wrap(width, x.wrapping_sub(y)),
+            NumMul => x.checked_mul(y).filter(|result| fits(width, *result))?,
+            NumMulWrap => wrap(width, x.wrapping_mul(y)),
+            _ => return None,
+        };
+
+        IntValue::Signed(result)
+    };
+
+    Some(result.into_literal())
+}
+
+fn fits(width: IntWidth, value: i128) -> bool {
+    value == wrap(width, value)
+}
+
+/// Wrap around a value like an integer of the given width would
+fn wrap(width: IntWidth, value: i128) -> i128 {
+    use IntWidth::*;
+
+    match width {
+        U8 => value as u8 as i128,
+        U16 => value as u16 as i128,
+        U32 => value as u32 as i128,
+        U64 => value as u64 as i128,
+        I8 => value as i8 as i128,
+        I16 => value as i16 as i128,
+        I32 => value as i32 as i128,
+        I64 => value as i64 as i128,
+        I128 | U128 => value,
+    }
+}
+
+/// The label of the `Switch` branch that is taken when the condition is this literal
+fn switch_value(literal: &Literal) -> Option<u64> {
+    match literal {
+        Literal::Int(bytes) => Some(i128::from_ne_bytes(*bytes) as u64),
+        Literal::U128(bytes) => Some(u128::from_ne_bytes(*bytes) as u64),
+        Literal::Bool(value) => Some(*value as u64),
+        Literal::Byte(value) => Some(*value as u64),
+        Literal::Float(_) | Literal::Decimal(_) | Literal::Str(_) => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::fold_constants;
+    use crate::ir::text::{parse_procs, procs_to_text};
+    use crate::ir::{OptLevel, ProcLayout};
+    use crate::layout::STLayoutInterner;
+    use bumpalo::Bump;
+    use roc_collections::all::MutMap;
+    use roc_intern::SingleThreadedInterner;
+    use roc_module::symbol::Interns;
+
+    /// Fold the constants in the procedures of `text`, and print the last one
+    fn fold(text: &str) -> String {
+        let arena = Bump::new();
+        let mut interns = Interns::default();
+        let interner: STLayoutInterner = SingleThreadedInterner::with_capacity(4);
+
+        let procs = match parse_procs(&arena, &mut interns, text) {
+            Ok(procs) => procs,
+            Err(error) => panic!("{}", error),
+        };
+        let last = procs.last().unwrap().name.name();
+        let mut procs: MutMap<_, _> = procs
+            .into_iter()
+            .map(|proc| {
+                let arguments = arena.alloc_slice_fill_iter(proc.args.iter().map(|(l, _)| *l));
+                let proc_layout = ProcLayout::new(
+                    &arena,
+                    arguments,
+                    proc.name.captures_niche(),
+                    proc.ret_layout,
+                );
+
+                ((proc.name.name(), proc_layout), proc)
+            })
+            .collect();
+
+        fold_constants(&arena, OptLevel::Normal, &mut procs);
+
+        let folded = procs.values().filter(|proc| proc.name.name() == last);
+
+        procs_to_text(&interns, &interner, folded)
+    }
+
+    #[test]
+    fn fold_arithmetic_and_comparisons() {
+        const TEXT: &str = r#"procedure Test.0 () -> Int1:
+    let Test.1 : I64 = 1i64;
+    let Test.2 : I64 = 2i64;
+    let Test.3 : I64 = lowlevel NumAdd Test.1 Test.2;
+    let Test.4 : Int1 = lowlevel NumGt Test.3 Test.2;
+    ret Test.4;
+"#;
+
+        const FOLDED: &str = r#"procedure Test.0 () -> Int1:
+    let Test.1 : I64 = 1i64;
+    let Test.2 : I64 = 2i64;
+    let Test.3 : I64 = 3i64;
+    let Test.4 : Int1 = true;
+    ret Test.4;
+"#;
+
+        assert_eq!(fold(TEXT), FOLDED);
+    }
+
+    #[test]
+    fn fold_calls_to_builtins() {
+        const TEXT: &str = r#"procedure Num.19 (Num.1 : U8, Num.2 : U8) -> U8:
+    let Num.3 : U8 = lowlevel NumAdd Num.1 Num.2;
+    ret Num.3;
+
+procedure Bool.2 () -> Int1:
+    let Bool.1 : Int1 = true;
+    ret Bool.1;
+
+procedure Test.0 () -> {Int1, U8}:
+    let Test.1 : U8 = 20i64;
+    let Test.2 : U8 = CallByName Num.19 Test.1 Test.1;
+    let Test.3 : Int1 = CallByName Bool.2;
+    let Test.4 : {Int1, U8} = Struct {Test.3, Test.2};
+    ret Test.4;
+"#;
+
+        const FOLDED: &str = r#"procedure Test.0 () -> {Int1, U8}:
+    let Test.1 : U8 = 20i64;
+    let Test.2 : U8 = 40i64;
+    let Test.3 : Int1 = true;
+    let Test.4 : {Int1, U8} = Struct {Test.3, Test.2};
+    ret Test.4;
+"#;
+
+        assert_eq!(fold(TEXT), FOLDED);
+    }
+
+    #[test]
+    fn keep_overflowing_arithmetic() {
+        const TEXT: &str = r#"procedure Test.0 () -> {U8, U8}:
+    let Test.1 : U8 = 255i64;
+    let Test.2 : U8 = 1i64;
+    let Test.3 : U8 = lowlevel NumAdd Test.1 Test.2;
+    let Test.4 : U8 = lowlevel NumAddWrap Test.1 Test.2;
+    let Test.5 : {U8, U8} = Struct {Test.3, Test.4};
+    ret Test.5;
+"#;
+
+        const FOLDED: &str = r#"procedure Test.0 () -> {U8, U8}:
+    let Test.1 : U8 = 255i64;
+    let Test.2 : U8 = 1i64;
+    let Test.3 : U8 = lowlevel NumAdd Test.1 Test.2;
+    let Test.4 : U8 = 0i64;
+    let Test.5 : {U8, U8} = Struct {Test.3, Test.4};
+    ret Test.5;
+"#;
+
+        assert_eq!(fold(TEXT), FOLDED);
+    }
+
+    #[test]
+    fn fold_switch_on_known_condition() {
+        const TEXT: &str = r#"procedure Test.0 (Test.1 : I64) -> I64:
+    let Test.2 : I64 = 1i64;
+    let Test.3 : I64 = 2i64;
+    let Test.4 : Int1 = lowlevel NumLt Test.2 Test.3;
+    switch Test.4 : Int1 -> I64:
+        case 1:
+            ret Test.1;
+        default:
+            let Test.5 : I64 = 0i64;
+            ret Test.5;
+"#;
+
+        const FOLDED: &str = r#"procedure Test.0 (Test.1 : I64) -> I64:
+    let Test.2 : I64 = 1i64;
+    let Test.3 : I64 = 2i64;
+    let Test.4 : Int1 = true;
+    ret Test.1;
+"#;
+
+        assert_eq!(fold(TEXT), FOLDED);
+    }
+
+    #[test]
+    fn skip_folding_in_development() {
+        const TEXT: &str = r#"procedure Test.0 () -> I64:
+    let Test.1 : I64 = 1i64;
+    let Test.2 : I64 = lowlevel NumAdd Test.1 Test.1;
+    ret Test.2;
+"#;
+
+        let arena = Bump::new();
+        let mut interns = Interns::default();
+        let interner: STLayoutInterner = SingleThreadedInterner::with_capacity(4);
+
+        let proc = parse_procs(&arena, &mut interns, TEXT).unwrap().remove(0);
+        let proc_layout = ProcLayout::new(&arena, &[], proc.name.captures_niche(), proc.ret_layout);
+        let mut procs = MutMap::default();
+        procs.insert((proc.name.name(), proc_layout), proc);
+
+        fold_constants(&arena, OptLevel::Development, &mut procs);
+
+        assert_eq!(procs_to_text(&interns, &interner, procs.values()), TEXT);
+    }
+}
@@ -0,0 +1,234 @@
+//! Remove code that can never run, and values that are never used.
+//!
+//! After specialization, a `Switch` on the tag id of a union can have branches for tag ids that the
+//! layout of the union does not have. Those branches are removed, and when only one branch is left
+//! the `Switch` is replaced by it. If the branches cover every tag id, the default branch can't be
+//! taken either.
+//!
+//! Bindings whose value is never used are removed too, when computing the value has no effect (so
+//! e.g. a call, which could crash, is kept). That includes the literals left over by constant
+//! folding and the captures of a closure that the function doesn't refer to. Join points that are
+//! never jumped to are removed as well.
+//!
+//! Like the other optimizations, this is skipped in development builds.
+
+use crate::inc_dec::occurring_variables_expr;
+use crate::ir::{Expr, JoinPointId, OptLevel, Proc, ProcLayout, Stmt};
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+use roc_collections::all::{MutMap, MutSet};
+use roc_module::symbol::Symbol;
+
+pub fn eliminate_dead_code<'a>(
+    arena: &'a Bump,
+    opt_level: OptLevel,
+    procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+) {
+    if let OptLevel::Development = opt_level {
+        return;
+    }
+
+    for proc in procs.values_mut() {
+        let mut env = Env {
+            arena,
+            tag_counts: MutMap::default(),
+            used: MutSet::default(),
+            jumped_to: MutSet::default(),
+        };
+
+        proc.body = env.eliminate_stmt(&proc.body).clone();
+    }
+}
+
+struct Env<'a> {
+    arena: &'a Bump,
+    /// The number of tags of the union whose tag id a symbol is bound to
+    tag_counts: MutMap<Symbol, usize>,
+    /// The symbols that are used by the statements that were visited. Statements are visited
+    /// bottom-up, so when a binding is visited all of its uses have been seen.
+    used: MutSet<Symbol>,
+    /// Like `used`, but for join points
+    jumped_to: MutSet<JoinPointId>,
+}
+
+impl<'a> Env<'a> {
+    fn eliminate_stmt(&mut self, stmt: &Stmt<'a>) -> &'a Stmt<'a> {
+        use Stmt::*;
+
+        let arena = self.arena;
+
+        match stmt {
+            Let(symbol, expr, layout, cont) => {
+                if let Expr::GetTagId { union_layout, .. } = expr {
+                    self.tag_counts
+                        .insert(*symbol, union_layout.number_of_tags());
+                }
+
+                let cont = self.eliminate_stmt(cont);
+
+                if !self.used.contains(symbol) && has_no_effects(expr) {
+                    return cont;
+                }
+
+                occurring_variables_expr(expr, &mut self.used);
+
+                arena.alloc(Let(*symbol, expr.clone(), *layout, cont))
+            }
+            Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout,
+            } => {
+                let tag_count = self.tag_counts.get(cond_symbol).copied();
+
+                let reachable = Vec::from_iter_in(
+                    branches.iter().filter(|(label, _, _)| {
+                        tag_count.map_or(true, |count| *label < count as u64)
+                    }),
+                    arena,
+                );
+
+                // Branch labels are distinct, so if there is a branch for every tag id, the
+                // default branch is never taken
+                let default_reachable = tag_count.map_or(true, |count| reachable.len() < count);
+
+                let (branches, default_branch) = match reachable.split_last() {
+                    None => return self.eliminate_stmt(default_branch.1),
+                    Some(((_, _, branch), [])) if !default_reachable => {
+                        return self.eliminate_stmt(branch)
+                    }
+                    Some(((_, info, branch), rest)) if !default_reachable => {
+                        (rest, (info.clone(), self.eliminate_stmt(branch)))
+                    }
+                    Some(_) => (
+                        reachable.as_slice(),
+                        (
+                            default_branch.0.clone(),
+                            self.eliminate_stmt(default_branch.1),
+                        ),
+                    ),
+                };
+
+                let branches = Vec::from_iter_in(
+                    branches.iter().map(|(label, info, branch)| {
+                        (*label, info.clone(), self.eliminate_stmt(branch).clone())
+                    }),
+                    arena,
+                );
+
+                self.used.insert(*cond_symbol);
+
+                arena.alloc(Switch {
+                    cond_symbol: *cond_symbol,
+                    cond_layout: *cond_layout,
+                    branches: branches.into_bump_slice(),
+                    default_branch,
+                    ret_layout: *ret_layout,
+                })
+            }
+            Join {
+                id,
+                parameters,
+                body,
+                remainder,
+            } => {
+                let remainder = self.eliminate_stmt(remainder);
+
+                // Only the remainder can enter the join point; jumps in its body are loops
+                if !self.jumped_to.contains(id) {
+                    return remainder;
+                }
+
+                let body = self.eliminate_stmt(body);
+
+                arena.alloc(Join {
+                    id: *id,
+                    parameters: *parameters,
+                    body,
+                    remainder,
+                })
+            }
+            Jump(id, arguments) => {
+                self.jumped_to.insert(*id);
+                self.used.extend(arguments.iter().copied());
+
+                arena.alloc(stmt.clone())
+            }
+            Refcounting(modify, cont) => {
+                let cont = self.eliminate_stmt(cont);
+
+                self.used.insert(modify.get_symbol());
+
+                arena.alloc(Refcounting(*modify, cont))
+            }
+            Expect {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => {
+                let remainder = self.eliminate_stmt(remainder);
+
+                self.used.insert(*condition);
+                self.used.extend(lookups.iter().copied());
+
+                arena.alloc(Expect {
+                    condition: *condition,
+                    region: *region,
+                    lookups: *lookups,
+                    variables: *variables,
+                    remainder,
+                })
+            }
+            ExpectFx {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => {
+                let remainder = self.eliminate_stmt(remainder);
+
+                self.used.insert(*condition);
+                self.used.extend(lookups.iter().copied());
+
+                arena.alloc(ExpectFx {
+                    condition: *condition,
+                    region: *region,
+                    lookups: *lookups,
+                    variables: *variables,
+                    remainder,
+                })
+            }
+            Ret(symbol) | Crash(symbol, _) => {
+                self.used.insert(*symbol);
+
+                arena.alloc(stmt.clone())
+            }
+        }
+    }
+}
+
+/// Whether evaluating `expr` only produces its value, so that it can be skipped if the value is
+/// not used
+fn has_no_effects(expr: &Expr) -> bool {
+    use Expr::*;
+
+    match expr {
+        Literal(_)
+        | Tag { .. }
+        | Struct(_)
+        | StructAtIndex { .. }
+        | GetTagId { .. }
+        | UnionAtIndex { .. }
+        | Array { .. }
+        | EmptyArray
+        | ExprBox { .. }
+        | ExprUnbox { .. } => true,
+        // A call can crash, loop forever or perform effects, and a reuse belongs to its reset
+        Call(_) | Reuse { .. } | ReuseBox { .. } | Reset { .. } | RuntimeErrorFunction(_) => false,
+    }
+}
@@ -1,5 +1,7 @@
 mod checker;
 mod report;
+mod specializations;
 
-pub use checker::{check_procs, Problem, Problems};
+pub use checker::{check_closure_erasure, check_procs, Problem, Problems};
 pub use report::format_problems;
+pub use specializations::explain_specializations;
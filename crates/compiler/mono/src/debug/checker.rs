@@ -1,15 +1,16 @@
 //! Type-checking of the generated [ir][crate::ir::Proc].
 
 use bumpalo::Bump;
-use roc_collections::{MutMap, VecMap, VecSet};
+use roc_collections::{MutMap, MutSet, VecMap, VecSet};
 use roc_module::symbol::Symbol;
 
 use crate::{
+    closure_erasure::erasable_arguments,
     ir::{
         Call, CallSpecId, CallType, Expr, HigherOrderLowLevel, JoinPointId, ListLiteralElement,
-        ModifyRc, Param, Proc, ProcLayout, Stmt,
+        ModifyRc, Param, Proc, ProcLayout, Stmt, UpdateModeId,
     },
-    layout::{Builtin, Layout, STLayoutInterner, TagIdIntType, UnionLayout},
+    layout::{Builtin, LambdaSet, Layout, STLayoutInterner, TagIdIntType, UnionLayout},
 };
 
 pub enum UseKind {
@@ -111,6 +112,32 @@ pub enum ProblemKind<'a> {
         num_needed: usize,
         num_given: usize,
     },
+    UnerasedClosureArgument {
+        symbol: Symbol,
+        lambda_set: LambdaSet<'a>,
+    },
+    RefcountingNonRefcounted {
+        symbol: Symbol,
+        def_line: usize,
+        layout: Layout<'a>,
+    },
+    ReuseWithoutReset {
+        symbol: Symbol,
+        def_line: usize,
+    },
+    ReuseUpdateModeMismatch {
+        symbol: Symbol,
+        def_line: usize,
+    },
+    UseAfterDecrement {
+        symbol: Symbol,
+        dec_line: usize,
+    },
+    DecrementedTooOften {
+        symbol: Symbol,
+        def_line: usize,
+        dec_line: usize,
+    },
 }
 
 pub struct Problem<'a> {
@@ -148,6 +175,8 @@ pub fn check_procs<'a>(
             procs,
             venv: Default::default(),
             joinpoints: Default::default(),
+            resets: Default::default(),
+            references: Default::default(),
             line: 0,
         };
         ctx.check_proc(proc);
@@ -156,9 +185,54 @@ pub fn check_procs<'a>(
     Problems(problems)
 }
 
+/// Checks that [closure erasure][crate::closure_erasure] left no procedure that it could rewrite
+/// taking the closure of a single function that captures nothing, which its callers would have
+/// to build, rather than calling the function directly.
+pub fn check_closure_erasure<'a>(
+    arena: &'a Bump,
+    interner: &'a STLayoutInterner<'a>,
+    exposed_to_host: &MutSet<Symbol>,
+    procs: &'a Procs<'a>,
+) -> Problems<'a> {
+    let mut problems = Vec::new();
+
+    for (key, (_, keep)) in erasable_arguments(arena, interner, exposed_to_host, procs) {
+        let proc = &procs[&key];
+
+        for ((layout, symbol), keep) in proc.args.iter().zip(keep) {
+            if let (Layout::LambdaSet(lambda_set), false) = (layout, keep) {
+                problems.push(Problem {
+                    proc,
+                    proc_layout: key.1,
+                    line: 0,
+                    kind: ProblemKind::UnerasedClosureArgument {
+                        symbol: *symbol,
+                        lambda_set: *lambda_set,
+                    },
+                });
+            }
+        }
+    }
+
+    Problems(problems)
+}
+
 type VEnv<'a> = VecMap<Symbol, (usize, Layout<'a>)>;
 type JoinPoints<'a> = VecMap<JoinPointId, (usize, &'a [Param<'a>])>;
 type CallSpecIds = VecMap<CallSpecId, usize>;
+type Resets = VecMap<Symbol, UpdateModeId>;
+type References = VecMap<Symbol, Owned>;
+
+/// How many references to a symbol's value a procedure owns at most, going by its definition and
+/// the increments and decrements since. Owned calls and jumps take references too, but can't
+/// give any back, so once this is zero the value may have been freed.
+#[derive(Clone, Copy)]
+struct Owned {
+    count: u64,
+    /// The line of the last decrement, if any
+    dec_line: usize,
+}
+
 struct Ctx<'a, 'r> {
     arena: &'a Bump,
     interner: &'a STLayoutInterner<'a>,
@@ -170,6 +244,9 @@ struct Ctx<'a, 'r> {
     ret_layout: Layout<'a>,
     venv: VEnv<'a>,
     joinpoints: JoinPoints<'a>,
+    /// The symbols bound to a [Expr::Reset], and their update mode
+    resets: Resets,
+    references: References,
     line: usize,
 }
 
@@ -189,8 +266,10 @@ impl<'a, 'r> Ctx<'a, 'r> {
 
     fn in_scope<T>(&mut self, f: impl FnOnce(&mut Self) -> T) -> T {
         let old_venv = self.venv.clone();
+        let old_references = self.references.clone();
         let r = f(self);
         self.venv = old_venv;
+        self.references = old_references;
         r
     }
 
@@ -210,12 +289,27 @@ impl<'a, 'r> Ctx<'a, 'r> {
         if let Some((old_line, _)) = self.venv.insert(symbol, (self.line, layout)) {
             self.problem(ProblemKind::RedefinedSymbol { symbol, old_line })
         }
+        self.references.insert(
+            symbol,
+            Owned {
+                count: 1,
+                dec_line: 0,
+            },
+        );
+    }
+
+    /// A value must not be used after the decrement that may have freed it
+    fn check_not_freed(&mut self, symbol: Symbol) {
+        if let Some(&Owned { count: 0, dec_line }) = self.references.get(&symbol) {
+            self.problem(ProblemKind::UseAfterDecrement { symbol, dec_line })
+        }
     }
 
     fn check_sym_exists(&mut self, symbol: Symbol) {
         if !self.venv.contains_key(&symbol) {
             self.problem(ProblemKind::NoSymbolInScope { symbol })
         }
+        self.check_not_freed(symbol);
     }
 
     fn with_sym_layout<T>(
@@ -224,6 +318,7 @@ impl<'a, 'r> Ctx<'a, 'r> {
         f: impl FnOnce(&mut Self, usize, Layout<'a>) -> Option<T>,
     ) -> Option<T> {
         if let Some(&(def_line, layout)) = self.venv.get(&symbol) {
+            self.check_not_freed(symbol);
             f(self, def_line, layout)
         } else {
             self.problem(ProblemKind::NoSymbolInScope { symbol });
@@ -233,6 +328,7 @@ impl<'a, 'r> Ctx<'a, 'r> {
 
     fn check_sym_layout(&mut self, symbol: Symbol, expected_layout: Layout<'a>, use_kind: UseKind) {
         if let Some(&(def_line, layout)) = self.venv.get(&symbol) {
+            self.check_not_freed(symbol);
             if self.resolve(layout) != self.resolve(expected_layout) {
                 self.problem(ProblemKind::SymbolUseMismatch {
                     symbol,
@@ -269,6 +365,9 @@ impl<'a, 'r> Ctx<'a, 'r> {
                         })
                     }
                 }
+                if let Expr::Reset { update_mode, .. } = e {
+                    self.resets.insert(*x, *update_mode);
+                }
                 self.insert(*x, *x_layout);
                 self.check_stmt(rest);
             }
@@ -444,15 +543,27 @@ impl<'a, 'r> Ctx<'a, 'r> {
             &Expr::Reuse {
                 symbol,
                 update_tag_id: _,
-                update_mode: _,
+                update_mode,
                 tag_layout,
                 tag_id: _,
                 arguments: _,
             } => {
                 self.check_sym_layout(symbol, Layout::Union(tag_layout), UseKind::TagReuse);
+                self.check_reuse(symbol, update_mode);
                 // TODO also check update arguments
                 Some(Layout::Union(tag_layout))
             }
+            &Expr::ReuseBox {
+                symbol,
+                update_mode,
+                value,
+            } => {
+                self.check_sym_exists(symbol);
+                self.check_reuse(symbol, update_mode);
+                self.with_sym_layout(value, |ctx, _def_line, layout| {
+                    Some(Layout::Boxed(ctx.alloc(layout)))
+                })
+            }
             &Expr::Reset {
                 symbol,
                 update_mode: _,
@@ -547,36 +658,27 @@ impl<'a, 'r> Ctx<'a, 'r> {
                     result: **ret_layout,
                     captures_niche: name.captures_niche(),
                 };
-                if !self.procs.contains_key(&(name.name(), proc_layout)) {
-                    let similar = self
-                        .procs
-                        .keys()
-                        .filter(|(sym, _)| *sym == name.name())
-                        .map(|(_, lay)| *lay)
-                        .collect();
-                    self.problem(ProblemKind::CallingUndefinedProc {
-                        symbol: name.name(),
-                        proc_layout,
-                        similar,
-                    });
-                }
+                self.check_proc_exists(name.name(), proc_layout);
                 for (arg, wanted_layout) in arguments.iter().zip(arg_layouts.iter()) {
                     self.check_sym_layout(*arg, *wanted_layout, UseKind::CallArg);
                 }
-                if let Some(old_call_line) =
-                    self.call_spec_ids.insert(*specialization_id, self.line)
-                {
-                    self.problem(ProblemKind::DuplicateCallSpecId { old_call_line });
-                }
+                self.check_call_spec_id(*specialization_id);
                 Some(**ret_layout)
             }
             CallType::HigherOrder(HigherOrderLowLevel {
                 op: _,
                 closure_env_layout: _,
                 update_mode: _,
-                passed_function: _,
+                passed_function,
             }) => {
-                // TODO
+                let proc_layout = ProcLayout {
+                    arguments: passed_function.argument_layouts,
+                    result: passed_function.return_layout,
+                    captures_niche: passed_function.name.captures_niche(),
+                };
+                self.check_proc_exists(passed_function.name.name(), proc_layout);
+                self.check_call_spec_id(passed_function.specialization_id);
+                // TODO check the layouts of the arguments
                 None
             }
             CallType::Foreign {
@@ -590,6 +692,28 @@ impl<'a, 'r> Ctx<'a, 'r> {
         }
     }
 
+    fn check_proc_exists(&mut self, symbol: Symbol, proc_layout: ProcLayout<'a>) {
+        if !self.procs.contains_key(&(symbol, proc_layout)) {
+            let similar = self
+                .procs
+                .keys()
+                .filter(|(sym, _)| *sym == symbol)
+                .map(|(_, lay)| *lay)
+                .collect();
+            self.problem(ProblemKind::CallingUndefinedProc {
+                symbol,
+                proc_layout,
+                similar,
+            });
+        }
+    }
+
+    fn check_call_spec_id(&mut self, specialization_id: CallSpecId) {
+        if let Some(old_call_line) = self.call_spec_ids.insert(specialization_id, self.line) {
+            self.problem(ProblemKind::DuplicateCallSpecId { old_call_line });
+        }
+    }
+
     fn check_tag_expr(&mut self, union_layout: UnionLayout<'a>, tag_id: u16, arguments: &[Symbol]) {
         match get_tag_id_payloads(union_layout, tag_id) {
             TagPayloads::IdNotInUnion => {
@@ -614,10 +738,69 @@ impl<'a, 'r> Ctx<'a, 'r> {
     }
 
     fn check_modify_rc(&mut self, rc: ModifyRc) {
+        let symbol = match rc {
+            ModifyRc::Inc(symbol, _) | ModifyRc::Dec(symbol) | ModifyRc::DecRef(symbol) => symbol,
+        };
+
+        let def_line = match self.venv.get(&symbol) {
+            Some(&(def_line, layout)) => {
+                if !layout.contains_refcounted(self.interner) {
+                    self.problem(ProblemKind::RefcountingNonRefcounted {
+                        symbol,
+                        def_line,
+                        layout,
+                    });
+                }
+                def_line
+            }
+            None => {
+                self.problem(ProblemKind::NoSymbolInScope { symbol });
+                return;
+            }
+        };
+
+        // Each decrement must be paired with the definition or with an increment
+        let line = self.line;
+        let owned = match self.references.get_mut(&symbol) {
+            Some(owned) => owned,
+            None => return,
+        };
+
         match rc {
-            ModifyRc::Inc(sym, _) | ModifyRc::Dec(sym) | ModifyRc::DecRef(sym) => {
-                // TODO: also check that sym layout needs refcounting
-                self.check_sym_exists(sym);
+            ModifyRc::Inc(_, amount) => {
+                if owned.count == 0 {
+                    let dec_line = owned.dec_line;
+                    self.problem(ProblemKind::UseAfterDecrement { symbol, dec_line });
+                } else {
+                    owned.count += amount;
+                }
+            }
+            ModifyRc::Dec(_) | ModifyRc::DecRef(_) => {
+                if owned.count == 0 {
+                    let dec_line = owned.dec_line;
+                    self.problem(ProblemKind::DecrementedTooOften {
+                        symbol,
+                        def_line,
+                        dec_line,
+                    });
+                } else {
+                    owned.count -= 1;
+                    owned.dec_line = line;
+                }
+            }
+        }
+    }
+
+    /// A reuse must take the memory that a [Expr::Reset] made available, in the same update mode
+    fn check_reuse(&mut self, symbol: Symbol, update_mode: UpdateModeId) {
+        // a symbol that is not in scope is reported by the other checks of the reuse
+        if let Some(&(def_line, _)) = self.venv.get(&symbol) {
+            match self.resets.get(&symbol).copied() {
+                None => self.problem(ProblemKind::ReuseWithoutReset { symbol, def_line }),
+                Some(reset_update_mode) if reset_update_mode != update_mode => {
+                    self.problem(ProblemKind::ReuseUpdateModeMismatch { symbol, def_line })
+                }
+                Some(_) => {}
             }
         }
     }
@@ -686,5 +869,187 @@ fn get_tag_id_payloads(union_layout: UnionLayout, tag_id: TagIdIntType) -> TagPa
                 TagPayloads::Payloads(other_fields)
             }
         }
+        UnionLayout::NullablePointer {
+            nullable_id,
+            pointer,
+        } => {
+            if tag_id == nullable_id as _ {
+                TagPayloads::Payloads(&[])
+            } else {
+                check_tag_id_oob!(2);
+                TagPayloads::Payloads(std::slice::from_ref(pointer))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_closure_erasure, check_procs, Problem, ProblemKind};
+    use crate::ir::text::parse_procs;
+    use crate::ir::{HostExposedLayouts, OptLevel, Proc, ProcLayout, SelfRecursive, Stmt};
+    use crate::layout::{CapturesNiche, LambdaName, LambdaSet, Layout, STLayoutInterner};
+    use bumpalo::Bump;
+    use roc_collections::{MutMap, MutSet};
+    use roc_intern::{Interner, SingleThreadedInterner};
+    use roc_module::symbol::{Interns, ModuleId, Symbol};
+
+    /// Check the procedures of `text`, and call `f` with the problems found
+    fn check(text: &str, f: impl FnOnce(&[Problem])) {
+        let arena = Bump::new();
+        let mut interns = Interns::default();
+        let interner: STLayoutInterner = SingleThreadedInterner::with_capacity(4);
+
+        let procs: MutMap<_, _> = match parse_procs(&arena, &mut interns, text) {
+            Ok(procs) => procs
+                .into_iter()
+                .map(|proc| {
+                    let arguments =
+                        arena.alloc_slice_fill_iter(proc.args.iter().map(|(layout, _)| *layout));
+                    let proc_layout = ProcLayout::new(
+                        &arena,
+                        arguments,
+                        proc.name.captures_niche(),
+                        proc.ret_layout,
+                    );
+
+                    ((proc.name.name(), proc_layout), proc)
+                })
+                .collect(),
+            Err(error) => panic!("{}", error),
+        };
+
+        f(&check_procs(&arena, &interner, &procs).0);
+    }
+
+    #[test]
+    fn paired_increments_and_decrements() {
+        const TEXT: &str = r#"procedure Test.0 (Test.1 : Str, Test.2 : Int1) -> Str:
+    switch Test.2 : Int1 -> Str:
+        case 1:
+            inc Test.1;
+            dec Test.1;
+            ret Test.1;
+        default:
+            dec Test.1;
+            let Test.3 : Str = "a";
+            ret Test.3;
+"#;
+
+        check(TEXT, |problems| assert!(problems.is_empty()));
+    }
+
+    #[test]
+    fn use_after_decrement() {
+        const TEXT: &str = r#"procedure Test.0 (Test.1 : Str) -> Str:
+    dec Test.1;
+    ret Test.1;
+"#;
+
+        check(TEXT, |problems| {
+            assert!(matches!(
+                problems,
+                [Problem {
+                    kind: ProblemKind::UseAfterDecrement { dec_line: 1, .. },
+                    ..
+                }]
+            ))
+        });
+    }
+
+    #[test]
+    fn decrement_too_often() {
+        const TEXT: &str = r#"procedure Test.0 (Test.1 : Str) -> I64:
+    let Test.2 : I64 = 1i64;
+    inc Test.1;
+    dec Test.1;
+    dec Test.1;
+    dec Test.1;
+    ret Test.2;
+"#;
+
+        check(TEXT, |problems| {
+            assert!(matches!(
+                problems,
+                [Problem {
+                    line: 5,
+                    kind: ProblemKind::DecrementedTooOften { dec_line: 4, .. },
+                    ..
+                }]
+            ))
+        });
+    }
+
+    #[test]
+    fn refcount_non_refcounted() {
+        const TEXT: &str = r#"procedure Test.0 (Test.1 : I64) -> I64:
+    inc Test.1;
+    ret Test.1;
+"#;
+
+        check(TEXT, |problems| {
+            assert!(matches!(
+                problems,
+                [Problem {
+                    kind: ProblemKind::RefcountingNonRefcounted { .. },
+                    ..
+                }]
+            ))
+        });
+    }
+
+    /// `apply = \f, x -> x`, specialized to a closure of a single function that captures nothing
+    #[test]
+    fn unerased_closure_argument() {
+        let arena = &Bump::new();
+        let mut interner: STLayoutInterner = SingleThreadedInterner::with_capacity(4);
+
+        let sym = |index| Symbol::from_index(ModuleId::ATTR, index);
+        let (neg, apply, f, x) = (sym(1), sym(2), sym(3), sym(4));
+
+        let lambda_set = LambdaSet::from_parts(
+            arena.alloc([(neg, &[] as &[_])]),
+            interner.insert(&Layout::UNIT),
+        );
+        let closure = Layout::LambdaSet(lambda_set);
+        let int = Layout::i64();
+
+        let apply_proc = Proc {
+            name: LambdaName::no_niche(apply),
+            args: arena.alloc([(closure, f), (int, x)]),
+            body: Stmt::Ret(x),
+            closure_data_layout: None,
+            ret_layout: int,
+            is_self_recursive: SelfRecursive::NotSelfRecursive,
+            must_own_arguments: false,
+            host_exposed_layouts: HostExposedLayouts::NotHostExposed,
+        };
+        let apply_layout = ProcLayout::new(arena, &[closure, int], CapturesNiche::no_niche(), int);
+
+        let procs: MutMap<_, _> = [((apply, apply_layout), apply_proc)].into_iter().collect();
+
+        let problems = check_closure_erasure(arena, &interner, &MutSet::default(), &procs);
+        assert!(matches!(
+            problems.0.as_slice(),
+            [Problem {
+                line: 0,
+                kind: ProblemKind::UnerasedClosureArgument { symbol, .. },
+                ..
+            }] if *symbol == f
+        ));
+
+        // the procedure is exposed to the host, so it must keep its arguments
+        let exposed = [apply].into_iter().collect();
+        assert!(check_closure_erasure(arena, &interner, &exposed, &procs).is_empty());
+
+        let mut erased = procs.clone();
+        Proc::erase_closure_arguments(
+            arena,
+            &interner,
+            OptLevel::Normal,
+            &MutSet::default(),
+            &mut erased,
+        );
+        assert!(check_closure_erasure(arena, &interner, &MutSet::default(), &erased).is_empty());
     }
 }
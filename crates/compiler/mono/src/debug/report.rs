@@ -102,7 +102,7 @@ fn format_sourced_doc<'d>(f: &'d Arena<'d>, line: usize, source: &str, doc: Doc<
     stack(f, [pretty_lines, doc])
 }
 
-fn format_header<'d>(f: &'d Arena<'d>, title: &str) -> Doc<'d> {
+pub(super) fn format_header<'d>(f: &'d Arena<'d>, title: &str) -> Doc<'d> {
     let title_width = title.len() + 4;
     f.text(format!(
         "── {} {}",
@@ -411,11 +411,87 @@ where
                 f.as_string(num_given),
             ])
         }
+        ProblemKind::UnerasedClosureArgument { symbol, lambda_set } => {
+            title = "CLOSURE OF A SINGLE FUNCTION IS NOT ERASED";
+            docs_before = vec![];
+            f.concat([
+                format_symbol(f, interns, symbol),
+                f.reflow(" can only be one function, which captures nothing, so it should be called directly. But it is still an argument, of layout "),
+                Layout::LambdaSet(lambda_set).to_doc(f, interner, Parens::NotNeeded),
+            ])
+        }
+        ProblemKind::RefcountingNonRefcounted {
+            symbol,
+            def_line,
+            layout,
+        } => {
+            title = "REFCOUNTING A VALUE WITHOUT A REFCOUNT";
+            docs_before = vec![(
+                def_line,
+                f.concat([
+                    format_symbol(f, interns, symbol),
+                    f.reflow(" defined here with layout "),
+                    layout.to_doc(f, interner, Parens::NotNeeded),
+                ]),
+            )];
+            f.reflow("contains no refcounted values, but its refcount is changed here")
+        }
+        ProblemKind::ReuseWithoutReset { symbol, def_line } => {
+            title = "REUSE OF A VALUE THAT WAS NOT RESET";
+            docs_before = vec![(
+                def_line,
+                f.concat([
+                    format_symbol(f, interns, symbol),
+                    f.reflow(" is not defined by a reset here"),
+                ]),
+            )];
+            f.reflow("but its memory is reused here")
+        }
+        ProblemKind::ReuseUpdateModeMismatch { symbol, def_line } => {
+            title = "REUSE DOESN'T MATCH ITS RESET";
+            docs_before = vec![(
+                def_line,
+                f.concat([
+                    format_symbol(f, interns, symbol),
+                    f.reflow(" is reset here"),
+                ]),
+            )];
+            f.reflow("but reused here with a different update mode")
+        }
+        ProblemKind::UseAfterDecrement { symbol, dec_line } => {
+            title = "USE AFTER DECREMENT";
+            docs_before = vec![(
+                dec_line,
+                f.concat([
+                    format_symbol(f, interns, symbol),
+                    f.reflow(" may be freed by this decrement"),
+                ]),
+            )];
+            f.reflow("but it is used here")
+        }
+        ProblemKind::DecrementedTooOften {
+            symbol,
+            def_line,
+            dec_line,
+        } => {
+            title = "DECREMENTED TOO OFTEN";
+            docs_before = vec![
+                (
+                    def_line,
+                    f.concat([format_symbol(f, interns, symbol), f.reflow(" defined here")]),
+                ),
+                (
+                    dec_line,
+                    f.reflow("has had as many decrements as increments by here"),
+                ),
+            ];
+            f.reflow("but it is decremented again here")
+        }
     };
     (title, docs_before, doc)
 }
 
-fn format_symbol<'d>(f: &'d Arena<'d>, interns: &'d Interns, symbol: Symbol) -> Doc<'d> {
+pub(super) fn format_symbol<'d>(f: &'d Arena<'d>, interns: &'d Interns, symbol: Symbol) -> Doc<'d> {
     f.text(symbol.module_string(interns).to_string())
         .append(f.text("."))
         .append(f.text(symbol.as_str(interns)))
@@ -454,7 +530,7 @@ where
     ])
 }
 
-fn format_proc_layout<'a, 'd, I>(
+pub(super) fn format_proc_layout<'a, 'd, I>(
     f: &'d Arena<'d>,
     interner: &I,
     proc_layout: ProcLayout<'a>,
@@ -0,0 +1,278 @@
+use std::cmp::Reverse;
+use std::fmt::Write;
+
+use roc_collections::all::MutMap;
+use roc_intern::Interner;
+use roc_module::symbol::{Interns, Symbol};
+use ven_pretty::Arena;
+
+use crate::inline::{called_proc, for_each_call, size};
+use crate::ir::{Proc, ProcLayout};
+use crate::layout::Layout;
+
+use super::report::{format_header, format_proc_layout, format_symbol};
+
+type ProcKey<'a> = (Symbol, ProcLayout<'a>);
+
+struct Specialization {
+    layout: String,
+    /// The number of statements in the specialized body, as an estimate of its code size
+    size: usize,
+    /// The procedures that call this specialization, with the number of calls each makes
+    callers: Vec<(String, usize)>,
+}
+
+/// Lists every function that was specialized for more than one layout, with the layouts it was
+/// specialized for, the procedures that call each specialization, and an estimate of their size.
+/// The functions that take up the most code come first.
+///
+/// This runs on the procedures as they are after optimization, and says so in the report: calls
+/// that were inlined are gone, and the size of a specialization includes what was inlined into it.
+pub fn explain_specializations<'a, I>(
+    interns: &Interns,
+    interner: &I,
+    procs: &MutMap<ProcKey<'a>, Proc<'a>>,
+) -> String
+where
+    I: Interner<'a, Layout<'a>>,
+{
+    let f = Arena::new();
+    let render_layout = |proc_layout: ProcLayout<'a>| {
+        format_proc_layout(&f, interner, proc_layout)
+            .1
+            .pretty(80)
+            .to_string()
+    };
+    let render_proc = |(symbol, proc_layout): ProcKey<'a>| {
+        format!(
+            "{} : {}",
+            format_symbol(&f, interns, symbol).1.pretty(80),
+            render_layout(proc_layout)
+        )
+    };
+
+    let mut calls: MutMap<ProcKey<'a>, MutMap<ProcKey<'a>, usize>> = MutMap::default();
+    for (caller, proc) in procs.iter() {
+        for_each_call(&proc.body, &mut |call| {
+            if let Some(callee) = called_proc(call) {
+                *calls.entry(callee).or_default().entry(*caller).or_insert(0) += 1;
+            }
+        });
+    }
+
+    let mut by_function: MutMap<Symbol, Vec<Specialization>> = MutMap::default();
+    for (key, proc) in procs.iter() {
+        let mut callers: Vec<_> = calls
+            .remove(key)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|(caller, count)| (render_proc(caller), count))
+            .collect();
+        callers.sort();
+
+        by_function.entry(key.0).or_default().push(Specialization {
+            layout: render_layout(key.1),
+            size: size(&proc.body),
+            callers,
+        });
+    }
+
+    let mut functions: Vec<_> = by_function
+        .into_iter()
+        .filter(|(_, specializations)| specializations.len() > 1)
+        .map(|(symbol, mut specializations)| {
+            specializations
+                .sort_by(|a, b| (Reverse(a.size), &a.layout).cmp(&(Reverse(b.size), &b.layout)));
+            let total_size: usize = specializations.iter().map(|s| s.size).sum();
+            let name = format_symbol(&f, interns, symbol).1.pretty(80).to_string();
+
+            (name, total_size, specializations)
+        })
+        .collect();
+    functions.sort_by(|(name_a, size_a, _), (name_b, size_b, _)| {
+        (Reverse(size_a), name_a).cmp(&(Reverse(size_b), name_b))
+    });
+
+    let mut report = format_header(&f, "SPECIALIZATIONS")
+        .1
+        .pretty(80)
+        .to_string();
+    report.push_str("\n\n");
+    report.push_str(
+        "These are the procedures after optimization, so inlined calls are not counted.\n\n",
+    );
+
+    match functions.len() {
+        0 => report.push_str("No function was specialized for more than one layout.\n"),
+        1 => report.push_str("1 function was specialized for more than one layout.\n"),
+        n => writeln!(
+            report,
+            "{} functions were specialized for more than one layout.",
+            n
+        )
+        .unwrap(),
+    }
+
+    for (name, total_size, specializations) in functions {
+        writeln!(
+            report,
+            "\n{} has {} specializations, about {} in total",
+            name,
+            specializations.len(),
+            statements(total_size)
+        )
+        .unwrap();
+
+        for specialization in specializations {
+            writeln!(
+                report,
+                "    {}, about {}",
+                specialization.layout,
+                statements(specialization.size)
+            )
+            .unwrap();
+
+            if specialization.callers.is_empty() {
+                report.push_str("        not called by other procedures\n");
+            }
+
+            for (caller, count) in specialization.callers {
+                if count == 1 {
+                    writeln!(report, "        called once from {}", caller).unwrap();
+                } else {
+                    writeln!(report, "        called {} times from {}", count, caller).unwrap();
+                }
+            }
+        }
+    }
+
+    report
+}
+
+fn statements(count: usize) -> String {
+    if count == 1 {
+        "1 statement".to_string()
+    } else {
+        format!("{} statements", count)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::explain_specializations;
+    use crate::ir::text::parse_procs;
+    use crate::ir::ProcLayout;
+    use crate::layout::STLayoutInterner;
+    use bumpalo::Bump;
+    use roc_collections::all::MutMap;
+    use roc_intern::SingleThreadedInterner;
+    use roc_module::symbol::Interns;
+
+    const TEXT: &str = r#"procedure Test.1 (Test.2 : I64) -> I64:
+    ret Test.2;
+
+procedure Test.1 (Test.2 : U8) -> U8:
+    ret Test.2;
+
+procedure Test.0 (Test.3 : I64, Test.4 : U8) -> I64:
+    let Test.5 : I64 = CallByName Test.1 Test.3;
+    let Test.6 : U8 = CallByName Test.1 Test.4;
+    let Test.7 : I64 = CallByName Test.1 Test.5;
+    ret Test.7;
+"#;
+
+    const REPORT: &str = r#"── SPECIALIZATIONS ─────────────────────────────────────────────────────────────
+
+These are the procedures after optimization, so inlined calls are not counted.
+
+1 function was specialized for more than one layout.
+
+Test.1 has 2 specializations, about 2 statements in total
+    (I64) -> I64 (no niche), about 1 statement
+        called 2 times from Test.0 : (I64, U8) -> I64 (no niche)
+    (U8) -> U8 (no niche), about 1 statement
+        called once from Test.0 : (I64, U8) -> I64 (no niche)
+"#;
+
+    fn explain(text: &str) -> String {
+        let arena = Bump::new();
+        let mut interns = Interns::default();
+        let interner: STLayoutInterner = SingleThreadedInterner::with_capacity(4);
+
+        let procs = match parse_procs(&arena, &mut interns, text) {
+            Ok(procs) => procs,
+            Err(error) => panic!("{}", error),
+        };
+        let procs: MutMap<_, _> = procs
+            .into_iter()
+            .map(|proc| {
+                let arguments = arena.alloc_slice_fill_iter(proc.args.iter().map(|(l, _)| *l));
+                let proc_layout = ProcLayout::new(
+                    &arena,
+                    arguments,
+                    proc.name.captures_niche(),
+                    proc.ret_layout,
+                );
+
+                ((proc.name.name(), proc_layout), proc)
+            })
+            .collect();
+
+        explain_specializations(&interns, &interner, &procs)
+    }
+
+    #[test]
+    fn report_functions_with_several_specializations() {
+        assert_eq!(explain(TEXT), REPORT);
+    }
+
+    #[test]
+    fn report_no_functions() {
+        const TEXT: &str = r#"procedure Test.1 (Test.2 : I64) -> I64:
+    ret Test.2;
+
+procedure Test.0 (Test.3 : I64) -> I64:
+    let Test.4 : I64 = CallByName Test.1 Test.3;
+    ret Test.4;
+"#;
+
+        assert!(explain(TEXT).ends_with(
+            "after optimization, so inlined calls are not counted.\n\nNo function was specialized for more than one layout.\n"
+        ));
+    }
+
+    #[test]
+    fn report_largest_functions_first() {
+        const TEXT: &str = r#"procedure Test.1 (Test.2 : I64) -> I64:
+    ret Test.2;
+
+procedure Test.1 (Test.2 : U8) -> U8:
+    ret Test.2;
+
+procedure Test.5 (Test.6 : I64) -> I64:
+    let Test.7 : I64 = lowlevel NumAdd Test.6 Test.6;
+    ret Test.7;
+
+procedure Test.5 (Test.6 : U8) -> U8:
+    let Test.7 : U8 = lowlevel NumAdd Test.6 Test.6;
+    ret Test.7;
+"#;
+
+        const REPORT: &str = r#"2 functions were specialized for more than one layout.
+
+Test.5 has 2 specializations, about 4 statements in total
+    (I64) -> I64 (no niche), about 2 statements
+        not called by other procedures
+    (U8) -> U8 (no niche), about 2 statements
+        not called by other procedures
+
+Test.1 has 2 specializations, about 2 statements in total
+    (I64) -> I64 (no niche), about 1 statement
+        not called by other procedures
+    (U8) -> U8 (no niche), about 1 statement
+        not called by other procedures
+"#;
+
+        assert!(explain(TEXT).ends_with(REPORT));
+    }
+}
@@ -933,7 +933,8 @@ fn to_relevant_branch_help<'a>(
                         UnionLayout::NonRecursive(_)
                         | UnionLayout::Recursive(_)
                         | UnionLayout::NullableWrapped { .. }
-                        | UnionLayout::NullableUnwrapped { .. } => {
+                        | UnionLayout::NullableUnwrapped { .. }
+                        | UnionLayout::NullablePointer { .. } => {
                             let sub_positions =
                                 arguments
                                     .into_iter()
@@ -1,7 +1,7 @@
 use crate::borrow::{ParamMap, BORROWED, OWNED};
 use crate::ir::{
-    CallType, Expr, HigherOrderLowLevel, JoinPointId, ModifyRc, Param, Proc, ProcLayout, Stmt,
-    UpdateModeIds,
+    CallType, Expr, HigherOrderLowLevel, JoinPointId, ModifyRc, Param, PassedFunction, Proc,
+    ProcLayout, Stmt, UpdateModeIds,
 };
 use crate::layout::{Layout, STLayoutInterner};
 use bumpalo::collections::Vec;
@@ -198,6 +198,10 @@ pub fn occurring_variables_expr(expr: &Expr<'_>, result: &mut MutSet<Symbol>) {
             result.extend(arguments.iter().copied());
             result.insert(*symbol);
         }
+        ReuseBox { symbol, value, .. } => {
+            result.insert(*symbol);
+            result.insert(*value);
+        }
         Reset { symbol: x, .. } => {
             result.insert(*x);
         }
@@ -636,8 +640,8 @@ impl<'a, 'i> Context<'a, 'i> {
         } = lowlevel;
 
         macro_rules! create_call {
-            ($borrows:expr) => {
-                create_holl_call(self.arena, lowlevel, $borrows, arguments)
+            () => {
+                create_holl_call(self.arena, lowlevel, function_ps, arguments)
             };
         }
 
@@ -731,7 +735,7 @@ impl<'a, 'i> Context<'a, 'i> {
 
                 let b = handle_ownerships_post!(b, ownerships);
 
-                let v = create_call!(function_ps.get(1));
+                let v = create_call!();
 
                 handle_ownerships_pre!(Stmt::Let(z, v, l, b), ownerships)
             }
@@ -742,7 +746,7 @@ impl<'a, 'i> Context<'a, 'i> {
 
                 let b = handle_ownerships_post!(b, ownerships);
 
-                let v = create_call!(function_ps.get(2));
+                let v = create_call!();
 
                 handle_ownerships_pre!(Stmt::Let(z, v, l, b), ownerships)
             }
@@ -757,7 +761,7 @@ impl<'a, 'i> Context<'a, 'i> {
 
                 let b = handle_ownerships_post!(b, ownerships);
 
-                let v = create_call!(function_ps.get(3));
+                let v = create_call!();
 
                 handle_ownerships_pre!(Stmt::Let(z, v, l, b), ownerships)
             }
@@ -773,41 +777,28 @@ impl<'a, 'i> Context<'a, 'i> {
 
                 let b = handle_ownerships_post!(b, ownerships);
 
-                let v = create_call!(function_ps.get(3));
+                let v = create_call!();
 
                 handle_ownerships_pre!(Stmt::Let(z, v, l, b), ownerships)
             }
             ListSortWith { xs } => {
-                // NOTE: we may apply the function to the same argument multiple times. for that to
-                // be valid, the function must borrow its argument. This is not enforced at the
-                // moment
-                //
-                // we also don't check that both arguments have the same ownership characteristics
-                let ownerships = [(xs, function_ps[0])];
+                // NOTE: we apply the function to the same element multiple times. When the
+                // function owns its arguments, the backends increment both elements before each
+                // call (see `PassedFunction::borrows_arguments`), so the elements stay in the
+                // list either way, and it is as if the function borrows them.
+                let element_param = Param {
+                    borrow: BORROWED,
+                    ..function_ps[0]
+                };
+                let ownerships = [(xs, element_param)];
 
                 let b = self.add_dec_after_lowlevel(after_arguments, &borrows, b, b_live_vars);
 
                 // list-sort will sort in-place; that really changes how RC should work
                 let b = {
-                    let ownership = DataFunction::new(&self.vars, xs, function_ps[0]);
+                    let ownership = DataFunction::new(&self.vars, xs, element_param);
 
                     match ownership {
-                        DataOwnedFunctionOwns => {
-                            // if non-unique, elements have been consumed, must still consume the
-                            // list itself
-                            let rc = Stmt::Refcounting(ModifyRc::DecRef(xs), b);
-
-                            let condition_stmt = branch_on_list_uniqueness(
-                                self.arena,
-                                codegen,
-                                xs,
-                                l,
-                                b.clone(),
-                                self.arena.alloc(rc),
-                            );
-
-                            &*self.arena.alloc(condition_stmt)
-                        }
                         DataOwnedFunctionBorrows => {
                             // must consume list and elements
                             let rc = Stmt::Refcounting(ModifyRc::Dec(xs), b);
@@ -823,21 +814,17 @@ impl<'a, 'i> Context<'a, 'i> {
 
                             &*self.arena.alloc(condition_stmt)
                         }
-                        DataBorrowedFunctionOwns => {
-                            // elements have been consumed, must still consume the list itself
-                            let rest = self.arena.alloc(b);
-                            let rc = Stmt::Refcounting(ModifyRc::DecRef(xs), rest);
-
-                            &*self.arena.alloc(rc)
-                        }
                         DataBorrowedFunctionBorrows => {
                             // list borrows, function borrows, so there is nothing to do
                             b
                         }
+                        DataOwnedFunctionOwns | DataBorrowedFunctionOwns => {
+                            unreachable!("the elements are always borrowed by a sort")
+                        }
                     }
                 };
 
-                let v = create_call!(function_ps.get(2));
+                let v = create_call!();
 
                 handle_ownerships_pre!(Stmt::Let(z, v, l, b), ownerships)
             }
@@ -859,6 +846,11 @@ impl<'a, 'i> Context<'a, 'i> {
         let mut live_vars = update_live_vars(&v, b_live_vars);
         live_vars.remove(&z);
 
+        // A value that is never used must still be released. Dead code elimination only removes
+        // bindings whose value has no effect, so e.g. the result of a call that was only used in a
+        // branch that constant folding removed is still bound here, and would otherwise leak.
+        let b = self.add_dec_if_needed(z, b, b_live_vars);
+
         let new_b = match v {
             Reuse { arguments: ys, .. } | Tag { arguments: ys, .. } | Struct(ys) => self
                 .add_inc_before_consume_all(
@@ -917,7 +909,7 @@ impl<'a, 'i> Context<'a, 'i> {
                 self.arena.alloc(Stmt::Let(z, v, l, b))
             }
 
-            ExprBox { symbol: x } => {
+            ExprBox { symbol: x } | ReuseBox { value: x, .. } => {
                 // mimics Tag
                 self.add_inc_before_consume_all(
                     &[x],
@@ -1309,25 +1301,35 @@ fn branch_on_list_uniqueness<'a, 'i>(
 fn create_holl_call<'a>(
     arena: &'a Bump,
     holl: &'a crate::ir::HigherOrderLowLevel,
-    param: Option<&Param>,
+    function_ps: &[Param],
     arguments: &'a [Symbol],
 ) -> Expr<'a> {
-    let call = crate::ir::Call {
-        call_type: if let Some(OWNED) = param.map(|p| p.borrow) {
-            let mut passed_function = holl.passed_function;
-            passed_function.owns_captured_environment = true;
+    let arity = holl.op.function_arity();
+
+    // the captured environment (if any) is the parameter after the arguments
+    let owns_captured_environment = matches!(function_ps.get(arity), Some(p) if p.borrow == OWNED);
+    let borrows_arguments = function_ps[..arity].iter().all(|p| p.borrow == BORROWED);
 
+    let passed_function = holl.passed_function;
+
+    let call = crate::ir::Call {
+        call_type: if passed_function.owns_captured_environment == owns_captured_environment
+            && passed_function.borrows_arguments == borrows_arguments
+        {
+            CallType::HigherOrder(holl)
+        } else {
             let higher_order = HigherOrderLowLevel {
                 op: holl.op,
                 closure_env_layout: holl.closure_env_layout,
                 update_mode: holl.update_mode,
-                passed_function,
+                passed_function: PassedFunction {
+                    owns_captured_environment,
+                    borrows_arguments,
+                    ..passed_function
+                },
             };
 
             CallType::HigherOrder(arena.alloc(higher_order))
-        } else {
-            debug_assert!(!holl.passed_function.owns_captured_environment);
-            CallType::HigherOrder(holl)
         },
         arguments,
     };
@@ -0,0 +1,1002 @@
+//! Inline calls to small procedures, and to closures that are called from only one place.
+//!
+//! A call
+//!
+//! ```text
+//! let x = CallByName f a b;
+//! <rest>
+//! ```
+//!
+//! is replaced by the body of `f`, with `a` and `b` for its arguments and fresh symbols for
+//! everything it binds. When `f` just computes its result and returns it, the result is bound to
+//! `x` directly, followed by `<rest>`. Otherwise every `ret` of `f` becomes a jump to a new join
+//! point that has `x` for its parameter and `<rest>` for its body.
+//!
+//! Inlining runs before constant folding and dead code elimination, which can then simplify the
+//! inlined body for the arguments it is given. Only the bodies that procedures had before inlining
+//! are inlined, so calls are inlined one level deep at most, and recursive procedures are never
+//! inlined. How large a procedure may be to be inlined depends on the [OptLevel]; in development
+//! builds nothing is inlined.
+//!
+//! The fresh symbols are made in the caller's module, except for the conditions of expects. A
+//! failed expect is reported with the source and types of the module its condition symbol is in,
+//! so the conditions of expects inlined from another module stay in that module.
+
+use crate::ir::{
+    BranchInfo, Call, CallSpecId, CallType, Expr, HigherOrderLowLevel, JoinPointId,
+    ListLiteralElement, ModifyRc, OptLevel, Param, PassedFunction, Proc, ProcLayout, SelfRecursive,
+    Stmt, UpdateModeIds,
+};
+use crate::layout::Layout;
+use crate::low_level::HigherOrder;
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+use roc_collections::all::{MutMap, MutSet};
+use roc_debug_flags::dbg_do;
+#[cfg(debug_assertions)]
+use roc_debug_flags::ROC_PRINT_INLINING;
+use roc_module::symbol::{IdentIdsByModule, ModuleId, Symbol};
+
+type ProcKey<'a> = (Symbol, ProcLayout<'a>);
+
+pub fn inline_procedures<'a>(
+    arena: &'a Bump,
+    home: ModuleId,
+    all_ident_ids: &mut IdentIdsByModule,
+    update_mode_ids: &mut UpdateModeIds,
+    opt_level: OptLevel,
+    procs: &mut MutMap<ProcKey<'a>, Proc<'a>>,
+) {
+    let budget = Budget::for_opt_level(opt_level);
+    let largest = budget.small_proc.max(budget.single_use_closure);
+
+    if largest == 0 {
+        return;
+    }
+
+    let mut uses = MutMap::default();
+    for proc in procs.values() {
+        for_each_call(&proc.body, &mut |call| {
+            if let Some(key) = called_proc(call) {
+                *uses.entry(key).or_insert(0) += 1;
+            }
+        });
+    }
+
+    let callees: MutMap<_, _> = procs
+        .iter()
+        .map(|(key, proc)| {
+            let mut is_recursive =
+                !matches!(proc.is_self_recursive, SelfRecursive::NotSelfRecursive);
+            for_each_call(&proc.body, &mut |call| {
+                is_recursive |= called_proc(call) == Some(*key);
+            });
+
+            let size = size(&proc.body);
+            let code = if is_recursive || size > largest {
+                None
+            } else {
+                Some((proc.args, proc.body.clone()))
+            };
+
+            let callee = Callee {
+                size,
+                is_recursive,
+                is_single_use_closure: proc.closure_data_layout.is_some()
+                    && uses.get(key) == Some(&1),
+                code,
+            };
+
+            (*key, callee)
+        })
+        .collect();
+
+    for (key, proc) in procs.iter_mut() {
+        let mut env = Env {
+            arena,
+            home,
+            all_ident_ids: &mut *all_ident_ids,
+            update_mode_ids: &mut *update_mode_ids,
+            budget: &budget,
+            callees: &callees,
+            caller: key.0,
+            next_call_spec_id: max_call_spec_id(&proc.body).next(),
+            expect_conditions: MutSet::default(),
+        };
+
+        proc.body = env.inline_stmt(&proc.body).clone();
+    }
+}
+
+/// The size of the largest procedures that are inlined, in statements
+struct Budget {
+    /// For calls to any procedure
+    small_proc: usize,
+    /// For calls to a closure that is not called or passed to a higher-order function anywhere
+    /// else, so that inlining it does not duplicate any code
+    single_use_closure: usize,
+}
+
+impl Budget {
+    fn for_opt_level(opt_level: OptLevel) -> Self {
+        let (small_proc, single_use_closure) = match opt_level {
+            OptLevel::Development => (0, 0),
+            OptLevel::Size => (4, 16),
+            OptLevel::Normal => (12, 48),
+            OptLevel::Optimize => (32, 128),
+        };
+
+        Budget {
+            small_proc,
+            single_use_closure,
+        }
+    }
+
+    fn for_callee(&self, callee: &Callee) -> usize {
+        if callee.is_single_use_closure {
+            self.single_use_closure
+        } else {
+            self.small_proc
+        }
+    }
+}
+
+struct Callee<'a> {
+    size: usize,
+    is_recursive: bool,
+    is_single_use_closure: bool,
+    /// The arguments and body of the procedure, if it can be inlined with the largest budget
+    code: Option<(&'a [(Layout<'a>, Symbol)], Stmt<'a>)>,
+}
+
+/// What the `ret`s of an inlined body become
+enum Return<'a> {
+    /// The body has no branches; bind the symbol it returns to the result of the call, and
+    /// continue with the rest of the caller
+    Bind {
+        returned: Symbol,
+        layout: Layout<'a>,
+        cont: &'a Stmt<'a>,
+    },
+    /// Jump to the join point whose body is the rest of the caller
+    Jump(JoinPointId),
+}
+
+struct Env<'a, 'r> {
+    arena: &'a Bump,
+    home: ModuleId,
+    all_ident_ids: &'r mut IdentIdsByModule,
+    update_mode_ids: &'r mut UpdateModeIds,
+    budget: &'r Budget,
+    callees: &'r MutMap<ProcKey<'a>, Callee<'a>>,
+    caller: Symbol,
+    /// Calls in inlined bodies need specialization ids that are not used in the caller yet
+    next_call_spec_id: CallSpecId,
+    /// The conditions of the expects in the body that is being inlined
+    expect_conditions: MutSet<Symbol>,
+}
+
+impl<'a, 'r> Env<'a, 'r> {
+    fn inline_stmt(&mut self, stmt: &Stmt<'a>) -> &'a Stmt<'a> {
+        use Stmt::*;
+
+        let arena = self.arena;
+
+        match stmt {
+            Let(symbol, expr, layout, cont) => {
+                let cont = self.inline_stmt(cont);
+
+                if let Expr::Call(
+                    call @ Call {
+                        call_type: CallType::ByName { .. },
+                        arguments,
+                    },
+                ) = expr
+                {
+                    let callees = self.callees;
+
+                    let key = called_proc(call);
+
+                    if let Some((key, callee)) = key.and_then(|key| Some((key, callees.get(&key)?)))
+                    {
+                        let budget = self.budget.for_callee(callee);
+                        let inline = !callee.is_recursive && callee.size <= budget;
+
+                        dbg_do!(ROC_PRINT_INLINING, {
+                            if inline {
+                                eprintln!(
+                                    "in {:?}: inlined the call to {:?} of size {}",
+                                    self.caller, key.0, callee.size
+                                );
+                            } else if callee.is_recursive {
+                                eprintln!(
+                                    "in {:?}: did not inline the call to {:?} because it is recursive",
+                                    self.caller, key.0
+                                );
+                            } else {
+                                eprintln!(
+                                    "in {:?}: did not inline the call to {:?} because its size {} is over the budget of {}",
+                                    self.caller, key.0, callee.size, budget
+                                );
+                            }
+                        });
+
+                        if let (true, Some((args, body))) = (inline, &callee.code) {
+                            return self.inline_call(args, body, arguments, *symbol, *layout, cont);
+                        }
+                    }
+                }
+
+                arena.alloc(Let(*symbol, expr.clone(), *layout, cont))
+            }
+            Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout,
+            } => {
+                let branches = Vec::from_iter_in(
+                    branches.iter().map(|(label, info, branch)| {
+                        (*label, info.clone(), self.inline_stmt(branch).clone())
+                    }),
+                    arena,
+                );
+
+                arena.alloc(Switch {
+                    cond_symbol: *cond_symbol,
+                    cond_layout: *cond_layout,
+                    branches: branches.into_bump_slice(),
+                    default_branch: (default_branch.0.clone(), self.inline_stmt(default_branch.1)),
+                    ret_layout: *ret_layout,
+                })
+            }
+            Join {
+                id,
+                parameters,
+                body,
+                remainder,
+            } => arena.alloc(Join {
+                id: *id,
+                parameters: *parameters,
+                body: self.inline_stmt(body),
+                remainder: self.inline_stmt(remainder),
+            }),
+            Refcounting(modify, cont) => arena.alloc(Refcounting(*modify, self.inline_stmt(cont))),
+            Expect {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => arena.alloc(Expect {
+                condition: *condition,
+                region: *region,
+                lookups: *lookups,
+                variables: *variables,
+                remainder: self.inline_stmt(remainder),
+            }),
+            ExpectFx {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => arena.alloc(ExpectFx {
+                condition: *condition,
+                region: *region,
+                lookups: *lookups,
+                variables: *variables,
+                remainder: self.inline_stmt(remainder),
+            }),
+            Ret(_) | Jump(..) | Crash(..) => arena.alloc(stmt.clone()),
+        }
+    }
+
+    fn inline_call(
+        &mut self,
+        args: &[(Layout<'a>, Symbol)],
+        body: &Stmt<'a>,
+        arguments: &[Symbol],
+        symbol: Symbol,
+        layout: Layout<'a>,
+        cont: &'a Stmt<'a>,
+    ) -> &'a Stmt<'a> {
+        let mut renaming: MutMap<Symbol, Symbol> = args
+            .iter()
+            .zip(arguments)
+            .map(|((_, param), argument)| (*param, *argument))
+            .collect();
+
+        self.expect_conditions.clear();
+        for_each_expect_condition(body, &mut |condition| {
+            self.expect_conditions.insert(condition);
+        });
+
+        match returned_symbol(body) {
+            Some(returned) if !renaming.contains_key(&returned) => {
+                renaming.insert(returned, symbol);
+
+                let ret = Return::Bind {
+                    returned,
+                    layout,
+                    cont,
+                };
+
+                self.copy_stmt(body, &mut renaming, &ret)
+            }
+            _ => {
+                let id = JoinPointId(self.fresh_symbol());
+                let remainder = self.copy_stmt(body, &mut renaming, &Return::Jump(id));
+
+                self.arena.alloc(Stmt::Join {
+                    id,
+                    parameters: self.arena.alloc([Param {
+                        symbol,
+                        borrow: false,
+                        layout,
+                    }]),
+                    body: cont,
+                    remainder,
+                })
+            }
+        }
+    }
+
+    /// Copy an inlined body, giving every symbol and join point it binds a fresh name
+    fn copy_stmt(
+        &mut self,
+        stmt: &Stmt<'a>,
+        renaming: &mut MutMap<Symbol, Symbol>,
+        ret: &Return<'a>,
+    ) -> &'a Stmt<'a> {
+        use Stmt::*;
+
+        let arena = self.arena;
+
+        match stmt {
+            Let(symbol, expr, layout, cont) => {
+                let expr = self.copy_expr(expr, renaming);
+                let layout = match ret {
+                    Return::Bind {
+                        returned,
+                        layout: call_layout,
+                        ..
+                    } if returned == symbol => *call_layout,
+                    _ => *layout,
+                };
+                let symbol = self.bind(*symbol, renaming);
+                let cont = self.copy_stmt(cont, renaming, ret);
+
+                arena.alloc(Let(symbol, expr, layout, cont))
+            }
+            Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout,
+            } => {
+                let branches = Vec::from_iter_in(
+                    branches.iter().map(|(label, info, branch)| {
+                        (
+                            *label,
+                            rename_branch_info(info, renaming),
+                            self.copy_stmt(branch, renaming, ret).clone(),
+                        )
+                    }),
+                    arena,
+                );
+                let default_branch = (
+                    rename_branch_info(&default_branch.0, renaming),
+                    self.copy_stmt(default_branch.1, renaming, ret),
+                );
+
+                arena.alloc(Switch {
+                    cond_symbol: rename(renaming, *cond_symbol),
+                    cond_layout: *cond_layout,
+                    branches: branches.into_bump_slice(),
+                    default_branch,
+                    ret_layout: *ret_layout,
+                })
+            }
+            Ret(symbol) => match ret {
+                Return::Bind { cont, .. } => *cont,
+                Return::Jump(id) => {
+                    arena.alloc(Jump(*id, arena.alloc([rename(renaming, *symbol)])))
+                }
+            },
+            Refcounting(modify, cont) => {
+                let modify = match *modify {
+                    ModifyRc::Inc(symbol, n) => ModifyRc::Inc(rename(renaming, symbol), n),
+                    ModifyRc::Dec(symbol) => ModifyRc::Dec(rename(renaming, symbol)),
+                    ModifyRc::DecRef(symbol) => ModifyRc::DecRef(rename(renaming, symbol)),
+                };
+
+                arena.alloc(Refcounting(modify, self.copy_stmt(cont, renaming, ret)))
+            }
+            Expect {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => arena.alloc(Expect {
+                condition: rename(renaming, *condition),
+                region: *region,
+                lookups: rename_all(arena, renaming, lookups),
+                variables: *variables,
+                remainder: self.copy_stmt(remainder, renaming, ret),
+            }),
+            ExpectFx {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => arena.alloc(ExpectFx {
+                condition: rename(renaming, *condition),
+                region: *region,
+                lookups: rename_all(arena, renaming, lookups),
+                variables: *variables,
+                remainder: self.copy_stmt(remainder, renaming, ret),
+            }),
+            Join {
+                id,
+                parameters,
+                body,
+                remainder,
+            } => {
+                let id = JoinPointId(self.bind(id.0, renaming));
+                let parameters = Vec::from_iter_in(
+                    parameters.iter().map(|param| Param {
+                        symbol: self.bind(param.symbol, renaming),
+                        ..*param
+                    }),
+                    arena,
+                );
+                let body = self.copy_stmt(body, renaming, ret);
+                let remainder = self.copy_stmt(remainder, renaming, ret);
+
+                arena.alloc(Join {
+                    id,
+                    parameters: parameters.into_bump_slice(),
+                    body,
+                    remainder,
+                })
+            }
+            Jump(id, arguments) => arena.alloc(Jump(
+                JoinPointId(rename(renaming, id.0)),
+                rename_all(arena, renaming, arguments),
+            )),
+            Crash(symbol, tag) => arena.alloc(Crash(rename(renaming, *symbol), *tag)),
+        }
+    }
+
+    fn copy_expr(&mut self, expr: &Expr<'a>, renaming: &MutMap<Symbol, Symbol>) -> Expr<'a> {
+        use Expr::*;
+
+        let arena = self.arena;
+
+        match expr {
+            Literal(_) | EmptyArray | RuntimeErrorFunction(_) => expr.clone(),
+            Call(call) => Call(self.copy_call(call, renaming)),
+            Tag {
+                tag_layout,
+                tag_id,
+                arguments,
+            } => Tag {
+                tag_layout: *tag_layout,
+                tag_id: *tag_id,
+                arguments: rename_all(arena, renaming, arguments),
+            },
+            Struct(fields) => Struct(rename_all(arena, renaming, fields)),
+            StructAtIndex {
+                index,
+                field_layouts,
+                structure,
+            } => StructAtIndex {
+                index: *index,
+                field_layouts: *field_layouts,
+                structure: rename(renaming, *structure),
+            },
+            GetTagId {
+                structure,
+                union_layout,
+            } => GetTagId {
+                structure: rename(renaming, *structure),
+                union_layout: *union_layout,
+            },
+            UnionAtIndex {
+                structure,
+                tag_id,
+                union_layout,
+                index,
+            } => UnionAtIndex {
+                structure: rename(renaming, *structure),
+                tag_id: *tag_id,
+                union_layout: *union_layout,
+                index: *index,
+            },
+            Array { elem_layout, elems } => {
+                let elems = Vec::from_iter_in(
+                    elems.iter().map(|elem| match elem {
+                        ListLiteralElement::Symbol(symbol) => {
+                            ListLiteralElement::Symbol(rename(renaming, *symbol))
+                        }
+                        ListLiteralElement::Literal(_) => *elem,
+                    }),
+                    arena,
+                );
+
+                Array {
+                    elem_layout: *elem_layout,
+                    elems: elems.into_bump_slice(),
+                }
+            }
+            ExprBox { symbol } => ExprBox {
+                symbol: rename(renaming, *symbol),
+            },
+            ExprUnbox { symbol } => ExprUnbox {
+                symbol: rename(renaming, *symbol),
+            },
+            // Inlining runs before reset/reuse is inserted, but if a reset and its reuse are
+            // copied they keep their update mode, so that the reuse still belongs to the reset
+            Reuse {
+                symbol,
+                update_tag_id,
+                update_mode,
+                tag_layout,
+                tag_id,
+                arguments,
+            } => Reuse {
+                symbol: rename(renaming, *symbol),
+                update_tag_id: *update_tag_id,
+                update_mode: *update_mode,
+                tag_layout: *tag_layout,
+                tag_id: *tag_id,
+                arguments: rename_all(arena, renaming, arguments),
+            },
+            ReuseBox {
+                symbol,
+                update_mode,
+                value,
+            } => ReuseBox {
+                symbol: rename(renaming, *symbol),
+                update_mode: *update_mode,
+                value: rename(renaming, *value),
+            },
+            Reset {
+                symbol,
+                update_mode,
+            } => Reset {
+                symbol: rename(renaming, *symbol),
+                update_mode: *update_mode,
+            },
+        }
+    }
+
+    fn copy_call(&mut self, call: &Call<'a>, renaming: &MutMap<Symbol, Symbol>) -> Call<'a> {
+        let call_type = match &call.call_type {
+            CallType::ByName {
+                name,
+                ret_layout,
+                arg_layouts,
+                specialization_id: _,
+            } => CallType::ByName {
+                name: *name,
+                ret_layout: *ret_layout,
+                arg_layouts: *arg_layouts,
+                specialization_id: self.fresh_call_spec_id(),
+            },
+            CallType::Foreign { .. } => call.call_type.clone(),
+            CallType::LowLevel { op, update_mode: _ } => CallType::LowLevel {
+                op: *op,
+                update_mode: self.update_mode_ids.next_id(),
+            },
+            CallType::HigherOrder(higher_order) => {
+                use HigherOrder::*;
+
+                let op = match higher_order.op {
+                    ListMap { xs } => ListMap {
+                        xs: rename(renaming, xs),
+                    },
+                    ListMap2 { xs, ys } => ListMap2 {
+                        xs: rename(renaming, xs),
+                        ys: rename(renaming, ys),
+                    },
+                    ListMap3 { xs, ys, zs } => ListMap3 {
+                        xs: rename(renaming, xs),
+                        ys: rename(renaming, ys),
+                        zs: rename(renaming, zs),
+                    },
+                    ListMap4 { xs, ys, zs, ws } => ListMap4 {
+                        xs: rename(renaming, xs),
+                        ys: rename(renaming, ys),
+                        zs: rename(renaming, zs),
+                        ws: rename(renaming, ws),
+                    },
+                    ListSortWith { xs } => ListSortWith {
+                        xs: rename(renaming, xs),
+                    },
+                };
+
+                let passed_function = PassedFunction {
+                    specialization_id: self.fresh_call_spec_id(),
+                    captured_environment: rename(
+                        renaming,
+                        higher_order.passed_function.captured_environment,
+                    ),
+                    ..higher_order.passed_function
+                };
+
+                CallType::HigherOrder(self.arena.alloc(HigherOrderLowLevel {
+                    op,
+                    closure_env_layout: higher_order.closure_env_layout,
+                    update_mode: self.update_mode_ids.next_id(),
+                    passed_function,
+                }))
+            }
+        };
+
+        Call {
+            call_type,
+            arguments: rename_all(self.arena, renaming, call.arguments),
+        }
+    }
+
+    /// The name that a symbol bound in an inlined body gets in the caller
+    fn bind(&mut self, symbol: Symbol, renaming: &mut MutMap<Symbol, Symbol>) -> Symbol {
+        let module_id = if self.expect_conditions.contains(&symbol) {
+            symbol.module_id()
+        } else {
+            self.home
+        };
+
+        *renaming
+            .entry(symbol)
+            .or_insert_with(|| fresh_symbol_in(self.all_ident_ids, module_id))
+    }
+
+    fn fresh_symbol(&mut self) -> Symbol {
+        fresh_symbol_in(self.all_ident_ids, self.home)
+    }
+
+    fn fresh_call_spec_id(&mut self) -> CallSpecId {
+        let id = self.next_call_spec_id;
+        self.next_call_spec_id = id.next();
+
+        id
+    }
+}
+
+fn fresh_symbol_in(all_ident_ids: &mut IdentIdsByModule, module_id: ModuleId) -> Symbol {
+    Symbol::new(
+        module_id,
+        all_ident_ids.get_or_insert(module_id).gen_unique(),
+    )
+}
+
+fn rename(renaming: &MutMap<Symbol, Symbol>, symbol: Symbol) -> Symbol {
+    renaming.get(&symbol).copied().unwrap_or(symbol)
+}
+
+fn rename_all<'a>(
+    arena: &'a Bump,
+    renaming: &MutMap<Symbol, Symbol>,
+    symbols: &[Symbol],
+) -> &'a [Symbol] {
+    Vec::from_iter_in(
+        symbols.iter().map(|symbol| rename(renaming, *symbol)),
+        arena,
+    )
+    .into_bump_slice()
+}
+
+fn rename_branch_info<'a>(
+    info: &BranchInfo<'a>,
+    renaming: &MutMap<Symbol, Symbol>,
+) -> BranchInfo<'a> {
+    match info {
+        BranchInfo::None => BranchInfo::None,
+        BranchInfo::Constructor {
+            scrutinee,
+            layout,
+            tag_id,
+        } => BranchInfo::Constructor {
+            scrutinee: rename(renaming, *scrutinee),
+            layout: *layout,
+            tag_id: *tag_id,
+        },
+    }
+}
+
+/// The procedure that a call calls, or passes to a higher-order function
+pub(crate) fn called_proc<'a>(call: &Call<'a>) -> Option<ProcKey<'a>> {
+    match &call.call_type {
+        CallType::ByName {
+            name,
+            ret_layout,
+            arg_layouts,
+            ..
+        } => Some((
+            name.name(),
+            ProcLayout {
+                arguments: arg_layouts,
+                result: **ret_layout,
+                captures_niche: name.captures_niche(),
+            },
+        )),
+        CallType::HigherOrder(higher_order) => {
+            let passed_function = &higher_order.passed_function;
+
+            Some((
+                passed_function.name.name(),
+                ProcLayout {
+                    arguments: passed_function.argument_layouts,
+                    result: passed_function.return_layout,
+                    captures_niche: passed_function.name.captures_niche(),
+                },
+            ))
+        }
+        CallType::Foreign { .. } | CallType::LowLevel { .. } => None,
+    }
+}
+
+pub(crate) fn for_each_call<'a>(stmt: &Stmt<'a>, f: &mut impl FnMut(&Call<'a>)) {
+    use Stmt::*;
+
+    let mut stmt = stmt;
+    loop {
+        match stmt {
+            Let(_, expr, _, cont) => {
+                if let Expr::Call(call) = expr {
+                    f(call);
+                }
+                stmt = cont;
+            }
+            Switch {
+                branches,
+                default_branch,
+                ..
+            } => {
+                for (_, _, branch) in branches.iter() {
+                    for_each_call(branch, f);
+                }
+                stmt = default_branch.1;
+            }
+            Join {
+                body, remainder, ..
+            } => {
+                for_each_call(body, f);
+                stmt = remainder;
+            }
+            Refcounting(_, cont) => stmt = cont,
+            Expect { remainder, .. } | ExpectFx { remainder, .. } => stmt = remainder,
+            Ret(_) | Jump(..) | Crash(..) => return,
+        }
+    }
+}
+
+fn for_each_expect_condition(stmt: &Stmt, f: &mut impl FnMut(Symbol)) {
+    use Stmt::*;
+
+    let mut stmt = stmt;
+    loop {
+        match stmt {
+            Let(_, _, _, cont) | Refcounting(_, cont) => stmt = cont,
+            Switch {
+                branches,
+                default_branch,
+                ..
+            } => {
+                for (_, _, branch) in branches.iter() {
+                    for_each_expect_condition(branch, f);
+                }
+                stmt = default_branch.1;
+            }
+            Join {
+                body, remainder, ..
+            } => {
+                for_each_expect_condition(body, f);
+                stmt = remainder;
+            }
+            Expect {
+                condition,
+                remainder,
+                ..
+            }
+            | ExpectFx {
+                condition,
+                remainder,
+                ..
+            } => {
+                f(*condition);
+                stmt = remainder;
+            }
+            Ret(_) | Jump(..) | Crash(..) => return,
+        }
+    }
+}
+
+/// The largest specialization id of the calls in `stmt`
+pub(crate) fn max_call_spec_id(stmt: &Stmt) -> CallSpecId {
+    let mut max = CallSpecId::BACKEND_DUMMY;
+
+    for_each_call(stmt, &mut |call| {
+        let id = match &call.call_type {
+            CallType::ByName {
+                specialization_id, ..
+            } => *specialization_id,
+            CallType::HigherOrder(higher_order) => higher_order.passed_function.specialization_id,
+            CallType::Foreign { .. } | CallType::LowLevel { .. } => return,
+        };
+        max = max.max(id);
+    });
+
+    max
+}
+
+/// The number of statements in `stmt`
+pub(crate) fn size(stmt: &Stmt) -> usize {
+    use Stmt::*;
+
+    match stmt {
+        Let(_, _, _, cont) | Refcounting(_, cont) => 1 + size(cont),
+        Expect { remainder, .. } | ExpectFx { remainder, .. } => 1 + size(remainder),
+        Switch {
+            branches,
+            default_branch,
+            ..
+        } => {
+            1 + branches
+                .iter()
+                .map(|(_, _, branch)| size(branch))
+                .sum::<usize>()
+                + size(default_branch.1)
+        }
+        Join {
+            body, remainder, ..
+        } => 1 + size(body) + size(remainder),
+        Ret(_) | Jump(..) | Crash(..) => 1,
+    }
+}
+
+/// The symbol that a body without branches returns
+fn returned_symbol(stmt: &Stmt) -> Option<Symbol> {
+    use Stmt::*;
+
+    let mut stmt = stmt;
+    loop {
+        match stmt {
+            Let(_, _, _, cont) | Refcounting(_, cont) => stmt = cont,
+            Expect { remainder, .. } | ExpectFx { remainder, .. } => stmt = remainder,
+            Ret(symbol) => return Some(*symbol),
+            Switch { .. } | Join { .. } | Jump(..) | Crash(..) => return None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::inline_procedures;
+    use crate::ir::text::parse_procs;
+    use crate::ir::{OptLevel, ProcLayout, UpdateModeIds};
+    use crate::layout::STLayoutInterner;
+    use bumpalo::Bump;
+    use roc_collections::all::MutMap;
+    use roc_intern::SingleThreadedInterner;
+    use roc_module::ident::ModuleName;
+    use roc_module::symbol::Interns;
+
+    const TEXT: &str = r#"procedure Test.1 (Test.2 : I64) -> I64:
+    let Test.3 : I64 = 1i64;
+    let Test.4 : I64 = lowlevel NumAdd Test.2 Test.3;
+    ret Test.4;
+
+procedure Test.5 (Test.6 : Int1) -> I64:
+    switch Test.6 : Int1 -> I64:
+        case 1:
+            let Test.7 : I64 = 1i64;
+            ret Test.7;
+        default:
+            let Test.8 : I64 = 0i64;
+            ret Test.8;
+
+procedure Test.0 (Test.9 : Int1) -> I64:
+    let Test.10 : I64 = CallByName Test.5 Test.9;
+    let Test.11 : I64 = CallByName Test.1 Test.10;
+    ret Test.11;
+"#;
+
+    const INLINED: &str = r#"procedure Test.0 (Test.9 : Int1) -> I64:
+    joinpoint Test.13 (Test.10 : I64):
+        let Test.12 : I64 = 1i64;
+        let Test.11 : I64 = lowlevel NumAdd Test.10 Test.12;
+        ret Test.11;
+    in
+    switch Test.9 : Int1 -> I64:
+        case 1:
+            let Test.14 : I64 = 1i64;
+            jump Test.13 Test.14;
+        default:
+            let Test.15 : I64 = 0i64;
+            jump Test.13 Test.15;
+"#;
+
+    const CROSS_MODULE_TEXT: &str = r#"procedure Dep.1 (Dep.2 : I64) -> I64:
+    let Dep.3 : I64 = 1i64;
+    let Dep.4 : Int1 = lowlevel NumGt Dep.2 Dep.3;
+    expect Dep.4 with Dep.2;
+    let Dep.5 : I64 = lowlevel NumAdd Dep.2 Dep.3;
+    ret Dep.5;
+
+procedure Test.0 (Test.1 : I64) -> I64:
+    let Test.2 : I64 = CallByName Dep.1 Test.1;
+    ret Test.2;
+"#;
+
+    const CROSS_MODULE_INLINED: &str = r#"procedure Test.0 (Test.1 : I64) -> I64:
+    let Test.3 : I64 = 1i64;
+    let Dep.6 : Int1 = lowlevel NumGt Test.1 Test.3;
+    expect Dep.6 with Test.1;
+    let Test.2 : I64 = lowlevel NumAdd Test.1 Test.3;
+    ret Test.2;
+"#;
+
+    /// Inline the calls in the procedures of `text`, and print the last one
+    fn inline_into_last(text: &str) -> String {
+        let arena = Bump::new();
+        let mut interns = Interns::default();
+        let interner: STLayoutInterner = SingleThreadedInterner::with_capacity(4);
+
+        let procs = match parse_procs(&arena, &mut interns, text) {
+            Ok(procs) => procs,
+            Err(error) => panic!("{}", error),
+        };
+        let caller = procs.last().unwrap().name.name();
+        let mut procs: MutMap<_, _> = procs
+            .into_iter()
+            .map(|proc| {
+                let arguments = arena.alloc_slice_fill_iter(proc.args.iter().map(|(l, _)| *l));
+                let proc_layout = ProcLayout::new(
+                    &arena,
+                    arguments,
+                    proc.name.captures_niche(),
+                    proc.ret_layout,
+                );
+
+                ((proc.name.name(), proc_layout), proc)
+            })
+            .collect();
+
+        let home = interns.module_id(&ModuleName::from("Test"));
+        let mut update_mode_ids = UpdateModeIds::new();
+
+        inline_procedures(
+            &arena,
+            home,
+            &mut interns.all_ident_ids,
+            &mut update_mode_ids,
+            OptLevel::Normal,
+            &mut procs,
+        );
+
+        let caller = procs
+            .values()
+            .find(|proc| proc.name.name() == caller)
+            .unwrap();
+
+        caller.to_text(&interns, &interner)
+    }
+
+    #[test]
+    fn inline_straight_line_and_branching_procs() {
+        assert_eq!(inline_into_last(TEXT), INLINED);
+    }
+
+    #[test]
+    fn inlined_expect_stays_in_its_module() {
+        assert_eq!(inline_into_last(CROSS_MODULE_TEXT), CROSS_MODULE_INLINED);
+    }
+}
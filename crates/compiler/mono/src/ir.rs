@@ -11,7 +11,7 @@ use roc_builtins::bitcode::{FloatWidth, IntWidth};
 use roc_can::abilities::SpecializationId;
 use roc_can::expr::{AnnotatedMark, ClosureData, ExpectLookup, IntValue};
 use roc_can::module::ExposedByModule;
-use roc_collections::all::{default_hasher, BumpMap, BumpMapDefault, MutMap};
+use roc_collections::all::{default_hasher, BumpMap, BumpMapDefault, MutMap, MutSet};
 use roc_collections::VecMap;
 use roc_debug_flags::dbg_do;
 #[cfg(debug_assertions)]
@@ -27,7 +27,7 @@ use roc_late_solve::storage::{ExternalModuleStorage, ExternalModuleStorageSnapsh
 use roc_late_solve::{resolve_ability_specialization, AbilitiesView, Resolved, UnificationFailed};
 use roc_module::ident::{ForeignSymbol, Lowercase, TagName};
 use roc_module::low_level::LowLevel;
-use roc_module::symbol::{IdentId, IdentIds, ModuleId, Symbol};
+use roc_module::symbol::{IdentId, IdentIds, IdentIdsByModule, ModuleId, Symbol};
 use roc_problem::can::{RuntimeError, ShadowKind};
 use roc_region::all::{Loc, Region};
 use roc_std::RocDec;
@@ -39,6 +39,8 @@ use roc_types::subs::{
 use std::collections::HashMap;
 use ven_pretty::{BoxAllocator, DocAllocator, DocBuilder};
 
+pub mod text;
+
 #[inline(always)]
 pub fn pretty_print_ir_symbols() -> bool {
     dbg_do!(ROC_PRINT_IR_AFTER_SPECIALIZATION, {
@@ -402,7 +404,7 @@ impl<'a> Proc<'a> {
         update_mode_ids: &'i mut UpdateModeIds,
         procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
     ) {
-        let borrow_params = arena.alloc(crate::borrow::infer_borrow(arena, procs));
+        let borrow_params = arena.alloc(crate::borrow::infer_borrow(arena, layout_interner, procs));
 
         crate::inc_dec::visit_procs(
             arena,
@@ -415,6 +417,87 @@ impl<'a> Proc<'a> {
         );
     }
 
+    #[allow(clippy::too_many_arguments)]
+    pub fn fuse_list_operations<'i>(
+        arena: &'a Bump,
+        layout_interner: &'i STLayoutInterner<'a>,
+        home: ModuleId,
+        ident_ids: &'i mut IdentIds,
+        update_mode_ids: &'i mut UpdateModeIds,
+        target_info: TargetInfo,
+        opt_level: OptLevel,
+        procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    ) {
+        crate::list_fusion::fuse_list_operations(
+            arena,
+            layout_interner,
+            home,
+            ident_ids,
+            update_mode_ids,
+            target_info,
+            opt_level,
+            procs,
+        );
+    }
+
+    pub fn inline_procedures<'i>(
+        arena: &'a Bump,
+        home: ModuleId,
+        all_ident_ids: &'i mut IdentIdsByModule,
+        update_mode_ids: &'i mut UpdateModeIds,
+        opt_level: OptLevel,
+        procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    ) {
+        crate::inline::inline_procedures(
+            arena,
+            home,
+            all_ident_ids,
+            update_mode_ids,
+            opt_level,
+            procs,
+        );
+    }
+
+    pub fn fold_constant_operations(
+        arena: &'a Bump,
+        opt_level: OptLevel,
+        procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    ) {
+        crate::constant_folding::fold_constants(arena, opt_level, procs);
+    }
+
+    pub fn erase_closure_arguments(
+        arena: &'a Bump,
+        interner: &STLayoutInterner<'a>,
+        opt_level: OptLevel,
+        exposed_to_host: &MutSet<Symbol>,
+        procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    ) {
+        crate::closure_erasure::erase_closure_arguments(
+            arena,
+            interner,
+            opt_level,
+            exposed_to_host,
+            procs,
+        );
+    }
+
+    pub fn eliminate_dead_code(
+        arena: &'a Bump,
+        opt_level: OptLevel,
+        procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    ) {
+        crate::dead_code::eliminate_dead_code(arena, opt_level, procs);
+    }
+
+    pub fn elide_refcount_operations(
+        arena: &'a Bump,
+        opt_level: OptLevel,
+        procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    ) {
+        crate::refcount_elision::elide_refcount_operations(arena, opt_level, procs);
+    }
+
     pub fn insert_reset_reuse_operations<'i>(
         arena: &'a Bump,
         home: ModuleId,
@@ -433,34 +516,6 @@ impl<'a> Proc<'a> {
             *proc = new_proc;
         }
     }
-
-    fn make_tail_recursive(&mut self, env: &mut Env<'a, '_>) {
-        let mut args = Vec::with_capacity_in(self.args.len(), env.arena);
-        let mut proc_args = Vec::with_capacity_in(self.args.len(), env.arena);
-
-        for (layout, symbol) in self.args {
-            let new = env.unique_symbol();
-            args.push((*layout, *symbol, new));
-            proc_args.push((*layout, new));
-        }
-
-        use self::SelfRecursive::*;
-        if let SelfRecursive(id) = self.is_self_recursive {
-            let transformed = crate::tail_recursion::make_tail_recursive(
-                env.arena,
-                id,
-                self.name,
-                self.body.clone(),
-                args.into_bump_slice(),
-                self.ret_layout,
-            );
-
-            if let Some(with_tco) = transformed {
-                self.body = with_tco;
-                self.args = proc_args.into_bump_slice();
-            }
-        }
-    }
 }
 
 /// A host-exposed function must be specialized; it's a seed for subsequent specializations
@@ -710,6 +765,9 @@ struct Specialized<'a> {
     symbols: std::vec::Vec<Symbol>,
     proc_layouts: std::vec::Vec<ProcLayout<'a>>,
     procedures: std::vec::Vec<InProgressProc<'a>>,
+    /// Where each (symbol, layout) pair is in the vectors above, so that looking up a
+    /// specialization doesn't take a scan over all of them
+    indices: MutMap<(Symbol, ProcLayout<'a>), usize>,
 }
 
 impl<'a> Specialized<'a> {
@@ -742,76 +800,55 @@ impl<'a> Specialized<'a> {
     }
 
     fn is_specialized(&self, symbol: Symbol, layout: &ProcLayout<'a>) -> bool {
-        for (i, s) in self.symbols.iter().enumerate() {
-            if *s == symbol && &self.proc_layouts[i] == layout {
-                return true;
-            }
-        }
+        self.indices.contains_key(&(symbol, *layout))
+    }
 
-        false
+    fn push(&mut self, symbol: Symbol, layout: ProcLayout<'a>, procedure: InProgressProc<'a>) {
+        self.indices.insert((symbol, layout), self.symbols.len());
+        self.symbols.push(symbol);
+        self.proc_layouts.push(layout);
+        self.procedures.push(procedure);
     }
 
     fn mark_in_progress(&mut self, symbol: Symbol, layout: ProcLayout<'a>) {
-        for (i, s) in self.symbols.iter().enumerate() {
-            if *s == symbol && self.proc_layouts[i] == layout {
-                match &self.procedures[i] {
-                    InProgressProc::InProgress => {
-                        return;
-                    }
-                    InProgressProc::Done(_) => {
-                        panic!("marking in progress, but this proc is already done!")
-                    }
+        match self.indices.get(&(symbol, layout)) {
+            Some(&i) => match &self.procedures[i] {
+                InProgressProc::InProgress => {}
+                InProgressProc::Done(_) => {
+                    panic!("marking in progress, but this proc is already done!")
                 }
+            },
+            None => {
+                // the key/layout combo was not found; insert it
+                self.push(symbol, layout, InProgressProc::InProgress);
             }
         }
-
-        // the key/layout combo was not found; insert it
-        self.symbols.push(symbol);
-        self.proc_layouts.push(layout);
-        self.procedures.push(InProgressProc::InProgress);
     }
 
     fn remove_specialized(&mut self, symbol: Symbol, layout: &ProcLayout<'a>) -> bool {
-        let mut index = None;
+        match self.indices.remove(&(symbol, *layout)) {
+            Some(index) => {
+                self.symbols[index] = Symbol::REMOVED_SPECIALIZATION;
 
-        for (i, s) in self.symbols.iter().enumerate() {
-            if *s == symbol && &self.proc_layouts[i] == layout {
-                index = Some(i);
+                true
             }
-        }
-
-        if let Some(index) = index {
-            self.symbols[index] = Symbol::REMOVED_SPECIALIZATION;
-
-            true
-        } else {
-            false
+            None => false,
         }
     }
 
     fn insert_specialized(&mut self, symbol: Symbol, layout: ProcLayout<'a>, proc: Proc<'a>) {
-        for (i, s) in self.symbols.iter().enumerate() {
-            if *s == symbol && self.proc_layouts[i] == layout {
-                match &self.procedures[i] {
-                    InProgressProc::InProgress => {
-                        self.procedures[i] = InProgressProc::Done(proc);
-                        return;
-                    }
-                    InProgressProc::Done(_) => {
-                        // overwrite existing! this is important in practice
-                        // TODO investigate why we generate the wrong proc in some cases and then
-                        // correct later
-                        self.procedures[i] = InProgressProc::Done(proc);
-                        return;
-                    }
-                }
+        match self.indices.get(&(symbol, layout)) {
+            Some(&i) => {
+                // if this proc is already done, overwrite it! this is important in practice
+                // TODO investigate why we generate the wrong proc in some cases and then
+                // correct later
+                self.procedures[i] = InProgressProc::Done(proc);
+            }
+            None => {
+                // the key/layout combo was not found; insert it
+                self.push(symbol, layout, InProgressProc::Done(proc));
             }
         }
-
-        // the key/layout combo was not found; insert it
-        self.symbols.push(symbol);
-        self.proc_layouts.push(layout);
-        self.procedures.push(InProgressProc::Done(proc));
     }
 }
 
@@ -973,6 +1010,9 @@ pub struct Procs<'a> {
     pub externals_we_need: BumpMap<ModuleId, ExternalSpecializations<'a>>,
     symbol_specializations: SymbolSpecializations<'a>,
     specialization_stack: SpecializationStack<'a>,
+    /// Where each function calls another by name in tail position, so that the tail calls that
+    /// can't be made into jumps can be reported at the call
+    tail_call_regions: MutMap<(Symbol, Symbol), std::vec::Vec<Region>>,
 }
 
 impl<'a> Procs<'a> {
@@ -988,6 +1028,23 @@ impl<'a> Procs<'a> {
             externals_we_need: BumpMap::new_in(arena),
             symbol_specializations: Default::default(),
             specialization_stack: SpecializationStack(Vec::with_capacity_in(16, arena)),
+            tail_call_regions: MutMap::default(),
+        }
+    }
+
+    /// Remember where the function being specialized calls `callee`, if the call is in tail
+    /// position (its result is returned right away)
+    fn record_tail_call(&mut self, callee: Symbol, region: Region, assigned: Symbol, hole: &Stmt) {
+        let caller = match (self.specialization_stack.0.last(), hole) {
+            (Some(caller), Stmt::Ret(returned)) if *returned == assigned => *caller,
+            _ => return,
+        };
+
+        let regions = self.tail_call_regions.entry((caller, callee)).or_default();
+
+        // every specialization of the caller lowers the same call
+        if !regions.contains(&region) {
+            regions.push(region);
         }
     }
 
@@ -1049,17 +1106,25 @@ impl<'a> Procs<'a> {
     pub fn get_specialized_procs_without_rc(
         self,
         env: &mut Env<'a, '_>,
-    ) -> (MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>, ProcsBase<'a>) {
+    ) -> (
+        MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+        std::vec::Vec<roc_problem::can::Problem>,
+        ProcsBase<'a>,
+    ) {
         let mut specialized_procs =
             MutMap::with_capacity_and_hasher(self.specialized.len(), default_hasher());
 
-        for (symbol, layout, mut proc) in self.specialized.into_iter_assert_done() {
-            proc.make_tail_recursive(env);
-
+        for (symbol, layout, proc) in self.specialized.into_iter_assert_done() {
             let key = (symbol, layout);
             specialized_procs.insert(key, proc);
         }
 
+        let tail_call_problems = crate::tail_recursion::make_tail_recursive(
+            env,
+            &mut specialized_procs,
+            &self.tail_call_regions,
+        );
+
         let restored_procs_base = ProcsBase {
             partial_procs: self.partial_procs.drain().collect(),
             module_thunks: self.module_thunks,
@@ -1069,7 +1134,7 @@ impl<'a> Procs<'a> {
             imported_module_thunks: self.imported_module_thunks,
         };
 
-        (specialized_procs, restored_procs_base)
+        (specialized_procs, tail_call_problems, restored_procs_base)
     }
 
     // TODO trim these down
@@ -1844,7 +1909,7 @@ impl<'a> Call<'a> {
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub struct CallSpecId {
     id: u32,
 }
@@ -1857,6 +1922,11 @@ impl CallSpecId {
     /// Dummy value for generating refcount helper procs in the backends
     /// This happens *after* specialization so it's safe
     pub const BACKEND_DUMMY: Self = Self { id: 0 };
+
+    /// The id after this one, for passes that add calls after specialization
+    pub(crate) fn next(self) -> Self {
+        Self { id: self.id + 1 }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -1925,6 +1995,11 @@ pub struct PassedFunction<'a> {
     pub captured_environment: Symbol,
 
     pub owns_captured_environment: bool,
+
+    /// Whether the function borrows its (non-environment) arguments. This is decided by the
+    /// borrow inference. When it is false, backends that pass the same element to the function
+    /// more than once (like `List.sortWith` does) must increment the element before each call.
+    pub borrows_arguments: bool,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -1996,6 +2071,14 @@ pub enum Expr<'a> {
         tag_id: TagIdIntType,
         arguments: &'a [Symbol],
     },
+    /// Like [Expr::ExprBox], but stores the value in the memory of a box that was [Expr::Reset]
+    ReuseBox {
+        /// The result of the reset
+        symbol: Symbol,
+        update_mode: UpdateModeId,
+        /// The value to box
+        value: Symbol,
+    },
     Reset {
         symbol: Symbol,
         update_mode: UpdateModeId,
@@ -2116,6 +2199,17 @@ impl<'a> Expr<'a> {
                     .append(alloc.space())
                     .append(alloc.intersperse(it, " "))
             }
+            ReuseBox {
+                symbol,
+                update_mode,
+                value,
+            } => alloc
+                .text("ReuseBox ")
+                .append(symbol_to_doc(alloc, *symbol, pretty))
+                .append(alloc.space())
+                .append(format!("{:?}", update_mode))
+                .append(alloc.space())
+                .append(symbol_to_doc(alloc, *value, pretty)),
             Reset {
                 symbol,
                 update_mode,
@@ -3174,22 +3268,63 @@ fn specialize_external_specializations<'a>(
 
     let offset_variable = StorageSubs::merge_into(store, env.subs);
 
+    // A module can ask for the same variable more than once; those requests don't even need
+    // their layout computed.
+    let mut seen = MutSet::default();
+
     for (symbol, solved_types) in it {
         for store_variable in solved_types {
             let imported_variable = offset_variable(store_variable);
 
-            roc_tracing::debug!(proc_name = ?symbol, ?store_variable, ?imported_variable, "specializing needed external");
+            if !seen.insert((symbol, imported_variable)) {
+                continue;
+            }
 
-            // historical note: we used to deduplicate with a hash here,
-            // but the cost of that hash is very high. So for now we make
-            // duplicate specializations, and the insertion into a hash map
-            // below will deduplicate them.
+            // Several modules often need the same specialization (think `List.map` on
+            // `List Str`), and each of them asks for it. Lowering it once is enough, so skip
+            // requests for a layout that is already specialized. Comparing layouts is much
+            // cheaper than hashing the types, which we used to do here.
+            if is_specialized_for_var(env, procs, layout_cache, symbol, imported_variable) {
+                roc_tracing::debug!(proc_name = ?symbol, ?imported_variable, "reusing specialization of needed external");
+                continue;
+            }
+
+            roc_tracing::debug!(proc_name = ?symbol, ?store_variable, ?imported_variable, "specializing needed external");
 
             specialize_external_help(env, procs, layout_cache, symbol, imported_variable, &[])
         }
     }
 }
 
+fn is_specialized_for_var<'a>(
+    env: &mut Env<'a, '_>,
+    procs: &Procs<'a>,
+    layout_cache: &mut LayoutCache<'a>,
+    name: LambdaName<'a>,
+    variable: Variable,
+) -> bool {
+    match layout_cache.raw_from_var(env.arena, variable, env.subs) {
+        Ok(raw) => {
+            // Module thunks are specialized with the layout `specialize_variable` gives them.
+            let raw = if procs.is_module_thunk(name.name()) {
+                raw.as_module_thunk()
+            } else {
+                raw
+            };
+
+            let top_level = ProcLayout::from_raw(
+                env.arena,
+                &layout_cache.interner,
+                raw,
+                name.captures_niche(),
+            );
+
+            procs.specialized.is_specialized(name.name(), &top_level)
+        }
+        Err(_) => false,
+    }
+}
+
 fn specialize_external_help<'a>(
     env: &mut Env<'a, '_>,
     procs: &mut Procs<'a>,
@@ -3975,12 +4110,7 @@ fn specialize_variable<'a>(
         .unwrap_or_else(|err| panic!("TODO handle invalid function {:?}", err));
 
     let raw = if procs.is_module_thunk(proc_name.name()) {
-        match raw {
-            RawFunctionLayout::Function(_, lambda_set, _) => {
-                RawFunctionLayout::ZeroArgumentThunk(Layout::LambdaSet(lambda_set))
-            }
-            _ => raw,
-        }
+        raw.as_module_thunk()
     } else {
         raw
     };
@@ -4037,7 +4167,7 @@ fn specialize_variable<'a>(
     result
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub struct ProcLayout<'a> {
     pub arguments: &'a [Layout<'a>],
     pub result: Layout<'a>,
@@ -5245,6 +5375,8 @@ pub fn with_hole<'a>(
 
             match loc_expr.value {
                 roc_can::expr::Expr::Var(proc_name, _) if is_known(proc_name) => {
+                    procs.record_tail_call(proc_name, loc_expr.region, assigned, hole);
+
                     // a call by a known name
                     call_by_name(
                         env,
@@ -5261,6 +5393,13 @@ pub fn with_hole<'a>(
                     let specialization_proc_name =
                         late_resolve_ability_specialization(env, member, specialization_id, fn_var);
 
+                    procs.record_tail_call(
+                        specialization_proc_name,
+                        loc_expr.region,
+                        assigned,
+                        hole,
+                    );
+
                     call_by_name(
                         env,
                         procs,
@@ -5572,6 +5711,7 @@ pub fn with_hole<'a>(
                                         name: top_level_function,
                                         captured_environment: closure_data_symbol,
                                         owns_captured_environment: false,
+                                        borrows_arguments: false,
                                         specialization_id,
                                         argument_layouts: arg_layouts,
                                         return_layout: ret_layout,
@@ -7310,7 +7450,9 @@ fn substitute_in_expr<'a>(
             }
         }
 
-        Reuse { .. } | Reset { .. } => unreachable!("reset/reuse have not been introduced yet"),
+        Reuse { .. } | ReuseBox { .. } | Reset { .. } => {
+            unreachable!("reset/reuse have not been introduced yet")
+        }
 
         Struct(args) => {
             let mut did_change = false;
@@ -11017,3 +11159,39 @@ where
         remainder: env.arena.alloc(switch),
     }
 }
+
+#[cfg(test)]
+mod test_specialized {
+    use super::{ProcLayout, Specialized};
+    use crate::layout::{CapturesNiche, Layout};
+    use bumpalo::Bump;
+    use roc_module::symbol::Symbol;
+
+    #[test]
+    fn look_up_specializations_by_symbol_and_layout() {
+        let arena = Bump::new();
+        let unit_to_unit = ProcLayout::new(
+            &arena,
+            &[Layout::UNIT],
+            CapturesNiche::no_niche(),
+            Layout::UNIT,
+        );
+        let thunk = ProcLayout::new(&arena, &[], CapturesNiche::no_niche(), Layout::UNIT);
+
+        let mut specialized = Specialized::default();
+
+        specialized.mark_in_progress(Symbol::LIST_MAP, unit_to_unit);
+        specialized.mark_in_progress(Symbol::LIST_MAP, thunk);
+        specialized.mark_in_progress(Symbol::LIST_MAP, unit_to_unit);
+
+        assert_eq!(specialized.len(), 2);
+        assert!(specialized.is_specialized(Symbol::LIST_MAP, &unit_to_unit));
+        assert!(specialized.is_specialized(Symbol::LIST_MAP, &thunk));
+        assert!(!specialized.is_specialized(Symbol::LIST_WALK, &unit_to_unit));
+
+        assert!(specialized.remove_specialized(Symbol::LIST_MAP, &thunk));
+        assert!(!specialized.remove_specialized(Symbol::LIST_MAP, &thunk));
+        assert!(!specialized.is_specialized(Symbol::LIST_MAP, &thunk));
+        assert!(specialized.is_specialized(Symbol::LIST_MAP, &unit_to_unit));
+    }
+}
@@ -0,0 +1,1653 @@
+//! A stable text format for mono IR, with a printer and a parser.
+//!
+//! The format looks like the output of [Proc::to_pretty], but writes down everything that is
+//! needed to read the IR back: the layouts of arguments and join point parameters, the layouts a
+//! `switch` works on, and the function that a higher-order low-level calls. There is exactly one
+//! way to write every procedure, lines are never wrapped, and symbols are always written as
+//! `Module.index`, so that the same IR is always printed the same way and changes to it make for
+//! readable diffs.
+//!
+//! ```text
+//! procedure Num.19 (#Attr.2 : I64, #Attr.3 : I64) -> I64:
+//!     let Num.256 : I64 = lowlevel NumAdd #Attr.2 #Attr.3;
+//!     ret Num.256;
+//! ```
+//!
+//! Some information does not survive a round trip, and gets a default value when parsing:
+//!
+//! - lambda sets are written as their runtime representation
+//! - struct layouts don't remember the order of the record fields they were made from
+//! - `switch` branches have no [BranchInfo], and expects have no region or lookup types
+//! - procedures are not self-recursive, don't capture anything, and are not exposed to the host
+//! - call specialization ids and update mode ids are renumbered
+
+use super::{
+    BranchInfo, Call, CallSpecId, CallType, CrashTag, Expr, HigherOrderLowLevel,
+    HostExposedLayouts, JoinPointId, ListLiteralElement, Literal, ModifyRc, Param, PassedFunction,
+    Proc, SelfRecursive, Stmt, UpdateModeId,
+};
+use crate::layout::{Builtin, CapturesNiche, LambdaName, Layout, UnionLayout};
+use crate::low_level::HigherOrder;
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+use roc_builtins::bitcode::{FloatWidth, IntWidth};
+use roc_collections::all::MutMap;
+use roc_intern::Interner;
+use roc_module::ident::{ForeignSymbol, ModuleName};
+use roc_module::low_level::LowLevel;
+use roc_module::symbol::{Interns, Symbol};
+use roc_region::all::Region;
+use roc_std::RocDec;
+use roc_types::subs::Variable;
+use std::fmt::Write;
+
+const INDENT: usize = 4;
+
+impl<'a> Proc<'a> {
+    /// This procedure in the [text format](self)
+    pub fn to_text<I>(&self, interns: &Interns, interner: &I) -> String
+    where
+        I: Interner<'a, Layout<'a>>,
+    {
+        let mut printer = Printer {
+            interns,
+            interner,
+            text: String::new(),
+        };
+
+        printer.proc(self);
+
+        printer.text
+    }
+}
+
+impl<'a> Layout<'a> {
+    /// This layout as it is written in the [text format](self)
+    pub fn to_text<I>(&self, interns: &Interns, interner: &I) -> String
+    where
+        I: Interner<'a, Layout<'a>>,
+    {
+        let mut printer = Printer {
+            interns,
+            interner,
+            text: String::new(),
+        };
+
+        printer.layout(self);
+
+        printer.text
+    }
+}
+
+/// The procedures in the [text format](self), separated by empty lines
+pub fn procs_to_text<'a, 'p, I>(
+    interns: &Interns,
+    interner: &I,
+    procs: impl IntoIterator<Item = &'p Proc<'a>>,
+) -> String
+where
+    'a: 'p,
+    I: Interner<'a, Layout<'a>>,
+{
+    procs
+        .into_iter()
+        .map(|proc| proc.to_text(interns, interner))
+        .collect::<std::vec::Vec<_>>()
+        .join("\n")
+}
+
+struct Printer<'p, I> {
+    interns: &'p Interns,
+    interner: &'p I,
+    text: String,
+}
+
+impl<'a, 'p, I> Printer<'p, I>
+where
+    I: Interner<'a, Layout<'a>>,
+{
+    fn proc(&mut self, proc: &Proc<'a>) {
+        self.text.push_str("procedure ");
+        self.lambda_name(proc.name);
+        self.text.push_str(" (");
+        self.separated(proc.args, ", ", |p, (layout, symbol)| {
+            p.symbol(*symbol);
+            p.text.push_str(" : ");
+            p.layout(layout);
+        });
+        self.text.push_str(") -> ");
+        self.layout(&proc.ret_layout);
+        self.text.push_str(":\n");
+
+        self.stmt(&proc.body, INDENT);
+    }
+
+    fn stmt(&mut self, mut stmt: &Stmt<'a>, indent: usize) {
+        use Stmt::*;
+
+        loop {
+            self.indent(indent);
+
+            match stmt {
+                Let(symbol, expr, layout, cont) => {
+                    self.text.push_str("let ");
+                    self.symbol(*symbol);
+                    self.text.push_str(" : ");
+                    self.layout(layout);
+                    self.text.push_str(" = ");
+                    self.expr(expr);
+                    self.text.push_str(";\n");
+
+                    stmt = cont;
+                }
+                Switch {
+                    cond_symbol,
+                    cond_layout,
+                    branches,
+                    default_branch,
+                    ret_layout,
+                } => {
+                    self.text.push_str("switch ");
+                    self.symbol(*cond_symbol);
+                    self.text.push_str(" : ");
+                    self.layout(cond_layout);
+                    self.text.push_str(" -> ");
+                    self.layout(ret_layout);
+                    self.text.push_str(":\n");
+
+                    for (label, _, branch) in branches.iter() {
+                        self.indent(indent + INDENT);
+                        let _ = writeln!(self.text, "case {}:", label);
+                        self.stmt(branch, indent + 2 * INDENT);
+                    }
+
+                    self.indent(indent + INDENT);
+                    self.text.push_str("default:\n");
+                    self.stmt(default_branch.1, indent + 2 * INDENT);
+
+                    return;
+                }
+                Ret(symbol) => {
+                    self.text.push_str("ret ");
+                    self.symbol(*symbol);
+                    self.text.push_str(";\n");
+
+                    return;
+                }
+                Refcounting(modify, cont) => {
+                    match modify {
+                        ModifyRc::Inc(symbol, 1) => {
+                            self.text.push_str("inc ");
+                            self.symbol(*symbol);
+                        }
+                        ModifyRc::Inc(symbol, n) => {
+                            let _ = write!(self.text, "inc {} ", n);
+                            self.symbol(*symbol);
+                        }
+                        ModifyRc::Dec(symbol) => {
+                            self.text.push_str("dec ");
+                            self.symbol(*symbol);
+                        }
+                        ModifyRc::DecRef(symbol) => {
+                            self.text.push_str("decref ");
+                            self.symbol(*symbol);
+                        }
+                    }
+                    self.text.push_str(";\n");
+
+                    stmt = cont;
+                }
+                Expect {
+                    condition,
+                    lookups,
+                    remainder,
+                    ..
+                } => {
+                    self.expect("expect", *condition, lookups);
+
+                    stmt = remainder;
+                }
+                ExpectFx {
+                    condition,
+                    lookups,
+                    remainder,
+                    ..
+                } => {
+                    self.expect("expect-fx", *condition, lookups);
+
+                    stmt = remainder;
+                }
+                Join {
+                    id,
+                    parameters,
+                    body,
+                    remainder,
+                } => {
+                    self.text.push_str("joinpoint ");
+                    self.symbol(id.0);
+                    self.text.push_str(" (");
+                    self.separated(parameters, ", ", |p, param| {
+                        if param.borrow {
+                            p.text.push_str("borrowed ");
+                        }
+                        p.symbol(param.symbol);
+                        p.text.push_str(" : ");
+                        p.layout(&param.layout);
+                    });
+                    self.text.push_str("):\n");
+
+                    self.stmt(body, indent + INDENT);
+
+                    self.indent(indent);
+                    self.text.push_str("in\n");
+
+                    stmt = remainder;
+                }
+                Jump(id, arguments) => {
+                    self.text.push_str("jump ");
+                    self.symbol(id.0);
+                    self.arguments(arguments);
+                    self.text.push_str(";\n");
+
+                    return;
+                }
+                Crash(symbol, tag) => {
+                    self.text.push_str(match tag {
+                        CrashTag::Roc => "crash roc ",
+                        CrashTag::User => "crash user ",
+                    });
+                    self.symbol(*symbol);
+                    self.text.push_str(";\n");
+
+                    return;
+                }
+            }
+        }
+    }
+
+    fn expect(&mut self, keyword: &str, condition: Symbol, lookups: &[Symbol]) {
+        self.text.push_str(keyword);
+        self.text.push(' ');
+        self.symbol(condition);
+        if !lookups.is_empty() {
+            self.text.push_str(" with");
+            self.arguments(lookups);
+        }
+        self.text.push_str(";\n");
+    }
+
+    fn expr(&mut self, expr: &Expr<'a>) {
+        use Expr::*;
+
+        match expr {
+            Literal(literal) => self.literal(literal),
+            Call(call) => self.call(call),
+            Tag {
+                tag_id, arguments, ..
+            } => {
+                let _ = write!(self.text, "TagId({})", tag_id);
+                self.arguments(arguments);
+            }
+            Struct(fields) => {
+                self.text.push_str("Struct {");
+                self.separated(fields, ", ", |p, field| p.symbol(*field));
+                self.text.push('}');
+            }
+            StructAtIndex {
+                index, structure, ..
+            } => {
+                let _ = write!(self.text, "StructAtIndex {} ", index);
+                self.symbol(*structure);
+            }
+            GetTagId { structure, .. } => {
+                self.text.push_str("GetTagId ");
+                self.symbol(*structure);
+            }
+            UnionAtIndex {
+                structure,
+                tag_id,
+                index,
+                ..
+            } => {
+                let _ = write!(self.text, "UnionAtIndex (Id {}) (Index {}) ", tag_id, index);
+                self.symbol(*structure);
+            }
+            Array { elems, .. } => {
+                self.text.push_str("Array [");
+                self.separated(elems, ", ", |p, elem| match elem {
+                    ListLiteralElement::Literal(literal) => p.literal(literal),
+                    ListLiteralElement::Symbol(symbol) => p.symbol(*symbol),
+                });
+                self.text.push(']');
+            }
+            EmptyArray => self.text.push_str("Array []"),
+            ExprBox { symbol } => {
+                self.text.push_str("Box ");
+                self.symbol(*symbol);
+            }
+            ExprUnbox { symbol } => {
+                self.text.push_str("Unbox ");
+                self.symbol(*symbol);
+            }
+            Reuse {
+                symbol,
+                update_tag_id,
+                update_mode,
+                tag_id,
+                arguments,
+                ..
+            } => {
+                self.text.push_str("Reuse ");
+                self.symbol(*symbol);
+                let _ = write!(self.text, " (id {}) ", update_mode.id);
+                if *update_tag_id {
+                    self.text.push_str("update-tag-id ");
+                }
+                let _ = write!(self.text, "TagId({})", tag_id);
+                self.arguments(arguments);
+            }
+            ReuseBox {
+                symbol,
+                update_mode,
+                value,
+            } => {
+                self.text.push_str("ReuseBox ");
+                self.symbol(*symbol);
+                let _ = write!(self.text, " (id {}) ", update_mode.id);
+                self.symbol(*value);
+            }
+            Reset {
+                symbol,
+                update_mode,
+            } => {
+                self.text.push_str("Reset ");
+                self.symbol(*symbol);
+                let _ = write!(self.text, " (id {})", update_mode.id);
+            }
+            RuntimeErrorFunction(message) => {
+                let _ = write!(self.text, "ErrorFunction {:?}", message);
+            }
+        }
+    }
+
+    fn call(&mut self, call: &Call<'a>) {
+        match &call.call_type {
+            CallType::ByName { name, .. } => {
+                self.text.push_str("CallByName ");
+                self.lambda_name(*name);
+            }
+            CallType::Foreign { foreign_symbol, .. } => {
+                let _ = write!(self.text, "foreign {:?}", foreign_symbol.as_str());
+            }
+            CallType::LowLevel { op, .. } => {
+                let _ = write!(self.text, "lowlevel {:?}", op);
+            }
+            CallType::HigherOrder(higher_order) => {
+                let HigherOrderLowLevel {
+                    op,
+                    closure_env_layout,
+                    passed_function,
+                    ..
+                } = higher_order;
+
+                let (name, fields) = match *op {
+                    HigherOrder::ListMap { xs } => ("ListMap", vec![("xs", xs)]),
+                    HigherOrder::ListMap2 { xs, ys } => ("ListMap2", vec![("xs", xs), ("ys", ys)]),
+                    HigherOrder::ListMap3 { xs, ys, zs } => {
+                        ("ListMap3", vec![("xs", xs), ("ys", ys), ("zs", zs)])
+                    }
+                    HigherOrder::ListMap4 { xs, ys, zs, ws } => (
+                        "ListMap4",
+                        vec![("xs", xs), ("ys", ys), ("zs", zs), ("ws", ws)],
+                    ),
+                    HigherOrder::ListSortWith { xs } => ("ListSortWith", vec![("xs", xs)]),
+                };
+
+                let _ = write!(self.text, "higher-order {} {{", name);
+                self.separated(&fields, ", ", |p, (field, symbol)| {
+                    p.text.push_str(field);
+                    p.text.push_str(": ");
+                    p.symbol(*symbol);
+                });
+                self.text.push_str("} ");
+                self.lambda_name(passed_function.name);
+                self.text.push_str(" (");
+                self.separated(passed_function.argument_layouts, ", ", |p, layout| {
+                    p.layout(layout)
+                });
+                self.text.push_str(") -> ");
+                self.layout(&passed_function.return_layout);
+                self.text.push_str(" env ");
+                self.symbol(passed_function.captured_environment);
+                if let Some(layout) = closure_env_layout {
+                    self.text.push_str(" : ");
+                    self.layout(layout);
+                }
+                if passed_function.owns_captured_environment {
+                    self.text.push_str(" owned");
+                }
+                if passed_function.borrows_arguments {
+                    self.text.push_str(" borrowing");
+                }
+            }
+        }
+
+        self.arguments(call.arguments);
+    }
+
+    fn literal(&mut self, literal: &Literal<'a>) {
+        use Literal::*;
+
+        let _ = match literal {
+            Int(bytes) => write!(self.text, "{}i64", i128::from_ne_bytes(*bytes)),
+            U128(bytes) => write!(self.text, "{}u128", u128::from_ne_bytes(*bytes)),
+            Float(lit) => write!(self.text, "{}f64", lit),
+            Decimal(bytes) => write!(self.text, "{}dec", RocDec::from_ne_bytes(*bytes)),
+            Bool(lit) => write!(self.text, "{}", lit),
+            Byte(lit) => write!(self.text, "{}u8", lit),
+            Str(lit) => write!(self.text, "{:?}", lit),
+        };
+    }
+
+    fn layout(&mut self, layout: &Layout<'a>) {
+        match layout {
+            Layout::Builtin(builtin) => match builtin {
+                Builtin::Int(width) => {
+                    let _ = write!(self.text, "{:?}", width);
+                }
+                Builtin::Float(FloatWidth::F32) => self.text.push_str("Float32"),
+                Builtin::Float(FloatWidth::F64) => self.text.push_str("Float64"),
+                Builtin::Bool => self.text.push_str("Int1"),
+                Builtin::Decimal => self.text.push_str("Decimal"),
+                Builtin::Str => self.text.push_str("Str"),
+                Builtin::List(elem) => {
+                    self.text.push_str("List ");
+                    self.layout(elem);
+                }
+            },
+            Layout::Struct { field_layouts, .. } => {
+                self.text.push('{');
+                self.separated(field_layouts, ", ", |p, field| p.layout(field));
+                self.text.push('}');
+            }
+            Layout::Boxed(inner) => {
+                self.text.push_str("Boxed(");
+                self.layout(inner);
+                self.text.push(')');
+            }
+            Layout::Union(union_layout) => self.union_layout(union_layout),
+            Layout::LambdaSet(lambda_set) => {
+                self.layout(&lambda_set.runtime_representation(self.interner))
+            }
+            Layout::RecursivePointer => self.text.push_str("*self"),
+        }
+    }
+
+    fn union_layout(&mut self, union_layout: &UnionLayout<'a>) {
+        self.text.push('[');
+
+        match union_layout {
+            UnionLayout::NonRecursive(tags) => {
+                self.separated(tags, ", ", |p, fields| p.tag(fields));
+            }
+            UnionLayout::Recursive(tags) => {
+                self.text.push_str("<r>");
+                self.separated(tags, ", ", |p, fields| p.tag(fields));
+            }
+            UnionLayout::NonNullableUnwrapped(fields) => {
+                self.text.push_str("<rnnu>");
+                self.tag(fields);
+            }
+            UnionLayout::NullableWrapped {
+                nullable_id,
+                other_tags,
+            } => {
+                self.text.push_str("<rnw>");
+                let nullable_id = *nullable_id as usize;
+                for tag_id in 0..=other_tags.len() {
+                    if tag_id > 0 {
+                        self.text.push_str(", ");
+                    }
+                    match tag_id.cmp(&nullable_id) {
+                        std::cmp::Ordering::Less => self.tag(other_tags[tag_id]),
+                        std::cmp::Ordering::Equal => self.text.push_str("<null>"),
+                        std::cmp::Ordering::Greater => self.tag(other_tags[tag_id - 1]),
+                    }
+                }
+            }
+            UnionLayout::NullableUnwrapped {
+                nullable_id,
+                other_fields,
+            } => {
+                self.text.push_str("<rnu>");
+                if *nullable_id {
+                    self.tag(other_fields);
+                    self.text.push_str(", <null>");
+                } else {
+                    self.text.push_str("<null>, ");
+                    self.tag(other_fields);
+                }
+            }
+            UnionLayout::NullablePointer {
+                nullable_id,
+                pointer,
+            } => {
+                self.text.push_str("<np>");
+                if *nullable_id {
+                    self.tag(std::slice::from_ref(*pointer));
+                    self.text.push_str(", <null>");
+                } else {
+                    self.text.push_str("<null>, ");
+                    self.tag(std::slice::from_ref(*pointer));
+                }
+            }
+        }
+
+        self.text.push(']');
+    }
+
+    fn tag(&mut self, fields: &[Layout<'a>]) {
+        self.text.push('C');
+        for field in fields {
+            self.text.push(' ');
+            self.layout(field);
+        }
+    }
+
+    fn lambda_name(&mut self, name: LambdaName<'a>) {
+        self.symbol(name.name());
+
+        let captures_niche = name.captures_niche().0;
+        if !captures_niche.is_empty() {
+            self.text.push('<');
+            self.separated(captures_niche, ", ", |p, layout| p.layout(layout));
+            self.text.push('>');
+        }
+    }
+
+    fn symbol(&mut self, symbol: Symbol) {
+        let _ = write!(
+            self.text,
+            "{}.{}",
+            self.interns.module_name(symbol.module_id()).as_str(),
+            symbol.ident_id().index()
+        );
+    }
+
+    fn arguments(&mut self, arguments: &[Symbol]) {
+        for argument in arguments {
+            self.text.push(' ');
+            self.symbol(*argument);
+        }
+    }
+
+    fn indent(&mut self, indent: usize) {
+        self.text.extend(std::iter::repeat(' ').take(indent));
+    }
+
+    fn separated<T>(&mut self, items: &[T], separator: &str, mut item: impl FnMut(&mut Self, &T)) {
+        for (i, it) in items.iter().enumerate() {
+            if i > 0 {
+                self.text.push_str(separator);
+            }
+            item(self, it);
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    /// The line the error is on, starting at 1
+    pub line: usize,
+    pub message: String,
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+/// Parse procedures written in the [text format](self). Modules and identifiers that `interns`
+/// does not know about yet are added to it.
+pub fn parse_procs<'a>(
+    arena: &'a Bump,
+    interns: &mut Interns,
+    text: &str,
+) -> Result<std::vec::Vec<Proc<'a>>, ParseError> {
+    let lines = text
+        .lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(|(i, line)| {
+            let text = line.trim_end();
+            let trimmed = text.trim_start_matches(' ');
+
+            Line {
+                number: i + 1,
+                indent: text.len() - trimmed.len(),
+                text: trimmed,
+            }
+        })
+        .collect();
+
+    let mut parser = Parser {
+        arena,
+        interns,
+        lines,
+        next_line: 0,
+        layouts: MutMap::default(),
+        next_call_spec_id: 0,
+        update_modes: MutMap::default(),
+        next_update_mode_id: 0,
+    };
+
+    let mut procs = std::vec::Vec::new();
+    while parser.next_line < parser.lines.len() {
+        procs.push(parser.proc()?);
+    }
+
+    Ok(procs)
+}
+
+type ParseResult<T> = Result<T, ParseError>;
+
+struct Line<'t> {
+    number: usize,
+    indent: usize,
+    text: &'t str,
+}
+
+/// The rest of a line that is being parsed
+struct Cursor<'t> {
+    line: usize,
+    text: &'t str,
+}
+
+impl<'t> Cursor<'t> {
+    fn error<T>(&self, message: impl Into<String>) -> ParseResult<T> {
+        Err(ParseError {
+            line: self.line,
+            message: message.into(),
+        })
+    }
+
+    fn skip_spaces(&mut self) {
+        self.text = self.text.trim_start();
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_spaces();
+        self.text.chars().next()
+    }
+
+    /// Skip `token` if the line continues with it
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_spaces();
+        match self.text.strip_prefix(token) {
+            Some(rest) => {
+                self.text = rest;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn expect(&mut self, token: &str) -> ParseResult<()> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            self.error(format!("expected `{}`, found `{}`", token, self.text))
+        }
+    }
+
+    /// Skip `keyword` if the next word is exactly `keyword`
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        let before = self.text;
+        match self.word() {
+            Ok(word) if word == keyword => true,
+            _ => {
+                self.text = before;
+                false
+            }
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> ParseResult<()> {
+        if self.eat_keyword(keyword) {
+            Ok(())
+        } else {
+            self.error(format!("expected `{}`, found `{}`", keyword, self.text))
+        }
+    }
+
+    /// Symbols, keywords, numbers and literals are words; brackets and punctuation end them
+    fn word(&mut self) -> ParseResult<&'t str> {
+        self.skip_spaces();
+
+        let end = self
+            .text
+            .find(|c: char| c.is_whitespace() || ",;:(){}[]<>".contains(c))
+            .unwrap_or(self.text.len());
+
+        if end == 0 {
+            return self.error(format!("expected a word, found `{}`", self.text));
+        }
+
+        let (word, rest) = self.text.split_at(end);
+        self.text = rest;
+
+        Ok(word)
+    }
+
+    fn number<T: std::str::FromStr>(&mut self) -> ParseResult<T> {
+        let word = self.word()?;
+        match word.parse() {
+            Ok(number) => Ok(number),
+            Err(_) => self.error(format!("expected a number, found `{}`", word)),
+        }
+    }
+
+    fn string(&mut self) -> ParseResult<String> {
+        self.expect("\"")?;
+
+        let mut string = String::new();
+        let text = self.text;
+        let mut chars = text.char_indices();
+
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.text = &text[i + 1..];
+                    return Ok(string);
+                }
+                '\\' => {
+                    let escaped = match chars.next().map(|(_, c)| c) {
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('0') => '\0',
+                        Some(c @ ('\\' | '"' | '\'')) => c,
+                        Some('u') => {
+                            let rest = &text[i + 2..];
+                            let code = rest
+                                .strip_prefix('{')
+                                .and_then(|rest| rest.split_once('}'))
+                                .and_then(|(hex, _)| u32::from_str_radix(hex, 16).ok())
+                                .and_then(char::from_u32);
+
+                            match code {
+                                Some(c) => {
+                                    // skip the `{..}`
+                                    chars.find(|(_, c)| *c == '}');
+                                    c
+                                }
+                                None => return self.error("invalid unicode escape in string"),
+                            }
+                        }
+                        _ => return self.error("invalid escape in string"),
+                    };
+
+                    string.push(escaped);
+                }
+                c => string.push(c),
+            }
+        }
+
+        self.error("unterminated string")
+    }
+
+    fn finish(&mut self) -> ParseResult<()> {
+        self.skip_spaces();
+
+        if self.text.is_empty() {
+            Ok(())
+        } else {
+            self.error(format!("unexpected `{}` at the end of the line", self.text))
+        }
+    }
+}
+
+struct Parser<'a, 'i, 't> {
+    arena: &'a Bump,
+    interns: &'i mut Interns,
+    lines: std::vec::Vec<Line<'t>>,
+    next_line: usize,
+    /// The layouts of the symbols in scope, which give the layouts that the text leaves out
+    layouts: MutMap<Symbol, Layout<'a>>,
+    next_call_spec_id: u32,
+    /// The update mode ids of the text, and the ids they are renumbered to
+    update_modes: MutMap<u32, UpdateModeId>,
+    next_update_mode_id: u32,
+}
+
+impl<'a, 'i, 't> Parser<'a, 'i, 't> {
+    /// The next line, which must be indented by `indent` spaces
+    fn line(&mut self, indent: usize) -> ParseResult<Cursor<'t>> {
+        match self.lines.get(self.next_line) {
+            Some(line) if line.indent == indent => {
+                self.next_line += 1;
+
+                Ok(Cursor {
+                    line: line.number,
+                    text: line.text,
+                })
+            }
+            Some(line) => Err(ParseError {
+                line: line.number,
+                message: format!("expected a line indented by {} spaces", indent),
+            }),
+            None => Err(ParseError {
+                line: self.lines.last().map_or(0, |line| line.number),
+                message: "unexpected end of input".to_string(),
+            }),
+        }
+    }
+
+    fn proc(&mut self) -> ParseResult<Proc<'a>> {
+        self.layouts.clear();
+
+        let mut cursor = self.line(0)?;
+        cursor.expect_keyword("procedure")?;
+        let name = self.lambda_name(&mut cursor)?;
+
+        cursor.expect("(")?;
+        let args = self.comma_separated(&mut cursor, ")", |p, cursor| {
+            let symbol = p.symbol(cursor)?;
+            cursor.expect(":")?;
+            let layout = p.layout(cursor)?;
+            p.layouts.insert(symbol, layout);
+
+            Ok((layout, symbol))
+        })?;
+        cursor.expect("->")?;
+        let ret_layout = self.layout(&mut cursor)?;
+        cursor.expect(":")?;
+        cursor.finish()?;
+
+        let body = self.stmt(INDENT)?;
+
+        Ok(Proc {
+            name,
+            args,
+            body,
+            closure_data_layout: None,
+            ret_layout,
+            is_self_recursive: SelfRecursive::NotSelfRecursive,
+            must_own_arguments: false,
+            host_exposed_layouts: HostExposedLayouts::NotHostExposed,
+        })
+    }
+
+    fn stmt(&mut self, indent: usize) -> ParseResult<Stmt<'a>> {
+        let arena = self.arena;
+        let mut cursor = self.line(indent)?;
+
+        let stmt = match cursor.word()? {
+            "let" => {
+                let symbol = self.symbol(&mut cursor)?;
+                cursor.expect(":")?;
+                let layout = self.layout(&mut cursor)?;
+                cursor.expect("=")?;
+                let expr = self.expr(&mut cursor, layout)?;
+                cursor.expect(";")?;
+                cursor.finish()?;
+
+                self.layouts.insert(symbol, layout);
+                let cont = self.stmt(indent)?;
+
+                return Ok(Stmt::Let(symbol, expr, layout, arena.alloc(cont)));
+            }
+            "switch" => {
+                let cond_symbol = self.symbol(&mut cursor)?;
+                cursor.expect(":")?;
+                let cond_layout = self.layout(&mut cursor)?;
+                cursor.expect("->")?;
+                let ret_layout = self.layout(&mut cursor)?;
+                cursor.expect(":")?;
+                cursor.finish()?;
+
+                let mut branches = Vec::new_in(arena);
+                loop {
+                    let mut cursor = self.line(indent + INDENT)?;
+                    match cursor.word()? {
+                        "case" => {
+                            let label = cursor.number()?;
+                            cursor.expect(":")?;
+                            cursor.finish()?;
+
+                            let branch = self.stmt(indent + 2 * INDENT)?;
+                            branches.push((label, BranchInfo::None, branch));
+                        }
+                        "default" => {
+                            cursor.expect(":")?;
+                            cursor.finish()?;
+
+                            let default_branch = self.stmt(indent + 2 * INDENT)?;
+
+                            return Ok(Stmt::Switch {
+                                cond_symbol,
+                                cond_layout,
+                                branches: branches.into_bump_slice(),
+                                default_branch: (BranchInfo::None, arena.alloc(default_branch)),
+                                ret_layout,
+                            });
+                        }
+                        word => {
+                            return cursor
+                                .error(format!("expected `case` or `default`, found `{}`", word))
+                        }
+                    }
+                }
+            }
+            "ret" => {
+                let symbol = self.symbol(&mut cursor)?;
+                cursor.expect(";")?;
+
+                Stmt::Ret(symbol)
+            }
+            keyword @ ("inc" | "dec" | "decref") => {
+                let word = cursor.word()?;
+                let modify = match keyword {
+                    "inc" => match word.parse() {
+                        Ok(count) => ModifyRc::Inc(self.symbol(&mut cursor)?, count),
+                        Err(_) => ModifyRc::Inc(self.symbol_from_word(&cursor, word)?, 1),
+                    },
+                    "dec" => ModifyRc::Dec(self.symbol_from_word(&cursor, word)?),
+                    _ => ModifyRc::DecRef(self.symbol_from_word(&cursor, word)?),
+                };
+                cursor.expect(";")?;
+                cursor.finish()?;
+
+                let cont = self.stmt(indent)?;
+
+                return Ok(Stmt::Refcounting(modify, arena.alloc(cont)));
+            }
+            keyword @ ("expect" | "expect-fx") => {
+                let condition = self.symbol(&mut cursor)?;
+                let lookups = if cursor.eat_keyword("with") {
+                    self.symbols_until(&mut cursor, ';')?
+                } else {
+                    &[]
+                };
+                cursor.expect(";")?;
+                cursor.finish()?;
+
+                let region = Region::zero();
+                let variables = Vec::from_iter_in(lookups.iter().map(|_| Variable::NULL), arena)
+                    .into_bump_slice();
+                let remainder = arena.alloc(self.stmt(indent)?);
+
+                return Ok(if keyword == "expect" {
+                    Stmt::Expect {
+                        condition,
+                        region,
+                        lookups,
+                        variables,
+                        remainder,
+                    }
+                } else {
+                    Stmt::ExpectFx {
+                        condition,
+                        region,
+                        lookups,
+                        variables,
+                        remainder,
+                    }
+                });
+            }
+            "joinpoint" => {
+                let id = JoinPointId(self.symbol(&mut cursor)?);
+                cursor.expect("(")?;
+                let parameters = self.comma_separated(&mut cursor, ")", |p, cursor| {
+                    let borrow = cursor.eat_keyword("borrowed");
+                    let symbol = p.symbol(cursor)?;
+                    cursor.expect(":")?;
+                    let layout = p.layout(cursor)?;
+                    p.layouts.insert(symbol, layout);
+
+                    Ok(Param {
+                        symbol,
+                        borrow,
+                        layout,
+                    })
+                })?;
+                cursor.expect(":")?;
+                cursor.finish()?;
+
+                let body = self.stmt(indent + INDENT)?;
+
+                let mut cursor = self.line(indent)?;
+                cursor.expect_keyword("in")?;
+                cursor.finish()?;
+
+                let remainder = self.stmt(indent)?;
+
+                return Ok(Stmt::Join {
+                    id,
+                    parameters,
+                    body: arena.alloc(body),
+                    remainder: arena.alloc(remainder),
+                });
+            }
+            "jump" => {
+                let id = JoinPointId(self.symbol(&mut cursor)?);
+                let arguments = self.symbols_until(&mut cursor, ';')?;
+                cursor.expect(";")?;
+
+                Stmt::Jump(id, arguments)
+            }
+            "crash" => {
+                let tag = match cursor.word()? {
+                    "roc" => CrashTag::Roc,
+                    "user" => CrashTag::User,
+                    word => {
+                        return cursor.error(format!("expected `roc` or `user`, found `{}`", word))
+                    }
+                };
+                let symbol = self.symbol(&mut cursor)?;
+                cursor.expect(";")?;
+
+                Stmt::Crash(symbol, tag)
+            }
+            word => return cursor.error(format!("expected a statement, found `{}`", word)),
+        };
+
+        cursor.finish()?;
+
+        Ok(stmt)
+    }
+
+    /// Parse the expression bound to a symbol of `layout`
+    fn expr(&mut self, cursor: &mut Cursor<'t>, layout: Layout<'a>) -> ParseResult<Expr<'a>> {
+        let arena = self.arena;
+
+        if cursor.peek() == Some('"') {
+            let string = cursor.string()?;
+
+            return Ok(Expr::Literal(Literal::Str(arena.alloc_str(&string))));
+        }
+
+        let word = cursor.word()?;
+        let expr = match word {
+            "CallByName" => {
+                let name = self.lambda_name(cursor)?;
+                let arguments = self.symbols_until(cursor, ';')?;
+                let arg_layouts = Vec::from_iter_in(
+                    arguments
+                        .iter()
+                        .map(|argument| self.layout_of(cursor, *argument))
+                        .collect::<ParseResult<std::vec::Vec<_>>>()?,
+                    arena,
+                );
+
+                Expr::Call(Call {
+                    call_type: CallType::ByName {
+                        name,
+                        ret_layout: arena.alloc(layout),
+                        arg_layouts: arg_layouts.into_bump_slice(),
+                        specialization_id: self.call_spec_id(),
+                    },
+                    arguments,
+                })
+            }
+            "lowlevel" => {
+                let name = cursor.word()?;
+                let op: LowLevel = match name.parse() {
+                    Ok(op) => op,
+                    Err(()) => {
+                        return cursor.error(format!("unknown low-level operation `{}`", name))
+                    }
+                };
+                let update_mode = self.fresh_update_mode();
+
+                Expr::Call(Call {
+                    call_type: CallType::LowLevel { op, update_mode },
+                    arguments: self.symbols_until(cursor, ';')?,
+                })
+            }
+            "foreign" => {
+                let name = cursor.string()?;
+
+                Expr::Call(Call {
+                    call_type: CallType::Foreign {
+                        foreign_symbol: ForeignSymbol::from(name.as_str()),
+                        ret_layout: arena.alloc(layout),
+                    },
+                    arguments: self.symbols_until(cursor, ';')?,
+                })
+            }
+            "higher-order" => self.higher_order(cursor)?,
+            "TagId" => {
+                cursor.expect("(")?;
+                let tag_id = cursor.number()?;
+                cursor.expect(")")?;
+
+                Expr::Tag {
+                    tag_layout: self.union_layout_of(cursor, layout)?,
+                    tag_id,
+                    arguments: self.symbols_until(cursor, ';')?,
+                }
+            }
+            "Struct" => {
+                cursor.expect("{")?;
+                let fields = self.comma_separated(cursor, "}", |p, cursor| p.symbol(cursor))?;
+
+                Expr::Struct(fields)
+            }
+            "StructAtIndex" => {
+                let index = cursor.number()?;
+                let structure = self.symbol(cursor)?;
+                let field_layouts = match self.layout_of(cursor, structure)? {
+                    Layout::Struct { field_layouts, .. } => field_layouts,
+                    _ => return cursor.error("`StructAtIndex` of a symbol that is not a struct"),
+                };
+
+                Expr::StructAtIndex {
+                    index,
+                    field_layouts,
+                    structure,
+                }
+            }
+            "GetTagId" => {
+                let structure = self.symbol(cursor)?;
+                let layout = self.layout_of(cursor, structure)?;
+
+                Expr::GetTagId {
+                    structure,
+                    union_layout: self.union_layout_of(cursor, layout)?,
+                }
+            }
+            "UnionAtIndex" => {
+                cursor.expect("(")?;
+                cursor.expect_keyword("Id")?;
+                let tag_id = cursor.number()?;
+                cursor.expect(")")?;
+                cursor.expect("(")?;
+                cursor.expect_keyword("Index")?;
+                let index = cursor.number()?;
+                cursor.expect(")")?;
+                let structure = self.symbol(cursor)?;
+                let layout = self.layout_of(cursor, structure)?;
+
+                Expr::UnionAtIndex {
+                    structure,
+                    tag_id,
+                    union_layout: self.union_layout_of(cursor, layout)?,
+                    index,
+                }
+            }
+            "Array" => {
+                cursor.expect("[")?;
+                let elems = self.comma_separated(cursor, "]", |p, cursor| {
+                    if cursor.peek() == Some('"') {
+                        let string = cursor.string()?;
+                        let literal = Literal::Str(p.arena.alloc_str(&string));
+
+                        return Ok(ListLiteralElement::Literal(literal));
+                    }
+
+                    let word = cursor.word()?;
+                    match literal(word) {
+                        Some(literal) => Ok(ListLiteralElement::Literal(literal)),
+                        None => Ok(ListLiteralElement::Symbol(
+                            p.symbol_from_word(cursor, word)?,
+                        )),
+                    }
+                })?;
+
+                match layout {
+                    _ if elems.is_empty() => Expr::EmptyArray,
+                    Layout::Builtin(Builtin::List(elem_layout)) => Expr::Array {
+                        elem_layout: *elem_layout,
+                        elems,
+                    },
+                    _ => return cursor.error("`Array` bound to a symbol that is not a list"),
+                }
+            }
+            "Box" => Expr::ExprBox {
+                symbol: self.symbol(cursor)?,
+            },
+            "Unbox" => Expr::ExprUnbox {
+                symbol: self.symbol(cursor)?,
+            },
+            "Reuse" => {
+                let symbol = self.symbol(cursor)?;
+                let update_mode = self.update_mode(cursor)?;
+                let update_tag_id = cursor.eat_keyword("update-tag-id");
+                cursor.expect_keyword("TagId")?;
+                cursor.expect("(")?;
+                let tag_id = cursor.number()?;
+                cursor.expect(")")?;
+
+                Expr::Reuse {
+                    symbol,
+                    update_tag_id,
+                    update_mode,
+                    tag_layout: self.union_layout_of(cursor, layout)?,
+                    tag_id,
+                    arguments: self.symbols_until(cursor, ';')?,
+                }
+            }
+            "ReuseBox" => Expr::ReuseBox {
+                symbol: self.symbol(cursor)?,
+                update_mode: self.update_mode(cursor)?,
+                value: self.symbol(cursor)?,
+            },
+            "Reset" => Expr::Reset {
+                symbol: self.symbol(cursor)?,
+                update_mode: self.update_mode(cursor)?,
+            },
+            "ErrorFunction" => {
+                let message = cursor.string()?;
+
+                Expr::RuntimeErrorFunction(arena.alloc_str(&message))
+            }
+            word => match literal(word) {
+                Some(literal) => Expr::Literal(literal),
+                None => return cursor.error(format!("expected an expression, found `{}`", word)),
+            },
+        };
+
+        Ok(expr)
+    }
+
+    fn higher_order(&mut self, cursor: &mut Cursor<'t>) -> ParseResult<Expr<'a>> {
+        let arena = self.arena;
+
+        let name = cursor.word()?;
+        cursor.expect("{")?;
+        let mut fields = std::vec::Vec::new();
+        if !cursor.eat("}") {
+            loop {
+                let field = cursor.word()?;
+                cursor.expect(":")?;
+                fields.push((field, self.symbol(cursor)?));
+
+                if cursor.eat("}") {
+                    break;
+                }
+                cursor.expect(",")?;
+            }
+        }
+
+        let op = match (name, fields.as_slice()) {
+            ("ListMap", [("xs", xs)]) => HigherOrder::ListMap { xs: *xs },
+            ("ListMap2", [("xs", xs), ("ys", ys)]) => HigherOrder::ListMap2 { xs: *xs, ys: *ys },
+            ("ListMap3", [("xs", xs), ("ys", ys), ("zs", zs)]) => HigherOrder::ListMap3 {
+                xs: *xs,
+                ys: *ys,
+                zs: *zs,
+            },
+            ("ListMap4", [("xs", xs), ("ys", ys), ("zs", zs), ("ws", ws)]) => {
+                HigherOrder::ListMap4 {
+                    xs: *xs,
+                    ys: *ys,
+                    zs: *zs,
+                    ws: *ws,
+                }
+            }
+            ("ListSortWith", [("xs", xs)]) => HigherOrder::ListSortWith { xs: *xs },
+            _ => return cursor.error(format!("invalid higher-order operation `{}`", name)),
+        };
+
+        let function_name = self.lambda_name(cursor)?;
+        cursor.expect("(")?;
+        let argument_layouts = self.comma_separated(cursor, ")", |p, cursor| p.layout(cursor))?;
+        cursor.expect("->")?;
+        let return_layout = self.layout(cursor)?;
+
+        cursor.expect_keyword("env")?;
+        let captured_environment = self.symbol(cursor)?;
+        let closure_env_layout = if cursor.eat(":") {
+            Some(self.layout(cursor)?)
+        } else {
+            None
+        };
+        let owns_captured_environment = cursor.eat_keyword("owned");
+        let borrows_arguments = cursor.eat_keyword("borrowing");
+
+        let higher_order = HigherOrderLowLevel {
+            op,
+            closure_env_layout,
+            update_mode: self.fresh_update_mode(),
+            passed_function: PassedFunction {
+                name: function_name,
+                argument_layouts,
+                return_layout,
+                specialization_id: self.call_spec_id(),
+                captured_environment,
+                owns_captured_environment,
+                borrows_arguments,
+            },
+        };
+
+        Ok(Expr::Call(Call {
+            call_type: CallType::HigherOrder(arena.alloc(higher_order)),
+            arguments: self.symbols_until(cursor, ';')?,
+        }))
+    }
+
+    fn layout(&mut self, cursor: &mut Cursor<'t>) -> ParseResult<Layout<'a>> {
+        let arena = self.arena;
+
+        match cursor.peek() {
+            Some('{') => {
+                cursor.expect("{")?;
+                let fields = self.comma_separated(cursor, "}", |p, cursor| p.layout(cursor))?;
+
+                return Ok(Layout::struct_no_name_order(fields));
+            }
+            Some('[') => return Ok(Layout::Union(self.union_layout(cursor)?)),
+            _ => {}
+        }
+
+        let word = cursor.word()?;
+        let layout = match word {
+            "I8" => Layout::Builtin(Builtin::Int(IntWidth::I8)),
+            "I16" => Layout::Builtin(Builtin::Int(IntWidth::I16)),
+            "I32" => Layout::Builtin(Builtin::Int(IntWidth::I32)),
+            "I64" => Layout::Builtin(Builtin::Int(IntWidth::I64)),
+            "I128" => Layout::Builtin(Builtin::Int(IntWidth::I128)),
+            "U8" => Layout::Builtin(Builtin::Int(IntWidth::U8)),
+            "U16" => Layout::Builtin(Builtin::Int(IntWidth::U16)),
+            "U32" => Layout::Builtin(Builtin::Int(IntWidth::U32)),
+            "U64" => Layout::Builtin(Builtin::Int(IntWidth::U64)),
+            "U128" => Layout::Builtin(Builtin::Int(IntWidth::U128)),
+            "Float32" => Layout::Builtin(Builtin::Float(FloatWidth::F32)),
+            "Float64" => Layout::Builtin(Builtin::Float(FloatWidth::F64)),
+            "Int1" => Layout::Builtin(Builtin::Bool),
+            "Decimal" => Layout::Builtin(Builtin::Decimal),
+            "Str" => Layout::Builtin(Builtin::Str),
+            "List" => Layout::Builtin(Builtin::List(arena.alloc(self.layout(cursor)?))),
+            "Boxed" => {
+                cursor.expect("(")?;
+                let inner = self.layout(cursor)?;
+                cursor.expect(")")?;
+
+                Layout::Boxed(arena.alloc(inner))
+            }
+            "*self" => Layout::RecursivePointer,
+            word => return cursor.error(format!("expected a layout, found `{}`", word)),
+        };
+
+        Ok(layout)
+    }
+
+    fn union_layout(&mut self, cursor: &mut Cursor<'t>) -> ParseResult<UnionLayout<'a>> {
+        let arena = self.arena;
+
+        cursor.expect("[")?;
+
+        let union_layout = if cursor.eat("<rnnu>") {
+            let fields = self.tag(cursor)?;
+            cursor.expect("]")?;
+
+            UnionLayout::NonNullableUnwrapped(fields)
+        } else if cursor.eat("<rnu>") {
+            let tags = self.comma_separated(cursor, "]", |p, cursor| p.nullable_tag(cursor))?;
+
+            match *tags {
+                [None, Some(other_fields)] => UnionLayout::NullableUnwrapped {
+                    nullable_id: false,
+                    other_fields,
+                },
+                [Some(other_fields), None] => UnionLayout::NullableUnwrapped {
+                    nullable_id: true,
+                    other_fields,
+                },
+                _ => return cursor.error("expected a `<null>` tag and one other tag"),
+            }
+        } else if cursor.eat("<np>") {
+            let tags = self.comma_separated(cursor, "]", |p, cursor| p.nullable_tag(cursor))?;
+
+            match *tags {
+                [None, Some([pointer])] => UnionLayout::NullablePointer {
+                    nullable_id: false,
+                    pointer,
+                },
+                [Some([pointer]), None] => UnionLayout::NullablePointer {
+                    nullable_id: true,
+                    pointer,
+                },
+                _ => return cursor.error("expected a `<null>` tag and one tag with a pointer"),
+            }
+        } else if cursor.eat("<rnw>") {
+            let tags = self.comma_separated(cursor, "]", |p, cursor| p.nullable_tag(cursor))?;
+
+            let mut nullable_ids = tags.iter().enumerate().filter(|(_, tag)| tag.is_none());
+            let nullable_id = match (nullable_ids.next(), nullable_ids.next()) {
+                (Some((nullable_id, _)), None) => nullable_id as u16,
+                _ => return cursor.error("expected exactly one `<null>` tag"),
+            };
+            let other_tags = Vec::from_iter_in(tags.iter().flatten().copied(), arena);
+
+            UnionLayout::NullableWrapped {
+                nullable_id,
+                other_tags: other_tags.into_bump_slice(),
+            }
+        } else if cursor.eat("<r>") {
+            UnionLayout::Recursive(self.comma_separated(cursor, "]", |p, cursor| p.tag(cursor))?)
+        } else {
+            UnionLayout::NonRecursive(self.comma_separated(cursor, "]", |p, cursor| p.tag(cursor))?)
+        };
+
+        Ok(union_layout)
+    }
+
+    /// A tag of a nullable union, which is `None` for the `<null>` tag
+    fn nullable_tag(&mut self, cursor: &mut Cursor<'t>) -> ParseResult<Option<&'a [Layout<'a>]>> {
+        if cursor.eat("<null>") {
+            Ok(None)
+        } else {
+            Ok(Some(self.tag(cursor)?))
+        }
+    }
+
+    fn tag(&mut self, cursor: &mut Cursor<'t>) -> ParseResult<&'a [Layout<'a>]> {
+        cursor.expect_keyword("C")?;
+
+        let mut fields = Vec::new_in(self.arena);
+        while !matches!(cursor.peek(), Some(',' | ']') | None) {
+            fields.push(self.layout(cursor)?);
+        }
+
+        Ok(fields.into_bump_slice())
+    }
+
+    fn lambda_name(&mut self, cursor: &mut Cursor<'t>) -> ParseResult<LambdaName<'a>> {
+        let name = self.symbol(cursor)?;
+
+        if cursor.text.starts_with('<') {
+            cursor.expect("<")?;
+            let layouts = self.comma_separated(cursor, ">", |p, cursor| p.layout(cursor))?;
+
+            Ok(LambdaName::with_captures_niche(
+                name,
+                CapturesNiche(layouts),
+            ))
+        } else {
+            Ok(LambdaName::no_niche(name))
+        }
+    }
+
+    fn symbol(&mut self, cursor: &mut Cursor<'t>) -> ParseResult<Symbol> {
+        let word = cursor.word()?;
+
+        self.symbol_from_word(cursor, word)
+    }
+
+    fn symbol_from_word(&mut self, cursor: &Cursor<'t>, word: &str) -> ParseResult<Symbol> {
+        let (module_name, index) = match word
+            .rsplit_once('.')
+            .and_then(|(module_name, index)| Some((module_name, index.parse::<u32>().ok()?)))
+        {
+            Some((module_name, index)) if !module_name.is_empty() => (module_name, index),
+            _ => return cursor.error(format!("expected a symbol, found `{}`", word)),
+        };
+
+        let module_id = self.interns.module_id(&ModuleName::from(module_name));
+        let ident_ids = self.interns.all_ident_ids.get_or_insert(module_id);
+        while ident_ids.len() <= index as usize {
+            ident_ids.gen_unique();
+        }
+
+        Ok(Interns::from_index(module_id, index))
+    }
+
+    /// The symbols up to `end`, which is not skipped
+    fn symbols_until(&mut self, cursor: &mut Cursor<'t>, end: char) -> ParseResult<&'a [Symbol]> {
+        let mut symbols = Vec::new_in(self.arena);
+        while cursor.peek() != Some(end) {
+            symbols.push(self.symbol(cursor)?);
+        }
+
+        Ok(symbols.into_bump_slice())
+    }
+
+    /// Items separated by commas, up to and including `close`
+    fn comma_separated<T>(
+        &mut self,
+        cursor: &mut Cursor<'t>,
+        close: &str,
+        mut item: impl FnMut(&mut Self, &mut Cursor<'t>) -> ParseResult<T>,
+    ) -> ParseResult<&'a [T]> {
+        let mut items = Vec::new_in(self.arena);
+
+        if !cursor.eat(close) {
+            loop {
+                items.push(item(self, cursor)?);
+
+                if cursor.eat(close) {
+                    break;
+                }
+                cursor.expect(",")?;
+            }
+        }
+
+        Ok(items.into_bump_slice())
+    }
+
+    fn layout_of(&self, cursor: &Cursor<'t>, symbol: Symbol) -> ParseResult<Layout<'a>> {
+        match self.layouts.get(&symbol) {
+            Some(layout) => Ok(*layout),
+            None => cursor.error(format!("the layout of `{:?}` is not known", symbol)),
+        }
+    }
+
+    fn union_layout_of(
+        &self,
+        cursor: &Cursor<'t>,
+        layout: Layout<'a>,
+    ) -> ParseResult<UnionLayout<'a>> {
+        match layout {
+            Layout::Union(union_layout) => Ok(union_layout),
+            _ => cursor.error("expected a symbol with a union layout"),
+        }
+    }
+
+    fn call_spec_id(&mut self) -> CallSpecId {
+        let id = CallSpecId {
+            id: self.next_call_spec_id,
+        };
+        self.next_call_spec_id += 1;
+        id
+    }
+
+    fn fresh_update_mode(&mut self) -> UpdateModeId {
+        let id = UpdateModeId {
+            id: self.next_update_mode_id,
+        };
+        self.next_update_mode_id += 1;
+        id
+    }
+
+    fn update_mode(&mut self, cursor: &mut Cursor<'t>) -> ParseResult<UpdateModeId> {
+        cursor.expect("(")?;
+        cursor.expect_keyword("id")?;
+        let id: u32 = cursor.number()?;
+        cursor.expect(")")?;
+
+        if let Some(update_mode) = self.update_modes.get(&id) {
+            return Ok(*update_mode);
+        }
+
+        let update_mode = self.fresh_update_mode();
+        self.update_modes.insert(id, update_mode);
+
+        Ok(update_mode)
+    }
+}
+
+fn literal<'a>(word: &str) -> Option<Literal<'a>> {
+    let literal = match word {
+        "true" => Literal::Bool(true),
+        "false" => Literal::Bool(false),
+        _ => {
+            if let Some(n) = word.strip_suffix("i64") {
+                Literal::Int(n.parse::<i128>().ok()?.to_ne_bytes())
+            } else if let Some(n) = word.strip_suffix("u128") {
+                Literal::U128(n.parse::<u128>().ok()?.to_ne_bytes())
+            } else if let Some(n) = word.strip_suffix("u8") {
+                Literal::Byte(n.parse().ok()?)
+            } else if let Some(n) = word.strip_suffix("f64") {
+                Literal::Float(n.parse().ok()?)
+            } else if let Some(n) = word.strip_suffix("dec") {
+                Literal::Decimal(RocDec::from_str(n)?.to_ne_bytes())
+            } else {
+                return None;
+            }
+        }
+    };
+
+    Some(literal)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_procs, procs_to_text};
+    use crate::layout::STLayoutInterner;
+    use bumpalo::Bump;
+    use roc_intern::SingleThreadedInterner;
+    use roc_module::symbol::Interns;
+
+    const TEXT: &str = r#"procedure Test.1 (Test.2 : [<rnu><null>, C I64 *self], Test.3 : List Str, Test.14 : [<np>C Boxed(I64), <null>]) -> I64:
+    joinpoint Test.4 (Test.5 : I64, borrowed Test.6 : {I64, Str}):
+        let Test.7 : I64 = StructAtIndex 0 Test.6;
+        let Test.8 : I64 = lowlevel NumAdd Test.5 Test.7;
+        ret Test.8;
+    in
+    let Test.9 : Int1 = lowlevel ListIsUnique Test.3;
+    switch Test.9 : Int1 -> I64:
+        case 1:
+            let Test.12 : I64 = 1i64;
+            let Test.10 : Str = "a \"b\"\n";
+            let Test.11 : {I64, Str} = Struct {Test.12, Test.10};
+            inc 2 Test.10;
+            jump Test.4 Test.12 Test.11;
+        default:
+            let Test.13 : U8 = GetTagId Test.2;
+            crash user Test.13;
+
+procedure Test.5 (Test.15 : Str, Test.16 : Str) -> Str:
+    let Test.17 : Str = lowlevel StrConcat Test.15 Test.16;
+    ret Test.17;
+
+procedure Test.0 (Test.18 : List Str, Test.19 : Str) -> List Str:
+    let Test.20 : List Float64 = Array [1.5f64, 2f64];
+    let Test.21 : List Str = higher-order ListMap {xs: Test.18} Test.5 (Str, Str) -> Str env Test.19 : Str owned borrowing Test.18 Test.5 Test.19;
+    ret Test.21;
+"#;
+
+    #[test]
+    fn parse_and_print_handwritten_ir() {
+        let arena = Bump::new();
+        let mut interns = Interns::default();
+        let interner: STLayoutInterner = SingleThreadedInterner::with_capacity(4);
+
+        match parse_procs(&arena, &mut interns, TEXT) {
+            Ok(procs) => assert_eq!(procs_to_text(&interns, &interner, &procs), TEXT),
+            Err(error) => panic!("{}", error),
+        }
+    }
+}
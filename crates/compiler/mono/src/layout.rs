@@ -10,7 +10,7 @@ use roc_module::ident::{Lowercase, TagName};
 use roc_module::symbol::{Interns, Symbol};
 use roc_problem::can::RuntimeError;
 use roc_target::{PtrWidth, TargetInfo};
-use roc_types::num::NumericRange;
+use roc_types::num::{FloatWidth as FracWidth, NumericRange};
 use roc_types::subs::{
     self, Content, FlatType, GetSubsSlice, Label, OptVariable, RecordFields, Subs,
     UnsortedUnionLabels, Variable,
@@ -100,6 +100,10 @@ macro_rules! inc_stat {
 pub type LayoutInterner<'a> = ThreadLocalInterner<'a, Layout<'a>>;
 pub type STLayoutInterner<'a> = SingleThreadedInterner<'a, Layout<'a>>;
 
+/// A layout in the [LayoutInterner]. Equal layouts are interned only once, so two interned
+/// layouts are equal exactly when their indices are.
+pub type InLayout<'a> = Interned<Layout<'a>>;
+
 /// Layout cache to avoid recomputing [Layout] from a [Variable] multiple times.
 #[derive(Debug)]
 pub struct LayoutCache<'a> {
@@ -109,6 +113,10 @@ pub struct LayoutCache<'a> {
 
     pub interner: LayoutInterner<'a>,
 
+    /// What fractional numbers compile to when nothing determines their type, like the `1.5` in
+    /// `Num.toStr 1.5`.
+    pub frac_default: FracWidth,
+
     /// Statistics on the usage of the layout cache.
     #[cfg(debug_assertions)]
     stats: CacheStatistics,
@@ -129,6 +137,8 @@ impl<'a> LayoutCache<'a> {
 
             interner,
 
+            frac_default: FracWidth::F64,
+
             #[cfg(debug_assertions)]
             stats: CacheStatistics::default(),
             #[cfg(debug_assertions)]
@@ -459,6 +469,17 @@ impl<'a> RawFunctionLayout<'a> {
         matches!(self, RawFunctionLayout::ZeroArgumentThunk(_))
     }
 
+    /// The layout of a module thunk whose value has this layout. A thunk takes no arguments, so
+    /// if its value is a function, the thunk returns the function's closure.
+    pub fn as_module_thunk(self) -> Self {
+        match self {
+            RawFunctionLayout::Function(_, lambda_set, _) => {
+                RawFunctionLayout::ZeroArgumentThunk(Layout::LambdaSet(lambda_set))
+            }
+            RawFunctionLayout::ZeroArgumentThunk(_) => self,
+        }
+    }
+
     fn new_help<'b>(
         env: &mut Env<'a, 'b>,
         var: Variable,
@@ -654,6 +675,10 @@ impl FieldOrderHash {
 }
 
 /// Types for code gen must be monomorphic. No type variables allowed!
+///
+/// Layouts computed by the [LayoutCache] are hash-consed in its interner, so equal layouts share
+/// their fields and tags. To check whether two interned layouts are equal, compare their
+/// [InLayout]s rather than the layouts.
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
 pub enum Layout<'a> {
     Builtin(Builtin<'a>),
@@ -718,6 +743,20 @@ pub enum UnionLayout<'a> {
         nullable_id: bool,
         other_fields: &'a [Layout<'a>],
     },
+    /// A non-recursive tag union with only two variants, where one is empty, and the other has
+    /// just one field, which is a box of sized content, and so a pointer that is never NULL.
+    /// Optimization: The union is represented as that pointer, and the empty variant as NULL, so
+    /// it needs no tag ID and takes no more memory than the pointer itself.
+    /// e.g. `Maybe a : [Nothing, Just (Box a)]`, but not `[Nothing, Just Str]`, because an empty
+    /// string or list does use NULL.
+    ///
+    /// As with the NullableUnwrapped variant, nullable_id is the index of the empty tag. Unlike
+    /// the recursive variants, the union does not have an allocation of its own: the pointer
+    /// belongs to the payload.
+    NullablePointer {
+        nullable_id: bool,
+        pointer: &'a Layout<'a>,
+    },
 }
 
 impl<'a> UnionLayout<'a> {
@@ -805,6 +844,21 @@ impl<'a> UnionLayout<'a> {
                     .append(tags_doc)
                     .append(alloc.text("]"))
             }
+            NullablePointer {
+                nullable_id,
+                pointer,
+            } => {
+                let pointer_doc =
+                    alloc
+                        .text("C ")
+                        .append(pointer.to_doc(alloc, interner, Parens::InTypeParam));
+                let tags_doc = if nullable_id {
+                    alloc.concat(vec![pointer_doc, alloc.text(", <null>")])
+                } else {
+                    alloc.concat(vec![alloc.text("<null>, "), pointer_doc])
+                };
+                alloc.text("[<np>").append(tags_doc).append(alloc.text("]"))
+            }
             _ => alloc.text("TODO"),
         }
     }
@@ -847,6 +901,17 @@ impl<'a> UnionLayout<'a> {
 
                 other_fields[index as usize]
             }
+
+            UnionLayout::NullablePointer {
+                nullable_id,
+                pointer,
+            } => {
+                debug_assert_ne!(nullable_id, tag_id != 0);
+                debug_assert_eq!(index, 0);
+
+                // this cannot be recursive; return immediately
+                return *pointer;
+            }
         };
 
         if let Layout::RecursivePointer = result {
@@ -856,13 +921,27 @@ impl<'a> UnionLayout<'a> {
         }
     }
 
+    /// The tags of a [UnionLayout::NullablePointer], in the order of their tag ids
+    pub fn nullable_pointer_tags(
+        nullable_id: bool,
+        pointer: &'a Layout<'a>,
+    ) -> [&'a [Layout<'a>]; 2] {
+        let pointer_tag = std::slice::from_ref(pointer);
+
+        if nullable_id {
+            [pointer_tag, &[]]
+        } else {
+            [&[], pointer_tag]
+        }
+    }
+
     pub fn number_of_tags(&'a self) -> usize {
         match self {
             UnionLayout::NonRecursive(tags) | UnionLayout::Recursive(tags) => tags.len(),
 
             UnionLayout::NullableWrapped { other_tags, .. } => other_tags.len() + 1,
             UnionLayout::NonNullableUnwrapped(_) => 1,
-            UnionLayout::NullableUnwrapped { .. } => 2,
+            UnionLayout::NullableUnwrapped { .. } | UnionLayout::NullablePointer { .. } => 2,
         }
     }
 
@@ -876,6 +955,7 @@ impl<'a> UnionLayout<'a> {
             }
             UnionLayout::NonNullableUnwrapped(_) => Discriminant::from_number_of_tags(2),
             UnionLayout::NullableUnwrapped { .. } => Discriminant::from_number_of_tags(1),
+            UnionLayout::NullablePointer { .. } => Discriminant::from_number_of_tags(2),
         }
     }
 
@@ -905,7 +985,9 @@ impl<'a> UnionLayout<'a> {
             | UnionLayout::NullableWrapped {
                 other_tags: tags, ..
             } => !Self::stores_tag_id_in_pointer_bits(tags, target_info),
-            UnionLayout::NonNullableUnwrapped(_) | UnionLayout::NullableUnwrapped { .. } => false,
+            UnionLayout::NonNullableUnwrapped(_)
+            | UnionLayout::NullableUnwrapped { .. }
+            | UnionLayout::NullablePointer { .. } => false,
         }
     }
 
@@ -916,7 +998,9 @@ impl<'a> UnionLayout<'a> {
             | UnionLayout::NullableWrapped {
                 other_tags: tags, ..
             } => Self::stores_tag_id_in_pointer_bits(tags, target_info),
-            UnionLayout::NonNullableUnwrapped(_) | UnionLayout::NullableUnwrapped { .. } => false,
+            UnionLayout::NonNullableUnwrapped(_)
+            | UnionLayout::NullableUnwrapped { .. }
+            | UnionLayout::NullablePointer { .. } => false,
         }
     }
 
@@ -926,7 +1010,8 @@ impl<'a> UnionLayout<'a> {
             | UnionLayout::NonNullableUnwrapped(_)
             | UnionLayout::Recursive(_) => false,
             UnionLayout::NullableWrapped { nullable_id, .. } => *nullable_id == tag_id,
-            UnionLayout::NullableUnwrapped { nullable_id, .. } => *nullable_id == (tag_id != 0),
+            UnionLayout::NullableUnwrapped { nullable_id, .. }
+            | UnionLayout::NullablePointer { nullable_id, .. } => *nullable_id == (tag_id != 0),
         }
     }
 
@@ -935,7 +1020,9 @@ impl<'a> UnionLayout<'a> {
             UnionLayout::NonRecursive(_)
             | UnionLayout::Recursive(_)
             | UnionLayout::NonNullableUnwrapped { .. } => false,
-            UnionLayout::NullableWrapped { .. } | UnionLayout::NullableUnwrapped { .. } => true,
+            UnionLayout::NullableWrapped { .. }
+            | UnionLayout::NullableUnwrapped { .. }
+            | UnionLayout::NullablePointer { .. } => true,
         }
     }
 
@@ -973,6 +1060,10 @@ impl<'a> UnionLayout<'a> {
             UnionLayout::NullableUnwrapped { other_fields, .. } => {
                 Layout::struct_no_name_order(other_fields).alignment_bytes(interner, target_info)
             }
+            UnionLayout::NullablePointer { pointer, .. } => {
+                // the union is never allocated, but the payload of the pointer is
+                return pointer.allocation_alignment_bytes(interner, target_info);
+            }
         };
 
         // because we store a refcount, the alignment must be at least the size of a pointer
@@ -1051,6 +1142,9 @@ impl<'a> UnionLayout<'a> {
             Self::NullableUnwrapped { other_fields, .. } => {
                 Layout::stack_size_and_alignment_slices(interner, &[other_fields], target_info)
             }
+            Self::NullablePointer { pointer, .. } => {
+                pointer.stack_size_and_alignment(interner, target_info)
+            }
         }
     }
 
@@ -1064,7 +1158,9 @@ impl<'a> UnionLayout<'a> {
             | UnionLayout::NullableWrapped {
                 other_tags: tags, ..
             } => Some(Self::tag_id_offset_help(interner, tags, target_info)),
-            UnionLayout::NonNullableUnwrapped(_) | UnionLayout::NullableUnwrapped { .. } => None,
+            UnionLayout::NonNullableUnwrapped(_)
+            | UnionLayout::NullableUnwrapped { .. }
+            | UnionLayout::NullablePointer { .. } => None,
         }
     }
 
@@ -1095,7 +1191,8 @@ impl<'a> UnionLayout<'a> {
             UnionLayout::Recursive(_)
             | UnionLayout::NonNullableUnwrapped(_)
             | UnionLayout::NullableWrapped { .. }
-            | UnionLayout::NullableUnwrapped { .. } => target_info.ptr_width() as u32,
+            | UnionLayout::NullableUnwrapped { .. }
+            | UnionLayout::NullablePointer { .. } => target_info.ptr_width() as u32,
         }
     }
 }
@@ -1203,7 +1300,7 @@ impl std::fmt::Debug for LambdaSet<'_> {
 /// between such differences when constructing closure capture data.
 ///
 /// See also https://github.com/roc-lang/roc/issues/3336.
-#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug)]
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, PartialOrd, Ord)]
 pub struct CapturesNiche<'a>(pub(crate) &'a [Layout<'a>]);
 
 impl CapturesNiche<'_> {
@@ -1242,6 +1339,14 @@ impl<'a> LambdaName<'a> {
         }
     }
 
+    #[inline(always)]
+    pub(crate) fn with_captures_niche(name: Symbol, captures_niche: CapturesNiche<'a>) -> Self {
+        Self {
+            name,
+            captures_niche,
+        }
+    }
+
     #[inline(always)]
     pub fn replace_name(&self, name: Symbol) -> Self {
         Self {
@@ -1256,7 +1361,7 @@ pub struct LambdaSet<'a> {
     /// collection of function names and their closure arguments
     set: &'a [(Symbol, &'a [Layout<'a>])],
     /// how the closure will be represented at runtime
-    representation: Interned<Layout<'a>>,
+    representation: InLayout<'a>,
 }
 
 #[derive(Debug)]
@@ -1351,6 +1456,24 @@ impl<'a> LambdaSet<'a> {
         self.set.len()
     }
 
+    /// Whether this lambda set is a single function that captures nothing. That function is
+    /// always called directly, so a value of the lambda set carries no information.
+    pub fn is_singleton_without_captures(&self) -> bool {
+        matches!(self.set, [(_, captures)] if captures.is_empty())
+    }
+
+    /// For tests that build IR by hand
+    #[cfg(test)]
+    pub(crate) fn from_parts(
+        set: &'a [(Symbol, &'a [Layout<'a>])],
+        representation: InLayout<'a>,
+    ) -> Self {
+        Self {
+            set,
+            representation,
+        }
+    }
+
     #[inline(always)]
     pub fn is_empty(&self) -> bool {
         self.set.is_empty()
@@ -1535,6 +1658,9 @@ impl<'a> LambdaSet<'a> {
                         nullable_id: _,
                         other_tags: _,
                     } => todo!("recursive closures"),
+                    UnionLayout::NullablePointer { .. } => {
+                        internal_error!("lambda sets are never represented as a nullable pointer")
+                    }
                 }
             }
             Layout::Struct { .. } => {
@@ -1769,7 +1895,7 @@ impl<'a> LambdaSet<'a> {
                     set_with_variables,
                     opt_recursion_var.into_variable(),
                 );
-                let representation = env.cache.interner.insert(env.arena.alloc(representation));
+                let representation = env.intern(representation);
 
                 Cacheable(
                     Ok(LambdaSet {
@@ -1784,7 +1910,7 @@ impl<'a> LambdaSet<'a> {
                 // See also https://github.com/roc-lang/roc/issues/3163.
                 cacheable(Ok(LambdaSet {
                     set: &[],
-                    representation: env.cache.interner.insert(env.arena.alloc(Layout::UNIT)),
+                    representation: env.intern(Layout::UNIT),
                 }))
             }
         }
@@ -2141,6 +2267,15 @@ impl<'a, 'b> Env<'a, 'b> {
             return Cacheable(Ok(Layout::RecursivePointer), NAKED_RECURSION_PTR);
         }
 
+        // Hand out the interned copy of the layout, so that equal layouts share their fields
+        let compute_layout = |env: &mut Env<'a, 'b>| {
+            compute_layout(env).then(|layout| {
+                let in_layout = env.intern(layout);
+
+                *env.cache.interner.get(in_layout)
+            })
+        };
+
         cached_or_impl!(self, var, compute_layout, get, insert, stats)
     }
 
@@ -2159,6 +2294,10 @@ impl<'a, 'b> Env<'a, 'b> {
             raw_function_stats
         )
     }
+
+    fn intern(&mut self, layout: Layout<'a>) -> InLayout<'a> {
+        self.cache.interner.insert(self.arena.alloc(layout))
+    }
 }
 
 pub const fn round_up_to_alignment(width: u32, alignment: u32) -> u32 {
@@ -2263,8 +2402,8 @@ impl<'a> Layout<'a> {
                         if is_unresolved_var(env.subs, actual_var)
                             || is_any_float_range(env.subs, actual_var) =>
                     {
-                        // default to f64
-                        return cacheable(Ok(Layout::f64()));
+                        // default to f64, unless configured otherwise
+                        return cacheable(Ok(Layout::default_frac(env.cache.frac_default)));
                     }
 
                     _ => Self::from_var(env, actual_var),
@@ -2324,7 +2463,8 @@ impl<'a> Layout<'a> {
                     Recursive(_)
                     | NullableWrapped { .. }
                     | NullableUnwrapped { .. }
-                    | NonNullableUnwrapped(_) => {
+                    | NonNullableUnwrapped(_)
+                    | NullablePointer { .. } => {
                         // a recursive union will always contain a pointer, and is thus not safe to memcpy
                         false
                     }
@@ -2372,6 +2512,7 @@ impl<'a> Layout<'a> {
                 | UnionLayout::NullableUnwrapped {
                     other_fields: tags, ..
                 } => tags.iter().all(Self::is_zero_sized),
+                UnionLayout::NullablePointer { .. } => false,
             },
             // Recursive pointers are considered zero-sized because
             // if you have a recursive data structure where everything
@@ -2382,6 +2523,16 @@ impl<'a> Layout<'a> {
         }
     }
 
+    /// Whether values of this layout are a pointer that is never NULL at runtime, so that NULL
+    /// can represent another value. Recursive unions are never NULL either, but glue describes a
+    /// nullable pointer as the allocation of what it points to, and theirs may be tagged.
+    fn is_non_nullable_pointer(&self) -> bool {
+        match self {
+            Layout::Boxed(content) => !content.is_zero_sized(),
+            _ => false,
+        }
+    }
+
     pub fn is_passed_by_reference<I>(&self, interner: &I, target_info: TargetInfo) -> bool
     where
         I: Interner<'a, Layout<'a>>,
@@ -2497,7 +2648,8 @@ impl<'a> Layout<'a> {
                     Recursive(_)
                     | NullableWrapped { .. }
                     | NullableUnwrapped { .. }
-                    | NonNullableUnwrapped(_) => target_info.ptr_width() as u32,
+                    | NonNullableUnwrapped(_)
+                    | NullablePointer { .. } => target_info.ptr_width() as u32,
                 }
             }
             Layout::LambdaSet(lambda_set) => lambda_set
@@ -2562,7 +2714,7 @@ impl<'a> Layout<'a> {
         use Layout::*;
 
         match self {
-            Union(UnionLayout::NonRecursive(_)) => false,
+            Union(UnionLayout::NonRecursive(_) | UnionLayout::NullablePointer { .. }) => false,
 
             Union(_) => true,
 
@@ -2599,7 +2751,8 @@ impl<'a> Layout<'a> {
                     Recursive(_)
                     | NullableWrapped { .. }
                     | NullableUnwrapped { .. }
-                    | NonNullableUnwrapped(_) => true,
+                    | NonNullableUnwrapped(_)
+                    | NullablePointer { .. } => true,
                 }
             }
             LambdaSet(lambda_set) => lambda_set
@@ -2750,8 +2903,12 @@ impl<'a> Layout<'a> {
         Layout::i64()
     }
 
-    pub fn default_float() -> Layout<'a> {
-        Layout::f64()
+    pub fn default_frac(frac_default: FracWidth) -> Layout<'a> {
+        match frac_default {
+            FracWidth::Dec => Layout::Builtin(Builtin::Decimal),
+            FracWidth::F32 => Layout::f32(),
+            FracWidth::F64 => Layout::f64(),
+        }
     }
 
     pub fn int_literal_width_to_int(
@@ -3042,7 +3199,7 @@ fn layout_from_flat_type<'a>(
                     let var = args[0];
                     let content = subs.get_content_without_compacting(var);
 
-                    layout_from_num_content(content, target_info)
+                    layout_from_num_content(content, target_info, env.cache.frac_default)
                 }
 
                 Symbol::STR_STR => cacheable(Ok(Layout::Builtin(Builtin::Str))),
@@ -3929,7 +4086,15 @@ where
                     let mut tag_layouts = Vec::with_capacity_in(tags.len(), env.arena);
                     tag_layouts.extend(tags.iter().map(|r| r.1));
 
-                    Layout::Union(UnionLayout::NonRecursive(tag_layouts.into_bump_slice()))
+                    // closures keep their tag ids, which the lambda set relies on
+                    let is_tag_union = matches!(tags[0].0, TagOrClosure::Tag(_));
+
+                    let union_layout = match nullable_pointer_layout(&tag_layouts) {
+                        Some(union_layout) if is_tag_union => union_layout,
+                        _ => UnionLayout::NonRecursive(tag_layouts.into_bump_slice()),
+                    };
+
+                    Layout::Union(union_layout)
                 }
 
                 Recursive { .. }
@@ -3945,6 +4110,23 @@ where
     Cacheable(result, criteria)
 }
 
+/// The [UnionLayout::NullablePointer] of a non-recursive union with these tags, if it has one.
+///
+/// Lists and strings don't qualify, even though they hold a pointer: the elements of an empty
+/// list (and the bytes of an empty or small string) are NULL, so `[None, Some (List a)]` keeps
+/// its tag id.
+fn nullable_pointer_layout<'a>(tag_layouts: &[&'a [Layout<'a>]]) -> Option<UnionLayout<'a>> {
+    match tag_layouts {
+        [[], [pointer]] | [[pointer], []] if pointer.is_non_nullable_pointer() => {
+            Some(UnionLayout::NullablePointer {
+                nullable_id: !tag_layouts[0].is_empty(),
+                pointer,
+            })
+        }
+        _ => None,
+    }
+}
+
 fn layout_from_recursive_union<'a, L>(
     env: &mut Env<'a, '_>,
     rec_var: Variable,
@@ -4088,6 +4270,7 @@ pub fn ext_var_is_empty_tag_union(_: &Subs, _: Variable) -> bool {
 fn layout_from_num_content<'a>(
     content: &Content,
     target_info: TargetInfo,
+    frac_default: FracWidth,
 ) -> Cacheable<LayoutResult<'a>> {
     use roc_types::subs::Content::*;
     use roc_types::subs::FlatType::*;
@@ -4120,7 +4303,7 @@ fn layout_from_num_content<'a>(
             Symbol::NUM_U8 => Ok(Layout::u8()),
 
             // Floats
-            Symbol::NUM_FLOATINGPOINT => Ok(Layout::f64()),
+            Symbol::NUM_FLOATINGPOINT => Ok(Layout::default_frac(frac_default)),
             Symbol::NUM_F64 => Ok(Layout::f64()),
             Symbol::NUM_F32 => Ok(Layout::f32()),
 
@@ -4192,6 +4375,7 @@ impl LayoutId {
     }
 }
 
+#[derive(Clone)]
 struct IdsByLayout<'a> {
     by_id: MutMap<Layout<'a>, u32>,
     toplevels_by_id: MutMap<crate::ir::ProcLayout<'a>, u32>,
@@ -4256,7 +4440,7 @@ impl<'a> IdsByLayout<'a> {
     }
 }
 
-#[derive(Default)]
+#[derive(Default, Clone)]
 pub struct LayoutIds<'a> {
     by_symbol: MutMap<Symbol, IdsByLayout<'a>>,
 }
@@ -4344,6 +4528,27 @@ mod test {
         assert_eq!(layout.alignment_bytes(&interner, target_info), 1);
     }
 
+    #[test]
+    fn module_thunk_of_function_returns_closure() {
+        let mut interner = SingleThreadedInterner::with_capacity(4);
+
+        let lambda_set = LambdaSet {
+            set: &[(Symbol::LIST_MAP, &[])],
+            representation: interner.insert(&Layout::UNIT),
+        };
+
+        let function = RawFunctionLayout::Function(&[Layout::UNIT], lambda_set, &Layout::UNIT);
+
+        assert_eq!(
+            function.as_module_thunk(),
+            RawFunctionLayout::ZeroArgumentThunk(Layout::LambdaSet(lambda_set))
+        );
+
+        let thunk = RawFunctionLayout::ZeroArgumentThunk(Layout::UNIT);
+
+        assert_eq!(thunk.as_module_thunk(), thunk);
+    }
+
     #[test]
     fn memcpy_size_result_u32_unit() {
         let interner = SingleThreadedInterner::with_capacity(4);
@@ -4367,4 +4572,85 @@ mod test {
         let target_info = TargetInfo::default_x86_64();
         assert_eq!(Layout::VOID.stack_size(&interner, target_info), 0);
     }
+
+    #[test]
+    fn equal_layouts_in_different_slices() {
+        let u32_layout = Layout::Builtin(Builtin::Int(IntWidth::U32));
+        let fields_a = [u32_layout, Layout::UNIT];
+        let fields_b = [u32_layout, Layout::UNIT];
+        let tags_a = [&fields_a as &[_]];
+        let tags_b = [&fields_b as &[_]];
+
+        assert_eq!(
+            Layout::Union(UnionLayout::NonRecursive(&tags_a)),
+            Layout::Union(UnionLayout::NonRecursive(&tags_b)),
+        );
+        assert_ne!(
+            Layout::Union(UnionLayout::NonRecursive(&tags_a)),
+            Layout::Union(UnionLayout::Recursive(&tags_b)),
+        );
+        assert_ne!(Layout::Boxed(&fields_a[0]), Layout::Boxed(&fields_b[1]),);
+    }
+
+    #[test]
+    fn equal_layouts_are_interned_once() {
+        let arena = Bump::new();
+        let subs = Subs::new();
+        let interner = roc_intern::GlobalInterner::with_capacity(4);
+        let mut cache = LayoutCache::new(interner.fork(), TargetInfo::default_x86_64());
+        let mut env = Env {
+            target_info: TargetInfo::default_x86_64(),
+            arena: &arena,
+            seen: Vec::new_in(&arena),
+            subs: &subs,
+            cache: &mut cache,
+        };
+
+        let u32_layout = Layout::Builtin(Builtin::Int(IntWidth::U32));
+        let fields_a = arena.alloc_slice_copy(&[u32_layout, Layout::UNIT]);
+        let fields_b = arena.alloc_slice_copy(&[u32_layout, Layout::UNIT]);
+
+        let a = env.intern(Layout::struct_no_name_order(fields_a));
+        let b = env.intern(Layout::struct_no_name_order(fields_b));
+        let c = env.intern(Layout::struct_no_name_order(&fields_a[..1]));
+
+        assert_eq!(a, b);
+        assert_ne!(a, c);
+    }
+
+    #[test]
+    fn option_of_box_is_a_nullable_pointer() {
+        let u64_layout = Layout::Builtin(Builtin::Int(IntWidth::U64));
+        let boxed = [Layout::Boxed(&u64_layout)];
+
+        assert_eq!(
+            nullable_pointer_layout(&[&[], &boxed]),
+            Some(UnionLayout::NullablePointer {
+                nullable_id: false,
+                pointer: &boxed[0],
+            })
+        );
+        assert_eq!(
+            nullable_pointer_layout(&[&boxed, &[]]),
+            Some(UnionLayout::NullablePointer {
+                nullable_id: true,
+                pointer: &boxed[0],
+            })
+        );
+    }
+
+    #[test]
+    fn option_of_list_or_str_keeps_its_tag_id() {
+        let u64_layout = Layout::Builtin(Builtin::Int(IntWidth::U64));
+        let list = [Layout::Builtin(Builtin::List(&u64_layout))];
+        let string = [Layout::Builtin(Builtin::Str)];
+        let boxed_unit = [Layout::Boxed(&Layout::UNIT)];
+        let boxed = [Layout::Boxed(&u64_layout)];
+
+        assert_eq!(nullable_pointer_layout(&[&[], &list]), None);
+        assert_eq!(nullable_pointer_layout(&[&string, &[]]), None);
+        assert_eq!(nullable_pointer_layout(&[&[], &boxed_unit]), None);
+        assert_eq!(nullable_pointer_layout(&[&[], &boxed, &[]]), None);
+        assert_eq!(nullable_pointer_layout(&[&boxed, &boxed]), None);
+    }
 }
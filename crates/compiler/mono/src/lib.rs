@@ -8,12 +8,18 @@
 #![allow(clippy::large_enum_variant, clippy::upper_case_acronyms)]
 
 pub mod borrow;
+pub mod closure_erasure;
 pub mod code_gen_help;
+pub mod constant_folding;
+pub mod dead_code;
 pub mod inc_dec;
+pub mod inline;
 pub mod ir;
 pub mod layout;
 pub mod layout_soa;
+pub mod list_fusion;
 pub mod low_level;
+pub mod refcount_elision;
 pub mod reset_reuse;
 pub mod tail_recursion;
 
@@ -0,0 +1,1234 @@
+//! Fuse chains of `List.map` into a single `List.map`, so that the intermediate lists are never
+//! allocated.
+//!
+//! ```text
+//! let ys = higher-order ListMap {xs: xs} f (a, cf) -> b env ef : cf xs f ef;
+//! let zs = higher-order ListMap {xs: ys} g (b, cg) -> c env eg : cg ys g eg;
+//! ```
+//!
+//! becomes, when `ys` is used nowhere else,
+//!
+//! ```text
+//! let e = Struct {ef, eg};
+//! let zs = higher-order ListMap {xs: xs} h (a, {cf, cg}) -> c env e : {cf, cg} xs h e;
+//!
+//! procedure h (x : a, e : {cf, cg}) -> c:
+//!     let ef = StructAtIndex 0 e;
+//!     let y = CallByName f x ef;
+//!     let eg = StructAtIndex 1 e;
+//!     let z = CallByName g y eg;
+//!     ret z;
+//! ```
+//!
+//! User code calls `List.map` through its specialization, a procedure whose body is just the
+//! `ListMap`, so calls to those are fused like the `ListMap` they wrap.
+//!
+//! `List.walk` and `List.keepIf` are defined in Roc rather than as low-level operations, so a map
+//! whose result is only walked or filtered is fused with them by replacing both with a loop over
+//! the original list, which applies the map's function to each element as it goes:
+//!
+//! ```text
+//! let ys = CallByName List.5 xs cf;
+//! let s = CallByName List.18 ys s0 cg;
+//! ```
+//!
+//! becomes
+//!
+//! ```text
+//! let len = lowlevel ListLen xs;
+//! let start = 0;
+//! joinpoint after (s):
+//!     ...
+//! in
+//! joinpoint loop (i, acc):
+//!     let more = lowlevel NumLt i len;
+//!     if more then
+//!         let x = lowlevel ListGetUnsafe xs i;
+//!         let y = CallByName f x cf;
+//!         let next = lowlevel NumAdd i 1;
+//!         let acc2 = CallByName g acc y cg;
+//!         jump loop next acc2;
+//!     else
+//!         jump after acc;
+//! in
+//! jump loop start s0;
+//! ```
+//!
+//! The functions passed to `List.walk` and `List.keepIf` are only called directly when their lambda
+//! set has a single member; otherwise their calls are left alone. Nor is a map over a list of
+//! refcounted elements fused into a loop: the loop reads each element with `ListGetUnsafe`, which
+//! increments it, where the map would hand a unique list's elements to its function as they are.
+//!
+//! Only maps in the same block are fused, so that a map is never moved into a loop. Fusion runs
+//! before inlining, which can then inline the functions into the fused procedure or loop.
+
+use crate::inc_dec::occurring_variables_expr;
+use crate::inline::max_call_spec_id;
+use crate::ir::{
+    Call, CallSpecId, CallType, Expr, HigherOrderLowLevel, HostExposedLayouts, JoinPointId,
+    Literal, OptLevel, Param, PassedFunction, Proc, ProcLayout, SelfRecursive, Stmt, UpdateModeIds,
+};
+use crate::layout::{Builtin, LambdaName, LambdaSet, Layout, STLayoutInterner};
+use crate::low_level::HigherOrder;
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+use roc_collections::all::{MutMap, MutSet};
+use roc_module::low_level::LowLevel;
+use roc_module::symbol::{IdentIds, ModuleId, Symbol};
+use roc_target::TargetInfo;
+
+#[allow(clippy::too_many_arguments)]
+pub fn fuse_list_operations<'a>(
+    arena: &'a Bump,
+    layout_interner: &STLayoutInterner<'a>,
+    home: ModuleId,
+    ident_ids: &mut IdentIds,
+    update_mode_ids: &mut UpdateModeIds,
+    target_info: TargetInfo,
+    opt_level: OptLevel,
+    procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+) {
+    if let OptLevel::Development = opt_level {
+        return;
+    }
+
+    let map_wrappers = map_wrappers(procs);
+    let mut fused_procs = std::vec::Vec::new();
+
+    for proc in procs.values_mut() {
+        let mut uses = MutMap::default();
+        count_uses(&proc.body, &mut uses);
+
+        let mut env = Env {
+            arena,
+            layout_interner,
+            home,
+            ident_ids: &mut *ident_ids,
+            update_mode_ids: &mut *update_mode_ids,
+            target_info,
+            map_wrappers: &map_wrappers,
+            ret_layout: proc.ret_layout,
+            uses,
+            next_call_spec_id: max_call_spec_id(&proc.body).next(),
+            removed: MutSet::default(),
+            replaced: MutMap::default(),
+            loops: MutMap::default(),
+            fused_procs: &mut fused_procs,
+        };
+
+        env.find_fusions(&proc.body, &mut MutMap::default());
+
+        if !env.replaced.is_empty() || !env.loops.is_empty() {
+            proc.body = env.fuse_stmt(&proc.body).clone();
+        }
+    }
+
+    for proc in fused_procs {
+        let key = (
+            proc.name.name(),
+            ProcLayout::new(
+                arena,
+                arena.alloc_slice_fill_iter(proc.args.iter().map(|(layout, _)| *layout)),
+                proc.name.captures_niche(),
+                proc.ret_layout,
+            ),
+        );
+
+        procs.insert(key, proc);
+    }
+}
+
+/// The specializations of `List.map` whose body is a single `ListMap`, with the function they
+/// pass to it and the layout of its environment
+fn map_wrappers<'a>(
+    procs: &MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+) -> MutMap<ProcLayout<'a>, (PassedFunction<'a>, Option<Layout<'a>>)> {
+    let mut wrappers = MutMap::default();
+
+    for ((name, proc_layout), proc) in procs.iter() {
+        if *name != Symbol::LIST_MAP {
+            continue;
+        }
+
+        if let Stmt::Let(
+            symbol,
+            Expr::Call(Call {
+                call_type: CallType::HigherOrder(higher_order),
+                ..
+            }),
+            _,
+            Stmt::Ret(result),
+        ) = &proc.body
+        {
+            let function = higher_order.passed_function;
+
+            match (higher_order.op, proc.args) {
+                (HigherOrder::ListMap { xs }, [(_, list), (_, closure)])
+                    if symbol == result
+                        && xs == *list
+                        && function.captured_environment == *closure =>
+                {
+                    wrappers.insert(*proc_layout, (function, higher_order.closure_env_layout));
+                }
+                _ => {}
+            }
+        }
+    }
+
+    wrappers
+}
+
+/// A `List.map` whose result can be fused into a later operation
+#[derive(Clone, Copy)]
+struct ListMap<'a> {
+    xs: Symbol,
+    function: PassedFunction<'a>,
+    env_layout: Option<Layout<'a>>,
+}
+
+/// A function passed to `List.walk` or `List.keepIf`, whose lambda set has a single member
+#[derive(Clone, Copy)]
+struct Closure<'a> {
+    name: LambdaName<'a>,
+    arg_layouts: &'a [Layout<'a>],
+    ret_layout: Layout<'a>,
+    /// The closure itself, when it captures something and so is passed to the function
+    captures: Option<Symbol>,
+}
+
+/// An operation that consumes the result of a map, and can be fused with it into a loop
+#[derive(Clone, Copy)]
+enum Consumer<'a> {
+    Walk { state: Symbol, step: Closure<'a> },
+    KeepIf { predicate: Closure<'a> },
+}
+
+struct Env<'a, 'r> {
+    arena: &'a Bump,
+    layout_interner: &'r STLayoutInterner<'a>,
+    home: ModuleId,
+    ident_ids: &'r mut IdentIds,
+    update_mode_ids: &'r mut UpdateModeIds,
+    target_info: TargetInfo,
+    map_wrappers: &'r MutMap<ProcLayout<'a>, (PassedFunction<'a>, Option<Layout<'a>>)>,
+    /// The return layout of the procedure being fused
+    ret_layout: Layout<'a>,
+    /// The number of statements and expressions that use a symbol
+    uses: MutMap<Symbol, usize>,
+    next_call_spec_id: CallSpecId,
+    /// The maps whose result is only used by the operation they are fused into
+    removed: MutSet<Symbol>,
+    /// The fused maps, with the struct of their captured environments if they need one
+    replaced: MutMap<Symbol, (Option<(Symbol, Expr<'a>, Layout<'a>)>, Call<'a>)>,
+    /// The walks and filters that become a loop, with the map fused into them
+    loops: MutMap<Symbol, (ListMap<'a>, Consumer<'a>)>,
+    fused_procs: &'r mut std::vec::Vec<Proc<'a>>,
+}
+
+impl<'a, 'r> Env<'a, 'r> {
+    fn find_fusions(&mut self, stmt: &Stmt<'a>, maps: &mut MutMap<Symbol, ListMap<'a>>) {
+        use Stmt::*;
+
+        match stmt {
+            Let(symbol, expr, _, cont) => {
+                if let Some(map) = self.list_map(expr) {
+                    let map = match maps.get(&map.xs) {
+                        Some(first) if self.uses.get(&map.xs) == Some(&1) => {
+                            let first = *first;
+                            self.removed.insert(map.xs);
+                            self.fuse(*symbol, first, map)
+                        }
+                        _ => map,
+                    };
+
+                    maps.insert(*symbol, map);
+                } else if let Some((ys, consumer)) = self.consumer(expr) {
+                    match maps.get(&ys) {
+                        Some(map) if self.uses.get(&ys) == Some(&1) && self.can_loop_over(map) => {
+                            self.removed.insert(ys);
+                            self.loops.insert(*symbol, (*map, consumer));
+                        }
+                        _ => {}
+                    }
+                }
+
+                self.find_fusions(cont, maps);
+            }
+            Switch {
+                branches,
+                default_branch,
+                ..
+            } => {
+                for (_, _, branch) in branches.iter() {
+                    self.find_fusions(branch, &mut MutMap::default());
+                }
+                self.find_fusions(default_branch.1, &mut MutMap::default());
+            }
+            Join {
+                body, remainder, ..
+            } => {
+                self.find_fusions(body, &mut MutMap::default());
+                self.find_fusions(remainder, maps);
+            }
+            Refcounting(_, cont) => self.find_fusions(cont, maps),
+            Expect { remainder, .. } | ExpectFx { remainder, .. } => {
+                self.find_fusions(remainder, maps)
+            }
+            Ret(_) | Jump(..) | Crash(..) => {}
+        }
+    }
+
+    /// The map that `expr` is, either a `ListMap` or a call to a specialization of `List.map`
+    fn list_map(&self, expr: &Expr<'a>) -> Option<ListMap<'a>> {
+        match expr {
+            Expr::Call(Call {
+                call_type: CallType::HigherOrder(higher_order),
+                ..
+            }) => match higher_order.op {
+                HigherOrder::ListMap { xs } => Some(ListMap {
+                    xs,
+                    function: higher_order.passed_function,
+                    env_layout: higher_order.closure_env_layout,
+                }),
+                _ => None,
+            },
+            Expr::Call(Call {
+                call_type:
+                    CallType::ByName {
+                        name,
+                        ret_layout,
+                        arg_layouts,
+                        ..
+                    },
+                arguments,
+            }) if name.name() == Symbol::LIST_MAP && arguments.len() == 2 => {
+                let proc_layout =
+                    ProcLayout::new(self.arena, arg_layouts, name.captures_niche(), **ret_layout);
+                let (function, env_layout) = self.map_wrappers.get(&proc_layout)?;
+
+                Some(ListMap {
+                    xs: arguments[0],
+                    function: PassedFunction {
+                        captured_environment: arguments[1],
+                        ..*function
+                    },
+                    env_layout: *env_layout,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// The list that `expr` walks or filters, if it does so with a function it can call directly
+    fn consumer(&self, expr: &Expr<'a>) -> Option<(Symbol, Consumer<'a>)> {
+        let (name, ret_layout, arg_layouts, arguments) = match expr {
+            Expr::Call(Call {
+                call_type:
+                    CallType::ByName {
+                        name,
+                        ret_layout,
+                        arg_layouts,
+                        ..
+                    },
+                arguments,
+            }) => (name.name(), **ret_layout, *arg_layouts, *arguments),
+            _ => return None,
+        };
+
+        let (element, arg_layouts) = match arg_layouts {
+            [Layout::Builtin(Builtin::List(element)), rest @ ..] => (**element, rest),
+            _ => return None,
+        };
+
+        match (name, arg_layouts, arguments) {
+            (
+                Symbol::LIST_WALK,
+                [state_layout, Layout::LambdaSet(lambda_set)],
+                [ys, state, step],
+            ) => {
+                let step =
+                    self.closure(*lambda_set, *step, &[*state_layout, element], ret_layout)?;
+
+                Some((
+                    *ys,
+                    Consumer::Walk {
+                        state: *state,
+                        step,
+                    },
+                ))
+            }
+            (Symbol::LIST_KEEP_IF, [Layout::LambdaSet(lambda_set)], [ys, predicate]) => {
+                let predicate =
+                    self.closure(*lambda_set, *predicate, &[element], Layout::bool())?;
+
+                Some((*ys, Consumer::KeepIf { predicate }))
+            }
+            _ => None,
+        }
+    }
+
+    fn closure(
+        &self,
+        lambda_set: LambdaSet<'a>,
+        symbol: Symbol,
+        arg_layouts: &[Layout<'a>],
+        ret_layout: Layout<'a>,
+    ) -> Option<Closure<'a>> {
+        if lambda_set.len() != 1 {
+            return None;
+        }
+
+        let name = lambda_set.iter_set().next()?;
+        let arg_layouts = lambda_set.extend_argument_list(
+            self.arena,
+            self.layout_interner,
+            self.arena.alloc_slice_copy(arg_layouts),
+        );
+        let captures = lambda_set
+            .is_represented(self.layout_interner)
+            .map(|_| symbol);
+
+        Some(Closure {
+            name,
+            arg_layouts,
+            ret_layout,
+            captures,
+        })
+    }
+
+    /// Whether the loop can read the elements that `map` passes to its function
+    fn can_loop_over(&self, map: &ListMap<'a>) -> bool {
+        !map.function.argument_layouts[0].contains_refcounted(self.layout_interner)
+    }
+
+    /// Fuse `second` into `first`, for the map bound to `symbol`
+    fn fuse(&mut self, symbol: Symbol, first: ListMap<'a>, second: ListMap<'a>) -> ListMap<'a> {
+        let arena = self.arena;
+
+        let name = self.fresh_symbol();
+
+        let envs = Vec::from_iter_in(
+            [first, second].iter().filter_map(|map| {
+                map.env_layout
+                    .map(|layout| (map.function.captured_environment, layout))
+            }),
+            arena,
+        );
+        let (captured_environment, env_layout, env_struct) = match envs.as_slice() {
+            [] => (first.function.captured_environment, None, None),
+            [(env, layout)] => (*env, Some(*layout), None),
+            _ => {
+                let env = self.fresh_symbol();
+                let fields = arena.alloc_slice_fill_iter(envs.iter().map(|(env, _)| *env));
+                let layouts = arena.alloc_slice_fill_iter(envs.iter().map(|(_, layout)| *layout));
+                let layout = Layout::struct_no_name_order(layouts);
+
+                (env, Some(layout), Some((env, Expr::Struct(fields), layout)))
+            }
+        };
+
+        // The function that applies `first` and then `second`
+        let x = self.fresh_symbol();
+        let env_param = env_layout.map(|layout| (self.fresh_symbol(), layout));
+
+        let mut lets = Vec::new_in(arena);
+        let mut proc_call_spec_id = CallSpecId::BACKEND_DUMMY;
+        let mut field_index = 0;
+
+        let mut result = x;
+        for map in [first, second] {
+            let env = match (map.env_layout, env_param, &env_struct) {
+                (Some(_), Some((env, _)), None) => Some(env),
+                (Some(layout), Some((env, _)), Some((_, Expr::Struct(_), env_layout))) => {
+                    let field = self.fresh_symbol();
+                    let field_layouts = match env_layout {
+                        Layout::Struct { field_layouts, .. } => *field_layouts,
+                        _ => unreachable!("the environments of two functions are in a struct"),
+                    };
+                    lets.push((
+                        field,
+                        Expr::StructAtIndex {
+                            index: field_index,
+                            field_layouts,
+                            structure: env,
+                        },
+                        layout,
+                    ));
+                    field_index += 1;
+
+                    Some(field)
+                }
+                _ => None,
+            };
+
+            proc_call_spec_id = proc_call_spec_id.next();
+
+            let arguments = match env {
+                Some(env) => arena.alloc_slice_copy(&[result, env]),
+                None => arena.alloc_slice_copy(&[result]),
+            };
+            let call = Call {
+                call_type: CallType::ByName {
+                    name: map.function.name,
+                    ret_layout: arena.alloc(map.function.return_layout),
+                    arg_layouts: map.function.argument_layouts,
+                    specialization_id: proc_call_spec_id,
+                },
+                arguments,
+            };
+
+            result = self.fresh_symbol();
+            lets.push((result, Expr::Call(call), map.function.return_layout));
+        }
+
+        let mut body = Stmt::Ret(result);
+        for (symbol, expr, layout) in lets.into_iter().rev() {
+            body = Stmt::Let(symbol, expr, layout, arena.alloc(body));
+        }
+
+        let element_layout = first.function.argument_layouts[0];
+        let args = match env_param {
+            Some((env, layout)) => arena.alloc_slice_copy(&[(element_layout, x), (layout, env)]),
+            None => arena.alloc_slice_copy(&[(element_layout, x)]),
+        };
+        let argument_layouts = arena.alloc_slice_fill_iter(args.iter().map(|(layout, _)| *layout));
+
+        self.fused_procs.push(Proc {
+            name: LambdaName::no_niche(name),
+            args,
+            body,
+            closure_data_layout: env_layout,
+            ret_layout: second.function.return_layout,
+            is_self_recursive: SelfRecursive::NotSelfRecursive,
+            must_own_arguments: false,
+            host_exposed_layouts: HostExposedLayouts::NotHostExposed,
+        });
+
+        let fused = ListMap {
+            xs: first.xs,
+            function: PassedFunction {
+                name: LambdaName::no_niche(name),
+                argument_layouts,
+                return_layout: second.function.return_layout,
+                specialization_id: self.fresh_call_spec_id(),
+                captured_environment,
+                owns_captured_environment: false,
+                borrows_arguments: false,
+            },
+            env_layout,
+        };
+
+        let higher_order = HigherOrderLowLevel {
+            op: HigherOrder::ListMap { xs: fused.xs },
+            closure_env_layout: env_layout,
+            update_mode: self.update_mode_ids.next_id(),
+            passed_function: fused.function,
+        };
+        let call = Call {
+            call_type: CallType::HigherOrder(arena.alloc(higher_order)),
+            arguments: arena.alloc_slice_copy(&[fused.xs, name, captured_environment]),
+        };
+
+        self.replaced.insert(symbol, (env_struct, call));
+
+        fused
+    }
+
+    fn fuse_stmt(&mut self, stmt: &Stmt<'a>) -> &'a Stmt<'a> {
+        use Stmt::*;
+
+        let arena = self.arena;
+
+        match stmt {
+            Let(symbol, expr, layout, cont) => {
+                let mut stmt = self.fuse_stmt(cont);
+
+                if let Some((map, consumer)) = self.loops.get(symbol).copied() {
+                    return self.fuse_loop(*symbol, *layout, map, consumer, stmt);
+                }
+
+                let (env_struct, expr) = match self.replaced.get(symbol) {
+                    Some((env_struct, call)) => (env_struct.clone(), Expr::Call(call.clone())),
+                    None => (None, expr.clone()),
+                };
+
+                if !self.removed.contains(symbol) {
+                    stmt = arena.alloc(Let(*symbol, expr, *layout, stmt));
+                }
+
+                // a map that was fused into a later one still builds the environment of the
+                // fused function
+                if let Some((env, expr, layout)) = env_struct {
+                    stmt = arena.alloc(Let(env, expr, layout, stmt));
+                }
+
+                stmt
+            }
+            Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout,
+            } => {
+                let branches = Vec::from_iter_in(
+                    branches.iter().map(|(label, info, branch)| {
+                        (*label, info.clone(), self.fuse_stmt(branch).clone())
+                    }),
+                    arena,
+                );
+
+                arena.alloc(Switch {
+                    cond_symbol: *cond_symbol,
+                    cond_layout: *cond_layout,
+                    branches: branches.into_bump_slice(),
+                    default_branch: (default_branch.0.clone(), self.fuse_stmt(default_branch.1)),
+                    ret_layout: *ret_layout,
+                })
+            }
+            Join {
+                id,
+                parameters,
+                body,
+                remainder,
+            } => arena.alloc(Join {
+                id: *id,
+                parameters: *parameters,
+                body: self.fuse_stmt(body),
+                remainder: self.fuse_stmt(remainder),
+            }),
+            Refcounting(modify, cont) => arena.alloc(Refcounting(*modify, self.fuse_stmt(cont))),
+            Expect {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => arena.alloc(Expect {
+                condition: *condition,
+                region: *region,
+                lookups: *lookups,
+                variables: *variables,
+                remainder: self.fuse_stmt(remainder),
+            }),
+            ExpectFx {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => arena.alloc(ExpectFx {
+                condition: *condition,
+                region: *region,
+                lookups: *lookups,
+                variables: *variables,
+                remainder: self.fuse_stmt(remainder),
+            }),
+            Ret(_) | Jump(..) | Crash(..) => arena.alloc(stmt.clone()),
+        }
+    }
+
+    /// The loop that replaces the walk or filter bound to `symbol`, and the map it consumes
+    fn fuse_loop(
+        &mut self,
+        symbol: Symbol,
+        layout: Layout<'a>,
+        map: ListMap<'a>,
+        consumer: Consumer<'a>,
+        cont: &'a Stmt<'a>,
+    ) -> &'a Stmt<'a> {
+        let arena = self.arena;
+        let nat = Layout::usize(self.target_info);
+
+        let len = self.fresh_symbol();
+        let start = self.fresh_symbol();
+        let index = self.fresh_symbol();
+        let acc = self.fresh_symbol();
+        let more = self.fresh_symbol();
+        let x = self.fresh_symbol();
+        let y = self.fresh_symbol();
+        let one = self.fresh_symbol();
+        let next = self.fresh_symbol();
+        let next_acc = self.fresh_symbol();
+        let loop_id = JoinPointId(self.fresh_symbol());
+        let after_id = JoinPointId(self.fresh_symbol());
+
+        // the step of the loop, once `y` is the mapped element and `next` the next index
+        let step = match consumer {
+            Consumer::Walk { step, .. } => {
+                let call = self.call_closure(step, &[acc, y]);
+                let jump = arena.alloc(Stmt::Jump(loop_id, arena.alloc([next, next_acc])));
+
+                Stmt::Let(next_acc, call, layout, jump)
+            }
+            Consumer::KeepIf { predicate } => {
+                let keep = self.fresh_symbol();
+                let call = self.call_closure(predicate, &[y]);
+                let append = self.lowlevel(LowLevel::ListAppendUnsafe, &[acc, y]);
+
+                let kept = Stmt::Let(
+                    next_acc,
+                    append,
+                    layout,
+                    arena.alloc(Stmt::Jump(loop_id, arena.alloc([next, next_acc]))),
+                );
+                let skipped = arena.alloc(Stmt::Jump(loop_id, arena.alloc([next, acc])));
+                let switch = Stmt::if_then_else(arena, keep, self.ret_layout, kept, skipped);
+
+                Stmt::Let(keep, call, Layout::bool(), arena.alloc(switch))
+            }
+        };
+
+        let get = self.lowlevel(LowLevel::ListGetUnsafe, &[map.xs, index]);
+        let map_call = {
+            let arguments = match map.env_layout {
+                Some(_) => arena.alloc_slice_copy(&[x, map.function.captured_environment]),
+                None => arena.alloc_slice_copy(&[x]),
+            };
+            let call_type = CallType::ByName {
+                name: map.function.name,
+                ret_layout: arena.alloc(map.function.return_layout),
+                arg_layouts: map.function.argument_layouts,
+                specialization_id: self.fresh_call_spec_id(),
+            };
+
+            Expr::Call(Call {
+                call_type,
+                arguments,
+            })
+        };
+        let add = self.lowlevel(LowLevel::NumAdd, &[index, one]);
+
+        let element = Stmt::Let(
+            x,
+            get,
+            map.function.argument_layouts[0],
+            arena.alloc(Stmt::Let(
+                y,
+                map_call,
+                map.function.return_layout,
+                arena.alloc(Stmt::Let(
+                    one,
+                    int_literal(1),
+                    nat,
+                    arena.alloc(Stmt::Let(next, add, nat, arena.alloc(step))),
+                )),
+            )),
+        );
+        let done = arena.alloc(Stmt::Jump(after_id, arena.alloc([acc])));
+        let loop_body = Stmt::Let(
+            more,
+            self.lowlevel(LowLevel::NumLt, &[index, len]),
+            Layout::bool(),
+            arena.alloc(Stmt::if_then_else(
+                arena,
+                more,
+                self.ret_layout,
+                element,
+                done,
+            )),
+        );
+
+        let (init, init_let) = match consumer {
+            Consumer::Walk { state, .. } => (state, None),
+            Consumer::KeepIf { .. } => {
+                let empty = self.fresh_symbol();
+                let with_capacity = self.lowlevel(LowLevel::ListWithCapacity, &[len]);
+
+                (empty, Some((empty, with_capacity)))
+            }
+        };
+
+        let mut stmt: &'a Stmt<'a> = arena.alloc(Stmt::Join {
+            id: after_id,
+            parameters: arena.alloc([Param {
+                symbol,
+                borrow: false,
+                layout,
+            }]),
+            body: cont,
+            remainder: arena.alloc(Stmt::Join {
+                id: loop_id,
+                parameters: arena.alloc([
+                    Param {
+                        symbol: index,
+                        borrow: false,
+                        layout: nat,
+                    },
+                    Param {
+                        symbol: acc,
+                        borrow: false,
+                        layout,
+                    },
+                ]),
+                body: arena.alloc(loop_body),
+                remainder: arena.alloc(Stmt::Jump(loop_id, arena.alloc([start, init]))),
+            }),
+        });
+
+        if let Some((empty, with_capacity)) = init_let {
+            stmt = arena.alloc(Stmt::Let(empty, with_capacity, layout, stmt));
+        }
+
+        stmt = arena.alloc(Stmt::Let(start, int_literal(0), nat, stmt));
+
+        let list_len = self.lowlevel(LowLevel::ListLen, &[map.xs]);
+        arena.alloc(Stmt::Let(len, list_len, nat, stmt))
+    }
+
+    fn call_closure(&mut self, closure: Closure<'a>, arguments: &[Symbol]) -> Expr<'a> {
+        let arena = self.arena;
+
+        let mut all_arguments = Vec::from_iter_in(arguments.iter().copied(), arena);
+        all_arguments.extend(closure.captures);
+
+        Expr::Call(Call {
+            call_type: CallType::ByName {
+                name: closure.name,
+                ret_layout: arena.alloc(closure.ret_layout),
+                arg_layouts: closure.arg_layouts,
+                specialization_id: self.fresh_call_spec_id(),
+            },
+            arguments: all_arguments.into_bump_slice(),
+        })
+    }
+
+    fn lowlevel(&mut self, op: LowLevel, arguments: &[Symbol]) -> Expr<'a> {
+        Expr::Call(Call {
+            call_type: CallType::LowLevel {
+                op,
+                update_mode: self.update_mode_ids.next_id(),
+            },
+            arguments: self.arena.alloc_slice_copy(arguments),
+        })
+    }
+
+    fn fresh_symbol(&mut self) -> Symbol {
+        Symbol::new(self.home, self.ident_ids.gen_unique())
+    }
+
+    fn fresh_call_spec_id(&mut self) -> CallSpecId {
+        let id = self.next_call_spec_id;
+        self.next_call_spec_id = id.next();
+
+        id
+    }
+}
+
+fn int_literal<'a>(int: i128) -> Expr<'a> {
+    Expr::Literal(Literal::Int(int.to_ne_bytes()))
+}
+
+fn count_uses(stmt: &Stmt, uses: &mut MutMap<Symbol, usize>) {
+    use Stmt::*;
+
+    match stmt {
+        Let(_, expr, _, cont) => {
+            let mut used = MutSet::default();
+            occurring_variables_expr(expr, &mut used);
+            count(uses, used);
+            count_uses(cont, uses);
+        }
+        Switch {
+            cond_symbol,
+            branches,
+            default_branch,
+            ..
+        } => {
+            count(uses, [*cond_symbol]);
+            for (_, _, branch) in branches.iter() {
+                count_uses(branch, uses);
+            }
+            count_uses(default_branch.1, uses);
+        }
+        Join {
+            body, remainder, ..
+        } => {
+            count_uses(body, uses);
+            count_uses(remainder, uses);
+        }
+        Refcounting(modify, cont) => {
+            count(uses, [modify.get_symbol()]);
+            count_uses(cont, uses);
+        }
+        Expect {
+            condition,
+            lookups,
+            remainder,
+            ..
+        }
+        | ExpectFx {
+            condition,
+            lookups,
+            remainder,
+            ..
+        } => {
+            count(
+                uses,
+                std::iter::once(*condition).chain(lookups.iter().copied()),
+            );
+            count_uses(remainder, uses);
+        }
+        Ret(symbol) | Crash(symbol, _) => count(uses, [*symbol]),
+        Jump(_, arguments) => count(uses, arguments.iter().copied()),
+    }
+}
+
+fn count(uses: &mut MutMap<Symbol, usize>, symbols: impl IntoIterator<Item = Symbol>) {
+    for symbol in symbols {
+        *uses.entry(symbol).or_insert(0) += 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::fuse_list_operations;
+    use crate::ir::text::{parse_procs, procs_to_text};
+    use crate::ir::{Call, CallType, Expr, OptLevel, ProcLayout, Stmt, UpdateModeIds};
+    use crate::layout::{LambdaSet, Layout, STLayoutInterner};
+    use bumpalo::collections::Vec;
+    use bumpalo::Bump;
+    use roc_collections::all::MutMap;
+    use roc_intern::{Interner, SingleThreadedInterner};
+    use roc_module::ident::ModuleName;
+    use roc_module::symbol::{Interns, Symbol};
+    use roc_target::TargetInfo;
+
+    /// The text format has no lambda sets, so give the `{}` closure passed to `List.walk` and
+    /// `List.keepIf` the layout `closure`
+    fn with_lambda_set<'a>(arena: &'a Bump, stmt: &Stmt<'a>, closure: Layout<'a>) -> Stmt<'a> {
+        match stmt {
+            Stmt::Let(symbol, expr, layout, cont) => {
+                let expr = match expr {
+                    Expr::Call(Call {
+                        call_type:
+                            CallType::ByName {
+                                name,
+                                ret_layout,
+                                arg_layouts,
+                                specialization_id,
+                            },
+                        arguments,
+                    }) if matches!(name.name(), Symbol::LIST_WALK | Symbol::LIST_KEEP_IF) => {
+                        let mut arg_layouts = Vec::from_iter_in(arg_layouts.iter().copied(), arena);
+                        *arg_layouts.last_mut().unwrap() = closure;
+
+                        Expr::Call(Call {
+                            call_type: CallType::ByName {
+                                name: *name,
+                                ret_layout: *ret_layout,
+                                arg_layouts: arg_layouts.into_bump_slice(),
+                                specialization_id: *specialization_id,
+                            },
+                            arguments: *arguments,
+                        })
+                    }
+                    _ => expr.clone(),
+                };
+                let cont = with_lambda_set(arena, cont, closure);
+
+                Stmt::Let(*symbol, expr, *layout, arena.alloc(cont))
+            }
+            Stmt::Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout,
+            } => {
+                let branches = Vec::from_iter_in(
+                    branches.iter().map(|(label, info, branch)| {
+                        (
+                            *label,
+                            info.clone(),
+                            with_lambda_set(arena, branch, closure),
+                        )
+                    }),
+                    arena,
+                );
+                let default = with_lambda_set(arena, default_branch.1, closure);
+
+                Stmt::Switch {
+                    cond_symbol: *cond_symbol,
+                    cond_layout: *cond_layout,
+                    branches: branches.into_bump_slice(),
+                    default_branch: (default_branch.0.clone(), arena.alloc(default)),
+                    ret_layout: *ret_layout,
+                }
+            }
+            _ => stmt.clone(),
+        }
+    }
+
+    /// Fuse the list operations in the last procedure of `text`, and print it along with the
+    /// procedures fusion added. The functions it walks or filters with are the first procedure.
+    fn fuse(text: &str) -> String {
+        let arena = Bump::new();
+        let mut interns = Interns::default();
+        let mut interner: STLayoutInterner = SingleThreadedInterner::with_capacity(4);
+
+        let mut procs = match parse_procs(&arena, &mut interns, text) {
+            Ok(procs) => procs,
+            Err(error) => panic!("{}", error),
+        };
+
+        let function = procs.first().unwrap().name.name();
+        let lambda_set = LambdaSet::from_parts(
+            arena.alloc([(function, &[] as &[_])]),
+            interner.insert(&Layout::UNIT),
+        );
+        let caller = procs.last_mut().unwrap();
+        caller.body = with_lambda_set(&arena, &caller.body, Layout::LambdaSet(lambda_set));
+        let caller = caller.name.name();
+        let mut procs: MutMap<_, _> = procs
+            .into_iter()
+            .map(|proc| {
+                let arguments = arena.alloc_slice_fill_iter(proc.args.iter().map(|(l, _)| *l));
+                let proc_layout = ProcLayout::new(
+                    &arena,
+                    arguments,
+                    proc.name.captures_niche(),
+                    proc.ret_layout,
+                );
+
+                ((proc.name.name(), proc_layout), proc)
+            })
+            .collect();
+        let callees: std::vec::Vec<_> = procs.keys().map(|(name, _)| *name).collect();
+
+        let home = interns.module_id(&ModuleName::from("Test"));
+        let ident_ids = interns.all_ident_ids.get_or_insert(home);
+        let mut update_mode_ids = UpdateModeIds::new();
+
+        fuse_list_operations(
+            &arena,
+            &interner,
+            home,
+            ident_ids,
+            &mut update_mode_ids,
+            TargetInfo::default_x86_64(),
+            OptLevel::Normal,
+            &mut procs,
+        );
+
+        let mut changed: std::vec::Vec<_> = procs
+            .values()
+            .filter(|proc| {
+                let name = proc.name.name();
+                name == caller || !callees.contains(&name)
+            })
+            .collect();
+        changed.sort_by_key(|proc| proc.name.name() != caller);
+
+        procs_to_text(&interns, &interner, changed)
+    }
+
+    const FUSED: &str = r#"procedure Test.0 (Test.9 : List I64, Test.10 : I64, Test.11 : I64) -> List Int1:
+    let Test.15 : {I64, I64} = Struct {Test.10, Test.11};
+    let Test.13 : List Int1 = higher-order ListMap {xs: Test.9} Test.14 (I64, {I64, I64}) -> Int1 env Test.15 : {I64, I64} Test.9 Test.14 Test.15;
+    ret Test.13;
+
+procedure Test.14 (Test.16 : I64, Test.17 : {I64, I64}) -> Int1:
+    let Test.18 : I64 = StructAtIndex 0 Test.17;
+    let Test.19 : I64 = CallByName Test.1 Test.16 Test.18;
+    let Test.20 : I64 = StructAtIndex 1 Test.17;
+    let Test.21 : Int1 = CallByName Test.5 Test.19 Test.20;
+    ret Test.21;
+"#;
+
+    #[test]
+    fn fuse_two_maps() {
+        const TEXT: &str = r#"procedure Test.1 (Test.2 : I64, Test.3 : I64) -> I64:
+    let Test.4 : I64 = lowlevel NumAdd Test.2 Test.3;
+    ret Test.4;
+
+procedure Test.5 (Test.6 : I64, Test.7 : I64) -> Int1:
+    let Test.8 : Int1 = lowlevel NumGt Test.6 Test.7;
+    ret Test.8;
+
+procedure Test.0 (Test.9 : List I64, Test.10 : I64, Test.11 : I64) -> List Int1:
+    let Test.12 : List I64 = higher-order ListMap {xs: Test.9} Test.1 (I64, I64) -> I64 env Test.10 : I64 Test.9 Test.1 Test.10;
+    let Test.13 : List Int1 = higher-order ListMap {xs: Test.12} Test.5 (I64, I64) -> Int1 env Test.11 : I64 Test.12 Test.5 Test.11;
+    ret Test.13;
+"#;
+
+        assert_eq!(fuse(TEXT), FUSED);
+    }
+
+    #[test]
+    fn fuse_two_calls_to_list_map() {
+        // `List.5` is `List.map`, which user code calls through its specializations
+        const TEXT: &str = r#"procedure Test.1 (Test.2 : I64, Test.3 : I64) -> I64:
+    let Test.4 : I64 = lowlevel NumAdd Test.2 Test.3;
+    ret Test.4;
+
+procedure Test.5 (Test.6 : I64, Test.7 : I64) -> Int1:
+    let Test.8 : Int1 = lowlevel NumGt Test.6 Test.7;
+    ret Test.8;
+
+procedure List.5 (List.100 : List I64, List.101 : I64) -> List I64:
+    let List.102 : List I64 = higher-order ListMap {xs: List.100} Test.1 (I64, I64) -> I64 env List.101 : I64 List.100 Test.1 List.101;
+    ret List.102;
+
+procedure List.5 (List.103 : List I64, List.104 : I64) -> List Int1:
+    let List.105 : List Int1 = higher-order ListMap {xs: List.103} Test.5 (I64, I64) -> Int1 env List.104 : I64 List.103 Test.5 List.104;
+    ret List.105;
+
+procedure Test.0 (Test.9 : List I64, Test.10 : I64, Test.11 : I64) -> List Int1:
+    let Test.12 : List I64 = CallByName List.5 Test.9 Test.10;
+    let Test.13 : List Int1 = CallByName List.5 Test.12 Test.11;
+    ret Test.13;
+"#;
+
+        assert_eq!(fuse(TEXT), FUSED);
+    }
+
+    #[test]
+    fn keep_map_used_twice() {
+        const TEXT: &str = r#"procedure Test.1 (Test.2 : I64, Test.3 : I64) -> I64:
+    let Test.4 : I64 = lowlevel NumAdd Test.2 Test.3;
+    ret Test.4;
+
+procedure List.5 (List.100 : List I64, List.101 : I64) -> List I64:
+    let List.102 : List I64 = higher-order ListMap {xs: List.100} Test.1 (I64, I64) -> I64 env List.101 : I64 List.100 Test.1 List.101;
+    ret List.102;
+
+procedure Test.0 (Test.9 : List I64, Test.10 : I64) -> {List I64, List I64}:
+    let Test.12 : List I64 = CallByName List.5 Test.9 Test.10;
+    let Test.13 : List I64 = CallByName List.5 Test.12 Test.10;
+    let Test.14 : {List I64, List I64} = Struct {Test.12, Test.13};
+    ret Test.14;
+"#;
+
+        let expected = &TEXT[TEXT.rfind("procedure").unwrap()..];
+
+        assert_eq!(fuse(TEXT), expected);
+    }
+
+    /// The step of a walk, and a map over a list of `I64`, for the tests of loops
+    const WALK_AND_MAP: &str = r#"procedure Test.1 (Test.2 : I64, Test.3 : I64) -> I64:
+    let Test.4 : I64 = lowlevel NumAdd Test.2 Test.3;
+    ret Test.4;
+
+procedure Test.5 (Test.6 : I64, Test.7 : I64) -> I64:
+    let Test.8 : I64 = lowlevel NumMul Test.6 Test.7;
+    ret Test.8;
+
+procedure List.5 (List.100 : List I64, List.101 : I64) -> List I64:
+    let List.102 : List I64 = higher-order ListMap {xs: List.100} Test.5 (I64, I64) -> I64 env List.101 : I64 List.100 Test.5 List.101;
+    ret List.102;
+
+"#;
+
+    #[test]
+    fn fuse_map_into_walk() {
+        let text = format!(
+            "{}{}",
+            WALK_AND_MAP,
+            r#"procedure Test.0 (Test.9 : List I64, Test.10 : I64, Test.11 : I64) -> I64:
+    let Test.12 : List I64 = CallByName List.5 Test.9 Test.10;
+    let Test.13 : {} = Struct {};
+    let Test.14 : I64 = CallByName List.18 Test.12 Test.11 Test.13;
+    ret Test.14;
+"#
+        );
+
+        let fused = fuse(&text);
+
+        assert!(fused.contains("lowlevel ListGetUnsafe Test.9"), "{}", fused);
+        assert!(fused.contains("CallByName Test.5"), "{}", fused);
+        assert!(fused.contains("CallByName Test.1"), "{}", fused);
+        assert!(!fused.contains("CallByName List."), "{}", fused);
+    }
+
+    #[test]
+    fn keep_walked_map_used_twice() {
+        let caller = r#"procedure Test.0 (Test.9 : List I64, Test.10 : I64, Test.11 : I64) -> {I64, List I64}:
+    let Test.12 : List I64 = CallByName List.5 Test.9 Test.10;
+    let Test.13 : {} = Struct {};
+    let Test.14 : I64 = CallByName List.18 Test.12 Test.11 Test.13;
+    let Test.15 : {I64, List I64} = Struct {Test.14, Test.12};
+    ret Test.15;
+"#;
+
+        assert_eq!(fuse(&format!("{}{}", WALK_AND_MAP, caller)), caller);
+    }
+
+    #[test]
+    fn keep_map_walked_in_a_branch() {
+        // fusing would move the map into the branch
+        let caller = r#"procedure Test.0 (Test.9 : List I64, Test.10 : I64, Test.11 : I64, Test.16 : Int1) -> I64:
+    let Test.12 : List I64 = CallByName List.5 Test.9 Test.10;
+    switch Test.16 : Int1 -> I64:
+        case 1:
+            let Test.13 : {} = Struct {};
+            let Test.14 : I64 = CallByName List.18 Test.12 Test.11 Test.13;
+            ret Test.14;
+        default:
+            ret Test.11;
+"#;
+
+        assert_eq!(fuse(&format!("{}{}", WALK_AND_MAP, caller)), caller);
+    }
+
+    #[test]
+    fn keep_walked_map_over_refcounted_elements() {
+        const TEXT: &str = r#"procedure Test.1 (Test.2 : I64, Test.3 : I64) -> I64:
+    let Test.4 : I64 = lowlevel NumAdd Test.2 Test.3;
+    ret Test.4;
+
+procedure Test.5 (Test.6 : Str, Test.7 : I64) -> I64:
+    ret Test.7;
+
+procedure List.5 (List.100 : List Str, List.101 : I64) -> List I64:
+    let List.102 : List I64 = higher-order ListMap {xs: List.100} Test.5 (Str, I64) -> I64 env List.101 : I64 List.100 Test.5 List.101;
+    ret List.102;
+
+procedure Test.0 (Test.9 : List Str, Test.10 : I64, Test.11 : I64) -> I64:
+    let Test.12 : List I64 = CallByName List.5 Test.9 Test.10;
+    let Test.13 : {} = Struct {};
+    let Test.14 : I64 = CallByName List.18 Test.12 Test.11 Test.13;
+    ret Test.14;
+"#;
+
+        let expected = &TEXT[TEXT.rfind("procedure").unwrap()..];
+
+        assert_eq!(fuse(TEXT), expected);
+    }
+
+    #[test]
+    fn keep_walked_map_of_nested_maps() {
+        // the outer map's elements are the lists that the inner map consumes
+        const TEXT: &str = r#"procedure Test.1 (Test.2 : U64, Test.3 : List I64) -> U64:
+    let Test.4 : U64 = lowlevel ListLen Test.3;
+    let Test.20 : U64 = lowlevel NumAdd Test.2 Test.4;
+    ret Test.20;
+
+procedure Test.21 (Test.22 : I64, Test.23 : I64) -> I64:
+    let Test.24 : I64 = lowlevel NumMul Test.22 Test.23;
+    ret Test.24;
+
+procedure List.5 (List.100 : List I64, List.101 : I64) -> List I64:
+    let List.102 : List I64 = higher-order ListMap {xs: List.100} Test.21 (I64, I64) -> I64 env List.101 : I64 List.100 Test.21 List.101;
+    ret List.102;
+
+procedure Test.5 (Test.6 : List I64, Test.7 : I64) -> List I64:
+    let Test.8 : List I64 = CallByName List.5 Test.6 Test.7;
+    ret Test.8;
+
+procedure List.5 (List.103 : List List I64, List.104 : I64) -> List List I64:
+    let List.105 : List List I64 = higher-order ListMap {xs: List.103} Test.5 (List I64, I64) -> List I64 env List.104 : I64 List.103 Test.5 List.104;
+    ret List.105;
+
+procedure Test.0 (Test.9 : List List I64, Test.10 : I64, Test.11 : U64) -> U64:
+    let Test.12 : List List I64 = CallByName List.5 Test.9 Test.10;
+    let Test.13 : {} = Struct {};
+    let Test.14 : U64 = CallByName List.18 Test.12 Test.11 Test.13;
+    ret Test.14;
+"#;
+
+        let expected = &TEXT[TEXT.rfind("procedure").unwrap()..];
+
+        assert_eq!(fuse(TEXT), expected);
+    }
+}
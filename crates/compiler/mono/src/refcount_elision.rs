@@ -0,0 +1,455 @@
+//! Remove reference count operations that have no observable effect.
+//!
+//! Inserting refcount operations works one binding at a time, and so it leaves increments and
+//! decrements that undo each other. This pass
+//!
+//! - moves every `inc` down past the statements that can't observe the reference count, and
+//!   cancels it against a `dec` of the same symbol that it meets on the way;
+//! - moves an `inc` that reaches a `switch` into its branches, so only the branches that use the
+//!   value pay for the increment, and branches that would immediately `dec` it pay nothing;
+//! - for a value that is allocated in the procedure and never leaves it (its only uses are reads
+//!   that borrow it), keeps track of what its reference count would be, and removes every `inc`
+//!   and `dec` of it except the `dec` that frees it.
+//!
+//! Nothing can observe the reference count in between, as long as no value is passed to a call
+//! that may consume it (and so may update it in place), and no other value is decremented (which
+//! could free a value that contains it).
+
+use crate::borrow::{lowlevel_borrow_signature, BORROWED};
+use crate::inc_dec::occurring_variables_expr;
+use crate::ir::{Call, CallType, Expr, ModifyRc, OptLevel, Proc, ProcLayout, Stmt};
+use crate::layout::UnionLayout;
+use bumpalo::collections::Vec;
+use bumpalo::Bump;
+use roc_collections::all::{MutMap, MutSet};
+use roc_collections::VecMap;
+use roc_module::low_level::LowLevel;
+use roc_module::symbol::Symbol;
+
+pub fn elide_refcount_operations<'a>(
+    arena: &'a Bump,
+    opt_level: OptLevel,
+    procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+) {
+    if let OptLevel::Development = opt_level {
+        return;
+    }
+
+    for proc in procs.values_mut() {
+        let mut env = Env {
+            arena,
+            escaping: MutSet::default(),
+        };
+
+        env.find_escaping(&proc.body);
+
+        proc.body = env.elide_stmt(&proc.body, &mut VecMap::default()).clone();
+    }
+}
+
+struct Env<'a> {
+    arena: &'a Bump,
+    /// The symbols that are used in a way that could store them, or hand them to code that may
+    /// change their reference count
+    escaping: MutSet<Symbol>,
+}
+
+/// What the reference count of values that are allocated in the procedure would be without this
+/// pass. With it, their reference count is 1 until they are freed.
+type Counts = VecMap<Symbol, u64>;
+
+impl<'a> Env<'a> {
+    fn elide_stmt(&mut self, stmt: &Stmt<'a>, counts: &mut Counts) -> &'a Stmt<'a> {
+        use Stmt::*;
+
+        let arena = self.arena;
+
+        match stmt {
+            Let(symbol, expr, layout, cont) => {
+                if is_allocation(expr) && !self.escaping.contains(symbol) {
+                    counts.insert(*symbol, 1);
+                }
+
+                let cont = self.elide_stmt(cont, counts);
+
+                arena.alloc(Let(*symbol, expr.clone(), *layout, cont))
+            }
+            Refcounting(ModifyRc::Inc(symbol, n), cont) => match counts.get_mut(symbol) {
+                Some(count) => {
+                    *count += *n;
+
+                    self.elide_stmt(cont, counts)
+                }
+                None => {
+                    let cont = self.elide_stmt(cont, counts);
+
+                    self.push_inc(*symbol, *n, cont)
+                }
+            },
+            Refcounting(ModifyRc::Dec(symbol), cont) => match counts.get_mut(symbol) {
+                Some(count) if *count > 1 => {
+                    *count -= 1;
+
+                    self.elide_stmt(cont, counts)
+                }
+                _ => {
+                    // This is the `dec` that frees the value
+                    counts.remove(symbol);
+
+                    arena.alloc(Refcounting(
+                        ModifyRc::Dec(*symbol),
+                        self.elide_stmt(cont, counts),
+                    ))
+                }
+            },
+            Refcounting(modify, cont) => {
+                arena.alloc(Refcounting(*modify, self.elide_stmt(cont, counts)))
+            }
+            Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout,
+            } => {
+                let branches = Vec::from_iter_in(
+                    branches.iter().map(|(label, info, branch)| {
+                        let branch = self.elide_stmt(branch, &mut counts.clone());
+
+                        (*label, info.clone(), branch.clone())
+                    }),
+                    arena,
+                );
+                let default_branch = (
+                    default_branch.0.clone(),
+                    self.elide_stmt(default_branch.1, &mut counts.clone()),
+                );
+
+                arena.alloc(Switch {
+                    cond_symbol: *cond_symbol,
+                    cond_layout: *cond_layout,
+                    branches: branches.into_bump_slice(),
+                    default_branch,
+                    ret_layout: *ret_layout,
+                })
+            }
+            Join {
+                id,
+                parameters,
+                body,
+                remainder,
+            } => {
+                // The body can be entered from several jumps, with different counts; each jump
+                // restores the reference counts that the body expects
+                let body = self.elide_stmt(body, &mut Counts::default());
+                let remainder = self.elide_stmt(remainder, counts);
+
+                arena.alloc(Join {
+                    id: *id,
+                    parameters: *parameters,
+                    body,
+                    remainder,
+                })
+            }
+            Expect {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => arena.alloc(Expect {
+                condition: *condition,
+                region: *region,
+                lookups: *lookups,
+                variables: *variables,
+                remainder: self.elide_stmt(remainder, counts),
+            }),
+            ExpectFx {
+                condition,
+                region,
+                lookups,
+                variables,
+                remainder,
+            } => arena.alloc(ExpectFx {
+                condition: *condition,
+                region: *region,
+                lookups: *lookups,
+                variables: *variables,
+                remainder: self.elide_stmt(remainder, counts),
+            }),
+            Jump(..) | Ret(_) => {
+                let mut stmt: &'a Stmt<'a> = arena.alloc(stmt.clone());
+                for (symbol, count) in counts.iter() {
+                    if *count > 1 {
+                        stmt = arena.alloc(Refcounting(ModifyRc::Inc(*symbol, count - 1), stmt));
+                    }
+                }
+
+                stmt
+            }
+            Crash(..) => arena.alloc(stmt.clone()),
+        }
+    }
+
+    /// Put `inc symbol n` in front of `stmt`, as far into it as possible
+    fn push_inc(&mut self, symbol: Symbol, n: u64, stmt: &'a Stmt<'a>) -> &'a Stmt<'a> {
+        use Stmt::*;
+
+        let arena = self.arena;
+
+        match stmt {
+            Refcounting(ModifyRc::Dec(other), cont) if *other == symbol => match n {
+                1 => *cont,
+                _ => self.push_inc(symbol, n - 1, cont),
+            },
+            Refcounting(ModifyRc::Inc(other, m), cont) if *other == symbol => {
+                self.push_inc(symbol, n + m, cont)
+            }
+            Refcounting(modify @ ModifyRc::Inc(..), cont) => {
+                arena.alloc(Refcounting(*modify, self.push_inc(symbol, n, cont)))
+            }
+            Let(binding, expr, layout, cont) if !self.observes(expr, symbol) => arena.alloc(Let(
+                *binding,
+                expr.clone(),
+                *layout,
+                self.push_inc(symbol, n, cont),
+            )),
+            Switch {
+                cond_symbol,
+                cond_layout,
+                branches,
+                default_branch,
+                ret_layout,
+            } => {
+                let branches = Vec::from_iter_in(
+                    branches.iter().map(|(label, info, branch)| {
+                        (
+                            *label,
+                            info.clone(),
+                            self.push_inc(symbol, n, branch).clone(),
+                        )
+                    }),
+                    arena,
+                );
+                let default_branch = (
+                    default_branch.0.clone(),
+                    self.push_inc(symbol, n, default_branch.1),
+                );
+
+                arena.alloc(Switch {
+                    cond_symbol: *cond_symbol,
+                    cond_layout: *cond_layout,
+                    branches: branches.into_bump_slice(),
+                    default_branch,
+                    ret_layout: *ret_layout,
+                })
+            }
+            _ => arena.alloc(Refcounting(ModifyRc::Inc(symbol, n), stmt)),
+        }
+    }
+
+    /// Whether evaluating `expr` could depend on the reference count of `symbol`, or change what
+    /// it should be
+    fn observes(&self, expr: &Expr<'a>, symbol: Symbol) -> bool {
+        match expr {
+            // A call that only borrows its arguments does not update them in place, unless it
+            // looks at their reference count
+            Expr::Call(Call {
+                call_type: CallType::LowLevel { op, .. },
+                ..
+            }) => {
+                reads_refcount(*op)
+                    || lowlevel_borrow_signature(self.arena, *op)
+                        .iter()
+                        .any(|ownership| *ownership != BORROWED)
+            }
+            Expr::Call(_) | Expr::Reset { .. } | Expr::Reuse { .. } | Expr::ReuseBox { .. } => true,
+            _ => {
+                let mut used = MutSet::default();
+                occurring_variables_expr(expr, &mut used);
+
+                used.contains(&symbol)
+            }
+        }
+    }
+
+    fn find_escaping(&mut self, stmt: &Stmt<'a>) {
+        use Stmt::*;
+
+        match stmt {
+            Let(_, expr, _, cont) => {
+                match expr {
+                    Expr::Call(Call {
+                        call_type: CallType::LowLevel { op, .. },
+                        arguments,
+                    }) if reads_refcount(*op) => {
+                        self.escaping.extend(arguments.iter().copied());
+                    }
+                    Expr::Call(Call {
+                        call_type: CallType::LowLevel { op, .. },
+                        arguments,
+                    }) => {
+                        let signature = lowlevel_borrow_signature(self.arena, *op);
+                        for (argument, ownership) in arguments.iter().zip(signature) {
+                            if *ownership != BORROWED {
+                                self.escaping.insert(*argument);
+                            }
+                        }
+                    }
+                    Expr::GetTagId { .. } | Expr::UnionAtIndex { .. } | Expr::ExprUnbox { .. } => {}
+                    _ => occurring_variables_expr(expr, &mut self.escaping),
+                }
+
+                self.find_escaping(cont);
+            }
+            Refcounting(modify, cont) => {
+                if let ModifyRc::DecRef(symbol) = modify {
+                    self.escaping.insert(*symbol);
+                }
+
+                self.find_escaping(cont);
+            }
+            Switch {
+                branches,
+                default_branch,
+                ..
+            } => {
+                for (_, _, branch) in branches.iter() {
+                    self.find_escaping(branch);
+                }
+                self.find_escaping(default_branch.1);
+            }
+            Join {
+                body, remainder, ..
+            } => {
+                self.find_escaping(body);
+                self.find_escaping(remainder);
+            }
+            Expect { remainder, .. } | ExpectFx { remainder, .. } => {
+                self.find_escaping(remainder);
+            }
+            Ret(symbol) | Crash(symbol, _) => {
+                self.escaping.insert(*symbol);
+            }
+            Jump(_, arguments) => {
+                self.escaping.extend(arguments.iter().copied());
+            }
+        }
+    }
+}
+
+/// Whether the result of `op` depends on the reference count of its arguments, or changes it,
+/// even though it borrows them
+fn reads_refcount(op: LowLevel) -> bool {
+    matches!(
+        op,
+        LowLevel::ListIsUnique | LowLevel::RefCountInc | LowLevel::RefCountDec
+    )
+}
+
+/// Whether `expr` allocates a new value on the heap, with a reference count of 1
+fn is_allocation(expr: &Expr) -> bool {
+    match expr {
+        Expr::Tag { tag_layout, .. } => !matches!(
+            tag_layout,
+            UnionLayout::NonRecursive(_) | UnionLayout::NullablePointer { .. }
+        ),
+        Expr::Array { elems, .. } => !elems.is_empty(),
+        Expr::ExprBox { .. } => true,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::elide_refcount_operations;
+    use crate::ir::text::parse_procs;
+    use crate::ir::{OptLevel, ProcLayout};
+    use crate::layout::STLayoutInterner;
+    use bumpalo::Bump;
+    use roc_collections::all::MutMap;
+    use roc_intern::SingleThreadedInterner;
+    use roc_module::symbol::Interns;
+
+    const TEXT: &str = r#"procedure Test.0 (Test.1 : Str, Test.2 : Int1) -> U64:
+    let Test.3 : List I64 = Array [1i64, 2i64];
+    inc 2 Test.3;
+    let Test.4 : U64 = lowlevel ListLen Test.3;
+    dec Test.3;
+    let Test.5 : U64 = lowlevel ListLen Test.3;
+    dec Test.3;
+    let Test.6 : U64 = lowlevel NumAdd Test.4 Test.5;
+    dec Test.3;
+    inc Test.1;
+    switch Test.2 : Int1 -> U64:
+        case 1:
+            dec Test.1;
+            ret Test.6;
+        default:
+            let Test.7 : U64 = lowlevel StrCountGraphemes Test.1;
+            dec Test.1;
+            ret Test.7;
+"#;
+
+    const ELIDED: &str = r#"procedure Test.0 (Test.1 : Str, Test.2 : Int1) -> U64:
+    let Test.3 : List I64 = Array [1i64, 2i64];
+    let Test.4 : U64 = lowlevel ListLen Test.3;
+    let Test.5 : U64 = lowlevel ListLen Test.3;
+    let Test.6 : U64 = lowlevel NumAdd Test.4 Test.5;
+    dec Test.3;
+    switch Test.2 : Int1 -> U64:
+        case 1:
+            ret Test.6;
+        default:
+            let Test.7 : U64 = lowlevel StrCountGraphemes Test.1;
+            ret Test.7;
+"#;
+
+    /// The `inc` must stay before `ListIsUnique`, or the list looks unique when it isn't
+    const READS_REFCOUNT: &str = r#"procedure Test.0 (Test.1 : List I64) -> Int1:
+    inc Test.1;
+    let Test.2 : Int1 = lowlevel ListIsUnique Test.1;
+    dec Test.1;
+    ret Test.2;
+"#;
+
+    fn elide(text: &str) -> String {
+        let arena = Bump::new();
+        let mut interns = Interns::default();
+        let interner: STLayoutInterner = SingleThreadedInterner::with_capacity(4);
+
+        let procs = match parse_procs(&arena, &mut interns, text) {
+            Ok(procs) => procs,
+            Err(error) => panic!("{}", error),
+        };
+        let mut procs: MutMap<_, _> = procs
+            .into_iter()
+            .map(|proc| {
+                let arguments = arena.alloc_slice_fill_iter(proc.args.iter().map(|(l, _)| *l));
+                let proc_layout = ProcLayout::new(
+                    &arena,
+                    arguments,
+                    proc.name.captures_niche(),
+                    proc.ret_layout,
+                );
+
+                ((proc.name.name(), proc_layout), proc)
+            })
+            .collect();
+
+        elide_refcount_operations(&arena, OptLevel::Normal, &mut procs);
+
+        let proc = procs.values().next().unwrap();
+        proc.to_text(&interns, &interner)
+    }
+
+    #[test]
+    fn cancel_sink_and_remove_refcount_operations() {
+        assert_eq!(elide(TEXT), ELIDED);
+    }
+
+    #[test]
+    fn keep_refcount_operations_around_refcount_reads() {
+        assert_eq!(elide(READS_REFCOUNT), READS_REFCOUNT);
+    }
+}
@@ -12,6 +12,13 @@
 //! See also
 //! - [Counting Immutable Beans](https://arxiv.org/pdf/1908.05647.pdf) (Ullrich and Moura, 2020)
 //! - [The lean implementation](https://github.com/leanprover/lean4/blob/master/src/Lean/Compiler/IR/ResetReuse.lean)
+//!
+//! Besides the constructors of heap-allocated unions, boxes are reused too: after the last use of
+//! a unique box, a new box of the same layout is written into its memory. Records are stored
+//! inline rather than on the heap, so there is no allocation to reuse for them; a record inside
+//! a box is updated in place this way.
+//!
+//! Set `ROC_PRINT_IN_PLACE_UPDATES` to see where memory was reused, and where it could not be.
 
 use crate::inc_dec::{collect_stmt, occurring_variables_expr, JPLiveVarMap, LiveVarSet};
 use crate::ir::{
@@ -21,6 +28,9 @@ use crate::layout::{Layout, TagIdIntType, UnionLayout};
 use bumpalo::collections::Vec;
 use bumpalo::Bump;
 use roc_collections::all::MutSet;
+use roc_debug_flags::dbg_do;
+#[cfg(debug_assertions)]
+use roc_debug_flags::ROC_PRINT_IN_PLACE_UPDATES;
 use roc_module::symbol::{IdentIds, ModuleId, Symbol};
 
 pub fn insert_reset_reuse<'a, 'i>(
@@ -36,6 +46,7 @@ pub fn insert_reset_reuse<'a, 'i>(
         ident_ids,
         update_mode_ids,
         jp_live_vars: Default::default(),
+        proc_name: proc.name.name(),
     };
 
     let new_body = function_r(&mut env, arena.alloc(proc.body));
@@ -44,24 +55,43 @@ pub fn insert_reset_reuse<'a, 'i>(
     proc
 }
 
+/// What the value that provides the memory was built with
 #[derive(Debug)]
-struct CtorInfo<'a> {
-    id: TagIdIntType,
-    layout: UnionLayout<'a>,
+enum CtorInfo<'a> {
+    Tag {
+        id: TagIdIntType,
+        layout: UnionLayout<'a>,
+    },
+    /// A box, with the layout of the box itself
+    Box(Layout<'a>),
 }
 
-fn may_reuse(tag_layout: UnionLayout, tag_id: TagIdIntType, other: &CtorInfo) -> bool {
-    if tag_layout != other.layout {
-        return false;
+impl<'a> CtorInfo<'a> {
+    fn layout(&self) -> Layout<'a> {
+        match self {
+            CtorInfo::Tag { layout, .. } => Layout::Union(*layout),
+            CtorInfo::Box(layout) => *layout,
+        }
     }
+}
+
+fn may_reuse(tag_layout: UnionLayout, tag_id: TagIdIntType, other: &CtorInfo) -> bool {
+    let other_id = match other {
+        CtorInfo::Tag { id, layout } if *layout == tag_layout => *id,
+        _ => return false,
+    };
 
     // we should not get here if the tag we matched on is represented as NULL
-    debug_assert!(!tag_layout.tag_is_null(other.id as _));
+    debug_assert!(!tag_layout.tag_is_null(other_id as _));
 
     // furthermore, we can only use the memory if the tag we're creating is non-NULL
     !tag_layout.tag_is_null(tag_id)
 }
 
+fn may_reuse_box(box_layout: &Layout, other: &CtorInfo) -> bool {
+    matches!(other, CtorInfo::Box(layout) if layout == box_layout)
+}
+
 #[derive(Debug)]
 struct Env<'a, 'i> {
     arena: &'a Bump,
@@ -72,6 +102,9 @@ struct Env<'a, 'i> {
     update_mode_ids: &'i mut UpdateModeIds,
 
     jp_live_vars: JPLiveVarMap,
+
+    /// The procedure being processed, for debug output
+    proc_name: Symbol,
 }
 
 impl<'a, 'i> Env<'a, 'i> {
@@ -99,6 +132,13 @@ fn function_s<'a, 'i>(
                 tag_id,
                 arguments,
             } if may_reuse(*tag_layout, *tag_id, c) => {
+                dbg_do!(ROC_PRINT_IN_PLACE_UPDATES, {
+                    eprintln!(
+                        "in {:?}: {:?} reuses the memory of {:?}",
+                        env.proc_name, symbol, w.reused
+                    );
+                });
+
                 // for now, always overwrite the tag ID just to be sure
                 let update_tag_id = true;
 
@@ -114,6 +154,23 @@ fn function_s<'a, 'i>(
 
                 arena.alloc(new_stmt)
             }
+            Expr::ExprBox { symbol: value } if may_reuse_box(layout, c) => {
+                dbg_do!(ROC_PRINT_IN_PLACE_UPDATES, {
+                    eprintln!(
+                        "in {:?}: {:?} reuses the memory of {:?}",
+                        env.proc_name, symbol, w.reused
+                    );
+                });
+
+                let new_expr = Expr::ReuseBox {
+                    symbol: w.symbol,
+                    update_mode: w.update_mode,
+                    value: *value,
+                };
+                let new_stmt = Let(*symbol, new_expr, *layout, continuation);
+
+                arena.alloc(new_stmt)
+            }
             _ => {
                 let rest = function_s(env, w, c, continuation);
                 let new_stmt = Let(*symbol, expr.clone(), *layout, rest);
@@ -249,6 +306,8 @@ fn function_s<'a, 'i>(
 struct Opportunity {
     symbol: Symbol,
     update_mode: UpdateModeId,
+    /// The value whose memory is reused
+    reused: Symbol,
 }
 
 fn try_function_s<'a, 'i>(
@@ -260,14 +319,22 @@ fn try_function_s<'a, 'i>(
     let w = Opportunity {
         symbol: env.unique_symbol(),
         update_mode: env.update_mode_ids.next_id(),
+        reused: x,
     };
 
     let new_stmt = function_s(env, w, c, stmt);
 
     if std::ptr::eq(stmt, new_stmt) || stmt == new_stmt {
+        dbg_do!(ROC_PRINT_IN_PLACE_UPDATES, {
+            eprintln!(
+                "in {:?}: the memory of {:?} is not reused, because nothing with its layout is built after its last use",
+                env.proc_name, x
+            );
+        });
+
         stmt
     } else {
-        insert_reset(env, w, x, c.layout, new_stmt)
+        insert_reset(env, w, x, c.layout(), new_stmt)
     }
 }
 
@@ -275,7 +342,7 @@ fn insert_reset<'a>(
     env: &mut Env<'a, '_>,
     w: Opportunity,
     x: Symbol,
-    union_layout: UnionLayout<'a>,
+    layout: Layout<'a>,
     mut stmt: &'a Stmt<'a>,
 ) -> &'a Stmt<'a> {
     use crate::ir::Expr::*;
@@ -284,23 +351,20 @@ fn insert_reset<'a>(
 
     while let Stmt::Let(symbol, expr, expr_layout, rest) = stmt {
         match &expr {
-            StructAtIndex { .. } | GetTagId { .. } | UnionAtIndex { .. } => {
+            StructAtIndex { .. } | GetTagId { .. } | UnionAtIndex { .. } | ExprUnbox { .. } => {
                 stack.push((symbol, expr, expr_layout));
                 stmt = rest;
             }
 
-            ExprBox { .. } | ExprUnbox { .. } => {
-                // TODO
-                break;
-            }
-
             Literal(_)
             | Call(_)
             | Tag { .. }
             | Struct(_)
             | Array { .. }
             | EmptyArray
+            | ExprBox { .. }
             | Reuse { .. }
+            | ReuseBox { .. }
             | Reset { .. }
             | RuntimeErrorFunction(_) => break,
         }
@@ -311,8 +375,6 @@ fn insert_reset<'a>(
         update_mode: w.update_mode,
     };
 
-    let layout = Layout::Union(union_layout);
-
     stmt = env
         .arena
         .alloc(Stmt::Let(w.symbol, reset_expr, layout, stmt));
@@ -357,6 +419,29 @@ fn function_d_main<'a, 'i>(
                     // If the scrutinee `x` (the one that is providing memory) is being
                     // stored in a constructor, then reuse will probably not be able to reuse memory at runtime.
                     // It may work only if the new cell is consumed, but we ignore this case.
+                    dbg_do!(ROC_PRINT_IN_PLACE_UPDATES, {
+                        eprintln!(
+                            "in {:?}: the memory of {:?} is not reused, because it is stored in {:?}",
+                            env.proc_name, x, symbol
+                        );
+                    });
+
+                    (stmt, true)
+                }
+                Expr::ExprBox { symbol: value } if *value == x => {
+                    // Likewise for a box that `x` is stored in
+                    dbg_do!(ROC_PRINT_IN_PLACE_UPDATES, {
+                        eprintln!(
+                            "in {:?}: the memory of {:?} is not reused, because it is stored in {:?}",
+                            env.proc_name, x, symbol
+                        );
+                    });
+
+                    (stmt, true)
+                }
+                Expr::Reset { symbol: reset, .. } if *reset == x => {
+                    // `x` is unboxed more than once, and the memory is already claimed by a
+                    // later unboxing
                     (stmt, true)
                 }
                 _ => {
@@ -564,9 +649,11 @@ fn function_r_branch_body<'a, 'i>(
             layout,
             tag_id,
         } => match layout {
-            Layout::Union(UnionLayout::NonRecursive(_)) => temp,
+            Layout::Union(UnionLayout::NonRecursive(_) | UnionLayout::NullablePointer { .. }) => {
+                temp
+            }
             Layout::Union(union_layout) if !union_layout.tag_is_null(*tag_id) => {
-                let ctor_info = CtorInfo {
+                let ctor_info = CtorInfo::Tag {
                     layout: *union_layout,
                     id: *tag_id,
                 };
@@ -648,6 +735,17 @@ fn function_r<'a, 'i>(env: &mut Env<'a, 'i>, stmt: &'a Stmt<'a>) -> &'a Stmt<'a>
         Let(symbol, expr, layout, continuation) => {
             let b = function_r(env, continuation);
 
+            let b = match expr {
+                Expr::ExprUnbox { symbol: boxed } => {
+                    // Like the scrutinee of a branch, the box is likely not used after its
+                    // value was read, so a new box can be built in its memory
+                    let box_layout = Layout::Boxed(arena.alloc(*layout));
+
+                    function_d(env, *boxed, &CtorInfo::Box(box_layout), b)
+                }
+                _ => b,
+            };
+
             arena.alloc(Let(*symbol, expr.clone(), *layout, b))
         }
         Refcounting(modify_rc, continuation) => {
@@ -791,6 +889,7 @@ fn has_live_var_expr<'a>(expr: &'a Expr<'a>, needle: Symbol) -> bool {
         Expr::Reuse {
             symbol, arguments, ..
         } => needle == *symbol || arguments.iter().any(|s| *s == needle),
+        Expr::ReuseBox { symbol, value, .. } => needle == *symbol || needle == *value,
         Expr::Reset { symbol, .. } => needle == *symbol,
         Expr::ExprBox { symbol, .. } => needle == *symbol,
         Expr::ExprUnbox { symbol, .. } => needle == *symbol,
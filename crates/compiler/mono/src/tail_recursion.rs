@@ -1,10 +1,14 @@
 #![allow(clippy::manual_map)]
 
-use crate::ir::{CallType, Expr, JoinPointId, Param, Stmt};
+use crate::ir::{CallType, Env, Expr, JoinPointId, Param, Proc, ProcLayout, SelfRecursive, Stmt};
 use crate::layout::{LambdaName, Layout};
 use bumpalo::collections::Vec;
 use bumpalo::Bump;
+use roc_collections::all::{MutMap, MutSet};
+use roc_collections::ReferenceMatrix;
 use roc_module::symbol::Symbol;
+use roc_problem::can::{Problem, TailCallBlocker};
+use roc_region::all::Region;
 
 /// Make tail calls into loops (using join points)
 ///
@@ -28,19 +32,308 @@ use roc_module::symbol::Symbol;
 ///
 /// This will effectively compile into a loop in llvm, and
 /// won't grow the call stack for each iteration
+///
+/// Mutually recursive functions are handled the same way: each function of the group gets the
+/// bodies of the others as join points, so e.g. in `isEven`, a tail call to `isOdd` jumps to the
+/// body of `isOdd`, and a tail call back to `isEven` from there jumps to the start of `isEven`.
+///
+/// Returns a warning for each tail call within a group that can't be made into a jump, with the
+/// reason why. `tail_call_regions` has the regions of the tail calls from one function to
+/// another.
 pub fn make_tail_recursive<'a>(
-    arena: &'a Bump,
+    env: &mut Env<'a, '_>,
+    procs: &mut MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+    tail_call_regions: &MutMap<(Symbol, Symbol), std::vec::Vec<Region>>,
+) -> std::vec::Vec<Problem> {
+    let keys: std::vec::Vec<_> = procs.keys().copied().collect();
+    let index_of: MutMap<_, _> = keys.iter().enumerate().map(|(i, key)| (*key, i)).collect();
+
+    let mut matrix = ReferenceMatrix::new(keys.len());
+
+    for (row, key) in keys.iter().enumerate() {
+        for_each_tail_call(&procs[key].body, &mut |callee| {
+            if let Some(col) = index_of.get(&callee.key()) {
+                matrix.set_row_col(row, *col, true);
+            }
+        });
+    }
+
+    let sccs = matrix.strongly_connected_components_all();
+    let mut unoptimized = MutSet::default();
+    let mut problems = std::vec::Vec::new();
+
+    for (group, _) in sccs.groups() {
+        let members: std::vec::Vec<usize> = group.iter_ones().collect();
+
+        if let [single] = members[..] {
+            if !matrix.get_row_col(single, single) {
+                continue;
+            }
+        }
+
+        let group_procs: std::vec::Vec<Proc<'a>> =
+            members.iter().map(|i| procs[&keys[*i]].clone()).collect();
+        let calls = |from: usize, to: usize| matrix.get_row_col(members[from], members[to]);
+
+        for (entry, index) in members.iter().enumerate() {
+            let proc = make_tail_recursive_entry(env, &group_procs, calls, entry, &mut |blocked| {
+                // Every specialization of a function reports the same call, so only keep the
+                // first, and leave out the builtins, which users can't change.
+                if !blocked.0.module_id().is_builtin() && unoptimized.insert(blocked) {
+                    let (caller, callee, reason) = blocked;
+                    let regions = tail_call_regions.get(&(caller, callee));
+
+                    for region in regions.into_iter().flatten() {
+                        problems.push(Problem::UnoptimizedTailCall {
+                            caller,
+                            callee,
+                            region: *region,
+                            reason,
+                        });
+                    }
+                }
+            });
+
+            procs.insert(keys[*index], proc);
+        }
+    }
+
+    problems
+}
+
+/// A function whose tail calls become jumps to the join point `id`
+struct Target<'a> {
+    name: LambdaName<'a>,
+    arguments: &'a [Layout<'a>],
+    result: Layout<'a>,
     id: JoinPointId,
-    needle: LambdaName,
-    stmt: Stmt<'a>,
-    args: &'a [(Layout<'a>, Symbol, Symbol)],
-    ret_layout: Layout,
-) -> Option<Stmt<'a>> {
-    let allocated = arena.alloc(stmt);
+}
+
+struct TailCall<'a> {
+    name: LambdaName<'a>,
+    arguments: &'a [Layout<'a>],
+    result: Layout<'a>,
+}
+
+impl<'a> TailCall<'a> {
+    fn key(&self) -> (Symbol, ProcLayout<'a>) {
+        let layout = ProcLayout {
+            arguments: self.arguments,
+            result: self.result,
+            captures_niche: self.name.captures_niche(),
+        };
+
+        (self.name.name(), layout)
+    }
+}
+
+fn arg_layouts<'a>(arena: &'a Bump, proc: &Proc<'a>) -> &'a [Layout<'a>] {
+    Vec::from_iter_in(proc.args.iter().map(|(layout, _)| *layout), arena).into_bump_slice()
+}
+
+/// Make the tail calls of `group[entry]` into jumps, to itself or to the bodies of the other
+/// functions of the group, which are added as join points.
+///
+/// Join points are only in scope in their own body and remainder, so the join points are nested
+/// with the functions that others call outermost. Calls that go inward stay calls, but the callee
+/// then loops in its own procedure, so the stack does not grow more than one frame for them.
+///
+/// The tail calls of the entry that stay calls are passed to `report` as the caller, the callee
+/// and why the callee's body couldn't be included.
+fn make_tail_recursive_entry<'a>(
+    env: &mut Env<'a, '_>,
+    group: &[Proc<'a>],
+    calls: impl Fn(usize, usize) -> bool,
+    entry: usize,
+    report: &mut impl FnMut((Symbol, Symbol, TailCallBlocker)),
+) -> Proc<'a> {
+    let arena = env.arena;
+    let entry_proc = &group[entry];
+
+    // Decide which other functions of the group can have their body added as a join point
+    let mut blocked: MutMap<usize, TailCallBlocker> = MutMap::default();
+    let mut included = std::vec::Vec::new();
+    let mut bound = MutSet::default();
+    bound_symbols_proc(entry_proc, &mut bound);
+
+    for (index, proc) in group.iter().enumerate() {
+        if index == entry {
+            continue;
+        }
+
+        let same_function = std::iter::once(entry)
+            .chain(included.iter().copied())
+            .any(|other| group[other].name.name() == proc.name.name());
+
+        if same_function {
+            blocked.insert(index, TailCallBlocker::OtherSpecialization);
+            continue;
+        }
+
+        let mut proc_bound = MutSet::default();
+        bound_symbols_proc(proc, &mut proc_bound);
+
+        if !proc_bound.is_disjoint(&bound) {
+            blocked.insert(index, TailCallBlocker::SharedSymbols);
+            continue;
+        }
+
+        bound.extend(proc_bound);
+        included.push(index);
+    }
+
+    // Order the join points so that callees are outside of (and so visible to) their callers
+    let mut order = std::vec::Vec::with_capacity(included.len());
+    let mut visited = MutSet::default();
+
+    for index in included.iter().copied() {
+        visit_callees_first(index, &included, &calls, &mut visited, &mut order);
+    }
+
+    let entry_id = match entry_proc.is_self_recursive {
+        SelfRecursive::SelfRecursive(id) => id,
+        SelfRecursive::NotSelfRecursive => JoinPointId(env.unique_symbol()),
+    };
+
+    let target = |proc: &Proc<'a>, id| Target {
+        name: proc.name,
+        arguments: arg_layouts(arena, proc),
+        result: proc.ret_layout,
+        id,
+    };
+
+    let mut targets = vec![target(entry_proc, entry_id)];
+    for index in order.iter() {
+        targets.push(target(&group[*index], JoinPointId(env.unique_symbol())));
+    }
+
+    // The body of the entry sees all join points, the others only their own and the outer ones
+    let mut bodies = std::vec::Vec::with_capacity(targets.len());
+    let mut used = std::vec::Vec::with_capacity(targets.len());
+
+    for (position, proc) in std::iter::once(entry_proc)
+        .chain(order.iter().map(|index| &group[*index]))
+        .enumerate()
+    {
+        let visible = if position == 0 {
+            &targets[..]
+        } else {
+            &targets[..=position]
+        };
+
+        let mut proc_used = MutSet::default();
+        let body: &'a Stmt<'a> = arena.alloc(proc.body.clone());
+        let body = insert_jumps(arena, body, visible, &mut proc_used).unwrap_or(body);
+
+        bodies.push(body);
+        used.push(proc_used);
+    }
+
+    // Only keep the join points that are jumped to from the entry, or from a kept join point
+    let mut kept = MutSet::default();
+    let mut stack = vec![0];
+
+    while let Some(position) = stack.pop() {
+        for (other, target) in targets.iter().enumerate() {
+            if used[position].contains(&target.id) && kept.insert(other) {
+                stack.push(other);
+            }
+        }
+    }
+
+    for_each_tail_call(bodies[0], &mut |callee| {
+        let callee_index = group.iter().position(|proc| {
+            proc.name == callee.name
+                && proc.ret_layout == callee.result
+                && arg_layouts(arena, proc) == callee.arguments
+        });
+
+        if let Some(reason) = callee_index.and_then(|index| blocked.get(&index)) {
+            report((entry_proc.name.name(), callee.name.name(), *reason));
+        }
+    });
+
+    let mut proc = entry_proc.clone();
+
+    if kept.is_empty() {
+        return proc;
+    }
+
+    let mut stmt = bodies[0];
+
+    for position in (1..targets.len()).rev() {
+        if !kept.contains(&position) {
+            continue;
+        }
+
+        let params = Vec::from_iter_in(
+            group[order[position - 1]]
+                .args
+                .iter()
+                .map(|(layout, symbol)| Param {
+                    symbol: *symbol,
+                    layout: *layout,
+                    borrow: true,
+                }),
+            arena,
+        )
+        .into_bump_slice();
+
+        stmt = arena.alloc(Stmt::Join {
+            id: targets[position].id,
+            parameters: params,
+            body: bodies[position],
+            remainder: stmt,
+        });
+    }
+
+    if kept.contains(&0) {
+        let mut args = Vec::with_capacity_in(proc.args.len(), arena);
+        let mut proc_args = Vec::with_capacity_in(proc.args.len(), arena);
+
+        for (layout, symbol) in proc.args {
+            let new = env.unique_symbol();
+            args.push((*layout, *symbol, new));
+            proc_args.push((*layout, new));
+        }
 
-    let new_stmt = insert_jumps(arena, allocated, id, needle, args, ret_layout)?;
+        proc.body = make_join(arena, entry_id, stmt, args.into_bump_slice());
+        proc.args = proc_args.into_bump_slice();
+    } else {
+        proc.body = stmt.clone();
+    }
+
+    proc
+}
 
-    // if we did not early-return, jumps were inserted, we must now add a join point
+/// Depth-first, so that each function comes after the functions it calls
+fn visit_callees_first(
+    index: usize,
+    included: &[usize],
+    calls: &impl Fn(usize, usize) -> bool,
+    visited: &mut MutSet<usize>,
+    order: &mut std::vec::Vec<usize>,
+) {
+    if !visited.insert(index) {
+        return;
+    }
+
+    for callee in included.iter().copied() {
+        if callee != index && calls(index, callee) {
+            visit_callees_first(callee, included, calls, visited, order);
+        }
+    }
+
+    order.push(index);
+}
+
+fn make_join<'a>(
+    arena: &'a Bump,
+    id: JoinPointId,
+    new_stmt: &'a Stmt<'a>,
+    args: &'a [(Layout<'a>, Symbol, Symbol)],
+) -> Stmt<'a> {
+    // jumps were inserted, we must now add a join point
 
     let params = Vec::from_iter_in(
         args.iter().map(|(layout, symbol, _)| Param {
@@ -57,32 +350,29 @@ pub fn make_tail_recursive<'a>(
 
     let jump = arena.alloc(Stmt::Jump(id, args));
 
-    let join = Stmt::Join {
+    Stmt::Join {
         id,
         remainder: jump,
         parameters: params,
         body: new_stmt,
-    };
-
-    Some(join)
+    }
 }
 
 fn insert_jumps<'a>(
     arena: &'a Bump,
     stmt: &'a Stmt<'a>,
-    goal_id: JoinPointId,
-    needle: LambdaName,
-    needle_arguments: &'a [(Layout<'a>, Symbol, Symbol)],
-    needle_result: Layout,
+    targets: &[Target<'a>],
+    used: &mut MutSet<JoinPointId>,
 ) -> Option<&'a Stmt<'a>> {
     use Stmt::*;
 
-    // to insert a tail-call, it must not just be a call to the function itself, but it must also
-    // have the same layout. In particular when lambda sets get involved, a self-recursive call may
-    // have a different type and should not be converted to a jump!
-    let is_equal_function = |function_name: LambdaName, arguments: &[_], result| {
-        let it = needle_arguments.iter().map(|t| &t.0);
-        needle == function_name && it.eq(arguments.iter()) && needle_result == result
+    // to insert a tail-call, it must not just be a call to a function of the group, but it must
+    // also have the same layout. In particular when lambda sets get involved, a self-recursive call
+    // may have a different type and should not be converted to a jump!
+    let target_of = |function_name: LambdaName, arguments: &[Layout], result| {
+        targets.iter().find(|target| {
+            target.name == function_name && target.arguments == arguments && target.result == result
+        })
     };
 
     match stmt {
@@ -100,23 +390,18 @@ fn insert_jumps<'a>(
             }),
             _,
             Stmt::Ret(rsym),
-        ) if symbol == rsym && is_equal_function(*fsym, arg_layouts, **ret_layout) => {
+        ) if symbol == rsym && target_of(*fsym, arg_layouts, **ret_layout).is_some() => {
             // replace the call and return with a jump
+            let target = target_of(*fsym, arg_layouts, **ret_layout).unwrap();
+            used.insert(target.id);
 
-            let jump = Stmt::Jump(goal_id, arguments);
+            let jump = Stmt::Jump(target.id, arguments);
 
             Some(arena.alloc(jump))
         }
 
         Let(symbol, expr, layout, cont) => {
-            let opt_cont = insert_jumps(
-                arena,
-                cont,
-                goal_id,
-                needle,
-                needle_arguments,
-                needle_result,
-            );
+            let opt_cont = insert_jumps(arena, cont, targets, used);
 
             if opt_cont.is_some() {
                 let cont = opt_cont.unwrap_or(cont);
@@ -133,22 +418,8 @@ fn insert_jumps<'a>(
             remainder,
             body: continuation,
         } => {
-            let opt_remainder = insert_jumps(
-                arena,
-                remainder,
-                goal_id,
-                needle,
-                needle_arguments,
-                needle_result,
-            );
-            let opt_continuation = insert_jumps(
-                arena,
-                continuation,
-                goal_id,
-                needle,
-                needle_arguments,
-                needle_result,
-            );
+            let opt_remainder = insert_jumps(arena, remainder, targets, used);
+            let opt_continuation = insert_jumps(arena, continuation, targets, used);
 
             if opt_remainder.is_some() || opt_continuation.is_some() {
                 let remainder = opt_remainder.unwrap_or(remainder);
@@ -171,27 +442,13 @@ fn insert_jumps<'a>(
             default_branch,
             ret_layout,
         } => {
-            let opt_default = insert_jumps(
-                arena,
-                default_branch.1,
-                goal_id,
-                needle,
-                needle_arguments,
-                needle_result,
-            );
+            let opt_default = insert_jumps(arena, default_branch.1, targets, used);
 
             let mut did_change = false;
 
             let opt_branches = Vec::from_iter_in(
                 branches.iter().map(|(label, info, branch)| {
-                    match insert_jumps(
-                        arena,
-                        branch,
-                        goal_id,
-                        needle,
-                        needle_arguments,
-                        needle_result,
-                    ) {
+                    match insert_jumps(arena, branch, targets, used) {
                         None => None,
                         Some(branch) => {
                             did_change = true;
@@ -235,19 +492,10 @@ fn insert_jumps<'a>(
                 None
             }
         }
-        Refcounting(modify, cont) => {
-            match insert_jumps(
-                arena,
-                cont,
-                goal_id,
-                needle,
-                needle_arguments,
-                needle_result,
-            ) {
-                Some(cont) => Some(arena.alloc(Refcounting(*modify, cont))),
-                None => None,
-            }
-        }
+        Refcounting(modify, cont) => match insert_jumps(arena, cont, targets, used) {
+            Some(cont) => Some(arena.alloc(Refcounting(*modify, cont))),
+            None => None,
+        },
 
         Expect {
             condition,
@@ -255,14 +503,7 @@ fn insert_jumps<'a>(
             lookups,
             variables,
             remainder,
-        } => match insert_jumps(
-            arena,
-            remainder,
-            goal_id,
-            needle,
-            needle_arguments,
-            needle_result,
-        ) {
+        } => match insert_jumps(arena, remainder, targets, used) {
             Some(cont) => Some(arena.alloc(Expect {
                 condition: *condition,
                 region: *region,
@@ -279,14 +520,7 @@ fn insert_jumps<'a>(
             lookups,
             variables,
             remainder,
-        } => match insert_jumps(
-            arena,
-            remainder,
-            goal_id,
-            needle,
-            needle_arguments,
-            needle_result,
-        ) {
+        } => match insert_jumps(arena, remainder, targets, used) {
             Some(cont) => Some(arena.alloc(ExpectFx {
                 condition: *condition,
                 region: *region,
@@ -302,3 +536,90 @@ fn insert_jumps<'a>(
         Crash(..) => None,
     }
 }
+
+/// Calls `f` with every call by name in tail position, i.e. whose result is returned directly.
+fn for_each_tail_call<'a>(stmt: &Stmt<'a>, f: &mut impl FnMut(TailCall<'a>)) {
+    use Stmt::*;
+
+    match stmt {
+        Let(
+            symbol,
+            Expr::Call(crate::ir::Call {
+                call_type:
+                    CallType::ByName {
+                        name,
+                        ret_layout,
+                        arg_layouts,
+                        ..
+                    },
+                ..
+            }),
+            _,
+            Stmt::Ret(rsym),
+        ) if symbol == rsym => f(TailCall {
+            name: *name,
+            arguments: arg_layouts,
+            result: **ret_layout,
+        }),
+        Let(_, _, _, cont) | Refcounting(_, cont) => for_each_tail_call(cont, f),
+        Expect { remainder, .. } | ExpectFx { remainder, .. } => for_each_tail_call(remainder, f),
+        Join {
+            body, remainder, ..
+        } => {
+            for_each_tail_call(body, f);
+            for_each_tail_call(remainder, f);
+        }
+        Switch {
+            branches,
+            default_branch,
+            ..
+        } => {
+            for (_, _, branch) in branches.iter() {
+                for_each_tail_call(branch, f);
+            }
+            for_each_tail_call(default_branch.1, f);
+        }
+        Ret(_) | Jump(_, _) | Crash(..) => {}
+    }
+}
+
+fn bound_symbols_proc(proc: &Proc, bound: &mut MutSet<Symbol>) {
+    bound.extend(proc.args.iter().map(|(_, symbol)| *symbol));
+    bound_symbols(&proc.body, bound);
+}
+
+/// All symbols that `stmt` defines, including the ids of its join points
+fn bound_symbols(stmt: &Stmt, bound: &mut MutSet<Symbol>) {
+    use Stmt::*;
+
+    match stmt {
+        Let(symbol, _, _, cont) => {
+            bound.insert(*symbol);
+            bound_symbols(cont, bound);
+        }
+        Refcounting(_, cont) => bound_symbols(cont, bound),
+        Expect { remainder, .. } | ExpectFx { remainder, .. } => bound_symbols(remainder, bound),
+        Join {
+            id,
+            parameters,
+            body,
+            remainder,
+        } => {
+            bound.insert(id.0);
+            bound.extend(parameters.iter().map(|param| param.symbol));
+            bound_symbols(body, bound);
+            bound_symbols(remainder, bound);
+        }
+        Switch {
+            branches,
+            default_branch,
+            ..
+        } => {
+            for (_, _, branch) in branches.iter() {
+                bound_symbols(branch, bound);
+            }
+            bound_symbols(default_branch.1, bound);
+        }
+        Ret(_) | Jump(_, _) | Crash(..) => {}
+    }
+}
@@ -7,6 +7,8 @@ use roc_parse::pattern::PatternType;
 use roc_region::all::{Loc, Region};
 use roc_types::types::AliasKind;
 
+use crate::lint::Lint;
+use crate::suggest;
 use crate::Severity;
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -21,6 +23,12 @@ pub enum BadPattern {
     Unsupported(PatternType),
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum NameKind {
+    Tag,
+    RecordField,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum ShadowKind {
     Variable,
@@ -32,14 +40,20 @@ pub enum ShadowKind {
 /// Problems that can occur in the course of canonicalization.
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum Problem {
-    UnusedDef(Symbol, Region),
+    /// The first region is the one to report, the second spans the whole definition, so that
+    /// deleting it removes the unused symbol. These are the same for destructuring defs, which
+    /// may bind other symbols that are used.
+    UnusedDef(Symbol, Region, Region),
+    /// The region spans the exposed entry in the import, which is what should be deleted.
     UnusedImport(Symbol, Region),
+    /// The region spans the whole import entry, which is what should be deleted.
     UnusedModuleImport(ModuleId, Region),
     ExposedButNotDefined(Symbol),
     UnknownGeneratesWith(Loc<Ident>),
     /// First symbol is the name of the closure with that argument
     /// Bool is whether the closure is anonymous
     /// Second symbol is the name of the argument that is unused
+    /// The region spans the argument's pattern; prefixing an identifier with `_` silences this.
     UnusedArgument(Symbol, bool, Symbol, Region),
     UnusedBranchDef(Symbol, Region),
     DefsOnlyUsedInRecursion(usize, Region),
@@ -204,6 +218,32 @@ pub enum Problem {
     OverAppliedCrash {
         region: Region,
     },
+    /// A tag or record field name that matches one used earlier in the module except for its
+    /// capitalization, e.g. `userName` and `username`. `original` is the earlier use.
+    NameDiffersOnlyInCase {
+        kind: NameKind,
+        original: Loc<Box<str>>,
+        similar: Loc<Box<str>>,
+    },
+    /// A call in tail position from `caller` to a function of the same (mutually) recursive
+    /// group that can't be made into a jump, so each call grows the stack. `region` is the
+    /// function of the call.
+    UnoptimizedTailCall {
+        caller: Symbol,
+        callee: Symbol,
+        region: Region,
+        reason: TailCallBlocker,
+    },
+}
+
+/// Why a tail call to a function of the same recursive group can't be made into a jump
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TailCallBlocker {
+    /// The callee is a specialization of the same function as the caller (or as another function
+    /// in the group) at a different layout, e.g. because a lambda set is different
+    OtherSpecialization,
+    /// The callee defines some of the same symbols as the caller, so its body can't be included
+    SharedSymbols,
 }
 
 impl Problem {
@@ -211,7 +251,7 @@ impl Problem {
         use Severity::{RuntimeError, Warning};
 
         match self {
-            Problem::UnusedDef(_, _) => Warning,
+            Problem::UnusedDef(_, _, _) => Warning,
             Problem::UnusedImport(_, _) => Warning,
             Problem::UnusedModuleImport(_, _) => Warning,
             Problem::ExposedButNotDefined(_) => RuntimeError,
@@ -268,7 +308,48 @@ impl Problem {
             // injecting a crash message
             Problem::UnappliedCrash { .. } => RuntimeError,
             Problem::OverAppliedCrash { .. } => RuntimeError,
+            Problem::NameDiffersOnlyInCase { .. } => Warning,
             Problem::DefsOnlyUsedInRecursion(_, _) => Warning,
+            Problem::UnoptimizedTailCall { .. } => Warning,
+        }
+    }
+
+    /// The lint that controls how this problem is reported, if it can be configured per project.
+    pub fn lint(&self) -> Option<Lint> {
+        match self {
+            Problem::UnusedDef(..) | Problem::UnusedBranchDef(..) => Some(Lint::UnusedDef),
+            Problem::UnusedImport(..) | Problem::UnusedModuleImport(..) => Some(Lint::UnusedImport),
+            Problem::UnusedArgument(..) => Some(Lint::UnusedArgument),
+            Problem::NameDiffersOnlyInCase { .. } => Some(Lint::NameDiffersOnlyInCase),
+            Problem::PhantomTypeArgument { .. } => Some(Lint::UnusedTypeParameter),
+            Problem::DuplicateRecordFieldValue { .. } => Some(Lint::DuplicateRecordField),
+            Problem::UnnecessaryOutputWildcard { .. } => Some(Lint::UnnecessaryWildcard),
+            Problem::UnoptimizedTailCall { .. } => Some(Lint::UnoptimizedTailCall),
+            _ => None,
+        }
+    }
+
+    /// Problems with the same cascade key most likely have the same root cause (e.g. a missing
+    /// import makes every use of the module an error), so they can be reported together.
+    pub fn cascade_key(&self) -> Option<String> {
+        match self {
+            Problem::RuntimeError(RuntimeError::LookupNotInScope(loc_ident, _)) => {
+                Some(format!("not in scope: {}", loc_ident.value.as_str()))
+            }
+            Problem::RuntimeError(RuntimeError::ModuleNotImported { module_name, .. }) => {
+                Some(format!("not imported: {}", module_name.as_str()))
+            }
+            Problem::RuntimeError(RuntimeError::ValueNotExposed {
+                module_name, ident, ..
+            }) => Some(format!(
+                "not exposed: {}.{}",
+                module_name.as_str(),
+                ident.as_str()
+            )),
+            Problem::RuntimeError(RuntimeError::OpaqueNotDefined { usage, .. }) => {
+                Some(format!("opaque not defined: {}", usage.value.as_str()))
+            }
+            _ => None,
         }
     }
 
@@ -280,7 +361,7 @@ impl Problem {
     /// on their Region being outside the expression currently being evaluated.
     pub fn region(&self) -> Option<Region> {
         match self {
-            Problem::UnusedDef(_, region)
+            Problem::UnusedDef(_, region, _)
             | Problem::Shadowing {
                 original_region: region,
                 ..
@@ -402,6 +483,11 @@ impl Problem {
             | Problem::UnnecessaryOutputWildcard { region }
             | Problem::OverAppliedCrash { region }
             | Problem::UnappliedCrash { region }
+            | Problem::NameDiffersOnlyInCase {
+                similar: Loc { region, .. },
+                ..
+            }
+            | Problem::UnoptimizedTailCall { region, .. }
             | Problem::DefsOnlyUsedInRecursion(_, region) => Some(*region),
             Problem::RuntimeError(RuntimeError::CircularDef(cycle_entries))
             | Problem::BadRecursion(cycle_entries) => {
@@ -417,6 +503,15 @@ impl Problem {
             | Problem::ExposedButNotDefined(_) => None,
         }
     }
+
+    /// Names the user may have meant instead of the one this problem is about, closest first.
+    /// Empty for problems that are not about a name that could not be found.
+    pub fn suggestions(&self) -> Vec<Box<str>> {
+        match self {
+            Problem::RuntimeError(runtime_error) => runtime_error.suggestions(),
+            _ => Vec::new(),
+        }
+    }
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -595,6 +690,47 @@ impl RuntimeError {
             err => format!("{:?}", err),
         }
     }
+
+    /// Names the user may have meant instead of the one that could not be found, closest first.
+    ///
+    /// These are ranked by edit distance against the names that were actually available, so they
+    /// are suitable both for "did you mean" hints in reports and for automatic fixes.
+    pub fn suggestions(&self) -> Vec<Box<str>> {
+        use RuntimeError::*;
+
+        match self {
+            LookupNotInScope(loc_ident, options) => suggest::best(
+                loc_ident.value.as_str(),
+                options.iter().map(|option| option.as_ref()),
+            ),
+            OpaqueNotDefined {
+                usage,
+                opaques_in_scope,
+                ..
+            } => suggest::best(
+                usage.value.as_str(),
+                opaques_in_scope.iter().map(|option| option.as_ref()),
+            ),
+            ValueNotExposed {
+                ident,
+                exposed_values,
+                ..
+            } => suggest::best(
+                ident.as_str(),
+                exposed_values.iter().map(|option| option.as_str()),
+            ),
+            ModuleNotImported {
+                module_name,
+                imported_modules,
+                module_exists: false,
+                ..
+            } => suggest::best(
+                module_name.as_str(),
+                imported_modules.iter().map(|option| option.as_ref()),
+            ),
+            _ => Vec::new(),
+        }
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
@@ -3,6 +3,8 @@
 // See github.com/roc-lang/roc/issues/800 for discussion of the large_enum_variant check.
 #![allow(clippy::large_enum_variant)]
 pub mod can;
+pub mod lint;
+pub mod suggest;
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum Severity {
@@ -0,0 +1,189 @@
+//! Per-project configuration of how seriously to take warnings.
+//!
+//! A project can put a [`LINTS_FILE_NAME`] file next to its main `.roc` file, with one
+//! `lint-name = allow|warn|deny` entry per line. Only problems that are warnings by default can
+//! be configured; errors always stay errors, since the program cannot run correctly with them.
+//!
+//! A `warnings = allow|warn|deny` entry sets the level of every lint that has no entry of its
+//! own, e.g. to fail CI on any warning. The same entries can be given on the command line, which
+//! take precedence over the project's file.
+use roc_collections::MutMap;
+
+pub const LINTS_FILE_NAME: &str = "roc-lints.txt";
+
+/// The name that configures every lint without an entry of its own
+pub const ALL_WARNINGS: &str = "warnings";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Lint {
+    UnusedDef,
+    UnusedImport,
+    UnusedArgument,
+    NameDiffersOnlyInCase,
+    UnusedTypeParameter,
+    DuplicateRecordField,
+    UnnecessaryWildcard,
+    RedundantPattern,
+    UnoptimizedTailCall,
+}
+
+impl Lint {
+    pub const ALL: &'static [Lint] = &[
+        Lint::UnusedDef,
+        Lint::UnusedImport,
+        Lint::UnusedArgument,
+        Lint::NameDiffersOnlyInCase,
+        Lint::UnusedTypeParameter,
+        Lint::DuplicateRecordField,
+        Lint::UnnecessaryWildcard,
+        Lint::RedundantPattern,
+        Lint::UnoptimizedTailCall,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnusedDef => "unused-def",
+            Lint::UnusedImport => "unused-import",
+            Lint::UnusedArgument => "unused-argument",
+            Lint::NameDiffersOnlyInCase => "name-differs-only-in-case",
+            Lint::UnusedTypeParameter => "unused-type-parameter",
+            Lint::DuplicateRecordField => "duplicate-record-field",
+            Lint::UnnecessaryWildcard => "unnecessary-wildcard",
+            Lint::RedundantPattern => "redundant-pattern",
+            Lint::UnoptimizedTailCall => "unoptimized-tail-call",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Lint> {
+        Lint::ALL.iter().copied().find(|lint| lint.name() == name)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LintLevel {
+    /// Don't report the problem at all
+    Allow,
+    /// Report the problem as a warning (the default)
+    Warn,
+    /// Report the problem as an error
+    Deny,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LintConfig {
+    levels: MutMap<Lint, LintLevel>,
+    /// The level of the lints without an entry of their own, see [`ALL_WARNINGS`]
+    warnings: Option<LintLevel>,
+}
+
+impl LintConfig {
+    /// Parse the contents of a [`LINTS_FILE_NAME`] file. Blank lines and lines starting with `#`
+    /// are ignored.
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut config = LintConfig::default();
+
+        for (index, line) in src.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            config
+                .add_entry(line)
+                .map_err(|err| format!("line {}: {}", index + 1, err))?;
+        }
+
+        Ok(config)
+    }
+
+    /// Add a `lint-name = level` entry (or a `warnings = level` one), replacing any earlier
+    /// entry for the same lint.
+    pub fn add_entry(&mut self, entry: &str) -> Result<(), String> {
+        let (name, level) = entry
+            .split_once('=')
+            .ok_or_else(|| "expected `lint-name = level`".to_string())?;
+
+        let level = match level.trim() {
+            "allow" => LintLevel::Allow,
+            "warn" => LintLevel::Warn,
+            "deny" => LintLevel::Deny,
+            other => {
+                return Err(format!(
+                    "unknown level `{}`, expected `allow`, `warn`, or `deny`",
+                    other
+                ))
+            }
+        };
+
+        match name.trim() {
+            ALL_WARNINGS => self.warnings = Some(level),
+            name => {
+                let lint =
+                    Lint::from_name(name).ok_or_else(|| format!("unknown lint `{}`", name))?;
+
+                self.levels.insert(lint, level);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Apply the entries of another configuration on top of this one, e.g. the command line's on
+    /// top of the project's.
+    pub fn merge(&mut self, overrides: &LintConfig) {
+        self.levels
+            .extend(overrides.levels.iter().map(|(k, v)| (*k, *v)));
+
+        if overrides.warnings.is_some() {
+            self.warnings = overrides.warnings;
+        }
+    }
+
+    pub fn level(&self, lint: Lint) -> LintLevel {
+        self.levels
+            .get(&lint)
+            .copied()
+            .or(self.warnings)
+            .unwrap_or(LintLevel::Warn)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn parse_levels() {
+        let src =
+            "# we like our scratch code\nunused-def = allow\n\nname-differs-only-in-case=deny\n";
+        let config = LintConfig::parse(src).unwrap();
+
+        assert_eq!(config.level(Lint::UnusedDef), LintLevel::Allow);
+        assert_eq!(config.level(Lint::NameDiffersOnlyInCase), LintLevel::Deny);
+        assert_eq!(config.level(Lint::UnusedImport), LintLevel::Warn);
+    }
+
+    #[test]
+    fn warnings_entry() {
+        let mut config = LintConfig::parse("warnings = deny\nunused-import = warn\n").unwrap();
+
+        assert_eq!(config.level(Lint::UnusedDef), LintLevel::Deny);
+        assert_eq!(config.level(Lint::UnusedImport), LintLevel::Warn);
+
+        let mut overrides = LintConfig::default();
+        overrides.add_entry("unused-def=allow").unwrap();
+        config.merge(&overrides);
+
+        assert_eq!(config.level(Lint::UnusedDef), LintLevel::Allow);
+        assert_eq!(config.level(Lint::UnusedArgument), LintLevel::Deny);
+    }
+
+    #[test]
+    fn parse_unknown_lint() {
+        assert_eq!(
+            LintConfig::parse("unused-everything = allow"),
+            Err("line 1: unknown lint `unused-everything`".to_string())
+        );
+    }
+}
@@ -0,0 +1,94 @@
+//! Ranks the names that are in scope by how close they are to a name that could not be found,
+//! so that problems can point at what the user most likely meant.
+
+/// The number of suggestions that are attached to a single problem.
+pub const MAX_SUGGESTIONS: usize = 4;
+
+/// Sort `options` so that the ones closest to `typo` come first.
+///
+/// The sort is stable, so options that are equally close keep their relative order.
+pub fn sort_by_key<T, F>(typo: &str, options: &mut [T], mut to_str: F)
+where
+    F: FnMut(&T) -> &str,
+{
+    options.sort_by_cached_key(|option| distance::damerau_levenshtein(typo, to_str(option)));
+}
+
+/// The best `MAX_SUGGESTIONS` candidates for `typo`, closest first.
+pub fn best<'a, I>(typo: &str, candidates: I) -> Vec<Box<str>>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut seen = std::collections::HashSet::new();
+    let mut candidates: Vec<&str> = candidates
+        .into_iter()
+        .filter(|candidate| seen.insert(*candidate))
+        .collect();
+
+    sort_by_key(typo, &mut candidates, |candidate| candidate);
+    candidates.truncate(MAX_SUGGESTIONS);
+
+    candidates.into_iter().map(Box::from).collect()
+}
+
+#[cfg(test)]
+mod test_suggest {
+    use super::best;
+    use crate::can::RuntimeError;
+    use roc_module::ident::{Ident, Lowercase, ModuleName};
+    use roc_region::all::Region;
+
+    #[test]
+    fn closest_first() {
+        let suggestions = best("lenght", ["append", "length", "len", "map"]);
+
+        assert_eq!(suggestions.first().map(|s| s.as_ref()), Some("length"));
+    }
+
+    #[test]
+    fn at_most_four() {
+        let suggestions = best("x", ["a", "b", "c", "d", "e", "f"]);
+
+        assert_eq!(suggestions.len(), super::MAX_SUGGESTIONS);
+    }
+
+    #[test]
+    fn ties_keep_their_order() {
+        let suggestions = best("ab", ["ac", "xb", "ac", "ad"]);
+
+        assert_eq!(
+            suggestions.iter().map(|s| s.as_ref()).collect::<Vec<_>>(),
+            ["ac", "xb", "ad"]
+        );
+    }
+
+    #[test]
+    fn value_not_exposed() {
+        let error = RuntimeError::ValueNotExposed {
+            module_name: ModuleName::from("List"),
+            ident: Ident::from("lenght"),
+            region: Region::zero(),
+            exposed_values: ["map", "len", "length"]
+                .into_iter()
+                .map(Lowercase::from)
+                .collect(),
+        };
+
+        assert_eq!(
+            error.suggestions().first().map(|s| s.as_ref()),
+            Some("length")
+        );
+    }
+
+    #[test]
+    fn no_typos_of_modules_that_exist() {
+        let error = RuntimeError::ModuleNotImported {
+            module_name: ModuleName::from("Task"),
+            imported_modules: ["Test".into()].into_iter().collect(),
+            region: Region::zero(),
+            module_exists: true,
+        };
+
+        assert!(error.suggestions().is_empty());
+    }
+}
@@ -25,7 +25,7 @@ use roc_error_macros::internal_error;
 use roc_module::ident::TagName;
 use roc_module::symbol::{ModuleId, Symbol};
 use roc_problem::can::CycleEntry;
-use roc_region::all::Loc;
+use roc_region::all::{Loc, Region};
 use roc_solve_problem::TypeError;
 use roc_types::subs::{
     self, AliasVariables, Content, Descriptor, FlatType, GetSubsSlice, LambdaSet, Mark,
@@ -37,8 +37,8 @@ use roc_types::types::{
     RecordField, Type, TypeExtension, TypeTag, Types, Uls,
 };
 use roc_unify::unify::{
-    unify, unify_introduced_ability_specialization, Env as UEnv, Mode, Obligated,
-    SpecializationLsetCollector, Unified::*,
+    unify, unify_introduced_ability_specialization, unify_with_annotation, Env as UEnv, Mode,
+    Obligated, SpecializationLsetCollector, Unified::*,
 };
 
 // Type checking system adapted from Elm by Evan Czaplicki, BSD-3-Clause Licensed
@@ -557,7 +557,7 @@ pub fn run(
 /// Modify an existing subs in-place instead
 #[allow(clippy::too_many_arguments)] // TODO: put params in a context/env var
 fn run_in_place(
-    _home: ModuleId, // TODO: remove me?
+    home: ModuleId,
     mut types: Types,
     constraints: &Constraints,
     problems: &mut Vec<TypeError>,
@@ -601,6 +601,8 @@ fn run_in_place(
         exposed_types: exposed_by_module,
     };
 
+    let mut typed_holes = Vec::new();
+
     let state = solve(
         &arena,
         types,
@@ -616,11 +618,80 @@ fn run_in_place(
         &mut obligation_cache,
         &mut awaiting_specializations,
         &derived_env,
+        &mut typed_holes,
     );
 
+    // Holes are reported last, once their types are as solved as they are going to get.
+    for typed_hole in typed_holes {
+        report_typed_hole(subs, problems, home, typed_hole);
+    }
+
     state.env
 }
 
+/// A typed hole we came across while solving, along with the environment it was in.
+struct PendingTypedHole {
+    variable: Variable,
+    region: Region,
+    env: Env,
+}
+
+/// The maximum number of values reported to fit a typed hole.
+const MAX_TYPED_HOLE_FITS: usize = 8;
+
+fn report_typed_hole(
+    subs: &mut Subs,
+    problems: &mut Vec<TypeError>,
+    home: ModuleId,
+    typed_hole: PendingTypedHole,
+) {
+    let PendingTypedHole {
+        variable,
+        region,
+        env,
+    } = typed_hole;
+
+    // If nothing constrains the hole, every value fits; listing them wouldn't help anyone.
+    let is_unconstrained = matches!(
+        subs.get_content_without_compacting(variable),
+        Content::FlexVar(_) | Content::Error
+    );
+
+    let mut fits = Vec::new();
+
+    if !is_unconstrained {
+        // Most recently introduced values first, since those are usually the closest to the hole.
+        for (symbol, candidate) in env.vars_by_symbol().collect::<Vec<_>>().into_iter().rev() {
+            if fits.len() == MAX_TYPED_HOLE_FITS {
+                break;
+            }
+
+            if symbol.module_id() != home {
+                continue;
+            }
+
+            // Try the candidate, then undo whatever unification did, fitting or not.
+            let snapshot = subs.snapshot();
+            let unified = unify(
+                &mut UEnv::new(subs),
+                candidate,
+                variable,
+                Mode::EQ,
+                Polarity::OF_VALUE,
+            );
+            subs.rollback_to(snapshot);
+
+            if matches!(unified, Success { .. }) {
+                fits.push(symbol);
+            }
+        }
+    }
+
+    let typ = subs.var_to_error_type(variable, Polarity::OF_VALUE);
+
+    problems.push(TypeError::TypedHole { region, typ, fits });
+}
+
 #[derive(Debug)]
 enum Work<'a> {
     Constraint {
@@ -674,6 +745,7 @@ fn solve(
     obligation_cache: &mut ObligationCache,
     awaiting_specializations: &mut AwaitingSpecializations,
     derived_env: &DerivedEnv,
+    typed_holes: &mut Vec<PendingTypedHole>,
 ) -> State {
     let initial = Work::Constraint {
         env: &Env::default(),
@@ -921,13 +993,26 @@ fn solve(
                     *expectation.get_type_ref(),
                 );
 
-                match unify(
-                    &mut UEnv::new(subs),
-                    actual,
-                    expected,
-                    Mode::EQ,
-                    Polarity::OF_VALUE,
-                ) {
+                // If the expected type comes from an annotation, keep the annotation intact when
+                // the value doesn't match it, so that later uses are still checked against it.
+                let unified = match expectation {
+                    Expected::FromAnnotation(..) => unify_with_annotation(
+                        &mut UEnv::new(subs),
+                        actual,
+                        expected,
+                        Mode::EQ,
+                        Polarity::OF_VALUE,
+                    ),
+                    Expected::NoExpectation(_) | Expected::ForReason(..) => unify(
+                        &mut UEnv::new(subs),
+                        actual,
+                        expected,
+                        Mode::EQ,
+                        Polarity::OF_VALUE,
+                    ),
+                };
+
+                match unified {
                     Success {
                         vars,
                         must_implement_ability,
@@ -1746,6 +1831,15 @@ fn solve(
                     cycle_mark.set_illegal(subs);
                 }
 
+                state
+            }
+            TypedHole(variable, region) => {
+                typed_holes.push(PendingTypedHole {
+                    variable: *variable,
+                    region: *region,
+                    env: env.clone(),
+                });
+
                 state
             }
         };
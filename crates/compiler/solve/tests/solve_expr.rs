@@ -188,7 +188,7 @@ mod solve_expr {
         can_problems.retain(|prob| {
             !matches!(
                 prob,
-                roc_problem::can::Problem::UnusedDef(_, _)
+                roc_problem::can::Problem::UnusedDef(_, _, _)
                     | roc_problem::can::Problem::UnusedBranchDef(..)
             )
         });
@@ -8583,4 +8583,23 @@ mod solve_expr {
         @"polyDbg : a -[[polyDbg(1)]]-> a"
         );
     }
+
+    #[test]
+    fn annotation_survives_mismatched_body() {
+        infer_queries!(
+            indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                f : Str -> Str
+                f = \_ -> 1
+
+                main = f "hello"
+                #^^^^{-1}
+                "#
+            ),
+        @"main : Str"
+        allow_errors: true
+        );
+    }
 }
@@ -1,10 +1,12 @@
 //! Provides types to describe problems that can occur during solving.
 use roc_can::expected::{Expected, PExpected};
+use roc_collections::all::SendMap;
 use roc_module::{ident::Lowercase, symbol::Symbol};
-use roc_problem::{can::CycleEntry, Severity};
+use roc_problem::{can::CycleEntry, lint::Lint, suggest, Severity};
 use roc_region::all::Region;
 
-use roc_types::types::{Category, ErrorType, PatternCategory};
+use roc_types::num::FloatWidth;
+use roc_types::types::{AliasKind, Category, ErrorType, PatternCategory, TypeExt};
 
 #[derive(Debug, Clone)]
 pub enum TypeError {
@@ -29,6 +31,20 @@ pub enum TypeError {
         expected_opaque: Symbol,
         found_opaque: Symbol,
     },
+    /// A typed hole (`_` in expression position), with the type it must have and the values in
+    /// scope that have that type.
+    TypedHole {
+        region: Region,
+        typ: ErrorType,
+        fits: Vec<Symbol>,
+    },
+    /// A fractional literal whose type is left to default, but that the configured default
+    /// fractional type cannot represent, e.g. `1e300` defaulting to `Dec`.
+    UnrepresentableDefault {
+        region: Region,
+        literal: Box<str>,
+        typ: FloatWidth,
+    },
 }
 
 impl TypeError {
@@ -48,8 +64,135 @@ impl TypeError {
             TypeError::Exhaustive(exhtv) => exhtv.severity(),
             TypeError::StructuralSpecialization { .. } => RuntimeError,
             TypeError::WrongSpecialization { .. } => RuntimeError,
+            TypeError::TypedHole { .. } => RuntimeError,
+            TypeError::UnrepresentableDefault { .. } => RuntimeError,
         }
     }
+
+    /// The region the problem is reported at, if it has one, e.g. for an editor to underline.
+    pub fn region(&self) -> Option<Region> {
+        use roc_exhaustive::Error as Exhaustive;
+
+        match self {
+            TypeError::BadExpr(region, ..)
+            | TypeError::BadPattern(region, ..)
+            | TypeError::CircularType(region, ..)
+            | TypeError::BadExprMissingAbility(region, ..)
+            | TypeError::BadPatternMissingAbility(region, ..)
+            | TypeError::StructuralSpecialization { region, .. }
+            | TypeError::WrongSpecialization { region, .. }
+            | TypeError::TypedHole { region, .. }
+            | TypeError::UnrepresentableDefault { region, .. }
+            | TypeError::Exhaustive(Exhaustive::Incomplete(region, ..))
+            | TypeError::Exhaustive(Exhaustive::Redundant {
+                branch_region: region,
+                ..
+            })
+            | TypeError::Exhaustive(Exhaustive::Unmatchable {
+                branch_region: region,
+                ..
+            })
+            | TypeError::UnfulfilledAbility(Unfulfilled::OpaqueUnderivable {
+                derive_region: region,
+                ..
+            }) => Some(*region),
+            TypeError::CircularDef(entries) => entries.first().map(|entry| entry.symbol_region),
+            TypeError::UnexposedLookup(_) | TypeError::UnfulfilledAbility(_) => None,
+        }
+    }
+
+    /// The lint that controls how this problem is reported, if it can be configured per project.
+    pub fn lint(&self) -> Option<Lint> {
+        match self {
+            TypeError::Exhaustive(
+                roc_exhaustive::Error::Redundant { .. } | roc_exhaustive::Error::Unmatchable { .. },
+            ) => Some(Lint::RedundantPattern),
+            _ => None,
+        }
+    }
+
+    /// When a mismatch looks like a misspelled record field or tag, the field or tag names the
+    /// user may have meant, closest first. Empty otherwise.
+    pub fn suggestions(&self) -> Vec<Box<str>> {
+        match self {
+            TypeError::BadExpr(_, _, found, expected) => {
+                typo_suggestions(found, expected.get_type_ref())
+            }
+            TypeError::BadPattern(_, _, found, expected) => {
+                typo_suggestions(found, expected.get_type_ref())
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+fn typo_suggestions(found: &ErrorType, expected: &ErrorType) -> Vec<Box<str>> {
+    use ErrorType::*;
+
+    match (
+        unwrap_structural_alias(found),
+        unwrap_structural_alias(expected),
+    ) {
+        (Record(found_fields, _), Record(expected_fields, expected_ext)) => {
+            typo_in(found_fields, expected_fields, expected_ext, |field| {
+                field.as_str()
+            })
+        }
+        (
+            TagUnion(found_tags, ..) | RecursiveTagUnion(_, found_tags, ..),
+            TagUnion(expected_tags, expected_ext, _)
+            | RecursiveTagUnion(_, expected_tags, expected_ext, _),
+        ) => typo_in(found_tags, expected_tags, expected_ext, |tag| {
+            tag.0.as_str()
+        }),
+        _ => Vec::new(),
+    }
+}
+
+/// A name that only one side has is probably a typo of a name that only the other side has.
+///
+/// An open expected type only demands the names it mentions (as in `record.field`), so the typo
+/// is on the expected side. Otherwise the expected type is the reference, and the typo is in
+/// what was found.
+fn typo_in<K, V, F>(
+    found: &SendMap<K, V>,
+    expected: &SendMap<K, V>,
+    expected_ext: &TypeExt,
+    to_str: F,
+) -> Vec<Box<str>>
+where
+    K: std::hash::Hash + Eq + Clone,
+    V: Clone,
+    F: Fn(&K) -> &str,
+{
+    let only_in = |this: &SendMap<K, V>, other: &SendMap<K, V>| -> Vec<K> {
+        this.keys()
+            .filter(|name| !other.contains_key(*name))
+            .cloned()
+            .collect()
+    };
+
+    let only_found = only_in(found, expected);
+    let only_expected = only_in(expected, found);
+
+    let (typos, candidates) = match expected_ext {
+        TypeExt::Closed => (only_found, only_expected),
+        TypeExt::FlexOpen(_) | TypeExt::RigidOpen(_) => (only_expected, only_found),
+    };
+
+    match typos.first() {
+        Some(typo) if !candidates.is_empty() => {
+            suggest::best(to_str(typo), candidates.iter().map(to_str))
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn unwrap_structural_alias(typ: &ErrorType) -> &ErrorType {
+    match typ {
+        ErrorType::Alias(_, _, real, AliasKind::Structural) => unwrap_structural_alias(real),
+        real => real,
+    }
 }
 
 #[derive(PartialEq, Eq, Debug, Clone)]
@@ -0,0 +1,7 @@
+use crate::helpers::differential;
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
+fn random_programs_match_reference_evaluator() {
+    differential::check_random_programs(differential::BACKEND, differential::run);
+}
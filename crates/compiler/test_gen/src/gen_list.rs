@@ -1286,6 +1286,85 @@ fn list_map_all_inline() {
     );
 }
 
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+fn list_map_then_list_map() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            offset : I64
+            offset = 10
+
+            factor : I64
+            factor = 3
+
+            [1, 2, 3]
+                |> List.map (\x -> x + offset)
+                |> List.map (\x -> x * factor)
+            "#
+        ),
+        RocList::from_slice(&[33, 36, 39]),
+        RocList<i64>
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+fn list_map_then_list_walk() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            offset : I64
+            offset = 10
+
+            [1, 2, 3]
+                |> List.map (\x -> x + offset)
+                |> List.walk 100 (\state, x -> state - x + offset)
+            "#
+        ),
+        94,
+        i64
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+fn list_map_then_list_keep_if() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            offset : I64
+            offset = 10
+
+            limit : I64
+            limit = 12
+
+            [1, 2, 3, 4]
+                |> List.map (\x -> x + offset)
+                |> List.keepIf (\x -> x != limit)
+            "#
+        ),
+        RocList::from_slice(&[11, 13, 14]),
+        RocList<i64>
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+fn list_map_of_strings_then_list_keep_if() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            ["a", "bb", "ccc"]
+                |> List.map (\s -> Str.concat s "!")
+                |> List.keepIf (\s -> Str.countGraphemes s > 2)
+            "#
+        ),
+        RocList::from_slice(&[RocStr::from("bb!"), RocStr::from("ccc!")]),
+        RocList<RocStr>
+    );
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
 fn list_map_closure() {
@@ -1940,7 +2019,7 @@ fn get_wildcard_empty_list() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn get_int_list_ok() {
     assert_evals_to!(
         indoc!(
@@ -1956,7 +2035,33 @@ fn get_int_list_ok() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+fn get_str_list_ok() {
+    // The element is shared by the list and the result, so its refcount must be incremented.
+    assert_evals_to!(
+        indoc!(
+            r#"
+                list = ["This string is long enough to be allocated on the heap", "short"]
+
+                first =
+                    when List.get list 0 is
+                        Ok val -> val
+                        Err _ -> ""
+
+                List.append list first
+            "#
+        ),
+        RocList::from_slice(&[
+            RocStr::from("This string is long enough to be allocated on the heap"),
+            RocStr::from("short"),
+            RocStr::from("This string is long enough to be allocated on the heap"),
+        ]),
+        RocList<RocStr>
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn get_int_list_oob() {
     assert_evals_to!(
         indoc!(
@@ -3459,6 +3564,26 @@ fn issue_3530_uninitialized_capacity_in_list_literal() {
     );
 }
 
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+fn identical_constant_list_literals_are_not_modified_in_place() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            x : List I64
+            x = [1, 2, 3]
+
+            y : List I64
+            y = List.set [1, 2, 3] 0 10
+
+            List.concat x y
+            "#
+        ),
+        RocList::from_slice(&[1, 2, 3, 10, 2, 3]),
+        RocList<i64>
+    );
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
 fn list_let_generalization() {
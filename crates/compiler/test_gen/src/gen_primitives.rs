@@ -515,6 +515,36 @@ fn factorial() {
     );
 }
 
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
+fn tail_call_swapping_arguments() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            fib = \n, a, b ->
+                when n is
+                    0 ->
+                        a
+
+                    _ ->
+                        fib (n - 1) b (a + b)
+
+            swap = \n, x, y ->
+                when n is
+                    0 ->
+                        x - y
+
+                    _ ->
+                        swap (n - 1) y x
+
+            fib 50 0 1 + swap 3 10 3
+            "#
+        ),
+        12586269025 - 7,
+        i64
+    );
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
 fn peano1() {
@@ -1035,7 +1065,7 @@ fn simple_closure() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn nested_closure() {
     assert_evals_to!(
         indoc!(
@@ -2411,7 +2441,7 @@ fn switch_fuse_rc_exhaustive() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn build_then_apply_closure() {
     assert_evals_to!(
         indoc!(
@@ -2532,7 +2562,7 @@ fn call_invalid_layout() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn increment_or_double_closure() {
     assert_evals_to!(
         indoc!(
@@ -2915,7 +2945,7 @@ fn value_not_exposed_hits_panic() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn mix_function_and_closure() {
     // see https://github.com/roc-lang/roc/pull/1706
     assert_evals_to!(
@@ -2941,7 +2971,7 @@ fn mix_function_and_closure() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn mix_function_and_closure_level_of_indirection() {
     // see https://github.com/roc-lang/roc/pull/1706
     assert_evals_to!(
@@ -3320,7 +3350,31 @@ fn box_and_unbox_tag_union() {
 }
 
 #[test]
-#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
+fn update_record_in_box() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            bump : Box { a : U8, b : Str } -> Box { a : U8, b : Str }
+            bump = \boxed ->
+                record = Box.unbox boxed
+
+                Box.box { record & a: record.a + 1 }
+
+            Box.box { a: 15u8, b: "a string that is long enough to be allocated" }
+                |> bump
+                |> bump
+                |> Box.unbox
+                |> .a
+            "#
+        ),
+        17,
+        u8
+    )
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-dev", feature = "gen-wasm"))]
 fn closure_called_in_its_defining_scope() {
     assert_evals_to!(
         indoc!(
@@ -3477,6 +3531,32 @@ fn mutual_recursion_top_level_defs() {
     )
 }
 
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
+fn mutual_tail_recursion_does_not_grow_stack() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+            app "test" provides [ main ] to "./platform"
+
+            isEven = \n ->
+                when n is
+                    0 -> Bool.true
+                    _ -> isOdd (n - 1)
+
+            isOdd = \n ->
+                when n is
+                    0 -> Bool.false
+                    _ -> isEven (n - 1)
+
+            main = isOdd 10_000_001
+            "#
+        ),
+        true,
+        bool
+    )
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-wasm"))]
 fn polymorphic_lambda_captures_polymorphic_value() {
@@ -4145,3 +4225,84 @@ fn issue_4712() {
         RocStr
     );
 }
+
+#[test]
+#[cfg(feature = "gen-wasm")]
+fn dbg_numbers_and_strings() {
+    use crate::helpers::wasm::assert_dbg_output;
+
+    assert_dbg_output!(
+        indoc!(
+            r#"
+            app "test" provides [main] to "./platform"
+
+            main =
+                x = 42u8
+                dbg x
+                dbg "hello"
+                dbg Bool.true
+                x
+            "#
+        ),
+        u8,
+        vec!["42", "\"hello\"", "Bool.true"]
+    );
+}
+
+#[test]
+#[cfg(feature = "gen-wasm")]
+fn dbg_records_lists_and_tags() {
+    use crate::helpers::wasm::assert_dbg_output;
+
+    assert_dbg_output!(
+        indoc!(
+            r#"
+            app "test" provides [main] to "./platform"
+
+            main =
+                record = { a: [1u8, 2, 3], b: "x" }
+                dbg record
+
+                result : Result U8 Str
+                result = Ok 5
+                dbg result
+
+                nested : List (Result (List U8) Str)
+                nested = [Ok [], Err "no"]
+                dbg nested
+
+                7u8
+            "#
+        ),
+        u8,
+        vec!["{ [1, 2, 3], \"x\" }", "#1 5", "[#1 [], #0 \"no\"]"]
+    );
+}
+
+#[test]
+#[cfg(feature = "gen-dev")]
+fn dev_object_does_not_depend_on_thread_count() {
+    use crate::helpers::dev::object_bytes;
+
+    // Enough procedures and refcounting helpers that they are split over several tasks.
+    let src = indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        main =
+            strs = List.map [1, 2, 3, 4] Num.toStr
+            records = List.map strs \s -> { s, len: Str.countUtf8Bytes s }
+            twos = List.keepIf strs \s -> s == "2"
+
+            List.len records + List.len twos + Str.countUtf8Bytes (Str.joinWith strs ", ")
+        "#
+    );
+
+    let serial = object_bytes(src, 1);
+    let parallel = object_bytes(src, 4);
+
+    assert!(
+        serial == parallel,
+        "objects built on 1 and 4 threads differ"
+    );
+}
@@ -496,3 +496,52 @@ fn boxed_str_dec() {
         ]
     );
 }
+
+#[test]
+#[cfg(any(feature = "gen-wasm"))]
+fn boxed_record_reused_when_unique() {
+    assert_refcounts!(
+        indoc!(
+            r#"
+                bump : Box { a : U64, b : U64 } -> Box { a : U64, b : U64 }
+                bump = \boxed ->
+                    record = Box.unbox boxed
+
+                    Box.box { record & a: record.a + 1 }
+
+                Box.box { a: 15, b: 2 }
+                    |> bump
+                    |> bump
+            "#
+        ),
+        Pointer,
+        &[
+            Live(1), // the only box, updated in place twice
+        ]
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-wasm"))]
+fn boxed_record_not_reused_when_shared() {
+    assert_refcounts!(
+        indoc!(
+            r#"
+                bump : Box { a : U64, b : U64 } -> Box { a : U64, b : U64 }
+                bump = \boxed ->
+                    record = Box.unbox boxed
+
+                    Box.box { record & a: record.a + 1 }
+
+                b = Box.box { a: 15, b: 2 }
+
+                Tuple (bump b) b
+            "#
+        ),
+        (Pointer, Pointer),
+        &[
+            Live(1), // b
+            Live(1), // bump b
+        ]
+    );
+}
@@ -315,6 +315,69 @@ fn when_on_just() {
     );
 }
 
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+fn when_on_nullable_pointer_nothing() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+                x : [Nothing, Just (Box I64)]
+                x = Nothing
+
+                when x is
+                    Just boxed -> Box.unbox boxed
+                    Nothing -> 0x2
+                "#
+        ),
+        2,
+        i64
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+fn when_on_nullable_pointer_just() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+                x : [Just (Box I64), Nothing]
+                x = Just (Box.box 41)
+
+                when x is
+                    Just boxed -> Box.unbox boxed + 0x1
+                    Nothing -> 0x2
+                "#
+        ),
+        42,
+        i64
+    );
+}
+
+#[test]
+#[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
+fn pass_nullable_pointer_to_function() {
+    assert_evals_to!(
+        indoc!(
+            r#"
+                app "test" provides [main] to "./platform"
+
+                Maybe a : [Nothing, Just (Box a)]
+
+                withDefault : Maybe I64, I64 -> I64
+                withDefault = \maybe, default ->
+                    when maybe is
+                        Just boxed -> Box.unbox boxed
+                        Nothing -> default
+
+                main =
+                    withDefault (Just (Box.box 40)) 0 + withDefault Nothing 2
+                "#
+        ),
+        42,
+        i64
+    );
+}
+
 #[test]
 #[cfg(any(feature = "gen-llvm", feature = "gen-wasm", feature = "gen-dev"))]
 fn when_on_result() {
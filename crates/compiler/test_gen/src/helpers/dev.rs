@@ -1,7 +1,7 @@
 use libloading::Library;
 use roc_build::link::{link, LinkType};
 use roc_builtins::bitcode;
-use roc_load::{EntryPoint, ExecutionMode, LoadConfig, Threading};
+use roc_load::{EntryPoint, ExecutionMode, FloatWidth, LoadConfig, OptLevel, Threading};
 use roc_mono::ir::SingleEntryPoint;
 use roc_packaging::cache::RocCacheDir;
 use roc_region::all::LineInfo;
@@ -53,11 +53,13 @@ pub fn helper(
     }
 
     let load_config = LoadConfig {
-        target_info: roc_target::TargetInfo::default_x86_64(),
+        target_info: roc_target::TargetInfo::from(&target_lexicon::Triple::host()),
         render: roc_reporting::report::RenderTarget::ColorTerminal,
         palette: roc_reporting::report::DEFAULT_PALETTE,
         threading: Threading::Single,
         exec_mode: ExecutionMode::Executable,
+        frac_default: FloatWidth::F64,
+        opt_level: OptLevel::Development,
     };
     let loaded = roc_load::load_and_monomorphize_from_str(
         arena,
@@ -132,7 +134,7 @@ pub fn helper(
     // errors whose reporting we delay (so we can see that code gen generates runtime errors)
     let mut delayed_errors = Vec::new();
 
-    for (home, (module_path, src)) in loaded.sources {
+    for (&home, (module_path, src)) in loaded.sources.iter() {
         use roc_reporting::report::{can_problem, type_problem, RocDocAllocator, DEFAULT_PALETTE};
 
         let can_problems = loaded.can_problems.remove(&home).unwrap_or_default();
@@ -155,10 +157,12 @@ pub fn helper(
         for problem in can_problems.into_iter() {
             // Ignore "unused" problems
             match problem {
-                UnusedDef(_, _) | UnusedArgument(_, _, _, _) | UnusedModuleImport(_, _) => {
+                UnusedDef(_, _, _) | UnusedArgument(_, _, _, _) | UnusedModuleImport(_, _) => {
                     delayed_errors.push(problem);
                     continue;
                 }
+                // A tail call that isn't optimized still runs, it just uses more stack
+                UnoptimizedTailCall { .. } => continue,
                 _ => {
                     let report = can_problem(&alloc, &line_info, module_path.clone(), problem);
                     let mut buf = String::new();
@@ -193,6 +197,9 @@ pub fn helper(
         exposed_to_host: exposed_to_host.values.keys().copied().collect(),
         lazy_literals,
         generate_allocators: true, // Needed for testing, since we don't have a platform
+        export_all_procs: true,
+        sources: &loaded.sources,
+        def_regions: &loaded.def_regions,
     };
 
     let target = target_lexicon::Triple::host();
@@ -235,6 +242,64 @@ pub fn helper(
     (main_fn_name, delayed_errors, lib)
 }
 
+/// Builds the object for a module with the dev backend, on a thread pool with the given number of threads.
+#[allow(dead_code)]
+pub fn object_bytes(src: &str, num_threads: usize) -> Vec<u8> {
+    let pool = rayon::ThreadPoolBuilder::new()
+        .num_threads(num_threads)
+        .build()
+        .expect("failed to build thread pool");
+
+    pool.install(|| {
+        let arena = bumpalo::Bump::new();
+        let load_config = LoadConfig {
+            target_info: roc_target::TargetInfo::from(&target_lexicon::Triple::host()),
+            render: roc_reporting::report::RenderTarget::ColorTerminal,
+            palette: roc_reporting::report::DEFAULT_PALETTE,
+            threading: Threading::Single,
+            exec_mode: ExecutionMode::Executable,
+            frac_default: FloatWidth::F64,
+            opt_level: OptLevel::Development,
+        };
+        let loaded = roc_load::load_and_monomorphize_from_str(
+            &arena,
+            std::path::PathBuf::from("Test.roc"),
+            src,
+            std::path::PathBuf::from("fake/test/path"),
+            Default::default(),
+            RocCacheDir::Disallowed,
+            load_config,
+        )
+        .expect("failed to load module");
+
+        let roc_load::MonomorphizedModule {
+            module_id,
+            procedures,
+            mut interns,
+            exposed_to_host,
+            layout_interner,
+            ..
+        } = loaded;
+
+        let env = roc_gen_dev::Env {
+            arena: &arena,
+            layout_interner: &layout_interner,
+            module_id,
+            exposed_to_host: exposed_to_host.values.keys().copied().collect(),
+            lazy_literals: true,
+            generate_allocators: true,
+            export_all_procs: true,
+            sources: &loaded.sources,
+            def_regions: &loaded.def_regions,
+        };
+
+        let target = target_lexicon::Triple::host();
+        roc_gen_dev::build_module(&env, &mut interns, &target, procedures)
+            .write()
+            .expect("failed to build output object")
+    })
+}
+
 #[allow(unused_macros)]
 macro_rules! assert_evals_to {
     ($src:expr, $expected:expr, $ty:ty) => {{
@@ -0,0 +1,1108 @@
+//! Differential testing of the backends.
+//!
+//! We generate random well-typed programs together with the values they should evaluate to,
+//! run them through whichever backend the tests were built for, and compare. Each program
+//! renders its result to a `Str`, so every backend returns the same Rust type no matter what
+//! the program computes.
+//!
+//! Every backend is compared against the same reference evaluator here, so a program that one
+//! backend gets wrong and another gets right shows up as a failure for the first one. When a
+//! program fails, we shrink it to the smallest one we can find that still fails, and report
+//! that along with the seed that reproduces it.
+//!
+//! The number of programs and the seed come from the environment:
+//!
+//! ```sh
+//! ROC_DIFFERENTIAL_CASES=500 ROC_DIFFERENTIAL_SEED=1234 cargo test-gen-dev differential
+//! ```
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+use roc_std::RocStr;
+
+/// How many programs to check when `ROC_DIFFERENTIAL_CASES` isn't set. Each one gets compiled
+/// from scratch, so keep this small enough for CI.
+const DEFAULT_CASES: u64 = 16;
+
+/// How many times we'll run the backend while shrinking a failing program
+const MAX_SHRINK_RUNS: usize = 200;
+
+/// How deeply expressions (and types) can nest
+const MAX_DEPTH: usize = 4;
+
+const STR_LITERALS: &[&str] = &[
+    "",
+    "a",
+    "Roc",
+    "hello, world",
+    "é∆",
+    "🐦",
+    // Too long to be a small string on any target
+    "the quick brown fox jumps over the lazy dog",
+];
+
+const INT_LITERALS: &[i64] = &[
+    0,
+    1,
+    -1,
+    2,
+    7,
+    42,
+    -100,
+    255,
+    65_536,
+    i64::MAX,
+    i64::MIN + 1,
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    I64,
+    Bool,
+    Str,
+    List(Box<Type>),
+    /// Fields are named `f0`, `f1`, ...
+    Record(Vec<Type>),
+    /// The payload of each tag. Tags are named `C0`, `C1`, ...
+    Union(Vec<Vec<Type>>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    I64(i64),
+    Bool(bool),
+    Str(String),
+    List(Vec<Value>),
+    Record(Vec<Value>),
+    Tag(usize, Vec<Value>),
+}
+
+/// A variable is only ever bound once, so it's identified by its number (`v0`, `v1`, ...).
+type Var = usize;
+
+/// Everything the generator knows how to build. Each expression knows its own type, so
+/// shrinking can replace it with another expression of the same type.
+#[derive(Debug, Clone)]
+pub enum Expr {
+    Int(i64),
+    Bool(bool),
+    Str(&'static str),
+    Var(Var, Type),
+    AddWrap(Box<Expr>, Box<Expr>),
+    SubWrap(Box<Expr>, Box<Expr>),
+    MulWrap(Box<Expr>, Box<Expr>),
+    Lt(Box<Expr>, Box<Expr>),
+    Eq(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    If(Box<Expr>, Box<Expr>, Box<Expr>),
+    StrConcat(Box<Expr>, Box<Expr>),
+    IntToStr(Box<Expr>),
+    CountUtf8Bytes(Box<Expr>),
+    /// A list literal, along with its element type in case it's empty
+    List(Type, Vec<Expr>),
+    ListAppend(Box<Expr>, Box<Expr>),
+    ListConcat(Box<Expr>, Box<Expr>),
+    ListLen(Box<Expr>),
+    ListMap {
+        list: Box<Expr>,
+        var: Var,
+        body: Box<Expr>,
+    },
+    Record(Vec<Expr>),
+    Field(Box<Expr>, usize),
+    /// A tag, along with the union it belongs to
+    Tag(Type, usize, Vec<Expr>),
+    /// `(\var -> body) value`, so that the body is a closure capturing whatever it uses
+    Let {
+        var: Var,
+        value: Box<Expr>,
+        body: Box<Expr>,
+    },
+}
+
+/// A small, seedable PRNG (splitmix64), so that failures can be reproduced from their seed
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+        z ^ (z >> 31)
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn one_in(&mut self, n: usize) -> bool {
+        self.below(n) == 0
+    }
+
+    fn pick<T: Copy>(&mut self, items: &[T]) -> T {
+        items[self.below(items.len())]
+    }
+}
+
+struct Generator {
+    rng: Rng,
+    next_var: Var,
+    /// The variables in scope, innermost last
+    scope: Vec<(Var, Type)>,
+}
+
+impl Generator {
+    fn new(seed: u64) -> Self {
+        Self {
+            rng: Rng(seed),
+            next_var: 0,
+            scope: Vec::new(),
+        }
+    }
+
+    fn gen_type(&mut self, depth: usize) -> Type {
+        let choices = if depth == 0 { 3 } else { 6 };
+
+        match self.rng.below(choices) {
+            0 => Type::I64,
+            1 => Type::Bool,
+            2 => Type::Str,
+            3 => Type::List(Box::new(self.gen_type(depth - 1))),
+            4 => {
+                let len = 1 + self.rng.below(3);
+
+                Type::Record((0..len).map(|_| self.gen_type(depth - 1)).collect())
+            }
+            _ => {
+                let tags = 1 + self.rng.below(3);
+                let union = (0..tags)
+                    .map(|_| {
+                        let arity = self.rng.below(3);
+
+                        (0..arity).map(|_| self.gen_type(depth - 1)).collect()
+                    })
+                    .collect();
+
+                Type::Union(union)
+            }
+        }
+    }
+
+    fn fresh_var(&mut self) -> Var {
+        self.next_var += 1;
+        self.next_var - 1
+    }
+
+    fn gen_expr(&mut self, ty: &Type, depth: usize) -> Expr {
+        if depth == 0 || self.rng.one_in(4) {
+            return self.gen_leaf(ty);
+        }
+
+        let depth = depth - 1;
+
+        // These work for any type
+        match self.rng.below(8) {
+            0 => {
+                return Expr::If(
+                    Box::new(self.gen_expr(&Type::Bool, depth)),
+                    Box::new(self.gen_expr(ty, depth)),
+                    Box::new(self.gen_expr(ty, depth)),
+                );
+            }
+            1 => {
+                let value_ty = self.gen_type(1);
+                let value = self.gen_expr(&value_ty, depth);
+                let var = self.fresh_var();
+
+                self.scope.push((var, value_ty));
+                let body = self.gen_expr(ty, depth);
+                self.scope.pop();
+
+                return Expr::Let {
+                    var,
+                    value: Box::new(value),
+                    body: Box::new(body),
+                };
+            }
+            2 => {
+                let mut fields: Vec<Type> =
+                    (0..self.rng.below(3)).map(|_| self.gen_type(1)).collect();
+                let index = self.rng.below(fields.len() + 1);
+                fields.insert(index, ty.clone());
+
+                let record = self.gen_expr(&Type::Record(fields), depth);
+
+                return Expr::Field(Box::new(record), index);
+            }
+            _ => {}
+        }
+
+        match ty {
+            Type::I64 => match self.rng.below(6) {
+                0 => Expr::AddWrap(
+                    Box::new(self.gen_expr(ty, depth)),
+                    Box::new(self.gen_expr(ty, depth)),
+                ),
+                1 => Expr::SubWrap(
+                    Box::new(self.gen_expr(ty, depth)),
+                    Box::new(self.gen_expr(ty, depth)),
+                ),
+                2 => Expr::MulWrap(
+                    Box::new(self.gen_expr(ty, depth)),
+                    Box::new(self.gen_expr(ty, depth)),
+                ),
+                3 => Expr::CountUtf8Bytes(Box::new(self.gen_expr(&Type::Str, depth))),
+                4 => {
+                    let elem_ty = self.gen_type(1);
+
+                    Expr::ListLen(Box::new(
+                        self.gen_expr(&Type::List(Box::new(elem_ty)), depth),
+                    ))
+                }
+                _ => self.gen_leaf(ty),
+            },
+            Type::Bool => match self.rng.below(3) {
+                0 => Expr::Lt(
+                    Box::new(self.gen_expr(&Type::I64, depth)),
+                    Box::new(self.gen_expr(&Type::I64, depth)),
+                ),
+                1 => {
+                    let operand_ty = self.gen_type(2);
+
+                    Expr::Eq(
+                        Box::new(self.gen_expr(&operand_ty, depth)),
+                        Box::new(self.gen_expr(&operand_ty, depth)),
+                    )
+                }
+                _ => Expr::Not(Box::new(self.gen_expr(ty, depth))),
+            },
+            Type::Str => match self.rng.below(2) {
+                0 => Expr::StrConcat(
+                    Box::new(self.gen_expr(ty, depth)),
+                    Box::new(self.gen_expr(ty, depth)),
+                ),
+                _ => Expr::IntToStr(Box::new(self.gen_expr(&Type::I64, depth))),
+            },
+            Type::List(elem_ty) => match self.rng.below(4) {
+                0 => {
+                    let len = self.rng.below(4);
+                    let elems = (0..len).map(|_| self.gen_expr(elem_ty, depth)).collect();
+
+                    Expr::List(elem_ty.as_ref().clone(), elems)
+                }
+                1 => Expr::ListAppend(
+                    Box::new(self.gen_expr(ty, depth)),
+                    Box::new(self.gen_expr(elem_ty, depth)),
+                ),
+                2 => Expr::ListConcat(
+                    Box::new(self.gen_expr(ty, depth)),
+                    Box::new(self.gen_expr(ty, depth)),
+                ),
+                _ => {
+                    let arg_ty = self.gen_type(1);
+                    let list = self.gen_expr(&Type::List(Box::new(arg_ty.clone())), depth);
+                    let var = self.fresh_var();
+
+                    self.scope.push((var, arg_ty));
+                    let body = self.gen_expr(elem_ty, depth);
+                    self.scope.pop();
+
+                    Expr::ListMap {
+                        list: Box::new(list),
+                        var,
+                        body: Box::new(body),
+                    }
+                }
+            },
+            Type::Record(fields) => Expr::Record(
+                fields
+                    .iter()
+                    .map(|field| self.gen_expr(field, depth))
+                    .collect(),
+            ),
+            Type::Union(tags) => {
+                let tag_id = self.rng.below(tags.len());
+                let payload = tags[tag_id]
+                    .iter()
+                    .map(|arg| self.gen_expr(arg, depth))
+                    .collect();
+
+                Expr::Tag(ty.clone(), tag_id, payload)
+            }
+        }
+    }
+
+    /// An expression of the given type that doesn't nest any further
+    fn gen_leaf(&mut self, ty: &Type) -> Expr {
+        let in_scope: Vec<Var> = self
+            .scope
+            .iter()
+            .filter(|(_, var_ty)| var_ty == ty)
+            .map(|(var, _)| *var)
+            .collect();
+
+        if !in_scope.is_empty() && self.rng.one_in(2) {
+            return Expr::Var(self.rng.pick(&in_scope), ty.clone());
+        }
+
+        match ty {
+            Type::I64 => Expr::Int(self.rng.pick(INT_LITERALS)),
+            Type::Bool => Expr::Bool(self.rng.one_in(2)),
+            Type::Str => Expr::Str(self.rng.pick(STR_LITERALS)),
+            Type::List(elem_ty) => {
+                let len = self.rng.below(3);
+
+                Expr::List(
+                    elem_ty.as_ref().clone(),
+                    (0..len).map(|_| self.gen_leaf(elem_ty)).collect(),
+                )
+            }
+            Type::Record(fields) => {
+                Expr::Record(fields.iter().map(|field| self.gen_leaf(field)).collect())
+            }
+            Type::Union(tags) => {
+                let tag_id = self.rng.below(tags.len());
+                let payload = tags[tag_id].iter().map(|arg| self.gen_leaf(arg)).collect();
+
+                Expr::Tag(ty.clone(), tag_id, payload)
+            }
+        }
+    }
+}
+
+impl Type {
+    /// The simplest expression of this type
+    fn simplest(&self) -> Expr {
+        match self {
+            Type::I64 => Expr::Int(0),
+            Type::Bool => Expr::Bool(false),
+            Type::Str => Expr::Str(""),
+            Type::List(elem_ty) => Expr::List(elem_ty.as_ref().clone(), Vec::new()),
+            Type::Record(fields) => Expr::Record(fields.iter().map(Type::simplest).collect()),
+            Type::Union(tags) => {
+                let (tag_id, payload) = tags
+                    .iter()
+                    .enumerate()
+                    .min_by_key(|(_, payload)| payload.len())
+                    .expect("unions have at least one tag");
+
+                Expr::Tag(
+                    self.clone(),
+                    tag_id,
+                    payload.iter().map(Type::simplest).collect(),
+                )
+            }
+        }
+    }
+}
+
+impl Expr {
+    pub fn ty(&self) -> Type {
+        match self {
+            Expr::Int(_)
+            | Expr::AddWrap(..)
+            | Expr::SubWrap(..)
+            | Expr::MulWrap(..)
+            | Expr::CountUtf8Bytes(_)
+            | Expr::ListLen(_) => Type::I64,
+            Expr::Bool(_) | Expr::Lt(..) | Expr::Eq(..) | Expr::Not(_) => Type::Bool,
+            Expr::Str(_) | Expr::StrConcat(..) | Expr::IntToStr(_) => Type::Str,
+            Expr::Var(_, ty) | Expr::Tag(ty, _, _) => ty.clone(),
+            Expr::If(_, then, _) => then.ty(),
+            Expr::List(elem_ty, _) => Type::List(Box::new(elem_ty.clone())),
+            Expr::ListAppend(list, _) | Expr::ListConcat(list, _) => list.ty(),
+            Expr::ListMap { body, .. } => Type::List(Box::new(body.ty())),
+            Expr::Record(fields) => Type::Record(fields.iter().map(Expr::ty).collect()),
+            Expr::Field(record, index) => match record.ty() {
+                Type::Record(mut fields) => fields.swap_remove(*index),
+                other => unreachable!("field access on a {:?}", other),
+            },
+            Expr::Let { body, .. } => body.ty(),
+        }
+    }
+
+    fn mentions(&self, var: Var) -> bool {
+        let mut mentioned = false;
+
+        self.visit_children(&mut |child| mentioned |= child.mentions(var));
+
+        mentioned || matches!(self, Expr::Var(v, _) if *v == var)
+    }
+
+    fn visit_children(&self, f: &mut impl FnMut(&Expr)) {
+        match self {
+            Expr::Int(_) | Expr::Bool(_) | Expr::Str(_) | Expr::Var(..) => {}
+            Expr::Not(a)
+            | Expr::IntToStr(a)
+            | Expr::CountUtf8Bytes(a)
+            | Expr::ListLen(a)
+            | Expr::Field(a, _) => f(a),
+            Expr::AddWrap(a, b)
+            | Expr::SubWrap(a, b)
+            | Expr::MulWrap(a, b)
+            | Expr::Lt(a, b)
+            | Expr::Eq(a, b)
+            | Expr::StrConcat(a, b)
+            | Expr::ListAppend(a, b)
+            | Expr::ListConcat(a, b)
+            | Expr::ListMap {
+                list: a, body: b, ..
+            }
+            | Expr::Let {
+                value: a, body: b, ..
+            } => {
+                f(a);
+                f(b);
+            }
+            Expr::If(a, b, c) => {
+                f(a);
+                f(b);
+                f(c);
+            }
+            Expr::List(_, elems) | Expr::Record(elems) | Expr::Tag(_, _, elems) => {
+                elems.iter().for_each(f)
+            }
+        }
+    }
+
+    fn children_mut(&mut self) -> Vec<&mut Expr> {
+        match self {
+            Expr::Int(_) | Expr::Bool(_) | Expr::Str(_) | Expr::Var(..) => Vec::new(),
+            Expr::Not(a)
+            | Expr::IntToStr(a)
+            | Expr::CountUtf8Bytes(a)
+            | Expr::ListLen(a)
+            | Expr::Field(a, _) => vec![a.as_mut()],
+            Expr::AddWrap(a, b)
+            | Expr::SubWrap(a, b)
+            | Expr::MulWrap(a, b)
+            | Expr::Lt(a, b)
+            | Expr::Eq(a, b)
+            | Expr::StrConcat(a, b)
+            | Expr::ListAppend(a, b)
+            | Expr::ListConcat(a, b)
+            | Expr::ListMap {
+                list: a, body: b, ..
+            }
+            | Expr::Let {
+                value: a, body: b, ..
+            } => vec![a.as_mut(), b.as_mut()],
+            Expr::If(a, b, c) => vec![a.as_mut(), b.as_mut(), c.as_mut()],
+            Expr::List(_, elems) | Expr::Record(elems) | Expr::Tag(_, _, elems) => {
+                elems.iter_mut().collect()
+            }
+        }
+    }
+
+    /// Smaller expressions of the same type, most promising first. Variables stay in scope:
+    /// a body is only pulled out of its binding when it doesn't use the bound variable.
+    fn shrinks(&self) -> Vec<Expr> {
+        let ty = self.ty();
+        let mut candidates = Vec::new();
+
+        let simplest = ty.simplest();
+
+        if simplest.size() < self.size() {
+            candidates.push(simplest);
+        }
+
+        let mut same_type_child = |child: &Expr| {
+            if child.ty() == ty {
+                candidates.push(child.clone());
+            }
+        };
+
+        match self {
+            Expr::ListMap { .. } => {}
+            Expr::Let { var, body, .. } => {
+                if !body.mentions(*var) {
+                    same_type_child(body);
+                }
+            }
+            other => other.visit_children(&mut same_type_child),
+        }
+
+        // Drop list elements one at a time
+        if let Expr::List(elem_ty, elems) = self {
+            for index in 0..elems.len() {
+                let mut elems = elems.clone();
+                elems.remove(index);
+
+                candidates.push(Expr::List(elem_ty.clone(), elems));
+            }
+        }
+
+        // Shrink one child, keeping the rest
+        let mut children = Vec::new();
+        self.visit_children(&mut |child| children.push(child.clone()));
+
+        for (index, child) in children.iter().enumerate() {
+            for shrunk in child.shrinks() {
+                let mut candidate = self.clone();
+                *candidate.children_mut().swap_remove(index) = shrunk;
+
+                candidates.push(candidate);
+            }
+        }
+
+        candidates
+    }
+
+    fn size(&self) -> usize {
+        let mut size = 1;
+
+        self.visit_children(&mut |child| size += child.size());
+
+        size
+    }
+
+    /// What the program should evaluate to, according to Roc's semantics
+    pub fn eval(&self, env: &mut Vec<(Var, Value)>) -> Value {
+        let int = |expr: &Expr, env: &mut Vec<(Var, Value)>| match expr.eval(env) {
+            Value::I64(n) => n,
+            other => unreachable!("expected an I64, got {:?}", other),
+        };
+        let bool = |expr: &Expr, env: &mut Vec<(Var, Value)>| match expr.eval(env) {
+            Value::Bool(b) => b,
+            other => unreachable!("expected a Bool, got {:?}", other),
+        };
+        let str = |expr: &Expr, env: &mut Vec<(Var, Value)>| match expr.eval(env) {
+            Value::Str(s) => s,
+            other => unreachable!("expected a Str, got {:?}", other),
+        };
+        let list = |expr: &Expr, env: &mut Vec<(Var, Value)>| match expr.eval(env) {
+            Value::List(elems) => elems,
+            other => unreachable!("expected a List, got {:?}", other),
+        };
+
+        match self {
+            Expr::Int(n) => Value::I64(*n),
+            Expr::Bool(b) => Value::Bool(*b),
+            Expr::Str(s) => Value::Str(s.to_string()),
+            Expr::Var(var, _) => env
+                .iter()
+                .rev()
+                .find(|(v, _)| v == var)
+                .map(|(_, value)| value.clone())
+                .expect("variables are always in scope"),
+            Expr::AddWrap(a, b) => Value::I64(int(a, env).wrapping_add(int(b, env))),
+            Expr::SubWrap(a, b) => Value::I64(int(a, env).wrapping_sub(int(b, env))),
+            Expr::MulWrap(a, b) => Value::I64(int(a, env).wrapping_mul(int(b, env))),
+            Expr::Lt(a, b) => Value::Bool(int(a, env) < int(b, env)),
+            Expr::Eq(a, b) => Value::Bool(a.eval(env) == b.eval(env)),
+            Expr::Not(a) => Value::Bool(!bool(a, env)),
+            Expr::If(cond, then, otherwise) => {
+                if bool(cond, env) {
+                    then.eval(env)
+                } else {
+                    otherwise.eval(env)
+                }
+            }
+            Expr::StrConcat(a, b) => Value::Str(str(a, env) + &str(b, env)),
+            Expr::IntToStr(a) => Value::Str(int(a, env).to_string()),
+            Expr::CountUtf8Bytes(a) => Value::I64(str(a, env).len() as i64),
+            Expr::List(_, elems) => Value::List(elems.iter().map(|elem| elem.eval(env)).collect()),
+            Expr::ListAppend(a, b) => {
+                let mut elems = list(a, env);
+                elems.push(b.eval(env));
+
+                Value::List(elems)
+            }
+            Expr::ListConcat(a, b) => {
+                let mut elems = list(a, env);
+                elems.extend(list(b, env));
+
+                Value::List(elems)
+            }
+            Expr::ListLen(a) => Value::I64(list(a, env).len() as i64),
+            Expr::ListMap { list: a, var, body } => {
+                let mapped = list(a, env)
+                    .into_iter()
+                    .map(|elem| {
+                        env.push((*var, elem));
+                        let value = body.eval(env);
+                        env.pop();
+
+                        value
+                    })
+                    .collect();
+
+                Value::List(mapped)
+            }
+            Expr::Record(fields) => {
+                Value::Record(fields.iter().map(|field| field.eval(env)).collect())
+            }
+            Expr::Field(record, index) => match record.eval(env) {
+                Value::Record(mut fields) => fields.swap_remove(*index),
+                other => unreachable!("expected a record, got {:?}", other),
+            },
+            Expr::Tag(_, tag_id, payload) => {
+                Value::Tag(*tag_id, payload.iter().map(|arg| arg.eval(env)).collect())
+            }
+            Expr::Let { var, value, body } => {
+                let value = value.eval(env);
+
+                env.push((*var, value));
+                let result = body.eval(env);
+                env.pop();
+
+                result
+            }
+        }
+    }
+
+    /// Write the expression as Roc source. Everything but literals and variables gets
+    /// parenthesized, so the output never depends on precedence or indentation.
+    fn to_roc(&self, buf: &mut String) {
+        use std::fmt::Write;
+
+        fn call(buf: &mut String, function: &str, args: &[&Expr]) {
+            buf.push('(');
+            buf.push_str(function);
+
+            for arg in args {
+                buf.push(' ');
+                arg.to_roc(buf);
+            }
+
+            buf.push(')');
+        }
+
+        match self {
+            Expr::Int(n) => write!(buf, "({}i64)", n).unwrap(),
+            Expr::Bool(true) => buf.push_str("Bool.true"),
+            Expr::Bool(false) => buf.push_str("Bool.false"),
+            Expr::Str(s) => write!(buf, "\"{}\"", s).unwrap(),
+            Expr::Var(var, _) => write!(buf, "v{}", var).unwrap(),
+            Expr::AddWrap(a, b) => call(buf, "Num.addWrap", &[a, b]),
+            Expr::SubWrap(a, b) => call(buf, "Num.subWrap", &[a, b]),
+            Expr::MulWrap(a, b) => call(buf, "Num.mulWrap", &[a, b]),
+            Expr::Lt(a, b) => call(buf, "Num.isLt", &[a, b]),
+            Expr::Eq(a, b) => call(buf, "Bool.isEq", &[a, b]),
+            Expr::Not(a) => call(buf, "Bool.not", &[a]),
+            Expr::If(cond, then, otherwise) => {
+                buf.push_str("(if ");
+                cond.to_roc(buf);
+                buf.push_str(" then ");
+                then.to_roc(buf);
+                buf.push_str(" else ");
+                otherwise.to_roc(buf);
+                buf.push(')');
+            }
+            Expr::StrConcat(a, b) => call(buf, "Str.concat", &[a, b]),
+            Expr::IntToStr(a) => call(buf, "Num.toStr", &[a]),
+            Expr::CountUtf8Bytes(a) => {
+                buf.push_str("(Num.toI64 ");
+                call(buf, "Str.countUtf8Bytes", &[a]);
+                buf.push(')');
+            }
+            Expr::List(elem_ty, elems) if elems.is_empty() => {
+                // A bare `[]` could leave the element type unconstrained (e.g. if all we do
+                // with it is compare it to another empty list), so give it one to drop.
+                let one_elem = Expr::List(elem_ty.clone(), vec![elem_ty.simplest()]);
+
+                call(buf, "List.dropLast", &[&one_elem]);
+            }
+            Expr::List(_, elems) => {
+                buf.push('[');
+
+                for (index, elem) in elems.iter().enumerate() {
+                    if index > 0 {
+                        buf.push_str(", ");
+                    }
+
+                    elem.to_roc(buf);
+                }
+
+                buf.push(']');
+            }
+            Expr::ListAppend(a, b) => call(buf, "List.append", &[a, b]),
+            Expr::ListConcat(a, b) => call(buf, "List.concat", &[a, b]),
+            Expr::ListLen(a) => {
+                buf.push_str("(Num.toI64 ");
+                call(buf, "List.len", &[a]);
+                buf.push(')');
+            }
+            Expr::ListMap { list, var, body } => {
+                buf.push_str("(List.map ");
+                list.to_roc(buf);
+                buf.push(' ');
+                write_lambda(buf, *var, body);
+                buf.push(')');
+            }
+            Expr::Record(fields) => {
+                buf.push_str("{ ");
+
+                for (index, field) in fields.iter().enumerate() {
+                    if index > 0 {
+                        buf.push_str(", ");
+                    }
+
+                    write!(buf, "f{}: ", index).unwrap();
+                    field.to_roc(buf);
+                }
+
+                buf.push_str(" }");
+            }
+            Expr::Field(record, index) => call(buf, &format!(".f{}", index), &[record]),
+            Expr::Tag(_, tag_id, payload) if payload.is_empty() => {
+                write!(buf, "C{}", tag_id).unwrap()
+            }
+            Expr::Tag(_, tag_id, payload) => {
+                let payload: Vec<&Expr> = payload.iter().collect();
+
+                call(buf, &format!("C{}", tag_id), &payload)
+            }
+            Expr::Let { var, value, body } => {
+                buf.push('(');
+                write_lambda(buf, *var, body);
+                buf.push(' ');
+                value.to_roc(buf);
+                buf.push(')');
+            }
+        }
+    }
+}
+
+/// `(\v0 -> body)`, or `(\_ -> body)` if the body doesn't use the variable, since the
+/// backends' test helpers report unused arguments.
+fn write_lambda(buf: &mut String, var: Var, body: &Expr) {
+    if body.mentions(var) {
+        buf.push_str(&format!("(\\v{} -> ", var));
+    } else {
+        buf.push_str("(\\_ -> ");
+    }
+
+    body.to_roc(buf);
+    buf.push(')');
+}
+
+impl Value {
+    /// Must match what the program's render functions produce
+    fn render(&self) -> String {
+        fn join<'a>(values: impl Iterator<Item = &'a Value>) -> Vec<String> {
+            values.map(Value::render).collect()
+        }
+
+        match self {
+            Value::I64(n) => n.to_string(),
+            Value::Bool(true) => "Bool.true".to_string(),
+            Value::Bool(false) => "Bool.false".to_string(),
+            Value::Str(s) => format!("\"{}\"", s),
+            Value::List(elems) => format!("[{}]", join(elems.iter()).join(", ")),
+            Value::Record(fields) => {
+                let fields: Vec<String> = join(fields.iter())
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, field)| format!("f{}: {}", index, field))
+                    .collect();
+
+                format!("{{ {} }}", fields.join(", "))
+            }
+            Value::Tag(tag_id, payload) if payload.is_empty() => format!("C{}", tag_id),
+            Value::Tag(tag_id, payload) => {
+                let mut parts = vec![format!("C{}", tag_id)];
+                parts.extend(join(payload.iter()));
+
+                format!("({})", parts.join(" "))
+            }
+        }
+    }
+}
+
+/// The functions the program uses to turn its result into a `Str`, one per type
+#[derive(Default)]
+struct Renderers {
+    types: Vec<Type>,
+    defs: Vec<String>,
+}
+
+impl Renderers {
+    fn name(&mut self, ty: &Type) -> String {
+        if let Some(index) = self.types.iter().position(|known| known == ty) {
+            return format!("render{}", index);
+        }
+
+        let body = match ty {
+            Type::I64 => "Num.toStr v".to_string(),
+            Type::Bool => "if v then \"Bool.true\" else \"Bool.false\"".to_string(),
+            Type::Str => "Str.joinWith [\"\\\"\", v, \"\\\"\"] \"\"".to_string(),
+            Type::List(elem_ty) => {
+                let elem = self.name(elem_ty);
+
+                format!(
+                    "Str.joinWith [\"[\", Str.joinWith (List.map v {}) \", \", \"]\"] \"\"",
+                    elem
+                )
+            }
+            Type::Record(fields) => {
+                let mut parts = Vec::new();
+
+                for (index, field_ty) in fields.iter().enumerate() {
+                    let separator = if index == 0 { "{ " } else { ", " };
+                    let field = self.name(field_ty);
+
+                    parts.push(format!("\"{}f{}: \"", separator, index));
+                    parts.push(format!("{} (.f{} v)", field, index));
+                }
+
+                parts.push("\" }\"".to_string());
+
+                format!("Str.joinWith [{}] \"\"", parts.join(", "))
+            }
+            Type::Union(tags) => {
+                let mut branches = String::new();
+
+                for (tag_id, payload) in tags.iter().enumerate() {
+                    let mut pattern = format!("C{}", tag_id);
+                    let args: Vec<String> = (0..payload.len())
+                        .map(|index| format!("p{}", index))
+                        .collect();
+
+                    for arg in args.iter() {
+                        pattern.push(' ');
+                        pattern.push_str(arg);
+                    }
+
+                    let rendered = if payload.is_empty() {
+                        format!("\"C{}\"", tag_id)
+                    } else {
+                        let mut parts = vec![format!("\"(C{}\"", tag_id)];
+
+                        for (arg, arg_ty) in args.iter().zip(payload) {
+                            let render_arg = self.name(arg_ty);
+
+                            parts.push("\" \"".to_string());
+                            parts.push(format!("{} {}", render_arg, arg));
+                        }
+
+                        parts.push("\")\"".to_string());
+
+                        format!("Str.joinWith [{}] \"\"", parts.join(", "))
+                    };
+
+                    branches.push_str(&format!("\n        {} -> {}", pattern, rendered));
+                }
+
+                format!("\n    when v is{}", branches)
+            }
+        };
+
+        let index = self.types.len();
+        self.types.push(ty.clone());
+        self.defs.push(format!(
+            "render{} = \\v ->{}{}",
+            index,
+            separator(&body),
+            body
+        ));
+
+        format!("render{}", index)
+    }
+}
+
+fn separator(body: &str) -> &'static str {
+    if body.starts_with('\n') {
+        ""
+    } else {
+        " "
+    }
+}
+
+/// A generated program: an expression, and the functions that render its value
+pub struct Program {
+    pub expr: Expr,
+}
+
+impl Program {
+    pub fn generate(seed: u64) -> Self {
+        let mut generator = Generator::new(seed);
+        let ty = generator.gen_type(2);
+        let expr = generator.gen_expr(&ty, MAX_DEPTH);
+
+        Self { expr }
+    }
+
+    /// The program as an expression the test helpers can compile
+    pub fn to_roc(&self) -> String {
+        let mut renderers = Renderers::default();
+        let render = renderers.name(&self.expr.ty());
+
+        let mut src = renderers.defs.join("\n\n");
+        src.push_str("\n\n");
+        src.push_str(&render);
+        src.push(' ');
+        self.expr.to_roc(&mut src);
+        src.push('\n');
+
+        src
+    }
+
+    pub fn expected(&self) -> String {
+        self.expr.eval(&mut Vec::new()).render()
+    }
+}
+
+/// Run a backend on the program, turning compiler panics into errors
+fn run_program(
+    run: &impl Fn(&str) -> Result<String, String>,
+    program: &Program,
+) -> Result<String, String> {
+    let src = program.to_roc();
+
+    match catch_unwind(AssertUnwindSafe(|| run(&src))) {
+        Ok(result) => result,
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|s| s.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "<non-string panic>".to_string());
+
+            Err(format!("panicked: {}", message))
+        }
+    }
+}
+
+fn fails(run: &impl Fn(&str) -> Result<String, String>, program: &Program) -> bool {
+    run_program(run, program) != Ok(program.expected())
+}
+
+/// Greedily replace the program with smaller failing ones until none of its shrinks fail
+fn shrink(run: &impl Fn(&str) -> Result<String, String>, mut program: Program) -> Program {
+    let mut runs = 0;
+
+    'outer: while runs < MAX_SHRINK_RUNS {
+        for candidate in program.expr.shrinks() {
+            if runs >= MAX_SHRINK_RUNS {
+                break 'outer;
+            }
+
+            let candidate = Program { expr: candidate };
+            runs += 1;
+
+            if fails(run, &candidate) {
+                program = candidate;
+                continue 'outer;
+            }
+        }
+
+        break;
+    }
+
+    program
+}
+
+fn env_u64(name: &str) -> Option<u64> {
+    let value = std::env::var(name).ok()?;
+
+    match value.parse() {
+        Ok(n) => Some(n),
+        Err(_) => panic!("{} must be a number, but it was {:?}", name, value),
+    }
+}
+
+/// Check randomly generated programs against the reference evaluator, panicking with the
+/// smallest failing program we can find if the backend gets one wrong.
+pub fn check_random_programs(backend: &str, run: impl Fn(&str) -> Result<String, String>) {
+    let seed = env_u64("ROC_DIFFERENTIAL_SEED").unwrap_or(0);
+    let cases = env_u64("ROC_DIFFERENTIAL_CASES").unwrap_or(DEFAULT_CASES);
+
+    for case in 0..cases {
+        let program_seed = seed.wrapping_add(case);
+        let program = Program::generate(program_seed);
+
+        if !fails(&run, &program) {
+            continue;
+        }
+
+        let program = shrink(&run, program);
+        let actual = match run_program(&run, &program) {
+            Ok(value) => value,
+            Err(problem) => problem,
+        };
+
+        panic!(
+            "The {} backend disagrees with the reference evaluator.\n\n\
+            Reproduce with ROC_DIFFERENTIAL_SEED={} ROC_DIFFERENTIAL_CASES=1\n\n\
+            Smallest failing program:\n\n{}\n\
+            Expected: {}\n\
+            Got:      {}",
+            backend,
+            program_seed,
+            program.to_roc(),
+            program.expected(),
+            actual,
+        );
+    }
+}
+
+#[cfg(all(feature = "gen-llvm", not(feature = "gen-llvm-wasm")))]
+pub const BACKEND: &str = "LLVM";
+
+#[cfg(all(feature = "gen-llvm", not(feature = "gen-llvm-wasm")))]
+pub fn run(src: &str) -> Result<String, String> {
+    use crate::helpers::llvm::{helper, try_run_lib_function, HelperConfig, OPT_LEVEL};
+    use roc_gen_llvm::llvm::build::LlvmBackendMode;
+
+    let arena = bumpalo::Bump::new();
+    let context = inkwell::context::Context::create();
+
+    let config = HelperConfig {
+        mode: LlvmBackendMode::GenTest,
+        add_debug_info: false,
+        ignore_problems: false,
+        opt_level: OPT_LEVEL,
+    };
+
+    let (main_fn_name, errors, lib) = helper(&arena, config, src, &context);
+
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+
+    try_run_lib_function::<RocStr>(main_fn_name, &lib)
+        .map(|output| output.as_str().to_string())
+        .map_err(|(message, _)| message)
+}
+
+#[cfg(feature = "gen-llvm-wasm")]
+pub const BACKEND: &str = "LLVM wasm";
+
+#[cfg(feature = "gen-llvm-wasm")]
+pub fn run(src: &str) -> Result<String, String> {
+    crate::helpers::llvm::assert_wasm_evals_to_help::<RocStr>(src, false)
+        .map(|output| output.as_str().to_string())
+}
+
+#[cfg(feature = "gen-dev")]
+pub const BACKEND: &str = "dev";
+
+#[cfg(feature = "gen-dev")]
+pub fn run(src: &str) -> Result<String, String> {
+    let arena = bumpalo::Bump::new();
+    let (main_fn_name, errors, lib) = crate::helpers::dev::helper(&arena, src, true, false);
+
+    if !errors.is_empty() {
+        return Err(format!("{:?}", errors));
+    }
+
+    unsafe {
+        let main: libloading::Symbol<unsafe extern "C" fn() -> RocStr> = lib
+            .get(main_fn_name.as_bytes())
+            .map_err(|err| format!("Unable to JIT compile `{}`: {}", main_fn_name, err))?;
+
+        Ok(main().as_str().to_string())
+    }
+}
+
+#[cfg(feature = "gen-wasm")]
+pub const BACKEND: &str = "wasm";
+
+#[cfg(feature = "gen-wasm")]
+pub fn run(src: &str) -> Result<String, String> {
+    crate::helpers::wasm::assert_evals_to_help::<RocStr>(src, std::marker::PhantomData)
+        .map(|output| output.as_str().to_string())
+}
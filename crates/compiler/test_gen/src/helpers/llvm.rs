@@ -6,8 +6,13 @@ use libloading::Library;
 use roc_build::link::llvm_module_to_dylib;
 use roc_collections::all::MutSet;
 use roc_gen_llvm::llvm::externs::add_default_roc_externs;
-use roc_gen_llvm::{llvm::build::LlvmBackendMode, run_roc::RocCallResult};
-use roc_load::{EntryPoint, ExecutionMode, LoadConfig, LoadMonomorphizedError, Threading};
+use roc_gen_llvm::{
+    llvm::build::{DebugSources, LlvmBackendMode},
+    run_roc::RocCallResult,
+};
+use roc_load::{
+    EntryPoint, ExecutionMode, FloatWidth, LoadConfig, LoadMonomorphizedError, Threading,
+};
 use roc_mono::ir::{CrashTag, OptLevel, SingleEntryPoint};
 use roc_packaging::cache::RocCacheDir;
 use roc_region::all::LineInfo;
@@ -74,6 +79,8 @@ fn create_llvm_module<'a>(
         palette: DEFAULT_PALETTE,
         threading: Threading::Single,
         exec_mode: ExecutionMode::Executable,
+        frac_default: FloatWidth::F64,
+        opt_level: OPT_LEVEL,
     };
     let loaded = roc_load::load_and_monomorphize_from_str(
         arena,
@@ -108,11 +115,11 @@ fn create_llvm_module<'a>(
     // errors whose reporting we delay (so we can see that code gen generates runtime errors)
     let mut delayed_errors = Vec::new();
 
-    for (home, (module_path, src)) in loaded.sources {
+    for (home, (module_path, src)) in loaded.sources.iter() {
         use roc_reporting::report::{can_problem, type_problem, RocDocAllocator};
 
-        let can_problems = loaded.can_problems.remove(&home).unwrap_or_default();
-        let type_problems = loaded.type_problems.remove(&home).unwrap_or_default();
+        let can_problems = loaded.can_problems.remove(home).unwrap_or_default();
+        let type_problems = loaded.type_problems.remove(home).unwrap_or_default();
 
         let error_count = can_problems.len() + type_problems.len();
 
@@ -120,18 +127,18 @@ fn create_llvm_module<'a>(
             continue;
         }
 
-        let line_info = LineInfo::new(&src);
+        let line_info = LineInfo::new(src);
         let src_lines: Vec<&str> = src.split('\n').collect();
         let palette = DEFAULT_PALETTE;
 
         // Report parsing and canonicalization problems
-        let alloc = RocDocAllocator::new(&src_lines, home, &interns);
+        let alloc = RocDocAllocator::new(&src_lines, *home, &interns);
 
         use roc_problem::can::Problem::*;
         for problem in can_problems.into_iter() {
             match problem {
                 // Ignore "unused" problems
-                UnusedDef(_, _)
+                UnusedDef(_, _, _)
                 | UnusedArgument(_, _, _, _)
                 | UnusedModuleImport(_, _)
                 | RuntimeError(_)
@@ -147,6 +154,8 @@ fn create_llvm_module<'a>(
                 }
                 // We should be able to compile even when abilities are used as types
                 AbilityUsedAsType(..) => {}
+                // A tail call that isn't optimized still runs, it just uses more stack
+                UnoptimizedTailCall { .. } => {}
                 _ => {
                     let report = can_problem(&alloc, &line_info, module_path.clone(), problem);
                     let mut buf = String::new();
@@ -228,6 +237,7 @@ fn create_llvm_module<'a>(
         mode: config.mode,
         // important! we don't want any procedures to get the C calling convention
         exposed_to_host: MutSet::default(),
+        debug_sources: DebugSources::new(&loaded.sources, &loaded.def_regions),
     };
 
     // strip Zig debug stuff
@@ -4,6 +4,7 @@ pub mod platform_functions;
 
 #[cfg(feature = "gen-dev")]
 pub mod dev;
+pub mod differential;
 pub mod from_wasm32_memory;
 #[cfg(feature = "gen-llvm")]
 pub mod llvm;
@@ -1,10 +1,10 @@
 use super::RefCount;
 use crate::helpers::from_wasm32_memory::FromWasm32Memory;
 use bumpalo::Bump;
-use roc_collections::all::MutSet;
+use roc_collections::all::{MutMap, MutSet};
 use roc_gen_wasm::wasm32_result::Wasm32Result;
 use roc_gen_wasm::DEBUG_SETTINGS;
-use roc_load::{ExecutionMode, LoadConfig, Threading};
+use roc_load::{ExecutionMode, FloatWidth, LoadConfig, OptLevel, Threading};
 use roc_packaging::cache::RocCacheDir;
 use roc_reporting::report::DEFAULT_PALETTE_HTML;
 use roc_std::RocStr;
@@ -92,6 +92,8 @@ fn compile_roc_to_wasm_bytes<'a, T: Wasm32Result>(
         palette: DEFAULT_PALETTE_HTML,
         threading: Threading::Single,
         exec_mode: ExecutionMode::Executable,
+        frac_default: FloatWidth::F64,
+        opt_level: OptLevel::Development,
     };
     let loaded = roc_load::load_and_monomorphize_from_str(
         arena,
@@ -129,6 +131,7 @@ fn compile_roc_to_wasm_bytes<'a, T: Wasm32Result>(
         module_id,
         exposed_to_host,
         stack_bytes: roc_gen_wasm::Env::DEFAULT_STACK_BYTES,
+        dbg_locations: MutMap::default(),
     };
 
     let host_module = roc_gen_wasm::parse_host(env.arena, host_bytes).unwrap_or_else(|e| {
@@ -181,8 +184,36 @@ where
     run_wasm_test_bytes::<T>(TEST_WRAPPER_NAME, wasm_bytes)
 }
 
+#[allow(dead_code)]
+pub fn assert_dbg_output_help<T>(src: &str, phantom: PhantomData<T>) -> Result<Vec<String>, String>
+where
+    T: FromWasm32Memory + Wasm32Result,
+{
+    let arena = bumpalo::Bump::new();
+
+    let wasm_bytes = crate::helpers::wasm::compile_to_wasm_bytes(&arena, src, phantom);
+
+    let require_relocatable = false;
+    let module = WasmModule::preload(&arena, &wasm_bytes, require_relocatable)
+        .map_err(|e| format!("{:?}", e))?;
+
+    let dispatcher = TestDispatcher {
+        wasi: wasi::WasiDispatcher::default(),
+        dbgs: Vec::new(),
+    };
+    let is_debug_mode = roc_debug_flags::dbg_set!(roc_debug_flags::ROC_LOG_WASM_INTERP);
+    let mut inst = Instance::for_module(&arena, &module, dispatcher, is_debug_mode)?;
+
+    // Run the test, ignoring the result
+    inst.call_export(TEST_WRAPPER_NAME, [])?;
+
+    Ok(std::mem::take(&mut inst.import_dispatcher.dbgs))
+}
+
 struct TestDispatcher<'a> {
     wasi: WasiDispatcher<'a>,
+    /// The text of each value the app passed to `dbg`
+    dbgs: Vec<String>,
 }
 
 impl<'a> ImportDispatcher for TestDispatcher<'a> {
@@ -205,6 +236,14 @@ impl<'a> ImportDispatcher for TestDispatcher<'a> {
                 tag => format!(r#"Got an invald panic tag: "{}""#, tag),
             };
             panic!("{}", msg)
+        } else if module_name == "env" && function_name == "send_dbg_msg_to_rust" {
+            let loc_ptr = arguments[0].expect_i32().unwrap();
+            let msg_ptr = arguments[1].expect_i32().unwrap();
+            let loc = RocStr::decode(memory, loc_ptr as _);
+            let msg = RocStr::decode(memory, msg_ptr as _);
+            eprintln!("[{}] {}", loc, msg);
+            self.dbgs.push(msg.to_string());
+            None
         } else {
             panic!(
                 "TestDispatcher does not implement {}.{}",
@@ -238,6 +277,7 @@ where
 {
     let dispatcher = TestDispatcher {
         wasi: wasi::WasiDispatcher::default(),
+        dbgs: Vec::new(),
     };
     let is_debug_mode = roc_debug_flags::dbg_set!(roc_debug_flags::ROC_LOG_WASM_INTERP);
     let mut inst = Instance::for_module(&arena, &module, dispatcher, is_debug_mode)?;
@@ -267,6 +307,7 @@ where
 
     let dispatcher = TestDispatcher {
         wasi: wasi::WasiDispatcher::default(),
+        dbgs: Vec::new(),
     };
     let is_debug_mode = roc_debug_flags::dbg_set!(roc_debug_flags::ROC_LOG_WASM_INTERP);
     let mut inst = Instance::for_module(&arena, &module, dispatcher, is_debug_mode)?;
@@ -408,5 +449,20 @@ macro_rules! assert_refcounts {
 #[allow(unused_imports)]
 pub(crate) use assert_evals_to;
 
+#[allow(unused_macros)]
+macro_rules! assert_dbg_output {
+    // We need the result type to generate the test_wrapper, even though we ignore the value
+    ($src: expr, $ty: ty, $expected: expr) => {{
+        let phantom = std::marker::PhantomData;
+        match $crate::helpers::wasm::assert_dbg_output_help::<$ty>($src, phantom) {
+            Err(msg) => panic!("{}", msg),
+            Ok(actual) => assert_eq!(actual, $expected),
+        }
+    }};
+}
+
 #[allow(unused_imports)]
 pub(crate) use assert_refcounts;
+
+#[allow(unused_imports)]
+pub(crate) use assert_dbg_output;
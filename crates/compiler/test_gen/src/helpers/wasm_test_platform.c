@@ -134,6 +134,15 @@ void roc_panic(void* msg, unsigned int tag_id)
 
 //--------------------------
 
+extern void send_dbg_msg_to_rust(void* loc, void* msg);
+
+void roc_dbg(void* loc, void* msg)
+{
+    send_dbg_msg_to_rust(loc, msg);
+}
+
+//--------------------------
+
 void roc_memcpy(void *dest, const void *src, size_t n)
 {
     memcpy(dest, src, n);
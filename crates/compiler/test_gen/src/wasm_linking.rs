@@ -19,7 +19,7 @@ use roc_mono::ir::{
 };
 use roc_mono::layout::{Builtin, CapturesNiche, LambdaName, Layout, STLayoutInterner};
 use roc_wasm_interp::{wasi, ImportDispatcher, Instance, WasiDispatcher};
-use roc_wasm_module::{Value, WasmModule};
+use roc_wasm_module::{HostIndex, Value, WasmModule};
 
 const LINKING_TEST_HOST_WASM: &str = "build/wasm_linking_test_host.wasm";
 const LINKING_TEST_HOST_NATIVE: &str = "build/wasm_linking_test_host";
@@ -163,6 +163,7 @@ impl<'a> BackendInputs<'a> {
             module_id,
             exposed_to_host,
             stack_bytes: Env::DEFAULT_STACK_BYTES,
+            dbg_locations: MutMap::default(),
         };
 
         // Identifier stuff for the backend
@@ -375,3 +376,39 @@ fn test_linking_with_dce() {
         dump_filename,
     );
 }
+
+#[test]
+fn test_indexed_host_matches_parsed_host() {
+    let arena = Bump::new();
+    let host_bytes = fs::read(LINKING_TEST_HOST_WASM).unwrap();
+    let index_bytes = roc_gen_wasm::index_host(&host_bytes).unwrap();
+
+    let parsed = roc_gen_wasm::parse_host(&arena, &host_bytes).unwrap();
+    let indexed = roc_gen_wasm::parse_indexed_host(&arena, &host_bytes, &index_bytes).unwrap();
+
+    assert_eq!(parsed.code.function_offsets, indexed.code.function_offsets);
+    assert_eq!(parsed.reloc_code.entries, indexed.reloc_code.entries);
+    assert_eq!(parsed.reloc_data.entries, indexed.reloc_data.entries);
+
+    let mut parsed_buffer = Vec::with_capacity(parsed.size());
+    parsed.serialize(&mut parsed_buffer);
+    let mut indexed_buffer = Vec::with_capacity(indexed.size());
+    indexed.serialize(&mut indexed_buffer);
+    assert_eq!(parsed_buffer, indexed_buffer);
+}
+
+#[test]
+fn test_index_of_rebuilt_host_is_stale() {
+    let arena = Bump::new();
+    let host_bytes = fs::read(LINKING_TEST_HOST_WASM).unwrap();
+    let index_bytes = roc_gen_wasm::index_host(&host_bytes).unwrap();
+    let index = HostIndex::parse(&arena, &index_bytes).unwrap();
+
+    assert!(index.matches(&host_bytes));
+
+    // A host that was rebuilt can have the same size as the old one
+    let mut rebuilt_bytes = host_bytes.clone();
+    *rebuilt_bytes.last_mut().unwrap() ^= 1;
+
+    assert!(!index.matches(&rebuilt_bytes));
+}
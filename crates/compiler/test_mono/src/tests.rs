@@ -16,8 +16,10 @@ const EXPANDED_STACK_SIZE: usize = 8 * 1024 * 1024;
 use bumpalo::Bump;
 use roc_collections::all::MutMap;
 use roc_load::ExecutionMode;
+use roc_load::FloatWidth;
 use roc_load::LoadConfig;
 use roc_load::LoadMonomorphizedError;
+use roc_load::OptLevel;
 use roc_load::Threading;
 use roc_module::symbol::Interns;
 use roc_module::symbol::Symbol;
@@ -76,7 +78,7 @@ fn promote_expr_to_module(src: &str) -> String {
     buffer
 }
 
-fn compiles_to_ir(test_name: &str, src: &str, mode: &str, no_check: bool) {
+fn compiles_to_ir(test_name: &str, src: &str, mode: &str, no_check: bool, opt_level: &str) {
     use roc_packaging::cache::RocCacheDir;
     use std::path::PathBuf;
 
@@ -86,6 +88,14 @@ fn compiles_to_ir(test_name: &str, src: &str, mode: &str, no_check: bool) {
         _ => panic!("Invalid test_mono exec mode {mode}"),
     };
 
+    let opt_level = match opt_level {
+        "development" => OptLevel::Development,
+        "normal" => OptLevel::Normal,
+        "size" => OptLevel::Size,
+        "optimize" => OptLevel::Optimize,
+        _ => panic!("Invalid test_mono opt level {opt_level}"),
+    };
+
     let arena = &Bump::new();
 
     let filename = PathBuf::from("Test.roc");
@@ -108,6 +118,8 @@ fn compiles_to_ir(test_name: &str, src: &str, mode: &str, no_check: bool) {
         render: roc_reporting::report::RenderTarget::Generic,
         palette: roc_reporting::report::DEFAULT_PALETTE,
         exec_mode,
+        frac_default: FloatWidth::F64,
+        opt_level,
     };
     let loaded = roc_load::load_and_monomorphize_from_str(
         arena,
@@ -136,7 +148,7 @@ fn compiles_to_ir(test_name: &str, src: &str, mode: &str, no_check: bool) {
         procedures,
         exposed_to_host,
         layout_interner,
-        interns,
+        mut interns,
         ..
     } = loaded;
 
@@ -153,6 +165,7 @@ fn compiles_to_ir(test_name: &str, src: &str, mode: &str, no_check: bool) {
 
     if !no_check {
         check_procedures(arena, &interns, &layout_interner, &procedures);
+        check_text_round_trip(arena, &mut interns, &layout_interner, &procedures);
     }
 
     verify_procedures(test_name, layout_interner, procedures, main_fn_symbol);
@@ -173,6 +186,20 @@ fn check_procedures<'a>(
     panic!("IR problems found:\n{formatted}");
 }
 
+fn check_text_round_trip<'a>(
+    arena: &'a Bump,
+    interns: &mut Interns,
+    interner: &STLayoutInterner<'a>,
+    procedures: &MutMap<(Symbol, ProcLayout<'a>), Proc<'a>>,
+) {
+    use roc_mono::ir::text::{parse_procs, procs_to_text};
+    let text = procs_to_text(interns, interner, procedures.values());
+    let parsed = parse_procs(arena, interns, &text)
+        .unwrap_or_else(|error| panic!("IR text does not parse: {error}\n{text}"));
+    let reprinted = procs_to_text(interns, interner, &parsed);
+    assert_eq!(text, reprinted, "IR text does not round-trip");
+}
+
 fn verify_procedures<'a>(
     test_name: &str,
     interner: STLayoutInterner<'a>,
@@ -2220,3 +2247,90 @@ fn lambda_set_with_imported_toplevels_issue_4733() {
         "###
     )
 }
+
+#[mono_test(opt_level = "normal")]
+fn inc_before_list_set_on_shared_list() {
+    indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        main =
+            list : List I64
+            list = [1, 2, 3]
+
+            updated = List.set list 0 42
+
+            List.len list + List.len updated
+        "#
+    )
+}
+
+#[mono_test(opt_level = "normal")]
+fn fold_constants_and_branches() {
+    indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        main =
+            x = 1 + 2
+
+            if x > 2 then 10 else 20
+        "#
+    )
+}
+
+#[mono_test(opt_level = "normal")]
+fn fold_branch_that_used_a_call_result() {
+    indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        main =
+            list = List.repeat 1i64 3
+
+            if Bool.false then List.len list else 0
+        "#
+    )
+}
+
+#[mono_test(opt_level = "normal")]
+fn fuse_list_map_into_list_map() {
+    indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        main =
+            [1, 2, 3]
+            |> List.map (\x -> x + 1)
+            |> List.map (\x -> x * 2)
+        "#
+    )
+}
+
+#[mono_test(opt_level = "normal")]
+fn fuse_list_map_into_list_walk() {
+    indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        main =
+            [1, 2, 3]
+            |> List.map (\x -> x + 1)
+            |> List.walk 0 (\state, x -> state + x)
+        "#
+    )
+}
+
+#[mono_test(opt_level = "normal")]
+fn fuse_list_map_into_list_keep_if() {
+    indoc!(
+        r#"
+        app "test" provides [main] to "./platform"
+
+        main =
+            [1, 2, 3]
+            |> List.map (\x -> x + 1)
+            |> List.keepIf (\x -> x > 2)
+        "#
+    )
+}
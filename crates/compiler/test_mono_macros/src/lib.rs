@@ -8,6 +8,7 @@ use quote::quote;
 pub fn mono_test(args: TokenStream, item: TokenStream) -> TokenStream {
     let mut no_check = false;
     let mut mode = "exec".to_owned();
+    let mut opt_level = "development".to_owned();
     for arg in syn::parse_macro_input!(args as syn::AttributeArgs) {
         use syn::{Lit, Meta, MetaNameValue, NestedMeta};
         if matches!(&arg, NestedMeta::Meta(Meta::Path(p)) if p.is_ident("no_check")) {
@@ -22,6 +23,9 @@ pub fn mono_test(args: TokenStream, item: TokenStream) -> TokenStream {
             if path.is_ident("mode") {
                 mode = s.value();
             }
+            if path.is_ident("opt_level") {
+                opt_level = s.value();
+            }
         }
     }
 
@@ -40,7 +44,7 @@ pub fn mono_test(args: TokenStream, item: TokenStream) -> TokenStream {
         #[test]
         #(#attributes)*
         #visibility fn #name(#args) {
-            compiles_to_ir(#name_str, #body, &#mode, #no_check);
+            compiles_to_ir(#name_str, #body, &#mode, #no_check, &#opt_level);
 
         }
     };
@@ -334,6 +334,22 @@ pub enum FloatWidth {
     F64,
 }
 
+impl FloatWidth {
+    pub fn type_str(&self) -> &'static str {
+        match self {
+            FloatWidth::Dec => "Dec",
+            FloatWidth::F32 => "F32",
+            FloatWidth::F64 => "F64",
+        }
+    }
+
+    pub fn from_type_str(type_str: &str) -> Option<Self> {
+        [FloatWidth::Dec, FloatWidth::F32, FloatWidth::F64]
+            .into_iter()
+            .find(|width| width.type_str() == type_str)
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum SignDemand {
     /// Can be signed or unsigned.
@@ -2099,9 +2099,8 @@ impl Subs {
         self.uls_of_var.rollback_to(snapshot.uls_of_var_snapshot);
     }
 
-    pub fn commit_snapshot(&mut self, _snapshot: SubsSnapshot) {
-        // self.utable.commit(snapshot.utable_snapshot)
-        // self.uls_of_var.commit(snapshot.uls_of_var_snapshot)
+    pub fn commit_snapshot(&mut self, snapshot: SubsSnapshot) {
+        self.utable.commit(snapshot.utable_snapshot)
     }
 
     pub fn vars_since_snapshot(&mut self, snapshot: &SubsSnapshot) -> core::ops::Range<Variable> {
@@ -3225,24 +3224,37 @@ fn is_empty_record(subs: &Subs, mut var: Variable) -> bool {
     }
 }
 
+struct OccursScratchpad {
+    /// The variables on the path from the start of the occurs check to the current variable
+    path: Vec<Variable>,
+    /// Variables whose structure was already fully checked during this occurs check.
+    /// Types are DAGs, so without this, structure shared by many fields or tags (e.g. one alias
+    /// used all over a large record) is checked once for every path that leads to it.
+    acyclic: MutSet<Variable>,
+}
+
 std::thread_local! {
-    static SCRATCHPAD_FOR_OCCURS: RefCell<Option<Vec<Variable>>> = RefCell::new(Some(Vec::with_capacity(1024)));
+    static SCRATCHPAD_FOR_OCCURS: RefCell<Option<OccursScratchpad>> = RefCell::new(Some(OccursScratchpad {
+        path: Vec::with_capacity(1024),
+        acyclic: MutSet::default(),
+    }));
 }
 
-fn take_occurs_scratchpad() -> Vec<Variable> {
+fn take_occurs_scratchpad() -> OccursScratchpad {
     SCRATCHPAD_FOR_OCCURS.with(|f| f.take().unwrap())
 }
 
-fn put_occurs_scratchpad(mut scratchpad: Vec<Variable>) {
+fn put_occurs_scratchpad(mut scratchpad: OccursScratchpad) {
     SCRATCHPAD_FOR_OCCURS.with(|f| {
-        scratchpad.clear();
+        scratchpad.path.clear();
+        scratchpad.acyclic.clear();
         f.replace(Some(scratchpad));
     });
 }
 
 fn occurs(
     subs: &Subs,
-    seen: &mut Vec<Variable>,
+    seen: &mut OccursScratchpad,
     input_var: Variable,
 ) -> Result<(), (Variable, Vec<Variable>)> {
     use self::Content::*;
@@ -3250,10 +3262,12 @@ fn occurs(
 
     let root_var = subs.get_root_key_without_compacting(input_var);
 
-    if seen.contains(&root_var) {
+    if seen.path.contains(&root_var) {
         Err((root_var, Vec::with_capacity(0)))
+    } else if seen.acyclic.contains(&root_var) {
+        Ok(())
     } else {
-        seen.push(root_var);
+        seen.path.push(root_var);
         let result = (|| match subs.get_content_without_compacting(root_var) {
             FlexVar(_)
             | RigidVar(_)
@@ -3318,7 +3332,12 @@ fn occurs(
             }
             RangedNumber(_range_vars) => Ok(()),
         })();
-        seen.pop();
+        seen.path.pop();
+
+        if result.is_ok() {
+            seen.acyclic.insert(root_var);
+        }
+
         result
     }
 }
@@ -3327,7 +3346,7 @@ fn occurs(
 fn occurs_union<L: Label>(
     subs: &Subs,
     root_var: Variable,
-    seen: &mut Vec<Variable>,
+    seen: &mut OccursScratchpad,
     tags: &UnionLabels<L>,
 ) -> Result<(), (Variable, Vec<Variable>)> {
     for slice_index in tags.variables() {
@@ -3344,7 +3363,7 @@ fn occurs_union<L: Label>(
 fn short_circuit<'a, T>(
     subs: &Subs,
     root_key: Variable,
-    seen: &mut Vec<Variable>,
+    seen: &mut OccursScratchpad,
     iter: T,
 ) -> Result<(), (Variable, Vec<Variable>)>
 where
@@ -3361,7 +3380,7 @@ where
 fn short_circuit_help(
     subs: &Subs,
     root_key: Variable,
-    seen: &mut Vec<Variable>,
+    seen: &mut OccursScratchpad,
     var: Variable,
 ) -> Result<(), (Variable, Vec<Variable>)> {
     if let Err((v, mut vec)) = occurs(subs, seen, var) {
@@ -5618,3 +5637,61 @@ fn is_inhabited(subs: &Subs, var: Variable) -> bool {
 
     true
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn occurs_checks_shared_structure_once() {
+        // Each record holds the previous one in two fields, so there are 2^64 paths to the
+        // innermost record; without remembering acyclic variables this would never finish.
+        let mut subs = Subs::new();
+        let mut var = subs.fresh_unnamed_flex_var();
+
+        for _ in 0..64 {
+            let fields = RecordFields::insert_into_subs(
+                &mut subs,
+                [
+                    ("a".into(), RecordField::Required(var)),
+                    ("b".into(), RecordField::Required(var)),
+                ],
+            );
+            let record = Content::Structure(FlatType::Record(fields, Variable::EMPTY_RECORD));
+            var = subs.fresh(record.into());
+        }
+
+        assert!(subs.occurs(var).is_ok());
+    }
+
+    #[test]
+    fn rollback_undoes_unions_of_nested_snapshots() {
+        let mut subs = Subs::new();
+        let a = subs.fresh_unnamed_flex_var();
+        let b = subs.fresh_unnamed_flex_var();
+        let c = subs.fresh_unnamed_flex_var();
+        let len = subs.len();
+
+        let outer = subs.snapshot();
+        subs.union(a, b, Content::Structure(FlatType::EmptyRecord).into());
+
+        let inner = subs.snapshot();
+        let d = subs.fresh_unnamed_flex_var();
+        subs.union(b, c, subs.get_without_compacting(b));
+        subs.union(c, d, subs.get_without_compacting(c));
+        assert_eq!(subs.get_root_key(a), subs.get_root_key(d));
+        subs.commit_snapshot(inner);
+
+        subs.rollback_to(outer);
+
+        assert_eq!(subs.len(), len);
+        assert!(!subs.equivalent_without_compacting(a, b));
+        assert!(!subs.equivalent_without_compacting(b, c));
+        for var in [a, b, c] {
+            assert!(matches!(
+                subs.get_content_without_compacting(var),
+                Content::FlexVar(None)
+            ));
+        }
+    }
+}
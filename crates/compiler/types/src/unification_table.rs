@@ -7,9 +7,23 @@ use roc_serialize::bytes;
 pub struct UnificationTable {
     contents: Vec<Content>,
     metadata: Vec<Combine>,
+    /// The old values of entries that were overwritten while a snapshot was open, so rolling back
+    /// only costs as much as the work done since the snapshot, not the size of the table.
+    undo_log: Vec<Undo>,
+    /// The table length at each open snapshot, innermost last
+    snapshot_lengths: Vec<usize>,
 }
 
-pub(crate) struct Snapshot(UnificationTable);
+pub(crate) struct Snapshot {
+    length: usize,
+    undo_log_length: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Undo {
+    Content(u32, Content),
+    Metadata(u32, Combine),
+}
 
 #[derive(Debug, Clone, Copy)]
 enum Combine {
@@ -42,6 +56,8 @@ impl UnificationTable {
         Self {
             contents: Vec::with_capacity(cap),
             metadata: Vec::with_capacity(cap),
+            undo_log: Vec::new(),
+            snapshot_lengths: Vec::new(),
         }
     }
 
@@ -107,9 +123,40 @@ impl UnificationTable {
     ) {
         let index = key.index() as usize;
 
+        self.write_content(index, content);
+        self.write_metadata(index, Combine::Root(Root { rank, mark, copy }));
+    }
+
+    // UNDO LOG
+
+    /// Whether overwriting the entry at `index` has to be undone on rollback. Entries added since
+    /// the innermost open snapshot are dropped on rollback anyway.
+    #[inline(always)]
+    fn is_logged(&self, index: usize) -> bool {
+        match self.snapshot_lengths.last() {
+            Some(length) => index < *length,
+            None => false,
+        }
+    }
+
+    #[inline(always)]
+    fn write_content(&mut self, index: usize, content: Content) {
+        if self.is_logged(index) {
+            self.undo_log
+                .push(Undo::Content(index as u32, self.contents[index]));
+        }
+
         self.contents[index] = content;
+    }
+
+    #[inline(always)]
+    fn write_metadata(&mut self, index: usize, combine: Combine) {
+        if self.is_logged(index) {
+            self.undo_log
+                .push(Undo::Metadata(index as u32, self.metadata[index]));
+        }
 
-        self.metadata[index] = Combine::Root(Root { rank, mark, copy });
+        self.metadata[index] = combine;
     }
 
     pub fn modify<F, T>(&mut self, key: Variable, mapper: F) -> T
@@ -216,7 +263,14 @@ impl UnificationTable {
     where
         F: Fn(&mut Root) -> T,
     {
-        match &mut self.metadata[key.index() as usize] {
+        let index = key.index() as usize;
+
+        if self.is_logged(index) {
+            self.undo_log
+                .push(Undo::Metadata(index as u32, self.metadata[index]));
+        }
+
+        match &mut self.metadata[index] {
             Combine::Root(root) => f(root),
             Combine::Redirect(_) => {
                 if cfg!(debug_assertions) {
@@ -247,7 +301,7 @@ impl UnificationTable {
     #[allow(unused)]
     #[inline(always)]
     pub fn set_content_unchecked(&mut self, key: Variable, value: Content) {
-        self.contents[key.index() as usize] = value;
+        self.write_content(key.index() as usize, value);
     }
 
     // SET CHECKED
@@ -273,7 +327,7 @@ impl UnificationTable {
     #[inline(always)]
     pub fn set_content(&mut self, key: Variable, value: Content) {
         let index = self.root_key(key).index() as usize;
-        self.contents[index] = value;
+        self.write_content(index, value);
     }
 
     // ROOT KEY
@@ -282,9 +336,12 @@ impl UnificationTable {
     pub fn root_key(&mut self, mut key: Variable) -> Variable {
         let root = self.root_key_without_compacting(key);
 
-        while let Combine::Redirect(redirect) = &mut self.metadata[key.index() as usize] {
-            key = *redirect;
-            *redirect = root;
+        while let Combine::Redirect(redirect) = self.metadata[key.index() as usize] {
+            if redirect != root {
+                self.write_metadata(key.index() as usize, Combine::Redirect(root));
+            }
+
+            key = redirect;
         }
 
         root
@@ -299,17 +356,52 @@ impl UnificationTable {
         key
     }
 
-    pub(crate) fn snapshot(&self) -> Snapshot {
-        Snapshot(self.clone())
+    /// Snapshots must be rolled back or committed in the reverse order they were taken in.
+    pub(crate) fn snapshot(&mut self) -> Snapshot {
+        self.snapshot_lengths.push(self.len());
+
+        Snapshot {
+            length: self.len(),
+            undo_log_length: self.undo_log.len(),
+        }
     }
 
     pub(crate) fn rollback_to(&mut self, snapshot: Snapshot) {
-        *self = snapshot.0;
+        let length = self.snapshot_lengths.pop();
+        debug_assert_eq!(
+            length,
+            Some(snapshot.length),
+            "snapshots rolled back out of order"
+        );
+
+        for undo in self.undo_log.drain(snapshot.undo_log_length..).rev() {
+            match undo {
+                Undo::Content(index, content) => self.contents[index as usize] = content,
+                Undo::Metadata(index, combine) => self.metadata[index as usize] = combine,
+            }
+        }
+
+        self.contents.truncate(snapshot.length);
+        self.metadata.truncate(snapshot.length);
+    }
+
+    pub(crate) fn commit(&mut self, snapshot: Snapshot) {
+        let length = self.snapshot_lengths.pop();
+        debug_assert_eq!(
+            length,
+            Some(snapshot.length),
+            "snapshots committed out of order"
+        );
+
+        // An enclosing snapshot may still be rolled back, and then needs these entries
+        if self.snapshot_lengths.is_empty() {
+            self.undo_log.clear();
+        }
     }
 
     pub(crate) fn vars_since_snapshot(&self, snapshot: &Snapshot) -> std::ops::Range<Variable> {
         unsafe {
-            let start = Variable::from_index(snapshot.0.len() as u32);
+            let start = Variable::from_index(snapshot.length as u32);
             let end = Variable::from_index(self.len() as u32);
 
             start..end
@@ -347,7 +439,7 @@ impl UnificationTable {
 
         // redirect from -> to
         if from_index != to_index {
-            self.metadata[from_index] = Combine::Redirect(to)
+            self.write_metadata(from_index, Combine::Redirect(to))
         }
 
         // update to's Descriptor
@@ -443,6 +535,8 @@ impl UnificationTable {
         let this = Self {
             contents: contents.to_vec(),
             metadata,
+            undo_log: Vec::new(),
+            snapshot_lengths: Vec::new(),
         };
 
         (this, offset)
@@ -390,7 +390,31 @@ pub fn unify(
     mode: Mode,
     observed_pol: Polarity,
 ) -> Unified {
-    unify_help(env, var1, var2, mode, observed_pol)
+    unify_help(env, var1, var2, mode, observed_pol, OnFailure::PoisonBoth)
+}
+
+/// Unify the type of a value with the type its annotation demands.
+///
+/// This behaves like [unify], except when unification fails: only `actual` is turned into an
+/// error type, and `annotation` keeps its structure. Since error types unify with anything, the
+/// mismatch is reported once, and the rest of the module is still checked against what the
+/// annotation says rather than against an error type that would swallow every later mistake.
+#[inline(always)]
+pub fn unify_with_annotation(
+    env: &mut Env,
+    actual: Variable,
+    annotation: Variable,
+    mode: Mode,
+    observed_pol: Polarity,
+) -> Unified {
+    unify_help(
+        env,
+        actual,
+        annotation,
+        mode,
+        observed_pol,
+        OnFailure::PoisonFirst,
+    )
 }
 
 #[inline(always)]
@@ -407,6 +431,7 @@ pub fn unify_introduced_ability_specialization(
         specialization_var,
         mode,
         Polarity::OF_VALUE,
+        OnFailure::PoisonBoth,
     )
 }
 
@@ -419,7 +444,14 @@ pub fn unify_with_collector<M: MetaCollector>(
     mode: Mode,
     observed_pol: Polarity,
 ) -> Unified<M> {
-    unify_help(env, var1, var2, mode, observed_pol)
+    unify_help(env, var1, var2, mode, observed_pol, OnFailure::PoisonBoth)
+}
+
+/// Which variables are turned into error types when a top-level unification fails.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum OnFailure {
+    PoisonBoth,
+    PoisonFirst,
 }
 
 #[inline(always)]
@@ -430,6 +462,7 @@ fn unify_help<M: MetaCollector>(
     var2: Variable,
     mode: Mode,
     observed_pol: Polarity,
+    on_failure: OnFailure,
 ) -> Unified<M> {
     let mut vars = Vec::new();
     let Outcome {
@@ -460,7 +493,10 @@ fn unify_help<M: MetaCollector>(
             .subs
             .var_to_error_type_contextual(var2, error_context, observed_pol);
 
-        env.subs.union(var1, var2, Content::Error.into());
+        match on_failure {
+            OnFailure::PoisonBoth => env.subs.union(var1, var2, Content::Error.into()),
+            OnFailure::PoisonFirst => env.subs.set_content(var1, Content::Error),
+        }
 
         let do_not_implement_ability = mismatches
             .into_iter()
@@ -1486,6 +1522,7 @@ fn separate_union_lambdas<M: MetaCollector>(
                                 pool.truncate(pool_snapshot);
                                 continue 'try_next_right;
                             } else {
+                                env.subs.commit_snapshot(subs_snapshot);
                                 let outcome = unify_pool(env, pool, var1, var2, mode);
                                 whole_outcome.union(outcome);
                             }
@@ -1933,8 +1970,14 @@ fn unify_record<M: MetaCollector>(
                 return ext_outcome;
             }
 
-            let mut field_outcome =
-                unify_shared_fields(env, pool, ctx, shared_fields, OtherFields::None, ext1);
+            let mut field_outcome = unify_shared_fields(
+                env,
+                pool,
+                ctx,
+                shared_fields,
+                OtherFields::None([fields1, fields2]),
+                ext1,
+            );
 
             field_outcome.union(ext_outcome);
 
@@ -1949,8 +1992,14 @@ fn unify_record<M: MetaCollector>(
                 return ext_outcome;
             }
 
-            let mut field_outcome =
-                unify_shared_fields(env, pool, ctx, shared_fields, OtherFields::None, sub_record);
+            let mut field_outcome = unify_shared_fields(
+                env,
+                pool,
+                ctx,
+                shared_fields,
+                OtherFields::None([fields1, fields2]),
+                sub_record,
+            );
 
             field_outcome.union(ext_outcome);
 
@@ -1966,8 +2015,14 @@ fn unify_record<M: MetaCollector>(
             return ext_outcome;
         }
 
-        let mut field_outcome =
-            unify_shared_fields(env, pool, ctx, shared_fields, OtherFields::None, sub_record);
+        let mut field_outcome = unify_shared_fields(
+            env,
+            pool,
+            ctx,
+            shared_fields,
+            OtherFields::None([fields1, fields2]),
+            sub_record,
+        );
 
         field_outcome.union(ext_outcome);
 
@@ -2009,7 +2064,9 @@ fn unify_record<M: MetaCollector>(
 }
 
 enum OtherFields {
-    None,
+    /// No fields beyond the shared ones; carries the original fields of both records, which the
+    /// unified record can reuse if it ends up with exactly the same fields.
+    None([RecordFields; 2]),
     Other(RecordFields, RecordFields),
 }
 
@@ -2105,12 +2162,12 @@ fn unify_shared_fields<M: MetaCollector>(
         let ext_fields: Vec<_> = ext_fields.into_iter().collect();
 
         let fields: RecordFields = match other_fields {
-            OtherFields::None => {
+            OtherFields::None(originals) => {
                 if ext_fields.is_empty() {
-                    RecordFields::insert_into_subs(env.subs, matching_fields)
+                    insert_or_reuse_record_fields(env.subs, originals, matching_fields)
                 } else {
                     let all_fields = merge_sorted(matching_fields, ext_fields);
-                    RecordFields::insert_into_subs(env.subs, all_fields)
+                    insert_or_reuse_record_fields(env.subs, originals, all_fields)
                 }
             }
             OtherFields::Other(other1, other2) => {
@@ -2151,6 +2208,34 @@ fn unify_shared_fields<M: MetaCollector>(
     }
 }
 
+/// Insert the fields of a unified record into subs, unless one of the `candidates` already holds
+/// exactly these fields.
+///
+/// Unifying records that are already (mostly) unified happens all the time, e.g. when a large
+/// record flows through many functions. Reusing the existing fields in that case keeps subs from
+/// growing by a copy of every field on every such unification.
+fn insert_or_reuse_record_fields(
+    subs: &mut Subs,
+    candidates: [RecordFields; 2],
+    fields: Vec<(Lowercase, RecordField<Variable>)>,
+) -> RecordFields {
+    for candidate in candidates {
+        let is_same = candidate.len() == fields.len()
+            && candidate
+                .iter_all()
+                .zip(fields.iter())
+                .all(|((i1, i2, i3), (name, field))| {
+                    &subs[i1] == name && subs[i3].map(|_| subs[i2]) == *field
+                });
+
+        if is_same {
+            return candidate;
+        }
+    }
+
+    RecordFields::insert_into_subs(subs, fields)
+}
+
 fn separate_record_fields(
     subs: &Subs,
     fields1: RecordFields,
@@ -0,0 +1,155 @@
+use roc_load::docs::{DocEntry, ModuleDocumentation};
+
+/// Which of a module's exposed values and types have doc comments
+pub struct ModuleCoverage<'a> {
+    pub name: &'a str,
+    pub documented: usize,
+    pub undocumented: Vec<&'a str>,
+}
+
+impl ModuleCoverage<'_> {
+    pub fn total(&self) -> usize {
+        self.documented + self.undocumented.len()
+    }
+}
+
+pub fn module_coverage<'a>(
+    module: &'a ModuleDocumentation,
+    exposed_names: &[String],
+) -> ModuleCoverage<'a> {
+    let mut coverage = ModuleCoverage {
+        name: module.name.as_str(),
+        documented: 0,
+        undocumented: Vec::new(),
+    };
+
+    for entry in &module.entries {
+        if let DocEntry::DocDef(doc_def) = entry {
+            if !exposed_names.contains(&doc_def.name) {
+                continue;
+            }
+
+            let has_docs = doc_def
+                .docs
+                .as_deref()
+                .map_or(false, |docs| !docs.trim().is_empty());
+
+            if has_docs {
+                coverage.documented += 1;
+            } else {
+                coverage.undocumented.push(doc_def.name.as_str());
+            }
+        }
+    }
+
+    coverage
+}
+
+/// A line per module with how much of it is documented, followed by what isn't,
+/// and a summary for all of the modules.
+pub fn render_report(coverages: &[ModuleCoverage]) -> String {
+    let mut buf = String::new();
+    let mut documented = 0;
+    let mut total = 0;
+
+    let name_width = coverages
+        .iter()
+        .map(|coverage| coverage.name.len())
+        .max()
+        .unwrap_or(0);
+
+    for coverage in coverages {
+        documented += coverage.documented;
+        total += coverage.total();
+
+        buf.push_str(&format!(
+            "{:<name_width$}  {}/{} ({:.1}%)\n",
+            coverage.name,
+            coverage.documented,
+            coverage.total(),
+            percentage(coverage.documented, coverage.total()),
+        ));
+
+        if !coverage.undocumented.is_empty() {
+            buf.push_str(&format!(
+                "    missing docs: {}\n",
+                coverage.undocumented.join(", ")
+            ));
+        }
+    }
+
+    buf.push_str(&format!(
+        "\n{}/{} exposed values and types are documented ({:.1}%)\n",
+        documented,
+        total,
+        percentage(documented, total),
+    ));
+
+    buf
+}
+
+fn percentage(documented: usize, total: usize) -> f64 {
+    if total == 0 {
+        // There is nothing to document, so nothing is missing.
+        100.0
+    } else {
+        documented as f64 * 100.0 / total as f64
+    }
+}
+
+#[cfg(test)]
+mod test_coverage {
+    use super::*;
+    use crate::test_docs::{apply, doc_def, module_documentation};
+
+    #[test]
+    fn coverage_of_exposed_defs() {
+        let str_type = || apply("Str", vec![]);
+        let parser = module_documentation(
+            "Parser",
+            vec![
+                DocEntry::DetachedDoc("A parser.".to_string()),
+                doc_def("parse", str_type(), Some("Parses.")),
+                doc_def("blank", str_type(), Some(" \n")),
+                doc_def("missing", str_type(), None),
+                doc_def("helper", str_type(), None),
+            ],
+        );
+        let util = module_documentation("Util", vec![doc_def("id", str_type(), Some("Same."))]);
+
+        let exposed = |names: &[&str]| {
+            names
+                .iter()
+                .map(|name| name.to_string())
+                .collect::<Vec<_>>()
+        };
+        let coverages = [
+            module_coverage(&parser, &exposed(&["parse", "blank", "missing"])),
+            module_coverage(&util, &exposed(&["id"])),
+        ];
+
+        // only exposed defs count, and blank docs count as missing
+        assert_eq!(coverages[0].documented, 1);
+        assert_eq!(coverages[0].undocumented, ["blank", "missing"]);
+        assert_eq!(coverages[0].total(), 3);
+
+        assert_eq!(
+            render_report(&coverages),
+            "Parser  1/3 (33.3%)\n    \
+             missing docs: blank, missing\n\
+             Util    1/1 (100.0%)\n\
+             \n\
+             2/4 exposed values and types are documented (50.0%)\n"
+        );
+    }
+
+    #[test]
+    fn nothing_to_document() {
+        let module = module_documentation("Empty", Vec::new());
+
+        assert_eq!(
+            render_report(&[module_coverage(&module, &[])]),
+            "Empty  0/0 (100.0%)\n\n0/0 exposed values and types are documented (100.0%)\n"
+        );
+    }
+}
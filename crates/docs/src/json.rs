@@ -0,0 +1,115 @@
+use crate::one_line_type_annotation;
+use roc_load::docs::{DocEntry, Documentation, ModuleDocumentation};
+use serde_json::{json, Value};
+
+/// Render a package's docs as JSON, e.g. for a package registry:
+///
+/// ```json
+/// {
+///   "name": "...", "version": "...", "docs": "...",
+///   "modules": [
+///     {
+///       "name": "Str",
+///       "entries": [
+///         {"kind": "def", "name": "concat", "typeVars": [], "type": "Str, Str -> Str", "docs": "..."},
+///         {"kind": "docs", "docs": "..."}
+///       ]
+///     }
+///   ]
+/// }
+/// ```
+///
+/// Docs are the markdown they were written in, or `null` for defs without any.
+pub fn render_package<'a, I: Iterator<Item = (&'a ModuleDocumentation, Vec<String>)>>(
+    package: &Documentation,
+    modules: I,
+) -> String {
+    let modules: Vec<Value> = modules
+        .map(|(module, exposed_values)| {
+            let entries: Vec<Value> = module
+                .entries
+                .iter()
+                .filter_map(|entry| match entry {
+                    // We don't want to include entries that aren't exposed
+                    DocEntry::DocDef(doc_def) if !exposed_values.contains(&doc_def.name) => None,
+                    DocEntry::DocDef(doc_def) => Some(json!({
+                        "kind": "def",
+                        "name": doc_def.name,
+                        "typeVars": doc_def.type_vars,
+                        "type": one_line_type_annotation(&doc_def.type_annotation),
+                        "docs": doc_def.docs.as_deref().map(str::trim),
+                    })),
+                    DocEntry::DetachedDoc(docs) => Some(json!({
+                        "kind": "docs",
+                        "docs": docs.trim(),
+                    })),
+                })
+                .collect();
+
+            json!({
+                "name": module.name,
+                "entries": entries,
+            })
+        })
+        .collect();
+
+    let package = json!({
+        "name": package.name,
+        "version": package.version,
+        "docs": package.docs,
+        "modules": modules,
+    });
+
+    let mut buf = serde_json::to_string_pretty(&package).unwrap();
+
+    buf.push('\n');
+
+    buf
+}
+
+#[cfg(test)]
+mod test_json {
+    use super::*;
+    use crate::test_docs::{apply, doc_def, module_documentation};
+
+    #[test]
+    fn package_json() {
+        let package = Documentation {
+            name: "parsers".to_string(),
+            version: "1.0.0".to_string(),
+            docs: "Parsers for \"everything\".".to_string(),
+            modules: Vec::new(),
+        };
+        let module = module_documentation(
+            "Parser",
+            vec![
+                DocEntry::DetachedDoc(" A parser.\n".to_string()),
+                doc_def("parse", apply("List", vec![apply("Str", vec![])]), None),
+                doc_def("helper", apply("Str", vec![]), Some("Not exposed.")),
+            ],
+        );
+
+        let rendered = render_package(
+            &package,
+            std::iter::once((&module, vec!["parse".to_string()])),
+        );
+
+        assert_eq!(
+            serde_json::from_str::<Value>(&rendered).unwrap(),
+            json!({
+                "name": "parsers",
+                "version": "1.0.0",
+                "docs": "Parsers for \"everything\".",
+                "modules": [
+                    {
+                        "name": "Parser",
+                        "entries": [
+                            {"kind": "docs", "docs": "A parser."},
+                            {"kind": "def", "name": "parse", "typeVars": [], "type": "List Str", "docs": null},
+                        ],
+                    },
+                ],
+            })
+        );
+    }
+}
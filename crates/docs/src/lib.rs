@@ -9,24 +9,59 @@ use roc_can::scope::Scope;
 use roc_code_markup::markup::nodes::MarkupNode;
 use roc_code_markup::slow_pool::SlowPool;
 use roc_highlight::highlight_parser::{highlight_defs, highlight_expr};
-use roc_load::docs::DocEntry::DocDef;
-use roc_load::docs::{DocEntry, TypeAnnotation};
+use roc_load::docs::{type_annotation_from_str, DocDef, DocEntry, TypeAnnotation};
 use roc_load::docs::{Documentation, ModuleDocumentation, RecordField};
-use roc_load::{ExecutionMode, LoadConfig, LoadedModule, LoadingProblem, Threading};
+use roc_load::symbol_index::{index_file, SymbolIndex, SymbolKind};
+use roc_load::{
+    ExecutionMode, FloatWidth, LoadConfig, LoadedModule, LoadingProblem, OptLevel, Threading,
+};
 use roc_module::symbol::{IdentIdsByModule, Interns, ModuleId};
 use roc_packaging::cache::{self, RocCacheDir};
 use roc_parse::ident::{parse_ident, Ident};
 use roc_parse::state::State;
-use roc_region::all::Region;
+use roc_region::all::{LineInfo, Region};
 use std::fs;
 use std::path::{Path, PathBuf};
 
+mod coverage;
 mod docs_error;
 mod html;
+mod json;
+mod markdown;
 
 const BUILD_DIR: &str = "./generated-docs";
 
+/// How many matches `roc docs --search` prints
+const MAX_SEARCH_RESULTS: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocsFormat {
+    /// A website, with a page per module
+    Html,
+    /// A markdown file per module, for static site generators
+    Markdown,
+    /// A single JSON file describing every module, for package registries and other tools
+    Json,
+}
+
+impl<'a> TryFrom<&'a str> for DocsFormat {
+    type Error = ();
+
+    fn try_from(format: &'a str) -> Result<Self, Self::Error> {
+        match format {
+            "html" => Ok(DocsFormat::Html),
+            "markdown" => Ok(DocsFormat::Markdown),
+            "json" => Ok(DocsFormat::Json),
+            _ => Err(()),
+        }
+    }
+}
+
 pub fn generate_docs_html(filenames: Vec<PathBuf>) {
+    generate_docs(filenames, DocsFormat::Html)
+}
+
+pub fn generate_docs(filenames: Vec<PathBuf>, format: DocsFormat) {
     let build_dir = Path::new(BUILD_DIR);
     let loaded_modules = load_modules_for_files(filenames);
 
@@ -42,26 +77,146 @@ pub fn generate_docs_html(filenames: Vec<PathBuf>) {
         fs::create_dir_all(build_dir).expect("TODO gracefully handle unable to create build dir");
     }
 
-    // Copy over the assets
-    fs::write(
-        build_dir.join("search.js"),
-        include_str!("./static/search.js"),
-    )
-    .expect("TODO gracefully handle failing to make the search javascript");
+    match format {
+        DocsFormat::Html => write_html(&package, build_dir),
+        DocsFormat::Markdown => {
+            for (module, exposed_values) in module_pairs(&package) {
+                let module_path = build_dir.join(format!("{}.md", module.name));
 
-    fs::write(
-        build_dir.join("styles.css"),
-        include_str!("./static/styles.css"),
-    )
-    .expect("TODO gracefully handle failing to make the stylesheet");
+                fs::write(
+                    module_path,
+                    markdown::render_module(module, &exposed_values),
+                )
+                .expect("TODO gracefully handle failing to write a module's markdown file");
+            }
+        }
+        DocsFormat::Json => {
+            fs::write(
+                build_dir.join("docs.json"),
+                json::render_package(&package, module_pairs(&package)),
+            )
+            .expect("TODO gracefully handle failing to write docs.json");
+        }
+    }
 
-    fs::write(
-        build_dir.join("favicon.svg"),
-        include_str!("./static/favicon.svg"),
-    )
-    .expect("TODO gracefully handle failing to make the favicon");
+    println!("🎉 Docs generated in {}", build_dir.display());
+}
+
+/// Print which exposed values and types of the given modules lack doc comments.
+/// Returns whether all of them have docs.
+pub fn report_docs_coverage(filenames: Vec<PathBuf>) -> bool {
+    let loaded_modules = load_modules_for_files(filenames);
+
+    let coverages: Vec<coverage::ModuleCoverage> = loaded_modules
+        .iter()
+        .filter_map(|loaded_module| {
+            let module = loaded_module.documentation.get(&loaded_module.module_id)?;
+            let home = loaded_module.module_id;
+
+            let exposed_names: Vec<String> = loaded_module
+                .exposed_values
+                .iter()
+                .chain(loaded_module.exposed_aliases.keys())
+                .filter(|symbol| symbol.module_id() == home)
+                .map(|symbol| symbol.as_str(&loaded_module.interns).to_string())
+                .collect();
+
+            Some(coverage::module_coverage(module, &exposed_names))
+        })
+        .collect();
+
+    print!("{}", coverage::render_report(&coverages));
+
+    coverages
+        .iter()
+        .all(|coverage| coverage.undocumented.is_empty())
+}
+
+/// Print the definitions in the given modules that match the query, best matches first.
+/// Returns whether anything matched.
+///
+/// The modules are indexed in the Roc cache directory, per working directory, so each search
+/// only loads the modules that changed since the last one.
+pub fn search_docs(filenames: Vec<PathBuf>, query: &str) -> bool {
+    let root = std::env::current_dir().unwrap_or_default();
+    let file = index_file(&cache::roc_cache_dir(), &root);
+    let mut index = SymbolIndex::load(&file);
+
+    let paths: Vec<PathBuf> = filenames
+        .into_iter()
+        .map(|filename| fs::canonicalize(&filename).unwrap_or(filename))
+        .collect();
+
+    index.refresh(&paths, |path| {
+        let arena = Bump::new();
+
+        match roc_load::load_and_typecheck(
+            &arena,
+            path.to_path_buf(),
+            Default::default(),
+            RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
+            load_config(),
+        ) {
+            Ok(loaded) => Some(loaded),
+            Err(LoadingProblem::FormattedReport(report)) => {
+                eprintln!("{}", report);
+
+                None
+            }
+            Err(e) => panic!("{:?}", e),
+        }
+    });
+
+    if let Err(err) = index.save(&file) {
+        eprintln!(
+            "Could not save the index of definitions to {}: {}",
+            file.display(),
+            err
+        );
+    }
+
+    let matches = index.search(query);
+
+    for symbol in matches.iter().take(MAX_SEARCH_RESULTS) {
+        let src = fs::read_to_string(&symbol.path).unwrap_or_default();
+        let position = LineInfo::new(&src).convert_pos(symbol.region.start());
+        let path = symbol.path.strip_prefix(&root).unwrap_or(&symbol.path);
+
+        let definition = match symbol.kind {
+            // These signatures are the first line of the definition, which starts with the name.
+            SymbolKind::Alias | SymbolKind::Opaque | SymbolKind::Ability => {
+                format!("{}.{}", symbol.module, symbol.signature)
+            }
+            _ if symbol.signature.is_empty() => format!("{}.{}", symbol.module, symbol.name),
+            _ => format!("{}.{} : {}", symbol.module, symbol.name, symbol.signature),
+        };
 
-    let module_pairs = package.modules.iter().flat_map(|loaded_module| {
+        println!(
+            "{}:{}:{}\t{}",
+            path.display(),
+            position.line + 1,
+            position.column + 1,
+            definition
+        );
+    }
+
+    if matches.len() > MAX_SEARCH_RESULTS {
+        println!("...and {} more", matches.len() - MAX_SEARCH_RESULTS);
+    } else if matches.is_empty() {
+        println!(
+            "Nothing is defined with a name or type matching {:?}",
+            query
+        );
+    }
+
+    !matches.is_empty()
+}
+
+/// Each module being documented, along with the names of the values it exposes
+fn module_pairs(
+    package: &Documentation,
+) -> impl Iterator<Item = (&ModuleDocumentation, Vec<String>)> + Clone {
+    package.modules.iter().flat_map(|loaded_module| {
         loaded_module
             .documentation
             .iter()
@@ -83,9 +238,44 @@ pub fn generate_docs_html(filenames: Vec<PathBuf>) {
                     None
                 }
             })
-    });
+    })
+}
+
+fn write_html(package: &Documentation, build_dir: &Path) {
+    // Copy over the assets
+    fs::write(
+        build_dir.join("search.js"),
+        include_str!("./static/search.js"),
+    )
+    .expect("TODO gracefully handle failing to make the search javascript");
+
+    fs::write(
+        build_dir.join("styles.css"),
+        include_str!("./static/styles.css"),
+    )
+    .expect("TODO gracefully handle failing to make the stylesheet");
+
+    fs::write(
+        build_dir.join("favicon.svg"),
+        include_str!("./static/favicon.svg"),
+    )
+    .expect("TODO gracefully handle failing to make the favicon");
+
+    let module_pairs = module_pairs(package);
+
+    fs::write(
+        build_dir.join("search-index.js"),
+        render_search_index(module_pairs.clone()),
+    )
+    .expect("TODO gracefully handle failing to make the search index");
+
+    let documented_modules: Vec<&str> = module_pairs
+        .clone()
+        .map(|(module, _)| module.name.as_str())
+        .collect();
 
     let template_html = include_str!("./static/index.html")
+        .replace("<!-- search-index.js -->", "/search-index.js")
         .replace("<!-- search.js -->", "/search.js")
         .replace("<!-- styles.css -->", "/styles.css")
         .replace("<!-- favicon.svg -->", "/favicon.svg")
@@ -118,7 +308,7 @@ pub fn generate_docs_html(filenames: Vec<PathBuf>) {
                 let rendered_module = template_html
                     .replace(
                         "<!-- Page title -->",
-                        page_title(&package, module_docs).as_str(),
+                        page_title(package, module_docs).as_str(),
                     )
                     .replace(
                         "<!-- Package Name and Version -->",
@@ -127,7 +317,12 @@ pub fn generate_docs_html(filenames: Vec<PathBuf>) {
                     )
                     .replace(
                         "<!-- Module Docs -->",
-                        render_module_documentation(module_docs, loaded_module).as_str(),
+                        render_module_documentation(
+                            module_docs,
+                            loaded_module,
+                            &documented_modules,
+                        )
+                        .as_str(),
                     );
 
                 fs::write(module_dir.join("index.html"), rendered_module).expect(
@@ -136,8 +331,6 @@ pub fn generate_docs_html(filenames: Vec<PathBuf>) {
             }
         }
     }
-
-    println!("🎉 Docs generated in {}", build_dir.display());
 }
 
 fn sidebar_link_url(module: &ModuleDocumentation) -> String {
@@ -197,8 +390,14 @@ pub fn syntax_highlight_top_level_defs(code_str: &str) -> DocsResult<String> {
 fn render_module_documentation(
     module: &ModuleDocumentation,
     loaded_module: &LoadedModule,
+    documented_modules: &[&str],
 ) -> String {
     let mut buf = String::new();
+    let type_links = TypeLinks {
+        scope: &module.scope,
+        interns: &loaded_module.interns,
+        documented_modules,
+    };
 
     buf.push_str(
         html_to_string(
@@ -214,7 +413,7 @@ fn render_module_documentation(
     for entry in &module.entries {
         let mut should_render_entry = true;
 
-        if let DocDef(def) = entry {
+        if let DocEntry::DocDef(def) = entry {
             // We dont want to render entries that arent exposed
             should_render_entry = exposed_values.contains(&def.name.as_str());
         }
@@ -243,14 +442,9 @@ fn render_module_documentation(
 
                     let type_ann = &doc_def.type_annotation;
 
-                    match type_ann {
-                        TypeAnnotation::NoTypeAnn => {}
-                        _ => {
-                            content.push_str(" : ");
-                        }
-                    }
+                    content.push_str(annotation_separator(type_ann));
 
-                    type_annotation_to_html(0, &mut content, type_ann, false);
+                    type_annotation_to_html(0, &mut content, Some(&type_links), type_ann, false);
 
                     buf.push_str(
                         html_to_string(
@@ -451,26 +645,120 @@ fn render_sidebar<'a, I: Iterator<Item = (&'a ModuleDocumentation, Vec<String>)>
     buf
 }
 
+/// The search box looks up exposed values by their name, type, and the first line of their
+/// docs, so it can find them across every module without loading each module's page.
+fn render_search_index<'a, I: Iterator<Item = (&'a ModuleDocumentation, Vec<String>)>>(
+    modules: I,
+) -> String {
+    let mut buf = String::new();
+
+    buf.push_str("window.ROC_SEARCH_INDEX = [");
+
+    for (module, exposed_values) in modules {
+        let href = sidebar_link_url(module);
+
+        for entry in &module.entries {
+            if let DocEntry::DocDef(doc_def) = entry {
+                if !exposed_values.contains(&doc_def.name) {
+                    continue;
+                }
+
+                let mut type_ann = String::new();
+
+                for type_var in &doc_def.type_vars {
+                    type_ann.push_str(type_var.as_str());
+                    type_ann.push(' ');
+                }
+
+                // Signatures are laid out over several lines on the page, but results show
+                // them on one line.
+                type_ann.push_str(&one_line_type_annotation(&doc_def.type_annotation));
+
+                let doc = doc_def
+                    .docs
+                    .as_deref()
+                    .and_then(|docs| docs.lines().map(str::trim).find(|line| !line.is_empty()))
+                    .unwrap_or("");
+
+                let entry = serde_json::json!({
+                    "module": module.name,
+                    "name": doc_def.name,
+                    "type": type_ann,
+                    "doc": doc,
+                    "url": format!("{}#{}", href, doc_def.name),
+                });
+
+                // JSON is valid JavaScript, so the index can be loaded with a <script> tag
+                buf.push_str("\n  ");
+                buf.push_str(&entry.to_string());
+                buf.push(',');
+            }
+        }
+    }
+
+    buf.push_str("\n];\n");
+
+    buf
+}
+
+/// The type annotation as it's shown on the page, but without links
+fn plain_type_annotation(type_ann: &TypeAnnotation) -> String {
+    let mut buf = String::new();
+
+    type_annotation_to_html(0, &mut buf, None, type_ann, false);
+
+    buf
+}
+
+fn one_line_type_annotation(type_ann: &TypeAnnotation) -> String {
+    plain_type_annotation(type_ann)
+        .split_whitespace()
+        .collect::<Vec<&str>>()
+        .join(" ")
+}
+
+/// e.g. `map : List a, (a -> b) -> List b`, or `Result ok err : [Ok ok, Err err]`
+fn plain_signature(doc_def: &DocDef) -> String {
+    let mut buf = doc_def.name.clone();
+
+    for type_var in &doc_def.type_vars {
+        buf.push(' ');
+        buf.push_str(type_var.as_str());
+    }
+
+    buf.push_str(annotation_separator(&doc_def.type_annotation));
+    buf.push_str(&plain_type_annotation(&doc_def.type_annotation));
+
+    buf
+}
+
+/// What goes between a definition's name and its type annotation
+fn annotation_separator(type_ann: &TypeAnnotation) -> &'static str {
+    match type_ann {
+        TypeAnnotation::NoTypeAnn => "",
+        // e.g. `Hash has` followed by the ability's members
+        TypeAnnotation::Ability { .. } => " has",
+        _ => " : ",
+    }
+}
+
 pub fn load_modules_for_files(filenames: Vec<PathBuf>) -> Vec<LoadedModule> {
     let arena = Bump::new();
     let mut modules = Vec::with_capacity(filenames.len());
 
     for filename in filenames {
-        let load_config = LoadConfig {
-            target_info: roc_target::TargetInfo::default_x86_64(), // This is just type-checking for docs, so "target" doesn't matter
-            render: roc_reporting::report::RenderTarget::ColorTerminal,
-            palette: roc_reporting::report::DEFAULT_PALETTE,
-            threading: Threading::AllAvailable,
-            exec_mode: ExecutionMode::Check,
-        };
         match roc_load::load_and_typecheck(
             &arena,
             filename,
             Default::default(),
             RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
-            load_config,
+            load_config(),
         ) {
-            Ok(loaded) => modules.push(loaded),
+            Ok(mut loaded) => {
+                add_inferred_types(&mut loaded);
+
+                modules.push(loaded);
+            }
             Err(LoadingProblem::FormattedReport(report)) => {
                 eprintln!("{}", report);
                 std::process::exit(1);
@@ -482,6 +770,57 @@ pub fn load_modules_for_files(filenames: Vec<PathBuf>) -> Vec<LoadedModule> {
     modules
 }
 
+fn load_config() -> LoadConfig {
+    LoadConfig {
+        target_info: roc_target::TargetInfo::default_x86_64(), // This is just type-checking for docs, so "target" doesn't matter
+        render: roc_reporting::report::RenderTarget::ColorTerminal,
+        palette: roc_reporting::report::Palette::from_env(),
+        threading: Threading::AllAvailable,
+        exec_mode: ExecutionMode::Check,
+        frac_default: FloatWidth::F64,
+        opt_level: OptLevel::Development,
+    }
+}
+
+/// Show the inferred types of exposed values that don't have an annotation,
+/// instead of leaving their type out of the docs.
+fn add_inferred_types(loaded: &mut LoadedModule) {
+    let signatures = roc_load::signatures::inferred_signatures(loaded);
+    let interns = &loaded.interns;
+
+    let module_docs = match loaded.documentation.get_mut(&loaded.module_id) {
+        Some(module_docs) => module_docs,
+        None => return,
+    };
+
+    for entry in module_docs.entries.iter_mut() {
+        if let DocEntry::DocDef(doc_def) = entry {
+            if !matches!(doc_def.type_annotation, TypeAnnotation::NoTypeAnn) {
+                continue;
+            }
+
+            let inferred = signatures.iter().find_map(|signature| {
+                let name = signature.symbol.as_str(interns);
+
+                if name == doc_def.name {
+                    // The signature is the whole annotation, e.g. `add : Num a, Num a -> Num a`
+                    signature
+                        .text
+                        .strip_prefix(name)?
+                        .trim_start()
+                        .strip_prefix(':')
+                } else {
+                    None
+                }
+            });
+
+            if let Some(type_ann) = inferred.and_then(type_annotation_from_str) {
+                doc_def.type_annotation = type_ann;
+            }
+        }
+    }
+}
+
 const INDENT: &str = "    ";
 
 fn indent(buf: &mut String, times: usize) {
@@ -494,10 +833,71 @@ fn new_line(buf: &mut String) {
     buf.push('\n');
 }
 
+/// Builtins are documented at roc-lang.org, so types from them can be linked to from any package.
+const BUILTINS_DOCS_URL: &str = "https://www.roc-lang.org/builtins/";
+
+/// What's needed to link the type names in a module's signatures to where those types are defined
+struct TypeLinks<'a> {
+    scope: &'a Scope,
+    interns: &'a Interns,
+    /// The modules these docs are being generated for
+    documented_modules: &'a [&'a str],
+}
+
+impl TypeLinks<'_> {
+    fn type_url(&self, name: &str) -> Option<String> {
+        // Qualified names say which module the type is from; otherwise it's whichever
+        // module the type was imported from (or this one, if it's defined here).
+        let (module_id, type_name) = match name.rsplit_once('.') {
+            Some((module_name, type_name)) => {
+                let module_id = self.interns.module_ids.get_id(&module_name.into())?;
+
+                (module_id, type_name)
+            }
+            None => {
+                let symbol = self.scope.lookup_str(name, Region::zero()).ok()?;
+
+                (symbol.module_id(), name)
+            }
+        };
+        let module_name = self.interns.module_ids.get_name(module_id)?.as_str();
+
+        let mut url = if self.documented_modules.contains(&module_name) {
+            base_url()
+        } else if module_id.is_builtin() {
+            BUILTINS_DOCS_URL.to_string()
+        } else {
+            // We don't know where other packages' docs are hosted.
+            return None;
+        };
+
+        url.push_str(module_name);
+        url.push('#');
+        url.push_str(type_name);
+
+        Some(url)
+    }
+}
+
+fn push_type_name(buf: &mut String, links: Option<&TypeLinks>, name: &str) {
+    match links.and_then(|links| links.type_url(name)) {
+        Some(url) => buf.push_str(
+            html_to_string(
+                "a",
+                vec![("class", "type-link"), ("href", url.as_str())],
+                name,
+            )
+            .as_str(),
+        ),
+        None => buf.push_str(name),
+    }
+}
+
 // html is written to buf
 fn type_annotation_to_html(
     indent_level: usize,
     buf: &mut String,
+    links: Option<&TypeLinks>,
     type_ann: &TypeAnnotation,
     needs_parens: bool,
 ) {
@@ -534,7 +934,7 @@ fn type_annotation_to_html(
 
                     for type_value in &tag.values {
                         buf.push(' ');
-                        type_annotation_to_html(next_indent_level, buf, type_value, true);
+                        type_annotation_to_html(next_indent_level, buf, links, type_value, true);
                     }
 
                     if is_multiline {
@@ -553,23 +953,23 @@ fn type_annotation_to_html(
                 buf.push(']');
             }
 
-            type_annotation_to_html(indent_level, buf, extension, true);
+            type_annotation_to_html(indent_level, buf, links, extension, true);
         }
         TypeAnnotation::BoundVariable(var_name) => {
             buf.push_str(var_name);
         }
         TypeAnnotation::Apply { name, parts } => {
             if parts.is_empty() {
-                buf.push_str(name);
+                push_type_name(buf, links, name);
             } else {
                 if needs_parens {
                     buf.push('(');
                 }
 
-                buf.push_str(name);
+                push_type_name(buf, links, name);
                 for part in parts {
                     buf.push(' ');
-                    type_annotation_to_html(indent_level, buf, part, true);
+                    type_annotation_to_html(indent_level, buf, links, part, true);
                 }
 
                 if needs_parens {
@@ -617,13 +1017,25 @@ fn type_annotation_to_html(
                             type_annotation, ..
                         } => {
                             buf.push_str(" : ");
-                            type_annotation_to_html(next_indent_level, buf, type_annotation, false);
+                            type_annotation_to_html(
+                                next_indent_level,
+                                buf,
+                                links,
+                                type_annotation,
+                                false,
+                            );
                         }
                         RecordField::OptionalField {
                             type_annotation, ..
                         } => {
                             buf.push_str(" ? ");
-                            type_annotation_to_html(next_indent_level, buf, type_annotation, false);
+                            type_annotation_to_html(
+                                next_indent_level,
+                                buf,
+                                links,
+                                type_annotation,
+                                false,
+                            );
                         }
                         RecordField::LabelOnly { .. } => {}
                     }
@@ -646,43 +1058,107 @@ fn type_annotation_to_html(
                 buf.push('}');
             }
 
-            type_annotation_to_html(indent_level, buf, extension, true);
+            type_annotation_to_html(indent_level, buf, links, extension, true);
         }
         TypeAnnotation::Function { args, output } => {
+            if needs_parens {
+                buf.push('(');
+            }
+
+            // Multiline functions put each argument on its own line, followed by the arrow:
+            //
+            //     List a,
+            //     (a -> b)
+            //     -> List b
             let mut peekable_args = args.iter().peekable();
             while let Some(arg) = peekable_args.next() {
-                if is_multiline {
-                    if !should_be_multiline(arg) {
-                        new_line(buf);
-                    }
+                if is_multiline && !should_be_multiline(arg) {
+                    new_line(buf);
                     indent(buf, indent_level + 1);
                 }
 
-                type_annotation_to_html(indent_level, buf, arg, false);
+                // A function can only be an argument if it's wrapped in parens.
+                let arg_needs_parens = matches!(arg, TypeAnnotation::Function { .. });
+
+                type_annotation_to_html(indent_level, buf, links, arg, arg_needs_parens);
 
                 if peekable_args.peek().is_some() {
-                    buf.push_str(", ");
+                    buf.push(',');
+
+                    if !is_multiline {
+                        buf.push(' ');
+                    }
                 }
             }
 
             if is_multiline {
                 new_line(buf);
                 indent(buf, indent_level + 1);
+                buf.push_str("-> ");
+            } else {
+                buf.push_str(" -> ");
             }
 
-            buf.push_str(" -> ");
-
             let mut next_indent_level = indent_level;
 
             if should_be_multiline(output) {
                 next_indent_level += 1;
             }
 
-            type_annotation_to_html(next_indent_level, buf, output, false);
+            type_annotation_to_html(next_indent_level, buf, links, output, false);
+
+            if needs_parens {
+                buf.push(')');
+            }
+        }
+        TypeAnnotation::Ability { members } => {
+            for member in members {
+                new_line(buf);
+                indent(buf, indent_level + 1);
+
+                buf.push_str(member.name.as_str());
+                buf.push_str(" : ");
+
+                type_annotation_to_html(
+                    indent_level + 1,
+                    buf,
+                    links,
+                    &member.type_annotation,
+                    false,
+                );
+
+                if !member.able_variables.is_empty() {
+                    buf.push_str(" | ");
+                    push_has_clauses(indent_level + 1, buf, links, &member.able_variables);
+                }
+            }
         }
-        TypeAnnotation::Ability { members: _ } => {
-            // TODO(abilities): fill me in
+        TypeAnnotation::Where { ann, has_clauses } => {
+            type_annotation_to_html(indent_level, buf, links, ann, needs_parens);
+
+            // Keep the clauses from getting lost at the end of a long signature.
+            if is_multiline {
+                new_line(buf);
+                indent(buf, indent_level + 1);
+                buf.push_str("| ");
+            } else {
+                buf.push_str(" | ");
+            }
+
+            push_has_clauses(indent_level, buf, links, has_clauses);
+        }
+        TypeAnnotation::As { ann, name, vars } => {
+            type_annotation_to_html(indent_level, buf, links, ann, true);
+
+            buf.push_str(" as ");
+            buf.push_str(name.as_str());
+
+            for var in vars {
+                buf.push(' ');
+                buf.push_str(var.as_str());
+            }
         }
+        TypeAnnotation::Inferred => buf.push('_'),
         TypeAnnotation::ObscuredTagUnion => {
             buf.push_str("[@..]");
         }
@@ -694,6 +1170,108 @@ fn type_annotation_to_html(
     }
 }
 
+/// e.g. `a has Hash & Eq, hasher has Hasher`
+fn push_has_clauses(
+    indent_level: usize,
+    buf: &mut String,
+    links: Option<&TypeLinks>,
+    has_clauses: &[(String, Vec<TypeAnnotation>)],
+) {
+    for (index, (var_name, abilities)) in has_clauses.iter().enumerate() {
+        if index > 0 {
+            buf.push_str(", ");
+        }
+
+        buf.push_str(var_name.as_str());
+        buf.push_str(" has ");
+
+        for (index, ability) in abilities.iter().enumerate() {
+            if index > 0 {
+                buf.push_str(" & ");
+            }
+
+            type_annotation_to_html(indent_level, buf, links, ability, true);
+        }
+    }
+}
+
+/// Signatures that would be wider than this on one line get split across lines
+const MAX_SIGNATURE_WIDTH: usize = 80;
+
+/// Roughly how wide the annotation would be if it were all on one line
+fn flat_width(type_ann: &TypeAnnotation) -> usize {
+    fn separators(count: usize, width: usize) -> usize {
+        count.saturating_sub(1) * width
+    }
+
+    match type_ann {
+        TypeAnnotation::TagUnion { tags, extension } => {
+            let tags_width: usize = tags
+                .iter()
+                .map(|tag| {
+                    tag.name.len()
+                        + tag
+                            .values
+                            .iter()
+                            .map(|value| 1 + flat_width(value))
+                            .sum::<usize>()
+                })
+                .sum();
+
+            2 + tags_width + separators(tags.len(), 2) + flat_width(extension)
+        }
+        TypeAnnotation::Function { args, output } => {
+            let args_width: usize = args.iter().map(flat_width).sum();
+
+            args_width + separators(args.len(), 2) + 4 + flat_width(output)
+        }
+        TypeAnnotation::ObscuredTagUnion => 5,
+        TypeAnnotation::ObscuredRecord => 7,
+        TypeAnnotation::BoundVariable(var_name) => var_name.len(),
+        TypeAnnotation::Apply { name, parts } => {
+            name.len() + parts.iter().map(|part| 1 + flat_width(part)).sum::<usize>()
+        }
+        TypeAnnotation::Record { fields, extension } => {
+            let fields_width: usize = fields
+                .iter()
+                .map(|field| match field {
+                    RecordField::RecordField {
+                        name,
+                        type_annotation,
+                    }
+                    | RecordField::OptionalField {
+                        name,
+                        type_annotation,
+                    } => name.len() + 3 + flat_width(type_annotation),
+                    RecordField::LabelOnly { name } => name.len(),
+                })
+                .sum();
+
+            4 + fields_width + separators(fields.len(), 2) + flat_width(extension)
+        }
+        TypeAnnotation::Where { ann, has_clauses } => {
+            let clauses_width: usize = has_clauses
+                .iter()
+                .map(|(var_name, abilities)| {
+                    var_name.len()
+                        + 5
+                        + abilities.iter().map(flat_width).sum::<usize>()
+                        + separators(abilities.len(), 3)
+                })
+                .sum();
+
+            flat_width(ann) + 3 + clauses_width + separators(has_clauses.len(), 2)
+        }
+        TypeAnnotation::As { ann, name, vars } => {
+            flat_width(ann) + 4 + name.len() + vars.iter().map(|var| 1 + var.len()).sum::<usize>()
+        }
+        // Abilities always list their members on separate lines.
+        TypeAnnotation::Ability { .. } => 0,
+        TypeAnnotation::Inferred | TypeAnnotation::Wildcard => 1,
+        TypeAnnotation::NoTypeAnn => 0,
+    }
+}
+
 fn should_be_multiline(type_ann: &TypeAnnotation) -> bool {
     match type_ann {
         TypeAnnotation::TagUnion { tags, extension } => {
@@ -711,7 +1289,9 @@ fn should_be_multiline(type_ann: &TypeAnnotation) -> bool {
             is_multiline
         }
         TypeAnnotation::Function { args, output } => {
-            let mut is_multiline = should_be_multiline(output) || args.len() > 2;
+            let mut is_multiline = should_be_multiline(output)
+                || args.len() > 2
+                || flat_width(type_ann) > MAX_SIGNATURE_WIDTH;
 
             for arg in args {
                 if is_multiline {
@@ -760,6 +1340,9 @@ fn should_be_multiline(type_ann: &TypeAnnotation) -> bool {
             is_multiline
         }
         TypeAnnotation::Ability { .. } => true,
+        TypeAnnotation::Where { ann, .. } => should_be_multiline(ann),
+        TypeAnnotation::As { ann, .. } => should_be_multiline(ann),
+        TypeAnnotation::Inferred => false,
         TypeAnnotation::Wildcard => false,
         TypeAnnotation::NoTypeAnn => false,
     }
@@ -1044,3 +1627,169 @@ fn markdown_to_html(
 
     docs_html
 }
+
+#[cfg(test)]
+mod test_docs {
+    use super::*;
+    use roc_load::docs::AbilityMember;
+    use roc_module::ident::ModuleName;
+    use roc_module::symbol::{IdentIds, ModuleIds};
+
+    pub(crate) fn module_documentation(name: &str, entries: Vec<DocEntry>) -> ModuleDocumentation {
+        let home = ModuleIds::default().get_or_insert(&ModuleName::from(name));
+
+        ModuleDocumentation {
+            name: name.to_string(),
+            entries,
+            scope: Scope::new(home, IdentIds::default(), Default::default()),
+        }
+    }
+
+    pub(crate) fn doc_def(
+        name: &str,
+        type_annotation: TypeAnnotation,
+        docs: Option<&str>,
+    ) -> DocEntry {
+        DocEntry::DocDef(DocDef {
+            name: name.to_string(),
+            type_vars: Vec::new(),
+            type_annotation,
+            docs: docs.map(str::to_string),
+        })
+    }
+
+    pub(crate) fn apply(name: &str, parts: Vec<TypeAnnotation>) -> TypeAnnotation {
+        TypeAnnotation::Apply {
+            name: name.to_string(),
+            parts,
+        }
+    }
+
+    #[test]
+    fn search_index_has_exposed_values() {
+        let module = module_documentation(
+            "Parser",
+            vec![
+                DocEntry::DetachedDoc("A parser.".to_string()),
+                doc_def(
+                    "parse",
+                    TypeAnnotation::Function {
+                        args: vec![apply("Str", vec![]), apply("U64", vec![])],
+                        output: Box::new(apply("List", vec![apply("Str", vec![])])),
+                    },
+                    Some("\n  Parses a \"number\".\n\nIt has to be positive."),
+                ),
+                doc_def("helper", apply("Str", vec![]), None),
+            ],
+        );
+
+        let index = render_search_index(std::iter::once((&module, vec!["parse".to_string()])));
+        let entries: Vec<serde_json::Value> = index
+            .lines()
+            .filter_map(|line| line.trim().strip_suffix(','))
+            .map(|entry| serde_json::from_str(entry).unwrap())
+            .collect();
+
+        assert!(index.starts_with("window.ROC_SEARCH_INDEX = ["));
+        assert_eq!(
+            entries,
+            vec![serde_json::json!({
+                "module": "Parser",
+                "name": "parse",
+                "type": "Str, U64 -> List Str",
+                "doc": "Parses a \"number\".",
+                "url": format!("{}#parse", sidebar_link_url(&module)),
+            })]
+        );
+    }
+
+    #[test]
+    fn type_links() {
+        let mut interns = Interns::default();
+        let home = interns.module_id(&ModuleName::from("Parser"));
+        interns.module_id(&ModuleName::from("Json"));
+        interns.module_id(&ModuleName::from("Http"));
+
+        let scope = Scope::new(home, IdentIds::default(), Default::default());
+        let links = TypeLinks {
+            scope: &scope,
+            interns: &interns,
+            documented_modules: &["Parser", "Json"],
+        };
+
+        // builtins are linked to their docs on roc-lang.org
+        assert_eq!(
+            links.type_url("Str"),
+            Some(format!("{}Str#Str", BUILTINS_DOCS_URL))
+        );
+        // types from the modules being documented are linked to their pages
+        assert_eq!(
+            links.type_url("Json.Value"),
+            Some(format!("{}Json#Value", base_url()))
+        );
+        // we don't know where other packages' docs are
+        assert_eq!(links.type_url("Http.Request"), None);
+        assert_eq!(links.type_url("Missing"), None);
+        assert_eq!(links.type_url("Missing.Type"), None);
+
+        let mut buf = String::new();
+        let type_ann = apply("List", vec![apply("Missing", vec![])]);
+        type_annotation_to_html(0, &mut buf, Some(&links), &type_ann, false);
+
+        assert_eq!(
+            buf,
+            format!(
+                "<a class=\"type-link\" href=\"{}List#List\" >List</a> Missing",
+                BUILTINS_DOCS_URL
+            )
+        );
+    }
+
+    fn signature(src: &str) -> String {
+        one_line_type_annotation(&type_annotation_from_str(src).unwrap())
+    }
+
+    #[test]
+    fn has_clauses_and_as_aliases() {
+        assert_eq!(
+            signature("a, b -> Str | a has Inspect & Eq, b has Hash"),
+            "a, b -> Str | a has Inspect & Eq, b has Hash"
+        );
+        assert_eq!(
+            signature("[Cons a (ConsList a), Nil] as ConsList a"),
+            "[ Cons a (ConsList a), Nil ] as ConsList a"
+        );
+        assert_eq!(signature("List _"), "List _");
+    }
+
+    #[test]
+    fn ability_members() {
+        let doc_def = DocDef {
+            name: "Hash".to_string(),
+            type_vars: Vec::new(),
+            type_annotation: TypeAnnotation::Ability {
+                members: vec![AbilityMember {
+                    name: "hash".to_string(),
+                    type_annotation: TypeAnnotation::Function {
+                        args: vec![
+                            TypeAnnotation::BoundVariable("hasher".to_string()),
+                            TypeAnnotation::BoundVariable("a".to_string()),
+                        ],
+                        output: Box::new(TypeAnnotation::BoundVariable("hasher".to_string())),
+                    },
+                    able_variables: vec![
+                        ("a".to_string(), vec![apply("Hash", vec![])]),
+                        ("hasher".to_string(), vec![apply("Hasher", vec![])]),
+                    ],
+                    docs: None,
+                }],
+            },
+            docs: None,
+        };
+
+        assert_eq!(
+            plain_signature(&doc_def),
+            "Hash has\n    hash : hasher, a -> hasher | a has Hash, hasher has Hasher"
+        );
+    }
+}
@@ -0,0 +1,96 @@
+use crate::plain_signature;
+use roc_load::docs::{DocEntry, ModuleDocumentation};
+
+/// Render a module's docs as markdown, e.g. for a static site generator. Doc comments are
+/// markdown already, so they are included as they were written.
+pub fn render_module(module: &ModuleDocumentation, exposed_values: &[String]) -> String {
+    let mut buf = String::new();
+
+    buf.push_str("# ");
+    buf.push_str(module.name.as_str());
+    buf.push('\n');
+
+    for entry in &module.entries {
+        match entry {
+            DocEntry::DocDef(doc_def) => {
+                // We don't want to render entries that aren't exposed
+                if !exposed_values.contains(&doc_def.name) {
+                    continue;
+                }
+
+                buf.push_str("\n## ");
+                buf.push_str(doc_def.name.as_str());
+                buf.push_str("\n\n```roc\n");
+                buf.push_str(plain_signature(doc_def).as_str());
+                buf.push_str("\n```\n");
+
+                if let Some(docs) = &doc_def.docs {
+                    push_docs(&mut buf, docs);
+                }
+            }
+            DocEntry::DetachedDoc(docs) => {
+                push_docs(&mut buf, docs);
+            }
+        }
+    }
+
+    buf
+}
+
+fn push_docs(buf: &mut String, docs: &str) {
+    let docs = docs.trim();
+
+    if !docs.is_empty() {
+        buf.push('\n');
+        buf.push_str(docs);
+        buf.push('\n');
+    }
+}
+
+#[cfg(test)]
+mod test_markdown {
+    use super::*;
+    use crate::test_docs::{apply, doc_def, module_documentation};
+    use roc_load::docs::TypeAnnotation;
+
+    #[test]
+    fn module_markdown() {
+        let module = module_documentation(
+            "Parser",
+            vec![
+                DocEntry::DetachedDoc(" A parser.\n".to_string()),
+                doc_def(
+                    "parse",
+                    TypeAnnotation::Function {
+                        args: vec![apply("Str", vec![])],
+                        output: Box::new(apply("List", vec![apply("Str", vec![])])),
+                    },
+                    Some(" Parses `Str`s.\n"),
+                ),
+                doc_def("helper", apply("Str", vec![]), Some("Not exposed.")),
+                doc_def("empty", apply("Str", vec![]), None),
+            ],
+        );
+
+        assert_eq!(
+            render_module(&module, &["parse".to_string(), "empty".to_string()]),
+            "# Parser\n\
+             \n\
+             A parser.\n\
+             \n\
+             ## parse\n\
+             \n\
+             ```roc\n\
+             parse : Str -> List Str\n\
+             ```\n\
+             \n\
+             Parses `Str`s.\n\
+             \n\
+             ## empty\n\
+             \n\
+             ```roc\n\
+             empty : Str\n\
+             ```\n"
+        );
+    }
+}
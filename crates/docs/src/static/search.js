@@ -1,10 +1,86 @@
 (() => {
   let sidebar = document.getElementById("sidebar-nav");
   let searchBox = document.getElementById("module-search");
+  let searchResults = document.getElementById("search-results");
+  let searchIndex = window.ROC_SEARCH_INDEX || [];
+
+  // How many results to show at once, so the sidebar stays usable for short queries.
+  const MAX_RESULTS = 20;
+
+  // Lower is better: exact name matches first, then names starting with the query,
+  // then names containing it, then entries whose type or docs mention it.
+  function rank(entry, text) {
+    let name = entry.name.toLowerCase();
+    let qualifiedName = `${entry.module}.${entry.name}`.toLowerCase();
+
+    if (name === text || qualifiedName === text) {
+      return 0;
+    } else if (name.startsWith(text) || qualifiedName.startsWith(text)) {
+      return 1;
+    } else if (qualifiedName.includes(text)) {
+      return 2;
+    } else if (entry.type.toLowerCase().includes(text)) {
+      return 3;
+    } else if (entry.doc.toLowerCase().includes(text)) {
+      return 4;
+    }
+
+    return null;
+  }
+
+  function renderResult(entry) {
+    let link = document.createElement("a");
+    link.href = entry.url;
+
+    let name = document.createElement("span");
+    name.className = "search-result-name";
+    name.textContent = `${entry.module}.${entry.name}`;
+    link.appendChild(name);
+
+    if (entry.type !== "") {
+      let type = document.createElement("code");
+      type.className = "search-result-type";
+      type.textContent = ` : ${entry.type}`;
+      link.appendChild(type);
+    }
+
+    if (entry.doc !== "") {
+      let doc = document.createElement("p");
+      doc.className = "search-result-doc";
+      doc.textContent = entry.doc;
+      link.appendChild(doc);
+    }
+
+    let item = document.createElement("li");
+    item.appendChild(link);
+
+    return item;
+  }
+
+  function showResults(text) {
+    searchResults.replaceChildren();
+
+    if (text === "") {
+      searchResults.classList.add("hidden");
+      return;
+    }
+
+    let matches = searchIndex
+      .map((entry) => ({ entry, rank: rank(entry, text) }))
+      .filter((match) => match.rank !== null)
+      .sort((a, b) => a.rank - b.rank || a.entry.name.localeCompare(b.entry.name))
+      .slice(0, MAX_RESULTS);
+
+    matches.forEach((match) => searchResults.appendChild(renderResult(match.entry)));
+
+    searchResults.classList.toggle("hidden", matches.length === 0);
+  }
 
   function search() {
     let text = searchBox.value.toLowerCase(); // Search is case-insensitive.
 
+    showResults(text);
+
     if (text === "") {
       // Un-hide everything
       sidebar.querySelectorAll(".sidebar-entry a").forEach((entry) => entry.classList.remove("hidden"));
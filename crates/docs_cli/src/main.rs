@@ -1,15 +1,16 @@
 //! Provides a binary that is only used for static build servers.
 use clap::{Arg, Command};
-use roc_docs::generate_docs_html;
+use roc_docs::{generate_docs, DocsFormat};
 use std::fs::{self, FileType};
 use std::io;
 use std::path::{Path, PathBuf};
 
 pub const DIRECTORY_OR_FILES: &str = "DIRECTORY_OR_FILES";
+pub const FLAG_FORMAT: &str = "format";
 
 fn main() -> io::Result<()> {
     let matches = Command::new("roc-docs")
-        .about("Build HTML documentation files from the given .roc files")
+        .about("Build documentation files (HTML by default) from the given .roc files")
         .arg(
             Arg::new(DIRECTORY_OR_FILES)
                 .multiple_values(true)
@@ -17,6 +18,14 @@ fn main() -> io::Result<()> {
                 .help("The directory or files to build documentation for")
                 .allow_invalid_utf8(true),
         )
+        .arg(
+            Arg::new(FLAG_FORMAT)
+                .long(FLAG_FORMAT)
+                .help("Choose what kind of documentation to generate\n(`markdown` writes a .md file per module, for static site generators. `json` writes a single docs.json file, for package registries and other tools.)")
+                .possible_values(["html", "markdown", "json"])
+                .default_value("html")
+                .required(false),
+        )
         .get_matches();
 
     let mut roc_files = Vec::new();
@@ -27,7 +36,9 @@ fn main() -> io::Result<()> {
         roc_files_recursive(os_str, metadata.file_type(), &mut roc_files)?;
     }
 
-    generate_docs_html(roc_files);
+    let format = DocsFormat::try_from(matches.value_of(FLAG_FORMAT).unwrap()).unwrap();
+
+    generate_docs(roc_files, format);
 
     Ok(())
 }
@@ -0,0 +1,767 @@
+//! Generates a C header for a platform's Roc API, so C and C++ hosts can use the same
+//! memory layouts as the Roc app, instead of transcribing them by hand.
+//!
+//! Declarations that differ between targets (e.g. because of pointer sizes) are wrapped in
+//! `#if` blocks for each architecture, and every struct and union statically asserts that
+//! its size and alignment match what Roc uses on that target.
+use crate::rust_glue::{max_pointer_tagged_variants, tagged_pointer_bitmask};
+use crate::types::{RocNum, RocTagUnion, RocType, TypeId, Types};
+use indexmap::IndexMap;
+use roc_target::{Architecture, TargetInfo};
+use std::fmt::Write;
+
+pub static HEADER: &[u8] = include_bytes!("../templates/header.h");
+pub const FOOTER: &str = "\n#ifdef __cplusplus\n}\n#endif\n";
+const INDENT: &str = "    ";
+
+pub fn emit(types_and_targets: &[(Types, TargetInfo)]) -> String {
+    let mut buf = String::new();
+
+    let first_types = match types_and_targets.first() {
+        Some((types, _)) => types,
+        None => return buf,
+    };
+
+    // Every target has the same types in the same order; only their sizes and alignments
+    // differ. So the first target's order works for all of them.
+    let ids = first_types.sorted_ids();
+
+    // Declare every struct and union up front, so they can refer to each other
+    // through pointers regardless of the order they are defined in.
+    let mut forward_decls = String::new();
+
+    for id in ids.iter() {
+        add_forward_decl(*id, first_types, &mut forward_decls);
+    }
+
+    if !forward_decls.is_empty() {
+        buf.push('\n');
+        buf.push_str(&forward_decls);
+    }
+
+    // Recursive tag unions are pointers, so they can be defined before their payloads.
+    for id in ids.iter() {
+        let decls = types_and_targets
+            .iter()
+            .map(|(types, target_info)| (*target_info, pointer_decl(*id, types)))
+            .collect::<Vec<_>>();
+
+        write_for_targets(&decls, &mut buf);
+    }
+
+    for id in ids.iter() {
+        let decls = types_and_targets
+            .iter()
+            .map(|(types, target_info)| (*target_info, type_decl(*id, types, *target_info)))
+            .collect::<Vec<_>>();
+
+        write_for_targets(&decls, &mut buf);
+    }
+
+    let decls = types_and_targets
+        .iter()
+        .map(|(types, target_info)| (*target_info, entry_point_decls(types)))
+        .collect::<Vec<_>>();
+
+    write_for_targets(&decls, &mut buf);
+
+    buf
+}
+
+/// Write each distinct declaration once, guarded by `#if` for the targets it applies to.
+/// A declaration that is the same on every target doesn't need a guard.
+fn write_for_targets(decls: &[(TargetInfo, String)], buf: &mut String) {
+    let mut targets_by_decl: IndexMap<&str, Vec<TargetInfo>> = IndexMap::default();
+
+    for (target_info, decl) in decls {
+        if !decl.is_empty() {
+            targets_by_decl
+                .entry(decl.as_str())
+                .or_default()
+                .push(*target_info);
+        }
+    }
+
+    match targets_by_decl.get_index(0) {
+        None => {}
+        Some((decl, targets)) if targets.len() == decls.len() => {
+            buf.push('\n');
+            buf.push_str(decl);
+        }
+        Some(_) => {
+            for (index, (decl, targets)) in targets_by_decl.iter().enumerate() {
+                let directive = if index == 0 { "#if" } else { "#elif" };
+                let condition = targets
+                    .iter()
+                    .map(|target_info| arch_condition(target_info.architecture))
+                    .collect::<Vec<_>>()
+                    .join(" || ");
+
+                write!(buf, "\n{directive} {condition}\n\n{decl}").unwrap();
+            }
+
+            buf.push_str("\n#endif\n");
+        }
+    }
+}
+
+fn arch_condition(architecture: Architecture) -> &'static str {
+    match architecture {
+        Architecture::X86_64 => "defined(__x86_64__) || defined(_M_X64)",
+        Architecture::X86_32 => "defined(__i386__) || defined(_M_IX86)",
+        Architecture::Aarch64 => "defined(__aarch64__) || defined(_M_ARM64)",
+        Architecture::Aarch32 => "defined(__arm__) || defined(_M_ARM)",
+        Architecture::Wasm32 => "defined(__wasm32__)",
+    }
+}
+
+fn add_forward_decl(id: TypeId, types: &Types, buf: &mut String) {
+    match types.get_type(id) {
+        RocType::Struct { name, .. }
+        | RocType::TagUnionPayload { name, .. }
+        | RocType::TagUnion(RocTagUnion::SingleTagStruct { name, .. })
+        | RocType::TagUnion(RocTagUnion::NullableUnwrapped { name, .. })
+        | RocType::TagUnion(RocTagUnion::NonNullableUnwrapped { name, .. }) => {
+            let name = escape_kw(name);
+
+            writeln!(buf, "typedef struct {name} {name};").unwrap();
+        }
+        RocType::TagUnion(RocTagUnion::NonRecursive { name, tags, .. }) if !tags.is_empty() => {
+            let name = escape_kw(name);
+
+            writeln!(buf, "typedef union {name} {name};").unwrap();
+        }
+        RocType::TagUnion(RocTagUnion::Recursive { name, .. })
+        | RocType::TagUnion(RocTagUnion::NullableWrapped { name, .. }) => {
+            let name = escape_kw(name);
+
+            writeln!(buf, "typedef struct {name} {name};").unwrap();
+            writeln!(buf, "typedef union union_{name} union_{name};").unwrap();
+        }
+        RocType::RocResult(ok_id, err_id) => {
+            let name = result_name(*ok_id, *err_id, types);
+
+            writeln!(buf, "typedef struct {name} {name};").unwrap();
+        }
+        RocType::TagUnion(RocTagUnion::NonRecursive { .. })
+        | RocType::TagUnion(RocTagUnion::Enumeration { .. })
+        | RocType::Unit
+        | RocType::EmptyTagUnion
+        | RocType::Num(_)
+        | RocType::Bool
+        | RocType::RocStr
+        | RocType::RocDict(_, _)
+        | RocType::RocSet(_)
+        | RocType::RocList(_)
+        | RocType::RocBox(_)
+        | RocType::RecursivePointer(_)
+        | RocType::Function { .. } => {}
+    }
+}
+
+/// The struct for a recursive tag union, which only holds a pointer to its heap allocation
+fn pointer_decl(id: TypeId, types: &Types) -> String {
+    match types.get_type(id) {
+        RocType::TagUnion(RocTagUnion::Recursive { name, .. }) => {
+            let name = escape_kw(name);
+
+            format!(
+                r#"// The discriminant is stored in the unused low bits of the pointer
+struct {name} {{
+    union_{name} *pointer;
+}};
+"#
+            )
+        }
+        RocType::TagUnion(RocTagUnion::NullableWrapped {
+            name,
+            index_of_null_tag,
+            tags,
+            ..
+        }) => {
+            let name = escape_kw(name);
+            let (null_tag, _) = &tags[*index_of_null_tag as usize];
+
+            format!(
+                r#"// A NULL pointer represents the {null_tag} tag. Otherwise,
+// the discriminant is stored in the unused low bits of the pointer.
+struct {name} {{
+    union_{name} *pointer;
+}};
+"#
+            )
+        }
+        RocType::TagUnion(RocTagUnion::NullableUnwrapped {
+            name,
+            null_tag,
+            non_null_tag,
+            non_null_payload,
+            ..
+        }) => {
+            let name = escape_kw(name);
+            let payload = declare(&pointer_to(*non_null_payload, types), "pointer");
+
+            format!(
+                r#"// A NULL pointer represents the {null_tag} tag, and any other pointer the {non_null_tag} tag.
+struct {name} {{
+    {payload};
+}};
+"#
+            )
+        }
+        RocType::TagUnion(RocTagUnion::NonNullableUnwrapped {
+            name,
+            tag_name,
+            payload,
+        }) => {
+            let name = escape_kw(name);
+            let payload = declare(&pointer_to(*payload, types), "pointer");
+
+            format!(
+                r#"// The only tag is {tag_name}, so there is no discriminant.
+struct {name} {{
+    {payload};
+}};
+"#
+            )
+        }
+        _ => String::new(),
+    }
+}
+
+fn type_decl(id: TypeId, types: &Types, target_info: TargetInfo) -> String {
+    match types.get_type(id) {
+        RocType::Struct { name, fields } => {
+            let fields = fields
+                .iter()
+                .map(|(label, type_id)| (escape_kw(label), *type_id));
+
+            struct_decl(&escape_kw(name), fields, id, types)
+        }
+        RocType::TagUnionPayload { name, fields } => {
+            // Tag union payload fields are numbered, so give them an "f" prefix
+            let fields = fields
+                .iter()
+                .map(|(label, type_id)| (format!("f{label}"), *type_id));
+
+            struct_decl(&escape_kw(name), fields, id, types)
+        }
+        RocType::TagUnion(RocTagUnion::SingleTagStruct {
+            name,
+            payload_fields,
+            ..
+        }) => {
+            let fields = payload_fields
+                .iter()
+                .enumerate()
+                .map(|(index, type_id)| (format!("f{index}"), *type_id));
+
+            struct_decl(&escape_kw(name), fields, id, types)
+        }
+        RocType::TagUnion(RocTagUnion::Enumeration { name, tags, size }) => {
+            enumeration_decl(&escape_kw(name), tags, *size)
+        }
+        RocType::TagUnion(RocTagUnion::NonRecursive {
+            name,
+            tags,
+            discriminant_size,
+            discriminant_offset,
+        }) => {
+            if tags.is_empty() {
+                // Empty tag unions can never come up at runtime,
+                // and so don't need declared types.
+                return String::new();
+            }
+
+            let name = escape_kw(name);
+            let size = types.size_rounded_to_alignment(id);
+            let mut buf = String::new();
+
+            if *discriminant_size > 0 {
+                buf.push_str(&discriminant_decl(&name, tags, *discriminant_size));
+                buf.push('\n');
+            }
+
+            writeln!(buf, "union {name} {{").unwrap();
+            write_union_members(tags, types, &mut buf);
+
+            // Roc puts the discriminant in the padding at the end of the largest payload
+            // when there's room, so the union can be smaller than a struct of the
+            // payloads and the discriminant would be. This makes room for it either way.
+            writeln!(buf, "{INDENT}uint8_t _bytes[{size}];\n}};").unwrap();
+            write_layout_asserts(&format!("union {name}"), &name, id, types, &mut buf);
+
+            if *discriminant_size > 0 {
+                write!(
+                    buf,
+                    r#"
+static inline discriminant_{name} {name}_discriminant(const {name} *tag_union) {{
+    discriminant_{name} discriminant;
+
+    memcpy(&discriminant, tag_union->_bytes + {discriminant_offset}, sizeof(discriminant));
+
+    return discriminant;
+}}
+
+static inline void {name}_set_discriminant({name} *tag_union, discriminant_{name} discriminant) {{
+    memcpy(tag_union->_bytes + {discriminant_offset}, &discriminant, sizeof(discriminant));
+}}
+"#
+                )
+                .unwrap();
+            }
+
+            buf
+        }
+        RocType::TagUnion(RocTagUnion::Recursive {
+            name,
+            tags,
+            discriminant_size,
+            discriminant_offset,
+        }) => recursive_union_decl(
+            &escape_kw(name),
+            tags,
+            None,
+            *discriminant_size,
+            *discriminant_offset,
+            types,
+            target_info,
+        ),
+        RocType::TagUnion(RocTagUnion::NullableWrapped {
+            name,
+            index_of_null_tag,
+            tags,
+            discriminant_size,
+            discriminant_offset,
+        }) => recursive_union_decl(
+            &escape_kw(name),
+            tags,
+            Some(&tags[*index_of_null_tag as usize].0),
+            *discriminant_size,
+            *discriminant_offset,
+            types,
+            target_info,
+        ),
+        RocType::RocResult(ok_id, err_id) => {
+            let name = result_name(*ok_id, *err_id, types);
+            let mut buf = format!("struct {name} {{\n{INDENT}union {{\n");
+
+            for (label, type_id) in [("err", *err_id), ("ok", *ok_id)] {
+                if types.size_ignoring_alignment(type_id) > 0 {
+                    let field = declare(&type_name(type_id, types), label);
+
+                    writeln!(buf, "{INDENT}{INDENT}{field};").unwrap();
+                }
+            }
+
+            writeln!(
+                buf,
+                "{INDENT}{INDENT}uint8_t _bytes[{}];\n{INDENT}}} payload;\n{INDENT}// 0 for Err, 1 for Ok\n{INDENT}uint8_t tag;\n}};",
+                types
+                    .size_ignoring_alignment(*ok_id)
+                    .max(types.size_ignoring_alignment(*err_id))
+                    .max(1)
+            )
+            .unwrap();
+            write_layout_asserts(&format!("struct {name}"), &name, id, types, &mut buf);
+
+            buf
+        }
+        // The pointers of these tag unions are declared up front by `pointer_decl`,
+        // and their payloads are declared as types of their own.
+        RocType::TagUnion(RocTagUnion::NullableUnwrapped { .. })
+        | RocType::TagUnion(RocTagUnion::NonNullableUnwrapped { .. }) => String::new(),
+        // These types are declared in the header, or don't need to be declared in C.
+        RocType::Unit
+        | RocType::EmptyTagUnion
+        | RocType::Num(_)
+        | RocType::Bool
+        | RocType::RocStr
+        | RocType::RocDict(_, _)
+        | RocType::RocSet(_)
+        | RocType::RocList(_)
+        | RocType::RocBox(_)
+        | RocType::RecursivePointer(_) => String::new(),
+        RocType::Function {
+            name,
+            args,
+            ret,
+            extern_name: Some(extern_name),
+        } => {
+            let mut params = args
+                .iter()
+                .enumerate()
+                .map(|(index, arg_id)| {
+                    declare(
+                        &format!("const {}", pointer_to(*arg_id, types)),
+                        &format!("arg{index}"),
+                    )
+                })
+                .collect::<Vec<_>>();
+
+            params.push("const void *closure_data".to_string());
+            params.push(declare(&pointer_to(*ret, types), "output"));
+
+            format!(
+                r#"// The Roc function {name}, which the app passes to the host. The values it captured
+// depend on the app, so their size is only known at runtime. The app takes ownership of
+// the arguments and the captured values when the function is called.
+void {extern_name}_caller({});
+int64_t {extern_name}_size(void);
+int64_t {extern_name}_result_size(void);
+"#,
+                params.join(", ")
+            )
+        }
+        RocType::Function {
+            extern_name: None, ..
+        } => {
+            // See `RocType::Function::extern_name`
+            String::new()
+        }
+    }
+}
+
+fn struct_decl<I>(name: &str, fields: I, id: TypeId, types: &Types) -> String
+where
+    I: Iterator<Item = (String, TypeId)>,
+{
+    let mut buf = format!("struct {name} {{\n");
+    let mut has_fields = false;
+
+    for (label, type_id) in fields {
+        // C doesn't have zero-sized types, but they don't affect the layout anyway.
+        if types.size_ignoring_alignment(type_id) > 0 {
+            writeln!(
+                buf,
+                "{INDENT}{};",
+                declare(&type_name(type_id, types), &label)
+            )
+            .unwrap();
+
+            has_fields = true;
+        }
+    }
+
+    if !has_fields {
+        return String::new();
+    }
+
+    buf.push_str("};\n");
+    write_layout_asserts(&format!("struct {name}"), name, id, types, &mut buf);
+
+    buf
+}
+
+fn enumeration_decl(name: &str, tags: &[String], size: u32) -> String {
+    // C enums have the size of an int, so use an integer of the right size for the type,
+    // and constants for each of the tags.
+    let mut buf = format!("typedef uint{}_t {name};\n\nenum {{\n", size * 8);
+
+    for (index, tag_name) in tags.iter().enumerate() {
+        writeln!(buf, "{INDENT}{name}_{tag_name} = {index},").unwrap();
+    }
+
+    buf.push_str("};\n");
+
+    buf
+}
+
+fn discriminant_decl(name: &str, tags: &[(String, Option<TypeId>)], size: u32) -> String {
+    let tag_names = tags
+        .iter()
+        .map(|(tag_name, _)| tag_name.clone())
+        .collect::<Vec<_>>();
+
+    // The constants are named after the discriminant type, e.g. `discriminant_Expr_Add`,
+    // because `Expr_Add` is the name of the struct holding that tag's payload.
+    enumeration_decl(&format!("discriminant_{name}"), &tag_names, size)
+}
+
+fn write_union_members(tags: &[(String, Option<TypeId>)], types: &Types, buf: &mut String) {
+    for (tag_name, opt_payload_id) in tags {
+        if let Some(payload_id) = opt_payload_id {
+            if types.size_ignoring_alignment(*payload_id) > 0 {
+                let member = declare(&type_name(*payload_id, types), tag_name);
+
+                writeln!(buf, "{INDENT}{member};").unwrap();
+            }
+        }
+    }
+}
+
+fn recursive_union_decl(
+    name: &str,
+    tags: &[(String, Option<TypeId>)],
+    null_tag: Option<&str>,
+    discriminant_size: u32,
+    discriminant_offset: u32,
+    types: &Types,
+    target_info: TargetInfo,
+) -> String {
+    let mut buf = String::new();
+
+    if discriminant_size > 0 {
+        buf.push_str(&discriminant_decl(name, tags, discriminant_size));
+        buf.push('\n');
+    }
+
+    writeln!(buf, "union union_{name} {{").unwrap();
+    write_union_members(tags, types, &mut buf);
+
+    if !tags
+        .iter()
+        .any(|(_, opt_payload_id)| matches!(opt_payload_id, Some(id) if types.size_ignoring_alignment(*id) > 0))
+    {
+        // C doesn't allow empty unions
+        writeln!(buf, "{INDENT}uint8_t _unused;").unwrap();
+    }
+
+    buf.push_str("};\n");
+
+    if discriminant_size == 0 {
+        return buf;
+    }
+
+    let null_check = match null_tag {
+        Some(null_tag) => format!(
+            r#"
+    if (tag_union.pointer == NULL) {{
+        return discriminant_{name}_{null_tag};
+    }}
+"#
+        ),
+        None => String::new(),
+    };
+
+    if tags.len() <= max_pointer_tagged_variants(target_info.architecture) {
+        let bitmask = tagged_pointer_bitmask(target_info.architecture);
+
+        write!(
+            buf,
+            r#"
+static inline discriminant_{name} {name}_discriminant({name} tag_union) {{{null_check}
+    return (discriminant_{name})((uintptr_t)tag_union.pointer & {bitmask:#x});
+}}
+
+static inline union_{name} *{name}_union_pointer({name} tag_union) {{
+    return (union_{name} *)((uintptr_t)tag_union.pointer & ~(uintptr_t){bitmask:#x});
+}}
+"#
+        )
+        .unwrap();
+    } else {
+        // There are too many tags to fit the discriminant in the pointer,
+        // so it's stored in the heap allocation instead.
+        write!(
+            buf,
+            r#"
+static inline union_{name} *{name}_union_pointer({name} tag_union) {{
+    return tag_union.pointer;
+}}
+
+static inline discriminant_{name} {name}_discriminant({name} tag_union) {{{null_check}
+    discriminant_{name} discriminant;
+
+    memcpy(&discriminant, (const uint8_t *)tag_union.pointer + {discriminant_offset}, sizeof(discriminant));
+
+    return discriminant;
+}}
+"#
+        )
+        .unwrap();
+    }
+
+    buf
+}
+
+fn write_layout_asserts(c_type: &str, name: &str, id: TypeId, types: &Types, buf: &mut String) {
+    let size = types.size_rounded_to_alignment(id);
+    let align = types.align(id);
+
+    write!(
+        buf,
+        r#"
+ROC_STATIC_ASSERT(sizeof({c_type}) == {size}, "{name} has the wrong size for this target");
+ROC_STATIC_ASSERT(ROC_ALIGNOF({c_type}) == {align}, "{name} has the wrong alignment for this target");
+"#
+    )
+    .unwrap();
+}
+
+/// The functions the host calls to run the app. Like the `_exposed_generic` functions
+/// Roc generates, these write their return value through the first argument.
+fn entry_point_decls(types: &Types) -> String {
+    let mut buf = String::new();
+
+    for (name, type_id) in types.entry_points() {
+        let symbol = format!("roc__{name}_1_exposed_generic");
+
+        match types.get_type(*type_id) {
+            RocType::Function { args, ret, .. } => {
+                let mut params = vec![declare(&pointer_to(*ret, types), "ret")];
+
+                for (index, arg_id) in args.iter().enumerate() {
+                    let arg_type = match types.get_type(*arg_id) {
+                        // Roc passes these by reference
+                        RocType::RocStr
+                        | RocType::RocList(_)
+                        | RocType::RocDict(_, _)
+                        | RocType::RocSet(_) => pointer_to(*arg_id, types),
+                        _ => type_name(*arg_id, types),
+                    };
+
+                    params.push(declare(&arg_type, &format!("arg{index}")));
+                }
+
+                writeln!(buf, "void {symbol}({});", params.join(", ")).unwrap();
+            }
+            _ => {
+                let ret = declare(&pointer_to(*type_id, types), "ret");
+
+                writeln!(buf, "void {symbol}({ret});").unwrap();
+            }
+        }
+    }
+
+    buf
+}
+
+fn type_name(id: TypeId, types: &Types) -> String {
+    match types.get_type(id) {
+        RocType::Unit | RocType::EmptyTagUnion => "void".to_string(),
+        RocType::RocStr => "RocStr".to_string(),
+        RocType::Bool => "bool".to_string(),
+        RocType::Num(RocNum::U8) => "uint8_t".to_string(),
+        RocType::Num(RocNum::U16) => "uint16_t".to_string(),
+        RocType::Num(RocNum::U32) => "uint32_t".to_string(),
+        RocType::Num(RocNum::U64) => "uint64_t".to_string(),
+        RocType::Num(RocNum::U128) => "RocU128".to_string(),
+        RocType::Num(RocNum::I8) => "int8_t".to_string(),
+        RocType::Num(RocNum::I16) => "int16_t".to_string(),
+        RocType::Num(RocNum::I32) => "int32_t".to_string(),
+        RocType::Num(RocNum::I64) => "int64_t".to_string(),
+        RocType::Num(RocNum::I128) => "RocI128".to_string(),
+        RocType::Num(RocNum::F32) => "float".to_string(),
+        RocType::Num(RocNum::F64) => "double".to_string(),
+        RocType::Num(RocNum::Dec) => "RocDec".to_string(),
+        RocType::RocDict(_, _) | RocType::RocSet(_) => "RocDict".to_string(),
+        RocType::RocList(_) => "RocList".to_string(),
+        RocType::RocBox(elem_id) => pointer_to(*elem_id, types),
+        RocType::RocResult(ok_id, err_id) => result_name(*ok_id, *err_id, types),
+        RocType::Struct { name, .. }
+        | RocType::TagUnionPayload { name, .. }
+        | RocType::TagUnion(RocTagUnion::NonRecursive { name, .. })
+        | RocType::TagUnion(RocTagUnion::Recursive { name, .. })
+        | RocType::TagUnion(RocTagUnion::Enumeration { name, .. })
+        | RocType::TagUnion(RocTagUnion::NullableWrapped { name, .. })
+        | RocType::TagUnion(RocTagUnion::NullableUnwrapped { name, .. })
+        | RocType::TagUnion(RocTagUnion::NonNullableUnwrapped { name, .. })
+        | RocType::TagUnion(RocTagUnion::SingleTagStruct { name, .. }) => escape_kw(name),
+        RocType::RecursivePointer(content) => type_name(*content, types),
+        RocType::Function { name, .. } => escape_kw(name),
+    }
+}
+
+fn pointer_to(id: TypeId, types: &Types) -> String {
+    let name = type_name(id, types);
+
+    if name.ends_with('*') {
+        format!("{name}*")
+    } else {
+        format!("{name} *")
+    }
+}
+
+/// e.g. `RocResult_RocStr_int32_t` for `Result Str I32`
+fn result_name(ok_id: TypeId, err_id: TypeId, types: &Types) -> String {
+    let ident = |id| {
+        type_name(id, types)
+            .replace(" *", "_ptr")
+            .replace('*', "_ptr")
+    };
+
+    format!("RocResult_{}_{}", ident(ok_id), ident(err_id))
+}
+
+/// Declare a variable or field, e.g. `int32_t count` or `RocStr *name`
+fn declare(type_str: &str, name: &str) -> String {
+    if type_str.ends_with('*') {
+        format!("{type_str}{name}")
+    } else {
+        format!("{type_str} {name}")
+    }
+}
+
+fn escape_kw(name: &str) -> String {
+    if RESERVED_KEYWORDS.contains(&name) {
+        format!("{name}_")
+    } else {
+        name.to_string()
+    }
+}
+
+/// The keywords of C, along with the ones C++ adds, since the header can be used from either.
+const RESERVED_KEYWORDS: &[&str] = &[
+    "alignas",
+    "alignof",
+    "auto",
+    "bool",
+    "break",
+    "case",
+    "catch",
+    "char",
+    "class",
+    "const",
+    "constexpr",
+    "continue",
+    "default",
+    "delete",
+    "do",
+    "double",
+    "else",
+    "enum",
+    "explicit",
+    "extern",
+    "false",
+    "float",
+    "for",
+    "friend",
+    "goto",
+    "if",
+    "inline",
+    "int",
+    "long",
+    "mutable",
+    "namespace",
+    "new",
+    "noexcept",
+    "nullptr",
+    "operator",
+    "private",
+    "protected",
+    "public",
+    "register",
+    "restrict",
+    "return",
+    "short",
+    "signed",
+    "sizeof",
+    "static",
+    "struct",
+    "switch",
+    "template",
+    "this",
+    "throw",
+    "true",
+    "try",
+    "typedef",
+    "typename",
+    "union",
+    "unsigned",
+    "using",
+    "virtual",
+    "void",
+    "volatile",
+    "while",
+];
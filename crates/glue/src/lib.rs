@@ -1,14 +1,16 @@
 //! Generates code needed for platform hosts to communicate with Roc apps.
 //! This tool is not necessary for writing a platform in another language,
-//! however, it's a great convenience! Currently supports Rust platforms, and
-//! the plan is to support any language via a plugin model.
+//! however, it's a great convenience! Currently supports Rust, C, and Zig platforms,
+//! and the plan is to support any language via a plugin model.
+pub mod c_glue;
 pub mod enums;
 pub mod load;
 pub mod rust_glue;
 pub mod structs;
 pub mod types;
+pub mod zig_glue;
 
 #[rustfmt::skip]
 pub mod glue;
 
-pub use load::generate;
+pub use load::{generate, GlueLanguage};
@@ -1,11 +1,22 @@
-use crate::rust_glue;
 use crate::types::{Env, Types};
+use crate::{c_glue, rust_glue, zig_glue};
 use bumpalo::Bump;
+use roc_can::expr::Expr;
+use roc_collections::MutMap;
 use roc_intern::GlobalInterner;
-use roc_load::{ExecutionMode, LoadConfig, LoadedModule, LoadingProblem, Threading};
+use roc_load::{
+    ExecutionMode, FloatWidth, LoadConfig, LoadedModule, LoadingProblem, OptLevel, Threading,
+};
+use roc_module::symbol::Interns;
 use roc_packaging::cache::{self, RocCacheDir};
-use roc_reporting::report::{RenderTarget, DEFAULT_PALETTE};
+use roc_parse::ast::{Header, Module};
+use roc_parse::module::parse_header;
+use roc_parse::state::State;
+use roc_reporting::cli::report_problems;
+use roc_reporting::report::{Palette, RenderTarget};
 use roc_target::{Architecture, OperatingSystem, TargetInfo};
+use roc_types::subs::{Subs, Variable};
+use std::ffi::OsStr;
 use std::fs::File;
 use std::io::{self, ErrorKind, Write};
 use std::path::{Path, PathBuf};
@@ -21,7 +32,38 @@ impl IgnoreErrors {
     const NONE: Self = IgnoreErrors { can: false };
 }
 
-pub fn generate(input_path: &Path, output_path: &Path) -> io::Result<i32> {
+/// The languages `roc glue` can generate code for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GlueLanguage {
+    Rust,
+    C,
+    Zig,
+}
+
+impl GlueLanguage {
+    /// Which language to generate, based on the extension of the output file
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension().and_then(OsStr::to_str) {
+            Some("rs") => Some(GlueLanguage::Rust),
+            Some("h") => Some(GlueLanguage::C),
+            Some("zig") => Some(GlueLanguage::Zig),
+            _ => None,
+        }
+    }
+}
+
+pub fn generate(
+    input_path: &Path,
+    output_path: &Path,
+    language: GlueLanguage,
+    async_effects: bool,
+) -> io::Result<i32> {
+    if async_effects && language != GlueLanguage::Rust {
+        eprintln!("Currently, --async-effects is only supported when generating Rust glue.");
+
+        return Ok(1);
+    }
+
     match load_types(
         input_path.to_path_buf(),
         Threading::AllAvailable,
@@ -38,10 +80,37 @@ pub fn generate(input_path: &Path, output_path: &Path) -> io::Result<i32> {
                 process::exit(1);
             });
 
-            let mut buf = std::str::from_utf8(rust_glue::HEADER).unwrap().to_string();
-            let body = rust_glue::emit(&types_and_targets);
+            let buf = match language {
+                GlueLanguage::Rust => {
+                    let mut buf = std::str::from_utf8(rust_glue::HEADER).unwrap().to_string();
+                    let body = rust_glue::emit(&types_and_targets);
+
+                    buf.push_str(&body);
+
+                    if async_effects {
+                        buf.push_str(&rust_glue::emit_async_effects(&types_and_targets));
+                    }
+
+                    buf
+                }
+                GlueLanguage::C => {
+                    let mut buf = std::str::from_utf8(c_glue::HEADER).unwrap().to_string();
+                    let body = c_glue::emit(&types_and_targets);
+
+                    buf.push_str(&body);
+                    buf.push_str(c_glue::FOOTER);
 
-            buf.push_str(&body);
+                    buf
+                }
+                GlueLanguage::Zig => {
+                    let mut buf = std::str::from_utf8(zig_glue::HEADER).unwrap().to_string();
+                    let body = zig_glue::emit(&types_and_targets);
+
+                    buf.push_str(&body);
+
+                    buf
+                }
+            };
 
             file.write_all(buf.as_bytes()).unwrap_or_else(|err| {
                 eprintln!(
@@ -58,6 +127,10 @@ pub fn generate(input_path: &Path, output_path: &Path) -> io::Result<i32> {
                 output_path.display()
             );
 
+            if language == GlueLanguage::Rust {
+                write_layout_tests(input_path, output_path, &types_and_targets);
+            }
+
             Ok(0)
         }
         Err(err) => match err.kind() {
@@ -77,6 +150,80 @@ pub fn generate(input_path: &Path, output_path: &Path) -> io::Result<i32> {
     }
 }
 
+/// Write tests for the Rust glue next to it, e.g. `glue_layout_tests.rs` for `glue.rs`, and
+/// the copy of the platform that provides the entry points they call next to the platform,
+/// e.g. `platform_layout_tests.roc` for `platform.roc`.
+fn write_layout_tests(
+    input_path: &Path,
+    output_path: &Path,
+    types_and_targets: &[(Types, TargetInfo)],
+) {
+    let glue_module = output_path
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .unwrap_or("glue");
+    let platform_stem = input_path
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .unwrap_or("main");
+    let tests_path = output_path.with_file_name(format!("{glue_module}_layout_tests.rs"));
+    let platform_path = input_path.with_file_name(format!("{platform_stem}_layout_tests.roc"));
+
+    let platform_src = std::fs::read_to_string(input_path).unwrap_or_else(|err| {
+        eprintln!(
+            "Unable to read platform module file {} - {:?}",
+            input_path.display(),
+            err
+        );
+
+        process::exit(1);
+    });
+
+    let platform = match rust_glue::emit_layout_test_platform(types_and_targets, &platform_src) {
+        Some(platform) => platform,
+        None => {
+            eprintln!(
+                "\nNot generating layout tests, because {} provides nothing to add their entry points to.",
+                input_path.display()
+            );
+
+            return;
+        }
+    };
+
+    if let Err(err) = std::fs::write(&platform_path, platform) {
+        eprintln!(
+            "Unable to write the platform for the layout tests to {} - {:?}",
+            platform_path.display(),
+            err
+        );
+
+        process::exit(1);
+    }
+
+    let platform_file = platform_path
+        .file_name()
+        .and_then(OsStr::to_str)
+        .unwrap_or_default();
+    let tests = rust_glue::emit_layout_tests(types_and_targets, glue_module, platform_file);
+
+    if let Err(err) = std::fs::write(&tests_path, tests) {
+        eprintln!(
+            "Unable to write layout tests to {} - {:?}",
+            tests_path.display(),
+            err
+        );
+
+        process::exit(1);
+    }
+
+    println!(
+        "\n🧪 Generated layout round-trip tests in:\n\n\t{}\n\nand the platform they run against in:\n\n\t{}",
+        tests_path.display(),
+        platform_path.display()
+    );
+}
+
 pub fn load_types(
     full_file_path: PathBuf,
     threading: Threading,
@@ -84,7 +231,6 @@ pub fn load_types(
 ) -> Result<Vec<(Types, TargetInfo)>, io::Error> {
     let target_info = (&Triple::host()).into();
     let arena = &Bump::new();
-    let subs_by_module = Default::default();
     let LoadedModule {
         module_id: home,
         mut can_problems,
@@ -92,30 +238,10 @@ pub fn load_types(
         mut declarations_by_id,
         mut solved,
         interns,
+        exposed_to_host,
+        sources,
         ..
-    } = roc_load::load_and_typecheck(
-        arena,
-        full_file_path,
-        subs_by_module,
-        RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
-        LoadConfig {
-            target_info,
-            render: RenderTarget::Generic,
-            palette: DEFAULT_PALETTE,
-            threading,
-            exec_mode: ExecutionMode::Check,
-        },
-    )
-    .unwrap_or_else(|problem| match problem {
-        LoadingProblem::FormattedReport(report) => {
-            eprintln!("{}", report);
-
-            process::exit(1);
-        }
-        problem => {
-            todo!("{:?}", problem);
-        }
-    });
+    } = load_module(arena, full_file_path, target_info, threading);
 
     let decls = declarations_by_id.remove(&home).unwrap();
     let subs = solved.inner_mut();
@@ -124,11 +250,29 @@ pub fn load_types(
     let type_problems = type_problems.remove(&home).unwrap_or_default();
 
     if (!ignore_errors.can && !can_problems.is_empty()) || !type_problems.is_empty() {
-        todo!(
-            "Gracefully report compilation problems during glue generation: {:?}, {:?}",
-            can_problems,
-            type_problems
+        let mut can_problems_by_id = MutMap::default();
+        let mut type_problems_by_id = MutMap::default();
+
+        if !ignore_errors.can {
+            can_problems_by_id.insert(home, can_problems);
+        }
+
+        type_problems_by_id.insert(home, type_problems);
+
+        let total_problems = can_problems_by_id.values().map(Vec::len).sum::<usize>()
+            + type_problems_by_id.values().map(Vec::len).sum::<usize>();
+
+        report_problems(
+            total_problems,
+            &sources,
+            &interns,
+            &mut can_problems_by_id,
+            &mut type_problems_by_id,
+            &Default::default(),
+            None,
         );
+
+        process::exit(1);
     }
 
     let variables = (0..decls.len()).filter_map(|index| {
@@ -152,6 +296,40 @@ pub fn load_types(
         }
     });
 
+    let mut entry_points: Vec<(String, Variable)> = exposed_to_host
+        .iter()
+        .map(|(symbol, var)| (symbol.as_str(&interns).to_string(), *var))
+        .collect();
+
+    entry_points.sort_by(|(name1, _), (name2, _)| name1.cmp(name2));
+
+    // Hosted modules are typechecked on their own, so load each one separately
+    // to find the signatures of the functions the host provides.
+    let hosted_modules: Vec<(Subs, Interns, Vec<(String, Variable)>)> = sources
+        .values()
+        .filter(|(_, src)| is_hosted_module(arena, src))
+        .map(|(path, _)| {
+            let LoadedModule {
+                module_id,
+                mut declarations_by_id,
+                solved,
+                interns,
+                ..
+            } = load_module(arena, path.clone(), target_info, threading);
+
+            let decls = declarations_by_id.remove(&module_id).unwrap();
+            let hosted_functions = (0..decls.len())
+                .filter_map(|index| {
+                    let name = foreign_call_name(&decls.expressions[index].value)?;
+
+                    Some((name.to_string(), decls.variables[index]))
+                })
+                .collect();
+
+            (solved.into_inner(), interns, hosted_functions)
+        })
+        .collect();
+
     let layout_interner = GlobalInterner::with_capacity(128);
 
     let architectures = Architecture::iter();
@@ -162,14 +340,82 @@ pub fn load_types(
             operating_system: OperatingSystem::Unix,
         };
 
-        let types = {
+        let mut types = {
             let mut env = Env::new(arena, subs, &interns, layout_interner.fork(), target_info);
 
-            env.vars_to_types(variables.clone())
+            env.vars_and_entry_points_to_types(variables.clone(), entry_points.iter().cloned())
         };
 
+        for (subs, interns, hosted_functions) in hosted_modules.iter() {
+            let mut env = Env::new(arena, subs, interns, layout_interner.fork(), target_info);
+
+            env.add_hosted_functions(hosted_functions.iter().cloned(), &mut types);
+        }
+
         types_and_targets.push((types, target_info));
     }
 
     Ok(types_and_targets)
 }
+
+fn load_module(
+    arena: &Bump,
+    path: PathBuf,
+    target_info: TargetInfo,
+    threading: Threading,
+) -> LoadedModule {
+    roc_load::load_and_typecheck(
+        arena,
+        path.clone(),
+        Default::default(),
+        RocCacheDir::Persistent(cache::roc_cache_dir().as_path()),
+        LoadConfig {
+            target_info,
+            render: RenderTarget::Generic,
+            palette: Palette::from_env(),
+            threading,
+            exec_mode: ExecutionMode::Check,
+            frac_default: FloatWidth::F64,
+            opt_level: OptLevel::Development,
+        },
+    )
+    .unwrap_or_else(|problem| match problem {
+        LoadingProblem::FormattedReport(report) => {
+            eprintln!("{}", report);
+
+            process::exit(1);
+        }
+        problem => {
+            // Every other problem is one the loader ran into before it had a module to
+            // report on, like a file that couldn't be read.
+            eprintln!("Unable to load {}: {:?}", path.display(), problem);
+
+            process::exit(1);
+        }
+    })
+}
+
+/// Whether this is the source of a module with a `hosted` header
+fn is_hosted_module(arena: &Bump, src: &str) -> bool {
+    matches!(
+        parse_header(arena, State::new(src.as_bytes())),
+        Ok((
+            Module {
+                header: Header::Hosted(_),
+                ..
+            },
+            _
+        ))
+    )
+}
+
+/// The name of the function the host provides for this hosted module def, if it is one.
+/// e.g. `putLine` for the `roc_fx_putLine` that `putLine : Str -> Effect {}` calls
+fn foreign_call_name(expr: &Expr) -> Option<&str> {
+    match expr {
+        Expr::OpaqueRef { argument, .. } => foreign_call_name(&argument.1.value),
+        Expr::Closure(closure_data) => foreign_call_name(&closure_data.loc_body.value),
+        Expr::ForeignCall { foreign_symbol, .. } => foreign_symbol.as_str().strip_prefix("roc_fx_"),
+        _ => None,
+    }
+}
@@ -1,9 +1,14 @@
 use crate::types::{RocNum, RocTagUnion, RocType, TypeId, Types};
+use bumpalo::Bump;
 use indexmap::IndexMap;
+use roc_parse::ast::{Header, Module};
+use roc_parse::module::parse_header;
+use roc_parse::state::State;
 use roc_target::{Architecture, TargetInfo};
 use std::fmt::{Display, Write};
 
 pub static HEADER: &[u8] = include_bytes!("../templates/header.rs");
+static ASYNC_EFFECTS_RUNTIME: &str = include_str!("../templates/async_effects.rs");
 const INDENT: &str = "    ";
 const DISCRIMINANT_DOC_COMMENT: &str =
     "/// Returns which variant this tag union holds. Note that this never includes a payload!";
@@ -138,6 +143,386 @@ pub fn emit(types_and_targets: &[(Types, TargetInfo)]) -> String {
     buf
 }
 
+/// Generate a trait for implementing the platform's effects with async functions,
+/// along with the `roc_fx_` functions Roc calls, which suspend the Roc call until
+/// the host's future for the effect completes.
+pub fn emit_async_effects(types_and_targets: &[(Types, TargetInfo)]) -> String {
+    // Hosted functions only refer to types by name, so they're the same on every target.
+    let types = match types_and_targets.first() {
+        Some((types, _)) if !types.hosted_functions().is_empty() => types,
+        _ => return String::new(),
+    };
+
+    let mut trait_methods = String::new();
+    let mut requests = String::new();
+    let mut responses = String::new();
+    let mut handlers = String::new();
+    let mut shims = String::new();
+
+    for hosted in types.hosted_functions() {
+        let name = &hosted.name;
+        let ret_type = type_name(hosted.ret, types);
+        let mut params = vec!["&self".to_string()];
+        let mut arg_types = Vec::with_capacity(hosted.args.len());
+        let mut arg_names = Vec::with_capacity(hosted.args.len());
+        let mut extern_params = Vec::with_capacity(hosted.args.len());
+        let mut owned_args = Vec::with_capacity(hosted.args.len());
+
+        for (index, arg_id) in hosted.args.iter().enumerate() {
+            let arg_type = type_name(*arg_id, types);
+
+            params.push(format!("arg{index}: {arg_type}"));
+            arg_names.push(format!("arg{index}"));
+
+            if is_passed_by_reference(*arg_id, types) {
+                extern_params.push(format!("arg{index}: &{arg_type}"));
+                owned_args.push(format!("arg{index}.clone()"));
+            } else {
+                extern_params.push(format!("arg{index}: {arg_type}"));
+                owned_args.push(format!("arg{index}"));
+            }
+
+            arg_types.push(arg_type);
+        }
+
+        let params = params.join(", ");
+        let arg_names = arg_names.join(", ");
+        let extern_params = extern_params.join(", ");
+
+        let (request, pattern) = if hosted.args.is_empty() {
+            (name.clone(), name.clone())
+        } else {
+            (
+                format!("{name}({})", arg_types.join(", ")),
+                format!("{name}({arg_names})"),
+            )
+        };
+        let request_value = if hosted.args.is_empty() {
+            name.clone()
+        } else {
+            format!("{name}({})", owned_args.join(", "))
+        };
+
+        let _ = writeln!(
+            trait_methods,
+            "{INDENT}{INDENT}fn {name}({params}) -> EffectFuture<'_, {ret_type}>;"
+        );
+        let _ = writeln!(requests, "{INDENT}{INDENT}{request},");
+        let _ = writeln!(responses, "{INDENT}{INDENT}{name}({ret_type}),");
+        let _ = writeln!(
+            handlers,
+            "{INDENT}{INDENT}{INDENT}EffectRequest::{pattern} => EffectResponse::{name}(effects.{name}({arg_names}).await),"
+        );
+        let _ = write!(
+            shims,
+            r#"
+    #[no_mangle]
+    pub extern "C" fn roc_fx_{name}({extern_params}) -> {ret_type} {{
+        match perform(EffectRequest::{request_value}) {{
+            Some(EffectResponse::{name}(output)) => output,
+            Some(_) => unreachable!(),
+            // The Roc call is suspending, and Asyncify discards this as it unwinds the stack.
+            None => unsafe {{ core::mem::zeroed() }},
+        }}
+    }}
+"#
+        );
+    }
+
+    format!(
+        r#"
+/// Lets the host implement the platform's effects with async functions.
+#[allow(unreachable_patterns)]
+pub mod async_effects {{
+    #[allow(unused_imports)]
+    use super::*;
+{ASYNC_EFFECTS_RUNTIME}
+    /// The effects the host provides to the Roc app, as async functions
+    pub trait RocEffects {{
+{trait_methods}    }}
+
+    enum EffectRequest {{
+{requests}    }}
+
+    enum EffectResponse {{
+{responses}    }}
+
+    async fn handle<E: RocEffects + ?Sized>(effects: &E, request: EffectRequest) -> EffectResponse {{
+        match request {{
+{handlers}        }}
+    }}
+{shims}}}
+"#
+    )
+}
+
+/// The platform's named types, with an expression that builds a sample value of each for the
+/// layout tests, if there is one. Sample values only refer to types by name, so they're the
+/// same on every target.
+fn layout_tested_types(
+    types_and_targets: &[(Types, TargetInfo)],
+) -> Vec<(TypeId, &str, Option<String>)> {
+    let types = match types_and_targets.first() {
+        Some((types, _)) => types,
+        None => return Vec::new(),
+    };
+
+    types
+        .sorted_ids()
+        .into_iter()
+        .filter_map(|id| {
+            let name = match types.get_type(id) {
+                RocType::Struct { name, .. }
+                | RocType::TagUnion(RocTagUnion::Enumeration { name, .. })
+                | RocType::TagUnion(RocTagUnion::NonRecursive { name, .. })
+                | RocType::TagUnion(RocTagUnion::Recursive { name, .. })
+                | RocType::TagUnion(RocTagUnion::NullableWrapped { name, .. })
+                | RocType::TagUnion(RocTagUnion::NullableUnwrapped { name, .. })
+                | RocType::TagUnion(RocTagUnion::NonNullableUnwrapped { name, .. })
+                | RocType::TagUnion(RocTagUnion::SingleTagStruct { name, .. }) => name,
+                _ => return None,
+            };
+
+            Some((id, name.as_str(), sample_value(id, types)))
+        })
+        .collect()
+}
+
+/// Generate tests which pass a sample value of each of the platform's types through an
+/// `echo` entry point and check that the same value comes back, so the host notices when
+/// the compiler and the glue stop agreeing on how a type is laid out.
+///
+/// The entry points are in the copy of the platform that [emit_layout_test_platform] makes,
+/// which `roc glue` writes to `platform_file`.
+pub fn emit_layout_tests(
+    types_and_targets: &[(Types, TargetInfo)],
+    glue_module: &str,
+    platform_file: &str,
+) -> String {
+    let types = match types_and_targets.first() {
+        Some((types, _)) => types,
+        None => return String::new(),
+    };
+
+    let mut externs = String::new();
+    let mut tests = String::new();
+
+    for (id, name, sample) in layout_tested_types(types_and_targets) {
+        let type_name = type_name(id, types);
+        let symbol = format!("roc__echo{name}_1_exposed_generic");
+
+        let sample = match sample {
+            Some(sample) => sample,
+            None => {
+                let _ = writeln!(
+                    tests,
+                    "\n// {name} isn't round-tripped, because there's no way to build a sample value of it yet."
+                );
+
+                continue;
+            }
+        };
+
+        let (arg_type, arg) = if is_passed_by_reference(id, types) {
+            (format!("&{type_name}"), "&before")
+        } else {
+            (type_name.clone(), "before.clone()")
+        };
+
+        let _ = writeln!(
+            externs,
+            "{INDENT}fn {symbol}(ret: *mut {type_name}, arg: {arg_type});"
+        );
+        let _ = write!(
+            tests,
+            r#"
+#[test]
+fn round_trip_{name}() {{
+    let before: {type_name} = {sample};
+    let mut after = core::mem::MaybeUninit::<{type_name}>::uninit();
+
+    let after = unsafe {{
+        {symbol}(after.as_mut_ptr(), {arg});
+
+        after.assume_init()
+    }};
+
+    assert_eq!(before, after);
+}}
+"#
+        );
+    }
+
+    format!(
+        r#"// ⚠️ GENERATED CODE ⚠️ - this entire file was generated by the `roc glue` CLI command
+
+//! Passes a sample value of each of the platform's types through the Roc app and back,
+//! to catch the compiler and the glue in `{glue_module}` disagreeing on how they're laid out.
+//!
+//! These call an `echo` entry point for each type, which only the copy of the platform in
+//! `{platform_file}` provides. To run them, build the app against that platform for the
+//! host's tests, and add `#[cfg(test)] mod {glue_module}_layout_tests;` next to `mod {glue_module};`.
+
+#![allow(non_snake_case)]
+#![allow(unused_imports)]
+
+use super::{glue_module}::*;
+
+extern "C" {{
+{externs}}}
+{tests}"#
+    )
+}
+
+/// A copy of the platform module that also provides an `echo` entry point for each type the
+/// layout tests round-trip. Returns `None` if the source isn't a platform module that provides
+/// something, since there's nowhere to add the entry points then.
+pub fn emit_layout_test_platform(
+    types_and_targets: &[(Types, TargetInfo)],
+    platform_src: &str,
+) -> Option<String> {
+    let arena = Bump::new();
+    let provides = match parse_header(&arena, State::new(platform_src.as_bytes())) {
+        Ok((
+            Module {
+                header: Header::Platform(header),
+                ..
+            },
+            _,
+        )) => header.provides.item,
+        _ => return None,
+    };
+    let provides_end = provides.items.last()?.region.end().offset as usize;
+
+    let mut echo_names = String::new();
+    let mut echo_defs = String::new();
+
+    for (_, name, sample) in layout_tested_types(types_and_targets) {
+        if sample.is_none() {
+            continue;
+        }
+
+        let _ = write!(echo_names, ", echo{name}");
+        let _ = write!(
+            echo_defs,
+            "\necho{name} : {name} -> {name}\necho{name} = \\value -> value\n"
+        );
+    }
+
+    Some(format!(
+        "# ⚠️ GENERATED CODE ⚠️ - this entire file was generated by the `roc glue` CLI command\n\
+        # It's the platform, plus the `echo` entry points its layout tests call.\n\n\
+        {}{}{}{}{}",
+        &platform_src[..provides_end],
+        echo_names,
+        &platform_src[provides_end..],
+        if platform_src.ends_with('\n') {
+            ""
+        } else {
+            "\n"
+        },
+        echo_defs
+    ))
+}
+
+/// An expression that builds a value of this type, if there is one that doesn't need
+/// a recursive tag union's payload
+fn sample_value(id: TypeId, types: &Types) -> Option<String> {
+    let sample = match types.get_type(id) {
+        RocType::Unit => "()".to_string(),
+        RocType::Bool => "true".to_string(),
+        RocType::RocStr => {
+            // long enough to be stored on the heap, so the pointer is round-tripped too
+            r#"roc_std::RocStr::from("a string that is too long to be stored inline")"#.to_string()
+        }
+        RocType::Num(RocNum::U128) => "roc_std::U128::from(42)".to_string(),
+        RocType::Num(RocNum::I128) => "roc_std::I128::from(-42)".to_string(),
+        RocType::Num(RocNum::Dec) => r#"roc_std::RocDec::from_str("1.5").unwrap()"#.to_string(),
+        RocType::Num(RocNum::F32 | RocNum::F64) => "1.5".to_string(),
+        RocType::Num(RocNum::I8 | RocNum::I16 | RocNum::I32 | RocNum::I64) => "-42".to_string(),
+        RocType::Num(RocNum::U8 | RocNum::U16 | RocNum::U32 | RocNum::U64) => "42".to_string(),
+        RocType::RocList(elem_id) => {
+            format!(
+                "roc_std::RocList::from_slice(&[{}])",
+                sample_value(*elem_id, types)?
+            )
+        }
+        RocType::RocBox(elem_id) => {
+            format!("roc_std::RocBox::new({})", sample_value(*elem_id, types)?)
+        }
+        // Only Roc can hash entries into a dict, so the host can only build empty ones.
+        RocType::RocDict(_, _) => "roc_std::RocDict::default()".to_string(),
+        RocType::RocSet(_) => "roc_std::RocSet::default()".to_string(),
+        RocType::RocResult(ok_id, _) => {
+            format!("roc_std::RocResult::ok({})", sample_value(*ok_id, types)?)
+        }
+        RocType::Struct { name, fields } => {
+            let name = escape_kw(name.clone());
+            let mut field_values = Vec::with_capacity(fields.len());
+
+            for (label, field_id) in fields {
+                let label = escape_kw(label.clone());
+
+                field_values.push(format!("{label}: {}", sample_value(*field_id, types)?));
+            }
+
+            format!("{name} {{ {} }}", field_values.join(", "))
+        }
+        RocType::TagUnion(RocTagUnion::Enumeration { name, tags, .. }) => {
+            format!("{}::{}", escape_kw(name.clone()), tags.first()?)
+        }
+        RocType::TagUnion(RocTagUnion::SingleTagStruct {
+            name,
+            tag_name,
+            payload_fields,
+        }) => {
+            let name = escape_kw(name.clone());
+
+            if payload_fields.is_empty() {
+                format!("{name}::{tag_name}")
+            } else {
+                let args = payload_fields
+                    .iter()
+                    .map(|field_id| sample_value(*field_id, types))
+                    .collect::<Option<Vec<_>>>()?;
+
+                format!("{name}::{tag_name}({})", args.join(", "))
+            }
+        }
+        RocType::TagUnion(
+            RocTagUnion::NonRecursive { name, tags, .. }
+            | RocTagUnion::Recursive { name, tags, .. }
+            | RocTagUnion::NullableWrapped { name, tags, .. },
+        ) => {
+            let (tag_name, _) = tags.iter().find(|(_, payload)| payload.is_none())?;
+
+            format!("{}::{tag_name}", escape_kw(name.clone()))
+        }
+        RocType::TagUnion(RocTagUnion::NullableUnwrapped { name, null_tag, .. }) => {
+            format!("{}::{null_tag}", escape_kw(name.clone()))
+        }
+        RocType::EmptyTagUnion
+        | RocType::TagUnion(RocTagUnion::NonNullableUnwrapped { .. })
+        | RocType::TagUnionPayload { .. }
+        | RocType::RecursivePointer(_)
+        | RocType::Function { .. } => return None,
+    };
+
+    Some(sample)
+}
+
+/// Whether Roc passes arguments of this type to `roc_fx_` functions by reference
+fn is_passed_by_reference(id: TypeId, types: &Types) -> bool {
+    !matches!(
+        types.get_type(id),
+        RocType::Num(_)
+            | RocType::Bool
+            | RocType::Unit
+            | RocType::RocBox(_)
+            | RocType::TagUnion(RocTagUnion::Enumeration { .. })
+    )
+}
+
 fn add_type(target_info: TargetInfo, id: TypeId, types: &Types, impls: &mut Impls) {
     match types.get_type(id) {
         RocType::Struct { name, fields } => {
@@ -294,10 +679,187 @@ fn add_type(target_info: TargetInfo, id: TypeId, types: &Types, impls: &mut Impl
             // This is recursively pointing to a type that should already have been added,
             // so no extra work needs to happen.
         }
-        RocType::Function { .. } => {
-            // TODO actually generate glue functions!
+        RocType::Function {
+            name,
+            args,
+            ret,
+            extern_name: Some(extern_name),
+        } => add_function(target_info, name, args, *ret, extern_name, types, impls),
+        RocType::Function {
+            extern_name: None, ..
+        } => {
+            // See `RocType::Function::extern_name`
+        }
+    }
+}
+
+fn add_function(
+    target_info: TargetInfo,
+    name: &str,
+    args: &[TypeId],
+    ret: TypeId,
+    extern_name: &str,
+    types: &Types,
+    impls: &mut Impls,
+) {
+    let name = escape_kw(name.to_string());
+    let ret_type = type_name(ret, types);
+
+    add_decl(
+        impls,
+        None,
+        target_info,
+        format!(
+            r#"/// The Roc function {name}, which the app passes to the host. The values it
+/// captured depend on the app, so their size is only known at runtime.
+#[derive(Debug)]
+#[repr(transparent)]
+pub struct {name} {{
+    closure_data: roc_std::RocClosureData,
+}}
+"#
+        ),
+    );
+
+    let opt_impl = Some(format!("impl {name}"));
+
+    add_decl(
+        impls,
+        opt_impl.clone(),
+        target_info,
+        format!(
+            r#"/// The size of the values this function captured, which depends on the app.
+    pub fn closure_size() -> usize {{
+        extern "C" {{
+            #[link_name = "{extern_name}_size"]
+            fn size() -> i64;
+        }}
+
+        unsafe {{ size() as usize }}
+    }}"#
+        ),
+    );
+
+    add_decl(
+        impls,
+        opt_impl.clone(),
+        target_info,
+        r#"/// The alignment of the values this function captured.
+    pub fn closure_alignment() -> usize {
+        roc_std::RocClosureData::ALIGNMENT
+    }"#
+        .to_string(),
+    );
+
+    add_decl(
+        impls,
+        opt_impl.clone(),
+        target_info,
+        format!(
+            r#"/// The size of the value this function returns.
+    pub fn result_size() -> usize {{
+        extern "C" {{
+            #[link_name = "{extern_name}_result_size"]
+            fn size() -> i64;
+        }}
+
+        unsafe {{ size() as usize }}
+    }}"#
+        ),
+    );
+
+    add_decl(
+        impls,
+        opt_impl.clone(),
+        target_info,
+        r#"/// Allocate space for this function's captured values, for the app to write to.
+    pub fn new() -> Self {
+        Self {
+            closure_data: roc_std::RocClosureData::new(Self::closure_size()),
+        }
+    }"#
+        .to_string(),
+    );
+
+    add_decl(
+        impls,
+        opt_impl.clone(),
+        target_info,
+        r#"/// Copy this function's captured values out of memory the app wrote them to.
+    ///
+    /// # Safety
+    ///
+    /// `closure_data` must point to the values the app captured for this function.
+    pub unsafe fn from_closure_data(closure_data: *const u8) -> Self {
+        Self {
+            closure_data: roc_std::RocClosureData::copy_from(closure_data, Self::closure_size()),
         }
+    }"#
+        .to_string(),
+    );
+
+    add_decl(
+        impls,
+        opt_impl.clone(),
+        target_info,
+        r#"/// The address for the app to write this function's captured values to.
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.closure_data.as_mut_ptr()
+    }"#
+        .to_string(),
+    );
+
+    let mut params = Vec::with_capacity(args.len());
+    let mut extern_params = Vec::with_capacity(args.len() + 2);
+    let mut wrapped_args = String::new();
+    let mut call_args = Vec::with_capacity(args.len() + 2);
+
+    for (index, arg_id) in args.iter().enumerate() {
+        let arg_type = type_name(*arg_id, types);
+
+        params.push(format!("arg{index}: {arg_type}"));
+        extern_params.push(format!("arg{index}: *const {arg_type}"));
+        call_args.push(format!("&*arg{index}"));
+
+        // The app takes ownership of the arguments, so they mustn't be dropped here.
+        let _ = writeln!(
+            wrapped_args,
+            "{INDENT}{INDENT}let arg{index} = core::mem::ManuallyDrop::new(arg{index});"
+        );
     }
+
+    extern_params.push("closure_data: *const u8".to_string());
+    extern_params.push(format!("output: *mut {ret_type}"));
+    call_args.push("self.closure_data.as_ptr()".to_string());
+    call_args.push("output.as_mut_ptr()".to_string());
+
+    let params = params.join(", ");
+    let extern_params = extern_params.join(", ");
+    let call_args = call_args.join(", ");
+
+    add_decl(
+        impls,
+        opt_impl,
+        target_info,
+        format!(
+            r#"/// Call this function. The app takes ownership of its captured values,
+    /// so it can only be called once.
+    pub fn call(self, {params}) -> {ret_type} {{
+        extern "C" {{
+            #[link_name = "{extern_name}_caller"]
+            fn caller({extern_params});
+        }}
+
+{wrapped_args}        let mut output = core::mem::MaybeUninit::uninit();
+
+        unsafe {{
+            caller({call_args});
+
+            output.assume_init()
+        }}
+    }}"#
+        ),
+    );
 }
 
 fn add_single_tag_struct(
@@ -570,6 +1132,164 @@ fn add_discriminant(
     discriminant_name
 }
 
+/// Add a plain Rust enum with the same tags as the tag union, which owns each tag's payload.
+/// The tag union itself has to match Roc's memory layout (e.g. its discriminant can come after
+/// the payload, or be stored in the spare bits of a pointer), but this enum can be matched on
+/// without any unsafe code, and converted to and from the tag union with `.into()`.
+fn add_variant_enum(
+    recursiveness: Recursiveness,
+    name: &str,
+    discriminant_name: &str,
+    typ: &RocType,
+    variants: &[(&str, Option<Vec<String>>)],
+    target_info: TargetInfo,
+    types: &Types,
+    impls: &mut Impls,
+) {
+    // e.g.
+    //
+    // pub enum variant_MyTagUnion {
+    //     Bar(roc_std::RocStr),
+    //     Baz,
+    //     Foo(u8, i64),
+    // }
+    let variant_name = format!("variant_{name}");
+
+    {
+        let derive = derive_str(typ, types, true);
+        let mut buf = format!(
+            "/// The tags of `{name}` as a Rust enum, so they can be matched on safely.\n{derive}\npub enum {variant_name} {{\n"
+        );
+
+        for (tag_name, opt_fields) in variants {
+            match opt_fields {
+                Some(fields) => {
+                    writeln!(buf, "{INDENT}{tag_name}({}),", fields.join(", ")).unwrap()
+                }
+                None => writeln!(buf, "{INDENT}{tag_name},").unwrap(),
+            }
+        }
+
+        buf.push('}');
+
+        add_decl(impls, None, target_info, buf);
+    }
+
+    {
+        let mut buf = String::new();
+
+        for (tag_name, opt_fields) in variants {
+            write_indents(3, &mut buf);
+
+            // A recursive tag union's payload may be shared, so clone it out rather than moving it.
+            let (get_payload, field_suffix) = match recursiveness {
+                Recursiveness::Recursive => (format!("self.as_{tag_name}()"), ".clone()"),
+                Recursiveness::NonRecursive => (format!("self.into_{tag_name}()"), ""),
+            };
+
+            match opt_fields {
+                Some(fields) if fields.len() == 1 => {
+                    writeln!(
+                        buf,
+                        "{discriminant_name}::{tag_name} => {variant_name}::{tag_name}(unsafe {{ {get_payload} }}{field_suffix}),"
+                    )
+                    .unwrap();
+                }
+                Some(fields) => {
+                    let bindings = (0..fields.len())
+                        .map(|index| format!("f{index}"))
+                        .collect::<Vec<String>>();
+                    let fields = bindings
+                        .iter()
+                        .map(|binding| format!("{binding}{field_suffix}"))
+                        .collect::<Vec<String>>()
+                        .join(", ");
+                    let bindings = bindings.join(", ");
+
+                    writeln!(
+                        buf,
+                        "{discriminant_name}::{tag_name} => {{
+                let ({bindings}) = unsafe {{ {get_payload} }};
+
+                {variant_name}::{tag_name}({fields})
+            }}"
+                    )
+                    .unwrap();
+                }
+                None => {
+                    writeln!(
+                        buf,
+                        "{discriminant_name}::{tag_name} => {variant_name}::{tag_name},"
+                    )
+                    .unwrap();
+                }
+            }
+        }
+
+        add_decl(
+            impls,
+            Some(format!("impl {name}")),
+            target_info,
+            format!(
+                r#"/// Converts this `{name}` to a `{variant_name}`, whose payloads can be matched on safely.
+    pub fn into_variant(self) -> {variant_name} {{
+        match self.discriminant() {{
+{buf}        }}
+    }}"#
+            ),
+        );
+    }
+
+    {
+        let mut buf = String::new();
+
+        for (tag_name, opt_fields) in variants {
+            write_indents(3, &mut buf);
+
+            match opt_fields {
+                Some(fields) => {
+                    let bindings = (0..fields.len())
+                        .map(|index| format!("f{index}"))
+                        .collect::<Vec<String>>()
+                        .join(", ");
+
+                    writeln!(
+                        buf,
+                        "{variant_name}::{tag_name}({bindings}) => Self::{tag_name}({bindings}),"
+                    )
+                    .unwrap();
+                }
+                None => {
+                    writeln!(buf, "{variant_name}::{tag_name} => Self::{tag_name},").unwrap();
+                }
+            }
+        }
+
+        add_decl(
+            impls,
+            Some(format!("impl From<{variant_name}> for {name}")),
+            target_info,
+            format!(
+                r#"fn from(variant: {variant_name}) -> Self {{
+        match variant {{
+{buf}        }}
+    }}"#
+            ),
+        );
+    }
+
+    add_decl(
+        impls,
+        Some(format!("impl From<{name}> for {variant_name}")),
+        target_info,
+        format!(
+            r#"fn from(tag_union: {name}) -> Self {{
+        tag_union.into_variant()
+    }}"#
+        ),
+    );
+}
+
 #[derive(Copy, Clone, PartialEq, Debug)]
 enum Recursiveness {
     Recursive,
@@ -834,6 +1554,9 @@ pub struct {name} {{
             }
         }
 
+        // The tags of the safe Rust enum, along with the types of each one's payload fields
+        let mut variants: Vec<(&str, Option<Vec<String>>)> = Vec::with_capacity(tags.len());
+
         for (tag_index, (tag_name, opt_payload_id)) in tags.iter().enumerate() {
             // Add a convenience constructor function to the impl, e.g.
             //
@@ -983,6 +1706,23 @@ pub struct {name} {{
                     RocType::Function { .. } => todo!(),
                 };
 
+                let variant_fields = match payload_type {
+                    RocType::TagUnionPayload { fields, .. } if fields.len() != 1 => {
+                        // These are returned as a tuple, in the same order as the constructor's args
+                        let mut sorted_fields = fields.iter().collect::<Vec<_>>();
+
+                        sorted_fields.sort_by(|(label1, _), (label2, _)| label1.cmp(label2));
+
+                        sorted_fields
+                            .iter()
+                            .map(|(_, type_id)| type_name(*type_id, types))
+                            .collect()
+                    }
+                    _ => vec![owned_ret_type.clone()],
+                };
+
+                variants.push((tag_name, Some(variant_fields)));
+
                 {
                     let body = match recursiveness {
                         Recursiveness::Recursive => {
@@ -1095,11 +1835,53 @@ pub struct {name} {{
         let payload = {borrowed_get_payload};
 
         {borrowed_ret}
+    }}"#,
+                        ),
+                    );
+                }
+
+                if discriminant_size != 0 {
+                    add_decl(
+                        impls,
+                        opt_impl.clone(),
+                        target_info,
+                        format!(
+                            r#"/// Returns `{tag_name}`'s payload if this `{name}` has a `.discriminant()` of `{tag_name}`,
+    /// and `None` otherwise.
+    pub fn get_{tag_name}(&self) -> Option<{borrowed_ret_type}> {{
+        if self.discriminant() == {discriminant_name}::{tag_name} {{
+            Some(unsafe {{ self.as_{tag_name}() }})
+        }} else {{
+            None
+        }}
+    }}"#,
+                        ),
+                    );
+                }
+
+                // A recursive tag union's payload may be shared with other references to it,
+                // so it can only be moved out by the `unsafe` `into_` method.
+                if discriminant_size != 0 && recursiveness == Recursiveness::NonRecursive {
+                    add_decl(
+                        impls,
+                        opt_impl.clone(),
+                        target_info,
+                        format!(
+                            r#"/// Converts this `{name}` to `{tag_name}`'s payload if it has a `.discriminant()` of `{tag_name}`,
+    /// and gives it back unchanged otherwise.
+    pub fn try_into_{tag_name}(self) -> Result<{owned_ret_type}, Self> {{
+        if self.discriminant() == {discriminant_name}::{tag_name} {{
+            Ok(unsafe {{ self.into_{tag_name}() }})
+        }} else {{
+            Err(self)
+        }}
     }}"#,
                         ),
                     );
                 }
             } else if Some(tag_index) == null_tag_index {
+                variants.push((tag_name, None));
+
                 // The null tag index only occurs for nullable-wrapped tag unions,
                 // and it always has no payload. This is the one scenario where
                 // that constructor could come up!
@@ -1115,6 +1897,8 @@ pub struct {name} {{
                     ),
                 );
             } else {
+                variants.push((tag_name, None));
+
                 add_decl(
                     impls,
                     opt_impl.clone(),
@@ -1158,6 +1942,19 @@ pub struct {name} {{
                 );
             }
         }
+
+        if discriminant_size != 0 {
+            add_variant_enum(
+                recursiveness,
+                &name,
+                &discriminant_name,
+                typ,
+                &variants,
+                target_info,
+                types,
+                impls,
+            );
+        }
     }
 
     // The Drop impl for the tag union
@@ -2193,7 +2990,7 @@ fn write_indents(indentations: usize, buf: &mut String) {
     }
 }
 
-fn max_pointer_tagged_variants(architecture: Architecture) -> usize {
+pub(crate) fn max_pointer_tagged_variants(architecture: Architecture) -> usize {
     match architecture {
         // On a 64-bit system, pointers have 3 bits that are unused, so return 2^3 = 8
         Architecture::X86_64 | Architecture::Aarch64 => 8,
@@ -2203,7 +3000,7 @@ fn max_pointer_tagged_variants(architecture: Architecture) -> usize {
 }
 
 #[inline(always)]
-fn tagged_pointer_bitmask(architecture: Architecture) -> u8 {
+pub(crate) fn tagged_pointer_bitmask(architecture: Architecture) -> u8 {
     match architecture {
         // On a 64-bit system, pointers have 3 bits that are unused
         Architecture::X86_64 | Architecture::Aarch64 => 0b0000_0111,
@@ -7,6 +7,7 @@ use roc_builtins::bitcode::{
     IntWidth::{self, *},
 };
 use roc_collections::VecMap;
+use roc_error_macros::user_error;
 use roc_module::{
     ident::TagName,
     symbol::{Interns, Symbol},
@@ -50,6 +51,22 @@ pub struct Types {
     /// type declaration earlier in the file than where it gets referenced by another type.
     deps: VecMap<TypeId, Vec<TypeId>>,
     target: TargetInfo,
+
+    /// The values the platform exposes to the host, by name, e.g. `mainForHost`
+    entry_points: Vec<(String, TypeId)>,
+
+    /// The functions the host provides to the platform's hosted module
+    hosted_functions: Vec<HostedFunction>,
+}
+
+/// A function the host provides for Roc to call, e.g. `roc_fx_putLine` for
+/// `putLine : Str -> Effect {}` in the platform's hosted module.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HostedFunction {
+    pub name: String,
+    pub args: Vec<TypeId>,
+    /// The type the Effect produces, e.g. `{}` for `putLine`
+    pub ret: TypeId,
 }
 
 impl Types {
@@ -61,6 +78,8 @@ impl Types {
             sizes: Vec::new(),
             aligns: Vec::new(),
             deps: VecMap::with_capacity(cap),
+            entry_points: Vec::new(),
+            hosted_functions: Vec::new(),
         }
     }
 
@@ -301,11 +320,13 @@ impl Types {
                     name: name_a,
                     args: args_a,
                     ret: ret_a,
+                    ..
                 },
                 Function {
                     name: name_b,
                     args: args_b,
                     ret: ret_b,
+                    ..
                 },
             ) => {
                 // for functions, the name is actually important because two functions
@@ -465,6 +486,14 @@ impl Types {
         (0..self.types.len()).map(TypeId)
     }
 
+    pub fn entry_points(&self) -> &[(String, TypeId)] {
+        &self.entry_points
+    }
+
+    pub fn hosted_functions(&self) -> &[HostedFunction] {
+        &self.hosted_functions
+    }
+
     pub fn sorted_ids(&self) -> Vec<TypeId> {
         use roc_collections::{ReferenceMatrix, TopologicalSort};
 
@@ -525,6 +554,11 @@ pub enum RocType {
         name: String,
         args: Vec<TypeId>,
         ret: TypeId,
+        /// The prefix of the symbols Roc generates so the host can call this function,
+        /// e.g. `roc__mainForHost_1__Fx` for `roc__mainForHost_1__Fx_caller`.
+        /// Only functions the platform names with `as` get these symbols. Without them the host
+        /// has no way to call the function, so glue declares and wraps nothing for it.
+        extern_name: Option<String>,
     },
     /// A zero-sized type, such as an empty record or a single-tag union with no payload
     Unit,
@@ -666,6 +700,8 @@ pub struct Env<'a> {
     enum_names: Enums,
     pending_recursive_types: VecMap<TypeId, Variable>,
     known_recursive_types: VecMap<Variable, TypeId>,
+    /// The entry point whose type is currently being added, if any
+    current_entry_point: Option<String>,
     target: TargetInfo,
 }
 
@@ -685,6 +721,7 @@ impl<'a> Env<'a> {
             enum_names: Default::default(),
             pending_recursive_types: Default::default(),
             known_recursive_types: Default::default(),
+            current_entry_point: None,
             layout_cache: LayoutCache::new(layout_interner, target),
             target,
         }
@@ -693,6 +730,16 @@ impl<'a> Env<'a> {
     pub fn vars_to_types<I>(&mut self, variables: I) -> Types
     where
         I: Iterator<Item = Variable>,
+    {
+        self.vars_and_entry_points_to_types(variables, core::iter::empty())
+    }
+
+    /// Like `vars_to_types`, but also records which types the given entry points have,
+    /// so that glue can declare the functions the host calls to run the app.
+    pub fn vars_and_entry_points_to_types<I, E>(&mut self, variables: I, entry_points: E) -> Types
+    where
+        I: Iterator<Item = Variable>,
+        E: Iterator<Item = (String, Variable)>,
     {
         let mut types = Types::with_capacity(variables.size_hint().0, self.target);
 
@@ -700,11 +747,64 @@ impl<'a> Env<'a> {
             self.add_type(var, &mut types);
         }
 
+        for (name, var) in entry_points {
+            self.current_entry_point = Some(name.clone());
+
+            let type_id = self.add_type(var, &mut types);
+
+            types.entry_points.push((name, type_id));
+        }
+
+        self.current_entry_point = None;
+
         self.resolve_pending_recursive_types(&mut types);
 
         types
     }
 
+    /// Add the signatures of the given hosted functions to `types`. Hosted modules are
+    /// typechecked separately from the platform, so this must be called on an `Env` made
+    /// from the hosted module's `Subs`.
+    pub fn add_hosted_functions<I>(&mut self, hosted_functions: I, types: &mut Types)
+    where
+        I: Iterator<Item = (String, Variable)>,
+    {
+        for (name, var) in hosted_functions {
+            // e.g. `putLine : Str -> Effect {}`, or `getLine : Effect Str`
+            let (arg_vars, effect_var) = match self.subs.get_content_without_compacting(var) {
+                Content::Structure(FlatType::Func(args, _, ret_var)) => {
+                    (self.subs.get_subs_slice(*args).to_vec(), *ret_var)
+                }
+                _ => (Vec::new(), var),
+            };
+
+            let ret_var = match self.subs.get_content_without_compacting(effect_var) {
+                Content::Alias(_, alias_vars, _, _) if alias_vars.type_variables_len == 1 => {
+                    self.subs.get_subs_slice(alias_vars.type_variables())[0]
+                }
+                other => {
+                    user_error!(
+                        "The hosted function {} should return an Effect, but it returns {:?}",
+                        name,
+                        other
+                    )
+                }
+            };
+
+            let args = arg_vars
+                .into_iter()
+                .map(|arg_var| self.add_type(arg_var, types))
+                .collect();
+            let ret = self.add_type(ret_var, types);
+
+            types
+                .hosted_functions
+                .push(HostedFunction { name, args, ret });
+        }
+
+        self.resolve_pending_recursive_types(types);
+    }
+
     fn add_type(&mut self, var: Variable, types: &mut Types) -> TypeId {
         roc_tracing::debug!(content=?roc_types::subs::SubsFmtContent(self.subs.get_content_without_compacting(var), self.subs), "adding type");
 
@@ -799,10 +899,25 @@ fn add_type_help<'a>(
 
             add_tag_union(env, opt_name, tags, var, types, layout, Some(rec_root))
         }
-        Content::Structure(FlatType::Apply(symbol, _)) => match layout {
+        Content::Structure(FlatType::Apply(symbol, args)) => match layout {
             Layout::Builtin(builtin) => {
                 add_builtin_type(env, builtin, var, opt_name, types, layout)
             }
+            Layout::Boxed(elem_layout) if *symbol == Symbol::BOX_BOX_TYPE => {
+                let args = env.subs.get_subs_slice(*args);
+                debug_assert_eq!(args.len(), 1);
+
+                let elem_id = add_type_help(env, *elem_layout, args[0], None, types);
+                let box_id = types.add_anonymous(
+                    &env.layout_cache.interner,
+                    RocType::RocBox(elem_id),
+                    layout,
+                );
+
+                types.depends(box_id, elem_id);
+
+                box_id
+            }
             _ => {
                 if symbol.is_builtin() {
                     todo!(
@@ -841,7 +956,21 @@ fn add_type_help<'a>(
                 add_type_help(env, ret_layout, *ret_var, None, types)
             };
 
-            let name = format!("TODO_roc_function_{:?}", closure_var);
+            // Roc only generates symbols for calling a function from the host when
+            // the platform names it with `as`, e.g. `mainForHost : Task {} [] as Fx`
+            let (name, extern_name) = match (opt_name, &env.current_entry_point) {
+                (Some(alias), Some(entry_point)) => (
+                    alias.as_str(env.interns).to_string(),
+                    Some(format!(
+                        "roc__{}_1_{}_{}",
+                        entry_point,
+                        alias.module_string(env.interns).as_str(),
+                        alias.as_str(env.interns)
+                    )),
+                ),
+                (Some(alias), None) => (alias.as_str(env.interns).to_string(), None),
+                (None, _) => (format!("TODO_roc_function_{:?}", closure_var), None),
+            };
             let fn_type_id = types.add_named(
                 &env.layout_cache.interner,
                 name.clone(),
@@ -849,6 +978,7 @@ fn add_type_help<'a>(
                     name,
                     args: arg_type_ids.clone(),
                     ret: ret_type_id,
+                    extern_name,
                 },
                 layout,
             );
@@ -929,6 +1059,11 @@ fn add_type_help<'a>(
                             | UnionLayout::NullableUnwrapped { .. } => {
                                 unreachable!();
                             }
+                            UnionLayout::NullablePointer { .. } => {
+                                // e.g. `Result (Box a) {}` is no RocResult, so describe it like
+                                // any other tag union.
+                                add_type_help(env, layout, *real_var, opt_name, types)
+                            }
                         }
                     }
                     Layout::Struct { .. } if *name == Symbol::DICT_DICT => {
@@ -1412,6 +1547,67 @@ fn add_tag_union<'a>(
 
                     let (non_null_tag, non_null_payload) = non_null;
 
+                    RocTagUnion::NullableUnwrapped {
+                        name: name.clone(),
+                        null_tag,
+                        non_null_tag,
+                        non_null_payload: non_null_payload.unwrap(),
+                        null_represents_first_tag,
+                    }
+                }
+                // A non-recursive tag union with two variants, where one is empty and the
+                // other has a box for its payload.
+                // Optimization: Use null for the empty variant AND the box's pointer for the other.
+                // e.g. `Maybe a : [Nothing, Just (Box a)]`
+                // The box points at its content's allocation, the way a nullable unwrapped union
+                // points at its payload's, so hosts see one whose payload is the box's content.
+                NullablePointer {
+                    nullable_id: null_represents_first_tag,
+                    pointer,
+                } => {
+                    let content_layout = match pointer {
+                        Layout::Boxed(content_layout) => **content_layout,
+                        other => unreachable!("a nullable pointer to a {:?} is not a box", other),
+                    };
+
+                    let mut tags: Vec<(String, &[Variable])> = union_tags
+                        .iter_from_subs(subs)
+                        .map(|(tag_name, payload_vars)| {
+                            (tag_name.0.as_str().to_string(), payload_vars)
+                        })
+                        .collect();
+                    tags.sort_by(|(name1, _), (name2, _)| name1.cmp(name2));
+                    // Nullable pointer tag unions should always have exactly 2 tags.
+                    debug_assert_eq!(tags.len(), 2);
+
+                    let (null_tag, non_null) = if null_represents_first_tag {
+                        (tags[1].0.clone(), &tags[0])
+                    } else {
+                        (tags[0].0.clone(), &tags[1])
+                    };
+                    let (non_null_tag, payload_vars) = non_null;
+
+                    let box_var = payload_vars
+                        .iter()
+                        .copied()
+                        .find(|var| {
+                            let layout = env.layout_cache.from_var(env.arena, *var, subs).unwrap();
+
+                            !layout.is_dropped_because_empty()
+                        })
+                        .unwrap();
+                    let content_var = box_content_var(subs, box_var);
+
+                    let (non_null_tag, non_null_payload) = tag_to_type(
+                        &name,
+                        env,
+                        non_null_tag.clone(),
+                        &[content_var],
+                        types,
+                        content_layout,
+                        true,
+                    );
+
                     RocTagUnion::NullableUnwrapped {
                         name: name.clone(),
                         null_tag,
@@ -1501,6 +1697,22 @@ fn add_int_enumeration(
     }
 }
 
+/// The variable of what a `Box` holds
+fn box_content_var(subs: &Subs, mut var: Variable) -> Variable {
+    loop {
+        match subs.get_content_without_compacting(var) {
+            Content::Alias(_, _, real_var, _) => var = *real_var,
+            Content::Structure(FlatType::Apply(Symbol::BOX_BOX_TYPE, args)) => {
+                let args = subs.get_subs_slice(*args);
+                debug_assert_eq!(args.len(), 1);
+
+                return args[0];
+            }
+            other => unreachable!("expected a Box, but found {:?}", other),
+        }
+    }
+}
+
 fn union_tags_to_types<'a>(
     name: &str,
     union_tags: &UnionLabels<TagName>,
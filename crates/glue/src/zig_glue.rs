@@ -0,0 +1,844 @@
+//! Generates a Zig file for a platform's Roc API, so Zig hosts can use the same
+//! memory layouts as the Roc app, instead of maintaining bindings by hand.
+//!
+//! Types whose layout differs between targets (e.g. because of pointer sizes) are declared
+//! with a `switch` on `builtin.cpu.arch`, and every type checks at compile time that its
+//! size and alignment match what Roc uses on that target.
+use crate::rust_glue::{max_pointer_tagged_variants, tagged_pointer_bitmask};
+use crate::types::{RocNum, RocTagUnion, RocType, TypeId, Types};
+use indexmap::IndexMap;
+use roc_target::{Architecture, TargetInfo};
+use std::fmt::Write;
+
+pub static HEADER: &[u8] = include_bytes!("../templates/header.zig");
+const INDENT: &str = "    ";
+
+pub fn emit(types_and_targets: &[(Types, TargetInfo)]) -> String {
+    let mut buf = String::new();
+
+    let first_types = match types_and_targets.first() {
+        Some((types, _)) => types,
+        None => return buf,
+    };
+
+    // Every target has the same types in the same order; only their sizes and alignments
+    // differ. Zig doesn't care what order declarations are in, but this keeps the
+    // output in the same order as the other glue languages.
+    for id in first_types.sorted_ids() {
+        let name = match declared_name(id, first_types) {
+            Some(name) => name,
+            None => continue,
+        };
+
+        let exprs = types_and_targets
+            .iter()
+            .map(|(types, target_info)| (*target_info, type_expr(id, types, *target_info)))
+            .collect::<Vec<_>>();
+
+        write_decl(&name, &exprs, &mut buf);
+    }
+
+    // Entry points only refer to types by name, so they're the same on every target.
+    buf.push_str(&entry_point_decls(first_types));
+
+    buf
+}
+
+/// Declare `name` as the given type expression. If the expression differs between
+/// targets, switch on the architecture to pick the right one.
+fn write_decl(name: &str, exprs: &[(TargetInfo, String)], buf: &mut String) {
+    let mut targets_by_expr: IndexMap<&str, Vec<TargetInfo>> = IndexMap::default();
+
+    for (target_info, expr) in exprs {
+        targets_by_expr
+            .entry(expr.as_str())
+            .or_default()
+            .push(*target_info);
+    }
+
+    if targets_by_expr.len() == 1 {
+        let (expr, _) = targets_by_expr.get_index(0).unwrap();
+
+        write!(buf, "\npub const {name} = {expr};\n").unwrap();
+    } else {
+        write!(buf, "\npub const {name} = switch (builtin.cpu.arch) {{\n").unwrap();
+
+        for (expr, targets) in targets_by_expr.iter() {
+            let arches = targets
+                .iter()
+                .map(|target_info| arch_name(target_info.architecture))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            writeln!(buf, "{INDENT}{arches} => {},", indent(expr)).unwrap();
+        }
+
+        writeln!(
+            buf,
+            "{INDENT}else => @compileError(\"Roc doesn't support this architecture\"),\n}};"
+        )
+        .unwrap();
+    }
+}
+
+fn arch_name(architecture: Architecture) -> &'static str {
+    match architecture {
+        Architecture::X86_64 => ".x86_64",
+        Architecture::X86_32 => ".i386",
+        Architecture::Aarch64 => ".aarch64",
+        Architecture::Aarch32 => ".arm",
+        Architecture::Wasm32 => ".wasm32",
+    }
+}
+
+/// Indent every line after the first, for nesting a multi-line expression in a block
+fn indent(expr: &str) -> String {
+    expr.lines()
+        .enumerate()
+        .map(|(index, line)| {
+            if index == 0 || line.is_empty() {
+                line.to_string()
+            } else {
+                format!("{INDENT}{line}")
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// The name of the declaration for this type, if it needs one
+fn declared_name(id: TypeId, types: &Types) -> Option<String> {
+    match types.get_type(id) {
+        RocType::Struct { name, .. }
+        | RocType::TagUnionPayload { name, .. }
+        | RocType::TagUnion(RocTagUnion::Enumeration { name, .. })
+        | RocType::TagUnion(RocTagUnion::Recursive { name, .. })
+        | RocType::TagUnion(RocTagUnion::NullableWrapped { name, .. })
+        | RocType::TagUnion(RocTagUnion::NullableUnwrapped { name, .. })
+        | RocType::TagUnion(RocTagUnion::NonNullableUnwrapped { name, .. })
+        | RocType::TagUnion(RocTagUnion::SingleTagStruct { name, .. }) => Some(escape_kw(name)),
+        RocType::TagUnion(RocTagUnion::NonRecursive { name, tags, .. }) => {
+            // Empty tag unions can never come up at runtime,
+            // and so don't need declared types.
+            if tags.is_empty() {
+                None
+            } else {
+                Some(escape_kw(name))
+            }
+        }
+        RocType::RocResult(ok_id, err_id) => Some(result_name(*ok_id, *err_id, types)),
+        // These types are declared in the header, or are builtin Zig types.
+        RocType::Unit
+        | RocType::EmptyTagUnion
+        | RocType::Num(_)
+        | RocType::Bool
+        | RocType::RocStr
+        | RocType::RocDict(_, _)
+        | RocType::RocSet(_)
+        | RocType::RocList(_)
+        | RocType::RocBox(_)
+        | RocType::RecursivePointer(_) => None,
+        RocType::Function {
+            name,
+            extern_name: Some(_),
+            ..
+        } => Some(escape_kw(name)),
+        // See `RocType::Function::extern_name`
+        RocType::Function {
+            extern_name: None, ..
+        } => None,
+    }
+}
+
+fn type_expr(id: TypeId, types: &Types, target_info: TargetInfo) -> String {
+    match types.get_type(id) {
+        RocType::Struct { fields, .. } => {
+            let fields = fields
+                .iter()
+                .map(|(label, type_id)| (escape_kw(label), *type_id));
+
+            struct_expr(fields, id, types)
+        }
+        RocType::TagUnionPayload { fields, .. } => {
+            // Tag union payload fields are numbered, so give them an "f" prefix
+            let fields = fields
+                .iter()
+                .map(|(label, type_id)| (format!("f{label}"), *type_id));
+
+            struct_expr(fields, id, types)
+        }
+        RocType::TagUnion(RocTagUnion::SingleTagStruct { payload_fields, .. }) => {
+            let fields = payload_fields
+                .iter()
+                .enumerate()
+                .map(|(index, type_id)| (format!("f{index}"), *type_id));
+
+            struct_expr(fields, id, types)
+        }
+        RocType::TagUnion(RocTagUnion::Enumeration { tags, size, .. }) => {
+            enum_expr(tags.iter(), *size)
+        }
+        RocType::TagUnion(RocTagUnion::NonRecursive {
+            tags,
+            discriminant_size,
+            discriminant_offset,
+            ..
+        }) => {
+            let size = types.size_rounded_to_alignment(id);
+            let mut buf = "extern union {\n".to_string();
+
+            write_union_members(tags, types, &mut buf);
+
+            // Roc puts the discriminant in the padding at the end of the largest payload
+            // when there's room, so the union can be smaller than a struct of the
+            // payloads and the discriminant would be. This makes room for it either way.
+            writeln!(buf, "{INDENT}_bytes: [{size}]u8,").unwrap();
+
+            if *discriminant_size > 0 {
+                let tag_enum = enum_expr(
+                    tags.iter().map(|(tag_name, _)| tag_name),
+                    *discriminant_size,
+                );
+
+                write!(
+                    buf,
+                    r#"
+    pub const Tag = {};
+
+    pub fn tag(self: *const @This()) Tag {{
+        const bytes = @ptrCast([*]const u8, self);
+
+        return @ptrCast(*const Tag, @alignCast(@alignOf(Tag), bytes + {discriminant_offset})).*;
+    }}
+
+    pub fn setTag(self: *@This(), new_tag: Tag) void {{
+        const bytes = @ptrCast([*]u8, self);
+
+        @ptrCast(*Tag, @alignCast(@alignOf(Tag), bytes + {discriminant_offset})).* = new_tag;
+    }}
+"#,
+                    indent(&tag_enum)
+                )
+                .unwrap();
+
+                for (tag_name, opt_payload_id) in tags {
+                    let payload = opt_payload_id
+                        .filter(|payload_id| types.size_ignoring_alignment(*payload_id) > 0);
+                    let tag_field = escape_kw(tag_name);
+
+                    match payload {
+                        Some(payload_id) => {
+                            let payload_type = type_name(payload_id, types);
+
+                            write!(
+                                buf,
+                                r#"
+    pub fn init{tag_name}(payload: {payload_type}) @This() {{
+        var tag_union: @This() = undefined;
+
+        tag_union.{tag_field} = payload;
+        tag_union.setTag(.{tag_field});
+
+        return tag_union;
+    }}
+"#
+                            )
+                            .unwrap();
+                        }
+                        None => {
+                            write!(
+                                buf,
+                                r#"
+    pub fn init{tag_name}() @This() {{
+        var tag_union: @This() = undefined;
+
+        tag_union.setTag(.{tag_field});
+
+        return tag_union;
+    }}
+"#
+                            )
+                            .unwrap();
+                        }
+                    }
+                }
+            }
+
+            write_layout_asserts(id, types, &mut buf);
+            buf.push('}');
+
+            buf
+        }
+        RocType::TagUnion(RocTagUnion::Recursive {
+            tags,
+            discriminant_size,
+            discriminant_offset,
+            ..
+        }) => recursive_union_expr(
+            tags,
+            None,
+            *discriminant_size,
+            *discriminant_offset,
+            id,
+            types,
+            target_info,
+        ),
+        RocType::TagUnion(RocTagUnion::NullableWrapped {
+            index_of_null_tag,
+            tags,
+            discriminant_size,
+            discriminant_offset,
+            ..
+        }) => recursive_union_expr(
+            tags,
+            Some(&tags[*index_of_null_tag as usize].0),
+            *discriminant_size,
+            *discriminant_offset,
+            id,
+            types,
+            target_info,
+        ),
+        RocType::TagUnion(RocTagUnion::NullableUnwrapped {
+            null_tag,
+            non_null_tag,
+            non_null_payload,
+            ..
+        }) => {
+            let payload_type = type_name(*non_null_payload, types);
+            let mut buf = format!(
+                r#"extern struct {{
+    /// null represents the {null_tag} tag, and any other pointer the {non_null_tag} tag.
+    pointer: ?*{payload_type},
+"#
+            );
+
+            write_layout_asserts(id, types, &mut buf);
+            buf.push('}');
+
+            buf
+        }
+        RocType::TagUnion(RocTagUnion::NonNullableUnwrapped {
+            tag_name, payload, ..
+        }) => {
+            let payload_type = type_name(*payload, types);
+            let mut buf = format!(
+                r#"extern struct {{
+    /// The only tag is {tag_name}, so there is no discriminant.
+    pointer: *{payload_type},
+"#
+            );
+
+            write_layout_asserts(id, types, &mut buf);
+            buf.push('}');
+
+            buf
+        }
+        RocType::RocResult(ok_id, err_id) => {
+            let mut buf = "extern struct {\n    payload: extern union {\n".to_string();
+
+            for (label, type_id) in [("err", *err_id), ("ok", *ok_id)] {
+                if types.size_ignoring_alignment(type_id) > 0 {
+                    writeln!(
+                        buf,
+                        "{INDENT}{INDENT}{label}: {},",
+                        type_name(type_id, types)
+                    )
+                    .unwrap();
+                }
+            }
+
+            let payload_size = types
+                .size_ignoring_alignment(*ok_id)
+                .max(types.size_ignoring_alignment(*err_id))
+                .max(1);
+
+            write!(
+                buf,
+                r#"        _bytes: [{payload_size}]u8,
+    }},
+    tag: Tag,
+
+    pub const Tag = enum(u8) {{
+        Err = 0,
+        Ok = 1,
+    }};
+
+    pub fn isOk(self: *const @This()) bool {{
+        return self.tag == .Ok;
+    }}
+"#
+            )
+            .unwrap();
+
+            write_layout_asserts(id, types, &mut buf);
+            buf.push('}');
+
+            buf
+        }
+        RocType::Unit
+        | RocType::EmptyTagUnion
+        | RocType::Num(_)
+        | RocType::Bool
+        | RocType::RocStr
+        | RocType::RocDict(_, _)
+        | RocType::RocSet(_)
+        | RocType::RocList(_)
+        | RocType::RocBox(_)
+        | RocType::RecursivePointer(_)
+        | RocType::Function {
+            extern_name: None, ..
+        } => type_name(id, types),
+        RocType::Function {
+            args,
+            ret,
+            extern_name: Some(extern_name),
+            ..
+        } => function_expr(args, *ret, extern_name, types),
+    }
+}
+
+/// A namespace for calling a Roc function value the app passes to the host.
+/// The values it captured depend on the app, so their size is only known at runtime.
+fn function_expr(args: &[TypeId], ret: TypeId, extern_name: &str, types: &Types) -> String {
+    let ret_is_zero_sized = types.size_ignoring_alignment(ret) == 0;
+    let ret_type = type_name(ret, types);
+    let mut extern_params = Vec::with_capacity(args.len() + 2);
+    let mut params = vec!["closure_data: [*]const u8".to_string()];
+    let mut call_args = Vec::with_capacity(args.len() + 2);
+
+    for (index, arg_id) in args.iter().enumerate() {
+        let arg_type = type_name(*arg_id, types);
+
+        extern_params.push(format!("arg{index}: *const {arg_type}"));
+        params.push(format!("arg{index}: {arg_type}"));
+        call_args.push(format!("&arg{index}"));
+    }
+
+    extern_params.push("closure_data: [*]const u8".to_string());
+    call_args.push("closure_data".to_string());
+    call_args.push("&output".to_string());
+
+    let (output_param, output_decl, wrapper_ret_type, ret_expr) = if ret_is_zero_sized {
+        // Pointers to zero-sized types can't be passed to C functions
+        (
+            "output: *anyopaque".to_string(),
+            "var output: [1]u8 = undefined;".to_string(),
+            "void",
+            "",
+        )
+    } else {
+        (
+            format!("output: *{ret_type}"),
+            format!("var output: {ret_type} = undefined;"),
+            ret_type.as_str(),
+            " output",
+        )
+    };
+
+    extern_params.push(output_param);
+
+    format!(
+        r#"struct {{
+    extern fn {extern_name}_caller({}) void;
+    extern fn {extern_name}_size() i64;
+    extern fn {extern_name}_result_size() i64;
+
+    /// The alignment of the values this function captured
+    pub const closure_alignment = 16;
+
+    /// The size of the values this function captured, which depends on the app
+    pub fn closureSize() usize {{
+        return @intCast(usize, {extern_name}_size());
+    }}
+
+    /// The size of the value this function returns
+    pub fn resultSize() usize {{
+        return @intCast(usize, {extern_name}_result_size());
+    }}
+
+    /// Call this function with the values it captured. The app takes ownership
+    /// of the captured values and the arguments.
+    pub fn call({}) {wrapper_ret_type} {{
+        {output_decl}
+
+        {extern_name}_caller({});
+
+        return{ret_expr};
+    }}
+}}"#,
+        extern_params.join(", "),
+        params.join(", "),
+        call_args.join(", "),
+    )
+}
+
+fn struct_expr<I>(fields: I, id: TypeId, types: &Types) -> String
+where
+    I: Iterator<Item = (String, TypeId)>,
+{
+    let mut buf = "extern struct {\n".to_string();
+
+    for (label, type_id) in fields {
+        // Zero-sized fields don't affect the layout, and extern structs can't hold them.
+        if types.size_ignoring_alignment(type_id) > 0 {
+            writeln!(buf, "{INDENT}{label}: {},", type_name(type_id, types)).unwrap();
+        }
+    }
+
+    write_layout_asserts(id, types, &mut buf);
+    buf.push('}');
+
+    buf
+}
+
+fn enum_expr<'a, I>(tags: I, size: u32) -> String
+where
+    I: Iterator<Item = &'a String>,
+{
+    let mut buf = format!("enum(u{}) {{\n", size * 8);
+
+    for (index, tag_name) in tags.enumerate() {
+        writeln!(buf, "{INDENT}{} = {index},", escape_kw(tag_name)).unwrap();
+    }
+
+    buf.push('}');
+
+    buf
+}
+
+fn write_union_members(tags: &[(String, Option<TypeId>)], types: &Types, buf: &mut String) {
+    for (tag_name, opt_payload_id) in tags {
+        if let Some(payload_id) = opt_payload_id {
+            if types.size_ignoring_alignment(*payload_id) > 0 {
+                let payload_type = type_name(*payload_id, types);
+
+                writeln!(buf, "{INDENT}{}: {payload_type},", escape_kw(tag_name)).unwrap();
+            }
+        }
+    }
+}
+
+fn recursive_union_expr(
+    tags: &[(String, Option<TypeId>)],
+    null_tag: Option<&str>,
+    discriminant_size: u32,
+    discriminant_offset: u32,
+    id: TypeId,
+    types: &Types,
+    target_info: TargetInfo,
+) -> String {
+    let mut union_buf = "extern union {\n".to_string();
+
+    write_union_members(tags, types, &mut union_buf);
+
+    if !tags.iter().any(|(_, opt_payload_id)| {
+        matches!(opt_payload_id, Some(id) if types.size_ignoring_alignment(*id) > 0)
+    }) {
+        // Zig doesn't allow empty extern unions
+        writeln!(union_buf, "{INDENT}_unused: u8,").unwrap();
+    }
+
+    union_buf.push('}');
+
+    let pointer_doc = match null_tag {
+        Some(null_tag) => format!(
+            "/// null represents the {null_tag} tag. Otherwise, the discriminant is stored\n    /// in the unused low bits of the pointer."
+        ),
+        None => "/// The discriminant is stored in the unused low bits of the pointer.".to_string(),
+    };
+
+    let mut buf = format!(
+        r#"extern struct {{
+    {pointer_doc}
+    pointer: ?*anyopaque,
+
+    pub const Union = {};
+"#,
+        indent(&union_buf)
+    );
+
+    if discriminant_size > 0 {
+        let tag_enum = enum_expr(tags.iter().map(|(tag_name, _)| tag_name), discriminant_size);
+        let null_check = match null_tag {
+            Some(null_tag) => format!(
+                r#"
+        if (self.pointer == null) {{
+            return .{};
+        }}
+"#,
+                escape_kw(null_tag)
+            ),
+            None => String::new(),
+        };
+
+        write!(buf, "\n    pub const Tag = {};\n", indent(&tag_enum)).unwrap();
+
+        if tags.len() <= max_pointer_tagged_variants(target_info.architecture) {
+            let bitmask = tagged_pointer_bitmask(target_info.architecture);
+
+            write!(
+                buf,
+                r#"
+    pub fn tag(self: @This()) Tag {{{null_check}
+        return @intToEnum(Tag, @ptrToInt(self.pointer) & {bitmask:#x});
+    }}
+
+    pub fn unionPointer(self: @This()) *Union {{
+        return @intToPtr(*Union, @ptrToInt(self.pointer) & ~@as(usize, {bitmask:#x}));
+    }}
+"#
+            )
+            .unwrap();
+        } else {
+            // There are too many tags to fit the discriminant in the pointer,
+            // so it's stored in the heap allocation instead.
+            write!(
+                buf,
+                r#"
+    pub fn tag(self: @This()) Tag {{{null_check}
+        const bytes = @ptrCast([*]const u8, self.pointer);
+
+        return @ptrCast(*const Tag, @alignCast(@alignOf(Tag), bytes + {discriminant_offset})).*;
+    }}
+
+    pub fn unionPointer(self: @This()) *Union {{
+        return @ptrCast(*Union, @alignCast(@alignOf(Union), self.pointer));
+    }}
+"#
+            )
+            .unwrap();
+        }
+    }
+
+    write_layout_asserts(id, types, &mut buf);
+    buf.push('}');
+
+    buf
+}
+
+fn write_layout_asserts(id: TypeId, types: &Types, buf: &mut String) {
+    let size = types.size_rounded_to_alignment(id);
+    let align = types.align(id);
+
+    write!(
+        buf,
+        r#"
+    comptime {{
+        std.debug.assert(@sizeOf(@This()) == {size});
+        std.debug.assert(@alignOf(@This()) == {align});
+    }}
+"#
+    )
+    .unwrap();
+}
+
+/// Wrappers around the functions the host calls to run the app. The `_exposed_generic`
+/// functions Roc generates write their return value through the first argument,
+/// and take Str and List arguments by reference.
+fn entry_point_decls(types: &Types) -> String {
+    let mut buf = String::new();
+
+    for (name, type_id) in types.entry_points() {
+        let symbol = format!("roc__{name}_1_exposed_generic");
+        let wrapper = format!("call{}{}", name[..1].to_uppercase(), &name[1..]);
+
+        let (args, ret_id) = match types.get_type(*type_id) {
+            RocType::Function { args, ret, .. } => (args.as_slice(), *ret),
+            _ => (&[][..], *type_id),
+        };
+
+        let ret_is_zero_sized = types.size_ignoring_alignment(ret_id) == 0;
+        let ret_type = type_name(ret_id, types);
+        let mut extern_params = vec![if ret_is_zero_sized {
+            // Pointers to zero-sized types can't be passed to C functions
+            "ret: *anyopaque".to_string()
+        } else {
+            format!("ret: *{ret_type}")
+        }];
+        let mut wrapper_params = Vec::new();
+        let mut call_args = vec!["&ret".to_string()];
+
+        for (index, arg_id) in args.iter().enumerate() {
+            let arg_type = type_name(*arg_id, types);
+
+            match types.get_type(*arg_id) {
+                // Roc passes these by reference
+                RocType::RocStr
+                | RocType::RocList(_)
+                | RocType::RocDict(_, _)
+                | RocType::RocSet(_) => {
+                    extern_params.push(format!("arg{index}: *const {arg_type}"));
+                    call_args.push(format!("&arg{index}"));
+                }
+                _ => {
+                    extern_params.push(format!("arg{index}: {arg_type}"));
+                    call_args.push(format!("arg{index}"));
+                }
+            }
+
+            wrapper_params.push(format!("arg{index}: {arg_type}"));
+        }
+
+        let ret_decl = if ret_is_zero_sized {
+            "var ret: [1]u8 = undefined;".to_string()
+        } else {
+            format!("var ret: {ret_type} = undefined;")
+        };
+        let (wrapper_ret_type, ret_expr) = if ret_is_zero_sized {
+            ("void", "")
+        } else {
+            (ret_type.as_str(), " ret")
+        };
+
+        write!(
+            buf,
+            r#"
+extern fn {symbol}({}) void;
+
+/// Runs the Roc app's `{name}`
+pub fn {wrapper}({}) {wrapper_ret_type} {{
+    {ret_decl}
+
+    {symbol}({});
+
+    return{ret_expr};
+}}
+"#,
+            extern_params.join(", "),
+            wrapper_params.join(", "),
+            call_args.join(", "),
+        )
+        .unwrap();
+    }
+
+    buf
+}
+
+fn type_name(id: TypeId, types: &Types) -> String {
+    match types.get_type(id) {
+        RocType::Unit | RocType::EmptyTagUnion => "void".to_string(),
+        RocType::RocStr => "RocStr".to_string(),
+        RocType::Bool => "bool".to_string(),
+        RocType::Num(RocNum::U8) => "u8".to_string(),
+        RocType::Num(RocNum::U16) => "u16".to_string(),
+        RocType::Num(RocNum::U32) => "u32".to_string(),
+        RocType::Num(RocNum::U64) => "u64".to_string(),
+        RocType::Num(RocNum::U128) => "u128".to_string(),
+        RocType::Num(RocNum::I8) => "i8".to_string(),
+        RocType::Num(RocNum::I16) => "i16".to_string(),
+        RocType::Num(RocNum::I32) => "i32".to_string(),
+        RocType::Num(RocNum::I64) => "i64".to_string(),
+        RocType::Num(RocNum::I128) => "i128".to_string(),
+        RocType::Num(RocNum::F32) => "f32".to_string(),
+        RocType::Num(RocNum::F64) => "f64".to_string(),
+        RocType::Num(RocNum::Dec) => "RocDec".to_string(),
+        RocType::RocDict(_, _) | RocType::RocSet(_) => "RocDict".to_string(),
+        RocType::RocList(_) => "RocList".to_string(),
+        RocType::RocBox(elem_id) => format!("*{}", type_name(*elem_id, types)),
+        RocType::RocResult(ok_id, err_id) => result_name(*ok_id, *err_id, types),
+        RocType::Struct { name, .. }
+        | RocType::TagUnionPayload { name, .. }
+        | RocType::TagUnion(RocTagUnion::NonRecursive { name, .. })
+        | RocType::TagUnion(RocTagUnion::Recursive { name, .. })
+        | RocType::TagUnion(RocTagUnion::Enumeration { name, .. })
+        | RocType::TagUnion(RocTagUnion::NullableWrapped { name, .. })
+        | RocType::TagUnion(RocTagUnion::NullableUnwrapped { name, .. })
+        | RocType::TagUnion(RocTagUnion::NonNullableUnwrapped { name, .. })
+        | RocType::TagUnion(RocTagUnion::SingleTagStruct { name, .. }) => escape_kw(name),
+        RocType::RecursivePointer(content) => type_name(*content, types),
+        RocType::Function { name, .. } => escape_kw(name),
+    }
+}
+
+/// e.g. `RocResult_RocStr_i32` for `Result Str I32`
+fn result_name(ok_id: TypeId, err_id: TypeId, types: &Types) -> String {
+    let ident = |id| type_name(id, types).replace('*', "ptr_");
+
+    format!("RocResult_{}_{}", ident(ok_id), ident(err_id))
+}
+
+/// Zig allows any identifier as long as it's quoted, e.g. `@"struct"`
+fn escape_kw(name: &str) -> String {
+    if RESERVED_KEYWORDS.contains(&name) {
+        format!("@\"{name}\"")
+    } else {
+        name.to_string()
+    }
+}
+
+/// Zig's keywords, along with the names of primitive types, which can't be shadowed
+const RESERVED_KEYWORDS: &[&str] = &[
+    "addrspace",
+    "align",
+    "allowzero",
+    "and",
+    "anyframe",
+    "anyopaque",
+    "anytype",
+    "asm",
+    "async",
+    "await",
+    "bool",
+    "break",
+    "callconv",
+    "catch",
+    "comptime",
+    "const",
+    "continue",
+    "defer",
+    "else",
+    "enum",
+    "errdefer",
+    "error",
+    "export",
+    "extern",
+    "f16",
+    "f32",
+    "f64",
+    "f128",
+    "false",
+    "fn",
+    "for",
+    "i8",
+    "i16",
+    "i32",
+    "i64",
+    "i128",
+    "if",
+    "inline",
+    "isize",
+    "linksection",
+    "noalias",
+    "noinline",
+    "nosuspend",
+    "null",
+    "opaque",
+    "or",
+    "orelse",
+    "packed",
+    "pub",
+    "resume",
+    "return",
+    "struct",
+    "suspend",
+    "switch",
+    "test",
+    "threadlocal",
+    "true",
+    "try",
+    "type",
+    "u8",
+    "u16",
+    "u32",
+    "u64",
+    "u128",
+    "undefined",
+    "union",
+    "unreachable",
+    "usize",
+    "usingnamespace",
+    "var",
+    "void",
+    "volatile",
+    "while",
+];
@@ -0,0 +1,313 @@
+    use core::future::Future;
+    use core::pin::Pin;
+
+    /// The future an effect returns. It doesn't need to be `Send`, since Roc values
+    /// aren't either.
+    pub type EffectFuture<'a, T> = Pin<Box<dyn Future<Output = T> + 'a>>;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    pub use threads::run;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    use threads::perform;
+
+    #[cfg(target_arch = "wasm32")]
+    pub use asyncify::run;
+
+    #[cfg(target_arch = "wasm32")]
+    use asyncify::perform;
+
+    /// Natively, the Roc app runs on a thread of its own, which blocks whenever the app
+    /// performs an effect, until the host's future for that effect completes.
+    #[cfg(not(target_arch = "wasm32"))]
+    mod threads {
+        use super::{handle, EffectRequest, EffectResponse, RocEffects};
+        use core::future::Future;
+        use core::pin::Pin;
+        use core::task::{Context, Poll, Waker};
+        use std::cell::RefCell;
+        use std::sync::{mpsc, Arc, Mutex};
+
+        /// Roc values use non-atomic reference counts, so they aren't `Send`. Only one thread
+        /// uses them at a time here, though: the Roc thread is blocked while the host handles
+        /// an effect, and the host is waiting for the Roc thread the rest of the time.
+        struct AssertSend<T>(T);
+
+        unsafe impl<T> Send for AssertSend<T> {}
+
+        enum Message {
+            Effect(EffectRequest),
+            Done,
+        }
+
+        #[derive(Default)]
+        struct Mailbox {
+            message: Option<AssertSend<Message>>,
+            waker: Option<Waker>,
+        }
+
+        #[derive(Default)]
+        struct Shared {
+            mailbox: Mutex<Mailbox>,
+        }
+
+        impl Shared {
+            fn send(&self, message: Message) {
+                let mut mailbox = self.mailbox.lock().unwrap();
+
+                mailbox.message = Some(AssertSend(message));
+
+                if let Some(waker) = mailbox.waker.take() {
+                    waker.wake();
+                }
+            }
+        }
+
+        /// Tells the host the Roc thread is done, even if it panicked.
+        struct DoneGuard(Arc<Shared>);
+
+        impl Drop for DoneGuard {
+            fn drop(&mut self) {
+                self.0.send(Message::Done);
+            }
+        }
+
+        /// What the Roc thread needs to hand effects to the host and resume once they're done
+        struct Continuation {
+            shared: Arc<Shared>,
+            responses: mpsc::Receiver<AssertSend<EffectResponse>>,
+        }
+
+        thread_local! {
+            static CONTINUATION: RefCell<Option<Continuation>> = RefCell::new(None);
+        }
+
+        /// Called on the Roc thread: hand the effect to the host, and block until it's done.
+        /// This always returns the effect's response; it's only `None` when targeting wasm32.
+        pub(super) fn perform(request: EffectRequest) -> Option<EffectResponse> {
+            CONTINUATION.with(|continuation| {
+                let continuation = continuation.borrow();
+                let continuation = continuation
+                    .as_ref()
+                    .expect("The Roc app performed an effect outside of async_effects::run");
+
+                continuation.shared.send(Message::Effect(request));
+
+                match continuation.responses.recv() {
+                    Ok(AssertSend(response)) => Some(response),
+                    Err(_) => {
+                        panic!("The host stopped running the Roc app in the middle of an effect")
+                    }
+                }
+            })
+        }
+
+        struct NextMessage<'a>(&'a Shared);
+
+        impl Future for NextMessage<'_> {
+            type Output = Message;
+
+            fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Message> {
+                let mut mailbox = self.0.mailbox.lock().unwrap();
+
+                match mailbox.message.take() {
+                    Some(AssertSend(message)) => Poll::Ready(message),
+                    None => {
+                        mailbox.waker = Some(cx.waker().clone());
+
+                        Poll::Pending
+                    }
+                }
+            }
+        }
+
+        /// Run `roc_main`, which calls into the Roc app, on a thread of its own, and handle
+        /// each effect the app performs with `effects`. Returns what `roc_main` returned.
+        ///
+        /// Roc calls effects synchronously, so the Roc thread blocks whenever the app performs
+        /// an effect, until the host's future for that effect completes. The host's executor
+        /// never blocks, though: the Roc thread hands back what `roc_main` returned before it
+        /// says it's done, so there's no need to join it.
+        pub async fn run<E, F, T>(effects: &E, roc_main: F) -> T
+        where
+            E: RocEffects + ?Sized,
+            F: FnOnce() -> T + Send + 'static,
+            T: Send + 'static,
+        {
+            let shared = Arc::new(Shared::default());
+            let (response_sender, responses) = mpsc::channel();
+            let (output_sender, output) = mpsc::channel();
+
+            {
+                let shared = Arc::clone(&shared);
+
+                std::thread::spawn(move || {
+                    let _done = DoneGuard(Arc::clone(&shared));
+
+                    CONTINUATION.with(|continuation| {
+                        *continuation.borrow_mut() = Some(Continuation { shared, responses });
+                    });
+
+                    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(roc_main));
+
+                    // The host is waiting for `Done`, which `_done` sends after this.
+                    let _ = output_sender.send(result);
+                });
+            }
+
+            loop {
+                match NextMessage(&shared).await {
+                    Message::Effect(request) => {
+                        let response = handle(effects, request).await;
+
+                        // The Roc thread only stops listening once it's done, so this can't fail.
+                        let _ = response_sender.send(AssertSend(response));
+                    }
+                    Message::Done => break,
+                }
+            }
+
+            match output.try_recv() {
+                Ok(Ok(output)) => output,
+                Ok(Err(panic)) => std::panic::resume_unwind(panic),
+                Err(_) => unreachable!("The Roc thread was done without handing back its output"),
+            }
+        }
+    }
+
+    /// On wasm32 there are no threads to block, so the Roc call is suspended in place with
+    /// [Asyncify](https://github.com/WebAssembly/binaryen/blob/main/src/passes/Asyncify.cpp):
+    /// it unwinds the stack back to `run` when the app performs an effect, and rewinds it once
+    /// the host's future for that effect completes.
+    ///
+    /// This needs the host's `.wasm` file to be run through Asyncify after it's linked, telling
+    /// it to leave `roc_async_effects_call` alone, so that's where the stack unwinds to:
+    ///
+    ///     wasm-opt --asyncify --pass-arg=asyncify-removelist@roc_async_effects_call host.wasm -o host.wasm
+    #[cfg(target_arch = "wasm32")]
+    mod asyncify {
+        use super::{handle, EffectRequest, EffectResponse, RocEffects};
+        use core::cell::{Cell, RefCell};
+
+        /// How many bytes Asyncify can use to save the Roc call's stack while it's suspended
+        const STACK_SIZE: usize = 64 * 1024;
+
+        /// Asyncify's bookkeeping: the first two fields are the part of `stack` it hasn't used yet.
+        #[repr(C)]
+        struct Data {
+            next: *mut u8,
+            end: *mut u8,
+            stack: [u8; STACK_SIZE],
+        }
+
+        static mut DATA: Data = Data {
+            next: core::ptr::null_mut(),
+            end: core::ptr::null_mut(),
+            stack: [0; STACK_SIZE],
+        };
+
+        // Asyncify replaces these imports with the functions it adds to the module.
+        #[link(wasm_import_module = "asyncify")]
+        extern "C" {
+            fn start_unwind(data: *mut Data);
+            fn stop_unwind();
+            fn start_rewind(data: *mut Data);
+            fn stop_rewind();
+        }
+
+        thread_local! {
+            /// Whether the Roc call just suspended, and the stack is unwinding
+            static SUSPENDED: Cell<bool> = const { Cell::new(false) };
+
+            /// The effect the Roc call is suspended on
+            static REQUEST: RefCell<Option<EffectRequest>> = const { RefCell::new(None) };
+
+            /// The response to that effect, once the host has handled it
+            static RESPONSE: RefCell<Option<EffectResponse>> = const { RefCell::new(None) };
+        }
+
+        /// Called by the `roc_fx_` functions: suspend the Roc call until the host has handled
+        /// the effect, and return `None` so they return right away. Once it's handled, Asyncify
+        /// rewinds the stack and calls this again, and this time it returns the response.
+        ///
+        /// Asyncify doesn't instrument the functions that start unwinding, so this has to run
+        /// to completion both times, rather than picking up where it left off.
+        #[inline(never)]
+        pub(super) fn perform(request: EffectRequest) -> Option<EffectResponse> {
+            match RESPONSE.with(|response| response.borrow_mut().take()) {
+                Some(response) => {
+                    unsafe { stop_rewind() };
+
+                    Some(response)
+                }
+                None => {
+                    REQUEST.with(|pending| *pending.borrow_mut() = Some(request));
+                    SUSPENDED.with(|suspended| suspended.set(true));
+
+                    unsafe {
+                        let data = core::ptr::addr_of_mut!(DATA);
+
+                        (*data).next = (*data).stack.as_mut_ptr();
+                        (*data).end = (*data).next.add(STACK_SIZE);
+
+                        start_unwind(data);
+                    }
+
+                    None
+                }
+            }
+        }
+
+        /// Call `roc_main`, rewinding the stack first if it's resuming a suspended Roc call.
+        /// Returns whether the call suspended again.
+        ///
+        /// Asyncify must not instrument this (see above), so that the stack stops unwinding
+        /// here rather than going on to unwind `run` too.
+        #[no_mangle]
+        #[inline(never)]
+        fn roc_async_effects_call(roc_main: &mut dyn FnMut(), rewind: bool) -> bool {
+            unsafe {
+                if rewind {
+                    start_rewind(core::ptr::addr_of_mut!(DATA));
+                }
+
+                roc_main();
+
+                let suspended = SUSPENDED.with(|suspended| suspended.replace(false));
+
+                if suspended {
+                    stop_unwind();
+                }
+
+                suspended
+            }
+        }
+
+        /// Call `roc_main`, which calls into the Roc app, and handle each effect the app
+        /// performs with `effects`. Returns what `roc_main` returned.
+        ///
+        /// Each time the app performs an effect, the Roc call is suspended until the host's
+        /// future for that effect completes, and then `roc_main` is called again, which resumes
+        /// it where it left off.
+        pub async fn run<E, F, T>(effects: &E, mut roc_main: F) -> T
+        where
+            E: RocEffects + ?Sized,
+            F: FnMut() -> T,
+        {
+            let mut output = None;
+            let mut call_roc_main = || output = Some(roc_main());
+            let mut rewind = false;
+
+            while roc_async_effects_call(&mut call_roc_main, rewind) {
+                let request = REQUEST
+                    .with(|pending| pending.borrow_mut().take())
+                    .expect("The Roc call suspended without performing an effect");
+                let response = handle(effects, request).await;
+
+                RESPONSE.with(|pending| *pending.borrow_mut() = Some(response));
+                rewind = true;
+            }
+
+            output.expect("The Roc call returned without finishing")
+        }
+    }
@@ -0,0 +1,69 @@
+// ⚠️ GENERATED CODE ⚠️ - this entire file was generated by the `roc glue` CLI command
+
+#pragma once
+
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <string.h>
+
+#ifdef __cplusplus
+#define ROC_ALIGNAS(n) alignas(n)
+#define ROC_ALIGNOF(type) alignof(type)
+#define ROC_STATIC_ASSERT(condition, message) static_assert(condition, message)
+
+extern "C" {
+#else
+#define ROC_ALIGNAS(n) _Alignas(n)
+#define ROC_ALIGNOF(type) _Alignof(type)
+#define ROC_STATIC_ASSERT(condition, message) _Static_assert(condition, message)
+#endif
+
+// Roc's builtin types
+
+// A Str is stored on the heap like a List (U8), unless it is small enough to fit in
+// this struct itself. In that case, the highest bit of the last byte is set,
+// and the rest of that byte holds the string's length.
+typedef struct RocStr {
+    uint8_t *bytes;
+    size_t length;
+    size_t capacity;
+} RocStr;
+
+// The elements of a List are stored on the heap, right after its reference count.
+typedef struct RocList {
+    void *elements;
+    size_t length;
+    size_t capacity;
+} RocList;
+
+// A Dict's entries are in `data`, in the order they were inserted. The other lists are
+// an index into them, built by Roc's hash function. A Set is a Dict whose values are all {}.
+typedef struct RocDict {
+    RocList data;
+    RocList data_indices;
+    RocList metadata;
+    size_t size;
+} RocDict;
+
+typedef struct RocI128 {
+    ROC_ALIGNAS(16) uint8_t bytes[16];
+} RocI128;
+
+typedef struct RocU128 {
+    ROC_ALIGNAS(16) uint8_t bytes[16];
+} RocU128;
+
+// A fixed-point decimal: an I128 that is 10^18 times the number it represents
+typedef struct RocDec {
+    ROC_ALIGNAS(16) uint8_t bytes[16];
+} RocDec;
+
+// The host must define these, for the Roc app to call
+
+void *roc_alloc(size_t size, uint32_t alignment);
+void *roc_realloc(void *ptr, size_t new_size, size_t old_size, uint32_t alignment);
+void roc_dealloc(void *ptr, uint32_t alignment);
+void roc_panic(void *message, uint32_t tag_id);
+void *roc_memcpy(void *dest, const void *src, size_t n);
+void *roc_memset(void *str, int c, size_t n);
@@ -0,0 +1,39 @@
+// ⚠️ GENERATED CODE ⚠️ - this entire file was generated by the `roc glue` CLI command
+
+const std = @import("std");
+const builtin = @import("builtin");
+
+// Roc's builtin types
+
+/// A Str is stored on the heap like a List U8, unless it is small enough to fit in
+/// this struct itself. In that case, the highest bit of the last byte is set,
+/// and the rest of that byte holds the string's length.
+pub const RocStr = extern struct {
+    bytes: ?[*]u8,
+    length: usize,
+    capacity: usize,
+};
+
+/// The elements of a List are stored on the heap, right after its reference count.
+pub const RocList = extern struct {
+    elements: ?[*]u8,
+    length: usize,
+    capacity: usize,
+};
+
+/// A Dict's entries are in `data`, in the order they were inserted. The other lists are
+/// an index into them, built by Roc's hash function. A Set is a Dict whose values are all {}.
+pub const RocDict = extern struct {
+    data: RocList,
+    data_indices: RocList,
+    metadata: RocList,
+    size: usize,
+};
+
+/// A fixed-point decimal: an I128 that is 10^18 times the number it represents
+pub const RocDec = extern struct {
+    num: i128,
+};
+
+// The host must export roc_alloc, roc_realloc, roc_dealloc, roc_panic, roc_memcpy,
+// and roc_memset with the C calling convention, for the Roc app to call.
@@ -1,7 +1,7 @@
 mod test_glue;
 
 use indoc::indoc;
-use test_glue::Expr;
+use test_glue::{variant_Expr, Expr};
 
 extern "C" {
     #[link_name = "roc__mainForHost_1_exposed_generic"]
@@ -45,6 +45,24 @@ pub extern "C" fn rust_main() -> i32 {
         Expr::String("this is a test".into()),
     ); // Debug
 
+    // Verify the safe accessors and the Rust enum.
+
+    let (left, right) = tag_union.get_Concat().unwrap();
+
+    assert_eq!(left.get_String().map(|s| s.as_str()), Some("Hello, "));
+    assert_eq!(right.get_String().map(|s| s.as_str()), Some("World!"));
+    assert!(tag_union.get_String().is_none());
+
+    match tag_union.clone().into_variant() {
+        variant_Expr::Concat(left, right) => {
+            assert!(left == Expr::String("Hello, ".into()));
+            assert!(right == Expr::String("World!".into()));
+        }
+        other => panic!("Expected Concat, got {:?}", other),
+    }
+
+    assert!(Expr::from(tag_union.clone().into_variant()) == tag_union);
+
     let mut set = HashSet::new();
 
     set.insert(tag_union.clone()); // Eq, Hash
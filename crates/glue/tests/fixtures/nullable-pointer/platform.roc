@@ -0,0 +1,11 @@
+platform "test-platform"
+    requires {} { main : _ }
+    exposes []
+    packages {}
+    imports []
+    provides [mainForHost]
+
+MaybeStr : [Nothing, Just (Box Str)]
+
+mainForHost : MaybeStr
+mainForHost = main
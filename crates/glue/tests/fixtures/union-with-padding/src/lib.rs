@@ -1,6 +1,6 @@
 mod test_glue;
 
-use test_glue::NonRecursive;
+use test_glue::{variant_NonRecursive, NonRecursive};
 
 extern "C" {
     #[link_name = "roc__mainForHost_1_exposed_generic"]
@@ -38,6 +38,19 @@ pub extern "C" fn rust_main() -> i32 {
         NonRecursive::Blah(456),
     ); // Debug
 
+    // Verify the safe accessors and the Rust enum.
+
+    assert_eq!(tag_union.get_Foo().map(|s| s.as_str()), Some("This is a test"));
+    assert!(tag_union.get_Bar().is_none());
+
+    match tag_union.clone().into_variant() {
+        variant_NonRecursive::Foo(s) => assert_eq!(s.as_str(), "This is a test"),
+        other => panic!("Expected Foo, got {:?}", other),
+    }
+
+    assert!(NonRecursive::from(variant_NonRecursive::Baz) == NonRecursive::Baz);
+    assert!(NonRecursive::from(tag_union.clone().into_variant()) == tag_union);
+
     let mut set = HashSet::new();
 
     set.insert(tag_union.clone()); // Eq, Hash
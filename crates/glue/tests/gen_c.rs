@@ -0,0 +1,140 @@
+#[macro_use]
+extern crate pretty_assertions;
+
+#[macro_use]
+extern crate indoc;
+
+mod helpers;
+
+#[cfg(test)]
+mod test_gen_c {
+    use crate::helpers::generate_c_bindings;
+
+    #[test]
+    fn tag_union_enumeration() {
+        let module = indoc!(
+            r#"
+            Enumeration : [Blah, Foo, Bar,]
+
+            main : Enumeration
+            main = Foo
+        "#
+        );
+
+        assert_eq!(
+            generate_c_bindings(module)
+                .strip_prefix('\n')
+                .unwrap_or_default(),
+            indoc!(
+                r#"
+                typedef uint8_t Enumeration;
+
+                enum {
+                    Enumeration_Bar = 0,
+                    Enumeration_Blah = 1,
+                    Enumeration_Foo = 2,
+                };
+
+                void roc__main_1_exposed_generic(Enumeration *ret);
+            "#
+            )
+        );
+    }
+
+    #[test]
+    fn record_with_padding() {
+        let module = indoc!(
+            r#"
+            MyRcd : { a : U8, b : U32, c : U16 }
+
+            main : MyRcd
+            main = { a: 1, b: 2, c: 3 }
+        "#
+        );
+
+        assert_eq!(
+            generate_c_bindings(module)
+                .strip_prefix('\n')
+                .unwrap_or_default(),
+            indoc!(
+                r#"
+                typedef struct MyRcd MyRcd;
+
+                struct MyRcd {
+                    uint32_t b;
+                    uint16_t c;
+                    uint8_t a;
+                };
+
+                ROC_STATIC_ASSERT(sizeof(struct MyRcd) == 8, "MyRcd has the wrong size for this target");
+                ROC_STATIC_ASSERT(ROC_ALIGNOF(struct MyRcd) == 4, "MyRcd has the wrong alignment for this target");
+
+                void roc__main_1_exposed_generic(MyRcd *ret);
+            "#
+            )
+        );
+    }
+
+    #[test]
+    fn tag_union_with_payloads() {
+        let module = indoc!(
+            r#"
+            MyUnion : [Foo U32, Bar U8 U16, Baz]
+
+            main : MyUnion
+            main = Foo 1
+        "#
+        );
+
+        assert_eq!(
+            generate_c_bindings(module)
+                .strip_prefix('\n')
+                .unwrap_or_default(),
+            indoc!(
+                r#"
+                typedef struct MyUnion_Bar MyUnion_Bar;
+                typedef union MyUnion MyUnion;
+
+                struct MyUnion_Bar {
+                    uint16_t f1;
+                    uint8_t f0;
+                };
+
+                ROC_STATIC_ASSERT(sizeof(struct MyUnion_Bar) == 4, "MyUnion_Bar has the wrong size for this target");
+                ROC_STATIC_ASSERT(ROC_ALIGNOF(struct MyUnion_Bar) == 2, "MyUnion_Bar has the wrong alignment for this target");
+
+                typedef uint8_t discriminant_MyUnion;
+
+                enum {
+                    discriminant_MyUnion_Bar = 0,
+                    discriminant_MyUnion_Baz = 1,
+                    discriminant_MyUnion_Foo = 2,
+                };
+
+                union MyUnion {
+                    MyUnion_Bar Bar;
+                    uint32_t Foo;
+                    uint8_t _bytes[8];
+                };
+
+                ROC_STATIC_ASSERT(sizeof(union MyUnion) == 8, "MyUnion has the wrong size for this target");
+                ROC_STATIC_ASSERT(ROC_ALIGNOF(union MyUnion) == 4, "MyUnion has the wrong alignment for this target");
+
+                static inline discriminant_MyUnion MyUnion_discriminant(const MyUnion *tag_union) {
+                    discriminant_MyUnion discriminant;
+
+                    memcpy(&discriminant, tag_union->_bytes + 4, sizeof(discriminant));
+
+                    return discriminant;
+                }
+
+                static inline void MyUnion_set_discriminant(MyUnion *tag_union, discriminant_MyUnion discriminant) {
+                    memcpy(tag_union->_bytes + 4, &discriminant, sizeof(discriminant));
+                }
+
+                void roc__main_1_exposed_generic(MyUnion *ret);
+            "#
+            )
+        );
+    }
+}
@@ -8,7 +8,10 @@ mod helpers;
 
 #[cfg(test)]
 mod test_gen_rs {
-    use crate::helpers::generate_bindings;
+    use crate::helpers::{
+        generate_async_effects, generate_bindings, generate_layout_test_platform,
+        generate_layout_tests,
+    };
 
     #[test]
     fn basic_record_aliased() {
@@ -233,4 +236,208 @@ mod test_gen_rs {
             )
         );
     }
+
+    #[test]
+    fn record_with_box_and_dict() {
+        let module = indoc!(
+            r#"
+            MyRcd : { a : Box U64, b : Dict Str U64 }
+
+            main : MyRcd
+            main = { a: Box.box 1u64, b: Dict.empty }
+        "#
+        );
+
+        assert_eq!(
+            generate_bindings(module)
+                .strip_prefix('\n')
+                .unwrap_or_default(),
+            indoc!(
+                r#"
+                #[cfg(any(
+                    target_arch = "arm",
+                    target_arch = "aarch64",
+                    target_arch = "wasm32",
+                    target_arch = "x86",
+                    target_arch = "x86_64"
+                ))]
+                #[derive(Clone, Debug, Default, Eq, Ord, Hash, PartialEq, PartialOrd)]
+                #[repr(C)]
+                pub struct MyRcd {
+                    pub a: roc_std::RocBox<u64>,
+                    pub b: roc_std::RocDict<roc_std::RocStr, u64>,
+                }
+            "#
+            )
+        );
+    }
+
+    #[test]
+    fn layout_tests_record() {
+        let module = indoc!(
+            r#"
+            MyRcd : { a : U64, b : I128 }
+
+            main : MyRcd
+            main = { a: 1u64, b: 2i128 }
+        "#
+        );
+
+        assert_eq!(
+            generate_layout_tests(module),
+            indoc!(
+                r#"
+                // ⚠️ GENERATED CODE ⚠️ - this entire file was generated by the `roc glue` CLI command
+
+                //! Passes a sample value of each of the platform's types through the Roc app and back,
+                //! to catch the compiler and the glue in `glue` disagreeing on how they're laid out.
+                //!
+                //! These call an `echo` entry point for each type, which only the copy of the platform in
+                //! `platform_layout_tests.roc` provides. To run them, build the app against that platform for the
+                //! host's tests, and add `#[cfg(test)] mod glue_layout_tests;` next to `mod glue;`.
+
+                #![allow(non_snake_case)]
+                #![allow(unused_imports)]
+
+                use super::glue::*;
+
+                extern "C" {
+                    fn roc__echoMyRcd_1_exposed_generic(ret: *mut MyRcd, arg: &MyRcd);
+                }
+
+                #[test]
+                fn round_trip_MyRcd() {
+                    let before: MyRcd = MyRcd { b: roc_std::I128::from(-42), a: 42 };
+                    let mut after = core::mem::MaybeUninit::<MyRcd>::uninit();
+
+                    let after = unsafe {
+                        roc__echoMyRcd_1_exposed_generic(after.as_mut_ptr(), &before);
+
+                        after.assume_init()
+                    };
+
+                    assert_eq!(before, after);
+                }
+            "#
+            )
+        );
+    }
+
+    #[test]
+    fn async_effects() {
+        let hosted = indoc!(
+            r#"
+            hosted Effect
+                exposes [Effect, after, map, always, getLine, putLine]
+                imports []
+                generates Effect with [after, map, always]
+
+            getLine : Effect Str
+
+            putLine : Str -> Effect {}
+        "#
+        );
+
+        let glue = generate_async_effects(hosted);
+
+        // Both the native and the wasm32 runtime are always there, for the host's target to pick from.
+        assert!(glue.contains("#[cfg(not(target_arch = \"wasm32\"))]\n    mod threads {"));
+        assert!(glue.contains("#[cfg(target_arch = \"wasm32\")]\n    mod asyncify {"));
+        assert!(glue.contains(
+            "fn roc_async_effects_call(roc_main: &mut dyn FnMut(), rewind: bool) -> bool"
+        ));
+
+        let (_, effects) = glue
+            .split_once(
+                "    /// The effects the host provides to the Roc app, as async functions\n",
+            )
+            .unwrap();
+
+        assert_eq!(
+            effects,
+            indoc!(
+                r#"
+                    pub trait RocEffects {
+                        fn getLine(&self) -> EffectFuture<'_, roc_std::RocStr>;
+                        fn putLine(&self, arg0: roc_std::RocStr) -> EffectFuture<'_, ()>;
+                    }
+
+                    enum EffectRequest {
+                        getLine,
+                        putLine(roc_std::RocStr),
+                    }
+
+                    enum EffectResponse {
+                        getLine(roc_std::RocStr),
+                        putLine(()),
+                    }
+
+                    async fn handle<E: RocEffects + ?Sized>(effects: &E, request: EffectRequest) -> EffectResponse {
+                        match request {
+                            EffectRequest::getLine => EffectResponse::getLine(effects.getLine().await),
+                            EffectRequest::putLine(arg0) => EffectResponse::putLine(effects.putLine(arg0).await),
+                        }
+                    }
+
+                    #[no_mangle]
+                    pub extern "C" fn roc_fx_getLine() -> roc_std::RocStr {
+                        match perform(EffectRequest::getLine) {
+                            Some(EffectResponse::getLine(output)) => output,
+                            Some(_) => unreachable!(),
+                            // The Roc call is suspending, and Asyncify discards this as it unwinds the stack.
+                            None => unsafe { core::mem::zeroed() },
+                        }
+                    }
+
+                    #[no_mangle]
+                    pub extern "C" fn roc_fx_putLine(arg0: &roc_std::RocStr) -> () {
+                        match perform(EffectRequest::putLine(arg0.clone())) {
+                            Some(EffectResponse::putLine(output)) => output,
+                            Some(_) => unreachable!(),
+                            // The Roc call is suspending, and Asyncify discards this as it unwinds the stack.
+                            None => unsafe { core::mem::zeroed() },
+                        }
+                    }
+                }
+            "#
+            )
+        );
+    }
+
+    #[test]
+    fn layout_test_platform_provides_echoes() {
+        let module = indoc!(
+            r#"
+            MyRcd : { a : U64, b : I128 }
+
+            main : MyRcd
+            main = { a: 1u64, b: 2i128 }
+        "#
+        );
+
+        assert_eq!(
+            generate_layout_test_platform(module),
+            indoc!(
+                r#"
+                # ⚠️ GENERATED CODE ⚠️ - this entire file was generated by the `roc glue` CLI command
+                # It's the platform, plus the `echo` entry points its layout tests call.
+
+                platform "main"
+                    requires {} { nothing : {} }
+                    exposes []
+                    packages {}
+                    imports []
+                    provides [main, echoMyRcd]
+
+                MyRcd : { a : U64, b : I128 }
+
+                main : MyRcd
+                main = { a: 1u64, b: 2i128 }
+
+                echoMyRcd : MyRcd -> MyRcd
+                echoMyRcd = \value -> value
+            "#
+            )
+        );
+    }
 }
@@ -0,0 +1,190 @@
+#[macro_use]
+extern crate pretty_assertions;
+
+#[macro_use]
+extern crate indoc;
+
+mod helpers;
+
+#[cfg(test)]
+mod test_gen_zig {
+    use crate::helpers::generate_zig_bindings;
+
+    #[test]
+    fn tag_union_enumeration() {
+        let module = indoc!(
+            r#"
+            Enumeration : [Blah, Foo, Bar,]
+
+            main : Enumeration
+            main = Foo
+        "#
+        );
+
+        assert_eq!(
+            generate_zig_bindings(module)
+                .strip_prefix('\n')
+                .unwrap_or_default(),
+            indoc!(
+                r#"
+                pub const Enumeration = enum(u8) {
+                    Bar = 0,
+                    Blah = 1,
+                    Foo = 2,
+                };
+
+                extern fn roc__main_1_exposed_generic(ret: *Enumeration) void;
+
+                /// Runs the Roc app's `main`
+                pub fn callMain() Enumeration {
+                    var ret: Enumeration = undefined;
+
+                    roc__main_1_exposed_generic(&ret);
+
+                    return ret;
+                }
+            "#
+            )
+        );
+    }
+
+    #[test]
+    fn record_with_padding() {
+        let module = indoc!(
+            r#"
+            MyRcd : { a : U8, b : U32, c : U16 }
+
+            main : MyRcd
+            main = { a: 1, b: 2, c: 3 }
+        "#
+        );
+
+        assert_eq!(
+            generate_zig_bindings(module)
+                .strip_prefix('\n')
+                .unwrap_or_default(),
+            indoc!(
+                r#"
+                pub const MyRcd = extern struct {
+                    b: u32,
+                    c: u16,
+                    a: u8,
+
+                    comptime {
+                        std.debug.assert(@sizeOf(@This()) == 8);
+                        std.debug.assert(@alignOf(@This()) == 4);
+                    }
+                };
+
+                extern fn roc__main_1_exposed_generic(ret: *MyRcd) void;
+
+                /// Runs the Roc app's `main`
+                pub fn callMain() MyRcd {
+                    var ret: MyRcd = undefined;
+
+                    roc__main_1_exposed_generic(&ret);
+
+                    return ret;
+                }
+            "#
+            )
+        );
+    }
+
+    #[test]
+    fn tag_union_with_payloads() {
+        let module = indoc!(
+            r#"
+            MyUnion : [Foo U32, Bar U8 U16, Baz]
+
+            main : MyUnion
+            main = Foo 1
+        "#
+        );
+
+        assert_eq!(
+            generate_zig_bindings(module)
+                .strip_prefix('\n')
+                .unwrap_or_default(),
+            indoc!(
+                r#"
+                pub const MyUnion_Bar = extern struct {
+                    f1: u16,
+                    f0: u8,
+
+                    comptime {
+                        std.debug.assert(@sizeOf(@This()) == 4);
+                        std.debug.assert(@alignOf(@This()) == 2);
+                    }
+                };
+
+                pub const MyUnion = extern union {
+                    Bar: MyUnion_Bar,
+                    Foo: u32,
+                    _bytes: [8]u8,
+
+                    pub const Tag = enum(u8) {
+                        Bar = 0,
+                        Baz = 1,
+                        Foo = 2,
+                    };
+
+                    pub fn tag(self: *const @This()) Tag {
+                        const bytes = @ptrCast([*]const u8, self);
+
+                        return @ptrCast(*const Tag, @alignCast(@alignOf(Tag), bytes + 4)).*;
+                    }
+
+                    pub fn setTag(self: *@This(), new_tag: Tag) void {
+                        const bytes = @ptrCast([*]u8, self);
+
+                        @ptrCast(*Tag, @alignCast(@alignOf(Tag), bytes + 4)).* = new_tag;
+                    }
+
+                    pub fn initBar(payload: MyUnion_Bar) @This() {
+                        var tag_union: @This() = undefined;
+
+                        tag_union.Bar = payload;
+                        tag_union.setTag(.Bar);
+
+                        return tag_union;
+                    }
+
+                    pub fn initBaz() @This() {
+                        var tag_union: @This() = undefined;
+
+                        tag_union.setTag(.Baz);
+
+                        return tag_union;
+                    }
+
+                    pub fn initFoo(payload: u32) @This() {
+                        var tag_union: @This() = undefined;
+
+                        tag_union.Foo = payload;
+                        tag_union.setTag(.Foo);
+
+                        return tag_union;
+                    }
+
+                    comptime {
+                        std.debug.assert(@sizeOf(@This()) == 8);
+                        std.debug.assert(@alignOf(@This()) == 4);
+                    }
+                };
+
+                extern fn roc__main_1_exposed_generic(ret: *MyUnion) void;
+
+                /// Runs the Roc app's `main`
+                pub fn callMain() MyUnion {
+                    var ret: MyUnion = undefined;
+
+                    roc__main_1_exposed_generic(&ret);
+
+                    return ret;
+                }
+            "#
+            )
+        );
+    }
+}
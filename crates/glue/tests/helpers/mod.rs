@@ -1,6 +1,8 @@
 use roc_glue::load::{load_types, IgnoreErrors};
-use roc_glue::rust_glue;
+use roc_glue::types::Types;
+use roc_glue::{c_glue, rust_glue, zig_glue};
 use roc_load::Threading;
+use roc_target::TargetInfo;
 use std::env;
 use std::fs::File;
 use std::io::Write;
@@ -8,8 +10,71 @@ use std::path::PathBuf;
 
 #[allow(dead_code)]
 pub fn generate_bindings(decl_src: &str) -> String {
+    rust_glue::emit(&load_types_from_src(decl_src))
+}
+
+#[allow(dead_code)]
+pub fn generate_c_bindings(decl_src: &str) -> String {
+    c_glue::emit(&load_types_from_src(decl_src))
+}
+
+#[allow(dead_code)]
+pub fn generate_zig_bindings(decl_src: &str) -> String {
+    zig_glue::emit(&load_types_from_src(decl_src))
+}
+
+#[allow(dead_code)]
+pub fn generate_layout_tests(decl_src: &str) -> String {
+    rust_glue::emit_layout_tests(
+        &load_types_from_src(decl_src),
+        "glue",
+        "platform_layout_tests.roc",
+    )
+}
+
+#[allow(dead_code)]
+pub fn generate_layout_test_platform(decl_src: &str) -> String {
+    rust_glue::emit_layout_test_platform(&load_types_from_src(decl_src), &platform_src(decl_src))
+        .expect("the test platform provides `main`")
+}
+
+#[allow(dead_code)]
+pub fn generate_async_effects(hosted_src: &str) -> String {
     use tempfile::tempdir;
 
+    let platform_src = indoc!(
+        r#"
+            platform "main"
+                requires {} { main : Effect.Effect {} }
+                exposes []
+                packages {}
+                imports [Effect]
+                provides [mainForHost]
+
+            mainForHost : Effect.Effect {}
+            mainForHost = main
+        "#
+    );
+
+    let dir = tempdir().expect("Unable to create tempdir");
+    let platform_path = dir.path().join("platform.roc");
+
+    std::fs::write(&platform_path, platform_src).unwrap();
+    std::fs::write(dir.path().join("Effect.roc"), hosted_src).unwrap();
+
+    let result = load_types(
+        platform_path,
+        Threading::Single,
+        // required `main` is unused; that error is okay
+        IgnoreErrors { can: true },
+    );
+
+    dir.close().expect("Unable to close tempdir");
+
+    rust_glue::emit_async_effects(&result.expect("had problems loading"))
+}
+
+fn platform_src(decl_src: &str) -> String {
     let mut src = indoc!(
         r#"
             platform "main"
@@ -25,7 +90,15 @@ pub fn generate_bindings(decl_src: &str) -> String {
 
     src.push_str(decl_src);
 
-    let pairs = {
+    src
+}
+
+fn load_types_from_src(decl_src: &str) -> Vec<(Types, TargetInfo)> {
+    use tempfile::tempdir;
+
+    let src = platform_src(decl_src);
+
+    {
         let dir = tempdir().expect("Unable to create tempdir");
         let filename = PathBuf::from("platform.roc");
         let file_path = dir.path().join(filename);
@@ -43,9 +116,7 @@ pub fn generate_bindings(decl_src: &str) -> String {
         dir.close().expect("Unable to close tempdir");
 
         result.expect("had problems loading")
-    };
-
-    rust_glue::emit(&pairs)
+    }
 }
 
 #[allow(dead_code)]
@@ -109,6 +109,11 @@ mod glue_cli_run {
             `Cons "small str" Nil` is: StrConsList::Cons("small str", StrConsList::Nil)
             `Nil` is: StrConsList::Nil
         "#),
+        nullable_pointer:"nullable-pointer" => indoc!(r#"
+            tag_union was: MaybeStr::Just("Hello, World!")
+            `Just (Box.box "small str")` is: MaybeStr::Just("small str")
+            `Nothing` is: MaybeStr::Nothing
+        "#),
         nonnullable_unwrapped:"nonnullable-unwrapped" => indoc!(r#"
             tag_union was: StrRoseTree::Tree(ManuallyDrop { value: StrRoseTree_Tree { f0: "root", f1: [StrRoseTree::Tree(ManuallyDrop { value: StrRoseTree_Tree { f0: "leaf1", f1: [] } }), StrRoseTree::Tree(ManuallyDrop { value: StrRoseTree_Tree { f0: "leaf2", f1: [] } })] } })
             Tree "foo" [] is: StrRoseTree::Tree(ManuallyDrop { value: StrRoseTree_Tree { f0: "foo", f1: [] } })
@@ -0,0 +1,642 @@
+//! What we know about a document: the diagnostics, hover, definitions, and completions that come
+//! from loading and type-checking it.
+//!
+//! A document is loaded from its text in the editor, and everything it imports is loaded from
+//! disk. Imported interface modules that type-checked without problems before come from the type
+//! cache in the Roc cache directory, so after the first analysis, each keystroke only re-solves
+//! the document itself and whatever it imports that changed.
+use std::path::Path;
+
+use bumpalo::Bump;
+use roc_load::query::{definition_at, hover_at, Location};
+use roc_load::semantic_tokens::{semantic_tokens, TokenKind};
+use roc_load::symbol_index::SymbolIndex;
+use roc_load::{LoadedModule, LoadingProblem};
+use roc_packaging::cache::{self, RocCacheDir};
+use roc_parse::keyword::KEYWORDS;
+use roc_problem::lint::{LintConfig, LintLevel};
+use roc_problem::Severity;
+use roc_region::all::{LineInfo, Region};
+use roc_reporting::report::{
+    can_problem, type_problem, Fix, RenderTarget, Report, RocDocAllocator, DEFAULT_PALETTE,
+};
+use roc_target::TargetInfo;
+use roc_types::pretty_print::{name_and_print_var, DebugPrint};
+use serde_json::{json, Value};
+
+use crate::protocol::{path_to_uri, LineIndex};
+
+// The protocol's numbers for kinds of completions and severities of diagnostics
+const COMPLETION_FUNCTION: u8 = 3;
+const COMPLETION_VARIABLE: u8 = 6;
+const COMPLETION_MODULE: u8 = 9;
+const COMPLETION_KEYWORD: u8 = 14;
+const DIAGNOSTIC_ERROR: u8 = 1;
+const DIAGNOSTIC_WARNING: u8 = 2;
+
+/// The protocol's kind of code action that fixes a diagnostic
+pub const QUICK_FIX: &str = "quickfix";
+
+/// The kinds of semantic tokens we send, which editors know how to color. Each token refers to
+/// one by its index.
+pub const TOKEN_TYPES: [&str; 7] = [
+    "type",
+    "interface",
+    "enumMember",
+    "namespace",
+    "parameter",
+    "variable",
+    "function",
+];
+
+/// Each token has a bit for each of these that applies to it. Editors that don't know
+/// `effectful` still color the function as a function.
+pub const TOKEN_MODIFIERS: [&str; 2] = ["declaration", "effectful"];
+
+/// A document as of one version of its text, loaded and type-checked
+pub struct Analysis {
+    /// The text that was checked, which positions in the analysis refer to
+    text: String,
+    loaded: LoadedModule,
+}
+
+/// Load and check the document at `path`, whose text in the editor is `text`. Returns its
+/// diagnostics, and the analysis if loading got as far as type checking.
+pub fn analyze(path: &Path, text: &str) -> (Vec<Value>, Option<Analysis>) {
+    let arena = Bump::new();
+    let src_dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+    let roc_cache_dir = cache::roc_cache_dir();
+
+    let result = roc_load::load_and_typecheck_str(
+        &arena,
+        path.to_path_buf(),
+        arena.alloc_str(text),
+        src_dir,
+        Default::default(),
+        // Only used for reporting errors, like `roc check` does
+        TargetInfo::default_x86_64(),
+        RenderTarget::Generic,
+        RocCacheDir::Persistent(&roc_cache_dir),
+        DEFAULT_PALETTE,
+    );
+
+    match result {
+        Ok(mut loaded) => {
+            let diagnostics = diagnostics(&mut loaded, text);
+            let analysis = Analysis {
+                text: text.to_string(),
+                loaded,
+            };
+
+            (diagnostics, Some(analysis))
+        }
+        Err(problem) => (vec![loading_diagnostic(text, problem)], None),
+    }
+}
+
+/// The problems in the document itself, rendered like `roc check` renders them but without the
+/// header, since the editor shows which file they're in.
+fn diagnostics(loaded: &mut LoadedModule, text: &str) -> Vec<Value> {
+    let home = loaded.module_id;
+    let path = match loaded.sources.get(&home) {
+        Some((path, _)) => path.clone(),
+        None => return Vec::new(),
+    };
+
+    let src_lines: Vec<&str> = text.split('\n').collect();
+    let lines = LineInfo::new(text);
+    let index = LineIndex::new(text);
+    let uri = path_to_uri(&path);
+    let alloc = RocDocAllocator::new(&src_lines, home, &loaded.interns);
+    let lints = LintConfig::default();
+
+    let mut diagnostics = Vec::new();
+
+    for problem in loaded.can_problems.remove(&home).unwrap_or_default() {
+        if problem.lint().map(|lint| lints.level(lint)) == Some(LintLevel::Allow) {
+            continue;
+        }
+
+        let region = problem.region();
+        let report = can_problem(&alloc, &lines, path.clone(), problem);
+
+        diagnostics.push(diagnostic(&uri, &index, &lines, region, report, &alloc));
+    }
+
+    for problem in loaded.type_problems.remove(&home).unwrap_or_default() {
+        if problem.lint().map(|lint| lints.level(lint)) == Some(LintLevel::Allow) {
+            continue;
+        }
+
+        let region = problem.region();
+
+        if let Some(report) = type_problem(&alloc, &lines, path.clone(), problem) {
+            diagnostics.push(diagnostic(&uri, &index, &lines, region, report, &alloc));
+        }
+    }
+
+    diagnostics
+}
+
+fn diagnostic<'b>(
+    uri: &str,
+    index: &LineIndex,
+    lines: &LineInfo,
+    region: Option<Region>,
+    mut report: Report<'b>,
+    alloc: &'b RocDocAllocator<'b>,
+) -> Value {
+    let severity = match report.severity {
+        Severity::RuntimeError => DIAGNOSTIC_ERROR,
+        Severity::Warning => DIAGNOSTIC_WARNING,
+    };
+    let code = report.code;
+    let fixes: Vec<Value> = report
+        .fixes
+        .iter()
+        .map(|fix| fix_to_json(index, fix))
+        .collect();
+
+    let related: Vec<Value> = report
+        .related
+        .iter()
+        .map(|span| {
+            let region = Region::new(
+                lines.convert_line_column(span.region.start),
+                lines.convert_line_column(span.region.end),
+            );
+
+            json!({
+                "location": { "uri": uri, "range": index.range(region) },
+                "message": span.message,
+            })
+        })
+        .collect();
+
+    // Without a title, a report renders without its header. The editor shows the related spans
+    // next to the message, so it doesn't need them quoted in it, too.
+    report.title = String::new();
+    report.related = Vec::new();
+
+    let mut message = String::new();
+    report.render_ci(&mut message, alloc);
+
+    json!({
+        "range": index.range(region.unwrap_or_default()),
+        "severity": severity,
+        "code": code,
+        "source": "roc",
+        "message": message.trim(),
+        "relatedInformation": related,
+        // The editor hands this back when it asks for the diagnostic's code actions.
+        "data": { "fixes": fixes },
+    })
+}
+
+fn fix_to_json(index: &LineIndex, fix: &Fix) -> Value {
+    let edits: Vec<Value> = fix
+        .edits
+        .iter()
+        .map(|edit| json!({ "range": index.range(edit.region), "newText": edit.replacement }))
+        .collect();
+
+    json!({ "title": fix.description, "edits": edits })
+}
+
+/// The quick fixes for the diagnostics the editor asks about, in the document at `uri`. Each
+/// diagnostic carries its fixes, so this doesn't need the document's latest analysis: the fixes
+/// are for the text the diagnostics were reported on, which is what the editor has them for.
+pub fn code_actions(uri: &str, diagnostics: &[Value]) -> Value {
+    let mut actions = Vec::new();
+
+    for diagnostic in diagnostics {
+        let fixes = match diagnostic["data"]["fixes"].as_array() {
+            Some(fixes) => fixes,
+            None => continue,
+        };
+
+        for fix in fixes {
+            actions.push(json!({
+                "title": fix["title"],
+                "kind": QUICK_FIX,
+                "diagnostics": [diagnostic],
+                // When there's only one way to fix it, editors can apply it with a single key.
+                "isPreferred": fixes.len() == 1,
+                "edit": { "changes": { uri: fix["edits"] } },
+            }));
+        }
+    }
+
+    Value::Array(actions)
+}
+
+/// Loading stops at problems like syntax errors, which come to us already rendered. The report
+/// doesn't say where the problem is, other than by quoting the source, so we go by that.
+fn loading_diagnostic(text: &str, problem: LoadingProblem<'_>) -> Value {
+    let message = match problem {
+        LoadingProblem::FormattedReport(report) => report,
+        other => format!("{:?}", other),
+    };
+
+    let line = reported_line(&message).unwrap_or(0);
+
+    json!({
+        "range": LineIndex::new(text).line_range(line),
+        "severity": DIAGNOSTIC_ERROR,
+        "code": reported_code(&message),
+        "source": "roc",
+        "message": message.trim(),
+    })
+}
+
+/// The compiler panics on problems it doesn't have a report for yet. The panic message goes to
+/// stderr, which editors keep as the server's log.
+pub fn crash_diagnostic(text: &str) -> Value {
+    json!({
+        "range": LineIndex::new(text).line_range(0),
+        "severity": DIAGNOSTIC_ERROR,
+        "source": "roc",
+        "message": "The compiler crashed while checking this file. \
+            The details are in the language server's log.",
+    })
+}
+
+/// The code in a rendered report's header, e.g. `E0001` in `── SYNTAX PROBLEM [E0001] ──`
+fn reported_code(report: &str) -> Option<&str> {
+    let header = report.lines().find(|line| line.starts_with("──"))?;
+    let (_, rest) = header.split_once(" [")?;
+    let (code, _) = rest.split_once("] ")?;
+
+    Some(code)
+}
+
+/// The (0-based) line a rendered report points at: the one it underlines or marks with `>`, or
+/// else the first one it quotes.
+fn reported_line(report: &str) -> Option<usize> {
+    let mut quoted = None;
+    let mut first_quoted = None;
+
+    for line in report.lines() {
+        if let Some((number, rest)) = line.trim_start().split_once('│') {
+            if let Ok(number) = number.parse::<usize>() {
+                let line = number.checked_sub(1)?;
+
+                if rest.starts_with('>') {
+                    return Some(line);
+                }
+
+                quoted = Some(line);
+                first_quoted = first_quoted.or(quoted);
+
+                continue;
+            }
+        }
+
+        let trimmed = line.trim();
+
+        if quoted.is_some() && !trimmed.is_empty() && trimmed.chars().all(|c| c == '^') {
+            return quoted;
+        }
+    }
+
+    first_quoted
+}
+
+impl Analysis {
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    /// Index the document's top-level definitions, as of this analysis's text
+    pub fn update_index(&mut self, index: &mut SymbolIndex) {
+        index.update(&mut self.loaded);
+    }
+
+    pub fn into_loaded(self) -> LoadedModule {
+        self.loaded
+    }
+
+    /// The type of the expression or pattern at the byte offset, and the docs of what it refers to
+    pub fn hover(&mut self, offset: u32) -> Option<Value> {
+        let hover = hover_at(&mut self.loaded, offset)?;
+        let mut contents = format!("```roc\n{}\n```", hover.typ);
+
+        if let Some(docs) = hover.docs {
+            contents.push_str("\n\n");
+            contents.push_str(docs.trim());
+        }
+
+        Some(json!({
+            "contents": { "kind": "markdown", "value": contents },
+            "range": LineIndex::new(&self.text).range(hover.region),
+        }))
+    }
+
+    /// Where the symbol at the byte offset is defined. Modules whose types came from the cache
+    /// have no declarations to look in, so this can't find definitions in those.
+    pub fn definition(&self, offset: u32) -> Option<Value> {
+        self.location(definition_at(&self.loaded, offset)?)
+    }
+
+    fn location(&self, location: Location) -> Option<Value> {
+        let (path, src) = self.loaded.sources.get(&location.module_id)?;
+
+        Some(json!({
+            "uri": path_to_uri(path),
+            "range": LineIndex::new(src).range(location.region),
+        }))
+    }
+
+    /// The semantic tokens of the whole document, encoded as the protocol wants them: five
+    /// numbers per token, with each token's position relative to the one before it.
+    pub fn semantic_tokens(&self) -> Value {
+        let index = LineIndex::new(&self.text);
+        let mut data = Vec::new();
+        let (mut previous_line, mut previous_start) = (0, 0);
+
+        for token in semantic_tokens(&self.loaded) {
+            let (line, start) = index.line_and_character(token.region.start().offset);
+            let (_, end) = index.line_and_character(token.region.end().offset);
+
+            let token_type = match token.kind {
+                TokenKind::Type => 0,
+                TokenKind::Ability => 1,
+                TokenKind::Tag => 2,
+                TokenKind::Module => 3,
+                TokenKind::Parameter => 4,
+                TokenKind::Variable => 5,
+                TokenKind::Function | TokenKind::EffectfulFunction => 6,
+            };
+
+            let mut modifiers = 0;
+
+            if token.definition {
+                modifiers |= 1;
+            }
+
+            if token.kind == TokenKind::EffectfulFunction {
+                modifiers |= 1 << 1;
+            }
+
+            let delta_start = if line == previous_line {
+                start - previous_start
+            } else {
+                start
+            };
+
+            data.extend([
+                line - previous_line,
+                delta_start,
+                end.saturating_sub(start),
+                token_type,
+                modifiers,
+            ]);
+
+            previous_line = line;
+            previous_start = start;
+        }
+
+        json!({ "data": data })
+    }
+
+    /// What could go at the byte offset: after `Module.`, the values that module exposes, and
+    /// otherwise the document's top-level values, the modules it can refer to, and keywords.
+    /// The editor narrows them down by what has been typed so far.
+    pub fn completion(&mut self, offset: u32) -> Value {
+        let typed = identifier_before(&self.text, offset);
+
+        let items = match typed.rsplit_once('.') {
+            Some((module_name, _)) => self.exposed_by(module_name),
+            None => self.in_scope(),
+        };
+
+        Value::Array(items)
+    }
+
+    fn exposed_by(&self, module_name: &str) -> Vec<Value> {
+        let interns = &self.loaded.interns;
+        let module_id = self
+            .loaded
+            .dep_idents
+            .keys()
+            .find(|module_id| interns.module_name(**module_id).as_str() == module_name);
+
+        let ident_ids = match module_id.and_then(|module_id| self.loaded.dep_idents.get(module_id))
+        {
+            Some(ident_ids) => ident_ids,
+            None => return Vec::new(),
+        };
+
+        ident_ids
+            .ident_strs()
+            .filter(|(_, name)| is_value_name(name))
+            .map(|(_, name)| json!({ "label": name, "kind": COMPLETION_VARIABLE }))
+            .collect()
+    }
+
+    fn in_scope(&mut self) -> Vec<Value> {
+        let home = self.loaded.module_id;
+        let mut items = Vec::new();
+
+        if let Some(declarations) = self.loaded.declarations_by_id.get(&home) {
+            let defs = declarations
+                .symbols
+                .iter()
+                .zip(declarations.variables.iter());
+
+            for (symbol, var) in defs {
+                // Destructures have a placeholder symbol from another module.
+                if symbol.value.module_id() != home {
+                    continue;
+                }
+
+                let name = symbol.value.as_str(&self.loaded.interns);
+
+                if !is_value_name(name) {
+                    continue;
+                }
+
+                let typ = name_and_print_var(
+                    *var,
+                    self.loaded.solved.inner_mut(),
+                    home,
+                    &self.loaded.interns,
+                    DebugPrint::NOTHING,
+                );
+                let kind = if typ.contains("->") {
+                    COMPLETION_FUNCTION
+                } else {
+                    COMPLETION_VARIABLE
+                };
+
+                items.push(json!({ "label": name, "kind": kind, "detail": typ }));
+            }
+        }
+
+        let modules = self.loaded.dep_idents.keys().copied();
+
+        for module_id in modules.filter(|module_id| *module_id != home) {
+            items.push(json!({
+                "label": self.loaded.interns.module_name(module_id).as_str(),
+                "kind": COMPLETION_MODULE,
+            }));
+        }
+
+        for keyword in KEYWORDS {
+            items.push(json!({ "label": keyword, "kind": COMPLETION_KEYWORD }));
+        }
+
+        items
+    }
+}
+
+/// The (possibly qualified) identifier that ends at the byte offset, e.g. `Str.con`
+fn identifier_before(text: &str, offset: u32) -> &str {
+    let end = (offset as usize).min(text.len());
+
+    if !text.is_char_boundary(end) {
+        return "";
+    }
+
+    let start = text[..end]
+        .char_indices()
+        .rev()
+        .take_while(|(_, c)| c.is_alphanumeric() || *c == '_' || *c == '.')
+        .last()
+        .map_or(end, |(index, _)| index);
+
+    &text[start..end]
+}
+
+/// Compiler-generated names don't start with a lowercase letter, and neither do types.
+fn is_value_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_lowercase())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use roc_module::symbol::{Interns, ModuleId};
+    use roc_region::all::Position;
+    use roc_reporting::report::RelatedSpan;
+
+    #[test]
+    fn identifier_before_the_cursor() {
+        let text = "x = Str.con";
+
+        assert_eq!(identifier_before(text, text.len() as u32), "Str.con");
+        assert_eq!(identifier_before(text, 1), "x");
+        assert_eq!(identifier_before(text, 2), "");
+    }
+
+    #[test]
+    fn line_a_report_points_at() {
+        let report = "── UNRECOGNIZED NAME ──── main.roc ──\n\n\
+            Nothing is named `foo` in this scope.\n\n\
+            3│  x = 1\n\
+            4│  y = foo\n\
+            \x20        ^^^\n";
+
+        assert_eq!(reported_line(report), Some(3));
+        assert_eq!(reported_line("5│> when x is\n6│>     _ -> 1\n"), Some(4));
+        assert_eq!(reported_line("No source quoted"), None);
+    }
+
+    #[test]
+    fn code_actions_for_fixes() {
+        let text = "x = 1\ny = xx\n";
+        let index = LineIndex::new(text);
+        let fix = Fix::replace(
+            "Rename to `x`".to_string(),
+            Region::new(Position::new(10), Position::new(12)),
+            "x".to_string(),
+        );
+        let diagnostic = json!({
+            "message": "Nothing is named `xx` in this scope.",
+            "data": { "fixes": [fix_to_json(&index, &fix)] },
+        });
+        let unfixable = json!({ "message": "This is wrong in some other way." });
+
+        let actions = code_actions("file:///main.roc", &[diagnostic.clone(), unfixable]);
+
+        assert_eq!(
+            actions,
+            json!([{
+                "title": "Rename to `x`",
+                "kind": "quickfix",
+                "diagnostics": [diagnostic],
+                "isPreferred": true,
+                "edit": {
+                    "changes": {
+                        "file:///main.roc": [{
+                            "range": {
+                                "start": { "line": 1, "character": 4 },
+                                "end": { "line": 1, "character": 6 },
+                            },
+                            "newText": "x",
+                        }],
+                    },
+                },
+            }])
+        );
+    }
+
+    #[test]
+    fn related_spans_as_related_information() {
+        let text = "x = 1\nx = 2\n";
+        let src_lines: Vec<&str> = text.split('\n').collect();
+        let lines = LineInfo::new(text);
+        let interns = Interns::default();
+        let alloc = RocDocAllocator::new(&src_lines, ModuleId::ATTR, &interns);
+        let first = Region::new(Position::new(0), Position::new(1));
+        let second = Region::new(Position::new(6), Position::new(7));
+
+        let report = Report {
+            title: "DUPLICATE NAME".to_string(),
+            code: Some("E2005"),
+            filename: "main.roc".into(),
+            doc: alloc.reflow("The x name is already used."),
+            severity: Severity::RuntimeError,
+            fixes: Vec::new(),
+            related: vec![RelatedSpan {
+                region: lines.convert_region(first),
+                message: "It was first defined here".to_string(),
+            }],
+        };
+
+        let index = LineIndex::new(text);
+        let diagnostic = diagnostic(
+            "file:///main.roc",
+            &index,
+            &lines,
+            Some(second),
+            report,
+            &alloc,
+        );
+
+        // The related spans aren't quoted in the message, too.
+        assert_eq!(diagnostic["message"], "The x name is already used.");
+        assert_eq!(
+            diagnostic["relatedInformation"],
+            json!([{
+                "location": {
+                    "uri": "file:///main.roc",
+                    "range": {
+                        "start": { "line": 0, "character": 0 },
+                        "end": { "line": 0, "character": 1 },
+                    },
+                },
+                "message": "It was first defined here",
+            }])
+        );
+    }
+
+    #[test]
+    fn code_in_a_report_header() {
+        let report = "── SYNTAX PROBLEM [E0001] ──── main.roc ──\n\n\
+            Using != and == together requires parentheses.\n";
+
+        assert_eq!(reported_code(report), Some("E0001"));
+        assert_eq!(reported_code("── FILE PROBLEM ──── main.roc ──\n"), None);
+    }
+}
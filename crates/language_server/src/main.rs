@@ -0,0 +1,17 @@
+//! `roc_ls`, a language server for Roc.
+//!
+//! Editors start it and talk to it over stdin and stdout with the
+//! [Language Server Protocol](https://microsoft.github.io/language-server-protocol/). It shows
+//! the problems `roc check` would report as diagnostics, the types of expressions on hover, and
+//! can go to definitions, complete names, find definitions anywhere in the workspace, and tell
+//! the editor what each name is (a type, a parameter, an effectful function, ...) for semantic
+//! highlighting. It answers from the compiler's own loading and type checking, in the same
+//! process, rather than running `roc check` for every change.
+mod analysis;
+mod protocol;
+mod server;
+mod workspace;
+
+fn main() -> std::io::Result<()> {
+    server::run()
+}
@@ -0,0 +1,268 @@
+//! The wire format of the Language Server Protocol: JSON-RPC messages, each after a
+//! `Content-Length` header, which refer to documents by URI and to places in them by line and
+//! UTF-16 column.
+use std::io::{self, BufRead, Write};
+use std::path::{Path, PathBuf};
+
+use roc_region::all::Region;
+use serde_json::{json, Value};
+
+/// The JSON-RPC error for a request that can't be answered now, e.g. after `shutdown`
+pub const INVALID_REQUEST: i64 = -32600;
+
+/// The JSON-RPC error for a request we don't implement
+pub const METHOD_NOT_FOUND: i64 = -32601;
+
+/// The JSON-RPC error for a request whose params we can't make sense of
+pub const INVALID_PARAMS: i64 = -32602;
+
+/// Read the next message, or `None` once the editor has closed our stdin.
+pub fn read_message(reader: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut content_length = None;
+
+    loop {
+        let mut line = String::new();
+
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+
+        let line = line.trim_end();
+
+        if line.is_empty() {
+            break;
+        }
+
+        if let Some((name, value)) = line.split_once(':') {
+            if name.eq_ignore_ascii_case("Content-Length") {
+                content_length = value.trim().parse::<usize>().ok();
+            }
+        }
+    }
+
+    let length = content_length.ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            "a message had no Content-Length header",
+        )
+    })?;
+
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body)?;
+
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
+
+pub fn write_message(writer: &mut impl Write, message: &Value) -> io::Result<()> {
+    let body = message.to_string();
+
+    write!(writer, "Content-Length: {}\r\n\r\n{}", body.len(), body)?;
+    writer.flush()
+}
+
+pub fn response(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+pub fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+pub fn notification(method: &str, params: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "method": method, "params": params })
+}
+
+/// The path of a `file://` URI, which is the only kind of document we check
+pub fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    let path = uri.strip_prefix("file://")?;
+    let mut bytes = Vec::with_capacity(path.len());
+    let mut rest = path.as_bytes();
+
+    while let Some((&byte, after)) = rest.split_first() {
+        match (byte, after) {
+            (b'%', [high, low, after @ ..]) => {
+                let hex = std::str::from_utf8(&[*high, *low]).ok()?.to_string();
+                bytes.push(u8::from_str_radix(&hex, 16).ok()?);
+                rest = after;
+            }
+            _ => {
+                bytes.push(byte);
+                rest = after;
+            }
+        }
+    }
+
+    String::from_utf8(bytes).ok().map(PathBuf::from)
+}
+
+pub fn path_to_uri(path: &Path) -> String {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut uri = String::from("file://");
+
+    for byte in path.to_string_lossy().bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'/' | b'-' | b'_' | b'.' | b'~' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+
+    uri
+}
+
+/// Converts between the byte offsets that regions use and the positions the protocol uses,
+/// whose columns count UTF-16 code units.
+pub struct LineIndex<'a> {
+    text: &'a str,
+    /// The byte offset each line starts at
+    line_starts: Vec<usize>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(text: &'a str) -> Self {
+        let line_starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+
+        Self { text, line_starts }
+    }
+
+    pub fn position(&self, offset: u32) -> Value {
+        let (line, character) = self.line_and_character(offset);
+
+        json!({ "line": line, "character": character })
+    }
+
+    /// The (0-based) line and UTF-16 column of a byte offset
+    pub fn line_and_character(&self, offset: u32) -> (usize, usize) {
+        let mut offset = (offset as usize).min(self.text.len());
+
+        while !self.text.is_char_boundary(offset) {
+            offset -= 1;
+        }
+
+        let line = self.line_starts.partition_point(|start| *start <= offset) - 1;
+        let character = self.text[self.line_starts[line]..offset]
+            .encode_utf16()
+            .count();
+
+        (line, character)
+    }
+
+    pub fn range(&self, region: Region) -> Value {
+        json!({
+            "start": self.position(region.start().offset),
+            "end": self.position(region.end().offset),
+        })
+    }
+
+    /// The whole of the given (0-based) line
+    pub fn line_range(&self, line: usize) -> Value {
+        let line = line.min(self.line_starts.len() - 1);
+        let end = self.text[self.line_starts[line]..]
+            .split('\n')
+            .next()
+            .unwrap_or_default()
+            .encode_utf16()
+            .count();
+
+        json!({
+            "start": { "line": line, "character": 0 },
+            "end": { "line": line, "character": end },
+        })
+    }
+
+    /// The byte offset of a position, which is clamped to the end of its line
+    pub fn offset(&self, position: &Value) -> Option<u32> {
+        let line = position.get("line")?.as_u64()? as usize;
+        let character = position.get("character")?.as_u64()? as usize;
+
+        let start = match self.line_starts.get(line) {
+            Some(start) => *start,
+            None => return Some(self.text.len() as u32),
+        };
+
+        let mut offset = start;
+        let mut units = 0;
+
+        for c in self.text[start..].chars() {
+            if c == '\n' || units >= character {
+                break;
+            }
+
+            units += c.len_utf16();
+            offset += c.len_utf8();
+        }
+
+        Some(offset as u32)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use roc_region::all::Position;
+
+    #[test]
+    fn positions_count_utf16_code_units() {
+        let text = "x = \"é😀\"\ny = x\n";
+        let index = LineIndex::new(text);
+
+        let after_emoji = text.find('😀').unwrap() + '😀'.len_utf8();
+        let position = json!({ "line": 0, "character": 8 });
+
+        assert_eq!(index.position(after_emoji as u32), position);
+        assert_eq!(index.offset(&position), Some(after_emoji as u32));
+
+        let y = text.find('y').unwrap() as u32;
+        let region = Region::new(Position::new(y), Position::new(y + 1));
+
+        assert_eq!(
+            index.range(region),
+            json!({
+                "start": { "line": 1, "character": 0 },
+                "end": { "line": 1, "character": 1 },
+            })
+        );
+    }
+
+    #[test]
+    fn offsets_clamp_to_the_end_of_the_line() {
+        let index = LineIndex::new("ab\ncd");
+
+        assert_eq!(
+            index.offset(&json!({ "line": 0, "character": 10 })),
+            Some(2)
+        );
+        assert_eq!(index.offset(&json!({ "line": 5, "character": 0 })), Some(5));
+    }
+
+    #[test]
+    fn messages_round_trip() {
+        let message = notification("initialized", json!({}));
+        let mut buf = Vec::new();
+
+        write_message(&mut buf, &message).unwrap();
+
+        let read = read_message(&mut buf.as_slice()).unwrap();
+
+        assert_eq!(read, Some(message));
+        assert_eq!(read_message(&mut [].as_slice()).unwrap(), None);
+    }
+
+    #[test]
+    fn file_uris() {
+        assert_eq!(
+            uri_to_path("file:///home/me/My%20App/main.roc"),
+            Some(PathBuf::from("/home/me/My App/main.roc"))
+        );
+        assert_eq!(uri_to_path("untitled:Untitled-1"), None);
+    }
+}
@@ -0,0 +1,399 @@
+//! The server's state and main loop.
+//!
+//! Editors send a change for every keystroke, but checking a document takes longer than typing
+//! a character, so we don't check on every change. A document is checked once the editor has
+//! been quiet for [DEBOUNCE], or sooner if a request needs it. Until then, requests are answered
+//! with the document's last analysis that got as far as type checking, so e.g. hover still works
+//! while the document has a syntax error in it.
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::Duration;
+
+use roc_load::symbol_index::{index_file, SymbolIndex};
+use roc_packaging::cache;
+use serde_json::{json, Value};
+
+use crate::analysis::{
+    analyze, code_actions, crash_diagnostic, Analysis, QUICK_FIX, TOKEN_MODIFIERS, TOKEN_TYPES,
+};
+use crate::protocol::{
+    error_response, notification, read_message, response, uri_to_path, write_message, LineIndex,
+    INVALID_PARAMS, INVALID_REQUEST, METHOD_NOT_FOUND,
+};
+use crate::workspace::{load_from_disk, roc_files, symbol_information, MAX_WORKSPACE_SYMBOLS};
+
+/// How long the editor has to be quiet before we check the documents it changed
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// The protocol's number for sending the whole text of a document on every change
+const TEXT_DOCUMENT_SYNC_FULL: u8 = 1;
+
+struct Document {
+    path: PathBuf,
+    text: String,
+    /// Whether the text changed since the document was last checked
+    stale: bool,
+    analysis: Option<Analysis>,
+}
+
+struct Server<W> {
+    out: W,
+    /// By URI
+    documents: HashMap<String, Document>,
+    /// The workspace folder the editor opened, if any
+    root: Option<PathBuf>,
+    /// The top-level definitions of the workspace's modules and the open documents
+    symbols: SymbolIndex,
+    shutting_down: bool,
+}
+
+/// Answer the editor on stdin and stdout until it tells us to exit.
+pub fn run() -> io::Result<()> {
+    let (sender, receiver) = mpsc::channel();
+
+    // Read on a thread of its own, so we can tell when the editor has been quiet.
+    std::thread::spawn(move || {
+        let stdin = io::stdin();
+        let mut stdin = stdin.lock();
+
+        while let Ok(Some(message)) = read_message(&mut stdin) {
+            if sender.send(message).is_err() {
+                break;
+            }
+        }
+    });
+
+    let stdout = io::stdout();
+    let mut server = Server {
+        out: stdout.lock(),
+        documents: HashMap::new(),
+        root: None,
+        symbols: SymbolIndex::new(),
+        shutting_down: false,
+    };
+
+    server.serve(receiver)
+}
+
+impl<W: Write> Server<W> {
+    fn serve(&mut self, receiver: Receiver<Value>) -> io::Result<()> {
+        loop {
+            let next = if self.documents.values().any(|document| document.stale) {
+                receiver.recv_timeout(DEBOUNCE)
+            } else {
+                receiver.recv().map_err(|_| RecvTimeoutError::Disconnected)
+            };
+
+            let message = match next {
+                Ok(message) => message,
+                Err(RecvTimeoutError::Timeout) => {
+                    self.check_stale()?;
+
+                    continue;
+                }
+                // The editor closed our stdin without telling us to exit.
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            };
+
+            let method = message.get("method").and_then(Value::as_str).unwrap_or("");
+
+            if method == "exit" {
+                return Ok(());
+            }
+
+            let params = message.get("params").cloned().unwrap_or(Value::Null);
+
+            match message.get("id").cloned() {
+                Some(id) => self.request(id, method, params)?,
+                None => self.notification(method, params)?,
+            }
+        }
+    }
+
+    fn request(&mut self, id: Value, method: &str, params: Value) -> io::Result<()> {
+        if self.shutting_down {
+            let message = error_response(id, INVALID_REQUEST, "roc_ls is shutting down");
+
+            return write_message(&mut self.out, &message);
+        }
+
+        // Requests about a document are answered with its latest text.
+        if let Some(uri) = params["textDocument"]["uri"].as_str() {
+            self.check(uri)?;
+        }
+
+        let result = match method {
+            "initialize" => {
+                self.initialize(&params);
+
+                Ok(json!({
+                    "capabilities": {
+                        "textDocumentSync": TEXT_DOCUMENT_SYNC_FULL,
+                        "hoverProvider": true,
+                        "definitionProvider": true,
+                        "completionProvider": { "triggerCharacters": ["."] },
+                        "semanticTokensProvider": {
+                            "legend": {
+                                "tokenTypes": TOKEN_TYPES,
+                                "tokenModifiers": TOKEN_MODIFIERS,
+                            },
+                            "full": true,
+                        },
+                        "workspaceSymbolProvider": true,
+                        "codeActionProvider": { "codeActionKinds": [QUICK_FIX] },
+                    },
+                    "serverInfo": { "name": "roc_ls", "version": env!("CARGO_PKG_VERSION") },
+                }))
+            }
+            "shutdown" => {
+                self.shutting_down = true;
+                self.save_index();
+
+                Ok(Value::Null)
+            }
+            "textDocument/hover" => {
+                self.at_position(&params, |analysis, offset| analysis.hover(offset))
+            }
+            "textDocument/definition" => {
+                self.at_position(&params, |analysis, offset| analysis.definition(offset))
+            }
+            "textDocument/completion" => self.at_position(&params, |analysis, offset| {
+                Some(analysis.completion(offset))
+            }),
+            "textDocument/semanticTokens/full" => Ok(self.semantic_tokens(&params)),
+            "textDocument/codeAction" => {
+                let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+                let diagnostics = params["context"]["diagnostics"].as_array();
+
+                Ok(code_actions(
+                    uri,
+                    diagnostics.map_or(&[][..], Vec::as_slice),
+                ))
+            }
+            "workspace/symbol" => Ok(self.workspace_symbols(&params)),
+            _ => Err((
+                METHOD_NOT_FOUND,
+                format!("roc_ls does not implement {}", method),
+            )),
+        };
+
+        let message = match result {
+            Ok(result) => response(id, result),
+            Err((code, message)) => error_response(id, code, &message),
+        };
+
+        write_message(&mut self.out, &message)
+    }
+
+    fn notification(&mut self, method: &str, params: Value) -> io::Result<()> {
+        let uri = params["textDocument"]["uri"]
+            .as_str()
+            .unwrap_or("")
+            .to_string();
+
+        match method {
+            "textDocument/didOpen" => {
+                let path = match uri_to_path(&uri) {
+                    Some(path) => path,
+                    None => return Ok(()),
+                };
+                let text = params["textDocument"]["text"].as_str().unwrap_or("");
+
+                self.documents.insert(
+                    uri,
+                    Document {
+                        path,
+                        text: text.to_string(),
+                        stale: true,
+                        analysis: None,
+                    },
+                );
+            }
+            "textDocument/didChange" => {
+                let changes = params["contentChanges"].as_array();
+
+                // We ask for the whole text on every change, so only the last one matters.
+                let text = changes
+                    .and_then(|changes| changes.last())
+                    .and_then(|change| change["text"].as_str());
+
+                if let (Some(document), Some(text)) = (self.documents.get_mut(&uri), text) {
+                    document.text = text.to_string();
+                    document.stale = true;
+                }
+            }
+            "textDocument/didSave" => {
+                // Other documents read this one from disk if they import it.
+                for (other_uri, document) in self.documents.iter_mut() {
+                    if *other_uri != uri {
+                        document.stale = true;
+                    }
+                }
+            }
+            "textDocument/didClose" => {
+                if self.documents.remove(&uri).is_some() {
+                    self.publish_diagnostics(&uri, Vec::new())?;
+                }
+            }
+            // e.g. `initialized`, and `$/cancelRequest`: we answer requests in order, so by the
+            // time we read a cancellation, the request has been answered.
+            _ => {}
+        }
+
+        Ok(())
+    }
+
+    /// Pick up the workspace's index from where the last session left it.
+    fn initialize(&mut self, params: &Value) {
+        self.root = params["rootUri"].as_str().and_then(uri_to_path);
+
+        if let Some(root) = &self.root {
+            self.symbols = SymbolIndex::load(&index_file(&cache::roc_cache_dir(), root));
+        }
+    }
+
+    /// The definitions matching the query, after indexing the modules that changed since the
+    /// last search. The first search in a workspace indexes all of it, so it can take a while.
+    fn workspace_symbols(&mut self, params: &Value) -> Value {
+        let query = params["query"].as_str().unwrap_or("");
+        let documents = &self.documents;
+        let open_document = |path: &Path| documents.values().find(|document| document.path == path);
+
+        let mut paths = self.root.as_deref().map(roc_files).unwrap_or_default();
+
+        for document in documents.values() {
+            if !paths.contains(&document.path) {
+                paths.push(document.path.clone());
+            }
+        }
+
+        // Open documents are indexed with their text in the editor when they're checked, which
+        // may not be what's on disk.
+        self.symbols
+            .refresh(&paths, |path| match open_document(path) {
+                Some(_) => None,
+                None => load_from_disk(path),
+            });
+
+        let matches = self.symbols.search(query);
+
+        let information =
+            symbol_information(matches.into_iter().take(MAX_WORKSPACE_SYMBOLS), |path| {
+                match open_document(path).and_then(|document| document.analysis.as_ref()) {
+                    Some(analysis) => Some(analysis.text().to_string()),
+                    None => std::fs::read_to_string(path).ok(),
+                }
+            });
+
+        self.save_index();
+
+        information
+    }
+
+    fn save_index(&self) {
+        if let Some(root) = &self.root {
+            // The index is only a cache, so if it can't be saved, the next session rebuilds it.
+            let _ = self
+                .symbols
+                .save(&index_file(&cache::roc_cache_dir(), root));
+        }
+    }
+
+    /// Answer a request about a position in a document, with its latest analysis
+    fn at_position(
+        &mut self,
+        params: &Value,
+        answer: impl FnOnce(&mut Analysis, u32) -> Option<Value>,
+    ) -> Result<Value, (i64, String)> {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+
+        if !self.documents.contains_key(uri) {
+            return Err((INVALID_PARAMS, format!("{} is not open", uri)));
+        }
+
+        let analysis = match self
+            .documents
+            .get_mut(uri)
+            .and_then(|document| document.analysis.as_mut())
+        {
+            Some(analysis) => analysis,
+            None => return Ok(Value::Null),
+        };
+
+        let offset = LineIndex::new(analysis.text()).offset(&params["position"]);
+
+        Ok(offset
+            .and_then(|offset| answer(analysis, offset))
+            .unwrap_or(Value::Null))
+    }
+
+    /// Tokens only make sense for the text they came from, so a document whose latest text
+    /// didn't type-check has none. The editor keeps showing the ones it had.
+    fn semantic_tokens(&self, params: &Value) -> Value {
+        let uri = params["textDocument"]["uri"].as_str().unwrap_or("");
+
+        match self.documents.get(uri) {
+            Some(Document {
+                text,
+                analysis: Some(analysis),
+                ..
+            }) if analysis.text() == text => analysis.semantic_tokens(),
+            _ => Value::Null,
+        }
+    }
+
+    fn check_stale(&mut self) -> io::Result<()> {
+        let stale: Vec<String> = self
+            .documents
+            .iter()
+            .filter(|(_, document)| document.stale)
+            .map(|(uri, _)| uri.clone())
+            .collect();
+
+        for uri in stale {
+            self.check(&uri)?;
+        }
+
+        Ok(())
+    }
+
+    /// Check the document if it changed since it was last checked, and publish its diagnostics.
+    fn check(&mut self, uri: &str) -> io::Result<()> {
+        let document = match self.documents.get_mut(uri) {
+            Some(document) if document.stale => document,
+            _ => return Ok(()),
+        };
+
+        document.stale = false;
+
+        // The compiler panics on problems it doesn't have a report for yet, and that shouldn't
+        // take the editor's language server down with it.
+        let checked = catch_unwind(AssertUnwindSafe(|| analyze(&document.path, &document.text)));
+
+        let diagnostics = match checked {
+            Ok((diagnostics, analysis)) => {
+                if let Some(mut analysis) = analysis {
+                    analysis.update_index(&mut self.symbols);
+                    document.analysis = Some(analysis);
+                }
+
+                diagnostics
+            }
+            Err(_) => vec![crash_diagnostic(&document.text)],
+        };
+
+        self.publish_diagnostics(uri, diagnostics)
+    }
+
+    fn publish_diagnostics(&mut self, uri: &str, diagnostics: Vec<Value>) -> io::Result<()> {
+        let params = json!({ "uri": uri, "diagnostics": diagnostics });
+
+        write_message(
+            &mut self.out,
+            &notification("textDocument/publishDiagnostics", params),
+        )
+    }
+}
@@ -0,0 +1,111 @@
+//! Finding definitions anywhere in the workspace, for the editor's "go to symbol in workspace".
+//!
+//! The index of the workspace's definitions is saved in the Roc cache directory, so only the
+//! modules that changed since the last session are loaded again. Open documents keep their own
+//! entries up to date as they're checked, with their text in the editor rather than on disk.
+use std::collections::HashMap;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+
+use roc_load::symbol_index::{IndexedSymbol, SymbolKind};
+use roc_load::LoadedModule;
+use serde_json::{json, Value};
+
+use crate::analysis::analyze;
+use crate::protocol::{path_to_uri, LineIndex};
+
+/// Editors show a list of matches, and nobody scrolls past the first hundred.
+pub const MAX_WORKSPACE_SYMBOLS: usize = 100;
+
+// The protocol's numbers for the kinds of symbols
+const SYMBOL_METHOD: u8 = 6;
+const SYMBOL_INTERFACE: u8 = 11;
+const SYMBOL_FUNCTION: u8 = 12;
+const SYMBOL_CONSTANT: u8 = 14;
+const SYMBOL_STRUCT: u8 = 23;
+
+/// Every .roc file under the workspace root, except in hidden directories like `.git`
+pub fn roc_files(root: &Path) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    let mut dirs = vec![root.to_path_buf()];
+
+    while let Some(dir) = dirs.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+
+        for entry in entries.flatten() {
+            let path = entry.path();
+            let hidden = entry.file_name().to_string_lossy().starts_with('.');
+
+            match entry.file_type() {
+                Ok(file_type) if file_type.is_dir() && !hidden => dirs.push(path),
+                Ok(file_type)
+                    if file_type.is_file()
+                        && path.extension().map_or(false, |ext| ext == "roc") =>
+                {
+                    files.push(path)
+                }
+                _ => {}
+            }
+        }
+    }
+
+    files.sort();
+
+    files
+}
+
+/// Load a module from disk to index it, or `None` if it doesn't get as far as type checking
+pub fn load_from_disk(path: &Path) -> Option<LoadedModule> {
+    let text = std::fs::read_to_string(path).ok()?;
+
+    // The compiler panics on some problems, and one broken module shouldn't stop the rest of the
+    // workspace from being indexed.
+    catch_unwind(AssertUnwindSafe(|| analyze(path, &text)))
+        .ok()?
+        .1
+        .map(|analysis| analysis.into_loaded())
+}
+
+/// The matches as `SymbolInformation`s. Their regions refer to the text they were indexed from,
+/// which `text_of` gives for each file.
+pub fn symbol_information<'a>(
+    symbols: impl Iterator<Item = &'a IndexedSymbol>,
+    text_of: impl Fn(&Path) -> Option<String>,
+) -> Value {
+    let mut texts: HashMap<&Path, Option<String>> = HashMap::new();
+    let mut information = Vec::new();
+
+    for symbol in symbols {
+        let text = texts
+            .entry(symbol.path.as_path())
+            .or_insert_with(|| text_of(&symbol.path));
+
+        let text = match text {
+            Some(text) => text,
+            None => continue,
+        };
+
+        let kind = match symbol.kind {
+            SymbolKind::Function => SYMBOL_FUNCTION,
+            SymbolKind::Value => SYMBOL_CONSTANT,
+            SymbolKind::Alias | SymbolKind::Opaque => SYMBOL_STRUCT,
+            SymbolKind::Ability => SYMBOL_INTERFACE,
+            SymbolKind::AbilityMember => SYMBOL_METHOD,
+        };
+
+        information.push(json!({
+            "name": symbol.name,
+            "kind": kind,
+            "location": {
+                "uri": path_to_uri(&symbol.path),
+                "range": LineIndex::new(text).range(symbol.region),
+            },
+            "containerName": symbol.module,
+        }));
+    }
+
+    Value::Array(information)
+}
@@ -4,22 +4,25 @@ use object::{elf, endian};
 use object::{
     CompressedFileRange, CompressionFormat, LittleEndian as LE, NativeEndian, Object,
     ObjectSection, ObjectSymbol, RelocationKind, RelocationTarget, Section, SectionIndex,
-    SectionKind, Symbol, SymbolIndex, SymbolSection,
+    SectionKind, Symbol, SymbolIndex, SymbolKind, SymbolSection,
 };
 use roc_collections::all::MutMap;
 use roc_error_macros::{internal_error, user_error};
+use std::borrow::Cow;
 use std::convert::TryFrom;
 use std::ffi::CStr;
 use std::mem;
 use std::os::raw::c_char;
 use std::path::Path;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 
 use crate::metadata::{self, Metadata, VirtualOffset};
 
 use crate::{
     align_by_constraint, align_to_offset_by_constraint, load_struct_inplace,
-    load_struct_inplace_mut, load_structs_inplace_mut, open_mmap, open_mmap_mut,
+    load_struct_inplace_mut, load_structs_inplace, load_structs_inplace_mut, open_mmap,
+    open_mmap_mut, report_missing_symbol, MissingSymbol,
 };
 
 const MIN_SECTION_ALIGNMENT: usize = 0x40;
@@ -27,6 +30,10 @@ const MIN_SECTION_ALIGNMENT: usize = 0x40;
 // TODO: Analyze if this offset is always correct.
 const PLT_ADDRESS_OFFSET: u64 = 0x10;
 
+/// Text sections are scanned in chunks of about this many bytes, spread over all the cores,
+/// so that a host with one big text section (which is most of them) is scanned in parallel too.
+const TEXT_SCAN_CHUNK_SIZE: u64 = 256 * 1024;
+
 struct ElfDynamicDeps {
     got_app_syms: Vec<(String, usize)>,
     got_sections: Vec<(usize, usize)>,
@@ -100,14 +107,70 @@ fn collect_roc_definitions<'a>(object: &object::File<'a, &'a [u8]>) -> MutMap<St
     vaddresses
 }
 
+/// The instruction set specific details of ELF surgery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ElfArch {
+    X86_64,
+    Aarch64,
+}
+
+impl ElfArch {
+    fn from_file_header(exec_data: &[u8]) -> Self {
+        let exec_header = load_struct_inplace::<elf::FileHeader64<LE>>(exec_data, 0);
+
+        match exec_header.e_machine.get(NativeEndian) {
+            elf::EM_X86_64 => ElfArch::X86_64,
+            elf::EM_AARCH64 => ElfArch::Aarch64,
+            other => {
+                user_error!("The surgical linker does not support ELF hosts for machine type {other}. Please use `--linker=legacy`.");
+            }
+        }
+    }
+
+    /// The size of the first PLT entry, which calls into the dynamic linker.
+    /// It is followed by one entry of `PLT_ADDRESS_OFFSET` bytes per imported function.
+    fn plt_header_size(self) -> u64 {
+        match self {
+            ElfArch::X86_64 => 0x10,
+            ElfArch::Aarch64 => 0x20,
+        }
+    }
+
+    fn jump_slot_relocation(self) -> u32 {
+        match self {
+            ElfArch::X86_64 => elf::R_X86_64_JUMP_SLOT,
+            ElfArch::Aarch64 => elf::R_AARCH64_JUMP_SLOT,
+        }
+    }
+
+    fn glob_dat_relocation(self) -> u32 {
+        match self {
+            ElfArch::X86_64 => elf::R_X86_64_GLOB_DAT,
+            ElfArch::Aarch64 => elf::R_AARCH64_GLOB_DAT,
+        }
+    }
+
+    fn relative_relocation(self) -> u32 {
+        match self {
+            ElfArch::X86_64 => elf::R_X86_64_RELATIVE,
+            ElfArch::Aarch64 => elf::R_AARCH64_RELATIVE,
+        }
+    }
+}
+
 struct Surgeries<'a> {
     surgeries: MutMap<String, Vec<metadata::SurgeryEntry>>,
     app_func_addresses: MutMap<u64, &'a str>,
+    arch: ElfArch,
     indirect_warning_given: bool,
 }
 
 impl<'a> Surgeries<'a> {
-    fn new(application_symbols: &[Symbol], app_func_addresses: MutMap<u64, &'a str>) -> Self {
+    fn new(
+        application_symbols: &[Symbol],
+        app_func_addresses: MutMap<u64, &'a str>,
+        arch: ElfArch,
+    ) -> Self {
         let mut surgeries = MutMap::default();
 
         // for each symbol that the host expects from the application
@@ -120,6 +183,7 @@ impl<'a> Surgeries<'a> {
         Self {
             surgeries,
             app_func_addresses,
+            arch,
             indirect_warning_given: false,
         }
     }
@@ -150,13 +214,80 @@ impl<'a> Surgeries<'a> {
             println!("Analyzing instuctions for branches");
         }
 
-        for text_section in text_sections {
-            self.append_text_section(object_bytes, &text_section, verbose)
+        let text_sections: Vec<TextSection> = text_sections.iter().map(TextSection::new).collect();
+        let function_starts = function_starts(object);
+        let scans = scan_text_sections(
+            &text_sections,
+            &function_starts,
+            &self.app_func_addresses,
+            self.arch,
+            TEXT_SCAN_CHUNK_SIZE,
+            scan_threads(),
+        );
+
+        for scan in scans {
+            for branch in scan.branches {
+                let entry = branch.entry;
+
+                if verbose {
+                    println!(
+                        "Found branch from {:+x} to {:+x}({})",
+                        branch.ip, branch.target, branch.func_name
+                    );
+                    println!(
+                        "\tNeed to surgically replace {} bytes at file offset {:+x}",
+                        entry.size, entry.file_offset,
+                    );
+                    println!(
+                        "\tIts current value is {:+x?}",
+                        &object_bytes[entry.file_offset as usize..][..entry.size as usize]
+                    )
+                }
+
+                self.surgeries
+                    .get_mut(branch.func_name)
+                    .unwrap()
+                    .push(entry);
+            }
+
+            if scan.has_indirect_branch && !self.indirect_warning_given && verbose {
+                self.indirect_warning_given = true;
+                println!();
+                println!("Cannot analyze through indirect jmp type instructions");
+                println!(
+                    "Most likely this is not a problem, but it could mean a loss in optimizations"
+                );
+                println!();
+            }
         }
     }
+}
+
+struct BranchSurgery<'a> {
+    func_name: &'a str,
+    ip: u64,
+    target: u64,
+    entry: metadata::SurgeryEntry,
+}
 
-    fn append_text_section(&mut self, object_bytes: &[u8], sec: &Section, verbose: bool) {
-        let (file_offset, compressed) = match sec.compressed_file_range() {
+/// What was found in a chunk of a text section
+#[derive(Default)]
+struct TextSectionScan<'a> {
+    branches: Vec<BranchSurgery<'a>>,
+    has_indirect_branch: bool,
+}
+
+/// A text section's contents, loaded once so that each chunk of it can be scanned on its own
+struct TextSection<'s, 'd, 'f> {
+    section: &'s Section<'d, 'f>,
+    file_offset: u64,
+    compressed: bool,
+    data: Cow<'d, [u8]>,
+}
+
+impl<'s, 'd, 'f> TextSection<'s, 'd, 'f> {
+    fn new(section: &'s Section<'d, 'f>) -> Self {
+        let (file_offset, compressed) = match section.compressed_file_range() {
             Ok(CompressedFileRange {
                 format: CompressionFormat::None,
                 offset,
@@ -166,109 +297,286 @@ impl<'a> Surgeries<'a> {
             Err(err) => {
                 internal_error!(
                     "Issues dealing with section compression for {:+x?}: {}",
-                    sec,
+                    section,
                     err
                 );
             }
         };
 
-        let data = match sec.uncompressed_data() {
+        let data = match section.uncompressed_data() {
             Ok(data) => data,
             Err(err) => {
-                internal_error!("Failed to load text section, {:+x?}: {}", sec, err);
+                internal_error!("Failed to load text section, {:+x?}: {}", section, err);
             }
         };
-        let mut decoder = Decoder::with_ip(64, &data, sec.address(), DecoderOptions::NONE);
-        let mut inst = Instruction::default();
-
-        while decoder.can_decode() {
-            decoder.decode_out(&mut inst);
-
-            // Note: This gets really complex fast if we want to support more than basic calls/jumps.
-            // A lot of them have to load addresses into registers/memory so we would have to discover that value.
-            // Would probably require some static code analysis and would be impossible in some cases.
-            // As an alternative we can leave in the calls to the plt, but change the plt to jmp to the static function.
-            // That way any indirect call will just have the overhead of an extra jump.
-            match inst.try_op_kind(0) {
-                // Relative Offsets.
-                Ok(OpKind::NearBranch16 | OpKind::NearBranch32 | OpKind::NearBranch64) => {
-                    let target = inst.near_branch_target();
-                    if let Some(func_name) = self.app_func_addresses.get(&target) {
-                        if compressed {
-                            internal_error!("Surgical linking does not work with compressed text sections: {:+x?}", sec);
-                        }
 
-                        if verbose {
-                            println!(
-                                "Found branch from {:+x} to {:+x}({})",
-                                inst.ip(),
-                                target,
-                                func_name
-                            );
-                        }
+        Self {
+            section,
+            file_offset,
+            compressed,
+            data,
+        }
+    }
+}
 
-                        // TODO: Double check these offsets are always correct.
-                        // We may need to do a custom offset based on opcode instead.
-                        let op_kind = inst.op_code().try_op_kind(0).unwrap();
-                        let op_size: u8 = match op_kind {
-                            OpCodeOperandKind::br16_1 | OpCodeOperandKind::br32_1 => 1,
-                            OpCodeOperandKind::br16_2 => 2,
-                            OpCodeOperandKind::br32_4 | OpCodeOperandKind::br64_4 => 4,
-                            _ => {
-                                internal_error!(
-                                    "Ran into an unknown operand kind when analyzing branches: {:?}",
-                                    op_kind
-                                );
+/// A part of a text section, from `start` to `end` bytes into it, which begins and ends on
+/// instruction boundaries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TextChunk {
+    section: usize,
+    start: u64,
+    end: u64,
+}
+
+/// The addresses of the functions the host defines, sorted
+fn function_starts(object: &object::File) -> Vec<u64> {
+    let mut starts: Vec<u64> = object
+        .symbols()
+        .chain(object.dynamic_symbols())
+        .filter(|sym| sym.kind() == SymbolKind::Text && sym.is_definition())
+        .map(|sym| sym.address())
+        .collect();
+
+    starts.sort_unstable();
+    starts.dedup();
+
+    starts
+}
+
+/// Split the text sections into chunks of about `chunk_size` bytes.
+///
+/// Aarch64 instructions are all 4 bytes, so its sections can be split anywhere that's 4-byte
+/// aligned. x86 instructions vary in length, so decoding from the middle of one would read
+/// garbage; those sections are only split where a function starts. A host without symbols
+/// has no known function starts, so each of its sections is a single chunk.
+fn text_chunks(
+    sections: &[TextSection],
+    function_starts: &[u64],
+    arch: ElfArch,
+    chunk_size: u64,
+) -> Vec<TextChunk> {
+    let mut chunks = Vec::new();
+
+    for (index, text) in sections.iter().enumerate() {
+        let address = text.section.address();
+        let len = text.data.len() as u64;
+        let mut start = 0;
+
+        while start < len {
+            let wanted = start.saturating_add(chunk_size);
+            let end = if wanted >= len {
+                len
+            } else {
+                match arch {
+                    ElfArch::Aarch64 => ((wanted + 3) & !3).min(len),
+                    ElfArch::X86_64 => {
+                        let next = function_starts.partition_point(|&a| a < address + wanted);
+
+                        match function_starts.get(next) {
+                            Some(&function_start) if function_start < address + len => {
+                                function_start - address
                             }
-                        };
-                        let offset = inst.next_ip() - op_size as u64 - sec.address() + file_offset;
-                        if verbose {
-                            println!(
-                                "\tNeed to surgically replace {} bytes at file offset {:+x}",
-                                op_size, offset,
-                            );
-                            println!(
-                                "\tIts current value is {:+x?}",
-                                &object_bytes[offset as usize..(offset + op_size as u64) as usize]
-                            )
+                            _ => len,
                         }
-                        self.surgeries
-                            .get_mut(*func_name)
-                            .unwrap()
-                            .push(metadata::SurgeryEntry {
-                                file_offset: offset,
-                                virtual_offset: VirtualOffset::Relative(inst.next_ip()),
-                                size: op_size,
-                            });
                     }
                 }
-                Ok(OpKind::FarBranch16 | OpKind::FarBranch32) => {
+            };
+
+            chunks.push(TextChunk {
+                section: index,
+                start,
+                end,
+            });
+
+            start = end;
+        }
+    }
+
+    chunks
+}
+
+/// How many threads scan text sections
+fn scan_threads() -> usize {
+    std::thread::available_parallelism().map_or(1, |n| n.get())
+}
+
+/// Scan the text sections for branches to app functions, split into chunks of about
+/// `chunk_size` bytes, on up to `threads` threads. Returns what was found in each chunk, in
+/// the order of the sections, so the metadata doesn't depend on which thread was faster.
+fn scan_text_sections<'a>(
+    sections: &[TextSection],
+    function_starts: &[u64],
+    app_func_addresses: &MutMap<u64, &'a str>,
+    arch: ElfArch,
+    chunk_size: u64,
+    threads: usize,
+) -> Vec<TextSectionScan<'a>> {
+    let chunks = text_chunks(sections, function_starts, arch, chunk_size);
+    let next_chunk = AtomicUsize::new(0);
+
+    let mut scans: Vec<(usize, TextSectionScan)> = std::thread::scope(|scope| {
+        let chunks = &chunks;
+        let next_chunk = &next_chunk;
+
+        let handles: Vec<_> = (0..threads.clamp(1, chunks.len().max(1)))
+            .map(|_| {
+                scope.spawn(move || {
+                    let mut scans = Vec::new();
+
+                    loop {
+                        let index = next_chunk.fetch_add(1, Ordering::Relaxed);
+                        let chunk = match chunks.get(index) {
+                            Some(chunk) => chunk,
+                            None => break,
+                        };
+                        let text = &sections[chunk.section];
+
+                        scans.push((
+                            index,
+                            scan_text_chunk(text, chunk, app_func_addresses, arch),
+                        ));
+                    }
+
+                    scans
+                })
+            })
+            .collect();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| match handle.join() {
+                Ok(scans) => scans,
+                Err(payload) => std::panic::resume_unwind(payload),
+            })
+            .collect()
+    });
+
+    scans.sort_by_key(|(index, _)| *index);
+
+    scans.into_iter().map(|(_, scan)| scan).collect()
+}
+
+fn scan_text_chunk<'a>(
+    text: &TextSection,
+    chunk: &TextChunk,
+    app_func_addresses: &MutMap<u64, &'a str>,
+    arch: ElfArch,
+) -> TextSectionScan<'a> {
+    let sec = text.section;
+    let file_offset = text.file_offset;
+    let compressed = text.compressed;
+    let data = &text.data[chunk.start as usize..chunk.end as usize];
+    let address = sec.address() + chunk.start;
+    let mut scan = TextSectionScan::default();
+
+    if arch == ElfArch::Aarch64 {
+        // Every instruction is 4 bytes, and B/BL encode their target as a signed 26-bit word
+        // offset from the instruction itself.
+        for (i, word) in data.chunks_exact(4).enumerate() {
+            let inst = u32::from_le_bytes(<[u8; 4]>::try_from(word).unwrap());
+            if inst & 0x7C00_0000 != 0x1400_0000 {
+                continue;
+            }
+
+            let ip = address + 4 * i as u64;
+            let word_offset = ((inst << 6) as i32) >> 6;
+            let target = (ip as i64 + 4 * word_offset as i64) as u64;
+            if let Some(&func_name) = app_func_addresses.get(&target) {
+                if compressed {
                     internal_error!(
-                        "Found branch type instruction that is not yet support: {:+x?}",
-                        inst
+                        "Surgical linking does not work with compressed text sections: {:+x?}",
+                        sec
                     );
                 }
-                Ok(_) => {
-                    if (inst.is_call_far_indirect()
-                        || inst.is_call_near_indirect()
-                        || inst.is_jmp_far_indirect()
-                        || inst.is_jmp_near_indirect())
-                        && !self.indirect_warning_given
-                        && verbose
-                    {
-                        self.indirect_warning_given = true;
-                        println!();
-                        println!("Cannot analyze through indirect jmp type instructions");
-                        println!("Most likely this is not a problem, but it could mean a loss in optimizations");
-                        println!();
+
+                scan.branches.push(BranchSurgery {
+                    func_name,
+                    ip,
+                    target,
+                    entry: metadata::SurgeryEntry {
+                        file_offset: file_offset + chunk.start + 4 * i as u64,
+                        virtual_offset: VirtualOffset::Relative(ip),
+                        size: 4,
+                    },
+                });
+            }
+        }
+
+        return scan;
+    }
+
+    let mut decoder = Decoder::with_ip(64, data, address, DecoderOptions::NONE);
+    let mut inst = Instruction::default();
+
+    while decoder.can_decode() {
+        decoder.decode_out(&mut inst);
+
+        // Note: This gets really complex fast if we want to support more than basic calls/jumps.
+        // A lot of them have to load addresses into registers/memory so we would have to discover that value.
+        // Would probably require some static code analysis and would be impossible in some cases.
+        // As an alternative we can leave in the calls to the plt, but change the plt to jmp to the static function.
+        // That way any indirect call will just have the overhead of an extra jump.
+        match inst.try_op_kind(0) {
+            // Relative Offsets.
+            Ok(OpKind::NearBranch16 | OpKind::NearBranch32 | OpKind::NearBranch64) => {
+                let target = inst.near_branch_target();
+                if let Some(&func_name) = app_func_addresses.get(&target) {
+                    if compressed {
+                        internal_error!(
+                            "Surgical linking does not work with compressed text sections: {:+x?}",
+                            sec
+                        );
                     }
+
+                    // TODO: Double check these offsets are always correct.
+                    // We may need to do a custom offset based on opcode instead.
+                    let op_kind = inst.op_code().try_op_kind(0).unwrap();
+                    let op_size: u8 = match op_kind {
+                        OpCodeOperandKind::br16_1 | OpCodeOperandKind::br32_1 => 1,
+                        OpCodeOperandKind::br16_2 => 2,
+                        OpCodeOperandKind::br32_4 | OpCodeOperandKind::br64_4 => 4,
+                        _ => {
+                            internal_error!(
+                                "Ran into an unknown operand kind when analyzing branches: {:?}",
+                                op_kind
+                            );
+                        }
+                    };
+                    let offset = inst.next_ip() - op_size as u64 - sec.address() + file_offset;
+                    scan.branches.push(BranchSurgery {
+                        func_name,
+                        ip: inst.ip(),
+                        target,
+                        entry: metadata::SurgeryEntry {
+                            file_offset: offset,
+                            virtual_offset: VirtualOffset::Relative(inst.next_ip()),
+                            size: op_size,
+                        },
+                    });
                 }
-                Err(err) => {
-                    internal_error!("Failed to decode assembly: {}", err);
+            }
+            Ok(OpKind::FarBranch16 | OpKind::FarBranch32) => {
+                internal_error!(
+                    "Found branch type instruction that is not yet support: {:+x?}",
+                    inst
+                );
+            }
+            Ok(_) => {
+                if inst.is_call_far_indirect()
+                    || inst.is_call_near_indirect()
+                    || inst.is_jmp_far_indirect()
+                    || inst.is_jmp_near_indirect()
+                {
+                    scan.has_indirect_branch = true;
                 }
             }
+            Err(err) => {
+                internal_error!("Failed to decode assembly: {}", err);
+            }
         }
     }
+
+    scan
 }
 
 /// Constructs a `metadata::Metadata` from a host executable binary, and writes it to disk
@@ -291,6 +599,8 @@ pub(crate) fn preprocess_elf(
         }
     };
 
+    let arch = ElfArch::from_file_header(exec_data);
+
     let mut md = metadata::Metadata {
         roc_symbol_vaddresses: collect_roc_definitions(&exec_obj),
         ..Default::default()
@@ -348,9 +658,17 @@ pub(crate) fn preprocess_elf(
             internal_error!("Failed to find PLT section. Probably an malformed executable.");
         }
     };
+    // Hosts built with CET/IBT enabled (the default on several distros) call through a second
+    // table, `.plt.sec`, which has one entry per `.plt` entry minus the header.
+    let plt_sec_address = exec_obj
+        .section_by_name(".plt.sec")
+        .map(|section| section.address());
     if verbose {
         println!("PLT Address: {:+x}", plt_address);
         println!("PLT File Offset: {:+x}", plt_offset);
+        if let Some(plt_sec_address) = plt_sec_address {
+            println!("PLT.SEC Address: {:+x}", plt_sec_address);
+        }
     }
 
     let app_syms: Vec<_> = exec_obj
@@ -366,19 +684,21 @@ pub(crate) fn preprocess_elf(
                     internal_error!("Executable does not have any dynamic relocations. No work to do. Probably an invalid input.");
                 }
             })
-            .filter_map(|(_, reloc)| {
-                if let RelocationKind::Elf(7) = reloc.kind() {
-                    Some(reloc)
-                } else {
-                    None
-                }
+            .filter_map(|(_, reloc)| match reloc.kind() {
+                RelocationKind::Elf(r_type) if r_type == arch.jump_slot_relocation() => Some(reloc),
+                _ => None,
             });
     for (i, reloc) in plt_relocs.enumerate() {
         for symbol in app_syms.iter() {
             if reloc.target() == RelocationTarget::Symbol(symbol.index()) {
-                let func_address = (i as u64 + 1) * PLT_ADDRESS_OFFSET + plt_address;
-                let func_offset = (i as u64 + 1) * PLT_ADDRESS_OFFSET + plt_offset;
+                let plt_entry = arch.plt_header_size() + i as u64 * PLT_ADDRESS_OFFSET;
+                let func_address = plt_address + plt_entry;
+                let func_offset = plt_offset + plt_entry;
                 app_func_addresses.insert(func_address, symbol.name().unwrap());
+                if let Some(plt_sec_address) = plt_sec_address {
+                    let func_address = i as u64 * PLT_ADDRESS_OFFSET + plt_sec_address;
+                    app_func_addresses.insert(func_address, symbol.name().unwrap());
+                }
                 md.plt_addresses.insert(
                     symbol.name().unwrap().to_string(),
                     (func_offset, func_address),
@@ -408,7 +728,7 @@ pub(crate) fn preprocess_elf(
     // look at the text (i.e. code) sections and see collect work needs to be done
     let text_disassembly_start = Instant::now();
 
-    let mut surgeries = Surgeries::new(&app_syms, app_func_addresses);
+    let mut surgeries = Surgeries::new(&app_syms, app_func_addresses, arch);
     surgeries.append_text_sections(exec_data, &exec_obj, verbose);
     md.surgeries = surgeries.surgeries;
 
@@ -427,7 +747,7 @@ pub(crate) fn preprocess_elf(
                 dynamic_lib_count,
                 shared_lib_index,
             } = scan_elf_dynamic_deps(
-                &exec_obj, &mut md, &app_syms, shared_lib, exec_data, verbose,
+                &exec_obj, &mut md, &app_syms, shared_lib, exec_data, arch, verbose,
             );
 
             scanning_dynamic_deps_duration = scanning_dynamic_deps_start.elapsed();
@@ -443,6 +763,7 @@ pub(crate) fn preprocess_elf(
                 &got_sections,
                 dynamic_lib_count,
                 shared_lib_index,
+                arch,
                 verbose,
             )
         }
@@ -512,6 +833,7 @@ fn gen_elf_le(
     got_sections: &[(usize, usize)],
     dynamic_lib_count: usize,
     shared_lib_index: usize,
+    arch: ElfArch,
     verbose: bool,
 ) -> MmapMut {
     let exec_header = load_struct_inplace::<elf::FileHeader64<LE>>(exec_data, 0);
@@ -532,11 +854,23 @@ fn gen_elf_le(
         println!("SH Entry Count: {}", sh_num);
     }
 
+    // The thread pointer offsets of the executable's TLS variables are computed from the TLS
+    // segment's address modulo its alignment, so the shift must preserve that alignment.
+    let shift_alignment = load_structs_inplace::<elf::ProgramHeader64<LE>>(
+        exec_data,
+        ph_offset as usize,
+        ph_num as usize,
+    )
+    .iter()
+    .filter(|ph| ph.p_type.get(NativeEndian) == elf::PT_TLS)
+    .map(|ph| ph.p_align.get(NativeEndian))
+    .fold(MIN_SECTION_ALIGNMENT as u64, u64::max);
+
     // Copy header and shift everything to enable more program sections.
     let added_header_count = 2;
     md.added_byte_count = ph_ent_size as u64 * added_header_count;
-    md.added_byte_count = md.added_byte_count
-        + (MIN_SECTION_ALIGNMENT as u64 - md.added_byte_count % MIN_SECTION_ALIGNMENT as u64);
+    md.added_byte_count =
+        md.added_byte_count + (shift_alignment - md.added_byte_count % shift_alignment);
     let ph_end = ph_offset as usize + ph_num as usize * ph_ent_size as usize;
     let physical_shift_start = ph_end as u64;
 
@@ -552,7 +886,12 @@ fn gen_elf_le(
     );
     let mut first_load_found = false;
     let mut virtual_shift_start = 0;
+    let mut image_end = 0;
     for ph in program_headers.iter() {
+        if ph.p_type.get(NativeEndian) == elf::PT_LOAD {
+            image_end = image_end.max(ph.p_vaddr.get(NativeEndian) + ph.p_memsz.get(NativeEndian));
+        }
+
         let p_type = ph.p_type.get(NativeEndian);
         if p_type == elf::PT_LOAD && ph.p_offset.get(NativeEndian) == 0 {
             first_load_found = true;
@@ -674,18 +1013,18 @@ fn gen_elf_le(
                 rel.r_offset = endian::U64::new(LE, r_offset + md.added_byte_count);
                 // Deal with potential adjusts to absolute jumps.
                 // TODO: Verify other relocation types.
-                if rel.r_type(LE, false) == elf::R_X86_64_RELATIVE {
+                if rel.r_type(LE, false) == arch.relative_relocation() {
                     let r_addend = rel.r_addend.get(LE);
                     rel.r_addend.set(LE, r_addend + md.added_byte_count as i64);
                 }
             }
             // If the relocation goes to a roc function, we need to surgically link it and change it to relative.
             let r_type = rel.r_type(NativeEndian, false);
-            if r_type == elf::R_X86_64_GLOB_DAT {
+            if r_type == arch.glob_dat_relocation() {
                 let r_sym = rel.r_sym(NativeEndian, false);
                 for (name, index) in got_app_syms.iter() {
                     if *index as u32 == r_sym {
-                        rel.set_r_info(LE, false, 0, elf::R_X86_64_RELATIVE);
+                        rel.set_r_info(LE, false, 0, arch.relative_relocation());
                         let addend_addr = sec_offset as usize
                             + i * mem::size_of::<elf::Rela64<LE>>()
                             // This 16 skips the first 2 fields and gets to the addend field.
@@ -764,6 +1103,11 @@ fn gen_elf_le(
     );
 
     for sym in symbols {
+        // The value of a TLS symbol is an offset into the TLS segment, not an address.
+        if sym.st_type() == elf::STT_TLS {
+            continue;
+        }
+
         let addr = sym.st_value.get(NativeEndian);
         if virtual_shift_start <= addr {
             sym.st_value = endian::U64::new(LE, addr + md.added_byte_count);
@@ -778,8 +1122,10 @@ fn gen_elf_le(
             size / mem::size_of::<endian::U64<LE>>(),
         );
         for go in global_offsets.iter_mut() {
+            // Entries that are not addresses in the image are left alone. Notably, these are the
+            // thread pointer offsets the static linker resolved for initial-exec TLS accesses.
             let go_addr = go.get(NativeEndian);
-            if physical_shift_start <= go_addr {
+            if physical_shift_start <= go_addr && go_addr < image_end {
                 go.set(LE, go_addr + md.added_byte_count);
             }
         }
@@ -818,6 +1164,7 @@ fn scan_elf_dynamic_deps(
     app_syms: &[Symbol],
     shared_lib: &Path,
     exec_data: &[u8],
+    arch: ElfArch,
     verbose: bool,
 ) -> ElfDynamicDeps {
     let dyn_sec = match exec_obj.section_by_name(".dynamic") {
@@ -960,7 +1307,7 @@ fn scan_elf_dynamic_deps(
         }
     })
     .filter_map(|(_, reloc)| {
-        if let RelocationKind::Elf(6) = reloc.kind() {
+        if reloc.kind() == RelocationKind::Elf(arch.glob_dat_relocation()) {
             for symbol in app_syms.iter() {
                 if reloc.target() == RelocationTarget::Symbol(symbol.index()) {
                     return Some((symbol.name().unwrap().to_string(), symbol.index().0));
@@ -1007,6 +1354,15 @@ pub(crate) fn surgery_elf(
         std::process::exit(1);
     }
 
+    if app_obj
+        .sections()
+        .any(|sec| matches!(sec.kind(), SectionKind::Tls | SectionKind::UninitializedTls))
+    {
+        eprintln!("The surgical linker does not support thread-local storage defined by the app.");
+        eprintln!("Please use `--linker=legacy` to link this app.");
+        std::process::exit(1);
+    }
+
     let total_start = Instant::now();
 
     let loading_metadata_start = total_start;
@@ -1079,6 +1435,7 @@ fn surgery_elf_help(
     if !elf64 || !litte_endian {
         internal_error!("Only 64bit little endian elf currently supported for surgery");
     }
+    let arch = ElfArch::from_file_header(exec_mmap);
     let exec_header = load_struct_inplace::<elf::FileHeader64<LE>>(exec_mmap, 0);
 
     let ph_offset = exec_header.e_phoff.get(NativeEndian);
@@ -1272,6 +1629,16 @@ fn surgery_elf_help(
                     if let Some(target_offset) = target_offset {
                         let virt_base = section_virtual_offset as usize + rel.0 as usize;
                         let base = section_offset as usize + rel.0 as usize;
+
+                        if arch == ElfArch::Aarch64 {
+                            apply_aarch64_relocation(
+                                &mut exec_mmap[base..],
+                                virt_base as i64,
+                                target_offset + rel.1.addend(),
+                                aarch64_relocation_type(&rel.1),
+                            );
+                            continue;
+                        }
                         let target: i64 = match rel.1.kind() {
                             RelocationKind::Relative | RelocationKind::PltRelative => {
                                 target_offset - virt_base as i64 + rel.1.addend()
@@ -1307,7 +1674,12 @@ fn surgery_elf_help(
                             Ok(sym) if ALWAYS_LINKED.contains(&sym.name().unwrap_or_default()) => {
                                 continue
                             }
-                            _ => {
+                            Ok(sym) => report_missing_symbol(
+                                MissingSymbol::FromHost,
+                                sym.name().unwrap_or_default(),
+                                md.roc_symbol_vaddresses.keys().map(|name| name.as_str()),
+                            ),
+                            Err(_) => {
                                 internal_error!(
                                     "Undefined Symbol in relocation, {:+x?}: {:+x?}",
                                     rel,
@@ -1427,7 +1799,11 @@ fn surgery_elf_help(
         let func_virt_offset = match app_func_vaddr_map.get(func_name) {
             Some(offset) => *offset as u64,
             None => {
-                internal_error!("Function, {}, was not defined by the app", &func_name);
+                report_missing_symbol(
+                    MissingSymbol::FromApp,
+                    func_name,
+                    app_func_vaddr_map.keys().map(|name| name.as_str()),
+                );
             }
         };
         if verbose {
@@ -1446,6 +1822,15 @@ fn surgery_elf_help(
                 VirtualOffset::Absolute => 0,
             };
             match s.size {
+                4 if arch == ElfArch::Aarch64 => {
+                    // a B or BL instruction, see `scan_text_section`
+                    let file_offset = (s.file_offset + md.added_byte_count) as usize;
+                    let target = func_virt_offset as i64 - surgery_virt_offset;
+                    if verbose {
+                        println!("\tTarget Jump: {:+x}", target);
+                    }
+                    patch_aarch64_branch(&mut exec_mmap[file_offset..][..4], target);
+                }
                 4 => {
                     let target = (func_virt_offset as i64 - surgery_virt_offset) as i32;
                     if verbose {
@@ -1475,18 +1860,35 @@ fn surgery_elf_help(
         if let Some((plt_off, plt_vaddr)) = md.plt_addresses.get(func_name) {
             let plt_off = (*plt_off + md.added_byte_count) as usize;
             let plt_vaddr = *plt_vaddr + md.added_byte_count;
-            let jmp_inst_len = 5;
-            let target =
-                (func_virt_offset as i64 - (plt_vaddr as i64 + jmp_inst_len as i64)) as i32;
-            if verbose {
-                println!("\tPLT: {:+x}, {:+x}", plt_off, plt_vaddr);
-                println!("\tTarget Jump: {:+x}", target);
-            }
-            let data = target.to_le_bytes();
-            exec_mmap[plt_off] = 0xE9;
-            exec_mmap[plt_off + 1..plt_off + jmp_inst_len].copy_from_slice(&data);
-            for i in jmp_inst_len..PLT_ADDRESS_OFFSET as usize {
-                exec_mmap[plt_off + i] = 0x90;
+
+            if arch == ElfArch::Aarch64 {
+                let target = func_virt_offset as i64 - plt_vaddr as i64;
+                if verbose {
+                    println!("\tPLT: {:+x}, {:+x}", plt_off, plt_vaddr);
+                    println!("\tTarget Jump: {:+x}", target);
+                }
+
+                // b <func>, followed by nops
+                let plt_entry = &mut exec_mmap[plt_off..][..PLT_ADDRESS_OFFSET as usize];
+                for nop in plt_entry.chunks_exact_mut(4) {
+                    nop.copy_from_slice(&AARCH64_NOP.to_le_bytes());
+                }
+                plt_entry[..4].copy_from_slice(&AARCH64_B.to_le_bytes());
+                patch_aarch64_branch(&mut plt_entry[..4], target);
+            } else {
+                let jmp_inst_len = 5;
+                let target =
+                    (func_virt_offset as i64 - (plt_vaddr as i64 + jmp_inst_len as i64)) as i32;
+                if verbose {
+                    println!("\tPLT: {:+x}, {:+x}", plt_off, plt_vaddr);
+                    println!("\tTarget Jump: {:+x}", target);
+                }
+                let data = target.to_le_bytes();
+                exec_mmap[plt_off] = 0xE9;
+                exec_mmap[plt_off + 1..plt_off + jmp_inst_len].copy_from_slice(&data);
+                for i in jmp_inst_len..PLT_ADDRESS_OFFSET as usize {
+                    exec_mmap[plt_off + i] = 0x90;
+                }
             }
         }
 
@@ -1497,6 +1899,11 @@ fn surgery_elf_help(
             );
             sym.st_shndx = endian::U16::new(LE, new_text_section_index as u16);
             sym.st_value = endian::U64::new(LE, func_virt_offset as u64);
+            // The symbol is not in the host's hash tables, so the dynamic linker can't find it
+            // by name. Marking it hidden makes ld.so bind any remaining relocations against it
+            // (e.g. PLT slots when the host is linked with `-z now`) to this executable directly,
+            // instead of searching the shared libraries the host depends on.
+            sym.st_other = elf::STV_HIDDEN;
             sym.st_size = endian::U64::new(
                 LE,
                 match app_func_size_map.get(func_name) {
@@ -1511,6 +1918,99 @@ fn surgery_elf_help(
     *offset_ref = offset;
 }
 
+const AARCH64_B: u32 = 0x1400_0000;
+const AARCH64_NOP: u32 = 0xD503_201F;
+
+/// Rewrites the 26-bit word offset of a B or BL instruction.
+fn patch_aarch64_branch(instruction: &mut [u8], byte_offset: i64) {
+    if byte_offset % 4 != 0 || !(-(1 << 27)..(1 << 27)).contains(&byte_offset) {
+        internal_error!(
+            "The branch offset {byte_offset:+x} can't be encoded in a B/BL instruction"
+        );
+    }
+
+    let inst = u32::from_le_bytes(<[u8; 4]>::try_from(&*instruction).unwrap());
+    let inst = (inst & 0xFC00_0000) | ((byte_offset >> 2) as u32 & 0x03FF_FFFF);
+    instruction.copy_from_slice(&inst.to_le_bytes());
+}
+
+/// `object` only gives a generic kind to a few of the AArch64 relocations.
+fn aarch64_relocation_type(rel: &object::Relocation) -> u32 {
+    match (rel.kind(), rel.size()) {
+        (RelocationKind::Elf(r_type), _) => r_type,
+        (RelocationKind::PltRelative, 26) => elf::R_AARCH64_CALL26,
+        (RelocationKind::Relative, 64) => elf::R_AARCH64_PREL64,
+        (RelocationKind::Relative, 32) => elf::R_AARCH64_PREL32,
+        (kind, size) => {
+            internal_error!(
+                "Relocation Kind not yet support: {:?} ({} bits)",
+                kind,
+                size
+            );
+        }
+    }
+}
+
+/// Applies a relocation from the app to the instruction or data at the start of `bytes`, where
+/// `target` is the address being referenced (including the addend) and `virt_base` is the address
+/// of `bytes`.
+fn apply_aarch64_relocation(bytes: &mut [u8], virt_base: i64, target: i64, r_type: u32) {
+    let inst = u32::from_le_bytes(<[u8; 4]>::try_from(&bytes[..4]).unwrap());
+    let page = |address: i64| address & !0xFFF;
+
+    let inst = match r_type {
+        elf::R_AARCH64_CALL26 | elf::R_AARCH64_JUMP26 => {
+            patch_aarch64_branch(&mut bytes[..4], target - virt_base);
+            return;
+        }
+        // The app is linked with the host, so a GOT entry is never needed: ADRP computes the page
+        // of the target itself, and the LDR from the GOT below is turned into an ADD.
+        elf::R_AARCH64_ADR_PREL_PG_HI21 | elf::R_AARCH64_ADR_GOT_PAGE => {
+            let pages = (page(target) - page(virt_base)) >> 12;
+            if !(-(1 << 20)..(1 << 20)).contains(&pages) {
+                internal_error!(
+                    "The page offset {pages:+x} can't be encoded in an ADRP instruction"
+                );
+            }
+            let immlo = (pages as u32 & 0x3) << 29;
+            let immhi = ((pages >> 2) as u32 & 0x7FFFF) << 5;
+            (inst & 0x9F00_001F) | immlo | immhi
+        }
+        elf::R_AARCH64_LD64_GOT_LO12_NC => {
+            let rn_rd = inst & 0x3FF;
+            0x9100_0000 | ((target as u32 & 0xFFF) << 10) | rn_rd
+        }
+        elf::R_AARCH64_ADD_ABS_LO12_NC | elf::R_AARCH64_LDST8_ABS_LO12_NC => {
+            (inst & 0xFFC0_03FF) | ((target as u32 & 0xFFF) << 10)
+        }
+        elf::R_AARCH64_LDST16_ABS_LO12_NC
+        | elf::R_AARCH64_LDST32_ABS_LO12_NC
+        | elf::R_AARCH64_LDST64_ABS_LO12_NC
+        | elf::R_AARCH64_LDST128_ABS_LO12_NC => {
+            let shift = match r_type {
+                elf::R_AARCH64_LDST16_ABS_LO12_NC => 1,
+                elf::R_AARCH64_LDST32_ABS_LO12_NC => 2,
+                elf::R_AARCH64_LDST64_ABS_LO12_NC => 3,
+                _ => 4,
+            };
+            (inst & 0xFFC0_03FF) | (((target as u32 & 0xFFF) >> shift) << 10)
+        }
+        elf::R_AARCH64_PREL32 => {
+            bytes[..4].copy_from_slice(&((target - virt_base) as i32).to_le_bytes());
+            return;
+        }
+        elf::R_AARCH64_PREL64 => {
+            bytes[..8].copy_from_slice(&(target - virt_base).to_le_bytes());
+            return;
+        }
+        other => {
+            internal_error!("AArch64 relocation type not yet supported: {}", other);
+        }
+    };
+
+    bytes[..4].copy_from_slice(&inst.to_le_bytes());
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -1521,6 +2021,119 @@ mod tests {
 
     const ELF64_DYNHOST: &[u8] = include_bytes!("../dynhost_benchmarks_elf64") as &[_];
 
+    /// The instruction `inst` at `virt_base` after applying a relocation of type `r_type` to it
+    fn relocate_aarch64(inst: u32, virt_base: i64, target: i64, r_type: u32) -> u32 {
+        let mut bytes = inst.to_le_bytes();
+        apply_aarch64_relocation(&mut bytes, virt_base, target, r_type);
+        u32::from_le_bytes(bytes)
+    }
+
+    #[test]
+    fn aarch64_adrp_pages() {
+        // adrp x1, 0
+        let adrp = 0x9000_0001;
+
+        // 0x55 pages forward, whatever the offsets within the pages
+        assert_eq!(
+            relocate_aarch64(adrp, 0x40_1234, 0x45_6789, elf::R_AARCH64_ADR_PREL_PG_HI21),
+            0xB000_02A1
+        );
+        // 2 pages back
+        assert_eq!(
+            relocate_aarch64(adrp, 0x40_1234, 0x3F_F000, elf::R_AARCH64_ADR_GOT_PAGE),
+            0xD0FF_FFE1
+        );
+        // the same page
+        assert_eq!(
+            relocate_aarch64(adrp, 0x40_1000, 0x40_1FFF, elf::R_AARCH64_ADR_PREL_PG_HI21),
+            adrp
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "can't be encoded in an ADRP instruction")]
+    fn aarch64_adrp_out_of_range() {
+        relocate_aarch64(
+            0x9000_0001,
+            0x40_1000,
+            0x40_1000 + (1 << 32),
+            elf::R_AARCH64_ADR_PREL_PG_HI21,
+        );
+    }
+
+    #[test]
+    fn aarch64_lo12_scaling() {
+        let target = 0x45_6678;
+
+        // add x0, x1, #0
+        assert_eq!(
+            relocate_aarch64(0x9100_0020, 0, target, elf::R_AARCH64_ADD_ABS_LO12_NC),
+            0x9119_E020
+        );
+        // ldrb w2, [x3, #0]
+        assert_eq!(
+            relocate_aarch64(0x3940_0062, 0, target, elf::R_AARCH64_LDST8_ABS_LO12_NC),
+            0x3959_E062
+        );
+        // ldr w2, [x3, #0], which scales the offset by 4
+        assert_eq!(
+            relocate_aarch64(0xB940_0062, 0, target, elf::R_AARCH64_LDST32_ABS_LO12_NC),
+            0xB946_7862
+        );
+        // ldr x2, [x3, #0xfff * 8], whose old offset is replaced, scaled by 8
+        assert_eq!(
+            relocate_aarch64(0xF97F_FC62, 0, target, elf::R_AARCH64_LDST64_ABS_LO12_NC),
+            0xF943_3C62
+        );
+        // ldr x1, [x2, #0] from the GOT becomes add x1, x2, #0x678
+        assert_eq!(
+            relocate_aarch64(0xF940_0041, 0, target, elf::R_AARCH64_LD64_GOT_LO12_NC),
+            0x9119_E041
+        );
+    }
+
+    #[test]
+    fn aarch64_call26() {
+        // bl 0
+        let bl = 0x9400_0000;
+
+        assert_eq!(
+            relocate_aarch64(bl, 0x1000, 0x2000, elf::R_AARCH64_CALL26),
+            0x9400_0400
+        );
+        assert_eq!(
+            relocate_aarch64(bl, 0x2000, 0x1000, elf::R_AARCH64_CALL26),
+            0x97FF_FC00
+        );
+        // b 0 keeps its opcode
+        assert_eq!(
+            relocate_aarch64(0x1400_0000, 0x1000, 0x2000, elf::R_AARCH64_JUMP26),
+            0x1400_0400
+        );
+        // the furthest branches in either direction
+        assert_eq!(
+            relocate_aarch64(bl, 0, (1 << 27) - 4, elf::R_AARCH64_CALL26),
+            0x95FF_FFFF
+        );
+        assert_eq!(
+            relocate_aarch64(bl, 1 << 27, 0, elf::R_AARCH64_CALL26),
+            0x9600_0000
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "can't be encoded in a B/BL instruction")]
+    fn aarch64_call26_out_of_range() {
+        relocate_aarch64(0x9400_0000, 0, 1 << 27, elf::R_AARCH64_CALL26);
+    }
+
+    #[test]
+    #[should_panic(expected = "can't be encoded in a B/BL instruction")]
+    fn aarch64_call26_misaligned() {
+        let mut bytes = 0x9400_0000u32.to_le_bytes();
+        patch_aarch64_branch(&mut bytes, 2);
+    }
+
     #[test]
     fn collect_definitions() {
         let object = object::File::parse(ELF64_DYNHOST).unwrap();
@@ -1549,6 +2162,99 @@ mod tests {
         )
     }
 
+    /// Every direct call in the host's text sections to one of its own functions, as
+    /// (address, target, file offset)
+    fn host_calls(object: &object::File, chunk_size: u64, threads: usize) -> Vec<(u64, u64, u64)> {
+        let sections: Vec<Section> = object
+            .sections()
+            .filter(|sec| sec.kind() == SectionKind::Text)
+            .collect();
+        let text_sections: Vec<TextSection> = sections.iter().map(TextSection::new).collect();
+        let function_starts = function_starts(object);
+        let targets: MutMap<u64, &str> = function_starts.iter().map(|&a| (a, "")).collect();
+
+        scan_text_sections(
+            &text_sections,
+            &function_starts,
+            &targets,
+            ElfArch::X86_64,
+            chunk_size,
+            threads,
+        )
+        .into_iter()
+        .flat_map(|scan| scan.branches)
+        .map(|branch| (branch.ip, branch.target, branch.entry.file_offset))
+        .collect()
+    }
+
+    #[test]
+    fn text_chunks_start_at_functions() {
+        let object = object::File::parse(ELF64_DYNHOST).unwrap();
+        let sections: Vec<Section> = object
+            .sections()
+            .filter(|sec| sec.kind() == SectionKind::Text)
+            .collect();
+        let text_sections: Vec<TextSection> = sections.iter().map(TextSection::new).collect();
+        let function_starts = function_starts(&object);
+
+        let chunks = text_chunks(&text_sections, &function_starts, ElfArch::X86_64, 256);
+
+        assert!(chunks.len() > text_sections.len());
+
+        for chunk in chunks {
+            let address = text_sections[chunk.section].section.address();
+
+            assert!(chunk.start == 0 || function_starts.contains(&(address + chunk.start)));
+        }
+    }
+
+    #[test]
+    fn scan_text_in_chunks() {
+        let object = object::File::parse(ELF64_DYNHOST).unwrap();
+
+        let whole_sections = host_calls(&object, u64::MAX, 1);
+
+        assert!(!whole_sections.is_empty());
+        assert_eq!(host_calls(&object, 256, 4), whole_sections);
+    }
+
+    /// Compares scanning each text section on a thread of its own to scanning them in chunks
+    /// on every core. Run it with
+    ///
+    ///     cargo test --release -p roc_linker -- --ignored --nocapture bench_text_scan
+    ///
+    /// and set `ROC_BENCH_HOST` to the path of a host to scan a bigger one than the test host.
+    #[test]
+    #[ignore]
+    fn bench_text_scan() {
+        let host = match std::env::var_os("ROC_BENCH_HOST") {
+            Some(path) => std::fs::read(path).unwrap(),
+            None => ELF64_DYNHOST.to_vec(),
+        };
+        let object = object::File::parse(host.as_slice()).unwrap();
+        let section_count = object
+            .sections()
+            .filter(|sec| sec.kind() == SectionKind::Text)
+            .count();
+
+        let fastest = |chunk_size: u64, threads: usize| {
+            (0..10)
+                .map(|_| {
+                    let start = Instant::now();
+                    host_calls(&object, chunk_size, threads);
+                    start.elapsed()
+                })
+                .min()
+                .unwrap()
+        };
+
+        report_timing("a thread per section", fastest(u64::MAX, section_count));
+        report_timing(
+            "chunks on every core",
+            fastest(TEXT_SCAN_CHUNK_SIZE, scan_threads()),
+        );
+    }
+
     #[test]
     fn collect_undefined_symbols_elf() {
         let object = object::File::parse(ELF64_DYNHOST).unwrap();
@@ -1575,20 +2281,21 @@ mod tests {
     }
 
     #[allow(dead_code)]
-    fn zig_host_app_help(dir: &Path, target: &Triple) {
-        let host_zig = indoc!(
-            r#"
-            const std = @import("std");
+    const HOST_ZIG: &str = indoc!(
+        r#"
+        const std = @import("std");
 
-            extern fn roc_magic1(usize) callconv(.C) [*]const u8;
+        extern fn roc_magic1(usize) callconv(.C) [*]const u8;
 
-            pub fn main() !void {
-                const stdout = std.io.getStdOut().writer();
-                try stdout.print("Hello {s}\n", .{roc_magic1(0)[0..3]});
-            }
-            "#
-        );
+        pub fn main() !void {
+            const stdout = std.io.getStdOut().writer();
+            try stdout.print("Hello {s}\n", .{roc_magic1(0)[0..3]});
+        }
+        "#
+    );
 
+    #[allow(dead_code)]
+    fn zig_host_app_help(dir: &Path, target: &Triple, host_zig: &str, extra_host_args: &[&str]) {
         let app_zig = indoc!(
             r#"
             const X = [_][]const u8 { "foo" };
@@ -1600,6 +2307,7 @@ mod tests {
         );
 
         let zig = std::env::var("ROC_ZIG").unwrap_or_else(|_| "zig".into());
+        let zig_target = format!("{}-linux-gnu", target.architecture);
 
         std::fs::write(dir.join("host.zig"), host_zig.as_bytes()).unwrap();
         std::fs::write(dir.join("app.zig"), app_zig.as_bytes()).unwrap();
@@ -1612,7 +2320,7 @@ mod tests {
                 "app.zig",
                 "-fPIC",
                 "-target",
-                "x86_64-linux-gnu",
+                &zig_target,
                 "-OReleaseFast",
             ])
             .output()
@@ -1641,7 +2349,7 @@ mod tests {
                 .collect()
         };
 
-        let dylib_bytes = crate::generate_dylib::create_dylib_elf64(&names).unwrap();
+        let dylib_bytes = crate::generate_dylib::create_dylib_elf64(&names, target).unwrap();
         std::fs::write(dir.join("libapp.so"), dylib_bytes).unwrap();
 
         // now we can compile the host (it uses libapp.so, hence the order here)
@@ -1654,9 +2362,10 @@ mod tests {
                 "-fPIE",
                 "-lc",
                 "-target",
-                "x86_64-linux-gnu",
+                &zig_target,
                 "-OReleaseFast",
             ])
+            .args(extra_host_args)
             .output()
             .unwrap();
 
@@ -1692,15 +2401,19 @@ mod tests {
         );
     }
 
-    #[cfg(target_os = "linux")]
-    #[test]
-    fn zig_host_app() {
+    #[allow(dead_code)]
+    fn run_zig_host_app(host_zig: &str, extra_host_args: &[&str]) -> String {
         use std::str::FromStr;
 
         let dir = tempfile::tempdir().unwrap();
         let dir = dir.path();
 
-        zig_host_app_help(dir, &Triple::from_str("x86_64-unknown-linux-musl").unwrap());
+        zig_host_app_help(
+            dir,
+            &Triple::from_str(&format!("{}-unknown-linux-gnu", std::env::consts::ARCH)).unwrap(),
+            host_zig,
+            extra_host_args,
+        );
 
         let output = std::process::Command::new(&dir.join("final"))
             .current_dir(dir)
@@ -1716,8 +2429,50 @@ mod tests {
             panic!("app.exe failed");
         }
 
-        let output = String::from_utf8_lossy(&output.stdout);
+        String::from_utf8_lossy(&output.stdout).to_string()
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn zig_host_app() {
+        assert_eq!("Hello foo\n", run_zig_host_app(HOST_ZIG, &[]));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn zig_host_app_bind_now() {
+        // with `-z now` the dynamic linker resolves every PLT slot at startup, including the
+        // ones for app functions, and the host also depends on a second shared library
+        assert_eq!(
+            "Hello foo\n",
+            run_zig_host_app(HOST_ZIG, &["-lm", "-z", "now"])
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn zig_host_app_thread_local() {
+        // the TLS segment is aligned to more than the shift for the extra program headers,
+        // and large enough that offsets into it look like addresses in the image
+        let host_zig = indoc!(
+            r#"
+            const std = @import("std");
+
+            extern fn roc_magic1(usize) callconv(.C) [*]const u8;
+
+            threadlocal var padding: [0x4000]u8 align(256) = undefined;
+            threadlocal var index: usize = 0;
+
+            pub fn main() !void {
+                padding[0x3fff] = 1;
+                index += padding[0x3fff] - 1;
+
+                const stdout = std.io.getStdOut().writer();
+                try stdout.print("Hello {s}\n", .{roc_magic1(index)[0..3]});
+            }
+            "#
+        );
 
-        assert_eq!("Hello foo\n", output);
+        assert_eq!("Hello foo\n", run_zig_host_app(host_zig, &[]));
     }
 }
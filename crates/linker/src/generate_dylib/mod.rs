@@ -14,7 +14,7 @@ pub(crate) use pe::APP_DLL;
 
 pub fn generate(target: &Triple, custom_names: &[String]) -> object::read::Result<Vec<u8>> {
     match target.binary_format {
-        target_lexicon::BinaryFormat::Elf => elf64::create_dylib_elf64(custom_names),
+        target_lexicon::BinaryFormat::Elf => elf64::create_dylib_elf64(custom_names, target),
         target_lexicon::BinaryFormat::Macho => macho::create_dylib_macho(custom_names, target),
         target_lexicon::BinaryFormat::Coff => Ok(pe::synthetic_dll(custom_names)),
         other => unimplemented!("dylib creation for {:?}", other),
@@ -7,10 +7,10 @@ use memmap2::{Mmap, MmapMut};
 use object::Object;
 use roc_build::link::{get_target_triple_str, rebuild_host, LinkType};
 use roc_error_macros::internal_error;
-use roc_load::{EntryPoint, ExecutionMode, LoadConfig, Threading};
+use roc_load::{EntryPoint, ExecutionMode, FloatWidth, LoadConfig, Threading};
 use roc_mono::ir::OptLevel;
 use roc_packaging::cache::RocCacheDir;
-use roc_reporting::report::{RenderTarget, DEFAULT_PALETTE};
+use roc_reporting::report::{Palette, RenderTarget};
 use std::cmp::Ordering;
 use std::mem;
 use std::path::{Path, PathBuf};
@@ -26,6 +26,13 @@ mod metadata;
 pub fn supported(link_type: LinkType, target: &Triple) -> bool {
     if let LinkType::Executable = link_type {
         match target {
+            // static musl binaries have no dynamic loader to preprocess the host against
+            Triple {
+                operating_system: target_lexicon::OperatingSystem::Linux,
+                environment: target_lexicon::Environment::Musl,
+                ..
+            } => false,
+
             Triple {
                 architecture: target_lexicon::Architecture::X86_64,
                 operating_system: target_lexicon::OperatingSystem::Linux,
@@ -33,12 +40,21 @@ pub fn supported(link_type: LinkType, target: &Triple) -> bool {
                 ..
             } => true,
 
-            // macho support is incomplete
             Triple {
+                architecture: target_lexicon::Architecture::Aarch64(_),
+                operating_system: target_lexicon::OperatingSystem::Linux,
+                binary_format: target_lexicon::BinaryFormat::Elf,
+                ..
+            } => true,
+
+            // Surgery rewrites x86_64 call instructions and applies only x86_64 relocations, so
+            // arm64 hosts (Apple silicon) use the legacy linker.
+            Triple {
+                architecture: target_lexicon::Architecture::X86_64,
                 operating_system: target_lexicon::OperatingSystem::Darwin,
                 binary_format: target_lexicon::BinaryFormat::Macho,
                 ..
-            } => false,
+            } => true,
 
             Triple {
                 architecture: target_lexicon::Architecture::X86_64,
@@ -76,21 +92,144 @@ pub fn build_and_preprocess_host(
 
     let stub_dll_symbols = make_stub_dll_symbols(exposed_to_host, exported_closure_types);
     generate_dynamic_lib(target, &stub_dll_symbols, &stub_lib);
-    rebuild_host(opt_level, target, host_input_path, Some(&stub_lib));
+    rebuild_host(opt_level, target, host_input_path, Some(&stub_lib), false);
 
-    let metadata = host_input_path.with_file_name(metadata_file_name(target));
-    // let prehost = host_input_path.with_file_name(preprocessed_host_filename(target).unwrap());
+    preprocess_host_if_stale(
+        target,
+        &dynhost,
+        host_input_path,
+        preprocessed_host_path,
+        &stub_lib,
+        &stub_dll_symbols,
+    );
+}
 
-    preprocess(
+/// Preprocess a platform's prebuilt `dynhost` if the preprocessed host next to it is
+/// missing or was generated from a different `dynhost` or by a different compiler version.
+/// Does nothing if the platform does not ship a `dynhost`.
+pub fn refresh_prebuilt_preprocessed_host(
+    target: &Triple,
+    host_input_path: &Path,
+    preprocessed_host_path: &Path,
+    exposed_to_host: Vec<String>,
+    exported_closure_types: Vec<String>,
+) {
+    let (stub_lib, dynhost) =
+        if let target_lexicon::OperatingSystem::Windows = target.operating_system {
+            (
+                host_input_path.with_file_name("libapp.dll"),
+                host_input_path.with_file_name("dynhost.exe"),
+            )
+        } else {
+            (
+                host_input_path.with_file_name("libapp.so"),
+                host_input_path.with_file_name("dynhost"),
+            )
+        };
+
+    if !dynhost.exists() {
+        return;
+    }
+
+    let stub_dll_symbols = make_stub_dll_symbols(exposed_to_host, exported_closure_types);
+
+    if !stub_lib.exists() {
+        generate_dynamic_lib(target, &stub_dll_symbols, &stub_lib);
+    }
+
+    preprocess_host_if_stale(
         target,
         &dynhost,
-        &metadata,
+        host_input_path,
         preprocessed_host_path,
         &stub_lib,
         &stub_dll_symbols,
+    );
+}
+
+/// Preprocessing is slow, so the preprocessed host is reused as long as it was generated from
+/// the same `dynhost` and stub symbols, by the same build of the compiler. The key it was
+/// generated with is stored in a file next to it.
+fn preprocess_host_if_stale(
+    target: &Triple,
+    dynhost: &Path,
+    host_input_path: &Path,
+    preprocessed_host_path: &Path,
+    stub_lib: &Path,
+    stub_dll_symbols: &[String],
+) {
+    let metadata = host_input_path.with_file_name(metadata_file_name(target));
+    let cache_key_path = preprocessed_host_cache_key_path(preprocessed_host_path);
+
+    let cache_key = match std::fs::read(dynhost) {
+        Ok(dynhost_bytes) => preprocessed_host_cache_key(target, &dynhost_bytes, stub_dll_symbols),
+        Err(e) => internal_error!("Failed to read {}: {e}", dynhost.display()),
+    };
+
+    if preprocessed_host_is_up_to_date(preprocessed_host_path, &metadata, &cache_key) {
+        return;
+    }
+
+    // remove the old key first, so an interrupted preprocess is not mistaken for a valid one
+    let _ = std::fs::remove_file(&cache_key_path);
+
+    preprocess(
+        target,
+        dynhost,
+        &metadata,
+        preprocessed_host_path,
+        stub_lib,
+        stub_dll_symbols,
         false,
         false,
-    )
+    );
+
+    // failing to write the key only means we preprocess again next time
+    let _ = std::fs::write(&cache_key_path, cache_key);
+}
+
+fn preprocessed_host_is_up_to_date(
+    preprocessed_host_path: &Path,
+    metadata: &Path,
+    cache_key: &str,
+) -> bool {
+    let cache_key_path = preprocessed_host_cache_key_path(preprocessed_host_path);
+
+    preprocessed_host_path.exists()
+        && metadata.exists()
+        && std::fs::read_to_string(cache_key_path).ok().as_deref() == Some(cache_key)
+}
+
+fn preprocessed_host_cache_key_path(preprocessed_host_path: &Path) -> PathBuf {
+    let mut path = preprocessed_host_path.as_os_str().to_owned();
+    path.push(".hash");
+
+    PathBuf::from(path)
+}
+
+fn preprocessed_host_cache_key(
+    target: &Triple,
+    dynhost_bytes: &[u8],
+    stub_dll_symbols: &[String],
+) -> String {
+    use roc_collections::StableHasher;
+    use std::hash::Hasher;
+
+    // the key is stored on disk, so it must not depend on the Rust release roc was built with
+    let mut hasher = StableHasher::new();
+
+    hasher.write_compiler();
+    hasher.write_str(&target.to_string());
+    hasher.write_u64(stub_dll_symbols.len() as u64);
+
+    for symbol in stub_dll_symbols {
+        hasher.write_str(symbol);
+    }
+
+    hasher.write_u64(dynhost_bytes.len() as u64);
+    hasher.write(dynhost_bytes);
+
+    format!("{:016x}", hasher.finish())
 }
 
 fn metadata_file_name(target: &Triple) -> String {
@@ -130,9 +269,11 @@ pub fn generate_stub_lib(
         LoadConfig {
             target_info,
             render: RenderTarget::Generic,
-            palette: DEFAULT_PALETTE,
+            palette: Palette::from_env(),
             threading: Threading::AllAvailable,
             exec_mode: ExecutionMode::Executable,
+            frac_default: FloatWidth::F64,
+            opt_level: OptLevel::Development,
         },
     )
     .unwrap_or_else(|problem| todo!("{:?}", problem));
@@ -454,6 +595,132 @@ fn surgery(
     }
 }
 
+/// Which side of the link is missing a symbol that the other side needs
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MissingSymbol {
+    /// The app references a symbol that the host does not define
+    FromHost,
+    /// The host calls a function that the app does not define
+    FromApp,
+}
+
+/// Report a symbol that one side of the link needs but the other does not provide, then exit.
+///
+/// This is almost always caused by an app and a host that were built against different
+/// versions of the platform, so we say which side is likely out of date rather than
+/// printing the raw relocation.
+pub(crate) fn report_missing_symbol<'a, I>(missing: MissingSymbol, name: &str, candidates: I) -> !
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    eprint!("{}", missing_symbol_message(missing, name, candidates));
+
+    std::process::exit(1);
+}
+
+fn missing_symbol_message<'a, I>(missing: MissingSymbol, name: &str, candidates: I) -> String
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    use std::fmt::Write;
+
+    let demangled = demangle(name);
+    let candidates: Vec<String> = candidates.into_iter().map(demangle).collect();
+    let suggestions =
+        roc_problem::suggest::best(&demangled, candidates.iter().map(|name| name.as_str()));
+    let mut message = String::new();
+
+    match missing {
+        MissingSymbol::FromHost => {
+            writeln!(
+                message,
+                "The app references `{demangled}`, but the host does not define it."
+            )
+        }
+        MissingSymbol::FromApp => {
+            writeln!(
+                message,
+                "The host calls `{demangled}`, but the app does not define it."
+            )
+        }
+    }
+    .unwrap();
+
+    if demangled != name {
+        writeln!(message, "(The symbol's full name is `{name}`.)").unwrap();
+    }
+
+    if !suggestions.is_empty() {
+        let side = match missing {
+            MissingSymbol::FromHost => "host",
+            MissingSymbol::FromApp => "app",
+        };
+
+        writeln!(message, "\nThese symbols in the {side} have similar names:").unwrap();
+        for suggestion in suggestions.iter() {
+            writeln!(message, "    {suggestion}").unwrap();
+        }
+    }
+
+    message.push('\n');
+    match missing {
+        MissingSymbol::FromHost => {
+            message.push_str(
+                "The host is likely out of date with the platform the app was built for.\n",
+            );
+            message.push_str("Try rebuilding the host with `--prebuilt-platform=false`.\n");
+        }
+        MissingSymbol::FromApp => {
+            message.push_str("The host was likely built against a different version of the platform's API than the app.\n");
+            message.push_str("Check that the app's platform matches the host, then rebuild the host with `--prebuilt-platform=false`.\n");
+        }
+    }
+
+    message
+}
+
+/// Demangle a Rust (legacy scheme) symbol name like `_ZN4core3fmt5write17h0123456789abcdefE`
+/// into `core::fmt::write`, with or without the extra leading underscore that Mach-O adds.
+/// Other names are returned unchanged.
+pub(crate) fn demangle(name: &str) -> String {
+    let unprefixed = name.strip_prefix('_').unwrap_or(name);
+
+    demangle_rust_legacy(unprefixed)
+        .or_else(|| demangle_rust_legacy(name))
+        .unwrap_or_else(|| name.to_string())
+}
+
+fn demangle_rust_legacy(name: &str) -> Option<String> {
+    let mut rest = name.strip_prefix("_ZN")?;
+    let mut segments = Vec::new();
+
+    while !rest.starts_with('E') {
+        let digits = rest.len() - rest.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let length: usize = rest[..digits].parse().ok()?;
+        let segment = rest.get(digits..digits + length)?;
+
+        segments.push(segment);
+        rest = &rest[digits + length..];
+    }
+
+    if rest != "E" || segments.is_empty() {
+        return None;
+    }
+
+    // The last segment is a hash that disambiguates crate versions; it's noise in a diagnostic.
+    if let Some(last) = segments.last() {
+        let is_hash = last.len() == 17
+            && last.starts_with('h')
+            && last[1..].chars().all(|c| c.is_ascii_hexdigit());
+
+        if is_hash && segments.len() > 1 {
+            segments.pop();
+        }
+    }
+
+    Some(segments.join("::"))
+}
+
 pub(crate) fn align_by_constraint(offset: usize, constraint: usize) -> usize {
     if offset % constraint == 0 {
         offset
@@ -594,3 +861,150 @@ mod windows_roc_platform_functions {
         libc::free(c_ptr)
     }
 }
+
+#[cfg(test)]
+mod test_demangle {
+    use super::{demangle, missing_symbol_message, MissingSymbol};
+
+    #[test]
+    fn rust_legacy() {
+        assert_eq!(
+            demangle("_ZN4core3fmt5write17h0123456789abcdefE"),
+            "core::fmt::write"
+        );
+        assert_eq!(
+            demangle("__ZN4core3fmt5write17h0123456789abcdefE"),
+            "core::fmt::write"
+        );
+    }
+
+    #[test]
+    fn unmangled() {
+        assert_eq!(
+            demangle("roc__mainForHost_1_exposed"),
+            "roc__mainForHost_1_exposed"
+        );
+        assert_eq!(demangle("_ZN3fooX"), "_ZN3fooX");
+    }
+
+    #[test]
+    fn missing_from_host() {
+        let message = missing_symbol_message(
+            MissingSymbol::FromHost,
+            "_ZN4host7effects7putLine17h0123456789abcdefE",
+            ["_ZN4host7effects8putLines17hfedcba9876543210E", "roc_alloc"],
+        );
+
+        assert_eq!(
+            message,
+            "The app references `host::effects::putLine`, but the host does not define it.\n\
+             (The symbol's full name is `_ZN4host7effects7putLine17h0123456789abcdefE`.)\n\
+             \n\
+             These symbols in the host have similar names:\n    \
+             host::effects::putLines\n    \
+             roc_alloc\n\
+             \n\
+             The host is likely out of date with the platform the app was built for.\n\
+             Try rebuilding the host with `--prebuilt-platform=false`.\n"
+        );
+    }
+
+    #[test]
+    fn missing_from_app() {
+        let message =
+            missing_symbol_message(MissingSymbol::FromApp, "roc__mainForHost_1_exposed", []);
+
+        assert_eq!(
+            message,
+            "The host calls `roc__mainForHost_1_exposed`, but the app does not define it.\n\
+             \n\
+             The host was likely built against a different version of the platform's API than the app.\n\
+             Check that the app's platform matches the host, then rebuild the host with `--prebuilt-platform=false`.\n"
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_preprocessed_host_cache {
+    use super::{
+        preprocessed_host_cache_key, preprocessed_host_cache_key_path,
+        preprocessed_host_is_up_to_date,
+    };
+    use target_lexicon::Triple;
+
+    fn key(host: &[u8], stub_dll_symbols: &[&str]) -> String {
+        let stub_dll_symbols: Vec<String> =
+            stub_dll_symbols.iter().map(|s| s.to_string()).collect();
+
+        preprocessed_host_cache_key(&Triple::host(), host, &stub_dll_symbols)
+    }
+
+    #[test]
+    fn key_changes_with_host_and_symbols() {
+        let original = key(b"host", &["roc__mainForHost_1_exposed"]);
+
+        assert_eq!(original, key(b"host", &["roc__mainForHost_1_exposed"]));
+        assert_ne!(original, key(b"host!", &["roc__mainForHost_1_exposed"]));
+        assert_ne!(
+            original,
+            key(b"host", &["roc__mainForHost_1_exposed", "roc_fx_a"])
+        );
+        assert_ne!(
+            key(b"host", &["ab", "c"]),
+            key(b"host", &["a", "bc"]),
+            "symbol names must not run together"
+        );
+    }
+
+    #[test]
+    fn changed_host_or_key_invalidates_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let preprocessed_host_path = dir.path().join("preprocessedhost");
+        let metadata = dir.path().join("metadata_linux-x64.rm1");
+        let cache_key_path = preprocessed_host_cache_key_path(&preprocessed_host_path);
+
+        let cache_key = key(b"host", &["roc__mainForHost_1_exposed"]);
+
+        std::fs::write(&preprocessed_host_path, b"preprocessed").unwrap();
+        std::fs::write(&metadata, b"metadata").unwrap();
+        std::fs::write(&cache_key_path, &cache_key).unwrap();
+
+        assert!(preprocessed_host_is_up_to_date(
+            &preprocessed_host_path,
+            &metadata,
+            &cache_key
+        ));
+
+        // the host was rebuilt
+        let new_key = key(b"rebuilt host", &["roc__mainForHost_1_exposed"]);
+        assert!(!preprocessed_host_is_up_to_date(
+            &preprocessed_host_path,
+            &metadata,
+            &new_key
+        ));
+
+        // the stored key is from an earlier compiler, or an interrupted preprocess removed it
+        std::fs::write(&cache_key_path, "0123456789abcdef").unwrap();
+        assert!(!preprocessed_host_is_up_to_date(
+            &preprocessed_host_path,
+            &metadata,
+            &cache_key
+        ));
+
+        std::fs::remove_file(&cache_key_path).unwrap();
+        assert!(!preprocessed_host_is_up_to_date(
+            &preprocessed_host_path,
+            &metadata,
+            &cache_key
+        ));
+
+        // the metadata is gone
+        std::fs::write(&cache_key_path, &cache_key).unwrap();
+        std::fs::remove_file(&metadata).unwrap();
+        assert!(!preprocessed_host_is_up_to_date(
+            &preprocessed_host_path,
+            &metadata,
+            &cache_key
+        ));
+    }
+}
@@ -8,7 +8,7 @@ use object::{
     SymbolIndex, SymbolSection,
 };
 use roc_collections::all::MutMap;
-use roc_error_macros::internal_error;
+use roc_error_macros::{internal_error, user_error};
 use std::ffi::CStr;
 use std::mem;
 use std::path::Path;
@@ -18,13 +18,12 @@ use target_lexicon::Triple;
 use crate::{
     align_by_constraint, align_to_offset_by_constraint, load_struct_inplace,
     load_struct_inplace_mut, load_structs_inplace, load_structs_inplace_mut, open_mmap,
-    open_mmap_mut,
+    open_mmap_mut, report_missing_symbol, MissingSymbol,
 };
 
 const MIN_SECTION_ALIGNMENT: usize = 0x40;
 
-// TODO: Analyze if this offset is always correct.
-const PLT_ADDRESS_OFFSET: u64 = 0x10;
+// The size of an x86_64 stub. The stubs section header also records it, and we prefer that.
 const STUB_ADDRESS_OFFSET: u64 = 0x06;
 
 // struct MachoDynamicDeps {
@@ -132,125 +131,174 @@ impl<'a> Surgeries<'a> {
             println!("Analyzing instuctions for branches");
         }
 
-        for text_section in text_sections {
-            self.append_text_section(object_bytes, &text_section, verbose)
-        }
-    }
+        // Every section is decoded on its own, so scan them in parallel. The results are merged
+        // in section order afterwards to keep the metadata deterministic.
+        let app_func_addresses = &self.app_func_addresses;
+        let scans: Vec<TextSectionScan> = std::thread::scope(|scope| {
+            let handles: Vec<_> = text_sections
+                .iter()
+                .map(|sec| scope.spawn(move || scan_text_section(sec, app_func_addresses)))
+                .collect();
+
+            handles
+                .into_iter()
+                .map(|handle| match handle.join() {
+                    Ok(scan) => scan,
+                    Err(payload) => std::panic::resume_unwind(payload),
+                })
+                .collect()
+        });
 
-    fn append_text_section(&mut self, object_bytes: &[u8], sec: &Section, verbose: bool) {
-        let (file_offset, compressed) = match sec.compressed_file_range() {
-            Ok(CompressedFileRange {
-                format: CompressionFormat::None,
-                offset,
-                ..
-            }) => (offset, false),
-            Ok(range) => (range.offset, true),
-            Err(err) => {
-                internal_error!(
-                    "Issues dealing with section compression for {:+x?}: {}",
-                    sec,
-                    err
-                );
+        for scan in scans {
+            for branch in scan.branches {
+                let entry = branch.entry;
+
+                if verbose {
+                    println!(
+                        "Found branch from {:+x} to {:+x}({})",
+                        branch.ip, branch.target, branch.func_name
+                    );
+                    println!(
+                        "\tNeed to surgically replace {} bytes at file offset {:+x}",
+                        entry.size, entry.file_offset,
+                    );
+                    println!(
+                        "\tIts current value is {:+x?}",
+                        &object_bytes[entry.file_offset as usize..][..entry.size as usize]
+                    )
+                }
+
+                self.surgeries
+                    .get_mut(branch.func_name)
+                    .unwrap()
+                    .push(entry);
             }
-        };
 
-        let data = match sec.uncompressed_data() {
-            Ok(data) => data,
-            Err(err) => {
-                internal_error!("Failed to load text section, {:+x?}: {}", sec, err);
+            if scan.has_indirect_branch && !self.indirect_warning_given && verbose {
+                self.indirect_warning_given = true;
+                println!();
+                println!("Cannot analyze through indirect jmp type instructions");
+                println!(
+                    "Most likely this is not a problem, but it could mean a loss in optimizations"
+                );
+                println!();
             }
-        };
-        let mut decoder = Decoder::with_ip(64, &data, sec.address(), DecoderOptions::NONE);
-        let mut inst = Instruction::default();
-
-        while decoder.can_decode() {
-            decoder.decode_out(&mut inst);
-
-            // Note: This gets really complex fast if we want to support more than basic calls/jumps.
-            // A lot of them have to load addresses into registers/memory so we would have to discover that value.
-            // Would probably require some static code analysis and would be impossible in some cases.
-            // As an alternative we can leave in the calls to the plt, but change the plt to jmp to the static function.
-            // That way any indirect call will just have the overhead of an extra jump.
-            match inst.try_op_kind(0) {
-                // Relative Offsets.
-                Ok(OpKind::NearBranch16 | OpKind::NearBranch32 | OpKind::NearBranch64) => {
-                    let target = inst.near_branch_target();
-                    if let Some(func_name) = self.app_func_addresses.get(&target) {
-                        if compressed {
-                            internal_error!("Surgical linking does not work with compressed text sections: {:+x?}", sec);
-                        }
+        }
+    }
+}
 
-                        if verbose {
-                            println!(
-                                "Found branch from {:+x} to {:+x}({})",
-                                inst.ip(),
-                                target,
-                                func_name
-                            );
-                        }
+struct BranchSurgery<'a> {
+    func_name: &'a str,
+    ip: u64,
+    target: u64,
+    entry: metadata::SurgeryEntry,
+}
 
-                        // TODO: Double check these offsets are always correct.
-                        // We may need to do a custom offset based on opcode instead.
-                        let op_kind = inst.op_code().try_op_kind(0).unwrap();
-                        let op_size: u8 = match op_kind {
-                            OpCodeOperandKind::br16_1 | OpCodeOperandKind::br32_1 => 1,
-                            OpCodeOperandKind::br16_2 => 2,
-                            OpCodeOperandKind::br32_4 | OpCodeOperandKind::br64_4 => 4,
-                            _ => {
-                                internal_error!(
-                                    "Ran into an unknown operand kind when analyzing branches: {:?}",
-                                    op_kind
-                                );
-                            }
-                        };
-                        let offset = inst.next_ip() - op_size as u64 - sec.address() + file_offset;
-                        if verbose {
-                            println!(
-                                "\tNeed to surgically replace {} bytes at file offset {:+x}",
-                                op_size, offset,
+#[derive(Default)]
+struct TextSectionScan<'a> {
+    branches: Vec<BranchSurgery<'a>>,
+    has_indirect_branch: bool,
+}
+
+fn scan_text_section<'a>(
+    sec: &Section,
+    app_func_addresses: &MutMap<u64, &'a str>,
+) -> TextSectionScan<'a> {
+    let (file_offset, compressed) = match sec.compressed_file_range() {
+        Ok(CompressedFileRange {
+            format: CompressionFormat::None,
+            offset,
+            ..
+        }) => (offset, false),
+        Ok(range) => (range.offset, true),
+        Err(err) => {
+            internal_error!(
+                "Issues dealing with section compression for {:+x?}: {}",
+                sec,
+                err
+            );
+        }
+    };
+
+    let data = match sec.uncompressed_data() {
+        Ok(data) => data,
+        Err(err) => {
+            internal_error!("Failed to load text section, {:+x?}: {}", sec, err);
+        }
+    };
+    let mut decoder = Decoder::with_ip(64, &data, sec.address(), DecoderOptions::NONE);
+    let mut inst = Instruction::default();
+    let mut scan = TextSectionScan::default();
+
+    while decoder.can_decode() {
+        decoder.decode_out(&mut inst);
+
+        // Note: This gets really complex fast if we want to support more than basic calls/jumps.
+        // A lot of them have to load addresses into registers/memory so we would have to discover that value.
+        // Would probably require some static code analysis and would be impossible in some cases.
+        // As an alternative we can leave in the calls to the plt, but change the plt to jmp to the static function.
+        // That way any indirect call will just have the overhead of an extra jump.
+        match inst.try_op_kind(0) {
+            // Relative Offsets.
+            Ok(OpKind::NearBranch16 | OpKind::NearBranch32 | OpKind::NearBranch64) => {
+                let target = inst.near_branch_target();
+                if let Some(&func_name) = app_func_addresses.get(&target) {
+                    if compressed {
+                        internal_error!(
+                            "Surgical linking does not work with compressed text sections: {:+x?}",
+                            sec
+                        );
+                    }
+
+                    // TODO: Double check these offsets are always correct.
+                    // We may need to do a custom offset based on opcode instead.
+                    let op_kind = inst.op_code().try_op_kind(0).unwrap();
+                    let op_size: u8 = match op_kind {
+                        OpCodeOperandKind::br16_1 | OpCodeOperandKind::br32_1 => 1,
+                        OpCodeOperandKind::br16_2 => 2,
+                        OpCodeOperandKind::br32_4 | OpCodeOperandKind::br64_4 => 4,
+                        _ => {
+                            internal_error!(
+                                "Ran into an unknown operand kind when analyzing branches: {:?}",
+                                op_kind
                             );
-                            println!(
-                                "\tIts current value is {:+x?}",
-                                &object_bytes[offset as usize..(offset + op_size as u64) as usize]
-                            )
                         }
-                        self.surgeries
-                            .get_mut(*func_name)
-                            .unwrap()
-                            .push(metadata::SurgeryEntry {
-                                file_offset: offset,
-                                virtual_offset: VirtualOffset::Relative(inst.next_ip()),
-                                size: op_size,
-                            });
-                    }
-                }
-                Ok(OpKind::FarBranch16 | OpKind::FarBranch32) => {
-                    internal_error!(
-                        "Found branch type instruction that is not yet support: {:+x?}",
-                        inst
-                    );
-                }
-                Ok(_) => {
-                    if (inst.is_call_far_indirect()
-                        || inst.is_call_near_indirect()
-                        || inst.is_jmp_far_indirect()
-                        || inst.is_jmp_near_indirect())
-                        && !self.indirect_warning_given
-                        && verbose
-                    {
-                        self.indirect_warning_given = true;
-                        println!();
-                        println!("Cannot analyze through indirect jmp type instructions");
-                        println!("Most likely this is not a problem, but it could mean a loss in optimizations");
-                        println!();
-                    }
+                    };
+                    let offset = inst.next_ip() - op_size as u64 - sec.address() + file_offset;
+                    scan.branches.push(BranchSurgery {
+                        func_name,
+                        ip: inst.ip(),
+                        target,
+                        entry: metadata::SurgeryEntry {
+                            file_offset: offset,
+                            virtual_offset: VirtualOffset::Relative(inst.next_ip()),
+                            size: op_size,
+                        },
+                    });
                 }
-                Err(err) => {
-                    internal_error!("Failed to decode assembly: {}", err);
+            }
+            Ok(OpKind::FarBranch16 | OpKind::FarBranch32) => {
+                internal_error!(
+                    "Found branch type instruction that is not yet support: {:+x?}",
+                    inst
+                );
+            }
+            Ok(_) => {
+                if inst.is_call_far_indirect()
+                    || inst.is_call_near_indirect()
+                    || inst.is_jmp_far_indirect()
+                    || inst.is_jmp_near_indirect()
+                {
+                    scan.has_indirect_branch = true;
                 }
             }
+            Err(err) => {
+                internal_error!("Failed to decode assembly: {}", err);
+            }
         }
     }
+
+    scan
 }
 
 /// Constructs a `metadata::Metadata` from a host executable binary, and writes it to disk
@@ -263,6 +311,13 @@ pub(crate) fn preprocess_macho(
     verbose: bool,
     time: bool,
 ) {
+    if target.architecture != target_lexicon::Architecture::X86_64 {
+        user_error!(
+            "The surgical linker only supports x86_64 macOS hosts, but this one is for {}. Use `--linker=legacy` instead.",
+            target.architecture
+        );
+    }
+
     let total_start = Instant::now();
     let exec_parsing_start = total_start;
     let exec_data = &*open_mmap(host_exe_path);
@@ -321,9 +376,10 @@ pub(crate) fn preprocess_macho(
 
     let mut app_func_addresses: MutMap<u64, &str> = MutMap::default();
     let mut macho_load_so_offset = None;
+    let mut app_dylib_ordinal = None;
 
     {
-        use macho::{DyldInfoCommand, DylibCommand, Section64, SegmentCommand64};
+        use macho::{DylibCommand, DysymtabCommand, Section64, SegmentCommand64};
 
         let exec_header = load_struct_inplace::<macho::MachHeader64<LittleEndian>>(exec_data, 0);
         let num_load_cmds = exec_header.ncmds.get(NativeEndian);
@@ -332,6 +388,7 @@ pub(crate) fn preprocess_macho(
 
         let mut stubs_symbol_index = None;
         let mut stubs_symbol_count = None;
+        let mut stub_size = STUB_ADDRESS_OFFSET;
 
         'cmds: for _ in 0..num_load_cmds {
             let info = load_struct_inplace::<macho::LoadCommand<LittleEndian>>(exec_data, offset);
@@ -352,9 +409,15 @@ pub(crate) fn preprocess_macho(
 
                     for section_info in sections_info {
                         if &section_info.sectname[0..7] == b"__stubs" {
+                            // reserved2 holds the size of a single stub for symbol stub sections.
+                            match section_info.reserved2.get(NativeEndian) {
+                                0 => {}
+                                size => stub_size = size as u64,
+                            }
+
                             stubs_symbol_index = Some(section_info.reserved1.get(NativeEndian));
                             stubs_symbol_count =
-                                Some(section_info.size.get(NativeEndian) / STUB_ADDRESS_OFFSET);
+                                Some(section_info.size.get(NativeEndian) / stub_size);
 
                             break 'cmds;
                         }
@@ -376,42 +439,68 @@ pub(crate) fn preprocess_macho(
         offset = mem::size_of_val(exec_header);
 
         let shared_lib_filename = shared_lib.file_name();
+        let mut dylib_ordinal = 0;
 
         for _ in 0..num_load_cmds {
             let info = load_struct_inplace::<macho::LoadCommand<LittleEndian>>(exec_data, offset);
             let cmd = info.cmd.get(NativeEndian);
             let cmdsize = info.cmdsize.get(NativeEndian);
 
-            if cmd == macho::LC_DYLD_INFO_ONLY {
-                let info = load_struct_inplace::<DyldInfoCommand<LittleEndian>>(exec_data, offset);
+            if cmd == macho::LC_DYSYMTAB {
+                let info = load_struct_inplace::<DysymtabCommand<LittleEndian>>(exec_data, offset);
 
-                let lazy_bind_offset = info.lazy_bind_off.get(NativeEndian) as usize;
-
-                let lazy_bind_symbols = mach_object::LazyBind::parse(
-                    &exec_data[lazy_bind_offset..],
-                    mem::size_of::<usize>(),
+                // Each stub has an entry in the indirect symbol table, starting at the index
+                // stored in the stubs section header. This works the same whether the host
+                // binds its imports with dyld info opcodes or with chained fixups.
+                let indirect_symbols = load_structs_inplace::<object::U32<LittleEndian>>(
+                    exec_data,
+                    info.indirectsymoff.get(NativeEndian) as usize,
+                    info.nindirectsyms.get(NativeEndian) as usize,
                 );
 
-                // Find all the lazily-bound roc symbols
+                // Find all the roc symbols that are called through a stub
                 // (e.g. "_roc__mainForHost_1_exposed")
-                // For Macho, we may need to deal with some GOT stuff here as well.
-                for (i, symbol) in lazy_bind_symbols
+                for (i, symbol_index) in indirect_symbols
+                    .iter()
                     .skip(stubs_symbol_index as usize)
                     .take(stubs_symbol_count as usize)
                     .enumerate()
                 {
+                    let symbol_index = symbol_index.get(NativeEndian);
+
+                    if symbol_index & (macho::INDIRECT_SYMBOL_LOCAL | macho::INDIRECT_SYMBOL_ABS)
+                        != 0
+                    {
+                        continue;
+                    }
+
                     if let Some(sym) = app_syms
                         .iter()
-                        .find(|app_sym| app_sym.name() == Ok(&symbol.name))
+                        .find(|app_sym| app_sym.index().0 == symbol_index as usize)
                     {
-                        let func_address = (i as u64 + 1) * STUB_ADDRESS_OFFSET + plt_address;
-                        let func_offset = (i as u64 + 1) * STUB_ADDRESS_OFFSET + plt_offset;
+                        let func_address = i as u64 * stub_size + plt_address;
+                        let func_offset = i as u64 * stub_size + plt_offset;
                         app_func_addresses.insert(func_address, sym.name().unwrap());
                         md.plt_addresses
                             .insert(sym.name().unwrap().to_string(), (func_offset, func_address));
                     }
                 }
-            } else if cmd == macho::LC_LOAD_DYLIB {
+            } else if matches!(
+                cmd,
+                macho::LC_LOAD_DYLIB
+                    | macho::LC_LOAD_WEAK_DYLIB
+                    | macho::LC_REEXPORT_DYLIB
+                    | macho::LC_LAZY_LOAD_DYLIB
+                    | macho::LC_LOAD_UPWARD_DYLIB
+            ) {
+                // Dylib ordinals count every command that loads a dylib, in order, starting at 1.
+                dylib_ordinal += 1;
+
+                if cmd != macho::LC_LOAD_DYLIB {
+                    offset += cmdsize as usize;
+                    continue;
+                }
+
                 let info = load_struct_inplace::<DylibCommand<LittleEndian>>(exec_data, offset);
                 let name_offset = info.dylib.name.offset.get(NativeEndian) as usize;
                 let str_start_index = offset + name_offset;
@@ -443,6 +532,7 @@ pub(crate) fn preprocess_macho(
 
                 if path.file_name() == shared_lib_filename {
                     macho_load_so_offset = Some(offset);
+                    app_dylib_ordinal = Some(dylib_ordinal);
                 }
             }
 
@@ -518,6 +608,7 @@ pub(crate) fn preprocess_macho(
                     &mut md,
                     preprocessed_path,
                     macho_load_so_offset,
+                    app_dylib_ordinal.unwrap_or_default(),
                     target,
                     verbose,
                 )
@@ -584,8 +675,9 @@ fn gen_macho_le(
     md: &mut metadata::Metadata,
     out_filename: &Path,
     macho_load_so_offset: usize,
+    app_dylib_ordinal: u32,
     _target: &Triple,
-    _verbose: bool,
+    verbose: bool,
 ) -> MmapMut {
     // Just adding some extra context/useful info here.
     // I was talking to Jakub from the Zig team about macho linking and here are some useful comments:
@@ -603,10 +695,11 @@ fn gen_macho_le(
     let exec_header = load_struct_inplace::<macho::MachHeader64<LittleEndian>>(exec_data, 0);
     let num_load_cmds = exec_header.ncmds.get(NativeEndian);
     let size_of_cmds = exec_header.sizeofcmds.get(NativeEndian) as usize;
+    let end_of_cmds = size_of_cmds + mem::size_of_val(exec_header);
 
-    // Add a new text segment and data segment
-    let segment_cmd_size = mem::size_of::<SegmentCommand64<LittleEndian>>();
-    let section_size = mem::size_of::<Section64<LittleEndian>>();
+    // Surgery adds a segment for the app's data and one for its code, with one section each.
+    let new_cmds_size = 2 * mem::size_of::<SegmentCommand64<LittleEndian>>()
+        + 2 * mem::size_of::<Section64<LittleEndian>>();
 
     // We need the full command size, including the dynamic-length string at the end.
     // To get that, we need to load the command.
@@ -614,513 +707,338 @@ fn gen_macho_le(
         load_struct_inplace::<macho::LoadCommand<LittleEndian>>(exec_data, macho_load_so_offset);
     let total_cmd_size = info.cmdsize.get(NativeEndian) as usize;
 
-    // ======================== Important TODO ==========================
-    // TODO: we accidentally instroduced a big change here.
-    // We use a mix of added_bytes and md.added_byte_count.
-    // Theses should proabably be the same variable.
-    // Also, I just realized that I have been shifting a lot of virtual offsets.
-    // This should not be necessary. If we add a fully 4k of buffer to the file, all of the virtual offsets will still stay aligned.
-    // So a lot of the following work can probably be commented out if we fix that.
-    // Of coruse, it will cost about 4k bytes to a final executable.
-    // This is what the elf version currently does.
-    // Theoretically it is not needed (if we update all virtual addresses in the binary),
-    // but I definitely ran into problems with elf when not adding this extra buffering.
-
-    // Copy header and shift everything to enable more program sections.
-    let added_byte_count = ((2 * segment_cmd_size) + (2 * section_size) - total_cmd_size) as u64;
-    md.added_byte_count = added_byte_count
-        // add some alignment padding
-        + (MIN_SECTION_ALIGNMENT as u64 - added_byte_count % MIN_SECTION_ALIGNMENT as u64);
-
-    md.exec_len = exec_data.len() as u64 + md.added_byte_count;
+    // Linkers leave padding between the load commands and the first section, so tools like
+    // install_name_tool can edit the commands in place. We do the same: the new segment commands
+    // go into that padding, so no section moves and no file offset or virtual address changes.
+    let header_padding = first_section_offset(exec_data) - end_of_cmds;
+    if header_padding + total_cmd_size < new_cmds_size {
+        user_error!(
+            "The host executable only has {} bytes of padding after its load commands, but the surgical linker needs {}.\nRelink the host with `-headerpad_max_install_names`, or use `--linker=legacy`.",
+            header_padding,
+            new_cmds_size - total_cmd_size
+        );
+    }
+
+    md.added_byte_count = 0;
+    md.exec_len = exec_data.len() as u64;
 
     let mut out_mmap = open_mmap_mut(out_filename, md.exec_len as usize);
-    let end_of_cmds = size_of_cmds + mem::size_of_val(exec_header);
+    out_mmap.copy_from_slice(exec_data);
 
-    // "Delete" the dylib load command - by copying all the bytes before it
-    // and all the bytes after it, while skipping over its bytes.
+    // "Delete" the dylib load command - by sliding all the commands after it over its bytes.
     // It has a dynamic-length string at the end that we also need to delete,
     // in addition to the header.
-    out_mmap[..macho_load_so_offset].copy_from_slice(&exec_data[..macho_load_so_offset]);
-
-    out_mmap[macho_load_so_offset..end_of_cmds - total_cmd_size]
-        .copy_from_slice(&exec_data[macho_load_so_offset + total_cmd_size..end_of_cmds]);
-
-    // Copy the rest of the file, leaving a gap for the surgical linking to add our 2 commands
-    // (which happens after preprocessing), and some zero padding at the end for alignemnt.
-    // (It seems to cause bugs if that padding isn't there!)
-    let rest_of_data = &exec_data[end_of_cmds..];
-    let start_index = end_of_cmds + md.added_byte_count as usize;
-
-    out_mmap[start_index..start_index + rest_of_data.len()].copy_from_slice(rest_of_data);
+    out_mmap.copy_within(
+        macho_load_so_offset + total_cmd_size..end_of_cmds,
+        macho_load_so_offset,
+    );
+    out_mmap[end_of_cmds - total_cmd_size..end_of_cmds].fill(0);
 
     let out_header = load_struct_inplace_mut::<macho::MachHeader64<LittleEndian>>(&mut out_mmap, 0);
-
-    // TODO: this needs to change to adding the 2 new commands when we are ready.
-    // -1 because we're deleting 1 load command and then NOT adding 2 new ones.
-    {
-        let added_bytes = -(total_cmd_size as isize); // TODO: Change when add the new sections.
-        out_header.ncmds.set(LittleEndian, num_load_cmds - 1);
-        out_header
-            .sizeofcmds
-            .set(LittleEndian, (size_of_cmds as isize + added_bytes) as u32);
-    }
-
-    // Go through every command and shift it by added_bytes if it's absolute, unless it's inside the command header
+    out_header.ncmds.set(LittleEndian, num_load_cmds - 1);
+    out_header
+        .sizeofcmds
+        .set(LittleEndian, (size_of_cmds - total_cmd_size) as u32);
+
+    // With the app's dylib gone, every dylib loaded after it now has an ordinal one lower.
+    // Imports from the app itself get turned into weak flat lookups. They will never be found,
+    // so dyld binds them to null, but nothing uses them anymore: surgery redirects every call.
     let mut offset = mem::size_of_val(exec_header);
 
-    // TODO: Add this back in the future when needed.
-    // let cpu_type = match target.architecture {
-    //     target_lexicon::Architecture::X86_64 => macho::CPU_TYPE_X86_64,
-    //     target_lexicon::Architecture::Aarch64(_) => macho::CPU_TYPE_ARM64,
-    //     _ => {
-    //         // We should have verified this via supported() before calling this function
-    //         unreachable!()
-    //     }
-    // };
-
     // minus one because we "deleted" a load command
     for _ in 0..(num_load_cmds - 1) {
         let info = load_struct_inplace::<macho::LoadCommand<LittleEndian>>(&out_mmap, offset);
         let cmd_size = info.cmdsize.get(NativeEndian) as usize;
 
         match info.cmd.get(NativeEndian) {
-            macho::LC_SEGMENT_64 => {
-                let cmd = load_struct_inplace_mut::<macho::SegmentCommand64<LittleEndian>>(
-                    &mut out_mmap,
-                    offset,
-                );
-
-                // Ignore page zero, it never moves.
-                if cmd.segname == "__PAGEZERO\0\0\0\0\0\0".as_bytes()
-                    || cmd.vmaddr.get(NativeEndian) == 0
-                {
-                    offset += cmd_size;
-                    continue;
-                }
-
-                let old_file_offest = cmd.fileoff.get(NativeEndian);
-                // The segment with file offset zero also includes the header.
-                // As such, its file offset does not change.
-                // Instead, its file size should be increased.
-                if old_file_offest > 0 {
-                    cmd.fileoff
-                        .set(LittleEndian, old_file_offest + md.added_byte_count as u64);
-                    cmd.vmaddr.set(
-                        LittleEndian,
-                        cmd.vmaddr.get(NativeEndian) + md.added_byte_count as u64,
-                    );
-                } else {
-                    cmd.filesize.set(
-                        LittleEndian,
-                        cmd.filesize.get(NativeEndian) + md.added_byte_count as u64,
-                    );
-                    cmd.vmsize.set(
-                        LittleEndian,
-                        cmd.vmsize.get(NativeEndian) + md.added_byte_count as u64,
-                    );
-                }
-
-                // let num_sections = cmd.nsects.get(NativeEndian);
-                // let sections = load_structs_inplace_mut::<macho::Section64<LittleEndian>>(
-                //     &mut out_mmap,
-                //     offset + mem::size_of::<macho::SegmentCommand64<LittleEndian>>(),
-                //     num_sections as usize,
-                // );
-                // struct Relocation {
-                //     offset: u32,
-                //     num_relocations: u32,
-                // }
-
-                // let mut relocation_offsets = Vec::with_capacity(sections.len());
-
-                // for section in sections {
-                //     section.addr.set(
-                //         LittleEndian,
-                //         section.addr.get(NativeEndian) + md.added_byte_count as u64,
-                //     );
-
-                //     // If offset is zero, don't update it.
-                //     // Zero is used for things like BSS that don't exist in the file.
-                //     let old_offset = section.offset.get(NativeEndian);
-                //     if old_offset > 0 {
-                //         section
-                //             .offset
-                //             .set(LittleEndian, old_offset + md.added_byte_count as u32);
-                //     }
-
-                //     // dbg!(&section.reloff.get(NativeEndian));
-                //     // dbg!(section.reloff.get(NativeEndian) as i32);
-                //     // dbg!(&section);
-                //     // dbg!(&md.added_byte_count);
-                //     // dbg!(String::from_utf8_lossy(&section.sectname));
-                //     if section.nreloc.get(NativeEndian) > 0 {
-                //         section.reloff.set(
-                //             LittleEndian,
-                //             section.reloff.get(NativeEndian) + md.added_byte_count as u32,
-                //         );
-                //     }
-
-                //     relocation_offsets.push(Relocation {
-                //         offset: section.reloff.get(NativeEndian),
-                //         num_relocations: section.nreloc.get(NativeEndian),
-                //     });
-                // }
-
-                // TODO FIXME this is necessary for ARM, but seems to be broken. Skipping for now since we're just targeting x86
-                // for Relocation {
-                //     offset,
-                //     num_relocations,
-                // } in relocation_offsets
-                // {
-                //     let relos = load_structs_inplace_mut::<macho::Relocation<LittleEndian>>(
-                //         &mut out_mmap,
-                //         offset as usize,
-                //         num_relocations as usize,
-                //     );
-
-                //     // TODO this has never been tested, because scattered relocations only come up on ARM!
-                //     for relo in relos.iter_mut() {
-                //         if relo.r_scattered(LittleEndian, cpu_type) {
-                //             let mut scattered_info = relo.scattered_info(NativeEndian);
-
-                //             if !scattered_info.r_pcrel {
-                //                 scattered_info.r_value += md.added_byte_count as u32;
-
-                //                 let new_info = scattered_info.relocation(LittleEndian);
-
-                //                 relo.r_word0 = new_info.r_word0;
-                //                 relo.r_word1 = new_info.r_word1;
-                //             }
-                //         }
-                //     }
-                // }
-
-                // TODO this seems to be wrong and unnecessary, and should probably be deleted.
-                // offset += num_sections as usize * mem::size_of::<macho::Section64<LittleEndian>>();
-            }
             macho::LC_SYMTAB => {
-                let cmd = load_struct_inplace_mut::<macho::SymtabCommand<LittleEndian>>(
-                    &mut out_mmap,
-                    offset,
-                );
-
-                let sym_offset = cmd.symoff.get(NativeEndian);
-                let num_syms = cmd.nsyms.get(NativeEndian);
-
-                if num_syms > 0 {
-                    cmd.symoff
-                        .set(LittleEndian, sym_offset + md.added_byte_count as u32);
-                }
-
-                if cmd.strsize.get(NativeEndian) > 0 {
-                    cmd.stroff.set(
-                        LittleEndian,
-                        cmd.stroff.get(NativeEndian) + md.added_byte_count as u32,
-                    );
-                }
+                let cmd =
+                    load_struct_inplace::<macho::SymtabCommand<LittleEndian>>(&out_mmap, offset);
+                let sym_offset = cmd.symoff.get(NativeEndian) as usize;
+                let num_syms = cmd.nsyms.get(NativeEndian) as usize;
 
                 let table = load_structs_inplace_mut::<macho::Nlist64<LittleEndian>>(
                     &mut out_mmap,
-                    sym_offset as usize + md.added_byte_count as usize,
-                    num_syms as usize,
+                    sym_offset,
+                    num_syms,
                 );
 
                 for entry in table {
-                    let entry_type = entry.n_type & macho::N_TYPE;
-                    if entry_type == macho::N_ABS || entry_type == macho::N_SECT {
-                        entry.n_value.set(
-                            LittleEndian,
-                            entry.n_value.get(NativeEndian) + md.added_byte_count as u64,
-                        );
+                    if entry.n_type & macho::N_TYPE != macho::N_UNDF
+                        || entry.n_type & macho::N_EXT == 0
+                    {
+                        continue;
                     }
+
+                    let desc = entry.n_desc.get(NativeEndian);
+                    let ordinal = (desc >> 8) as u32;
+                    let new_desc = if ordinal == app_dylib_ordinal {
+                        (desc & 0xff)
+                            | macho::N_WEAK_REF
+                            | ((macho::DYNAMIC_LOOKUP_ORDINAL as u16) << 8)
+                    } else if ordinal > app_dylib_ordinal
+                        && ordinal <= macho::MAX_LIBRARY_ORDINAL as u32
+                    {
+                        desc - (1 << 8)
+                    } else {
+                        desc
+                    };
+
+                    entry.n_desc.set(LittleEndian, new_desc);
                 }
             }
-            macho::LC_DYSYMTAB => {
-                let cmd = load_struct_inplace_mut::<macho::DysymtabCommand<LittleEndian>>(
-                    &mut out_mmap,
-                    offset,
+            macho::LC_DYLD_CHAINED_FIXUPS => {
+                let cmd = load_struct_inplace::<macho::LinkeditDataCommand<LittleEndian>>(
+                    &out_mmap, offset,
                 );
+                let data_offset = cmd.dataoff.get(NativeEndian) as usize;
+                let data_size = cmd.datasize.get(NativeEndian) as usize;
 
-                if cmd.ntoc.get(NativeEndian) > 0 {
-                    cmd.tocoff.set(
-                        LittleEndian,
-                        cmd.tocoff.get(NativeEndian) + md.added_byte_count as u32,
-                    );
-                }
+                update_chained_fixup_imports(
+                    &mut out_mmap[data_offset..data_offset + data_size],
+                    app_dylib_ordinal,
+                );
+            }
+            macho::LC_DYLD_INFO | macho::LC_DYLD_INFO_ONLY => {
+                let cmd =
+                    load_struct_inplace::<macho::DyldInfoCommand<LittleEndian>>(&out_mmap, offset);
+                let binds = [
+                    (cmd.bind_off, cmd.bind_size),
+                    (cmd.lazy_bind_off, cmd.lazy_bind_size),
+                ]
+                .map(|(off, size)| {
+                    (
+                        off.get(NativeEndian) as usize,
+                        size.get(NativeEndian) as usize,
+                    )
+                });
 
-                if cmd.nmodtab.get(NativeEndian) > 0 {
-                    cmd.modtaboff.set(
-                        LittleEndian,
-                        cmd.modtaboff.get(NativeEndian) + md.added_byte_count as u32,
+                for (bind_offset, bind_size) in binds {
+                    update_bind_opcodes(
+                        &mut out_mmap[bind_offset..bind_offset + bind_size],
+                        app_dylib_ordinal,
                     );
                 }
+            }
+            _ => {}
+        }
 
-                if cmd.nextrefsyms.get(NativeEndian) > 0 {
-                    cmd.extrefsymoff.set(
-                        LittleEndian,
-                        cmd.extrefsymoff.get(NativeEndian) + md.added_byte_count as u32,
-                    );
-                }
+        offset += cmd_size;
+    }
 
-                if cmd.nindirectsyms.get(NativeEndian) > 0 {
-                    cmd.indirectsymoff.set(
-                        LittleEndian,
-                        cmd.indirectsymoff.get(NativeEndian) + md.added_byte_count as u32,
-                    );
-                }
+    if verbose {
+        println!(
+            "Header padding left for new load commands: {:+x}",
+            header_padding + total_cmd_size
+        );
+    }
 
-                if cmd.nextrel.get(NativeEndian) > 0 {
-                    cmd.extreloff.set(
-                        LittleEndian,
-                        cmd.extreloff.get(NativeEndian) + md.added_byte_count as u32,
-                    );
-                }
+    // cmd_loc should be where the last offset ended
+    md.macho_cmd_loc = offset as u64;
 
-                if cmd.nlocrel.get(NativeEndian) > 0 {
-                    cmd.locreloff.set(
-                        LittleEndian,
-                        cmd.locreloff.get(NativeEndian) + md.added_byte_count as u32,
-                    );
-                }
+    out_mmap
+}
 
-                // TODO maybe we need to update something else too - relocations maybe?
-                // I think this also has symbols that need to get moved around.
-                // Look at otool -I at least for the indirect symbols.
-            }
-            macho::LC_TWOLEVEL_HINTS => {
-                let cmd = load_struct_inplace_mut::<macho::TwolevelHintsCommand<LittleEndian>>(
-                    &mut out_mmap,
-                    offset,
-                );
+/// The file offset of the first section, which is where the load commands' padding ends.
+fn first_section_offset(exec_data: &[u8]) -> usize {
+    use macho::{Section64, SegmentCommand64};
 
-                if cmd.nhints.get(NativeEndian) > 0 {
-                    cmd.offset.set(
-                        LittleEndian,
-                        cmd.offset.get(NativeEndian) + md.added_byte_count as u32,
-                    );
-                }
-            }
-            macho::LC_FUNCTION_STARTS => {
-                let cmd = load_struct_inplace_mut::<macho::LinkeditDataCommand<LittleEndian>>(
-                    &mut out_mmap,
-                    offset,
-                );
+    let exec_header = load_struct_inplace::<macho::MachHeader64<LittleEndian>>(exec_data, 0);
+    let mut offset = mem::size_of_val(exec_header);
+    let mut first_section_offset = exec_data.len();
 
-                if cmd.datasize.get(NativeEndian) > 0 {
-                    cmd.dataoff.set(
-                        LittleEndian,
-                        cmd.dataoff.get(NativeEndian) + md.added_byte_count as u32,
-                    );
-                    // TODO: This lists the start of every function. Which, of course, have moved.
-                    // That being said, to my understanding this section is optional and may just be debug information.
-                    // As such, updating it should not be required.
-                    // It will be more work to update due to being in "DWARF-style ULEB128" values.
+    for _ in 0..exec_header.ncmds.get(NativeEndian) {
+        let info = load_struct_inplace::<macho::LoadCommand<LittleEndian>>(exec_data, offset);
+
+        if info.cmd.get(NativeEndian) == macho::LC_SEGMENT_64 {
+            let cmd = load_struct_inplace::<SegmentCommand64<LittleEndian>>(exec_data, offset);
+            let sections = load_structs_inplace::<Section64<LittleEndian>>(
+                exec_data,
+                offset + mem::size_of_val(cmd),
+                cmd.nsects.get(NativeEndian) as usize,
+            );
+
+            for section in sections {
+                let section_type = section.flags.get(NativeEndian) & macho::SECTION_TYPE;
+                let section_offset = section.offset.get(NativeEndian) as usize;
+
+                // Zero fill sections have no file contents, so their offset means nothing.
+                if section_offset > 0
+                    && section_type != macho::S_ZEROFILL
+                    && section_type != macho::S_GB_ZEROFILL
+                    && section_type != macho::S_THREAD_LOCAL_ZEROFILL
+                {
+                    first_section_offset = first_section_offset.min(section_offset);
                 }
             }
-            macho::LC_DATA_IN_CODE => {
-                let (offset, size) = {
-                    let cmd = load_struct_inplace_mut::<macho::LinkeditDataCommand<LittleEndian>>(
-                        &mut out_mmap,
-                        offset,
-                    );
+        }
 
-                    if cmd.datasize.get(NativeEndian) > 0 {
-                        cmd.dataoff.set(
-                            LittleEndian,
-                            cmd.dataoff.get(NativeEndian) + md.added_byte_count as u32,
-                        );
-                    }
-                    (
-                        cmd.dataoff.get(NativeEndian),
-                        cmd.datasize.get(NativeEndian),
-                    )
+        offset += info.cmdsize.get(NativeEndian) as usize;
+    }
+
+    first_section_offset
+}
+
+// These are from mach-o/fixup-chains.h
+const DYLD_CHAINED_IMPORT: u32 = 1;
+const DYLD_CHAINED_IMPORT_ADDEND: u32 = 2;
+const DYLD_CHAINED_IMPORT_ADDEND64: u32 = 3;
+
+/// Point imports from the app's dylib at a weak flat lookup,
+/// and renumber the ordinals of the dylibs loaded after it.
+fn update_chained_fixup_imports(fixups: &mut [u8], app_dylib_ordinal: u32) {
+    let read_u32 =
+        |bytes: &[u8], at: usize| u32::from_le_bytes(bytes[at..at + 4].try_into().unwrap());
+
+    // struct dyld_chained_fixups_header
+    let imports_offset = read_u32(fixups, 8) as usize;
+    let imports_count = read_u32(fixups, 16) as usize;
+    let imports_format = read_u32(fixups, 20);
+
+    match imports_format {
+        DYLD_CHAINED_IMPORT | DYLD_CHAINED_IMPORT_ADDEND => {
+            // lib_ordinal: 8, weak_import: 1, name_offset: 23
+            let stride = if imports_format == DYLD_CHAINED_IMPORT {
+                4
+            } else {
+                8
+            };
+
+            for i in 0..imports_count {
+                let at = imports_offset + i * stride;
+                let import = read_u32(fixups, at);
+                let ordinal = import & 0xff;
+
+                let import = if ordinal == app_dylib_ordinal {
+                    (import & !0x1ff) | (macho::BIND_SPECIAL_DYLIB_FLAT_LOOKUP as u8 as u32) | 0x100
+                } else if ordinal > app_dylib_ordinal
+                    && ordinal <= macho::MAX_LIBRARY_ORDINAL as u32
+                {
+                    import - 1
+                } else {
+                    import
                 };
 
-                // Update every data in code entry.
-                if size > 0 {
-                    let entry_size = mem::size_of::<macho::DataInCodeEntry<LittleEndian>>();
-                    let entries = load_structs_inplace_mut::<macho::DataInCodeEntry<LittleEndian>>(
-                        &mut out_mmap,
-                        offset as usize,
-                        size as usize / entry_size,
-                    );
-                    for entry in entries.iter_mut() {
-                        entry.offset.set(
-                            LittleEndian,
-                            entry.offset.get(NativeEndian) + md.added_byte_count as u32,
-                        )
-                    }
-                }
+                fixups[at..at + 4].copy_from_slice(&import.to_le_bytes());
             }
-            macho::LC_CODE_SIGNATURE
-            | macho::LC_SEGMENT_SPLIT_INFO
-            | macho::LC_DYLIB_CODE_SIGN_DRS
-            | macho::LC_LINKER_OPTIMIZATION_HINT
-            | macho::LC_DYLD_EXPORTS_TRIE
-            | macho::LC_DYLD_CHAINED_FIXUPS => {
-                let cmd = load_struct_inplace_mut::<macho::LinkeditDataCommand<LittleEndian>>(
-                    &mut out_mmap,
-                    offset,
-                );
+        }
+        DYLD_CHAINED_IMPORT_ADDEND64 => {
+            // lib_ordinal: 16, weak_import: 1, reserved: 15, name_offset: 32, addend: 64
+            for i in 0..imports_count {
+                let at = imports_offset + i * 16;
+                let import = u64::from_le_bytes(fixups[at..at + 8].try_into().unwrap());
+                let ordinal = (import & 0xffff) as u32;
+
+                let import = if ordinal == app_dylib_ordinal {
+                    (import & !0x1ffff)
+                        | (macho::BIND_SPECIAL_DYLIB_FLAT_LOOKUP as i16 as u16 as u64)
+                        | 0x10000
+                } else if ordinal > app_dylib_ordinal && ordinal < 0xfff0 {
+                    import - 1
+                } else {
+                    import
+                };
 
-                if cmd.datasize.get(NativeEndian) > 0 {
-                    cmd.dataoff.set(
-                        LittleEndian,
-                        cmd.dataoff.get(NativeEndian) + md.added_byte_count as u32,
-                    );
-                }
+                fixups[at..at + 8].copy_from_slice(&import.to_le_bytes());
             }
-            macho::LC_ENCRYPTION_INFO_64 => {
-                let cmd = load_struct_inplace_mut::<macho::EncryptionInfoCommand64<LittleEndian>>(
-                    &mut out_mmap,
-                    offset,
-                );
+        }
+        other => {
+            internal_error!("Unknown chained fixups import format: {}", other);
+        }
+    }
+}
 
-                if cmd.cryptsize.get(NativeEndian) > 0 {
-                    cmd.cryptoff.set(
-                        LittleEndian,
-                        cmd.cryptoff.get(NativeEndian) + md.added_byte_count as u32,
-                    );
-                }
-            }
-            macho::LC_DYLD_INFO | macho::LC_DYLD_INFO_ONLY => {
-                let cmd = load_struct_inplace_mut::<macho::DyldInfoCommand<LittleEndian>>(
-                    &mut out_mmap,
-                    offset,
-                );
+/// The dyld info equivalent of `update_chained_fixup_imports`, done in place on the bind opcodes.
+fn update_bind_opcodes(binds: &mut [u8], app_dylib_ordinal: u32) {
+    fn skip_leb128(bytes: &[u8], mut at: usize) -> usize {
+        while bytes[at] & 0x80 != 0 {
+            at += 1;
+        }
+        at + 1
+    }
 
-                if cmd.rebase_size.get(NativeEndian) > 0 {
-                    cmd.rebase_off.set(
-                        LittleEndian,
-                        cmd.rebase_off.get(NativeEndian) + md.added_byte_count as u32,
-                    );
+    let mut at = 0;
+    let mut binding_app_symbol = false;
+
+    while at < binds.len() {
+        let opcode = binds[at] & macho::BIND_OPCODE_MASK;
+        let immediate = binds[at] & macho::BIND_IMMEDIATE_MASK;
+        let opcode_at = at;
+        at += 1;
+
+        match opcode {
+            // Lazy binds end each entry with DONE, so keep going until the end of the data.
+            macho::BIND_OPCODE_DONE
+            | macho::BIND_OPCODE_SET_TYPE_IMM
+            | macho::BIND_OPCODE_DO_BIND
+            | macho::BIND_OPCODE_DO_BIND_ADD_ADDR_IMM_SCALED => {}
+            macho::BIND_OPCODE_SET_DYLIB_ORDINAL_IMM => {
+                let ordinal = immediate as u32;
+                binding_app_symbol = ordinal == app_dylib_ordinal;
+
+                if binding_app_symbol {
+                    binds[opcode_at] = macho::BIND_OPCODE_SET_DYLIB_SPECIAL_IMM
+                        | (macho::BIND_SPECIAL_DYLIB_FLAT_LOOKUP as u8
+                            & macho::BIND_IMMEDIATE_MASK);
+                } else if ordinal > app_dylib_ordinal {
+                    binds[opcode_at] = opcode | (immediate - 1);
                 }
-
-                if cmd.bind_size.get(NativeEndian) > 0 {
-                    cmd.bind_off.set(
-                        LittleEndian,
-                        cmd.bind_off.get(NativeEndian) + md.added_byte_count as u32,
-                    );
+            }
+            macho::BIND_OPCODE_SET_DYLIB_ORDINAL_ULEB => {
+                let end = skip_leb128(binds, at);
+                let mut ordinal = 0;
+                for (i, byte) in binds[at..end].iter().enumerate() {
+                    ordinal |= ((byte & 0x7f) as u32) << (7 * i);
                 }
 
-                if cmd.weak_bind_size.get(NativeEndian) > 0 {
-                    cmd.weak_bind_off.set(
-                        LittleEndian,
-                        cmd.weak_bind_off.get(NativeEndian) + md.added_byte_count as u32,
-                    );
-                }
+                binding_app_symbol = ordinal == app_dylib_ordinal;
 
-                if cmd.lazy_bind_size.get(NativeEndian) > 0 {
-                    cmd.lazy_bind_off.set(
-                        LittleEndian,
-                        cmd.lazy_bind_off.get(NativeEndian) + md.added_byte_count as u32,
-                    );
+                if binding_app_symbol {
+                    internal_error!("The surgical linker does not support hosts that load more than 15 dylibs before the app.");
+                } else if ordinal > app_dylib_ordinal {
+                    // Rewrite the ordinal with the same number of bytes, so nothing else moves.
+                    let mut value = ordinal - 1;
+                    for byte in binds[at..end].iter_mut() {
+                        *byte = (value & 0x7f) as u8 | (*byte & 0x80);
+                        value >>= 7;
+                    }
                 }
 
-                // TODO: Parse and update the related tables here.
-                // It is possible we may just need to delete things that point to stuff that will be in the roc app.
-                // We also may just be able to ignore it (lazy bindings should never run).
-                // This definitely has a list of virtual address that need to be updated.
-                // Some of them definitely will point to the roc app and should probably be removed.
-                // Also `xcrun dyldinfo` is useful for debugging this.
+                at = end;
             }
-            macho::LC_SYMSEG => {
-                let cmd = load_struct_inplace_mut::<macho::SymsegCommand<LittleEndian>>(
-                    &mut out_mmap,
-                    offset,
-                );
-
-                if cmd.size.get(NativeEndian) > 0 {
-                    cmd.offset.set(
-                        LittleEndian,
-                        cmd.offset.get(NativeEndian) + md.added_byte_count as u32,
-                    );
-                }
-            }
-            macho::LC_MAIN => {
-                let cmd = load_struct_inplace_mut::<macho::EntryPointCommand<LittleEndian>>(
-                    &mut out_mmap,
-                    offset,
-                );
-
-                cmd.entryoff.set(
-                    LittleEndian,
-                    cmd.entryoff.get(NativeEndian) + md.added_byte_count as u64,
-                );
+            macho::BIND_OPCODE_SET_DYLIB_SPECIAL_IMM => {
+                binding_app_symbol = false;
             }
-            macho::LC_NOTE => {
-                let cmd = load_struct_inplace_mut::<macho::NoteCommand<LittleEndian>>(
-                    &mut out_mmap,
-                    offset,
-                );
+            macho::BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM => {
+                if binding_app_symbol {
+                    binds[opcode_at] |= macho::BIND_SYMBOL_FLAGS_WEAK_IMPORT;
+                }
 
-                if cmd.size.get(NativeEndian) > 0 {
-                    cmd.offset.set(
-                        LittleEndian,
-                        cmd.offset.get(NativeEndian) + md.added_byte_count as u64,
-                    );
+                while binds[at] != 0 {
+                    at += 1;
                 }
+                at += 1;
             }
-            macho::LC_ID_DYLIB
-            | macho::LC_LOAD_WEAK_DYLIB
-            | macho::LC_LOAD_DYLIB
-            | macho::LC_REEXPORT_DYLIB
-            | macho::LC_SOURCE_VERSION
-            | macho::LC_IDENT
-            | macho::LC_LINKER_OPTION
-            | macho::LC_BUILD_VERSION
-            | macho::LC_VERSION_MIN_MACOSX
-            | macho::LC_VERSION_MIN_IPHONEOS
-            | macho::LC_VERSION_MIN_WATCHOS
-            | macho::LC_VERSION_MIN_TVOS
-            | macho::LC_UUID
-            | macho::LC_RPATH
-            | macho::LC_ID_DYLINKER
-            | macho::LC_LOAD_DYLINKER
-            | macho::LC_DYLD_ENVIRONMENT
-            | macho::LC_ROUTINES_64
-            | macho::LC_THREAD
-            | macho::LC_UNIXTHREAD
-            | macho::LC_PREBOUND_DYLIB
-            | macho::LC_SUB_FRAMEWORK
-            | macho::LC_SUB_CLIENT
-            | macho::LC_SUB_UMBRELLA
-            | macho::LC_SUB_LIBRARY => {
-                // These don't involve file offsets, so no change is needed for these.
-                // Just continue the loop!
+            macho::BIND_OPCODE_SET_ADDEND_SLEB
+            | macho::BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB
+            | macho::BIND_OPCODE_ADD_ADDR_ULEB
+            | macho::BIND_OPCODE_DO_BIND_ADD_ADDR_ULEB => {
+                at = skip_leb128(binds, at);
             }
-            macho::LC_PREBIND_CKSUM => {
-                // We don't *think* we need to change this, but
-                // maybe what we're doing will break checksums?
+            macho::BIND_OPCODE_DO_BIND_ULEB_TIMES_SKIPPING_ULEB => {
+                at = skip_leb128(binds, at);
+                at = skip_leb128(binds, at);
             }
-            macho::LC_SEGMENT | macho::LC_ROUTINES | macho::LC_ENCRYPTION_INFO => {
-                // These are 32-bit and unsuppoted
-                unreachable!();
+            macho::BIND_OPCODE_THREADED => {
+                if immediate == macho::BIND_SUBOPCODE_THREADED_SET_BIND_ORDINAL_TABLE_SIZE_ULEB {
+                    at = skip_leb128(binds, at);
+                }
             }
-            macho::LC_FVMFILE | macho::LC_IDFVMLIB | macho::LC_LOADFVMLIB => {
-                // These are obsolete and unsupported
-                unreachable!()
-            }
-            cmd => {
-                eprintln!(
-                    "- - - Unrecognized Mach-O command during linker preprocessing: 0x{:x?}",
-                    cmd
-                );
-                // panic!(
-                //     "Unrecognized Mach-O command during linker preprocessing: 0x{:x?}",
-                //     cmd
-                // );
+            other => {
+                internal_error!("Unknown Mach-O bind opcode: 0x{:x}", other);
             }
         }
-
-        offset += dbg!(cmd_size);
     }
-
-    // cmd_loc should be where the last offset ended
-    md.macho_cmd_loc = offset as u64;
-
-    out_mmap
 }
 
 // fn scan_macho_dynamic_deps(
@@ -1148,6 +1066,14 @@ pub(crate) fn surgery_macho(
         }
     };
 
+    // Surgery only knows how to apply x86_64 relocations; see `preprocess_macho`.
+    if app_obj.architecture() != object::Architecture::X86_64 {
+        user_error!(
+            "The surgical linker only supports x86_64 macOS apps, but this one is for {:?}. Use `--linker=legacy` instead.",
+            app_obj.architecture()
+        );
+    }
+
     let total_start = Instant::now();
 
     let loading_metadata_start = total_start;
@@ -1155,7 +1081,8 @@ pub(crate) fn surgery_macho(
     let loading_metadata_duration = loading_metadata_start.elapsed();
 
     let load_and_mmap_start = Instant::now();
-    let max_out_len = md.exec_len + roc_app_bytes.len() as u64 + md.load_align_constraint;
+    // Leave room for page aligning the app's code, its data, and the moved __LINKEDIT segment.
+    let max_out_len = md.exec_len + roc_app_bytes.len() as u64 + 3 * md.load_align_constraint;
     let mut exec_mmap = open_mmap_mut(executable_path, max_out_len as usize);
     let load_and_mmap_duration = load_and_mmap_start.elapsed();
 
@@ -1183,6 +1110,13 @@ pub(crate) fn surgery_macho(
     // Also drop files to to ensure data is fully written here.
     drop(exec_mmap);
 
+    // __LINKEDIT has to end the file, so drop the slack we mapped in case alignment needed it.
+    std::fs::OpenOptions::new()
+        .write(true)
+        .open(executable_path)
+        .and_then(|file| file.set_len(offset as u64))
+        .unwrap_or_else(|e| internal_error!("{}", e));
+
     let flushing_data_duration = flushing_data_start.elapsed();
 
     // Make sure the final executable has permision to execute.
@@ -1198,6 +1132,29 @@ pub(crate) fn surgery_macho(
         fs::set_permissions(executable_path, perms).unwrap_or_else(|e| internal_error!("{}", e));
     }
 
+    // The host's code signature no longer matches its contents, and macOS kills processes
+    // with an invalid signature. Re-sign ad-hoc, which is what the system linker does by default.
+    let signing_start = Instant::now();
+
+    #[cfg(target_os = "macos")]
+    {
+        let output = std::process::Command::new("codesign")
+            .args(["--force", "--sign", "-"])
+            .arg(executable_path)
+            .output()
+            .unwrap_or_else(|e| internal_error!("Failed to run codesign: {}", e));
+
+        if !output.status.success() {
+            internal_error!(
+                "Failed to sign {}:\n{}",
+                executable_path.display(),
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+    }
+
+    let signing_duration = signing_start.elapsed();
+
     let total_duration = total_start.elapsed();
 
     if verbose || time {
@@ -1206,11 +1163,13 @@ pub(crate) fn surgery_macho(
         report_timing("Loading and mmap-ing", load_and_mmap_duration);
         report_timing("Output Generation", out_gen_duration);
         report_timing("Flushing Data to Disk", flushing_data_duration);
+        report_timing("Code Signing", signing_duration);
 
         let sum = loading_metadata_duration
             + load_and_mmap_duration
             + out_gen_duration
-            + flushing_data_duration;
+            + flushing_data_duration
+            + signing_duration;
 
         report_timing("Other", total_duration.saturating_sub(sum));
         report_timing("Total", total_duration);
@@ -1228,16 +1187,30 @@ fn surgery_macho_help(
     offset_ref: &mut usize, // TODO return this instead of taking a mutable reference to it
     app_obj: object::File,
 ) {
-    let mut offset = align_by_constraint(md.exec_len as usize, MIN_SECTION_ALIGNMENT);
-    // let new_rodata_section_offset = offset;
-
-    // Align physical and virtual address of new segment.
-    let mut virt_offset = align_to_offset_by_constraint(
-        md.last_vaddr as usize,
-        offset,
-        md.load_align_constraint as usize,
-    );
+    let page_size = md.load_align_constraint as usize;
+    let linkedit_cmd_offset = find_linkedit_segment(exec_mmap);
+    let (linkedit_offset, linkedit_size, linkedit_vaddr) = {
+        let cmd = load_struct_inplace::<macho::SegmentCommand64<LittleEndian>>(
+            exec_mmap,
+            linkedit_cmd_offset,
+        );
+
+        (
+            cmd.fileoff.get(NativeEndian) as usize,
+            cmd.filesize.get(NativeEndian) as usize,
+            cmd.vmaddr.get(NativeEndian) as usize,
+        )
+    };
+
+    // The app goes where __LINKEDIT is now, and __LINKEDIT moves after the app.
+    // codesign refuses to sign anything where __LINKEDIT is not the last thing in the file.
+    // __LINKEDIT is page aligned in both the file and memory, so the app's segments start out aligned too.
+    let mut offset = linkedit_offset;
+    let mut virt_offset = linkedit_vaddr;
+    let new_rodata_section_offset = offset;
     let new_rodata_section_vaddr = virt_offset;
+    let mut new_text_section_offset = offset;
+    let mut new_text_section_vaddr = virt_offset;
     if verbose {
         println!();
         println!(
@@ -1284,6 +1257,14 @@ fn surgery_macho_help(
         .chain(bss_sections.iter())
         .chain(text_sections.iter())
     {
+        if sec.index() == text_sections[0].index() {
+            // Code gets a segment of its own, so that it can be mapped executable.
+            offset = align_by_constraint(offset, page_size);
+            virt_offset = align_to_offset_by_constraint(virt_offset, offset, page_size);
+            new_text_section_offset = offset;
+            new_text_section_vaddr = virt_offset;
+        }
+
         offset = align_by_constraint(offset, MIN_SECTION_ALIGNMENT);
         virt_offset =
             align_to_offset_by_constraint(virt_offset, offset, md.load_align_constraint as usize);
@@ -1327,13 +1308,22 @@ fn surgery_macho_help(
         println!("Found App Function Symbols: {:+x?}", app_func_vaddr_map);
     }
 
-    // let (new_text_section_offset, new_text_section_vaddr) = text_sections
-    //     .iter()
-    //     .map(|sec| section_offset_map.get(&sec.index()).unwrap())
-    //     .min()
-    //     .unwrap();
-    // let (new_text_section_offset, new_text_section_vaddr) =
-    //     (*new_text_section_offset, *new_text_section_vaddr);
+    let new_linkedit_offset = align_by_constraint(offset, page_size);
+    let new_linkedit_vaddr =
+        align_to_offset_by_constraint(virt_offset, new_linkedit_offset, page_size);
+
+    // Move __LINKEDIT out of the way before the app's sections get written over its old location.
+    exec_mmap.copy_within(
+        linkedit_offset..linkedit_offset + linkedit_size,
+        new_linkedit_offset,
+    );
+    exec_mmap[linkedit_offset..new_linkedit_offset].fill(0);
+    move_linkedit_segment(
+        exec_mmap,
+        linkedit_cmd_offset,
+        new_linkedit_offset,
+        new_linkedit_vaddr,
+    );
 
     // Move data and deal with relocations.
     for sec in rodata_sections
@@ -1431,6 +1421,12 @@ fn surgery_macho_help(
                     {
                         // Explicitly ignore some symbols that are currently always linked.
                         continue;
+                    } else if let Ok(sym) = app_obj.symbol_by_index(index) {
+                        report_missing_symbol(
+                            MissingSymbol::FromHost,
+                            sym.name().unwrap_or_default(),
+                            md.roc_symbol_vaddresses.keys().map(|name| name.as_str()),
+                        );
                     } else {
                         internal_error!(
                             "Undefined Symbol in relocation, {:+x?}: {:+x?}",
@@ -1449,100 +1445,71 @@ fn surgery_macho_help(
 
     // Flush app only data to speed up write to disk.
     exec_mmap
-        .flush_async_range(md.exec_len as usize, offset - md.exec_len as usize)
+        .flush_async_range(
+            new_rodata_section_offset,
+            offset - new_rodata_section_offset,
+        )
         .unwrap_or_else(|e| internal_error!("{}", e));
 
     // TODO: look into merging symbol tables, debug info, and eh frames to enable better debugger experience.
 
-    // let mut cmd_offset = md.macho_cmd_loc as usize;
-
-    // // Load this section (we made room for it earlier) and then mutate all its data to make it the desired command
-    // {
-    //     let cmd =
-    //         load_struct_inplace_mut::<macho::SegmentCommand64<LittleEndian>>(exec_mmap, cmd_offset);
-    //     let size_of_section = mem::size_of::<macho::Section64<LittleEndian>>() as u32;
-    //     let size_of_cmd = mem::size_of_val(cmd);
-
-    //     cmd_offset += size_of_cmd;
-
-    //     cmd.cmd.set(LittleEndian, macho::LC_SEGMENT_64);
-    //     cmd.cmdsize
-    //         .set(LittleEndian, size_of_section + size_of_cmd as u32);
-    //     cmd.segname = *b"__DATA_CONST\0\0\0\0";
-    //     cmd.vmaddr
-    //         .set(LittleEndian, new_rodata_section_vaddr as u64);
-    //     cmd.vmsize.set(
-    //         LittleEndian,
-    //         (new_text_section_vaddr - new_rodata_section_vaddr) as u64,
-    //     );
-    //     cmd.fileoff
-    //         .set(LittleEndian, new_rodata_section_offset as u64);
-    //     cmd.filesize.set(
-    //         LittleEndian,
-    //         (new_text_section_offset - new_rodata_section_offset) as u64,
-    //     );
-    //     cmd.nsects.set(LittleEndian, 1);
-    //     cmd.maxprot.set(LittleEndian, 0x00000003);
-    //     cmd.initprot.set(LittleEndian, 0x00000003);
-
-    //     // TODO set protection
-    // }
-
-    // {
-    //     let cmd = load_struct_inplace_mut::<macho::Section64<LittleEndian>>(exec_mmap, cmd_offset);
-    //     let size_of_cmd = mem::size_of_val(cmd);
-
-    //     cmd_offset += size_of_cmd;
-
-    //     cmd.sectname = *b"__const\0\0\0\0\0\0\0\0\0";
-    //     cmd.segname = *b"__DATA_CONST\0\0\0\0";
-    //     cmd.addr.set(LittleEndian, new_rodata_section_vaddr as u64);
-    //     cmd.size.set(
-    //         LittleEndian,
-    //         (new_text_section_offset - new_rodata_section_offset) as u64,
-    //     );
-    //     cmd.offset.set(LittleEndian, 0); // TODO is this offset since the start of the file, or segment offset?
-    //     cmd.align.set(LittleEndian, 12); // TODO should this be 4096?
-    //     cmd.reloff.set(LittleEndian, 264); // TODO this should NOT be hardcoded! Should get it from somewhere.
-    // }
-
-    // {
-    //     let cmd =
-    //         load_struct_inplace_mut::<macho::SegmentCommand64<LittleEndian>>(exec_mmap, cmd_offset);
-    //     let size_of_section = mem::size_of::<macho::Section64<LittleEndian>>() as u32;
-    //     let size_of_cmd = mem::size_of_val(cmd);
-
-    //     cmd_offset += size_of_cmd;
-
-    //     cmd.cmd.set(LittleEndian, macho::LC_SEGMENT_64);
-    //     cmd.cmdsize
-    //         .set(LittleEndian, size_of_section + size_of_cmd as u32);
-    //     cmd.segname = *b"__TEXT\0\0\0\0\0\0\0\0\0\0";
-    //     cmd.vmaddr.set(LittleEndian, new_text_section_vaddr as u64);
-    //     cmd.vmsize
-    //         .set(LittleEndian, (offset - new_text_section_offset) as u64);
-    //     cmd.fileoff
-    //         .set(LittleEndian, new_text_section_offset as u64);
-    //     cmd.filesize
-    //         .set(LittleEndian, (offset - new_text_section_offset) as u64);
-    //     cmd.nsects.set(LittleEndian, 1);
-    //     cmd.maxprot.set(LittleEndian, 0x00000005); // this is what a zig-generated host had
-    //     cmd.initprot.set(LittleEndian, 0x00000005); // this is what a zig-generated host had
-    // }
-
-    // {
-    //     let cmd = load_struct_inplace_mut::<macho::Section64<LittleEndian>>(exec_mmap, cmd_offset);
-
-    //     cmd.segname = *b"__TEXT\0\0\0\0\0\0\0\0\0\0";
-    //     cmd.sectname = *b"__text\0\0\0\0\0\0\0\0\0\0";
-    //     cmd.addr.set(LittleEndian, new_text_section_vaddr as u64);
-    //     cmd.size
-    //         .set(LittleEndian, (offset - new_text_section_offset) as u64);
-    //     cmd.offset.set(LittleEndian, 0); // TODO is this offset since the start of the file, or segment offset?
-    //     cmd.align.set(LittleEndian, 12); // TODO this is 4096 (2^12) - which load_align_constraint does, above - but should it?
-    //     cmd.flags.set(LittleEndian, 0x80000400); // TODO this is what a zig-generated host had
-    //     cmd.reloff.set(LittleEndian, 264); // TODO this should NOT be hardcoded! Should get it from somewhere.
-    // }
+    // Map the app's sections by adding a segment for each of them. They go right before the
+    // __LINKEDIT segment command, so that segment commands stay in address order.
+    // Preprocessing made sure there is enough padding after the load commands for them.
+    let mut new_segments = Vec::with_capacity(2);
+
+    if new_text_section_vaddr > new_rodata_section_vaddr {
+        new_segments.push(NewSegment {
+            segname: *b"__ROC_DATA\0\0\0\0\0\0",
+            sectname: *b"__const\0\0\0\0\0\0\0\0\0",
+            fileoff: new_rodata_section_offset,
+            filesize: new_text_section_offset - new_rodata_section_offset,
+            vmaddr: new_rodata_section_vaddr,
+            vmsize: new_text_section_vaddr - new_rodata_section_vaddr,
+            // Read and write, since bss lives here too.
+            prot: macho::VM_PROT_READ | macho::VM_PROT_WRITE,
+            section_flags: macho::S_REGULAR,
+        });
+    }
+
+    new_segments.push(NewSegment {
+        segname: *b"__ROC_TEXT\0\0\0\0\0\0",
+        sectname: *b"__text\0\0\0\0\0\0\0\0\0\0",
+        fileoff: new_text_section_offset,
+        // Segments are contiguous and page sized, the same as the system linker lays them out.
+        filesize: new_linkedit_offset - new_text_section_offset,
+        vmaddr: new_text_section_vaddr,
+        vmsize: new_linkedit_vaddr - new_text_section_vaddr,
+        prot: macho::VM_PROT_READ | macho::VM_PROT_EXECUTE,
+        section_flags: macho::S_REGULAR
+            | macho::S_ATTR_PURE_INSTRUCTIONS
+            | macho::S_ATTR_SOME_INSTRUCTIONS,
+    });
+
+    let segment_cmd_size = mem::size_of::<macho::SegmentCommand64<LittleEndian>>()
+        + mem::size_of::<macho::Section64<LittleEndian>>();
+    let new_cmds_size = new_segments.len() * segment_cmd_size;
+
+    exec_mmap.copy_within(
+        linkedit_cmd_offset..md.macho_cmd_loc as usize,
+        linkedit_cmd_offset + new_cmds_size,
+    );
+
+    for (i, segment) in new_segments.iter().enumerate() {
+        segment.write(exec_mmap, linkedit_cmd_offset + i * segment_cmd_size);
+    }
+
+    {
+        let header = load_struct_inplace_mut::<macho::MachHeader64<LittleEndian>>(exec_mmap, 0);
+        header.ncmds.set(
+            LittleEndian,
+            header.ncmds.get(NativeEndian) + new_segments.len() as u32,
+        );
+        header.sizeofcmds.set(
+            LittleEndian,
+            header.sizeofcmds.get(NativeEndian) + new_cmds_size as u32,
+        );
+    }
 
     // Update calls from platform and dynamic symbols.
     // let dynsym_offset = md.dynamic_symbol_table_section_offset + md.added_byte_count;
@@ -1551,7 +1518,11 @@ fn surgery_macho_help(
         let func_virt_offset = match app_func_vaddr_map.get(func_name) {
             Some(offset) => *offset as u64,
             None => {
-                internal_error!("Function, {}, was not defined by the app", &func_name);
+                report_missing_symbol(
+                    MissingSymbol::FromApp,
+                    func_name,
+                    app_func_vaddr_map.keys().map(|name| name.as_str()),
+                );
             }
         };
         if verbose {
@@ -1611,7 +1582,7 @@ fn surgery_macho_help(
             let data = target.to_le_bytes();
             exec_mmap[plt_off] = 0xE9;
             exec_mmap[plt_off + 1..plt_off + jmp_inst_len].copy_from_slice(&data);
-            for i in jmp_inst_len..PLT_ADDRESS_OFFSET as usize {
+            for i in jmp_inst_len..STUB_ADDRESS_OFFSET as usize {
                 exec_mmap[plt_off + i] = 0x90;
             }
         }
@@ -1635,5 +1606,415 @@ fn surgery_macho_help(
         // }
     }
 
-    *offset_ref = offset;
+    *offset_ref = new_linkedit_offset + linkedit_size;
+}
+
+/// A segment surgery adds to map part of the app, with a single section covering all of it.
+struct NewSegment {
+    segname: [u8; 16],
+    sectname: [u8; 16],
+    fileoff: usize,
+    filesize: usize,
+    vmaddr: usize,
+    vmsize: usize,
+    prot: u32,
+    section_flags: u32,
+}
+
+impl NewSegment {
+    fn write(&self, exec_mmap: &mut [u8], cmd_offset: usize) {
+        let cmd =
+            load_struct_inplace_mut::<macho::SegmentCommand64<LittleEndian>>(exec_mmap, cmd_offset);
+        let size_of_cmd = mem::size_of_val(cmd);
+        let size_of_section = mem::size_of::<macho::Section64<LittleEndian>>();
+
+        cmd.cmd.set(LittleEndian, macho::LC_SEGMENT_64);
+        cmd.cmdsize
+            .set(LittleEndian, (size_of_cmd + size_of_section) as u32);
+        cmd.segname = self.segname;
+        cmd.vmaddr.set(LittleEndian, self.vmaddr as u64);
+        cmd.vmsize.set(LittleEndian, self.vmsize as u64);
+        cmd.fileoff.set(LittleEndian, self.fileoff as u64);
+        cmd.filesize.set(LittleEndian, self.filesize as u64);
+        cmd.maxprot.set(LittleEndian, self.prot);
+        cmd.initprot.set(LittleEndian, self.prot);
+        cmd.nsects.set(LittleEndian, 1);
+        cmd.flags.set(LittleEndian, 0);
+
+        let section = load_struct_inplace_mut::<macho::Section64<LittleEndian>>(
+            exec_mmap,
+            cmd_offset + size_of_cmd,
+        );
+
+        section.sectname = self.sectname;
+        section.segname = self.segname;
+        section.addr.set(LittleEndian, self.vmaddr as u64);
+        section.size.set(LittleEndian, self.filesize as u64);
+        section.offset.set(LittleEndian, self.fileoff as u32);
+        section
+            .align
+            .set(LittleEndian, MIN_SECTION_ALIGNMENT.trailing_zeros());
+        section.reloff.set(LittleEndian, 0);
+        section.nreloc.set(LittleEndian, 0);
+        section.flags.set(LittleEndian, self.section_flags);
+        section.reserved1.set(LittleEndian, 0);
+        section.reserved2.set(LittleEndian, 0);
+        section.reserved3.set(LittleEndian, 0);
+    }
+}
+
+/// The offset of the __LINKEDIT segment command.
+fn find_linkedit_segment(exec_data: &[u8]) -> usize {
+    let exec_header = load_struct_inplace::<macho::MachHeader64<LittleEndian>>(exec_data, 0);
+    let mut offset = mem::size_of_val(exec_header);
+
+    for _ in 0..exec_header.ncmds.get(NativeEndian) {
+        let info = load_struct_inplace::<macho::LoadCommand<LittleEndian>>(exec_data, offset);
+
+        if info.cmd.get(NativeEndian) == macho::LC_SEGMENT_64 {
+            let cmd =
+                load_struct_inplace::<macho::SegmentCommand64<LittleEndian>>(exec_data, offset);
+
+            if &cmd.segname[0..10] == b"__LINKEDIT" {
+                return offset;
+            }
+        }
+
+        offset += info.cmdsize.get(NativeEndian) as usize;
+    }
+
+    internal_error!("Host executable has no __LINKEDIT segment!");
+}
+
+/// Point the __LINKEDIT segment, and every load command with data in it, at a new location.
+fn move_linkedit_segment(
+    exec_mmap: &mut [u8],
+    linkedit_cmd_offset: usize,
+    new_offset: usize,
+    new_vaddr: usize,
+) {
+    let delta = {
+        let cmd = load_struct_inplace_mut::<macho::SegmentCommand64<LittleEndian>>(
+            exec_mmap,
+            linkedit_cmd_offset,
+        );
+        let delta = new_offset as u32 - cmd.fileoff.get(NativeEndian) as u32;
+
+        cmd.fileoff.set(LittleEndian, new_offset as u64);
+        cmd.vmaddr.set(LittleEndian, new_vaddr as u64);
+
+        delta
+    };
+
+    // Zero offsets mean there is no data, so leave those alone.
+    let shift = |field: &mut object::U32<LittleEndian>| {
+        let value = field.get(NativeEndian);
+        if value > 0 {
+            field.set(LittleEndian, value + delta);
+        }
+    };
+
+    let exec_header = load_struct_inplace::<macho::MachHeader64<LittleEndian>>(exec_mmap, 0);
+    let num_load_cmds = exec_header.ncmds.get(NativeEndian);
+    let mut offset = mem::size_of_val(exec_header);
+
+    for _ in 0..num_load_cmds {
+        let info = load_struct_inplace::<macho::LoadCommand<LittleEndian>>(exec_mmap, offset);
+        let cmd_size = info.cmdsize.get(NativeEndian) as usize;
+
+        match info.cmd.get(NativeEndian) {
+            macho::LC_SYMTAB => {
+                let cmd = load_struct_inplace_mut::<macho::SymtabCommand<LittleEndian>>(
+                    exec_mmap, offset,
+                );
+                shift(&mut cmd.symoff);
+                shift(&mut cmd.stroff);
+            }
+            macho::LC_DYSYMTAB => {
+                let cmd = load_struct_inplace_mut::<macho::DysymtabCommand<LittleEndian>>(
+                    exec_mmap, offset,
+                );
+                shift(&mut cmd.tocoff);
+                shift(&mut cmd.modtaboff);
+                shift(&mut cmd.extrefsymoff);
+                shift(&mut cmd.indirectsymoff);
+                shift(&mut cmd.extreloff);
+                shift(&mut cmd.locreloff);
+            }
+            macho::LC_TWOLEVEL_HINTS => {
+                let cmd = load_struct_inplace_mut::<macho::TwolevelHintsCommand<LittleEndian>>(
+                    exec_mmap, offset,
+                );
+                shift(&mut cmd.offset);
+            }
+            macho::LC_CODE_SIGNATURE
+            | macho::LC_SEGMENT_SPLIT_INFO
+            | macho::LC_FUNCTION_STARTS
+            | macho::LC_DATA_IN_CODE
+            | macho::LC_DYLIB_CODE_SIGN_DRS
+            | macho::LC_LINKER_OPTIMIZATION_HINT
+            | macho::LC_DYLD_EXPORTS_TRIE
+            | macho::LC_DYLD_CHAINED_FIXUPS => {
+                let cmd = load_struct_inplace_mut::<macho::LinkeditDataCommand<LittleEndian>>(
+                    exec_mmap, offset,
+                );
+                shift(&mut cmd.dataoff);
+            }
+            macho::LC_DYLD_INFO | macho::LC_DYLD_INFO_ONLY => {
+                let cmd = load_struct_inplace_mut::<macho::DyldInfoCommand<LittleEndian>>(
+                    exec_mmap, offset,
+                );
+                shift(&mut cmd.rebase_off);
+                shift(&mut cmd.bind_off);
+                shift(&mut cmd.weak_bind_off);
+                shift(&mut cmd.lazy_bind_off);
+                shift(&mut cmd.export_off);
+            }
+            _ => {}
+        }
+
+        offset += cmd_size;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use indoc::indoc;
+
+    #[test]
+    fn only_x86_64_macos_is_supported() {
+        use std::str::FromStr;
+
+        let supported = |triple: &str| {
+            crate::supported(
+                roc_build::link::LinkType::Executable,
+                &Triple::from_str(triple).unwrap(),
+            )
+        };
+
+        assert!(supported("x86_64-apple-darwin"));
+        assert!(!supported("aarch64-apple-darwin"));
+    }
+
+    #[test]
+    fn chained_fixup_imports_from_the_app_become_flat_lookups() {
+        // struct dyld_chained_fixups_header, followed by three DYLD_CHAINED_IMPORTs
+        let imports_offset = 28u32;
+        let mut fixups = Vec::new();
+        for field in [0, 0, imports_offset, 0, 3, DYLD_CHAINED_IMPORT, 0] {
+            fixups.extend_from_slice(&field.to_le_bytes());
+        }
+
+        // lib_ordinal: 8, weak_import: 1, name_offset: 23
+        let import = |ordinal: u32, name_offset: u32| ordinal | (name_offset << 9);
+        for (ordinal, name_offset) in [(1, 10), (2, 20), (3, 30)] {
+            fixups.extend_from_slice(&import(ordinal, name_offset).to_le_bytes());
+        }
+
+        update_chained_fixup_imports(&mut fixups, 2);
+
+        let imports: Vec<u32> = fixups[imports_offset as usize..]
+            .chunks(4)
+            .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap()))
+            .collect();
+
+        let flat_lookup = macho::BIND_SPECIAL_DYLIB_FLAT_LOOKUP as u8 as u32;
+        assert_eq!(
+            imports,
+            [
+                import(1, 10),
+                import(flat_lookup, 20) | 0x100,
+                import(2, 30)
+            ]
+        );
+    }
+
+    #[test]
+    fn bind_opcodes_for_the_app_become_weak_flat_lookups() {
+        let mut binds = Vec::new();
+
+        // a symbol from the app's dylib
+        binds.push(macho::BIND_OPCODE_SET_DYLIB_ORDINAL_IMM | 2);
+        binds.push(macho::BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM);
+        binds.extend_from_slice(b"_roc_magic1\0");
+        binds.push(macho::BIND_OPCODE_SET_TYPE_IMM | macho::BIND_TYPE_POINTER);
+        binds.extend_from_slice(&[
+            macho::BIND_OPCODE_SET_SEGMENT_AND_OFFSET_ULEB | 2,
+            0x90,
+            0x01,
+        ]);
+        binds.push(macho::BIND_OPCODE_DO_BIND);
+
+        // a symbol from a dylib loaded after it
+        let puts = binds.len();
+        binds.push(macho::BIND_OPCODE_SET_DYLIB_ORDINAL_IMM | 3);
+        binds.push(macho::BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM);
+        binds.extend_from_slice(b"_puts\0");
+        binds.push(macho::BIND_OPCODE_DO_BIND);
+
+        // a symbol from a dylib loaded after it, with an ordinal that doesn't fit the immediate
+        let cos = binds.len();
+        binds.extend_from_slice(&[macho::BIND_OPCODE_SET_DYLIB_ORDINAL_ULEB, 0x80 | 16, 0x00]);
+        binds.push(macho::BIND_OPCODE_SET_SYMBOL_TRAILING_FLAGS_IMM);
+        binds.extend_from_slice(b"_cos\0");
+        binds.push(macho::BIND_OPCODE_DO_BIND);
+        binds.push(macho::BIND_OPCODE_DONE);
+
+        let mut expected = binds.clone();
+        let flat_lookup = macho::BIND_SPECIAL_DYLIB_FLAT_LOOKUP as u8 & macho::BIND_IMMEDIATE_MASK;
+        expected[0] = macho::BIND_OPCODE_SET_DYLIB_SPECIAL_IMM | flat_lookup;
+        expected[1] |= macho::BIND_SYMBOL_FLAGS_WEAK_IMPORT;
+        expected[puts] = macho::BIND_OPCODE_SET_DYLIB_ORDINAL_IMM | 2;
+        expected[cos + 1] = 0x80 | 15;
+
+        update_bind_opcodes(&mut binds, 2);
+
+        assert_eq!(binds, expected);
+    }
+
+    #[allow(dead_code)]
+    const HOST_ZIG: &str = indoc!(
+        r#"
+        const std = @import("std");
+
+        extern fn roc_magic1(usize) callconv(.C) [*]const u8;
+
+        pub fn main() !void {
+            const stdout = std.io.getStdOut().writer();
+            try stdout.print("Hello {s}\n", .{roc_magic1(0)[0..3]});
+        }
+        "#
+    );
+
+    #[allow(dead_code)]
+    fn zig_host_app_help(dir: &Path, target: &Triple, host_zig: &str) {
+        let app_zig = indoc!(
+            r#"
+            const X = [_][]const u8 { "foo" };
+
+            export fn roc_magic1(index: usize) [*]const u8 {
+                return X[index].ptr;
+            }
+            "#
+        );
+
+        let zig = std::env::var("ROC_ZIG").unwrap_or_else(|_| "zig".into());
+        let zig_target = format!("{}-macos", target.architecture);
+
+        std::fs::write(dir.join("host.zig"), host_zig.as_bytes()).unwrap();
+        std::fs::write(dir.join("app.zig"), app_zig.as_bytes()).unwrap();
+
+        let run = |args: &[&str]| {
+            let output = std::process::Command::new(&zig)
+                .current_dir(dir)
+                .args(args)
+                .output()
+                .unwrap();
+
+            if !output.status.success() {
+                use std::io::Write;
+
+                std::io::stdout().write_all(&output.stdout).unwrap();
+                std::io::stderr().write_all(&output.stderr).unwrap();
+
+                panic!("zig {} failed", args[0]);
+            }
+        };
+
+        // we need to compile the app first
+        run(&[
+            "build-obj",
+            "app.zig",
+            "-fPIC",
+            "-target",
+            &zig_target,
+            "-OReleaseFast",
+        ]);
+
+        // open our app object; we'll copy sections from it later
+        let file = std::fs::File::open(dir.join("app.o")).unwrap();
+        let roc_app = unsafe { memmap2::Mmap::map(&file) }.unwrap();
+
+        // the dylib generator adds the leading underscore of Mach-O symbol names back
+        let names: Vec<String> = {
+            let object = object::File::parse(&*roc_app).unwrap();
+
+            object
+                .symbols()
+                .filter(|s| !s.is_local())
+                .map(|e| e.name().unwrap().trim_start_matches('_').to_string())
+                .collect()
+        };
+
+        let dylib_bytes = crate::generate_dylib::generate(target, &names).unwrap();
+        std::fs::write(dir.join("libapp.dylib"), dylib_bytes).unwrap();
+
+        // now we can compile the host (it uses libapp.dylib, hence the order here)
+        run(&[
+            "build-exe",
+            "libapp.dylib",
+            "host.zig",
+            "-fPIE",
+            "-lc",
+            "-target",
+            &zig_target,
+            "-OReleaseFast",
+        ]);
+
+        let preprocessed_host_filename =
+            dir.join(roc_build::link::preprocessed_host_filename(target).unwrap());
+
+        preprocess_macho(
+            target,
+            &dir.join("host"),
+            &dir.join("metadata"),
+            &preprocessed_host_filename,
+            &dir.join("libapp.dylib"),
+            false,
+            false,
+        );
+
+        std::fs::copy(&preprocessed_host_filename, &dir.join("final")).unwrap();
+
+        surgery_macho(
+            &roc_app,
+            &dir.join("metadata"),
+            &dir.join("final"),
+            false,
+            false,
+        );
+    }
+
+    #[cfg(all(target_os = "macos", target_arch = "x86_64"))]
+    #[test]
+    fn zig_host_app() {
+        use std::str::FromStr;
+
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+
+        zig_host_app_help(
+            dir,
+            &Triple::from_str("x86_64-apple-darwin").unwrap(),
+            HOST_ZIG,
+        );
+
+        let output = std::process::Command::new(&dir.join("final"))
+            .current_dir(dir)
+            .output()
+            .unwrap();
+
+        if !output.status.success() {
+            use std::io::Write;
+
+            std::io::stdout().write_all(&output.stdout).unwrap();
+            std::io::stderr().write_all(&output.stderr).unwrap();
+
+            panic!("app.exe failed");
+        }
+
+        assert_eq!("Hello foo\n", String::from_utf8_lossy(&output.stdout));
+    }
 }
@@ -61,6 +61,9 @@ struct PeMetadata {
 
     reloc_section_index: usize,
 
+    /// Size of the host's base relocation table, which starts at `reloc_offset_in_file`
+    reloc_directory_size: usize,
+
     /// Constants from the host .exe header
     image_base: u64,
     file_alignment: u32,
@@ -126,6 +129,11 @@ impl PeMetadata {
 
         let reloc_offset_in_file = reloc_section.file_range().unwrap().0 as usize;
 
+        let reloc_directory_size = dynhost_obj
+            .data_directory(pe::IMAGE_DIRECTORY_ENTRY_BASERELOC)
+            .map(|dir| dir.size.get(LE) as usize)
+            .unwrap_or_default();
+
         let optional_header = dynhost_obj.nt_headers().optional_header;
         let optional_header_offset = dynhost_obj.dos_header().nt_headers_offset() as usize
             + std::mem::size_of::<u32>()
@@ -175,6 +183,7 @@ impl PeMetadata {
             dummy_dll_thunk_section_virtual_address,
             reloc_offset_in_file,
             reloc_section_index,
+            reloc_directory_size,
         }
     }
 }
@@ -189,7 +198,7 @@ pub(crate) fn preprocess_windows(
 ) -> object::read::Result<()> {
     let data = open_mmap(host_exe_filename);
 
-    let new_sections = [*b".text\0\0\0", *b".rdata\0\0"];
+    let new_sections = [*b".text\0\0\0", *b".rdata\0\0", *b".reloc1\0"];
     let mut preprocessed = Preprocessor::preprocess(
         preprocessed_filename,
         &data,
@@ -302,7 +311,25 @@ pub(crate) fn surgery_pe(executable_path: &Path, metadata_path: &Path, roc_app_b
         .map(|s| next_multiple_of(s.bytes.len(), file_alignment))
         .sum();
 
-    let executable = &mut open_mmap_mut(executable_path, md.dynhost_file_size + app_sections_size);
+    // worst case, each absolute relocation needs its own block in the base relocation table
+    let absolute_relocations = app_obj_sections
+        .sections
+        .iter()
+        .flat_map(|s| s.relocations.values().flatten())
+        .filter(|r| r.relocation.kind() == object::RelocationKind::Absolute)
+        .count();
+    let base_relocations_size = next_multiple_of(
+        md.reloc_directory_size
+            + 4
+            + absolute_relocations
+                * (std::mem::size_of::<ImageBaseRelocation>() + 2 * std::mem::size_of::<u16>()),
+        file_alignment,
+    );
+
+    let executable = &mut open_mmap_mut(
+        executable_path,
+        md.dynhost_file_size + app_sections_size + base_relocations_size,
+    );
 
     let app_code_section_va = md.last_host_section_address
         + next_multiple_of(
@@ -317,8 +344,6 @@ pub(crate) fn surgery_pe(executable_path: &Path, metadata_path: &Path, roc_app_b
     let mut section_header_start = md.dynamic_relocations.section_headers_offset_in_file as usize
         + md.host_section_count * std::mem::size_of::<ImageSectionHeader>();
 
-    relocate_dummy_dll_entries(executable, &md);
-
     let mut code_bytes_added = 0;
     let mut data_bytes_added = 0;
     let mut file_bytes_added = 0;
@@ -332,6 +357,10 @@ pub(crate) fn surgery_pe(executable_path: &Path, metadata_path: &Path, roc_app_b
         section_alignment,
     );
 
+    // virtual addresses of absolute relocations in the app. The loader has to adjust these if
+    // it does not load the executable at its preferred image base.
+    let mut base_relocations = Vec::new();
+
     for kind in [SectionKind::Text, SectionKind::ReadOnlyData] {
         let length: usize = app_obj_sections
             .sections
@@ -388,6 +417,10 @@ pub(crate) fn surgery_pe(executable_path: &Path, metadata_path: &Path, roc_app_b
             let slice = section.bytes;
             executable[offset..][..slice.len()].copy_from_slice(slice);
 
+            // app sections of the same kind are concatenated, so this one may not be the first
+            let section_virtual_address =
+                section_virtual_address + (offset - section_file_offset) as u32;
+
             let it = section
                 .relocations
                 .iter()
@@ -400,26 +433,20 @@ pub(crate) fn surgery_pe(executable_path: &Path, metadata_path: &Path, roc_app_b
                     address,
                 } = app_relocation;
 
-                if let Some(destination) = md.exports.get(name) {
-                    match relocation.kind() {
-                        object::RelocationKind::Relative => {
-                            relocate_to(
-                                executable,
-                                offset + *offset_in_section as usize,
-                                destination
-                                    - section_virtual_address as i64
-                                    - *offset_in_section as i64,
-                                relocation,
-                            );
-                        }
-                        _ => todo!(),
-                    }
-                } else if let Some(destination) = inter_app_relocations.get(name) {
-                    relocate_to(
+                let destination = md
+                    .exports
+                    .get(name)
+                    .or_else(|| inter_app_relocations.get(name));
+
+                if let Some(destination) = destination {
+                    relocate_to_virtual_address(
                         executable,
                         offset + *offset_in_section as usize,
-                        destination - section_virtual_address as i64 - *offset_in_section as i64,
+                        section_virtual_address + *offset_in_section as u32,
+                        *destination,
                         relocation,
+                        image_base,
+                        &mut base_relocations,
                     );
                 } else if name == "___chkstk_ms" {
                     // this is a stack probe that is inserted when a function uses more than 2
@@ -467,6 +494,35 @@ pub(crate) fn surgery_pe(executable_path: &Path, metadata_path: &Path, roc_app_b
         file_bytes_added += next_multiple_of(length, section_alignment) as u32;
     }
 
+    // the host's base relocations followed by the app's, in a section of their own
+    {
+        let length = write_base_relocations(
+            executable,
+            &md,
+            section_file_offset,
+            section_virtual_address,
+            &base_relocations,
+        );
+        let size_of_raw_data = next_multiple_of(length, file_alignment) as u32;
+
+        data_bytes_added += size_of_raw_data;
+
+        write_section_header(
+            executable,
+            *b".reloc1\0",
+            pe::IMAGE_SCN_MEM_READ
+                | pe::IMAGE_SCN_CNT_INITIALIZED_DATA
+                | pe::IMAGE_SCN_MEM_DISCARDABLE,
+            section_header_start,
+            section_file_offset,
+            length as u32,
+            section_virtual_address,
+            size_of_raw_data,
+        );
+
+        file_bytes_added += next_multiple_of(length, section_alignment) as u32;
+    }
+
     update_optional_header(
         executable,
         md.optional_header_offset,
@@ -1289,8 +1345,64 @@ fn write_section_header(
     data[section_header_start..][..header_array.len()].copy_from_slice(&header_array);
 }
 
-fn relocate_dummy_dll_entries(executable: &mut [u8], md: &PeMetadata) {
-    // in the data directories, update the length of the base relocations
+/// Write a base relocation table with the host's entries and one `DIR64` entry for each of
+/// `app_relocations`, and point the base relocation data directory at it.
+/// Returns the size of the table, including trailing padding.
+fn write_base_relocations(
+    executable: &mut [u8],
+    md: &PeMetadata,
+    file_offset: usize,
+    virtual_address: u32,
+    app_relocations: &[u32],
+) -> usize {
+    const BLOCK_HEADER_SIZE: usize = std::mem::size_of::<ImageBaseRelocation>();
+    const PAGE_SIZE: u32 = 0x1000;
+
+    let host_size = md.reloc_directory_size;
+    executable.copy_within(
+        md.reloc_offset_in_file..md.reloc_offset_in_file + host_size,
+        file_offset,
+    );
+
+    let mut app_relocations = app_relocations.to_vec();
+    app_relocations.sort_unstable();
+
+    let mut offset = file_offset + host_size;
+    let mut relocations = app_relocations.as_slice();
+
+    // one block for each page that has relocations in it
+    while let Some(first) = relocations.first() {
+        let page = first & !(PAGE_SIZE - 1);
+        let count = relocations
+            .iter()
+            .take_while(|rva| *rva & !(PAGE_SIZE - 1) == page)
+            .count();
+
+        // blocks must be a multiple of 4 bytes; an entry of type IMAGE_REL_BASED_ABSOLUTE pads
+        let entries = next_multiple_of(count, 2);
+        let block_size = BLOCK_HEADER_SIZE + entries * std::mem::size_of::<u16>();
+
+        executable[offset..][..4].copy_from_slice(&page.to_le_bytes());
+        executable[offset + 4..][..4].copy_from_slice(&(block_size as u32).to_le_bytes());
+
+        for (i, rva) in relocations[..count].iter().enumerate() {
+            let entry = (pe::IMAGE_REL_BASED_DIR64 << 12) | (rva - page) as u16;
+            executable[offset + BLOCK_HEADER_SIZE + 2 * i..][..2]
+                .copy_from_slice(&entry.to_le_bytes());
+        }
+
+        offset += block_size;
+        relocations = &relocations[count..];
+    }
+
+    // for unclear reasons, we must bump the image directory size here.
+    // we also need some zeroed-out memory at the end, so if the directory
+    // ends at a multiple of `file_alignment`, pick the next one.
+    let new_reloc_directory_size =
+        next_multiple_of(offset - file_offset + 4, md.file_alignment as usize);
+    executable[offset..file_offset + new_reloc_directory_size].fill(0);
+
+    // in the data directories, point the base relocations at the new table
     let dir = load_struct_inplace_mut::<pe::ImageDataDirectory>(
         executable,
         md.dynamic_relocations.data_directories_offset_in_file as usize
@@ -1298,12 +1410,51 @@ fn relocate_dummy_dll_entries(executable: &mut [u8], md: &PeMetadata) {
                 * std::mem::size_of::<pe::ImageDataDirectory>(),
     );
 
-    // for unclear reasons, we must bump the image directory size here.
-    // we also need some zeroed-out memory at the end, so if the directory
-    // ends at a multiple of `file_alignment`, pick the next one.
-    let new_reloc_directory_size =
-        next_multiple_of(dir.size.get(LE) as usize + 4, md.file_alignment as usize);
+    dir.virtual_address.set(LE, virtual_address);
     dir.size.set(LE, new_reloc_directory_size as u32);
+
+    new_reloc_directory_size
+}
+
+/// Like `relocate_to`, but for relocations where we know both the virtual address of the
+/// relocation and of its target. Absolute relocations are recorded in `base_relocations`.
+fn relocate_to_virtual_address(
+    executable: &mut [u8],
+    file_offset: usize,
+    virtual_address: u32,
+    destination: i64,
+    relocation: &object::Relocation,
+    image_base: u64,
+    base_relocations: &mut Vec<u32>,
+) {
+    match relocation.kind() {
+        object::RelocationKind::Relative => {
+            relocate_to(
+                executable,
+                file_offset,
+                destination - virtual_address as i64,
+                relocation,
+            );
+        }
+        object::RelocationKind::Absolute => {
+            relocate_to(
+                executable,
+                file_offset,
+                image_base as i64 + destination,
+                relocation,
+            );
+
+            if relocation.size() == 64 {
+                base_relocations.push(virtual_address);
+            }
+        }
+        object::RelocationKind::ImageOffset => {
+            relocate_to(executable, file_offset, destination, relocation);
+        }
+        other => {
+            internal_error!("relocations of kind {other:?} are not supported")
+        }
+    }
 }
 
 /// Redirect `memcpy` and similar libc functions to their roc equivalents
@@ -1887,6 +2038,49 @@ mod test {
         assert_eq!("Hello foo\n", wine_test(test_internal_relocations))
     }
 
+    /// The table of pointers in this zig code sample is stored in the rodata section, and each
+    /// entry is an absolute relocation. These need entries in the base relocation table, so the
+    /// loader can fix them up when the executable is not loaded at its preferred address.
+    #[allow(dead_code)]
+    fn test_absolute_relocations(dir: &Path) {
+        zig_host_app(
+            dir,
+            indoc!(
+                r#"
+                const std = @import("std");
+
+                extern fn roc_magic1(usize) callconv(.C) [*:0]const u8;
+
+                pub fn main() !void {
+                    const stdout = std.io.getStdOut().writer();
+                    try stdout.print("Hello {s} {s}\n", .{roc_magic1(0), roc_magic1(1)});
+                }
+                "#
+            ),
+            indoc!(
+                r#"
+                const X = [_][*:0]const u8 { "foo", "bar" };
+
+                export fn roc_magic1(index: usize) [*:0]const u8 {
+                    return X[index];
+                }
+                "#
+            ),
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn app_absolute_relocations_windows() {
+        assert_eq!("Hello foo bar\n", windows_test(test_absolute_relocations))
+    }
+
+    #[ignore]
+    #[test]
+    fn app_absolute_relocations_wine() {
+        assert_eq!("Hello foo bar\n", wine_test(test_absolute_relocations))
+    }
+
     /// Run our preprocessing on an all-zig host. There is no app here to simplify things.
     fn preprocessing_help(dir: &Path) {
         let zig = std::env::var("ROC_ZIG").unwrap_or_else(|_| "zig".into());
@@ -1,5 +1,9 @@
 #[cfg(not(target_family = "wasm"))]
-use crate::https::{self, PackageMetadata, Problem};
+use crate::{
+    git::{self, GitPackage, GIT_URL_PREFIX},
+    https::{self, PackageMetadata, Problem},
+    lockfile::{LockedPackage, Lockfile},
+};
 use roc_error_macros::internal_error;
 use std::{
     fs,
@@ -29,11 +33,19 @@ pub enum RocCacheDir<'a> {
 ///
 /// Returns the path to the installed package (which will be in the cache dir somewhere), as well
 /// as the requested root module filename (optionally specified via the URL fragment).
+///
+/// Git URLs are installed by `install_git_package` instead. Either way, what the URL resolved
+/// to is recorded in the given lockfile.
 #[cfg(not(target_family = "wasm"))]
 pub fn install_package<'a>(
     roc_cache_dir: RocCacheDir<'_>,
     url: &'a str,
+    lockfile: &mut Lockfile,
 ) -> Result<(PathBuf, Option<&'a str>), Problem> {
+    if url.starts_with(GIT_URL_PREFIX) {
+        return install_git_package(roc_cache_dir, url, lockfile);
+    }
+
     let PackageMetadata {
         cache_subdir,
         content_hash,
@@ -42,6 +54,16 @@ pub fn install_package<'a>(
 
     match roc_cache_dir {
         RocCacheDir::Persistent(cache_dir) => {
+            // The URL already pins the hash, but recording it lets the lockfile describe
+            // every package the app depends on.
+            lockfile.insert(
+                url,
+                LockedPackage {
+                    commit: None,
+                    content_hash: content_hash.to_string(),
+                },
+            );
+
             // e.g. ~/.cache/roc/example.com/roc-packages/
             let parent_dir = cache_dir.join(cache_subdir);
             // e.g. ~/.cache/roc/example.com/roc-packages/jDRlAFAA3738vu3-vMpLUoyxtA86Z7CaZneoOKrihbE
@@ -66,31 +88,7 @@ pub fn install_package<'a>(
                 // The tarball name is the hash of its contents.
                 if downloaded_hash == content_hash {
                     // Now that we've verified the hash, rename the tempdir to the real dir.
-
-                    // Create the destination dir's parent dir, since it may not exist yet.
-                    fs::create_dir_all(parent_dir).map_err(Problem::IoErr)?;
-
-                    // This rename should be super cheap if it succeeds - just an inode change.
-                    if fs::rename(tempdir_path, &dest_dir).is_err() {
-                        // If the rename failed, try a recursive copy -
-                        // it could have failed due to std::io::ErrorKind::CrossesDevices
-                        // (e.g. if the source an destination directories are on different disks)
-                        // which as of this implementation is nightly-only
-                        // https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.CrossesDevices                       match io_err.kind() {
-                        // but if that's what happened, this should work!
-
-                        // fs_extra::dir::copy needs the destination directory to exist already.
-                        fs::create_dir(&dest_dir).map_err(Problem::IoErr)?;
-                        fs_extra::dir::copy(
-                            tempdir_path,
-                            &dest_dir,
-                            &fs_extra::dir::CopyOptions {
-                                content_only: true,
-                                ..Default::default()
-                            },
-                        )
-                        .map_err(Problem::FsExtraErr)?;
-                    }
+                    move_into_cache(tempdir_path, &parent_dir, &dest_dir)?;
 
                     // The package's files are now in the cache. We're done!
                     Ok((dest_dir, root_module_filename))
@@ -113,6 +111,111 @@ pub fn install_package<'a>(
     }
 }
 
+/// Like `install_package`, but for packages fetched with git. The first time we see the URL,
+/// its tag or revision is resolved to a commit, and that commit and the hash of its files are
+/// recorded in the lockfile. After that, we always check out the locked commit, and make sure
+/// its files still have the locked hash.
+///
+/// The cache is content-addressed like it is for tarballs, e.g. the package's files end up in
+/// ~/.cache/roc/github.com/roc-lang/basic-cli.git/jDRlAFAA3738vu3-vMpLUoyxtA86Z7CaZneoOKrihbE
+#[cfg(not(target_family = "wasm"))]
+pub fn install_git_package<'a>(
+    roc_cache_dir: RocCacheDir<'_>,
+    url: &'a str,
+    lockfile: &mut Lockfile,
+) -> Result<(PathBuf, Option<&'a str>), Problem> {
+    let package = GitPackage::try_from(url).map_err(Problem::InvalidUrl)?;
+    let locked = lockfile.get(url).cloned();
+
+    match roc_cache_dir {
+        RocCacheDir::Persistent(cache_dir) => {
+            let parent_dir = cache_dir.join(package.cache_subdir);
+
+            if let Some(locked) = &locked {
+                let dest_dir = parent_dir.join(&locked.content_hash);
+
+                // Same as for tarballs: if it's in the cache, it has the right contents.
+                if dest_dir.exists() {
+                    return Ok((dest_dir, package.root_module_filename));
+                }
+            }
+
+            println!(
+                "Fetching \u{001b}[36m{url}\u{001b}[0m\n    into {}\n",
+                cache_dir.display()
+            );
+            let tempdir = tempfile::tempdir().map_err(Problem::IoErr)?;
+            let checkout_dir = tempdir.path().join("checkout");
+            let locked_commit = locked.as_ref().and_then(|locked| locked.commit.as_deref());
+            let checkout = git::checkout(&package, locked_commit, &checkout_dir)?;
+
+            if let Some(locked) = &locked {
+                if locked.content_hash != checkout.content_hash {
+                    return Err(Problem::InvalidContentHash {
+                        expected: locked.content_hash.clone(),
+                        actual: checkout.content_hash,
+                    });
+                }
+            }
+
+            let dest_dir = parent_dir.join(&checkout.content_hash);
+
+            if !dest_dir.exists() {
+                move_into_cache(&checkout_dir, &parent_dir, &dest_dir)?;
+            }
+
+            lockfile.insert(
+                url,
+                LockedPackage {
+                    commit: Some(checkout.commit),
+                    content_hash: checkout.content_hash,
+                },
+            );
+
+            Ok((dest_dir, package.root_module_filename))
+        }
+        RocCacheDir::Disallowed => {
+            internal_error!(
+                "Tried to fetch a package ({:?}) via RocCacheDir::Disallowed - which was explicitly used in order to disallow downloading packages in the current context!",
+                url
+            )
+        }
+        #[cfg(test)]
+        RocCacheDir::Temp(temp_dir) => Ok((temp_dir.path().to_path_buf(), None)),
+    }
+}
+
+/// Move a package's files from a temporary directory to where they go in the cache
+#[cfg(not(target_family = "wasm"))]
+fn move_into_cache(src_dir: &Path, parent_dir: &Path, dest_dir: &Path) -> Result<(), Problem> {
+    // Create the destination dir's parent dir, since it may not exist yet.
+    fs::create_dir_all(parent_dir).map_err(Problem::IoErr)?;
+
+    // This rename should be super cheap if it succeeds - just an inode change.
+    if fs::rename(src_dir, dest_dir).is_err() {
+        // If the rename failed, try a recursive copy -
+        // it could have failed due to std::io::ErrorKind::CrossesDevices
+        // (e.g. if the source an destination directories are on different disks)
+        // which as of this implementation is nightly-only
+        // https://doc.rust-lang.org/std/io/enum.ErrorKind.html#variant.CrossesDevices
+        // but if that's what happened, this should work!
+
+        // fs_extra::dir::copy needs the destination directory to exist already.
+        fs::create_dir(dest_dir).map_err(Problem::IoErr)?;
+        fs_extra::dir::copy(
+            src_dir,
+            dest_dir,
+            &fs_extra::dir::CopyOptions {
+                content_only: true,
+                ..Default::default()
+            },
+        )
+        .map_err(Problem::FsExtraErr)?;
+    }
+
+    Ok(())
+}
+
 #[cfg(windows)]
 // e.g. the "Roc" in %APPDATA%\\Roc
 const ROC_CACHE_DIR_NAME: &str = "Roc";
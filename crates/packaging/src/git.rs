@@ -0,0 +1,283 @@
+use std::{fs, path::Path, process::Command};
+
+use walkdir::WalkDir;
+
+use crate::https::{is_within_cache_dir, Problem, UrlProblem};
+
+/// Packages fetched with git are written as e.g.
+///
+/// git+https://github.com/roc-lang/basic-cli.git?tag=0.2.0#main.roc
+///
+/// where the query picks a tag or a revision (any commit-ish git accepts, e.g. a commit hash),
+/// and the optional fragment picks the root module like it does for tarball URLs.
+pub const GIT_URL_PREFIX: &str = "git+";
+
+const HTTPS: &str = "https://";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitRef<'a> {
+    Tag(&'a str),
+    Rev(&'a str),
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GitPackage<'a> {
+    /// What we tell git to clone, e.g. https://github.com/roc-lang/basic-cli.git
+    pub repo_url: &'a str,
+    /// On disk, this will be the subfolder inside the cache dir where the package's versions live
+    pub cache_subdir: &'a str,
+    pub git_ref: GitRef<'a>,
+    /// Other code will default this to main.roc, but this module isn't concerned with that default.
+    pub root_module_filename: Option<&'a str>,
+}
+
+impl<'a> TryFrom<&'a str> for GitPackage<'a> {
+    type Error = UrlProblem;
+
+    fn try_from(url: &'a str) -> Result<Self, Self::Error> {
+        GitPackage::new(url)
+    }
+}
+
+impl<'a> GitPackage<'a> {
+    fn new(url: &'a str) -> Result<Self, UrlProblem> {
+        let without_prefix = url
+            .strip_prefix(GIT_URL_PREFIX)
+            .ok_or(UrlProblem::MissingGitPrefix)?;
+
+        let without_protocol = without_prefix
+            .strip_prefix(HTTPS)
+            .ok_or(UrlProblem::MissingHttps)?;
+
+        let (without_fragment, fragment) = match without_protocol.rsplit_once('#') {
+            Some((before_fragment, fragment)) => {
+                const EXT: &str = ".roc";
+
+                // The fragment must be a .roc file, and the part before ".roc" can't be empty
+                if fragment.ends_with(EXT) && fragment.len() > EXT.len() {
+                    (before_fragment, Some(fragment))
+                } else {
+                    return Err(UrlProblem::InvalidFragment(fragment.to_string()));
+                }
+            }
+            None => (without_protocol, None),
+        };
+
+        let (cache_subdir, query) = without_fragment
+            .split_once('?')
+            .ok_or(UrlProblem::MissingGitRef)?;
+
+        let git_ref = match query.split_once('=') {
+            Some(("tag", tag)) if is_valid_ref(tag) => GitRef::Tag(tag),
+            Some(("rev", rev)) if is_valid_ref(rev) => GitRef::Rev(rev),
+            _ => return Err(UrlProblem::InvalidGitRef(query.to_string())),
+        };
+
+        if cache_subdir.is_empty() {
+            return Err(UrlProblem::MissingHttps);
+        }
+
+        if !is_within_cache_dir(cache_subdir) {
+            return Err(UrlProblem::OutsideCacheDir(cache_subdir.to_string()));
+        }
+
+        // e.g. https://github.com/roc-lang/basic-cli.git
+        let repo_url = &without_prefix[..HTTPS.len() + cache_subdir.len()];
+
+        Ok(GitPackage {
+            repo_url,
+            cache_subdir,
+            git_ref,
+            root_module_filename: fragment,
+        })
+    }
+}
+
+/// Whether git will take this tag or revision as one, rather than as an option (like
+/// `--upload-pack=...`) or as several arguments
+fn is_valid_ref(git_ref: &str) -> bool {
+    !git_ref.is_empty()
+        && !git_ref.starts_with('-')
+        && !git_ref.chars().any(|c| c.is_whitespace() || c.is_control())
+}
+
+/// A git package checked out at an exact commit
+pub struct Checkout {
+    pub commit: String,
+    /// The base64url-encoded BLAKE3 hash of the checked-out files; see `hash_dir`
+    pub content_hash: String,
+}
+
+/// Check out the package into the given (empty) directory, at the locked commit if there
+/// is one and at the package's tag or revision otherwise. Git's own files are removed
+/// afterwards, so all that's left is the package's contents.
+pub fn checkout(
+    package: &GitPackage<'_>,
+    locked_commit: Option<&str>,
+    dest_dir: &Path,
+) -> Result<Checkout, Problem> {
+    let dest = dest_dir.to_string_lossy();
+    let dest: &str = &dest;
+
+    git(&[
+        "clone",
+        "--quiet",
+        "--no-checkout",
+        "--",
+        package.repo_url,
+        dest,
+    ])?;
+
+    let target = match (locked_commit, package.git_ref) {
+        // The lockfile is only meant to have commit hashes in it, but it's a file anyone can edit.
+        (Some(commit), _) if !commit.chars().all(|c| c.is_ascii_hexdigit()) => {
+            return Err(Problem::InvalidUrl(UrlProblem::InvalidGitRef(format!(
+                "rev={}",
+                commit
+            ))));
+        }
+        (Some(commit), _) => commit.to_string(),
+        (None, GitRef::Tag(tag)) => format!("refs/tags/{}", tag),
+        (None, GitRef::Rev(rev)) => rev.to_string(),
+    };
+
+    // The `--` makes git take the target as a revision even if there's a file by that name.
+    git(&["-C", dest, "checkout", "--quiet", "--detach", &target, "--"])?;
+
+    let commit = git(&["-C", dest, "rev-parse", "HEAD"])?;
+
+    fs::remove_dir_all(dest_dir.join(".git")).map_err(Problem::IoErr)?;
+
+    Ok(Checkout {
+        commit,
+        content_hash: hash_dir(dest_dir)?,
+    })
+}
+
+/// Run git, and return what it printed (without the trailing newline)
+fn git(args: &[&str]) -> Result<String, Problem> {
+    let output = Command::new("git")
+        .args(args)
+        .output()
+        .map_err(Problem::IoErr)?;
+
+    if output.status.success() {
+        Ok(String::from_utf8_lossy(&output.stdout)
+            .trim_end()
+            .to_string())
+    } else {
+        Err(Problem::GitErr {
+            command: format!("git {}", args.join(" ")),
+            stderr: String::from_utf8_lossy(&output.stderr)
+                .trim_end()
+                .to_string(),
+        })
+    }
+}
+
+/// The base64url-encoded BLAKE3 hash of every file in the directory and its path within it,
+/// so two checkouts have the same hash exactly when they have the same files.
+pub fn hash_dir(dir: &Path) -> Result<String, Problem> {
+    let mut hasher = blake3::Hasher::new();
+
+    for entry in WalkDir::new(dir).sort_by_file_name() {
+        let entry = entry.map_err(|err| Problem::IoErr(err.into()))?;
+
+        if !entry.file_type().is_file() {
+            continue;
+        }
+
+        let relative = entry
+            .path()
+            .strip_prefix(dir)
+            .unwrap_or_else(|_| entry.path());
+
+        // Always use / so the hash is the same on every OS
+        let relative = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        let contents = fs::read(entry.path()).map_err(Problem::IoErr)?;
+
+        hasher.update(relative.as_bytes());
+        hasher.update(&[0]);
+        hasher.update(&(contents.len() as u64).to_le_bytes());
+        hasher.update(&contents);
+    }
+
+    Ok(base64_url::encode(hasher.finalize().as_bytes()))
+}
+
+#[test]
+fn git_url_with_tag() {
+    let package =
+        GitPackage::try_from("git+https://github.com/roc-lang/basic-cli.git?tag=0.2.0#main.roc")
+            .unwrap();
+
+    assert_eq!(
+        package,
+        GitPackage {
+            repo_url: "https://github.com/roc-lang/basic-cli.git",
+            cache_subdir: "github.com/roc-lang/basic-cli.git",
+            git_ref: GitRef::Tag("0.2.0"),
+            root_module_filename: Some("main.roc"),
+        }
+    );
+}
+
+#[test]
+fn git_url_without_ref() {
+    assert!(matches!(
+        GitPackage::try_from("git+https://github.com/roc-lang/basic-cli.git"),
+        Err(UrlProblem::MissingGitRef)
+    ));
+}
+
+#[test]
+fn git_url_with_option_as_ref() {
+    assert!(matches!(
+        GitPackage::try_from(
+            "git+https://github.com/roc-lang/basic-cli.git?rev=--upload-pack=touch"
+        ),
+        Err(UrlProblem::InvalidGitRef(_))
+    ));
+    assert!(matches!(
+        GitPackage::try_from("git+https://github.com/roc-lang/basic-cli.git?tag=a%20b c"),
+        Err(UrlProblem::InvalidGitRef(_))
+    ));
+}
+
+#[test]
+fn git_url_outside_cache_dir() {
+    assert!(matches!(
+        GitPackage::try_from("git+https://github.com/../../etc?tag=0.2.0"),
+        Err(UrlProblem::OutsideCacheDir(_))
+    ));
+    assert!(matches!(
+        GitPackage::try_from("git+https:///etc/roc?tag=0.2.0"),
+        Err(UrlProblem::OutsideCacheDir(_))
+    ));
+}
+
+#[test]
+fn hash_dir_depends_on_paths_and_contents() {
+    let first = tempfile::tempdir().unwrap();
+    let second = tempfile::tempdir().unwrap();
+
+    fs::write(first.path().join("main.roc"), "x").unwrap();
+    fs::write(second.path().join("main.roc"), "x").unwrap();
+
+    assert_eq!(
+        hash_dir(first.path()).unwrap(),
+        hash_dir(second.path()).unwrap()
+    );
+
+    fs::write(second.path().join("Other.roc"), "").unwrap();
+
+    assert_ne!(
+        hash_dir(first.path()).unwrap(),
+        hash_dir(second.path()).unwrap()
+    );
+}
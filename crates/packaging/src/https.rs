@@ -1,6 +1,7 @@
 use std::{
+    fmt,
     io::{self, Read},
-    path::Path,
+    path::{Component, Path},
 };
 
 use crate::tarball::Compression;
@@ -36,6 +37,61 @@ pub enum UrlProblem {
     InvalidFragment(String),
     MissingHash,
     MissingHttps,
+    MissingGitPrefix,
+    /// A git URL needs either ?tag=... or ?rev=...
+    MissingGitRef,
+    InvalidGitRef(String),
+    /// The URL would put the package outside of the cache dir, e.g. with a `..` in its path
+    OutsideCacheDir(String),
+}
+
+impl fmt::Display for UrlProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UrlProblem::InvalidExtensionSuffix(suffix) => write!(
+                f,
+                "the URL must end in .tar, .tar.gz or .tar.br, but it ends in .tar{}",
+                suffix
+            ),
+            UrlProblem::MissingTarExt => {
+                write!(f, "the URL must end in .tar, .tar.gz or .tar.br")
+            }
+            UrlProblem::InvalidFragment(fragment) => write!(
+                f,
+                "the part after the # must be the name of a .roc file, but it is {:?}",
+                fragment
+            ),
+            UrlProblem::MissingHash => write!(
+                f,
+                "the URL must end in the hash of the package's contents, like .../<hash>.tar.br"
+            ),
+            UrlProblem::MissingHttps => write!(f, "the URL must start with https://"),
+            UrlProblem::MissingGitPrefix => write!(f, "a git URL must start with git+https://"),
+            UrlProblem::MissingGitRef => write!(
+                f,
+                "a git URL must pick a tag or revision, like ?tag=1.0.0 or ?rev=<commit>"
+            ),
+            UrlProblem::InvalidGitRef(query) => write!(
+                f,
+                "a git URL must pick a tag or revision, like ?tag=1.0.0 or ?rev=<commit>, but it has ?{}",
+                query
+            ),
+            UrlProblem::OutsideCacheDir(path) => write!(
+                f,
+                "{:?} would put the package outside of the package cache",
+                path
+            ),
+        }
+    }
+}
+
+/// Whether the package can live at this path within the cache dir, which it can't if the
+/// path is absolute or goes up a directory
+pub(crate) fn is_within_cache_dir(path: &str) -> bool {
+    !path.is_empty()
+        && Path::new(path)
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)))
 }
 
 impl<'a> TryFrom<&'a str> for PackageMetadata<'a> {
@@ -94,6 +150,12 @@ impl<'a> PackageMetadata<'a> {
             }
         };
 
+        for part in [path, tarball_name] {
+            if !is_within_cache_dir(part) {
+                return Err(UrlProblem::OutsideCacheDir(part.to_string()));
+            }
+        }
+
         Ok(PackageMetadata {
             cache_subdir: path,
             content_hash: tarball_name,
@@ -116,6 +178,48 @@ pub enum Problem {
     InvalidUrl(UrlProblem),
     /// The Content-Length header of the response exceeded max_download_bytes
     DownloadTooBig(u64),
+    GitErr {
+        command: String,
+        stderr: String,
+    },
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::UnsupportedEncoding(encoding) => {
+                write!(
+                    f,
+                    "the server sent it with an unsupported encoding: {}",
+                    encoding
+                )
+            }
+            Problem::MultipleEncodings(encodings) => {
+                write!(
+                    f,
+                    "the server sent it with several encodings: {}",
+                    encodings
+                )
+            }
+            Problem::InvalidContentHash { expected, actual } => write!(
+                f,
+                "its contents have the hash {}, but the URL says they should have the hash {}",
+                actual, expected
+            ),
+            Problem::IoErr(err) => write!(f, "{}", err),
+            Problem::FsExtraErr(err) => write!(f, "{}", err),
+            Problem::HttpErr(err) => write!(f, "{}", err),
+            Problem::InvalidUrl(problem) => write!(f, "{}", problem),
+            Problem::DownloadTooBig(bytes) => write!(
+                f,
+                "it is {} bytes, which is more than the most I will download",
+                bytes
+            ),
+            Problem::GitErr { command, stderr } => {
+                write!(f, "`{}` failed:\n\n{}", command, stderr)
+            }
+        }
+    }
 }
 
 pub fn download_and_hash(
@@ -1,4 +1,7 @@
 pub mod cache;
 #[cfg(not(target_family = "wasm"))]
+pub mod git;
+#[cfg(not(target_family = "wasm"))]
 pub mod https;
+pub mod lockfile;
 pub mod tarball;
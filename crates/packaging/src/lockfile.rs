@@ -0,0 +1,174 @@
+use std::{
+    collections::BTreeMap,
+    fmt::Write,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// The lockfile lives next to the app module, and is meant to be committed along with it.
+pub const LOCKFILE_NAME: &str = "roc.lock";
+
+const HEADER: &str =
+    "# This file is generated by roc. Commit it so every build uses exactly the same packages.\n";
+
+/// What a package URL resolved to the first time we installed it, so later builds get
+/// exactly the same package even if e.g. a git tag has moved since then.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LockedPackage {
+    /// The commit a git package was checked out at. Tarball URLs don't have one.
+    pub commit: Option<String>,
+    /// The base64url-encoded BLAKE3 hash of the package's contents
+    pub content_hash: String,
+}
+
+/// Each line of a lockfile is a package URL followed by what it resolved to, e.g.
+///
+/// git+https://github.com/roc-lang/basic-cli.git?tag=0.2.0 commit=8d3c1a... hash=jDRlAFAA...
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Lockfile {
+    packages: BTreeMap<String, LockedPackage>,
+    changed: bool,
+}
+
+impl Lockfile {
+    /// The lockfile in the given directory. If there isn't one yet, an empty one.
+    pub fn read(dir: &Path) -> io::Result<Self> {
+        match fs::read_to_string(Self::path(dir)) {
+            Ok(src) => Self::parse(&src).map_err(|line| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid line in {}: {}", LOCKFILE_NAME, line),
+                )
+            }),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err),
+        }
+    }
+
+    /// On failure, returns the line that isn't valid
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut packages = BTreeMap::new();
+
+        for line in src.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut parts = line.split_whitespace();
+            let url = parts.next().ok_or_else(|| line.to_string())?;
+
+            let mut commit = None;
+            let mut content_hash = None;
+
+            for part in parts {
+                match part.split_once('=') {
+                    Some(("commit", value)) => commit = Some(value.to_string()),
+                    Some(("hash", value)) => content_hash = Some(value.to_string()),
+                    _ => return Err(line.to_string()),
+                }
+            }
+
+            let content_hash = content_hash.ok_or_else(|| line.to_string())?;
+
+            packages.insert(
+                url.to_string(),
+                LockedPackage {
+                    commit,
+                    content_hash,
+                },
+            );
+        }
+
+        Ok(Self {
+            packages,
+            changed: false,
+        })
+    }
+
+    pub fn path(dir: &Path) -> PathBuf {
+        dir.join(LOCKFILE_NAME)
+    }
+
+    pub fn get(&self, url: &str) -> Option<&LockedPackage> {
+        self.packages.get(url)
+    }
+
+    pub fn insert(&mut self, url: &str, package: LockedPackage) {
+        if self.packages.get(url) != Some(&package) {
+            self.packages.insert(url.to_string(), package);
+            self.changed = true;
+        }
+    }
+
+    /// Forget the packages the app doesn't depend on anymore
+    pub fn retain(&mut self, urls: &[&str]) {
+        let before = self.packages.len();
+
+        self.packages.retain(|url, _| urls.contains(&url.as_str()));
+
+        self.changed |= self.packages.len() != before;
+    }
+
+    /// Write the lockfile to the given directory, unless nothing about it changed since we
+    /// read it.
+    pub fn write_if_changed(&self, dir: &Path) -> io::Result<()> {
+        if self.changed {
+            fs::write(Self::path(dir), self.to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl std::fmt::Display for Lockfile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(HEADER)?;
+
+        for (url, package) in self.packages.iter() {
+            let mut line = url.clone();
+
+            if let Some(commit) = &package.commit {
+                write!(line, " commit={}", commit)?;
+            }
+
+            writeln!(f, "{} hash={}", line, package.content_hash)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[test]
+fn lockfile_round_trip() {
+    let mut lockfile = Lockfile::default();
+
+    lockfile.insert(
+        "git+https://github.com/roc-lang/basic-cli.git?tag=0.2.0",
+        LockedPackage {
+            commit: Some("8d3c1a2b".to_string()),
+            content_hash: "jDRlAFAA3738vu3".to_string(),
+        },
+    );
+    lockfile.insert(
+        "https://example.com/roc-packages/jDRlAFAA3738vu3.tar.br",
+        LockedPackage {
+            commit: None,
+            content_hash: "jDRlAFAA3738vu3".to_string(),
+        },
+    );
+
+    let src = lockfile.to_string();
+
+    assert_eq!(
+        src.lines().skip(1).collect::<Vec<_>>(),
+        [
+            "git+https://github.com/roc-lang/basic-cli.git?tag=0.2.0 commit=8d3c1a2b hash=jDRlAFAA3738vu3",
+            "https://example.com/roc-packages/jDRlAFAA3738vu3.tar.br hash=jDRlAFAA3738vu3",
+        ]
+    );
+
+    let parsed = Lockfile::parse(&src).unwrap();
+
+    assert_eq!(parsed.packages, lockfile.packages);
+    assert!(!parsed.changed);
+}
@@ -128,17 +128,14 @@ fn write_archive<W: Write>(path: &Path, writer: W) -> io::Result<()> {
     // TODO use this when finding .roc files by discovering them from the root module.
     // let other_modules: &[Module<'_>] =
     match read_header(&arena, &mut buf, path)?.header {
-        Header::Interface(_) => {
-            todo!();
-            // TODO report error
-        }
-        Header::App(_) => {
-            todo!();
-            // TODO report error
-        }
-        Header::Hosted(_) => {
-            todo!();
-            // TODO report error
+        Header::Interface(_) | Header::App(_) | Header::Hosted(_) => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!(
+                    "{} is not a package or platform module, so it cannot be bundled",
+                    path.display()
+                ),
+            ));
         }
         Header::Package(_) => {
             add_dot_roc_files(root_dir, &mut builder)?;
@@ -154,6 +151,8 @@ fn write_archive<W: Write>(path: &Path, writer: W) -> io::Result<()> {
                     Some("rh1"),
                     // metadata file
                     Some("rm1"),
+                    // ABI hash of each prebuilt host
+                    Some("abi"),
                     // legacy linker formats
                     Some("o"),
                     Some("obj"),
@@ -163,6 +162,8 @@ fn write_archive<W: Write>(path: &Path, writer: W) -> io::Result<()> {
                     Some("zig"),
                 ]
                 .contains(&path.extension().and_then(OsStr::to_str))
+                    // the manifest listing the prebuilt hosts for each target
+                    || path.file_name() == Some(OsStr::new("prebuilt-hosts.txt"))
                 {
                     builder.append_path_with_name(
                         &path,
@@ -268,9 +269,16 @@ fn read_header<'a>(
     // (We can't use that for the parser state and still return Module<'a> unfortunately.)
     let arena_buf = bumpalo::collections::Vec::from_iter_in(buf.iter().copied(), arena);
     let parse_state = State::new(arena_buf.into_bump_slice());
-    let (module, _) = parse_header(arena, parse_state).unwrap_or_else(|_err| {
-        todo!(); // TODO report a nice error and exit 1 - or maybe just return Err, for better testability?
-    });
+    let (module, _) = parse_header(arena, parse_state).map_err(|err| {
+        io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!(
+                "{} does not start with a valid module header: {:?}",
+                path.display(),
+                err.problem
+            ),
+        )
+    })?;
 
     Ok(module)
 }
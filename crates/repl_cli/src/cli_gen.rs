@@ -3,7 +3,7 @@ use inkwell::context::Context;
 use libloading::Library;
 use roc_build::link::llvm_module_to_dylib;
 use roc_collections::all::MutSet;
-use roc_gen_llvm::llvm::build::LlvmBackendMode;
+use roc_gen_llvm::llvm::build::{DebugSources, LlvmBackendMode};
 use roc_gen_llvm::llvm::externs::add_default_roc_externs;
 use roc_gen_llvm::{run_jit_function, run_jit_function_dynamic_type};
 use roc_intern::SingleThreadedInterner;
@@ -12,9 +12,9 @@ use roc_mono::ir::OptLevel;
 use roc_mono::layout::Layout;
 use roc_parse::ast::Expr;
 use roc_repl_eval::eval::jit_to_ast;
-use roc_repl_eval::gen::{compile_to_mono, format_answer, Problems, ReplOutput};
+use roc_repl_eval::gen::{compile_to_mono, format_answer, Problems, ReplOutput, FRAC_DEFAULT};
 use roc_repl_eval::{ReplApp, ReplAppMemory};
-use roc_reporting::report::DEFAULT_PALETTE;
+use roc_reporting::report::Palette;
 use roc_std::RocStr;
 use roc_target::TargetInfo;
 use roc_types::pretty_print::{name_and_print_var, DebugPrint};
@@ -33,7 +33,7 @@ pub fn gen_and_eval_llvm<'a, I: Iterator<Item = &'a str>>(
     let mut loaded;
     let problems;
 
-    match compile_to_mono(&arena, defs, src, target_info, DEFAULT_PALETTE) {
+    match compile_to_mono(&arena, defs, src, target_info, Palette::from_env()) {
         (Some(mono), probs) => {
             loaded = mono;
             problems = probs;
@@ -84,6 +84,7 @@ pub fn gen_and_eval_llvm<'a, I: Iterator<Item = &'a str>>(
         &interns,
         layout_interner.into_global().fork(),
         target_info,
+        FRAC_DEFAULT,
     );
     let expr_str = format_answer(&arena, expr).to_string();
 
@@ -198,6 +199,8 @@ fn mono_module_to_dylib<'a>(
         interns,
         subs,
         layout_interner,
+        sources,
+        def_regions,
         ..
     } = loaded;
 
@@ -227,6 +230,7 @@ fn mono_module_to_dylib<'a>(
         mode: LlvmBackendMode::GenTest, // so roc_panic is generated
         // important! we don't want any procedures to get the C calling convention
         exposed_to_host: MutSet::default(),
+        debug_sources: DebugSources::new(&sources, &def_regions),
     };
 
     // Add roc_alloc, roc_realloc, and roc_dealloc, since the repl has no
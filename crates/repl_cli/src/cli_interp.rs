@@ -0,0 +1,461 @@
+use bumpalo::Bump;
+use roc_interpret::{Interpreter, Value};
+use roc_load::MonomorphizedModule;
+use roc_mono::layout::{Builtin, Layout, STLayoutInterner, UnionLayout};
+use roc_parse::ast::Expr;
+use roc_repl_eval::eval::jit_to_ast;
+use roc_repl_eval::gen::{compile_to_mono, format_answer, Problems, ReplOutput, FRAC_DEFAULT};
+use roc_repl_eval::{ReplApp, ReplAppMemory};
+use roc_reporting::report::Palette;
+use roc_target::TargetInfo;
+use roc_types::pretty_print::{name_and_print_var, DebugPrint};
+use target_lexicon::Triple;
+
+/// Each Roc call the interpreter makes nests a few Rust calls, so it runs on a thread with a
+/// much bigger stack than the main one.
+const INTERPRETER_STACK_SIZE: usize = 1 << 30;
+
+/// Like `gen_and_eval_llvm`, except that it runs the expression with the interpreter, so it
+/// doesn't need LLVM (or a linker) at all.
+pub fn eval_interpreted<'a, I: Iterator<Item = &'a str>>(
+    defs: I,
+    src: &str,
+) -> (Option<ReplOutput>, Problems) {
+    let defs: Vec<String> = defs.map(String::from).collect();
+    let src = src.to_string();
+
+    let thread = std::thread::Builder::new()
+        .name("roc repl interpreter".to_string())
+        .stack_size(INTERPRETER_STACK_SIZE)
+        .spawn(move || eval_interpreted_help(defs.iter().map(String::as_str), &src))
+        .expect("failed to spawn the interpreter thread");
+
+    match thread.join() {
+        Ok(output) => output,
+        Err(panic) => std::panic::resume_unwind(panic),
+    }
+}
+
+fn eval_interpreted_help<'a, I: Iterator<Item = &'a str>>(
+    defs: I,
+    src: &str,
+) -> (Option<ReplOutput>, Problems) {
+    let arena = Bump::new();
+    let target_info = TargetInfo::from(&Triple::host());
+
+    let mut loaded;
+    let mut problems;
+
+    match compile_to_mono(&arena, defs, src, target_info, Palette::from_env()) {
+        (Some(mono), probs) => {
+            loaded = mono;
+            problems = probs;
+        }
+        (None, probs) => {
+            return (None, probs);
+        }
+    };
+
+    debug_assert_eq!(loaded.exposed_to_host.values.len(), 1);
+    let (main_fn_symbol, main_fn_var) = loaded.exposed_to_host.values.iter().next().unwrap();
+    let main_fn_symbol = *main_fn_symbol;
+    let main_fn_var = *main_fn_var;
+
+    // pretty-print the expr type string for later.
+    let expr_type_str = name_and_print_var(
+        main_fn_var,
+        &mut loaded.subs,
+        loaded.module_id,
+        &loaded.interns,
+        DebugPrint::NOTHING,
+    );
+
+    let (_, main_fn_layout) = match loaded.procedures.keys().find(|(s, _)| *s == main_fn_symbol) {
+        Some(layout) => *layout,
+        None => {
+            return (None, problems);
+        }
+    };
+
+    // Only thunks get run; functions are printed without calling them.
+    let mut app = InterpretedApp { result: None };
+
+    if main_fn_layout.arguments.is_empty() {
+        let mut interpreter = Interpreter::new(&loaded.procedures);
+
+        match interpreter.call(main_fn_symbol, main_fn_layout, Vec::new()) {
+            Ok(value) => {
+                let mut encoder = Encoder::new(&loaded.layout_interner, target_info);
+                let addr = encoder.encode(&value, &main_fn_layout.result);
+
+                app.result = Some((arena.alloc(encoder.into_memory(&arena)), addr));
+            }
+            Err(problem) => {
+                problems.errors.push(problem.to_string());
+
+                return (None, problems);
+            }
+        }
+    }
+
+    let MonomorphizedModule {
+        interns,
+        subs,
+        layout_interner,
+        ..
+    } = loaded;
+
+    let expr = jit_to_ast(
+        &arena,
+        &mut app,
+        "main",
+        main_fn_layout,
+        main_fn_var,
+        &subs,
+        &interns,
+        layout_interner.into_global().fork(),
+        target_info,
+        FRAC_DEFAULT,
+    );
+    let expr_str = format_answer(&arena, expr).to_string();
+
+    (
+        Some(ReplOutput {
+            expr: expr_str,
+            expr_type: expr_type_str,
+        }),
+        problems,
+    )
+}
+
+/// The result of a main function the interpreter has already run, written to memory the
+/// same way compiled code would have
+struct InterpretedApp<'a> {
+    result: Option<(&'a InterpretedMemory<'a>, usize)>,
+}
+
+impl<'a> InterpretedApp<'a> {
+    fn result(&self) -> (&'a InterpretedMemory<'a>, usize) {
+        match self.result {
+            Some(result) => result,
+            None => unreachable!("the repl only asks for the result of thunks"),
+        }
+    }
+}
+
+impl<'a> ReplApp<'a> for InterpretedApp<'a> {
+    type Memory = InterpretedMemory<'a>;
+
+    fn call_function<Return, F>(&mut self, _main_fn_name: &str, mut transform: F) -> Expr<'a>
+    where
+        F: FnMut(&'a Self::Memory, Return) -> Expr<'a>,
+        Self::Memory: 'a,
+    {
+        let (mem, addr) = self.result();
+
+        assert!(addr + std::mem::size_of::<Return>() <= mem.bytes.len());
+
+        let result: Return = unsafe {
+            let ptr = mem.bytes[addr..].as_ptr() as *const Return;
+            ptr.read_unaligned()
+        };
+
+        transform(mem, result)
+    }
+
+    fn call_function_dynamic_size<T, F>(
+        &mut self,
+        _main_fn_name: &str,
+        _ret_bytes: usize,
+        mut transform: F,
+    ) -> T
+    where
+        F: FnMut(&'a Self::Memory, usize) -> T,
+        Self::Memory: 'a,
+    {
+        let (mem, addr) = self.result();
+
+        transform(mem, addr)
+    }
+}
+
+/// The interpreter's values, written out in the memory layout compiled code uses. Addresses
+/// are offsets into the bytes, and address 0 is never used, so it can stand for NULL.
+pub struct InterpretedMemory<'a> {
+    bytes: &'a [u8],
+}
+
+macro_rules! deref_number {
+    ($name: ident, $t: ty) => {
+        fn $name(&self, addr: usize) -> $t {
+            const N: usize = std::mem::size_of::<$t>();
+            let mut array = [0; N];
+            array.copy_from_slice(&self.bytes[addr..][..N]);
+            <$t>::from_le_bytes(array)
+        }
+    };
+}
+
+impl<'a> ReplAppMemory for InterpretedMemory<'a> {
+    fn deref_bool(&self, addr: usize) -> bool {
+        self.bytes[addr] != 0
+    }
+
+    deref_number!(deref_u8, u8);
+    deref_number!(deref_u16, u16);
+    deref_number!(deref_u32, u32);
+    deref_number!(deref_u64, u64);
+    deref_number!(deref_u128, u128);
+    deref_number!(deref_usize, usize);
+
+    deref_number!(deref_i8, i8);
+    deref_number!(deref_i16, i16);
+    deref_number!(deref_i32, i32);
+    deref_number!(deref_i64, i64);
+    deref_number!(deref_i128, i128);
+    deref_number!(deref_isize, isize);
+
+    deref_number!(deref_f32, f32);
+    deref_number!(deref_f64, f64);
+
+    fn deref_str(&self, addr: usize) -> &str {
+        // The encoder always writes strings in the big representation
+        let chars_addr = self.deref_usize(addr);
+        let len = self.deref_usize(addr + std::mem::size_of::<usize>());
+
+        std::str::from_utf8(&self.bytes[chars_addr..][..len]).unwrap()
+    }
+
+    fn deref_pointer_with_tag_id(&self, addr: usize) -> (u16, u64) {
+        let addr_with_id = self.deref_usize(addr);
+        let tag_id_mask = 0b111;
+
+        let tag_id = addr_with_id & tag_id_mask;
+        let data_addr = addr_with_id & !tag_id_mask;
+        (tag_id as _, data_addr as _)
+    }
+}
+
+struct Encoder<'a, 'r> {
+    interner: &'r STLayoutInterner<'a>,
+    target_info: TargetInfo,
+    bytes: Vec<u8>,
+}
+
+impl<'a, 'r> Encoder<'a, 'r> {
+    fn new(interner: &'r STLayoutInterner<'a>, target_info: TargetInfo) -> Self {
+        Self {
+            interner,
+            target_info,
+            // Keep address 0 free to stand for NULL
+            bytes: vec![0; 8],
+        }
+    }
+
+    fn into_memory(self, arena: &'a Bump) -> InterpretedMemory<'a> {
+        InterpretedMemory {
+            bytes: arena.alloc_slice_copy(&self.bytes),
+        }
+    }
+
+    /// Write the value to fresh memory, and return its address
+    fn encode(&mut self, value: &Value, layout: &Layout<'a>) -> usize {
+        let (size, alignment) = layout.stack_size_and_alignment(self.interner, self.target_info);
+        let addr = self.alloc(size as usize, alignment as usize);
+
+        self.write(addr, value, layout, None);
+
+        addr
+    }
+
+    fn alloc(&mut self, size: usize, alignment: usize) -> usize {
+        let alignment = alignment.max(1);
+        let addr = (self.bytes.len() + alignment - 1) / alignment * alignment;
+
+        self.bytes.resize(addr + size, 0);
+
+        addr
+    }
+
+    fn write_bytes(&mut self, addr: usize, bytes: &[u8]) {
+        self.bytes[addr..][..bytes.len()].copy_from_slice(bytes);
+    }
+
+    fn write_usize(&mut self, addr: usize, n: usize) {
+        let width = self.target_info.ptr_size();
+
+        self.write_bytes(addr, &n.to_le_bytes()[..width]);
+    }
+
+    fn write(
+        &mut self,
+        addr: usize,
+        value: &Value,
+        layout: &Layout<'a>,
+        recursive: Option<UnionLayout<'a>>,
+    ) {
+        match (value, layout) {
+            (Value::Int(n, width), _) => {
+                let size = width.stack_size() as usize;
+
+                self.write_bytes(addr, &n.to_le_bytes()[..size]);
+            }
+            (Value::Float(n, _), Layout::Builtin(Builtin::Float(width))) => {
+                match width.stack_size() {
+                    4 => self.write_bytes(addr, &(*n as f32).to_le_bytes()),
+                    _ => self.write_bytes(addr, &n.to_le_bytes()),
+                }
+            }
+            (Value::Float(n, _), _) => self.write_bytes(addr, &n.to_le_bytes()),
+            (Value::Dec(n), _) => self.write_bytes(addr, &n.to_le_bytes()),
+            (Value::Bool(b), _) => self.write_bytes(addr, &[*b as u8]),
+            (Value::Str(s), _) => {
+                let chars_addr = self.alloc(s.len(), 1);
+                self.write_bytes(chars_addr, s.as_bytes());
+
+                self.write_list_header(addr, chars_addr, s.len());
+            }
+            (Value::List(elems), Layout::Builtin(Builtin::List(elem_layout))) => {
+                let (elem_size, alignment) =
+                    elem_layout.stack_size_and_alignment(self.interner, self.target_info);
+                let elem_size = elem_size as usize;
+                let elems_addr = self.alloc(elem_size * elems.len(), alignment as usize);
+
+                for (index, elem) in elems.iter().enumerate() {
+                    self.write(elems_addr + index * elem_size, elem, elem_layout, recursive);
+                }
+
+                self.write_list_header(addr, elems_addr, elems.len());
+            }
+            (Value::Struct(fields), Layout::Struct { field_layouts, .. }) => {
+                self.write_fields(addr, fields, field_layouts, recursive);
+            }
+            (Value::Box(inner), Layout::Boxed(inner_layout)) => {
+                let inner_addr = self.encode(inner, inner_layout);
+
+                self.write_usize(addr, inner_addr);
+            }
+            (_, Layout::LambdaSet(lambda_set)) => {
+                let layout = lambda_set.runtime_representation(self.interner);
+
+                self.write(addr, value, &layout, recursive);
+            }
+            (_, Layout::RecursivePointer) => match recursive {
+                Some(union_layout) => {
+                    self.write(addr, value, &Layout::Union(union_layout), recursive)
+                }
+                None => unreachable!("a recursive pointer outside of a recursive union"),
+            },
+            (Value::Tag { id, fields }, Layout::Union(union_layout)) => {
+                self.write_tag(addr, *id, fields, *union_layout, recursive);
+            }
+            (value, layout) => {
+                unreachable!("the interpreter produced {:?} for {:?}", value, layout)
+            }
+        }
+    }
+
+    fn write_list_header(&mut self, addr: usize, elems_addr: usize, len: usize) {
+        let width = self.target_info.ptr_size();
+
+        self.write_usize(addr, elems_addr);
+        self.write_usize(addr + width, len);
+        self.write_usize(addr + 2 * width, len);
+    }
+
+    /// Struct fields are read back one after the other, without padding in between
+    fn write_fields(
+        &mut self,
+        addr: usize,
+        fields: &[Value],
+        field_layouts: &[Layout<'a>],
+        recursive: Option<UnionLayout<'a>>,
+    ) {
+        let mut field_addr = addr;
+
+        for (field, field_layout) in fields.iter().zip(field_layouts) {
+            self.write(field_addr, field, field_layout, recursive);
+
+            field_addr += field_layout.stack_size(self.interner, self.target_info) as usize;
+        }
+    }
+
+    fn write_tag(
+        &mut self,
+        addr: usize,
+        tag_id: u16,
+        fields: &[Value],
+        union_layout: UnionLayout<'a>,
+        recursive: Option<UnionLayout<'a>>,
+    ) {
+        let field_layouts = match union_layout {
+            UnionLayout::NonRecursive(tags) | UnionLayout::Recursive(tags) => tags[tag_id as usize],
+            UnionLayout::NonNullableUnwrapped(fields) => fields,
+            UnionLayout::NullableWrapped {
+                nullable_id,
+                other_tags,
+            } => {
+                if tag_id == nullable_id {
+                    &[]
+                } else if tag_id < nullable_id {
+                    other_tags[tag_id as usize]
+                } else {
+                    other_tags[tag_id as usize - 1]
+                }
+            }
+            UnionLayout::NullableUnwrapped { other_fields, .. } => other_fields,
+            UnionLayout::NullablePointer { pointer, .. } => std::slice::from_ref(pointer),
+        };
+
+        match union_layout {
+            UnionLayout::NonRecursive(_) => {
+                self.write_fields(addr, fields, field_layouts, recursive);
+                self.write_tag_id(addr, tag_id, union_layout);
+            }
+            UnionLayout::NullablePointer { .. } => {
+                if union_layout.tag_is_null(tag_id) {
+                    self.write_usize(addr, 0);
+                } else {
+                    self.write_fields(addr, fields, field_layouts, recursive);
+                }
+            }
+            _ if union_layout.tag_is_null(tag_id) => {
+                self.write_usize(addr, 0);
+            }
+            _ => {
+                let (size, alignment) =
+                    union_layout.data_size_and_alignment(self.interner, self.target_info);
+
+                // Data is at least 8-byte aligned, so the tag id can go in the pointer's low bits
+                let data_addr = self.alloc(size as usize, (alignment as usize).max(8));
+
+                self.write_fields(data_addr, fields, field_layouts, Some(union_layout));
+
+                let pointer = if union_layout.stores_tag_id_in_pointer(self.target_info) {
+                    data_addr | tag_id as usize
+                } else {
+                    self.write_tag_id(data_addr, tag_id, union_layout);
+
+                    data_addr
+                };
+
+                self.write_usize(addr, pointer);
+            }
+        }
+    }
+
+    fn write_tag_id(&mut self, data_addr: usize, tag_id: u16, union_layout: UnionLayout<'a>) {
+        use roc_mono::layout::Discriminant::*;
+
+        let offset = match union_layout.data_size_without_tag_id(self.interner, self.target_info) {
+            Some(offset) => offset as usize,
+            None => return,
+        };
+
+        match union_layout.discriminant() {
+            U0 => {}
+            U1 | U8 => self.write_bytes(data_addr + offset, &[tag_id as u8]),
+            U16 => self.write_bytes(data_addr + offset, &tag_id.to_le_bytes()),
+        }
+    }
+}
@@ -1,4 +1,5 @@
 use crate::cli_gen::gen_and_eval_llvm;
+use crate::cli_interp::eval_interpreted;
 use crate::colors::{BLUE, END_COL, GREEN, PINK};
 use bumpalo::Bump;
 use const_format::concatcp;
@@ -68,9 +69,20 @@ struct PastDef {
     src: String,
 }
 
+/// How the repl runs the expressions it's given
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReplBackend {
+    /// Compile them with LLVM and run the resulting machine code
+    #[default]
+    Llvm,
+    /// Run their mono IR with the interpreter, which doesn't need LLVM
+    Interpreter,
+}
+
 #[derive(Completer, Helper, Hinter)]
 pub struct ReplState {
     validator: InputValidator,
+    backend: ReplBackend,
     past_defs: Vec<PastDef>,
     past_def_idents: MutSet<String>,
     last_auto_ident: u64,
@@ -84,8 +96,13 @@ impl Default for ReplState {
 
 impl ReplState {
     pub fn new() -> Self {
+        Self::with_backend(ReplBackend::default())
+    }
+
+    pub fn with_backend(backend: ReplBackend) -> Self {
         Self {
             validator: InputValidator::new(),
+            backend,
             past_defs: Default::default(),
             past_def_idents: Default::default(),
             last_auto_ident: 0,
@@ -249,20 +266,10 @@ impl ReplState {
                 Some(existing_ident) => {
                     opt_var_name = Some(existing_ident);
 
-                    gen_and_eval_llvm(
-                        self.past_defs.iter().map(|def| def.src.as_str()),
-                        src,
-                        Triple::host(),
-                        OptLevel::Normal,
-                    )
+                    self.eval(src)
                 }
                 None => {
-                    let (output, problems) = gen_and_eval_llvm(
-                        self.past_defs.iter().map(|def| def.src.as_str()),
-                        src,
-                        Triple::host(),
-                        OptLevel::Normal,
-                    );
+                    let (output, problems) = self.eval(src);
 
                     // Don't persist defs that have compile errors
                     if problems.errors.is_empty() {
@@ -287,6 +294,15 @@ impl ReplState {
         format_output(output, problems, opt_var_name, dimensions)
     }
 
+    fn eval(&self, src: &str) -> (Option<ReplOutput>, Problems) {
+        let defs = self.past_defs.iter().map(|def| def.src.as_str());
+
+        match self.backend {
+            ReplBackend::Llvm => gen_and_eval_llvm(defs, src, Triple::host(), OptLevel::Normal),
+            ReplBackend::Interpreter => eval_interpreted(defs, src),
+        }
+    }
+
     fn next_auto_ident(&mut self) -> u64 {
         self.last_auto_ident += 1;
         self.last_auto_ident
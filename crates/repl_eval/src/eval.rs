@@ -1,5 +1,6 @@
 use bumpalo::collections::Vec;
 use bumpalo::Bump;
+use roc_types::num::FloatWidth as FracWidth;
 use roc_types::types::AliasKind;
 use std::cmp::{max_by_key, min_by_key};
 
@@ -48,13 +49,18 @@ pub fn jit_to_ast<'a, A: ReplApp<'a>>(
     interns: &'a Interns,
     layout_interner: LayoutInterner<'a>,
     target_info: TargetInfo,
+    frac_default: FracWidth,
 ) -> Expr<'a> {
+    // fractional literals were compiled to this, so their values have to be read back with it
+    let mut layout_cache = LayoutCache::new(layout_interner, target_info);
+    layout_cache.frac_default = frac_default;
+
     let mut env = Env {
         arena,
         subs,
         target_info,
         interns,
-        layout_cache: LayoutCache::new(layout_interner, target_info),
+        layout_cache,
     };
 
     match layout {
@@ -457,7 +463,8 @@ fn jit_to_ast_help<'a, A: ReplApp<'a>>(
                 struct_addr_to_ast,
             )
         }
-        Layout::Union(UnionLayout::NonRecursive(_)) => {
+        Layout::Union(UnionLayout::NonRecursive(_))
+        | Layout::Union(UnionLayout::NullablePointer { .. }) => {
             let size = layout.stack_size(&env.layout_cache.interner, env.target_info);
 
             app.call_function_dynamic_size(
@@ -691,6 +698,46 @@ fn addr_to_ast<'a, M: ReplAppMemory>(
                 WhenRecursive::Unreachable,
             )
         }
+        (_, Layout::Union(UnionLayout::NullablePointer { .. })) => {
+            let tags = match raw_content {
+                Content::Structure(FlatType::TagUnion(tags, _)) => tags,
+                other => unreachable!("Weird content for nullable pointer Union layout: {:?}", other),
+            };
+
+            debug_assert_eq!(tags.len(), 2);
+
+            let (vars_of_tag, union_variant) = get_tags_vars_and_variant(env, tags, None);
+
+            let tags_and_layouts = match union_variant {
+                UnionVariant::Wrapped(WrappedVariant::NonRecursive {
+                    sorted_tag_layouts
+                }) => sorted_tag_layouts,
+                other => unreachable!("A nullable pointer layout comes from a nonrecursive variant; found variant {:?}", other),
+            };
+
+            // The union is stored as the pointer of its one field, and NULL for the other tag
+            let (null_tag, other_tag) = match tags_and_layouts.as_slice() {
+                [first, second] if first.1.is_empty() => (first, second),
+                [first, second] => (second, first),
+                _ => unreachable!("A nullable pointer always has exactly two tags"),
+            };
+
+            if mem.deref_usize(addr) == 0 {
+                tag_name_to_expr(env, null_tag.0.expect_tag_ref())
+            } else {
+                let (tag_name, arg_layouts) = other_tag;
+
+                expr_of_tag(
+                    env,
+                    mem,
+                    addr,
+                    tag_name.expect_tag_ref(),
+                    arg_layouts,
+                    &vars_of_tag[tag_name.expect_tag_ref()],
+                    WhenRecursive::Unreachable,
+                )
+            }
+        }
         (_, Layout::Union(union_layout @ UnionLayout::Recursive(union_layouts))) => {
             let (rec_var, tags) = match raw_content {
                 Content::Structure(FlatType::RecursiveTagUnion(rec_var, tags, _)) => {
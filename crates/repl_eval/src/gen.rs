@@ -1,5 +1,7 @@
 use bumpalo::Bump;
-use roc_load::{ExecutionMode, LoadConfig, LoadMonomorphizedError, Threading};
+use roc_load::{
+    ExecutionMode, FloatWidth, LoadConfig, LoadMonomorphizedError, OptLevel, Threading,
+};
 use roc_packaging::cache::{self, RocCacheDir};
 use roc_problem::Severity;
 use roc_reporting::report::Palette;
@@ -13,6 +15,9 @@ use roc_region::all::LineInfo;
 use roc_reporting::report::{can_problem, type_problem, RocDocAllocator};
 use roc_target::TargetInfo;
 
+/// What fractional literals compile to in the repl, which has no `--frac-default`
+pub const FRAC_DEFAULT: FloatWidth = FloatWidth::F64;
+
 #[derive(Debug)]
 pub struct ReplOutput {
     pub expr: String,
@@ -68,6 +73,8 @@ pub fn compile_to_mono<'a, 'i, I: Iterator<Item = &'i str>>(
             palette,
             threading: Threading::Single,
             exec_mode: ExecutionMode::Executable,
+            frac_default: FRAC_DEFAULT,
+            opt_level: OptLevel::Development,
         },
     );
 
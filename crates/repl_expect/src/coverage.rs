@@ -0,0 +1,369 @@
+//! Which definitions `roc test --coverage` ran.
+//!
+//! Every Roc procedure of a `roc test` build calls `roc_coverage_hook` when it is entered, and
+//! we count those calls per definition. A definition counts as covered if any specialization
+//! of it ran.
+//!
+//! This is definition coverage, not line or branch coverage: mono statements don't keep their
+//! regions, so there is nothing finer-grained to attribute a hit to. The lcov output says as
+//! much, with function records (`FN`/`FNDA`) and no line (`DA`) or branch (`BRDA`) records, so
+//! tools don't report the lines in between definitions as uncovered.
+//!
+//! The counters live in memory shared with the processes that run the expects, so the calls
+//! made there count too. The results are written as `lcov.info`, which most coverage tooling
+//! reads, and as an HTML page per module.
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicPtr, AtomicU64, Ordering};
+
+use roc_collections::MutMap;
+use roc_gen_llvm::llvm::externs::ROC_COVERAGE_HOOK;
+use roc_module::symbol::{Interns, ModuleId, Symbol};
+use roc_region::all::{LineInfo, Region};
+
+static TABLE: AtomicPtr<CoverageTable> = AtomicPtr::new(std::ptr::null_mut());
+
+struct CoverageTable {
+    /// The index of each covered definition's counter, by its symbol
+    indices: HashMap<u64, usize>,
+    /// In a shared mapping, so the processes that run expects update the same counters
+    counters: &'static [AtomicU64],
+}
+
+/// A definition that coverage is reported for
+struct Definition {
+    name: String,
+    /// 1-based
+    line: u32,
+    hits: u64,
+}
+
+/// Count the calls to the definitions of every module with a source file (which builtins don't
+/// have) from now on. Call [write_coverage] once the tests are done.
+pub fn enable(
+    sources: &MutMap<ModuleId, (PathBuf, Box<str>)>,
+    def_regions: &MutMap<Symbol, Region>,
+) {
+    let symbols: Vec<Symbol> = def_regions
+        .keys()
+        .copied()
+        .filter(|symbol| {
+            let module_id = symbol.module_id();
+
+            !module_id.is_builtin() && sources.contains_key(&module_id)
+        })
+        .collect();
+
+    let counters = shared_counters(symbols.len());
+
+    let indices = symbols
+        .iter()
+        .enumerate()
+        .map(|(index, symbol)| (symbol.as_u64(), index))
+        .collect();
+
+    // The table lives as long as the process, since the expects can call the hook at any time.
+    let table = Box::leak(Box::new(CoverageTable { indices, counters }));
+
+    TABLE.store(table, Ordering::Release);
+}
+
+fn shared_counters(count: usize) -> &'static [AtomicU64] {
+    if count == 0 {
+        return &[];
+    }
+
+    let size = count * std::mem::size_of::<AtomicU64>();
+
+    let ptr = unsafe {
+        libc::mmap(
+            std::ptr::null_mut(),
+            size,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED | libc::MAP_ANONYMOUS,
+            -1,
+            0,
+        )
+    };
+
+    if ptr == libc::MAP_FAILED {
+        roc_error_macros::internal_error!("failed to mmap the coverage counters");
+    }
+
+    // An anonymous mapping starts out zeroed, which is what a counter starts at.
+    unsafe { std::slice::from_raw_parts(ptr.cast(), count) }
+}
+
+extern "C" fn count_call(symbol: u64) {
+    let table = TABLE.load(Ordering::Acquire);
+
+    if let Some(table) = unsafe { table.as_ref() } {
+        if let Some(index) = table.indices.get(&symbol) {
+            table.counters[*index].fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Point the coverage hook of the expect dylib at us, if coverage is enabled.
+pub(crate) fn install_hook(lib: &libloading::Library) {
+    type Hook = Option<extern "C" fn(u64)>;
+
+    if TABLE.load(Ordering::Acquire).is_null() {
+        return;
+    }
+
+    unsafe {
+        if let Ok(hook) = lib.get::<*mut Hook>(ROC_COVERAGE_HOOK.as_bytes()) {
+            **hook = Some(count_call);
+        }
+    }
+}
+
+/// Write `lcov.info`, and an `index.html` linking to a page for each module, into the given
+/// directory. Returns the number of covered definitions and the number of definitions.
+pub fn write_coverage(
+    dir: &Path,
+    interns: &Interns,
+    sources: &MutMap<ModuleId, (PathBuf, Box<str>)>,
+    def_regions: &MutMap<Symbol, Region>,
+) -> std::io::Result<(usize, usize)> {
+    let table = match unsafe { TABLE.load(Ordering::Acquire).as_ref() } {
+        Some(table) => table,
+        None => return Ok((0, 0)),
+    };
+
+    let mut modules: Vec<(ModuleId, &Path, &str, Vec<Definition>)> = Vec::new();
+
+    for (module_id, (path, src)) in sources.iter() {
+        let line_info = LineInfo::new(src);
+
+        let mut definitions: Vec<Definition> = def_regions
+            .iter()
+            .filter(|(symbol, _)| symbol.module_id() == *module_id)
+            .filter_map(|(symbol, region)| {
+                let index = table.indices.get(&symbol.as_u64())?;
+
+                Some(Definition {
+                    name: symbol.as_str(interns).to_string(),
+                    line: line_info.convert_pos(region.start()).line + 1,
+                    hits: table.counters[*index].load(Ordering::Relaxed),
+                })
+            })
+            .collect();
+
+        if definitions.is_empty() {
+            continue;
+        }
+
+        definitions.sort_by_key(|definition| definition.line);
+        modules.push((*module_id, path.as_path(), &**src, definitions));
+    }
+
+    modules.sort_by(|a, b| a.1.cmp(b.1));
+
+    std::fs::create_dir_all(dir)?;
+
+    let mut lcov = String::new();
+    let mut index_rows = String::new();
+    let mut total_covered = 0;
+    let mut total = 0;
+
+    for (module_id, path, src, definitions) in modules.iter() {
+        let covered = definitions.iter().filter(|def| def.hits > 0).count();
+
+        total_covered += covered;
+        total += definitions.len();
+
+        write_lcov_record(&mut lcov, path, definitions);
+
+        let page = format!("{}.html", interns.module_name(*module_id).as_str());
+        std::fs::write(dir.join(&page), module_page(path, src, definitions))?;
+
+        writeln!(
+            index_rows,
+            "<tr><td><a href=\"{}\">{}</a></td><td>{}</td></tr>",
+            escape_html(&page),
+            escape_html(&path.display().to_string()),
+            percentage(covered, definitions.len()),
+        )
+        .unwrap();
+    }
+
+    std::fs::write(dir.join("lcov.info"), lcov)?;
+
+    let index = format!(
+        "{}<h1>Coverage</h1>\n<p>{} of {} definitions ({}) ran during the tests.</p>\n\
+        <table>\n<tr><th>Module</th><th>Coverage</th></tr>\n{}</table>\n</body></html>\n",
+        html_head("Coverage"),
+        total_covered,
+        total,
+        percentage(total_covered, total),
+        index_rows
+    );
+    std::fs::write(dir.join("index.html"), index)?;
+
+    Ok((total_covered, total))
+}
+
+/// See the `geninfo(1)` man page for the format
+fn write_lcov_record(out: &mut String, path: &Path, definitions: &[Definition]) {
+    let path = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+
+    writeln!(out, "TN:").unwrap();
+    writeln!(out, "SF:{}", path.display()).unwrap();
+
+    for definition in definitions {
+        writeln!(out, "FN:{},{}", definition.line, definition.name).unwrap();
+    }
+
+    for definition in definitions {
+        writeln!(out, "FNDA:{},{}", definition.hits, definition.name).unwrap();
+    }
+
+    let hit = definitions.iter().filter(|def| def.hits > 0).count();
+
+    writeln!(out, "FNF:{}", definitions.len()).unwrap();
+    writeln!(out, "FNH:{}", hit).unwrap();
+    writeln!(out, "end_of_record").unwrap();
+}
+
+/// The module's source, with the line each definition starts on marked as covered or not
+fn module_page(path: &Path, src: &str, definitions: &[Definition]) -> String {
+    let title = path.display().to_string();
+    let mut rows = String::new();
+
+    for (index, line) in src.lines().enumerate() {
+        let line_number = index as u32 + 1;
+
+        let (class, hits) = match definitions.iter().find(|def| def.line == line_number) {
+            Some(def) if def.hits > 0 => (" class=\"covered\"", def.hits.to_string()),
+            Some(def) => (
+                " class=\"uncovered\"",
+                format!("0 <span>({} never ran)</span>", escape_html(&def.name)),
+            ),
+            None => ("", String::new()),
+        };
+
+        writeln!(
+            rows,
+            "<tr{}><td>{}</td><td>{}</td><td><pre>{}</pre></td></tr>",
+            class,
+            line_number,
+            hits,
+            escape_html(line)
+        )
+        .unwrap();
+    }
+
+    format!(
+        "{}<h1>{}</h1>\n<p><a href=\"index.html\">All modules</a></p>\n\
+        <table class=\"source\">\n{}</table>\n</body></html>\n",
+        html_head(&title),
+        escape_html(&title),
+        rows
+    )
+}
+
+fn html_head(title: &str) -> String {
+    format!(
+        "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>{}</title>\n<style>\n\
+        body {{ font-family: sans-serif; }}\n\
+        td {{ padding: 0 0.5em; vertical-align: top; }}\n\
+        pre {{ margin: 0; }}\n\
+        .source td:nth-child(-n+2) {{ color: #888; text-align: right; }}\n\
+        .covered {{ background: #dfd; }}\n\
+        .uncovered {{ background: #fdd; }}\n\
+        </style></head><body>\n",
+        escape_html(title)
+    )
+}
+
+fn percentage(covered: usize, total: usize) -> String {
+    if total == 0 {
+        "-".to_string()
+    } else {
+        format!("{:.1}%", covered as f64 * 100.0 / total as f64)
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for c in text.chars() {
+        match c {
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '&' => escaped.push_str("&amp;"),
+            '"' => escaped.push_str("&quot;"),
+            c => escaped.push(c),
+        }
+    }
+
+    escaped
+}
+
+#[cfg(test)]
+mod test {
+    use super::{escape_html, module_page, percentage, write_lcov_record, Definition};
+    use std::path::Path;
+
+    fn definitions() -> Vec<Definition> {
+        vec![
+            Definition {
+                name: "main".to_string(),
+                line: 3,
+                hits: 2,
+            },
+            Definition {
+                name: "unused".to_string(),
+                line: 5,
+                hits: 0,
+            },
+        ]
+    }
+
+    #[test]
+    fn lcov_has_function_records_only() {
+        let mut lcov = String::new();
+
+        write_lcov_record(
+            &mut lcov,
+            Path::new("/no/such/dir/Main.roc"),
+            &definitions(),
+        );
+
+        assert_eq!(
+            lcov,
+            "TN:\nSF:/no/such/dir/Main.roc\nFN:3,main\nFN:5,unused\nFNDA:2,main\nFNDA:0,unused\nFNF:2\nFNH:1\nend_of_record\n"
+        );
+    }
+
+    #[test]
+    fn page_marks_definition_lines() {
+        let src = "app \"test\" provides [main] to \"./platform\"\n\nmain = 1 < 2\n\nunused = 3\n";
+        let page = module_page(Path::new("Main.roc"), src, &definitions());
+
+        assert!(page.contains(
+            "<tr class=\"covered\"><td>3</td><td>2</td><td><pre>main = 1 &lt; 2</pre></td></tr>"
+        ));
+        assert!(page.contains(
+            "<tr class=\"uncovered\"><td>5</td><td>0 <span>(unused never ran)</span></td><td><pre>unused = 3</pre></td></tr>"
+        ));
+        assert!(page.contains("<tr><td>2</td><td></td><td><pre></pre></td></tr>"));
+    }
+
+    #[test]
+    fn percentages() {
+        assert_eq!(percentage(1, 3), "33.3%");
+        assert_eq!(percentage(0, 0), "-");
+    }
+
+    #[test]
+    fn escape() {
+        assert_eq!(
+            escape_html("<a href=\"x\">&</a>"),
+            "&lt;a href=&quot;x&quot;&gt;&amp;&lt;/a&gt;"
+        );
+    }
+}
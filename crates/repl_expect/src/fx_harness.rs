@@ -0,0 +1,99 @@
+//! Running `expect-fx` tests against a harness that performs their effects.
+//!
+//! The test dylib has no host, so the effects it calls (e.g. `roc_fx_putLine`) must come from
+//! somewhere else: a harness, which is a shared library exporting those functions. It can do
+//! the real thing (a platform builds its host as a shared library) or hand back canned
+//! responses (a mock written for the tests). A platform provides its harness as
+//! `<target>-test.so` (`.dylib` on macOS) next to its main module, e.g. `linux-x86_64-test.so`,
+//! and `roc test --fx-harness=<path>` uses a different one.
+//!
+//! The harness doesn't need to define `roc_alloc` and friends; it gets the test dylib's. It may
+//! export `roc_test_setup` and `roc_test_teardown` (both `void (void)`), which run before and
+//! after each `expect-fx`, in the process that runs it.
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use libloading::os::unix::{Library, Symbol, RTLD_GLOBAL, RTLD_LAZY};
+use roc_build::link::get_target_triple_str;
+use target_lexicon::{OperatingSystem, Triple};
+
+const SETUP_NAME: &[u8] = b"roc_test_setup";
+const TEARDOWN_NAME: &[u8] = b"roc_test_teardown";
+
+type Hook = extern "C" fn();
+
+struct FxHarness {
+    /// Never unloaded, because the test dylib's effects point into it
+    _lib: Library,
+    setup: Option<Symbol<Hook>>,
+    teardown: Option<Symbol<Hook>>,
+}
+
+static HARNESS: Mutex<Option<FxHarness>> = Mutex::new(None);
+
+/// The harness a platform provides for the given target, if it has one
+pub fn platform_harness(platform_path: &Path, target: &Triple) -> Option<PathBuf> {
+    let extension = match target.operating_system {
+        OperatingSystem::Darwin => "dylib",
+        _ => "so",
+    };
+
+    let file_name = format!("{}-test.{}", get_target_triple_str(target)?, extension);
+    let path = platform_path.with_file_name(file_name);
+
+    path.exists().then(|| path)
+}
+
+/// Load the harness, so the test dylib can call its effects. `expect_mono_module_to_dylib`
+/// loads dylibs with `expect-fx`s so they can see the harness, so call this before it.
+pub fn load(path: &Path) -> Result<(), String> {
+    // Lazily, since the harness calls roc_alloc and friends, which the test dylib defines.
+    let lib = unsafe { Library::open(Some(path), RTLD_LAZY | RTLD_GLOBAL) }
+        .map_err(|err| format!("could not load {}: {}", path.display(), err))?;
+
+    let setup = unsafe { lib.get::<Hook>(SETUP_NAME) }.ok();
+    let teardown = unsafe { lib.get::<Hook>(TEARDOWN_NAME) }.ok();
+
+    *lock() = Some(FxHarness {
+        _lib: lib,
+        setup,
+        teardown,
+    });
+
+    Ok(())
+}
+
+/// Whether a harness is loaded, so the test dylib needs to see its symbols
+pub fn is_loaded() -> bool {
+    lock().is_some()
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Option<FxHarness>> {
+    HARNESS
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Run the harness's `roc_test_setup`, if it has one.
+pub(crate) fn setup() {
+    // Not while holding the lock, in case it panics
+    let setup = lock()
+        .as_ref()
+        .and_then(|harness| harness.setup.as_ref().map(|setup| **setup));
+
+    if let Some(setup) = setup {
+        setup();
+    }
+}
+
+/// Run the harness's `roc_test_teardown`, if it has one.
+pub(crate) fn teardown() {
+    // Not while holding the lock, in case it panics
+    let teardown = lock()
+        .as_ref()
+        .and_then(|harness| harness.teardown.as_ref().map(|teardown| **teardown));
+
+    if let Some(teardown) = teardown {
+        teardown();
+    }
+}
@@ -0,0 +1,294 @@
+//! The allocation-balance check of `roc test --leak-check`.
+//!
+//! The testing `roc_alloc`, `roc_realloc`, and `roc_dealloc` call back into us (through
+//! `roc_alloc_hook` and `roc_dealloc_hook`), so while a toplevel expect runs we know which of
+//! its Roc heap allocations are still live. Whatever is left when it returns was leaked, and a
+//! dealloc of something it already freed is a double free, which we report and don't pass on to
+//! libc so the test runner survives it.
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+
+use backtrace::Backtrace;
+use roc_gen_llvm::llvm::externs::{ROC_ALLOC_HOOK, ROC_DEALLOC_HOOK};
+
+use crate::stack_trace::roc_frames;
+
+/// How many bytes of a leaked value to show
+const PREVIEW_BYTES: usize = 24;
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static TRACKER: Mutex<Option<Tracker>> = Mutex::new(None);
+
+#[derive(Default)]
+struct Tracker {
+    live: HashMap<usize, Allocation>,
+    /// Freed during this expect, and not handed out again since
+    freed: HashSet<usize>,
+    double_frees: Vec<Backtrace>,
+}
+
+struct Allocation {
+    size: usize,
+    alignment: u32,
+    backtrace: Backtrace,
+}
+
+/// A leak or double free, described for the expect's report, along with the Roc frames it
+/// happened in
+pub type MemoryProblem = (String, Vec<String>);
+
+/// Check every toplevel expect that runs from now on for leaks and double frees
+pub fn enable() {
+    ENABLED.store(true, Ordering::Relaxed);
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Option<Tracker>> {
+    // The hooks don't panic while holding the lock, but an expect might unwind past them.
+    TRACKER
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+impl Tracker {
+    fn alloc(&mut self, addr: usize, size: usize, alignment: u32) {
+        self.freed.remove(&addr);
+        self.live.insert(
+            addr,
+            Allocation {
+                size,
+                alignment,
+                // Resolving symbols is slow, so only do it for allocations that leak.
+                backtrace: Backtrace::new_unresolved(),
+            },
+        );
+    }
+
+    /// Returns false if the allocation must not be freed
+    fn dealloc(&mut self, addr: usize) -> bool {
+        if self.live.remove(&addr).is_some() {
+            self.freed.insert(addr);
+        } else if self.freed.contains(&addr) {
+            self.double_frees.push(Backtrace::new_unresolved());
+
+            return false;
+        }
+
+        true
+    }
+
+    /// What was leaked or freed twice
+    ///
+    /// # Safety
+    ///
+    /// The allocations that are still live must not have been freed behind our back.
+    unsafe fn problems(self) -> Vec<MemoryProblem> {
+        let mut problems = Vec::new();
+
+        for backtrace in self.double_frees {
+            problems.push((
+                "A value was freed after it had already been freed.".to_string(),
+                roc_frames(backtrace),
+            ));
+        }
+
+        let mut leaks: Vec<_> = self.live.into_iter().collect();
+        leaks.sort_by_key(|(addr, _)| *addr);
+
+        for (addr, allocation) in leaks {
+            let description = describe_leak(addr as *const u8, &allocation);
+
+            problems.push((description, roc_frames(allocation.backtrace)));
+        }
+
+        problems
+    }
+}
+
+extern "C" fn on_alloc(ptr: *mut u8, size: usize, alignment: u32) {
+    if let Some(tracker) = lock().as_mut() {
+        tracker.alloc(ptr as usize, size, alignment);
+    }
+}
+
+/// Returns nonzero if the allocation must not be freed
+extern "C" fn on_dealloc(ptr: *mut u8) -> u8 {
+    match lock().as_mut() {
+        Some(tracker) if !tracker.dealloc(ptr as usize) => 1,
+        _ => 0,
+    }
+}
+
+/// Point the allocator hooks of the expect dylib at us, if the check is enabled.
+pub(crate) fn install_hooks(lib: &libloading::Library) {
+    type AllocHook = Option<extern "C" fn(*mut u8, usize, u32)>;
+    type DeallocHook = Option<extern "C" fn(*mut u8) -> u8>;
+
+    if !ENABLED.load(Ordering::Relaxed) {
+        return;
+    }
+
+    unsafe {
+        if let Ok(hook) = lib.get::<*mut AllocHook>(ROC_ALLOC_HOOK.as_bytes()) {
+            **hook = Some(on_alloc);
+        }
+
+        if let Ok(hook) = lib.get::<*mut DeallocHook>(ROC_DEALLOC_HOOK.as_bytes()) {
+            **hook = Some(on_dealloc);
+        }
+    }
+}
+
+/// Start tracking the allocations of an expect that's about to run
+pub(crate) fn start() {
+    if ENABLED.load(Ordering::Relaxed) {
+        *lock() = Some(Tracker::default());
+    }
+}
+
+/// Stop tracking, and return what the expect leaked or freed twice.
+pub(crate) fn finish() -> Vec<MemoryProblem> {
+    let tracker = match lock().take() {
+        Some(tracker) => tracker,
+        None => return Vec::new(),
+    };
+
+    // The hooks saw every dealloc, so whatever is still live hasn't been freed.
+    unsafe { tracker.problems() }
+}
+
+/// Roc puts a refcount just before the data of each allocation, which starts at
+/// `max(alignment, size_of::<usize>())` bytes in.
+///
+/// # Safety
+///
+/// The allocation must still be live.
+unsafe fn describe_leak(ptr: *const u8, allocation: &Allocation) -> String {
+    let extra_bytes = (allocation.alignment as usize).max(std::mem::size_of::<usize>());
+
+    if allocation.size < extra_bytes {
+        return format!("A {}-byte allocation was never freed.", allocation.size);
+    }
+
+    let data = ptr.add(extra_bytes);
+    let refcount = std::ptr::read_unaligned(data.cast::<isize>().sub(1));
+
+    // isize::MIN means a refcount of 1
+    let refcount = refcount.wrapping_sub(isize::MIN).wrapping_add(1);
+
+    let length = (allocation.size - extra_bytes).min(PREVIEW_BYTES);
+    let bytes = std::slice::from_raw_parts(data, length);
+
+    let preview = match std::str::from_utf8(bytes) {
+        Ok(text) if !text.is_empty() && !text.chars().any(char::is_control) => {
+            format!("{:?}", text)
+        }
+        _ => bytes
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<Vec<_>>()
+            .join(" "),
+    };
+
+    let ellipsis = if allocation.size - extra_bytes > length {
+        " ..."
+    } else {
+        ""
+    };
+
+    format!(
+        "A {}-byte allocation with a refcount of {} was never freed. It holds: {}{}",
+        allocation.size, refcount, preview, ellipsis
+    )
+}
+
+#[cfg(test)]
+mod test_leak_check {
+    use super::Tracker;
+
+    /// A Roc allocation of the given data, with a refcount of 1 in the word before it
+    fn allocation(data: &[u8]) -> Vec<u8> {
+        let mut bytes = isize::MIN.to_ne_bytes().to_vec();
+        bytes.extend_from_slice(data);
+        bytes
+    }
+
+    #[test]
+    fn balanced_allocations() {
+        let mut tracker = Tracker::default();
+        tracker.alloc(0x1000, 32, 8);
+        tracker.alloc(0x2000, 32, 8);
+
+        assert!(tracker.dealloc(0x2000));
+        assert!(tracker.dealloc(0x1000));
+        assert!(unsafe { tracker.problems() }.is_empty());
+    }
+
+    #[test]
+    fn double_free() {
+        let mut tracker = Tracker::default();
+        tracker.alloc(0x1000, 32, 8);
+
+        assert!(tracker.dealloc(0x1000));
+        assert!(!tracker.dealloc(0x1000));
+
+        let problems = unsafe { tracker.problems() };
+        assert_eq!(problems.len(), 1);
+        assert_eq!(
+            problems[0].0,
+            "A value was freed after it had already been freed."
+        );
+    }
+
+    #[test]
+    fn reallocated_address_is_not_a_double_free() {
+        let mut tracker = Tracker::default();
+        tracker.alloc(0x1000, 32, 8);
+        assert!(tracker.dealloc(0x1000));
+
+        tracker.alloc(0x1000, 32, 8);
+        assert!(tracker.dealloc(0x1000));
+        assert!(unsafe { tracker.problems() }.is_empty());
+    }
+
+    #[test]
+    fn untracked_dealloc_is_passed_on() {
+        // Allocated before the expect started, so it's not ours to judge.
+        let mut tracker = Tracker::default();
+
+        assert!(tracker.dealloc(0x1000));
+        assert!(unsafe { tracker.problems() }.is_empty());
+    }
+
+    #[test]
+    fn leaked_string() {
+        let bytes = allocation(b"hello");
+        let mut tracker = Tracker::default();
+        tracker.alloc(bytes.as_ptr() as usize, bytes.len(), 8);
+
+        let problems = unsafe { tracker.problems() };
+        assert_eq!(problems.len(), 1);
+        assert_eq!(
+            problems[0].0,
+            format!(
+                "A {}-byte allocation with a refcount of 1 was never freed. It holds: \"hello\"",
+                bytes.len()
+            )
+        );
+    }
+
+    #[test]
+    fn leaked_bytes_are_shown_in_hex_and_cut_short() {
+        let bytes = allocation(&[0; 32]);
+        let mut tracker = Tracker::default();
+        tracker.alloc(bytes.as_ptr() as usize, bytes.len(), 8);
+
+        let problems = unsafe { tracker.problems() };
+        let expected = format!(
+            "A {}-byte allocation with a refcount of 1 was never freed. It holds: {} ...",
+            bytes.len(),
+            vec!["00"; super::PREVIEW_BYTES].join(" ")
+        );
+        assert_eq!(problems[0].0, expected);
+    }
+}
@@ -10,6 +10,7 @@ use {
     roc_parse::ast::Expr,
     roc_repl_eval::{eval::jit_to_ast, ReplAppMemory},
     roc_target::TargetInfo,
+    roc_types::num::FloatWidth,
     roc_types::subs::{Subs, Variable},
     std::sync::Arc,
 };
@@ -17,7 +18,21 @@ use {
 #[cfg(not(windows))]
 mod app;
 #[cfg(not(windows))]
+pub mod coverage;
+#[cfg(not(windows))]
+pub mod fx_harness;
+#[cfg(not(windows))]
+pub mod leak_check;
+#[cfg(not(windows))]
+pub mod property;
+#[cfg(not(windows))]
 pub mod run;
+#[cfg(not(windows))]
+pub mod snapshot;
+#[cfg(not(windows))]
+mod stack_trace;
+#[cfg(not(windows))]
+pub mod wasm;
 
 #[cfg(not(windows))]
 use app::{ExpectMemory, ExpectReplApp};
@@ -33,6 +48,7 @@ pub fn get_values<'a>(
     start: *const u8,
     start_offset: usize,
     number_of_lookups: usize,
+    frac_default: FloatWidth,
 ) -> (usize, Vec<Expr<'a>>, Vec<Variable>) {
     let mut result = Vec::with_capacity(number_of_lookups);
     let mut result_vars = Vec::with_capacity(number_of_lookups);
@@ -62,6 +78,7 @@ pub fn get_values<'a>(
         let expr = {
             // TODO: pass layout_cache to jit_to_ast directly
             let mut layout_cache = LayoutCache::new(layout_interner.fork(), target_info);
+            layout_cache.frac_default = frac_default;
             let layout = layout_cache.from_var(arena, variable, subs).unwrap();
 
             let proc_layout = ProcLayout {
@@ -80,6 +97,7 @@ pub fn get_values<'a>(
                 interns,
                 layout_interner.fork(),
                 target_info,
+                frac_default,
             )
         };
 
@@ -96,7 +114,7 @@ mod test {
     use indoc::indoc;
     use pretty_assertions::assert_eq;
     use roc_gen_llvm::{llvm::build::LlvmBackendMode, run_roc::RocCallResult, run_roc_dylib};
-    use roc_load::{ExecutionMode, LoadConfig, LoadMonomorphizedError, Threading};
+    use roc_load::{ExecutionMode, FloatWidth, LoadConfig, LoadMonomorphizedError, Threading};
     use roc_packaging::cache::RocCacheDir;
     use roc_reporting::report::{RenderTarget, DEFAULT_PALETTE};
     use target_lexicon::Triple;
@@ -106,6 +124,10 @@ mod test {
     use super::*;
 
     fn run_expect_test(source: &str, expected: &str) {
+        run_expect_test_with_threads(source, expected, 1)
+    }
+
+    fn run_expect_test_with_threads(source: &str, expected: &str, test_threads: usize) {
         let arena = bumpalo::Bump::new();
         let arena = &arena;
 
@@ -127,6 +149,8 @@ mod test {
             palette: DEFAULT_PALETTE,
             threading: Threading::Single,
             exec_mode: ExecutionMode::Test,
+            frac_default: FloatWidth::F64,
+            opt_level,
         };
         let loaded = match roc_load::load_and_monomorphize_from_str(
             arena,
@@ -183,6 +207,8 @@ mod test {
             &mut expectations,
             expects,
             &mut memory,
+            &crate::snapshot::Snapshots::default(),
+            test_threads,
         )
         .unwrap();
 
@@ -211,6 +237,80 @@ mod test {
         }
     }
 
+    /// Runs the expects in the source as wasm32 in the interpreter, and returns how many failed
+    /// and how many passed.
+    fn run_wasm_expect_test(source: &str) -> (usize, usize) {
+        let arena = &bumpalo::Bump::new();
+        let opt_level = roc_mono::ir::OptLevel::Normal;
+
+        let src_dir = tempfile::tempdir().unwrap();
+        let filename = src_dir.path().join("Test.roc");
+
+        std::fs::write(&filename, source).unwrap();
+
+        let load_config = LoadConfig {
+            target_info: TargetInfo::default_wasm32(),
+            render: RenderTarget::ColorTerminal,
+            palette: DEFAULT_PALETTE,
+            threading: Threading::Single,
+            exec_mode: ExecutionMode::Test,
+            frac_default: FloatWidth::F64,
+            opt_level,
+        };
+        let mut loaded = match roc_load::load_and_monomorphize_from_str(
+            arena,
+            filename,
+            source,
+            src_dir.path().to_path_buf(),
+            Default::default(),
+            RocCacheDir::Disallowed,
+            load_config,
+        ) {
+            Ok(m) => m,
+            Err(LoadMonomorphizedError::ErrorModule(m)) => {
+                panic!("{:?}", (m.can_problems, m.type_problems))
+            }
+            Err(e) => panic!("{e:?}"),
+        };
+
+        let mut expectations = std::mem::take(&mut loaded.expectations);
+        let interns = arena.alloc(loaded.interns.clone());
+
+        let (wasm_bytes, expects) =
+            crate::wasm::expect_mono_module_to_wasm(arena, loaded, opt_level).unwrap();
+
+        let mut writer = Vec::new();
+
+        crate::wasm::run_toplevel_expects_wasm(
+            &mut writer,
+            RenderTarget::ColorTerminal,
+            arena,
+            interns,
+            &mut expectations,
+            &wasm_bytes,
+            expects,
+            &crate::wasm::WasmRuntime::Interpreter,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn wasm_counts_passed_and_failed() {
+        let source = indoc!(
+            r#"
+            interface Test exposes [] imports []
+
+            expect 1 == 1
+
+            expect 1 == 2
+
+            expect Str.concat "a" "b" == "ab"
+            "#
+        );
+
+        assert_eq!(run_wasm_expect_test(source), (1, 2));
+    }
+
     #[test]
     fn equals_pass() {
         run_expect_test(
@@ -225,6 +325,34 @@ mod test {
         );
     }
 
+    #[test]
+    fn failure_in_worker() {
+        run_expect_test_with_threads(
+            indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main = 0
+
+                expect 1 == 1
+
+                expect 1 == 2
+
+                expect 2 == 2
+                "#
+            ),
+            indoc!(
+                r#"
+                This expectation failed:
+
+                7│  expect 1 == 2
+                    ^^^^^^^^^^^^^
+                "#
+            ),
+            2,
+        );
+    }
+
     #[test]
     fn equals_fail() {
         run_expect_test(
@@ -484,6 +612,57 @@ mod test {
         );
     }
 
+    #[test]
+    fn structural_diff() {
+        run_expect_test(
+            indoc!(
+                r#"
+                app "test" provides [main] to "./platform"
+
+                main = 0
+
+                expect
+                    expected = { name: "Ada", age: 36u8, tags: ["a", "b", "c"] }
+                    actual = { name: "Ada", age: 37u8, tags: ["a", "x"] }
+
+                    expected == actual
+                "#
+            ),
+            indoc!(
+                r#"
+                This expectation failed:
+
+                5│>  expect
+                6│>      expected = { name: "Ada", age: 36u8, tags: ["a", "b", "c"] }
+                7│>      actual = { name: "Ada", age: 37u8, tags: ["a", "x"] }
+                8│>
+                9│>      expected == actual
+
+                When it failed, these variables had these values:
+
+                expected : { age : U8, name : Str, tags : List Str }
+
+                actual : { age : U8, name : Str, tags : List Str }
+
+                These are the parts of expected and actual that differ; everything
+                else about them is equal:
+
+                .age
+                    expected = 36
+                    actual = 37
+
+                .tags[1]
+                    expected = "b"
+                    actual = "x"
+
+                .tags[2]
+                    expected = "c"
+                    actual = (missing)
+                "#
+            ),
+        );
+    }
+
     #[test]
     fn two_strings() {
         run_expect_test(
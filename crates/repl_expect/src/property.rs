@@ -0,0 +1,809 @@
+//! Property tests: a top-level `expect` of a function, like
+//!
+//! ```roc
+//! expect \list -> List.len (List.reverse list) == List.len list
+//! ```
+//!
+//! gets called with generated arguments, based on the types of its parameters. When it fails,
+//! the arguments are shrunk to the smallest ones we can find that still make it fail.
+//!
+//! Like doc tests and snapshots, this works by rewriting the module's source. Each property
+//! becomes a top-level def (of the same length, so every other region stays where it was), and
+//! every batch of arguments becomes an `expect` at the end of the module that calls it. Finding
+//! out which arguments make a property fail means compiling and running such a module, so we
+//! try many arguments at once.
+use std::fmt::Write;
+
+use roc_module::symbol::Symbol;
+use roc_region::all::{Position, Region};
+use roc_types::subs::{Subs, Variable};
+use roc_types::types::{AliasKind, ErrorType, Polarity, RecordField};
+
+const PROPERTY_EXPECT: &str = "expect \\";
+
+/// How many times each property is called before we decide it holds
+pub const DEFAULT_CASES: usize = 100;
+
+/// Shrinking stops after this many rounds, even if it could go on.
+const MAX_SHRINK_ROUNDS: usize = 20;
+
+/// The most smaller arguments tried in one round of shrinking, per property
+const MAX_SHRINK_CANDIDATES: usize = 32;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PropertyExpect {
+    /// Where the `expect` is in the original source
+    pub region: Region,
+    /// The name of the def it was rewritten to, e.g. `p001`
+    pub name: String,
+}
+
+/// The module's source with each property rewritten to a def (the source to load to find out
+/// the types of their parameters, and to call them), the module's source without the properties
+/// (the source to run the other expects with), and the properties.
+pub fn rewrite_property_expects(src: &str) -> (String, String, Vec<PropertyExpect>) {
+    let mut with_defs = String::with_capacity(src.len());
+    let mut without = String::with_capacity(src.len());
+    let mut properties = Vec::new();
+
+    let mut offset = 0;
+    let mut lines = src.split_inclusive('\n').peekable();
+
+    while let Some(line) = lines.next() {
+        let start = offset;
+        offset += line.len();
+
+        if !line.starts_with(PROPERTY_EXPECT) {
+            with_defs.push_str(line);
+            without.push_str(line);
+
+            continue;
+        }
+
+        // `p001 = ` is exactly as long as `expect `
+        let name = format!("p{:03}", properties.len() + 1);
+
+        with_defs.push_str(&name);
+        with_defs.push_str(" = ");
+        with_defs.push_str(&line["expect ".len()..]);
+        without.push_str(&blank(line));
+
+        // The function goes on for as long as the lines are indented.
+        while let Some(next) = lines.peek() {
+            if !next.starts_with(' ') || next.trim().is_empty() {
+                break;
+            }
+
+            offset += next.len();
+            with_defs.push_str(next);
+            without.push_str(&blank(next));
+            lines.next();
+        }
+
+        let end = src[..offset].trim_end().len();
+
+        properties.push(PropertyExpect {
+            region: Region::new(Position::new(start as u32), Position::new(end as u32)),
+            name,
+        });
+    }
+
+    (with_defs, without, properties)
+}
+
+/// Replace everything but the line break with spaces.
+fn blank(line: &str) -> String {
+    let content = line.trim_end_matches(['\r', '\n']);
+
+    " ".repeat(content.len()) + &line[content.len()..]
+}
+
+/// The kinds of values we know how to generate
+#[derive(Debug, Clone, PartialEq)]
+pub enum Shape {
+    Int { min: i128, max: i128 },
+    Frac,
+    Bool,
+    Str,
+    List(Box<Shape>),
+    Record(Vec<(String, Shape)>),
+    TagUnion(Vec<(String, Vec<Shape>)>),
+}
+
+/// The shapes of the parameters of the property with this type, if it returns a Bool and we can
+/// generate all of them
+pub fn parameter_shapes(subs: &mut Subs, var: Variable) -> Option<Vec<Shape>> {
+    match subs.var_to_error_type(var, Polarity::OF_VALUE) {
+        ErrorType::Function(arguments, _closure, ret) if shape(&ret) == Some(Shape::Bool) => {
+            arguments.iter().map(shape).collect()
+        }
+        _ => None,
+    }
+}
+
+fn shape(typ: &ErrorType) -> Option<Shape> {
+    use ErrorType::*;
+
+    match typ {
+        Type(Symbol::STR_STR, _) => Some(Shape::Str),
+        Type(Symbol::LIST_LIST, elem) => Some(Shape::List(Box::new(shape(elem.first()?)?))),
+        Type(Symbol::NUM_NUM, args) => num_shape(args.first()?),
+        Alias(Symbol::NUM_NUM, args, _, _) => num_shape(args.first()?),
+        Alias(Symbol::BOOL_BOOL, _, _, _) => Some(Shape::Bool),
+        Alias(symbol, _, real, kind) => match int_range(*symbol) {
+            Some((min, max)) => Some(Shape::Int { min, max }),
+            None if is_frac(*symbol) => Some(Shape::Frac),
+            None if *kind == AliasKind::Structural => shape(real),
+            None => None,
+        },
+        // e.g. the type of a number literal; generate I64s, like the number would default to
+        Range(_) | FlexVar(_) | FlexAbleVar(_, _) => {
+            int_range(Symbol::NUM_I64).map(|(min, max)| Shape::Int { min, max })
+        }
+        Record(fields, _) => {
+            let mut shapes = Vec::with_capacity(fields.len());
+
+            for (name, field) in fields.iter() {
+                match field {
+                    RecordField::Optional(_) | RecordField::RigidOptional(_) => continue,
+                    RecordField::Demanded(typ)
+                    | RecordField::Required(typ)
+                    | RecordField::RigidRequired(typ) => {
+                        shapes.push((name.to_string(), shape(typ)?));
+                    }
+                }
+            }
+
+            shapes.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            Some(Shape::Record(shapes))
+        }
+        TagUnion(tags, _, _) => {
+            let mut shapes = Vec::with_capacity(tags.len());
+
+            for (name, payload) in tags.iter() {
+                let payload = payload.iter().map(shape).collect::<Option<Vec<_>>>()?;
+
+                shapes.push((name.0.to_string(), payload));
+            }
+
+            shapes.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+            if shapes.is_empty() {
+                None
+            } else {
+                Some(Shape::TagUnion(shapes))
+            }
+        }
+        _ => None,
+    }
+}
+
+/// The shape of a `Num *`
+fn num_shape(arg: &ErrorType) -> Option<Shape> {
+    use ErrorType::*;
+
+    match arg {
+        Alias(Symbol::NUM_INTEGER, args, _, _) | Type(Symbol::NUM_INTEGER, args) => {
+            let symbol = match args.first() {
+                Some(Alias(symbol, _, _, _)) | Some(Type(symbol, _)) => *symbol,
+                _ => Symbol::NUM_SIGNED64,
+            };
+
+            let (min, max) = int_range(symbol).or_else(|| int_range(Symbol::NUM_I64))?;
+
+            Some(Shape::Int { min, max })
+        }
+        Alias(Symbol::NUM_FLOATINGPOINT, _, _, _) | Type(Symbol::NUM_FLOATINGPOINT, _) => {
+            Some(Shape::Frac)
+        }
+        FlexVar(_) | FlexAbleVar(_, _) | Range(_) => {
+            int_range(Symbol::NUM_I64).map(|(min, max)| Shape::Int { min, max })
+        }
+        _ => None,
+    }
+}
+
+/// The values we generate for an integer type. The 128-bit ones stay within 64 bits, which is
+/// plenty to find bugs with and keeps the literals readable.
+fn int_range(symbol: Symbol) -> Option<(i128, i128)> {
+    let range = match symbol {
+        Symbol::NUM_I8 | Symbol::NUM_SIGNED8 => (i8::MIN as i128, i8::MAX as i128),
+        Symbol::NUM_U8 | Symbol::NUM_UNSIGNED8 => (0, u8::MAX as i128),
+        Symbol::NUM_I16 | Symbol::NUM_SIGNED16 => (i16::MIN as i128, i16::MAX as i128),
+        Symbol::NUM_U16 | Symbol::NUM_UNSIGNED16 => (0, u16::MAX as i128),
+        Symbol::NUM_I32 | Symbol::NUM_SIGNED32 => (i32::MIN as i128, i32::MAX as i128),
+        Symbol::NUM_U32 | Symbol::NUM_UNSIGNED32 | Symbol::NUM_NAT | Symbol::NUM_NATURAL => {
+            (0, u32::MAX as i128)
+        }
+        Symbol::NUM_I64 | Symbol::NUM_SIGNED64 | Symbol::NUM_I128 | Symbol::NUM_SIGNED128 => {
+            (i64::MIN as i128, i64::MAX as i128)
+        }
+        Symbol::NUM_U64 | Symbol::NUM_UNSIGNED64 | Symbol::NUM_U128 | Symbol::NUM_UNSIGNED128 => {
+            (0, u64::MAX as i128)
+        }
+        _ => return None,
+    };
+
+    Some(range)
+}
+
+fn is_frac(symbol: Symbol) -> bool {
+    matches!(
+        symbol,
+        Symbol::NUM_F32 | Symbol::NUM_F64 | Symbol::NUM_DEC | Symbol::NUM_FRAC
+    )
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Int(i128),
+    Frac(f64),
+    Bool(bool),
+    Str(String),
+    List(Vec<Value>),
+    Record(Vec<(String, Value)>),
+    Tag(String, Vec<Value>),
+}
+
+impl Value {
+    /// The value as a Roc literal, e.g. `{ name: "x", tags: [] }`
+    pub fn to_roc(&self) -> String {
+        let mut buf = String::new();
+
+        self.write_roc(&mut buf);
+
+        buf
+    }
+
+    fn write_roc(&self, buf: &mut String) {
+        match self {
+            Value::Int(n) => write!(buf, "{}", n).unwrap(),
+            Value::Frac(x) => {
+                let text = x.to_string();
+
+                buf.push_str(&text);
+
+                if !text.contains('.') {
+                    buf.push_str(".0");
+                }
+            }
+            Value::Bool(b) => buf.push_str(if *b { "Bool.true" } else { "Bool.false" }),
+            Value::Str(s) => {
+                buf.push('"');
+
+                for c in s.chars() {
+                    match c {
+                        '"' => buf.push_str("\\\""),
+                        '\\' => buf.push_str("\\\\"),
+                        '\n' => buf.push_str("\\n"),
+                        '\t' => buf.push_str("\\t"),
+                        c => buf.push(c),
+                    }
+                }
+
+                buf.push('"');
+            }
+            Value::List(elems) => {
+                buf.push('[');
+
+                for (index, elem) in elems.iter().enumerate() {
+                    if index > 0 {
+                        buf.push_str(", ");
+                    }
+
+                    elem.write_roc(buf);
+                }
+
+                buf.push(']');
+            }
+            Value::Record(fields) if fields.is_empty() => buf.push_str("{}"),
+            Value::Record(fields) => {
+                buf.push_str("{ ");
+
+                for (index, (name, value)) in fields.iter().enumerate() {
+                    if index > 0 {
+                        buf.push_str(", ");
+                    }
+
+                    buf.push_str(name);
+                    buf.push_str(": ");
+                    value.write_roc(buf);
+                }
+
+                buf.push_str(" }");
+            }
+            Value::Tag(name, payload) => {
+                buf.push_str(name);
+
+                for value in payload {
+                    buf.push_str(" (");
+                    value.write_roc(buf);
+                    buf.push(')');
+                }
+            }
+        }
+    }
+}
+
+/// A small, fast and (most importantly) reproducible source of randomness (splitmix64)
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        Rng(seed)
+    }
+
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+
+        z ^ (z >> 31)
+    }
+
+    /// A number in `0..n`
+    fn below(&mut self, n: u64) -> u64 {
+        if n == 0 {
+            0
+        } else {
+            self.next() % n
+        }
+    }
+
+    fn chance(&mut self, one_in: u64) -> bool {
+        self.below(one_in) == 0
+    }
+}
+
+const INTERESTING_CHARS: &[char] = &[' ', '"', '\\', '\n', 'é', 'ß', '€', '🙂'];
+
+impl Shape {
+    /// A random value of this shape. The size (from 0 to 100) bounds how big it gets; we start
+    /// small, since small failures are the easiest to understand.
+    pub fn generate(&self, rng: &mut Rng, size: u64) -> Value {
+        match self {
+            Shape::Int { min, max } => {
+                let edges = [*min, *max, 0, 1, -1];
+
+                if rng.chance(8) {
+                    let edge = edges[rng.below(edges.len() as u64) as usize];
+
+                    return Value::Int(edge.clamp(*min, *max));
+                }
+
+                // Grow the bound quickly, so that big sizes reach the whole range.
+                let bound = (size as i128 + 1).pow(4);
+                let low = (-bound).max(*min);
+                let high = bound.min(*max);
+                let span = (high - low) as u64;
+
+                Value::Int(low + rng.below(span.saturating_add(1)) as i128)
+            }
+            Shape::Frac => {
+                let bound = (size + 1) as f64 * 10.0;
+                let unit = rng.below(1 << 20) as f64 / (1 << 20) as f64;
+
+                // Keep a few decimals, so the literals stay readable.
+                Value::Frac(((unit * 2.0 - 1.0) * bound * 1000.0).round() / 1000.0)
+            }
+            Shape::Bool => Value::Bool(rng.chance(2)),
+            Shape::Str => {
+                let len = rng.below(size / 4 + 2);
+                let string = (0..len)
+                    .map(|_| {
+                        if rng.chance(10) {
+                            INTERESTING_CHARS[rng.below(INTERESTING_CHARS.len() as u64) as usize]
+                        } else {
+                            (b' ' + rng.below(95) as u8) as char
+                        }
+                    })
+                    .collect();
+
+                Value::Str(string)
+            }
+            Shape::List(elem) => {
+                let len = rng.below(size / 4 + 2);
+
+                Value::List((0..len).map(|_| elem.generate(rng, size / 2)).collect())
+            }
+            Shape::Record(fields) => Value::Record(
+                fields
+                    .iter()
+                    .map(|(name, field)| (name.clone(), field.generate(rng, size)))
+                    .collect(),
+            ),
+            Shape::TagUnion(tags) => {
+                let (name, payload) = &tags[rng.below(tags.len() as u64) as usize];
+
+                Value::Tag(
+                    name.clone(),
+                    payload
+                        .iter()
+                        .map(|shape| shape.generate(rng, size / 2))
+                        .collect(),
+                )
+            }
+        }
+    }
+
+    /// Values of this shape that are a little smaller than the given one, smallest first
+    fn shrink(&self, value: &Value) -> Vec<Value> {
+        let mut candidates = Vec::new();
+
+        match (self, value) {
+            (Shape::Int { min, max }, Value::Int(n)) => {
+                let n = *n;
+
+                for smaller in [0, n / 2, n - n.signum()] {
+                    let smaller = smaller.clamp(*min, *max);
+
+                    if smaller.abs() < n.abs() && !candidates.contains(&Value::Int(smaller)) {
+                        candidates.push(Value::Int(smaller));
+                    }
+                }
+            }
+            (Shape::Frac, Value::Frac(x)) => {
+                for smaller in [0.0, x.trunc(), x / 2.0] {
+                    if smaller.abs() < x.abs() && !candidates.contains(&Value::Frac(smaller)) {
+                        candidates.push(Value::Frac(smaller));
+                    }
+                }
+            }
+            (Shape::Bool, Value::Bool(true)) => candidates.push(Value::Bool(false)),
+            (Shape::Str, Value::Str(s)) => {
+                let chars: Vec<char> = s.chars().collect();
+
+                for list in shrink_list(&chars) {
+                    candidates.push(Value::Str(list.into_iter().collect()));
+                }
+
+                // Then make the characters simpler.
+                for (index, c) in chars.iter().enumerate() {
+                    if *c != 'a' {
+                        let mut simpler = chars.clone();
+                        simpler[index] = 'a';
+
+                        candidates.push(Value::Str(simpler.into_iter().collect()));
+                    }
+                }
+            }
+            (Shape::List(elem), Value::List(elems)) => {
+                candidates.extend(shrink_list(elems).into_iter().map(Value::List));
+
+                for (index, value) in elems.iter().enumerate() {
+                    for smaller in elem.shrink(value) {
+                        let mut list = elems.clone();
+                        list[index] = smaller;
+
+                        candidates.push(Value::List(list));
+                    }
+                }
+            }
+            (Shape::Record(shapes), Value::Record(fields)) => {
+                for (index, ((_, shape), (_, value))) in shapes.iter().zip(fields).enumerate() {
+                    for smaller in shape.shrink(value) {
+                        let mut record = fields.clone();
+                        record[index].1 = smaller;
+
+                        candidates.push(Value::Record(record));
+                    }
+                }
+            }
+            (Shape::TagUnion(tags), Value::Tag(name, payload)) => {
+                let shapes = tags
+                    .iter()
+                    .find(|(tag, _)| tag == name)
+                    .map(|(_, shapes)| shapes.as_slice())
+                    .unwrap_or_default();
+
+                for (index, (shape, value)) in shapes.iter().zip(payload).enumerate() {
+                    for smaller in shape.shrink(value) {
+                        let mut values = payload.clone();
+                        values[index] = smaller;
+
+                        candidates.push(Value::Tag(name.clone(), values));
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        candidates
+    }
+}
+
+/// Shorter versions of a list: empty, each half, and without each of its (first few) elements
+fn shrink_list<T: Clone>(list: &[T]) -> Vec<Vec<T>> {
+    let mut candidates = Vec::new();
+
+    if list.is_empty() {
+        return candidates;
+    }
+
+    candidates.push(Vec::new());
+
+    if list.len() > 1 {
+        candidates.push(list[..list.len() / 2].to_vec());
+        candidates.push(list[list.len() / 2..].to_vec());
+    }
+
+    for index in 0..list.len().min(8) {
+        let mut shorter = list.to_vec();
+        shorter.remove(index);
+
+        if !candidates.contains(&shorter) {
+            candidates.push(shorter);
+        }
+    }
+
+    candidates
+}
+
+/// Smaller versions of a property's arguments, shrinking one argument at a time
+fn shrink_arguments(shapes: &[Shape], arguments: &[Value]) -> Vec<Vec<Value>> {
+    let mut candidates = Vec::new();
+
+    for (index, (shape, value)) in shapes.iter().zip(arguments).enumerate() {
+        for smaller in shape.shrink(value) {
+            let mut args = arguments.to_vec();
+            args[index] = smaller;
+
+            candidates.push(args);
+        }
+    }
+
+    candidates.truncate(MAX_SHRINK_CANDIDATES);
+
+    candidates
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PropertyOutcome {
+    Passed {
+        cases: usize,
+    },
+    Failed {
+        cases: usize,
+        /// The smallest arguments we found that make it fail, as Roc literals
+        arguments: Vec<String>,
+        shrinks: usize,
+    },
+    /// We couldn't run it, e.g. because we can't generate its arguments.
+    Untestable {
+        reason: String,
+    },
+}
+
+/// A property we know how to generate arguments for
+pub struct TestableProperty<'a> {
+    pub property: &'a PropertyExpect,
+    pub shapes: Vec<Shape>,
+}
+
+/// The batch of calls we run in one go
+struct Round {
+    src: String,
+    /// The property each call is for, its arguments, and where its `expect` starts
+    calls: Vec<(usize, Vec<Value>, u32)>,
+}
+
+impl Round {
+    fn new(src_with_defs: &str) -> Self {
+        let mut src = src_with_defs.to_string();
+
+        if !src.ends_with('\n') {
+            src.push('\n');
+        }
+
+        Round {
+            src,
+            calls: Vec::new(),
+        }
+    }
+
+    fn add_call(&mut self, index: usize, property: &PropertyExpect, arguments: Vec<Value>) {
+        self.src.push('\n');
+
+        let offset = self.src.len() as u32;
+
+        self.src.push_str("expect ");
+        self.src.push_str(&property.name);
+
+        for argument in &arguments {
+            self.src.push_str(" (");
+            argument.write_roc(&mut self.src);
+            self.src.push(')');
+        }
+
+        self.src.push('\n');
+
+        self.calls.push((index, arguments, offset));
+    }
+
+    /// The arguments of the first call of each property that failed, given the regions of the
+    /// `expect`s that failed
+    fn first_failures(&self, failed: &[Region], properties: usize) -> Vec<Option<Vec<Value>>> {
+        let mut failures = vec![None; properties];
+
+        for (index, arguments, offset) in &self.calls {
+            let call_failed = failed
+                .iter()
+                .any(|region| region.start().offset <= *offset && *offset < region.end().offset);
+
+            if call_failed && failures[*index].is_none() {
+                failures[*index] = Some(arguments.clone());
+            }
+        }
+
+        failures
+    }
+}
+
+/// Call each property with generated arguments, and shrink the arguments of the ones that fail.
+///
+/// `run` compiles a module and returns the regions of its failing `expect`s (or a message saying
+/// why it couldn't compile or run it); it's given the source and the offset where the generated
+/// `expect`s start, since only those need to run.
+pub fn run_properties<F>(
+    src_with_defs: &str,
+    properties: &[TestableProperty],
+    seed: u64,
+    cases: usize,
+    mut run: F,
+) -> Vec<PropertyOutcome>
+where
+    F: FnMut(&str, u32) -> Result<Vec<Region>, String>,
+{
+    let mut rng = Rng::new(seed);
+    let mut round = Round::new(src_with_defs);
+    let generated_start = round.src.len() as u32;
+
+    for case in 0..cases {
+        let size = (case * 100 / cases.max(1)) as u64;
+
+        for (index, testable) in properties.iter().enumerate() {
+            let arguments = testable
+                .shapes
+                .iter()
+                .map(|shape| shape.generate(&mut rng, size))
+                .collect();
+
+            round.add_call(index, testable.property, arguments);
+        }
+    }
+
+    let failed = match run(&round.src, generated_start) {
+        Ok(failed) => failed,
+        Err(reason) => {
+            return properties
+                .iter()
+                .map(|_| PropertyOutcome::Untestable {
+                    reason: reason.clone(),
+                })
+                .collect();
+        }
+    };
+
+    let mut failures = round.first_failures(&failed, properties.len());
+    let mut shrinks = vec![0; properties.len()];
+
+    for _ in 0..MAX_SHRINK_ROUNDS {
+        let mut round = Round::new(src_with_defs);
+
+        for (index, failure) in failures.iter().enumerate() {
+            if let Some(arguments) = failure {
+                for candidate in shrink_arguments(&properties[index].shapes, arguments) {
+                    round.add_call(index, properties[index].property, candidate);
+                }
+            }
+        }
+
+        if round.calls.is_empty() {
+            break;
+        }
+
+        // If shrinking goes wrong, the arguments we have still make the property fail.
+        let failed = match run(&round.src, generated_start) {
+            Ok(failed) => failed,
+            Err(_) => break,
+        };
+
+        let smaller = round.first_failures(&failed, properties.len());
+
+        if smaller.iter().all(Option::is_none) {
+            break;
+        }
+
+        for (index, smaller) in smaller.into_iter().enumerate() {
+            if let Some(arguments) = smaller {
+                failures[index] = Some(arguments);
+                shrinks[index] += 1;
+            }
+        }
+    }
+
+    failures
+        .into_iter()
+        .zip(shrinks)
+        .map(|(failure, shrinks)| match failure {
+            None => PropertyOutcome::Passed { cases },
+            Some(arguments) => PropertyOutcome::Failed {
+                cases,
+                arguments: arguments.iter().map(Value::to_roc).collect(),
+                shrinks,
+            },
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{rewrite_property_expects, Rng, Shape, Value};
+
+    #[test]
+    fn rewrite() {
+        let src = "x = 1\nexpect \\a, b ->\n    a + b == b + a\n\nmain = x\n";
+        let (with_defs, without, properties) = rewrite_property_expects(src);
+
+        assert_eq!(
+            with_defs,
+            "x = 1\np001 = \\a, b ->\n    a + b == b + a\n\nmain = x\n"
+        );
+        assert_eq!(
+            without,
+            "x = 1\n               \n                  \n\nmain = x\n"
+        );
+        assert_eq!(with_defs.len(), src.len());
+        assert_eq!(properties.len(), 1);
+        assert_eq!(properties[0].name, "p001");
+    }
+
+    #[test]
+    fn generated_values_fit_their_shape() {
+        let shape = Shape::List(Box::new(Shape::Int { min: 0, max: 255 }));
+        let mut rng = Rng::new(42);
+
+        for size in 0..100 {
+            match shape.generate(&mut rng, size) {
+                Value::List(elems) => assert!(elems
+                    .iter()
+                    .all(|elem| matches!(elem, Value::Int(n) if (0..=255).contains(n)))),
+                other => panic!("not a list: {:?}", other),
+            }
+        }
+    }
+
+    #[test]
+    fn shrinks_towards_zero() {
+        let shape = Shape::Int {
+            min: i64::MIN as i128,
+            max: i64::MAX as i128,
+        };
+
+        assert_eq!(
+            shape.shrink(&Value::Int(-10)),
+            vec![Value::Int(0), Value::Int(-5), Value::Int(-9)]
+        );
+        assert!(shape.shrink(&Value::Int(0)).is_empty());
+    }
+
+    #[test]
+    fn roc_literals() {
+        let value = Value::Record(vec![
+            ("name".to_string(), Value::Str("a\"b".to_string())),
+            (
+                "tags".to_string(),
+                Value::List(vec![Value::Tag("Ok".to_string(), vec![Value::Int(-1)])]),
+            ),
+            ("ratio".to_string(), Value::Frac(2.0)),
+        ]);
+
+        assert_eq!(
+            value.to_roc(),
+            "{ name: \"a\\\"b\", tags: [Ok (-1)], ratio: 2.0 }"
+        );
+    }
+}
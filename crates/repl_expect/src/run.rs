@@ -9,12 +9,15 @@ use std::{
 use bumpalo::collections::Vec as BumpVec;
 use bumpalo::Bump;
 use inkwell::context::Context;
-use roc_build::link::llvm_module_to_dylib;
+use roc_build::link::{llvm_module_to_dylib, llvm_module_to_global_dylib};
 use roc_can::expr::ExpectLookup;
 use roc_collections::{MutSet, VecMap};
 use roc_error_macros::internal_error;
 use roc_gen_llvm::{
-    llvm::{build::LlvmBackendMode, externs::add_default_roc_externs},
+    llvm::{
+        build::{DebugSources, LlvmBackendMode},
+        externs::add_default_roc_externs,
+    },
     run_roc::RocCallResult,
     run_roc_dylib,
 };
@@ -28,6 +31,10 @@ use roc_target::TargetInfo;
 use roc_types::subs::Subs;
 use target_lexicon::Triple;
 
+use crate::snapshot::{diff_lines, SnapshotExpect, SnapshotOutcome, Snapshots};
+use crate::stack_trace::{install_panic_hook, symbolize_frames, take_panic_frames};
+use crate::{coverage, fx_harness, leak_check};
+
 pub struct ExpectMemory<'a> {
     ptr: *mut u8,
     length: usize,
@@ -106,6 +113,12 @@ impl<'a> ExpectMemory<'a> {
         }
     }
 
+    fn unlink(&self) {
+        if let Some(shm_name) = &self.shm_name {
+            unsafe { libc::shm_unlink(shm_name.as_ptr().cast()) };
+        }
+    }
+
     fn set_shared_buffer(&mut self, lib: &libloading::Library) {
         let set_shared_buffer = run_roc_dylib!(lib, "set_shared_buffer", (*mut u8, usize), ());
         let mut result = RocCallResult::default();
@@ -147,6 +160,8 @@ pub fn run_inline_expects<'a, W: std::io::Write>(
         expectations,
         expects,
         &mut memory,
+        &Snapshots::default(),
+        1,
     )
 }
 
@@ -160,6 +175,8 @@ pub fn run_toplevel_expects<'a, W: std::io::Write>(
     lib: &libloading::Library,
     expectations: &mut VecMap<ModuleId, Expectations>,
     expects: ExpectFunctions<'_>,
+    snapshots: &Snapshots,
+    test_threads: usize,
 ) -> std::io::Result<(usize, usize)> {
     let shm_name = format!("/roc_expect_buffer_{}", std::process::id());
     let mut memory = ExpectMemory::create_or_reuse_mmap(&shm_name);
@@ -174,6 +191,8 @@ pub fn run_toplevel_expects<'a, W: std::io::Write>(
         expectations,
         expects,
         &mut memory,
+        snapshots,
+        test_threads,
     )
 }
 
@@ -188,10 +207,16 @@ pub(crate) fn run_expects_with_memory<'a, W: std::io::Write>(
     expectations: &mut VecMap<ModuleId, Expectations>,
     expects: ExpectFunctions<'_>,
     memory: &mut ExpectMemory,
+    snapshots: &Snapshots,
+    test_threads: usize,
 ) -> std::io::Result<(usize, usize)> {
     let mut failed = 0;
     let mut passed = 0;
 
+    install_panic_hook(lib);
+    leak_check::install_hooks(lib);
+    coverage::install_hook(lib);
+
     for expect in expects.fx {
         let result = run_expect_fx(
             writer,
@@ -211,6 +236,25 @@ pub(crate) fn run_expects_with_memory<'a, W: std::io::Write>(
         }
     }
 
+    let workers = test_threads.min(expects.pure.len());
+
+    if workers > 1 {
+        let (pure_failed, pure_passed) = run_expects_in_workers(
+            writer,
+            render_target,
+            arena,
+            interns,
+            layout_interner,
+            lib,
+            expectations,
+            &expects.pure,
+            snapshots,
+            workers,
+        )?;
+
+        return Ok((failed + pure_failed, passed + pure_passed));
+    }
+
     memory.set_shared_buffer(lib);
 
     for expect in expects.pure {
@@ -224,6 +268,7 @@ pub(crate) fn run_expects_with_memory<'a, W: std::io::Write>(
             expectations,
             memory,
             expect,
+            snapshots,
         )?;
 
         match result {
@@ -235,6 +280,250 @@ pub(crate) fn run_expects_with_memory<'a, W: std::io::Write>(
     Ok((failed, passed))
 }
 
+/// The outcome of one expect that ran in a worker process, along with what it printed
+struct WorkerResult {
+    index: usize,
+    passed: bool,
+    output: Vec<u8>,
+}
+
+impl WorkerResult {
+    fn write_to(&self, pipe: &mut impl std::io::Write) -> std::io::Result<()> {
+        pipe.write_all(&(self.index as u64).to_le_bytes())?;
+        pipe.write_all(&[self.passed as u8])?;
+        pipe.write_all(&(self.output.len() as u64).to_le_bytes())?;
+        pipe.write_all(&self.output)
+    }
+
+    fn read_all(mut bytes: &[u8]) -> Vec<Self> {
+        fn read_u64(bytes: &mut &[u8]) -> Option<u64> {
+            let (head, rest) = (bytes.get(..8)?, &bytes[8..]);
+            *bytes = rest;
+            Some(u64::from_le_bytes(head.try_into().unwrap()))
+        }
+
+        let mut results = Vec::new();
+
+        // A worker that crashed can leave a half-written result at the end, which we ignore.
+        while let Some(index) = read_u64(&mut bytes) {
+            let passed = match bytes.split_first() {
+                Some((passed, rest)) => {
+                    bytes = rest;
+                    *passed != 0
+                }
+                None => break,
+            };
+            let len = match read_u64(&mut bytes) {
+                Some(len) if len as usize <= bytes.len() => len as usize,
+                _ => break,
+            };
+            let (output, rest) = bytes.split_at(len);
+            bytes = rest;
+
+            results.push(WorkerResult {
+                index: index as usize,
+                passed,
+                output: output.to_vec(),
+            });
+        }
+
+        results
+    }
+}
+
+/// Run the pure expects across a few child processes, so a large test suite doesn't run one
+/// test at a time. Each worker has its own shared memory buffer, and renders the report for
+/// each of its tests into a buffer, which it sends back over a pipe. Then we print them in
+/// their original order, so the output doesn't depend on which worker finished first.
+/// If a worker dies (say, from a stack overflow), its remaining tests count as failures.
+#[allow(clippy::too_many_arguments)]
+fn run_expects_in_workers<'a, W: std::io::Write>(
+    writer: &mut W,
+    render_target: RenderTarget,
+    arena: &'a Bump,
+    interns: &'a Interns,
+    layout_interner: &Arc<GlobalInterner<'a, Layout<'a>>>,
+    lib: &libloading::Library,
+    expectations: &mut VecMap<ModuleId, Expectations>,
+    expects: &[ToplevelExpect<'_>],
+    snapshots: &Snapshots,
+    workers: usize,
+) -> std::io::Result<(usize, usize)> {
+    use std::fs::File;
+    use std::io::Read;
+    use std::os::unix::io::FromRawFd;
+
+    // Otherwise the workers would print anything still in the buffer again when they exit.
+    writer.flush()?;
+
+    let mut pipes = Vec::with_capacity(workers);
+    let mut worker_pids = Vec::with_capacity(workers);
+
+    for worker in 0..workers {
+        let mut fds = [0; 2];
+        if unsafe { libc::pipe(fds.as_mut_ptr()) } == -1 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let (read_fd, write_fd) = (fds[0], fds[1]);
+
+        match unsafe { libc::fork() } {
+            0 => {
+                // we are the child. A Rust panic must not unwind back into the parent's code.
+                let outcome = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                    unsafe { libc::close(read_fd) };
+                    let mut pipe = unsafe { File::from_raw_fd(write_fd) };
+
+                    let shm_name = format!("/roc_expect_buffer_{}", std::process::id());
+                    let mut memory = ExpectMemory::create_or_reuse_mmap(&shm_name);
+                    memory.set_shared_buffer(lib);
+
+                    for (index, expect) in expects.iter().enumerate().skip(worker).step_by(workers)
+                    {
+                        let mut output = Vec::new();
+                        let passed = run_expect_pure(
+                            &mut output,
+                            render_target,
+                            arena,
+                            interns,
+                            layout_interner,
+                            lib,
+                            expectations,
+                            &mut memory,
+                            *expect,
+                            snapshots,
+                        )
+                        .unwrap_or(false);
+
+                        let result = WorkerResult {
+                            index,
+                            passed,
+                            output,
+                        };
+
+                        if result.write_to(&mut pipe).is_err() {
+                            break;
+                        }
+                    }
+
+                    memory.unlink();
+                }));
+
+                // Skip the exit handlers and destructors, which belong to the parent and
+                // would otherwise run a second time in each worker.
+                unsafe { libc::_exit(outcome.is_err() as i32) }
+            }
+            -1 => return Err(std::io::Error::last_os_error()),
+            worker_pid => {
+                unsafe { libc::close(write_fd) };
+                pipes.push(unsafe { File::from_raw_fd(read_fd) });
+                worker_pids.push(worker_pid);
+            }
+        }
+    }
+
+    // Read from all of the workers at once, so none of them blocks on a full pipe.
+    let outputs: Vec<Vec<u8>> = std::thread::scope(|scope| {
+        let readers: Vec<_> = pipes
+            .into_iter()
+            .map(|mut pipe| {
+                scope.spawn(move || {
+                    let mut bytes = Vec::new();
+                    let _ = pipe.read_to_end(&mut bytes);
+                    bytes
+                })
+            })
+            .collect();
+
+        readers
+            .into_iter()
+            .map(|reader| reader.join().unwrap_or_default())
+            .collect()
+    });
+
+    // Reap the workers, so they don't stick around as zombies. Only these ones, since other
+    // children (like the expect-fx ones) are waited for by whoever started them.
+    for worker_pid in worker_pids {
+        unsafe { libc::waitpid(worker_pid, std::ptr::null_mut(), 0) };
+    }
+
+    let mut results: Vec<Option<WorkerResult>> = expects.iter().map(|_| None).collect();
+
+    for result in outputs
+        .iter()
+        .flat_map(|bytes| WorkerResult::read_all(bytes))
+    {
+        if let Some(slot) = results.get_mut(result.index) {
+            *slot = Some(result);
+        }
+    }
+
+    let mut failed = 0;
+    let mut passed = 0;
+
+    for (expect, result) in expects.iter().zip(results) {
+        match result {
+            Some(result) => {
+                writer.write_all(&result.output)?;
+
+                match result.passed {
+                    true => passed += 1,
+                    false => failed += 1,
+                }
+            }
+            None => {
+                let module_id = expect.symbol.module_id();
+                let data = expectations.get_mut(&module_id).unwrap();
+                let filename = data.path.to_owned();
+                let source = std::fs::read_to_string(&data.path).unwrap();
+
+                let renderer =
+                    Renderer::new(arena, interns, render_target, module_id, filename, &source);
+                let region = snapshots
+                    .find(expect.region)
+                    .map_or(expect.region, |snapshot| snapshot.region);
+
+                renderer.render_panic(
+                    writer,
+                    "The process running this test stopped unexpectedly.",
+                    region,
+                    &[],
+                )?;
+                writeln!(writer)?;
+
+                failed += 1;
+            }
+        }
+    }
+
+    Ok((failed, passed))
+}
+
+/// The regions of the given pure toplevel expects that fail (or crash), without rendering
+/// anything; used to try many arguments of property tests.
+pub fn failing_expects(lib: &libloading::Library, expects: &[ToplevelExpect<'_>]) -> Vec<Region> {
+    use roc_gen_llvm::try_run_jit_function;
+
+    let shm_name = format!("/roc_expect_buffer_{}", std::process::id());
+    let mut memory = ExpectMemory::create_or_reuse_mmap(&shm_name);
+
+    memory.set_shared_buffer(lib);
+
+    let mut failed = Vec::new();
+
+    for expect in expects {
+        let sequence = ExpectSequence::new(memory.ptr.cast());
+
+        let result: Result<(), (String, _)> =
+            try_run_jit_function!(lib, expect.name, (), |v: ()| v);
+
+        if result.is_err() || sequence.count_failures() > 0 {
+            failed.push(expect.region);
+        }
+    }
+
+    failed
+}
+
 #[allow(clippy::too_many_arguments)]
 fn run_expect_pure<'a, W: std::io::Write>(
     writer: &mut W,
@@ -246,12 +535,15 @@ fn run_expect_pure<'a, W: std::io::Write>(
     expectations: &mut VecMap<ModuleId, Expectations>,
     shared_memory: &mut ExpectMemory,
     expect: ToplevelExpect<'_>,
+    snapshots: &Snapshots,
 ) -> std::io::Result<bool> {
     use roc_gen_llvm::try_run_jit_function;
 
     let sequence = ExpectSequence::new(shared_memory.ptr.cast());
 
+    leak_check::start();
     let result: Result<(), (String, _)> = try_run_jit_function!(lib, expect.name, (), |v: ()| v);
+    let memory_problems = leak_check::finish();
 
     let shared_memory_ptr: *const u8 = shared_memory.ptr.cast();
 
@@ -265,8 +557,28 @@ fn run_expect_pure<'a, W: std::io::Write>(
 
         let renderer = Renderer::new(arena, interns, render_target, module_id, filename, &source);
 
+        let snapshot = snapshots.find(expect.region);
+
         if let Err((roc_panic_message, _roc_panic_tag)) = result {
-            renderer.render_panic(writer, &roc_panic_message, expect.region)?;
+            renderer.render_panic(
+                writer,
+                &roc_panic_message,
+                snapshot.map_or(expect.region, |snapshot| snapshot.region),
+                &take_panic_frames(),
+            )?;
+        } else if let Some(snapshot) = snapshot {
+            // The expects generated for snapshots always fail, so that we get to see the value.
+            return check_snapshot(
+                writer,
+                &renderer,
+                arena,
+                expectations,
+                interns,
+                layout_interner,
+                shared_memory_ptr,
+                snapshots,
+                snapshot,
+            );
         } else {
             let mut offset = ExpectSequence::START_OFFSET;
 
@@ -287,6 +599,19 @@ fn run_expect_pure<'a, W: std::io::Write>(
 
         writeln!(writer)?;
 
+        Ok(false)
+    } else if !memory_problems.is_empty() {
+        // A test that failed can't be expected to have cleaned up after itself.
+        let module_id = expect.symbol.module_id();
+        let data = expectations.get_mut(&module_id).unwrap();
+        let filename = data.path.to_owned();
+        let source = std::fs::read_to_string(&data.path).unwrap();
+
+        let renderer = Renderer::new(arena, interns, render_target, module_id, filename, &source);
+
+        renderer.render_memory_problems(writer, expect.region, &memory_problems)?;
+        writeln!(writer)?;
+
         Ok(false)
     } else {
         Ok(true)
@@ -321,11 +646,20 @@ fn run_expect_fx<'a, W: std::io::Write>(
 
             child_memory.set_shared_buffer(lib);
 
+            fx_harness::setup();
             let result: Result<(), (String, _)> =
                 try_run_jit_function!(lib, expect.name, (), |v: ()| v);
+            fx_harness::teardown();
 
             if let Err((msg, _)) = result {
-                panic!("roc panic {}", msg);
+                let mut trace = String::new();
+
+                for frame in take_panic_frames() {
+                    trace.push_str("\n    at ");
+                    trace.push_str(&frame);
+                }
+
+                panic!("roc panic {}{}", msg, trace);
             }
 
             if sequence.count_failures() > 0 {
@@ -342,14 +676,44 @@ fn run_expect_fx<'a, W: std::io::Write>(
 
             std::process::exit(1)
         }
-        1.. => {
+        child_pid @ 1.. => {
             let mut has_succeeded = true;
 
             for sig in &mut signals {
                 match sig {
                     SIGCHLD => {
-                        // done!
-                        return Ok(has_succeeded);
+                        // done! An effect the harness doesn't provide makes the child crash.
+                        let mut status = 0;
+                        unsafe { libc::waitpid(child_pid, &mut status, 0) };
+
+                        if libc::WIFEXITED(status) && libc::WEXITSTATUS(status) == 0 {
+                            return Ok(has_succeeded);
+                        }
+
+                        let module_id = expect.symbol.module_id();
+                        let data = expectations.get_mut(&module_id).unwrap();
+                        let filename = data.path.to_owned();
+                        let source = std::fs::read_to_string(&data.path).unwrap();
+
+                        let renderer = Renderer::new(
+                            arena,
+                            interns,
+                            render_target,
+                            module_id,
+                            filename,
+                            &source,
+                        );
+
+                        renderer.render_panic(
+                            writer,
+                            "The process running this test crashed. If it called an effect, \
+                            check that the platform's test harness (or --fx-harness) provides it.",
+                            expect.region,
+                            &[],
+                        )?;
+                        writeln!(writer)?;
+
+                        return Ok(false);
                     }
                     SIGUSR1 => {
                         // this is the signal we use for an expect failure. Let's see what the child told us
@@ -433,6 +797,32 @@ pub fn render_expects_in_memory<'a>(
     )
 }
 
+/// Where the frames of a panic start in the shared memory, in words; see `notifyParentPanic`
+/// in the builtins.
+const PANIC_FRAMES_OFFSET: usize = 3;
+const MAX_PANIC_FRAMES: usize = 64;
+
+/// The Roc frames of the stack the child sent when it panicked, innermost first. `executable`
+/// is the child's executable, whose debug info maps the frames back to Roc procedures.
+pub fn panic_frames_in_memory(memory: &ExpectMemory, executable: &[u8]) -> Vec<String> {
+    let words = memory.ptr as *const usize;
+
+    let (anchor, count) = unsafe {
+        (
+            std::ptr::read_unaligned(words.add(PANIC_FRAMES_OFFSET)),
+            std::ptr::read_unaligned(words.add(PANIC_FRAMES_OFFSET + 1)),
+        )
+    };
+
+    let addresses: Vec<usize> = (0..count.min(MAX_PANIC_FRAMES))
+        .map(|index| unsafe {
+            std::ptr::read_unaligned(words.add(PANIC_FRAMES_OFFSET + 2 + index))
+        })
+        .collect();
+
+    symbolize_frames(executable, anchor, &addresses)
+}
+
 pub fn render_dbgs_in_memory<'a>(
     writer: &mut impl std::io::Write,
     arena: &'a Bump,
@@ -532,6 +922,7 @@ fn render_dbg_failure<'a>(
         start,
         frame.start_offset,
         1,
+        data.frac_default,
     );
 
     renderer.render_dbg(writer, &expressions, expect_region, failure_region)?;
@@ -539,6 +930,63 @@ fn render_dbg_failure<'a>(
     Ok(offset)
 }
 
+#[allow(clippy::too_many_arguments)]
+fn check_snapshot<'a>(
+    writer: &mut impl std::io::Write,
+    renderer: &Renderer,
+    arena: &'a Bump,
+    expectations: &mut VecMap<ModuleId, Expectations>,
+    interns: &'a Interns,
+    layout_interner: &Arc<GlobalInterner<'a, Layout<'a>>>,
+    start: *const u8,
+    snapshots: &Snapshots,
+    snapshot: &SnapshotExpect,
+) -> std::io::Result<bool> {
+    // we always run programs as the host
+    let target_info = (&target_lexicon::Triple::host()).into();
+
+    let frame = ExpectFrame::at_offset(start, ExpectSequence::START_OFFSET);
+    let failure_region = frame.region;
+
+    let data = expectations.get_mut(&frame.module_id).unwrap();
+
+    let current = match data.expectations.get(&failure_region) {
+        None => panic!("region {failure_region:?} not in list of expects"),
+        Some(current) => current,
+    };
+
+    let symbols = split_expect_lookups(&data.subs, current);
+
+    let (_offset, expressions, _variables) = crate::get_values(
+        target_info,
+        arena,
+        &data.subs,
+        interns,
+        layout_interner,
+        start,
+        frame.start_offset,
+        symbols.len(),
+        data.frac_default,
+    );
+
+    let rendered = match expressions.first() {
+        Some(expr) => renderer.format_value(expr),
+        None => internal_error!("the expect of a snapshot did not look up its value"),
+    };
+
+    match snapshots.check(snapshot, &rendered)? {
+        SnapshotOutcome::Recorded | SnapshotOutcome::Matched => Ok(true),
+        SnapshotOutcome::Changed { recorded } => {
+            let diff = diff_lines(&recorded, &rendered);
+
+            renderer.render_snapshot_mismatch(writer, snapshot.region, &snapshot.path, &diff)?;
+            writeln!(writer)?;
+
+            Ok(false)
+        }
+    }
+}
+
 #[allow(clippy::too_many_arguments)]
 fn render_expect_failure<'a>(
     writer: &mut impl std::io::Write,
@@ -578,6 +1026,7 @@ fn render_expect_failure<'a>(
         start,
         frame.start_offset,
         symbols.len(),
+        data.frac_default,
     );
 
     renderer.render_failure(
@@ -636,6 +1085,7 @@ impl ExpectSequence {
                 0 => std::hint::spin_loop(),
                 1 => break ChildProcessMsg::Expect,
                 2 => break ChildProcessMsg::Dbg,
+                4 => break ChildProcessMsg::Panic,
                 n => panic!("invalid atomic value set by the child: {:#x}", n),
             }
         }
@@ -655,6 +1105,7 @@ pub enum ChildProcessMsg {
     Expect = 1,
     Dbg = 2,
     Terminate = 3,
+    Panic = 4,
 }
 
 struct ExpectFrame {
@@ -710,20 +1161,52 @@ pub fn expect_mono_module_to_dylib<'a>(
     ),
     libloading::Error,
 > {
-    let target_info = TargetInfo::from(&target);
+    // The harness's effects are undefined in the dylib, and its allocations go through the
+    // dylib's roc_alloc, so they need to see each other's symbols.
+    let needs_harness = fx_harness::is_loaded() && !loaded.toplevel_expects.fx.is_empty();
+
+    let (lib, expects, layout_interner) =
+        expect_mono_module_to_llvm(arena, &target, loaded, opt_level, mode, |module| {
+            if needs_harness {
+                llvm_module_to_global_dylib(module, &target, opt_level)
+            } else {
+                llvm_module_to_dylib(module, &target, opt_level)
+            }
+        });
+
+    lib.map(|lib| (lib, expects, layout_interner))
+}
+
+/// Generate the LLVM module that exposes each toplevel expect, and hand it to `finish`, e.g. to
+/// turn it into a dylib.
+pub(crate) fn expect_mono_module_to_llvm<'a, T>(
+    arena: &'a Bump,
+    target: &Triple,
+    loaded: MonomorphizedModule<'a>,
+    opt_level: OptLevel,
+    mode: LlvmBackendMode,
+    finish: impl FnOnce(&inkwell::module::Module) -> T,
+) -> (
+    T,
+    ExpectFunctions<'a>,
+    SingleThreadedInterner<'a, Layout<'a>>,
+) {
+    let target_info = TargetInfo::from(target);
 
     let MonomorphizedModule {
         toplevel_expects,
         procedures,
         interns,
         layout_interner,
+        sources,
+        def_regions,
         ..
     } = loaded;
 
     let context = Context::create();
     let builder = context.create_builder();
     let module = arena.alloc(roc_gen_llvm::llvm::build::module_from_builtins(
-        &target, &context, "",
+        target, &context, "",
     ));
 
     let module = arena.alloc(module);
@@ -746,6 +1229,7 @@ pub fn expect_mono_module_to_dylib<'a>(
         mode,
         // important! we don't want any procedures to get the C calling convention
         exposed_to_host: MutSet::default(),
+        debug_sources: DebugSources::new(&sources, &def_regions),
     };
 
     // Add roc_alloc, roc_realloc, and roc_dealloc, since the repl has no
@@ -798,8 +1282,7 @@ pub fn expect_mono_module_to_dylib<'a>(
 
     env.dibuilder.finalize();
 
-    // we don't use the debug info, and it causes weird errors.
-    module.strip_debug_info();
+    // Keep the debug info: it's what maps the stack of a panicking expect back to Roc procedures.
 
     // Uncomment this to see the module's un-optimized LLVM instruction output:
     // env.module.print_to_stderr();
@@ -819,5 +1302,5 @@ pub fn expect_mono_module_to_dylib<'a>(
         );
     }
 
-    llvm_module_to_dylib(env.module, &target, opt_level).map(|lib| (lib, expects, layout_interner))
+    (finish(env.module), expects, layout_interner)
 }
@@ -0,0 +1,291 @@
+//! Snapshot tests: `expect snapshot <expr>` records how the value of `<expr>` renders in a file
+//! the first time it runs, and fails later runs if it renders differently.
+//!
+//! Like doc tests, this doesn't need any help from the compiler. Each snapshot expect becomes
+//! a top-level def of the same length (so everything else stays where it was), and we add an
+//! `expect` at the end of the module that always fails, which makes the value come back to us
+//! the same way the values of a failed expect's variables do.
+use std::path::{Path, PathBuf};
+
+use roc_region::all::{Position, Region};
+
+/// Where the snapshots are kept, next to the module they're in
+pub const SNAPSHOTS_DIR: &str = "snapshots";
+
+const SNAPSHOT_EXPECT: &str = "expect snapshot ";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotExpect {
+    /// Where the `expect snapshot` is in the original source
+    pub region: Region,
+    /// Where the `expect` we generated for it starts in the rewritten source
+    generated_offset: u32,
+    /// The file its snapshot is kept in
+    pub path: PathBuf,
+}
+
+#[derive(Debug, Default)]
+pub struct Snapshots {
+    pub expects: Vec<SnapshotExpect>,
+    /// Record the current values, rather than comparing them to the recorded ones
+    pub update: bool,
+}
+
+pub(crate) enum SnapshotOutcome {
+    Recorded,
+    Matched,
+    Changed { recorded: String },
+}
+
+impl Snapshots {
+    /// The snapshot expect that the toplevel expect at this region was generated for, if any
+    pub(crate) fn find(&self, expect_region: Region) -> Option<&SnapshotExpect> {
+        self.expects.iter().find(|snapshot| {
+            let offset = snapshot.generated_offset;
+
+            expect_region.start().offset <= offset && offset < expect_region.end().offset
+        })
+    }
+
+    pub(crate) fn check(
+        &self,
+        snapshot: &SnapshotExpect,
+        rendered: &str,
+    ) -> std::io::Result<SnapshotOutcome> {
+        let recorded = match std::fs::read_to_string(&snapshot.path) {
+            Ok(recorded) if !self.update => recorded,
+            Ok(_) => return record(snapshot, rendered),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                return record(snapshot, rendered);
+            }
+            Err(err) => return Err(err),
+        };
+
+        if recorded.trim_end() == rendered.trim_end() {
+            Ok(SnapshotOutcome::Matched)
+        } else {
+            Ok(SnapshotOutcome::Changed { recorded })
+        }
+    }
+}
+
+fn record(snapshot: &SnapshotExpect, rendered: &str) -> std::io::Result<SnapshotOutcome> {
+    if let Some(dir) = snapshot.path.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+
+    std::fs::write(&snapshot.path, format!("{}\n", rendered.trim_end()))?;
+
+    Ok(SnapshotOutcome::Recorded)
+}
+
+/// The module's source with its snapshot expects rewritten so they can run, and where the
+/// snapshots of the module at the given path are.
+pub fn rewrite_snapshot_expects(module_path: &Path, src: &str) -> (String, Vec<SnapshotExpect>) {
+    let mut buf = String::with_capacity(src.len());
+    let mut generated = String::new();
+    let mut expects = Vec::new();
+    let mut exprs = Vec::new();
+
+    let mut offset = 0;
+    let mut lines = src.split_inclusive('\n').peekable();
+
+    while let Some(line) = lines.next() {
+        let start = offset;
+        offset += line.len();
+
+        let first_line = match line.strip_prefix(SNAPSHOT_EXPECT) {
+            Some(rest) => rest,
+            None => {
+                buf.push_str(line);
+
+                continue;
+            }
+        };
+
+        // `rocSnap000001 = ` is exactly as long as `expect snapshot `
+        let name = format!("rocSnap{:06}", expects.len() + 1);
+
+        buf.push_str(&name);
+        buf.push_str(" = ");
+        buf.push_str(first_line);
+
+        let mut expr = first_line.trim().to_string();
+
+        // The expression goes on for as long as the lines are indented.
+        while let Some(next) = lines.peek() {
+            if !next.starts_with(' ') || next.trim().is_empty() {
+                break;
+            }
+
+            offset += next.len();
+            expr.push(' ');
+            expr.push_str(next.trim());
+            buf.push_str(next);
+            lines.next();
+        }
+
+        let end = src[..offset].trim_end().len();
+
+        // Where it is in the generated expects; made relative to the whole source below.
+        let generated_offset = generated.len() + 1;
+
+        generated.push_str(&format!(
+            "\nexpect\n    rocSnapshotValue = {}\n    rocSnapshotValue != rocSnapshotValue\n",
+            name
+        ));
+
+        expects.push(SnapshotExpect {
+            region: Region::new(Position::new(start as u32), Position::new(end as u32)),
+            generated_offset: generated_offset as u32,
+            path: PathBuf::new(),
+        });
+        exprs.push(expr);
+    }
+
+    if expects.is_empty() {
+        return (buf, expects);
+    }
+
+    if !buf.ends_with('\n') {
+        buf.push('\n');
+    }
+
+    let generated_start = buf.len() as u32;
+    buf.push_str(&generated);
+
+    let dir = module_path
+        .parent()
+        .unwrap_or_else(|| Path::new("."))
+        .join(SNAPSHOTS_DIR);
+    let stem = module_path
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or("snapshot");
+
+    let mut used_names = Vec::with_capacity(expects.len());
+
+    for (snapshot, expr) in expects.iter_mut().zip(exprs) {
+        let base = format!("{}-{}", stem, slug(&expr));
+        let mut file_name = base.clone();
+        let mut n = 1;
+
+        while used_names.contains(&file_name) {
+            n += 1;
+            file_name = format!("{}-{}", base, n);
+        }
+
+        snapshot.path = dir.join(format!("{}.txt", file_name));
+        snapshot.generated_offset += generated_start;
+
+        used_names.push(file_name);
+    }
+
+    (buf, expects)
+}
+
+/// A file name for the snapshot of the given expression, e.g. `render-bigInput`
+fn slug(expr: &str) -> String {
+    let mut slug = String::with_capacity(expr.len());
+
+    for c in expr.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+
+        if slug.len() >= 60 {
+            break;
+        }
+    }
+
+    let slug = slug.trim_end_matches('-');
+
+    if slug.is_empty() {
+        "snapshot".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// The lines of a line-by-line diff from the recorded snapshot to the new one, starting with
+/// `-` for removed lines, `+` for added ones and a space for the ones they have in common
+pub(crate) fn diff_lines(recorded: &str, rendered: &str) -> Vec<String> {
+    let old: Vec<&str> = recorded.trim_end().lines().collect();
+    let new: Vec<&str> = rendered.trim_end().lines().collect();
+
+    // lcs[i][j] is the length of the longest common subsequence of old[i..] and new[j..]
+    let mut lcs = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            lcs[i][j] = if old[i] == new[j] {
+                lcs[i + 1][j + 1] + 1
+            } else {
+                lcs[i + 1][j].max(lcs[i][j + 1])
+            };
+        }
+    }
+
+    let mut diff = Vec::with_capacity(old.len().max(new.len()));
+    let (mut i, mut j) = (0, 0);
+
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            diff.push(format!("  {}", old[i]));
+            i += 1;
+            j += 1;
+        } else if j < new.len() && (i == old.len() || lcs[i][j + 1] >= lcs[i + 1][j]) {
+            diff.push(format!("+ {}", new[j]));
+            j += 1;
+        } else {
+            diff.push(format!("- {}", old[i]));
+            i += 1;
+        }
+    }
+
+    diff
+}
+
+#[cfg(test)]
+mod test {
+    use super::{diff_lines, rewrite_snapshot_expects, SNAPSHOTS_DIR};
+    use std::path::Path;
+
+    #[test]
+    fn rewrite() {
+        let src = "app \"test\" provides [main] to \"./platform\"\n\nexpect snapshot render 42\n\nmain = 1\n";
+        let (rewritten, expects) = rewrite_snapshot_expects(Path::new("/proj/Main.roc"), src);
+
+        assert_eq!(
+            rewritten,
+            "app \"test\" provides [main] to \"./platform\"\n\nrocSnap000001 = render 42\n\nmain = 1\n\nexpect\n    rocSnapshotValue = rocSnap000001\n    rocSnapshotValue != rocSnapshotValue\n"
+        );
+
+        assert_eq!(expects.len(), 1);
+        assert_eq!(
+            &src[expects[0].region.start().offset as usize
+                ..expects[0].region.end().offset as usize],
+            "expect snapshot render 42"
+        );
+        assert_eq!(
+            &rewritten[expects[0].generated_offset as usize..][..6],
+            "expect"
+        );
+        assert_eq!(
+            expects[0].path,
+            Path::new("/proj")
+                .join(SNAPSHOTS_DIR)
+                .join("Main-render-42.txt")
+        );
+    }
+
+    #[test]
+    fn diff() {
+        assert_eq!(
+            diff_lines("a\nb\nc\n", "a\nc\nd"),
+            vec!["  a", "- b", "  c", "+ d"]
+        );
+    }
+}
@@ -0,0 +1,196 @@
+//! Roc-level stack traces for the panics of `roc test` and `roc dev`.
+//!
+//! The testing `roc_panic` calls back into us (through `roc_panic_hook`) before it unwinds, so
+//! we capture the stack at that point and later keep only the frames whose debug info points
+//! at a `.roc` file, since those are the Roc procedures that were running.
+//!
+//! A program run by `roc dev` is another process, so it sends us the return addresses of its
+//! stack through the shared memory that expects use, and we look them up in the debug info of
+//! its executable.
+use std::path::Path;
+use std::sync::Mutex;
+
+use addr2line::object::{Object, ObjectSymbol};
+
+use backtrace::Backtrace;
+use roc_gen_llvm::llvm::externs::ROC_PANIC_HOOK;
+
+/// Recursion can make for very deep stacks; the innermost frames are the interesting ones.
+const MAX_FRAMES: usize = 32;
+
+static PANIC_BACKTRACE: Mutex<Option<Backtrace>> = Mutex::new(None);
+
+extern "C" fn capture_panic_backtrace() {
+    // Resolving symbols is slow, so only do it if the panic is actually rendered.
+    let backtrace = Backtrace::new_unresolved();
+
+    if let Ok(mut slot) = PANIC_BACKTRACE.lock() {
+        *slot = Some(backtrace);
+    }
+}
+
+/// Make the `roc_panic` of the expect dylib capture a backtrace before it unwinds.
+pub(crate) fn install_panic_hook(lib: &libloading::Library) {
+    type Hook = Option<extern "C" fn()>;
+
+    unsafe {
+        if let Ok(hook) = lib.get::<*mut Hook>(ROC_PANIC_HOOK.as_bytes()) {
+            **hook = Some(capture_panic_backtrace);
+        }
+    }
+}
+
+/// The Roc frames of the stack at the last panic, innermost first, e.g.
+/// `Main.parse (/home/user/main.roc:12)`.
+pub(crate) fn take_panic_frames() -> Vec<String> {
+    let backtrace = PANIC_BACKTRACE.lock().ok().and_then(|mut slot| slot.take());
+
+    match backtrace {
+        Some(backtrace) => roc_frames(backtrace),
+        None => Vec::new(),
+    }
+}
+
+/// The Roc frames of a backtrace, innermost first
+pub(crate) fn roc_frames(mut backtrace: Backtrace) -> Vec<String> {
+    backtrace.resolve();
+
+    let mut frames = Vec::new();
+
+    let symbols = backtrace.frames().iter().flat_map(|frame| frame.symbols());
+
+    for symbol in symbols {
+        let name = symbol.name();
+        let name = name.as_ref().and_then(|name| name.as_str());
+
+        if let Some(frame) = format_frame(name, symbol.filename(), symbol.lineno()) {
+            frames.push(frame);
+        }
+    }
+
+    truncate_frames(frames)
+}
+
+/// The name the builtins' `notifyParentPanic` has in a Roc executable. The child sends its
+/// address along with the stack, which tells us where the executable was loaded.
+const PANIC_ANCHOR_SYMBOL: &str = "roc_builtins.utils.notify_parent_panic";
+
+/// The Roc frames of a stack that a `roc dev` program sent when it panicked, innermost first.
+/// `anchor` is where the program's `notifyParentPanic` was loaded.
+pub(crate) fn symbolize_frames(
+    executable: &[u8],
+    anchor: usize,
+    addresses: &[usize],
+) -> Vec<String> {
+    let file = match addr2line::object::File::parse(executable) {
+        Ok(file) => file,
+        Err(_) => return Vec::new(),
+    };
+
+    let context = match addr2line::Context::new(&file) {
+        Ok(context) => context,
+        Err(_) => return Vec::new(),
+    };
+
+    // Position-independent executables are loaded at a different address every time.
+    let slide = file
+        .symbols()
+        .find(|symbol| matches!(symbol.name(), Ok(name) if name == PANIC_ANCHOR_SYMBOL))
+        .map_or(0, |symbol| (anchor as u64).wrapping_sub(symbol.address()));
+
+    let mut frames = Vec::new();
+
+    for address in addresses {
+        // A return address points after the call, which may be on the next line already.
+        let probe = (*address as u64).wrapping_sub(slide).wrapping_sub(1);
+
+        let mut found = match context.find_frames(probe) {
+            Ok(found) => found,
+            Err(_) => continue,
+        };
+
+        // Inlined procedures come first, then the procedure they were inlined into.
+        while let Ok(Some(frame)) = found.next() {
+            let name = frame
+                .function
+                .as_ref()
+                .and_then(|function| function.raw_name().ok());
+            let location = frame.location.as_ref();
+
+            if let Some(frame) = format_frame(
+                name.as_deref(),
+                location.and_then(|location| location.file).map(Path::new),
+                location.and_then(|location| location.line),
+            ) {
+                frames.push(frame);
+            }
+        }
+    }
+
+    truncate_frames(frames)
+}
+
+/// A frame as `Main.parse (/home/user/main.roc:12)`, or `None` if it isn't in a `.roc` file
+fn format_frame(name: Option<&str>, path: Option<&Path>, line: Option<u32>) -> Option<String> {
+    let path = match path {
+        Some(path) if path.extension().map_or(false, |ext| ext == "roc") => path,
+        _ => return None,
+    };
+
+    let name = match name {
+        Some(name) => demangle_proc_name(name).unwrap_or_else(|| name.to_string()),
+        None => "<unknown>".to_string(),
+    };
+
+    let frame = match line {
+        Some(line) => format!("{} ({}:{})", name, path.display(), line),
+        None => format!("{} ({})", name, path.display()),
+    };
+
+    Some(frame)
+}
+
+fn truncate_frames(mut frames: Vec<String>) -> Vec<String> {
+    if frames.len() > MAX_FRAMES {
+        let hidden = frames.len() - MAX_FRAMES;
+
+        frames.truncate(MAX_FRAMES);
+        frames.push(format!("... and {} more", hidden));
+    }
+
+    frames
+}
+
+/// Turn the name of a procedure's LLVM function, `Module_ident_<specialization>`, back into
+/// the `Module.ident` it was written as.
+fn demangle_proc_name(name: &str) -> Option<String> {
+    let (rest, specialization) = name.rsplit_once('_')?;
+
+    if specialization.is_empty() || !specialization.bytes().all(|b| b.is_ascii_hexdigit()) {
+        return None;
+    }
+
+    match rest.split_once('_')? {
+        ("", _) | (_, "") => None,
+        (module, ident) => Some(format!("{}.{}", module, ident)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::demangle_proc_name;
+
+    #[test]
+    fn demangle() {
+        assert_eq!(
+            demangle_proc_name("Main_parseLine_a3f0"),
+            Some("Main.parseLine".to_string())
+        );
+        assert_eq!(
+            demangle_proc_name("Num_add_1b"),
+            Some("Num.add".to_string())
+        );
+        assert_eq!(demangle_proc_name("roc_panic"), None);
+        assert_eq!(demangle_proc_name("memcpy"), None);
+    }
+}
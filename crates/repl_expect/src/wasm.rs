@@ -0,0 +1,350 @@
+//! Running toplevel expects compiled to wasm32, for `roc test --target=wasm32`.
+//!
+//! The expects run in a WASI runtime, so they see the same environment as the programs people
+//! ship to the browser. By default that's the interpreter in roc_wasm_interp; setting
+//! ROC_WASM_RUNTIME=wasmtime (or the path to a wasmtime binary) runs each expect in its own
+//! wasmtime process instead.
+//!
+//! On wasm32, expects can't share memory with the test runner, so a failed expect stops the
+//! test like a crash does, and its report can't show the values the expect looked up.
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use bumpalo::Bump;
+use roc_build::link::llvm_module_to_wasm;
+use roc_collections::VecMap;
+use roc_gen_llvm::llvm::build::LlvmBackendMode;
+use roc_load::{Expectations, MonomorphizedModule};
+use roc_module::symbol::{Interns, ModuleId};
+use roc_mono::ir::OptLevel;
+use roc_reporting::{error::expect::Renderer, report::RenderTarget};
+use roc_wasm_interp::{DefaultImportDispatcher, Instance, Value};
+use target_lexicon::{Architecture, BinaryFormat, OperatingSystem, Triple};
+
+use crate::run::{expect_mono_module_to_llvm, ExpectFunctions, ToplevelExpect};
+
+const RUNTIME_VAR: &str = "ROC_WASM_RUNTIME";
+
+/// What the wasm32 code generator panics with when an expect fails
+const EXPECT_FAILED_MESSAGE: &str = "An expectation failed!";
+
+/// The size and alignment of a `RocCallResult<()>` on wasm32: a u64 tag and a pointer to the
+/// panic message
+const CALL_RESULT_SIZE: i32 = 16;
+const CALL_RESULT_ALIGN: i32 = 8;
+
+pub enum WasmRuntime {
+    Interpreter,
+    /// A wasmtime binary, which runs each expect in its own process
+    Wasmtime(PathBuf),
+}
+
+impl WasmRuntime {
+    pub fn from_env() -> Self {
+        match std::env::var_os(RUNTIME_VAR) {
+            Some(runtime) if runtime != "interpreter" => Self::Wasmtime(PathBuf::from(runtime)),
+            _ => Self::Interpreter,
+        }
+    }
+}
+
+enum Outcome {
+    Passed,
+    Failed(String),
+}
+
+pub fn expect_mono_module_to_wasm<'a>(
+    arena: &'a Bump,
+    loaded: MonomorphizedModule<'a>,
+    opt_level: OptLevel,
+) -> std::io::Result<(Vec<u8>, ExpectFunctions<'a>)> {
+    let target = Triple {
+        architecture: Architecture::Wasm32,
+        operating_system: OperatingSystem::Wasi,
+        binary_format: BinaryFormat::Wasm,
+        ..Triple::unknown()
+    };
+
+    let (wasm_bytes, expects, _layout_interner) = expect_mono_module_to_llvm(
+        arena,
+        &target,
+        loaded,
+        opt_level,
+        LlvmBackendMode::CliTest,
+        |module| llvm_module_to_wasm(module, opt_level),
+    );
+
+    wasm_bytes.map(|bytes| (bytes, expects))
+}
+
+/// Run every toplevel expect in the given runtime, printing a report for each one that fails.
+/// Returns how many failed and how many passed.
+#[allow(clippy::too_many_arguments)]
+pub fn run_toplevel_expects_wasm<'a, W: Write>(
+    writer: &mut W,
+    render_target: RenderTarget,
+    arena: &'a Bump,
+    interns: &'a Interns,
+    expectations: &mut VecMap<ModuleId, Expectations>,
+    wasm_bytes: &[u8],
+    expects: ExpectFunctions<'_>,
+    runtime: &WasmRuntime,
+) -> std::io::Result<(usize, usize)> {
+    // wasmtime reads the module from a file, so we only write one if we need it.
+    let wasm_file = match runtime {
+        WasmRuntime::Interpreter => None,
+        WasmRuntime::Wasmtime(_) => {
+            let mut file = tempfile::Builder::new().suffix(".wasm").tempfile()?;
+            file.write_all(wasm_bytes)?;
+
+            Some(file)
+        }
+    };
+
+    let mut failed = 0;
+    let mut passed = 0;
+
+    for expect in expects.fx.iter().chain(expects.pure.iter()) {
+        let outcome = match (runtime, &wasm_file) {
+            (WasmRuntime::Wasmtime(wasmtime), Some(file)) => {
+                run_in_wasmtime(writer, wasmtime, file.path(), expect.name)?
+            }
+            _ => run_in_interpreter(wasm_bytes, expect.name),
+        };
+
+        match outcome {
+            Outcome::Passed => passed += 1,
+            Outcome::Failed(message) => {
+                render_failure(
+                    writer,
+                    render_target,
+                    arena,
+                    interns,
+                    expectations,
+                    expect,
+                    &message,
+                )?;
+
+                failed += 1;
+            }
+        }
+    }
+
+    Ok((failed, passed))
+}
+
+/// Each expect gets a fresh instance, so one that corrupts memory can't affect the others.
+fn run_in_interpreter(wasm_bytes: &[u8], expect_name: &str) -> Outcome {
+    let arena = Bump::new();
+
+    let result = Instance::from_bytes(
+        &arena,
+        wasm_bytes,
+        DefaultImportDispatcher::default(),
+        false,
+    )
+    .and_then(|mut instance| {
+        let allocated = instance.call_export(
+            "roc_alloc",
+            [Value::I32(CALL_RESULT_SIZE), Value::I32(CALL_RESULT_ALIGN)],
+        )?;
+
+        let result_addr = match allocated {
+            Some(Value::I32(addr)) => addr as usize,
+            other => return Err(format!("roc_alloc returned {:?}", other)),
+        };
+
+        instance.call_export(expect_name, [Value::I32(result_addr as i32)])?;
+
+        Ok(read_call_result(&instance.memory, result_addr))
+    });
+
+    match result {
+        Ok(outcome) => outcome,
+        Err(trap) => Outcome::Failed(format!("The test crashed:\n\n{}", trap.trim_end())),
+    }
+}
+
+fn read_call_result(memory: &[u8], addr: usize) -> Outcome {
+    let tag = u64::from_le_bytes(memory[addr..][..8].try_into().unwrap());
+
+    if tag == 0 {
+        Outcome::Passed
+    } else {
+        let message_addr = u32::from_le_bytes(memory[addr + 8..][..4].try_into().unwrap());
+
+        Outcome::Failed(read_roc_str(memory, message_addr as usize))
+    }
+}
+
+fn read_roc_str(memory: &[u8], addr: usize) -> String {
+    let str_bytes = &memory[addr..][..12];
+    let last_byte = str_bytes[11];
+
+    let bytes = if last_byte >= 0x80 {
+        // a small string, stored inline with its length in the last byte
+        &str_bytes[..(last_byte & 0x7f) as usize]
+    } else {
+        let elements = u32::from_le_bytes(str_bytes[0..4].try_into().unwrap()) as usize;
+        let length = u32::from_le_bytes(str_bytes[4..8].try_into().unwrap()) as usize;
+
+        &memory[elements..][..length]
+    };
+
+    String::from_utf8_lossy(bytes).into_owned()
+}
+
+/// wasmtime can't pass the expect a pointer to write its result into, so we call the
+/// `_status` function generated for each expect, which returns 0 if it passed.
+fn run_in_wasmtime<W: Write>(
+    writer: &mut W,
+    wasmtime: &Path,
+    wasm_file: &Path,
+    expect_name: &str,
+) -> std::io::Result<Outcome> {
+    let output = Command::new(wasmtime)
+        .arg("run")
+        .arg("--invoke")
+        .arg(format!("{}_status", expect_name))
+        .arg(wasm_file)
+        .stdin(Stdio::null())
+        .stderr(Stdio::inherit())
+        .output()
+        .map_err(|err| {
+            std::io::Error::new(
+                err.kind(),
+                format!("could not run {}: {}", wasmtime.display(), err),
+            )
+        })?;
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // wasmtime prints what the function returned after anything the program itself printed.
+    let (printed, status) = match stdout.trim_end().rsplit_once('\n') {
+        Some((printed, status)) => (printed, status),
+        None => ("", stdout.trim_end()),
+    };
+
+    if !printed.is_empty() {
+        writeln!(writer, "{}", printed)?;
+    }
+
+    let outcome = if !output.status.success() {
+        Outcome::Failed(format!(
+            "The test crashed: wasmtime exited with {}",
+            output.status
+        ))
+    } else {
+        match status.trim().parse::<i32>() {
+            Ok(0) => Outcome::Passed,
+            Ok(_) => Outcome::Failed(
+                "The test failed or crashed. (Run it without ROC_WASM_RUNTIME to see which.)"
+                    .to_string(),
+            ),
+            Err(_) => Outcome::Failed(format!(
+                "wasmtime did not print the test's status; its output was:\n\n{}",
+                stdout
+            )),
+        }
+    };
+
+    Ok(outcome)
+}
+
+fn render_failure<'a, W: Write>(
+    writer: &mut W,
+    render_target: RenderTarget,
+    arena: &'a Bump,
+    interns: &'a Interns,
+    expectations: &mut VecMap<ModuleId, Expectations>,
+    expect: &ToplevelExpect<'_>,
+    message: &str,
+) -> std::io::Result<()> {
+    let module_id = expect.symbol.module_id();
+    let data = expectations.get_mut(&module_id).unwrap();
+    let filename = data.path.to_owned();
+    let source = std::fs::read_to_string(&data.path)?;
+
+    let renderer = Renderer::new(arena, interns, render_target, module_id, filename, &source);
+
+    if message == EXPECT_FAILED_MESSAGE {
+        renderer.render_failure(
+            writer,
+            &mut data.subs,
+            &[],
+            &[],
+            &[],
+            Some(expect.region),
+            expect.region,
+        )?;
+    } else {
+        renderer.render_panic(writer, message, expect.region, &[])?;
+    }
+
+    writeln!(writer)
+}
+
+#[cfg(test)]
+mod test_wasm {
+    use super::{read_call_result, read_roc_str, Outcome};
+
+    fn write_u32(memory: &mut [u8], addr: usize, value: u32) {
+        memory[addr..][..4].copy_from_slice(&value.to_le_bytes());
+    }
+
+    /// Writes a big string at `addr`, with its bytes at `bytes_addr`
+    fn write_big_str(memory: &mut [u8], addr: usize, bytes_addr: usize, text: &str) {
+        memory[bytes_addr..][..text.len()].copy_from_slice(text.as_bytes());
+        write_u32(memory, addr, bytes_addr as u32);
+        write_u32(memory, addr + 4, text.len() as u32);
+        write_u32(memory, addr + 8, text.len() as u32);
+    }
+
+    #[test]
+    fn small_str() {
+        let mut memory = vec![0; 64];
+        memory[16..][..5].copy_from_slice(b"hello");
+        memory[16 + 11] = 0x80 | 5;
+
+        assert_eq!(read_roc_str(&memory, 16), "hello");
+    }
+
+    #[test]
+    fn empty_small_str() {
+        let mut memory = vec![0; 64];
+        memory[16 + 11] = 0x80;
+
+        assert_eq!(read_roc_str(&memory, 16), "");
+    }
+
+    #[test]
+    fn big_str() {
+        let text = "this string is too long to be small";
+        let mut memory = vec![0; 128];
+        write_big_str(&mut memory, 8, 32, text);
+
+        assert_eq!(read_roc_str(&memory, 8), text);
+    }
+
+    #[test]
+    fn call_result_passed() {
+        let memory = vec![0; 32];
+
+        assert!(matches!(read_call_result(&memory, 8), Outcome::Passed));
+    }
+
+    #[test]
+    fn call_result_failed() {
+        let message = "An expectation failed!";
+        let mut memory = vec![0; 128];
+        memory[8] = 1;
+        write_u32(&mut memory, 16, 32);
+        write_big_str(&mut memory, 32, 64, message);
+
+        match read_call_result(&memory, 8) {
+            Outcome::Failed(actual) => assert_eq!(actual, message),
+            Outcome::Passed => panic!("a nonzero tag should be a failure"),
+        }
+    }
+}
@@ -40,9 +40,15 @@ fn path_to_roc_binary() -> PathBuf {
 }
 
 pub fn repl_eval(input: &str) -> Out {
+    repl_eval_with_args(&[], input)
+}
+
+/// Like [repl_eval], but passes the given flags to `roc repl`, e.g. `--interpret`
+pub fn repl_eval_with_args(args: &[&str], input: &str) -> Out {
     let mut cmd = Command::new(path_to_roc_binary());
 
     cmd.arg("repl");
+    cmd.args(args);
 
     let mut child = cmd
         .stdin(Stdio::piped())
@@ -124,8 +130,18 @@ pub fn repl_eval(input: &str) -> Out {
 }
 
 pub fn expect_success(input: &str, expected: &str) {
-    let out = repl_eval(input.trim());
+    assert_success(repl_eval(input.trim()), expected);
+}
+
+/// Like [expect_success], but evaluates the input with `roc repl --interpret`
+pub fn expect_interpreted_success(input: &str, expected: &str) {
+    assert_success(
+        repl_eval_with_args(&["--interpret"], input.trim()),
+        expected,
+    );
+}
 
+fn assert_success(out: Out, expected: &str) {
     assert_multiline_str_eq!("", out.stderr.as_str());
 
     // Don't consider the auto variable name (e.g. "# val1") at the end.
@@ -0,0 +1,144 @@
+//! The same kinds of expressions as in tests.rs, evaluated with `roc repl --interpret`, which
+//! should print exactly what the compiled repl prints.
+
+use indoc::indoc;
+
+use crate::cli::{expect_interpreted_success, repl_eval_with_args};
+
+#[test]
+fn literals() {
+    expect_interpreted_success("42", "42 : Num *");
+    expect_interpreted_success("0x42", "66 : Int *");
+    expect_interpreted_success("4.2", "4.2 : Float *");
+    expect_interpreted_success("\"Hello, World!\"", "\"Hello, World!\" : Str");
+    expect_interpreted_success("1 == 1", "Bool.true : Bool");
+}
+
+#[test]
+fn arithmetic() {
+    expect_interpreted_success("1 + 2", "3 : Num *");
+    expect_interpreted_success("1.1 + 2", "3.1 : Float *");
+    expect_interpreted_success("299 % 10", "9 : Int *");
+    expect_interpreted_success("Num.divCeil 4 3", "2 : Int *");
+    expect_interpreted_success("Num.bitwiseXor 15 14", "1 : Int *");
+}
+
+#[test]
+fn overflow() {
+    expect_interpreted_success("Num.addWrap Num.maxI64 1", "-9223372036854775808 : I64");
+    expect_interpreted_success("Num.mulSaturated Num.maxI64 2", "9223372036854775807 : I64");
+    expect_interpreted_success(
+        "Num.addChecked Num.maxI64 1",
+        "Err Overflow : Result I64 [Overflow]",
+    );
+}
+
+#[test]
+fn division_by_zero() {
+    expect_interpreted_success(
+        "Num.divTruncChecked 4 0",
+        "Err DivByZero : Result (Int *) [DivByZero]",
+    );
+}
+
+#[test]
+fn crash() {
+    let out = repl_eval_with_args(&["--interpret"], "Num.maxI64 + 1");
+
+    assert!(
+        out.stdout.contains("Integer addition overflowed!"),
+        "Expected the interpreter to report the overflow, but the repl printed:\n\n{}",
+        out.stdout
+    );
+    assert!(out.status.success());
+}
+
+#[test]
+fn dec() {
+    expect_interpreted_success(
+        indoc!(
+            r#"
+            x: Dec
+            x=1.23
+            x"#
+        ),
+        "1.23 : Dec",
+    );
+}
+
+#[test]
+fn strings() {
+    expect_interpreted_success(
+        "Str.concat \"Hello, \" \"World!\"",
+        "\"Hello, World!\" : Str",
+    );
+    expect_interpreted_success("Str.countGraphemes \"å🤔\"", "2 : Nat");
+}
+
+#[test]
+fn lists() {
+    expect_interpreted_success("[1, 2, 3]", "[1, 2, 3] : List (Num *)");
+    expect_interpreted_success(
+        r#"[[["a", "b", "cd"], ["y", "z"]], [[]], []]"#,
+        r#"[[["a", "b", "cd"], ["y", "z"]], [[]], []] : List (List (List Str))"#,
+    );
+    expect_interpreted_success(
+        "List.concat [1.1, 2.2] [3.3, 4.4, 5.5]",
+        "[1.1, 2.2, 3.3, 4.4, 5.5] : List (Float *)",
+    );
+    expect_interpreted_success("List.sum [1, 2, 3]", "6 : Num *");
+    expect_interpreted_success(
+        "List.first [12, 9, 6, 3]",
+        "Ok 12 : Result (Num *) [ListWasEmpty]",
+    );
+}
+
+#[test]
+fn higher_order_list_functions() {
+    expect_interpreted_success(
+        indoc!(
+            r#"
+            A a := a
+            List.map [1u8, 2u8, 3u8] @A"#
+        ),
+        "[@A 1, @A 2, @A 3] : List (A U8)",
+    );
+    expect_interpreted_success(
+        "List.sortWith [3, 1, 2] Num.compare",
+        "[1, 2, 3] : List (Num *)",
+    );
+}
+
+#[test]
+fn records() {
+    expect_interpreted_success(
+        "{ x: 1 == 1, y: 1 != 1 }",
+        "{ x: Bool.true, y: Bool.false } : { x : Bool, y : Bool }",
+    );
+    expect_interpreted_success(
+        "{ foo: { bar: { baz: 42 } } }",
+        "{ foo: { bar: { baz: 42 } } } : { foo : { bar : { baz : Num * } } }",
+    );
+}
+
+#[test]
+fn tags() {
+    expect_interpreted_success(
+        "if 1 == 1 then Red else if 1 == 1 then Green else Blue",
+        "Red : [Blue, Green, Red]",
+    );
+    expect_interpreted_success(
+        "{ x: Foo 1 2 3, y : 4 }",
+        "{ x: Foo 1 2 3, y: 4 } : { x : [Foo (Num *) (Num *) (Num *)], y : Num * }",
+    );
+    expect_interpreted_success(
+        indoc!(
+            r#"
+            DeepList : [Nil, Cons Deep]
+            v : DeepList
+            v = (Cons (L (Cons (L (Cons (L Nil))))))
+            v"#
+        ),
+        "Cons (L (Cons (L (Cons (L Nil))))) : DeepList",
+    );
+}
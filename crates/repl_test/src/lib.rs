@@ -7,5 +7,8 @@ mod state;
 #[cfg(all(test, not(feature = "wasm")))]
 mod cli;
 
+#[cfg(all(test, not(feature = "wasm")))]
+mod interpret;
+
 #[cfg(all(test, feature = "wasm"))]
 mod wasm;
@@ -66,7 +66,7 @@ fn exhaustiveness_problem() {
 
         const EXPECTED_ERROR: &str = indoc!(
             r#"
-            ── UNSAFE PATTERN ──────────────────────────────────────────────────────────────
+            ── UNSAFE PATTERN [E1017] ──────────────────────────────────────────────────────
 
             This when does not cover all the possibilities:
 
@@ -1,13 +1,13 @@
 use bumpalo::{collections::vec::Vec, Bump};
 use std::mem::size_of;
 
-use roc_collections::all::MutSet;
+use roc_collections::all::{MutMap, MutSet};
 use roc_gen_wasm::wasm32_result;
 use roc_load::MonomorphizedModule;
 use roc_parse::ast::Expr;
 use roc_repl_eval::{
     eval::jit_to_ast,
-    gen::{compile_to_mono, format_answer},
+    gen::{compile_to_mono, format_answer, FRAC_DEFAULT},
     ReplApp, ReplAppMemory,
 };
 use roc_reporting::report::DEFAULT_PALETTE_HTML;
@@ -242,6 +242,7 @@ pub async fn entrypoint_from_js(src: String) -> Result<String, String> {
                 .keys()
                 .copied()
                 .collect::<MutSet<_>>(),
+            dbg_locations: MutMap::default(),
         };
 
         let (mut module, mut called_fns, main_fn_index) = {
@@ -291,6 +292,7 @@ pub async fn entrypoint_from_js(src: String) -> Result<String, String> {
         &interns,
         layout_interner.into_global().fork(),
         target_info,
+        FRAC_DEFAULT,
     );
 
     let var_name = String::new(); // TODO turn this into something like " # val1"
@@ -64,6 +64,56 @@ void roc_panic(void *ptr, unsigned int alignment)
 
 //--------------------------
 
+typedef struct
+{
+    char *bytes;
+    size_t length;
+    size_t capacity;
+} RocStr;
+
+// A small string has its bytes in the struct itself. Its last byte is its length, with the top bit set.
+static size_t roc_str_bytes(RocStr *str, char **bytes)
+{
+    unsigned char last_byte = ((unsigned char *)str)[sizeof(RocStr) - 1];
+    if (last_byte & 0x80)
+    {
+        *bytes = (char *)str;
+        return last_byte & 0x7f;
+    }
+    *bytes = str->bytes;
+    return str->length;
+}
+
+typedef struct
+{
+    const void *buf;
+    size_t len;
+} Ciovec;
+
+// Write straight to WASI, rather than using stdio, so we don't bloat the .wasm file
+__attribute__((import_module("wasi_snapshot_preview1"), import_name("fd_write")))
+int wasi_fd_write(int fd, const Ciovec *iovs, size_t iovs_len, size_t *nwritten);
+
+void roc_dbg(RocStr *loc, RocStr *msg)
+{
+    char *loc_bytes;
+    char *msg_bytes;
+    size_t loc_len = roc_str_bytes(loc, &loc_bytes);
+    size_t msg_len = roc_str_bytes(msg, &msg_bytes);
+
+    Ciovec iovs[] = {
+        {"[", 1},
+        {loc_bytes, loc_len},
+        {"] ", 2},
+        {msg_bytes, msg_len},
+        {"\n", 1},
+    };
+    size_t nwritten;
+    wasi_fd_write(2, iovs, sizeof(iovs) / sizeof(Ciovec), &nwritten);
+}
+
+//--------------------------
+
 void *roc_memcpy(void *dest, const void *src, size_t n)
 {
     return memcpy(dest, src, n);
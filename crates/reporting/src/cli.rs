@@ -1,7 +1,8 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use roc_collections::MutMap;
 use roc_module::symbol::{Interns, ModuleId};
+use roc_problem::lint::{LintConfig, LintLevel};
 use roc_region::all::LineInfo;
 use roc_solve_problem::TypeError;
 
@@ -28,16 +29,23 @@ pub fn report_problems(
     interns: &Interns,
     can_problems: &mut MutMap<ModuleId, Vec<roc_problem::can::Problem>>,
     type_problems: &mut MutMap<ModuleId, Vec<TypeError>>,
+    lints: &LintConfig,
+    html_output: Option<&Path>,
 ) -> Problems {
-    use crate::report::{can_problem, type_problem, Report, RocDocAllocator, DEFAULT_PALETTE};
+    use crate::error::canonicalize::{add_cascade_note, group_cascading_problems};
+    use crate::html::html_page;
+    use crate::report::{can_problem, type_problem, Palette, Report, RocDocAllocator};
     use roc_problem::Severity::*;
-    let palette = DEFAULT_PALETTE;
+    let palette = Palette::from_env();
 
     // This will often over-allocate total memory, but it means we definitely
     // never need to re-allocate either the warnings or the errors vec!
     let mut warnings = Vec::with_capacity(total_problems);
     let mut errors = Vec::with_capacity(total_problems);
 
+    // Unlike the terminal output, the HTML page has the warnings even if there are errors.
+    let mut html_reports = Vec::new();
+
     for (home, (module_path, src)) in sources.iter() {
         let mut src_lines: Vec<&str> = Vec::new();
 
@@ -48,10 +56,26 @@ pub fn report_problems(
         // Report parsing and canonicalization problems
         let alloc = RocDocAllocator::new(&src_lines, *home, interns);
 
-        let problems = can_problems.remove(home).unwrap_or_default();
+        let problems = group_cascading_problems(can_problems.remove(home).unwrap_or_default());
+
+        for (problem, cascade) in problems.into_iter() {
+            let lint_level = problem.lint().map(|lint| lints.level(lint));
+
+            if lint_level == Some(LintLevel::Allow) {
+                continue;
+            }
 
-        for problem in problems.into_iter() {
             let report = can_problem(&alloc, &lines, module_path.clone(), problem);
+            let mut report = add_cascade_note(&alloc, &lines, report, &cascade);
+
+            if lint_level == Some(LintLevel::Deny) {
+                report.severity = RuntimeError;
+            }
+
+            if html_output.is_some() {
+                html_reports.push(report.render_html(&alloc));
+            }
+
             let severity = report.severity;
             let mut buf = String::new();
 
@@ -70,7 +94,21 @@ pub fn report_problems(
         let problems = type_problems.remove(home).unwrap_or_default();
 
         for problem in problems {
-            if let Some(report) = type_problem(&alloc, &lines, module_path.clone(), problem) {
+            let lint_level = problem.lint().map(|lint| lints.level(lint));
+
+            if lint_level == Some(LintLevel::Allow) {
+                continue;
+            }
+
+            if let Some(mut report) = type_problem(&alloc, &lines, module_path.clone(), problem) {
+                if lint_level == Some(LintLevel::Deny) {
+                    report.severity = RuntimeError;
+                }
+
+                if html_output.is_some() {
+                    html_reports.push(report.render_html(&alloc));
+                }
+
                 let severity = report.severity;
                 let mut buf = String::new();
 
@@ -115,6 +153,12 @@ pub fn report_problems(
         println!("{}\u{001B}[0m\n", Report::horizontal_rule(&palette));
     }
 
+    if let Some(path) = html_output {
+        if let Err(err) = std::fs::write(path, html_page(&html_reports)) {
+            eprintln!("Could not write the report to {}: {}", path.display(), err);
+        }
+    }
+
     Problems {
         errors: errors.len(),
         warnings: warnings.len(),
@@ -0,0 +1,487 @@
+//! Stable codes for the kinds of problems we report, e.g. `TYPE MISMATCH` is `E1001`.
+//!
+//! Each report gets its code from one of the constants below where it's built, next to its
+//! title. The code is shown in the report's header and sent to editors, and `roc explain <code>`
+//! prints a longer description of the problem. A code must never change or be reused once it's
+//! been assigned, so new kinds of problems always get a new code at the end of their range:
+//!
+//! - `E0xxx`: syntax
+//! - `E1xxx`: types and patterns
+//! - `E2xxx`: names and definitions
+//! - `E3xxx`: abilities
+//! - `E4xxx`: number literals
+//! - `E5xxx`: modules, headers, and files
+//! - `E6xxx`: expects
+
+// Syntax
+pub const SYNTAX_PROBLEM: &str = "E0001";
+pub const PARSE_PROBLEM: &str = "E0002";
+pub const ARGUMENTS_BEFORE_EQUALS: &str = "E0003";
+pub const BAD_BACKPASSING_ARROW: &str = "E0004";
+pub const BAD_TYPE_VARIABLE: &str = "E0005";
+pub const DOUBLE_COMMA: &str = "E0006";
+pub const DOUBLE_DOT: &str = "E0007";
+pub const EMPTY_PARENTHESES: &str = "E0008";
+pub const END_OF_FILE: &str = "E0009";
+pub const ENDLESS_FORMAT: &str = "E0010";
+pub const ENDLESS_STRING: &str = "E0011";
+pub const IF_GUARD_NO_CONDITION: &str = "E0012";
+pub const INCORRECT_REST_PATTERN: &str = "E0013";
+pub const INDENT_ENDS_AFTER_EXPRESSION: &str = "E0014";
+pub const INSUFFICIENT_INDENT_IN_MULTI_LINE_STRING: &str = "E0015";
+pub const MISSING_ARROW: &str = "E0016";
+pub const MISSING_EXPRESSION: &str = "E0017";
+pub const MISSING_FINAL_EXPRESSION: &str = "E0018";
+pub const NEED_MORE_INDENTATION: &str = "E0019";
+pub const NOT_AN_INLINE_ALIAS: &str = "E0020";
+pub const NOT_END_OF_FILE: &str = "E0021";
+pub const PROBLEM_IN_RECORD_PATTERN: &str = "E0022";
+pub const PROBLEM_IN_RECORD_TYPE: &str = "E0023";
+pub const QUALIFIED_ALIAS_NAME: &str = "E0024";
+pub const RECORD_PARSE_PROBLEM: &str = "E0025";
+pub const TAB_CHARACTER: &str = "E0026";
+pub const TRAILING_DOT: &str = "E0027";
+pub const TYPE_ARGUMENT_NOT_LOWERCASE: &str = "E0028";
+pub const UNEXPECTED_ARROW: &str = "E0029";
+pub const UNFINISHED_ABILITY: &str = "E0030";
+pub const UNFINISHED_ARGUMENT_LIST: &str = "E0031";
+pub const UNFINISHED_FUNCTION: &str = "E0032";
+pub const UNFINISHED_IF: &str = "E0033";
+pub const UNFINISHED_INLINE_ALIAS: &str = "E0034";
+pub const UNFINISHED_LIST_PATTERN: &str = "E0035";
+pub const UNFINISHED_LIST: &str = "E0036";
+pub const UNFINISHED_PARENTHESES: &str = "E0037";
+pub const UNFINISHED_PATTERN: &str = "E0038";
+pub const UNFINISHED_RECORD_PATTERN: &str = "E0039";
+pub const UNFINISHED_RECORD_TYPE: &str = "E0040";
+pub const UNFINISHED_TAG_UNION_TYPE: &str = "E0041";
+pub const UNFINISHED_TYPE: &str = "E0042";
+pub const UNFINISHED_WHEN: &str = "E0043";
+pub const UNKNOWN_OPERATOR: &str = "E0044";
+pub const WEIRD_ARROW: &str = "E0045";
+pub const WEIRD_CODE_POINT: &str = "E0046";
+pub const WEIRD_ESCAPE: &str = "E0047";
+pub const WEIRD_IDENTIFIER: &str = "E0048";
+pub const WEIRD_QUALIFIED_NAME: &str = "E0049";
+pub const WEIRD_TAG_NAME: &str = "E0050";
+pub const INVALID_UNICODE: &str = "E0051";
+pub const BAD_OPTIONAL_VALUE: &str = "E0052";
+
+// Types and patterns
+pub const TYPE_MISMATCH: &str = "E1001";
+pub const TOO_MANY_ARGS: &str = "E1002";
+pub const TOO_FEW_ARGS: &str = "E1003";
+pub const CIRCULAR_TYPE: &str = "E1004";
+pub const CYCLIC_ALIAS: &str = "E1005";
+pub const NESTED_DATATYPE: &str = "E1006";
+pub const TOO_MANY_TYPE_ARGUMENTS: &str = "E1007";
+pub const TOO_FEW_TYPE_ARGUMENTS: &str = "E1008";
+pub const UNBOUND_TYPE_VARIABLE: &str = "E1009";
+pub const UNUSED_TYPE_ALIAS_PARAMETER: &str = "E1010";
+pub const OPAQUE_TYPE_NOT_DEFINED: &str = "E1011";
+pub const OPAQUE_TYPE_DECLARED_OUTSIDE_SCOPE: &str = "E1012";
+pub const OPAQUE_TYPE_NOT_APPLIED: &str = "E1013";
+pub const OPAQUE_TYPE_APPLIED_TO_TOO_MANY_ARGS: &str = "E1014";
+pub const INVALID_EXTENSION_TYPE: &str = "E1015";
+pub const TYPED_HOLE: &str = "E1016";
+pub const UNSAFE_PATTERN: &str = "E1017";
+pub const REDUNDANT_PATTERN: &str = "E1018";
+pub const UNMATCHABLE_PATTERN: &str = "E1019";
+pub const MULTIPLE_LIST_REST_PATTERNS: &str = "E1020";
+pub const NAME_NOT_BOUND_IN_ALL_PATTERNS: &str = "E1021";
+pub const UNNECESSARY_WILDCARD: &str = "E1022";
+pub const DEGENERATE_BRANCH: &str = "E1023";
+pub const UNAPPLIED_CRASH: &str = "E1024";
+pub const OVERAPPLIED_CRASH: &str = "E1025";
+
+// Names and definitions
+pub const UNRECOGNIZED_NAME: &str = "E2001";
+pub const UNUSED_DEFINITION: &str = "E2002";
+pub const UNUSED_IMPORT: &str = "E2003";
+pub const UNUSED_ARGUMENT: &str = "E2004";
+pub const DUPLICATE_NAME: &str = "E2005";
+pub const DUPLICATE_FIELD_NAME: &str = "E2006";
+pub const DUPLICATE_TAG_NAME: &str = "E2007";
+pub const CIRCULAR_DEFINITION: &str = "E2008";
+pub const DEFINITION_ONLY_USED_IN_RECURSION: &str = "E2009";
+pub const UNNECESSARY_DEFINITION: &str = "E2010";
+pub const NAMING_PROBLEM: &str = "E2011";
+pub const NAME_DIFFERS_ONLY_IN_CASE: &str = "E2012";
+pub const UNOPTIMIZED_TAIL_CALL: &str = "E2013";
+
+// Abilities
+pub const ABILITY_HAS_TYPE_VARIABLES: &str = "E3001";
+pub const ABILITY_IMPLEMENTATION_NOT_IDENTIFIER: &str = "E3002";
+pub const ABILITY_MEMBER_BINDS_MULTIPLE_VARIABLES: &str = "E3003";
+pub const ABILITY_MEMBER_MISSING_HAS_CLAUSE: &str = "E3004";
+pub const ABILITY_NOT_ON_TOP_LEVEL: &str = "E3005";
+pub const ABILITY_USED_AS_TYPE: &str = "E3006";
+pub const DUPLICATE_BOUND_ABILITY: &str = "E3007";
+pub const DUPLICATE_IMPLEMENTATION: &str = "E3008";
+pub const HAS_CLAUSE_IS_NOT_AN_ABILITY: &str = "E3009";
+pub const ILLEGAL_DERIVE: &str = "E3010";
+pub const ILLEGAL_HAS_CLAUSE: &str = "E3011";
+pub const ILLEGAL_SPECIALIZATION: &str = "E3012";
+pub const IMPLEMENTATION_NOT_FOUND: &str = "E3013";
+pub const INCOMPLETE_ABILITY_IMPLEMENTATION: &str = "E3014";
+pub const NOT_AN_ABILITY: &str = "E3015";
+pub const NOT_AN_ABILITY_MEMBER: &str = "E3016";
+pub const OPTIONAL_ABILITY_IMPLEMENTATION: &str = "E3017";
+pub const OVERLOADED_SPECIALIZATION: &str = "E3018";
+pub const QUALIFIED_ABILITY_IMPLEMENTATION: &str = "E3019";
+pub const SPECIALIZATION_NOT_ON_TOP_LEVEL: &str = "E3020";
+pub const UNNECESSARY_IMPLEMENTATIONS: &str = "E3021";
+pub const WRONG_SPECIALIZATION_TYPE: &str = "E3022";
+
+// Number literals
+pub const CONFLICTING_NUMBER_SUFFIX: &str = "E4001";
+pub const INVALID_NUMBER_LITERAL: &str = "E4002";
+pub const NUMBER_DOES_NOT_FIT_DEFAULT: &str = "E4003";
+pub const NUMBER_OVERFLOWS_SUFFIX: &str = "E4004";
+pub const NUMBER_UNDERFLOWS_SUFFIX: &str = "E4005";
+
+// Modules, headers, and files
+pub const MODULE_NOT_IMPORTED: &str = "E5001";
+pub const NOT_EXPOSED: &str = "E5002";
+pub const MISSING_DEFINITION: &str = "E5003";
+pub const IMPORT_CYCLE: &str = "E5004";
+pub const FILE_NOT_FOUND: &str = "E5005";
+pub const FILE_PERMISSION_DENIED: &str = "E5006";
+pub const FILE_PROBLEM: &str = "E5007";
+pub const INCORRECT_MODULE_NAME: &str = "E5008";
+pub const NO_PLATFORM: &str = "E5009";
+pub const UNKNOWN_GENERATES_FUNCTION: &str = "E5010";
+pub const BAD_REQUIRES: &str = "E5011";
+pub const BAD_REQUIRES_RIGIDS: &str = "E5012";
+pub const INCOMPLETE_HEADER: &str = "E5013";
+pub const INVALID_PACKAGE_NAME: &str = "E5014";
+pub const INVALID_PLATFORM_NAME: &str = "E5015";
+pub const MISSING_HEADER: &str = "E5016";
+pub const MISSING_PACKAGES: &str = "E5017";
+pub const MISSING_REQUIRES: &str = "E5018";
+pub const WEIRD_APP_NAME: &str = "E5019";
+pub const WEIRD_EXPOSES: &str = "E5020";
+pub const WEIRD_GENERATED_TYPE_NAME: &str = "E5021";
+pub const WEIRD_GENERATES: &str = "E5022";
+pub const WEIRD_IMPORTS: &str = "E5023";
+pub const WEIRD_MODULE_NAME: &str = "E5024";
+pub const WEIRD_PROVIDES: &str = "E5025";
+pub const PACKAGE_PROBLEM: &str = "E5026";
+
+// Expects
+pub const EXPECT_FAILED: &str = "E6001";
+pub const EXPECT_PANICKED: &str = "E6002";
+pub const SNAPSHOT_MISMATCH: &str = "E6003";
+pub const PROPERTY_FAILED: &str = "E6004";
+pub const UNTESTABLE_PROPERTY: &str = "E6005";
+
+pub struct ErrorCode {
+    pub code: &'static str,
+    /// The title of the reports with this code
+    pub title: &'static str,
+    /// A longer description of the problem, with examples and common fixes
+    pub explanation: Option<&'static str>,
+}
+
+const fn code(code: &'static str, title: &'static str) -> ErrorCode {
+    ErrorCode {
+        code,
+        title,
+        explanation: None,
+    }
+}
+
+const fn explained(
+    code: &'static str,
+    title: &'static str,
+    explanation: &'static str,
+) -> ErrorCode {
+    ErrorCode {
+        code,
+        title,
+        explanation: Some(explanation),
+    }
+}
+
+pub const ERROR_CODES: &[ErrorCode] = &[
+    // Syntax
+    explained(
+        SYNTAX_PROBLEM,
+        "SYNTAX PROBLEM",
+        include_str!("explanations/E0001.md"),
+    ),
+    code(PARSE_PROBLEM, "PARSE PROBLEM"),
+    code(ARGUMENTS_BEFORE_EQUALS, "ARGUMENTS BEFORE EQUALS"),
+    code(BAD_BACKPASSING_ARROW, "BAD BACKPASSING ARROW"),
+    code(BAD_TYPE_VARIABLE, "BAD TYPE VARIABLE"),
+    code(DOUBLE_COMMA, "DOUBLE COMMA"),
+    code(DOUBLE_DOT, "DOUBLE DOT"),
+    code(EMPTY_PARENTHESES, "EMPTY PARENTHESES"),
+    code(END_OF_FILE, "END OF FILE"),
+    code(ENDLESS_FORMAT, "ENDLESS FORMAT"),
+    code(ENDLESS_STRING, "ENDLESS STRING"),
+    code(IF_GUARD_NO_CONDITION, "IF GUARD NO CONDITION"),
+    code(INCORRECT_REST_PATTERN, "INCORRECT REST PATTERN"),
+    code(INDENT_ENDS_AFTER_EXPRESSION, "INDENT ENDS AFTER EXPRESSION"),
+    code(
+        INSUFFICIENT_INDENT_IN_MULTI_LINE_STRING,
+        "INSUFFICIENT INDENT IN MULTI-LINE STRING",
+    ),
+    code(MISSING_ARROW, "MISSING ARROW"),
+    code(MISSING_EXPRESSION, "MISSING EXPRESSION"),
+    code(MISSING_FINAL_EXPRESSION, "MISSING FINAL EXPRESSION"),
+    code(NEED_MORE_INDENTATION, "NEED MORE INDENTATION"),
+    code(NOT_AN_INLINE_ALIAS, "NOT AN INLINE ALIAS"),
+    code(NOT_END_OF_FILE, "NOT END OF FILE"),
+    code(PROBLEM_IN_RECORD_PATTERN, "PROBLEM IN RECORD PATTERN"),
+    code(PROBLEM_IN_RECORD_TYPE, "PROBLEM IN RECORD TYPE"),
+    code(QUALIFIED_ALIAS_NAME, "QUALIFIED ALIAS NAME"),
+    code(RECORD_PARSE_PROBLEM, "RECORD PARSE PROBLEM"),
+    code(TAB_CHARACTER, "TAB CHARACTER"),
+    code(TRAILING_DOT, "TRAILING DOT"),
+    code(TYPE_ARGUMENT_NOT_LOWERCASE, "TYPE ARGUMENT NOT LOWERCASE"),
+    code(UNEXPECTED_ARROW, "UNEXPECTED ARROW"),
+    code(UNFINISHED_ABILITY, "UNFINISHED ABILITY"),
+    code(UNFINISHED_ARGUMENT_LIST, "UNFINISHED ARGUMENT LIST"),
+    code(UNFINISHED_FUNCTION, "UNFINISHED FUNCTION"),
+    code(UNFINISHED_IF, "UNFINISHED IF"),
+    code(UNFINISHED_INLINE_ALIAS, "UNFINISHED INLINE ALIAS"),
+    code(UNFINISHED_LIST_PATTERN, "UNFINISHED LIST PATTERN"),
+    code(UNFINISHED_LIST, "UNFINISHED LIST"),
+    code(UNFINISHED_PARENTHESES, "UNFINISHED PARENTHESES"),
+    code(UNFINISHED_PATTERN, "UNFINISHED PATTERN"),
+    code(UNFINISHED_RECORD_PATTERN, "UNFINISHED RECORD PATTERN"),
+    code(UNFINISHED_RECORD_TYPE, "UNFINISHED RECORD TYPE"),
+    code(UNFINISHED_TAG_UNION_TYPE, "UNFINISHED TAG UNION TYPE"),
+    code(UNFINISHED_TYPE, "UNFINISHED TYPE"),
+    code(UNFINISHED_WHEN, "UNFINISHED WHEN"),
+    code(UNKNOWN_OPERATOR, "UNKNOWN OPERATOR"),
+    code(WEIRD_ARROW, "WEIRD ARROW"),
+    code(WEIRD_CODE_POINT, "WEIRD CODE POINT"),
+    code(WEIRD_ESCAPE, "WEIRD ESCAPE"),
+    code(WEIRD_IDENTIFIER, "WEIRD IDENTIFIER"),
+    code(WEIRD_QUALIFIED_NAME, "WEIRD QUALIFIED NAME"),
+    code(WEIRD_TAG_NAME, "WEIRD TAG NAME"),
+    code(INVALID_UNICODE, "INVALID UNICODE"),
+    code(BAD_OPTIONAL_VALUE, "BAD OPTIONAL VALUE"),
+    // Types and patterns
+    explained(
+        TYPE_MISMATCH,
+        "TYPE MISMATCH",
+        include_str!("explanations/E1001.md"),
+    ),
+    explained(
+        TOO_MANY_ARGS,
+        "TOO MANY ARGS",
+        include_str!("explanations/E1002.md"),
+    ),
+    explained(
+        TOO_FEW_ARGS,
+        "TOO FEW ARGS",
+        include_str!("explanations/E1003.md"),
+    ),
+    code(CIRCULAR_TYPE, "CIRCULAR TYPE"),
+    code(CYCLIC_ALIAS, "CYCLIC ALIAS"),
+    code(NESTED_DATATYPE, "NESTED DATATYPE"),
+    code(TOO_MANY_TYPE_ARGUMENTS, "TOO MANY TYPE ARGUMENTS"),
+    code(TOO_FEW_TYPE_ARGUMENTS, "TOO FEW TYPE ARGUMENTS"),
+    code(UNBOUND_TYPE_VARIABLE, "UNBOUND TYPE VARIABLE"),
+    code(UNUSED_TYPE_ALIAS_PARAMETER, "UNUSED TYPE ALIAS PARAMETER"),
+    code(OPAQUE_TYPE_NOT_DEFINED, "OPAQUE TYPE NOT DEFINED"),
+    code(
+        OPAQUE_TYPE_DECLARED_OUTSIDE_SCOPE,
+        "OPAQUE TYPE DECLARED OUTSIDE SCOPE",
+    ),
+    code(OPAQUE_TYPE_NOT_APPLIED, "OPAQUE TYPE NOT APPLIED"),
+    code(
+        OPAQUE_TYPE_APPLIED_TO_TOO_MANY_ARGS,
+        "OPAQUE TYPE APPLIED TO TOO MANY ARGS",
+    ),
+    code(INVALID_EXTENSION_TYPE, "INVALID_EXTENSION_TYPE"),
+    code(TYPED_HOLE, "TYPED HOLE"),
+    explained(
+        UNSAFE_PATTERN,
+        "UNSAFE PATTERN",
+        include_str!("explanations/E1017.md"),
+    ),
+    explained(
+        REDUNDANT_PATTERN,
+        "REDUNDANT PATTERN",
+        include_str!("explanations/E1018.md"),
+    ),
+    code(UNMATCHABLE_PATTERN, "UNMATCHABLE PATTERN"),
+    code(MULTIPLE_LIST_REST_PATTERNS, "MULTIPLE LIST REST PATTERNS"),
+    code(
+        NAME_NOT_BOUND_IN_ALL_PATTERNS,
+        "NAME NOT BOUND IN ALL PATTERNS",
+    ),
+    code(UNNECESSARY_WILDCARD, "UNNECESSARY WILDCARD"),
+    code(DEGENERATE_BRANCH, "DEGENERATE BRANCH"),
+    code(UNAPPLIED_CRASH, "UNAPPLIED CRASH"),
+    code(OVERAPPLIED_CRASH, "OVERAPPLIED CRASH"),
+    // Names and definitions
+    explained(
+        UNRECOGNIZED_NAME,
+        "UNRECOGNIZED NAME",
+        include_str!("explanations/E2001.md"),
+    ),
+    explained(
+        UNUSED_DEFINITION,
+        "UNUSED DEFINITION",
+        include_str!("explanations/E2002.md"),
+    ),
+    explained(
+        UNUSED_IMPORT,
+        "UNUSED IMPORT",
+        include_str!("explanations/E2003.md"),
+    ),
+    code(UNUSED_ARGUMENT, "UNUSED ARGUMENT"),
+    explained(
+        DUPLICATE_NAME,
+        "DUPLICATE NAME",
+        include_str!("explanations/E2005.md"),
+    ),
+    code(DUPLICATE_FIELD_NAME, "DUPLICATE FIELD NAME"),
+    code(DUPLICATE_TAG_NAME, "DUPLICATE TAG NAME"),
+    explained(
+        CIRCULAR_DEFINITION,
+        "CIRCULAR DEFINITION",
+        include_str!("explanations/E2008.md"),
+    ),
+    code(
+        DEFINITION_ONLY_USED_IN_RECURSION,
+        "DEFINITION ONLY USED IN RECURSION",
+    ),
+    code(UNNECESSARY_DEFINITION, "UNNECESSARY DEFINITION"),
+    code(NAMING_PROBLEM, "NAMING PROBLEM"),
+    code(NAME_DIFFERS_ONLY_IN_CASE, "NAME DIFFERS ONLY IN CASE"),
+    code(UNOPTIMIZED_TAIL_CALL, "UNOPTIMIZED TAIL CALL"),
+    // Abilities
+    code(ABILITY_HAS_TYPE_VARIABLES, "ABILITY HAS TYPE VARIABLES"),
+    code(
+        ABILITY_IMPLEMENTATION_NOT_IDENTIFIER,
+        "ABILITY IMPLEMENTATION NOT IDENTIFIER",
+    ),
+    code(
+        ABILITY_MEMBER_BINDS_MULTIPLE_VARIABLES,
+        "ABILITY MEMBER BINDS MULTIPLE VARIABLES",
+    ),
+    code(
+        ABILITY_MEMBER_MISSING_HAS_CLAUSE,
+        "ABILITY MEMBER MISSING HAS CLAUSE",
+    ),
+    code(ABILITY_NOT_ON_TOP_LEVEL, "ABILITY NOT ON TOP-LEVEL"),
+    code(ABILITY_USED_AS_TYPE, "ABILITY USED AS TYPE"),
+    code(DUPLICATE_BOUND_ABILITY, "DUPLICATE BOUND ABILITY"),
+    code(DUPLICATE_IMPLEMENTATION, "DUPLICATE IMPLEMENTATION"),
+    code(HAS_CLAUSE_IS_NOT_AN_ABILITY, "HAS CLAUSE IS NOT AN ABILITY"),
+    code(ILLEGAL_DERIVE, "ILLEGAL DERIVE"),
+    code(ILLEGAL_HAS_CLAUSE, "ILLEGAL HAS CLAUSE"),
+    code(ILLEGAL_SPECIALIZATION, "ILLEGAL SPECIALIZATION"),
+    code(IMPLEMENTATION_NOT_FOUND, "IMPLEMENTATION NOT FOUND"),
+    code(
+        INCOMPLETE_ABILITY_IMPLEMENTATION,
+        "INCOMPLETE ABILITY IMPLEMENTATION",
+    ),
+    code(NOT_AN_ABILITY, "NOT AN ABILITY"),
+    code(NOT_AN_ABILITY_MEMBER, "NOT AN ABILITY MEMBER"),
+    code(
+        OPTIONAL_ABILITY_IMPLEMENTATION,
+        "OPTIONAL ABILITY IMPLEMENTATION",
+    ),
+    code(OVERLOADED_SPECIALIZATION, "OVERLOADED SPECIALIZATION"),
+    code(
+        QUALIFIED_ABILITY_IMPLEMENTATION,
+        "QUALIFIED ABILITY IMPLEMENTATION",
+    ),
+    code(
+        SPECIALIZATION_NOT_ON_TOP_LEVEL,
+        "SPECIALIZATION NOT ON TOP-LEVEL",
+    ),
+    code(UNNECESSARY_IMPLEMENTATIONS, "UNNECESSARY IMPLEMENTATIONS"),
+    code(WRONG_SPECIALIZATION_TYPE, "WRONG SPECIALIZATION TYPE"),
+    // Number literals
+    code(CONFLICTING_NUMBER_SUFFIX, "CONFLICTING NUMBER SUFFIX"),
+    code(INVALID_NUMBER_LITERAL, "INVALID NUMBER LITERAL"),
+    code(NUMBER_DOES_NOT_FIT_DEFAULT, "NUMBER DOES NOT FIT DEFAULT"),
+    code(NUMBER_OVERFLOWS_SUFFIX, "NUMBER OVERFLOWS SUFFIX"),
+    code(NUMBER_UNDERFLOWS_SUFFIX, "NUMBER UNDERFLOWS SUFFIX"),
+    // Modules, headers, and files
+    explained(
+        MODULE_NOT_IMPORTED,
+        "MODULE NOT IMPORTED",
+        include_str!("explanations/E5001.md"),
+    ),
+    explained(
+        NOT_EXPOSED,
+        "NOT EXPOSED",
+        include_str!("explanations/E5002.md"),
+    ),
+    explained(
+        MISSING_DEFINITION,
+        "MISSING DEFINITION",
+        include_str!("explanations/E5003.md"),
+    ),
+    explained(
+        IMPORT_CYCLE,
+        "IMPORT CYCLE",
+        include_str!("explanations/E5004.md"),
+    ),
+    explained(
+        FILE_NOT_FOUND,
+        "FILE NOT FOUND",
+        include_str!("explanations/E5005.md"),
+    ),
+    code(FILE_PERMISSION_DENIED, "FILE PERMISSION DENIED"),
+    code(FILE_PROBLEM, "FILE PROBLEM"),
+    code(INCORRECT_MODULE_NAME, "INCORRECT MODULE NAME"),
+    code(NO_PLATFORM, "NO PLATFORM"),
+    code(UNKNOWN_GENERATES_FUNCTION, "UNKNOWN GENERATES FUNCTION"),
+    code(BAD_REQUIRES, "BAD REQUIRES"),
+    code(BAD_REQUIRES_RIGIDS, "BAD REQUIRES RIGIDS"),
+    code(INCOMPLETE_HEADER, "INCOMPLETE HEADER"),
+    code(INVALID_PACKAGE_NAME, "INVALID PACKAGE NAME"),
+    code(INVALID_PLATFORM_NAME, "INVALID PLATFORM NAME"),
+    code(MISSING_HEADER, "MISSING HEADER"),
+    code(MISSING_PACKAGES, "MISSING PACKAGES"),
+    code(MISSING_REQUIRES, "MISSING REQUIRES"),
+    code(WEIRD_APP_NAME, "WEIRD APP NAME"),
+    code(WEIRD_EXPOSES, "WEIRD EXPOSES"),
+    code(WEIRD_GENERATED_TYPE_NAME, "WEIRD GENERATED TYPE NAME"),
+    code(WEIRD_GENERATES, "WEIRD GENERATES"),
+    code(WEIRD_IMPORTS, "WEIRD IMPORTS"),
+    code(WEIRD_MODULE_NAME, "WEIRD MODULE NAME"),
+    code(WEIRD_PROVIDES, "WEIRD PROVIDES"),
+    code(PACKAGE_PROBLEM, "PACKAGE PROBLEM"),
+    // Expects
+    code(EXPECT_FAILED, "EXPECT FAILED"),
+    code(EXPECT_PANICKED, "EXPECT PANICKED"),
+    code(SNAPSHOT_MISMATCH, "SNAPSHOT MISMATCH"),
+    code(PROPERTY_FAILED, "PROPERTY FAILED"),
+    code(UNTESTABLE_PROPERTY, "UNTESTABLE PROPERTY"),
+];
+
+/// Look up a code, ignoring case (so `e1001` finds `E1001`)
+pub fn lookup(code: &str) -> Option<&'static ErrorCode> {
+    ERROR_CODES
+        .iter()
+        .find(|error_code| error_code.code.eq_ignore_ascii_case(code))
+}
+
+#[cfg(test)]
+mod test_codes {
+    use super::ERROR_CODES;
+
+    #[test]
+    fn codes_are_unique() {
+        for (index, error_code) in ERROR_CODES.iter().enumerate() {
+            assert!(
+                ERROR_CODES[..index]
+                    .iter()
+                    .all(|other| other.code != error_code.code),
+                "{} is used more than once",
+                error_code.code
+            );
+        }
+    }
+}
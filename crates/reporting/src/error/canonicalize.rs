@@ -1,18 +1,18 @@
-use roc_collections::all::MutSet;
+use roc_collections::all::MutMap;
 use roc_module::ident::{Ident, Lowercase, ModuleName};
 use roc_module::symbol::DERIVABLE_ABILITIES;
 use roc_problem::can::PrecedenceProblem::BothNonAssociative;
 use roc_problem::can::{
-    BadPattern, CycleEntry, ExtensionTypeKind, FloatErrorKind, IntErrorKind, Problem, RuntimeError,
-    ShadowKind,
+    BadPattern, CycleEntry, ExtensionTypeKind, FloatErrorKind, IntErrorKind, NameKind, Problem,
+    RuntimeError, ShadowKind, TailCallBlocker,
 };
 use roc_problem::Severity;
 use roc_region::all::{LineColumn, LineColumnRegion, LineInfo, Loc, Region};
 use roc_types::types::AliasKind;
 use std::path::PathBuf;
 
-use crate::error::r#type::suggest;
-use crate::report::{Annotation, Report, RocDocAllocator, RocDocBuilder};
+use crate::codes;
+use crate::report::{Annotation, Fix, RelatedSpan, Report, RocDocAllocator, RocDocBuilder};
 use ven_pretty::DocAllocator;
 
 const SYNTAX_PROBLEM: &str = "SYNTAX PROBLEM";
@@ -59,6 +59,8 @@ const ABILITY_IMPLEMENTATION_NOT_IDENTIFIER: &str = "ABILITY IMPLEMENTATION NOT
 const DUPLICATE_IMPLEMENTATION: &str = "DUPLICATE IMPLEMENTATION";
 const UNNECESSARY_IMPLEMENTATIONS: &str = "UNNECESSARY IMPLEMENTATIONS";
 const INCOMPLETE_ABILITY_IMPLEMENTATION: &str = "INCOMPLETE ABILITY IMPLEMENTATION";
+const NAME_DIFFERS_ONLY_IN_CASE: &str = "NAME DIFFERS ONLY IN CASE";
+const UNOPTIMIZED_TAIL_CALL: &str = "UNOPTIMIZED TAIL CALL";
 
 pub fn can_problem<'b>(
     alloc: &'b RocDocAllocator<'b>,
@@ -68,10 +70,13 @@ pub fn can_problem<'b>(
 ) -> Report<'b> {
     let doc;
     let title;
+    let code;
     let severity = problem.severity();
+    let fixes = can_problem_fixes(alloc, lines, &problem);
+    let mut related = Vec::new();
 
     match problem {
-        Problem::UnusedDef(symbol, region) => {
+        Problem::UnusedDef(symbol, region, _) => {
             let line =
                 r#" then remove it so future readers of your code don't wonder why it is there."#;
 
@@ -87,6 +92,7 @@ pub fn can_problem<'b>(
             ]);
 
             title = UNUSED_DEF.to_string();
+            code = codes::UNUSED_DEFINITION;
         }
         Problem::UnusedImport(symbol, region) => {
             doc = alloc.stack([
@@ -103,6 +109,7 @@ pub fn can_problem<'b>(
             ]);
 
             title = UNUSED_IMPORT.to_string();
+            code = codes::UNUSED_IMPORT;
         }
         Problem::UnusedModuleImport(module_id, region) => {
             doc = alloc.stack([
@@ -120,6 +127,7 @@ pub fn can_problem<'b>(
             ]);
 
             title = UNUSED_IMPORT.to_string();
+            code = codes::UNUSED_IMPORT;
         }
         Problem::DefsOnlyUsedInRecursion(1, region) => {
             doc = alloc.stack([
@@ -131,6 +139,7 @@ pub fn can_problem<'b>(
             ]);
 
             title = "DEFINITION ONLY USED IN RECURSION".to_string();
+            code = codes::DEFINITION_ONLY_USED_IN_RECURSION;
         }
         Problem::DefsOnlyUsedInRecursion(n, region) => {
             doc = alloc.stack([
@@ -146,6 +155,7 @@ pub fn can_problem<'b>(
             ]);
 
             title = "DEFINITIONs ONLY USED IN RECURSION".to_string();
+            code = codes::DEFINITION_ONLY_USED_IN_RECURSION;
         }
         Problem::ExposedButNotDefined(symbol) => {
             doc = alloc.stack([
@@ -161,6 +171,7 @@ pub fn can_problem<'b>(
             ]);
 
             title = MISSING_DEFINITION.to_string();
+            code = codes::MISSING_DEFINITION;
         }
         Problem::UnknownGeneratesWith(loc_ident) => {
             doc = alloc.stack([
@@ -175,6 +186,7 @@ pub fn can_problem<'b>(
             ]);
 
             title = UNKNOWN_GENERATES_WITH.to_string();
+            code = codes::UNKNOWN_GENERATES_FUNCTION;
         }
         Problem::UnusedArgument(closure_symbol, is_anonymous, argument_symbol, region) => {
             let line = "\". Adding an underscore at the start of a variable name is a way of saying that the variable is not used.";
@@ -209,6 +221,7 @@ pub fn can_problem<'b>(
             ]);
 
             title = UNUSED_ARG.to_string();
+            code = codes::UNUSED_ARGUMENT;
         }
         Problem::UnusedBranchDef(symbol, region) => {
             doc = alloc.stack([
@@ -229,6 +242,7 @@ pub fn can_problem<'b>(
             ]);
 
             title = UNUSED_DEF.to_string();
+            code = codes::UNUSED_DEFINITION;
         }
         Problem::PrecedenceProblem(BothNonAssociative(region, left_bin_op, right_bin_op)) => {
             doc = alloc.stack([
@@ -257,6 +271,7 @@ pub fn can_problem<'b>(
             ]);
 
             title = SYNTAX_PROBLEM.to_string();
+            code = codes::SYNTAX_PROBLEM;
         }
         Problem::UnsupportedPattern(BadPattern::Unsupported(pattern_type), region) => {
             use roc_parse::pattern::PatternType::*;
@@ -287,6 +302,7 @@ pub fn can_problem<'b>(
             ]);
 
             title = SYNTAX_PROBLEM.to_string();
+            code = codes::SYNTAX_PROBLEM;
         }
         Problem::Shadowing {
             original_region,
@@ -298,6 +314,7 @@ pub fn can_problem<'b>(
 
             doc = res_doc;
             title = res_title.to_string();
+            code = codes::DUPLICATE_NAME;
         }
         Problem::CyclicAlias(symbol, region, others, alias_kind) => {
             let answer = crate::error::r#type::cyclic_alias(
@@ -306,6 +323,7 @@ pub fn can_problem<'b>(
 
             doc = answer.0;
             title = answer.1;
+            code = codes::CYCLIC_ALIAS;
         }
         Problem::PhantomTypeArgument {
             typ: alias,
@@ -333,6 +351,7 @@ pub fn can_problem<'b>(
             ]);
 
             title = UNUSED_ALIAS_PARAM.to_string();
+            code = codes::UNUSED_TYPE_ALIAS_PARAMETER;
         }
         Problem::UnboundTypeVariable {
             typ: alias,
@@ -369,10 +388,12 @@ pub fn can_problem<'b>(
             doc = alloc.stack(stack);
 
             title = UNBOUND_TYPE_VARIABLE.to_string();
+            code = codes::UNBOUND_TYPE_VARIABLE;
         }
         Problem::BadRecursion(entries) => {
             doc = to_circular_def_doc(alloc, lines, &entries);
             title = CIRCULAR_DEF.to_string();
+            code = codes::CIRCULAR_DEFINITION;
         }
         Problem::DuplicateRecordFieldValue {
             field_name,
@@ -407,6 +428,7 @@ pub fn can_problem<'b>(
             ]);
 
             title = DUPLICATE_FIELD_NAME.to_string();
+            code = codes::DUPLICATE_FIELD_NAME;
         }
         Problem::InvalidOptionalValue {
             field_name,
@@ -455,6 +477,7 @@ pub fn can_problem<'b>(
             ]);
 
             title = DUPLICATE_FIELD_NAME.to_string();
+            code = codes::DUPLICATE_FIELD_NAME;
         }
         Problem::DuplicateTag {
             tag_name,
@@ -489,6 +512,7 @@ pub fn can_problem<'b>(
             ]);
 
             title = DUPLICATE_TAG_NAME.to_string();
+            code = codes::DUPLICATE_TAG_NAME;
         }
         Problem::SignatureDefMismatch {
             ref annotation_pattern,
@@ -505,6 +529,7 @@ pub fn can_problem<'b>(
             ]);
 
             title = NAMING_PROBLEM.to_string();
+            code = codes::NAMING_PROBLEM;
         }
         Problem::InvalidAliasRigid {
             alias_name: type_name,
@@ -527,6 +552,7 @@ pub fn can_problem<'b>(
             ]);
 
             title = SYNTAX_PROBLEM.to_string();
+            code = codes::SYNTAX_PROBLEM;
         }
         Problem::InvalidHexadecimal(region) => {
             doc = alloc.stack([
@@ -543,6 +569,7 @@ pub fn can_problem<'b>(
             ]);
 
             title = INVALID_UNICODE.to_string();
+            code = codes::INVALID_UNICODE;
         }
         Problem::InvalidUnicodeCodePt(region) => {
             doc = alloc.stack([
@@ -552,6 +579,7 @@ pub fn can_problem<'b>(
             ]);
 
             title = INVALID_UNICODE.to_string();
+            code = codes::INVALID_UNICODE;
         }
         Problem::InvalidInterpolation(region) => {
             doc = alloc.stack([
@@ -568,12 +596,14 @@ pub fn can_problem<'b>(
             ]);
 
             title = SYNTAX_PROBLEM.to_string();
+            code = codes::SYNTAX_PROBLEM;
         }
         Problem::RuntimeError(runtime_error) => {
             let answer = pretty_runtime_error(alloc, lines, runtime_error);
 
             doc = answer.0;
             title = answer.1.to_string();
+            code = answer.2;
         }
         Problem::NestedDatatype {
             alias,
@@ -601,6 +631,7 @@ pub fn can_problem<'b>(
             ]);
 
             title = NESTED_DATATYPE.to_string();
+            code = codes::NESTED_DATATYPE;
         }
 
         Problem::InvalidExtensionType { region, kind } => {
@@ -628,6 +659,7 @@ pub fn can_problem<'b>(
             ]);
 
             title = INVALID_EXTENSION_TYPE.to_string();
+            code = codes::INVALID_EXTENSION_TYPE;
         }
 
         Problem::AbilityHasTypeVariables {
@@ -646,6 +678,7 @@ pub fn can_problem<'b>(
                 ),
             ]);
             title = ABILITY_HAS_TYPE_VARIABLES.to_string();
+            code = codes::ABILITY_HAS_TYPE_VARIABLES;
         }
 
         Problem::HasClauseIsNotAbility {
@@ -656,6 +689,7 @@ pub fn can_problem<'b>(
                 alloc.region(lines.convert_region(clause_region)),
             ]);
             title = HAS_CLAUSE_IS_NOT_AN_ABILITY.to_string();
+            code = codes::HAS_CLAUSE_IS_NOT_AN_ABILITY;
         }
 
         Problem::IllegalHasClause { region } => {
@@ -674,6 +708,7 @@ pub fn can_problem<'b>(
                 ]),
             ]);
             title = ILLEGAL_HAS_CLAUSE.to_string();
+            code = codes::ILLEGAL_HAS_CLAUSE;
         }
 
         Problem::DuplicateHasAbility { ability, region } => {
@@ -691,6 +726,7 @@ pub fn can_problem<'b>(
                 ]),
             ]);
             title = "DUPLICATE BOUND ABILITY".to_string();
+            code = codes::DUPLICATE_BOUND_ABILITY;
         }
 
         Problem::AbilityMemberMissingHasClause {
@@ -725,6 +761,7 @@ pub fn can_problem<'b>(
                     .reflow("Otherwise, the function does not need to be part of the ability!")]),
             ]);
             title = ABILITY_MEMBER_MISSING_HAS_CLAUSE.to_string();
+            code = codes::ABILITY_MEMBER_MISSING_HAS_CLAUSE;
         }
 
         Problem::AbilityMemberMultipleBoundVars {
@@ -752,6 +789,7 @@ pub fn can_problem<'b>(
                 ])
             ]);
             title = ABILITY_MEMBER_BINDS_MULTIPLE_VARIABLES.to_string();
+            code = codes::ABILITY_MEMBER_BINDS_MULTIPLE_VARIABLES;
         }
 
         Problem::AbilityNotOnToplevel { region } => {
@@ -763,6 +801,7 @@ pub fn can_problem<'b>(
                 alloc.reflow("Abilities can only be defined on the top-level of a Roc module."),
             ]);
             title = ABILITY_NOT_ON_TOPLEVEL.to_string();
+            code = codes::ABILITY_NOT_ON_TOP_LEVEL;
         }
 
         Problem::AbilityUsedAsType(suggested_var_name, ability, region) => {
@@ -790,6 +829,7 @@ pub fn can_problem<'b>(
                 ])),
             ]);
             title = ABILITY_USED_AS_TYPE.to_string();
+            code = codes::ABILITY_USED_AS_TYPE;
         }
         Problem::NestedSpecialization(member, region) => {
             doc = alloc.stack([
@@ -802,6 +842,7 @@ pub fn can_problem<'b>(
                 alloc.reflow("Specializations can only be defined on the top-level of a module."),
             ]);
             title = SPECIALIZATION_NOT_ON_TOPLEVEL.to_string();
+            code = codes::SPECIALIZATION_NOT_ON_TOP_LEVEL;
         }
         Problem::IllegalDerivedAbility(region) => {
             doc = alloc.stack([
@@ -813,6 +854,7 @@ pub fn can_problem<'b>(
                     .append(list_builtin_abilities(alloc)),
             ]);
             title = ILLEGAL_DERIVE.to_string();
+            code = codes::ILLEGAL_DERIVE;
         }
         Problem::NotAnAbility(region) => {
             doc = alloc.stack([
@@ -821,6 +863,7 @@ pub fn can_problem<'b>(
                 alloc.reflow("Only abilities can be implemented."),
             ]);
             title = NOT_AN_ABILITY.to_string();
+            code = codes::NOT_AN_ABILITY;
         }
         Problem::NotAnAbilityMember {
             ability,
@@ -835,6 +878,7 @@ pub fn can_problem<'b>(
                 alloc.reflow("Only implementations for members an ability has can be specified in this location.")
             ]);
             title = NOT_AN_ABILITY_MEMBER.to_string();
+            code = codes::NOT_AN_ABILITY_MEMBER;
         }
         Problem::ImplementationNotFound { member, region } => {
             let member_str = member.as_str(alloc.interns);
@@ -846,6 +890,7 @@ pub fn can_problem<'b>(
                 alloc.tip().append(alloc.concat([alloc.reflow("consider adding a value of name "), alloc.symbol_unqualified(member), alloc.reflow(" in this scope, or using another variable that implements this ability member, like "), alloc.type_str(&format!("{{ {}: my{} }}", member_str, member_str))]))
             ]);
             title = IMPLEMENTATION_NOT_FOUND.to_string();
+            code = codes::IMPLEMENTATION_NOT_FOUND;
         }
         Problem::OptionalAbilityImpl { ability, region } => {
             let hint = if ability.is_builtin() {
@@ -864,6 +909,7 @@ pub fn can_problem<'b>(
                 hint,
             ]);
             title = OPTIONAL_ABILITY_IMPLEMENTATION.to_string();
+            code = codes::OPTIONAL_ABILITY_IMPLEMENTATION;
         }
         Problem::QualifiedAbilityImpl { region } => {
             doc = alloc.stack([
@@ -874,6 +920,7 @@ pub fn can_problem<'b>(
                 ),
             ]);
             title = QUALIFIED_ABILITY_IMPLEMENTATION.to_string();
+            code = codes::QUALIFIED_ABILITY_IMPLEMENTATION;
         }
         Problem::AbilityImplNotIdent { region } => {
             doc = alloc.stack([
@@ -885,6 +932,7 @@ pub fn can_problem<'b>(
                 alloc.tip().append(alloc.reflow("consider defining this expression as a variable."))
             ]);
             title = ABILITY_IMPLEMENTATION_NOT_IDENTIFIER.to_string();
+            code = codes::ABILITY_IMPLEMENTATION_NOT_IDENTIFIER;
         }
         Problem::DuplicateImpl {
             original,
@@ -893,12 +941,15 @@ pub fn can_problem<'b>(
             doc = alloc.stack([
                 alloc.reflow("This ability member implementation is duplicate:"),
                 alloc.region(lines.convert_region(duplicate)),
-                alloc.reflow("The first implementation was defined here:"),
-                alloc.region(lines.convert_region(original)),
                 alloc
                     .reflow("Only one custom implementation can be defined for an ability member."),
             ]);
+            related.push(RelatedSpan {
+                region: lines.convert_region(original),
+                message: "The first implementation was defined here".to_string(),
+            });
             title = DUPLICATE_IMPLEMENTATION.to_string();
+            code = codes::DUPLICATE_IMPLEMENTATION;
         }
         Problem::ImplementsNonRequired {
             region,
@@ -923,6 +974,7 @@ pub fn can_problem<'b>(
                 ),
             ]);
             title = UNNECESSARY_IMPLEMENTATIONS.to_string();
+            code = codes::UNNECESSARY_IMPLEMENTATIONS;
         }
         Problem::DoesNotImplementAbility {
             region,
@@ -947,6 +999,7 @@ pub fn can_problem<'b>(
                 ),
             ]);
             title = INCOMPLETE_ABILITY_IMPLEMENTATION.to_string();
+            code = codes::INCOMPLETE_ABILITY_IMPLEMENTATION;
         }
         Problem::NotBoundInAllPatterns {
             unbound_symbol,
@@ -967,6 +1020,7 @@ pub fn can_problem<'b>(
                 ]),
             ]);
             title = "NAME NOT BOUND IN ALL PATTERNS".to_string();
+            code = codes::NAME_NOT_BOUND_IN_ALL_PATTERNS;
         }
         Problem::NoIdentifiersIntroduced(region) => {
             doc = alloc.stack([
@@ -975,6 +1029,7 @@ pub fn can_problem<'b>(
                 alloc.reflow("If you don't need to use the value on the right-hand-side of this assignment, consider removing the assignment. Since Roc is purely functional, assignments that don't introduce variables cannot affect a program's behavior!"),
             ]);
             title = "UNNECESSARY DEFINITION".to_string();
+            code = codes::UNNECESSARY_DEFINITION;
         }
         Problem::OverloadedSpecialization {
             ability_member,
@@ -994,6 +1049,7 @@ pub fn can_problem<'b>(
                 alloc.reflow("Ability specializations can only provide implementations for one opaque type, since all opaque types are different!"),
             ]);
             title = "OVERLOADED SPECIALIZATION".to_string();
+            code = codes::OVERLOADED_SPECIALIZATION;
         }
         Problem::UnnecessaryOutputWildcard { region } => {
             doc = alloc.stack([
@@ -1013,6 +1069,7 @@ pub fn can_problem<'b>(
                 alloc.reflow("You can safely remove this to make the code more concise without changing what it means."),
             ]);
             title = "UNNECESSARY WILDCARD".to_string();
+            code = codes::UNNECESSARY_WILDCARD;
         }
         Problem::MultipleListRestPattern { region } => {
             doc = alloc.stack([
@@ -1025,6 +1082,7 @@ pub fn can_problem<'b>(
                 ]),
             ]);
             title = "MULTIPLE LIST REST PATTERNS".to_string();
+            code = codes::MULTIPLE_LIST_REST_PATTERNS;
         }
         Problem::BadTypeArguments {
             symbol,
@@ -1059,10 +1117,16 @@ pub fn can_problem<'b>(
                 alloc.reflow("Are there missing parentheses?"),
             ]);
 
-            title = if type_got > alias_needs {
-                "TOO MANY TYPE ARGUMENTS".to_string()
+            (title, code) = if type_got > alias_needs {
+                (
+                    "TOO MANY TYPE ARGUMENTS".to_string(),
+                    codes::TOO_MANY_TYPE_ARGUMENTS,
+                )
             } else {
-                "TOO FEW TYPE ARGUMENTS".to_string()
+                (
+                    "TOO FEW TYPE ARGUMENTS".to_string(),
+                    codes::TOO_FEW_TYPE_ARGUMENTS,
+                )
             };
         }
         Problem::UnappliedCrash { region } => {
@@ -1077,6 +1141,7 @@ pub fn can_problem<'b>(
                 ])
             ]);
             title = "UNAPPLIED CRASH".to_string();
+            code = codes::UNAPPLIED_CRASH;
         }
         Problem::OverAppliedCrash { region } => {
             doc = alloc.stack([
@@ -1092,17 +1157,257 @@ pub fn can_problem<'b>(
                 ]),
             ]);
             title = "OVERAPPLIED CRASH".to_string();
+            code = codes::OVERAPPLIED_CRASH;
+        }
+        Problem::NameDiffersOnlyInCase {
+            kind,
+            original,
+            similar,
+        } => {
+            let (what, original_name) = match kind {
+                NameKind::Tag => ("tag", alloc.tag(original.value.as_ref().into())),
+                NameKind::RecordField => (
+                    "record field",
+                    alloc.record_field(original.value.as_ref().into()),
+                ),
+            };
+
+            doc = alloc.stack([
+                alloc.concat([
+                    alloc.reflow("This "),
+                    alloc.reflow(what),
+                    alloc.reflow(" name only differs in capitalization from one used earlier:"),
+                ]),
+                alloc.region(lines.convert_region(similar.region)),
+                alloc.concat([
+                    alloc.reflow("It was first spelled "),
+                    original_name,
+                    alloc.reflow(" here:"),
+                ]),
+                alloc.region(lines.convert_region(original.region)),
+                alloc.reflow(
+                    "Names this similar are easy to mix up. If these are meant to be the same, use one spelling for both.",
+                ),
+            ]);
+            title = NAME_DIFFERS_ONLY_IN_CASE.to_string();
+            code = codes::NAME_DIFFERS_ONLY_IN_CASE;
+        }
+        Problem::UnoptimizedTailCall {
+            caller,
+            callee,
+            region,
+            reason,
+        } => {
+            let because = match reason {
+                TailCallBlocker::OtherSpecialization => alloc.concat([
+                    alloc.reflow("This is because "),
+                    alloc.symbol_unqualified(callee),
+                    alloc.reflow(" is called with a different type here (e.g. a different function as an argument) than in the rest of the loop, so they are compiled separately."),
+                ]),
+                TailCallBlocker::SharedSymbols => alloc.concat([
+                    alloc.reflow("This is because "),
+                    alloc.symbol_unqualified(caller),
+                    alloc.reflow(" and "),
+                    alloc.symbol_unqualified(callee),
+                    alloc.reflow(" both use some of the same values, and I can't combine two functions that do into one loop. Merging "),
+                    alloc.symbol_unqualified(callee),
+                    alloc.reflow(" into "),
+                    alloc.symbol_unqualified(caller),
+                    alloc.reflow(", so that the recursion goes through one function, avoids this."),
+                ]),
+            };
+
+            doc = alloc.stack([
+                alloc.concat([
+                    alloc.symbol_unqualified(caller),
+                    alloc.reflow(" ends by calling "),
+                    alloc.symbol_unqualified(callee),
+                    alloc.reflow(", which calls back into "),
+                    alloc.symbol_unqualified(caller),
+                    alloc.reflow(", but I could not turn that call into a jump:"),
+                ]),
+                alloc.region(lines.convert_region(region)),
+                because,
+                alloc.reflow(
+                    "Each of these calls uses more stack, so recursing deeply enough will overflow the stack.",
+                ),
+            ]);
+            title = UNOPTIMIZED_TAIL_CALL.to_string();
+            code = codes::UNOPTIMIZED_TAIL_CALL;
         }
     };
 
     Report {
         title,
+        code: Some(code),
         filename,
         doc,
         severity,
+        fixes,
+        related,
     }
 }
 
+/// Group the problems that most likely have the same root cause (see [Problem::cascade_key]),
+/// keeping the first problem of each group along with the regions of the others, so the
+/// root cause gets one report rather than a wall of them.
+pub fn group_cascading_problems(problems: Vec<Problem>) -> Vec<(Problem, Vec<Region>)> {
+    let mut grouped: Vec<(Problem, Vec<Region>)> = Vec::with_capacity(problems.len());
+    let mut group_indices: MutMap<String, usize> = MutMap::default();
+
+    for problem in problems {
+        if let Some(key) = problem.cascade_key() {
+            if let Some(&index) = group_indices.get(&key) {
+                if let Some(region) = problem.region() {
+                    grouped[index].1.push(region);
+
+                    continue;
+                }
+            } else {
+                group_indices.insert(key, grouped.len());
+            }
+        }
+
+        grouped.push((problem, Vec::new()));
+    }
+
+    for (_, regions) in grouped.iter_mut() {
+        regions.sort_by_key(|region| region.start().offset);
+    }
+
+    grouped
+}
+
+/// Add where else the report's problem happens to it, as one line rather than a snippet for each.
+pub fn add_cascade_note<'b>(
+    alloc: &'b RocDocAllocator<'b>,
+    lines: &LineInfo,
+    report: Report<'b>,
+    regions: &[Region],
+) -> Report<'b> {
+    if regions.is_empty() {
+        return report;
+    }
+
+    let locations: Vec<String> = regions
+        .iter()
+        .map(|region| {
+            let start = lines.convert_pos(region.start());
+
+            format!("{}:{}", start.line + 1, start.column + 1)
+        })
+        .collect();
+
+    let count = if regions.len() == 1 {
+        "1 more place".to_string()
+    } else {
+        format!("{} more places", regions.len())
+    };
+
+    let note = alloc.concat([
+        alloc.note(""),
+        alloc.reflow("The same problem happens in "),
+        alloc.string(count),
+        alloc.reflow(", at "),
+        alloc.string(locations.join(", ")),
+        alloc.text("."),
+    ]);
+
+    Report {
+        doc: alloc.stack([report.doc, note]),
+        ..report
+    }
+}
+
+fn can_problem_fixes(alloc: &RocDocAllocator, lines: &LineInfo, problem: &Problem) -> Vec<Fix> {
+    match problem {
+        Problem::RuntimeError(RuntimeError::LookupNotInScope(loc_name, _)) => {
+            let name = loc_name.value.as_inline_str().as_str();
+
+            // The region of a qualified lookup also covers the module name.
+            if alloc.source_text(lines.convert_region(loc_name.region)) != Some(name) {
+                return Vec::new();
+            }
+
+            problem
+                .suggestions()
+                .into_iter()
+                .map(|suggestion| {
+                    Fix::replace(
+                        format!("Rename to `{}`", suggestion),
+                        loc_name.region,
+                        suggestion.to_string(),
+                    )
+                })
+                .collect()
+        }
+        Problem::RuntimeError(RuntimeError::ModuleNotImported {
+            module_name,
+            module_exists: true,
+            ..
+        }) => match imports_insertion_point(alloc.src_lines) {
+            Some((line_column, is_empty)) => {
+                let text = if is_empty {
+                    module_name.to_string()
+                } else {
+                    format!(", {}", module_name)
+                };
+
+                vec![Fix::insert(
+                    format!("Import `{}`", module_name),
+                    lines.convert_line_column(line_column),
+                    text,
+                )]
+            }
+            None => Vec::new(),
+        },
+        _ => Vec::new(),
+    }
+}
+
+/// Where to add a module to the `imports` list in the module's header, and whether that list
+/// is empty. The header isn't part of the problem, so this finds the list in the source.
+fn imports_insertion_point(src_lines: &[&str]) -> Option<(LineColumn, bool)> {
+    let (start_line, start_column) = src_lines.iter().enumerate().find_map(|(line, src)| {
+        let after_keyword = src.find("imports")? + "imports".len();
+        let bracket = src[after_keyword..].find('[')?;
+
+        if !src[after_keyword..][..bracket].trim().is_empty() {
+            return None;
+        }
+
+        Some((line, after_keyword + bracket + 1))
+    })?;
+
+    // Right after the `[`, or after the last import
+    let mut insertion_point = LineColumn {
+        line: start_line as u32,
+        column: start_column as u32,
+    };
+    let mut is_empty = true;
+
+    for (line, src) in src_lines.iter().enumerate().skip(start_line) {
+        let start = if line == start_line { start_column } else { 0 };
+
+        for (column, ch) in src[start..].char_indices() {
+            match ch {
+                ']' => return Some((insertion_point, is_empty)),
+                ',' => {}
+                _ if ch.is_whitespace() => {}
+                _ => {
+                    is_empty = false;
+                    insertion_point = LineColumn {
+                        line: line as u32,
+                        column: (start + column + ch.len_utf8()) as u32,
+                    };
+                }
+            }
+        }
+    }
+
+    None
+}
+
 fn list_builtin_abilities<'a>(alloc: &'a RocDocAllocator<'a>) -> RocDocBuilder<'a> {
     alloc.intersperse(
         DERIVABLE_ABILITIES
@@ -1130,9 +1435,12 @@ fn to_invalid_optional_value_report<'b>(
 
     Report {
         title: "BAD OPTIONAL VALUE".to_string(),
+        code: Some(codes::BAD_OPTIONAL_VALUE),
         filename,
         doc,
         severity: Severity::RuntimeError,
+        fixes: Vec::new(),
+        related: Vec::new(),
     }
 }
 
@@ -1536,9 +1844,11 @@ fn pretty_runtime_error<'b>(
     alloc: &'b RocDocAllocator<'b>,
     lines: &LineInfo,
     runtime_error: RuntimeError,
-) -> (RocDocBuilder<'b>, &'static str) {
+) -> (RocDocBuilder<'b>, &'static str, &'static str) {
     let doc;
     let title;
+    let code;
+    let suggestions = runtime_error.suggestions();
 
     match runtime_error {
         RuntimeError::VoidValue => {
@@ -1558,15 +1868,18 @@ fn pretty_runtime_error<'b>(
             kind,
         } => {
             (title, doc) = report_shadowing(alloc, lines, original_region, shadow, kind);
+            code = codes::DUPLICATE_NAME;
         }
 
-        RuntimeError::LookupNotInScope(loc_name, options) => {
-            doc = not_found(alloc, lines, loc_name.region, &loc_name.value, options);
+        RuntimeError::LookupNotInScope(loc_name, _) => {
+            doc = not_found(alloc, lines, loc_name.region, &loc_name.value, suggestions);
             title = UNRECOGNIZED_NAME;
+            code = codes::UNRECOGNIZED_NAME;
         }
         RuntimeError::CircularDef(entries) => {
             doc = to_circular_def_doc(alloc, lines, &entries);
             title = CIRCULAR_DEF;
+            code = codes::CIRCULAR_DEFINITION;
         }
         RuntimeError::MalformedPattern(problem, region) => {
             use roc_parse::ast::Base;
@@ -1581,9 +1894,10 @@ fn pretty_runtime_error<'b>(
                 MalformedBase(Base::Decimal) => " integer ",
                 BadIdent(bad_ident) => {
                     title = NAMING_PROBLEM;
+                    code = codes::NAMING_PROBLEM;
                     doc = to_bad_ident_pattern_report(alloc, lines, bad_ident, region);
 
-                    return (doc, title);
+                    return (doc, title, code);
                 }
                 Unknown => " ",
                 QualifiedIdentifier => " qualified ",
@@ -1618,6 +1932,7 @@ fn pretty_runtime_error<'b>(
             ]);
 
             title = SYNTAX_PROBLEM;
+            code = codes::SYNTAX_PROBLEM;
         }
         RuntimeError::UnsupportedPattern(_) => {
             todo!("unsupported patterns are currently not parsed!")
@@ -1626,11 +1941,8 @@ fn pretty_runtime_error<'b>(
             module_name,
             ident,
             region,
-            exposed_values,
+            ..
         } => {
-            let mut suggestions = suggest::sort(ident.as_ref(), exposed_values);
-            suggestions.truncate(4);
-
             let did_you_mean = if suggestions.is_empty() {
                 alloc.concat([
                     alloc.reflow("In fact, it looks like "),
@@ -1640,7 +1952,7 @@ fn pretty_runtime_error<'b>(
             } else {
                 let qualified_suggestions = suggestions
                     .into_iter()
-                    .map(|v| alloc.string(module_name.to_string() + "." + v.as_str()));
+                    .map(|v| alloc.string(format!("{}.{}", module_name, v)));
                 alloc.stack([
                     alloc.reflow("Did you mean one of these?"),
                     alloc.vcat(qualified_suggestions).indent(4),
@@ -1659,24 +1971,26 @@ fn pretty_runtime_error<'b>(
             ]);
 
             title = VALUE_NOT_EXPOSED;
+            code = codes::NOT_EXPOSED;
         }
 
         RuntimeError::ModuleNotImported {
             module_name,
-            imported_modules,
             region,
             module_exists,
+            ..
         } => {
             doc = module_not_found(
                 alloc,
                 lines,
                 region,
                 &module_name,
-                imported_modules,
+                suggestions,
                 module_exists,
             );
 
             title = MODULE_NOT_IMPORTED;
+            code = codes::MODULE_NOT_IMPORTED;
         }
         RuntimeError::InvalidPrecedence(_, _) => {
             // do nothing, reported with PrecedenceProblem
@@ -1686,6 +2000,7 @@ fn pretty_runtime_error<'b>(
             doc = to_bad_ident_expr_report(alloc, lines, bad_ident, surroundings);
 
             title = SYNTAX_PROBLEM;
+            code = codes::SYNTAX_PROBLEM;
         }
         RuntimeError::MalformedTypeName(_box_str, surroundings) => {
             doc = alloc.stack([
@@ -1702,6 +2017,7 @@ fn pretty_runtime_error<'b>(
             ]);
 
             title = SYNTAX_PROBLEM;
+            code = codes::SYNTAX_PROBLEM;
         }
         RuntimeError::MalformedClosure(_) => {
             todo!("");
@@ -1736,6 +2052,7 @@ fn pretty_runtime_error<'b>(
             ]);
 
             title = SYNTAX_PROBLEM;
+            code = codes::SYNTAX_PROBLEM;
         }
         RuntimeError::InvalidFloat(FloatErrorKind::Error, region, _raw_str) => {
             let tip = alloc
@@ -1754,6 +2071,7 @@ fn pretty_runtime_error<'b>(
             ]);
 
             title = SYNTAX_PROBLEM;
+            code = codes::SYNTAX_PROBLEM;
         }
         RuntimeError::InvalidFloat(FloatErrorKind::IntSuffix, region, _raw_str) => {
             doc = alloc.stack([
@@ -1764,6 +2082,7 @@ fn pretty_runtime_error<'b>(
             ]);
 
             title = CONFLICTING_NUMBER_SUFFIX;
+            code = codes::CONFLICTING_NUMBER_SUFFIX;
         }
         RuntimeError::InvalidInt(error @ IntErrorKind::InvalidDigit, base, region, _raw_str)
         | RuntimeError::InvalidInt(error @ IntErrorKind::Empty, base, region, _raw_str) => {
@@ -1825,6 +2144,7 @@ fn pretty_runtime_error<'b>(
             ]);
 
             title = SYNTAX_PROBLEM;
+            code = codes::SYNTAX_PROBLEM;
         }
         RuntimeError::InvalidInt(error_kind @ IntErrorKind::Underflow, _base, region, _raw_str)
         | RuntimeError::InvalidInt(error_kind @ IntErrorKind::Overflow, _base, region, _raw_str) => {
@@ -1868,6 +2188,7 @@ fn pretty_runtime_error<'b>(
             ]);
 
             title = SYNTAX_PROBLEM;
+            code = codes::SYNTAX_PROBLEM;
         }
         RuntimeError::InvalidInt(IntErrorKind::FloatSuffix, _base, region, _raw_str) => {
             doc = alloc.stack([
@@ -1878,6 +2199,7 @@ fn pretty_runtime_error<'b>(
             ]);
 
             title = CONFLICTING_NUMBER_SUFFIX;
+            code = codes::CONFLICTING_NUMBER_SUFFIX;
         }
         RuntimeError::InvalidInt(
             IntErrorKind::OverflowsSuffix {
@@ -1902,6 +2224,7 @@ fn pretty_runtime_error<'b>(
             ]);
 
             title = NUMBER_OVERFLOWS_SUFFIX;
+            code = codes::NUMBER_OVERFLOWS_SUFFIX;
         }
         RuntimeError::InvalidInt(
             IntErrorKind::UnderflowsSuffix {
@@ -1926,6 +2249,7 @@ fn pretty_runtime_error<'b>(
             ]);
 
             title = NUMBER_UNDERFLOWS_SUFFIX;
+            code = codes::NUMBER_UNDERFLOWS_SUFFIX;
         }
         RuntimeError::InvalidOptionalValue {
             field_name,
@@ -1941,6 +2265,7 @@ fn pretty_runtime_error<'b>(
             );
 
             title = SYNTAX_PROBLEM;
+            code = codes::SYNTAX_PROBLEM;
         }
         RuntimeError::InvalidRecordUpdate { region } => {
             doc = alloc.stack([
@@ -1953,6 +2278,7 @@ fn pretty_runtime_error<'b>(
             ]);
 
             title = SYNTAX_PROBLEM;
+            code = codes::SYNTAX_PROBLEM;
         }
         RuntimeError::InvalidHexadecimal(region) => {
             todo!(
@@ -1986,6 +2312,7 @@ fn pretty_runtime_error<'b>(
                 .append(alloc.reflow(", but it was not defined anywhere in that module."))]);
 
             title = MISSING_DEFINITION;
+            code = codes::MISSING_DEFINITION;
         }
         RuntimeError::EmptySingleQuote(region) => {
             let tip = alloc
@@ -1999,6 +2326,7 @@ fn pretty_runtime_error<'b>(
             ]);
 
             title = SYNTAX_PROBLEM;
+            code = codes::SYNTAX_PROBLEM;
         }
         RuntimeError::MultipleCharsInSingleQuote(region) => {
             let tip = alloc
@@ -2015,6 +2343,7 @@ fn pretty_runtime_error<'b>(
             ]);
 
             title = SYNTAX_PROBLEM;
+            code = codes::SYNTAX_PROBLEM;
         }
         RuntimeError::OpaqueNotDefined {
             usage:
@@ -2022,15 +2351,9 @@ fn pretty_runtime_error<'b>(
                     region: used_region,
                     value: opaque,
                 },
-            opaques_in_scope,
             opt_defined_alias,
+            ..
         } => {
-            let mut suggestions = suggest::sort(
-                opaque.as_inline_str().as_str(),
-                opaques_in_scope.iter().map(|v| v.as_ref()).collect(),
-            );
-            suggestions.truncate(4);
-
             let details = if suggestions.is_empty() {
                 alloc.note("It looks like there are no opaque types declared in this scope yet!")
             } else {
@@ -2065,6 +2388,7 @@ fn pretty_runtime_error<'b>(
             doc = alloc.stack(stack);
 
             title = OPAQUE_NOT_DEFINED;
+            code = codes::OPAQUE_TYPE_NOT_DEFINED;
         }
         RuntimeError::OpaqueOutsideScope {
             opaque,
@@ -2086,6 +2410,7 @@ fn pretty_runtime_error<'b>(
             ]);
 
             title = OPAQUE_DECLARED_OUTSIDE_SCOPE;
+            code = codes::OPAQUE_TYPE_DECLARED_OUTSIDE_SCOPE;
         }
         RuntimeError::OpaqueNotApplied(loc_ident) => {
             doc = alloc.stack([
@@ -2095,6 +2420,7 @@ fn pretty_runtime_error<'b>(
             ]);
 
             title = OPAQUE_NOT_APPLIED;
+            code = codes::OPAQUE_TYPE_NOT_APPLIED;
         }
         RuntimeError::OpaqueAppliedToMultipleArgs(region) => {
             doc = alloc.stack([
@@ -2104,6 +2430,7 @@ fn pretty_runtime_error<'b>(
             ]);
 
             title = OPAQUE_OVER_APPLIED;
+            code = codes::OPAQUE_TYPE_APPLIED_TO_TOO_MANY_ARGS;
         }
         RuntimeError::DegenerateBranch(region) => {
             doc = alloc.stack([
@@ -2112,10 +2439,11 @@ fn pretty_runtime_error<'b>(
             ]);
 
             title = "DEGENERATE BRANCH";
+            code = codes::DEGENERATE_BRANCH;
         }
     }
 
-    (doc, title)
+    (doc, title, code)
 }
 
 pub fn to_circular_def_doc<'b>(
@@ -2172,14 +2500,8 @@ fn not_found<'b>(
     lines: &LineInfo,
     region: roc_region::all::Region,
     name: &Ident,
-    options: MutSet<Box<str>>,
+    suggestions: Vec<Box<str>>,
 ) -> RocDocBuilder<'b> {
-    let mut suggestions = suggest::sort(
-        name.as_inline_str().as_str(),
-        options.iter().map(|v| v.as_ref()).collect(),
-    );
-    suggestions.truncate(4);
-
     let default_no = alloc.concat([
         alloc.reflow("Is there an "),
         alloc.keyword("import"),
@@ -2222,7 +2544,7 @@ fn module_not_found<'b>(
     lines: &LineInfo,
     region: roc_region::all::Region,
     name: &ModuleName,
-    options: MutSet<Box<str>>,
+    suggestions: Vec<Box<str>>,
     module_exists: bool,
 ) -> RocDocBuilder<'b> {
     // If the module exists, sugguest that the user import it
@@ -2231,10 +2553,6 @@ fn module_not_found<'b>(
         alloc.reflow("Did you mean to import it?")
     } else {
         // If the module might not exist, sugguest that it's a typo
-        let mut suggestions =
-            suggest::sort(name.as_str(), options.iter().map(|v| v.as_ref()).collect());
-        suggestions.truncate(4);
-
         if suggestions.is_empty() {
             // We don't have any recommended spelling corrections
             alloc.concat([
@@ -1,15 +1,16 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use bumpalo::Bump;
 use roc_module::symbol::{Interns, ModuleId, Symbol};
-use roc_parse::ast::Expr;
+use roc_parse::ast::{AssignedField, Expr};
 use roc_problem::Severity;
-use roc_region::all::{LineColumnRegion, LineInfo, Region};
+use roc_region::all::{LineColumnRegion, LineInfo, Loc, Region};
 use roc_types::{
     subs::{Subs, Variable},
     types::{ErrorType, Polarity},
 };
 
+use crate::codes;
 use crate::report::{RenderTarget, RocDocAllocator, RocDocBuilder};
 
 pub struct Renderer<'a> {
@@ -18,6 +19,17 @@ pub struct Renderer<'a> {
     filename: PathBuf,
     line_info: LineInfo,
     render_target: RenderTarget,
+    interns: &'a Interns,
+    source: &'a str,
+}
+
+/// A place where two values that should have been equal are different
+struct Difference {
+    /// e.g. `.items[2].name`
+    path: String,
+    /// `None` when only the other value has something here, e.g. because its list is longer
+    left: Option<String>,
+    right: Option<String>,
 }
 
 impl<'a> Renderer<'a> {
@@ -40,6 +52,8 @@ impl<'a> Renderer<'a> {
             line_info,
             filename,
             render_target,
+            interns,
+            source,
         }
     }
 
@@ -72,34 +86,247 @@ impl<'a> Renderer<'a> {
         ])
     }
 
+    fn render_differences(
+        &'a self,
+        left: Symbol,
+        right: Symbol,
+        differences: Vec<Difference>,
+    ) -> RocDocBuilder<'a> {
+        use ven_pretty::DocAllocator;
+
+        let value = |symbol: Symbol, value: Option<String>| {
+            self.alloc
+                .symbol_unqualified(symbol)
+                .append(" = ")
+                .append(value.unwrap_or_else(|| "(missing)".to_string()))
+        };
+
+        let it = differences.into_iter().map(|difference| {
+            self.alloc.vcat([
+                self.alloc.text(difference.path),
+                self.alloc
+                    .vcat([value(left, difference.left), value(right, difference.right)])
+                    .indent(4),
+            ])
+        });
+
+        self.alloc.stack([
+            self.alloc.concat([
+                self.alloc.reflow("These are the parts of "),
+                self.alloc.symbol_unqualified(left),
+                self.alloc.reflow(" and "),
+                self.alloc.symbol_unqualified(right),
+                self.alloc
+                    .reflow(" that differ; everything else about them is equal:"),
+            ]),
+            self.alloc.stack(it),
+        ])
+    }
+
+    /// When the expectation ends in `a == b`, where `a` and `b` are variables we have the
+    /// values of, their positions in the lookups.
+    fn compared_lookups(
+        &self,
+        failure_region: Region,
+        symbols: &[Symbol],
+    ) -> Option<(usize, usize)> {
+        let start = failure_region.start().offset as usize;
+        let end = failure_region.end().offset as usize;
+
+        let condition = self
+            .source
+            .get(start..end)?
+            .lines()
+            .rev()
+            .map(str::trim)
+            .find(|line| !line.is_empty())?;
+        let condition = condition.strip_prefix("expect ").unwrap_or(condition);
+
+        let (left, right) = condition.split_once(" == ")?;
+        let position = |name: &str| {
+            symbols
+                .iter()
+                .position(|symbol| symbol.as_str(self.interns) == name.trim())
+        };
+
+        match (position(left)?, position(right)?) {
+            (left, right) if left != right => Some((left, right)),
+            _ => None,
+        }
+    }
+
+    /// The places where two values differ, if they have some structure in common. When they
+    /// differ everywhere, it's clearer to just show both of them.
+    fn structural_diff(&self, left: &Expr<'_>, right: &Expr<'_>) -> Option<Vec<Difference>> {
+        let mut differences = Vec::new();
+        let mut equal = 0;
+
+        self.diff_values(
+            &mut String::new(),
+            left,
+            right,
+            &mut differences,
+            &mut equal,
+        );
+
+        let at_root = differences
+            .iter()
+            .any(|difference| difference.path.is_empty());
+
+        if equal == 0 || differences.is_empty() || at_root {
+            None
+        } else {
+            Some(differences)
+        }
+    }
+
+    fn diff_values(
+        &self,
+        path: &mut String,
+        left: &Expr<'_>,
+        right: &Expr<'_>,
+        differences: &mut Vec<Difference>,
+        equal: &mut usize,
+    ) {
+        let len = path.len();
+
+        match (without_spaces(left), without_spaces(right)) {
+            (Expr::Record(left_fields), Expr::Record(right_fields)) => {
+                let right_fields: Vec<_> = right_fields.iter().filter_map(record_field).collect();
+
+                // Fields are in memory order; show them in the order their type is written in
+                let mut left_fields: Vec<_> = left_fields.iter().filter_map(record_field).collect();
+                left_fields.sort_by_key(|(name, _)| *name);
+
+                for (name, left_value) in left_fields {
+                    path.push('.');
+                    path.push_str(name);
+
+                    match right_fields.iter().find(|(other, _)| *other == name) {
+                        Some((_, right_value)) => {
+                            self.diff_values(path, left_value, right_value, differences, equal)
+                        }
+                        None => differences.push(Difference {
+                            path: path.clone(),
+                            left: Some(self.format_value(left_value)),
+                            right: None,
+                        }),
+                    }
+
+                    path.truncate(len);
+                }
+            }
+            (Expr::List(left_elems), Expr::List(right_elems)) => self.diff_elements(
+                path,
+                left_elems.items,
+                right_elems.items,
+                differences,
+                equal,
+                |path, index| path.push_str(&format!("[{}]", index)),
+            ),
+            (Expr::Tuple(left_elems), Expr::Tuple(right_elems)) => self.diff_elements(
+                path,
+                left_elems.items,
+                right_elems.items,
+                differences,
+                equal,
+                |path, index| path.push_str(&format!(".{}", index)),
+            ),
+            (left, right) => {
+                let left = self.format_value(left);
+                let right = self.format_value(right);
+
+                if left == right {
+                    *equal += 1;
+                } else {
+                    differences.push(Difference {
+                        path: path.clone(),
+                        left: Some(left),
+                        right: Some(right),
+                    });
+                }
+            }
+        }
+    }
+
+    fn diff_elements(
+        &self,
+        path: &mut String,
+        left: &[&Loc<Expr<'_>>],
+        right: &[&Loc<Expr<'_>>],
+        differences: &mut Vec<Difference>,
+        equal: &mut usize,
+        push_index: impl Fn(&mut String, usize),
+    ) {
+        let len = path.len();
+
+        for index in 0..left.len().max(right.len()) {
+            push_index(path, index);
+
+            match (left.get(index), right.get(index)) {
+                (Some(left), Some(right)) => {
+                    self.diff_values(path, &left.value, &right.value, differences, equal)
+                }
+                (left, right) => differences.push(Difference {
+                    path: path.clone(),
+                    left: left.map(|elem| self.format_value(&elem.value)),
+                    right: right.map(|elem| self.format_value(&elem.value)),
+                }),
+            }
+
+            path.truncate(len);
+        }
+    }
+
     fn render_lookups(
         &'a self,
         subs: &mut Subs,
         line_col_region: LineColumnRegion,
-
+        failure_region: Region,
         symbols: &[Symbol],
         variables: &[Variable],
         expressions: &[Expr<'_>],
     ) -> RocDocBuilder<'a> {
         use ven_pretty::DocAllocator;
 
-        let it =
-            symbols
-                .iter()
-                .zip(variables)
-                .zip(expressions)
-                .map(|((symbol, variable), expr)| {
-                    let error_type = subs.var_to_error_type(*variable, Polarity::OF_VALUE);
-                    self.render_lookup(*symbol, expr, error_type)
-                });
-
-        if it.len() > 0 {
+        let diff = self
+            .compared_lookups(failure_region, symbols)
+            .and_then(|(left, right)| {
+                let differences = self.structural_diff(&expressions[left], &expressions[right])?;
+
+                Some((left, right, differences))
+            });
+
+        let mut docs = Vec::with_capacity(symbols.len() + 1);
+
+        for (index, ((symbol, variable), expr)) in
+            symbols.iter().zip(variables).zip(expressions).enumerate()
+        {
+            let error_type = subs.var_to_error_type(*variable, Polarity::OF_VALUE);
+
+            match &diff {
+                // The values of compared variables are shown in the diff below
+                Some((left, right, _)) if index == *left || index == *right => docs.push(
+                    self.alloc
+                        .symbol_unqualified(*symbol)
+                        .append(" : ")
+                        .append(self.render_expr(error_type)),
+                ),
+                _ => docs.push(self.render_lookup(*symbol, expr, error_type)),
+            }
+        }
+
+        if let Some((left, right, differences)) = diff {
+            docs.push(self.render_differences(symbols[left], symbols[right], differences));
+        }
+
+        if !docs.is_empty() {
             self.alloc.stack([
                 self.alloc.text("This expectation failed:"),
                 self.alloc.region(line_col_region),
                 self.alloc
                     .text("When it failed, these variables had these values:"),
-                self.alloc.stack(it),
+                self.alloc.stack(docs),
                 self.alloc.text(""), // Blank line at the end
             ])
         } else {
@@ -149,13 +376,23 @@ impl<'a> Renderer<'a> {
         use crate::report::Report;
 
         let line_col_region = self.to_line_col_region(expect_region, failure_region);
-        let doc = self.render_lookups(subs, line_col_region, symbols, variables, expressions);
+        let doc = self.render_lookups(
+            subs,
+            line_col_region,
+            failure_region,
+            symbols,
+            variables,
+            expressions,
+        );
 
         let report = Report {
             title: "EXPECT FAILED".into(),
+            code: Some(codes::EXPECT_FAILED),
             doc,
             filename: self.filename.clone(),
             severity: Severity::RuntimeError,
+            fixes: Vec::new(),
+            related: Vec::new(),
         };
 
         let mut buf = String::new();
@@ -164,7 +401,7 @@ impl<'a> Renderer<'a> {
             self.render_target,
             &mut buf,
             &self.alloc,
-            &crate::report::DEFAULT_PALETTE,
+            &crate::report::Palette::from_env(),
         );
 
         write!(writer, "{}", buf)
@@ -201,11 +438,207 @@ impl<'a> Renderer<'a> {
         writeln!(writer, "{}", buf.as_str())
     }
 
+    /// The value as it would be written in Roc code
+    pub fn format_value(&self, expr: &Expr<'_>) -> String {
+        use roc_fmt::annotation::Formattable;
+
+        let mut buf = roc_fmt::Buf::new_in(self.arena);
+        expr.format(&mut buf, 0);
+
+        buf.as_str().to_string()
+    }
+
+    /// A property test failed. The arguments are Roc code, and the seed reproduces them.
+    pub fn render_property_failure<W>(
+        &self,
+        writer: &mut W,
+        expect_region: Region,
+        arguments: &[String],
+        cases: usize,
+        seed: u64,
+    ) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+    {
+        use ven_pretty::DocAllocator;
+
+        let line_col_region = self.line_info.convert_region(expect_region);
+        let command = format!("roc test --seed {}", seed);
+
+        let doc = self.alloc.stack([
+            self.alloc.text("This property failed:"),
+            self.alloc.region(line_col_region),
+            self.alloc.reflow(if arguments.len() == 1 {
+                "These are the smallest arguments I found that make it fail:"
+            } else {
+                "These are the smallest arguments I found that make it fail, in order:"
+            }),
+            self.alloc
+                .stack(
+                    arguments
+                        .iter()
+                        .map(|argument| self.alloc.text(argument.as_str())),
+                )
+                .indent(4),
+            self.alloc.concat([
+                self.alloc.reflow("I found them after trying "),
+                self.alloc.text(cases.to_string()),
+                self.alloc
+                    .reflow(" random cases. To try the same ones again, run "),
+                self.alloc.keyword(&command),
+            ]),
+        ]);
+
+        self.write_report(writer, "PROPERTY FAILED", Some(codes::PROPERTY_FAILED), doc)
+    }
+
+    /// We couldn't run a property test, e.g. because we don't know how to generate its
+    /// arguments.
+    pub fn render_untestable_property<W>(
+        &self,
+        writer: &mut W,
+        expect_region: Region,
+        reason: &str,
+    ) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+    {
+        use ven_pretty::DocAllocator;
+
+        let line_col_region = self.line_info.convert_region(expect_region);
+
+        let doc = self.alloc.stack([
+            self.alloc.reflow("I could not test this property:"),
+            self.alloc.region(line_col_region),
+            self.alloc.reflow(reason),
+        ]);
+
+        self.write_report(
+            writer,
+            "UNTESTABLE PROPERTY",
+            Some(codes::UNTESTABLE_PROPERTY),
+            doc,
+        )
+    }
+
+    fn write_report<'b, W>(
+        &'b self,
+        writer: &mut W,
+        title: &str,
+        code: Option<&'static str>,
+        doc: RocDocBuilder<'b>,
+    ) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+    {
+        let report = crate::report::Report {
+            title: title.into(),
+            code,
+            doc,
+            filename: self.filename.clone(),
+            severity: Severity::RuntimeError,
+            fixes: Vec::new(),
+            related: Vec::new(),
+        };
+
+        let mut buf = String::new();
+
+        report.render(
+            self.render_target,
+            &mut buf,
+            &self.alloc,
+            &crate::report::Palette::from_env(),
+        );
+
+        write!(writer, "{}", buf)
+    }
+
+    /// A snapshot expect's value doesn't render the way it did when its snapshot was recorded.
+    /// The diff goes from the recorded snapshot to the current value, one line at a time.
+    pub fn render_snapshot_mismatch<W>(
+        &self,
+        writer: &mut W,
+        expect_region: Region,
+        snapshot_path: &Path,
+        diff: &[String],
+    ) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+    {
+        use ven_pretty::DocAllocator;
+
+        let line_col_region = self.line_info.convert_region(expect_region);
+
+        let doc = self.alloc.stack([
+            self.alloc.text("This snapshot changed:"),
+            self.alloc.region(line_col_region),
+            self.alloc.concat([
+                self.alloc.reflow("Compared to the snapshot recorded in "),
+                self.alloc
+                    .text(snapshot_path.display().to_string())
+                    .annotate(crate::report::Annotation::Module),
+                self.alloc.reflow(", its value is now:"),
+            ]),
+            self.alloc
+                .stack(diff.iter().map(|line| self.alloc.text(line.as_str())))
+                .indent(4),
+            self.alloc.concat([
+                self.alloc.tip(),
+                self.alloc.reflow("If the new value is right, run "),
+                self.alloc.keyword("roc test --update-snapshots"),
+                self.alloc.reflow(" to record it."),
+            ]),
+        ]);
+
+        self.write_report(
+            writer,
+            "SNAPSHOT MISMATCH",
+            Some(codes::SNAPSHOT_MISMATCH),
+            doc,
+        )
+    }
+
+    /// Report the Roc heap allocations an expectation leaked or freed twice. Each problem is a
+    /// description of the allocation along with the Roc frames it was allocated in.
+    pub fn render_memory_problems<W>(
+        &self,
+        writer: &mut W,
+        expect_region: Region,
+        problems: &[(String, Vec<String>)],
+    ) -> std::io::Result<()>
+    where
+        W: std::io::Write,
+    {
+        use ven_pretty::DocAllocator;
+
+        let line_col_region = self.line_info.convert_region(expect_region);
+
+        let mut docs = vec![
+            self.alloc
+                .text("This expectation passed, but it did not free its memory correctly:"),
+            self.alloc.region(line_col_region),
+        ];
+
+        for (description, frames) in problems {
+            docs.push(self.alloc.reflow(description.as_str()));
+
+            if !frames.is_empty() {
+                docs.push(self.alloc.text("It was allocated in:").indent(4));
+                let frames = frames.iter().map(|frame| self.alloc.text(frame.as_str()));
+
+                docs.push(self.alloc.stack(frames).indent(8));
+            }
+        }
+
+        self.write_report(writer, "EXPECT MEMORY ERROR", None, self.alloc.stack(docs))
+    }
+
     pub fn render_panic<W>(
         &self,
         writer: &mut W,
         message: &str,
         expect_region: Region,
+        stack_trace: &[String],
     ) -> std::io::Result<()>
     where
         W: std::io::Write,
@@ -215,18 +648,32 @@ impl<'a> Renderer<'a> {
 
         let line_col_region = self.line_info.convert_region(expect_region);
 
-        let doc = self.alloc.stack([
+        let mut docs = vec![
             self.alloc.text("This expectation crashed while running:"),
             self.alloc.region(line_col_region),
             self.alloc.text("The crash reported this message:"),
             self.alloc.text(message),
-        ]);
+        ];
+
+        if !stack_trace.is_empty() {
+            docs.push(self.alloc.text("It happened in:"));
+            let frames = stack_trace
+                .iter()
+                .map(|frame| self.alloc.text(frame.as_str()));
+
+            docs.push(self.alloc.stack(frames).indent(4));
+        }
+
+        let doc = self.alloc.stack(docs);
 
         let report = Report {
             title: "EXPECT PANICKED".into(),
+            code: Some(codes::EXPECT_PANICKED),
             doc,
             filename: self.filename.clone(),
             severity: Severity::RuntimeError,
+            fixes: Vec::new(),
+            related: Vec::new(),
         };
 
         let mut buf = String::new();
@@ -235,9 +682,37 @@ impl<'a> Renderer<'a> {
             self.render_target,
             &mut buf,
             &self.alloc,
-            &crate::report::DEFAULT_PALETTE,
+            &crate::report::Palette::from_env(),
         );
 
         write!(writer, "{}", buf)
     }
 }
+
+fn without_spaces<'e>(mut expr: &'e Expr<'e>) -> &'e Expr<'e> {
+    while let Expr::SpaceBefore(inner, _) | Expr::SpaceAfter(inner, _) | Expr::ParensAround(inner) =
+        expr
+    {
+        expr = *inner;
+    }
+
+    expr
+}
+
+fn record_field<'e>(
+    field: &'e Loc<AssignedField<'e, Expr<'e>>>,
+) -> Option<(&'e str, &'e Expr<'e>)> {
+    let mut field = &field.value;
+
+    loop {
+        match field {
+            AssignedField::RequiredValue(name, _, value) => {
+                return Some((name.value, &value.value))
+            }
+            AssignedField::SpaceBefore(inner, _) | AssignedField::SpaceAfter(inner, _) => {
+                field = *inner
+            }
+            _ => return None,
+        }
+    }
+}
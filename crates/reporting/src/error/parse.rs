@@ -3,6 +3,7 @@ use roc_problem::Severity;
 use roc_region::all::{LineColumn, LineColumnRegion, LineInfo, Position, Region};
 use std::path::PathBuf;
 
+use crate::codes;
 use crate::report::{Report, RocDocAllocator, RocDocBuilder};
 use ven_pretty::DocAllocator;
 
@@ -72,7 +73,10 @@ fn to_syntax_report<'a>(
         filename: filename.clone(),
         doc,
         title: "PARSE PROBLEM".to_string(),
+        code: Some(codes::PARSE_PROBLEM),
         severity: Severity::RuntimeError,
+        fixes: Vec::new(),
+        related: Vec::new(),
     };
 
     match parse_problem {
@@ -86,7 +90,10 @@ fn to_syntax_report<'a>(
                 filename,
                 doc,
                 title: "PARSE PROBLEM".to_string(),
+                code: Some(codes::PARSE_PROBLEM),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         Unexpected(region) => {
@@ -118,7 +125,10 @@ fn to_syntax_report<'a>(
                 filename,
                 doc,
                 title: "NOT END OF FILE".to_string(),
+                code: Some(codes::NOT_END_OF_FILE),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         SyntaxError::Eof(region) => {
@@ -131,7 +141,10 @@ fn to_syntax_report<'a>(
                 filename,
                 doc,
                 title: "PARSE PROBLEM".to_string(),
+                code: Some(codes::PARSE_PROBLEM),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         SyntaxError::OutdentedTooFar => {
@@ -141,7 +154,10 @@ fn to_syntax_report<'a>(
                 filename,
                 doc,
                 title: "PARSE PROBLEM".to_string(),
+                code: Some(codes::PARSE_PROBLEM),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         Type(typ) => to_type_report(alloc, lines, filename, typ, Position::default()),
@@ -222,7 +238,10 @@ fn to_expr_report<'a>(
                 filename,
                 doc,
                 title: "ARGUMENTS BEFORE EQUALS".to_string(),
+                code: Some(codes::ARGUMENTS_BEFORE_EQUALS),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -320,7 +339,10 @@ fn to_expr_report<'a>(
                 filename,
                 doc,
                 title: "UNKNOWN OPERATOR".to_string(),
+                code: Some(codes::UNKNOWN_OPERATOR),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -346,14 +368,18 @@ fn to_expr_report<'a>(
                 filename,
                 doc,
                 title: "WEIRD IDENTIFIER".to_string(),
+                code: Some(codes::WEIRD_IDENTIFIER),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
         EExpr::Start(pos) | EExpr::IndentStart(pos) => {
-            let (title, expecting) = match &context {
+            let (title, code, expecting) = match &context {
                 Context::InNode { .. } | Context::InDef { .. } => (
                     "MISSING EXPRESSION",
+                    codes::MISSING_EXPRESSION,
                     alloc.concat([
                         alloc.reflow("I was expecting to see an expression like "),
                         alloc.parser_suggestion("42"),
@@ -364,6 +390,7 @@ fn to_expr_report<'a>(
                 ),
                 Context::InDefFinalExpr { .. } => (
                     "MISSING FINAL EXPRESSION",
+                    codes::MISSING_FINAL_EXPRESSION,
                     alloc.stack([
                         alloc.concat([
                             alloc.reflow("This definition is missing a final expression."),
@@ -428,7 +455,10 @@ fn to_expr_report<'a>(
                 filename,
                 doc,
                 title: title.to_string(),
+                code: Some(code),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -456,7 +486,10 @@ fn to_expr_report<'a>(
                 filename,
                 doc,
                 title: "MISSING FINAL EXPRESSION".to_string(),
+                code: Some(codes::MISSING_FINAL_EXPRESSION),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -487,7 +520,10 @@ fn to_expr_report<'a>(
                 filename,
                 doc,
                 title: "SYNTAX PROBLEM".to_string(),
+                code: Some(codes::SYNTAX_PROBLEM),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -510,7 +546,10 @@ fn to_expr_report<'a>(
                 filename,
                 doc,
                 title: "ARGUMENTS BEFORE EQUALS".to_string(),
+                code: Some(codes::ARGUMENTS_BEFORE_EQUALS),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -528,7 +567,10 @@ fn to_expr_report<'a>(
                 filename,
                 doc,
                 title: "BAD BACKPASSING ARROW".to_string(),
+                code: Some(codes::BAD_BACKPASSING_ARROW),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -546,7 +588,10 @@ fn to_expr_report<'a>(
                 filename,
                 doc,
                 title: "RECORD PARSE PROBLEM".to_string(),
+                code: Some(codes::RECORD_PARSE_PROBLEM),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -612,7 +657,10 @@ fn to_expr_report<'a>(
                 filename,
                 doc,
                 title: "INDENT ENDS AFTER EXPRESSION".to_string(),
+                code: Some(codes::INDENT_ENDS_AFTER_EXPRESSION),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         EExpr::Expect(e_expect, _position) => {
@@ -657,7 +705,10 @@ fn to_lambda_report<'a>(
                     filename,
                     doc,
                     title: "WEIRD ARROW".to_string(),
+                    code: Some(codes::WEIRD_ARROW),
                     severity: Severity::RuntimeError,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
             _ => {
@@ -679,7 +730,10 @@ fn to_lambda_report<'a>(
                     filename,
                     doc,
                     title: "MISSING ARROW".to_string(),
+                    code: Some(codes::MISSING_ARROW),
                     severity: Severity::RuntimeError,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
         },
@@ -704,7 +758,10 @@ fn to_lambda_report<'a>(
                     filename,
                     doc,
                     title: "WEIRD ARROW".to_string(),
+                    code: Some(codes::WEIRD_ARROW),
                     severity: Severity::RuntimeError,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
             _ => {
@@ -726,7 +783,10 @@ fn to_lambda_report<'a>(
                     filename,
                     doc,
                     title: "MISSING ARROW".to_string(),
+                    code: Some(codes::MISSING_ARROW),
                     severity: Severity::RuntimeError,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
         },
@@ -750,7 +810,10 @@ fn to_lambda_report<'a>(
                     filename,
                     doc,
                     title: "UNFINISHED ARGUMENT LIST".to_string(),
+                    code: Some(codes::UNFINISHED_ARGUMENT_LIST),
                     severity: Severity::RuntimeError,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
             _ => {
@@ -771,7 +834,10 @@ fn to_lambda_report<'a>(
                     filename,
                     doc,
                     title: "MISSING ARROW".to_string(),
+                    code: Some(codes::MISSING_ARROW),
                     severity: Severity::RuntimeError,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
         },
@@ -852,7 +918,10 @@ fn to_unfinished_lambda_report<'a>(
         filename,
         doc,
         title: "UNFINISHED FUNCTION".to_string(),
+        code: Some(codes::UNFINISHED_FUNCTION),
         severity: Severity::RuntimeError,
+        fixes: Vec::new(),
+        related: Vec::new(),
     }
 }
 
@@ -918,7 +987,10 @@ fn to_str_report<'a>(
                 filename,
                 doc,
                 title: "WEIRD ESCAPE".to_string(),
+                code: Some(codes::WEIRD_ESCAPE),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         EString::CodePtOpen(pos) | EString::CodePtEnd(pos) => {
@@ -944,7 +1016,10 @@ fn to_str_report<'a>(
                 filename,
                 doc,
                 title: "WEIRD CODE POINT".to_string(),
+                code: Some(codes::WEIRD_CODE_POINT),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         EString::FormatEnd(pos) => {
@@ -965,7 +1040,10 @@ fn to_str_report<'a>(
                 filename,
                 doc,
                 title: "ENDLESS FORMAT".to_string(),
+                code: Some(codes::ENDLESS_FORMAT),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         EString::EndlessSingle(pos) => {
@@ -988,7 +1066,10 @@ fn to_str_report<'a>(
                 filename,
                 doc,
                 title: "ENDLESS STRING".to_string(),
+                code: Some(codes::ENDLESS_STRING),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         EString::EndlessMulti(pos) => {
@@ -1011,7 +1092,10 @@ fn to_str_report<'a>(
                 filename,
                 doc,
                 title: "ENDLESS STRING".to_string(),
+                code: Some(codes::ENDLESS_STRING),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         EString::MultilineInsufficientIndent(pos) => {
@@ -1032,7 +1116,10 @@ fn to_str_report<'a>(
                 filename,
                 doc,
                 title: "INSUFFICIENT INDENT IN MULTI-LINE STRING".to_string(),
+                code: Some(codes::INSUFFICIENT_INDENT_IN_MULTI_LINE_STRING),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
     }
@@ -1074,7 +1161,10 @@ fn to_expr_in_parens_report<'a>(
                 filename,
                 doc,
                 title: "EMPTY PARENTHESES".to_string(),
+                code: Some(codes::EMPTY_PARENTHESES),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         EInParens::End(pos) | EInParens::IndentEnd(pos) => {
@@ -1098,7 +1188,10 @@ fn to_expr_in_parens_report<'a>(
                 filename,
                 doc,
                 title: "UNFINISHED PARENTHESES".to_string(),
+                code: Some(codes::UNFINISHED_PARENTHESES),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         EInParens::Open(pos) | EInParens::IndentOpen(pos) => {
@@ -1123,7 +1216,10 @@ fn to_expr_in_parens_report<'a>(
                 filename,
                 doc,
                 title: "UNFINISHED PARENTHESES".to_string(),
+                code: Some(codes::UNFINISHED_PARENTHESES),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
     }
@@ -1173,7 +1269,10 @@ fn to_list_report<'a>(
                         filename,
                         doc,
                         title: "UNFINISHED LIST".to_string(),
+                        code: Some(codes::UNFINISHED_LIST),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
                 _ => {
@@ -1206,7 +1305,10 @@ fn to_list_report<'a>(
                         filename,
                         doc,
                         title: "UNFINISHED LIST".to_string(),
+                        code: Some(codes::UNFINISHED_LIST),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
             }
@@ -1234,7 +1336,10 @@ fn to_list_report<'a>(
                 filename,
                 doc,
                 title: "UNFINISHED LIST".to_string(),
+                code: Some(codes::UNFINISHED_LIST),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
     }
@@ -1379,7 +1484,10 @@ fn to_unfinished_if_report<'a>(
         filename,
         doc,
         title: "UNFINISHED IF".to_string(),
+        code: Some(codes::UNFINISHED_IF),
         severity: Severity::RuntimeError,
+        fixes: Vec::new(),
+        related: Vec::new(),
     }
 }
 
@@ -1412,7 +1520,10 @@ fn to_when_report<'a>(
                         filename,
                         doc,
                         title: "IF GUARD NO CONDITION".to_string(),
+                        code: Some(codes::IF_GUARD_NO_CONDITION),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
                 _ => to_expr_report(
@@ -1444,7 +1555,10 @@ fn to_when_report<'a>(
                 filename,
                 doc,
                 title: "MISSING ARROW".to_string(),
+                code: Some(codes::MISSING_ARROW),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -1599,7 +1713,10 @@ fn to_unfinished_when_report<'a>(
                 filename,
                 doc,
                 title: "UNFINISHED WHEN".to_string(),
+                code: Some(codes::UNFINISHED_WHEN),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
     }
@@ -1639,7 +1756,10 @@ fn to_unexpected_arrow_report<'a>(
         filename,
         doc,
         title: "UNEXPECTED ARROW".to_string(),
+        code: Some(codes::UNEXPECTED_ARROW),
         severity: Severity::RuntimeError,
+        fixes: Vec::new(),
+        related: Vec::new(),
     }
 }
 
@@ -1715,7 +1835,10 @@ fn to_pattern_report<'a>(
                 filename,
                 doc,
                 title: "UNFINISHED PATTERN".to_string(),
+                code: Some(codes::UNFINISHED_PATTERN),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         EPattern::Record(record, pos) => to_precord_report(alloc, lines, filename, record, *pos),
@@ -1759,7 +1882,10 @@ fn to_precord_report<'a>(
                     filename,
                     doc,
                     title: "UNFINISHED RECORD PATTERN".to_string(),
+                    code: Some(codes::UNFINISHED_RECORD_PATTERN),
                     severity: Severity::RuntimeError,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
             _ => {
@@ -1776,7 +1902,10 @@ fn to_precord_report<'a>(
                     filename,
                     doc,
                     title: "UNFINISHED RECORD PATTERN".to_string(),
+                    code: Some(codes::UNFINISHED_RECORD_PATTERN),
                     severity: Severity::RuntimeError,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
         },
@@ -1801,7 +1930,10 @@ fn to_precord_report<'a>(
                         filename,
                         doc,
                         title: "UNFINISHED RECORD PATTERN".to_string(),
+                        code: Some(codes::UNFINISHED_RECORD_PATTERN),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
                 _ => {
@@ -1821,7 +1953,10 @@ fn to_precord_report<'a>(
                         filename,
                         doc,
                         title: "UNFINISHED RECORD PATTERN".to_string(),
+                        code: Some(codes::UNFINISHED_RECORD_PATTERN),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
             }
@@ -1846,7 +1981,10 @@ fn to_precord_report<'a>(
                     filename,
                     doc,
                     title: "UNFINISHED RECORD PATTERN".to_string(),
+                    code: Some(codes::UNFINISHED_RECORD_PATTERN),
                     severity: Severity::RuntimeError,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
             Next::Other(Some(',')) => todo!(),
@@ -1871,7 +2009,10 @@ fn to_precord_report<'a>(
                     filename,
                     doc,
                     title: "PROBLEM IN RECORD PATTERN".to_string(),
+                    code: Some(codes::PROBLEM_IN_RECORD_PATTERN),
                     severity: Severity::RuntimeError,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
         },
@@ -1913,7 +2054,10 @@ fn to_precord_report<'a>(
                 filename,
                 doc,
                 title: "UNFINISHED RECORD PATTERN".to_string(),
+                code: Some(codes::UNFINISHED_RECORD_PATTERN),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -1937,7 +2081,10 @@ fn to_precord_report<'a>(
                         filename,
                         doc,
                         title: "NEED MORE INDENTATION".to_string(),
+                        code: Some(codes::NEED_MORE_INDENTATION),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
                 None => {
@@ -1962,7 +2109,10 @@ fn to_precord_report<'a>(
                         filename,
                         doc,
                         title: "UNFINISHED RECORD PATTERN".to_string(),
+                        code: Some(codes::UNFINISHED_RECORD_PATTERN),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
             }
@@ -2002,7 +2152,10 @@ fn to_plist_report<'a>(
                 filename,
                 doc,
                 title: "UNFINISHED LIST PATTERN".to_string(),
+                code: Some(codes::UNFINISHED_LIST_PATTERN),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -2024,7 +2177,10 @@ fn to_plist_report<'a>(
                 filename,
                 doc,
                 title: "UNFINISHED LIST PATTERN".to_string(),
+                code: Some(codes::UNFINISHED_LIST_PATTERN),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -2046,7 +2202,10 @@ fn to_plist_report<'a>(
                 filename,
                 doc,
                 title: "INCORRECT REST PATTERN".to_string(),
+                code: Some(codes::INCORRECT_REST_PATTERN),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -2067,7 +2226,10 @@ fn to_plist_report<'a>(
                 filename,
                 doc,
                 title: "UNFINISHED LIST PATTERN".to_string(),
+                code: Some(codes::UNFINISHED_LIST_PATTERN),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -2094,7 +2256,10 @@ fn to_plist_report<'a>(
                         filename,
                         doc,
                         title: "NEED MORE INDENTATION".to_string(),
+                        code: Some(codes::NEED_MORE_INDENTATION),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
                 None => {
@@ -2119,7 +2284,10 @@ fn to_plist_report<'a>(
                         filename,
                         doc,
                         title: "UNFINISHED LIST PATTERN".to_string(),
+                        code: Some(codes::UNFINISHED_LIST_PATTERN),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
             }
@@ -2162,7 +2330,10 @@ fn to_pattern_in_parens_report<'a>(
                 filename,
                 doc,
                 title: "UNFINISHED PARENTHESES".to_string(),
+                code: Some(codes::UNFINISHED_PARENTHESES),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -2183,7 +2354,10 @@ fn to_pattern_in_parens_report<'a>(
                 filename,
                 doc,
                 title: "EMPTY PARENTHESES".to_string(),
+                code: Some(codes::EMPTY_PARENTHESES),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -2207,7 +2381,10 @@ fn to_pattern_in_parens_report<'a>(
                 filename,
                 doc,
                 title: "UNFINISHED PARENTHESES".to_string(),
+                code: Some(codes::UNFINISHED_PARENTHESES),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -2230,7 +2407,10 @@ fn to_pattern_in_parens_report<'a>(
                 filename,
                 doc,
                 title: "UNFINISHED PARENTHESES".to_string(),
+                code: Some(codes::UNFINISHED_PARENTHESES),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -2254,7 +2434,10 @@ fn to_pattern_in_parens_report<'a>(
                         filename,
                         doc,
                         title: "NEED MORE INDENTATION".to_string(),
+                        code: Some(codes::NEED_MORE_INDENTATION),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
                 None => {
@@ -2279,7 +2462,10 @@ fn to_pattern_in_parens_report<'a>(
                         filename,
                         doc,
                         title: "UNFINISHED PARENTHESES".to_string(),
+                        code: Some(codes::UNFINISHED_PARENTHESES),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
             }
@@ -2307,7 +2493,10 @@ fn to_malformed_number_literal_report<'a>(
         filename,
         doc,
         title: "INVALID NUMBER LITERAL".to_string(),
+        code: Some(codes::INVALID_NUMBER_LITERAL),
         severity: Severity::RuntimeError,
+        fixes: Vec::new(),
+        related: Vec::new(),
     }
 }
 
@@ -2347,7 +2536,10 @@ fn to_type_report<'a>(
                         filename,
                         doc,
                         title: "DOUBLE COMMA".to_string(),
+                        code: Some(codes::DOUBLE_COMMA),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
                 _ => todo!(),
@@ -2374,7 +2566,10 @@ fn to_type_report<'a>(
                 filename,
                 doc,
                 title: "UNFINISHED TYPE".to_string(),
+                code: Some(codes::UNFINISHED_TYPE),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -2392,7 +2587,10 @@ fn to_type_report<'a>(
                 filename,
                 doc,
                 title: "UNFINISHED TYPE".to_string(),
+                code: Some(codes::UNFINISHED_TYPE),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -2410,7 +2608,10 @@ fn to_type_report<'a>(
                 filename,
                 doc,
                 title: "UNFINISHED TYPE".to_string(),
+                code: Some(codes::UNFINISHED_TYPE),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -2428,7 +2629,10 @@ fn to_type_report<'a>(
                 filename,
                 doc,
                 title: "UNFINISHED INLINE ALIAS".to_string(),
+                code: Some(codes::UNFINISHED_INLINE_ALIAS),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -2445,7 +2649,10 @@ fn to_type_report<'a>(
                 filename,
                 doc,
                 title: "BAD TYPE VARIABLE".to_string(),
+                code: Some(codes::BAD_TYPE_VARIABLE),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -2482,7 +2689,10 @@ fn to_trecord_report<'a>(
                     filename,
                     doc,
                     title: "UNFINISHED RECORD TYPE".to_string(),
+                    code: Some(codes::UNFINISHED_RECORD_TYPE),
                     severity: Severity::RuntimeError,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
             _ => {
@@ -2503,7 +2713,10 @@ fn to_trecord_report<'a>(
                     filename,
                     doc,
                     title: "UNFINISHED RECORD TYPE".to_string(),
+                    code: Some(codes::UNFINISHED_RECORD_TYPE),
                     severity: Severity::RuntimeError,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
         },
@@ -2528,7 +2741,10 @@ fn to_trecord_report<'a>(
                         filename,
                         doc,
                         title: "UNFINISHED RECORD TYPE".to_string(),
+                        code: Some(codes::UNFINISHED_RECORD_TYPE),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
                 _ => {
@@ -2548,7 +2764,10 @@ fn to_trecord_report<'a>(
                         filename,
                         doc,
                         title: "UNFINISHED RECORD TYPE".to_string(),
+                        code: Some(codes::UNFINISHED_RECORD_TYPE),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
             }
@@ -2573,7 +2792,10 @@ fn to_trecord_report<'a>(
                     filename,
                     doc,
                     title: "UNFINISHED RECORD TYPE".to_string(),
+                    code: Some(codes::UNFINISHED_RECORD_TYPE),
                     severity: Severity::RuntimeError,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
             Next::Other(Some(',')) => todo!(),
@@ -2598,7 +2820,10 @@ fn to_trecord_report<'a>(
                     filename,
                     doc,
                     title: "PROBLEM IN RECORD TYPE".to_string(),
+                    code: Some(codes::PROBLEM_IN_RECORD_TYPE),
                     severity: Severity::RuntimeError,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
         },
@@ -2631,7 +2856,10 @@ fn to_trecord_report<'a>(
                 filename,
                 doc,
                 title: "UNFINISHED RECORD TYPE".to_string(),
+                code: Some(codes::UNFINISHED_RECORD_TYPE),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -2655,7 +2883,10 @@ fn to_trecord_report<'a>(
                         filename,
                         doc,
                         title: "NEED MORE INDENTATION".to_string(),
+                        code: Some(codes::NEED_MORE_INDENTATION),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
                 None => {
@@ -2680,7 +2911,10 @@ fn to_trecord_report<'a>(
                         filename,
                         doc,
                         title: "UNFINISHED RECORD TYPE".to_string(),
+                        code: Some(codes::UNFINISHED_RECORD_TYPE),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
             }
@@ -2727,7 +2961,10 @@ fn to_ttag_union_report<'a>(
                     filename,
                     doc,
                     title: "UNFINISHED TAG UNION TYPE".to_string(),
+                    code: Some(codes::UNFINISHED_TAG_UNION_TYPE),
                     severity: Severity::RuntimeError,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
             Next::Other(Some(c)) if c.is_alphabetic() => {
@@ -2749,7 +2986,10 @@ fn to_ttag_union_report<'a>(
                     filename,
                     doc,
                     title: "WEIRD TAG NAME".to_string(),
+                    code: Some(codes::WEIRD_TAG_NAME),
                     severity: Severity::RuntimeError,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
             _ => {
@@ -2770,7 +3010,10 @@ fn to_ttag_union_report<'a>(
                     filename,
                     doc,
                     title: "UNFINISHED TAG UNION TYPE".to_string(),
+                    code: Some(codes::UNFINISHED_TAG_UNION_TYPE),
                     severity: Severity::RuntimeError,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
         },
@@ -2796,7 +3039,10 @@ fn to_ttag_union_report<'a>(
                         filename,
                         doc,
                         title: "WEIRD TAG NAME".to_string(),
+                        code: Some(codes::WEIRD_TAG_NAME),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
                 _ => {
@@ -2816,7 +3062,10 @@ fn to_ttag_union_report<'a>(
                         filename,
                         doc,
                         title: "UNFINISHED TAG UNION TYPE".to_string(),
+                        code: Some(codes::UNFINISHED_TAG_UNION_TYPE),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
             }
@@ -2843,7 +3092,10 @@ fn to_ttag_union_report<'a>(
                 filename,
                 doc,
                 title: "UNFINISHED TAG UNION TYPE".to_string(),
+                code: Some(codes::UNFINISHED_TAG_UNION_TYPE),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -2870,7 +3122,10 @@ fn to_ttag_union_report<'a>(
                         filename,
                         doc,
                         title: "NEED MORE INDENTATION".to_string(),
+                        code: Some(codes::NEED_MORE_INDENTATION),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
                 None => {
@@ -2895,7 +3150,10 @@ fn to_ttag_union_report<'a>(
                         filename,
                         doc,
                         title: "UNFINISHED TAG UNION TYPE".to_string(),
+                        code: Some(codes::UNFINISHED_TAG_UNION_TYPE),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
             }
@@ -2936,7 +3194,10 @@ fn to_tinparens_report<'a>(
                         filename,
                         doc,
                         title: "UNFINISHED PARENTHESES".to_string(),
+                        code: Some(codes::UNFINISHED_PARENTHESES),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
                 Next::Other(Some(c)) if c.is_alphabetic() => {
@@ -2958,7 +3219,10 @@ fn to_tinparens_report<'a>(
                         filename,
                         doc,
                         title: "WEIRD TAG NAME".to_string(),
+                        code: Some(codes::WEIRD_TAG_NAME),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
                 _ => {
@@ -2981,7 +3245,10 @@ fn to_tinparens_report<'a>(
                         filename,
                         doc,
                         title: "UNFINISHED PARENTHESES".to_string(),
+                        code: Some(codes::UNFINISHED_PARENTHESES),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
             }
@@ -3004,7 +3271,10 @@ fn to_tinparens_report<'a>(
                 filename,
                 doc,
                 title: "EMPTY PARENTHESES".to_string(),
+                code: Some(codes::EMPTY_PARENTHESES),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3030,7 +3300,10 @@ fn to_tinparens_report<'a>(
                         filename,
                         doc,
                         title: "WEIRD TAG NAME".to_string(),
+                        code: Some(codes::WEIRD_TAG_NAME),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
                 _ => {
@@ -3050,7 +3323,10 @@ fn to_tinparens_report<'a>(
                         filename,
                         doc,
                         title: "UNFINISHED PARENTHESES".to_string(),
+                        code: Some(codes::UNFINISHED_PARENTHESES),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
             }
@@ -3078,7 +3354,10 @@ fn to_tinparens_report<'a>(
                 filename,
                 doc,
                 title: "UNFINISHED PARENTHESES".to_string(),
+                code: Some(codes::UNFINISHED_PARENTHESES),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3102,7 +3381,10 @@ fn to_tinparens_report<'a>(
                         filename,
                         doc,
                         title: "NEED MORE INDENTATION".to_string(),
+                        code: Some(codes::NEED_MORE_INDENTATION),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
                 None => {
@@ -3127,7 +3409,10 @@ fn to_tinparens_report<'a>(
                         filename,
                         doc,
                         title: "UNFINISHED PARENTHESES".to_string(),
+                        code: Some(codes::UNFINISHED_PARENTHESES),
                         severity: Severity::RuntimeError,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
             }
@@ -3160,7 +3445,10 @@ fn to_tapply_report<'a>(
                 filename,
                 doc,
                 title: "DOUBLE DOT".to_string(),
+                code: Some(codes::DOUBLE_DOT),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         ETypeApply::TrailingDot(pos) => {
@@ -3182,7 +3470,10 @@ fn to_tapply_report<'a>(
                 filename,
                 doc,
                 title: "TRAILING DOT".to_string(),
+                code: Some(codes::TRAILING_DOT),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         ETypeApply::StartIsNumber(pos) => {
@@ -3204,7 +3495,10 @@ fn to_tapply_report<'a>(
                 filename,
                 doc,
                 title: "WEIRD QUALIFIED NAME".to_string(),
+                code: Some(codes::WEIRD_QUALIFIED_NAME),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         ETypeApply::StartNotUppercase(pos) => {
@@ -3226,7 +3520,10 @@ fn to_tapply_report<'a>(
                 filename,
                 doc,
                 title: "WEIRD QUALIFIED NAME".to_string(),
+                code: Some(codes::WEIRD_QUALIFIED_NAME),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3244,7 +3541,10 @@ fn to_tapply_report<'a>(
                 filename,
                 doc,
                 title: "END OF FILE".to_string(),
+                code: Some(codes::END_OF_FILE),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3284,7 +3584,10 @@ fn to_talias_report<'a>(
                 filename,
                 doc,
                 title: "NOT AN INLINE ALIAS".to_string(),
+                code: Some(codes::NOT_AN_INLINE_ALIAS),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         ETypeInlineAlias::Qualified(pos) => {
@@ -3300,7 +3603,10 @@ fn to_talias_report<'a>(
                 filename,
                 doc,
                 title: "QUALIFIED ALIAS NAME".to_string(),
+                code: Some(codes::QUALIFIED_ALIAS_NAME),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         ETypeInlineAlias::ArgumentNotLowercase(pos) => {
@@ -3316,7 +3622,10 @@ fn to_talias_report<'a>(
                 filename,
                 doc,
                 title: "TYPE ARGUMENT NOT LOWERCASE".to_string(),
+                code: Some(codes::TYPE_ARGUMENT_NOT_LOWERCASE),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
     }
@@ -3362,7 +3671,10 @@ fn to_header_report<'a>(
                 filename,
                 doc,
                 title: "INCOMPLETE HEADER".to_string(),
+                code: Some(codes::INCOMPLETE_HEADER),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3390,7 +3702,10 @@ fn to_header_report<'a>(
                 filename,
                 doc,
                 title: "MISSING HEADER".to_string(),
+                code: Some(codes::MISSING_HEADER),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3414,7 +3729,10 @@ fn to_header_report<'a>(
                 filename,
                 doc,
                 title: "WEIRD MODULE NAME".to_string(),
+                code: Some(codes::WEIRD_MODULE_NAME),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3441,7 +3759,10 @@ fn to_header_report<'a>(
                 filename,
                 doc,
                 title: "WEIRD MODULE NAME".to_string(),
+                code: Some(codes::WEIRD_MODULE_NAME),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3465,7 +3786,10 @@ fn to_header_report<'a>(
                 filename,
                 doc,
                 title: "WEIRD APP NAME".to_string(),
+                code: Some(codes::WEIRD_APP_NAME),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3487,7 +3811,10 @@ fn to_header_report<'a>(
                 filename,
                 doc,
                 title: "INVALID PACKAGE NAME".to_string(),
+                code: Some(codes::INVALID_PACKAGE_NAME),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3510,7 +3837,10 @@ fn to_header_report<'a>(
                 filename,
                 doc,
                 title: "INVALID PLATFORM NAME".to_string(),
+                code: Some(codes::INVALID_PLATFORM_NAME),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3533,7 +3863,10 @@ fn to_header_report<'a>(
                 filename,
                 doc,
                 title: "WEIRD GENERATED TYPE NAME".to_string(),
+                code: Some(codes::WEIRD_GENERATED_TYPE_NAME),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         EHeader::GeneratesWith(generates_with, pos) => {
@@ -3573,7 +3906,10 @@ fn to_generates_with_report<'a>(
                 filename,
                 doc,
                 title: "WEIRD GENERATES".to_string(),
+                code: Some(codes::WEIRD_GENERATES),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3598,7 +3934,10 @@ fn to_generates_with_report<'a>(
                 filename,
                 doc,
                 title: "WEIRD GENERATES".to_string(),
+                code: Some(codes::WEIRD_GENERATES),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3639,7 +3978,10 @@ fn to_provides_report<'a>(
                 filename,
                 doc,
                 title: "WEIRD PROVIDES".to_string(),
+                code: Some(codes::WEIRD_PROVIDES),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3664,7 +4006,10 @@ fn to_provides_report<'a>(
                 filename,
                 doc,
                 title: "WEIRD PROVIDES".to_string(),
+                code: Some(codes::WEIRD_PROVIDES),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3704,7 +4049,10 @@ fn to_exposes_report<'a>(
                 filename,
                 doc,
                 title: "WEIRD EXPOSES".to_string(),
+                code: Some(codes::WEIRD_EXPOSES),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3729,7 +4077,10 @@ fn to_exposes_report<'a>(
                 filename,
                 doc,
                 title: "WEIRD EXPOSES".to_string(),
+                code: Some(codes::WEIRD_EXPOSES),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3768,7 +4119,10 @@ fn to_imports_report<'a>(
                 filename,
                 doc,
                 title: "WEIRD IMPORTS".to_string(),
+                code: Some(codes::WEIRD_IMPORTS),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3793,7 +4147,10 @@ fn to_imports_report<'a>(
                 filename,
                 doc,
                 title: "WEIRD IMPORTS".to_string(),
+                code: Some(codes::WEIRD_IMPORTS),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3819,7 +4176,10 @@ fn to_imports_report<'a>(
                 filename,
                 doc,
                 title: "WEIRD MODULE NAME".to_string(),
+                code: Some(codes::WEIRD_MODULE_NAME),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3838,7 +4198,10 @@ fn to_imports_report<'a>(
                 filename,
                 doc,
                 title: "WEIRD IMPORTS".to_string(),
+                code: Some(codes::WEIRD_IMPORTS),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3877,7 +4240,10 @@ fn to_requires_report<'a>(
                 filename,
                 doc,
                 title: "MISSING REQUIRES".to_string(),
+                code: Some(codes::MISSING_REQUIRES),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3904,7 +4270,10 @@ fn to_requires_report<'a>(
                 filename,
                 doc,
                 title: "MISSING REQUIRES".to_string(),
+                code: Some(codes::MISSING_REQUIRES),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3933,7 +4302,10 @@ fn to_requires_report<'a>(
                 filename,
                 doc,
                 title: "BAD REQUIRES RIGIDS".to_string(),
+                code: Some(codes::BAD_REQUIRES_RIGIDS),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3962,7 +4334,10 @@ fn to_requires_report<'a>(
                 filename,
                 doc,
                 title: "BAD REQUIRES".to_string(),
+                code: Some(codes::BAD_REQUIRES),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -3999,7 +4374,10 @@ fn to_packages_report<'a>(
                 filename,
                 doc,
                 title: "MISSING PACKAGES".to_string(),
+                code: Some(codes::MISSING_PACKAGES),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -4032,7 +4410,10 @@ fn to_space_report<'a>(
                 filename,
                 doc,
                 title: "TAB CHARACTER".to_string(),
+                code: Some(codes::TAB_CHARACTER),
                 severity: Severity::RuntimeError,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -4113,7 +4494,10 @@ fn to_unfinished_ability_report<'a>(
         filename,
         doc,
         title: "UNFINISHED ABILITY".to_string(),
+        code: Some(codes::UNFINISHED_ABILITY),
         severity: Severity::RuntimeError,
+        fixes: Vec::new(),
+        related: Vec::new(),
     }
 }
 
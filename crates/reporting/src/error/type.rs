@@ -1,7 +1,8 @@
 #![allow(clippy::too_many_arguments)]
 
+use crate::codes;
 use crate::error::canonicalize::{to_circular_def_doc, CIRCULAR_DEF};
-use crate::report::{Annotation, Report, RocDocAllocator, RocDocBuilder};
+use crate::report::{Annotation, Fix, Report, RocDocAllocator, RocDocBuilder};
 use roc_can::expected::{Expected, PExpected};
 use roc_collections::all::{HumanIndex, MutSet, SendMap};
 use roc_collections::VecMap;
@@ -11,12 +12,13 @@ use roc_module::called_via::{BinOp, CalledVia};
 use roc_module::ident::{IdentStr, Lowercase, TagName};
 use roc_module::symbol::Symbol;
 use roc_problem::Severity;
-use roc_region::all::{LineInfo, Region};
+use roc_region::all::{LineColumn, LineInfo, Region};
 use roc_solve_problem::{
     NotDerivableContext, NotDerivableDecode, NotDerivableEq, TypeError, UnderivableReason,
     Unfulfilled,
 };
 use roc_std::RocDec;
+use roc_types::num::FloatWidth;
 use roc_types::pretty_print::{Parens, WILDCARD};
 use roc_types::types::{
     AbilitySet, AliasKind, Category, ErrorType, PatternCategory, Polarity, Reason, RecordField,
@@ -43,20 +45,41 @@ pub fn type_problem<'b>(
 
     let severity = problem.severity();
 
-    let report =
-        move |title: String, doc: RocDocBuilder<'b>, filename: PathBuf| -> Option<Report<'b>> {
-            Some(Report {
-                title,
-                filename,
-                doc,
-                severity,
-            })
-        };
+    let report = move |title: String,
+                       code: &'static str,
+                       doc: RocDocBuilder<'b>,
+                       filename: PathBuf|
+          -> Option<Report<'b>> {
+        Some(Report {
+            title,
+            code: Some(code),
+            filename,
+            doc,
+            severity,
+            fixes: Vec::new(),
+            related: Vec::new(),
+        })
+    };
 
     match problem {
-        BadExpr(region, category, found, expected) => Some(to_expr_report(
-            alloc, lines, filename, severity, region, category, found, expected,
-        )),
+        BadExpr(region, category, found, expected) => {
+            let fixes = missing_record_fields_fixes(
+                alloc,
+                lines,
+                region,
+                &category,
+                &found,
+                expected.get_type_ref(),
+            )
+            .unwrap_or_default();
+
+            let mut report = to_expr_report(
+                alloc, lines, filename, severity, region, category, found, expected,
+            );
+            report.fixes = fixes;
+
+            Some(report)
+        }
         BadPattern(region, category, found, expected) => Some(to_pattern_report(
             alloc, lines, filename, severity, region, category, found, expected,
         )),
@@ -79,14 +102,19 @@ pub fn type_problem<'b>(
                     .append(alloc.symbol_unqualified(symbol))])
                 .append(alloc.reflow("."));
 
-            report(title, doc, filename)
+            report(title, codes::UNRECOGNIZED_NAME, doc, filename)
         }
         UnfulfilledAbility(incomplete) => {
             let title = "INCOMPLETE ABILITY IMPLEMENTATION".to_string();
 
             let doc = report_unfulfilled_ability(alloc, lines, incomplete);
 
-            report(title, doc, filename)
+            report(
+                title,
+                codes::INCOMPLETE_ABILITY_IMPLEMENTATION,
+                doc,
+                filename,
+            )
         }
         BadExprMissingAbility(region, _category, _found, incomplete) => {
             if region == roc_can::DERIVED_REGION {
@@ -108,9 +136,12 @@ pub fn type_problem<'b>(
 
             let report = Report {
                 title: "TYPE MISMATCH".to_string(),
+                code: Some(codes::TYPE_MISMATCH),
                 filename,
                 doc: alloc.stack(stack),
                 severity,
+                fixes: Vec::new(),
+                related: Vec::new(),
             };
             Some(report)
         }
@@ -130,9 +161,12 @@ pub fn type_problem<'b>(
 
             let report = Report {
                 title: "TYPE MISMATCH".to_string(),
+                code: Some(codes::TYPE_MISMATCH),
                 filename,
                 doc: alloc.stack(stack),
                 severity,
+                fixes: Vec::new(),
+                related: Vec::new(),
             };
             Some(report)
         }
@@ -143,9 +177,12 @@ pub fn type_problem<'b>(
 
             Some(Report {
                 title,
+                code: Some(codes::CIRCULAR_DEFINITION),
                 filename,
                 doc,
                 severity,
+                fixes: Vec::new(),
+                related: Vec::new(),
             })
         }
         StructuralSpecialization {
@@ -173,9 +210,12 @@ pub fn type_problem<'b>(
 
             Some(Report {
                 title: "ILLEGAL SPECIALIZATION".to_string(),
+                code: Some(codes::ILLEGAL_SPECIALIZATION),
                 filename,
                 doc: alloc.stack(stack),
                 severity,
+                fixes: Vec::new(),
+                related: Vec::new(),
             })
         }
         WrongSpecialization {
@@ -202,9 +242,81 @@ pub fn type_problem<'b>(
 
             Some(Report {
                 title: "WRONG SPECIALIZATION TYPE".to_string(),
+                code: Some(codes::WRONG_SPECIALIZATION_TYPE),
+                filename,
+                doc: alloc.stack(stack),
+                severity,
+                fixes: Vec::new(),
+                related: Vec::new(),
+            })
+        }
+        TypedHole { region, typ, fits } => {
+            let fits_doc = if fits.is_empty() {
+                alloc.reflow("None of the values in scope have this type.")
+            } else {
+                alloc.stack([
+                    alloc.reflow("These values in scope have this type:"),
+                    alloc
+                        .vcat(fits.into_iter().map(|fit| alloc.symbol_unqualified(fit)))
+                        .indent(4),
+                ])
+            };
+
+            let stack = [
+                alloc.reflow("This hole needs to be filled in:"),
+                alloc.region(lines.convert_region(region)),
+                alloc.reflow("It needs a value of type:"),
+                alloc.type_block(error_type_to_doc(alloc, typ)),
+                fits_doc,
+            ];
+
+            Some(Report {
+                title: "TYPED HOLE".to_string(),
+                code: Some(codes::TYPED_HOLE),
+                filename,
+                doc: alloc.stack(stack),
+                severity,
+                fixes: Vec::new(),
+                related: Vec::new(),
+            })
+        }
+        UnrepresentableDefault {
+            region,
+            literal,
+            typ,
+        } => {
+            let stack = [
+                alloc.concat([
+                    alloc.reflow("Nothing determines the type of this number, so it defaults to "),
+                    alloc.type_str(typ.type_str()),
+                    alloc.reflow(":"),
+                ]),
+                alloc.region(lines.convert_region(region)),
+                alloc.concat([
+                    alloc.reflow("But "),
+                    alloc.type_str(typ.type_str()),
+                    alloc.reflow(" cannot represent "),
+                    alloc.string(literal.to_string()),
+                    alloc.reflow("."),
+                ]),
+                alloc.concat([
+                    alloc.tip(),
+                    alloc.reflow(
+                        "Add a type annotation that picks a type which can represent it, like ",
+                    ),
+                    alloc.type_str(if typ == FloatWidth::F64 { "Dec" } else { "F64" }),
+                    alloc.reflow(", or use a different default fractional type."),
+                ]),
+            ];
+
+            Some(Report {
+                title: "NUMBER DOES NOT FIT DEFAULT".to_string(),
+                code: Some(codes::NUMBER_DOES_NOT_FIT_DEFAULT),
                 filename,
                 doc: alloc.stack(stack),
                 severity,
+                fixes: Vec::new(),
+                related: Vec::new(),
             })
         }
     }
@@ -457,6 +569,83 @@ pub fn cyclic_alias<'b>(
     (doc, "CYCLIC ALIAS".to_string())
 }
 
+/// Add the fields that a record literal is missing, with a `crash` as the value of each
+fn missing_record_fields_fixes(
+    alloc: &RocDocAllocator,
+    lines: &LineInfo,
+    region: Region,
+    category: &Category,
+    found: &ErrorType,
+    expected: &ErrorType,
+) -> Option<Vec<Fix>> {
+    if !matches!(category, Category::Record) {
+        return None;
+    }
+
+    let (found_fields, expected_fields) = match (
+        found.clone().unwrap_structural_alias(),
+        expected.clone().unwrap_structural_alias(),
+    ) {
+        (ErrorType::Record(found_fields, _), ErrorType::Record(expected_fields, _)) => {
+            (found_fields, expected_fields)
+        }
+        _ => return None,
+    };
+
+    let mut missing: Vec<String> = expected_fields
+        .iter()
+        .filter(|(name, field)| {
+            !found_fields.contains_key(*name)
+                && !matches!(
+                    field,
+                    RecordField::Optional(_) | RecordField::RigidOptional(_)
+                )
+        })
+        .map(|(name, _)| format!("{}: crash \"TODO\"", name))
+        .collect();
+
+    if missing.is_empty() {
+        return None;
+    }
+
+    missing.sort();
+
+    // The new fields go after the last field in the record, or after the `{` of an empty one.
+    let end = lines.convert_region(region).end;
+    let mut line = end.line;
+    let mut before_brace = alloc
+        .src_lines
+        .get(line as usize)?
+        .get(..end.column as usize)?
+        .strip_suffix('}')?
+        .trim_end();
+
+    while before_brace.is_empty() && line > 0 {
+        line -= 1;
+        before_brace = alloc.src_lines[line as usize].trim_end();
+    }
+
+    let missing = missing.join(", ");
+    let text = if before_brace.ends_with('{') {
+        format!(" {}", missing)
+    } else if before_brace.ends_with(',') {
+        format!(" {},", missing)
+    } else {
+        format!(", {}", missing)
+    };
+
+    let position = lines.convert_line_column(LineColumn {
+        line,
+        column: before_brace.len() as u32,
+    });
+
+    Some(vec![Fix::insert(
+        "Add the missing fields".to_string(),
+        position,
+        text,
+    )])
+}
+
 fn report_mismatch<'b>(
     alloc: &'b RocDocAllocator<'b>,
     lines: &LineInfo,
@@ -496,9 +685,12 @@ fn report_mismatch<'b>(
 
     Report {
         title: "TYPE MISMATCH".to_string(),
+        code: Some(codes::TYPE_MISMATCH),
         filename,
         doc: alloc.stack(lines),
         severity,
+        fixes: Vec::new(),
+        related: Vec::new(),
     }
 }
 
@@ -539,9 +731,12 @@ fn report_bad_type<'b>(
 
     Report {
         title: "TYPE MISMATCH".to_string(),
+        code: Some(codes::TYPE_MISMATCH),
         filename,
         doc: alloc.stack(lines),
         severity,
+        fixes: Vec::new(),
+        related: Vec::new(),
     }
 }
 
@@ -623,12 +818,15 @@ fn to_expr_report<'b>(
             Report {
                 filename,
                 title: "TYPE MISMATCH".to_string(),
+                code: Some(codes::TYPE_MISMATCH),
                 doc: alloc.stack([
                     alloc.text("This expression is used in an unexpected way:"),
                     alloc.region(lines.convert_region(expr_region)),
                     comparison,
                 ]),
                 severity,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         Expected::FromAnnotation(name, _arity, annotation_source, expected_type) => {
@@ -737,6 +935,7 @@ fn to_expr_report<'b>(
 
             Report {
                 title: "TYPE MISMATCH".to_string(),
+                code: Some(codes::TYPE_MISMATCH),
                 filename,
                 doc: alloc.stack([
                     alloc.text("Something is off with the ").append(thing),
@@ -752,6 +951,8 @@ fn to_expr_report<'b>(
                     comparison,
                 ]),
                 severity,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         Expected::ForReason(reason, expected_type, region) => match reason {
@@ -1105,8 +1306,11 @@ fn to_expr_report<'b>(
                     Report {
                         filename,
                         title: "TOO MANY ARGS".to_string(),
+                        code: Some(codes::TOO_MANY_ARGS),
                         doc: alloc.stack(lines),
                         severity,
+                        fixes: Vec::new(),
+                        related: Vec::new(),
                     }
                 }
                 n => {
@@ -1140,8 +1344,11 @@ fn to_expr_report<'b>(
                         Report {
                             filename,
                             title: "TOO MANY ARGS".to_string(),
+                            code: Some(codes::TOO_MANY_ARGS),
                             doc: alloc.stack(lines),
                             severity,
+                            fixes: Vec::new(),
+                            related: Vec::new(),
                         }
                     } else {
                         let lines = vec![
@@ -1167,8 +1374,11 @@ fn to_expr_report<'b>(
                         Report {
                             filename,
                             title: "TOO FEW ARGS".to_string(),
+                            code: Some(codes::TOO_FEW_ARGS),
                             doc: alloc.stack(lines),
                             severity,
+                            fixes: Vec::new(),
+                            related: Vec::new(),
                         }
                     }
                 }
@@ -1355,9 +1565,12 @@ fn to_expr_report<'b>(
 
                 Report {
                     title: "TYPE MISMATCH".to_string(),
+                    code: Some(codes::TYPE_MISMATCH),
                     filename,
                     doc: alloc.stack(lines),
                     severity,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
 
@@ -1393,8 +1606,11 @@ fn to_expr_report<'b>(
                 Report {
                     filename,
                     title: "TYPE MISMATCH".to_string(),
+                    code: Some(codes::TYPE_MISMATCH),
                     doc,
                     severity,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
 
@@ -1429,8 +1645,11 @@ fn to_expr_report<'b>(
                 Report {
                     filename,
                     title: "TYPE MISMATCH".to_string(),
+                    code: Some(codes::TYPE_MISMATCH),
                     doc: alloc.stack(lines),
                     severity,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
 
@@ -1805,8 +2024,11 @@ fn to_pattern_report<'b>(
             Report {
                 filename,
                 title: "TYPE MISMATCH".to_string(),
+                code: Some(codes::TYPE_MISMATCH),
                 doc,
                 severity,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
 
@@ -1848,8 +2070,11 @@ fn to_pattern_report<'b>(
                 Report {
                     filename,
                     title: "TYPE MISMATCH".to_string(),
+                    code: Some(codes::TYPE_MISMATCH),
                     doc,
                     severity,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
             PReason::WhenMatch { index, sub_pattern } => {
@@ -1928,8 +2153,11 @@ fn to_pattern_report<'b>(
                 Report {
                     filename,
                     title: "TYPE MISMATCH".to_string(),
+                    code: Some(codes::TYPE_MISMATCH),
                     doc,
                     severity,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
             PReason::ListElem => {
@@ -1955,8 +2183,11 @@ fn to_pattern_report<'b>(
                 Report {
                     filename,
                     title: "TYPE MISMATCH".to_string(),
+                    code: Some(codes::TYPE_MISMATCH),
                     doc,
                     severity,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
             PReason::TagArg { .. } | PReason::PatternGuard => {
@@ -2038,6 +2269,7 @@ fn to_circular_report<'b>(
 ) -> Report<'b> {
     Report {
         title: "CIRCULAR TYPE".to_string(),
+        code: Some(codes::CIRCULAR_TYPE),
         filename,
         doc: {
             alloc.stack([
@@ -2057,6 +2289,8 @@ fn to_circular_report<'b>(
             ])
         },
         severity,
+        fixes: Vec::new(),
+        related: Vec::new(),
     }
 }
 
@@ -2131,12 +2365,7 @@ pub mod suggest {
     where
         T: ToStr,
     {
-        options.sort_by(|a, b| {
-            let l = distance::damerau_levenshtein(typo, a.to_str());
-            let r = distance::damerau_levenshtein(typo, b.to_str());
-
-            l.cmp(&r)
-        });
+        roc_problem::suggest::sort_by_key(typo, &mut options, |option| option.to_str());
 
         options
     }
@@ -4187,8 +4416,11 @@ fn report_record_field_typo<'b>(
     Report {
         filename,
         title: "TYPE MISMATCH".to_string(),
+        code: Some(codes::TYPE_MISMATCH),
         doc,
         severity,
+        fixes: Vec::new(),
+        related: Vec::new(),
     }
 }
 
@@ -4224,8 +4456,11 @@ fn exhaustive_problem<'a>(
                 Report {
                     filename,
                     title: "UNSAFE PATTERN".to_string(),
+                    code: Some(codes::UNSAFE_PATTERN),
                     doc,
                     severity,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
             BadDestruct => {
@@ -4248,8 +4483,11 @@ fn exhaustive_problem<'a>(
                 Report {
                     filename,
                     title: "UNSAFE PATTERN".to_string(),
+                    code: Some(codes::UNSAFE_PATTERN),
                     doc,
                     severity,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
             BadCase => {
@@ -4272,8 +4510,11 @@ fn exhaustive_problem<'a>(
                 Report {
                     filename,
                     title: "UNSAFE PATTERN".to_string(),
+                    code: Some(codes::UNSAFE_PATTERN),
                     doc,
                     severity,
+                    fixes: Vec::new(),
+                    related: Vec::new(),
                 }
             }
         },
@@ -4301,8 +4542,11 @@ fn exhaustive_problem<'a>(
             Report {
                 filename,
                 title: "REDUNDANT PATTERN".to_string(),
+                code: Some(codes::REDUNDANT_PATTERN),
                 doc,
                 severity,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
         Unmatchable {
@@ -4329,8 +4573,11 @@ fn exhaustive_problem<'a>(
             Report {
                 filename,
                 title: "UNMATCHABLE PATTERN".to_string(),
+                code: Some(codes::UNMATCHABLE_PATTERN),
                 doc,
                 severity,
+                fixes: Vec::new(),
+                related: Vec::new(),
             }
         }
     }
@@ -0,0 +1,298 @@
+//! Render reports as a single self-contained HTML page, e.g. to keep around as a CI artifact.
+use std::fmt::{self, Write};
+use std::path::PathBuf;
+
+use roc_problem::Severity;
+use ven_pretty::{Render, RenderAnnotated};
+
+use crate::report::{Annotation, Report, RocDocAllocator};
+
+/// A report rendered to HTML, ready to be put on a page with [html_page]
+#[derive(Debug, Clone)]
+pub struct HtmlReport {
+    pub title: String,
+    pub code: Option<&'static str>,
+    pub filename: PathBuf,
+    pub severity: Severity,
+    /// The report's message, as HTML to be shown with `white-space: pre`
+    pub body: String,
+}
+
+impl<'b> Report<'b> {
+    /// Render to HTML, with a CSS class for every kind of annotation, links for URLs,
+    /// and source snippets that can be collapsed.
+    pub fn render_html(&self, alloc: &'b RocDocAllocator<'b>) -> HtmlReport {
+        let err_msg = "<buffer is not a utf-8 encoded string>";
+
+        let mut body = String::new();
+
+        self.doc_with_related(alloc)
+            .1
+            .render_raw(70, &mut HtmlWrite::new(&mut body))
+            .expect(err_msg);
+
+        HtmlReport {
+            title: self.title.clone(),
+            code: self.code,
+            filename: self.filename.clone(),
+            severity: self.severity,
+            body,
+        }
+    }
+}
+
+/// Render with HTML tags, escaping the text
+pub struct HtmlWrite<W> {
+    style_stack: Vec<Annotation>,
+    /// The text of the URL we're in, since it has to go in the link's `href` too
+    url: Option<String>,
+    upstream: W,
+}
+
+impl<W> HtmlWrite<W> {
+    pub fn new(upstream: W) -> HtmlWrite<W> {
+        HtmlWrite {
+            style_stack: vec![],
+            url: None,
+            upstream,
+        }
+    }
+}
+
+impl<W> Render for HtmlWrite<W>
+where
+    W: fmt::Write,
+{
+    type Error = fmt::Error;
+
+    fn write_str(&mut self, s: &str) -> Result<usize, fmt::Error> {
+        self.write_str_all(s).map(|_| s.len())
+    }
+
+    fn write_str_all(&mut self, s: &str) -> fmt::Result {
+        match self.url.as_mut() {
+            Some(url) => {
+                url.push_str(s);
+
+                Ok(())
+            }
+            None => write_escaped(&mut self.upstream, s),
+        }
+    }
+}
+
+impl<W> RenderAnnotated<Annotation> for HtmlWrite<W>
+where
+    W: fmt::Write,
+{
+    fn push_annotation(&mut self, annotation: &Annotation) -> Result<(), Self::Error> {
+        match annotation {
+            Annotation::Url => {
+                self.url = Some(String::new());
+            }
+            Annotation::CodeBlock => {
+                self.upstream
+                    .write_str("<details class=\"snippet\" open><summary></summary>")?;
+            }
+            _ => {
+                write!(self.upstream, "<span class=\"{}\">", css_class(*annotation))?;
+            }
+        }
+
+        self.style_stack.push(*annotation);
+
+        Ok(())
+    }
+
+    fn pop_annotation(&mut self) -> Result<(), Self::Error> {
+        match self.style_stack.pop() {
+            None => Ok(()),
+            Some(Annotation::Url) => {
+                let url = self.url.take().unwrap_or_default();
+
+                self.upstream.write_str("<a href=\"")?;
+                write_escaped(&mut self.upstream, &url)?;
+                self.upstream.write_str("\">")?;
+                write_escaped(&mut self.upstream, &url)?;
+                self.upstream.write_str("</a>")
+            }
+            Some(Annotation::CodeBlock) => self.upstream.write_str("</details>"),
+            Some(_) => self.upstream.write_str("</span>"),
+        }
+    }
+}
+
+fn css_class(annotation: Annotation) -> &'static str {
+    use Annotation::*;
+
+    match annotation {
+        Emphasized => "emphasized",
+        Url => "url",
+        Keyword => "keyword",
+        Tag => "tag",
+        RecordField => "record-field",
+        TypeVariable => "type-variable",
+        Alias => "alias",
+        Opaque => "opaque",
+        Structure => "structure",
+        Symbol => "symbol",
+        BinOp => "binop",
+        Error => "error",
+        GutterBar => "gutter-bar",
+        LineNumber => "line-number",
+        PlainText => "plain-text",
+        CodeBlock => "code-block",
+        TypeBlock => "type-block",
+        InlineTypeBlock => "inline-type-block",
+        Module => "module",
+        Typo => "typo",
+        TypoSuggestion => "typo-suggestion",
+        Tip => "tip",
+        Header => "header",
+        ParserSuggestion => "parser-suggestion",
+    }
+}
+
+fn write_escaped<W: fmt::Write>(buf: &mut W, s: &str) -> fmt::Result {
+    for c in s.chars() {
+        match c {
+            '&' => buf.write_str("&amp;")?,
+            '<' => buf.write_str("&lt;")?,
+            '>' => buf.write_str("&gt;")?,
+            '"' => buf.write_str("&quot;")?,
+            '\'' => buf.write_str("&#39;")?,
+            c => buf.write_char(c)?,
+        }
+    }
+
+    Ok(())
+}
+
+fn escape(s: &str) -> String {
+    let mut buf = String::with_capacity(s.len());
+
+    write_escaped(&mut buf, s).unwrap();
+
+    buf
+}
+
+const STYLE: &str = r#"
+body { background: #1b1b1f; color: #e4e4e7; font-family: sans-serif; margin: 2em auto; max-width: 60em; }
+a { color: #8ab4f8; }
+nav li.severity-error a::before { content: "error: "; color: #f87171; }
+nav li.severity-warning a::before { content: "warning: "; color: #facc15; }
+section { border-left: 4px solid #52525b; margin: 2em 0; padding: 0 1em; }
+section.severity-error { border-color: #f87171; }
+section.severity-warning { border-color: #facc15; }
+h2 { font-size: 1em; letter-spacing: 0.05em; }
+h2 .path { color: #a1a1aa; font-weight: normal; margin-left: 1em; }
+.body { font-family: monospace; white-space: pre; overflow-x: auto; }
+details.snippet { background: #27272a; padding: 0.5em; }
+details.snippet > summary { cursor: pointer; color: #a1a1aa; }
+details.snippet > summary::after { content: "source"; }
+.keyword, .structure, .module, .binop { color: #4ade80; }
+.symbol { color: #60a5fa; }
+.type-variable, .alias, .opaque, .typo, .typo-suggestion, .parser-suggestion { color: #facc15; }
+.error { color: #f87171; }
+.line-number, .gutter-bar { color: #22d3ee; }
+.emphasized { font-weight: bold; }
+.tip { text-decoration: underline; }
+"#;
+
+/// A complete HTML page with all the given reports, errors first, and a list of them at the top
+/// that links to each one.
+pub fn html_page(reports: &[HtmlReport]) -> String {
+    let cwd = std::env::current_dir().unwrap_or_default();
+
+    let mut sorted: Vec<&HtmlReport> = reports.iter().collect();
+    sorted.sort_by_key(|report| match report.severity {
+        Severity::RuntimeError => 0,
+        Severity::Warning => 1,
+    });
+
+    let class = |severity| match severity {
+        Severity::RuntimeError => "severity-error",
+        Severity::Warning => "severity-warning",
+    };
+
+    let mut buf = String::new();
+
+    buf.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    buf.push_str("<title>Roc problems</title>\n<style>");
+    buf.push_str(STYLE);
+    buf.push_str("</style>\n</head>\n<body>\n");
+
+    let errors = sorted
+        .iter()
+        .filter(|report| matches!(report.severity, Severity::RuntimeError))
+        .count();
+    let warnings = sorted.len() - errors;
+
+    writeln!(
+        buf,
+        "<h1>{} {} and {} {}</h1>",
+        errors,
+        if errors == 1 { "error" } else { "errors" },
+        warnings,
+        if warnings == 1 { "warning" } else { "warnings" },
+    )
+    .unwrap();
+
+    buf.push_str("<nav>\n<ol>\n");
+
+    for (index, report) in sorted.iter().enumerate() {
+        writeln!(
+            buf,
+            "<li class=\"{}\"><a href=\"#problem-{}\">{}</a></li>",
+            class(report.severity),
+            index + 1,
+            escape(&report.title),
+        )
+        .unwrap();
+    }
+
+    buf.push_str("</ol>\n</nav>\n");
+
+    for (index, report) in sorted.iter().enumerate() {
+        writeln!(
+            buf,
+            "<section id=\"problem-{}\" class=\"{}\">",
+            index + 1,
+            class(report.severity),
+        )
+        .unwrap();
+
+        buf.push_str("<h2>");
+        buf.push_str(&escape(&report.title));
+
+        if let Some(code) = report.code {
+            write!(
+                buf,
+                " <span class=\"code\" title=\"Run `roc explain {code}` for more\">[{code}]</span>"
+            )
+            .unwrap();
+        }
+
+        if report.filename != PathBuf::from("") {
+            let path = report
+                .filename
+                .strip_prefix(&cwd)
+                .unwrap_or(&report.filename);
+
+            write!(
+                buf,
+                "<span class=\"path\">{}</span>",
+                escape(&path.display().to_string())
+            )
+            .unwrap();
+        }
+
+        buf.push_str("</h2>\n<div class=\"body\">");
+        buf.push_str(&report.body);
+        buf.push_str("</div>\n</section>\n");
+    }
+
+    buf.push_str("</body>\n</html>\n");
+
+    buf
+}
@@ -4,5 +4,7 @@
 #![allow(clippy::large_enum_variant)]
 
 pub mod cli;
+pub mod codes;
 pub mod error;
+pub mod html;
 pub mod report;
@@ -2,7 +2,7 @@ use roc_module::ident::Ident;
 use roc_module::ident::{Lowercase, ModuleName, TagName, Uppercase};
 use roc_module::symbol::{Interns, ModuleId, PQModuleName, PackageQualified, Symbol};
 use roc_problem::Severity;
-use roc_region::all::LineColumnRegion;
+use roc_region::all::{LineColumnRegion, Position, Region};
 use std::fmt;
 use std::path::{Path, PathBuf};
 use ven_pretty::{BoxAllocator, DocAllocator, DocBuilder, Render, RenderAnnotated};
@@ -106,12 +106,61 @@ pub enum RenderTarget {
     Generic,
 }
 
+/// A change to the source of the report's file: replace what's in the region with the given
+/// text. An empty region inserts the text at that position.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Edit {
+    pub region: Region,
+    pub replacement: String,
+}
+
+/// A fix for the problem a report is about, which editors can apply directly
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fix {
+    /// What the fix does, e.g. "Rename to `foo`"
+    pub description: String,
+    pub edits: Vec<Edit>,
+}
+
+impl Fix {
+    pub fn replace(description: String, region: Region, replacement: String) -> Self {
+        Fix {
+            description,
+            edits: vec![Edit {
+                region,
+                replacement,
+            }],
+        }
+    }
+
+    pub fn insert(description: String, position: Position, text: String) -> Self {
+        Self::replace(description, Region::new(position, position), text)
+    }
+}
+
+/// Another place in the report's file that helps explain the problem, e.g. where a name was
+/// first defined
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RelatedSpan {
+    pub region: LineColumnRegion,
+    /// What is at this place, e.g. "The first implementation was defined here"
+    pub message: String,
+}
+
 /// A textual report.
 pub struct Report<'b> {
     pub title: String,
+    /// The stable code of this kind of report (e.g. `E1001`), see [crate::codes]
+    pub code: Option<&'static str>,
     pub filename: PathBuf,
     pub doc: RocDocBuilder<'b>,
     pub severity: Severity,
+    /// Machine-applicable fixes, if we know any. There can be several (e.g. one for each name
+    /// that an unrecognized name might be a typo of), and applying any one of them fixes the problem.
+    pub fixes: Vec<Fix>,
+    /// Secondary places that the problem involves. They are shown beneath the message, and
+    /// editors can show them next to the primary region.
+    pub related: Vec<RelatedSpan>,
 }
 
 impl<'b> Report<'b> {
@@ -154,17 +203,40 @@ impl<'b> Report<'b> {
             .expect(err_msg);
     }
 
+    /// The report's message, with its related spans beneath it
+    pub fn doc_with_related(&self, alloc: &'b RocDocAllocator<'b>) -> RocDocBuilder<'b> {
+        if self.related.is_empty() {
+            return self.doc.clone();
+        }
+
+        let related = self.related.iter().map(|span| {
+            alloc.stack([
+                alloc.string(format!("{}:", span.message)),
+                alloc.region(span.region),
+            ])
+        });
+
+        alloc.stack(std::iter::once(self.doc.clone()).chain(related))
+    }
+
     pub fn pretty(self, alloc: &'b RocDocAllocator<'b>) -> RocDocBuilder<'b> {
+        let doc = self.doc_with_related(alloc);
+
         if self.title.is_empty() {
-            self.doc
+            doc
         } else {
+            let title = match self.code {
+                Some(code) => format!("{} [{}]", self.title, code),
+                None => self.title,
+            };
+
             let header = if self.filename == PathBuf::from("") {
-                crate::report::pretty_header(&self.title)
+                crate::report::pretty_header(&title)
             } else {
-                crate::report::pretty_header_with_path(&self.title, &self.filename)
+                crate::report::pretty_header_with_path(&title, &self.filename)
             };
 
-            alloc.stack([alloc.text(header).annotate(Annotation::Header), self.doc])
+            alloc.stack([alloc.text(header).annotate(Annotation::Header), doc])
         }
     }
 
@@ -233,6 +305,115 @@ pub const DEFAULT_PALETTE: Palette = default_palette_from_style_codes(ANSI_STYLE
 
 pub const DEFAULT_PALETTE_HTML: Palette = default_palette_from_style_codes(HTML_STYLE_CODES);
 
+/// For terminals with a light background, where white and yellow text is hard to read
+pub const LIGHT_PALETTE: Palette = default_palette_from_style_codes(LIGHT_ANSI_STYLE_CODES);
+
+/// Bold, bright colors, for when the regular ones don't stand out enough
+pub const HIGH_CONTRAST_PALETTE: Palette =
+    default_palette_from_style_codes(HIGH_CONTRAST_ANSI_STYLE_CODES);
+
+/// No styles at all, for terminals without colors (and people who don't want them)
+pub const NO_COLOR_PALETTE: Palette = default_palette_from_style_codes(NO_STYLE_CODES);
+
+/// The environment variable that picks the palette of terminal reports: `dark` (the default),
+/// `light`, `high-contrast`, or `none`
+pub const THEME_ENV_VAR: &str = "ROC_REPORT_THEME";
+
+/// The environment variable that overrides the colors of individual elements of terminal reports,
+/// as `element=color` pairs separated by `:`, e.g. `keyword=blue:line-number=bright-black`
+pub const COLORS_ENV_VAR: &str = "ROC_REPORT_COLORS";
+
+impl Palette {
+    /// The palette for terminal reports, as configured by [THEME_ENV_VAR] and [COLORS_ENV_VAR].
+    /// Like other command-line tools, we leave out colors if `NO_COLOR` is set.
+    pub fn from_env() -> Palette {
+        let mut palette = match std::env::var(THEME_ENV_VAR).ok().as_deref() {
+            Some("light") => LIGHT_PALETTE,
+            Some("high-contrast") => HIGH_CONTRAST_PALETTE,
+            Some("none") => NO_COLOR_PALETTE,
+            Some(_) => DEFAULT_PALETTE,
+            None if std::env::var_os("NO_COLOR").map_or(false, |var| !var.is_empty()) => {
+                NO_COLOR_PALETTE
+            }
+            None => DEFAULT_PALETTE,
+        };
+
+        if let Ok(colors) = std::env::var(COLORS_ENV_VAR) {
+            palette.apply_overrides(&colors);
+        }
+
+        palette
+    }
+
+    /// Apply overrides like `keyword=blue:error=bold`, ignoring the ones we don't understand,
+    /// since a typo in an environment variable shouldn't keep anything from compiling.
+    pub fn apply_overrides(&mut self, overrides: &str) {
+        for entry in overrides.split(':') {
+            let (element, color) = match entry.split_once('=') {
+                Some((element, color)) => (element.trim(), color.trim()),
+                None => continue,
+            };
+
+            let code = match ansi_code(color) {
+                Some(code) => code,
+                None => continue,
+            };
+
+            let field = match element {
+                "primary" => &mut self.primary,
+                "code-block" => &mut self.code_block,
+                "keyword" => &mut self.keyword,
+                "variable" => &mut self.variable,
+                "type-variable" => &mut self.type_variable,
+                "structure" => &mut self.structure,
+                "alias" => &mut self.alias,
+                "opaque" => &mut self.opaque,
+                "error" => &mut self.error,
+                "line-number" => &mut self.line_number,
+                "header" => &mut self.header,
+                "gutter-bar" => &mut self.gutter_bar,
+                "module-name" => &mut self.module_name,
+                "binop" => &mut self.binop,
+                "typo" => &mut self.typo,
+                "typo-suggestion" => &mut self.typo_suggestion,
+                "parser-suggestion" => &mut self.parser_suggestion,
+                _ => continue,
+            };
+
+            *field = code;
+        }
+    }
+}
+
+/// The ANSI escape sequence for a color (or style) name
+fn ansi_code(color: &str) -> Option<&'static str> {
+    let code = match color {
+        "default" => "\u{001b}[39m",
+        "black" => "\u{001b}[30m",
+        "red" => "\u{001b}[31m",
+        "green" => "\u{001b}[32m",
+        "yellow" => "\u{001b}[33m",
+        "blue" => "\u{001b}[34m",
+        "magenta" => "\u{001b}[35m",
+        "cyan" => "\u{001b}[36m",
+        "white" => "\u{001b}[37m",
+        "bright-black" => "\u{001b}[90m",
+        "bright-red" => "\u{001b}[91m",
+        "bright-green" => "\u{001b}[92m",
+        "bright-yellow" => "\u{001b}[93m",
+        "bright-blue" => "\u{001b}[94m",
+        "bright-magenta" => "\u{001b}[95m",
+        "bright-cyan" => "\u{001b}[96m",
+        "bright-white" => "\u{001b}[97m",
+        "bold" => "\u{001b}[1m",
+        "underline" => "\u{001b}[4m",
+        "none" => "",
+        _ => return None,
+    };
+
+    Some(code)
+}
+
 /// A machine-readable format for text styles (colors and other styles)
 pub struct StyleCodes {
     pub red: &'static str,
@@ -262,6 +443,39 @@ pub const ANSI_STYLE_CODES: StyleCodes = StyleCodes {
     color_reset: "\u{1b}[39m",
 };
 
+/// The regular colors, except for the ones that are hard to read on a light background
+pub const LIGHT_ANSI_STYLE_CODES: StyleCodes = StyleCodes {
+    yellow: "\u{001b}[35m",
+    cyan: "\u{001b}[34m",
+    white: "\u{001b}[39m",
+    ..ANSI_STYLE_CODES
+};
+
+pub const HIGH_CONTRAST_ANSI_STYLE_CODES: StyleCodes = StyleCodes {
+    red: "\u{001b}[1;91m",
+    green: "\u{001b}[1;92m",
+    yellow: "\u{001b}[1;93m",
+    blue: "\u{001b}[1;94m",
+    magenta: "\u{001b}[1;95m",
+    cyan: "\u{001b}[1;96m",
+    white: "\u{001b}[1;97m",
+    ..ANSI_STYLE_CODES
+};
+
+pub const NO_STYLE_CODES: StyleCodes = StyleCodes {
+    red: "",
+    green: "",
+    yellow: "",
+    blue: "",
+    magenta: "",
+    cyan: "",
+    white: "",
+    bold: "",
+    underline: "",
+    reset: "",
+    color_reset: "",
+};
+
 macro_rules! html_color {
     ($name: expr) => {
         concat!("<span class='color-", $name, "'>")
@@ -327,6 +541,17 @@ impl<'a> RocDocAllocator<'a> {
         }
     }
 
+    /// The source code in the given region, if the region is on a single line
+    pub fn source_text(&self, region: LineColumnRegion) -> Option<&'a str> {
+        if region.start.line != region.end.line {
+            return None;
+        }
+
+        self.src_lines
+            .get(region.start.line as usize)?
+            .get(region.start.column as usize..region.end.column as usize)
+    }
+
     /// vertical concatenation. Adds a newline between elements
     pub fn vcat<A, I>(&'a self, docs: I) -> DocBuilder<'a, Self, A>
     where
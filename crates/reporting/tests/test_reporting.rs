@@ -13,7 +13,10 @@ mod test_reporting {
     use indoc::indoc;
     use roc_can::abilities::AbilitiesStore;
     use roc_can::expr::PendingDerives;
-    use roc_load::{self, ExecutionMode, LoadConfig, LoadedModule, LoadingProblem, Threading};
+    use roc_load::{
+        self, ExecutionMode, FloatWidth, LoadConfig, LoadedModule, LoadingProblem, OptLevel,
+        Threading,
+    };
     use roc_module::symbol::{Interns, ModuleId};
     use roc_packaging::cache::RocCacheDir;
     use roc_parse::module::parse_header;
@@ -40,9 +43,12 @@ mod test_reporting {
     fn to_simple_report(doc: RocDocBuilder) -> Report {
         Report {
             title: "".to_string(),
+            code: None,
             doc,
             filename: filename_from_string(r"/code/proj/Main.roc"),
             severity: Severity::RuntimeError,
+            fixes: Vec::new(),
+            related: Vec::new(),
         }
     }
 
@@ -128,6 +134,8 @@ mod test_reporting {
                 palette: DEFAULT_PALETTE,
                 threading: Threading::Single,
                 exec_mode: ExecutionMode::Check,
+                frac_default: FloatWidth::F64,
+                opt_level: OptLevel::Development,
             };
             let result = roc_load::load_and_typecheck(
                 arena,
@@ -225,6 +233,46 @@ mod test_reporting {
         }
     }
 
+    /// Each fix that the reports suggest, with the module's source after applying it
+    fn list_fixes(subdir: &str, src: &str) -> Vec<(String, String)> {
+        let arena = Bump::new();
+        let filename = filename_from_string(r"/code/proj/Main.roc");
+
+        let (module_src, type_problems, can_problems, home, interns) =
+            infer_expr_help_new(subdir, &arena, src).expect("failed to load");
+
+        let lines = LineInfo::new(&module_src);
+        let src_lines: Vec<&str> = module_src.split('\n').collect();
+        let alloc = RocDocAllocator::new(&src_lines, home, &interns);
+
+        let can_reports = can_problems
+            .into_iter()
+            .map(|problem| can_problem(&alloc, &lines, filename.clone(), problem));
+        let type_reports = type_problems
+            .into_iter()
+            .filter_map(|problem| type_problem(&alloc, &lines, filename.clone(), problem));
+
+        can_reports
+            .chain(type_reports)
+            .flat_map(|report| report.fixes)
+            .map(|fix| {
+                let mut fixed_src = module_src.clone();
+
+                // Apply the edits from last to first, so the earlier regions stay valid.
+                let mut edits = fix.edits;
+                edits.sort_by_key(|edit| std::cmp::Reverse(edit.region.start().offset));
+
+                for edit in edits {
+                    let range =
+                        edit.region.start().offset as usize..edit.region.end().offset as usize;
+                    fixed_src.replace_range(range, &edit.replacement);
+                }
+
+                (fix.description, fixed_src)
+            })
+            .collect()
+    }
+
     fn infer_expr_help<'a>(
         arena: &'a Bump,
         expr_src: &'a str,
@@ -480,7 +528,7 @@ mod test_reporting {
         "#
         ),
         @r###"
-    ── NOT EXPOSED ─────────────────────────────────────────── /code/proj/Main.roc ─
+    ── NOT EXPOSED [E5002] ─────────────────────────────────── /code/proj/Main.roc ─
 
     The List module does not expose `isempty`:
 
@@ -507,7 +555,7 @@ mod test_reporting {
         "#
         ),
         @r###"
-    ── UNUSED DEFINITION ───────────────────────────────────── /code/proj/Main.roc ─
+    ── UNUSED DEFINITION [E2002] ───────────────────────────── /code/proj/Main.roc ─
 
     `y` is not used anywhere in your code.
 
@@ -532,7 +580,7 @@ mod test_reporting {
        "#
         ),
         @r###"
-    ── DUPLICATE NAME ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── DUPLICATE NAME [E2005] ──────────────────────────────── /code/proj/Main.roc ─
 
     The `i` name is first defined here:
 
@@ -564,7 +612,7 @@ mod test_reporting {
        "#
         ),
         @r###"
-    ── DUPLICATE NAME ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── DUPLICATE NAME [E2005] ──────────────────────────────── /code/proj/Main.roc ─
 
     The `Booly` name is first defined here:
 
@@ -596,7 +644,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     Using != and == together requires parentheses, to clarify how they
     should be grouped.
@@ -625,7 +673,7 @@ mod test_reporting {
             "#
         ),
         @r#"
-        ── UNRECOGNIZED NAME ───────────────────────────────────── /code/proj/Main.roc ─
+        ── UNRECOGNIZED NAME [E2001] ───────────────────────────── /code/proj/Main.roc ─
 
         Nothing is named `bar` in this scope.
 
@@ -649,7 +697,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── UNRECOGNIZED NAME ───────────────────────────────────── /code/proj/Main.roc ─
+    ── UNRECOGNIZED NAME [E2001] ───────────────────────────── /code/proj/Main.roc ─
 
     Nothing is named `true` in this scope.
 
@@ -681,7 +729,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     Using more than one == like this requires parentheses, to clarify how
     things should be grouped.
@@ -707,7 +755,7 @@ mod test_reporting {
          "#
         ),
         @r###"
-    ── UNUSED ARGUMENT ─────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNUSED ARGUMENT [E2004] ─────────────────────────────── /code/proj/Main.roc ─
 
     `box` doesn't use `htmlChildren`.
 
@@ -720,7 +768,7 @@ mod test_reporting {
     at the start of a variable name is a way of saying that the variable
     is not used.
 
-    ── UNUSED DEFINITION ───────────────────────────────────── /code/proj/Main.roc ─
+    ── UNUSED DEFINITION [E2002] ───────────────────────────── /code/proj/Main.roc ─
 
     `y` is not used anywhere in your code.
 
@@ -804,7 +852,7 @@ mod test_reporting {
             ),
             indoc!(
                 r#"
-                <cyan>── UNRECOGNIZED NAME ───────────────────────────────────── /code/proj/Main.roc ─<reset>
+                <cyan>── UNRECOGNIZED NAME [E2001] ───────────────────────────── /code/proj/Main.roc ─<reset>
 
                 Nothing is named `theAdmin` in this scope.
 
@@ -830,7 +878,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This `if` condition needs to be a Bool:
 
@@ -856,7 +904,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This `if` guard condition needs to be a Bool:
 
@@ -881,7 +929,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This `if` has an `else` branch with a different type from its `then` branch:
 
@@ -908,7 +956,7 @@ mod test_reporting {
              "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     The 3rd branch of this `if` does not match all the previous branches:
 
@@ -938,7 +986,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     The 2nd branch of this `when` does not match all the previous branches:
 
@@ -967,7 +1015,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This list contains elements with different types:
 
@@ -994,7 +1042,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This list contains elements with different types:
 
@@ -1027,7 +1075,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     I cannot update the `.foo` field like this:
 
@@ -1057,7 +1105,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── CIRCULAR TYPE ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── CIRCULAR TYPE [E1004] ───────────────────────────────── /code/proj/Main.roc ─
 
     I'm inferring a weird self-referential type for `f`:
 
@@ -1082,7 +1130,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── CIRCULAR TYPE ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── CIRCULAR TYPE [E1004] ───────────────────────────────── /code/proj/Main.roc ─
 
     I'm inferring a weird self-referential type for `f`:
 
@@ -1108,7 +1156,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── CIRCULAR TYPE ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── CIRCULAR TYPE [E1004] ───────────────────────────────── /code/proj/Main.roc ─
 
     I'm inferring a weird self-referential type for `f`:
 
@@ -1121,7 +1169,7 @@ mod test_reporting {
 
         List ∞ -> *
 
-    ── CIRCULAR TYPE ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── CIRCULAR TYPE [E1004] ───────────────────────────────── /code/proj/Main.roc ─
 
     I'm inferring a weird self-referential type for `g`:
 
@@ -1148,7 +1196,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression is used in an unexpected way:
 
@@ -1184,7 +1232,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 1st argument to `f` has an unexpected type:
 
@@ -1218,7 +1266,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── CIRCULAR TYPE ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── CIRCULAR TYPE [E1004] ───────────────────────────────── /code/proj/Main.roc ─
 
     I'm inferring a weird self-referential type for `f`:
 
@@ -1244,7 +1292,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── CIRCULAR TYPE ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── CIRCULAR TYPE [E1004] ───────────────────────────────── /code/proj/Main.roc ─
 
     I'm inferring a weird self-referential type for `f`:
 
@@ -1282,7 +1330,7 @@ mod test_reporting {
         // against that extra variable, rather than possibly having to translate a `Type`
         // again.
         @r###"
-    ── CIRCULAR TYPE ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── CIRCULAR TYPE [E1004] ───────────────────────────────── /code/proj/Main.roc ─
 
     I'm inferring a weird self-referential type for `f`:
 
@@ -1295,7 +1343,7 @@ mod test_reporting {
 
         List ∞ -> List *
 
-    ── CIRCULAR TYPE ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── CIRCULAR TYPE [E1004] ───────────────────────────────── /code/proj/Main.roc ─
 
     I'm inferring a weird self-referential type for `g`:
 
@@ -1322,7 +1370,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── CIRCULAR TYPE ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── CIRCULAR TYPE [E1004] ───────────────────────────────── /code/proj/Main.roc ─
 
     I'm inferring a weird self-referential type for `f`:
 
@@ -1335,7 +1383,7 @@ mod test_reporting {
 
         List ∞ -> List *
 
-    ── CIRCULAR TYPE ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── CIRCULAR TYPE [E1004] ───────────────────────────────── /code/proj/Main.roc ─
 
     I'm inferring a weird self-referential type for `g`:
 
@@ -1363,7 +1411,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 1st argument to `f` has an unexpected type:
 
@@ -1397,7 +1445,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 1st argument to `f` has an unexpected type:
 
@@ -1431,7 +1479,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 1st argument to `f` has an unexpected type:
 
@@ -1465,7 +1513,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the `then` branch of this `if` expression:
 
@@ -1499,7 +1547,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `x` definition:
 
@@ -1532,7 +1580,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `x` definition:
 
@@ -1564,7 +1612,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TOO MANY ARGS ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TOO MANY ARGS [E1002] ───────────────────────────────── /code/proj/Main.roc ─
 
     The `x` value is not a function, but it was given 1 argument:
 
@@ -1586,7 +1634,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TOO MANY ARGS ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TOO MANY ARGS [E1002] ───────────────────────────────── /code/proj/Main.roc ─
 
     The `f` function expects 1 argument, but it got 2 instead:
 
@@ -1608,7 +1656,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TOO FEW ARGS ────────────────────────────────────────── /code/proj/Main.roc ─
+    ── TOO FEW ARGS [E1003] ────────────────────────────────── /code/proj/Main.roc ─
 
     The `f` function expects 2 arguments, but it got only 1:
 
@@ -1629,7 +1677,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     The branches of this `when` expression don't match the condition:
 
@@ -1658,7 +1706,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     The 2nd pattern in this `when` does not match the previous ones:
 
@@ -1684,7 +1732,7 @@ mod test_reporting {
              "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     The branches of this `when` expression don't match the condition:
 
@@ -1712,7 +1760,7 @@ mod test_reporting {
              "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     The branches of this `when` expression don't match the condition:
 
@@ -1741,7 +1789,7 @@ mod test_reporting {
              "#
         ),
         @r###"
-    ── UNRECOGNIZED NAME ───────────────────────────────────── /code/proj/Main.roc ─
+    ── UNRECOGNIZED NAME [E2001] ───────────────────────────── /code/proj/Main.roc ─
 
     Nothing is named `foo` in this scope.
 
@@ -1797,7 +1845,7 @@ mod test_reporting {
         ),
         // Just putting this here. We should probably handle or-patterns better
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     The 2nd pattern in this branch does not match the previous ones:
 
@@ -1825,7 +1873,7 @@ mod test_reporting {
         ),
         // Maybe this should specifically say the pattern doesn't work?
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression is used in an unexpected way:
 
@@ -1853,7 +1901,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of this definition:
 
@@ -1884,7 +1932,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This integer pattern is malformed:
 
@@ -1905,7 +1953,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This float pattern is malformed:
 
@@ -1926,7 +1974,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This hex integer pattern is malformed:
 
@@ -1947,7 +1995,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This octal integer pattern is malformed:
 
@@ -1968,7 +2016,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This binary integer pattern is malformed:
 
@@ -1990,7 +2038,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `x` definition:
 
@@ -2036,7 +2084,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the `else` branch of this `if` expression:
 
@@ -2070,7 +2118,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `f` definition:
 
@@ -2105,7 +2153,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `f` definition:
 
@@ -2141,7 +2189,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── UNRECOGNIZED NAME ───────────────────────────────────── /code/proj/Main.roc ─
+    ── UNRECOGNIZED NAME [E2001] ───────────────────────────── /code/proj/Main.roc ─
 
     Nothing is named `ok` in this scope.
 
@@ -2172,7 +2220,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── UNUSED DEFINITION ───────────────────────────────────── /code/proj/Main.roc ─
+    ── UNUSED DEFINITION [E2002] ───────────────────────────── /code/proj/Main.roc ─
 
     `ok` is not used anywhere in your code.
 
@@ -2182,7 +2230,7 @@ mod test_reporting {
     If you didn't intend on using `ok` then remove it so future readers of
     your code don't wonder why it is there.
 
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `f` definition:
 
@@ -2214,7 +2262,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── CIRCULAR DEFINITION ─────────────────────────────────── /code/proj/Main.roc ─
+    ── CIRCULAR DEFINITION [E2008] ─────────────────────────── /code/proj/Main.roc ─
 
     `f` is defined directly in terms of itself:
 
@@ -2241,7 +2289,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── CIRCULAR DEFINITION ─────────────────────────────────── /code/proj/Main.roc ─
+    ── CIRCULAR DEFINITION [E2008] ─────────────────────────── /code/proj/Main.roc ─
 
     The `foo` definition is causing a very tricky infinite loop:
 
@@ -2269,7 +2317,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This `x` record doesn’t have a `foo` field:
 
@@ -2291,7 +2339,7 @@ mod test_reporting {
         ),
         // TODO also suggest fields with the correct type
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This `x` record doesn’t have a `foo` field:
 
@@ -2324,7 +2372,7 @@ mod test_reporting {
         ),
         // TODO also suggest fields with the correct type
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This `r` record doesn’t have a `foo` field:
 
@@ -2352,7 +2400,7 @@ mod test_reporting {
         ),
         // TODO also suggest fields with the correct type
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This `x` record doesn’t have a `foo` field:
 
@@ -2382,7 +2430,7 @@ mod test_reporting {
         ),
         // TODO also suggest fields with the correct type
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 2nd argument to `add` has an unexpected type:
 
@@ -2407,7 +2455,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 2nd argument to `add` has an unexpected type:
 
@@ -2435,7 +2483,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 2nd argument to `add` has an unexpected type:
 
@@ -2463,7 +2511,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `f` definition:
 
@@ -2497,7 +2545,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `f` definition:
 
@@ -2536,7 +2584,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This pattern does not cover all the possibilities:
 
@@ -2567,7 +2615,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression is used in an unexpected way:
 
@@ -2598,7 +2646,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -2625,7 +2673,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -2653,7 +2701,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -2682,7 +2730,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -2711,7 +2759,7 @@ mod test_reporting {
         ),
         // Tip: Looks like a record field guard is not exhaustive. Learn more about record pattern matches at TODO.
         @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -2740,7 +2788,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -2766,7 +2814,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -2793,7 +2841,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── REDUNDANT PATTERN ───────────────────────────────────── /code/proj/Main.roc ─
+    ── REDUNDANT PATTERN [E1018] ───────────────────────────── /code/proj/Main.roc ─
 
     The 2nd pattern is redundant:
 
@@ -2821,7 +2869,7 @@ mod test_reporting {
         ),
         // de-aliases the alias to give a better error message
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 1st argument to `f` has an unexpected type:
 
@@ -2859,7 +2907,7 @@ mod test_reporting {
         ),
         // should not report Bar as unused!
         @r###"
-    ── CYCLIC ALIAS ────────────────────────────────────────── /code/proj/Main.roc ─
+    ── CYCLIC ALIAS [E1005] ────────────────────────────────── /code/proj/Main.roc ─
 
     The `Foo` alias is self-recursive in an invalid way:
 
@@ -2885,7 +2933,7 @@ mod test_reporting {
         ),
         // should not report Bar as unused!
         @r###"
-    ── CYCLIC ALIAS ────────────────────────────────────────── /code/proj/Main.roc ─
+    ── CYCLIC ALIAS [E1005] ────────────────────────────────── /code/proj/Main.roc ─
 
     The `Foo` alias is self-recursive in an invalid way:
 
@@ -2905,7 +2953,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── DUPLICATE FIELD NAME ────────────────────────────────── /code/proj/Main.roc ─
+    ── DUPLICATE FIELD NAME [E2006] ────────────────────────── /code/proj/Main.roc ─
 
     This record defines the `.x` field twice!
 
@@ -2929,7 +2977,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── DUPLICATE FIELD NAME ────────────────────────────────── /code/proj/Main.roc ─
+    ── DUPLICATE FIELD NAME [E2006] ────────────────────────── /code/proj/Main.roc ─
 
     This record defines the `.x` field twice!
 
@@ -2957,7 +3005,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── DUPLICATE FIELD NAME ────────────────────────────────── /code/proj/Main.roc ─
+    ── DUPLICATE FIELD NAME [E2006] ────────────────────────── /code/proj/Main.roc ─
 
     This record defines the `.x` field twice!
 
@@ -2992,7 +3040,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── DUPLICATE FIELD NAME ────────────────────────────────── /code/proj/Main.roc ─
+    ── DUPLICATE FIELD NAME [E2006] ────────────────────────── /code/proj/Main.roc ─
 
     This record defines the `.x` field twice!
 
@@ -3025,7 +3073,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── DUPLICATE FIELD NAME ────────────────────────────────── /code/proj/Main.roc ─
+    ── DUPLICATE FIELD NAME [E2006] ────────────────────────── /code/proj/Main.roc ─
 
     This record type defines the `.foo` field twice!
 
@@ -3053,7 +3101,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── DUPLICATE TAG NAME ──────────────────────────────────── /code/proj/Main.roc ─
+    ── DUPLICATE TAG NAME [E2007] ──────────────────────────── /code/proj/Main.roc ─
 
     This tag union type defines the `Foo` tag twice!
 
@@ -3082,7 +3130,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── NAMING PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── NAMING PROBLEM [E2011] ──────────────────────────────── /code/proj/Main.roc ─
 
     This annotation does not match the definition immediately following
     it:
@@ -3118,7 +3166,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This definition of `MyAlias` has an unexpected pattern:
 
@@ -3127,7 +3175,7 @@ mod test_reporting {
 
     Only type variables like `a` or `value` can occur in this position.
 
-    ── UNUSED DEFINITION ───────────────────────────────────── /code/proj/Main.roc ─
+    ── UNUSED DEFINITION [E2002] ───────────────────────────── /code/proj/Main.roc ─
 
     `MyAlias` is not used anywhere in your code.
 
@@ -3150,7 +3198,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This definition of `Age` has an unexpected pattern:
 
@@ -3172,7 +3220,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TOO MANY TYPE ARGUMENTS ─────────────────────────────── /code/proj/Main.roc ─
+    ── TOO MANY TYPE ARGUMENTS [E1007] ─────────────────────── /code/proj/Main.roc ─
 
     The `Num` opaque expects 1 type argument, but it got 2 instead:
 
@@ -3194,7 +3242,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TOO MANY TYPE ARGUMENTS ─────────────────────────────── /code/proj/Main.roc ─
+    ── TOO MANY TYPE ARGUMENTS [E1007] ─────────────────────── /code/proj/Main.roc ─
 
     The `Num` opaque expects 1 type argument, but it got 2 instead:
 
@@ -3218,7 +3266,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TOO FEW TYPE ARGUMENTS ──────────────────────────────── /code/proj/Main.roc ─
+    ── TOO FEW TYPE ARGUMENTS [E1008] ──────────────────────── /code/proj/Main.roc ─
 
     The `Pair` alias expects 2 type arguments, but it got 1 instead:
 
@@ -3242,7 +3290,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TOO MANY TYPE ARGUMENTS ─────────────────────────────── /code/proj/Main.roc ─
+    ── TOO MANY TYPE ARGUMENTS [E1007] ─────────────────────── /code/proj/Main.roc ─
 
     The `Pair` alias expects 2 type arguments, but it got 3 instead:
 
@@ -3265,7 +3313,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── UNUSED TYPE ALIAS PARAMETER ─────────────────────────── /code/proj/Main.roc ─
+    ── UNUSED TYPE ALIAS PARAMETER [E1010] ─────────────────── /code/proj/Main.roc ─
 
     The `a` type parameter is not used in the `Foo` alias definition:
 
@@ -3287,7 +3335,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── ARGUMENTS BEFORE EQUALS ────────────────── tmp/elm_function_syntax/Test.roc ─
+    ── ARGUMENTS BEFORE EQUALS [E0003] ────────── tmp/elm_function_syntax/Test.roc ─
 
     I am partway through parsing a definition, but I got stuck here:
 
@@ -3315,7 +3363,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `x` definition:
 
@@ -3352,7 +3400,7 @@ mod test_reporting {
         // TODO render tag unions across multiple lines
         // TODO do not show recursion var if the recursion var does not render on the surface of a type
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `x` definition:
 
@@ -3389,7 +3437,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This integer literal is too big:
 
@@ -3401,7 +3449,7 @@ mod test_reporting {
 
     Tip: Learn more about number literals at TODO
 
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This integer literal is too small:
 
@@ -3413,7 +3461,7 @@ mod test_reporting {
 
     Tip: Learn more about number literals at TODO
 
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This integer literal is too big:
 
@@ -3425,7 +3473,7 @@ mod test_reporting {
 
     Tip: Learn more about number literals at TODO
 
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This integer literal is too small:
 
@@ -3437,7 +3485,7 @@ mod test_reporting {
 
     Tip: Learn more about number literals at TODO
 
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 2nd argument to `add` has an unexpected type:
 
@@ -3466,7 +3514,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This float literal is too big:
 
@@ -3478,7 +3526,7 @@ mod test_reporting {
 
     Tip: Learn more about number literals at TODO
 
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This float literal is too small:
 
@@ -3511,7 +3559,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This integer literal contains an invalid digit:
 
@@ -3523,7 +3571,7 @@ mod test_reporting {
 
     Tip: Learn more about number literals at TODO
 
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This hex integer literal contains an invalid digit:
 
@@ -3535,7 +3583,7 @@ mod test_reporting {
 
     Tip: Learn more about number literals at TODO
 
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This octal integer literal contains an invalid digit:
 
@@ -3547,7 +3595,7 @@ mod test_reporting {
 
     Tip: Learn more about number literals at TODO
 
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This binary integer literal contains an invalid digit:
 
@@ -3577,7 +3625,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This hex integer literal contains no digits:
 
@@ -3589,7 +3637,7 @@ mod test_reporting {
 
     Tip: Learn more about number literals at TODO
 
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This octal integer literal contains no digits:
 
@@ -3601,7 +3649,7 @@ mod test_reporting {
 
     Tip: Learn more about number literals at TODO
 
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This binary integer literal contains no digits:
 
@@ -3625,7 +3673,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This float literal contains an invalid digit:
 
@@ -3656,7 +3704,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This expression cannot be updated:
 
@@ -3665,7 +3713,7 @@ mod test_reporting {
 
     Only variables can be updated with record update syntax.
 
-    ── MODULE NOT IMPORTED ─────────────────────────────────── /code/proj/Main.roc ─
+    ── MODULE NOT IMPORTED [E5001] ─────────────────────────── /code/proj/Main.roc ─
 
     The `Test` module is not imported:
 
@@ -3680,7 +3728,7 @@ mod test_reporting {
         Dict
         Hash
 
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This expression cannot be updated:
 
@@ -3699,7 +3747,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── MODULE NOT IMPORTED ─────────────────────────────────── /code/proj/Main.roc ─
+    ── MODULE NOT IMPORTED [E5001] ─────────────────────────── /code/proj/Main.roc ─
 
     The `Foo` module is not imported:
 
@@ -3724,7 +3772,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 2nd argument to `add` has an unexpected type:
 
@@ -3752,7 +3800,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     The 1st argument to `f` is weird:
 
@@ -3781,7 +3829,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of this definition:
 
@@ -3812,7 +3860,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     The 1st argument to `f` is weird:
 
@@ -3845,7 +3893,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     The branches of this `when` expression don't match the condition:
 
@@ -3878,7 +3926,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression is used in an unexpected way:
 
@@ -3909,7 +3957,7 @@ mod test_reporting {
                 "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 1st argument to this function has an unexpected type:
 
@@ -3943,7 +3991,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     The branches of this `when` expression don't match the condition:
 
@@ -3977,7 +4025,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     The branches of this `when` expression don't match the condition:
 
@@ -4005,7 +4053,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── BAD OPTIONAL VALUE ──────────────────────────────────── /code/proj/Main.roc ─
+    ── BAD OPTIONAL VALUE [E0052] ──────────────────────────── /code/proj/Main.roc ─
 
     This record uses an optional value for the `.y` field in an incorrect
     context!
@@ -4042,7 +4090,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── REDUNDANT PATTERN ───────────────────────────────────── /code/proj/Main.roc ─
+    ── REDUNDANT PATTERN [E1018] ───────────────────────────── /code/proj/Main.roc ─
 
     The 3rd pattern is redundant:
 
@@ -4087,7 +4135,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── UNUSED ARGUMENT ─────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNUSED ARGUMENT [E2004] ─────────────────────────────── /code/proj/Main.roc ─
 
     `f` doesn't use `foo`.
 
@@ -4109,7 +4157,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     I am trying to parse a qualified name here:
 
@@ -4130,7 +4178,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     I am trying to parse a qualified name here:
 
@@ -4150,7 +4198,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     I trying to parse a record field access here:
 
@@ -4172,7 +4220,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── UNKNOWN OPERATOR ──────────────── tmp/type_annotation_double_colon/Test.roc ─
+    ── UNKNOWN OPERATOR [E0044] ──────── tmp/type_annotation_double_colon/Test.roc ─
 
     This looks like an operator, but it's not one I recognize!
 
@@ -4204,7 +4252,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── TOO MANY ARGS ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TOO MANY ARGS [E1002] ───────────────────────────────── /code/proj/Main.roc ─
 
     This value is not a function, but it was given 3 arguments:
 
@@ -4223,7 +4271,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── UNFINISHED TAG UNION TYPE ───────────────────── tmp/tag_union_open/Test.roc ─
+    ── UNFINISHED TAG UNION TYPE [E0041] ───────────── tmp/tag_union_open/Test.roc ─
 
     I am partway through parsing a tag union type, but I got stuck here:
 
@@ -4245,7 +4293,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── UNFINISHED TAG UNION TYPE ────────────────────── tmp/tag_union_end/Test.roc ─
+    ── UNFINISHED TAG UNION TYPE [E0041] ────────────── tmp/tag_union_end/Test.roc ─
 
     I am partway through parsing a tag union type, but I got stuck here:
 
@@ -4267,7 +4315,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── WEIRD TAG NAME ────────────────── tmp/tag_union_lowercase_tag_name/Test.roc ─
+    ── WEIRD TAG NAME [E0050] ────────── tmp/tag_union_lowercase_tag_name/Test.roc ─
 
     I am partway through parsing a tag union type, but I got stuck here:
 
@@ -4288,7 +4336,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── WEIRD TAG NAME ─────────── tmp/tag_union_second_lowercase_tag_name/Test.roc ─
+    ── WEIRD TAG NAME [E0050] ─── tmp/tag_union_second_lowercase_tag_name/Test.roc ─
 
     I am partway through parsing a tag union type, but I got stuck here:
 
@@ -4309,7 +4357,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── UNFINISHED RECORD TYPE ────────────────────── tmp/record_type_open/Test.roc ─
+    ── UNFINISHED RECORD TYPE [E0040] ────────────── tmp/record_type_open/Test.roc ─
 
     I am partway through parsing a record type, but I got stuck here:
 
@@ -4332,7 +4380,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── UNFINISHED RECORD TYPE ─────────────── tmp/record_type_open_indent/Test.roc ─
+    ── UNFINISHED RECORD TYPE [E0040] ─────── tmp/record_type_open_indent/Test.roc ─
 
     I am partway through parsing a record type, but I got stuck here:
 
@@ -4354,7 +4402,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── UNFINISHED RECORD TYPE ─────────────────────── tmp/record_type_end/Test.roc ─
+    ── UNFINISHED RECORD TYPE [E0040] ─────────────── tmp/record_type_end/Test.roc ─
 
     I am partway through parsing a record type, but I got stuck here:
 
@@ -4376,7 +4424,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── UNFINISHED RECORD TYPE ──────── tmp/record_type_keyword_field_name/Test.roc ─
+    ── UNFINISHED RECORD TYPE [E0040] ─ ...record_type_keyword_field_name/Test.roc ─
 
     I just started parsing a record type, but I got stuck on this field
     name:
@@ -4398,7 +4446,7 @@ mod test_reporting {
             "#
         ),
         @r###"
-    ── UNFINISHED RECORD TYPE ───────────── tmp/record_type_missing_comma/Test.roc ─
+    ── UNFINISHED RECORD TYPE [E0040] ───── tmp/record_type_missing_comma/Test.roc ─
 
     I am partway through parsing a record type, but I got stuck here:
 
@@ -4415,7 +4463,7 @@ mod test_reporting {
         record_type_tab,
         "f : { foo \t }",
         @r###"
-    ── TAB CHARACTER ──────────────────────────────── tmp/record_type_tab/Test.roc ─
+    ── TAB CHARACTER [E0026] ──────────────────────── tmp/record_type_tab/Test.roc ─
 
     I encountered a tab character
 
@@ -4432,7 +4480,7 @@ mod test_reporting {
         |golden| pretty_assertions::assert_eq!(
             golden,
             &format!(
-                r###"── TAB CHARACTER ─────────────────────────────── tmp/comment_with_tab/Test.roc ─
+                r###"── TAB CHARACTER [E0026] ─────────────────────── tmp/comment_with_tab/Test.roc ─
 
 I encountered a tab character
 
@@ -4454,7 +4502,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── UNFINISHED PARENTHESES ────────────────── tmp/type_in_parens_start/Test.roc ─
+    ── UNFINISHED PARENTHESES [E0037] ────────── tmp/type_in_parens_start/Test.roc ─
 
     I am partway through parsing a type in parentheses, but I got stuck
     here:
@@ -4477,7 +4525,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── UNFINISHED PARENTHESES ──────────────────── tmp/type_in_parens_end/Test.roc ─
+    ── UNFINISHED PARENTHESES [E0037] ──────────── tmp/type_in_parens_end/Test.roc ─
 
     I am partway through parsing a type in parentheses, but I got stuck
     here:
@@ -4502,7 +4550,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     I am confused by this type name:
 
@@ -4513,7 +4561,7 @@ Tab characters are not allowed."###,
     qualified by a module name, like Bool or Http.Request.Request.
     "###
     );
-    //                ── DOUBLE DOT ──────────────────────────────────────────────────────────────────
+    //                ── DOUBLE DOT [E0007] ──────────────────────────────────────────────────────────
     //
     //                I encountered two dots in a row:
     //
@@ -4532,7 +4580,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     I am confused by this type name:
 
@@ -4543,7 +4591,7 @@ Tab characters are not allowed."###,
     qualified by a module name, like Bool or Http.Request.Request.
     "###
     );
-    //                ── TRAILING DOT ────────────────────────────────────────────────────────────────
+    //                ── TRAILING DOT [E0027] ────────────────────────────────────────────────────────
     //
     //                I encountered a dot with nothing after it:
     //
@@ -4561,7 +4609,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── UNFINISHED TYPE ───────────────────────── tmp/type_apply_stray_dot/Test.roc ─
+    ── UNFINISHED TYPE [E0042] ───────────────── tmp/type_apply_stray_dot/Test.roc ─
 
     I just started parsing a type, but I got stuck here:
 
@@ -4582,7 +4630,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     I am confused by this type name:
 
@@ -4593,7 +4641,7 @@ Tab characters are not allowed."###,
     qualified by a module name, like Bool or Http.Request.Request.
     "###
     );
-    //                ── WEIRD QUALIFIED NAME ────────────────────────────────────────────────────────
+    //                ── WEIRD QUALIFIED NAME [E0049] ────────────────────────────────────────────────
     //
     //                I encountered a number at the start of a qualified name segment:
     //
@@ -4613,7 +4661,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     I am confused by this type name:
 
@@ -4633,7 +4681,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── MISSING FINAL EXPRESSION ──────── tmp/def_missing_final_expression/Test.roc ─
+    ── MISSING FINAL EXPRESSION [E0018] ─ ...def_missing_final_expression/Test.roc ─
 
     I am partway through parsing a definition, but I got stuck here:
 
@@ -4661,7 +4709,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── INDENT ENDS AFTER EXPRESSION ────── tmp/expression_indentation_end/Test.roc ─
+    ── INDENT ENDS AFTER EXPRESSION [E0014] ─ ...pression_indentation_end/Test.roc ─
 
     I am partway through parsing an expression, but I got stuck here:
 
@@ -4687,7 +4735,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── UNFINISHED INLINE ALIAS ──────────────────── tmp/type_inline_alias/Test.roc ─
+    ── UNFINISHED INLINE ALIAS [E0034] ──────────── tmp/type_inline_alias/Test.roc ─
 
     I just started parsing an inline type alias, but I got stuck here:
 
@@ -4709,7 +4757,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── DOUBLE COMMA ─────────────────────────────── tmp/type_double_comma/Test.roc ─
+    ── DOUBLE COMMA [E0006] ─────────────────────── tmp/type_double_comma/Test.roc ─
 
     I just started parsing a function argument type, but I encountered two
     commas in a row:
@@ -4732,7 +4780,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── UNFINISHED TYPE ─────────────────────── tmp/type_argument_no_arrow/Test.roc ─
+    ── UNFINISHED TYPE [E0042] ─────────────── tmp/type_argument_no_arrow/Test.roc ─
 
     I am partway through parsing a type, but I got stuck here:
 
@@ -4755,7 +4803,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── UNFINISHED TYPE ───────────── tmp/type_argument_arrow_then_nothing/Test.roc ─
+    ── UNFINISHED TYPE [E0042] ───── tmp/type_argument_arrow_then_nothing/Test.roc ─
 
     I just started parsing a type, but I got stuck here:
 
@@ -4780,7 +4828,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `myDict` definition:
 
@@ -4815,7 +4863,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `myDict` definition:
 
@@ -4847,7 +4895,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── IF GUARD NO CONDITION ───────────── tmp/if_guard_without_condition/Test.roc ─
+    ── IF GUARD NO CONDITION [E0012] ───── tmp/if_guard_without_condition/Test.roc ─
 
     I just started parsing an if guard, but there is no guard condition:
 
@@ -4872,7 +4920,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── UNFINISHED PATTERN ────────────────────────── tmp/empty_or_pattern/Test.roc ─
+    ── UNFINISHED PATTERN [E0038] ────────────────── tmp/empty_or_pattern/Test.roc ─
 
     I just started parsing a pattern, but I got stuck here:
 
@@ -4897,7 +4945,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── MISSING ARROW ────────────────────────── tmp/pattern_binds_keyword/Test.roc ─
+    ── MISSING ARROW [E0016] ────────────────── tmp/pattern_binds_keyword/Test.roc ─
 
     I am partway through parsing a `when` expression, but got stuck here:
 
@@ -4933,7 +4981,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── UNFINISHED WHEN ─────────────────────────── tmp/when_missing_arrow/Test.roc ─
+    ── UNFINISHED WHEN [E0043] ─────────────────── tmp/when_missing_arrow/Test.roc ─
 
     I was partway through parsing a `when` expression, but I got stuck here:
 
@@ -4966,7 +5014,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── UNFINISHED ARGUMENT LIST ───────────────── tmp/lambda_double_comma/Test.roc ─
+    ── UNFINISHED ARGUMENT LIST [E0031] ───────── tmp/lambda_double_comma/Test.roc ─
 
     I am partway through parsing a function argument list, but I got stuck
     at this comma:
@@ -4987,7 +5035,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── UNFINISHED ARGUMENT LIST ──────────────── tmp/lambda_leading_comma/Test.roc ─
+    ── UNFINISHED ARGUMENT LIST [E0031] ──────── tmp/lambda_leading_comma/Test.roc ─
 
     I am partway through parsing a function argument list, but I got stuck
     at this comma:
@@ -5003,7 +5051,7 @@ Tab characters are not allowed."###,
     // this should get better with time
     // TODO this formerly gave
     //
-    //                ── UNFINISHED WHEN ─────────────────────────────────────────────────────────────
+    //                ── UNFINISHED WHEN [E0043] ─────────────────────────────────────────────────────
     //
     //                I was partway through parsing a `when` expression, but I got stuck here:
     //
@@ -5034,7 +5082,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── NOT END OF FILE ──────────────────────── tmp/when_outdented_branch/Test.roc ─
+    ── NOT END OF FILE [E0021] ──────────────── tmp/when_outdented_branch/Test.roc ─
 
     I expected to reach the end of the file, but got stuck here:
 
@@ -5053,7 +5101,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── UNEXPECTED ARROW ─────────────── tmp/when_over_indented_underscore/Test.roc ─
+    ── UNEXPECTED ARROW [E0029] ─────── tmp/when_over_indented_underscore/Test.roc ─
 
     I am parsing a `when` expression right now, but this arrow is confusing
     me:
@@ -5090,7 +5138,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── UNEXPECTED ARROW ────────────────────── tmp/when_over_indented_int/Test.roc ─
+    ── UNEXPECTED ARROW [E0029] ────────────── tmp/when_over_indented_int/Test.roc ─
 
     I am parsing a `when` expression right now, but this arrow is confusing
     me:
@@ -5130,7 +5178,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── UNFINISHED IF ────────────────────────────── tmp/if_outdented_then/Test.roc ─
+    ── UNFINISHED IF [E0033] ────────────────────── tmp/if_outdented_then/Test.roc ─
 
     I was partway through parsing an `if` expression, but I got stuck here:
 
@@ -5150,7 +5198,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── UNFINISHED IF ──────────────────────────────── tmp/if_missing_else/Test.roc ─
+    ── UNFINISHED IF [E0033] ──────────────────────── tmp/if_missing_else/Test.roc ─
 
     I was partway through parsing an `if` expression, but I got stuck here:
 
@@ -5169,7 +5217,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── UNFINISHED LIST ──────────────────────────── tmp/list_double_comma/Test.roc ─
+    ── UNFINISHED LIST [E0036] ──────────────────── tmp/list_double_comma/Test.roc ─
 
     I am partway through started parsing a list, but I got stuck here:
 
@@ -5189,7 +5237,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── UNFINISHED LIST ───────────────────────────── tmp/list_without_end/Test.roc ─
+    ── UNFINISHED LIST [E0036] ───────────────────── tmp/list_without_end/Test.roc ─
 
     I am partway through started parsing a list, but I got stuck here:
 
@@ -5215,7 +5263,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This float literal contains an invalid digit:
 
@@ -5233,7 +5281,7 @@ Tab characters are not allowed."###,
         unicode_not_hex,
         r#""abc\u(zzzz)def""#,
         @r###"
-    ── WEIRD CODE POINT ───────────────────────────── tmp/unicode_not_hex/Test.roc ─
+    ── WEIRD CODE POINT [E0046] ───────────────────── tmp/unicode_not_hex/Test.roc ─
 
     I am partway through parsing a unicode code point, but I got stuck
     here:
@@ -5251,7 +5299,7 @@ Tab characters are not allowed."###,
         interpolate_not_identifier,
         r#""abc\(32)def""#,
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This string interpolation is invalid:
 
@@ -5269,7 +5317,7 @@ Tab characters are not allowed."###,
         unicode_too_large,
         r#""abc\u(110000)def""#,
         @r###"
-    ── INVALID UNICODE ─────────────────────────────────────── /code/proj/Main.roc ─
+    ── INVALID UNICODE [E0051] ─────────────────────────────── /code/proj/Main.roc ─
 
     This unicode code point is invalid:
 
@@ -5284,7 +5332,7 @@ Tab characters are not allowed."###,
         weird_escape,
         r#""abc\qdef""#,
         @r###"
-    ── WEIRD ESCAPE ──────────────────────────────────── tmp/weird_escape/Test.roc ─
+    ── WEIRD ESCAPE [E0047] ──────────────────────────── tmp/weird_escape/Test.roc ─
 
     I was partway through parsing a  string literal, but I got stuck here:
 
@@ -5308,7 +5356,7 @@ Tab characters are not allowed."###,
         single_no_end,
         r#""there is no end"#,
         @r###"
-    ── ENDLESS STRING ───────────────────────────────── tmp/single_no_end/Test.roc ─
+    ── ENDLESS STRING [E0011] ───────────────────────── tmp/single_no_end/Test.roc ─
 
     I cannot find the end of this string:
 
@@ -5324,7 +5372,7 @@ Tab characters are not allowed."###,
         multi_no_end,
         r#""""there is no end"#,
         @r###"
-    ── ENDLESS STRING ────────────────────────────────── tmp/multi_no_end/Test.roc ─
+    ── ENDLESS STRING [E0011] ────────────────────────── tmp/multi_no_end/Test.roc ─
 
     I cannot find the end of this block string:
 
@@ -5340,7 +5388,7 @@ Tab characters are not allowed."###,
         multi_insufficient_indent,
         "    \"\"\"\n  testing\n    \"\"\"", // 4 space indent on the start, 2 space on the `testing` line
         @r###"
-    ── INSUFFICIENT INDENT IN MULTI-LINE STRING ─ ..._insufficient_indent/Test.roc ─
+    ── INSUFFICIENT INDENT IN MULTI-LINE STRING [E0015] ─ ...cient_indent/Test.roc ─
 
     This multiline string is not sufficiently indented:
 
@@ -5362,7 +5410,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── INDENT ENDS AFTER EXPRESSION ──── tmp/dbg_without_final_expression/Test.roc ─
+    ── INDENT ENDS AFTER EXPRESSION [E0014] ─ ...without_final_expression/Test.roc ─
 
     I am partway through parsing a dbg statement, but I got stuck here:
 
@@ -5384,7 +5432,7 @@ Tab characters are not allowed."###,
             "#
         ),
         @r###"
-    ── INDENT ENDS AFTER EXPRESSION ─ tmp/expect_without_final_expression/Test.roc ─
+    ── INDENT ENDS AFTER EXPRESSION [E0014] ─ ...without_final_expression/Test.roc ─
 
     I am partway through parsing an expect statement, but I got stuck
     here:
@@ -5410,7 +5458,7 @@ Tab characters are not allowed."###,
             "#,
             ),
             @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This `if` has an `else` branch with a different type from its `then` branch:
 
@@ -5436,7 +5484,7 @@ Tab characters are not allowed."###,
                 $name,
                 &format!(r#"if Bool.true then "abc" else 1 {} 2"#, $op),
                 |golden| assert_eq!(golden, format!(
-r#"── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+r#"── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
 This `if` has an `else` branch with a different type from its `then` branch:
 
@@ -5479,7 +5527,7 @@ All branches in an `if` must have the same type!
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This `foo` record doesn’t have a `if` field:
 
@@ -5498,7 +5546,7 @@ All branches in an `if` must have the same type!
             "#
         ),
         @r###"
-    ── NOT EXPOSED ─────────────────────────────────────────── /code/proj/Main.roc ─
+    ── NOT EXPOSED [E5002] ─────────────────────────────────── /code/proj/Main.roc ─
 
     The Num module does not expose `if`:
 
@@ -5522,7 +5570,7 @@ All branches in an `if` must have the same type!
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     I trying to parse a record field access here:
 
@@ -5541,7 +5589,7 @@ All branches in an `if` must have the same type!
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     I am very confused by this field access:
 
@@ -5560,7 +5608,7 @@ All branches in an `if` must have the same type!
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     I am very confused by this field access
 
@@ -5581,7 +5629,7 @@ All branches in an `if` must have the same type!
             "#
         ),
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     I trying to parse a record field access here:
 
@@ -5600,7 +5648,7 @@ All branches in an `if` must have the same type!
             "#
         ),
         @r###"
-    ── NAMING PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── NAMING PROBLEM [E2011] ──────────────────────────────── /code/proj/Main.roc ─
 
     I am trying to parse an identifier here:
 
@@ -5634,7 +5682,7 @@ All branches in an `if` must have the same type!
             "#
         ),
         @r###"
-    ── UNKNOWN OPERATOR ───────────────────────────────────── tmp/case_of/Test.roc ─
+    ── UNKNOWN OPERATOR [E0044] ───────────────────────────── tmp/case_of/Test.roc ─
 
     This looks like an operator, but it's not one I recognize!
 
@@ -5661,7 +5709,7 @@ All branches in an `if` must have the same type!
             "#
         ),
         @r###"
-    ── UNRECOGNIZED NAME ───────────────────────────────────── /code/proj/Main.roc ─
+    ── UNRECOGNIZED NAME [E2001] ───────────────────────────── /code/proj/Main.roc ─
 
     Nothing is named `bar` in this scope.
 
@@ -5686,7 +5734,7 @@ All branches in an `if` must have the same type!
             "#
         ),
         @r###"
-    ── UNKNOWN OPERATOR ──────────────────────────── tmp/invalid_operator/Test.roc ─
+    ── UNKNOWN OPERATOR [E0044] ──────────────────── tmp/invalid_operator/Test.roc ─
 
     This looks like an operator, but it's not one I recognize!
 
@@ -5711,7 +5759,7 @@ All branches in an `if` must have the same type!
             "#
         ),
         @r###"
-    ── UNKNOWN OPERATOR ───────────────────────────────── tmp/double_plus/Test.roc ─
+    ── UNKNOWN OPERATOR [E0044] ───────────────────────── tmp/double_plus/Test.roc ─
 
     This looks like an operator, but it's not one I recognize!
 
@@ -5738,7 +5786,7 @@ All branches in an `if` must have the same type!
             "#
         ),
         @r###"
-    ── UNKNOWN OPERATOR ────────────────────────────── tmp/inline_hastype/Test.roc ─
+    ── UNKNOWN OPERATOR [E0044] ────────────────────── tmp/inline_hastype/Test.roc ─
 
     This looks like an operator, but it's not one I recognize!
 
@@ -5769,7 +5817,7 @@ All branches in an `if` must have the same type!
         |golden| pretty_assertions::assert_eq!(
             golden,
             &format!(
-                r###"── UNKNOWN OPERATOR ───────────────────────────── tmp/wild_case_arrow/Test.roc ─
+                r###"── UNKNOWN OPERATOR [E0044] ───────────────────── tmp/wild_case_arrow/Test.roc ─
 
 This looks like an operator, but it's not one I recognize!
 
@@ -5802,7 +5850,7 @@ In roc, functions are always written as a lambda, like{}
             ),
             indoc!(
                 r#"
-                ── WEIRD PROVIDES ──────────────────────────────────────── /code/proj/Main.roc ─
+                ── WEIRD PROVIDES [E5025] ──────────────────────────────── /code/proj/Main.roc ─
 
                 I am partway through parsing a provides list, but I got stuck here:
 
@@ -5839,7 +5887,7 @@ In roc, functions are always written as a lambda, like{}
             ),
             indoc!(
                 r#"
-                ── BAD REQUIRES ────────────────────────────────────────── /code/proj/Main.roc ─
+                ── BAD REQUIRES [E5011] ────────────────────────────────── /code/proj/Main.roc ─
 
                 I am partway through parsing a header, but I got stuck here:
 
@@ -5867,7 +5915,7 @@ In roc, functions are always written as a lambda, like{}
             ),
             indoc!(
                 r#"
-                ── WEIRD IMPORTS ───────────────────────────────────────── /code/proj/Main.roc ─
+                ── WEIRD IMPORTS [E5023] ───────────────────────────────── /code/proj/Main.roc ─
 
                 I am partway through parsing a header, but I got stuck here:
 
@@ -5894,7 +5942,7 @@ In roc, functions are always written as a lambda, like{}
             ),
             indoc!(
                 r#"
-                ── WEIRD EXPOSES ───────────────────────────────────────── /code/proj/Main.roc ─
+                ── WEIRD EXPOSES [E5020] ───────────────────────────────── /code/proj/Main.roc ─
 
                 I am partway through parsing an `exposes` list, but I got stuck here:
 
@@ -5922,7 +5970,7 @@ In roc, functions are always written as a lambda, like{}
             ),
             indoc!(
                 r#"
-                ── WEIRD MODULE NAME ───────────────────────────────────── /code/proj/Main.roc ─
+                ── WEIRD MODULE NAME [E5024] ───────────────────────────── /code/proj/Main.roc ─
 
                 I am partway through parsing a header, but got stuck here:
 
@@ -5948,7 +5996,7 @@ In roc, functions are always written as a lambda, like{}
             ),
             indoc!(
                 r#"
-                ── WEIRD APP NAME ──────────────────────────────────────── /code/proj/Main.roc ─
+                ── WEIRD APP NAME [E5019] ──────────────────────────────── /code/proj/Main.roc ─
 
                 I am partway through parsing a header, but got stuck here:
 
@@ -5972,7 +6020,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TOO MANY ARGS ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TOO MANY ARGS [E1002] ───────────────────────────────── /code/proj/Main.roc ─
 
     This value is not a function, but it was given 2 arguments:
 
@@ -5993,7 +6041,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TOO MANY ARGS ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TOO MANY ARGS [E1002] ───────────────────────────────── /code/proj/Main.roc ─
 
     This value is not a function, but it was given 2 arguments:
 
@@ -6015,7 +6063,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `x` definition:
 
@@ -6041,7 +6089,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── UNFINISHED PARENTHESES ──────────────── tmp/pattern_in_parens_open/Test.roc ─
+    ── UNFINISHED PARENTHESES [E0037] ──────── tmp/pattern_in_parens_open/Test.roc ─
 
     I am partway through parsing a pattern in parentheses, but I got stuck
     here:
@@ -6064,7 +6112,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── UNFINISHED PARENTHESES ─────────── tmp/pattern_in_parens_end_comma/Test.roc ─
+    ── UNFINISHED PARENTHESES [E0037] ─── tmp/pattern_in_parens_end_comma/Test.roc ─
 
     I am partway through parsing a pattern in parentheses, but I got stuck
     here:
@@ -6087,7 +6135,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── UNFINISHED PARENTHESES ───────────────── tmp/pattern_in_parens_end/Test.roc ─
+    ── UNFINISHED PARENTHESES [E0037] ───────── tmp/pattern_in_parens_end/Test.roc ─
 
     I am partway through parsing a pattern in parentheses, but I got stuck
     here:
@@ -6111,7 +6159,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── UNFINISHED FUNCTION ───── tmp/unfinished_closure_pattern_in_parens/Test.roc ─
+    ── UNFINISHED FUNCTION [E0032] ─ ...inished_closure_pattern_in_parens/Test.roc ─
 
     I was partway through parsing a  function, but I got stuck here:
 
@@ -6131,7 +6179,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── UNFINISHED PARENTHESES ───────── tmp/pattern_in_parens_indent_open/Test.roc ─
+    ── UNFINISHED PARENTHESES [E0037] ─ tmp/pattern_in_parens_indent_open/Test.roc ─
 
     I just started parsing a pattern in parentheses, but I got stuck here:
 
@@ -6155,7 +6203,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 2nd argument to `map` has an unexpected type:
 
@@ -6183,7 +6231,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This `expect` condition needs to be a Bool:
 
@@ -6210,7 +6258,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 2nd argument to `mul` has an unexpected type:
 
@@ -6225,7 +6273,7 @@ In roc, functions are always written as a lambda, like{}
 
         Num *
 
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `mult` definition:
 
@@ -6254,7 +6302,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 2nd argument to `mul` has an unexpected type:
 
@@ -6269,7 +6317,7 @@ In roc, functions are always written as a lambda, like{}
 
         Num a
 
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `mult` definition:
 
@@ -6304,7 +6352,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── DUPLICATE NAME ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── DUPLICATE NAME [E2005] ──────────────────────────────── /code/proj/Main.roc ─
 
     This alias has the same name as a builtin:
 
@@ -6314,7 +6362,7 @@ In roc, functions are always written as a lambda, like{}
     All builtin aliases are in scope by default, so I need this alias to
     have a different name!
 
-    ── TOO FEW TYPE ARGUMENTS ──────────────────────────────── /code/proj/Main.roc ─
+    ── TOO FEW TYPE ARGUMENTS [E1008] ──────────────────────── /code/proj/Main.roc ─
 
     The `Result` alias expects 2 type arguments, but it got 1 instead:
 
@@ -6342,7 +6390,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── DUPLICATE NAME ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── DUPLICATE NAME [E2005] ──────────────────────────────── /code/proj/Main.roc ─
 
     This alias has the same name as a builtin:
 
@@ -6352,7 +6400,7 @@ In roc, functions are always written as a lambda, like{}
     All builtin aliases are in scope by default, so I need this alias to
     have a different name!
 
-    ── TOO MANY TYPE ARGUMENTS ─────────────────────────────── /code/proj/Main.roc ─
+    ── TOO MANY TYPE ARGUMENTS [E1007] ─────────────────────── /code/proj/Main.roc ─
 
     The `Result` alias expects 2 type arguments, but it got 3 instead:
 
@@ -6374,7 +6422,7 @@ In roc, functions are always written as a lambda, like{}
         ),
         // TODO: We should tell the user that we inferred `_` as `a`
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `f` definition:
 
@@ -6408,7 +6456,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `f` definition:
 
@@ -6440,7 +6488,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `f` definition:
 
@@ -6476,7 +6524,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `inner` definition:
 
@@ -6505,7 +6553,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── NOT AN INLINE ALIAS ────────── tmp/error_inline_alias_not_an_alias/Test.roc ─
+    ── NOT AN INLINE ALIAS [E0020] ── tmp/error_inline_alias_not_an_alias/Test.roc ─
 
     The inline type after this `as` is not a type alias:
 
@@ -6525,7 +6573,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── QUALIFIED ALIAS NAME ──────────── tmp/error_inline_alias_qualified/Test.roc ─
+    ── QUALIFIED ALIAS NAME [E0024] ──── tmp/error_inline_alias_qualified/Test.roc ─
 
     This type alias has a qualified name:
 
@@ -6545,7 +6593,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE ARGUMENT NOT LOWERCASE ─ ...r_inline_alias_argument_uppercase/Test.roc ─
+    ── TYPE ARGUMENT NOT LOWERCASE [E0028] ─ ..._alias_argument_uppercase/Test.roc ─
 
     This alias type argument is not lowercase:
 
@@ -6569,7 +6617,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 1st argument to `isEmpty` has an unexpected type:
 
@@ -6605,7 +6653,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 1st argument to `c` has an unexpected type:
 
@@ -6638,7 +6686,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── CYCLIC ALIAS ────────────────────────────────────────── /code/proj/Main.roc ─
+    ── CYCLIC ALIAS [E1005] ────────────────────────────────── /code/proj/Main.roc ─
 
     The `F` alias is self-recursive in an invalid way:
 
@@ -6661,7 +6709,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── CYCLIC ALIAS ────────────────────────────────────────── /code/proj/Main.roc ─
+    ── CYCLIC ALIAS [E1005] ────────────────────────────────── /code/proj/Main.roc ─
 
     The `F` alias is self-recursive in an invalid way:
 
@@ -6683,7 +6731,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── CYCLIC ALIAS ────────────────────────────────────────── /code/proj/Main.roc ─
+    ── CYCLIC ALIAS [E1005] ────────────────────────────────── /code/proj/Main.roc ─
 
     The `F` alias is self-recursive in an invalid way:
 
@@ -6708,7 +6756,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     The 1st argument to `job` is weird:
 
@@ -6743,7 +6791,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `job` definition:
 
@@ -6774,7 +6822,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── NESTED DATATYPE ─────────────────────────────────────── /code/proj/Main.roc ─
+    ── NESTED DATATYPE [E1006] ─────────────────────────────── /code/proj/Main.roc ─
 
     `Nested` is a nested datatype. Here is one recursive usage of it:
 
@@ -6802,7 +6850,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── NESTED DATATYPE ─────────────────────────────────────── /code/proj/Main.roc ─
+    ── NESTED DATATYPE [E1006] ─────────────────────────────── /code/proj/Main.roc ─
 
     `Nested` is a nested datatype. Here is one recursive usage of it:
 
@@ -6850,7 +6898,7 @@ In roc, functions are always written as a lambda, like{}
 
                     let real = format!(indoc!(
                         r#"
-                        ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+                        ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
                         This 1st argument to `use` has an unexpected type:
 
@@ -6917,7 +6965,7 @@ In roc, functions are always written as a lambda, like{}
 
                     let real = format!(indoc!(
                         r#"
-                        ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+                        ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
                         The branches of this `when` expression don't match the condition:
 
@@ -6969,7 +7017,7 @@ In roc, functions are always written as a lambda, like{}
         ),
         // TODO: link to number suffixes
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This integer literal contains an invalid digit:
 
@@ -6992,7 +7040,7 @@ In roc, functions are always written as a lambda, like{}
         ),
         // TODO: link to number suffixes
         @r###"
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     This integer literal contains an invalid digit:
 
@@ -7014,7 +7062,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── CONFLICTING NUMBER SUFFIX ───────────────────────────── /code/proj/Main.roc ─
+    ── CONFLICTING NUMBER SUFFIX [E4001] ───────────────────── /code/proj/Main.roc ─
 
     This number literal is an integer, but it has a float suffix:
 
@@ -7031,7 +7079,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── CONFLICTING NUMBER SUFFIX ───────────────────────────── /code/proj/Main.roc ─
+    ── CONFLICTING NUMBER SUFFIX [E4001] ───────────────────── /code/proj/Main.roc ─
 
     This number literal is a float, but it has an integer suffix:
 
@@ -7044,7 +7092,7 @@ In roc, functions are always written as a lambda, like{}
         u8_overflow,
         "256u8",
         @r###"
-    ── NUMBER OVERFLOWS SUFFIX ─────────────────────────────── /code/proj/Main.roc ─
+    ── NUMBER OVERFLOWS SUFFIX [E4004] ─────────────────────── /code/proj/Main.roc ─
 
     This integer literal overflows the type indicated by its suffix:
 
@@ -7060,7 +7108,7 @@ In roc, functions are always written as a lambda, like{}
         negative_u8,
         "-1u8",
         @r###"
-    ── NUMBER UNDERFLOWS SUFFIX ────────────────────────────── /code/proj/Main.roc ─
+    ── NUMBER UNDERFLOWS SUFFIX [E4005] ────────────────────── /code/proj/Main.roc ─
 
     This integer literal underflows the type indicated by its suffix:
 
@@ -7076,7 +7124,7 @@ In roc, functions are always written as a lambda, like{}
         u16_overflow,
         "65536u16",
         @r###"
-    ── NUMBER OVERFLOWS SUFFIX ─────────────────────────────── /code/proj/Main.roc ─
+    ── NUMBER OVERFLOWS SUFFIX [E4004] ─────────────────────── /code/proj/Main.roc ─
 
     This integer literal overflows the type indicated by its suffix:
 
@@ -7092,7 +7140,7 @@ In roc, functions are always written as a lambda, like{}
         negative_u16,
         "-1u16",
         @r###"
-    ── NUMBER UNDERFLOWS SUFFIX ────────────────────────────── /code/proj/Main.roc ─
+    ── NUMBER UNDERFLOWS SUFFIX [E4005] ────────────────────── /code/proj/Main.roc ─
 
     This integer literal underflows the type indicated by its suffix:
 
@@ -7108,7 +7156,7 @@ In roc, functions are always written as a lambda, like{}
         u32_overflow,
         "4_294_967_296u32",
         @r###"
-    ── NUMBER OVERFLOWS SUFFIX ─────────────────────────────── /code/proj/Main.roc ─
+    ── NUMBER OVERFLOWS SUFFIX [E4004] ─────────────────────── /code/proj/Main.roc ─
 
     This integer literal overflows the type indicated by its suffix:
 
@@ -7124,7 +7172,7 @@ In roc, functions are always written as a lambda, like{}
         negative_u32,
         "-1u32",
         @r###"
-    ── NUMBER UNDERFLOWS SUFFIX ────────────────────────────── /code/proj/Main.roc ─
+    ── NUMBER UNDERFLOWS SUFFIX [E4005] ────────────────────── /code/proj/Main.roc ─
 
     This integer literal underflows the type indicated by its suffix:
 
@@ -7140,7 +7188,7 @@ In roc, functions are always written as a lambda, like{}
         u64_overflow,
         "18_446_744_073_709_551_616u64",
         @r###"
-    ── NUMBER OVERFLOWS SUFFIX ─────────────────────────────── /code/proj/Main.roc ─
+    ── NUMBER OVERFLOWS SUFFIX [E4004] ─────────────────────── /code/proj/Main.roc ─
 
     This integer literal overflows the type indicated by its suffix:
 
@@ -7156,7 +7204,7 @@ In roc, functions are always written as a lambda, like{}
         negative_u64,
         "-1u64",
         @r###"
-    ── NUMBER UNDERFLOWS SUFFIX ────────────────────────────── /code/proj/Main.roc ─
+    ── NUMBER UNDERFLOWS SUFFIX [E4005] ────────────────────── /code/proj/Main.roc ─
 
     This integer literal underflows the type indicated by its suffix:
 
@@ -7172,7 +7220,7 @@ In roc, functions are always written as a lambda, like{}
         negative_u128,
         "-1u128",
         @r###"
-    ── NUMBER UNDERFLOWS SUFFIX ────────────────────────────── /code/proj/Main.roc ─
+    ── NUMBER UNDERFLOWS SUFFIX [E4005] ────────────────────── /code/proj/Main.roc ─
 
     This integer literal underflows the type indicated by its suffix:
 
@@ -7188,7 +7236,7 @@ In roc, functions are always written as a lambda, like{}
         i8_overflow,
         "128i8",
         @r###"
-    ── NUMBER OVERFLOWS SUFFIX ─────────────────────────────── /code/proj/Main.roc ─
+    ── NUMBER OVERFLOWS SUFFIX [E4004] ─────────────────────── /code/proj/Main.roc ─
 
     This integer literal overflows the type indicated by its suffix:
 
@@ -7204,7 +7252,7 @@ In roc, functions are always written as a lambda, like{}
         i8_underflow,
         "-129i8",
         @r###"
-    ── NUMBER UNDERFLOWS SUFFIX ────────────────────────────── /code/proj/Main.roc ─
+    ── NUMBER UNDERFLOWS SUFFIX [E4005] ────────────────────── /code/proj/Main.roc ─
 
     This integer literal underflows the type indicated by its suffix:
 
@@ -7220,7 +7268,7 @@ In roc, functions are always written as a lambda, like{}
         i16_overflow,
         "32768i16",
         @r###"
-    ── NUMBER OVERFLOWS SUFFIX ─────────────────────────────── /code/proj/Main.roc ─
+    ── NUMBER OVERFLOWS SUFFIX [E4004] ─────────────────────── /code/proj/Main.roc ─
 
     This integer literal overflows the type indicated by its suffix:
 
@@ -7236,7 +7284,7 @@ In roc, functions are always written as a lambda, like{}
         i16_underflow,
         "-32769i16",
         @r###"
-    ── NUMBER UNDERFLOWS SUFFIX ────────────────────────────── /code/proj/Main.roc ─
+    ── NUMBER UNDERFLOWS SUFFIX [E4005] ────────────────────── /code/proj/Main.roc ─
 
     This integer literal underflows the type indicated by its suffix:
 
@@ -7252,7 +7300,7 @@ In roc, functions are always written as a lambda, like{}
         i32_overflow,
         "2_147_483_648i32",
         @r###"
-    ── NUMBER OVERFLOWS SUFFIX ─────────────────────────────── /code/proj/Main.roc ─
+    ── NUMBER OVERFLOWS SUFFIX [E4004] ─────────────────────── /code/proj/Main.roc ─
 
     This integer literal overflows the type indicated by its suffix:
 
@@ -7268,7 +7316,7 @@ In roc, functions are always written as a lambda, like{}
         i32_underflow,
         "-2_147_483_649i32",
         @r###"
-    ── NUMBER UNDERFLOWS SUFFIX ────────────────────────────── /code/proj/Main.roc ─
+    ── NUMBER UNDERFLOWS SUFFIX [E4005] ────────────────────── /code/proj/Main.roc ─
 
     This integer literal underflows the type indicated by its suffix:
 
@@ -7284,7 +7332,7 @@ In roc, functions are always written as a lambda, like{}
         i64_overflow,
         "9_223_372_036_854_775_808i64",
         @r###"
-    ── NUMBER OVERFLOWS SUFFIX ─────────────────────────────── /code/proj/Main.roc ─
+    ── NUMBER OVERFLOWS SUFFIX [E4004] ─────────────────────── /code/proj/Main.roc ─
 
     This integer literal overflows the type indicated by its suffix:
 
@@ -7300,7 +7348,7 @@ In roc, functions are always written as a lambda, like{}
         i64_underflow,
         "-9_223_372_036_854_775_809i64",
         @r###"
-    ── NUMBER UNDERFLOWS SUFFIX ────────────────────────────── /code/proj/Main.roc ─
+    ── NUMBER UNDERFLOWS SUFFIX [E4005] ────────────────────── /code/proj/Main.roc ─
 
     This integer literal underflows the type indicated by its suffix:
 
@@ -7316,7 +7364,7 @@ In roc, functions are always written as a lambda, like{}
         i128_overflow,
         "170_141_183_460_469_231_731_687_303_715_884_105_728i128",
         @r###"
-    ── NUMBER OVERFLOWS SUFFIX ─────────────────────────────── /code/proj/Main.roc ─
+    ── NUMBER OVERFLOWS SUFFIX [E4004] ─────────────────────── /code/proj/Main.roc ─
 
     This integer literal overflows the type indicated by its suffix:
 
@@ -7338,7 +7386,7 @@ In roc, functions are always written as a lambda, like{}
         // TODO: this error message could be improved, e.g. something like "This argument can
         // be used as ... because of its literal value"
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 2nd argument to `get` has an unexpected type:
 
@@ -7364,7 +7412,7 @@ In roc, functions are always written as a lambda, like{}
              "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 2nd argument to `get` has an unexpected type:
 
@@ -7391,7 +7439,7 @@ In roc, functions are always written as a lambda, like{}
              "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 2nd argument to `get` has an unexpected type:
 
@@ -7418,7 +7466,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     The branches of this `when` expression don't match the condition:
 
@@ -7449,7 +7497,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── CYCLIC ALIAS ────────────────────────────────────────── /code/proj/Main.roc ─
+    ── CYCLIC ALIAS [E1005] ────────────────────────────────── /code/proj/Main.roc ─
 
     The `R` alias is self-recursive in an invalid way:
 
@@ -7472,7 +7520,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── CYCLIC ALIAS ────────────────────────────────────────── /code/proj/Main.roc ─
+    ── CYCLIC ALIAS [E1005] ────────────────────────────────── /code/proj/Main.roc ─
 
     The `R` alias is self-recursive in an invalid way:
 
@@ -7496,7 +7544,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── CYCLIC ALIAS ────────────────────────────────────────── /code/proj/Main.roc ─
+    ── CYCLIC ALIAS [E1005] ────────────────────────────────── /code/proj/Main.roc ─
 
     The `Foo` alias is recursive in an invalid way:
 
@@ -7531,7 +7579,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── DUPLICATE NAME ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── DUPLICATE NAME [E2005] ──────────────────────────────── /code/proj/Main.roc ─
 
     This alias has the same name as a builtin:
 
@@ -7551,7 +7599,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── OPAQUE TYPE NOT DEFINED ─────────────────────────────── /code/proj/Main.roc ─
+    ── OPAQUE TYPE NOT DEFINED [E1011] ─────────────────────── /code/proj/Main.roc ─
 
     The opaque type Age referenced here is not defined:
 
@@ -7572,7 +7620,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── OPAQUE TYPE NOT DEFINED ─────────────────────────────── /code/proj/Main.roc ─
+    ── OPAQUE TYPE NOT DEFINED [E1011] ─────────────────────── /code/proj/Main.roc ─
 
     The opaque type Age referenced here is not defined:
 
@@ -7586,7 +7634,7 @@ In roc, functions are always written as a lambda, like{}
 
     Note: It looks like there are no opaque types declared in this scope yet!
 
-    ── UNUSED DEFINITION ───────────────────────────────────── /code/proj/Main.roc ─
+    ── UNUSED DEFINITION [E2002] ───────────────────────────── /code/proj/Main.roc ─
 
     `Age` is not used anywhere in your code.
 
@@ -7609,7 +7657,7 @@ In roc, functions are always written as a lambda, like{}
         // and checking it during can. The reason the error appears is because it is parsed as
         // Apply(Error(OtherModule), [@Age, 21])
         @r###"
-    ── OPAQUE TYPE NOT DEFINED ─────────────────────────────── /code/proj/Main.roc ─
+    ── OPAQUE TYPE NOT DEFINED [E1011] ─────────────────────── /code/proj/Main.roc ─
 
     The opaque type Age referenced here is not defined:
 
@@ -7618,7 +7666,7 @@ In roc, functions are always written as a lambda, like{}
 
     Note: It looks like there are no opaque types declared in this scope yet!
 
-    ── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── SYNTAX PROBLEM [E0001] ──────────────────────────────── /code/proj/Main.roc ─
 
     I am trying to parse a qualified name here:
 
@@ -7645,7 +7693,7 @@ In roc, functions are always written as a lambda, like{}
         // `@Age` can be linked to the declaration of `Age` inside `age`, and a suggestion to
         // raise that declaration to the outer scope.
         @r###"
-    ── UNUSED DEFINITION ───────────────────────────────────── /code/proj/Main.roc ─
+    ── UNUSED DEFINITION [E2002] ───────────────────────────── /code/proj/Main.roc ─
 
     `Age` is not used anywhere in your code.
 
@@ -7655,7 +7703,7 @@ In roc, functions are always written as a lambda, like{}
     If you didn't intend on using `Age` then remove it so future readers of
     your code don't wonder why it is there.
 
-    ── OPAQUE TYPE NOT DEFINED ─────────────────────────────── /code/proj/Main.roc ─
+    ── OPAQUE TYPE NOT DEFINED [E1011] ─────────────────────── /code/proj/Main.roc ─
 
     The opaque type Age referenced here is not defined:
 
@@ -7676,7 +7724,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── MODULE NOT IMPORTED ─────────────────────────────────── /code/proj/Main.roc ─
+    ── MODULE NOT IMPORTED [E5001] ─────────────────────────── /code/proj/Main.roc ─
 
     The `Task` module is not imported:
 
@@ -7708,7 +7756,7 @@ In roc, functions are always written as a lambda, like{}
         // TODO(opaques): error could be improved by saying that the opaque definition demands
         // that the argument be a U8, and linking to the definitin!
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression is used in an unexpected way:
 
@@ -7737,7 +7785,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression is used in an unexpected way:
 
@@ -7767,7 +7815,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `v` definition:
 
@@ -7805,7 +7853,7 @@ In roc, functions are always written as a lambda, like{}
         // TODO(opaques): error could be improved by saying that the user-provided pattern
         // probably wants to change "Age" to "@Age"!
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     The 1st argument to `f` is weird:
 
@@ -7840,7 +7888,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     The 2nd pattern in this `when` does not match the previous ones:
 
@@ -7871,7 +7919,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     The branches of this `when` expression don't match the condition:
 
@@ -7909,7 +7957,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -7942,7 +7990,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 1st argument to `y` has an unexpected type:
 
@@ -7975,7 +8023,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -7999,7 +8047,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── INVALID_EXTENSION_TYPE ──────────────────────────────── /code/proj/Main.roc ─
+    ── INVALID_EXTENSION_TYPE [E1015] ──────────────────────── /code/proj/Main.roc ─
 
     This record extension type is invalid:
 
@@ -8020,7 +8068,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── INVALID_EXTENSION_TYPE ──────────────────────────────── /code/proj/Main.roc ─
+    ── INVALID_EXTENSION_TYPE [E1015] ──────────────────────── /code/proj/Main.roc ─
 
     This tag union extension type is invalid:
 
@@ -8047,7 +8095,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── UNRECOGNIZED NAME ───────────────────────────────────── /code/proj/Main.roc ─
+    ── UNRECOGNIZED NAME [E2001] ───────────────────────────── /code/proj/Main.roc ─
 
     Nothing is named `UnknownType` in this scope.
 
@@ -8061,7 +8109,7 @@ In roc, functions are always written as a lambda, like{}
         Unsigned32
         Unsigned16
 
-    ── UNRECOGNIZED NAME ───────────────────────────────────── /code/proj/Main.roc ─
+    ── UNRECOGNIZED NAME [E2001] ───────────────────────────── /code/proj/Main.roc ─
 
     Nothing is named `UnknownType` in this scope.
 
@@ -8088,7 +8136,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── UNFINISHED ABILITY ── tmp/ability_first_demand_not_indented_enough/Test.roc ─
+    ── UNFINISHED ABILITY [E0030] ─ ...y_first_demand_not_indented_enough/Test.roc ─
 
     I was partway through parsing an ability definition, but I got stuck
     here:
@@ -8113,7 +8161,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r#"
-        ── UNFINISHED ABILITY ─── tmp/ability_demands_not_indented_with_first/Test.roc ─
+        ── UNFINISHED ABILITY [E0030] ─ ...ty_demands_not_indented_with_first/Test.roc ─
 
         I was partway through parsing an ability definition, but I got stuck
         here:
@@ -8136,7 +8184,7 @@ In roc, functions are always written as a lambda, like{}
                 "#
         ),
         @r#"
-        ── UNFINISHED ABILITY ───────────── tmp/ability_demand_value_has_args/Test.roc ─
+        ── UNFINISHED ABILITY [E0030] ───── tmp/ability_demand_value_has_args/Test.roc ─
 
         I was partway through parsing an ability definition, but I got stuck
         here:
@@ -8160,7 +8208,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── UNFINISHED ABILITY ────────── tmp/ability_non_signature_expression/Test.roc ─
+    ── UNFINISHED ABILITY [E0030] ── tmp/ability_non_signature_expression/Test.roc ─
 
     I was partway through parsing an ability definition, but I got stuck
     here:
@@ -8183,7 +8231,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── UNBOUND TYPE VARIABLE ───────────────────────────────── /code/proj/Main.roc ─
+    ── UNBOUND TYPE VARIABLE [E1009] ───────────────────────── /code/proj/Main.roc ─
 
     The definition of `I` has an unbound type variable:
 
@@ -8205,7 +8253,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── UNBOUND TYPE VARIABLE ───────────────────────────────── /code/proj/Main.roc ─
+    ── UNBOUND TYPE VARIABLE [E1009] ───────────────────────── /code/proj/Main.roc ─
 
     The definition of `I` has an unbound type variable:
 
@@ -8227,7 +8275,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── UNBOUND TYPE VARIABLE ───────────────────────────────── /code/proj/Main.roc ─
+    ── UNBOUND TYPE VARIABLE [E1009] ───────────────────────── /code/proj/Main.roc ─
 
     The definition of `I` has 2 unbound type variables.
 
@@ -8251,7 +8299,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── UNBOUND TYPE VARIABLE ───────────────────────────────── /code/proj/Main.roc ─
+    ── UNBOUND TYPE VARIABLE [E1009] ───────────────────────── /code/proj/Main.roc ─
 
     The definition of `I` has an unbound type variable:
 
@@ -8273,7 +8321,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── UNBOUND TYPE VARIABLE ───────────────────────────────── /code/proj/Main.roc ─
+    ── UNBOUND TYPE VARIABLE [E1009] ───────────────────────── /code/proj/Main.roc ─
 
     The definition of `I` has an unbound type variable:
 
@@ -8296,7 +8344,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── ABILITY HAS TYPE VARIABLES ──────────────────────────── /code/proj/Main.roc ─
+    ── ABILITY HAS TYPE VARIABLES [E3001] ──────────────────── /code/proj/Main.roc ─
 
     The definition of the `MHash` ability includes type variables:
 
@@ -8306,7 +8354,7 @@ In roc, functions are always written as a lambda, like{}
     Abilities cannot depend on type variables, but their member values
     can!
 
-    ── UNUSED DEFINITION ───────────────────────────────────── /code/proj/Main.roc ─
+    ── UNUSED DEFINITION [E2002] ───────────────────────────── /code/proj/Main.roc ─
 
     `MHash` is not used anywhere in your code.
 
@@ -8328,7 +8376,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── HAS CLAUSE IS NOT AN ABILITY ────────────────────────── /code/proj/Main.roc ─
+    ── HAS CLAUSE IS NOT AN ABILITY [E3009] ────────────────── /code/proj/Main.roc ─
 
     The type referenced in this "has" clause is not an ability:
 
@@ -8347,7 +8395,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r#"
-        ── DUPLICATE NAME ──────────────────────────────────────── /code/proj/Main.roc ─
+        ── DUPLICATE NAME [E2005] ──────────────────────────────── /code/proj/Main.roc ─
 
         The `a` name is first defined here:
 
@@ -8376,7 +8424,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r#"
-        ── DUPLICATE NAME ──────────────────────────────────────── /code/proj/Main.roc ─
+        ── DUPLICATE NAME [E2005] ──────────────────────────────── /code/proj/Main.roc ─
 
         The `Ability` name is first defined here:
 
@@ -8403,7 +8451,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r#"
-        ── ABILITY MEMBER MISSING HAS CLAUSE ───────────────────── /code/proj/Main.roc ─
+        ── ABILITY MEMBER MISSING HAS CLAUSE [E3004] ───────────── /code/proj/Main.roc ─
 
         The definition of the ability member `ab` does not include a `has` clause
         binding a type variable to the ability `Ability`:
@@ -8418,7 +8466,7 @@ In roc, functions are always written as a lambda, like{}
 
         Otherwise, the function does not need to be part of the ability!
 
-        ── UNUSED DEFINITION ───────────────────────────────────── /code/proj/Main.roc ─
+        ── UNUSED DEFINITION [E2002] ───────────────────────────── /code/proj/Main.roc ─
 
         `Ability` is not used anywhere in your code.
 
@@ -8440,7 +8488,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r#"
-        ── ABILITY MEMBER BINDS MULTIPLE VARIABLES ─────────────── /code/proj/Main.roc ─
+        ── ABILITY MEMBER BINDS MULTIPLE VARIABLES [E3003] ─────── /code/proj/Main.roc ─
 
         The definition of the ability member `eq` includes multiple variables
         bound to the `MEq`` ability:`
@@ -8468,7 +8516,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── ILLEGAL HAS CLAUSE ──────────────────────────────────── /code/proj/Main.roc ─
+    ── ILLEGAL HAS CLAUSE [E3011] ──────────────────────────── /code/proj/Main.roc ─
 
     A `has` clause is not allowed here:
 
@@ -8477,7 +8525,7 @@ In roc, functions are always written as a lambda, like{}
 
     `has` clauses can only be specified on the top-level type annotations.
 
-    ── ABILITY MEMBER MISSING HAS CLAUSE ───────────────────── /code/proj/Main.roc ─
+    ── ABILITY MEMBER MISSING HAS CLAUSE [E3004] ───────────── /code/proj/Main.roc ─
 
     The definition of the ability member `hash` does not include a `has`
     clause binding a type variable to the ability `MHash`:
@@ -8508,7 +8556,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r#"
-        ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+        ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
         Something is off with this specialization of `hash`:
 
@@ -8541,7 +8589,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── INCOMPLETE ABILITY IMPLEMENTATION ───────────────────── /code/proj/Main.roc ─
+    ── INCOMPLETE ABILITY IMPLEMENTATION [E3014] ───────────── /code/proj/Main.roc ─
 
     This type does not fully implement the `MEq` ability:
 
@@ -8567,7 +8615,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── UNUSED DEFINITION ───────────────────────────────────── /code/proj/Main.roc ─
+    ── UNUSED DEFINITION [E2002] ───────────────────────────── /code/proj/Main.roc ─
 
     `hash` is not used anywhere in your code.
 
@@ -8596,7 +8644,7 @@ In roc, functions are always written as a lambda, like{}
         ),
         // TODO: the error message here could be seriously improved!
         @r###"
-    ── OVERLOADED SPECIALIZATION ───────────────────────────── /code/proj/Main.roc ─
+    ── OVERLOADED SPECIALIZATION [E3018] ───────────────────── /code/proj/Main.roc ─
 
     This ability member specialization is already claimed to specialize
     another opaque type:
@@ -8609,7 +8657,7 @@ In roc, functions are always written as a lambda, like{}
     Ability specializations can only provide implementations for one
     opaque type, since all opaque types are different!
 
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This specialization of `hash` is overly general:
 
@@ -8649,7 +8697,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── OVERLOADED SPECIALIZATION ───────────────────────────── /code/proj/Main.roc ─
+    ── OVERLOADED SPECIALIZATION [E3018] ───────────────────── /code/proj/Main.roc ─
 
     This ability member specialization is already claimed to specialize
     another opaque type:
@@ -8680,7 +8728,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with this specialization of `eq`:
 
@@ -8715,7 +8763,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `hash` definition:
 
@@ -8756,7 +8804,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression has a type that does not implement the abilities it's expected to:
 
@@ -8769,7 +8817,7 @@ In roc, functions are always written as a lambda, like{}
 
     Only builtin abilities can have generated implementations!
 
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression has a type that does not implement the abilities it's expected to:
 
@@ -8794,7 +8842,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r#"
-        ── ABILITY NOT ON TOP-LEVEL ────────────────────────────── /code/proj/Main.roc ─
+        ── ABILITY NOT ON TOP-LEVEL [E3005] ────────────────────── /code/proj/Main.roc ─
 
         This ability definition is not on the top-level of a module:
 
@@ -8822,7 +8870,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `hashable` definition:
 
@@ -8868,7 +8916,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── ABILITY USED AS TYPE ────────────────────────────────── /code/proj/Main.roc ─
+    ── ABILITY USED AS TYPE [E3006] ────────────────────────── /code/proj/Main.roc ─
 
     You are attempting to use the ability `MHash` as a type directly:
 
@@ -8882,7 +8930,7 @@ In roc, functions are always written as a lambda, like{}
 
         a has MHash
 
-    ── ABILITY USED AS TYPE ────────────────────────────────── /code/proj/Main.roc ─
+    ── ABILITY USED AS TYPE [E3006] ────────────────────────── /code/proj/Main.roc ─
 
     You are attempting to use the ability `MHash` as a type directly:
 
@@ -8912,7 +8960,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r#"
-        ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+        ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
         The branches of this `when` expression don't match the condition:
 
@@ -8961,7 +9009,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r#"
-        ── WEIRD IMPORTS ────────────────────────── tmp/imports_missing_comma/Test.roc ─
+        ── WEIRD IMPORTS [E5023] ────────────────── tmp/imports_missing_comma/Test.roc ─
 
         I am partway through parsing a imports list, but I got stuck here:
 
@@ -8986,7 +9034,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r#"
-        ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+        ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
         This `when` does not cover all the possibilities:
 
@@ -9033,7 +9081,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r#"
-        ── SPECIALIZATION NOT ON TOP-LEVEL ─────────────────────── /code/proj/Main.roc ─
+        ── SPECIALIZATION NOT ON TOP-LEVEL [E3020] ─────────────── /code/proj/Main.roc ─
 
         This specialization of the `default` ability member is in a nested
         scope:
@@ -9058,7 +9106,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 2nd argument to `isEq` has an unexpected type:
 
@@ -9094,7 +9142,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 1st argument to `remove` has an unexpected type:
 
@@ -9115,7 +9163,7 @@ In roc, functions are always written as a lambda, like{}
     change the type annotation to be more specific? Maybe change the code
     to be more general?
 
-    ── CIRCULAR TYPE ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── CIRCULAR TYPE [E1004] ───────────────────────────────── /code/proj/Main.roc ─
 
     I'm inferring a weird self-referential type for `new`:
 
@@ -9128,7 +9176,7 @@ In roc, functions are always written as a lambda, like{}
 
         { set : Set ∞ }
 
-    ── CIRCULAR TYPE ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── CIRCULAR TYPE [E1004] ───────────────────────────────── /code/proj/Main.roc ─
 
     I'm inferring a weird self-referential type for `goal`:
 
@@ -9158,7 +9206,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r#"
-        ── CIRCULAR DEFINITION ─────────────────────────────────── /code/proj/Main.roc ─
+        ── CIRCULAR DEFINITION [E2008] ─────────────────────────── /code/proj/Main.roc ─
 
         The `t1` definition is causing a very tricky infinite loop:
 
@@ -9186,7 +9234,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression has a type that does not implement the abilities it's expected to:
 
@@ -9214,7 +9262,7 @@ In roc, functions are always written as a lambda, like{}
         // TODO: this error message is quite unfortunate. We should remove the duplication, and
         // also support regions that point to things in other modules. See also https://github.com/roc-lang/roc/issues/3056.
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression has a type that does not implement the abilities it's expected to:
 
@@ -9251,7 +9299,7 @@ In roc, functions are always written as a lambda, like{}
                 "#
         ),
         @r#"
-            ── CIRCULAR DEFINITION ─────────────────────────────────── /code/proj/Main.roc ─
+            ── CIRCULAR DEFINITION [E2008] ─────────────────────────── /code/proj/Main.roc ─
 
             The `t1` definition is causing a very tricky infinite loop:
 
@@ -9281,7 +9329,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── IMPLEMENTATION NOT FOUND ────────────────────────────── /code/proj/Main.roc ─
+    ── IMPLEMENTATION NOT FOUND [E3013] ────────────────────── /code/proj/Main.roc ─
 
     An implementation of `eq` could not be found in this scope:
 
@@ -9292,7 +9340,7 @@ In roc, functions are always written as a lambda, like{}
     another variable that implements this ability member, like
     { eq: myeq }
 
-    ── INCOMPLETE ABILITY IMPLEMENTATION ───────────────────── /code/proj/Main.roc ─
+    ── INCOMPLETE ABILITY IMPLEMENTATION [E3014] ───────────── /code/proj/Main.roc ─
 
     This type does not fully implement the `MEq` ability:
 
@@ -9319,7 +9367,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── UNRECOGNIZED NAME ───────────────────────────────────── /code/proj/Main.roc ─
+    ── UNRECOGNIZED NAME [E2001] ───────────────────────────── /code/proj/Main.roc ─
 
     Nothing is named `aMEq` in this scope.
 
@@ -9333,7 +9381,7 @@ In roc, functions are always written as a lambda, like{}
         myMEq
         eq
 
-    ── INCOMPLETE ABILITY IMPLEMENTATION ───────────────────── /code/proj/Main.roc ─
+    ── INCOMPLETE ABILITY IMPLEMENTATION [E3014] ───────────── /code/proj/Main.roc ─
 
     This type does not fully implement the `MEq` ability:
 
@@ -9360,7 +9408,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── OPTIONAL ABILITY IMPLEMENTATION ─────────────────────── /code/proj/Main.roc ─
+    ── OPTIONAL ABILITY IMPLEMENTATION [E3017] ─────────────── /code/proj/Main.roc ─
 
     Ability implementations cannot be optional:
 
@@ -9371,7 +9419,7 @@ In roc, functions are always written as a lambda, like{}
 
 
 
-    ── INCOMPLETE ABILITY IMPLEMENTATION ───────────────────── /code/proj/Main.roc ─
+    ── INCOMPLETE ABILITY IMPLEMENTATION [E3014] ───────────── /code/proj/Main.roc ─
 
     This type does not fully implement the `MEq` ability:
 
@@ -9398,7 +9446,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── OPTIONAL ABILITY IMPLEMENTATION ─────────────────────── /code/proj/Main.roc ─
+    ── OPTIONAL ABILITY IMPLEMENTATION [E3017] ─────────────── /code/proj/Main.roc ─
 
     Ability implementations cannot be optional:
 
@@ -9411,7 +9459,7 @@ In roc, functions are always written as a lambda, like{}
     record of implementations. For example,    has [Encoding] will attempt
     to derive `Encoding`
 
-    ── INCOMPLETE ABILITY IMPLEMENTATION ───────────────────── /code/proj/Main.roc ─
+    ── INCOMPLETE ABILITY IMPLEMENTATION [E3014] ───────────── /code/proj/Main.roc ─
 
     This type does not fully implement the `Encoding` ability:
 
@@ -9436,7 +9484,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── QUALIFIED ABILITY IMPLEMENTATION ────────────────────── /code/proj/Main.roc ─
+    ── QUALIFIED ABILITY IMPLEMENTATION [E3019] ────────────── /code/proj/Main.roc ─
 
     This ability implementation is qualified:
 
@@ -9446,7 +9494,7 @@ In roc, functions are always written as a lambda, like{}
     Custom implementations must be defined in the local scope, and
     unqualified.
 
-    ── INCOMPLETE ABILITY IMPLEMENTATION ───────────────────── /code/proj/Main.roc ─
+    ── INCOMPLETE ABILITY IMPLEMENTATION [E3014] ───────────── /code/proj/Main.roc ─
 
     This type does not fully implement the `MEq` ability:
 
@@ -9471,7 +9519,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── ABILITY IMPLEMENTATION NOT IDENTIFIER ───────────────── /code/proj/Main.roc ─
+    ── ABILITY IMPLEMENTATION NOT IDENTIFIER [E3002] ───────── /code/proj/Main.roc ─
 
     This ability implementation is not an identifier:
 
@@ -9483,7 +9531,7 @@ In roc, functions are always written as a lambda, like{}
 
     Tip: consider defining this expression as a variable.
 
-    ── INCOMPLETE ABILITY IMPLEMENTATION ───────────────────── /code/proj/Main.roc ─
+    ── INCOMPLETE ABILITY IMPLEMENTATION [E3014] ───────────── /code/proj/Main.roc ─
 
     This type does not fully implement the `MEq` ability:
 
@@ -9510,19 +9558,19 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── DUPLICATE IMPLEMENTATION ────────────────────────────── /code/proj/Main.roc ─
+    ── DUPLICATE IMPLEMENTATION [E3008] ────────────────────── /code/proj/Main.roc ─
 
     This ability member implementation is duplicate:
 
     5│  A := U8 has [ MEq {eq: eqA, eq: eqA} ]
                                     ^^^^^^^
 
+    Only one custom implementation can be defined for an ability member.
+
     The first implementation was defined here:
 
     5│  A := U8 has [ MEq {eq: eqA, eq: eqA} ]
                            ^^^^^^^
-
-    Only one custom implementation can be defined for an ability member.
     "###
     );
 
@@ -9538,7 +9586,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── NOT AN ABILITY ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── NOT AN ABILITY [E3015] ──────────────────────────────── /code/proj/Main.roc ─
 
     This identifier is not an ability in scope:
 
@@ -9561,7 +9609,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── ILLEGAL DERIVE ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── ILLEGAL DERIVE [E3010] ──────────────────────────────── /code/proj/Main.roc ─
 
     This ability cannot be derived:
 
@@ -9584,7 +9632,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── INCOMPLETE ABILITY IMPLEMENTATION ───────────────────── /code/proj/Main.roc ─
+    ── INCOMPLETE ABILITY IMPLEMENTATION [E3014] ───────────── /code/proj/Main.roc ─
 
     I can't derive an implementation of the `Encoding` ability for `A`:
 
@@ -9609,7 +9657,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── INCOMPLETE ABILITY IMPLEMENTATION ───────────────────── /code/proj/Main.roc ─
+    ── INCOMPLETE ABILITY IMPLEMENTATION [E3014] ───────────── /code/proj/Main.roc ─
 
     I can't derive an implementation of the `Encoding` ability for `A`:
 
@@ -9661,7 +9709,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── DUPLICATE NAME ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── DUPLICATE NAME [E2005] ──────────────────────────────── /code/proj/Main.roc ─
 
     The `main` name is first defined here:
 
@@ -9676,7 +9724,7 @@ In roc, functions are always written as a lambda, like{}
     Since these variables have the same name, it's easy to use the wrong
     one on accident. Give one of them a new name.
 
-    ── UNNECESSARY DEFINITION ──────────────────────────────── /code/proj/Main.roc ─
+    ── UNNECESSARY DEFINITION [E2010] ──────────────────────── /code/proj/Main.roc ─
 
     This destructure assignment doesn't introduce any new variables:
 
@@ -9710,7 +9758,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r#"
-        ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+        ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
         Something is off with the body of the `withOpen` definition:
 
@@ -9745,7 +9793,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression is used in an unexpected way:
 
@@ -9792,7 +9840,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 1st argument to `foo` has an unexpected type:
 
@@ -9837,7 +9885,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 2nd argument to `isEq` has an unexpected type:
 
@@ -9862,7 +9910,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 2nd argument to `isEq` has an unexpected type:
 
@@ -9899,7 +9947,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the 2nd branch of this `when` expression:
 
@@ -9933,7 +9981,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 2nd argument to `map` has an unexpected type:
 
@@ -9959,7 +10007,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-        ── NAME NOT BOUND IN ALL PATTERNS ──────────────────────── /code/proj/Main.roc ─
+        ── NAME NOT BOUND IN ALL PATTERNS [E1021] ──────────────── /code/proj/Main.roc ─
 
         `x` is not bound in all patterns of this `when` branch
 
@@ -9970,7 +10018,7 @@ In roc, functions are always written as a lambda, like{}
         of the branch. Otherwise, the program would crash when it tries to use
         an identifier that wasn't bound!
 
-        ── NAME NOT BOUND IN ALL PATTERNS ──────────────────────── /code/proj/Main.roc ─
+        ── NAME NOT BOUND IN ALL PATTERNS [E1021] ──────────────── /code/proj/Main.roc ─
 
         `y` is not bound in all patterns of this `when` branch
 
@@ -9981,7 +10029,7 @@ In roc, functions are always written as a lambda, like{}
         of the branch. Otherwise, the program would crash when it tries to use
         an identifier that wasn't bound!
 
-        ── UNUSED DEFINITION ───────────────────────────────────── /code/proj/Main.roc ─
+        ── UNUSED DEFINITION [E2002] ───────────────────────────── /code/proj/Main.roc ─
 
         `y` is not used in this `when` branch.
 
@@ -10003,7 +10051,7 @@ In roc, functions are always written as a lambda, like{}
             "#
         ),
         @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -10023,26 +10071,57 @@ In roc, functions are always written as a lambda, like{}
         call_with_underscore_identifier,
         indoc!(
             r#"
+            f : I64, I64, I64 -> I64
             f = \x, y, z -> x + y + z
 
             f 1 _ 1
             "#
         ),
-        |golden| pretty_assertions::assert_eq!(
-            golden,
-            &format!(
-                r###"── SYNTAX PROBLEM ──────────────────────────────────────── /code/proj/Main.roc ─
+        @r###"
+    ── TYPED HOLE [E1016] ──────────────────────────────────── /code/proj/Main.roc ─
 
-Underscores are not allowed in identifier names:
+    This hole needs to be filled in:
 
-6│      f 1 _ 1
-{}
+    7│      f 1 _ 1
+                ^
 
-I recommend using camelCase. It's the standard style in Roc code!
-"###,
-                "  " // TODO make the reporter not insert extraneous spaces here in the first place!
-            ),
-        )
+    It needs a value of type:
+
+        I64
+
+    None of the values in scope have this type.
+    "###
+    );
+
+    test_report!(
+        typed_hole_lists_values_that_fit,
+        indoc!(
+            r#"
+            greeting : Str
+            greeting = "hi"
+
+            name : Str
+            name = _
+
+            Str.concat greeting name
+            "#
+        ),
+        @r###"
+    ── TYPED HOLE [E1016] ──────────────────────────────────── /code/proj/Main.roc ─
+
+    This hole needs to be filled in:
+
+    8│      name = _
+                   ^
+
+    It needs a value of type:
+
+        Str
+
+    These values in scope have this type:
+
+        `greeting`
+    "###
     );
 
     test_report!(
@@ -10061,7 +10140,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
         @r###"
-    ── UNNECESSARY DEFINITION ──────────────────────────────── /code/proj/Main.roc ─
+    ── UNNECESSARY DEFINITION [E2010] ──────────────────────── /code/proj/Main.roc ─
 
     This destructure assignment doesn't introduce any new variables:
 
@@ -10073,7 +10152,7 @@ I recommend using camelCase. It's the standard style in Roc code!
     functional, assignments that don't introduce variables cannot affect a
     program's behavior!
 
-    ── UNNECESSARY DEFINITION ──────────────────────────────── /code/proj/Main.roc ─
+    ── UNNECESSARY DEFINITION [E2010] ──────────────────────── /code/proj/Main.roc ─
 
     This destructure assignment doesn't introduce any new variables:
 
@@ -10085,7 +10164,7 @@ I recommend using camelCase. It's the standard style in Roc code!
     functional, assignments that don't introduce variables cannot affect a
     program's behavior!
 
-    ── UNNECESSARY DEFINITION ──────────────────────────────── /code/proj/Main.roc ─
+    ── UNNECESSARY DEFINITION [E2010] ──────────────────────── /code/proj/Main.roc ─
 
     This destructure assignment doesn't introduce any new variables:
 
@@ -10097,7 +10176,7 @@ I recommend using camelCase. It's the standard style in Roc code!
     functional, assignments that don't introduce variables cannot affect a
     program's behavior!
 
-    ── UNNECESSARY DEFINITION ──────────────────────────────── /code/proj/Main.roc ─
+    ── UNNECESSARY DEFINITION [E2010] ──────────────────────── /code/proj/Main.roc ─
 
     This destructure assignment doesn't introduce any new variables:
 
@@ -10127,7 +10206,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
         @r###"
-    ── UNNECESSARY DEFINITION ──────────────────────────────── /code/proj/Main.roc ─
+    ── UNNECESSARY DEFINITION [E2010] ──────────────────────── /code/proj/Main.roc ─
 
     This destructure assignment doesn't introduce any new variables:
 
@@ -10139,7 +10218,7 @@ I recommend using camelCase. It's the standard style in Roc code!
     functional, assignments that don't introduce variables cannot affect a
     program's behavior!
 
-    ── UNNECESSARY DEFINITION ──────────────────────────────── /code/proj/Main.roc ─
+    ── UNNECESSARY DEFINITION [E2010] ──────────────────────── /code/proj/Main.roc ─
 
     This destructure assignment doesn't introduce any new variables:
 
@@ -10151,7 +10230,7 @@ I recommend using camelCase. It's the standard style in Roc code!
     functional, assignments that don't introduce variables cannot affect a
     program's behavior!
 
-    ── UNNECESSARY DEFINITION ──────────────────────────────── /code/proj/Main.roc ─
+    ── UNNECESSARY DEFINITION [E2010] ──────────────────────── /code/proj/Main.roc ─
 
     This destructure assignment doesn't introduce any new variables:
 
@@ -10163,7 +10242,7 @@ I recommend using camelCase. It's the standard style in Roc code!
     functional, assignments that don't introduce variables cannot affect a
     program's behavior!
 
-    ── UNNECESSARY DEFINITION ──────────────────────────────── /code/proj/Main.roc ─
+    ── UNNECESSARY DEFINITION [E2010] ──────────────────────── /code/proj/Main.roc ─
 
     This destructure assignment doesn't introduce any new variables:
 
@@ -10191,7 +10270,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
         @r###"
-    ── UNUSED DEFINITION ───────────────────────────────────── /code/proj/Main.roc ─
+    ── UNUSED DEFINITION [E2002] ───────────────────────────── /code/proj/Main.roc ─
 
     `hash` is not used anywhere in your code.
 
@@ -10218,7 +10297,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
         @r###"
-    ── WRONG SPECIALIZATION TYPE ───────────────────────────── /code/proj/Main.roc ─
+    ── WRONG SPECIALIZATION TYPE [E3022] ───────────────────── /code/proj/Main.roc ─
 
     This specialization of `hash` is not for the expected type:
 
@@ -10241,7 +10320,7 @@ I recommend using camelCase. It's the standard style in Roc code!
                 "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `x` definition:
 
@@ -10271,7 +10350,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
         @r###"
-    ── CYCLIC ALIAS ────────────────────────────────────────── /code/proj/Main.roc ─
+    ── CYCLIC ALIAS [E1005] ────────────────────────────────── /code/proj/Main.roc ─
 
     The `Recursive` opaque is self-recursive in an invalid way:
 
@@ -10293,7 +10372,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
         @r###"
-    ── INCOMPLETE ABILITY IMPLEMENTATION ───────────────────── /code/proj/Main.roc ─
+    ── INCOMPLETE ABILITY IMPLEMENTATION [E3014] ───────────── /code/proj/Main.roc ─
 
     I can't derive an implementation of the `Decoding` ability for `A`:
 
@@ -10318,7 +10397,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
         @r###"
-    ── INCOMPLETE ABILITY IMPLEMENTATION ───────────────────── /code/proj/Main.roc ─
+    ── INCOMPLETE ABILITY IMPLEMENTATION [E3014] ───────────── /code/proj/Main.roc ─
 
     I can't derive an implementation of the `Decoding` ability for `A`:
 
@@ -10372,7 +10451,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression has a type that does not implement the abilities it's expected to:
 
@@ -10403,7 +10482,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression has a type that does not implement the abilities it's expected to:
 
@@ -10433,7 +10512,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── UNUSED ARGUMENT ─────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNUSED ARGUMENT [E2004] ─────────────────────────────── /code/proj/Main.roc ─
 
     This function doesn't use `x`.
 
@@ -10465,7 +10544,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     The branches of this `when` expression don't match the condition:
 
@@ -10498,7 +10577,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the body of the `f` definition:
 
@@ -10529,7 +10608,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     Something is off with the `then` branch of this `if` expression:
 
@@ -10558,7 +10637,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── UNUSED DEFINITION ───────────────────────────────────── /code/proj/Main.roc ─
+    ── UNUSED DEFINITION [E2002] ───────────────────────────── /code/proj/Main.roc ─
 
     `foo` is not used in this `when` branch.
 
@@ -10584,7 +10663,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression has a type that does not implement the abilities it's expected to:
 
@@ -10613,7 +10692,7 @@ I recommend using camelCase. It's the standard style in Roc code!
              "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression has a type that does not implement the abilities it's expected to:
 
@@ -10676,7 +10755,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -10704,7 +10783,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -10731,7 +10810,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── UNMATCHABLE PATTERN ─────────────────────────────────── /code/proj/Main.roc ─
+    ── UNMATCHABLE PATTERN [E1019] ─────────────────────────── /code/proj/Main.roc ─
 
     The 2nd pattern will never be matched:
 
@@ -10758,7 +10837,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── UNMATCHABLE PATTERN ─────────────────────────────────── /code/proj/Main.roc ─
+    ── UNMATCHABLE PATTERN [E1019] ─────────────────────────── /code/proj/Main.roc ─
 
     The 2nd pattern will never be matched:
 
@@ -10770,7 +10849,7 @@ I recommend using camelCase. It's the standard style in Roc code!
     It's impossible to create a value of this shape, so this pattern can
     be safely removed!
 
-    ── UNMATCHABLE PATTERN ─────────────────────────────────── /code/proj/Main.roc ─
+    ── UNMATCHABLE PATTERN [E1019] ─────────────────────────── /code/proj/Main.roc ─
 
     The 3rd pattern will never be matched:
 
@@ -10795,7 +10874,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── DUPLICATE NAME ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── DUPLICATE NAME [E2005] ──────────────────────────────── /code/proj/Main.roc ─
 
     This opaque type has the same name as a builtin:
 
@@ -10817,7 +10896,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── UNUSED IMPORT ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNUSED IMPORT [E2003] ───────────────────────────────── /code/proj/Main.roc ─
 
     `List.concat` is not used in this module.
 
@@ -10869,7 +10948,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── CIRCULAR DEFINITION ─────────────────────────────────── /code/proj/Main.roc ─
+    ── CIRCULAR DEFINITION [E2008] ─────────────────────────── /code/proj/Main.roc ─
 
     `main` is defined directly in terms of itself:
 
@@ -10891,7 +10970,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This `if` condition needs to be a Bool:
 
@@ -10917,7 +10996,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This `if` condition needs to be a Bool:
 
@@ -10945,7 +11024,7 @@ I recommend using camelCase. It's the standard style in Roc code!
              "#
         ),
         @r###"
-    ── INCOMPLETE ABILITY IMPLEMENTATION ───────────────────── /code/proj/Main.roc ─
+    ── INCOMPLETE ABILITY IMPLEMENTATION [E3014] ───────────── /code/proj/Main.roc ─
 
     I can't derive an implementation of the `Hash` ability for `A`:
 
@@ -10970,7 +11049,7 @@ I recommend using camelCase. It's the standard style in Roc code!
              "#
         ),
         @r###"
-    ── INCOMPLETE ABILITY IMPLEMENTATION ───────────────────── /code/proj/Main.roc ─
+    ── INCOMPLETE ABILITY IMPLEMENTATION [E3014] ───────────── /code/proj/Main.roc ─
 
     I can't derive an implementation of the `Hash` ability for `A`:
 
@@ -11052,7 +11131,7 @@ I recommend using camelCase. It's the standard style in Roc code!
              "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression has a type that does not implement the abilities it's expected to:
 
@@ -11079,7 +11158,7 @@ I recommend using camelCase. It's the standard style in Roc code!
              "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression has a type that does not implement the abilities it's expected to:
 
@@ -11112,7 +11191,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 2nd argument to `shiftRightZfBy` has an unexpected type:
 
@@ -11127,7 +11206,7 @@ I recommend using camelCase. It's the standard style in Roc code!
 
         U8
 
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 2nd argument to `shiftRightBy` has an unexpected type:
 
@@ -11142,7 +11221,7 @@ I recommend using camelCase. It's the standard style in Roc code!
 
         U8
 
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 2nd argument to `shiftLeftBy` has an unexpected type:
 
@@ -11170,7 +11249,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 2nd argument to `contains` has an unexpected type:
 
@@ -11197,7 +11276,7 @@ I recommend using camelCase. It's the standard style in Roc code!
              "#
         ),
         @r###"
-    ── INCOMPLETE ABILITY IMPLEMENTATION ───────────────────── /code/proj/Main.roc ─
+    ── INCOMPLETE ABILITY IMPLEMENTATION [E3014] ───────────── /code/proj/Main.roc ─
 
     I can't derive an implementation of the `Eq` ability for `A`:
 
@@ -11222,7 +11301,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     The branches of this `when` expression don't match the condition:
 
@@ -11252,7 +11331,7 @@ I recommend using camelCase. It's the standard style in Roc code!
              "#
         ),
         @r###"
-    ── INCOMPLETE ABILITY IMPLEMENTATION ───────────────────── /code/proj/Main.roc ─
+    ── INCOMPLETE ABILITY IMPLEMENTATION [E3014] ───────────── /code/proj/Main.roc ─
 
     I can't derive an implementation of the `Eq` ability for `A`:
 
@@ -11278,7 +11357,7 @@ I recommend using camelCase. It's the standard style in Roc code!
              "#
         ),
         @r###"
-    ── INCOMPLETE ABILITY IMPLEMENTATION ───────────────────── /code/proj/Main.roc ─
+    ── INCOMPLETE ABILITY IMPLEMENTATION [E3014] ───────────── /code/proj/Main.roc ─
 
     I can't derive an implementation of the `Eq` ability for `A`:
 
@@ -11306,7 +11385,7 @@ I recommend using camelCase. It's the standard style in Roc code!
              "#
         ),
         @r###"
-    ── INCOMPLETE ABILITY IMPLEMENTATION ───────────────────── /code/proj/Main.roc ─
+    ── INCOMPLETE ABILITY IMPLEMENTATION [E3014] ───────────── /code/proj/Main.roc ─
 
     I can't derive an implementation of the `Eq` ability for `A`:
 
@@ -11388,7 +11467,7 @@ I recommend using camelCase. It's the standard style in Roc code!
              "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression has a type that does not implement the abilities it's expected to:
 
@@ -11415,7 +11494,7 @@ I recommend using camelCase. It's the standard style in Roc code!
              "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression has a type that does not implement the abilities it's expected to:
 
@@ -11444,7 +11523,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression has a type that does not implement the abilities it's expected to:
 
@@ -11467,7 +11546,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
         @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression has a type that does not implement the abilities it's expected to:
 
@@ -11493,7 +11572,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
         @r###"
-    ── NOT EXPOSED ─────────────────────────────────────────── /code/proj/Main.roc ─
+    ── NOT EXPOSED [E5002] ─────────────────────────────────── /code/proj/Main.roc ─
 
     The Bool module does not expose `structuralEq`:
 
@@ -11507,7 +11586,7 @@ I recommend using camelCase. It's the standard style in Roc code!
         Bool.false
         Bool.isEq
 
-    ── NOT EXPOSED ─────────────────────────────────────────── /code/proj/Main.roc ─
+    ── NOT EXPOSED [E5002] ─────────────────────────────────── /code/proj/Main.roc ─
 
     The Bool module does not expose `structuralNotEq`:
 
@@ -11535,7 +11614,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression has a type that does not implement the abilities it's expected to:
 
@@ -11560,7 +11639,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     The 1st argument to `foo` is weird:
 
@@ -11589,7 +11668,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── INCOMPLETE ABILITY IMPLEMENTATION ───────────────────── /code/proj/Main.roc ─
+    ── INCOMPLETE ABILITY IMPLEMENTATION [E3014] ───────────── /code/proj/Main.roc ─
 
     I can't derive an implementation of the `Hash` ability for `F`:
 
@@ -11600,7 +11679,7 @@ I recommend using camelCase. It's the standard style in Roc code!
 
     Tip: You can define a custom implementation of `Hash` for `F`.
 
-    ── INCOMPLETE ABILITY IMPLEMENTATION ───────────────────── /code/proj/Main.roc ─
+    ── INCOMPLETE ABILITY IMPLEMENTATION [E3014] ───────────── /code/proj/Main.roc ─
 
     I can't derive an implementation of the `Eq` ability for `F`:
 
@@ -11611,7 +11690,7 @@ I recommend using camelCase. It's the standard style in Roc code!
 
     Tip: You can define a custom implementation of `Eq` for `F`.
 
-    ── INCOMPLETE ABILITY IMPLEMENTATION ───────────────────── /code/proj/Main.roc ─
+    ── INCOMPLETE ABILITY IMPLEMENTATION [E3014] ───────────── /code/proj/Main.roc ─
 
     I can't derive an implementation of the `Encoding` ability for `F`:
 
@@ -11634,7 +11713,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── DUPLICATE BOUND ABILITY ─────────────────────────────── /code/proj/Main.roc ─
+    ── DUPLICATE BOUND ABILITY [E3007] ─────────────────────── /code/proj/Main.roc ─
 
     I already saw that this type variable is bound to the `Hash` ability
     once before:
@@ -11659,7 +11738,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 1st argument to `g` has an unexpected type:
 
@@ -11696,7 +11775,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 1st argument to `g` has an unexpected type:
 
@@ -11734,7 +11813,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 1st argument to `g` has an unexpected type:
 
@@ -11767,7 +11846,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── UNFINISHED LIST PATTERN ────────── tmp/list_pattern_not_terminated/Test.roc ─
+    ── UNFINISHED LIST PATTERN [E0035] ── tmp/list_pattern_not_terminated/Test.roc ─
 
     I am partway through parsing a list pattern, but I got stuck here:
 
@@ -11789,7 +11868,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── UNFINISHED LIST PATTERN ──────────── tmp/list_pattern_weird_indent/Test.roc ─
+    ── UNFINISHED LIST PATTERN [E0035] ──── tmp/list_pattern_weird_indent/Test.roc ─
 
     I am partway through parsing a list pattern, but I got stuck here:
 
@@ -11811,7 +11890,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── INCORRECT REST PATTERN ─────── tmp/list_pattern_weird_rest_pattern/Test.roc ─
+    ── INCORRECT REST PATTERN [E0013] ─ ...ist_pattern_weird_rest_pattern/Test.roc ─
 
     It looks like you may trying to write a list rest pattern, but it's
     not the form I expect:
@@ -11833,7 +11912,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── UNNECESSARY WILDCARD ────────────────────────────────── /code/proj/Main.roc ─
+    ── UNNECESSARY WILDCARD [E1022] ────────────────────────── /code/proj/Main.roc ─
 
     This type annotation has a wildcard type variable (`*`) that isn't
     needed.
@@ -11860,7 +11939,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── MULTIPLE LIST REST PATTERNS ─────────────────────────── /code/proj/Main.roc ─
+    ── MULTIPLE LIST REST PATTERNS [E1020] ─────────────────── /code/proj/Main.roc ─
 
     This list pattern match has multiple rest patterns:
 
@@ -11870,7 +11949,7 @@ I recommend using camelCase. It's the standard style in Roc code!
     I only support compiling list patterns with one .. pattern! Can you
     remove this additional one?
 
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -11894,7 +11973,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── MULTIPLE LIST REST PATTERNS ─────────────────────────── /code/proj/Main.roc ─
+    ── MULTIPLE LIST REST PATTERNS [E1020] ─────────────────── /code/proj/Main.roc ─
 
     This list pattern match has multiple rest patterns:
 
@@ -11904,7 +11983,7 @@ I recommend using camelCase. It's the standard style in Roc code!
     I only support compiling list patterns with one .. pattern! Can you
     remove this additional one?
 
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -11928,7 +12007,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This list element doesn't match the types of other elements in the
     pattern:
@@ -11955,7 +12034,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     The branches of this `when` expression don't match the condition:
 
@@ -11985,7 +12064,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -12025,7 +12104,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -12081,7 +12160,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -12108,7 +12187,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -12151,7 +12230,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -12178,7 +12257,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -12227,7 +12306,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -12258,7 +12337,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -12290,7 +12369,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -12339,7 +12418,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── UNSAFE PATTERN ──────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNSAFE PATTERN [E1017] ──────────────────────────────── /code/proj/Main.roc ─
 
     This `when` does not cover all the possibilities:
 
@@ -12373,7 +12452,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── REDUNDANT PATTERN ───────────────────────────────────── /code/proj/Main.roc ─
+    ── REDUNDANT PATTERN [E1018] ───────────────────────────── /code/proj/Main.roc ─
 
     The 3rd pattern is redundant:
 
@@ -12401,7 +12480,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── REDUNDANT PATTERN ───────────────────────────────────── /code/proj/Main.roc ─
+    ── REDUNDANT PATTERN [E1018] ───────────────────────────── /code/proj/Main.roc ─
 
     The 3rd pattern is redundant:
 
@@ -12429,7 +12508,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── REDUNDANT PATTERN ───────────────────────────────────── /code/proj/Main.roc ─
+    ── REDUNDANT PATTERN [E1018] ───────────────────────────── /code/proj/Main.roc ─
 
     The 3rd pattern is redundant:
 
@@ -12457,7 +12536,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── REDUNDANT PATTERN ───────────────────────────────────── /code/proj/Main.roc ─
+    ── REDUNDANT PATTERN [E1018] ───────────────────────────── /code/proj/Main.roc ─
 
     The 2nd pattern is redundant:
 
@@ -12498,7 +12577,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This 1st argument to `foo` has an unexpected type:
 
@@ -12532,7 +12611,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression has a type that does not implement the abilities it's expected to:
 
@@ -12562,7 +12641,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This value passed to `crash` is not a string:
 
@@ -12587,7 +12666,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── UNAPPLIED CRASH ─────────────────────────────────────── /code/proj/Main.roc ─
+    ── UNAPPLIED CRASH [E1024] ─────────────────────────────── /code/proj/Main.roc ─
 
     This `crash` doesn't have a message given to it:
 
@@ -12608,7 +12687,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── OVERAPPLIED CRASH ───────────────────────────────────── /code/proj/Main.roc ─
+    ── OVERAPPLIED CRASH [E1025] ───────────────────────────── /code/proj/Main.roc ─
 
     This `crash` has too many values given to it:
 
@@ -12644,7 +12723,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── TYPE MISMATCH ───────────────────────────────────────── /code/proj/Main.roc ─
+    ── TYPE MISMATCH [E1001] ───────────────────────────────── /code/proj/Main.roc ─
 
     This expression has a type that does not implement the abilities it's expected to:
 
@@ -12686,7 +12765,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── DEFINITION ONLY USED IN RECURSION ───────────────────── /code/proj/Main.roc ─
+    ── DEFINITION ONLY USED IN RECURSION [E2009] ───────────── /code/proj/Main.roc ─
 
     This definition is only used in recursion with itself:
 
@@ -12719,7 +12798,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── DEFINITIONs ONLY USED IN RECURSION ──────────────────── /code/proj/Main.roc ─
+    ── DEFINITIONs ONLY USED IN RECURSION [E2009] ──────────── /code/proj/Main.roc ─
 
     These 2 definitions are only used in mutual recursion with themselves:
 
@@ -12755,7 +12834,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── DEFINITION ONLY USED IN RECURSION ───────────────────── /code/proj/Main.roc ─
+    ── DEFINITION ONLY USED IN RECURSION [E2009] ───────────── /code/proj/Main.roc ─
 
     This definition is only used in recursion with itself:
 
@@ -12791,7 +12870,7 @@ I recommend using camelCase. It's the standard style in Roc code!
             "#
         ),
     @r###"
-    ── DEFINITIONs ONLY USED IN RECURSION ──────────────────── /code/proj/Main.roc ─
+    ── DEFINITIONs ONLY USED IN RECURSION [E2009] ──────────── /code/proj/Main.roc ─
 
     These 2 definitions are only used in mutual recursion with themselves:
 
@@ -12817,4 +12896,330 @@ I recommend using camelCase. It's the standard style in Roc code!
     @r###"
     "###
     );
+
+    test_report!(
+        tag_differs_only_in_case,
+        indoc!(
+            r#"
+            color = if Bool.true then Red else RED
+
+            color
+            "#
+        ),
+    @r###"
+    ── NAME DIFFERS ONLY IN CASE [E2012] ───────────────────── /code/proj/Main.roc ─
+
+    This tag name only differs in capitalization from one used earlier:
+
+    4│      color = if Bool.true then Red else RED
+                                               ^^^
+
+    It was first spelled Red here:
+
+    4│      color = if Bool.true then Red else RED
+                                      ^^^
+
+    Names this similar are easy to mix up. If these are meant to be the
+    same, use one spelling for both.
+    "###
+    );
+
+    test_report!(
+        record_access_differs_only_in_case,
+        indoc!(
+            r#"
+            f = \r -> r.userName
+            g = \r -> r.username
+
+            { f, g }
+            "#
+        ),
+    @r###"
+    ── NAME DIFFERS ONLY IN CASE [E2012] ───────────────────── /code/proj/Main.roc ─
+
+    This record field name only differs in capitalization from one used
+    earlier:
+
+    5│      g = \r -> r.username
+                        ^^^^^^^^
+
+    It was first spelled .userName here:
+
+    4│      f = \r -> r.userName
+                        ^^^^^^^^
+
+    Names this similar are easy to mix up. If these are meant to be the
+    same, use one spelling for both.
+    "###
+    );
+
+    #[test]
+    fn fix_unrecognized_name() {
+        let fixes = list_fixes(
+            "fix_unrecognized_name",
+            indoc!(
+                r#"
+                value = 42
+
+                valeu
+                "#
+            ),
+        );
+
+        let (description, fixed_src) = &fixes[0];
+
+        assert_eq!(description, "Rename to `value`");
+        assert!(fixed_src.contains("\n    value\n"), "{}", fixed_src);
+    }
+
+    #[test]
+    fn fix_missing_record_fields() {
+        let fixes = list_fixes(
+            "fix_missing_record_fields",
+            indoc!(
+                r#"
+                point : { x : I64, y : I64 }
+                point = { x: 1 }
+
+                point
+                "#
+            ),
+        );
+
+        assert_eq!(
+            fixes,
+            vec![(
+                "Add the missing fields".to_string(),
+                promote_expr_to_module(indoc!(
+                    r#"
+                    point : { x : I64, y : I64 }
+                    point = { x: 1, y: crash "TODO" }
+
+                    point
+                    "#
+                )),
+            )]
+        );
+    }
+
+    #[test]
+    fn html_report() {
+        use roc_reporting::html::html_page;
+
+        let arena = Bump::new();
+        let filename = filename_from_string(r"/code/proj/Main.roc");
+
+        let (module_src, type_problems, _can_problems, home, interns) = infer_expr_help_new(
+            "html_report",
+            &arena,
+            indoc!(
+                r#"
+                if 1 < 2 then "yes" else 0
+                "#
+            ),
+        )
+        .expect("failed to load");
+
+        let lines = LineInfo::new(&module_src);
+        let src_lines: Vec<&str> = module_src.split('\n').collect();
+        let alloc = RocDocAllocator::new(&src_lines, home, &interns);
+
+        let reports: Vec<_> = type_problems
+            .into_iter()
+            .filter_map(|problem| type_problem(&alloc, &lines, filename.clone(), problem))
+            .map(|report| report.render_html(&alloc))
+            .collect();
+
+        assert_eq!(reports.len(), 1);
+        assert_eq!(reports[0].title, "TYPE MISMATCH");
+        assert!(reports[0].body.contains("1 &lt; 2"), "{}", reports[0].body);
+        assert!(reports[0].body.contains("<details class=\"snippet\" open>"));
+
+        let page = html_page(&reports);
+
+        assert!(page.contains("<a href=\"#problem-1\">TYPE MISMATCH</a>"));
+        assert!(page.contains("<section id=\"problem-1\" class=\"severity-error\">"));
+    }
+
+    #[test]
+    fn cascading_errors_are_grouped() {
+        use roc_reporting::error::canonicalize::{add_cascade_note, group_cascading_problems};
+
+        let arena = Bump::new();
+        let filename = filename_from_string(r"/code/proj/Main.roc");
+
+        let (module_src, _type_problems, can_problems, home, interns) = infer_expr_help_new(
+            "cascading_errors_are_grouped",
+            &arena,
+            indoc!(
+                r#"
+                a = missing 1
+                b = missing 2
+
+                [a, b, missing 3]
+                "#
+            ),
+        )
+        .expect("failed to load");
+
+        let lines = LineInfo::new(&module_src);
+        let src_lines: Vec<&str> = module_src.split('\n').collect();
+        let alloc = RocDocAllocator::new(&src_lines, home, &interns);
+
+        let mut grouped = group_cascading_problems(can_problems);
+
+        assert_eq!(grouped.len(), 1);
+
+        let (problem, cascade) = grouped.remove(0);
+
+        assert_eq!(cascade.len(), 2);
+
+        let report = can_problem(&alloc, &lines, filename, problem);
+        let report = add_cascade_note(&alloc, &lines, report, &cascade);
+
+        let mut buf = String::new();
+        report.render_ci(&mut buf, &alloc);
+
+        assert!(
+            buf.contains("The same problem happens in 2 more places, at"),
+            "{}",
+            buf
+        );
+    }
+
+    #[test]
+    fn unoptimized_tail_call() {
+        use roc_problem::can::{Problem, TailCallBlocker};
+        use roc_region::all::{Position, Region};
+
+        let arena = Bump::new();
+        let filename = filename_from_string(r"/code/proj/Main.roc");
+
+        let (module_src, _type_problems, _can_problems, home, interns) = infer_expr_help_new(
+            "unoptimized_tail_call",
+            &arena,
+            indoc!(
+                r#"
+                isEven = \n -> if n == 0 then Bool.true else isOdd (n - 1)
+                isOdd = \n -> if n == 0 then Bool.false else isEven (n - 1)
+
+                isEven 10
+                "#
+            ),
+        )
+        .expect("failed to load");
+
+        let symbol = |name: &str| {
+            let ident_ids = interns.all_ident_ids.get(&home).unwrap();
+            let ident_id = ident_ids.get_id(name).unwrap();
+
+            roc_module::symbol::Symbol::new(home, ident_id)
+        };
+
+        let lines = LineInfo::new(&module_src);
+        let src_lines: Vec<&str> = module_src.split('\n').collect();
+        let alloc = RocDocAllocator::new(&src_lines, home, &interns);
+
+        let call = "isOdd (n - 1)";
+        let start = module_src.find(call).unwrap() as u32;
+        let region = Region::new(
+            Position::new(start),
+            Position::new(start + "isOdd".len() as u32),
+        );
+
+        let problem = Problem::UnoptimizedTailCall {
+            caller: symbol("isEven"),
+            callee: symbol("isOdd"),
+            region,
+            reason: TailCallBlocker::SharedSymbols,
+        };
+
+        assert_eq!(problem.region(), Some(region));
+
+        assert_eq!(problem.severity(), roc_problem::Severity::Warning);
+
+        let report = can_problem(&alloc, &lines, filename, problem);
+
+        let mut buf = String::new();
+        report.render_ci(&mut buf, &alloc);
+
+        assert!(buf.contains("UNOPTIMIZED TAIL CALL [E2013]"), "{}", buf);
+        assert!(
+            buf.contains("isEven ends by calling isOdd, which calls back into isEven"),
+            "{}",
+            buf
+        );
+        assert!(
+            buf.contains(call) && buf.contains("^^^^^"),
+            "expected the call to be shown:\n{}",
+            buf
+        );
+        assert!(
+            buf.contains("This is because isEven and isOdd both use some of the same values"),
+            "{}",
+            buf
+        );
+        assert!(buf.contains("Merging isOdd into isEven"), "{}", buf);
+    }
+
+    #[test]
+    fn type_error_suggestions() {
+        let suggestions = |name: &str, src: &'static str| {
+            let arena = Bump::new();
+            let (_, type_problems, _, _, _) = infer_expr_help_new(name, &arena, src)
+                .unwrap_or_else(|_| panic!("{} failed to load", name));
+
+            assert_eq!(type_problems.len(), 1, "{:?}", type_problems);
+
+            type_problems[0]
+                .suggestions()
+                .iter()
+                .map(|suggestion| suggestion.to_string())
+                .collect::<Vec<_>>()
+        };
+
+        let field = suggestions(
+            "type_error_suggestions_field",
+            indoc!(
+                r#"
+                r : { foo : I64, bar : I64 }
+                r = { foo: 1, bar: 2 }
+
+                r.fop
+                "#
+            ),
+        );
+        assert_eq!(
+            field.first().map(String::as_str),
+            Some("foo"),
+            "{:?}",
+            field
+        );
+
+        let tag = suggestions(
+            "type_error_suggestions_tag",
+            indoc!(
+                r#"
+                f : [Red, Green] -> I64
+                f = \_ -> 1
+
+                f Gren
+                "#
+            ),
+        );
+        assert_eq!(tag.first().map(String::as_str), Some("Green"), "{:?}", tag);
+    }
+
+    #[test]
+    fn palette_overrides() {
+        use roc_reporting::report::{Palette, LIGHT_PALETTE};
+
+        let mut palette: Palette = LIGHT_PALETTE;
+        palette.apply_overrides("keyword=bright-blue: error = bold:nonsense=red:header=plaid");
+
+        assert_eq!(palette.keyword, "\u{001b}[94m");
+        assert_eq!(palette.error, "\u{001b}[1m");
+        assert_eq!(palette.header, LIGHT_PALETTE.header);
+        assert_eq!(palette.primary, "\u{001b}[39m");
+    }
 }
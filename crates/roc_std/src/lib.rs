@@ -12,6 +12,7 @@ use core::ops::Drop;
 use core::str;
 
 mod roc_box;
+mod roc_closure;
 mod roc_dict;
 mod roc_list;
 mod roc_set;
@@ -19,6 +20,7 @@ mod roc_str;
 mod storage;
 
 pub use roc_box::RocBox;
+pub use roc_closure::RocClosureData;
 pub use roc_dict::RocDict;
 pub use roc_list::{RocList, SendSafeRocList};
 pub use roc_set::RocSet;
@@ -5,6 +5,7 @@ use core::{
     cell::Cell,
     cmp::{self, Ordering},
     fmt::Debug,
+    hash::{Hash, Hasher},
     mem,
     ops::Deref,
     ptr::{self, NonNull},
@@ -33,7 +34,8 @@ impl<T> RocBox<T> {
         let contents = unsafe {
             let contents_ptr = ptr.cast::<u8>().add(alignment).cast::<T>();
 
-            *contents_ptr = contents;
+            // The memory is uninitialized, so there's nothing here to drop.
+            contents_ptr.write(contents);
 
             // We already verified that the original alloc pointer was non-null,
             // and this one is the alloc pointer with `alignment` bytes added to it,
@@ -49,8 +51,32 @@ impl<T> RocBox<T> {
         mem::align_of::<T>().max(mem::align_of::<Storage>())
     }
 
-    pub fn into_inner(self) -> T {
-        unsafe { ptr::read(self.contents.as_ptr() as *mut T) }
+    /// Take the contents out of the box. If other references to the box are still
+    /// around, this clones them instead.
+    pub fn into_inner(self) -> T
+    where
+        T: Clone,
+    {
+        if self.storage().get().is_unique() {
+            let alignment = Self::alloc_alignment();
+            let contents_ptr = self.contents.as_ptr();
+
+            // Move the contents out, and free the allocation without dropping them.
+            mem::forget(self);
+
+            unsafe {
+                let contents = ptr::read(contents_ptr);
+
+                roc_dealloc(
+                    contents_ptr.cast::<u8>().sub(alignment).cast(),
+                    alignment as u32,
+                );
+
+                contents
+            }
+        } else {
+            self.deref().clone()
+        }
     }
 
     fn storage(&self) -> &Cell<Storage> {
@@ -110,6 +136,24 @@ where
     }
 }
 
+impl<T> Hash for RocBox<T>
+where
+    T: Hash,
+{
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.deref().hash(state)
+    }
+}
+
+impl<T> Default for RocBox<T>
+where
+    T: Default,
+{
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
 impl<T> Debug for RocBox<T>
 where
     T: Debug,
@@ -0,0 +1,89 @@
+#![deny(unsafe_op_in_unsafe_fn)]
+
+use crate::{roc_alloc, roc_dealloc};
+use core::{fmt::Debug, ptr::NonNull};
+
+/// The values a Roc function value captured, which the host passes back to Roc when it
+/// calls the function. Which values get captured depends on the app, so unlike other Roc
+/// types, their size is only known at runtime (e.g. from `roc__mainForHost_1__Fx_size`).
+pub struct RocClosureData {
+    bytes: NonNull<u8>,
+    size: usize,
+}
+
+impl RocClosureData {
+    /// Roc never aligns a value to more than 16 bytes,
+    /// so this alignment works for any captured values.
+    pub const ALIGNMENT: usize = 16;
+
+    /// Allocate space for captured values of the given size, for Roc to write to.
+    pub fn new(size: usize) -> Self {
+        if size == 0 {
+            return Self {
+                bytes: Self::dangling(),
+                size,
+            };
+        }
+
+        let ptr = unsafe { roc_alloc(size, Self::ALIGNMENT as u32) };
+
+        match NonNull::new(ptr.cast::<u8>()) {
+            Some(bytes) => Self { bytes, size },
+            None => todo!("Call roc_panic with the info that an allocation failed."),
+        }
+    }
+
+    /// Copy captured values of the given size out of memory Roc wrote them to.
+    ///
+    /// # Safety
+    ///
+    /// `source` must point to `size` readable bytes.
+    pub unsafe fn copy_from(source: *const u8, size: usize) -> Self {
+        let closure_data = Self::new(size);
+
+        unsafe {
+            core::ptr::copy_nonoverlapping(source, closure_data.bytes.as_ptr(), size);
+        }
+
+        closure_data
+    }
+
+    pub fn as_ptr(&self) -> *const u8 {
+        self.bytes.as_ptr()
+    }
+
+    pub fn as_mut_ptr(&mut self) -> *mut u8 {
+        self.bytes.as_ptr()
+    }
+
+    pub fn len(&self) -> usize {
+        self.size
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.size == 0
+    }
+
+    fn dangling() -> NonNull<u8> {
+        // Like NonNull::dangling, but aligned for any captured values.
+        unsafe { NonNull::new_unchecked(Self::ALIGNMENT as *mut u8) }
+    }
+}
+
+impl Drop for RocClosureData {
+    fn drop(&mut self) {
+        if self.size > 0 {
+            unsafe {
+                roc_dealloc(self.bytes.as_ptr().cast(), Self::ALIGNMENT as u32);
+            }
+        }
+    }
+}
+
+impl Debug for RocClosureData {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("RocClosureData")
+            .field("size", &self.size)
+            .finish()
+    }
+}
@@ -5,8 +5,9 @@ use core::{
     mem::{align_of, ManuallyDrop},
 };
 
-/// At the moment, Roc's Dict is just an association list. Its lookups are O(n) but
-/// we haven't grown such big programs that it's a problem yet!
+/// A Roc Dict: its entries in insertion order, along with a hash index into them.
+/// The index is built by Roc's hash function, so the host can only build empty dicts;
+/// it can read and pass along the ones it gets from Roc, though.
 ///
 /// We do some things in this data structure that only make sense because the
 /// memory is managed in Roc:
@@ -15,33 +16,98 @@ use core::{
 ///    since Roc owns the memory, not rust.
 /// 2. We use a union for [`RocDictItem`] instead of just a struct. See the
 ///    comment on that data structure for why.
-#[derive(Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
-#[repr(transparent)]
-pub struct RocDict<K, V>(RocList<RocDictItem<K, V>>);
+///
+/// The fields are in the order Roc lays them out: by alignment, then alphabetically.
+#[repr(C)]
+pub struct RocDict<K, V> {
+    data: RocList<RocDictItem<K, V>>,
+    data_indices: RocList<usize>,
+    metadata: RocList<i8>,
+    size: usize,
+}
+
+/// How many slots an empty Dict's index starts with, like `Dict.empty` in Roc
+const INITIAL_SLOTS: usize = 8;
+
+/// Marks a slot in the index as unused, like `emptySlot` in Roc
+const EMPTY_SLOT: i8 = -128;
 
 impl<K, V> RocDict<K, V> {
     pub fn len(&self) -> usize {
-        self.0.len()
+        self.size
     }
 
     pub fn is_empty(&self) -> bool {
-        self.0.is_empty()
+        self.size == 0
     }
 
+    /// An empty dict, with room for `capacity` entries before Roc needs to reallocate them
     pub fn with_capacity(capacity: usize) -> Self {
-        Self(RocList::with_capacity(capacity))
+        Self {
+            data: RocList::with_capacity(capacity),
+            data_indices: RocList::from_slice(&[0; INITIAL_SLOTS]),
+            metadata: RocList::from_slice(&[EMPTY_SLOT; INITIAL_SLOTS]),
+            size: 0,
+        }
     }
 
     pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
-        self.0.iter().map(|item| (item.key(), item.value()))
+        self.data.iter().map(|item| (item.key(), item.value()))
     }
 
     pub fn iter_keys(&self) -> impl Iterator<Item = &K> {
-        self.0.iter().map(|item| item.key())
+        self.data.iter().map(|item| item.key())
     }
 
     pub fn iter_values(&self) -> impl Iterator<Item = &V> {
-        self.0.iter().map(|item| item.value())
+        self.data.iter().map(|item| item.value())
+    }
+}
+
+impl<K, V> Default for RocDict<K, V> {
+    fn default() -> Self {
+        Self::with_capacity(0)
+    }
+}
+
+/// Each field holds its own reference to a list, so cloning one just adds to their refcounts.
+impl<K, V> Clone for RocDict<K, V> {
+    fn clone(&self) -> Self {
+        Self {
+            data: self.data.clone(),
+            data_indices: self.data_indices.clone(),
+            metadata: self.metadata.clone(),
+            size: self.size,
+        }
+    }
+}
+
+// Two dicts with the same entries can have different indices (e.g. if one had entries
+// removed), so these only look at the entries.
+
+impl<K: PartialEq, V: PartialEq> PartialEq for RocDict<K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.data == other.data
+    }
+}
+
+impl<K: Eq, V: Eq> Eq for RocDict<K, V> {}
+
+impl<K: PartialOrd, V: PartialOrd> PartialOrd for RocDict<K, V> {
+    fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+        self.data.partial_cmp(&other.data)
+    }
+}
+
+impl<K: Ord, V: Ord> Ord for RocDict<K, V> {
+    fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+        self.data.cmp(&other.data)
+    }
+}
+
+impl<K: Hash, V: Hash> Hash for RocDict<K, V> {
+    fn hash<H: core::hash::Hasher>(&self, state: &mut H) {
+        self.data.hash(state)
     }
 }
 
@@ -76,7 +142,7 @@ impl<'a, K, V> IntoIterator for &'a RocDict<K, V> {
     fn into_iter(self) -> Self::IntoIter {
         IntoIter {
             index: 0,
-            items: self.0.as_slice(),
+            items: self.data.as_slice(),
         }
     }
 }
@@ -4,7 +4,9 @@ use core::{
     hash::Hash,
 };
 
+/// A Roc Set is a Dict whose values are all `{}`.
 #[derive(Default, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[repr(transparent)]
 pub struct RocSet<T>(RocDict<T, ()>);
 
 impl<T> RocSet<T> {
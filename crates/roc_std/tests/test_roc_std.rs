@@ -59,7 +59,10 @@ pub unsafe extern "C" fn roc_memset(dst: *mut c_void, c: i32, n: usize) -> *mut
 
 #[cfg(test)]
 mod test_roc_std {
-    use roc_std::{RocBox, RocDec, RocList, RocResult, RocStr, SendSafeRocList, SendSafeRocStr};
+    use roc_std::{
+        RocBox, RocClosureData, RocDec, RocDict, RocList, RocResult, RocStr, SendSafeRocList,
+        SendSafeRocStr,
+    };
 
     fn roc_str_byte_representation(string: &RocStr) -> [u8; RocStr::SIZE] {
         unsafe { core::mem::transmute_copy(string) }
@@ -280,6 +283,50 @@ mod test_roc_std {
         assert_eq!(roc_box.into_inner(), contents)
     }
 
+    #[test]
+    fn roc_box_into_inner_shared() {
+        let contents = RocStr::from("a string that is too long to be stored inline");
+        let roc_box = RocBox::new(contents.clone());
+        let other_box = roc_box.clone();
+
+        assert_eq!(roc_box.into_inner(), contents);
+        assert_eq!(other_box.into_inner(), contents);
+    }
+
+    #[test]
+    fn empty_roc_dict() {
+        let dict: RocDict<RocStr, u64> = RocDict::default();
+
+        assert!(dict.is_empty());
+        assert_eq!(dict.iter().count(), 0);
+        assert_eq!(dict.clone(), dict);
+    }
+
+    #[test]
+    fn roc_closure_data_copy() {
+        let captures = [1u8, 2, 3, 4, 5];
+        let closure_data = unsafe { RocClosureData::copy_from(captures.as_ptr(), captures.len()) };
+        let copied =
+            unsafe { core::slice::from_raw_parts(closure_data.as_ptr(), closure_data.len()) };
+
+        assert_eq!(copied, captures);
+        assert_eq!(
+            closure_data.as_ptr() as usize % RocClosureData::ALIGNMENT,
+            0
+        );
+    }
+
+    #[test]
+    fn roc_closure_data_empty() {
+        let closure_data = RocClosureData::new(0);
+
+        assert!(closure_data.is_empty());
+        assert_eq!(
+            closure_data.as_ptr() as usize % RocClosureData::ALIGNMENT,
+            0
+        );
+    }
+
     #[test]
     fn roc_dec_fmt() {
         assert_eq!(
@@ -0,0 +1,322 @@
+//! Compile telemetry in the Chrome trace format, which both `chrome://tracing` and
+//! [Perfetto](https://ui.perfetto.dev) can open.
+//!
+//! Setting ROC_CHROME_TRACE=<filepath> records a span for each phase the compiler runs, on
+//! whichever thread runs it, and writes them all to <filepath> when the compiler finishes.
+//! Unlike the logs, this works in release builds too; when the variable isn't set, starting a
+//! span costs a single atomic load.
+use serde_json::{json, Value};
+use std::cell::Cell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+const CHROME_TRACE_VAR: &str = "ROC_CHROME_TRACE";
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static TRACE: Mutex<Option<Trace>> = Mutex::new(None);
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    /// 0 until this thread first records a span
+    static THREAD_ID: Cell<u64> = Cell::new(0);
+}
+
+struct Trace {
+    path: PathBuf,
+    start: Instant,
+    spans: Vec<RecordedSpan>,
+    thread_names: Vec<(u64, String)>,
+    /// Names for the modules spans were recorded for, by the hash of their id
+    module_names: HashMap<u64, String>,
+}
+
+struct RecordedSpan {
+    category: &'static str,
+    phase: &'static str,
+    module: Option<u64>,
+    thread: u64,
+    start: Duration,
+    duration: Duration,
+}
+
+/// Start recording spans if ROC_CHROME_TRACE is set. Call this once, at an executable's entry
+/// point, and call [write_chrome_trace] before it exits.
+pub fn setup_chrome_trace() {
+    if let Some(path) = std::env::var_os(CHROME_TRACE_VAR) {
+        *lock() = Some(Trace {
+            path: PathBuf::from(path),
+            start: Instant::now(),
+            spans: Vec::new(),
+            thread_names: Vec::new(),
+            module_names: HashMap::new(),
+        });
+
+        ENABLED.store(true, Ordering::Relaxed);
+    }
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Option<Trace>> {
+    // A thread that panicked while holding the lock can't have left the trace half-updated.
+    TRACE
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn enabled() -> bool {
+    ENABLED.load(Ordering::Relaxed)
+}
+
+fn module_key(module: impl Hash) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    module.hash(&mut hasher);
+
+    hasher.finish()
+}
+
+/// A phase of compilation that's running. It's recorded when dropped.
+#[must_use]
+pub struct TraceSpan {
+    started: Option<(Instant, &'static str, &'static str, Option<u64>)>,
+}
+
+impl Drop for TraceSpan {
+    fn drop(&mut self) {
+        if let Some((started, category, phase, module)) = self.started.take() {
+            record(category, phase, module, started);
+        }
+    }
+}
+
+/// Trace a phase that isn't specific to a module, e.g. linking. The category groups related
+/// phases, e.g. "load" or "codegen".
+pub fn trace_span(category: &'static str, phase: &'static str) -> TraceSpan {
+    TraceSpan {
+        started: enabled().then(|| (Instant::now(), category, phase, None)),
+    }
+}
+
+/// Trace a phase run on one module, e.g. solving it. Modules are identified by anything
+/// hashable (usually their `ModuleId`); give them names with [trace_module_name].
+pub fn trace_module_span(
+    category: &'static str,
+    phase: &'static str,
+    module: impl Hash,
+) -> TraceSpan {
+    TraceSpan {
+        started: enabled().then(|| (Instant::now(), category, phase, Some(module_key(module)))),
+    }
+}
+
+/// The name to show for spans traced on the given module
+pub fn trace_module_name(module: impl Hash, name: impl FnOnce() -> String) {
+    if !enabled() {
+        return;
+    }
+
+    let key = module_key(module);
+
+    if let Some(trace) = lock().as_mut() {
+        trace.module_names.insert(key, name());
+    }
+}
+
+fn record(category: &'static str, phase: &'static str, module: Option<u64>, started: Instant) {
+    let duration = started.elapsed();
+    let mut guard = lock();
+
+    let trace = match guard.as_mut() {
+        Some(trace) => trace,
+        // The trace was already written
+        None => return,
+    };
+
+    let thread = THREAD_ID.with(|id| {
+        if id.get() == 0 {
+            id.set(NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed));
+
+            let current = std::thread::current();
+            let name = current.name().unwrap_or("worker").to_string();
+
+            trace.thread_names.push((id.get(), name));
+        }
+
+        id.get()
+    });
+
+    trace.spans.push(RecordedSpan {
+        category,
+        phase,
+        module,
+        thread,
+        start: started.saturating_duration_since(trace.start),
+        duration,
+    });
+}
+
+/// Write the spans recorded so far to the file named by ROC_CHROME_TRACE, if it was set.
+/// Spans that finish afterwards aren't recorded.
+pub fn write_chrome_trace() {
+    let trace = match lock().take() {
+        Some(trace) => trace,
+        None => return,
+    };
+
+    ENABLED.store(false, Ordering::Relaxed);
+
+    if let Err(err) = std::fs::write(&trace.path, to_json(&trace)) {
+        eprintln!(
+            "Could not write the trace to {}: {}",
+            trace.path.display(),
+            err
+        );
+    }
+}
+
+fn to_json(trace: &Trace) -> String {
+    let mut events = Vec::with_capacity(trace.spans.len() + trace.thread_names.len() + 1);
+
+    events.push(json!({
+        "ph": "M",
+        "pid": 1,
+        "name": "process_name",
+        "args": { "name": "roc" },
+    }));
+
+    for (thread, name) in trace.thread_names.iter() {
+        events.push(json!({
+            "ph": "M",
+            "pid": 1,
+            "tid": thread,
+            "name": "thread_name",
+            "args": { "name": name },
+        }));
+    }
+
+    for span in trace.spans.iter() {
+        let module = span.module.map(|key| match trace.module_names.get(&key) {
+            Some(name) => name.clone(),
+            None => format!("#{:x}", key),
+        });
+
+        let name = match &module {
+            Some(module) => format!("{} {}", span.phase, module),
+            None => span.phase.to_string(),
+        };
+
+        let mut event = json!({
+            "ph": "X",
+            "pid": 1,
+            "tid": span.thread,
+            "cat": span.category,
+            "name": name,
+            "ts": span.start.as_micros() as u64,
+            "dur": span.duration.as_micros() as u64,
+        });
+
+        if let Some(module) = module {
+            event["args"] = json!({ "module": module });
+        }
+
+        events.push(event);
+    }
+
+    // One event per line, so a trace cut short by a crash is still easy to read
+    let events: Vec<String> = events.iter().map(Value::to_string).collect();
+
+    format!("{{\"traceEvents\":[\n{}\n]}}\n", events.join(",\n"))
+}
+
+#[cfg(test)]
+mod test_chrome {
+    use super::{to_json, RecordedSpan, Trace};
+    use serde_json::{json, Value};
+    use std::collections::HashMap;
+    use std::path::PathBuf;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn spans_as_trace_events() {
+        let trace = Trace {
+            path: PathBuf::new(),
+            start: Instant::now(),
+            spans: vec![
+                RecordedSpan {
+                    category: "load",
+                    phase: "solve",
+                    module: Some(7),
+                    thread: 2,
+                    start: Duration::from_micros(10),
+                    duration: Duration::from_micros(5),
+                },
+                RecordedSpan {
+                    category: "codegen",
+                    phase: "link",
+                    module: None,
+                    thread: 1,
+                    start: Duration::from_micros(20),
+                    duration: Duration::from_micros(3),
+                },
+            ],
+            thread_names: vec![(1, "main".to_string()), (2, "worker \"1\"\n".to_string())],
+            module_names: HashMap::from([(7, "Json\\Decode".to_string())]),
+        };
+
+        let parsed: Value = serde_json::from_str(&to_json(&trace)).unwrap();
+
+        assert_eq!(
+            parsed,
+            json!({
+                "traceEvents": [
+                    { "ph": "M", "pid": 1, "name": "process_name", "args": { "name": "roc" } },
+                    { "ph": "M", "pid": 1, "tid": 1, "name": "thread_name", "args": { "name": "main" } },
+                    {
+                        "ph": "M",
+                        "pid": 1,
+                        "tid": 2,
+                        "name": "thread_name",
+                        "args": { "name": "worker \"1\"\n" }
+                    },
+                    {
+                        "ph": "X",
+                        "pid": 1,
+                        "tid": 2,
+                        "cat": "load",
+                        "name": "solve Json\\Decode",
+                        "ts": 10,
+                        "dur": 5,
+                        "args": { "module": "Json\\Decode" }
+                    },
+                    { "ph": "X", "pid": 1, "tid": 1, "cat": "codegen", "name": "link", "ts": 20, "dur": 3 },
+                ]
+            })
+        );
+    }
+
+    #[test]
+    fn unnamed_modules_by_key() {
+        let trace = Trace {
+            path: PathBuf::new(),
+            start: Instant::now(),
+            spans: vec![RecordedSpan {
+                category: "load",
+                phase: "parse",
+                module: Some(255),
+                thread: 1,
+                start: Duration::ZERO,
+                duration: Duration::ZERO,
+            }],
+            thread_names: Vec::new(),
+            module_names: HashMap::new(),
+        };
+
+        let parsed: Value = serde_json::from_str(&to_json(&trace)).unwrap();
+
+        assert_eq!(parsed["traceEvents"][1]["name"], "parse #ff");
+        assert_eq!(parsed["traceEvents"][1]["args"]["module"], "#ff");
+    }
+}
@@ -13,8 +13,18 @@
 //! Tracing is only turned on in debug builds. Use the provided [setup_tracing] macro to turn on
 //! tracing at an executable's entry point.
 //!
+//! Separately from the logs, ROC_CHROME_TRACE=<filepath> records how long each compiler phase
+//! takes, as a Chrome trace. See [setup_chrome_trace].
+//!
 //! [directive-syntax]: https://docs.rs/tracing-subscriber/latest/tracing_subscriber/filter/struct.EnvFilter.html#directives
 
+mod chrome;
+
+pub use chrome::{
+    setup_chrome_trace, trace_module_name, trace_module_span, trace_span, write_chrome_trace,
+    TraceSpan,
+};
+
 /// Sets up tracing of a Roc executable. The value of this macro must be bound to a variable that
 /// is not dropped until tracing has completed.
 ///
@@ -0,0 +1,102 @@
+use std::ops::Range;
+
+use bitvec::vec::BitVec;
+
+/// A set of indices below some length, one bit each, e.g. which of a module's functions are
+/// called. Unlike a hash set of indices, it iterates in ascending order, so whatever is built
+/// from iterating over it comes out the same every time.
+///
+/// The length is fixed up front, typically to the number of functions, and grows only by
+/// [BitSet::push], e.g. when a function is added.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BitSet {
+    bits: BitVec<usize>,
+}
+
+impl BitSet {
+    /// An empty set of indices below `len`
+    pub fn with_len(len: usize) -> Self {
+        Self {
+            bits: BitVec::repeat(false, len),
+        }
+    }
+
+    /// How many indices the set can hold, not how many it does; see [BitSet::count]
+    pub fn len(&self) -> usize {
+        self.bits.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.bits.is_empty()
+    }
+
+    /// How many indices are in the set
+    pub fn count(&self) -> usize {
+        self.bits.count_ones()
+    }
+
+    /// Make room for one more index, the new `len() - 1`, and add it to the set or not.
+    pub fn push(&mut self, contains: bool) {
+        self.bits.push(contains);
+    }
+
+    /// Adds the index, returning whether it was new. Panics if it's not below `len()`.
+    pub fn insert(&mut self, index: usize) -> bool {
+        !self.bits.replace(index, true)
+    }
+
+    pub fn insert_range(&mut self, range: Range<usize>) {
+        self.bits[range].fill(true);
+    }
+
+    /// Removes the index, returning whether it was in the set. Panics if it's not below `len()`.
+    pub fn remove(&mut self, index: usize) -> bool {
+        self.bits.replace(index, false)
+    }
+
+    /// Whether the index is in the set. Indices past the end never are.
+    pub fn contains(&self, index: usize) -> bool {
+        self.bits.get(index).map_or(false, |bit| *bit)
+    }
+
+    /// Adds every index in `other`, which must have the same length.
+    pub fn union_with(&mut self, other: &BitSet) {
+        debug_assert_eq!(self.len(), other.len());
+
+        self.bits |= &other.bits;
+    }
+
+    /// Removes every index, keeping the length.
+    pub fn clear(&mut self) {
+        self.bits.fill(false);
+    }
+
+    /// The indices in the set, in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bits.iter_ones()
+    }
+}
+
+#[cfg(test)]
+mod test_bit_set {
+    use super::BitSet;
+
+    #[test]
+    fn insert_remove_and_iterate() {
+        let mut set = BitSet::with_len(10);
+
+        assert!(set.insert(7));
+        assert!(set.insert(2));
+        assert!(!set.insert(7));
+        assert!(set.remove(2));
+        set.insert_range(4..6);
+
+        assert_eq!(set.iter().collect::<Vec<_>>(), vec![4, 5, 7]);
+        assert_eq!(set.count(), 3);
+        assert!(!set.contains(100));
+
+        set.push(true);
+        assert_eq!(set.len(), 11);
+        assert!(set.contains(10));
+    }
+}
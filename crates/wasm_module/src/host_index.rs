@@ -0,0 +1,204 @@
+//! Data about a host module that we can compute once, when the platform is preprocessed,
+//! rather than on every build of an app.
+//!
+//! The host doesn't change between builds, but `WasmModule::preload` decodes its Code section
+//! function by function and its relocation sections entry by entry. The index stores the
+//! results in a fixed-width format, so that loading the host becomes a few bulk copies.
+//! https://github.com/WebAssembly/tool-conventions/blob/main/Linking.md#relocation-sections
+
+use bumpalo::collections::vec::Vec;
+use bumpalo::Bump;
+
+use super::linking::{IndexRelocType, OffsetRelocType, RelocationEntry};
+use super::parse::ParseError;
+use super::serialize::SerialBuffer;
+use super::WasmModule;
+
+#[derive(Debug)]
+pub struct HostIndex<'a> {
+    /// Hash of the bytes of the host module this index was made from.
+    /// A host with a different hash has been rebuilt, and the index is stale.
+    pub host_hash: u64,
+    /// Offset of the Code section body in the host module
+    pub code_section_offset: u32,
+    /// The start of each function, relative to the Code section body
+    pub function_offsets: Vec<'a, u32>,
+    pub reloc_code: Vec<'a, RelocationEntry>,
+    pub reloc_data: Vec<'a, RelocationEntry>,
+}
+
+impl<'a> HostIndex<'a> {
+    const VERSION: u32 = 2;
+
+    pub fn new(host_bytes: &[u8], module: &WasmModule<'a>, arena: &'a Bump) -> Self {
+        HostIndex {
+            host_hash: Self::hash_host(host_bytes),
+            code_section_offset: module.code.section_offset,
+            function_offsets: module.code.function_offsets.clone(),
+            reloc_code: Vec::from_iter_in(module.reloc_code.entries.iter().cloned(), arena),
+            reloc_data: Vec::from_iter_in(module.reloc_data.entries.iter().cloned(), arena),
+        }
+    }
+
+    /// Whether this index was made from a host module with these bytes
+    pub fn matches(&self, host_bytes: &[u8]) -> bool {
+        self.host_hash == Self::hash_host(host_bytes)
+    }
+
+    /// The index is stored on disk next to the host, so the hash must be the same from one
+    /// compiler build to the next. It's 64-bit FNV-1a, which is fully specified.
+    fn hash_host(host_bytes: &[u8]) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+        host_bytes.iter().fold(FNV_OFFSET_BASIS, |hash, byte| {
+            (hash ^ *byte as u64).wrapping_mul(FNV_PRIME)
+        })
+    }
+
+    pub fn serialize<T: SerialBuffer>(&self, buffer: &mut T) {
+        buffer.write_unencoded_u32(Self::VERSION);
+        buffer.write_unencoded_u64(self.host_hash);
+        buffer.write_unencoded_u32(self.code_section_offset);
+
+        buffer.write_unencoded_u32(self.function_offsets.len() as u32);
+        for offset in self.function_offsets.iter() {
+            buffer.write_unencoded_u32(*offset);
+        }
+
+        for entries in [&self.reloc_code, &self.reloc_data] {
+            buffer.write_unencoded_u32(entries.len() as u32);
+            for entry in entries.iter() {
+                match entry {
+                    RelocationEntry::Index {
+                        type_id,
+                        offset,
+                        symbol_index,
+                    } => {
+                        buffer.append_u8(*type_id as u8);
+                        buffer.write_unencoded_u32(*offset);
+                        buffer.write_unencoded_u32(*symbol_index);
+                        buffer.write_unencoded_u32(0);
+                    }
+                    RelocationEntry::Offset {
+                        type_id,
+                        offset,
+                        symbol_index,
+                        addend,
+                    } => {
+                        buffer.append_u8(*type_id as u8);
+                        buffer.write_unencoded_u32(*offset);
+                        buffer.write_unencoded_u32(*symbol_index);
+                        buffer.write_unencoded_u32(*addend as u32);
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn parse(arena: &'a Bump, bytes: &[u8]) -> Result<Self, ParseError> {
+        let mut cursor = 0;
+
+        let version = read_u32(bytes, &mut cursor)?;
+        if version != Self::VERSION {
+            return Err(ParseError {
+                offset: 0,
+                message: format!(
+                    "This host index has version {}, but only version {} is supported.",
+                    version,
+                    Self::VERSION
+                ),
+            });
+        }
+
+        let host_hash = read_u64(bytes, &mut cursor)?;
+        let code_section_offset = read_u32(bytes, &mut cursor)?;
+
+        let function_count = read_u32(bytes, &mut cursor)?;
+        let mut function_offsets = Vec::with_capacity_in(function_count as usize, arena);
+        for _ in 0..function_count {
+            function_offsets.push(read_u32(bytes, &mut cursor)?);
+        }
+
+        let reloc_code = read_relocations(arena, bytes, &mut cursor)?;
+        let reloc_data = read_relocations(arena, bytes, &mut cursor)?;
+
+        Ok(HostIndex {
+            host_hash,
+            code_section_offset,
+            function_offsets,
+            reloc_code,
+            reloc_data,
+        })
+    }
+}
+
+fn read_u32(bytes: &[u8], cursor: &mut usize) -> Result<u32, ParseError> {
+    match bytes.get(*cursor..*cursor + 4) {
+        Some(slice) => {
+            *cursor += 4;
+            Ok(u32::from_le_bytes(slice.try_into().unwrap()))
+        }
+        None => Err(ParseError {
+            offset: *cursor,
+            message: "Unexpected end of host index".into(),
+        }),
+    }
+}
+
+fn read_u64(bytes: &[u8], cursor: &mut usize) -> Result<u64, ParseError> {
+    let low = read_u32(bytes, cursor)? as u64;
+    let high = read_u32(bytes, cursor)? as u64;
+
+    Ok(low | (high << 32))
+}
+
+fn read_relocations<'a>(
+    arena: &'a Bump,
+    bytes: &[u8],
+    cursor: &mut usize,
+) -> Result<Vec<'a, RelocationEntry>, ParseError> {
+    let count = read_u32(bytes, cursor)?;
+    let mut entries = Vec::with_capacity_in(count as usize, arena);
+
+    for _ in 0..count {
+        let type_id_offset = *cursor;
+        let type_id_byte = match bytes.get(*cursor) {
+            Some(byte) => *byte,
+            None => {
+                return Err(ParseError {
+                    offset: *cursor,
+                    message: "Unexpected end of host index".into(),
+                })
+            }
+        };
+        *cursor += 1;
+        let offset = read_u32(bytes, cursor)?;
+        let symbol_index = read_u32(bytes, cursor)?;
+        let addend = read_u32(bytes, cursor)? as i32;
+
+        let entry = if let Some(type_id) = IndexRelocType::from_u8(type_id_byte) {
+            RelocationEntry::Index {
+                type_id,
+                offset,
+                symbol_index,
+            }
+        } else if let Some(type_id) = OffsetRelocType::from_u8(type_id_byte) {
+            RelocationEntry::Offset {
+                type_id,
+                offset,
+                symbol_index,
+                addend,
+            }
+        } else {
+            return Err(ParseError {
+                offset: type_id_offset,
+                message: format!("Unknown relocation type 0x{:2x}", type_id_byte),
+            });
+        };
+
+        entries.push(entry);
+    }
+
+    Ok(entries)
+}
@@ -1,3 +1,5 @@
+mod bit_set;
+pub mod host_index;
 pub mod linking;
 pub mod opcodes;
 pub mod parse;
@@ -6,12 +8,13 @@ pub mod serialize;
 
 use std::iter::repeat;
 
+pub use bit_set::BitSet;
+pub use host_index::HostIndex;
 pub use linking::{OffsetRelocType, RelocationEntry, SymInfo};
 use opcodes::OpCode;
 use roc_error_macros::internal_error;
 pub use sections::{ConstExpr, Export, ExportType, Global, GlobalType, Signature};
 
-use bitvec::vec::BitVec;
 use bumpalo::{collections::Vec, Bump};
 
 use self::linking::{IndexRelocType, LinkingSection, RelocationSection, WasmObjectSymbol};
@@ -119,6 +122,30 @@ impl<'a> WasmModule<'a> {
         arena: &'a Bump,
         bytes: &[u8],
         require_relocatable: bool,
+    ) -> Result<Self, ParseError> {
+        Self::preload_help(arena, bytes, require_relocatable, None)
+    }
+
+    /// Preload a host module using an index created when the platform was preprocessed.
+    /// If the index does not belong to this host, this is the same as `preload`.
+    pub fn preload_indexed(
+        arena: &'a Bump,
+        bytes: &[u8],
+        require_relocatable: bool,
+        index: &HostIndex<'_>,
+    ) -> Result<Self, ParseError> {
+        if index.matches(bytes) {
+            Self::preload_help(arena, bytes, require_relocatable, Some(index))
+        } else {
+            Self::preload_help(arena, bytes, require_relocatable, None)
+        }
+    }
+
+    fn preload_help(
+        arena: &'a Bump,
+        bytes: &[u8],
+        require_relocatable: bool,
+        index: Option<&HostIndex<'_>>,
     ) -> Result<Self, ParseError> {
         let is_valid_magic_number = &bytes[0..4] == "\0asm".as_bytes();
         let is_valid_version = bytes[4..8] == Self::WASM_VERSION.to_le_bytes();
@@ -142,11 +169,38 @@ impl<'a> WasmModule<'a> {
         let start = OpaqueSection::parse((arena, SectionId::Start), bytes, &mut cursor)?;
         let element = ElementSection::parse(arena, bytes, &mut cursor)?;
         let _data_count = OpaqueSection::parse((arena, SectionId::DataCount), bytes, &mut cursor)?;
-        let code = CodeSection::parse(arena, bytes, &mut cursor)?;
+        let code = match index {
+            Some(index) => CodeSection::parse_indexed(
+                arena,
+                bytes,
+                &mut cursor,
+                index.code_section_offset,
+                &index.function_offsets,
+            )?,
+            None => CodeSection::parse(arena, bytes, &mut cursor)?,
+        };
         let data = DataSection::parse(arena, bytes, &mut cursor)?;
         let linking = LinkingSection::parse(arena, bytes, &mut cursor)?;
-        let reloc_code = RelocationSection::parse((arena, "reloc.CODE"), bytes, &mut cursor)?;
-        let reloc_data = RelocationSection::parse((arena, "reloc.DATA"), bytes, &mut cursor)?;
+        let (reloc_code, reloc_data) = match index {
+            Some(index) => (
+                RelocationSection::parse_indexed(
+                    (arena, "reloc.CODE"),
+                    bytes,
+                    &mut cursor,
+                    &index.reloc_code,
+                )?,
+                RelocationSection::parse_indexed(
+                    (arena, "reloc.DATA"),
+                    bytes,
+                    &mut cursor,
+                    &index.reloc_data,
+                )?,
+            ),
+            None => (
+                RelocationSection::parse((arena, "reloc.CODE"), bytes, &mut cursor)?,
+                RelocationSection::parse((arena, "reloc.DATA"), bytes, &mut cursor)?,
+            ),
+        };
         let names = NameSection::parse(arena, bytes, &mut cursor)?;
 
         let mut module_errors = String::new();
@@ -210,7 +264,7 @@ impl<'a> WasmModule<'a> {
         })
     }
 
-    pub fn eliminate_dead_code(&mut self, arena: &'a Bump, called_fns: BitVec<usize>) {
+    pub fn eliminate_dead_code(&mut self, arena: &'a Bump, called_fns: BitSet) {
         if DEBUG_SETTINGS.skip_dead_code_elim {
             return;
         }
@@ -266,7 +320,7 @@ impl<'a> WasmModule<'a> {
             if !matches!(import.description, ImportDesc::Func { .. }) {
                 true
             } else {
-                let live = live_flags[fn_index];
+                let live = live_flags.contains(fn_index);
                 if live {
                     live_import_fns.push(fn_index);
                 } else {
@@ -322,7 +376,7 @@ impl<'a> WasmModule<'a> {
         let mut buffer = Vec::with_capacity_in(self.code.bytes.len(), arena);
         self.code.function_count.serialize(&mut buffer);
         for (i, fn_index) in (fn_index_min..fn_index_max).enumerate() {
-            if live_flags[fn_index as usize] {
+            if live_flags.contains(fn_index as usize) {
                 let code_start = self.code.function_offsets[i] as usize;
                 let code_end = if i < self.code.function_offsets.len() - 1 {
                     self.code.function_offsets[i + 1] as usize
@@ -341,12 +395,12 @@ impl<'a> WasmModule<'a> {
     fn trace_live_functions<I: Iterator<Item = u32>>(
         &self,
         arena: &'a Bump,
-        called_fns: BitVec<usize>,
+        called_fns: BitSet,
         exported_fns: I,
         indirect_callees_and_signatures: Vec<'a, (u32, u32)>,
         fn_index_min: u32,
         fn_index_max: u32,
-    ) -> BitVec<usize> {
+    ) -> BitSet {
         let reloc_len = self.reloc_code.entries.len();
 
         let mut call_offsets_and_symbols = Vec::with_capacity_in(reloc_len, arena);
@@ -382,20 +436,19 @@ impl<'a> WasmModule<'a> {
         );
 
         // Loop variables for the main loop below
-        let mut live_flags = BitVec::repeat(false, called_fns.len());
-        let mut next_pass_fns = BitVec::repeat(false, called_fns.len());
+        let mut live_flags = BitSet::with_len(called_fns.len());
+        let mut next_pass_fns = BitSet::with_len(called_fns.len());
         let mut current_pass_fns = called_fns;
         for index in exported_fns {
-            current_pass_fns.set(index as usize, true);
+            current_pass_fns.insert(index as usize);
         }
 
-        while current_pass_fns.count_ones() > 0 {
+        while current_pass_fns.count() > 0 {
             // All functions in this pass are live (they have been reached by earlier passes)
-            debug_assert_eq!(live_flags.len(), current_pass_fns.len());
-            live_flags |= &current_pass_fns;
+            live_flags.union_with(&current_pass_fns);
 
             // For each live function in the current pass
-            for fn_index in current_pass_fns.iter_ones() {
+            for fn_index in current_pass_fns.iter() {
                 // Skip JS imports and Roc functions
                 if fn_index < fn_index_min as usize || fn_index >= fn_index_max as usize {
                     continue;
@@ -417,8 +470,10 @@ impl<'a> WasmModule<'a> {
                         let callee = symbol_fn_indices[*symbol as usize];
 
                         // If it's not already marked live, include it in the next pass
-                        if live_flags.get(callee as usize).as_deref() == Some(&false) {
-                            next_pass_fns.set(callee as usize, true);
+                        if (callee as usize) < live_flags.len()
+                            && !live_flags.contains(callee as usize)
+                        {
+                            next_pass_fns.insert(callee as usize);
                         }
                     }
                 }
@@ -433,8 +488,8 @@ impl<'a> WasmModule<'a> {
                             .map(|(f, _)| *f);
                         // Mark them all as live
                         for f in potential_callees {
-                            if live_flags.get(f as usize).as_deref() == Some(&false) {
-                                next_pass_fns.set(f as usize, true);
+                            if (f as usize) < live_flags.len() && !live_flags.contains(f as usize) {
+                                next_pass_fns.insert(f as usize);
                             }
                         }
                     }
@@ -442,7 +497,7 @@ impl<'a> WasmModule<'a> {
             }
 
             std::mem::swap(&mut current_pass_fns, &mut next_pass_fns);
-            next_pass_fns.fill(false);
+            next_pass_fns.clear();
         }
 
         live_flags
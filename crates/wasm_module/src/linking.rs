@@ -42,7 +42,7 @@ pub enum IndexRelocType {
 }
 
 impl IndexRelocType {
-    fn from_u8(x: u8) -> Option<IndexRelocType> {
+    pub(crate) fn from_u8(x: u8) -> Option<IndexRelocType> {
         match x {
             0 => Some(Self::FunctionIndexLeb),
             1 => Some(Self::TableIndexSleb),
@@ -87,7 +87,7 @@ pub enum OffsetRelocType {
 }
 
 impl OffsetRelocType {
-    fn from_u8(x: u8) -> Option<OffsetRelocType> {
+    pub(crate) fn from_u8(x: u8) -> Option<OffsetRelocType> {
         match x {
             3 => Some(Self::MemoryAddrLeb),
             4 => Some(Self::MemoryAddrSleb),
@@ -102,7 +102,7 @@ impl OffsetRelocType {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub enum RelocationEntry {
     Index {
         type_id: IndexRelocType,
@@ -240,6 +240,54 @@ impl<'a> Parse<RelocCtx<'a>> for RelocationSection<'a> {
     }
 }
 
+impl<'a> RelocationSection<'a> {
+    /// Like `parse`, but takes the entries from a `HostIndex` and skips over the encoded ones
+    pub(crate) fn parse_indexed(
+        ctx: RelocCtx<'a>,
+        bytes: &[u8],
+        cursor: &mut usize,
+        indexed_entries: &[RelocationEntry],
+    ) -> Result<Self, ParseError> {
+        let cursor_reset = *cursor;
+        let (arena, name) = ctx;
+
+        if *cursor >= bytes.len() || bytes[*cursor] != SectionId::Custom as u8 {
+            *cursor = cursor_reset;
+            return Ok(RelocationSection::new(arena, name));
+        }
+        *cursor += 1;
+        let body_size = u32::parse((), bytes, cursor)?;
+        let section_end = *cursor + body_size as usize;
+
+        let actual_name = <&'a str>::parse(arena, bytes, cursor)?;
+        if actual_name != name {
+            *cursor = cursor_reset;
+            return Ok(RelocationSection::new(arena, name));
+        }
+
+        let target_section_index = u32::parse((), bytes, cursor)?;
+        let entry_count = u32::parse((), bytes, cursor)?;
+        if entry_count as usize != indexed_entries.len() {
+            return Err(ParseError {
+                offset: *cursor,
+                message: format!(
+                    "The host index has {} entries for {}, but the host has {}",
+                    indexed_entries.len(),
+                    name,
+                    entry_count
+                ),
+            });
+        }
+
+        *cursor = section_end;
+        Ok(RelocationSection {
+            name,
+            target_section_index,
+            entries: Vec::from_iter_in(indexed_entries.iter().cloned(), arena),
+        })
+    }
+}
+
 /*******************************************************************
  *
  * Linking section
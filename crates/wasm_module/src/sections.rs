@@ -1447,6 +1447,49 @@ impl<'a> CodeSection<'a> {
             dead_import_dummy_count: 0,
         })
     }
+
+    /// Like `parse`, but takes the function offsets from a `HostIndex`,
+    /// so the section can be copied in one go instead of function by function.
+    pub(crate) fn parse_indexed(
+        arena: &'a Bump,
+        module_bytes: &[u8],
+        cursor: &mut usize,
+        indexed_section_offset: u32,
+        indexed_function_offsets: &[u32],
+    ) -> Result<Self, ParseError> {
+        if module_bytes[*cursor] != SectionId::Code as u8 {
+            return Err(ParseError {
+                offset: *cursor,
+                message: "Missing code section!".into(),
+            });
+        }
+        *cursor += 1;
+        let section_size = u32::parse((), module_bytes, cursor)? as usize;
+        let section_body_start = *cursor;
+        let function_count = u32::parse((), module_bytes, cursor)?;
+        let next_section_start = section_body_start + section_size;
+
+        if section_body_start != indexed_section_offset as usize
+            || function_count as usize != indexed_function_offsets.len()
+        {
+            return Err(ParseError {
+                offset: section_body_start,
+                message: "The host index does not match the host's code section".into(),
+            });
+        }
+
+        let mut bytes = Vec::with_capacity_in(section_size + section_size / 2, arena);
+        bytes.extend_from_slice(&module_bytes[section_body_start..next_section_start]);
+        *cursor = next_section_start;
+
+        Ok(CodeSection {
+            function_count,
+            section_offset: section_body_start as u32,
+            bytes,
+            function_offsets: Vec::from_iter_in(indexed_function_offsets.iter().copied(), arena),
+            dead_import_dummy_count: 0,
+        })
+    }
 }
 
 impl<'a> Serialize for CodeSection<'a> {
@@ -9,6 +9,12 @@ async function roc_web_platform_run(wasm_filename, callback) {
     callback(js_string);
   }
 
+  function js_dbg(loc_bytes, loc_len, msg_bytes, msg_len) {
+    const loc = decoder.decode(memory_bytes.subarray(loc_bytes, loc_bytes + loc_len));
+    const msg = decoder.decode(memory_bytes.subarray(msg_bytes, msg_bytes + msg_len));
+    console.log(`[${loc}] ${msg}`);
+  }
+
   const importObj = {
     wasi_snapshot_preview1: {
       proc_exit: (code) => {
@@ -23,6 +29,7 @@ async function roc_web_platform_run(wasm_filename, callback) {
     },
     env: {
       js_display_roc_string,
+      js_dbg,
       roc_panic: (_pointer, _tag_id) => {
         throw "Roc panicked!";
       },
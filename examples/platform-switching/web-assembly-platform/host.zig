@@ -43,6 +43,12 @@ extern fn roc__mainForHost_1_exposed(*RocStr) void;
 
 extern fn js_display_roc_string(str_bytes: ?[*]u8, str_len: usize) void;
 
+extern fn js_dbg(loc_bytes: ?[*]u8, loc_len: usize, msg_bytes: ?[*]u8, msg_len: usize) void;
+
+export fn roc_dbg(loc: *RocStr, msg: *RocStr) callconv(.C) void {
+    js_dbg(loc.asU8ptr(), loc.len(), msg.asU8ptr(), msg.len());
+}
+
 pub fn main() u8 {
     // actually call roc to populate the callresult
     var callresult = RocStr.empty();